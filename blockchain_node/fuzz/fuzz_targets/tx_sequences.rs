@@ -0,0 +1,15 @@
+//! cargo fuzz run tx_sequences
+//!
+//! Crashes land in fuzz/artifacts/tx_sequences. Replay one with
+//! `cargo fuzz run tx_sequences <artifact>`, then add the minimized sequence
+//! (see `fuzz::minimize_failure`) to fuzz/regressions.
+#![no_main]
+
+use blockchain_node::dev_tools::testing_framework::fuzz;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(violation) = fuzz::run_input(data) {
+        panic!("{}", violation);
+    }
+});
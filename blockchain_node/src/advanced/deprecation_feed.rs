@@ -22,6 +22,8 @@ pub struct DeprecationAnnouncement {
     pub migration_guide_url: String,
     pub severity: DeprecationSeverity,
     pub description: String,
+    #[serde(default)]
+    pub enforcement: EnforcementLevel,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -31,6 +33,16 @@ pub enum DeprecationSeverity {
     Critical, // Will break in next version
 }
 
+/// How consumers (e.g. ai-jobd submissions) treat use of a deprecated item
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnforcementLevel {
+    #[default]
+    Warn,      // Log and flag only
+    SoftBlock, // Rejected unless the caller sets an override flag
+    HardBlock, // Always rejected
+}
+
 pub struct DeprecationFeed {
     announcements: Arc<RwLock<Vec<DeprecationAnnouncement>>>,
 }
@@ -50,6 +62,7 @@ impl DeprecationFeed {
             migration_guide_url: "https://docs.arthachain.online/migration/dep-001".to_string(),
             severity: DeprecationSeverity::Warning,
             description: "Legacy registerIdentity() method is deprecated. Use createDID() with explicit auth and encryption keys.".to_string(),
+            enforcement: EnforcementLevel::Warn,
         });
         
         DeprecationFeed {
@@ -216,12 +229,34 @@ mod tests {
             migration_guide_url: "https://example.com".to_string(),
             severity: DeprecationSeverity::Info,
             description: "Test deprecation".to_string(),
+            enforcement: EnforcementLevel::SoftBlock,
         });
         
         let all = feed.get_all_announcements();
         assert!(all.iter().any(|a| a.id == "TEST-001"));
     }
 
+    #[test]
+    fn test_enforcement_level_serialization() {
+        let json = serde_json::to_value(EnforcementLevel::SoftBlock).unwrap();
+        assert_eq!(json, "soft-block");
+
+        // Announcements published before enforcement levels default to warn
+        let legacy = serde_json::json!({
+            "id": "DEP-LEGACY",
+            "component": "api",
+            "name": "oldEndpoint",
+            "announced_at": 1,
+            "sunset_date": 2,
+            "replacement": "newEndpoint",
+            "migration_guide_url": "https://example.com",
+            "severity": "Warning",
+            "description": "legacy",
+        });
+        let parsed: DeprecationAnnouncement = serde_json::from_value(legacy).unwrap();
+        assert_eq!(parsed.enforcement, EnforcementLevel::Warn);
+    }
+
     #[test]
     fn test_rss_generation() {
         let feed = DeprecationFeed::new();
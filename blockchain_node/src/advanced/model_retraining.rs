@@ -28,12 +28,94 @@ pub enum RetrainingStatus {
     Training,
     Uploading, // Uploading trained model
     Completed,
+    Canary, // Serving a fraction of live traffic
+    Promoted,
+    RolledBack { reason: String },
     Failed { reason: String },
 }
 
+/// Canary stage settings for promoting a retrained candidate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryConfig {
+    pub traffic_fraction: f64, // Share of inference routed to the candidate
+    pub window_secs: u64,
+    pub min_requests: u64, // Candidate requests needed before a decision
+    pub max_error_rate_increase: f64,
+    pub max_ethics_flag_rate_increase: f64,
+    pub max_fraud_flag_rate_increase: f64,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        CanaryConfig {
+            traffic_fraction: 0.05,
+            window_secs: 3600,
+            min_requests: 100,
+            max_error_rate_increase: 0.01,
+            max_ethics_flag_rate_increase: 0.005,
+            max_fraud_flag_rate_increase: 0.005,
+        }
+    }
+}
+
+/// Outcome of a single inference served during a canary
+#[derive(Debug, Clone, Default)]
+pub struct InferenceOutcome {
+    pub error: bool,
+    pub ethics_flagged: bool,
+    pub fraud_flagged: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CanaryMetrics {
+    pub requests: u64,
+    pub errors: u64,
+    pub ethics_flags: u64,
+    pub fraud_flags: u64,
+}
+
+impl CanaryMetrics {
+    fn record(&mut self, outcome: &InferenceOutcome) {
+        self.requests += 1;
+        self.errors += outcome.error as u64;
+        self.ethics_flags += outcome.ethics_flagged as u64;
+        self.fraud_flags += outcome.fraud_flagged as u64;
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        if self.requests == 0 { 0.0 } else { count as f64 / self.requests as f64 }
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        self.rate(self.errors)
+    }
+
+    pub fn ethics_flag_rate(&self) -> f64 {
+        self.rate(self.ethics_flags)
+    }
+
+    pub fn fraud_flag_rate(&self) -> f64 {
+        self.rate(self.fraud_flags)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryDeployment {
+    pub job_id: String,
+    pub aiid: String,
+    pub incumbent_cid: String,
+    pub candidate_cid: String,
+    pub config: CanaryConfig,
+    pub started_at: u64,
+    pub incumbent: CanaryMetrics,
+    pub candidate: CanaryMetrics,
+}
+
 pub struct RetrainingOrchestrator {
     jobs: HashMap<String, RetrainingJob>,
     scheduler_endpoint: String,
+    live_models: HashMap<String, String>, // aiid -> model CID serving live traffic
+    canaries: HashMap<String, CanaryDeployment>, // aiid -> active canary
 }
 
 impl RetrainingOrchestrator {
@@ -41,6 +123,8 @@ impl RetrainingOrchestrator {
         RetrainingOrchestrator {
             jobs: HashMap::new(),
             scheduler_endpoint,
+            live_models: HashMap::new(),
+            canaries: HashMap::new(),
         }
     }
 
@@ -118,6 +202,125 @@ impl RetrainingOrchestrator {
         Ok(())
     }
 
+    /// Set the model currently serving live traffic for an AIID
+    pub fn set_live_model(&mut self, aiid: &str, model_cid: String) {
+        self.live_models.insert(aiid.to_string(), model_cid);
+    }
+
+    pub fn live_model(&self, aiid: &str) -> Option<&String> {
+        self.live_models.get(aiid)
+    }
+
+    pub fn get_canary(&self, aiid: &str) -> Option<&CanaryDeployment> {
+        self.canaries.get(aiid)
+    }
+
+    /// Start a canary for a completed retraining job instead of flipping
+    /// live traffic wholesale. Without an incumbent the candidate is promoted directly.
+    pub fn start_canary(&mut self, job_id: &str, config: CanaryConfig, now: u64) -> Result<RetrainingStatus> {
+        if !(0.0..=1.0).contains(&config.traffic_fraction) {
+            return Err(anyhow!("Canary traffic fraction must be within [0, 1]"));
+        }
+
+        let job = self.jobs.get_mut(job_id)
+            .ok_or_else(|| anyhow!("Job not found"))?;
+        if job.status != RetrainingStatus::Completed {
+            return Err(anyhow!("Only completed jobs can be canaried"));
+        }
+        let candidate_cid = job.output_model_cid.clone()
+            .ok_or_else(|| anyhow!("Job has no output model"))?;
+
+        if self.canaries.contains_key(&job.aiid) {
+            return Err(anyhow!("A canary is already running for {}", job.aiid));
+        }
+
+        let Some(incumbent_cid) = self.live_models.get(&job.aiid).cloned() else {
+            job.status = RetrainingStatus::Promoted;
+            self.live_models.insert(job.aiid.clone(), candidate_cid);
+            return Ok(RetrainingStatus::Promoted);
+        };
+
+        job.status = RetrainingStatus::Canary;
+        println!("🐤 Canary started for {}: {:.1}% traffic to {}",
+            job.aiid, config.traffic_fraction * 100.0, candidate_cid);
+
+        self.canaries.insert(job.aiid.clone(), CanaryDeployment {
+            job_id: job_id.to_string(),
+            aiid: job.aiid.clone(),
+            incumbent_cid,
+            candidate_cid,
+            config,
+            started_at: now,
+            incumbent: CanaryMetrics::default(),
+            candidate: CanaryMetrics::default(),
+        });
+        Ok(RetrainingStatus::Canary)
+    }
+
+    /// Pick the model to serve an inference request. `sample` is uniform in [0, 1).
+    pub fn route_inference(&self, aiid: &str, sample: f64) -> Option<String> {
+        match self.canaries.get(aiid) {
+            Some(canary) if sample < canary.config.traffic_fraction => Some(canary.candidate_cid.clone()),
+            Some(canary) => Some(canary.incumbent_cid.clone()),
+            None => self.live_models.get(aiid).cloned(),
+        }
+    }
+
+    /// Record the outcome of an inference served by either arm of a canary
+    pub fn record_inference(&mut self, aiid: &str, model_cid: &str, outcome: &InferenceOutcome) {
+        if let Some(canary) = self.canaries.get_mut(aiid) {
+            if model_cid == canary.candidate_cid {
+                canary.candidate.record(outcome);
+            } else if model_cid == canary.incumbent_cid {
+                canary.incumbent.record(outcome);
+            }
+        }
+    }
+
+    /// Compare candidate against incumbent. Regressions roll back as soon as
+    /// enough candidate traffic is observed; promotion waits for the full window.
+    /// Returns the final status once the canary is decided.
+    pub fn evaluate_canary(&mut self, aiid: &str, now: u64) -> Option<RetrainingStatus> {
+        let canary = self.canaries.get(aiid)?;
+        if canary.candidate.requests < canary.config.min_requests {
+            return None;
+        }
+
+        let cfg = &canary.config;
+        let (cand, inc) = (&canary.candidate, &canary.incumbent);
+        let regression = if cand.error_rate() > inc.error_rate() + cfg.max_error_rate_increase {
+            Some(format!("error rate {:.3} vs incumbent {:.3}", cand.error_rate(), inc.error_rate()))
+        } else if cand.ethics_flag_rate() > inc.ethics_flag_rate() + cfg.max_ethics_flag_rate_increase {
+            Some(format!("ethics flag rate {:.3} vs incumbent {:.3}", cand.ethics_flag_rate(), inc.ethics_flag_rate()))
+        } else if cand.fraud_flag_rate() > inc.fraud_flag_rate() + cfg.max_fraud_flag_rate_increase {
+            Some(format!("fraud flag rate {:.3} vs incumbent {:.3}", cand.fraud_flag_rate(), inc.fraud_flag_rate()))
+        } else {
+            None
+        };
+
+        if regression.is_none() && now < canary.started_at + cfg.window_secs {
+            return None;
+        }
+
+        let canary = self.canaries.remove(aiid)?;
+        let status = match regression {
+            Some(reason) => {
+                println!("↩️  Canary for {} rolled back: {}", aiid, reason);
+                RetrainingStatus::RolledBack { reason }
+            }
+            None => {
+                println!("🚀 Canary for {} promoted: {}", aiid, canary.candidate_cid);
+                self.live_models.insert(aiid.to_string(), canary.candidate_cid.clone());
+                RetrainingStatus::Promoted
+            }
+        };
+
+        if let Some(job) = self.jobs.get_mut(&canary.job_id) {
+            job.status = status.clone();
+        }
+        Some(status)
+    }
+
     async fn notify_scheduler(&self, job_id: &str) -> Result<()> {
         // Notify scheduler of new job
         let url = format!("{}/schedule-retraining", self.scheduler_endpoint);
@@ -182,5 +385,89 @@ mod tests {
         assert_eq!(updated_job.progress, 0.5);
         assert!(matches!(updated_job.status, RetrainingStatus::Training));
     }
+
+    #[test]
+    fn test_canary_with_elevated_errors_rolls_back() {
+        let mut orchestrator = RetrainingOrchestrator::new("http://localhost:8080".to_string());
+        let aiid = "aiid:artha:test";
+        orchestrator.set_live_model(aiid, "artha://QmIncumbent".to_string());
+
+        let job = RetrainingJob {
+            job_id: "retrain-1".to_string(),
+            aiid: aiid.to_string(),
+            base_model_cid: "artha://QmIncumbent".to_string(),
+            new_dataset_cid: "artha://QmDataset".to_string(),
+            hyperparameters: HashMap::new(),
+            target_nodes: Vec::new(),
+            status: RetrainingStatus::Queued,
+            progress: 0.0,
+            created_at: 1000,
+            started_at: None,
+            completed_at: None,
+            output_model_cid: None,
+        };
+        orchestrator.jobs.insert("retrain-1".to_string(), job);
+        orchestrator.complete_job("retrain-1", "artha://QmCandidate".to_string()).unwrap();
+
+        let config = CanaryConfig { traffic_fraction: 0.1, min_requests: 50, ..CanaryConfig::default() };
+        assert_eq!(orchestrator.start_canary("retrain-1", config, 1000).unwrap(), RetrainingStatus::Canary);
+
+        // Only the configured fraction reaches the candidate
+        assert_eq!(orchestrator.route_inference(aiid, 0.05).unwrap(), "artha://QmCandidate");
+        assert_eq!(orchestrator.route_inference(aiid, 0.5).unwrap(), "artha://QmIncumbent");
+
+        for i in 0..1000 {
+            let sample = (i % 100) as f64 / 100.0;
+            let model = orchestrator.route_inference(aiid, sample).unwrap();
+            let outcome = InferenceOutcome {
+                // Candidate fails 1 in 5 requests, incumbent 1 in 100
+                error: if model == "artha://QmCandidate" { i % 5 == 0 } else { i % 100 == 50 },
+                ..InferenceOutcome::default()
+            };
+            orchestrator.record_inference(aiid, &model, &outcome);
+            if orchestrator.get_canary(aiid).unwrap().candidate.requests >= 50 {
+                break;
+            }
+        }
+
+        // Regression is acted on before the window ends
+        let status = orchestrator.evaluate_canary(aiid, 1010).unwrap();
+        assert!(matches!(status, RetrainingStatus::RolledBack { .. }));
+        assert_eq!(orchestrator.live_model(aiid).unwrap(), "artha://QmIncumbent");
+        assert!(orchestrator.get_canary(aiid).is_none());
+        assert!(matches!(orchestrator.get_job_status("retrain-1").unwrap().status, RetrainingStatus::RolledBack { .. }));
+    }
+
+    #[test]
+    fn test_healthy_canary_promotes_after_window() {
+        let mut orchestrator = RetrainingOrchestrator::new("http://localhost:8080".to_string());
+        let aiid = "aiid:artha:healthy";
+        orchestrator.set_live_model(aiid, "artha://QmIncumbent".to_string());
+        orchestrator.jobs.insert("retrain-2".to_string(), RetrainingJob {
+            job_id: "retrain-2".to_string(),
+            aiid: aiid.to_string(),
+            base_model_cid: "artha://QmIncumbent".to_string(),
+            new_dataset_cid: "artha://QmDataset".to_string(),
+            hyperparameters: HashMap::new(),
+            target_nodes: Vec::new(),
+            status: RetrainingStatus::Completed,
+            progress: 1.0,
+            created_at: 1000,
+            started_at: Some(1000),
+            completed_at: Some(2000),
+            output_model_cid: Some("artha://QmCandidate".to_string()),
+        });
+
+        let config = CanaryConfig { min_requests: 10, window_secs: 600, ..CanaryConfig::default() };
+        orchestrator.start_canary("retrain-2", config, 2000).unwrap();
+        for _ in 0..20 {
+            orchestrator.record_inference(aiid, "artha://QmCandidate", &InferenceOutcome::default());
+            orchestrator.record_inference(aiid, "artha://QmIncumbent", &InferenceOutcome::default());
+        }
+
+        assert!(orchestrator.evaluate_canary(aiid, 2100).is_none());
+        assert_eq!(orchestrator.evaluate_canary(aiid, 2600).unwrap(), RetrainingStatus::Promoted);
+        assert_eq!(orchestrator.live_model(aiid).unwrap(), "artha://QmCandidate");
+    }
 }
 
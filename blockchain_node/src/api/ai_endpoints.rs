@@ -1012,6 +1012,115 @@ pub async fn undeploy_model(
     Ok(StatusCode::OK)
 }
 
+// Versioned ai-jobd routing
+//
+// `/ai/v1/*` and `/ai/v2/*` forward verbatim to the same path under ai-jobd's
+// `/v1` and `/v2` routers, so the gateway needs no per-version handlers. The
+// legacy unversioned gateway routes above keep forwarding v1 semantics. Every
+// request is counted by the version it targets so v1 drain is visible.
+
+lazy_static::lazy_static! {
+    static ref AI_GATEWAY_REQUESTS: prometheus::IntCounterVec = prometheus::IntCounterVec::new(
+        prometheus::Opts::new("ai_gateway_requests_total", "AI gateway requests by ai-jobd API version and status class"),
+        &["api_version", "status_class"],
+    ).unwrap();
+}
+
+static REGISTER_GATEWAY_METRICS: std::sync::Once = std::sync::Once::new();
+
+/// Response headers from ai-jobd that carry versioning or retry information
+const FORWARDED_RESPONSE_HEADERS: [&str; 6] =
+    ["content-type", "api-version", "deprecation", "sunset", "link", "retry-after"];
+
+/// The ai-jobd API version a gateway path targets
+fn api_version_label(path: &str) -> &'static str {
+    if path.starts_with("/ai/v2/") {
+        "v2"
+    } else if path.starts_with("/ai/v1/") {
+        "v1"
+    } else {
+        "unversioned"
+    }
+}
+
+fn jobd_upstream(jobd_url: &str, version: &str, rest: &str, query: Option<&str>) -> String {
+    let url = format!("{}/{}/{}", jobd_url.trim_end_matches('/'), version, rest.trim_start_matches('/'));
+    match query {
+        Some(query) if !query.is_empty() => format!("{}?{}", url, query),
+        _ => url,
+    }
+}
+
+/// Middleware: count each request under the API version it targets
+async fn track_api_version(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    REGISTER_GATEWAY_METRICS.call_once(|| {
+        let _ = crate::monitoring::METRICS_REGISTRY.register(Box::new(AI_GATEWAY_REQUESTS.clone()));
+    });
+    let version = api_version_label(request.uri().path());
+    let response = next.run(request).await;
+    let status_class = format!("{}xx", response.status().as_u16() / 100);
+    AI_GATEWAY_REQUESTS.with_label_values(&[version, status_class.as_str()]).inc();
+    response
+}
+
+async fn proxy_jobd(
+    version: &'static str,
+    rest: String,
+    method: axum::http::Method,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<axum::response::Response, StatusCode> {
+    let jobd_url = std::env::var("ARTHA_JOBD_URL").unwrap_or_else(|_| "http://localhost:8081".to_string());
+    let url = jobd_upstream(&jobd_url, version, &rest, uri.query());
+
+    let method = reqwest::Method::from_bytes(method.as_str().as_bytes()).map_err(|_| StatusCode::METHOD_NOT_ALLOWED)?;
+    let mut request = reqwest::Client::new().request(method, &url).body(body);
+    for (name, value) in headers.iter() {
+        if name != axum::http::header::HOST && name != axum::http::header::CONTENT_LENGTH {
+            request = request.header(name.as_str(), value.as_bytes());
+        }
+    }
+    let response = request.send().await.map_err(|_| StatusCode::BAD_GATEWAY)?;
+
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut forwarded = axum::http::HeaderMap::new();
+    for name in FORWARDED_RESPONSE_HEADERS {
+        if let Some(value) = response.headers().get(name) {
+            if let Ok(value) = axum::http::HeaderValue::from_bytes(value.as_bytes()) {
+                forwarded.insert(name, value);
+            }
+        }
+    }
+    let body = response.bytes().await.map_err(|_| StatusCode::BAD_GATEWAY)?;
+    Ok((status, forwarded, body).into_response())
+}
+
+/// ANY /ai/v1/*path
+pub async fn proxy_jobd_v1(
+    Path(rest): Path<String>,
+    method: axum::http::Method,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<axum::response::Response, StatusCode> {
+    proxy_jobd("v1", rest, method, uri, headers, body).await
+}
+
+/// ANY /ai/v2/*path
+pub async fn proxy_jobd_v2(
+    Path(rest): Path<String>,
+    method: axum::http::Method,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<axum::response::Response, StatusCode> {
+    proxy_jobd("v2", rest, method, uri, headers, body).await
+}
+
 /// Build AI API router
 pub fn ai_router() -> Router {
     let state = Arc::new(AIServiceState::new());
@@ -1057,7 +1166,11 @@ pub fn ai_router() -> Router {
         .route("/ai/deployment/:id/status", get(get_deployment_status))
         .route("/ai/deployment/:id/scale", post(scale_deployment))
         .route("/ai/deployment/:id", axum::routing::delete(undeploy_model))
+        // Versioned ai-jobd surface
+        .route("/ai/v1/*path", axum::routing::any(proxy_jobd_v1))
+        .route("/ai/v2/*path", axum::routing::any(proxy_jobd_v2))
         .with_state(state)
+        .layer(axum::middleware::from_fn(track_api_version))
 }
 
 #[cfg(test)]
@@ -1083,5 +1196,22 @@ mod tests {
         assert!(json.contains("0.75"));
         assert!(json.contains("WARN"));
     }
+
+    #[test]
+    fn test_versioned_paths_route_to_matching_jobd_version() {
+        assert_eq!(api_version_label("/ai/v2/job/j1/status"), "v2");
+        assert_eq!(api_version_label("/ai/v1/jobs"), "v1");
+        assert_eq!(api_version_label("/ai/train"), "unversioned");
+        assert_eq!(api_version_label("/ai/v22/jobs"), "unversioned");
+
+        assert_eq!(
+            jobd_upstream("http://jobd:8081/", "v2", "job/j1/status", None),
+            "http://jobd:8081/v2/job/j1/status"
+        );
+        assert_eq!(
+            jobd_upstream("http://jobd:8081", "v1", "/jobs", Some("status=running&limit=10")),
+            "http://jobd:8081/v1/jobs?status=running&limit=10"
+        );
+    }
 }
 
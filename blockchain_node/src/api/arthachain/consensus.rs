@@ -5,15 +5,17 @@
 //! provides advanced consensus with self-healing capabilities.
 
 use crate::api::errors::ApiError;
+use crate::consensus::vote_records::{BlockVotes, ConsensusRecords, Epoch, ValidatorEntry, ValidatorPerformance};
 use crate::consensus::ConsensusManager;
 use crate::ledger::state::State;
 use axum::{
-    extract::Extension,
+    extract::{Extension, Path, Query},
     response::Json,
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -180,7 +182,7 @@ pub async fn get_svcp_consensus_status(
         active_validators,
         quorum_size,
         health_status: health_status.to_string(),
-        last_finalized_height: current_height.saturating_sub(5), // 5 block finality
+        last_finalized_height: state_guard.finalized_height().unwrap_or(0),
         consensus_latency_ms,
         view_change_progress,
         self_healing_active: true,
@@ -341,6 +343,81 @@ pub async fn get_consensus_health(
     Ok(Json(health_data))
 }
 
+/// Validator set of one epoch
+#[derive(Debug, Serialize)]
+pub struct EpochValidators {
+    pub epoch: u64,
+    pub validators: Vec<ValidatorEntry>,
+}
+
+/// Get the validator set at `?epoch=`, or at the current epoch
+pub async fn get_epoch_validators(
+    Extension(records): Extension<Arc<RwLock<ConsensusRecords>>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<EpochValidators>, ApiError> {
+    let epoch = match params.get("epoch") {
+        Some(epoch) => Some(
+            epoch
+                .parse::<u64>()
+                .map_err(|_| ApiError::bad_request("epoch must be a number"))?,
+        ),
+        None => None,
+    };
+    let records = records.read().await;
+    let (epoch, validators) = records
+        .validators(epoch)
+        .ok_or_else(|| ApiError::not_found("No validator set recorded for that epoch"))?;
+    Ok(Json(EpochValidators { epoch, validators }))
+}
+
+/// Get per-validator votes on a finalized block
+pub async fn get_block_votes(
+    Extension(records): Extension<Arc<RwLock<ConsensusRecords>>>,
+    Path(height): Path<u64>,
+) -> Result<Json<BlockVotes>, ApiError> {
+    let records = records.read().await;
+    match records.block_votes(height) {
+        Some(votes) => Ok(Json(votes.clone())),
+        None => Err(ApiError::not_found(&match records.oldest_detail_height() {
+            Some(oldest) if height < oldest => format!(
+                "Vote detail for block {} has been pruned; retained from height {}",
+                height, oldest
+            ),
+            _ => format!("No votes recorded for block {}", height),
+        })),
+    }
+}
+
+/// Get a validator's participation over the latest `?window=` epochs
+pub async fn get_validator_performance(
+    Extension(records): Extension<Arc<RwLock<ConsensusRecords>>>,
+    Path(address): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<ValidatorPerformance>, ApiError> {
+    let window = params
+        .get("window")
+        .map(|w| w.parse::<u64>())
+        .transpose()
+        .map_err(|_| ApiError::bad_request("window must be a number of epochs"))?
+        .unwrap_or(1);
+    if window == 0 {
+        return Err(ApiError::bad_request("window must be at least one epoch"));
+    }
+    records
+        .read()
+        .await
+        .performance(&address, window)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Validator has no recorded participation in that window"))
+}
+
+/// List epoch boundaries with their validator set changes
+pub async fn get_epochs(
+    Extension(records): Extension<Arc<RwLock<ConsensusRecords>>>,
+) -> Result<Json<Vec<Epoch>>, ApiError> {
+    Ok(Json(records.read().await.epochs()))
+}
+
 /// Create SVCP-SVBFT consensus router
 pub fn create_svcp_consensus_router() -> Router {
     Router::new()
@@ -349,4 +426,8 @@ pub fn create_svcp_consensus_router() -> Router {
         .route("/view-change", post(initiate_view_change))
         .route("/metrics", get(get_consensus_metrics))
         .route("/consensus-health", get(get_consensus_health))
+        .route("/validators", get(get_epoch_validators))
+        .route("/validators/:addr/performance", get(get_validator_performance))
+        .route("/blocks/:height/votes", get(get_block_votes))
+        .route("/epochs", get(get_epochs))
 }
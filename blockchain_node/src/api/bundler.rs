@@ -0,0 +1,114 @@
+//! Bundler REST Endpoints
+//! Submission, gas estimation and status lookup for sponsored user
+//! operations, plus signed account registrations, session grants and
+//! paymaster policies. Submissions are admitted here and take effect once a
+//! block includes them; paymaster deposits are plain value transfers to the
+//! EntryPoint.
+
+use crate::evm::account_abstraction::{
+    AaError, AccountAbstraction, AccountRegistration, EntryPointCall, PolicyUpdate, SessionGrant, UserOperation,
+};
+use crate::evm::types::EvmAddress;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use ethereum_types::H256;
+use std::sync::Arc;
+
+fn rejection(e: AaError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        AaError::UnknownAccount(_) => StatusCode::NOT_FOUND,
+        AaError::Execution(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        AaError::Submission(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() })))
+}
+
+async fn submit_user_op(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Json(op): Json<UserOperation>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let user_op_hash = aa.enqueue(EntryPointCall::HandleOp(op)).await.map_err(rejection)?;
+    Ok(Json(serde_json::json!({
+        "userOpHash": user_op_hash,
+        "status": "pending",
+    })))
+}
+
+async fn estimate_user_op(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Json(op): Json<UserOperation>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let estimate = aa.estimate(&op).await.map_err(rejection)?;
+    Ok(Json(serde_json::to_value(estimate).unwrap_or_default()))
+}
+
+async fn get_user_op(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Path(hash): Path<H256>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let receipt = aa.receipt(&hash).await.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(serde_json::to_value(receipt).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
+}
+
+async fn get_account(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Path(account): Path<EvmAddress>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let nonce = aa.next_nonce(account).await.map_err(rejection)?;
+    Ok(Json(serde_json::json!({ "account": account, "nonce": nonce })))
+}
+
+async fn add_session(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Json(grant): Json<SessionGrant>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let session_key = grant.session_key;
+    let grant_hash = aa.enqueue(EntryPointCall::AddSession(grant)).await.map_err(rejection)?;
+    Ok(Json(serde_json::json!({ "sessionKey": session_key, "grantHash": grant_hash, "status": "pending" })))
+}
+
+async fn register_account(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Json(registration): Json<AccountRegistration>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let account = registration.account();
+    let registration_hash = aa
+        .enqueue(EntryPointCall::RegisterAccount(registration))
+        .await
+        .map_err(rejection)?;
+    Ok(Json(serde_json::json!({
+        "account": account,
+        "registrationHash": registration_hash,
+        "status": "pending",
+    })))
+}
+
+async fn set_paymaster_policy(
+    State(aa): State<Arc<AccountAbstraction>>,
+    Json(update): Json<PolicyUpdate>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let paymaster = update.paymaster;
+    let update_hash = aa
+        .enqueue(EntryPointCall::SetPaymasterPolicy(update))
+        .await
+        .map_err(rejection)?;
+    Ok(Json(serde_json::json!({ "paymaster": paymaster, "updateHash": update_hash, "status": "pending" })))
+}
+
+pub fn bundler_router(aa: Arc<AccountAbstraction>) -> Router {
+    Router::new()
+        .route("/bundler/userop", post(submit_user_op))
+        .route("/bundler/userop/estimate", post(estimate_user_op))
+        .route("/bundler/userop/:hash", get(get_user_op))
+        .route("/bundler/accounts", post(register_account))
+        .route("/bundler/accounts/:address", get(get_account))
+        .route("/bundler/sessions", post(add_session))
+        .route("/bundler/paymasters/policy", post(set_paymaster_policy))
+        .with_state(aa)
+}
@@ -8,6 +8,7 @@ use tokio::sync::RwLock;
 
 use crate::api::ApiError;
 use crate::ledger::block::Block;
+use crate::ledger::state::finality::FinalityStatus;
 use crate::ledger::state::State;
 use crate::types::Hash;
 
@@ -54,6 +55,12 @@ pub struct BlockResponse {
     pub is_valid: bool,
     /// Finalization status
     pub is_finalized: bool,
+    /// Whether consensus has finalized this block
+    #[serde(default)]
+    pub finalized: bool,
+    /// Number of canonical blocks on top of (and including) this block
+    #[serde(default)]
+    pub confirmations: u64,
     /// Block creation time (ms)
     pub creation_time_ms: u64,
     /// Block processing time (ms)
@@ -128,13 +135,25 @@ impl From<&Block> for BlockResponse {
             }),
             transaction_hashes,
             is_valid: true, // Assume valid if in blockchain
-            is_finalized: current_time.saturating_sub(block.header.timestamp) > 300, // Finalized after 5 minutes
+            is_finalized: false, // Set from consensus finality by `with_finality`
+            finalized: false,
+            confirmations: 0,
             creation_time_ms: block.header.timestamp * 1000,
             processing_time_ms,
         }
     }
 }
 
+impl BlockResponse {
+    /// Fill in finality and confirmations from the consensus-published state
+    pub fn with_finality(mut self, state: &State) -> Self {
+        self.finalized = state.is_block_finalized(self.height);
+        self.is_finalized = self.finalized;
+        self.confirmations = state.confirmations(self.height);
+        self
+    }
+}
+
 /// Query parameters for block list
 #[derive(Debug, Deserialize)]
 pub struct BlockQueryParams {
@@ -157,7 +176,7 @@ pub async fn get_latest_block(
     let state = state.read().await;
 
     match state.latest_block() {
-        Some(block) => Ok(Json(BlockResponse::from(block))),
+        Some(block) => Ok(Json(BlockResponse::from(block).with_finality(&state))),
         None => {
             // Blockchain is empty - return genesis block
             let genesis_block = BlockResponse {
@@ -189,6 +208,8 @@ pub async fn get_latest_block(
                 transaction_hashes: vec![],
                 is_valid: true,
                 is_finalized: true,
+                finalized: true,
+                confirmations: 0,
                 creation_time_ms: std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
@@ -212,7 +233,7 @@ pub async fn get_block_by_hash(
 
     state
         .get_block_by_hash(&hash)
-        .map(|block| Json(BlockResponse::from(block)))
+        .map(|block| Json(BlockResponse::from(block).with_finality(&state)))
         .ok_or_else(|| ApiError::not_found("Block not found"))
 }
 
@@ -225,7 +246,7 @@ pub async fn get_block_by_height(
 
     state
         .get_block_by_height(height)
-        .map(|block| Json(BlockResponse::from(block)))
+        .map(|block| Json(BlockResponse::from(block).with_finality(&state)))
         .ok_or_else(|| ApiError::not_found(&format!("Block at height {height} not found")))
 }
 
@@ -240,7 +261,10 @@ pub async fn get_blocks(
         .get_blocks(params.start, params.limit)
         .map_err(|e| ApiError::internal_server_error(&format!("Failed to get blocks: {e}")))
         .map(|blocks| {
-            let responses: Vec<BlockResponse> = blocks.iter().map(BlockResponse::from).collect();
+            let responses: Vec<BlockResponse> = blocks
+                .iter()
+                .map(|block| BlockResponse::from(block).with_finality(&state))
+                .collect();
             Json(responses)
         })
 }
@@ -295,6 +319,15 @@ pub async fn sync_block_from_other_node(
     }))
 }
 
+/// Get finality status: finalized head, gap to chain head, recent
+/// finalization latency and reorg counters
+pub async fn get_finality_status(
+    Extension(state): Extension<Arc<RwLock<State>>>,
+) -> Result<Json<FinalityStatus>, ApiError> {
+    let state = state.read().await;
+    Ok(Json(state.finality_status()))
+}
+
 /// Get blockchain height
 pub async fn get_blockchain_height(
     Extension(state): Extension<Arc<RwLock<State>>>,
@@ -35,6 +35,9 @@ pub struct TransactionResponse {
     pub block_height: Option<u64>,
     /// Number of confirmations
     pub confirmations: u64,
+    /// Whether the including block has been finalized by consensus
+    #[serde(default)]
+    pub finalized: bool,
     /// Transaction type
     pub tx_type: u8,
     /// Transaction data (hex encoded)
@@ -59,6 +62,7 @@ impl TransactionResponse {
             block_hash: block_hash.map(|h| format!("0x{}", hex::encode(h.as_ref()))),
             block_height,
             confirmations,
+            finalized: false,
             tx_type: match tx.tx_type {
                 TransactionType::Transfer => 0,
                 TransactionType::ContractCreate => 1,
@@ -145,21 +149,18 @@ pub async fn get_transaction(
         // Convert types::Transaction to ledger::transaction::Transaction
         let ledger_tx: crate::ledger::transaction::Transaction = tx.clone();
 
-        // Calculate confirmations if the transaction is in a block
-        let confirmations = if let Some(latest_block) = state.latest_block() {
-            latest_block.header.height.saturating_sub(block_height) + 1
-        } else {
-            0
-        };
+        // Confirmations count canonical blocks only; reorged blocks drop out
+        let confirmations = state.confirmations(block_height);
 
         let block_hash: Option<String> = Some(block_hash);
         let block_hash_ref: Option<crate::utils::crypto::Hash> = None;
-        let response = TransactionResponse::from_tx(
+        let mut response = TransactionResponse::from_tx(
             &ledger_tx,
             block_hash_ref.as_ref(),
             Some(block_height),
             confirmations,
         );
+        response.finalized = state.is_block_finalized(block_height);
         Ok(Json(response))
     } else {
         Err(ApiError::not_found("Transaction not found"))
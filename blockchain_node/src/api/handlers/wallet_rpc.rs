@@ -697,15 +697,30 @@ async fn handle_get_transaction_receipt(
     };
 
     // Look up actual transaction receipt from blockchain state
-    let receipt = match state.read().await.get_transaction(tx_hash) {
+    let state_guard = state.read().await;
+    let receipt = match state_guard.get_transaction(tx_hash) {
         Some(tx) => {
-            // Get block information for this transaction
-            let block_info = None::<()>; // State does not expose block info; keep fields defaulted
+            // Block inclusion and finality, for callers waiting on settlement safety
+            let (block_hash, block_number, confirmations, finalized) =
+                match state_guard.get_transaction_by_hash(tx_hash) {
+                    Some((_, block_hash, height)) => (
+                        format!("0x{}", block_hash),
+                        height,
+                        state_guard.confirmations(height),
+                        state_guard.is_block_finalized(height),
+                    ),
+                    None => (
+                        "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                        0,
+                        0,
+                        false,
+                    ),
+                };
             json!({
                 "transactionHash": tx_hash,
                 "transactionIndex": format!("0x{:x}", 0u64),
-                "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
-                "blockNumber": format!("0x{:x}", 0u64),
+                "blockHash": block_hash,
+                "blockNumber": format!("0x{:x}", block_number),
                 "from": tx.sender,
                 "to": tx.recipient,
                 "cumulativeGasUsed": format!("0x{:x}", tx.gas_limit),
@@ -715,7 +730,9 @@ async fn handle_get_transaction_receipt(
                 "logsBloom": "0x" ,
                 "status": match tx.status { crate::ledger::transaction::TransactionStatus::Success | crate::ledger::transaction::TransactionStatus::Confirmed => "0x1", _ => "0x0" },
                 "effectiveGasPrice": format!("0x{:x}", tx.gas_price),
-                "type": "0x0"
+                "type": "0x0",
+                "confirmations": confirmations,
+                "finalized": finalized
             })
         },
         None => {
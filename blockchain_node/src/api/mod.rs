@@ -1,4 +1,5 @@
 pub mod blockchain_api;
+pub mod bundler;
 pub mod errors;
 pub mod faucet;
 pub mod fraud_monitoring;
@@ -19,7 +20,9 @@ pub mod websocket_service;
 pub mod arthachain;
 pub mod arthachain_router;
 pub mod ai_endpoints;
+pub mod rpc_guard;
 pub mod dashboard_api;
+pub mod svdb_replication;
 
 pub use errors::*;
 pub use metrics::MetricsService;
@@ -0,0 +1,187 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Budget a request is charged against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostClass {
+    Cheap,
+    Expensive,
+    Submit,
+}
+
+impl CostClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostClass::Cheap => "cheap",
+            CostClass::Expensive => "expensive",
+            CostClass::Submit => "submit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodCost {
+    pub class: CostClass,
+    pub weight: u32,
+}
+
+impl MethodCost {
+    pub const fn new(class: CostClass, weight: u32) -> Self {
+        Self { class, weight }
+    }
+}
+
+/// Token bucket refilled at `per_second` cost units up to `burst`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RpcGuardConfig {
+    /// Per-client budgets, by class
+    pub cheap: Budget,
+    pub expensive: Budget,
+    pub submit: Budget,
+    /// JSON-RPC method costs. A key ending in `*` matches by prefix, the
+    /// longest prefix winning; unlisted methods are cheap with weight 1.
+    pub methods: BTreeMap<String, MethodCost>,
+    /// REST costs by path prefix, the longest prefix winning
+    pub paths: BTreeMap<String, MethodCost>,
+    /// Expensive requests in flight across all public clients
+    pub expensive_max_concurrency: usize,
+    /// Suggested backoff when a request is turned away for load rather than budget
+    pub backoff_ms: u64,
+    /// Service key (sent as `x-service-key`) -> platform service name
+    pub service_keys: HashMap<String, String>,
+    /// Issued API keys (sent as `x-api-key`); a client presenting one is
+    /// budgeted under the key instead of its address
+    pub api_keys: HashSet<String>,
+    /// Request-handling workers, of which public traffic may never hold the reserved ones
+    pub workers: usize,
+    pub platform_reserved_workers: usize,
+    /// Weighted fair shares of the workers
+    pub platform_weight: u32,
+    pub public_weight: u32,
+    /// Public requests allowed to wait for a worker before new ones are turned away
+    pub public_queue_limit: usize,
+    /// Key clients by the first `x-forwarded-for` hop; only behind a trusted proxy
+    pub trust_forwarded_for: bool,
+    /// Consumers listed in stats and the periodic log
+    pub top_consumers: usize,
+}
+
+impl Default for RpcGuardConfig {
+    fn default() -> Self {
+        use CostClass::*;
+        let methods = [
+            ("eth_getLogs", MethodCost::new(Expensive, 10)),
+            ("eth_getFilterLogs", MethodCost::new(Expensive, 10)),
+            ("eth_getProof", MethodCost::new(Expensive, 8)),
+            ("getProgramAccounts", MethodCost::new(Expensive, 8)),
+            ("debug_trace*", MethodCost::new(Expensive, 20)),
+            ("trace_*", MethodCost::new(Expensive, 20)),
+            ("eth_call", MethodCost::new(Cheap, 2)),
+            ("eth_estimateGas", MethodCost::new(Cheap, 2)),
+            ("eth_estimateUserOperationGas", MethodCost::new(Cheap, 2)),
+            ("simulateTransaction", MethodCost::new(Cheap, 2)),
+            ("eth_sendRawTransaction", MethodCost::new(Submit, 1)),
+            ("eth_sendTransaction", MethodCost::new(Submit, 1)),
+            ("eth_sendUserOperation", MethodCost::new(Submit, 1)),
+            ("wasm_deployContract", MethodCost::new(Submit, 1)),
+        ];
+        let paths = [
+            ("/api/v1/transactions/submit", MethodCost::new(Submit, 1)),
+            ("/api/v1/faucet/request", MethodCost::new(Submit, 1)),
+            ("/api/v1/blocks/sync", MethodCost::new(Submit, 1)),
+            ("/api/v1/mempool/transactions", MethodCost::new(Cheap, 2)),
+        ];
+        Self {
+            cheap: Budget { per_second: 50.0, burst: 100.0 },
+            expensive: Budget { per_second: 20.0, burst: 60.0 },
+            submit: Budget { per_second: 10.0, burst: 20.0 },
+            methods: methods.into_iter().map(|(m, c)| (m.to_string(), c)).collect(),
+            paths: paths.into_iter().map(|(p, c)| (p.to_string(), c)).collect(),
+            expensive_max_concurrency: 8,
+            backoff_ms: 1000,
+            service_keys: HashMap::new(),
+            api_keys: HashSet::new(),
+            workers: 64,
+            platform_reserved_workers: 16,
+            platform_weight: 8,
+            public_weight: 1,
+            public_queue_limit: 1024,
+            trust_forwarded_for: false,
+            top_consumers: 10,
+        }
+    }
+}
+
+fn lookup(table: &BTreeMap<String, MethodCost>, key: &str, prefixes_need_star: bool) -> Option<MethodCost> {
+    if let Some(cost) = table.get(key) {
+        return Some(*cost);
+    }
+    table
+        .iter()
+        .filter_map(|(pattern, cost)| {
+            let prefix = match pattern.strip_suffix('*') {
+                Some(prefix) => prefix,
+                None if prefixes_need_star => return None,
+                None => pattern.as_str(),
+            };
+            key.starts_with(prefix).then_some((prefix.len(), *cost))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, cost)| cost)
+}
+
+impl RpcGuardConfig {
+    /// Load from a YAML file; missing fields keep their defaults
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read RPC guard config {}", path.display()))?;
+        let config: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse RPC guard config {}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (class, budget) in [("cheap", &self.cheap), ("expensive", &self.expensive), ("submit", &self.submit)] {
+            if !(budget.per_second > 0.0 && budget.burst >= 1.0) {
+                bail!("The {} budget needs a positive rate and a burst of at least 1", class);
+            }
+        }
+        if self.methods.values().chain(self.paths.values()).any(|c| c.weight == 0) {
+            bail!("Method and path weights must be positive");
+        }
+        if self.platform_reserved_workers >= self.workers {
+            bail!("Reserved platform workers must leave at least one worker for public traffic");
+        }
+        if self.platform_weight == 0 || self.public_weight == 0 || self.expensive_max_concurrency == 0 {
+            bail!("Lane weights and the expensive concurrency cap must be positive");
+        }
+        Ok(())
+    }
+
+    pub fn budget(&self, class: CostClass) -> Budget {
+        match class {
+            CostClass::Cheap => self.cheap,
+            CostClass::Expensive => self.expensive,
+            CostClass::Submit => self.submit,
+        }
+    }
+
+    pub fn method_cost(&self, method: &str) -> MethodCost {
+        lookup(&self.methods, method, true).unwrap_or(MethodCost::new(CostClass::Cheap, 1))
+    }
+
+    pub fn path_cost(&self, path: &str) -> MethodCost {
+        lookup(&self.paths, path, false).unwrap_or(MethodCost::new(CostClass::Cheap, 1))
+    }
+}
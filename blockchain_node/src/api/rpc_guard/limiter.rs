@@ -0,0 +1,139 @@
+use super::config::{Budget, CostClass};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Buckets idle this long are full again and can be forgotten
+const IDLE_EVICTION: Duration = Duration::from_secs(600);
+const SWEEP_EVERY: u64 = 4096;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Per-client token buckets, one per cost class. Budgets are read on every
+/// take, so a reloaded config applies to existing clients immediately.
+#[derive(Debug, Default)]
+pub struct ClientLimiter {
+    buckets: HashMap<(String, CostClass), Bucket>,
+    takes: u64,
+}
+
+impl ClientLimiter {
+    /// Charge `cost` to the client's bucket for `class`, or say how long
+    /// until it could be afforded. A cost above the burst is charged as a
+    /// full burst so that heavy requests stay possible, just rare.
+    pub fn take(&mut self, client: &str, class: CostClass, cost: f64, budget: Budget, now: Instant) -> Result<(), Duration> {
+        self.takes += 1;
+        if self.takes.is_multiple_of(SWEEP_EVERY) {
+            self.buckets.retain(|_, b| now.duration_since(b.updated) < IDLE_EVICTION);
+        }
+        let bucket = self
+            .buckets
+            .entry((client.to_string(), class))
+            .or_insert(Bucket { tokens: budget.burst, updated: now });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * budget.per_second).min(budget.burst);
+        bucket.updated = now;
+
+        let cost = cost.min(budget.burst);
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((cost - bucket.tokens) / budget.per_second))
+        }
+    }
+
+    pub fn clients(&self) -> usize {
+        self.buckets.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// The client's budget for the class is spent
+    RateLimited,
+    /// Too many expensive requests in flight
+    ExpensiveCap,
+    /// The public worker queue is full
+    QueueFull,
+}
+
+impl RejectReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::RateLimited => "rate_limited",
+            RejectReason::ExpensiveCap => "expensive_cap",
+            RejectReason::QueueFull => "queue_full",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConsumerStats {
+    pub client: String,
+    pub requests: u64,
+    pub cost: u64,
+    pub rejected: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectionCount {
+    pub reason: RejectReason,
+    pub class: CostClass,
+    pub count: u64,
+}
+
+/// What each client has consumed since the last report, and rejections
+/// since start
+#[derive(Debug, Default)]
+pub struct GuardStats {
+    consumers: HashMap<String, ConsumerStats>,
+    rejections: HashMap<(RejectReason, CostClass), u64>,
+}
+
+impl GuardStats {
+    fn consumer(&mut self, client: &str) -> &mut ConsumerStats {
+        self.consumers
+            .entry(client.to_string())
+            .or_insert_with(|| ConsumerStats { client: client.to_string(), ..Default::default() })
+    }
+
+    pub fn admitted(&mut self, client: &str, cost: u64) {
+        let consumer = self.consumer(client);
+        consumer.requests += 1;
+        consumer.cost += cost;
+    }
+
+    pub fn rejected(&mut self, client: &str, reason: RejectReason, class: CostClass) {
+        self.consumer(client).rejected += 1;
+        *self.rejections.entry((reason, class)).or_default() += 1;
+    }
+
+    /// Heaviest consumers first
+    pub fn top_consumers(&self, n: usize) -> Vec<ConsumerStats> {
+        let mut consumers: Vec<ConsumerStats> = self.consumers.values().cloned().collect();
+        consumers.sort_by(|a, b| b.cost.cmp(&a.cost).then(b.rejected.cmp(&a.rejected)).then(a.client.cmp(&b.client)));
+        consumers.truncate(n);
+        consumers
+    }
+
+    pub fn rejections(&self) -> Vec<RejectionCount> {
+        let mut counts: Vec<RejectionCount> = self
+            .rejections
+            .iter()
+            .map(|((reason, class), count)| RejectionCount { reason: *reason, class: *class, count: *count })
+            .collect();
+        counts.sort_by_key(|c| (c.reason.as_str(), c.class));
+        counts
+    }
+
+    /// Start a new consumption window
+    pub fn reset_consumers(&mut self) {
+        self.consumers.clear();
+    }
+}
@@ -0,0 +1,421 @@
+//! Public API Protection
+//! Rate limiting and worker scheduling in front of the node's JSON-RPC and
+//! REST endpoints. Every request is classified as a cheap read, an
+//! expensive query (logs, traces, proofs) or a transaction submission,
+//! with a per-method cost weight. Public clients, keyed by issued API key
+//! or else by IP, are charged against a token bucket per class, and the
+//! expensive class is also capped in flight across all public clients.
+//! Platform services presenting a service key skip the budgets and run in
+//! their own lane: request-handling workers are shared between the lanes
+//! by weighted fair queueing, with some held back for the platform lane
+//! alone, so a public flood waits in its own queue instead of ahead of
+//! ai-jobd or the scheduler. Turned-away requests get 429 and JSON-RPC
+//! error -32005 with a suggested backoff. The config is re-read when its
+//! file changes.
+
+pub mod config;
+pub mod limiter;
+pub mod scheduler;
+
+pub use config::{Budget, CostClass, MethodCost, RpcGuardConfig};
+pub use limiter::{ConsumerStats, RejectReason, RejectionCount};
+pub use scheduler::{Lane, PoolSnapshot};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use limiter::{ClientLimiter, GuardStats};
+use parking_lot::{Mutex, RwLock};
+use scheduler::{PoolLimits, WorkerPermit, WorkerPool};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// JSON-RPC error code for requests turned away for load or budget
+pub const LIMIT_EXCEEDED: i64 = -32005;
+
+/// Largest request body buffered for classification
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    static ref RPC_GUARD_REQUESTS: prometheus::IntCounterVec = prometheus::IntCounterVec::new(
+        prometheus::Opts::new("rpc_guard_requests_total", "API requests admitted by lane and cost class"),
+        &["lane", "class"],
+    ).unwrap();
+    static ref RPC_GUARD_REJECTIONS: prometheus::IntCounterVec = prometheus::IntCounterVec::new(
+        prometheus::Opts::new("rpc_guard_rejections_total", "API requests turned away by reason and cost class"),
+        &["reason", "class"],
+    ).unwrap();
+}
+
+static REGISTER_GUARD_METRICS: std::sync::Once = std::sync::Once::new();
+
+/// Who a request is charged to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// A platform service, by name
+    Platform(String),
+    /// A public client: `key:<api key>` or `ip:<address>`
+    Public(String),
+}
+
+impl Caller {
+    pub fn lane(&self) -> Lane {
+        match self {
+            Caller::Platform(_) => Lane::Platform,
+            Caller::Public(_) => Lane::Public,
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            Caller::Platform(service) => format!("service:{}", service),
+            Caller::Public(client) => client.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejection {
+    pub reason: RejectReason,
+    pub class: CostClass,
+    pub backoff: Duration,
+}
+
+impl Rejection {
+    /// 429 with Retry-After; JSON-RPC callers get an error object with `id`
+    pub fn into_response(self, rpc_id: Option<Value>) -> Response {
+        let backoff_ms = self.backoff.as_millis() as u64;
+        let message = match self.reason {
+            RejectReason::RateLimited => format!("Rate limit exceeded for {} requests", self.class.as_str()),
+            RejectReason::ExpensiveCap => "Too many expensive queries in flight".to_string(),
+            RejectReason::QueueFull => "Server busy".to_string(),
+        };
+        let data = json!({ "reason": self.reason, "class": self.class, "backoff_ms": backoff_ms });
+        let body = match rpc_id {
+            Some(id) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": LIMIT_EXCEEDED, "message": message, "data": data },
+            }),
+            None => json!({ "error": message, "code": LIMIT_EXCEEDED, "data": data }),
+        };
+        let retry_after = backoff_ms.div_ceil(1000).max(1).to_string();
+        (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after)], Json(body)).into_response()
+    }
+}
+
+/// Marks an expensive request in flight until dropped
+struct ExpensiveSlot(Arc<AtomicUsize>);
+
+impl Drop for ExpensiveSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Held while the request is handled
+pub struct Admission {
+    _worker: WorkerPermit,
+    _expensive: Option<ExpensiveSlot>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GuardSnapshot {
+    pub top_consumers: Vec<ConsumerStats>,
+    pub rejections: Vec<RejectionCount>,
+    pub expensive_in_flight: usize,
+    pub workers: PoolSnapshot,
+    pub tracked_clients: usize,
+}
+
+pub struct RpcGuard {
+    config: RwLock<Arc<RpcGuardConfig>>,
+    limiter: Mutex<ClientLimiter>,
+    stats: Mutex<GuardStats>,
+    pool: Arc<WorkerPool>,
+    expensive_in_flight: Arc<AtomicUsize>,
+}
+
+fn pool_limits(config: &RpcGuardConfig) -> PoolLimits {
+    PoolLimits {
+        workers: config.workers,
+        platform_reserved: config.platform_reserved_workers,
+        platform_weight: config.platform_weight,
+        public_weight: config.public_weight,
+        public_queue_limit: config.public_queue_limit,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl RpcGuard {
+    pub fn new(config: RpcGuardConfig) -> Arc<Self> {
+        REGISTER_GUARD_METRICS.call_once(|| {
+            let _ = crate::monitoring::METRICS_REGISTRY.register(Box::new(RPC_GUARD_REQUESTS.clone()));
+            let _ = crate::monitoring::METRICS_REGISTRY.register(Box::new(RPC_GUARD_REJECTIONS.clone()));
+        });
+        Arc::new(Self {
+            pool: WorkerPool::new(pool_limits(&config)),
+            config: RwLock::new(Arc::new(config)),
+            limiter: Mutex::new(ClientLimiter::default()),
+            stats: Mutex::new(GuardStats::default()),
+            expensive_in_flight: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Config from the YAML file at ARTHA_RPC_GUARD_CONFIG, watched for
+    /// changes, or the defaults
+    pub fn from_env() -> Arc<Self> {
+        let Some(path) = std::env::var_os("ARTHA_RPC_GUARD_CONFIG").map(PathBuf::from) else {
+            return Self::new(RpcGuardConfig::default());
+        };
+        let config = RpcGuardConfig::from_file(&path).unwrap_or_else(|e| {
+            log::error!("{:#}; using default RPC guard config", e);
+            RpcGuardConfig::default()
+        });
+        let guard = Self::new(config);
+        guard.watch(path, Duration::from_secs(5));
+        guard
+    }
+
+    pub fn config(&self) -> Arc<RpcGuardConfig> {
+        self.config.read().clone()
+    }
+
+    /// Swap in a new config. Budgets, costs, caps and service keys apply to
+    /// the next request; busy workers finish under the old pool size.
+    pub fn reload(&self, config: RpcGuardConfig) -> anyhow::Result<()> {
+        config.validate()?;
+        self.pool.set_limits(pool_limits(&config));
+        *self.config.write() = Arc::new(config);
+        log::info!("RPC guard config reloaded");
+        Ok(())
+    }
+
+    pub fn reload_from(&self, path: &Path) -> anyhow::Result<()> {
+        self.reload(RpcGuardConfig::from_file(path)?)
+    }
+
+    /// Reload whenever the file's modification time changes. A config that
+    /// fails to load is logged and the running one kept.
+    pub fn watch(self: &Arc<Self>, path: PathBuf, every: Duration) -> tokio::task::JoinHandle<()> {
+        let guard = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut seen = modified(&path);
+            let mut ticker = tokio::time::interval(every);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let current = modified(&path);
+                if current == seen {
+                    continue;
+                }
+                seen = current;
+                if let Err(e) = guard.reload_from(&path) {
+                    log::error!("{:#}; keeping the running RPC guard config", e);
+                }
+            }
+        })
+    }
+
+    /// Log the heaviest consumers and rejection counts every `every`, then
+    /// start a new consumption window
+    pub fn report(self: &Arc<Self>, every: Duration) -> tokio::task::JoinHandle<()> {
+        let guard = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let snapshot = guard.snapshot();
+                guard.stats.lock().reset_consumers();
+                if snapshot.top_consumers.is_empty() {
+                    continue;
+                }
+                let top: Vec<String> = snapshot
+                    .top_consumers
+                    .iter()
+                    .map(|c| format!("{} cost={} requests={} rejected={}", c.client, c.cost, c.requests, c.rejected))
+                    .collect();
+                log::info!("RPC top consumers: {}", top.join("; "));
+                if !snapshot.rejections.is_empty() {
+                    let rejections: Vec<String> = snapshot
+                        .rejections
+                        .iter()
+                        .map(|r| format!("{}/{}={}", r.reason.as_str(), r.class.as_str(), r.count))
+                        .collect();
+                    log::warn!("RPC rejections since start: {}", rejections.join(", "));
+                }
+            }
+        })
+    }
+
+    /// Service key first, then an issued API key, then the peer address
+    pub fn identify(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Caller {
+        let config = self.config();
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+        if let Some(service) = header("x-service-key").and_then(|k| config.service_keys.get(k)) {
+            return Caller::Platform(service.clone());
+        }
+        // Unissued keys fall back to the address, so minting keys buys nothing
+        if let Some(key) = header("x-api-key").filter(|k| config.api_keys.contains(*k)) {
+            return Caller::Public(format!("key:{}", key));
+        }
+        let forwarded = config
+            .trust_forwarded_for
+            .then(|| header("x-forwarded-for"))
+            .flatten()
+            .and_then(|v| v.split(',').next())
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok());
+        match forwarded.or(peer) {
+            Some(ip) => Caller::Public(format!("ip:{}", ip)),
+            None => Caller::Public("ip:unknown".to_string()),
+        }
+    }
+
+    /// Costs of a JSON-RPC body, one per call in a batch, and the id to
+    /// answer a rejection with. `None` if the body is not JSON-RPC.
+    pub fn classify_rpc(&self, body: &[u8]) -> Option<(Vec<MethodCost>, Value)> {
+        let value: Value = serde_json::from_slice(body).ok()?;
+        let config = self.config();
+        let cost = |call: &Value| call.get("method").and_then(Value::as_str).map(|m| config.method_cost(m));
+        match &value {
+            Value::Object(_) => Some((vec![cost(&value)?], value.get("id").cloned().unwrap_or(Value::Null))),
+            Value::Array(calls) if !calls.is_empty() => {
+                Some((calls.iter().map(cost).collect::<Option<Vec<_>>>()?, Value::Null))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn classify_path(&self, path: &str) -> MethodCost {
+        self.config().path_cost(path)
+    }
+
+    fn reject(&self, caller: &Caller, reason: RejectReason, class: CostClass, backoff: Duration) -> Rejection {
+        let client = caller.id();
+        self.stats.lock().rejected(&client, reason, class);
+        RPC_GUARD_REJECTIONS.with_label_values(&[reason.as_str(), class.as_str()]).inc();
+        log::debug!("RPC guard rejected {} ({} {})", client, reason.as_str(), class.as_str());
+        Rejection { reason, class, backoff }
+    }
+
+    /// Charge the request's costs and wait for a worker. The admission is
+    /// held until the request has been handled.
+    pub async fn admit(&self, caller: &Caller, costs: &[MethodCost]) -> Result<Admission, Rejection> {
+        let config = self.config();
+        let mut by_class: BTreeMap<CostClass, u32> = BTreeMap::new();
+        for cost in costs {
+            *by_class.entry(cost.class).or_default() += cost.weight;
+        }
+        let total: u32 = by_class.values().sum();
+        let expensive = by_class.contains_key(&CostClass::Expensive);
+        let class = if expensive {
+            CostClass::Expensive
+        } else {
+            by_class.keys().next_back().copied().unwrap_or(CostClass::Cheap)
+        };
+        let backoff = Duration::from_millis(config.backoff_ms);
+
+        if let Caller::Public(client) = caller {
+            let now = Instant::now();
+            let mut limiter = self.limiter.lock();
+            for (charged, weight) in &by_class {
+                if let Err(wait) = limiter.take(client, *charged, *weight as f64, config.budget(*charged), now) {
+                    drop(limiter);
+                    return Err(self.reject(caller, RejectReason::RateLimited, *charged, wait.max(backoff)));
+                }
+            }
+        }
+
+        let slot = if expensive {
+            let in_flight = self.expensive_in_flight.fetch_add(1, Ordering::AcqRel);
+            let slot = ExpensiveSlot(Arc::clone(&self.expensive_in_flight));
+            // Platform queries count toward the cap but are never held to it
+            if caller.lane() == Lane::Public && in_flight >= config.expensive_max_concurrency {
+                drop(slot);
+                return Err(self.reject(caller, RejectReason::ExpensiveCap, class, backoff));
+            }
+            Some(slot)
+        } else {
+            None
+        };
+
+        let worker = match self.pool.acquire(caller.lane(), total).await {
+            Ok(worker) => worker,
+            Err(_) => return Err(self.reject(caller, RejectReason::QueueFull, class, backoff)),
+        };
+        self.stats.lock().admitted(&caller.id(), total as u64);
+        let lane = match caller.lane() {
+            Lane::Platform => "platform",
+            Lane::Public => "public",
+        };
+        RPC_GUARD_REQUESTS.with_label_values(&[lane, class.as_str()]).inc();
+        Ok(Admission { _worker: worker, _expensive: slot })
+    }
+
+    pub fn snapshot(&self) -> GuardSnapshot {
+        let top = self.config().top_consumers;
+        let stats = self.stats.lock();
+        GuardSnapshot {
+            top_consumers: stats.top_consumers(top),
+            rejections: stats.rejections(),
+            expensive_in_flight: self.expensive_in_flight.load(Ordering::Acquire),
+            workers: self.pool.snapshot(),
+            tracked_clients: self.limiter.lock().clients(),
+        }
+    }
+}
+
+/// Middleware: classify, charge and schedule every request. Bodies are
+/// buffered so JSON-RPC calls can be priced by method; anything else is
+/// priced by path.
+pub async fn protect(State(guard): State<Arc<RpcGuard>>, request: Request, next: Next) -> Response {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0.ip());
+    let caller = guard.identify(request.headers(), peer);
+    let (parts, body) = request.into_parts();
+    let body = if parts.method == Method::POST {
+        match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+            Ok(bytes) => bytes,
+            Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+        }
+    } else {
+        Bytes::new()
+    };
+    let (costs, rpc_id) = match guard.classify_rpc(&body) {
+        Some((costs, id)) => (costs, Some(id)),
+        None => (vec![guard.classify_path(parts.uri.path())], None),
+    };
+
+    let _admission = match guard.admit(&caller, &costs).await {
+        Ok(admission) => admission,
+        Err(rejection) => return rejection.into_response(rpc_id),
+    };
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// `GET /api/v1/rpc-guard/stats`, for platform services only
+pub fn stats_router(guard: Arc<RpcGuard>) -> Router {
+    Router::new().route("/api/v1/rpc-guard/stats", get(get_stats)).with_state(guard)
+}
+
+async fn get_stats(State(guard): State<Arc<RpcGuard>>, headers: HeaderMap) -> Result<Json<GuardSnapshot>, StatusCode> {
+    match guard.identify(&headers, None) {
+        Caller::Platform(_) => Ok(Json(guard.snapshot())),
+        Caller::Public(_) => Err(StatusCode::FORBIDDEN),
+    }
+}
@@ -0,0 +1,171 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lane {
+    Platform,
+    Public,
+}
+
+impl Lane {
+    fn index(self) -> usize {
+        match self {
+            Lane::Platform => 0,
+            Lane::Public => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolLimits {
+    pub workers: usize,
+    pub platform_reserved: usize,
+    pub platform_weight: u32,
+    pub public_weight: u32,
+    pub public_queue_limit: usize,
+}
+
+struct Waiter {
+    finish: f64,
+    tx: oneshot::Sender<WorkerPermit>,
+}
+
+struct PoolState {
+    limits: PoolLimits,
+    busy: usize,
+    /// Finish tag of the request last given a worker
+    vtime: f64,
+    last_finish: [f64; 2],
+    queues: [VecDeque<Waiter>; 2],
+}
+
+impl PoolState {
+    fn weight(&self, lane: usize) -> f64 {
+        if lane == 0 {
+            self.limits.platform_weight as f64
+        } else {
+            self.limits.public_weight as f64
+        }
+    }
+
+    /// Public traffic never holds the workers reserved for platform services
+    fn has_worker_for(&self, lane: usize) -> bool {
+        let usable = if lane == 0 {
+            self.limits.workers
+        } else {
+            self.limits.workers - self.limits.platform_reserved
+        };
+        self.busy < usable
+    }
+}
+
+/// Request-handling workers shared by the platform and public lanes with
+/// self-clocked weighted fair queueing: a request's finish tag is
+/// max(vtime, lane's last tag) + cost / lane weight, and whenever a worker
+/// is free the eligible queue head with the lowest tag gets it. Under
+/// contention each lane receives workers in proportion to its weight, and
+/// a burst of public requests cannot push platform tags behind it because
+/// tags only advance per lane.
+pub struct WorkerPool {
+    state: Mutex<PoolState>,
+}
+
+/// A worker held for one request, handed back on drop
+pub struct WorkerPermit {
+    pool: Option<Arc<WorkerPool>>,
+}
+
+impl Drop for WorkerPermit {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            let mut state = pool.state.lock().unwrap();
+            state.busy -= 1;
+            pool.dispatch(&mut state);
+        }
+    }
+}
+
+/// The public lane's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolSnapshot {
+    pub busy: usize,
+    pub platform_waiting: usize,
+    pub public_waiting: usize,
+}
+
+impl WorkerPool {
+    pub fn new(limits: PoolLimits) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(PoolState {
+                limits,
+                busy: 0,
+                vtime: 0.0,
+                last_finish: [0.0; 2],
+                queues: [VecDeque::new(), VecDeque::new()],
+            }),
+        })
+    }
+
+    /// Workers already busy keep working; new limits apply as they free up
+    pub fn set_limits(self: &Arc<Self>, limits: PoolLimits) {
+        let mut state = self.state.lock().unwrap();
+        state.limits = limits;
+        self.dispatch(&mut state);
+    }
+
+    pub fn snapshot(&self) -> PoolSnapshot {
+        let state = self.state.lock().unwrap();
+        PoolSnapshot {
+            busy: state.busy,
+            platform_waiting: state.queues[0].len(),
+            public_waiting: state.queues[1].len(),
+        }
+    }
+
+    /// Wait for a worker. Only fails when the public queue is full; the
+    /// platform lane is never turned away.
+    pub async fn acquire(self: &Arc<Self>, lane: Lane, cost: u32) -> Result<WorkerPermit, QueueFull> {
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            let i = lane.index();
+            if lane == Lane::Public && state.queues[i].len() >= state.limits.public_queue_limit {
+                return Err(QueueFull);
+            }
+            let finish = state.vtime.max(state.last_finish[i]) + cost.max(1) as f64 / state.weight(i);
+            state.last_finish[i] = finish;
+            let (tx, rx) = oneshot::channel();
+            state.queues[i].push_back(Waiter { finish, tx });
+            self.dispatch(&mut state);
+            rx
+        };
+        // The pool outlives every waiter it holds a sender for
+        rx.await.map_err(|_| QueueFull)
+    }
+
+    fn dispatch(self: &Arc<Self>, state: &mut PoolState) {
+        loop {
+            let next = (0..2)
+                .filter(|&i| state.has_worker_for(i))
+                .filter_map(|i| state.queues[i].front().map(|w| (i, w.finish)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((i, finish)) = next else {
+                return;
+            };
+            let waiter = state.queues[i].pop_front().unwrap();
+            state.busy += 1;
+            state.vtime = state.vtime.max(finish);
+            if let Err(mut permit) = waiter.tx.send(WorkerPermit { pool: Some(Arc::clone(self)) }) {
+                // The caller gave up waiting; dropping the permit as is would
+                // re-enter the lock, so release its worker here
+                permit.pool = None;
+                state.busy -= 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! SVDB Replication REST Endpoints
+//! Per-object replication policy and status, and provider health reports
+//! from readers that feed the replicator's choice of sources and targets.
+
+use crate::storage::replication::{ReplicationPolicy, Replicator};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::post,
+    Router,
+};
+use base64::Engine;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct ProviderHealthReport {
+    #[serde(rename = "nodeId")]
+    pub node_id: String,
+    pub ok: bool,
+    pub latency_ms: Option<f64>,
+}
+
+/// Manifest hash hex from either `artha://<base64>` (prefix optional) or hex
+pub fn manifest_hex(cid: &str) -> Result<String, StatusCode> {
+    let cid = cid.trim_start_matches("artha://");
+    if cid.len() == 64 && cid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(cid.to_lowercase());
+    }
+    let bytes = base64::engine::general_purpose::STANDARD_NO_PAD
+        .decode(cid)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if bytes.len() < 2 + 32 + 1 + 8 + 1 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(hex::encode(&bytes[2..34]))
+}
+
+async fn set_replication_policy(
+    State(replicator): State<Arc<Replicator>>,
+    Path(cid): Path<String>,
+    Json(policy): Json<ReplicationPolicy>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let cid_hex = manifest_hex(&cid)?;
+    policy.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    replicator
+        .set_policy(&cid_hex, policy)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let status = replicator.status(&cid_hex).await.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(serde_json::to_value(status).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
+}
+
+async fn get_replication_status(
+    State(replicator): State<Arc<Replicator>>,
+    Path(cid): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let cid_hex = manifest_hex(&cid)?;
+    let status = replicator.status(&cid_hex).await.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(serde_json::to_value(status).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
+}
+
+async fn report_provider_health(
+    State(replicator): State<Arc<Replicator>>,
+    Json(report): Json<ProviderHealthReport>,
+) -> Json<serde_json::Value> {
+    replicator
+        .record_observation(&report.node_id, report.ok, report.latency_ms)
+        .await;
+    Json(serde_json::json!({ "recorded": true }))
+}
+
+pub fn svdb_replication_router(replicator: Arc<Replicator>) -> Router {
+    Router::new()
+        .route(
+            "/svdb/:cid/replication",
+            post(set_replication_policy).get(get_replication_status),
+        )
+        .route("/svdb/providers/health", post(report_provider_health))
+        .with_state(replicator)
+}
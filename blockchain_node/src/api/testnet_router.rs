@@ -25,6 +25,7 @@ use crate::api::{
     wallet_integration,
     ai_endpoints,
     dashboard_api,
+    svdb_replication,
 };
 use crate::gas_free::GasFreeManager;
 use crate::ledger::state::State;
@@ -32,6 +33,8 @@ use crate::transaction::mempool::Mempool;
 use crate::storage::{svdb_storage::SvdbStorage, Manifests, ChunkStore, Cid, Manifest, Codec, ManifestChunkEntry, MemMapStorage, Storage as _};
 use axum::http::HeaderMap;
 use crate::storage::EncryptionEnvelope;
+use crate::storage::replication::{stored_chunk_matches, ChunkRef, ProviderInfo, ReplicationPolicy, Replicator, ReplicatorConfig};
+use crate::storage::transfer::{TransferConfig, TransferManager};
 use ed25519_dalek::{VerifyingKey, Signature};
 use axum::http::StatusCode as HttpStatusCode;
 use reqwest::Client as HttpClient;
@@ -121,6 +124,28 @@ pub fn create_testnet_router(
     let node_runtime = NodeRuntimeState::new();
     let svdb = SvdbStorage::default();
     let deal_store = MemMapStorage::default();
+    let replicator = Arc::new(Replicator::new(
+        ReplicatorConfig::from_env(),
+        Arc::new(TransferManager::new(TransferConfig::from_env())),
+    ));
+
+    // Background replicator; this node is a source for what it uploads
+    {
+        let replicator = replicator.clone();
+        let node_rt = node_runtime.clone();
+        tokio::spawn(async move {
+            if !node_rt.role_storage_provider { return; }
+            if let Ok(http_addr) = std::env::var("ARTHA_HTTP_ADDR") {
+                replicator.register_provider(ProviderInfo {
+                    node_id: node_rt.node_id.clone(),
+                    region: std::env::var("ARTHA_REGION").unwrap_or_default(),
+                    http_addr,
+                    advertised_latency_ms: None,
+                }).await;
+            }
+            replicator.spawn();
+        });
+    }
 
     // Background epoch scheduler for Merkle sample payouts (v1)
     {
@@ -908,6 +933,8 @@ pub fn create_testnet_router(
         // Core Blockchain APIs - Connect to handlers
         .route("/api/v1/blockchain/height", get(blocks::get_blockchain_height))
         .route("/api/v1/blockchain/status", get(blocks::get_blockchain_status))
+        .route("/api/v1/finality", get(blocks::get_finality_status))
+        .route("/finality", get(blocks::get_finality_status))
         .route("/api/v1/node/id", get(identity::get_node_id))
         
         // Blocks API - Connect to handlers
@@ -1067,10 +1094,12 @@ pub fn create_testnet_router(
             let svdb = svdb.clone();
             let deal_store_for_access = deal_store.clone();
             let node_runtime_for_upload = node_runtime.clone();
+            let replicator = replicator.clone();
             move |mut multipart: Multipart, headers: HeaderMap| async move -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
                 let svdb = svdb.clone();
                 let deal_store_for_access = deal_store_for_access.clone();
                 let node_runtime_for_upload = node_runtime_for_upload.clone();
+                let replicator = replicator.clone();
                     if !node_runtime_for_upload.role_storage_provider { return Err(axum::http::StatusCode::FORBIDDEN); }
                     // Simple per-IP rate limit and size quota
                     let client_ip = headers.get("X-Client-IP").and_then(|v| v.to_str().ok()).unwrap_or("unknown");
@@ -1082,6 +1111,11 @@ pub fn create_testnet_router(
                     let _ = deal_store_for_access.put(rl_key.as_bytes(), &(cnt+1).to_le_bytes()).await;
                     // Optional access policy header: X-Artha-Access: public|private|allowlist
                     let access_mode = headers.get("X-Artha-Access").and_then(|v| v.to_str().ok()).unwrap_or("public");
+                    // Optional replication policy header: X-Artha-Replication: regions=a,b|replicas=N
+                    let replication_policy = match headers.get("X-Artha-Replication").and_then(|v| v.to_str().ok()) {
+                        Some(value) => Some(ReplicationPolicy::from_header(value).map_err(|_| axum::http::StatusCode::BAD_REQUEST)?),
+                        None => None,
+                    };
                     // Stream multipart without buffering entire file in memory
                     // Accumulate into fixed-size buffers and process on the fly
                     let chunk_size: usize = 8 * 1024 * 1024; // 8MB
@@ -1250,6 +1284,12 @@ pub fn create_testnet_router(
                     let mut list: Vec<String> = match deal_store_for_access.get(prov_key.as_bytes()).await { Ok(Some(b)) => serde_json::from_slice(&b).unwrap_or_default(), _ => Vec::new() };
                     if !list.contains(&node_runtime_for_upload.node_id) { list.push(node_runtime_for_upload.node_id.clone()); }
                     let _ = deal_store_for_access.put(prov_key.as_bytes(), serde_json::to_vec(&list).unwrap().as_slice()).await;
+                    // Track for replication; copies start once the object has a policy
+                    let chunk_refs = manifest.chunks.iter().map(|e| ChunkRef { cid_hex: hex::encode(e.cid.blake3), size: e.cid.size }).collect();
+                    replicator.track(&cid_hex, &cid_b64, manifest.codec.clone(), chunk_refs, serde_json::to_vec(&manifest).unwrap_or_default(), &node_runtime_for_upload.node_id).await;
+                    if let Some(policy) = replication_policy {
+                        let _ = replicator.set_policy(&cid_hex, policy).await;
+                    }
                     // Index manifest for epoch scheduler (mf:all)
                     let idx_key = b"mf:all".to_vec();
                     let mut manifests: Vec<String> = match deal_store_for_access.get(&idx_key).await { Ok(Some(b)) => serde_json::from_slice(&b).unwrap_or_default(), _ => Vec::new() };
@@ -1345,6 +1385,46 @@ pub fn create_testnet_router(
                 }
             }
         }))
+        // Accept a chunk pushed by a replicating peer
+        .route("/svdb/chunk/:cid_hex", axum::routing::put({
+            let svdb = svdb.clone();
+            let node_runtime_chunk = node_runtime.clone();
+            move |axum::extract::Path(cid_hex): axum::extract::Path<String>, body: axum::body::Bytes| {
+                let svdb = svdb.clone();
+                let node_runtime_chunk = node_runtime_chunk.clone();
+                async move {
+                    if !node_runtime_chunk.role_storage_provider { return Err(axum::http::StatusCode::FORBIDDEN); }
+                    let mut bl=[0u8;32]; let bytes = hex::decode(&cid_hex).map_err(|_| axum::http::StatusCode::BAD_REQUEST)?; if bytes.len()!=32 { return Err(axum::http::StatusCode::BAD_REQUEST) } bl.copy_from_slice(&bytes);
+                    if !stored_chunk_matches(&cid_hex, &body) { return Err(axum::http::StatusCode::UNPROCESSABLE_ENTITY); }
+                    let cid = Cid::new(0x0129, bl, None, body.len() as u64, Codec::Raw);
+                    ChunkStore::put(&svdb, &cid, &body).await.map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+                    Ok::<_, axum::http::StatusCode>(axum::http::StatusCode::OK)
+                }
+            }
+        }))
+        // Accept the manifest of a replicated object so this node can serve it
+        .route("/svdb/manifest/:cid_b64", axum::routing::put({
+            let svdb = svdb.clone();
+            let deal_store = deal_store.clone();
+            let node_runtime_manifest = node_runtime.clone();
+            move |axum::extract::Path(cid_b64): axum::extract::Path<String>, body: axum::body::Bytes| {
+                let svdb = svdb.clone();
+                let deal_store = deal_store.clone();
+                let node_runtime_manifest = node_runtime_manifest.clone();
+                async move {
+                    if !node_runtime_manifest.role_storage_provider { return Err(axum::http::StatusCode::FORBIDDEN); }
+                    let cid_hex = svdb_replication::manifest_hex(&cid_b64)?;
+                    if hex::encode(blake3::hash(&body).as_bytes()) != cid_hex { return Err(axum::http::StatusCode::UNPROCESSABLE_ENTITY); }
+                    let manifest: Manifest = serde_json::from_slice(&body).map_err(|_| axum::http::StatusCode::BAD_REQUEST)?;
+                    svdb.put_manifest(&manifest).await.map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+                    let prov_key = format!("prov:{}", cid_hex);
+                    let mut list: Vec<String> = match deal_store.get(prov_key.as_bytes()).await { Ok(Some(b)) => serde_json::from_slice(&b).unwrap_or_default(), _ => Vec::new() };
+                    if !list.contains(&node_runtime_manifest.node_id) { list.push(node_runtime_manifest.node_id.clone()); }
+                    let _ = deal_store.put(prov_key.as_bytes(), serde_json::to_vec(&list).unwrap().as_slice()).await;
+                    Ok::<_, axum::http::StatusCode>(axum::http::StatusCode::OK)
+                }
+            }
+        }))
         .route("/svdb/download/:cid_b64", get({
             let svdb = svdb.clone();
             let deal_store = deal_store.clone();
@@ -2865,8 +2945,10 @@ pub fn create_testnet_router(
         // Provider capabilities (co-location hints)
         .route("/svdb/providers/capabilities", post({
             let deal_store = deal_store.clone();
+            let replicator = replicator.clone();
             move |Json(body): Json<serde_json::Value>| {
                 let deal_store = deal_store.clone();
+                let replicator = replicator.clone();
                 async move {
                     let node_id = body.get("nodeId").and_then(|v| v.as_str()).ok_or(axum::http::StatusCode::BAD_REQUEST)?;
                     let region = body.get("region").and_then(|v| v.as_str()).unwrap_or("");
@@ -2885,6 +2967,15 @@ pub fn create_testnet_router(
                     });
                     let key = format!("caps:{}", node_id);
                     deal_store.put(key.as_bytes(), serde_json::to_string(&caps).unwrap().as_bytes()).await.map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+                    // Providers with an HTTP address can hold replicas
+                    if !http_addr.is_empty() {
+                        replicator.register_provider(ProviderInfo {
+                            node_id: node_id.to_string(),
+                            region: region.to_string(),
+                            http_addr: http_addr.to_string(),
+                            advertised_latency_ms: latency_ms.is_finite().then_some(latency_ms),
+                        }).await;
+                    }
                     // Update index
                     let idx_key = b"caps:index";
                     let mut list: Vec<String> = match deal_store.get(idx_key).await { Ok(Some(b)) => serde_json::from_slice(&b).unwrap_or_default(), _ => Vec::new() };
@@ -2977,8 +3068,10 @@ pub fn create_testnet_router(
         // Scheduler: plan co-located providers for model/dataset
         .route("/svdb/scheduler/plan", get({
             let deal_store = deal_store.clone();
+            let replicator = replicator.clone();
             move |Query(params): Query<HashMap<String, String>>| {
                 let deal_store = deal_store.clone();
+                let replicator = replicator.clone();
                 async move {
                     let dataset_cid = params.get("datasetCid").ok_or(axum::http::StatusCode::BAD_REQUEST)?.to_string();
                     let b64 = dataset_cid.trim_start_matches("artha://");
@@ -2987,7 +3080,11 @@ pub fn create_testnet_router(
                     let mut bl=[0u8;32]; bl.copy_from_slice(&bytes[2..34]);
                     let cid_hex = hex::encode(bl);
                     let prov_key = format!("prov:{}", cid_hex);
-                    let providers: Vec<String> = match deal_store.get(prov_key.as_bytes()).await { Ok(Some(b)) => serde_json::from_slice(&b).unwrap_or_default(), _ => Vec::new() };
+                    let mut providers: Vec<String> = match deal_store.get(prov_key.as_bytes()).await { Ok(Some(b)) => serde_json::from_slice(&b).unwrap_or_default(), _ => Vec::new() };
+                    // Verified replicas are candidates too
+                    for location in replicator.verified_locations(&cid_hex).await {
+                        if !providers.contains(&location.node_id) { providers.push(location.node_id); }
+                    }
                     // Fetch capabilities for providers
                     let mut ranked: Vec<serde_json::Value> = Vec::new();
                     let want_region = params.get("region").cloned();
@@ -3769,6 +3866,8 @@ pub fn create_testnet_router(
         .merge(ai_endpoints::ai_router())
         // Merge Dashboard API
         .merge(dashboard_api::dashboard_router())
+        // Merge SVDB replication policy/status
+        .merge(svdb_replication::svdb_replication_router(replicator))
         // Standardize error envelope for non-success responses
         .layer(axum::middleware::map_response(|res: AxumResponse| async move {
             if !res.status().is_success() {
@@ -2,6 +2,8 @@ use anyhow::Result;
 use arthachain_node::{
     config::Config,
     consensus::validator_set::ValidatorSetManager,
+    consensus::vote_records::{ConsensusRecords, VoteRecordConfig},
+    genesis::{ChainIdentity, ChainSpec, Genesis},
     ledger::{
         block::{Block, Transaction},
         state::State,
@@ -10,14 +12,20 @@ use arthachain_node::{
     transaction::Mempool,
     types::{Address, Hash},
     api::arthachain_router::{create_arthachain_api_router, AppState as ArthaChainAppState},
+    api::bundler::bundler_router,
+    api::handlers::wallet_rpc,
+    api::rpc_guard::{self, RpcGuard},
+    evm::{AccountAbstraction, EvmConfig, EvmExecutor},
+    storage::HybridStorage,
 };
 use axum::{
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use rand::Rng;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
@@ -51,6 +59,39 @@ struct Args {
     /// Enable testnet features
     #[clap(long)]
     enable_testnet_features: bool,
+
+    /// Directory the chain state is kept in
+    #[clap(long, default_value = "data/blockchain")]
+    data_dir: PathBuf,
+
+    /// Initialize the data directory from --chain-spec and exit
+    #[clap(long, requires = "chain_spec")]
+    init: bool,
+
+    /// Chain spec (TOML or JSON) used by --init
+    #[clap(long)]
+    chain_spec: Option<PathBuf>,
+
+    /// Let --init overwrite an already initialized data directory
+    #[clap(long)]
+    force: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the chain spec a running node was initialized from
+    DumpGenesis {
+        /// API URL of the node
+        #[clap(long, default_value = "http://localhost:1900")]
+        node: String,
+
+        /// Write the spec here instead of stdout
+        #[clap(long)]
+        out: Option<PathBuf>,
+    },
 }
 
 /// Global configuration for ArthaChain - Production-ready architecture
@@ -117,6 +158,7 @@ struct AppState {
     mempool: Arc<RwLock<Mempool>>,
     validator_manager: Arc<ValidatorSetManager>,
     config: GlobalConfig,
+    genesis: Option<Arc<Genesis>>,
 }
 
 #[tokio::main]
@@ -127,6 +169,26 @@ async fn main() -> Result<()> {
     // Parse command line arguments
     let args = Args::parse();
 
+    if let Some(Command::DumpGenesis { node, out }) = &args.command {
+        return dump_genesis(node, out.as_deref()).await;
+    }
+
+    if args.init {
+        let spec_path = args
+            .chain_spec
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("--init requires --chain-spec"))?;
+        let genesis = Genesis::build(&ChainSpec::from_file(spec_path)?)?;
+        genesis.initialize(&args.data_dir, args.force)?;
+        println!("✅ Initialized {} from {}", args.data_dir.display(), spec_path.display());
+        println!("   Chain ID: {}", genesis.spec.chain_id);
+        println!("   Spec hash: {}", genesis.spec_hash.to_evm_hex());
+        println!("   Genesis hash: {}", genesis.hash().to_evm_hex());
+        println!("   State root: {}", genesis.state_root.to_evm_hex());
+        return Ok(());
+    }
+    let genesis = Genesis::load(&args.data_dir)?.map(Arc::new);
+
     // Load global configuration
     let mut config = GlobalConfig::default();
 
@@ -136,6 +198,9 @@ async fn main() -> Result<()> {
     config.metrics_port = args.metrics_port;
     config.enable_faucet = args.enable_faucet;
     config.enable_testnet_features = args.enable_testnet_features;
+    if let Some(genesis) = &genesis {
+        config.chain_id = genesis.spec.chain_id;
+    }
 
     println!("🚀 ArthaChain Node Starting...");
     println!("📋 Configuration (ArthaChain Production Architecture):");
@@ -168,7 +233,20 @@ async fn main() -> Result<()> {
     artha_config.network.p2p_port = config.p2p_port;
     artha_config.network.bootstrap_nodes = config.seed_nodes.clone();
 
-    let state = Arc::new(RwLock::new(State::new(&artha_config)?));
+    let state = match &genesis {
+        Some(genesis) => {
+            let state = State::open(&args.data_dir.to_string_lossy())?;
+            // The persisted chain must be the one the spec describes
+            if ChainIdentity::from_state(&state).as_ref() != Some(&genesis.identity()) {
+                return Err(anyhow::anyhow!(
+                    "{} does not match its chain spec; re-run --init --force",
+                    args.data_dir.display()
+                ));
+            }
+            Arc::new(RwLock::new(state))
+        }
+        None => Arc::new(RwLock::new(State::new(&artha_config)?)),
+    };
     println!("✅ Blockchain state initialized");
 
     // Initialize mempool for real transaction processing
@@ -184,15 +262,36 @@ async fn main() -> Result<()> {
     let validator_manager = Arc::new(ValidatorSetManager::new(validator_config));
     println!("✅ Validator manager initialized");
 
-    // Generate genesis block
-    generate_genesis_block(&state).await?;
-    println!("✅ Genesis block generated");
+    // Finalized votes and validator set history, served to governance dashboards
+    let vote_records = Arc::new(RwLock::new(ConsensusRecords::open(VoteRecordConfig::from_env())?));
+
+    // Nodes initialized from a chain spec already hold their genesis block
+    match &genesis {
+        Some(genesis) => println!("✅ Genesis {} (spec {})", genesis.hash().to_evm_hex(), genesis.spec_hash.to_evm_hex()),
+        None => {
+            generate_genesis_block(&state).await?;
+            println!("✅ Genesis block generated");
+        }
+    }
+
+    // Sponsored user operations; EntryPoint calls reach it through the mempool and blocks
+    let evm_executor = Arc::new(EvmExecutor::new(
+        Arc::new(HybridStorage::new("memory://".to_string(), 1024 * 1024)?),
+        EvmConfig {
+            chain_id: config.chain_id,
+            ..EvmConfig::default()
+        },
+    ));
+    let account_abstraction =
+        Arc::new(AccountAbstraction::new(config.chain_id, evm_executor).with_sink(mempool.clone()));
+    println!("✅ Account abstraction EntryPoint initialized");
 
     // Start continuous mining system
     let state_clone = state.clone();
     let mempool_clone = mempool.clone();
+    let account_abstraction_clone = account_abstraction.clone();
     tokio::spawn(async move {
-        continuous_mining_system(state_clone, mempool_clone).await;
+        continuous_mining_system(state_clone, mempool_clone, account_abstraction_clone).await;
     });
 
     // Start P2P network (ArthaChain standard)
@@ -223,7 +322,8 @@ async fn main() -> Result<()> {
             mempool: Arc::clone(&mempool),
             validator_manager: Arc::clone(&validator_manager),
             config: artha_config.clone(),
-        });
+        })
+        .layer(Extension(vote_records));
     
     // Create the basic API router for backward compatibility
     let basic_router = Router::new()
@@ -407,15 +507,27 @@ async fn main() -> Result<()> {
         .route("/api/v1/transactions/:tx_hash", get(get_transaction_status))
         .route("/api/v1/testings/performance", get(get_testings_performance))
         .route("/api/v1/blocks/sync", post(sync_block_from_other_node))
+        .route("/api/v1/genesis", get(get_genesis))
+        .route("/rpc", post(wallet_rpc::handle_rpc_request))
+        .layer(Extension(Arc::clone(&state)))
         .with_state(AppState {
             state: Arc::clone(&state),
             mempool: Arc::clone(&mempool),
             validator_manager: Arc::clone(&validator_manager),
             config: config.clone(),
+            genesis: genesis.clone(),
         });
 
+    // Public traffic is budgeted and scheduled behind platform services
+    let rpc_guard = RpcGuard::from_env();
+    rpc_guard.report(Duration::from_secs(60));
+
     // Combine both routers
-    let app = basic_router.merge(arthachain_router);
+    let app = basic_router
+        .merge(arthachain_router)
+        .merge(rpc_guard::stats_router(Arc::clone(&rpc_guard)))
+        .merge(bundler_router(account_abstraction))
+        .layer(axum::middleware::from_fn_with_state(rpc_guard, rpc_guard::protect));
 
     // Bind to all interfaces for global access (ArthaChain standard)
     let addr = format!("0.0.0.0:{}", config.api_port);
@@ -439,7 +551,7 @@ async fn main() -> Result<()> {
     println!("🎯 Ready for global deployment!");
 
     // Start the server
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -464,6 +576,45 @@ async fn get_node_id() -> Json<serde_json::Value> {
     }))
 }
 
+/// Effective chain spec endpoint, for `dump-genesis`
+async fn get_genesis(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
+    let genesis = state.genesis.as_ref().ok_or(axum::http::StatusCode::NOT_FOUND)?;
+    Ok(Json(serde_json::json!({
+        "spec": genesis.spec,
+        "spec_hash": genesis.spec_hash.to_evm_hex(),
+        "genesis_hash": genesis.hash().to_evm_hex(),
+        "state_root": genesis.state_root.to_evm_hex(),
+    })))
+}
+
+/// Fetch a node's chain spec and check it rebuilds to the genesis the node reports
+async fn dump_genesis(node: &str, out: Option<&Path>) -> Result<()> {
+    let response: serde_json::Value = reqwest::get(format!("{}/api/v1/genesis", node.trim_end_matches('/')))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let spec: ChainSpec = serde_json::from_value(response["spec"].clone())?;
+    let genesis = Genesis::build(&spec)?;
+    if response["genesis_hash"].as_str() != Some(genesis.hash().to_evm_hex().as_str()) {
+        return Err(anyhow::anyhow!(
+            "Node reports genesis {} but its spec builds {}",
+            response["genesis_hash"],
+            genesis.hash().to_evm_hex()
+        ));
+    }
+
+    let text = serde_json::to_string_pretty(&genesis.spec)?;
+    match out {
+        Some(path) => std::fs::write(path, text)?,
+        None => println!("{}", text),
+    }
+    eprintln!("Spec hash {} matches genesis {}", genesis.spec_hash.to_evm_hex(), genesis.hash().to_evm_hex());
+    Ok(())
+}
+
 /// Get blockchain height endpoint
 async fn get_blockchain_height(
     axum::extract::State(state): axum::extract::State<AppState>,
@@ -741,7 +892,11 @@ async fn generate_genesis_block(state: &Arc<RwLock<State>>) -> Result<()> {
 }
 
 /// Continuous mining system that creates new blocks
-async fn continuous_mining_system(state: Arc<RwLock<State>>, mempool: Arc<RwLock<Mempool>>) {
+async fn continuous_mining_system(
+    state: Arc<RwLock<State>>,
+    mempool: Arc<RwLock<Mempool>>,
+    account_abstraction: Arc<AccountAbstraction>,
+) {
     let mut interval_timer = interval(Duration::from_secs(5)); // Create block every 5 seconds
 
     println!("⛏️ Starting PRODUCTION mining system...");
@@ -753,7 +908,7 @@ async fn continuous_mining_system(state: Arc<RwLock<State>>, mempool: Arc<RwLock
     loop {
         interval_timer.tick().await;
 
-        match create_new_block(&state, block_height, &mempool).await {
+        match create_new_block(&state, block_height, &mempool, &account_abstraction).await {
             Ok(block_hash) => {
                 println!("⛏️ Block {} created successfully", block_height);
                 block_height += 1;
@@ -770,6 +925,7 @@ async fn create_new_block(
     state: &Arc<RwLock<State>>,
     height: u64,
     mempool: &Arc<RwLock<Mempool>>,
+    account_abstraction: &AccountAbstraction,
 ) -> Result<Hash> {
     let state_write = state.write().await;
 
@@ -790,12 +946,20 @@ async fn create_new_block(
         height,
     )?;
 
+    // EntryPoint calls take effect in block order at the block's timestamp
+    account_abstraction
+        .apply_block(&new_block.transactions, new_block.header.timestamp, &state_write)
+        .await;
+
     // Get the block hash before moving the block
     let block_hash = new_block.hash()?;
 
     // Add block to state
     state_write.add_block(new_block)?;
 
+    // Blocks five deep are committed by SVBFT; publish them as final
+    state_write.mark_finalized(height.saturating_sub(5))?;
+
     Ok(block_hash)
 }
 
@@ -830,6 +994,8 @@ async fn generate_real_transactions(
                 },
             };
             transactions.push(ledger_tx);
+            // Included once; left in the pool it would be replayed into every later block
+            mempool_guard.mark_executed(&tx.hash()).await;
         }
         println!(
             "✅ Added {} real transactions from mempool to block",
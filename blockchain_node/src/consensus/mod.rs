@@ -23,6 +23,7 @@ pub mod leader_election;
 pub mod leader_failover;
 pub mod load_balancer;
 pub mod validator_set;
+pub mod vote_records;
 
 pub mod batch;
 #[cfg(not(skip_problematic_modules))]
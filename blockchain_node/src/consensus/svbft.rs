@@ -1,5 +1,6 @@
 use crate::config::Config;
 use crate::consensus::view_change::{ViewChangeConfig, ViewChangeManager, ViewChangeMessage};
+use crate::consensus::vote_records::{ConsensusRecords, FinalizedRound, VoteRecordConfig, VoteTiming};
 use crate::ledger::block::Block;
 use crate::ledger::state::State;
 use crate::types::Address;
@@ -7,7 +8,7 @@ use anyhow::{anyhow, Result};
 use hex;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
@@ -175,6 +176,12 @@ struct ConsensusRound {
     leader: String,
     /// Quorum size for this round
     quorum_size: usize,
+    /// When the leader's proposal arrived
+    proposed_at: Option<Instant>,
+    /// Prepare and pre-commit arrival times by validator
+    vote_timings: BTreeMap<String, VoteTiming>,
+    /// Leaders of earlier views whose slot passed without a decided block
+    missed_proposers: Vec<String>,
 }
 
 impl ConsensusRound {
@@ -192,6 +199,31 @@ impl ConsensusRound {
             current_timeout: base_timeout,
             leader,
             quorum_size,
+            proposed_at: None,
+            vote_timings: BTreeMap::new(),
+            missed_proposers: Vec::new(),
+        }
+    }
+
+    /// Note when a validator's prepare or pre-commit vote arrived
+    fn note_vote(&mut self, phase: ConsensusPhase, voter: &str) {
+        let since_proposal = self.proposed_at.unwrap_or(self.start_time).elapsed().as_millis() as u64;
+        let timing = self.vote_timings.entry(voter.to_string()).or_default();
+        match phase {
+            ConsensusPhase::Prepare => timing.prevote_ms.get_or_insert(since_proposal),
+            ConsensusPhase::PreCommit => timing.precommit_ms.get_or_insert(since_proposal),
+            _ => return,
+        };
+    }
+
+    /// Votes collected for the decided block
+    fn finalized(&self, block: &Block) -> FinalizedRound {
+        FinalizedRound {
+            height: block.header.height,
+            view: self.view,
+            proposer: self.leader.clone(),
+            missed_proposers: self.missed_proposers.clone(),
+            votes: self.vote_timings.clone(),
         }
     }
 
@@ -242,6 +274,8 @@ pub struct SVBFTConsensus {
     finalized_blocks: Arc<Mutex<HashMap<Vec<u8>, Block>>>,
     /// Enhanced view change manager with Byzantine fault tolerance
     view_change_manager: Arc<Mutex<ViewChangeManager>>,
+    /// Votes and validator set history kept past finalization
+    vote_records: Arc<RwLock<ConsensusRecords>>,
 }
 
 impl SVBFTConsensus {
@@ -293,9 +327,16 @@ impl SVBFTConsensus {
             node_id,
             finalized_blocks: Arc::new(Mutex::new(HashMap::new())),
             view_change_manager: Arc::new(Mutex::new(view_change_manager)),
+            vote_records: Arc::new(RwLock::new(ConsensusRecords::open(VoteRecordConfig::default())?)),
         })
     }
 
+    /// Record finalized votes into `records`, shared with the consensus API
+    pub fn with_vote_records(mut self, records: Arc<RwLock<ConsensusRecords>>) -> Self {
+        self.vote_records = records;
+        self
+    }
+
     /// Start the SVBFT consensus engine
     pub async fn start(&mut self) -> Result<JoinHandle<()>> {
         // Set running flag
@@ -325,12 +366,15 @@ impl SVBFTConsensus {
         let svbft_config = self.svbft_config.clone();
         let finalized_blocks = self.finalized_blocks.clone();
         let view_change_manager = self.view_change_manager.clone();
+        let vote_records = self.vote_records.clone();
 
         let handle = tokio::spawn(async move {
             info!("SVBFT consensus started");
 
             // Initialize validators and capabilities
-            if let Err(e) = Self::initialize_validators(&validators, &node_capabilities).await {
+            if let Err(e) =
+                Self::initialize_validators(&validators, &node_capabilities, &vote_records).await
+            {
                 error!("Failed to initialize validators: {}", e);
             }
 
@@ -390,6 +434,7 @@ impl SVBFTConsensus {
                         &node_id,
                         &message_sender,
                         &finalized_blocks,
+                        &vote_records,
                         &svbft_config,
                         &node_capabilities,
                     )
@@ -435,6 +480,7 @@ impl SVBFTConsensus {
     async fn initialize_validators(
         validators: &Arc<RwLock<HashSet<String>>>,
         node_capabilities: &Arc<RwLock<HashMap<String, NodeCapabilities>>>,
+        vote_records: &Arc<RwLock<ConsensusRecords>>,
     ) -> Result<()> {
         // In a real implementation, we would read validator list from state
         // For now, we'll just populate with some dummy data
@@ -469,6 +515,10 @@ impl SVBFTConsensus {
             );
         }
 
+        // The placeholder set carries no stake, so every validator weighs the same
+        let stakes = validators_set.iter().map(|v| (v.clone(), 1)).collect();
+        vote_records.write().await.queue_validator_set(stakes)?;
+
         info!("Initialized {} validators", validators_set.len());
         Ok(())
     }
@@ -595,6 +645,7 @@ impl SVBFTConsensus {
 
         // Set the proposed block
         round.proposed_block = Some(block.clone());
+        round.proposed_at = Some(Instant::now());
         round.phase = ConsensusPhase::Prepare;
 
         // Vote for prepare
@@ -613,6 +664,7 @@ impl SVBFTConsensus {
             node_id, signature, ..
         } = prepare
         {
+            round.note_vote(ConsensusPhase::Prepare, &node_id);
             round.prepare_votes.insert(node_id, signature);
         }
 
@@ -645,6 +697,7 @@ impl SVBFTConsensus {
         // In real implementation, verify the signature
 
         // Add the vote
+        round.note_vote(ConsensusPhase::Prepare, &voter);
         round.prepare_votes.insert(voter, signature);
 
         // Check if we have a quorum
@@ -668,6 +721,7 @@ impl SVBFTConsensus {
                 node_id, signature, ..
             } = precommit
             {
+                round.note_vote(ConsensusPhase::PreCommit, &node_id);
                 round.precommit_votes.insert(node_id, signature);
             }
 
@@ -701,6 +755,7 @@ impl SVBFTConsensus {
         // In real implementation, verify the signature
 
         // Add the vote
+        round.note_vote(ConsensusPhase::PreCommit, &voter);
         round.precommit_votes.insert(voter, signature);
 
         // Check if we have a quorum
@@ -804,6 +859,7 @@ impl SVBFTConsensus {
         node_id: &str,
         message_sender: &mpsc::Sender<ConsensusMessage>,
         finalized_blocks: &Arc<Mutex<HashMap<Vec<u8>, Block>>>,
+        vote_records: &Arc<RwLock<ConsensusRecords>>,
         svbft_config: &SVBFTConfig,
         node_capabilities: &Arc<RwLock<HashMap<String, NodeCapabilities>>>,
     ) -> Result<()> {
@@ -965,6 +1021,11 @@ impl SVBFTConsensus {
                         let mut finalized = finalized_blocks.lock().await;
                         finalized.insert(block_hash.clone(), block.clone());
 
+                        // Keep the round's votes once it is gone
+                        if let Err(e) = vote_records.write().await.record_block(round.finalized(block)) {
+                            error!("Failed to record votes for block {}: {}", hex::encode(&block_hash), e);
+                        }
+
                         info!(
                             "Decide quorum reached for block {} in view {}, block finalized",
                             hex::encode(&block_hash),
@@ -1098,6 +1159,10 @@ impl SVBFTConsensus {
 
             // Drop the round lock before view change operations
             let view = round.view;
+            let mut missed_proposers = round.missed_proposers.clone();
+            if round.proposed_block.is_none() {
+                missed_proposers.push(round.leader.clone());
+            }
             drop(round_guard);
 
             // Use enhanced view change manager with Byzantine fault tolerance
@@ -1188,6 +1253,11 @@ use crate::crypto::signature::{sign, Signature};
                                 node_capabilities,
                             )
                             .await?;
+
+                            // The next leader proposes at the same height
+                            if let Some(round) = current_round.lock().await.as_mut() {
+                                round.missed_proposers = missed_proposers;
+                            }
                         }
                     }
                     Err(e) => {
@@ -0,0 +1,508 @@
+//! Consensus Vote Records
+//!
+//! SVBFT drops a round's votes once its block is decided. Governance tooling
+//! needs them afterwards: who was in the validator set at each epoch, how
+//! each validator voted on recent blocks, missed-vote streaks and proposer
+//! rotation. `ConsensusRecords` keeps per-block prevote/precommit presence
+//! and timing for the most recent `detail_epochs` epochs, and per-validator
+//! aggregates for every epoch. Aggregates are updated as each block
+//! finalizes, so queries never rescan vote history.
+//!
+//! Validator set changes take effect at epoch boundaries. Each epoch records
+//! the set it ran with and its diff against the previous epoch.
+//!
+//! Everything is journaled to an append-only JSON-lines file and replayed on
+//! open. Pruning vote detail compacts the journal to a snapshot of the
+//! aggregates plus the detail still retained.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct VoteRecordConfig {
+    /// Blocks per epoch
+    pub epoch_length: u64,
+    /// Epochs of per-block vote detail kept; older epochs keep aggregates only
+    pub detail_epochs: u64,
+    /// Journal file; records are kept in memory only when unset
+    pub journal_path: Option<PathBuf>,
+}
+
+impl Default for VoteRecordConfig {
+    fn default() -> Self {
+        Self {
+            epoch_length: 1000,
+            detail_epochs: 8,
+            journal_path: None,
+        }
+    }
+}
+
+impl VoteRecordConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let number = |name: &str, fallback: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(fallback)
+        };
+        Self {
+            epoch_length: number("ARTHA_CONSENSUS_EPOCH_LENGTH", default.epoch_length),
+            detail_epochs: number("ARTHA_VOTE_DETAIL_EPOCHS", default.detail_epochs),
+            journal_path: std::env::var("ARTHA_VOTE_RECORDS_PATH").ok().map(PathBuf::from),
+        }
+    }
+}
+
+/// When a validator's votes on one block arrived, in ms after the proposal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteTiming {
+    pub prevote_ms: Option<u64>,
+    pub precommit_ms: Option<u64>,
+}
+
+/// A decided block and the votes the engine collected for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinalizedRound {
+    pub height: u64,
+    pub view: u64,
+    pub proposer: String,
+    /// Leaders of earlier views at this height whose slot passed without a proposal
+    #[serde(default)]
+    pub missed_proposers: Vec<String>,
+    pub votes: BTreeMap<String, VoteTiming>,
+}
+
+/// One validator's votes on a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorVote {
+    pub validator: String,
+    pub prevote: bool,
+    pub precommit: bool,
+    pub prevote_ms: Option<u64>,
+    pub precommit_ms: Option<u64>,
+}
+
+/// Served by `GET /consensus/blocks/:height/votes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockVotes {
+    pub height: u64,
+    pub epoch: u64,
+    pub view: u64,
+    pub proposer: String,
+    pub missed_proposers: Vec<String>,
+    /// Every member of the epoch's set, whether it voted or not
+    pub votes: Vec<ValidatorVote>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeChange {
+    pub address: String,
+    pub from: u64,
+    pub to: u64,
+}
+
+/// An epoch boundary and how the set changed at it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Epoch {
+    pub epoch: u64,
+    pub start_height: u64,
+    /// Last finalized height, once a later epoch has begun
+    pub end_height: Option<u64>,
+    /// Address to stake weight
+    pub validators: BTreeMap<String, u64>,
+    pub joined: Vec<String>,
+    pub left: Vec<String>,
+    pub stake_changes: Vec<StakeChange>,
+}
+
+/// A validator's place in an epoch's set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorEntry {
+    pub address: String,
+    pub stake: u64,
+    /// First height of the membership span covering the epoch
+    pub joined_height: u64,
+    /// First height the validator was no longer in the set, if it has left
+    pub left_height: Option<u64>,
+}
+
+/// Running totals for one validator over one epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Participation {
+    /// Blocks finalized while the validator was in the set
+    pub blocks: u64,
+    pub prevotes: u64,
+    pub precommits: u64,
+    pub latency_total_ms: u64,
+    pub latency_samples: u64,
+    pub proposer_assigned: u64,
+    pub proposer_fulfilled: u64,
+}
+
+impl Participation {
+    fn add(&mut self, other: &Participation) {
+        self.blocks += other.blocks;
+        self.prevotes += other.prevotes;
+        self.precommits += other.precommits;
+        self.latency_total_ms += other.latency_total_ms;
+        self.latency_samples += other.latency_samples;
+        self.proposer_assigned += other.proposer_assigned;
+        self.proposer_fulfilled += other.proposer_fulfilled;
+    }
+}
+
+/// Consecutive finalized blocks without the validator's precommit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissedStreak {
+    pub current: u64,
+    pub longest: u64,
+}
+
+/// Served by `GET /consensus/validators/:addr/performance`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorPerformance {
+    pub address: String,
+    pub from_epoch: u64,
+    pub to_epoch: u64,
+    pub blocks: u64,
+    /// Prevotes and precommits cast over those expected while in the set
+    pub participation_rate: f64,
+    pub average_vote_latency_ms: Option<f64>,
+    pub proposer_slots_assigned: u64,
+    pub proposer_slots_fulfilled: u64,
+    pub missed_streak: u64,
+    pub longest_missed_streak: u64,
+}
+
+/// Everything but the per-block detail; what compaction keeps of pruned epochs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Aggregates {
+    epochs: BTreeMap<u64, Epoch>,
+    /// Address to (joined height, left height) spans, oldest first
+    membership: BTreeMap<String, Vec<(u64, Option<u64>)>>,
+    /// Epoch to address to totals
+    participation: BTreeMap<u64, BTreeMap<String, Participation>>,
+    streaks: BTreeMap<String, MissedStreak>,
+    /// Set that takes effect at the next epoch boundary
+    pending_set: Option<BTreeMap<String, u64>>,
+    last_height: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalEntry {
+    ValidatorSet(BTreeMap<String, u64>),
+    Block(FinalizedRound),
+    Snapshot(Aggregates),
+    Detail(BlockVotes),
+}
+
+pub struct ConsensusRecords {
+    config: VoteRecordConfig,
+    aggregates: Aggregates,
+    detail: BTreeMap<u64, BlockVotes>,
+}
+
+impl ConsensusRecords {
+    /// Open the records, replaying the journal if one is configured
+    pub fn open(config: VoteRecordConfig) -> Result<Self> {
+        let mut records = Self {
+            config,
+            aggregates: Aggregates::default(),
+            detail: BTreeMap::new(),
+        };
+        let Some(path) = records.config.journal_path.clone() else {
+            return Ok(records);
+        };
+        if !path.exists() {
+            return Ok(records);
+        }
+        let file = std::fs::File::open(&path)?;
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: JournalEntry = serde_json::from_str(&line)
+                .map_err(|e| anyhow!("{}:{}: {}", path.display(), number + 1, e))?;
+            match entry {
+                JournalEntry::ValidatorSet(set) => records.aggregates.pending_set = Some(set),
+                JournalEntry::Block(round) => {
+                    records.apply(&round)?;
+                }
+                JournalEntry::Snapshot(aggregates) => {
+                    records.aggregates = aggregates;
+                    records.detail.clear();
+                }
+                JournalEntry::Detail(votes) => {
+                    records.detail.insert(votes.height, votes);
+                }
+            }
+        }
+        records.prune_detail();
+        Ok(records)
+    }
+
+    pub fn epoch_of(&self, height: u64) -> u64 {
+        height / self.config.epoch_length
+    }
+
+    /// Epoch of the latest finalized block
+    pub fn current_epoch(&self) -> Option<u64> {
+        self.aggregates.epochs.keys().next_back().copied()
+    }
+
+    /// Set the validator set, with stake weights, from the next epoch boundary
+    pub fn queue_validator_set(&mut self, set: BTreeMap<String, u64>) -> Result<()> {
+        self.journal(&[JournalEntry::ValidatorSet(set.clone())])?;
+        self.aggregates.pending_set = Some(set);
+        Ok(())
+    }
+
+    /// Record a finalized block's votes. Heights must increase.
+    pub fn record_block(&mut self, round: FinalizedRound) -> Result<()> {
+        let began_epoch = self.apply(&round)?;
+        self.journal(&[JournalEntry::Block(round)])?;
+        if began_epoch {
+            let before = self.detail.len();
+            self.prune_detail();
+            if self.detail.len() < before {
+                self.compact()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fold a round into the aggregates and detail. Returns whether it
+    /// began a new epoch.
+    fn apply(&mut self, round: &FinalizedRound) -> Result<bool> {
+        if let Some(last) = self.aggregates.last_height {
+            if round.height <= last {
+                return Err(anyhow!("Block {} is not above the last recorded height {}", round.height, last));
+            }
+        }
+        let epoch = self.epoch_of(round.height);
+        let began_epoch = self.current_epoch().is_none_or(|current| epoch > current);
+        if began_epoch {
+            self.begin_epoch(epoch, round.height);
+        }
+
+        let aggregates = &mut self.aggregates;
+        let members = aggregates.epochs[&epoch].validators.clone();
+        let totals = aggregates.participation.entry(epoch).or_default();
+        let mut votes = Vec::with_capacity(members.len());
+        for address in members.keys() {
+            let timing = round.votes.get(address).copied().unwrap_or_default();
+            let entry = totals.entry(address.clone()).or_default();
+            entry.blocks += 1;
+            for latency in [timing.prevote_ms, timing.precommit_ms].into_iter().flatten() {
+                entry.latency_total_ms += latency;
+                entry.latency_samples += 1;
+            }
+            entry.prevotes += timing.prevote_ms.is_some() as u64;
+            entry.precommits += timing.precommit_ms.is_some() as u64;
+
+            let streak = aggregates.streaks.entry(address.clone()).or_default();
+            if timing.precommit_ms.is_some() {
+                streak.current = 0;
+            } else {
+                streak.current += 1;
+                streak.longest = streak.longest.max(streak.current);
+            }
+
+            votes.push(ValidatorVote {
+                validator: address.clone(),
+                prevote: timing.prevote_ms.is_some(),
+                precommit: timing.precommit_ms.is_some(),
+                prevote_ms: timing.prevote_ms,
+                precommit_ms: timing.precommit_ms,
+            });
+        }
+
+        // Every leader whose view passed at this height held a slot; only the
+        // proposer of the decided block filled one
+        for missed in &round.missed_proposers {
+            if members.contains_key(missed) {
+                totals.entry(missed.clone()).or_default().proposer_assigned += 1;
+            }
+        }
+        if members.contains_key(&round.proposer) {
+            let proposer = totals.entry(round.proposer.clone()).or_default();
+            proposer.proposer_assigned += 1;
+            proposer.proposer_fulfilled += 1;
+        }
+
+        aggregates.last_height = Some(round.height);
+        self.detail.insert(
+            round.height,
+            BlockVotes {
+                height: round.height,
+                epoch,
+                view: round.view,
+                proposer: round.proposer.clone(),
+                missed_proposers: round.missed_proposers.clone(),
+                votes,
+            },
+        );
+        Ok(began_epoch)
+    }
+
+    /// Close the current epoch and open `epoch` at `start_height` with the
+    /// queued set, or the previous set if none is queued
+    fn begin_epoch(&mut self, epoch: u64, start_height: u64) {
+        let aggregates = &mut self.aggregates;
+        let previous = aggregates.epochs.values_mut().next_back();
+        let previous_set = previous.as_ref().map(|e| e.validators.clone()).unwrap_or_default();
+        if let Some(previous) = previous {
+            previous.end_height = aggregates.last_height;
+        }
+        let set = aggregates.pending_set.take().unwrap_or_else(|| previous_set.clone());
+
+        let joined: Vec<String> = set.keys().filter(|a| !previous_set.contains_key(*a)).cloned().collect();
+        let left: Vec<String> = previous_set.keys().filter(|a| !set.contains_key(*a)).cloned().collect();
+        let stake_changes = set
+            .iter()
+            .filter_map(|(address, &to)| {
+                let from = *previous_set.get(address)?;
+                (from != to).then(|| StakeChange { address: address.clone(), from, to })
+            })
+            .collect();
+
+        for address in &joined {
+            aggregates.membership.entry(address.clone()).or_default().push((start_height, None));
+        }
+        for address in &left {
+            if let Some(span) = aggregates.membership.get_mut(address).and_then(|spans| spans.last_mut()) {
+                span.1 = Some(start_height);
+            }
+        }
+
+        aggregates.epochs.insert(
+            epoch,
+            Epoch {
+                epoch,
+                start_height,
+                end_height: None,
+                validators: set,
+                joined,
+                left,
+                stake_changes,
+            },
+        );
+    }
+
+    /// Drop vote detail older than the retained epochs
+    fn prune_detail(&mut self) {
+        let Some(current) = self.current_epoch() else { return };
+        let oldest_kept = current.saturating_sub(self.config.detail_epochs.saturating_sub(1));
+        let cutoff = oldest_kept * self.config.epoch_length;
+        self.detail = self.detail.split_off(&cutoff);
+    }
+
+    /// Rewrite the journal as a snapshot plus the detail still retained
+    fn compact(&self) -> Result<()> {
+        let Some(path) = &self.config.journal_path else { return Ok(()) };
+        let temp = path.with_extension("compacting");
+        {
+            let mut file = std::fs::File::create(&temp)?;
+            writeln!(file, "{}", serde_json::to_string(&JournalEntry::Snapshot(self.aggregates.clone()))?)?;
+            for votes in self.detail.values() {
+                writeln!(file, "{}", serde_json::to_string(&JournalEntry::Detail(votes.clone()))?)?;
+            }
+            file.sync_all()?;
+        }
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    fn journal(&self, entries: &[JournalEntry]) -> Result<()> {
+        let Some(path) = &self.config.journal_path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        for entry in entries {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+        Ok(())
+    }
+
+    /// The set at `epoch`, or at the current epoch
+    pub fn validators(&self, epoch: Option<u64>) -> Option<(u64, Vec<ValidatorEntry>)> {
+        let epoch = epoch.or_else(|| self.current_epoch())?;
+        let record = self.aggregates.epochs.get(&epoch)?;
+        let entries = record
+            .validators
+            .iter()
+            .map(|(address, &stake)| {
+                let (joined_height, left_height) = self
+                    .aggregates
+                    .membership
+                    .get(address)
+                    .and_then(|spans| {
+                        spans.iter().rev().find(|(joined, _)| *joined <= record.start_height).copied()
+                    })
+                    .unwrap_or((record.start_height, None));
+                ValidatorEntry { address: address.clone(), stake, joined_height, left_height }
+            })
+            .collect();
+        Some((epoch, entries))
+    }
+
+    /// Votes on a finalized block, while its detail is retained
+    pub fn block_votes(&self, height: u64) -> Option<&BlockVotes> {
+        self.detail.get(&height)
+    }
+
+    /// Oldest height whose vote detail is still retained
+    pub fn oldest_detail_height(&self) -> Option<u64> {
+        self.detail.keys().next().copied()
+    }
+
+    /// Participation over the latest `window` epochs, from the aggregates
+    pub fn performance(&self, address: &str, window: u64) -> Option<ValidatorPerformance> {
+        let to_epoch = self.current_epoch()?;
+        let from_epoch = to_epoch.saturating_sub(window.max(1) - 1);
+        let mut total = Participation::default();
+        let mut seen = false;
+        for totals in self.aggregates.participation.range(from_epoch..=to_epoch).map(|(_, t)| t) {
+            if let Some(participation) = totals.get(address) {
+                total.add(participation);
+                seen = true;
+            }
+        }
+        if !seen {
+            return None;
+        }
+        let streak = self.aggregates.streaks.get(address).copied().unwrap_or_default();
+        let expected = total.blocks * 2;
+        Some(ValidatorPerformance {
+            address: address.to_string(),
+            from_epoch,
+            to_epoch,
+            blocks: total.blocks,
+            participation_rate: if expected == 0 {
+                0.0
+            } else {
+                (total.prevotes + total.precommits) as f64 / expected as f64
+            },
+            average_vote_latency_ms: (total.latency_samples > 0)
+                .then(|| total.latency_total_ms as f64 / total.latency_samples as f64),
+            proposer_slots_assigned: total.proposer_assigned,
+            proposer_slots_fulfilled: total.proposer_fulfilled,
+            missed_streak: streak.current,
+            longest_missed_streak: streak.longest,
+        })
+    }
+
+    /// Every epoch boundary, oldest first
+    pub fn epochs(&self) -> Vec<Epoch> {
+        self.aggregates.epochs.values().cloned().collect()
+    }
+}
@@ -3,7 +3,7 @@
 
 use anyhow::{anyhow, Result};
 use ethereum_types::H256;
-use rlp::RlpStream;
+use rlp::{Rlp, RlpStream};
 use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 
@@ -55,6 +55,83 @@ impl MerklePatriciaTrie {
         self.root
     }
 
+    /// The RLP-encoded nodes on the path from the root to `key`, root first.
+    /// Together they show the key's value, or its absence, to anyone holding
+    /// the root hash; see [`MerklePatriciaTrie::verify_proof`].
+    pub fn prove(&self, key: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let nibbles = Self::bytes_to_nibbles(key);
+        let mut path = &nibbles[..];
+        let mut node_hash = self.root;
+        let mut proof = Vec::new();
+
+        while node_hash != H256::zero() {
+            let node = self
+                .nodes
+                .get(&node_hash)
+                .ok_or_else(|| anyhow!("Node not found"))?;
+            proof.push(self.encode_node(node));
+
+            node_hash = match node {
+                TrieNode::Branch(children, _) if !path.is_empty() => {
+                    let child = children[path[0] as usize].unwrap_or(H256::zero());
+                    path = &path[1..];
+                    child
+                }
+                TrieNode::Extension(ext_path, next_hash) if path.starts_with(ext_path) => {
+                    path = &path[ext_path.len()..];
+                    *next_hash
+                }
+                _ => break,
+            };
+        }
+        Ok(proof)
+    }
+
+    /// The value `proof` shows `key` has in the trie with root `root`.
+    /// None if the key is absent or the proof doesn't check out: every node
+    /// must hash to the reference its parent holds, starting from `root`.
+    pub fn verify_proof(root: H256, key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+        let nibbles = Self::bytes_to_nibbles(key);
+        let mut path = &nibbles[..];
+        let mut expected = root;
+
+        for encoded in proof {
+            if H256::from_slice(&Keccak256::digest(encoded)) != expected {
+                return None;
+            }
+            let node = Rlp::new(encoded);
+            match node.item_count().ok()? {
+                2 => {
+                    let (node_path, is_leaf) = Self::decode_path(node.at(0).ok()?.data().ok()?)?;
+                    let payload = node.at(1).ok()?.data().ok()?;
+                    if is_leaf {
+                        return (path == &node_path[..]).then(|| payload.to_vec());
+                    }
+                    if !path.starts_with(&node_path) || payload.len() != 32 {
+                        return None;
+                    }
+                    path = &path[node_path.len()..];
+                    expected = H256::from_slice(payload);
+                }
+                17 => {
+                    if path.is_empty() {
+                        let value = node.at(16).ok()?.data().ok()?;
+                        return (!value.is_empty()).then(|| value.to_vec());
+                    }
+                    let child = node.at(path[0] as usize).ok()?.data().ok()?;
+                    if child.len() != 32 {
+                        return None;
+                    }
+                    path = &path[1..];
+                    expected = H256::from_slice(child);
+                }
+                _ => return None,
+            }
+        }
+        // The proof stopped before reaching the key
+        None
+    }
+
     /// Calculate state root from a list of (address, account) pairs
     pub fn calculate_state_root(accounts: &[(Vec<u8>, Vec<u8>)]) -> Result<H256> {
         let mut trie = Self::new();
@@ -323,6 +400,18 @@ impl MerklePatriciaTrie {
         encoded
     }
 
+    /// Inverse of `encode_path`: the nibbles and whether they end a leaf
+    fn decode_path(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+        let (&flags, rest) = encoded.split_first()?;
+        let is_leaf = flags & 0x20 != 0;
+        let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+        if flags & 0x10 != 0 {
+            nibbles.push(flags & 0x0F);
+        }
+        nibbles.extend(Self::bytes_to_nibbles(rest));
+        Some((nibbles, is_leaf))
+    }
+
     /// Find common prefix length between two paths
     fn common_prefix(a: &[u8], b: &[u8]) -> usize {
         let min_len = a.len().min(b.len());
@@ -3,6 +3,7 @@
 
 use super::{CustodyProvider, CustodyPolicy, KeyMetadata, CustodyMode};
 use anyhow::{Result, anyhow};
+use artha_log::Sensitive;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -10,7 +11,7 @@ use std::sync::{Arc, RwLock};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MPCKeyShare {
     pub share_id: u8,
-    pub share_data: Vec<u8>, // Encrypted share
+    pub share_data: Sensitive<Vec<u8>>, // Encrypted share
     pub party_id: String,
     pub commitment: Vec<u8>, // Commitment to share (for verification)
 }
@@ -85,7 +86,7 @@ impl MPCCustodyProvider {
                 let commitment = self.compute_commitment(&share);
                 MPCKeyShare {
                     share_id: i as u8 + 1,
-                    share_data: share,
+                    share_data: Sensitive::new(share),
                     party_id: format!("party-{}", i),
                     commitment,
                 }
@@ -120,7 +121,7 @@ impl MPCCustodyProvider {
 
         for (i, share) in key.shares.iter().take(self.threshold as usize).enumerate() {
             // Simulate party signing (in production, make network request to party)
-            let partial_sig = self.sign_with_share(&share.share_data, message)?;
+            let partial_sig = self.sign_with_share(share.share_data.expose(), message)?;
             signature_shares.push((share.share_id, partial_sig));
             println!("  ✓ Received signature share {} from {}", i + 1, share.party_id);
         }
@@ -355,7 +356,7 @@ impl CustodyProvider for MPCCustodyProvider {
         
         // Verify that all parties have valid commitments
         for share in &key.shares {
-            let computed_commitment = self.compute_commitment(&share.share_data);
+            let computed_commitment = self.compute_commitment(share.share_data.expose());
             if computed_commitment != share.commitment {
                 return Ok(false);
             }
@@ -3,6 +3,7 @@
 //! Architecture ready for frost-dalek upgrade when available
 
 use anyhow::{anyhow, Result};
+use artha_log::Sensitive;
 use secp256k1::{All, Message, PublicKey, Secp256k1, SecretKey};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -26,7 +27,7 @@ pub struct KeyShare {
     /// Party identifier  
     pub party_id: String,
     /// Secret key share (encrypted in production)
-    secret_share: SecretKey,
+    secret_share: Sensitive<SecretKey>,
     /// Public key commitment
     pub public_key: PublicKey,
 }
@@ -86,7 +87,7 @@ impl ProductionTss {
             let share = KeyShare {
                 share_id: i,
                 party_id: format!("party_{}", i),
-                secret_share: secret_key,
+                secret_share: Sensitive::new(secret_key),
                 public_key,
             };
 
@@ -130,7 +131,7 @@ impl ProductionTss {
             .map_err(|e| anyhow!("Invalid message: {}", e))?;
 
         // Sign with real ECDSA
-        let signature = self.secp.sign_ecdsa(&msg, &key_share.secret_share);
+        let signature = self.secp.sign_ecdsa(&msg, key_share.secret_share.expose());
 
         Ok(PartialSignature {
             party_id: key_share.party_id.clone(),
@@ -0,0 +1,509 @@
+//! API Client Generation
+//!
+//! Generates typed HTTP clients for the AI services from the OpenAPI
+//! documents they serve (ai-jobd at `/v1/openapi.json`). Each client is
+//! scaffolded through [`SDKManager::create_project`] with the language's
+//! `api-client` template, then the generated sources are written into it:
+//! one type per component schema and one method per operation, named after
+//! the operation's `operationId`. Clients are regenerated, never edited.
+
+use super::sdk::{python_package_name, ProgrammingLanguage, ProjectInfo, SDKManager};
+use anyhow::{anyhow, Result};
+use log::info;
+use serde_json::Value;
+
+/// Languages clients are generated for
+pub const CLIENT_LANGUAGES: [ProgrammingLanguage; 3] =
+    [ProgrammingLanguage::Rust, ProgrammingLanguage::TypeScript, ProgrammingLanguage::Python];
+
+const RUST_KEYWORDS: &[&str] = &["type", "match", "ref", "move", "mod", "use", "fn", "impl", "self", "crate"];
+const PYTHON_KEYWORDS: &[&str] = &["from", "import", "class", "def", "in", "is", "global", "lambda", "pass"];
+
+/// One operation of the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiOperation {
+    pub id: String, // operationId, snake case
+    pub method: String,
+    pub path: String, // OpenAPI syntax, `{id}` for parameters
+    pub summary: String,
+    pub path_params: Vec<String>,
+    pub request: Option<String>,  // Body schema name
+    pub response: Option<String>, // 200 schema name; untyped JSON without one
+}
+
+/// A service's OpenAPI document, ready to generate clients from
+pub struct ApiClientGenerator {
+    service: String,
+    spec: Value,
+}
+
+impl ApiClientGenerator {
+    pub fn from_spec(service: &str, spec: Value) -> Result<Self> {
+        let version = spec["openapi"].as_str().unwrap_or_default();
+        if !version.starts_with("3.") {
+            return Err(anyhow!("{} does not serve an OpenAPI 3 document (got {:?})", service, version));
+        }
+        if !spec["paths"].is_object() {
+            return Err(anyhow!("OpenAPI document of {} has no paths", service));
+        }
+        Ok(Self { service: service.to_string(), spec })
+    }
+
+    /// Fetch the document a running service serves under its versioned root,
+    /// e.g. `http://localhost:8081/v1`, the same root its clients are built with
+    pub async fn fetch(service: &str, base_url: &str) -> Result<Self> {
+        let url = format!("{}/openapi.json", base_url.trim_end_matches('/'));
+        let spec = reqwest::get(&url).await?.error_for_status()?.json().await?;
+        Self::from_spec(service, spec)
+    }
+
+    /// Operations in path order, each method of a path in turn
+    pub fn operations(&self) -> Result<Vec<ApiOperation>> {
+        let mut operations = Vec::new();
+        for (path, methods) in self.spec["paths"].as_object().into_iter().flatten() {
+            for (method, operation) in methods.as_object().into_iter().flatten() {
+                let id = operation["operationId"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("{} {} has no operationId", method, path))?;
+                operations.push(ApiOperation {
+                    id: id.to_string(),
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                    summary: operation["summary"].as_str().unwrap_or_default().to_string(),
+                    path_params: operation["parameters"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter(|p| p["in"] == "path")
+                        .filter_map(|p| p["name"].as_str().map(str::to_string))
+                        .collect(),
+                    request: schema_name(&operation["requestBody"]["content"]["application/json"]["schema"]),
+                    response: schema_name(&operation["responses"]["200"]["content"]["application/json"]["schema"]),
+                });
+            }
+        }
+        Ok(operations)
+    }
+
+    /// Component schemas by name
+    fn schemas(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.spec["components"]["schemas"].as_object().into_iter().flatten()
+    }
+
+    /// Name of the client project for `language`, e.g. `ai-jobd-client-ts`
+    pub fn project_name(&self, language: &ProgrammingLanguage) -> String {
+        let suffix = match language {
+            ProgrammingLanguage::Rust => "rs",
+            ProgrammingLanguage::TypeScript => "ts",
+            ProgrammingLanguage::Python => "py",
+            _ => "client",
+        };
+        format!("{}-client-{}", self.service, suffix)
+    }
+
+    /// Scaffold the client project for `language` and write its sources
+    pub async fn generate(&self, manager: &mut SDKManager, language: ProgrammingLanguage) -> Result<ProjectInfo> {
+        let name = self.project_name(&language);
+        let template = match language {
+            ProgrammingLanguage::Rust => "rust-api-client",
+            ProgrammingLanguage::TypeScript => "typescript-api-client",
+            ProgrammingLanguage::Python => "python-api-client",
+            ref other => return Err(anyhow!("No API client generator for {:?}", other)),
+        };
+        let project = manager.create_project(name, language.clone(), Some(template.to_string())).await?;
+        for (file, contents) in self.render(&language, &project.name)? {
+            let path = format!("{}/{}", project.path, file);
+            std::fs::write(&path, contents)?;
+        }
+        info!("Generated {:?} client for {} in {}", language, self.service, project.path);
+        Ok(project)
+    }
+
+    /// Clients for every language in [`CLIENT_LANGUAGES`]
+    pub async fn generate_all(&self, manager: &mut SDKManager) -> Result<Vec<ProjectInfo>> {
+        let mut projects = Vec::new();
+        for language in CLIENT_LANGUAGES {
+            projects.push(self.generate(manager, language).await?);
+        }
+        Ok(projects)
+    }
+
+    /// Generated files as (path within the project, contents)
+    pub fn render(&self, language: &ProgrammingLanguage, project_name: &str) -> Result<Vec<(String, String)>> {
+        let operations = self.operations()?;
+        match language {
+            ProgrammingLanguage::Rust => Ok(vec![
+                ("src/client.rs".to_string(), self.render_rust(&operations)),
+                ("src/lib.rs".to_string(), "pub mod client;\npub use client::*;\n".to_string()),
+            ]),
+            ProgrammingLanguage::TypeScript => Ok(vec![
+                ("src/client.ts".to_string(), self.render_typescript(&operations)),
+                ("src/index.ts".to_string(), "export * from \"./client\";\n".to_string()),
+            ]),
+            ProgrammingLanguage::Python => {
+                let package = python_package_name(project_name);
+                Ok(vec![
+                    (format!("{}/client.py", package), self.render_python(&operations)),
+                    (format!("{}/__init__.py", package), "from .client import *  # noqa: F401,F403\n".to_string()),
+                ])
+            }
+            other => Err(anyhow!("No API client generator for {:?}", other)),
+        }
+    }
+
+    fn header(&self, comment: &str) -> String {
+        format!("{} Generated from the {} OpenAPI document. Do not edit.\n", comment, self.service)
+    }
+
+    fn render_rust(&self, operations: &[ApiOperation]) -> String {
+        let mut out = self.header("//!");
+        out.push_str("\nuse serde::{Deserialize, Serialize};\nuse std::collections::HashMap;\n");
+
+        for (name, schema) in self.schemas() {
+            out.push('\n');
+            if schema["type"] != "object" {
+                out.push_str(&format!("pub type {} = {};\n", name, rust_type(schema, true)));
+                continue;
+            }
+            out.push_str("#[derive(Debug, Clone, Default, Serialize, Deserialize)]\n");
+            out.push_str(&format!("pub struct {} {{\n", name));
+            for (field, property) in properties(schema) {
+                let required = is_required(schema, field);
+                let ty = rust_type(property, required);
+                if let Some(description) = property["description"].as_str() {
+                    out.push_str(&format!("    /// {}\n", description));
+                }
+                if !required {
+                    out.push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+                }
+                let ident = if RUST_KEYWORDS.contains(&field.as_str()) { format!("r#{}", field) } else { field.clone() };
+                out.push_str(&format!("    pub {}: {},\n", ident, ty));
+            }
+            out.push_str("}\n");
+        }
+
+        out.push_str(
+            r#"
+/// A non-2xx answer, with the service's structured error body
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        ApiError { status: e.status().map_or(0, |s| s.as_u16()), body: serde_json::json!({ "message": e.to_string() }) }
+    }
+}
+
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Self {
+        Client { base_url: base_url.trim_end_matches('/').to_string(), http: reqwest::Client::new() }
+    }
+
+    async fn send<T: serde::de::DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T, ApiError> {
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        if !response.status().is_success() {
+            return Err(ApiError { status, body: response.json().await.unwrap_or_default() });
+        }
+        Ok(response.json().await?)
+    }
+"#,
+        );
+        for op in operations {
+            let mut args: Vec<String> = op.path_params.iter().map(|p| format!("{}: &str", p)).collect();
+            if let Some(request) = &op.request {
+                args.push(format!("body: &{}", request));
+            }
+            let response = op.response.clone().unwrap_or_else(|| "serde_json::Value".to_string());
+            let mut path = format!("\"{{}}{}\", self.base_url", op.path);
+            for param in &op.path_params {
+                path = path.replacen(&format!("{{{}}}", param), "{}", 1) + &format!(", {}", param);
+            }
+            out.push_str(&format!(
+                "\n    /// {} {}: {}\n    pub async fn {}(&self{}) -> Result<{}, ApiError> {{\n",
+                op.method,
+                op.path,
+                op.summary,
+                op.id,
+                args.iter().map(|a| format!(", {}", a)).collect::<String>(),
+                response
+            ));
+            let method = op.method.to_lowercase();
+            let body = if op.request.is_some() { ".json(body)" } else { "" };
+            out.push_str(&format!("        self.send(self.http.{}(format!({})){}).await\n    }}\n", method, path, body));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_typescript(&self, operations: &[ApiOperation]) -> String {
+        let mut out = self.header("//");
+
+        for (name, schema) in self.schemas() {
+            out.push('\n');
+            if schema["type"] != "object" {
+                out.push_str(&format!("export type {} = {};\n", name, ts_type(schema)));
+                continue;
+            }
+            out.push_str(&format!("export interface {} {{\n", name));
+            for (field, property) in properties(schema) {
+                if let Some(description) = property["description"].as_str() {
+                    out.push_str(&format!("  /** {} */\n", description));
+                }
+                let optional = if is_required(schema, field) { "" } else { "?" };
+                out.push_str(&format!("  {}{}: {};\n", field, optional, ts_type(property)));
+            }
+            out.push_str("}\n");
+        }
+
+        out.push_str(
+            r#"
+/** A non-2xx answer, with the service's structured error body */
+export class ApiError extends Error {
+  constructor(public status: number, public body: unknown) {
+    super(`HTTP ${status}`);
+  }
+}
+
+export class Client {
+  constructor(private baseUrl: string, private fetchImpl: typeof fetch = fetch) {
+    this.baseUrl = baseUrl.replace(/\/+$/, "");
+  }
+
+  private async send<T>(method: string, path: string, body?: unknown): Promise<T> {
+    const response = await this.fetchImpl(this.baseUrl + path, {
+      method,
+      headers: body === undefined ? {} : { "content-type": "application/json" },
+      body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const payload = await response.json().catch(() => null);
+    if (!response.ok) {
+      throw new ApiError(response.status, payload);
+    }
+    return payload as T;
+  }
+"#,
+        );
+        for op in operations {
+            let mut args: Vec<String> = op.path_params.iter().map(|p| format!("{}: string", camel_case(p))).collect();
+            if let Some(request) = &op.request {
+                args.push(format!("body: {}", request));
+            }
+            let mut path = op.path.clone();
+            for param in &op.path_params {
+                path = path.replace(&format!("{{{}}}", param), &format!("${{encodeURIComponent({})}}", camel_case(param)));
+            }
+            let body = if op.request.is_some() { ", body" } else { "" };
+            out.push_str(&format!(
+                "\n  /** {} {}: {} */\n  {}({}): Promise<{}> {{\n    return this.send(\"{}\", `{}`{});\n  }}\n",
+                op.method,
+                op.path,
+                op.summary,
+                camel_case(&op.id),
+                args.join(", "),
+                op.response.as_deref().unwrap_or("unknown"),
+                op.method,
+                path,
+                body
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_python(&self, operations: &[ApiOperation]) -> String {
+        let mut out = self.header("#");
+        out.push_str("\nfrom __future__ import annotations\n\nfrom typing import Any, NotRequired, Optional, TypedDict\nfrom urllib.parse import quote\n\nimport requests\n");
+
+        for (name, schema) in self.schemas() {
+            out.push_str("\n\n");
+            if schema["type"] != "object" {
+                out.push_str(&format!("{} = {}\n", name, py_type(schema)));
+                continue;
+            }
+            out.push_str(&format!("class {}(TypedDict):\n", name));
+            let fields: Vec<_> = properties(schema)
+                .filter(|(field, _)| !PYTHON_KEYWORDS.contains(&field.as_str()))
+                .collect();
+            if fields.is_empty() {
+                out.push_str("    pass\n");
+            }
+            for (field, property) in fields {
+                let ty = py_type(property);
+                let ty = if is_required(schema, field) { ty } else { format!("NotRequired[{}]", ty) };
+                out.push_str(&format!("    {}: {}\n", field, ty));
+                if let Some(description) = property["description"].as_str() {
+                    out.push_str(&format!("    \"\"\"{}\"\"\"\n", description));
+                }
+            }
+        }
+
+        out.push_str(
+            r#"
+
+
+class ApiError(Exception):
+    """A non-2xx answer, with the service's structured error body"""
+
+    def __init__(self, status: int, body: Any):
+        super().__init__(f"HTTP {status}")
+        self.status = status
+        self.body = body
+
+
+class Client:
+    def __init__(self, base_url: str, session: Optional[requests.Session] = None):
+        self.base_url = base_url.rstrip("/")
+        self.session = session or requests.Session()
+
+    def _send(self, method: str, path: str, body: Any = None) -> Any:
+        response = self.session.request(method, self.base_url + path, json=body)
+        try:
+            payload = response.json()
+        except ValueError:
+            payload = None
+        if not response.ok:
+            raise ApiError(response.status_code, payload)
+        return payload
+"#,
+        );
+        for op in operations {
+            let mut args: Vec<String> = op.path_params.iter().map(|p| format!("{}: str", p)).collect();
+            if let Some(request) = &op.request {
+                args.push(format!("body: {}", request));
+            }
+            let mut path = op.path.clone();
+            for param in &op.path_params {
+                path = path.replace(&format!("{{{}}}", param), &format!("{{quote({}, safe='')}}", param));
+            }
+            let body = if op.request.is_some() { ", body" } else { "" };
+            out.push_str(&format!(
+                "\n    def {}(self{}) -> {}:\n        \"\"\"{} {}: {}\"\"\"\n        return self._send(\"{}\", f\"{}\"{})\n",
+                op.id,
+                args.iter().map(|a| format!(", {}", a)).collect::<String>(),
+                op.response.as_deref().unwrap_or("Any"),
+                op.method,
+                op.path,
+                op.summary,
+                op.method,
+                path,
+                body
+            ));
+        }
+        out
+    }
+}
+
+/// `#/components/schemas/Name` -> `Name`
+fn schema_name(schema: &Value) -> Option<String> {
+    schema["$ref"].as_str().and_then(|r| r.strip_prefix("#/components/schemas/")).map(str::to_string)
+}
+
+fn properties(schema: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    schema["properties"].as_object().into_iter().flatten()
+}
+
+fn is_required(schema: &Value, field: &str) -> bool {
+    schema["required"].as_array().is_some_and(|required| required.iter().any(|r| r == field))
+}
+
+/// The schema's type and whether it admits null: `["string", "null"]` is a nullable string
+fn schema_type(schema: &Value) -> (Option<&str>, bool) {
+    match &schema["type"] {
+        Value::String(ty) => (Some(ty.as_str()), false),
+        Value::Array(types) => (
+            types.iter().filter_map(Value::as_str).find(|ty| *ty != "null"),
+            types.iter().any(|ty| ty == "null"),
+        ),
+        _ => (None, false),
+    }
+}
+
+fn rust_type(schema: &Value, required: bool) -> String {
+    let (ty, nullable) = schema_type(schema);
+    let inner = match (schema_name(schema), ty) {
+        (Some(name), _) => name,
+        (None, Some("string")) => "String".to_string(),
+        (None, Some("integer")) => "i64".to_string(),
+        (None, Some("number")) => "f64".to_string(),
+        (None, Some("boolean")) => "bool".to_string(),
+        (None, Some("array")) => format!("Vec<{}>", rust_type(&schema["items"], true)),
+        (None, Some("object")) if schema["additionalProperties"].is_object() => {
+            format!("HashMap<String, {}>", rust_type(&schema["additionalProperties"], true))
+        }
+        _ => "serde_json::Value".to_string(),
+    };
+    if nullable || !required {
+        format!("Option<{}>", inner)
+    } else {
+        inner
+    }
+}
+
+fn ts_type(schema: &Value) -> String {
+    let (ty, nullable) = schema_type(schema);
+    let inner = match (schema_name(schema), ty) {
+        (Some(name), _) => name,
+        (None, Some("string")) => match schema["enum"].as_array() {
+            Some(values) => values.iter().filter_map(Value::as_str).map(|v| format!("\"{}\"", v)).collect::<Vec<_>>().join(" | "),
+            None => "string".to_string(),
+        },
+        (None, Some("integer" | "number")) => "number".to_string(),
+        (None, Some("boolean")) => "boolean".to_string(),
+        (None, Some("array")) => format!("{}[]", ts_type(&schema["items"])),
+        (None, Some("object")) if schema["additionalProperties"].is_object() => {
+            format!("Record<string, {}>", ts_type(&schema["additionalProperties"]))
+        }
+        (None, Some("object")) => "Record<string, unknown>".to_string(),
+        _ => "unknown".to_string(),
+    };
+    if nullable {
+        format!("{} | null", inner)
+    } else {
+        inner
+    }
+}
+
+fn py_type(schema: &Value) -> String {
+    let (ty, nullable) = schema_type(schema);
+    let inner = match (schema_name(schema), ty) {
+        (Some(name), _) => format!("\"{}\"", name),
+        (None, Some("string")) => "str".to_string(),
+        (None, Some("integer")) => "int".to_string(),
+        (None, Some("number")) => "float".to_string(),
+        (None, Some("boolean")) => "bool".to_string(),
+        (None, Some("array")) => format!("list[{}]", py_type(&schema["items"])),
+        (None, Some("object")) if schema["additionalProperties"].is_object() => {
+            format!("dict[str, {}]", py_type(&schema["additionalProperties"]))
+        }
+        (None, Some("object")) => "dict[str, Any]".to_string(),
+        _ => "Any".to_string(),
+    };
+    if nullable {
+        format!("Optional[{}]", inner)
+    } else {
+        inner
+    }
+}
+
+/// `get_job_id_status` -> `getJobIdStatus`
+fn camel_case(snake: &str) -> String {
+    let mut parts = snake.split('_');
+    let first = parts.next().unwrap_or_default().to_string();
+    parts.fold(first, |mut out, part| {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            out.extend(c.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+        out
+    })
+}
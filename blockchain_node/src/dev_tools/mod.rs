@@ -4,6 +4,7 @@
 //! IDE integrations, testing frameworks, debugging tools, and deployment automation.
 
 pub mod sdk;
+pub mod api_clients;
 pub mod ide_integration;
 pub mod testing_framework;
 pub mod debugging_tools;
@@ -14,6 +15,7 @@ pub mod performance_profiler;
 pub mod security_analyzer;
 
 pub use sdk::*;
+pub use api_clients::*;
 pub use ide_integration::*;
 pub use testing_framework::*;
 pub use debugging_tools::*;
@@ -599,8 +599,15 @@ impl RustSDK {
             dependencies.insert("secp256k1".to_string(), "0.24".to_string());
         }
 
+        // Generated API clients: typed DTOs over a JSON HTTP client
+        if template.as_ref().map_or(false, |t| t.contains("api-client")) {
+            dependencies.insert("serde".to_string(), r#"{ version = "1.0", features = ["derive"] }"#.to_string());
+            dependencies.insert("serde_json".to_string(), "1.0".to_string());
+            dependencies.insert("reqwest".to_string(), r#"{ version = "0.11", features = ["json"] }"#.to_string());
+        }
+
         let deps_str = dependencies.iter()
-            .map(|(k, v)| format!("{} = \"{}\"", k, v))
+            .map(|(k, v)| if v.starts_with('{') { format!("{} = {}", k, v) } else { format!("{} = \"{}\"", k, v) })
             .collect::<Vec<_>>()
             .join("\n");
 
@@ -1336,6 +1343,356 @@ contract("{}", function (accounts) {{
     }
 }
 
+/// TypeScript SDK implementation
+pub struct TypeScriptSDK {
+    /// SDK version
+    version: String,
+    /// Configuration
+    config: Option<SDKConfig>,
+}
+
+impl SDK for TypeScriptSDK {
+    fn initialize(&mut self, config: &SDKConfig) -> Result<()> {
+        info!("Initializing TypeScript SDK version {}", self.version);
+        self.config = Some(config.clone());
+        Ok(())
+    }
+
+    async fn create_project(&self, name: String, template: Option<String>) -> Result<ProjectInfo> {
+        info!("Creating TypeScript project: {}", name);
+
+        let project_path = format!("./{}", name);
+
+        // Create project directory
+        std::fs::create_dir_all(format!("{}/src", project_path))?;
+
+        // Create package.json
+        let package_json = self.generate_package_json(&name, &template)?;
+        std::fs::write(format!("{}/package.json", project_path), package_json)?;
+
+        // Create tsconfig.json
+        std::fs::write(format!("{}/tsconfig.json", project_path), self.generate_tsconfig())?;
+
+        // Create entry point
+        std::fs::write(format!("{}/src/index.ts", project_path), "export {};\n")?;
+
+        // Create README.md
+        std::fs::write(
+            format!("{}/README.md", project_path),
+            format!("# {}\n\nArthaChain TypeScript project.\n\n```bash\nnpm install\nnpm run build\n```\n", name),
+        )?;
+
+        info!("TypeScript project created successfully: {}", name);
+        Ok(ProjectInfo {
+            name,
+            path: project_path,
+            language: ProgrammingLanguage::TypeScript,
+            template,
+            created_at: SystemTime::now(),
+            configuration: ProjectConfiguration {
+                version: "0.1.0".to_string(),
+                dependencies: HashMap::new(),
+                build_settings: BuildSettings {
+                    output_directory: "dist".to_string(),
+                    intermediate_directory: "dist".to_string(),
+                    clean_build: false,
+                    parallel_build: false,
+                    verbose_output: false,
+                },
+                test_settings: TestSettings {
+                    test_directory: "test".to_string(),
+                    test_pattern: "*.test.ts".to_string(),
+                    coverage_output: "coverage".to_string(),
+                    test_timeout: 300,
+                    parallel_tests: true,
+                },
+                deployment_settings: DeploymentSettings {
+                    target_environment: "npm".to_string(),
+                    deployment_script: None,
+                    health_check_url: None,
+                    rollback_strategy: "manual".to_string(),
+                },
+            },
+        })
+    }
+
+    async fn build_project(&self, project_path: &str) -> Result<BuildResults> {
+        info!("Building TypeScript project: {}", project_path);
+        let (success, duration, errors) = run_tool("npm", &["run", "build"], project_path)?;
+        Ok(BuildResults {
+            success,
+            duration,
+            output_files: vec![format!("{}/dist/", project_path)],
+            warnings: Vec::new(),
+            errors,
+            artifacts: BuildArtifacts {
+                executables: Vec::new(),
+                libraries: vec![format!("{}/dist/index.js", project_path)],
+                documentation: Vec::new(),
+                configuration: Vec::new(),
+            },
+        })
+    }
+
+    async fn test_project(&self, project_path: &str) -> Result<TestResults> {
+        info!("Testing TypeScript project: {}", project_path);
+        let (success, duration, _) = run_tool("npm", &["test"], project_path)?;
+        Ok(TestResults {
+            success,
+            total_tests: 0,
+            passed_tests: 0,
+            failed_tests: 0,
+            skipped_tests: 0,
+            duration,
+            coverage_percentage: 0.0,
+            test_details: Vec::new(),
+        })
+    }
+
+    async fn deploy_project(&self, project_path: &str, target: &str) -> Result<DeploymentResults> {
+        info!("Publishing TypeScript project: {} to {}", project_path, target);
+        let (success, duration, errors) = run_tool("npm", &["publish", "--dry-run"], project_path)?;
+        Ok(DeploymentResults {
+            success,
+            duration,
+            deployment_url: None,
+            health_check_status: None,
+            deployment_logs: errors,
+            rollback_info: None,
+        })
+    }
+
+    fn get_version(&self) -> String {
+        self.version.clone()
+    }
+
+    fn get_supported_features(&self) -> Vec<String> {
+        vec!["Typed API Clients".to_string(), "Web3 Integration".to_string()]
+    }
+}
+
+impl TypeScriptSDK {
+    fn new() -> Self {
+        Self {
+            version: "1.0.0".to_string(),
+            config: None,
+        }
+    }
+
+    fn generate_package_json(&self, name: &str, template: &Option<String>) -> Result<String> {
+        let mut package = serde_json::json!({
+            "name": name,
+            "version": "0.1.0",
+            "description": "ArthaChain TypeScript Project",
+            "main": "dist/index.js",
+            "types": "dist/index.d.ts",
+            "scripts": { "build": "tsc", "test": "node --test dist" },
+            "devDependencies": { "typescript": "^5.4.0" },
+        });
+        // Generated API clients use the platform fetch, so they need nothing more
+        if template.as_ref().map_or(false, |t| t.contains("api-client")) {
+            package["description"] = "ArthaChain API client".into();
+        }
+        Ok(serde_json::to_string_pretty(&package)?)
+    }
+
+    fn generate_tsconfig(&self) -> String {
+        r#"{
+  "compilerOptions": {
+    "target": "ES2022",
+    "module": "commonjs",
+    "declaration": true,
+    "strict": true,
+    "outDir": "dist",
+    "lib": ["ES2022", "DOM"]
+  },
+  "include": ["src"]
+}
+"#
+        .to_string()
+    }
+}
+
+/// Python SDK implementation
+pub struct PythonSDK {
+    /// SDK version
+    version: String,
+    /// Configuration
+    config: Option<SDKConfig>,
+}
+
+impl SDK for PythonSDK {
+    fn initialize(&mut self, config: &SDKConfig) -> Result<()> {
+        info!("Initializing Python SDK version {}", self.version);
+        self.config = Some(config.clone());
+        Ok(())
+    }
+
+    async fn create_project(&self, name: String, template: Option<String>) -> Result<ProjectInfo> {
+        info!("Creating Python project: {}", name);
+
+        let project_path = format!("./{}", name);
+        let package = python_package_name(&name);
+
+        // Create package and tests directories
+        std::fs::create_dir_all(format!("{}/{}", project_path, package))?;
+        std::fs::create_dir_all(format!("{}/tests", project_path))?;
+
+        // Create pyproject.toml
+        let pyproject = self.generate_pyproject(&name, &template);
+        std::fs::write(format!("{}/pyproject.toml", project_path), pyproject)?;
+
+        // Create package init
+        std::fs::write(format!("{}/{}/__init__.py", project_path, package), "")?;
+
+        // Create README.md
+        std::fs::write(
+            format!("{}/README.md", project_path),
+            format!("# {}\n\nArthaChain Python project.\n\n```bash\npip install -e .\n```\n", name),
+        )?;
+
+        info!("Python project created successfully: {}", name);
+        Ok(ProjectInfo {
+            name,
+            path: project_path,
+            language: ProgrammingLanguage::Python,
+            template,
+            created_at: SystemTime::now(),
+            configuration: ProjectConfiguration {
+                version: "0.1.0".to_string(),
+                dependencies: HashMap::new(),
+                build_settings: BuildSettings {
+                    output_directory: "dist".to_string(),
+                    intermediate_directory: "build".to_string(),
+                    clean_build: false,
+                    parallel_build: false,
+                    verbose_output: false,
+                },
+                test_settings: TestSettings {
+                    test_directory: "tests".to_string(),
+                    test_pattern: "test_*.py".to_string(),
+                    coverage_output: "htmlcov".to_string(),
+                    test_timeout: 300,
+                    parallel_tests: false,
+                },
+                deployment_settings: DeploymentSettings {
+                    target_environment: "pypi".to_string(),
+                    deployment_script: None,
+                    health_check_url: None,
+                    rollback_strategy: "manual".to_string(),
+                },
+            },
+        })
+    }
+
+    async fn build_project(&self, project_path: &str) -> Result<BuildResults> {
+        info!("Building Python project: {}", project_path);
+        let (success, duration, errors) = run_tool("python3", &["-m", "build"], project_path)?;
+        Ok(BuildResults {
+            success,
+            duration,
+            output_files: vec![format!("{}/dist/", project_path)],
+            warnings: Vec::new(),
+            errors,
+            artifacts: BuildArtifacts {
+                executables: Vec::new(),
+                libraries: vec![format!("{}/dist/", project_path)],
+                documentation: Vec::new(),
+                configuration: Vec::new(),
+            },
+        })
+    }
+
+    async fn test_project(&self, project_path: &str) -> Result<TestResults> {
+        info!("Testing Python project: {}", project_path);
+        let (success, duration, _) = run_tool("python3", &["-m", "pytest", "-q"], project_path)?;
+        Ok(TestResults {
+            success,
+            total_tests: 0,
+            passed_tests: 0,
+            failed_tests: 0,
+            skipped_tests: 0,
+            duration,
+            coverage_percentage: 0.0,
+            test_details: Vec::new(),
+        })
+    }
+
+    async fn deploy_project(&self, project_path: &str, target: &str) -> Result<DeploymentResults> {
+        info!("Publishing Python project: {} to {}", project_path, target);
+        let build_results = self.build_project(project_path).await?;
+        if !build_results.success {
+            return Err(anyhow!("Build failed, cannot deploy"));
+        }
+        let (success, duration, errors) = run_tool("python3", &["-m", "twine", "check", "dist/*"], project_path)?;
+        Ok(DeploymentResults {
+            success,
+            duration,
+            deployment_url: None,
+            health_check_status: None,
+            deployment_logs: errors,
+            rollback_info: None,
+        })
+    }
+
+    fn get_version(&self) -> String {
+        self.version.clone()
+    }
+
+    fn get_supported_features(&self) -> Vec<String> {
+        vec!["Typed API Clients".to_string(), "Web3 Integration".to_string()]
+    }
+}
+
+impl PythonSDK {
+    fn new() -> Self {
+        Self {
+            version: "1.0.0".to_string(),
+            config: None,
+        }
+    }
+
+    fn generate_pyproject(&self, name: &str, template: &Option<String>) -> String {
+        let dependencies = if template.as_ref().map_or(false, |t| t.contains("api-client")) {
+            r#""requests>=2.31""#
+        } else {
+            ""
+        };
+        format!(
+            r#"[build-system]
+requires = ["setuptools>=68"]
+build-backend = "setuptools.build_meta"
+
+[project]
+name = "{}"
+version = "0.1.0"
+description = "ArthaChain Python Project"
+requires-python = ">=3.11"
+dependencies = [{}]
+"#,
+            name, dependencies
+        )
+    }
+}
+
+/// A project's import name: `ai-jobd-client` becomes `ai_jobd_client`
+pub fn python_package_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Run a language toolchain command in a project, returning whether it
+/// succeeded, how long it took and its stderr on failure
+fn run_tool(program: &str, args: &[&str], project_path: &str) -> Result<(bool, Duration, Vec<String>)> {
+    let start_time = std::time::Instant::now();
+    let output = Command::new(program).args(args).current_dir(project_path).output()?;
+    let errors = if output.status.success() {
+        Vec::new()
+    } else {
+        vec![String::from_utf8_lossy(&output.stderr).to_string()]
+    };
+    Ok((output.status.success(), start_time.elapsed(), errors))
+}
+
 impl SDKManager {
     /// Create new SDK manager
     pub fn new() -> Self {
@@ -1349,6 +1706,10 @@ impl SDKManager {
         // Add Solidity SDK
         available_sdks.insert(ProgrammingLanguage::Solidity, Box::new(SoliditySDK::new()));
 
+        // Add TypeScript and Python SDKs, for generated API clients
+        available_sdks.insert(ProgrammingLanguage::TypeScript, Box::new(TypeScriptSDK::new()));
+        available_sdks.insert(ProgrammingLanguage::Python, Box::new(PythonSDK::new()));
+
         Self {
             available_sdks,
             sdk_configs: HashMap::new(),
@@ -1459,6 +1820,25 @@ impl SDKManager {
             },
         );
 
+        // Add API client templates, filled in by the API client generator
+        for (key, name, language) in [
+            ("rust-api-client", "Rust API Client", ProgrammingLanguage::Rust),
+            ("typescript-api-client", "TypeScript API Client", ProgrammingLanguage::TypeScript),
+            ("python-api-client", "Python API Client", ProgrammingLanguage::Python),
+        ] {
+            self.project_templates.insert(
+                key.to_string(),
+                ProjectTemplate {
+                    name: name.to_string(),
+                    description: "Typed client generated from a service's OpenAPI document".to_string(),
+                    language,
+                    template_files: HashMap::new(),
+                    dependencies: HashMap::new(),
+                    configuration: HashMap::new(),
+                },
+            );
+        }
+
         info!("Project templates loaded successfully");
         Ok(())
     }
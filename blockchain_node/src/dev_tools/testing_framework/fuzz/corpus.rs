@@ -0,0 +1,84 @@
+//! Regression corpus: minimized failing sequences persisted as JSON
+//!
+//! Every failure the harness finds is shrunk to a minimal sequence and
+//! written to the corpus directory. Fixed failures stay in the corpus and are
+//! replayed by the test suite so they cannot come back.
+
+use super::generators::FuzzAction;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    pub name: String,
+    #[serde(default)]
+    pub seed: Option<u64>, // Seed that produced the case in seeded mode
+    pub found: String,     // The violation as first reported
+    pub actions: Vec<FuzzAction>,
+}
+
+impl CorpusEntry {
+    pub fn persist(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).with_context(|| format!("creating corpus dir {}", dir.display()))?;
+        let path = dir.join(format!("{}.json", self.name));
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing corpus entry {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// All entries in `dir`, in file name order. A missing directory is an empty corpus.
+pub fn load_dir(dir: &Path) -> Result<Vec<CorpusEntry>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let json = fs::read_to_string(path)?;
+            serde_json::from_str(&json).with_context(|| format!("parsing corpus entry {}", path.display()))
+        })
+        .collect()
+}
+
+/// Shrink a failing sequence by removing chunks of actions (delta debugging)
+/// for as long as `still_fails` holds
+pub fn minimize<F>(actions: &[FuzzAction], mut still_fails: F) -> Vec<FuzzAction>
+where
+    F: FnMut(&[FuzzAction]) -> bool,
+{
+    let mut current = actions.to_vec();
+    let mut chunk = (current.len() / 2).max(1);
+
+    loop {
+        let mut removed = false;
+        let mut start = 0;
+        while start < current.len() {
+            let end = (start + chunk).min(current.len());
+            let candidate: Vec<FuzzAction> = current[..start].iter().chain(&current[end..]).cloned().collect();
+            if !candidate.is_empty() && still_fails(&candidate) {
+                current = candidate;
+                removed = true;
+            } else {
+                start = end;
+            }
+        }
+
+        if !removed {
+            if chunk == 1 {
+                break;
+            }
+            chunk = (chunk / 2).max(1);
+        }
+    }
+    current
+}
@@ -0,0 +1,330 @@
+//! In-memory devnet the harness executes against
+//!
+//! A ledger [`State`] seeded with boundary-value accounts and fixture
+//! contracts, driven through [`TransactionExecutor`]. EVM-style calls carry
+//! ABI calldata and WASM calls carry an export selector plus encoded
+//! arguments; both run on the executor's contract path. Each sequence runs
+//! inside a snapshot that is reverted afterwards, so one devnet serves many
+//! sequences.
+
+use super::generators::{wasm_call_data, FuzzAction, NonceChoice, TxParams, DEVNET_MAX_GAS_LIMIT};
+use super::invariants::{self, StateDump, TxOutcome, Violation};
+use crate::config::Config;
+use crate::execution::executor::ContractExecutor;
+use crate::execution::TransactionExecutor;
+use crate::ledger::state::State;
+use crate::ledger::transaction::{Transaction, TransactionType};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+/// Genesis accounts as (balance, nonce): funded, huge, empty, dust and
+/// saturated balances, and an account whose nonce cannot be incremented
+pub const GENESIS_ACCOUNTS: [(u64, u64); 6] = [
+    (1_000_000_000, 0),
+    (u64::MAX / 2, 0),
+    (0, 0),
+    (1, 0),
+    (u64::MAX, 0),
+    (10_000_000, u64::MAX),
+];
+
+/// Contracts deployed at genesis as (address, code)
+pub const FIXTURE_CONTRACTS: [(&str, &[u8]); 4] = [
+    ("fixture_evm_token", &[0x60, 0x80, 0x60, 0x40, 0x52, 0x34, 0x80, 0x15]),
+    ("fixture_evm_reentrant", &[0x60, 0x80, 0x60, 0x40, 0x52, 0x36, 0x15, 0x60]),
+    ("fixture_wasm_counter", b"\0asm\x01\0\0\0counter"),
+    ("fixture_wasm_memory", b"\0asm\x01\0\0\0memory"),
+];
+
+#[derive(Debug, Clone)]
+pub struct DevnetConfig {
+    pub max_gas_limit: u64,
+    pub min_gas_price: u64,
+}
+
+impl Default for DevnetConfig {
+    fn default() -> Self {
+        Self {
+            max_gas_limit: DEVNET_MAX_GAS_LIMIT,
+            min_gas_price: 1,
+        }
+    }
+}
+
+/// Address of devnet account `index`. Forty hex digits, so EVM calldata can
+/// name it in the low 20 bytes of an address word.
+pub fn account_address(index: usize) -> String {
+    format!("{:040x}", index + 1)
+}
+
+struct Checkpoint {
+    snapshot_id: u64,
+    dump: StateDump,
+    contracts: usize,
+    burned: u128,
+}
+
+pub struct Devnet {
+    state: State,
+    scratch: State, // Rebuilt from scratch to recompute the state root
+    executor: TransactionExecutor,
+    deployer: ContractExecutor,
+    runtime: tokio::runtime::Runtime,
+    config: DevnetConfig,
+    accounts: Vec<String>,
+    contracts: Vec<String>,
+}
+
+impl Devnet {
+    pub fn new(config: DevnetConfig) -> Result<Self> {
+        let state = State::new(&Config::default())?;
+        // Start from an empty ledger regardless of anything persisted on disk
+        *state.balances.write().unwrap() = HashMap::new();
+        *state.nonces.write().unwrap() = HashMap::new();
+        *state.storage.write().unwrap() = HashMap::new();
+
+        let accounts: Vec<String> = (0..GENESIS_ACCOUNTS.len()).map(account_address).collect();
+        for (address, (balance, nonce)) in accounts.iter().zip(GENESIS_ACCOUNTS) {
+            state.set_balance(address, balance)?;
+            state.set_nonce(address, nonce)?;
+        }
+
+        // Same storage layout as TransactionExecutor::execute_deploy
+        let mut contracts = Vec::new();
+        for (address, code) in FIXTURE_CONTRACTS {
+            state.set_storage(&format!("contract:{}", address), code.to_vec())?;
+            state.set_storage(&format!("contract_creator:{}", address), accounts[0].as_bytes().to_vec())?;
+            contracts.push(address.to_string());
+        }
+
+        Ok(Self {
+            state,
+            scratch: State::new(&Config::default())?,
+            executor: TransactionExecutor::new(
+                Some(Arc::new(ContractExecutor::new())),
+                1.0,
+                config.max_gas_limit,
+                config.min_gas_price,
+            ),
+            deployer: ContractExecutor::new(),
+            runtime: tokio::runtime::Builder::new_current_thread().build()?,
+            config,
+            accounts,
+            contracts,
+        })
+    }
+
+    pub fn accounts(&self) -> &[String] {
+        &self.accounts
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Run one sequence, checking invariants after every step and at the end,
+    /// then revert the devnet to genesis. Returns the number of transactions
+    /// executed. After a panic the devnet may be inconsistent and should be
+    /// rebuilt.
+    pub fn run_sequence(&mut self, actions: &[FuzzAction]) -> Result<usize, Violation> {
+        let genesis = StateDump::capture(&self.state);
+        let genesis_total = invariants::total_balance(&self.state);
+        let genesis_contracts = self.contracts.len();
+        let sequence_snapshot = self
+            .state
+            .create_snapshot()
+            .map_err(|e| harness_error(0, e))?;
+
+        let mut checkpoints = Vec::new();
+        let mut burned = 0u128;
+        let executed = self.run_steps(actions, &mut checkpoints, &mut burned);
+
+        // Checkpoints left open at the end of the sequence are simply dropped
+        for checkpoint in checkpoints.into_iter().rev() {
+            let _ = self.state.commit_snapshot(checkpoint.snapshot_id);
+        }
+
+        let end_checks = executed.and_then(|executed| {
+            invariants::check_sequence_conservation(
+                genesis_total,
+                invariants::total_balance(&self.state),
+                burned,
+            )?;
+            self.check_state_root(actions.len())?;
+            Ok(executed)
+        });
+
+        let reverted = self.state.revert_to_snapshot(sequence_snapshot);
+        self.contracts.truncate(genesis_contracts);
+        let executed = end_checks?;
+        reverted.map_err(|e| harness_error(actions.len(), e))?;
+
+        invariants::check_restored(None, &genesis, &StateDump::capture(&self.state))?;
+        invariants::check_open_snapshots(actions.len(), self.state.snapshot_count(), 0)?;
+        Ok(executed)
+    }
+
+    fn run_steps(
+        &mut self,
+        actions: &[FuzzAction],
+        checkpoints: &mut Vec<Checkpoint>,
+        burned: &mut u128,
+    ) -> Result<usize, Violation> {
+        let mut executed = 0;
+        for (step, action) in actions.iter().enumerate() {
+            match action {
+                FuzzAction::Checkpoint => {
+                    let snapshot_id = self
+                        .state
+                        .create_snapshot()
+                        .map_err(|e| harness_error(step, e))?;
+                    checkpoints.push(Checkpoint {
+                        snapshot_id,
+                        dump: StateDump::capture(&self.state),
+                        contracts: self.contracts.len(),
+                        burned: *burned,
+                    });
+                }
+                FuzzAction::Rollback => {
+                    let Some(checkpoint) = checkpoints.pop() else {
+                        continue;
+                    };
+                    self.state
+                        .revert_to_snapshot(checkpoint.snapshot_id)
+                        .map_err(|e| harness_error(step, e))?;
+                    self.contracts.truncate(checkpoint.contracts);
+                    *burned = checkpoint.burned;
+                    invariants::check_restored(Some(step), &checkpoint.dump, &StateDump::capture(&self.state))?;
+                }
+                _ => {
+                    let tx = self.transaction(action).map_err(|e| harness_error(step, e))?;
+                    let before = invariants::total_balance(&self.state);
+                    let outcome = self.execute(step, &tx)?;
+                    let after = invariants::total_balance(&self.state);
+
+                    *burned += invariants::check_transaction(
+                        step,
+                        &tx,
+                        &outcome,
+                        before,
+                        after,
+                        self.config.max_gas_limit,
+                    )?;
+                    // The sequence snapshot plus open checkpoints; nothing from the executor
+                    invariants::check_open_snapshots(step, self.state.snapshot_count(), checkpoints.len() + 1)?;
+
+                    if outcome.succeeded() && matches!(tx.tx_type, TransactionType::Deploy) {
+                        if let Ok(address) = self.deployer.deploy_contract(&tx.data, &tx.sender, tx.gas_limit) {
+                            self.contracts.push(address);
+                        }
+                    }
+                    executed += 1;
+                }
+            }
+        }
+        Ok(executed)
+    }
+
+    /// Build the ledger transaction for a transaction-bearing action
+    fn transaction(&self, action: &FuzzAction) -> Result<Transaction> {
+        let (tx_type, params, recipient, data) = match action {
+            FuzzAction::Transfer { tx, recipient } => {
+                (TransactionType::Transfer, tx, self.account(*recipient), Vec::new())
+            }
+            FuzzAction::EvmDeploy { tx, bytecode } => {
+                (TransactionType::Deploy, tx, self.account(tx.sender), bytecode.clone())
+            }
+            FuzzAction::EvmCall { tx, contract, calldata } => {
+                (TransactionType::ContractCall, tx, self.contract(*contract), calldata.clone())
+            }
+            FuzzAction::WasmDeploy { tx, module } => {
+                (TransactionType::Deploy, tx, self.account(tx.sender), module.clone())
+            }
+            FuzzAction::WasmCall { tx, contract, export, args, memory_pages } => (
+                TransactionType::Call,
+                tx,
+                self.contract(*contract),
+                wasm_call_data(export, *memory_pages, args),
+            ),
+            FuzzAction::Checkpoint | FuzzAction::Rollback => {
+                return Err(anyhow::anyhow!("{:?} carries no transaction", action))
+            }
+        };
+
+        let sender = self.account(params.sender);
+        let nonce = self.nonce(&sender, params)?;
+        let mut tx = Transaction::new(
+            tx_type,
+            sender,
+            recipient,
+            params.value,
+            nonce,
+            params.gas_price,
+            params.gas_limit,
+            data,
+        );
+        tx.signature = vec![1]; // The executor checks for a signature, not its validity
+        Ok(tx)
+    }
+
+    fn nonce(&self, sender: &str, params: &TxParams) -> Result<u64> {
+        let current = self.state.get_nonce(sender)?;
+        Ok(match params.nonce {
+            NonceChoice::Current => current,
+            NonceChoice::Stale => current.wrapping_sub(1),
+            NonceChoice::Future => current.wrapping_add(1),
+            NonceChoice::Exact(nonce) => nonce,
+        })
+    }
+
+    fn account(&self, index: usize) -> String {
+        self.accounts[index % self.accounts.len()].clone()
+    }
+
+    fn contract(&self, index: usize) -> String {
+        self.contracts[index % self.contracts.len()].clone()
+    }
+
+    /// Execute with panics caught; an escaping panic is itself a violation
+    fn execute(&self, step: usize, tx: &Transaction) -> Result<TxOutcome, Violation> {
+        let mut tx = tx.clone();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.runtime
+                .block_on(self.executor.execute_transaction(&mut tx, &self.state))
+        }));
+
+        match result {
+            Ok(Ok(result)) => Ok(TxOutcome::Executed(result)),
+            Ok(Err(e)) => Ok(TxOutcome::Error(e.to_string())),
+            Err(payload) => Err(Violation::Panic {
+                step,
+                message: payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "non-string panic payload".to_string()),
+            }),
+        }
+    }
+
+    /// Rebuild the current accounts and storage into fresh maps and compare
+    /// roots with the incrementally updated state
+    fn check_state_root(&self, step: usize) -> Result<(), Violation> {
+        let dump = StateDump::capture(&self.state);
+        *self.scratch.balances.write().unwrap() = dump.balances.into_iter().rev().collect();
+        *self.scratch.storage.write().unwrap() = dump.storage.into_iter().rev().collect();
+
+        let incremental = self.state.get_state_root().map_err(|e| harness_error(step, e))?;
+        let recomputed = self.scratch.get_state_root().map_err(|e| harness_error(step, e))?;
+        invariants::check_state_root(&incremental.to_hex(), &recomputed.to_hex())
+    }
+}
+
+fn harness_error(step: usize, error: impl std::fmt::Display) -> Violation {
+    Violation::Harness {
+        step,
+        message: error.to_string(),
+    }
+}
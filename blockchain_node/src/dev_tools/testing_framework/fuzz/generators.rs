@@ -0,0 +1,336 @@
+//! Structured generators for EVM transactions, WASM contract calls and
+//! sequences of them
+//!
+//! Generated values are "valid-ish": mostly well-formed calls against the
+//! devnet's fixture contracts with the current nonce, mixed with boundary
+//! values for gas, value and nonce and deliberately malformed encodings.
+
+use super::input::FuzzInput;
+use serde::{Deserialize, Serialize};
+
+/// Longest action sequence generated from one input
+pub const MAX_SEQUENCE_LEN: usize = 32;
+/// Longest generated calldata / argument payload
+pub const MAX_PAYLOAD_LEN: usize = 196;
+/// Gas limit enforced by the devnet executor (the EVM block gas limit)
+pub const DEVNET_MAX_GAS_LIMIT: u64 = crate::evm::BLOCK_GAS_LIMIT;
+/// Memory page limit of the WASM engine
+pub const WASM_MAX_MEMORY_PAGES: u32 = 1024;
+
+/// Values at and around the edges of the u64 range
+pub const BOUNDARY_U64: [u64; 8] = [
+    0,
+    1,
+    2,
+    u32::MAX as u64,
+    u64::MAX / 2,
+    u64::MAX - 1,
+    u64::MAX,
+    21_000,
+];
+
+const BOUNDARY_GAS_LIMITS: [u64; 8] = [
+    0,
+    1,
+    20_999,
+    21_000,
+    100_000,
+    DEVNET_MAX_GAS_LIMIT,
+    DEVNET_MAX_GAS_LIMIT + 1,
+    u64::MAX,
+];
+
+const BOUNDARY_GAS_PRICES: [u64; 6] = [0, 1, 2, 20_000_000_000, u64::MAX / 21_000, u64::MAX];
+
+/// Function selectors the ledger executor dispatches on for EVM-style calls
+pub const EVM_SELECTORS: [[u8; 4]; 6] = [
+    [0x70, 0xa0, 0x82, 0x31], // transfer(address,uint256)
+    [0x18, 0x16, 0x0d, 0xdd], // balanceOf(address)
+    [0x06, 0xfd, 0xde, 0x03], // name()
+    [0x95, 0xd8, 0x9b, 0x41], // symbol()
+    [0x31, 0x3c, 0xe5, 0x67], // decimals()
+    [0x18, 0x15, 0x5f, 0xcc], // totalSupply()
+];
+
+/// Exports of the WASM fixture contracts, plus names no contract exports
+pub const WASM_EXPORTS: [&str; 8] = [
+    "init",
+    "call",
+    "transfer",
+    "balance_of",
+    "grow_memory",
+    "",
+    "\u{0}",
+    "missing_export",
+];
+
+/// Sender nonce relative to the sender's current account nonce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonceChoice {
+    Current,
+    Stale,
+    Future,
+    Exact(u64),
+}
+
+/// Fields shared by every generated transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxParams {
+    pub sender: usize, // Devnet account index
+    pub value: u64,
+    pub nonce: NonceChoice,
+    pub gas_limit: u64,
+    pub gas_price: u64,
+}
+
+/// One step of a fuzzed sequence. Account and contract indices are resolved
+/// modulo what exists on the devnet when the step runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FuzzAction {
+    Transfer {
+        tx: TxParams,
+        recipient: usize, // Account index; equal to the sender for self-transfers
+    },
+    EvmDeploy {
+        tx: TxParams,
+        #[serde(with = "hex_bytes")]
+        bytecode: Vec<u8>,
+    },
+    EvmCall {
+        tx: TxParams,
+        contract: usize,
+        #[serde(with = "hex_bytes")]
+        calldata: Vec<u8>,
+    },
+    WasmDeploy {
+        tx: TxParams,
+        #[serde(with = "hex_bytes")]
+        module: Vec<u8>,
+    },
+    WasmCall {
+        tx: TxParams,
+        contract: usize,
+        export: String,
+        #[serde(with = "hex_bytes")]
+        args: Vec<u8>,
+        memory_pages: u32, // Requested memory growth
+    },
+    /// Take a state snapshot
+    Checkpoint,
+    /// Revert to the most recent open snapshot
+    Rollback,
+}
+
+impl FuzzAction {
+    pub fn tx(&self) -> Option<&TxParams> {
+        match self {
+            FuzzAction::Transfer { tx, .. }
+            | FuzzAction::EvmDeploy { tx, .. }
+            | FuzzAction::EvmCall { tx, .. }
+            | FuzzAction::WasmDeploy { tx, .. }
+            | FuzzAction::WasmCall { tx, .. } => Some(tx),
+            FuzzAction::Checkpoint | FuzzAction::Rollback => None,
+        }
+    }
+}
+
+/// Transaction data for a WASM call: a 4-byte export selector, the requested
+/// memory growth, then the raw argument encoding
+pub fn wasm_call_data(export: &str, memory_pages: u32, args: &[u8]) -> Vec<u8> {
+    let selector = blake3::hash(export.as_bytes());
+    let mut data = selector.as_bytes()[..4].to_vec();
+    data.extend_from_slice(&memory_pages.to_be_bytes());
+    data.extend_from_slice(args);
+    data
+}
+
+/// Mostly small values, often boundary values, occasionally anything
+fn value(input: &mut FuzzInput) -> u64 {
+    match input.byte() % 4 {
+        0 | 1 => input.u32() as u64 % 1_000_000,
+        2 => input.choose(&BOUNDARY_U64),
+        _ => input.u64(),
+    }
+}
+
+fn tx_params(input: &mut FuzzInput, accounts: usize) -> TxParams {
+    let sender = input.below(accounts);
+    let value = value(input);
+    let nonce = match input.byte() % 10 {
+        0 => NonceChoice::Stale,
+        1 => NonceChoice::Future,
+        2 => NonceChoice::Exact(input.choose(&BOUNDARY_U64)),
+        _ => NonceChoice::Current,
+    };
+    let gas_limit = if input.bool() {
+        input.choose(&BOUNDARY_GAS_LIMITS)
+    } else {
+        21_000 + input.u32() as u64 % 1_000_000
+    };
+    let gas_price = if input.byte() % 4 == 0 {
+        input.choose(&BOUNDARY_GAS_PRICES)
+    } else {
+        1 + input.byte() as u64
+    };
+
+    TxParams {
+        sender,
+        value,
+        nonce,
+        gas_limit,
+        gas_price,
+    }
+}
+
+/// ABI word holding a devnet account address (hex of its last 20 bytes)
+fn address_word(input: &mut FuzzInput, accounts: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(input.below(accounts) as u64 + 1).to_be_bytes());
+    word
+}
+
+fn amount_word(input: &mut FuzzInput) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value(input).to_be_bytes());
+    if input.byte() % 8 == 0 {
+        // High bytes the executor ignores; a correct decoder must not
+        word[..24].iter_mut().for_each(|b| *b = input.byte());
+    }
+    word
+}
+
+fn evm_calldata(input: &mut FuzzInput, accounts: usize) -> Vec<u8> {
+    let mut calldata = match input.byte() % 8 {
+        0 => return Vec::new(), // Fallback
+        1 => return input.bytes(3), // Shorter than a selector
+        2 => input.bytes(4),        // Unknown selector
+        _ => input.choose(&EVM_SELECTORS).to_vec(),
+    };
+
+    // Arguments: addresses and amounts, sometimes truncated mid-word
+    let words = input.below(4);
+    for _ in 0..words {
+        let word = if input.bool() {
+            address_word(input, accounts)
+        } else {
+            amount_word(input)
+        };
+        calldata.extend_from_slice(&word);
+    }
+    if input.byte() % 6 == 0 && !calldata.is_empty() {
+        let keep = input.below(calldata.len());
+        calldata.truncate(keep.max(1));
+    }
+    calldata.truncate(MAX_PAYLOAD_LEN);
+    calldata
+}
+
+fn wasm_module(input: &mut FuzzInput) -> Vec<u8> {
+    match input.byte() % 4 {
+        0 => Vec::new(),
+        1 => input.bytes(MAX_PAYLOAD_LEN),
+        _ => {
+            // Valid header followed by arbitrary sections
+            let mut module = b"\0asm\x01\0\0\0".to_vec();
+            module.extend(input.bytes(MAX_PAYLOAD_LEN));
+            module
+        }
+    }
+}
+
+/// Adversarial parameter encodings: well-formed little-endian words,
+/// truncated words, huge length prefixes, or noise
+fn wasm_args(input: &mut FuzzInput) -> Vec<u8> {
+    match input.byte() % 5 {
+        0 => Vec::new(),
+        1 => {
+            let mut args = value(input).to_le_bytes().to_vec();
+            args.extend_from_slice(&value(input).to_le_bytes());
+            args
+        }
+        2 => value(input).to_le_bytes()[..input.below(8)].to_vec(),
+        3 => {
+            let mut args = u64::MAX.to_le_bytes().to_vec();
+            args.extend(input.bytes(16));
+            args
+        }
+        _ => input.bytes(MAX_PAYLOAD_LEN),
+    }
+}
+
+fn memory_pages(input: &mut FuzzInput) -> u32 {
+    match input.byte() % 4 {
+        0 => 0,
+        1 => input.choose(&[
+            1,
+            WASM_MAX_MEMORY_PAGES - 1,
+            WASM_MAX_MEMORY_PAGES,
+            WASM_MAX_MEMORY_PAGES + 1,
+            u32::MAX,
+        ]),
+        _ => input.below(WASM_MAX_MEMORY_PAGES as usize + 2) as u32,
+    }
+}
+
+pub fn generate_action(input: &mut FuzzInput, accounts: usize) -> FuzzAction {
+    match input.byte() % 16 {
+        0..=4 | 15 => {
+            let tx = tx_params(input, accounts);
+            // Bias towards zero-value self-transfers, a classic edge case
+            let recipient = if input.byte() % 5 == 0 {
+                tx.sender
+            } else {
+                input.below(accounts)
+            };
+            FuzzAction::Transfer { tx, recipient }
+        }
+        5..=7 => FuzzAction::EvmCall {
+            tx: tx_params(input, accounts),
+            contract: input.u32() as usize,
+            calldata: evm_calldata(input, accounts),
+        },
+        8 => FuzzAction::EvmDeploy {
+            tx: tx_params(input, accounts),
+            bytecode: input.bytes(MAX_PAYLOAD_LEN),
+        },
+        9..=11 => FuzzAction::WasmCall {
+            tx: tx_params(input, accounts),
+            contract: input.u32() as usize,
+            export: input.choose(&WASM_EXPORTS).to_string(),
+            args: wasm_args(input),
+            memory_pages: memory_pages(input),
+        },
+        12 => FuzzAction::WasmDeploy {
+            tx: tx_params(input, accounts),
+            module: wasm_module(input),
+        },
+        13 => FuzzAction::Checkpoint,
+        _ => FuzzAction::Rollback,
+    }
+}
+
+/// A sequence of actions, ending early if the input runs out
+pub fn generate_sequence(input: &mut FuzzInput, accounts: usize) -> Vec<FuzzAction> {
+    let len = 1 + input.below(MAX_SEQUENCE_LEN);
+    let mut actions = Vec::with_capacity(len);
+    while actions.len() < len && !input.is_exhausted() {
+        actions.push(generate_action(input, accounts));
+    }
+    actions
+}
+
+/// Byte payloads are stored as hex so corpus entries stay readable
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(&s).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,73 @@
+//! Byte source for the generators
+//!
+//! libFuzzer hands the harness raw bytes; the seeded mode expands a seed into
+//! bytes with SplitMix64. Generators only ever read from a [`FuzzInput`], so
+//! both modes share them and every case can be replayed from its bytes.
+
+/// Cursor over fuzzer-provided bytes
+pub struct FuzzInput<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FuzzInput<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Next byte, or zero once the input is exhausted so generation always terminates
+    pub fn byte(&mut self) -> u8 {
+        let byte = self.data.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        byte
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.byte() & 1 == 1
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        (0..4).fold(0, |acc, _| (acc << 8) | self.byte() as u32)
+    }
+
+    pub fn u64(&mut self) -> u64 {
+        (0..8).fold(0, |acc, _| (acc << 8) | self.byte() as u64)
+    }
+
+    /// Uniform-ish index in `0..n`; zero when `n` is zero
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        self.u32() as usize % n
+    }
+
+    pub fn choose<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    /// Between zero and `max_len` bytes
+    pub fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.byte()).collect()
+    }
+}
+
+/// Expand `seed` into `len` pseudo-random bytes (SplitMix64)
+pub fn seeded_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    let mut out = Vec::with_capacity(len + 8);
+    while out.len() < len {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        out.extend_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+    out.truncate(len);
+    out
+}
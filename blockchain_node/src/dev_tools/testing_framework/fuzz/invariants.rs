@@ -0,0 +1,230 @@
+//! Invariants enforced on every fuzzed step and sequence
+//!
+//! - Total balance is conserved except for gas burned by successful transactions
+//! - No panic escapes the executor; every failure is a typed result or error
+//! - Gas charged never exceeds the transaction's limits
+//! - The state root recomputed from scratch matches the incrementally updated one
+//! - Snapshots and reverts restore state exactly and leave no open snapshots behind
+
+use crate::execution::ExecutionResult;
+use crate::ledger::state::State;
+use crate::ledger::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Ordered copy of the account and storage maps, for exact comparisons
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDump {
+    pub balances: BTreeMap<String, u64>,
+    pub nonces: BTreeMap<String, u64>,
+    pub storage: BTreeMap<String, Vec<u8>>,
+}
+
+impl StateDump {
+    pub fn capture(state: &State) -> Self {
+        Self {
+            balances: state.balances.read().unwrap().clone().into_iter().collect(),
+            nonces: state.nonces.read().unwrap().clone().into_iter().collect(),
+            storage: state.storage.read().unwrap().clone().into_iter().collect(),
+        }
+    }
+
+    /// First difference from `other`, described for a violation report
+    fn first_difference(&self, other: &StateDump) -> Option<String> {
+        fn diff<V: PartialEq + fmt::Debug>(
+            kind: &str,
+            a: &BTreeMap<String, V>,
+            b: &BTreeMap<String, V>,
+        ) -> Option<String> {
+            a.keys()
+                .chain(b.keys())
+                .find(|k| a.get(*k) != b.get(*k))
+                .map(|k| format!("{} of {}: {:?} vs {:?}", kind, k, a.get(k), b.get(k)))
+        }
+        diff("balance", &self.balances, &other.balances)
+            .or_else(|| diff("nonce", &self.nonces, &other.nonces))
+            .or_else(|| diff("storage", &self.storage, &other.storage))
+    }
+}
+
+/// Sum of all balances; u128 so saturated accounts cannot overflow the total
+pub fn total_balance(state: &State) -> u128 {
+    state
+        .balances
+        .read()
+        .unwrap()
+        .values()
+        .map(|b| *b as u128)
+        .sum()
+}
+
+/// What the executor returned for one transaction
+#[derive(Debug, Clone)]
+pub enum TxOutcome {
+    Executed(ExecutionResult),
+    Error(String),
+}
+
+impl TxOutcome {
+    pub fn succeeded(&self) -> bool {
+        matches!(self, TxOutcome::Executed(ExecutionResult::Success))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "invariant", rename_all = "snake_case")]
+pub enum Violation {
+    Panic {
+        step: usize,
+        message: String,
+    },
+    BalanceNotConserved {
+        step: Option<usize>, // None for the end-of-sequence check
+        before: u128,
+        after: u128,
+        expected_burn: u128,
+    },
+    GasLimitExceeded {
+        step: usize,
+        gas_limit: u64,
+        max_gas_limit: u64,
+        burned: u128,
+        fee_cap: u128,
+    },
+    StateRootMismatch {
+        incremental: String,
+        recomputed: String,
+    },
+    SnapshotResidue {
+        step: Option<usize>,
+        detail: String,
+    },
+    Harness {
+        step: usize,
+        message: String,
+    },
+}
+
+impl Violation {
+    /// Whether both violations broke the same invariant, ignoring details.
+    /// Minimization keeps a candidate only if it fails the same way.
+    pub fn same_kind(&self, other: &Violation) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Panic { step, message } => {
+                write!(f, "step {}: executor panicked: {}", step, message)
+            }
+            Violation::BalanceNotConserved { step, before, after, expected_burn } => write!(
+                f,
+                "{}: total balance {} -> {}, expected burn {}",
+                step.map_or("sequence".to_string(), |s| format!("step {}", s)),
+                before,
+                after,
+                expected_burn
+            ),
+            Violation::GasLimitExceeded { step, gas_limit, max_gas_limit, burned, fee_cap } => write!(
+                f,
+                "step {}: burned {} with fee cap {} (gas limit {}, max {})",
+                step, burned, fee_cap, gas_limit, max_gas_limit
+            ),
+            Violation::StateRootMismatch { incremental, recomputed } => write!(
+                f,
+                "state root {} does not match recomputed root {}",
+                incremental, recomputed
+            ),
+            Violation::SnapshotResidue { step, detail } => write!(
+                f,
+                "{}: snapshot residue: {}",
+                step.map_or("sequence".to_string(), |s| format!("step {}", s)),
+                detail
+            ),
+            Violation::Harness { step, message } => write!(f, "step {}: harness error: {}", step, message),
+        }
+    }
+}
+
+/// Conservation and gas bounds for one transaction. Returns the gas burned.
+pub fn check_transaction(
+    step: usize,
+    tx: &Transaction,
+    outcome: &TxOutcome,
+    before: u128,
+    after: u128,
+    max_gas_limit: u64,
+) -> Result<u128, Violation> {
+    let fee_cap = tx.gas_limit as u128 * tx.gas_price as u128;
+    let expected_burn = if outcome.succeeded() { fee_cap } else { 0 };
+    let not_conserved = Violation::BalanceNotConserved {
+        step: Some(step),
+        before,
+        after,
+        expected_burn,
+    };
+
+    if after > before {
+        return Err(not_conserved);
+    }
+    let burned = before - after;
+    if burned > fee_cap || (outcome.succeeded() && tx.gas_limit > max_gas_limit) {
+        return Err(Violation::GasLimitExceeded {
+            step,
+            gas_limit: tx.gas_limit,
+            max_gas_limit,
+            burned,
+            fee_cap,
+        });
+    }
+    if burned != expected_burn {
+        return Err(not_conserved);
+    }
+    Ok(burned)
+}
+
+/// Genesis total minus everything burned by the surviving steps
+pub fn check_sequence_conservation(genesis_total: u128, end_total: u128, burned: u128) -> Result<(), Violation> {
+    if genesis_total.checked_sub(burned) != Some(end_total) {
+        return Err(Violation::BalanceNotConserved {
+            step: None,
+            before: genesis_total,
+            after: end_total,
+            expected_burn: burned,
+        });
+    }
+    Ok(())
+}
+
+/// Executor-internal snapshots must be committed or reverted by the time a
+/// transaction returns
+pub fn check_open_snapshots(step: usize, open: usize, expected: usize) -> Result<(), Violation> {
+    if open != expected {
+        return Err(Violation::SnapshotResidue {
+            step: Some(step),
+            detail: format!("{} snapshots open, expected {}", open, expected),
+        });
+    }
+    Ok(())
+}
+
+/// A revert must restore the captured state exactly
+pub fn check_restored(step: Option<usize>, expected: &StateDump, actual: &StateDump) -> Result<(), Violation> {
+    match expected.first_difference(actual) {
+        Some(detail) => Err(Violation::SnapshotResidue { step, detail }),
+        None => Ok(()),
+    }
+}
+
+pub fn check_state_root(incremental: &str, recomputed: &str) -> Result<(), Violation> {
+    if incremental != recomputed {
+        return Err(Violation::StateRootMismatch {
+            incremental: incremental.to_string(),
+            recomputed: recomputed.to_string(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! Transaction fuzzing harness
+//!
+//! Generates sequences of EVM transactions and WASM contract calls with
+//! boundary values and adversarial encodings, executes them against an
+//! in-memory devnet and checks the invariants in [`invariants`] after every
+//! step and sequence.
+//!
+//! Two entry points share the generators:
+//! - [`run_input`] takes raw bytes and is called from the cargo-fuzz target
+//!   in `blockchain_node/fuzz`
+//! - [`run_seeded`] derives inputs from a seed for a bounded number of
+//!   iterations, so CI runs are reproducible. Failures are minimized and
+//!   persisted as corpus entries.
+
+pub mod corpus;
+pub mod devnet;
+pub mod generators;
+pub mod input;
+pub mod invariants;
+
+pub use corpus::CorpusEntry;
+pub use devnet::{Devnet, DevnetConfig};
+pub use generators::FuzzAction;
+pub use invariants::Violation;
+
+use anyhow::Result;
+use input::{seeded_bytes, FuzzInput};
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    pub seed: u64,
+    pub iterations: usize,
+    pub input_len: usize, // Bytes expanded from each seed
+    pub corpus_dir: Option<PathBuf>, // Where minimized failures are written
+    pub devnet: DevnetConfig,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0xA57A_F022,
+            iterations: 256,
+            input_len: 4096,
+            corpus_dir: None,
+            devnet: DevnetConfig::default(),
+        }
+    }
+}
+
+impl FuzzConfig {
+    /// Defaults overridden by ARTHA_FUZZ_SEED, ARTHA_FUZZ_ITERATIONS and
+    /// ARTHA_FUZZ_CORPUS_DIR
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            seed: env_parse("ARTHA_FUZZ_SEED").unwrap_or(defaults.seed),
+            iterations: env_parse("ARTHA_FUZZ_ITERATIONS").unwrap_or(defaults.iterations),
+            corpus_dir: std::env::var("ARTHA_FUZZ_CORPUS_DIR").ok().map(PathBuf::from),
+            ..defaults
+        }
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+#[derive(Debug, Default)]
+pub struct FuzzReport {
+    pub sequences: usize,
+    pub transactions: usize,
+    pub failures: Vec<CorpusEntry>, // Minimized, one per failing seed
+}
+
+/// Run `config.iterations` seeded sequences. Each failure is minimized and,
+/// when a corpus directory is configured, persisted there.
+pub fn run_seeded(config: &FuzzConfig) -> Result<FuzzReport> {
+    let mut devnet = Devnet::new(config.devnet.clone())?;
+    let mut report = FuzzReport::default();
+
+    for i in 0..config.iterations {
+        let seed = config.seed.wrapping_add(i as u64);
+        let bytes = seeded_bytes(seed, config.input_len);
+        let actions = generators::generate_sequence(&mut FuzzInput::new(&bytes), devnet.accounts().len());
+        report.sequences += 1;
+
+        match devnet.run_sequence(&actions) {
+            Ok(executed) => report.transactions += executed,
+            Err(violation) => {
+                let entry = minimize_failure(&format!("seed-{:016x}", seed), Some(seed), &actions, &violation, config)?;
+                if let Some(dir) = &config.corpus_dir {
+                    entry.persist(dir)?;
+                }
+                report.failures.push(entry);
+                // A failed sequence may have left the devnet inconsistent
+                devnet = Devnet::new(config.devnet.clone())?;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Shrink `actions` to a minimal sequence that still breaks the same invariant
+pub fn minimize_failure(
+    name: &str,
+    seed: Option<u64>,
+    actions: &[FuzzAction],
+    violation: &Violation,
+    config: &FuzzConfig,
+) -> Result<CorpusEntry> {
+    let minimized = corpus::minimize(actions, |candidate| {
+        Devnet::new(config.devnet.clone())
+            .map(|mut devnet| matches!(devnet.run_sequence(candidate), Err(v) if v.same_kind(violation)))
+            .unwrap_or(false)
+    });
+
+    Ok(CorpusEntry {
+        name: name.to_string(),
+        seed,
+        found: violation.to_string(),
+        actions: minimized,
+    })
+}
+
+/// Replay one corpus entry on a fresh devnet
+pub fn replay(entry: &CorpusEntry, config: &DevnetConfig) -> Result<Result<usize, Violation>> {
+    Ok(Devnet::new(config.clone())?.run_sequence(&entry.actions))
+}
+
+thread_local! {
+    static DEVNET: RefCell<Option<Devnet>> = const { RefCell::new(None) };
+}
+
+/// libFuzzer-style entry point: generate a sequence from raw bytes and run it.
+/// The devnet is reused across calls on the same thread and rebuilt after a
+/// violation.
+pub fn run_input(data: &[u8]) -> Result<(), Violation> {
+    DEVNET.with(|cell| {
+        let mut slot = cell.borrow_mut();
+        let devnet = match slot.as_mut() {
+            Some(devnet) => devnet,
+            None => slot.insert(Devnet::new(DevnetConfig::default()).expect("devnet construction")),
+        };
+
+        let actions = generators::generate_sequence(&mut FuzzInput::new(data), devnet.accounts().len());
+        let result = devnet.run_sequence(&actions).map(|_| ());
+        if result.is_err() {
+            *slot = None;
+        }
+        result
+    })
+}
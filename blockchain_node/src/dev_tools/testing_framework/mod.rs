@@ -0,0 +1,5 @@
+//! Testing Framework
+//!
+//! Tooling for exercising the node beyond hand-written unit tests.
+
+pub mod fuzz;
@@ -0,0 +1,508 @@
+//! Event Bus
+//! Each subscriber owns a bounded queue with the backpressure policy it chose
+//! at subscription. Block import only calls [`EventBus::emit`], which never
+//! waits: fan-out runs on the bus's dispatcher task, so a slow or panicking
+//! subscriber cannot stall the publisher.
+
+use super::matcher::{ContractFilter, ContractMatcher};
+use super::{Event, EventKind};
+use log::{debug, warn};
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify};
+use tokio::time::Instant;
+
+pub type SubscriberId = u64;
+
+/// What happens when a subscriber's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait for the subscriber to make room, up to the subscription's
+    /// `block_timeout`; the event is dropped for it after that
+    Block,
+    /// Evict the oldest queued event
+    DropOldest,
+    /// Discard the incoming event
+    DropNewest,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscribeOptions {
+    pub capacity: usize,
+    pub backpressure: Backpressure,
+    pub block_timeout: Duration,
+    /// Event kinds to receive; empty means all. Contract matches are routed
+    /// by `contracts` regardless of this list.
+    pub kinds: Vec<EventKind>,
+    pub contracts: Vec<ContractFilter>,
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            backpressure: Backpressure::DropOldest,
+            block_timeout: Duration::from_secs(1),
+            kinds: Vec::new(),
+            contracts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriberStats {
+    pub id: SubscriberId,
+    pub queued: usize,
+    pub dropped: u64,
+    pub panics: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BusStats {
+    pub published: u64,
+    /// Events dropped because the dispatcher fell behind the publisher
+    pub ingress_dropped: u64,
+    pub subscribers: Vec<SubscriberStats>,
+}
+
+struct Queue {
+    events: Mutex<VecDeque<Arc<Event>>>,
+    capacity: usize,
+    readable: Notify,
+    writable: Notify,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+    panics: AtomicU64,
+}
+
+impl Queue {
+    fn pop(&self) -> Option<Arc<Event>> {
+        let event = self.events.lock().unwrap().pop_front();
+        if event.is_some() {
+            self.writable.notify_one();
+        }
+        event
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.readable.notify_one();
+        self.writable.notify_waiters();
+    }
+}
+
+struct Subscriber {
+    id: SubscriberId,
+    options: SubscribeOptions,
+    queue: Arc<Queue>,
+}
+
+impl Subscriber {
+    fn wants(&self, kind: EventKind) -> bool {
+        self.options.kinds.is_empty() || self.options.kinds.contains(&kind)
+    }
+
+    /// Queue one event under the subscriber's backpressure policy
+    async fn deliver(&self, event: Arc<Event>) {
+        let queue = &self.queue;
+        if queue.closed.load(Ordering::SeqCst) {
+            return;
+        }
+
+        match self.options.backpressure {
+            Backpressure::DropNewest => {
+                let mut events = queue.events.lock().unwrap();
+                if events.len() >= queue.capacity {
+                    queue.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                events.push_back(event);
+            }
+            Backpressure::DropOldest => {
+                let mut events = queue.events.lock().unwrap();
+                if events.len() >= queue.capacity {
+                    events.pop_front();
+                    queue.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                events.push_back(event);
+            }
+            Backpressure::Block => {
+                let deadline = Instant::now() + self.options.block_timeout;
+                loop {
+                    {
+                        let mut events = queue.events.lock().unwrap();
+                        if events.len() < queue.capacity {
+                            events.push_back(event);
+                            break;
+                        }
+                    }
+                    let waited = tokio::time::timeout_at(deadline, queue.writable.notified()).await;
+                    if waited.is_err() || queue.closed.load(Ordering::SeqCst) {
+                        queue.dropped.fetch_add(1, Ordering::Relaxed);
+                        debug!("Subscriber {} timed out, dropping event", self.id);
+                        return;
+                    }
+                }
+            }
+        }
+        queue.readable.notify_one();
+    }
+}
+
+struct BusInner {
+    subscribers: RwLock<Vec<Arc<Subscriber>>>,
+    matcher: RwLock<ContractMatcher>,
+    ingress: mpsc::Sender<Event>,
+    next_id: AtomicU64,
+    published: AtomicU64,
+    ingress_dropped: AtomicU64,
+}
+
+impl BusInner {
+    fn unsubscribe(&self, id: SubscriberId) {
+        self.subscribers.write().unwrap().retain(|s| s.id != id);
+        self.matcher.write().unwrap().remove(id);
+    }
+}
+
+impl Drop for BusInner {
+    fn drop(&mut self) {
+        for subscriber in self.subscribers.get_mut().unwrap().iter() {
+            subscriber.queue.close();
+        }
+    }
+}
+
+/// Cheaply cloneable handle to the node's event bus
+#[derive(Clone)]
+pub struct EventBus {
+    inner: Arc<BusInner>,
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.inner.subscribers.read().unwrap().len())
+            .finish()
+    }
+}
+
+impl EventBus {
+    /// Create the bus and spawn its dispatcher. `ingress_capacity` bounds
+    /// the events emitted but not yet fanned out.
+    pub fn new(ingress_capacity: usize) -> Self {
+        let (ingress, mut rx) = mpsc::channel(ingress_capacity.max(1));
+        let inner = Arc::new(BusInner {
+            subscribers: RwLock::new(Vec::new()),
+            matcher: RwLock::new(ContractMatcher::default()),
+            ingress,
+            next_id: AtomicU64::new(1),
+            published: AtomicU64::new(0),
+            ingress_dropped: AtomicU64::new(0),
+        });
+
+        // The dispatcher holds a weak handle so dropping the last EventBus
+        // closes the ingress channel and ends it
+        let weak: Weak<BusInner> = Arc::downgrade(&inner);
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let Some(inner) = weak.upgrade() else { break };
+                EventBus { inner }.publish(event).await;
+            }
+        });
+
+        Self { inner }
+    }
+
+    /// Hand an event to the dispatcher without waiting. If the dispatcher is
+    /// that far behind, the event is dropped and counted.
+    pub fn emit(&self, event: Event) {
+        if self.inner.ingress.try_send(event).is_err() {
+            self.inner.ingress_dropped.fetch_add(1, Ordering::Relaxed);
+            warn!("Event bus ingress full, dropping event");
+        }
+    }
+
+    /// Fan an event out to every interested subscriber. Imported blocks are
+    /// run through the contract matcher once, and each match goes only to the
+    /// subscribers whose filters it satisfied. Subscribers that never block
+    /// are served first so a blocking one cannot delay them.
+    pub async fn publish(&self, event: Event) {
+        self.inner.published.fetch_add(1, Ordering::Relaxed);
+
+        let matched = match &event {
+            Event::BlockImported { height, hash, receipts } => {
+                self.inner.matcher.read().unwrap().match_receipts(*height, hash, receipts)
+            }
+            _ => Vec::new(),
+        };
+
+        let kind = event.kind();
+        let event = Arc::new(event);
+        let mut subscribers = self.inner.subscribers.read().unwrap().clone();
+        subscribers.sort_by_key(|s| s.options.backpressure == Backpressure::Block);
+
+        for subscriber in subscribers {
+            if subscriber.wants(kind) {
+                subscriber.deliver(event.clone()).await;
+            }
+            for (contract_event, recipients) in &matched {
+                if recipients.contains(&subscriber.id) {
+                    subscriber.deliver(contract_event.clone()).await;
+                }
+            }
+        }
+    }
+
+    pub fn subscribe(&self, options: SubscribeOptions) -> Subscription {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let queue = Arc::new(Queue {
+            events: Mutex::new(VecDeque::new()),
+            capacity: options.capacity.max(1),
+            readable: Notify::new(),
+            writable: Notify::new(),
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+            panics: AtomicU64::new(0),
+        });
+
+        {
+            let mut matcher = self.inner.matcher.write().unwrap();
+            for filter in &options.contracts {
+                matcher.add(id, filter.clone());
+            }
+        }
+        self.inner.subscribers.write().unwrap().push(Arc::new(Subscriber {
+            id,
+            options,
+            queue: queue.clone(),
+        }));
+
+        Subscription {
+            id,
+            queue,
+            bus: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Run `handler` for every event on its own task. A panicking handler
+    /// loses the event it panicked on and keeps receiving the rest.
+    pub fn spawn_handler<F>(&self, options: SubscribeOptions, mut handler: F) -> SubscriberId
+    where
+        F: FnMut(&Event) + Send + 'static,
+    {
+        let mut subscription = self.subscribe(options);
+        let id = subscription.id();
+        tokio::spawn(async move {
+            while let Some(event) = subscription.recv().await {
+                if catch_unwind(AssertUnwindSafe(|| handler(&event))).is_err() {
+                    subscription.queue.panics.fetch_add(1, Ordering::Relaxed);
+                    warn!("Event subscriber {} panicked handling {:?}", id, event.kind());
+                }
+            }
+        });
+        id
+    }
+
+    pub fn stats(&self) -> BusStats {
+        let subscribers = self
+            .inner
+            .subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|s| SubscriberStats {
+                id: s.id,
+                queued: s.queue.events.lock().unwrap().len(),
+                dropped: s.queue.dropped.load(Ordering::Relaxed),
+                panics: s.queue.panics.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        BusStats {
+            published: self.inner.published.load(Ordering::Relaxed),
+            ingress_dropped: self.inner.ingress_dropped.load(Ordering::Relaxed),
+            subscribers,
+        }
+    }
+}
+
+/// Receiving end of a subscription. Dropping it unsubscribes.
+pub struct Subscription {
+    id: SubscriberId,
+    queue: Arc<Queue>,
+    bus: Weak<BusInner>,
+}
+
+impl Subscription {
+    pub fn id(&self) -> SubscriberId {
+        self.id
+    }
+
+    /// Next event, or None once the bus is gone
+    pub async fn recv(&mut self) -> Option<Arc<Event>> {
+        loop {
+            if let Some(event) = self.queue.pop() {
+                return Some(event);
+            }
+            if self.queue.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            self.queue.readable.notified().await;
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<Arc<Event>> {
+        self.queue.pop()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.queue.close();
+        if let Some(bus) = self.bus.upgrade() {
+            bus.unsubscribe(self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance(n: u64) -> Event {
+        Event::BalanceChanged {
+            address: "0xabc".to_string(),
+            old: n,
+            new: n + 1,
+        }
+    }
+
+    fn old_balance(event: &Event) -> u64 {
+        match event {
+            Event::BalanceChanged { old, .. } => *old,
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    fn options(capacity: usize, backpressure: Backpressure) -> SubscribeOptions {
+        SubscribeOptions {
+            capacity,
+            backpressure,
+            block_timeout: Duration::from_millis(50),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_policies_under_a_stalled_consumer() {
+        let bus = EventBus::new(16);
+        let mut oldest = bus.subscribe(options(2, Backpressure::DropOldest));
+        let mut newest = bus.subscribe(options(2, Backpressure::DropNewest));
+
+        for n in 0..5 {
+            bus.publish(balance(n)).await;
+        }
+
+        assert_eq!(oldest.dropped(), 3);
+        assert_eq!(old_balance(&oldest.recv().await.unwrap()), 3);
+        assert_eq!(old_balance(&oldest.recv().await.unwrap()), 4);
+
+        assert_eq!(newest.dropped(), 3);
+        assert_eq!(old_balance(&newest.recv().await.unwrap()), 0);
+        assert_eq!(old_balance(&newest.recv().await.unwrap()), 1);
+        assert!(newest.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_policy_waits_for_room_then_times_out() {
+        let bus = EventBus::new(16);
+        let mut slow = bus.subscribe(options(1, Backpressure::Block));
+        let mut fast = bus.subscribe(options(8, Backpressure::DropNewest));
+
+        // A consumer that frees room in time gets every event
+        bus.publish(balance(0)).await;
+        let consumer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let first = slow.recv().await.unwrap();
+            (slow, first)
+        });
+        bus.publish(balance(1)).await;
+        let (mut slow, first) = consumer.await.unwrap();
+        assert_eq!(old_balance(&first), 0);
+        assert_eq!(slow.dropped(), 0);
+
+        // One that never reads only costs the publisher the timeout
+        let started = std::time::Instant::now();
+        bus.publish(balance(2)).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(slow.dropped(), 1);
+        assert_eq!(old_balance(&slow.recv().await.unwrap()), 1);
+
+        // The non-blocking subscriber saw everything regardless
+        for n in 0..3 {
+            assert_eq!(old_balance(&fast.recv().await.unwrap()), n);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_emit_never_waits_on_subscribers() {
+        let bus = EventBus::new(4);
+        let _stalled = bus.subscribe(options(1, Backpressure::Block));
+
+        let started = std::time::Instant::now();
+        for n in 0..100 {
+            bus.emit(balance(n));
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(bus.stats().ingress_dropped > 0);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_handler_is_isolated() {
+        let bus = EventBus::new(16);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let panicky = bus.spawn_handler(SubscribeOptions::default(), move |event| {
+            let n = old_balance(event);
+            if n == 0 {
+                panic!("handler bug");
+            }
+            tx.send(n).unwrap();
+        });
+        let mut healthy = bus.subscribe(SubscribeOptions::default());
+
+        bus.emit(balance(0));
+        bus.emit(balance(1));
+
+        assert_eq!(rx.recv().await, Some(1));
+        assert_eq!(old_balance(&healthy.recv().await.unwrap()), 0);
+        assert_eq!(old_balance(&healthy.recv().await.unwrap()), 1);
+
+        let stats = bus.stats();
+        let panicky_stats = stats.subscribers.iter().find(|s| s.id == panicky).unwrap();
+        assert_eq!(panicky_stats.panics, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_subscription_unsubscribes() {
+        let bus = EventBus::new(16);
+        let subscription = bus.subscribe(SubscribeOptions {
+            contracts: vec![ContractFilter::new("0xaaaa")],
+            ..Default::default()
+        });
+        assert_eq!(bus.stats().subscribers.len(), 1);
+
+        drop(subscription);
+        assert!(bus.stats().subscribers.is_empty());
+        assert!(bus.inner.matcher.read().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! Webhook Forwarder
+//! Forwards logs of the AI marketplace contracts to the off-chain services.
+//! Matched logs are held until their block is finalized, then POSTed to the
+//! routes configured for the emitting contract; logs of blocks that lost a
+//! reorg are discarded without being sent.
+
+use super::bus::{Backpressure, EventBus, SubscribeOptions, Subscription};
+use super::matcher::{normalize_address, ContractFilter};
+use super::{Event, EventKind};
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Logs of `contract` go to every URL in `targets`
+#[derive(Debug, Clone)]
+pub struct ForwardRoute {
+    pub name: String,
+    pub contract: String,
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ForwarderConfig {
+    pub routes: Vec<ForwardRoute>,
+}
+
+impl ForwarderConfig {
+    /// Routes from the contract addresses and service URLs in the
+    /// environment. AIJobManager logs go to ai-jobd, DealMarket logs to
+    /// receipts_daemon, ProofOfCompute logs to both, and NodeCertRegistry
+    /// logs to ai-scheduler. None when nothing would be forwarded.
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let jobd = var("ARTHA_EVENTS_JOBD_URL");
+        let receipts = var("ARTHA_EVENTS_RECEIPTS_URL");
+        let scheduler = var("ARTHA_EVENTS_SCHEDULER_URL");
+
+        let contracts = [
+            ("AIJobManager", "AI_JOB_MANAGER_ADDR", vec![jobd.clone()]),
+            ("DealMarket", "DEAL_MARKET_ADDR", vec![receipts.clone()]),
+            ("ProofOfCompute", "PROOF_OF_COMPUTE_ADDR", vec![jobd, receipts]),
+            ("NodeCertRegistry", "NODE_CERT_REGISTRY_ADDR", vec![scheduler]),
+        ];
+
+        let routes: Vec<ForwardRoute> = contracts
+            .into_iter()
+            .filter_map(|(name, address_var, targets)| {
+                let contract = var(address_var)?;
+                let targets: Vec<String> = targets.into_iter().flatten().collect();
+                (!targets.is_empty()).then(|| ForwardRoute {
+                    name: name.to_string(),
+                    contract,
+                    targets,
+                })
+            })
+            .collect();
+
+        (!routes.is_empty()).then_some(Self { routes })
+    }
+}
+
+pub struct WebhookForwarder {
+    routes: Vec<ForwardRoute>,
+    client: reqwest::Client,
+    /// Matched logs by block height, waiting for finality
+    pending: BTreeMap<u64, Vec<Arc<Event>>>,
+}
+
+impl WebhookForwarder {
+    /// Subscribe to the bus and forward on a background task
+    pub fn spawn(bus: &EventBus, config: ForwarderConfig) -> JoinHandle<()> {
+        let subscription = bus.subscribe(SubscribeOptions {
+            capacity: 4096,
+            backpressure: Backpressure::Block,
+            block_timeout: Duration::from_secs(2),
+            kinds: vec![EventKind::BlockFinalized],
+            contracts: config
+                .routes
+                .iter()
+                .map(|route| ContractFilter::new(&route.contract))
+                .collect(),
+        });
+
+        let mut forwarder = WebhookForwarder {
+            routes: config.routes,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            pending: BTreeMap::new(),
+        };
+        info!("Event forwarder watching {} contracts", forwarder.routes.len());
+
+        tokio::spawn(async move { forwarder.run(subscription).await })
+    }
+
+    async fn run(&mut self, mut subscription: Subscription) {
+        while let Some(event) = subscription.recv().await {
+            match event.as_ref() {
+                Event::ContractEventMatched { block_height, .. } => {
+                    self.pending.entry(*block_height).or_default().push(event.clone());
+                }
+                Event::BlockFinalized { height, hash } => self.on_finalized(*height, hash).await,
+                _ => {}
+            }
+        }
+    }
+
+    async fn on_finalized(&mut self, height: u64, hash: &str) {
+        // Anything still pending at or below a finalized height is settled now
+        let later = self.pending.split_off(&(height + 1));
+        let settled = std::mem::replace(&mut self.pending, later);
+
+        for (block_height, events) in settled {
+            for event in events {
+                let Event::ContractEventMatched { block_hash, address, .. } = event.as_ref() else {
+                    continue;
+                };
+                if block_height != height || block_hash != hash {
+                    debug!("Discarding log from non-canonical block {}", block_hash);
+                    continue;
+                }
+                let Some(route) = self
+                    .routes
+                    .iter()
+                    .find(|r| normalize_address(&r.contract) == *address)
+                else {
+                    continue;
+                };
+                let payload = webhook_payload(&route.name, &event);
+                for target in &route.targets {
+                    self.deliver(target, &payload).await;
+                }
+            }
+        }
+    }
+
+    async fn deliver(&self, target: &str, payload: &serde_json::Value) {
+        for attempt in 1..=DELIVERY_ATTEMPTS {
+            match self.client.post(target).json(payload).send().await {
+                Ok(response) if response.status().is_success() => return,
+                Ok(response) => warn!("Event webhook {} returned {}", target, response.status()),
+                Err(e) => warn!("Event webhook {} failed: {}", target, e),
+            }
+            if attempt < DELIVERY_ATTEMPTS {
+                tokio::time::sleep(Duration::from_millis(200 * attempt as u64)).await;
+            }
+        }
+        warn!("Giving up on event webhook {} after {} attempts", target, DELIVERY_ATTEMPTS);
+    }
+}
+
+fn webhook_payload(contract: &str, event: &Event) -> serde_json::Value {
+    match event {
+        Event::ContractEventMatched {
+            block_height,
+            block_hash,
+            tx_hash,
+            log_index,
+            address,
+            topics,
+            data,
+        } => serde_json::json!({
+            "type": "contract_event",
+            "contract": contract,
+            "address": format!("0x{}", address),
+            "block_height": block_height,
+            "block_hash": block_hash,
+            "tx_hash": format!("0x{}", tx_hash),
+            "log_index": log_index,
+            "topics": topics.iter().map(|t| format!("0x{}", hex::encode(t))).collect::<Vec<_>>(),
+            "data": format!("0x{}", hex::encode(data)),
+            "finalized": true,
+        }),
+        other => serde_json::to_value(other).unwrap_or_default(),
+    }
+}
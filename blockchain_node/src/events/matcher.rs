@@ -0,0 +1,184 @@
+//! Contract Event Matcher
+//! Address/topic filters indexed by contract address, so the logs of an
+//! imported block are scanned once no matter how many subscribers filter them.
+
+use super::bus::SubscriberId;
+use super::Event;
+use crate::ledger::transaction::TransactionReceipt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Lowercase hex without the `0x` prefix
+pub fn normalize_address(address: &str) -> String {
+    let trimmed = address.trim();
+    trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed)
+        .to_ascii_lowercase()
+}
+
+/// Logs emitted by `address` whose topics match positionally. A `None`
+/// position matches any topic, as in `eth_getLogs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractFilter {
+    pub address: String,
+    pub topics: Vec<Option<Vec<u8>>>,
+}
+
+impl ContractFilter {
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: normalize_address(address),
+            topics: Vec::new(),
+        }
+    }
+
+    /// Require `topic` at `position`
+    pub fn with_topic(mut self, position: usize, topic: Vec<u8>) -> Self {
+        if self.topics.len() <= position {
+            self.topics.resize(position + 1, None);
+        }
+        self.topics[position] = Some(topic);
+        self
+    }
+
+    pub fn matches_topics(&self, topics: &[Vec<u8>]) -> bool {
+        self.topics.iter().enumerate().all(|(i, wanted)| match wanted {
+            Some(wanted) => topics.get(i) == Some(wanted),
+            None => true,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ContractMatcher {
+    by_address: HashMap<String, Vec<(SubscriberId, ContractFilter)>>,
+}
+
+impl ContractMatcher {
+    pub fn add(&mut self, subscriber: SubscriberId, filter: ContractFilter) {
+        self.by_address
+            .entry(filter.address.clone())
+            .or_default()
+            .push((subscriber, filter));
+    }
+
+    pub fn remove(&mut self, subscriber: SubscriberId) {
+        self.by_address.retain(|_, filters| {
+            filters.retain(|(id, _)| *id != subscriber);
+            !filters.is_empty()
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_address.is_empty()
+    }
+
+    /// One `ContractEventMatched` per matching log with the subscribers it
+    /// goes to. Logs of failed transactions were reverted and never match.
+    pub fn match_receipts(
+        &self,
+        block_height: u64,
+        block_hash: &str,
+        receipts: &[TransactionReceipt],
+    ) -> Vec<(Arc<Event>, Vec<SubscriberId>)> {
+        let mut matched = Vec::new();
+        if self.is_empty() {
+            return matched;
+        }
+
+        for receipt in receipts.iter().filter(|r| r.status == 0) {
+            for (log_index, log) in receipt.logs.iter().enumerate() {
+                let address = normalize_address(&log.address);
+                let Some(filters) = self.by_address.get(&address) else { continue };
+
+                let mut recipients: Vec<SubscriberId> = filters
+                    .iter()
+                    .filter(|(_, filter)| filter.matches_topics(&log.topics))
+                    .map(|(id, _)| *id)
+                    .collect();
+                if recipients.is_empty() {
+                    continue;
+                }
+                recipients.sort_unstable();
+                recipients.dedup();
+
+                let event = Event::ContractEventMatched {
+                    block_height,
+                    block_hash: block_hash.to_string(),
+                    tx_hash: receipt.tx_hash.to_hex(),
+                    log_index: log_index as u32,
+                    address,
+                    topics: log.topics.clone(),
+                    data: log.data.clone(),
+                };
+                matched.push((Arc::new(event), recipients));
+            }
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::transaction::TransactionLog;
+    use crate::types::TransactionHash;
+
+    fn receipt(status: u32, logs: Vec<TransactionLog>) -> TransactionReceipt {
+        TransactionReceipt {
+            tx_hash: TransactionHash::from(vec![7u8; 32]),
+            block_height: 1,
+            block_timestamp: 0,
+            tx_index: 0,
+            status,
+            gas_used: 21_000,
+            logs,
+        }
+    }
+
+    fn log(address: &str, topics: Vec<Vec<u8>>) -> TransactionLog {
+        TransactionLog {
+            address: address.to_string(),
+            topics,
+            data: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_filters_match_by_address_and_topic_position() {
+        let mut matcher = ContractMatcher::default();
+        matcher.add(1, ContractFilter::new("0xAAaa"));
+        matcher.add(2, ContractFilter::new("aaaa").with_topic(0, vec![9]));
+        matcher.add(3, ContractFilter::new("0xaaaa").with_topic(1, vec![5]));
+        matcher.add(4, ContractFilter::new("0xbbbb"));
+
+        let receipts = vec![receipt(0, vec![log("0xAAAA", vec![vec![9], vec![4]])])];
+        let matched = matcher.match_receipts(1, "h1", &receipts);
+
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].1, vec![1, 2]);
+        match matched[0].0.as_ref() {
+            Event::ContractEventMatched { address, log_index, .. } => {
+                assert_eq!(address, "aaaa");
+                assert_eq!(*log_index, 0);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reverted_logs_and_removed_subscribers_do_not_match() {
+        let mut matcher = ContractMatcher::default();
+        matcher.add(1, ContractFilter::new("0xaaaa"));
+
+        let reverted = vec![receipt(1, vec![log("0xaaaa", vec![])])];
+        assert!(matcher.match_receipts(1, "h1", &reverted).is_empty());
+
+        matcher.remove(1);
+        assert!(matcher.is_empty());
+        let ok = vec![receipt(0, vec![log("0xaaaa", vec![])])];
+        assert!(matcher.match_receipts(1, "h1", &ok).is_empty());
+    }
+}
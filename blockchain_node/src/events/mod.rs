@@ -0,0 +1,67 @@
+//! Internal Event Bus
+//! Typed events published by consensus and state, fanned out to in-process
+//! subscribers over bounded queues. Contract log filters are evaluated once
+//! per imported block, and matches are routed only to the subscribers whose
+//! filters they satisfy.
+
+pub mod bus;
+pub mod forwarder;
+pub mod matcher;
+
+pub use bus::{Backpressure, BusStats, EventBus, SubscribeOptions, SubscriberId, Subscription};
+pub use forwarder::{ForwardRoute, ForwarderConfig, WebhookForwarder};
+pub use matcher::{ContractFilter, ContractMatcher};
+
+use crate::ledger::transaction::TransactionReceipt;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A block was added to the canonical chain
+    BlockImported {
+        height: u64,
+        hash: String,
+        receipts: Vec<TransactionReceipt>,
+    },
+    /// Consensus finalized the block at `height`
+    BlockFinalized { height: u64, hash: String },
+    /// A log of an imported block matched a subscriber's contract filter
+    ContractEventMatched {
+        block_height: u64,
+        block_hash: String,
+        tx_hash: String,
+        log_index: u32,
+        address: String,
+        topics: Vec<Vec<u8>>,
+        data: Vec<u8>,
+    },
+    BalanceChanged { address: String, old: u64, new: u64 },
+    ValidatorSlashed {
+        validator: String,
+        amount: u64,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    BlockImported,
+    BlockFinalized,
+    ContractEventMatched,
+    BalanceChanged,
+    ValidatorSlashed,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::BlockImported { .. } => EventKind::BlockImported,
+            Event::BlockFinalized { .. } => EventKind::BlockFinalized,
+            Event::ContractEventMatched { .. } => EventKind::ContractEventMatched,
+            Event::BalanceChanged { .. } => EventKind::BalanceChanged,
+            Event::ValidatorSlashed { .. } => EventKind::ValidatorSlashed,
+        }
+    }
+}
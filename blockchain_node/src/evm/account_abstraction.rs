@@ -0,0 +1,1270 @@
+//! Account Abstraction
+//! Native sponsored user operations, so AI job payments don't need gas-holding EOAs.
+//! An abstracted account is controlled by an owner key that grants scoped
+//! session keys. A user operation signed by a session key is validated
+//! against its grant and a paymaster's policy, then executed with gas
+//! charged to the paymaster's deposit instead of the account.
+//!
+//! Abstracted-account nonces live here, apart from the EVM account nonces
+//! that EOA transactions use. See docs/ACCOUNT_ABSTRACTION.md for the wire
+//! format and hashing.
+//!
+//! Nothing here changes on submission alone. Registrations, session grants,
+//! paymaster policies and user operations are checked on admission, then
+//! carried to `ENTRY_POINT` by chain transactions and applied when a block
+//! including them is applied, so every node reaches the same state.
+
+use crate::evm::executor::EvmExecutor;
+use crate::evm::types::{EvmAddress, EvmError, EvmExecutionResult, EvmTransaction};
+use crate::ledger::block::Transaction as BlockTransaction;
+use crate::ledger::state::State;
+use crate::transaction::Mempool;
+use crate::types::{Address, Transaction};
+use async_trait::async_trait;
+use ethereum_types::{H256, U256};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Address user operations are bound to, reported by `eth_supportedEntryPoints`
+pub const ENTRY_POINT: EvmAddress = EvmAddress::repeat_byte(0xaa);
+
+/// Gas charged per operation for validation, on top of the call's own gas
+pub const VERIFICATION_GAS: u64 = 35_000;
+
+/// A call made on behalf of an abstracted account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    /// Abstracted account the call is made from
+    pub sender: EvmAddress,
+    /// Account nonce, separate from EOA nonces
+    pub nonce: u64,
+    pub target: EvmAddress,
+    #[serde(default)]
+    pub value: U256,
+    #[serde(with = "hex_bytes", default)]
+    pub call_data: Vec<u8>,
+    pub call_gas_limit: u64,
+    pub max_fee_per_gas: U256,
+    /// Pays for gas; the account's own balance is never touched for it
+    pub paymaster: EvmAddress,
+    /// Session key that signed the operation
+    pub session_key: EvmAddress,
+    /// 65-byte r || s || v signature of `hash` by the session key
+    #[serde(with = "hex_bytes", default)]
+    pub signature: Vec<u8>,
+}
+
+/// Scope the account owner grants a session key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionGrant {
+    pub account: EvmAddress,
+    pub session_key: EvmAddress,
+    /// Contracts the key may call, e.g. AIJobManager only
+    pub allowed_targets: Vec<EvmAddress>,
+    /// Largest `value` one operation may carry
+    pub max_value: U256,
+    /// Unix seconds after which the key is rejected
+    pub valid_until: u64,
+    /// Signature of `hash` by the account owner
+    #[serde(with = "hex_bytes", default)]
+    pub signature: Vec<u8>,
+}
+
+/// What a paymaster agrees to sponsor
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymasterPolicy {
+    /// Empty sponsors any target
+    #[serde(default)]
+    pub allowed_targets: Vec<EvmAddress>,
+    /// Empty sponsors any account
+    #[serde(default)]
+    pub allowed_accounts: Vec<EvmAddress>,
+    /// Largest call gas limit sponsored per operation; 0 means no limit
+    #[serde(default)]
+    pub max_gas_per_op: u64,
+}
+
+/// Policy signed by the paymaster. `nonce` orders updates, so an older
+/// policy can't be replayed over a newer one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyUpdate {
+    pub paymaster: EvmAddress,
+    pub policy: PaymasterPolicy,
+    pub nonce: u64,
+    /// Signature of `hash` by the paymaster
+    #[serde(with = "hex_bytes", default)]
+    pub signature: Vec<u8>,
+}
+
+/// Owner-signed request for a new abstracted account. The account address
+/// is derived from the owner and salt, so nobody can claim an address whose
+/// balance they don't control.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRegistration {
+    pub owner: EvmAddress,
+    #[serde(default)]
+    pub salt: H256,
+    /// Signature of `hash` by the owner
+    #[serde(with = "hex_bytes", default)]
+    pub signature: Vec<u8>,
+}
+
+/// Call carried to `ENTRY_POINT` by a chain transaction, JSON-encoded in its
+/// data. Each call is authorized by its own signature, except deposits,
+/// which move the carrying transaction's value from its sender.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "camelCase")]
+pub enum EntryPointCall {
+    RegisterAccount(AccountRegistration),
+    AddSession(SessionGrant),
+    SetPaymasterPolicy(PolicyUpdate),
+    DepositTo { paymaster: EvmAddress },
+    HandleOp(UserOperation),
+}
+
+#[derive(Clone, Debug, Default)]
+struct Paymaster {
+    deposit: U256,
+    policy: PaymasterPolicy,
+    /// Nonce the next policy update must carry
+    policy_nonce: u64,
+}
+
+#[derive(Clone, Debug)]
+struct AbstractAccount {
+    owner: EvmAddress,
+    nonce: u64,
+    sessions: HashMap<EvmAddress, SessionGrant>,
+}
+
+#[derive(Default)]
+struct AaState {
+    accounts: HashMap<EvmAddress, AbstractAccount>,
+    paymasters: HashMap<EvmAddress, Paymaster>,
+    /// Nonce after the last operation this node admitted, per account
+    admitted: HashMap<EvmAddress, u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserOpStatus {
+    Pending,
+    Included,
+    Failed,
+}
+
+/// Outcome of a submitted operation, looked up by its hash
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOpReceipt {
+    pub user_op_hash: H256,
+    pub sender: EvmAddress,
+    pub nonce: u64,
+    pub paymaster: EvmAddress,
+    pub status: UserOpStatus,
+    pub actual_gas_used: u64,
+    pub actual_gas_cost: U256,
+    #[serde(with = "hex_bytes")]
+    pub return_data: Vec<u8>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOpGasEstimate {
+    pub verification_gas_limit: u64,
+    pub call_gas_limit: u64,
+    /// Most the paymaster is charged at the operation's fee cap
+    pub max_cost: U256,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AaError {
+    #[error("Unknown account: {0:?}")]
+    UnknownAccount(EvmAddress),
+    #[error("Account already registered: {0:?}")]
+    AccountExists(EvmAddress),
+    #[error("Invalid nonce: expected {expected}, got {got}")]
+    InvalidNonce { expected: u64, got: u64 },
+    #[error("Session key {0:?} not granted")]
+    UnknownSessionKey(EvmAddress),
+    #[error("Session key expired at {0}")]
+    SessionExpired(u64),
+    #[error("Target {0:?} outside session scope")]
+    TargetNotAllowed(EvmAddress),
+    #[error("Value {value} exceeds session limit {max}")]
+    ValueExceedsScope { value: U256, max: U256 },
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("Unknown paymaster: {0:?}")]
+    UnknownPaymaster(EvmAddress),
+    #[error("Paymaster policy rejected operation: {0}")]
+    PaymasterPolicy(String),
+    #[error("Paymaster deposit {available} below required {required}")]
+    InsufficientDeposit { required: U256, available: U256 },
+    #[error("Balance {available} below deposit {required}")]
+    InsufficientBalance { required: u64, available: u64 },
+    #[error("Invalid EntryPoint call: {0}")]
+    InvalidCall(String),
+    #[error("Submission failed: {0}")]
+    Submission(String),
+    #[error("Execution error: {0}")]
+    Execution(String),
+}
+
+/// Runs the call of a validated operation
+#[async_trait]
+pub trait UserOpExecutor: Send + Sync {
+    async fn execute(&self, tx: EvmTransaction) -> Result<EvmExecutionResult, EvmError>;
+}
+
+#[async_trait]
+impl UserOpExecutor for EvmExecutor {
+    async fn execute(&self, tx: EvmTransaction) -> Result<EvmExecutionResult, EvmError> {
+        self.execute_transaction_sync(tx).await
+    }
+}
+
+/// Orders admitted EntryPoint calls into blocks
+#[async_trait]
+pub trait EntryPointSink: Send + Sync {
+    async fn submit(&self, tx: Transaction) -> Result<(), AaError>;
+}
+
+#[async_trait]
+impl EntryPointSink for RwLock<Mempool> {
+    async fn submit(&self, tx: Transaction) -> Result<(), AaError> {
+        self.read()
+            .await
+            .add_transaction(tx)
+            .await
+            .map(|_| ())
+            .map_err(|e| AaError::Submission(e.to_string()))
+    }
+}
+
+fn word_address(out: &mut Vec<u8>, address: &EvmAddress) {
+    out.extend_from_slice(&[0u8; 12]);
+    out.extend_from_slice(address.as_bytes());
+}
+
+fn word_u256(out: &mut Vec<u8>, value: U256) {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    out.extend_from_slice(&word);
+}
+
+fn keccak(data: &[u8]) -> H256 {
+    H256::from_slice(&Keccak256::digest(data))
+}
+
+impl UserOperation {
+    /// keccak256 of the ABI-encoded fields, bound to the entry point and chain
+    pub fn hash(&self, chain_id: u64) -> H256 {
+        let mut data = Vec::with_capacity(32 * 11);
+        word_address(&mut data, &ENTRY_POINT);
+        word_u256(&mut data, chain_id.into());
+        word_address(&mut data, &self.sender);
+        word_u256(&mut data, self.nonce.into());
+        word_address(&mut data, &self.target);
+        word_u256(&mut data, self.value);
+        data.extend_from_slice(keccak(&self.call_data).as_bytes());
+        word_u256(&mut data, self.call_gas_limit.into());
+        word_u256(&mut data, self.max_fee_per_gas);
+        word_address(&mut data, &self.paymaster);
+        word_address(&mut data, &self.session_key);
+        keccak(&data)
+    }
+
+    /// Most the paymaster can be charged for this operation
+    pub fn max_cost(&self) -> U256 {
+        U256::from(self.call_gas_limit + VERIFICATION_GAS) * self.max_fee_per_gas
+    }
+}
+
+impl SessionGrant {
+    pub fn hash(&self, chain_id: u64) -> H256 {
+        let mut targets = Vec::with_capacity(32 * self.allowed_targets.len());
+        for target in &self.allowed_targets {
+            word_address(&mut targets, target);
+        }
+        let mut data = Vec::with_capacity(32 * 7);
+        word_address(&mut data, &ENTRY_POINT);
+        word_u256(&mut data, chain_id.into());
+        word_address(&mut data, &self.account);
+        word_address(&mut data, &self.session_key);
+        data.extend_from_slice(keccak(&targets).as_bytes());
+        word_u256(&mut data, self.max_value);
+        word_u256(&mut data, self.valid_until.into());
+        keccak(&data)
+    }
+}
+
+impl PolicyUpdate {
+    pub fn hash(&self, chain_id: u64) -> H256 {
+        let mut targets = Vec::with_capacity(32 * self.policy.allowed_targets.len());
+        for target in &self.policy.allowed_targets {
+            word_address(&mut targets, target);
+        }
+        let mut accounts = Vec::with_capacity(32 * self.policy.allowed_accounts.len());
+        for account in &self.policy.allowed_accounts {
+            word_address(&mut accounts, account);
+        }
+        let mut data = Vec::with_capacity(32 * 7);
+        word_address(&mut data, &ENTRY_POINT);
+        word_u256(&mut data, chain_id.into());
+        word_address(&mut data, &self.paymaster);
+        word_u256(&mut data, self.nonce.into());
+        data.extend_from_slice(keccak(&targets).as_bytes());
+        data.extend_from_slice(keccak(&accounts).as_bytes());
+        word_u256(&mut data, self.policy.max_gas_per_op.into());
+        keccak(&data)
+    }
+}
+
+impl AccountRegistration {
+    pub fn hash(&self, chain_id: u64) -> H256 {
+        let mut data = Vec::with_capacity(32 * 4);
+        word_address(&mut data, &ENTRY_POINT);
+        word_u256(&mut data, chain_id.into());
+        word_address(&mut data, &self.owner);
+        data.extend_from_slice(self.salt.as_bytes());
+        keccak(&data)
+    }
+
+    /// Address of the account this registration creates
+    pub fn account(&self) -> EvmAddress {
+        let mut data = Vec::with_capacity(32 * 2);
+        word_address(&mut data, &self.owner);
+        data.extend_from_slice(self.salt.as_bytes());
+        EvmAddress::from_slice(&keccak(&data).as_bytes()[12..])
+    }
+}
+
+impl EntryPointCall {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, AaError> {
+        serde_json::from_slice(data).map_err(|e| AaError::InvalidCall(e.to_string()))
+    }
+}
+
+/// Address that produced a 65-byte r || s || v signature over `hash`
+pub fn recover_signer(hash: &H256, signature: &[u8]) -> Result<EvmAddress, AaError> {
+    if signature.len() != 65 {
+        return Err(AaError::InvalidSignature(format!("expected 65 bytes, got {}", signature.len())));
+    }
+    let sig = Signature::from_slice(&signature[..64]).map_err(|e| AaError::InvalidSignature(e.to_string()))?;
+    let v = signature[64];
+    let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })
+        .ok_or_else(|| AaError::InvalidSignature(format!("bad recovery id {}", v)))?;
+    let key = VerifyingKey::recover_from_prehash(hash.as_bytes(), &sig, recovery_id)
+        .map_err(|e| AaError::InvalidSignature(e.to_string()))?;
+    Ok(address_of(&key))
+}
+
+/// Ethereum address of a secp256k1 public key
+pub fn address_of(key: &VerifyingKey) -> EvmAddress {
+    let point = key.to_encoded_point(false);
+    EvmAddress::from_slice(&Keccak256::digest(&point.as_bytes()[1..])[12..])
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Validates, sponsors and executes user operations
+pub struct AccountAbstraction {
+    chain_id: u64,
+    executor: Arc<dyn UserOpExecutor>,
+    /// Consensus path admitted calls are handed to
+    sink: Option<Arc<dyn EntryPointSink>>,
+    state: RwLock<AaState>,
+    receipts: RwLock<HashMap<H256, UserOpReceipt>>,
+}
+
+fn ledger_error(e: anyhow::Error) -> AaError {
+    AaError::Execution(e.to_string())
+}
+
+impl UserOpReceipt {
+    fn pending(user_op_hash: H256, op: &UserOperation) -> Self {
+        Self {
+            user_op_hash,
+            sender: op.sender,
+            nonce: op.nonce,
+            paymaster: op.paymaster,
+            status: UserOpStatus::Pending,
+            actual_gas_used: 0,
+            actual_gas_cost: U256::zero(),
+            return_data: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+impl AccountAbstraction {
+    pub fn new(chain_id: u64, executor: Arc<dyn UserOpExecutor>) -> Self {
+        Self {
+            chain_id,
+            executor,
+            sink: None,
+            state: RwLock::new(AaState::default()),
+            receipts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Hand admitted calls to `sink`, normally the node's mempool
+    pub fn with_sink(mut self, sink: Arc<dyn EntryPointSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Register an abstracted account controlled by `owner`
+    async fn register_account(&self, account: EvmAddress, owner: EvmAddress) -> Result<(), AaError> {
+        let mut state = self.state.write().await;
+        if state.accounts.contains_key(&account) {
+            return Err(AaError::AccountExists(account));
+        }
+        state.accounts.insert(
+            account,
+            AbstractAccount {
+                owner,
+                nonce: 0,
+                sessions: HashMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Store a session grant signed by the account owner
+    async fn add_session_grant(&self, grant: SessionGrant) -> Result<(), AaError> {
+        let mut state = self.state.write().await;
+        self.check_grant(&state, &grant)?;
+        if let Some(account) = state.accounts.get_mut(&grant.account) {
+            account.sessions.insert(grant.session_key, grant);
+        }
+        Ok(())
+    }
+
+    pub async fn revoke_session(&self, account: EvmAddress, session_key: EvmAddress) -> Result<(), AaError> {
+        let mut state = self.state.write().await;
+        let account = state.accounts.get_mut(&account).ok_or(AaError::UnknownAccount(account))?;
+        account
+            .sessions
+            .remove(&session_key)
+            .map(|_| ())
+            .ok_or(AaError::UnknownSessionKey(session_key))
+    }
+
+    /// Credit a paymaster's gas deposit
+    async fn deposit(&self, paymaster: EvmAddress, amount: U256) -> U256 {
+        let mut state = self.state.write().await;
+        let entry = state.paymasters.entry(paymaster).or_default();
+        entry.deposit = entry.deposit.saturating_add(amount);
+        entry.deposit
+    }
+
+    /// Replace a paymaster's policy; the next update has to carry the following nonce
+    async fn set_paymaster_policy(&self, paymaster: EvmAddress, policy: PaymasterPolicy) {
+        let mut state = self.state.write().await;
+        let entry = state.paymasters.entry(paymaster).or_default();
+        entry.policy = policy;
+        entry.policy_nonce += 1;
+    }
+
+    pub async fn paymaster_deposit(&self, paymaster: EvmAddress) -> U256 {
+        self.state
+            .read()
+            .await
+            .paymasters
+            .get(&paymaster)
+            .map(|p| p.deposit)
+            .unwrap_or_default()
+    }
+
+    /// Next nonce of an abstracted account
+    pub async fn nonce(&self, account: EvmAddress) -> Result<u64, AaError> {
+        self.state
+            .read()
+            .await
+            .accounts
+            .get(&account)
+            .map(|a| a.nonce)
+            .ok_or(AaError::UnknownAccount(account))
+    }
+
+    /// Nonce the account's next operation should carry, counting operations
+    /// admitted here that no block has included yet
+    pub async fn next_nonce(&self, account: EvmAddress) -> Result<u64, AaError> {
+        Self::next_nonce_locked(&*self.state.read().await, account)
+    }
+
+    fn next_nonce_locked(state: &AaState, account: EvmAddress) -> Result<u64, AaError> {
+        let nonce = state
+            .accounts
+            .get(&account)
+            .map(|a| a.nonce)
+            .ok_or(AaError::UnknownAccount(account))?;
+        Ok(state.admitted.get(&account).map_or(nonce, |admitted| nonce.max(*admitted)))
+    }
+
+    pub async fn receipt(&self, user_op_hash: &H256) -> Option<UserOpReceipt> {
+        self.receipts.read().await.get(user_op_hash).cloned()
+    }
+
+    fn check_registration(&self, state: &AaState, registration: &AccountRegistration) -> Result<EvmAddress, AaError> {
+        let signer = recover_signer(&registration.hash(self.chain_id), &registration.signature)?;
+        if signer != registration.owner {
+            return Err(AaError::InvalidSignature("registration not signed by owner".to_string()));
+        }
+        let account = registration.account();
+        if state.accounts.contains_key(&account) {
+            return Err(AaError::AccountExists(account));
+        }
+        Ok(account)
+    }
+
+    fn check_grant(&self, state: &AaState, grant: &SessionGrant) -> Result<(), AaError> {
+        let signer = recover_signer(&grant.hash(self.chain_id), &grant.signature)?;
+        let account = state
+            .accounts
+            .get(&grant.account)
+            .ok_or(AaError::UnknownAccount(grant.account))?;
+        if signer != account.owner {
+            return Err(AaError::InvalidSignature("grant not signed by account owner".to_string()));
+        }
+        Ok(())
+    }
+
+    fn check_policy_update(&self, state: &AaState, update: &PolicyUpdate) -> Result<(), AaError> {
+        let signer = recover_signer(&update.hash(self.chain_id), &update.signature)?;
+        if signer != update.paymaster {
+            return Err(AaError::InvalidSignature("policy not signed by paymaster".to_string()));
+        }
+        let expected = state
+            .paymasters
+            .get(&update.paymaster)
+            .map(|p| p.policy_nonce)
+            .unwrap_or_default();
+        if update.nonce != expected {
+            return Err(AaError::InvalidNonce {
+                expected,
+                got: update.nonce,
+            });
+        }
+        Ok(())
+    }
+
+    /// Session scope and paymaster checks, without nonce or signature
+    fn check_scope(state: &AaState, op: &UserOperation, now: u64) -> Result<(), AaError> {
+        let account = state.accounts.get(&op.sender).ok_or(AaError::UnknownAccount(op.sender))?;
+        let grant = account
+            .sessions
+            .get(&op.session_key)
+            .ok_or(AaError::UnknownSessionKey(op.session_key))?;
+        if now > grant.valid_until {
+            return Err(AaError::SessionExpired(grant.valid_until));
+        }
+        if !grant.allowed_targets.contains(&op.target) {
+            return Err(AaError::TargetNotAllowed(op.target));
+        }
+        if op.value > grant.max_value {
+            return Err(AaError::ValueExceedsScope {
+                value: op.value,
+                max: grant.max_value,
+            });
+        }
+
+        let paymaster = state
+            .paymasters
+            .get(&op.paymaster)
+            .ok_or(AaError::UnknownPaymaster(op.paymaster))?;
+        let policy = &paymaster.policy;
+        if !policy.allowed_targets.is_empty() && !policy.allowed_targets.contains(&op.target) {
+            return Err(AaError::PaymasterPolicy(format!("target {:?} not sponsored", op.target)));
+        }
+        if !policy.allowed_accounts.is_empty() && !policy.allowed_accounts.contains(&op.sender) {
+            return Err(AaError::PaymasterPolicy(format!("account {:?} not sponsored", op.sender)));
+        }
+        if policy.max_gas_per_op > 0 && op.call_gas_limit > policy.max_gas_per_op {
+            return Err(AaError::PaymasterPolicy(format!(
+                "call gas {} above limit {}",
+                op.call_gas_limit, policy.max_gas_per_op
+            )));
+        }
+        let required = op.max_cost();
+        if paymaster.deposit < required {
+            return Err(AaError::InsufficientDeposit {
+                required,
+                available: paymaster.deposit,
+            });
+        }
+        Ok(())
+    }
+
+    fn evm_transaction(&self, op: &UserOperation) -> EvmTransaction {
+        EvmTransaction {
+            from: op.sender,
+            to: Some(op.target),
+            value: op.value,
+            data: op.call_data.clone(),
+            // Gas is settled against the paymaster deposit, not the sender's balance
+            gas_price: U256::zero(),
+            gas_limit: U256::from(op.call_gas_limit),
+            nonce: U256::from(op.nonce),
+            chain_id: Some(self.chain_id),
+            signature: None,
+        }
+    }
+
+    fn check_signature(&self, op: &UserOperation) -> Result<H256, AaError> {
+        let hash = op.hash(self.chain_id);
+        let signer = recover_signer(&hash, &op.signature)?;
+        if signer != op.session_key {
+            return Err(AaError::InvalidSignature("not signed by session key".to_string()));
+        }
+        Ok(hash)
+    }
+
+    /// Full validation of `op` at `now`: scope, paymaster, nonce and signature
+    pub async fn validate(&self, op: &UserOperation, now: u64) -> Result<H256, AaError> {
+        let state = self.state.read().await;
+        self.validate_locked(&state, op, now)
+    }
+
+    fn validate_locked(&self, state: &AaState, op: &UserOperation, now: u64) -> Result<H256, AaError> {
+        Self::check_scope(state, op, now)?;
+        let expected = state.accounts[&op.sender].nonce;
+        if op.nonce != expected {
+            return Err(AaError::InvalidNonce { expected, got: op.nonce });
+        }
+        self.check_signature(op)
+    }
+
+    /// Checks `call` must pass at `now` before it is queued. Operations may
+    /// run ahead of the account nonce by those already admitted, or resubmit
+    /// one of them.
+    fn admit_locked(&self, state: &AaState, call: &EntryPointCall, now: u64) -> Result<H256, AaError> {
+        match call {
+            EntryPointCall::RegisterAccount(registration) => {
+                self.check_registration(state, registration)?;
+                Ok(registration.hash(self.chain_id))
+            }
+            EntryPointCall::AddSession(grant) => {
+                self.check_grant(state, grant)?;
+                Ok(grant.hash(self.chain_id))
+            }
+            EntryPointCall::SetPaymasterPolicy(update) => {
+                self.check_policy_update(state, update)?;
+                Ok(update.hash(self.chain_id))
+            }
+            EntryPointCall::DepositTo { .. } => Err(AaError::InvalidCall(
+                "deposits are value transfers to the EntryPoint sent by the funder".to_string(),
+            )),
+            EntryPointCall::HandleOp(op) => {
+                Self::check_scope(state, op, now)?;
+                let included = state.accounts[&op.sender].nonce;
+                let next = Self::next_nonce_locked(state, op.sender)?;
+                if op.nonce < included || op.nonce > next {
+                    return Err(AaError::InvalidNonce {
+                        expected: next,
+                        got: op.nonce,
+                    });
+                }
+                self.check_signature(op)
+            }
+        }
+    }
+
+    pub async fn enqueue(&self, call: EntryPointCall) -> Result<H256, AaError> {
+        self.enqueue_at(call, unix_now()).await
+    }
+
+    /// Admit `call` at `now` and hand it to consensus in a transaction to
+    /// `ENTRY_POINT`. Nothing changes until a block including it is applied.
+    /// Returns the operation hash, or the signed hash of other calls.
+    pub async fn enqueue_at(&self, call: EntryPointCall, now: u64) -> Result<H256, AaError> {
+        let sink = self
+            .sink
+            .clone()
+            .ok_or_else(|| AaError::Submission("no consensus path configured".to_string()))?;
+        let mut state = self.state.write().await;
+        let hash = self.admit_locked(&state, &call, now)?;
+
+        let (from, nonce) = match &call {
+            EntryPointCall::RegisterAccount(registration) => (registration.owner, 0),
+            EntryPointCall::AddSession(grant) => (grant.account, 0),
+            EntryPointCall::SetPaymasterPolicy(update) => (update.paymaster, update.nonce),
+            EntryPointCall::DepositTo { paymaster } => (*paymaster, 0),
+            EntryPointCall::HandleOp(op) => (op.sender, op.nonce),
+        };
+        let tx = Transaction::new(Address::new(from.0), Address::new(ENTRY_POINT.0), 0, call.encode(), nonce, 0, 0);
+        sink.submit(tx).await?;
+
+        if let EntryPointCall::HandleOp(op) = &call {
+            let admitted = state.admitted.entry(op.sender).or_default();
+            *admitted = (*admitted).max(op.nonce + 1);
+            drop(state);
+            self.receipts.write().await.insert(hash, UserOpReceipt::pending(hash, op));
+        }
+        Ok(hash)
+    }
+
+    /// Apply the EntryPoint calls of a block in block order, at the block's
+    /// timestamp. A call that fails is skipped, like a reverted transaction.
+    pub async fn apply_block(&self, transactions: &[BlockTransaction], timestamp: u64, ledger: &State) {
+        for tx in transactions.iter().filter(|tx| tx.to.as_slice() == ENTRY_POINT.as_bytes()) {
+            let applied = match EntryPointCall::decode(&tx.data) {
+                Ok(call) => self.apply_call(call, &tx.from, tx.amount, timestamp, ledger).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = applied {
+                warn!("EntryPoint call {} not applied: {}", tx.id.to_hex(), e);
+            }
+        }
+    }
+
+    async fn apply_call(
+        &self,
+        call: EntryPointCall,
+        from: &[u8],
+        value: u64,
+        now: u64,
+        ledger: &State,
+    ) -> Result<(), AaError> {
+        match call {
+            EntryPointCall::RegisterAccount(registration) => {
+                let account = self.check_registration(&*self.state.read().await, &registration)?;
+                self.register_account(account, registration.owner).await
+            }
+            EntryPointCall::AddSession(grant) => self.add_session_grant(grant).await,
+            EntryPointCall::SetPaymasterPolicy(update) => {
+                self.check_policy_update(&*self.state.read().await, &update)?;
+                self.set_paymaster_policy(update.paymaster, update.policy).await;
+                Ok(())
+            }
+            EntryPointCall::DepositTo { paymaster } => {
+                // The EntryPoint holds deposited funds; gas charged against them is burned
+                let funder = format!("0x{}", hex::encode(from));
+                let available = ledger.get_balance(&funder).map_err(ledger_error)?;
+                if available < value {
+                    return Err(AaError::InsufficientBalance {
+                        required: value,
+                        available,
+                    });
+                }
+                let held = format!("0x{}", hex::encode(ENTRY_POINT.as_bytes()));
+                let held_balance = ledger.get_balance(&held).map_err(ledger_error)?;
+                ledger.set_balance(&funder, available - value).map_err(ledger_error)?;
+                ledger
+                    .set_balance(&held, held_balance.saturating_add(value))
+                    .map_err(ledger_error)?;
+                self.deposit(paymaster, U256::from(value)).await;
+                Ok(())
+            }
+            EntryPointCall::HandleOp(op) => {
+                let hash = op.hash(self.chain_id);
+                match self.handle_op(op, now).await {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        // Admitted here, but no longer valid by the time it was included
+                        if let Some(receipt) = self.receipts.write().await.get_mut(&hash) {
+                            if receipt.status == UserOpStatus::Pending {
+                                receipt.status = UserOpStatus::Failed;
+                                receipt.error = Some(e.to_string());
+                            }
+                        }
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Validate an included `op` at block time `now`, reserve its nonce and
+    /// maximum cost, execute it and charge the paymaster for the gas used
+    async fn handle_op(&self, op: UserOperation, now: u64) -> Result<UserOpReceipt, AaError> {
+        let max_cost = op.max_cost();
+        let hash = {
+            let mut state = self.state.write().await;
+            let hash = self.validate_locked(&state, &op, now)?;
+            if let Some(account) = state.accounts.get_mut(&op.sender) {
+                account.nonce += 1;
+            }
+            if let Some(paymaster) = state.paymasters.get_mut(&op.paymaster) {
+                paymaster.deposit -= max_cost;
+            }
+            hash
+        };
+
+        let mut receipt = UserOpReceipt::pending(hash, &op);
+        self.receipts.write().await.insert(hash, receipt.clone());
+
+        // A failed call still pays for validation and whatever gas it burned
+        let call_gas = match self.executor.execute(self.evm_transaction(&op)).await {
+            Ok(result) => {
+                receipt.status = if result.success { UserOpStatus::Included } else { UserOpStatus::Failed };
+                receipt.return_data = result.return_data;
+                receipt.error = result.error;
+                result.gas_used.min(op.call_gas_limit)
+            }
+            Err(e) => {
+                receipt.status = UserOpStatus::Failed;
+                receipt.error = Some(e.to_string());
+                0
+            }
+        };
+        receipt.actual_gas_used = call_gas + VERIFICATION_GAS;
+        receipt.actual_gas_cost = U256::from(receipt.actual_gas_used) * op.max_fee_per_gas;
+
+        if let Some(paymaster) = self.state.write().await.paymasters.get_mut(&op.paymaster) {
+            paymaster.deposit += max_cost - receipt.actual_gas_cost;
+        }
+        self.receipts.write().await.insert(hash, receipt.clone());
+        Ok(receipt)
+    }
+
+    pub async fn estimate(&self, op: &UserOperation) -> Result<UserOpGasEstimate, AaError> {
+        self.estimate_at(op, unix_now()).await
+    }
+
+    /// Gas limits for `op`, simulated without its signature or nonce being
+    /// checked so callers can estimate before signing
+    pub async fn estimate_at(&self, op: &UserOperation, now: u64) -> Result<UserOpGasEstimate, AaError> {
+        {
+            let state = self.state.read().await;
+            Self::check_scope(&state, op, now)?;
+        }
+        let result = self
+            .executor
+            .execute(self.evm_transaction(op))
+            .await
+            .map_err(|e| AaError::Execution(e.to_string()))?;
+        if !result.success {
+            return Err(AaError::Execution(result.error.unwrap_or_else(|| "call reverted".to_string())));
+        }
+        // Headroom for state that changes between estimate and inclusion
+        let call_gas_limit = result.gas_used + result.gas_used / 5;
+        Ok(UserOpGasEstimate {
+            verification_gas_limit: VERIFICATION_GAS,
+            call_gas_limit,
+            max_cost: U256::from(call_gas_limit + VERIFICATION_GAS) * op.max_fee_per_gas,
+        })
+    }
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s.trim_start_matches("0x")).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use std::sync::Mutex;
+
+    const NOW: u64 = 1_700_000_000;
+
+    /// Records executed calls and burns a fixed amount of gas
+    struct MockExecutor {
+        calls: Mutex<Vec<EvmTransaction>>,
+        gas_used: u64,
+    }
+
+    #[async_trait]
+    impl UserOpExecutor for MockExecutor {
+        async fn execute(&self, tx: EvmTransaction) -> Result<EvmExecutionResult, EvmError> {
+            self.calls.lock().unwrap().push(tx);
+            Ok(EvmExecutionResult {
+                success: true,
+                gas_used: self.gas_used,
+                gas_refunded: 0,
+                return_data: vec![1],
+                contract_address: None,
+                logs: Vec::new(),
+                error: None,
+            })
+        }
+    }
+
+    struct Fixture {
+        aa: AccountAbstraction,
+        executor: Arc<MockExecutor>,
+        account: EvmAddress,
+        session: SigningKey,
+        job_manager: EvmAddress,
+        paymaster: EvmAddress,
+    }
+
+    fn key(byte: u8) -> SigningKey {
+        SigningKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn sign(signer: &SigningKey, hash: &H256) -> Vec<u8> {
+        let (sig, recovery_id) = signer.sign_prehash_recoverable(hash.as_bytes()).unwrap();
+        let mut out = sig.to_bytes().to_vec();
+        out.push(27 + recovery_id.to_byte());
+        out
+    }
+
+    fn signed_grant(owner: &SigningKey, account: EvmAddress, session: &SigningKey, target: EvmAddress, valid_until: u64) -> SessionGrant {
+        let mut grant = SessionGrant {
+            account,
+            session_key: address_of(session.verifying_key()),
+            allowed_targets: vec![target],
+            max_value: U256::from(1_000u64),
+            valid_until,
+            signature: Vec::new(),
+        };
+        grant.signature = sign(owner, &grant.hash(1));
+        grant
+    }
+
+    /// Collects what would have gone to the mempool
+    #[derive(Default)]
+    struct RecordingSink {
+        txs: Mutex<Vec<Transaction>>,
+    }
+
+    #[async_trait]
+    impl EntryPointSink for RecordingSink {
+        async fn submit(&self, tx: Transaction) -> Result<(), AaError> {
+            self.txs.lock().unwrap().push(tx);
+            Ok(())
+        }
+    }
+
+    /// Transactions as a block including them carries them
+    fn included(txs: &[Transaction]) -> Vec<BlockTransaction> {
+        txs.iter()
+            .map(|tx| BlockTransaction {
+                id: crate::types::Hash::new(tx.hash().as_bytes().to_vec()),
+                from: tx.from.0.to_vec(),
+                to: tx.to.0.to_vec(),
+                amount: tx.value,
+                fee: tx.gas_price,
+                data: tx.data.clone(),
+                nonce: tx.nonce,
+                signature: None,
+            })
+            .collect()
+    }
+
+    async fn fixture_with_account(account_byte: u8) -> Fixture {
+        let executor = Arc::new(MockExecutor {
+            calls: Mutex::new(Vec::new()),
+            gas_used: 50_000,
+        });
+        let aa = AccountAbstraction::new(1, executor.clone());
+        let owner = key(account_byte);
+        let session = key(account_byte + 100);
+        let account = EvmAddress::repeat_byte(account_byte);
+        let job_manager = EvmAddress::from_low_u64_be(0x10b);
+        let paymaster = EvmAddress::repeat_byte(0x99);
+
+        aa.register_account(account, address_of(owner.verifying_key())).await.unwrap();
+        aa.add_session_grant(signed_grant(&owner, account, &session, job_manager, NOW + 3600))
+            .await
+            .unwrap();
+        aa.deposit(paymaster, U256::from(10u64).pow(18.into())).await;
+        aa.set_paymaster_policy(
+            paymaster,
+            PaymasterPolicy {
+                allowed_targets: vec![job_manager],
+                allowed_accounts: Vec::new(),
+                max_gas_per_op: 1_000_000,
+            },
+        )
+        .await;
+
+        Fixture {
+            aa,
+            executor,
+            account,
+            session,
+            job_manager,
+            paymaster,
+        }
+    }
+
+    fn op(f: &Fixture, account: EvmAddress, session: &SigningKey, nonce: u64) -> UserOperation {
+        let mut op = UserOperation {
+            sender: account,
+            nonce,
+            target: f.job_manager,
+            value: U256::zero(),
+            // AIJobManager.submitTrain selector
+            call_data: hex::decode("19b04cbe").unwrap(),
+            call_gas_limit: 200_000,
+            max_fee_per_gas: U256::from(1_000_000_000u64),
+            paymaster: f.paymaster,
+            session_key: address_of(session.verifying_key()),
+            signature: Vec::new(),
+        };
+        op.signature = sign(session, &op.hash(1));
+        op
+    }
+
+    #[tokio::test]
+    async fn test_sponsored_job_call_with_zero_balance() {
+        let f = fixture_with_account(1).await;
+        let receipt = f.aa.handle_op(op(&f, f.account, &f.session, 0), NOW).await.unwrap();
+
+        assert_eq!(receipt.status, UserOpStatus::Included);
+        let calls = f.executor.calls.lock().unwrap().clone();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].from, f.account);
+        assert_eq!(calls[0].to, Some(f.job_manager));
+        assert_eq!(calls[0].gas_price, U256::zero());
+        assert_eq!(calls[0].data, hex::decode("19b04cbe").unwrap());
+
+        let looked_up = f.aa.receipt(&receipt.user_op_hash).await.unwrap();
+        assert_eq!(looked_up.status, UserOpStatus::Included);
+        assert_eq!(looked_up.nonce, 0);
+    }
+
+    #[tokio::test]
+    async fn test_session_scope_violations_rejected() {
+        let f = fixture_with_account(1).await;
+
+        let mut outside = op(&f, f.account, &f.session, 0);
+        outside.target = EvmAddress::repeat_byte(0x42);
+        outside.signature = sign(&f.session, &outside.hash(1));
+        assert_eq!(f.aa.validate(&outside, NOW).await, Err(AaError::TargetNotAllowed(outside.target)));
+
+        let mut costly = op(&f, f.account, &f.session, 0);
+        costly.value = U256::from(5_000u64);
+        costly.signature = sign(&f.session, &costly.hash(1));
+        assert!(matches!(f.aa.validate(&costly, NOW).await, Err(AaError::ValueExceedsScope { .. })));
+
+        // Signed by a key that was never granted
+        let stranger = key(77);
+        assert_eq!(
+            f.aa.handle_op(op(&f, f.account, &stranger, 0), NOW).await.unwrap_err(),
+            AaError::UnknownSessionKey(address_of(stranger.verifying_key()))
+        );
+
+        // Granted key, but the signature is someone else's
+        let mut forged = op(&f, f.account, &f.session, 0);
+        forged.signature = sign(&stranger, &forged.hash(1));
+        assert!(matches!(f.aa.validate(&forged, NOW).await, Err(AaError::InvalidSignature(_))));
+
+        assert!(f.executor.calls.lock().unwrap().is_empty());
+        assert_eq!(f.aa.nonce(f.account).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_session_expiry_enforced() {
+        let f = fixture_with_account(1).await;
+        let valid = op(&f, f.account, &f.session, 0);
+
+        assert!(f.aa.validate(&valid, NOW + 3600).await.is_ok());
+        assert_eq!(
+            f.aa.handle_op(valid.clone(), NOW + 3601).await.unwrap_err(),
+            AaError::SessionExpired(NOW + 3600)
+        );
+
+        // Revoked keys fail the same way as never-granted ones
+        f.aa.revoke_session(f.account, valid.session_key).await.unwrap();
+        assert_eq!(f.aa.validate(&valid, NOW).await, Err(AaError::UnknownSessionKey(valid.session_key)));
+    }
+
+    #[tokio::test]
+    async fn test_paymaster_charged_for_gas_used() {
+        let f = fixture_with_account(1).await;
+        let before = f.aa.paymaster_deposit(f.paymaster).await;
+
+        let sponsored = op(&f, f.account, &f.session, 0);
+        let receipt = f.aa.handle_op(sponsored.clone(), NOW).await.unwrap();
+
+        let expected = U256::from(50_000 + VERIFICATION_GAS) * sponsored.max_fee_per_gas;
+        assert_eq!(receipt.actual_gas_used, 50_000 + VERIFICATION_GAS);
+        assert_eq!(receipt.actual_gas_cost, expected);
+        assert_eq!(f.aa.paymaster_deposit(f.paymaster).await, before - expected);
+
+        // A deposit that can't cover the worst case is refused up front
+        let broke = EvmAddress::repeat_byte(0x55);
+        f.aa.deposit(broke, U256::from(1u64)).await;
+        let mut unfunded = op(&f, f.account, &f.session, 1);
+        unfunded.paymaster = broke;
+        unfunded.signature = sign(&f.session, &unfunded.hash(1));
+        assert!(matches!(
+            f.aa.handle_op(unfunded, NOW).await,
+            Err(AaError::InsufficientDeposit { .. })
+        ));
+        assert_eq!(f.aa.paymaster_deposit(broke).await, U256::from(1u64));
+    }
+
+    #[tokio::test]
+    async fn test_nonces_isolated_between_accounts() {
+        let f = fixture_with_account(1).await;
+        let other_owner = key(2);
+        let other_session = key(102);
+        let other = EvmAddress::repeat_byte(2);
+        f.aa.register_account(other, address_of(other_owner.verifying_key())).await.unwrap();
+        f.aa.add_session_grant(signed_grant(&other_owner, other, &other_session, f.job_manager, NOW + 3600))
+            .await
+            .unwrap();
+
+        f.aa.handle_op(op(&f, f.account, &f.session, 0), NOW).await.unwrap();
+        f.aa.handle_op(op(&f, f.account, &f.session, 1), NOW).await.unwrap();
+
+        // The second account starts at its own nonce 0
+        assert_eq!(f.aa.nonce(other).await.unwrap(), 0);
+        f.aa.handle_op(op(&f, other, &other_session, 0), NOW).await.unwrap();
+        assert_eq!(f.aa.nonce(f.account).await.unwrap(), 2);
+        assert_eq!(f.aa.nonce(other).await.unwrap(), 1);
+
+        // Replays are rejected
+        assert_eq!(
+            f.aa.handle_op(op(&f, f.account, &f.session, 1), NOW).await.unwrap_err(),
+            AaError::InvalidNonce { expected: 2, got: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_entry_point_calls_take_effect_when_included() {
+        let executor = Arc::new(MockExecutor {
+            calls: Mutex::new(Vec::new()),
+            gas_used: 50_000,
+        });
+        let sink = Arc::new(RecordingSink::default());
+        let aa = AccountAbstraction::new(1, executor.clone()).with_sink(sink.clone());
+        let ledger = State::new(&crate::config::Config::default()).unwrap();
+        let take = || sink.txs.lock().unwrap().drain(..).collect::<Vec<_>>();
+
+        let owner = key(1);
+        let session = key(101);
+        let paymaster_key = key(9);
+        let paymaster = address_of(paymaster_key.verifying_key());
+        let job_manager = EvmAddress::from_low_u64_be(0x10b);
+
+        let mut registration = AccountRegistration {
+            owner: address_of(owner.verifying_key()),
+            salt: H256::zero(),
+            signature: Vec::new(),
+        };
+        registration.signature = sign(&owner, &registration.hash(1));
+        let account = registration.account();
+        aa.enqueue_at(EntryPointCall::RegisterAccount(registration), NOW).await.unwrap();
+
+        // Admission alone changes nothing
+        assert_eq!(aa.nonce(account).await, Err(AaError::UnknownAccount(account)));
+        aa.apply_block(&included(&take()), NOW, &ledger).await;
+        assert_eq!(aa.nonce(account).await, Ok(0));
+
+        // Only the owner can register an account derived from its address
+        let mut forged = AccountRegistration {
+            owner: paymaster,
+            salt: H256::zero(),
+            signature: Vec::new(),
+        };
+        forged.signature = sign(&owner, &forged.hash(1));
+        assert!(matches!(
+            aa.enqueue_at(EntryPointCall::RegisterAccount(forged), NOW).await,
+            Err(AaError::InvalidSignature(_))
+        ));
+
+        let grant = signed_grant(&owner, account, &session, job_manager, NOW + 3600);
+        aa.enqueue_at(EntryPointCall::AddSession(grant), NOW).await.unwrap();
+        let mut update = PolicyUpdate {
+            paymaster,
+            policy: PaymasterPolicy {
+                allowed_targets: vec![job_manager],
+                ..Default::default()
+            },
+            nonce: 0,
+            signature: Vec::new(),
+        };
+        update.signature = sign(&paymaster_key, &update.hash(1));
+        aa.enqueue_at(EntryPointCall::SetPaymasterPolicy(update.clone()), NOW).await.unwrap();
+
+        // Deposits move the carrying transaction's value out of the funder's balance
+        let funder = format!("0x{}", hex::encode(paymaster.as_bytes()));
+        ledger.set_balance(&funder, 5_000_000_000_000_000).unwrap();
+        let mut block = take();
+        block.push(Transaction::new(
+            Address::new(paymaster.0),
+            Address::new(ENTRY_POINT.0),
+            1_000_000_000_000_000,
+            EntryPointCall::DepositTo { paymaster }.encode(),
+            1,
+            0,
+            0,
+        ));
+        aa.apply_block(&included(&block), NOW, &ledger).await;
+        assert_eq!(ledger.get_balance(&funder).unwrap(), 4_000_000_000_000_000);
+        assert_eq!(aa.paymaster_deposit(paymaster).await, U256::from(1_000_000_000_000_000u64));
+
+        // An applied policy update can't be replayed
+        assert_eq!(
+            aa.enqueue_at(EntryPointCall::SetPaymasterPolicy(update), NOW).await,
+            Err(AaError::InvalidNonce { expected: 1, got: 0 })
+        );
+
+        // Back-to-back operations: the second is admitted ahead of the first's inclusion
+        let user_op = |nonce: u64| {
+            let mut op = UserOperation {
+                sender: account,
+                nonce,
+                target: job_manager,
+                value: U256::zero(),
+                call_data: hex::decode("19b04cbe").unwrap(),
+                call_gas_limit: 200_000,
+                max_fee_per_gas: U256::from(1_000_000_000u64),
+                paymaster,
+                session_key: address_of(session.verifying_key()),
+                signature: Vec::new(),
+            };
+            op.signature = sign(&session, &op.hash(1));
+            op
+        };
+        let first = aa.enqueue_at(EntryPointCall::HandleOp(user_op(0)), NOW).await.unwrap();
+        assert_eq!(aa.next_nonce(account).await, Ok(1));
+        let second = aa.enqueue_at(EntryPointCall::HandleOp(user_op(1)), NOW).await.unwrap();
+        assert_eq!(aa.receipt(&first).await.unwrap().status, UserOpStatus::Pending);
+        assert!(executor.calls.lock().unwrap().is_empty());
+
+        let block = included(&take());
+        aa.apply_block(&block, NOW, &ledger).await;
+        assert_eq!(aa.receipt(&first).await.unwrap().status, UserOpStatus::Included);
+        assert_eq!(aa.receipt(&second).await.unwrap().status, UserOpStatus::Included);
+        assert_eq!(aa.nonce(account).await, Ok(2));
+        assert_eq!(executor.calls.lock().unwrap().len(), 2);
+
+        // Including the same operations again changes nothing
+        aa.apply_block(&block, NOW, &ledger).await;
+        assert_eq!(aa.nonce(account).await, Ok(2));
+        assert_eq!(aa.receipt(&second).await.unwrap().status, UserOpStatus::Included);
+    }
+}
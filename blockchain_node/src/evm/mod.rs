@@ -1,3 +1,4 @@
+pub mod account_abstraction;  // Sponsored user operations and session keys
 pub mod advanced_gas_metering;
 pub mod backend;
 pub mod database;  // Real EVM database implementation using RocksDB
@@ -27,6 +28,7 @@ pub const MAX_CODE_SIZE: u64 = 24_576; // 24KB
 pub const NATIVE_TO_GAS_CONVERSION_RATE: u64 = 1_000_000; // 1 native token = 1M gas units
 
 // Re-export commonly used types
+pub use account_abstraction::{AccountAbstraction, SessionGrant, UserOperation};
 pub use advanced_gas_metering::{
     AdvancedGasConfig, AdvancedGasMeter, Eip1559GasPrice, GasEstimationResult,
 };
@@ -1,7 +1,8 @@
+use crate::evm::account_abstraction::{AccountAbstraction, EntryPointCall, UserOperation, ENTRY_POINT};
 use crate::evm::executor::EvmExecutor;
 use crate::evm::types::EvmTransaction;
 use anyhow::{anyhow, Result};
-use ethereum_types::{H160, U256};
+use ethereum_types::{H160, H256, U256};
 use hex;
 use jsonrpc_core::{Error as RpcError, IoHandler, Params, Value};
 use jsonrpc_http_server::{Server as RpcServer, ServerBuilder};
@@ -20,6 +21,8 @@ pub struct EvmRpcService {
     executor: Arc<EvmExecutor>,
     /// Chain ID
     chain_id: u64,
+    /// Sponsored user operations, shared with the bundler REST router
+    account_abstraction: Arc<AccountAbstraction>,
 }
 
 /// Transaction parameters for eth_call and eth_estimateGas
@@ -34,17 +37,24 @@ pub struct CallRequest {
 }
 
 impl EvmRpcService {
-    /// Create a new EVM RPC service
-    pub fn new(executor: Arc<EvmExecutor>) -> Self {
+    /// Create a new EVM RPC service. User operations are served from the
+    /// node's shared account-abstraction state, the one blocks are applied to.
+    pub fn new(executor: Arc<EvmExecutor>, account_abstraction: Arc<AccountAbstraction>) -> Self {
         let config = executor.get_config();
 
         Self {
             server: None,
+            account_abstraction,
             executor,
             chain_id: config.chain_id,
         }
     }
 
+    /// Account abstraction state behind the user operation methods
+    pub fn account_abstraction(&self) -> Arc<AccountAbstraction> {
+        self.account_abstraction.clone()
+    }
+
     /// Start the RPC server
     pub fn start(&mut self, addr: SocketAddr) -> Result<(), anyhow::Error> {
         let mut io = IoHandler::new();
@@ -283,6 +293,64 @@ impl EvmRpcService {
             }
         });
 
+        // eth_supportedEntryPoints
+        io.add_method("eth_supportedEntryPoints", move |_params: Params| async move {
+            Ok(Value::Array(vec![Value::String(format!("{ENTRY_POINT:?}"))]))
+        });
+
+        // eth_sendUserOperation
+        let aa = self.account_abstraction.clone();
+        io.add_method("eth_sendUserOperation", move |params: Params| {
+            let aa = aa.clone();
+            async move {
+                let (op, entry_point): (UserOperation, H160) = params
+                    .parse()
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid parameters: {e:?}")))?;
+                if entry_point != ENTRY_POINT {
+                    return Err(RpcError::invalid_params("Unsupported entry point"));
+                }
+                let user_op_hash = aa
+                    .enqueue(EntryPointCall::HandleOp(op))
+                    .await
+                    .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+                Ok(Value::String(format!("{user_op_hash:?}")))
+            }
+        });
+
+        // eth_estimateUserOperationGas
+        let aa = self.account_abstraction.clone();
+        io.add_method("eth_estimateUserOperationGas", move |params: Params| {
+            let aa = aa.clone();
+            async move {
+                let (op, entry_point): (UserOperation, H160) = params
+                    .parse()
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid parameters: {e:?}")))?;
+                if entry_point != ENTRY_POINT {
+                    return Err(RpcError::invalid_params("Unsupported entry point"));
+                }
+                let estimate = aa
+                    .estimate(&op)
+                    .await
+                    .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+                serde_json::to_value(estimate).map_err(|_| RpcError::internal_error())
+            }
+        });
+
+        // eth_getUserOperationReceipt
+        let aa = self.account_abstraction.clone();
+        io.add_method("eth_getUserOperationReceipt", move |params: Params| {
+            let aa = aa.clone();
+            async move {
+                let (hash,): (H256,) = params
+                    .parse()
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid parameters: {e:?}")))?;
+                match aa.receipt(&hash).await {
+                    Some(receipt) => serde_json::to_value(receipt).map_err(|_| RpcError::internal_error()),
+                    None => Ok(Value::Null),
+                }
+            }
+        });
+
         // Start the server
         let server = ServerBuilder::new(io)
             .threads(4)
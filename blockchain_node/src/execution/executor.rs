@@ -27,6 +27,26 @@ pub enum ExecutionResult {
     InvalidNonce,
 }
 
+impl ExecutionResult {
+    /// Transaction status recorded for this result
+    pub fn status(&self) -> TransactionStatus {
+        match self {
+            ExecutionResult::Success => TransactionStatus::Success,
+            ExecutionResult::Failure(reason) => TransactionStatus::Failed(reason.clone()),
+            ExecutionResult::Reverted(reason) => TransactionStatus::Failed(format!("Reverted: {}", reason)),
+            ExecutionResult::ValidationError(reason) => TransactionStatus::Failed(reason.clone()),
+            ExecutionResult::InsufficientBalance => TransactionStatus::Failed("Insufficient balance".into()),
+            ExecutionResult::OutOfGas => TransactionStatus::Failed("Out of gas".into()),
+            ExecutionResult::InvalidNonce => TransactionStatus::Failed("Invalid nonce".into()),
+        }
+    }
+}
+
+/// Selector dispatched to the built-in token transfer
+pub(crate) const TRANSFER_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+/// Selector dispatched to the built-in balance query
+pub(crate) const BALANCE_OF_SELECTOR: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+
 /// Transaction executor responsible for processing transactions and updating state
 #[derive(Debug)]
 pub struct TransactionExecutor {
@@ -146,11 +166,17 @@ impl TransactionExecutor {
         }
 
         // Calculate transaction fee
-        let fee = transaction.fee();
+        let fee = match transaction.checked_fee() {
+            Some(fee) => fee,
+            None => {
+                transaction.set_status(TransactionStatus::Failed("Fee overflow".into()));
+                return Ok(ExecutionResult::Failure("Fee overflow".into()));
+            }
+        };
 
         // Check if sender has sufficient balance
         let sender_balance = state.get_balance(&transaction.sender)?;
-        if sender_balance < fee + transaction.amount {
+        if fee.checked_add(transaction.amount).map_or(true, |total| sender_balance < total) {
             transaction.set_status(TransactionStatus::Failed("Insufficient balance".into()));
             return Ok(ExecutionResult::InsufficientBalance);
         }
@@ -186,36 +212,30 @@ impl TransactionExecutor {
             TransactionType::Custom(_) => self.execute_system(transaction, state).await?, // Handle as system transaction
         };
 
+        transaction.set_status(result.status());
         match &result {
             ExecutionResult::Success => {
-                transaction.set_status(TransactionStatus::Success);
                 info!(
                     "Transaction executed successfully: {}",
                     hex::encode(transaction.hash().as_ref())
                 );
             }
             ExecutionResult::Failure(reason) => {
-                transaction.set_status(TransactionStatus::Failed(reason.clone()));
                 error!("Transaction failed: {}", reason);
             }
             ExecutionResult::Reverted(reason) => {
-                transaction.set_status(TransactionStatus::Failed(format!("Reverted: {}", reason)));
                 error!("Transaction reverted: {}", reason);
             }
             ExecutionResult::InsufficientBalance => {
-                transaction.set_status(TransactionStatus::Failed("Insufficient balance".into()));
                 error!("Transaction failed: Insufficient balance");
             }
             ExecutionResult::OutOfGas => {
-                transaction.set_status(TransactionStatus::Failed("Out of gas".into()));
                 error!("Transaction failed: Out of gas");
             }
             ExecutionResult::ValidationError(reason) => {
-                transaction.set_status(TransactionStatus::Failed(reason.clone()));
                 error!("Transaction validation failed: {}", reason);
             }
             ExecutionResult::InvalidNonce => {
-                transaction.set_status(TransactionStatus::Failed("Invalid nonce".into()));
                 error!("Transaction failed: Invalid nonce");
             }
         }
@@ -231,10 +251,16 @@ impl TransactionExecutor {
         );
 
         // Update sender nonce
-        state.set_nonce(&transaction.sender, transaction.nonce + 1)?;
+        let next_nonce = transaction
+            .nonce
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Nonce overflow"))?;
+        state.set_nonce(&transaction.sender, next_nonce)?;
 
         // Deduct fee from sender
-        let fee = transaction.fee();
+        let fee = transaction
+            .checked_fee()
+            .ok_or_else(|| anyhow!("Fee overflow"))?;
         let sender_balance = state.get_balance(&transaction.sender)?;
         state.set_balance(&transaction.sender, sender_balance - fee)?;
 
@@ -248,10 +274,10 @@ impl TransactionExecutor {
             state.set_balance(&transaction.sender, sender_balance - transaction.amount)?;
 
             let recipient_balance = state.get_balance(&transaction.recipient)?;
-            state.set_balance(
-                &transaction.recipient,
-                recipient_balance + transaction.amount,
-            )?;
+            let recipient_balance = recipient_balance
+                .checked_add(transaction.amount)
+                .ok_or_else(|| anyhow!("Recipient balance overflow"))?;
+            state.set_balance(&transaction.recipient, recipient_balance)?;
         }
 
         Ok(())
@@ -426,11 +452,11 @@ impl TransactionExecutor {
                 let selector = &transaction.data[0..4];
                 let args = &transaction.data[4..];
                 
-                match selector {
-                    [0x70, 0xa0, 0x82, 0x31] => { // transfer(address,uint256)
+                match <[u8; 4]>::try_from(selector)? {
+                    TRANSFER_SELECTOR => { // transfer(address,uint256)
                         output = self.execute_transfer_function(args, transaction, state).await?;
                     }
-                    [0x18, 0x16, 0x0d, 0xdd] => { // balanceOf(address)
+                    BALANCE_OF_SELECTOR => { // balanceOf(address)
                         output = self.execute_balance_function(args, transaction, state).await?;
                     }
                     [0x06, 0xfd, 0xde, 0x03] => { // name()
@@ -484,7 +510,10 @@ impl TransactionExecutor {
         // Execute transfer
         state.set_balance(&transaction.sender, sender_balance - amount)?;
         let recipient_balance = state.get_balance(&recipient)?;
-        state.set_balance(&recipient, recipient_balance + amount)?;
+        let recipient_balance = recipient_balance
+            .checked_add(amount)
+            .ok_or_else(|| anyhow!("Recipient balance overflow"))?;
+        state.set_balance(&recipient, recipient_balance)?;
         
         // Return success (true)
         Ok(vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01])
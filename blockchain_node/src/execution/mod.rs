@@ -1,11 +1,13 @@
 // Execution module for handling transaction processing
 
 pub mod executor;
+pub mod optimistic;
 pub mod parallel;
 pub mod transaction_engine;
 
 // Re-export key types
 pub use executor::{ExecutionResult, TransactionExecutor};
+pub use optimistic::{CallExecutor, OptimisticConfig, OptimisticExecutor, TransferExecutor};
 pub use parallel::{MemoryPool, ParallelProcessor, TopologicalSort, TransactionGraph};
 pub use transaction_engine::TransactionEngine;
 pub mod arthacoin_executor;
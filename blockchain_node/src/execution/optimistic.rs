@@ -0,0 +1,674 @@
+//! Optimistic parallel block execution
+//!
+//! Transactions of a block are split into batches. Every transaction in a batch
+//! executes concurrently against a versioned overlay of the state as of the
+//! start of the batch, recording its read and write sets. Results are then
+//! validated in block order: a transaction that read a key written by an
+//! earlier transaction of the same batch is re-executed serially against the
+//! merged state. The committed result is identical to sequential execution.
+//!
+//! `TransactionEngine` applies blocks through [`CallExecutor`] when
+//! `TransactionEngineConfig::optimistic` is set. Blocks holding deployments or
+//! transaction types the executor does not cover keep the sequential path.
+
+use crate::execution::executor::{ExecutionResult, BALANCE_OF_SELECTOR, TRANSFER_SELECTOR};
+use crate::ledger::state::State;
+use crate::ledger::transaction::{Transaction, TransactionType};
+use anyhow::Result;
+use log::debug;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// A single addressable piece of state touched by a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StateKey {
+    Balance(String),
+    Nonce(String),
+    Storage(String),
+}
+
+/// Value held under a [`StateKey`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateValue {
+    Balance(u64),
+    Nonce(u64),
+    Storage(Option<Vec<u8>>),
+}
+
+/// State access used by transaction execution. Executors that take part in
+/// optimistic execution (transfers, contract calls) must read and write state only
+/// through this trait so the overlay can capture read/write sets.
+pub trait StateAccess {
+    fn get_balance(&mut self, address: &str) -> Result<u64>;
+    fn set_balance(&mut self, address: &str, amount: u64) -> Result<()>;
+    fn get_nonce(&mut self, address: &str) -> Result<u64>;
+    fn set_nonce(&mut self, address: &str, nonce: u64) -> Result<()>;
+    fn get_storage(&mut self, key: &str) -> Result<Option<Vec<u8>>>;
+    fn set_storage(&mut self, key: &str, value: Vec<u8>) -> Result<()>;
+}
+
+impl StateAccess for &State {
+    fn get_balance(&mut self, address: &str) -> Result<u64> {
+        State::get_balance(self, address)
+    }
+
+    fn set_balance(&mut self, address: &str, amount: u64) -> Result<()> {
+        State::set_balance(self, address, amount)
+    }
+
+    fn get_nonce(&mut self, address: &str) -> Result<u64> {
+        State::get_nonce(self, address)
+    }
+
+    fn set_nonce(&mut self, address: &str, nonce: u64) -> Result<()> {
+        State::set_nonce(self, address, nonce)
+    }
+
+    fn get_storage(&mut self, key: &str) -> Result<Option<Vec<u8>>> {
+        State::get_storage(self, key)
+    }
+
+    fn set_storage(&mut self, key: &str, value: Vec<u8>) -> Result<()> {
+        State::set_storage(self, key, value)
+    }
+}
+
+/// Executes one transaction against a [`StateAccess`]
+pub trait OverlayTransactionExecutor: Sync {
+    fn execute(&self, transaction: &Transaction, state: &mut dyn StateAccess) -> Result<ExecutionResult>;
+
+    /// Whether `transaction` is executed with the semantics of
+    /// `TransactionExecutor`; blocks containing anything else run sequentially
+    fn supports(&self, transaction: &Transaction) -> bool;
+}
+
+/// Checks `TransactionExecutor::execute_transaction` makes before dispatching
+/// on the transaction type. Yields the fee and sender balance, or the result
+/// the transaction ends with.
+fn pre_execute(
+    transaction: &Transaction,
+    state: &mut dyn StateAccess,
+    max_gas_limit: u64,
+    min_gas_price: u64,
+) -> Result<std::result::Result<(u64, u64), ExecutionResult>> {
+    if let Err(e) = transaction.validate() {
+        return Ok(Err(ExecutionResult::Failure(format!("Validation error: {}", e))));
+    }
+
+    let current_nonce = state.get_nonce(&transaction.sender)?;
+    if transaction.nonce != current_nonce {
+        return Ok(Err(ExecutionResult::InvalidNonce));
+    }
+
+    if transaction.gas_price < min_gas_price {
+        return Ok(Err(ExecutionResult::Failure("Gas price too low".into())));
+    }
+    if transaction.gas_limit > max_gas_limit {
+        return Ok(Err(ExecutionResult::Failure("Gas limit too high".into())));
+    }
+
+    let fee = match transaction.checked_fee() {
+        Some(fee) => fee,
+        None => return Ok(Err(ExecutionResult::Failure("Fee overflow".into()))),
+    };
+    let sender_balance = state.get_balance(&transaction.sender)?;
+    if fee.checked_add(transaction.amount).map_or(true, |total| sender_balance < total) {
+        return Ok(Err(ExecutionResult::InsufficientBalance));
+    }
+
+    Ok(Ok((fee, sender_balance)))
+}
+
+/// Value-transfer semantics matching `TransactionExecutor::execute_transaction`
+#[derive(Debug, Clone)]
+pub struct TransferExecutor {
+    /// Maximum gas limit allowed
+    pub max_gas_limit: u64,
+    /// Minimum gas price allowed
+    pub min_gas_price: u64,
+}
+
+impl OverlayTransactionExecutor for TransferExecutor {
+    fn execute(&self, transaction: &Transaction, state: &mut dyn StateAccess) -> Result<ExecutionResult> {
+        let (fee, sender_balance) = match pre_execute(transaction, state, self.max_gas_limit, self.min_gas_price)? {
+            Ok(checked) => checked,
+            Err(result) => return Ok(result),
+        };
+
+        if !matches!(transaction.tx_type, TransactionType::Transfer) {
+            return Ok(ExecutionResult::Failure(format!(
+                "Unsupported transaction type for transfer execution: {:?}",
+                transaction.tx_type
+            )));
+        }
+
+        // Overflows fail the whole transaction before any write, matching the
+        // revert in TransactionExecutor::execute_transfer
+        let next_nonce = match transaction.nonce.checked_add(1) {
+            Some(nonce) => nonce,
+            None => return Ok(ExecutionResult::Failure("Nonce overflow".into())),
+        };
+        let sender_after = sender_balance - fee - transaction.amount;
+        let recipient_before = if transaction.recipient == transaction.sender {
+            sender_after
+        } else {
+            state.get_balance(&transaction.recipient)?
+        };
+        if recipient_before.checked_add(transaction.amount).is_none() {
+            return Ok(ExecutionResult::Failure("Recipient balance overflow".into()));
+        }
+
+        // Same state transition order as TransactionExecutor::apply_transaction
+        state.set_nonce(&transaction.sender, next_nonce)?;
+        let sender_balance = state.get_balance(&transaction.sender)?;
+        state.set_balance(&transaction.sender, sender_balance - fee)?;
+
+        let sender_balance = state.get_balance(&transaction.sender)?;
+        state.set_balance(&transaction.sender, sender_balance - transaction.amount)?;
+        let recipient_balance = state.get_balance(&transaction.recipient)?;
+        state.set_balance(&transaction.recipient, recipient_balance + transaction.amount)?;
+
+        Ok(ExecutionResult::Success)
+    }
+
+    fn supports(&self, transaction: &Transaction) -> bool {
+        matches!(transaction.tx_type, TransactionType::Transfer)
+    }
+}
+
+/// Transfer and contract-call semantics matching
+/// `TransactionExecutor::execute_transaction`, used for block application.
+/// Contract calls read the contract's code key and the balances the built-in
+/// token functions touch, so both show up in the read/write sets.
+#[derive(Debug, Clone)]
+pub struct CallExecutor {
+    /// Maximum gas limit allowed
+    pub max_gas_limit: u64,
+    /// Minimum gas price allowed
+    pub min_gas_price: u64,
+}
+
+impl CallExecutor {
+    /// Mirrors `TransactionExecutor::execute_contract_call`. Every check runs
+    /// before the first write, so a failed call leaves no writes behind just
+    /// as the snapshot revert does.
+    fn execute_call(&self, transaction: &Transaction, state: &mut dyn StateAccess) -> Result<ExecutionResult> {
+        let (fee, sender_balance) = match pre_execute(transaction, state, self.max_gas_limit, self.min_gas_price)? {
+            Ok(checked) => checked,
+            Err(result) => return Ok(result),
+        };
+
+        let next_nonce = match transaction.nonce.checked_add(1) {
+            Some(nonce) => nonce,
+            None => return Ok(ExecutionResult::Failure("Nonce overflow".into())),
+        };
+        let sender_after_fee = sender_balance - fee;
+
+        if state.get_storage(&format!("contract:{}", transaction.recipient))?.is_none() {
+            return Ok(ExecutionResult::Failure("Contract does not exist".into()));
+        }
+
+        let token_transfer = match Self::token_transfer(transaction, sender_after_fee, state)? {
+            Ok(transfer) => transfer,
+            Err(reason) => {
+                return Ok(ExecutionResult::Failure(format!("Contract execution failed: {}", reason)))
+            }
+        };
+
+        state.set_nonce(&transaction.sender, next_nonce)?;
+        state.set_balance(&transaction.sender, sender_after_fee)?;
+        if let Some((recipient, amount)) = token_transfer {
+            let sender_balance = state.get_balance(&transaction.sender)?;
+            state.set_balance(&transaction.sender, sender_balance - amount)?;
+            let recipient_balance = state.get_balance(&recipient)?;
+            state.set_balance(&recipient, recipient_balance + amount)?;
+        }
+
+        Ok(ExecutionResult::Success)
+    }
+
+    /// Runs the checks of `TransactionExecutor::execute_smart_contract` and
+    /// returns the balance move of a built-in token transfer, if any
+    fn token_transfer(
+        transaction: &Transaction,
+        sender_balance: u64,
+        state: &mut dyn StateAccess,
+    ) -> Result<std::result::Result<Option<(String, u64)>, &'static str>> {
+        if transaction.gas_limit < 21000 {
+            return Ok(Err("Gas limit too low for contract execution"));
+        }
+        if transaction.data.is_empty() {
+            return Ok(Ok(None));
+        }
+        if transaction.data.len() < 4 {
+            return Ok(Err("Invalid function call data"));
+        }
+
+        let selector: [u8; 4] = transaction.data[..4].try_into()?;
+        let args = &transaction.data[4..];
+        match selector {
+            TRANSFER_SELECTOR => {
+                if args.len() < 64 {
+                    return Ok(Err("Invalid transfer function arguments"));
+                }
+                let recipient = hex::encode(&args[12..32]);
+                let mut amount = [0u8; 8];
+                amount.copy_from_slice(&args[56..64]);
+                let amount = u64::from_be_bytes(amount);
+
+                if sender_balance < amount {
+                    return Ok(Err("Insufficient balance for transfer"));
+                }
+                let recipient_before = if recipient == transaction.sender {
+                    sender_balance - amount
+                } else {
+                    state.get_balance(&recipient)?
+                };
+                if recipient_before.checked_add(amount).is_none() {
+                    return Ok(Err("Recipient balance overflow"));
+                }
+                Ok(Ok(Some((recipient, amount))))
+            }
+            BALANCE_OF_SELECTOR => {
+                if args.len() < 32 {
+                    return Ok(Err("Invalid balance function arguments"));
+                }
+                state.get_balance(&hex::encode(&args[12..32]))?;
+                Ok(Ok(None))
+            }
+            _ => Ok(Ok(None)),
+        }
+    }
+}
+
+impl OverlayTransactionExecutor for CallExecutor {
+    fn execute(&self, transaction: &Transaction, state: &mut dyn StateAccess) -> Result<ExecutionResult> {
+        match transaction.tx_type {
+            TransactionType::Transfer => TransferExecutor {
+                max_gas_limit: self.max_gas_limit,
+                min_gas_price: self.min_gas_price,
+            }
+            .execute(transaction, state),
+            TransactionType::Call | TransactionType::ContractCall => self.execute_call(transaction, state),
+            _ => Ok(ExecutionResult::Failure(format!(
+                "Unsupported transaction type for call execution: {:?}",
+                transaction.tx_type
+            ))),
+        }
+    }
+
+    fn supports(&self, transaction: &Transaction) -> bool {
+        matches!(
+            transaction.tx_type,
+            TransactionType::Transfer | TransactionType::Call | TransactionType::ContractCall
+        )
+    }
+}
+
+/// Read-through view over the base state plus writes committed earlier in the block
+struct BlockView<'a> {
+    base: &'a State,
+    committed: HashMap<StateKey, StateValue>,
+}
+
+impl<'a> BlockView<'a> {
+    fn read(&self, key: &StateKey) -> Result<StateValue> {
+        if let Some(value) = self.committed.get(key) {
+            return Ok(value.clone());
+        }
+        Ok(match key {
+            StateKey::Balance(address) => StateValue::Balance(self.base.get_balance(address)?),
+            StateKey::Nonce(address) => StateValue::Nonce(self.base.get_nonce(address)?),
+            StateKey::Storage(k) => StateValue::Storage(self.base.get_storage(k)?),
+        })
+    }
+}
+
+/// Versioned overlay for a single transaction, recording its read/write sets
+pub struct VersionedOverlay<'a> {
+    view: &'a BlockView<'a>,
+    reads: HashSet<StateKey>,
+    writes: HashMap<StateKey, StateValue>,
+}
+
+impl<'a> VersionedOverlay<'a> {
+    fn new(view: &'a BlockView<'a>) -> Self {
+        Self {
+            view,
+            reads: HashSet::new(),
+            writes: HashMap::new(),
+        }
+    }
+
+    fn read(&mut self, key: StateKey) -> Result<StateValue> {
+        // Reads of the transaction's own writes are not external dependencies
+        if let Some(value) = self.writes.get(&key) {
+            return Ok(value.clone());
+        }
+        let value = self.view.read(&key)?;
+        self.reads.insert(key);
+        Ok(value)
+    }
+
+    pub fn read_set(&self) -> &HashSet<StateKey> {
+        &self.reads
+    }
+
+    pub fn write_set(&self) -> &HashMap<StateKey, StateValue> {
+        &self.writes
+    }
+}
+
+impl<'a> StateAccess for VersionedOverlay<'a> {
+    fn get_balance(&mut self, address: &str) -> Result<u64> {
+        match self.read(StateKey::Balance(address.to_string()))? {
+            StateValue::Balance(v) => Ok(v),
+            _ => unreachable!("balance key holds balance value"),
+        }
+    }
+
+    fn set_balance(&mut self, address: &str, amount: u64) -> Result<()> {
+        self.writes.insert(StateKey::Balance(address.to_string()), StateValue::Balance(amount));
+        Ok(())
+    }
+
+    fn get_nonce(&mut self, address: &str) -> Result<u64> {
+        match self.read(StateKey::Nonce(address.to_string()))? {
+            StateValue::Nonce(v) => Ok(v),
+            _ => unreachable!("nonce key holds nonce value"),
+        }
+    }
+
+    fn set_nonce(&mut self, address: &str, nonce: u64) -> Result<()> {
+        self.writes.insert(StateKey::Nonce(address.to_string()), StateValue::Nonce(nonce));
+        Ok(())
+    }
+
+    fn get_storage(&mut self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.read(StateKey::Storage(key.to_string()))? {
+            StateValue::Storage(v) => Ok(v),
+            _ => unreachable!("storage key holds storage value"),
+        }
+    }
+
+    fn set_storage(&mut self, key: &str, value: Vec<u8>) -> Result<()> {
+        self.writes.insert(StateKey::Storage(key.to_string()), StateValue::Storage(Some(value)));
+        Ok(())
+    }
+}
+
+/// Outcome of a single optimistic execution
+struct TxOutcome {
+    result: ExecutionResult,
+    reads: HashSet<StateKey>,
+    writes: HashMap<StateKey, StateValue>,
+}
+
+/// Tuning for the optimistic scheduler
+#[derive(Debug, Clone)]
+pub struct OptimisticConfig {
+    /// Initial batch size
+    pub initial_batch_size: usize,
+    /// Lower bound for adaptive batch size
+    pub min_batch_size: usize,
+    /// Upper bound for adaptive batch size
+    pub max_batch_size: usize,
+    /// Conflict rate above which the batch size shrinks
+    pub target_conflict_rate: f64,
+    /// Conflict rate of the previous block above which execution is fully sequential
+    pub sequential_fallback_rate: f64,
+}
+
+impl Default for OptimisticConfig {
+    fn default() -> Self {
+        Self {
+            initial_batch_size: 64,
+            min_batch_size: 4,
+            max_batch_size: 1024,
+            target_conflict_rate: 0.1,
+            sequential_fallback_rate: 0.5,
+        }
+    }
+}
+
+/// Counters exposed for monitoring
+#[derive(Debug, Default)]
+pub struct OptimisticMetrics {
+    /// Transactions whose optimistic result was committed
+    pub parallel_executed: AtomicU64,
+    /// Transactions re-executed serially after a conflict
+    pub re_executed: AtomicU64,
+    /// Blocks executed fully sequentially
+    pub sequential_blocks: AtomicU64,
+    /// Blocks executed optimistically
+    pub parallel_blocks: AtomicU64,
+}
+
+/// Snapshot of [`OptimisticMetrics`]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OptimisticMetricsSnapshot {
+    pub parallel_executed: u64,
+    pub re_executed: u64,
+    pub sequential_blocks: u64,
+    pub parallel_blocks: u64,
+    /// Estimated speedup: transactions executed per serial execution step
+    pub speedup_estimate: f64,
+    pub batch_size: usize,
+}
+
+/// Result of executing a block
+#[derive(Debug)]
+pub struct BlockExecutionOutcome {
+    /// Per-transaction results in block order
+    pub results: Vec<ExecutionResult>,
+    /// Whether the block was executed fully sequentially
+    pub sequential: bool,
+    /// Number of transactions re-executed because of conflicts
+    pub conflicts: usize,
+}
+
+/// Optimistic parallel block executor
+pub struct OptimisticExecutor<E: OverlayTransactionExecutor> {
+    executor: E,
+    config: OptimisticConfig,
+    batch_size: Mutex<usize>,
+    last_conflict_rate: Mutex<f64>,
+    /// Serial steps (batches + re-executions) used to estimate speedup
+    serial_steps: AtomicU64,
+    metrics: OptimisticMetrics,
+}
+
+impl<E: OverlayTransactionExecutor> OptimisticExecutor<E> {
+    /// Create a new optimistic executor
+    pub fn new(executor: E, config: OptimisticConfig) -> Self {
+        let batch_size = config.initial_batch_size.clamp(config.min_batch_size, config.max_batch_size);
+        Self {
+            executor,
+            config,
+            batch_size: Mutex::new(batch_size),
+            last_conflict_rate: Mutex::new(0.0),
+            serial_steps: AtomicU64::new(0),
+            metrics: OptimisticMetrics::default(),
+        }
+    }
+
+    /// Current adaptive batch size
+    pub fn batch_size(&self) -> usize {
+        *self.batch_size.lock().unwrap()
+    }
+
+    /// Snapshot of the monitoring counters
+    pub fn metrics(&self) -> OptimisticMetricsSnapshot {
+        let parallel = self.metrics.parallel_executed.load(Ordering::Relaxed);
+        let re_executed = self.metrics.re_executed.load(Ordering::Relaxed);
+        let steps = self.serial_steps.load(Ordering::Relaxed);
+        OptimisticMetricsSnapshot {
+            parallel_executed: parallel,
+            re_executed,
+            sequential_blocks: self.metrics.sequential_blocks.load(Ordering::Relaxed),
+            parallel_blocks: self.metrics.parallel_blocks.load(Ordering::Relaxed),
+            speedup_estimate: if steps > 0 {
+                (parallel + re_executed) as f64 / steps as f64
+            } else {
+                1.0
+            },
+            batch_size: self.batch_size(),
+        }
+    }
+
+    /// Whether a block must bypass optimistic execution
+    pub fn requires_sequential(&self, transactions: &[Transaction]) -> bool {
+        let has_deployment = transactions.iter().any(|tx| {
+            matches!(
+                tx.tx_type,
+                TransactionType::ContractCreate | TransactionType::Deploy | TransactionType::ContractDeployment
+            )
+        });
+        let unsupported = transactions.iter().any(|tx| !self.executor.supports(tx));
+        has_deployment
+            || unsupported
+            || *self.last_conflict_rate.lock().unwrap() > self.config.sequential_fallback_rate
+    }
+
+    /// Record a block the caller executed sequentially after
+    /// [`requires_sequential`](Self::requires_sequential) asked for it
+    pub fn record_sequential_block(&self) {
+        self.metrics.sequential_blocks.fetch_add(1, Ordering::Relaxed);
+        // A sequential block gives no conflict signal; allow the next block to try again
+        *self.last_conflict_rate.lock().unwrap() = 0.0;
+    }
+
+    /// Execute a block and commit the merged result to `state`
+    pub fn execute_block(&self, transactions: &[Transaction], state: &State) -> Result<BlockExecutionOutcome> {
+        if self.requires_sequential(transactions) {
+            let results = self.execute_sequential(transactions, state)?;
+            self.record_sequential_block();
+            return Ok(BlockExecutionOutcome {
+                results,
+                sequential: true,
+                conflicts: 0,
+            });
+        }
+
+        let mut view = BlockView {
+            base: state,
+            committed: HashMap::new(),
+        };
+        let mut results = Vec::with_capacity(transactions.len());
+        let mut conflicts = 0usize;
+        let mut start = 0usize;
+
+        while start < transactions.len() {
+            let batch_size = self.batch_size();
+            let end = (start + batch_size).min(transactions.len());
+            let batch = &transactions[start..end];
+
+            // Optimistic phase: all transactions see the state at batch start
+            let outcomes: Vec<Result<TxOutcome>> = batch
+                .par_iter()
+                .map(|tx| {
+                    let mut overlay = VersionedOverlay::new(&view);
+                    let result = self.executor.execute(tx, &mut overlay)?;
+                    Ok(TxOutcome {
+                        result,
+                        reads: overlay.reads,
+                        writes: overlay.writes,
+                    })
+                })
+                .collect();
+
+            // Validation phase in block order
+            let mut batch_writes: HashSet<StateKey> = HashSet::new();
+            let mut batch_conflicts = 0usize;
+            let mut pending: Vec<(StateKey, StateValue)> = Vec::new();
+            self.serial_steps.fetch_add(1, Ordering::Relaxed);
+
+            for (tx, outcome) in batch.iter().zip(outcomes) {
+                let outcome = outcome?;
+                let conflicted = outcome.reads.iter().any(|key| batch_writes.contains(key));
+
+                let (result, writes) = if conflicted {
+                    // Re-execute against the state including earlier writes of this batch
+                    for (key, value) in pending.drain(..) {
+                        view.committed.insert(key, value);
+                    }
+                    let mut overlay = VersionedOverlay::new(&view);
+                    let result = self.executor.execute(tx, &mut overlay)?;
+                    batch_conflicts += 1;
+                    self.metrics.re_executed.fetch_add(1, Ordering::Relaxed);
+                    self.serial_steps.fetch_add(1, Ordering::Relaxed);
+                    debug!("Re-executed conflicting transaction {}", hex::encode(tx.hash().as_ref()));
+                    (result, overlay.writes)
+                } else {
+                    self.metrics.parallel_executed.fetch_add(1, Ordering::Relaxed);
+                    (outcome.result, outcome.writes)
+                };
+
+                for (key, value) in writes {
+                    batch_writes.insert(key.clone());
+                    pending.push((key, value));
+                }
+                results.push(result);
+            }
+
+            for (key, value) in pending {
+                view.committed.insert(key, value);
+            }
+
+            conflicts += batch_conflicts;
+            self.adapt_batch_size(batch_conflicts, batch.len());
+            start = end;
+        }
+
+        // Commit merged writes
+        for (key, value) in view.committed {
+            match (key, value) {
+                (StateKey::Balance(address), StateValue::Balance(v)) => state.set_balance(&address, v)?,
+                (StateKey::Nonce(address), StateValue::Nonce(v)) => state.set_nonce(&address, v)?,
+                (StateKey::Storage(k), StateValue::Storage(Some(v))) => state.set_storage(&k, v)?,
+                (StateKey::Storage(k), StateValue::Storage(None)) => state.delete_storage(&k)?,
+                _ => unreachable!("state key and value kinds always match"),
+            }
+        }
+
+        let conflict_rate = if transactions.is_empty() {
+            0.0
+        } else {
+            conflicts as f64 / transactions.len() as f64
+        };
+        *self.last_conflict_rate.lock().unwrap() = conflict_rate;
+        self.metrics.parallel_blocks.fetch_add(1, Ordering::Relaxed);
+
+        Ok(BlockExecutionOutcome {
+            results,
+            sequential: false,
+            conflicts,
+        })
+    }
+
+    /// Fully sequential execution through the same executor
+    pub fn execute_sequential(&self, transactions: &[Transaction], state: &State) -> Result<Vec<ExecutionResult>> {
+        let mut results = Vec::with_capacity(transactions.len());
+        for tx in transactions {
+            let mut access = state;
+            results.push(self.executor.execute(tx, &mut access)?);
+            self.serial_steps.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(results)
+    }
+
+    fn adapt_batch_size(&self, conflicts: usize, batch_len: usize) {
+        if batch_len == 0 {
+            return;
+        }
+        let rate = conflicts as f64 / batch_len as f64;
+        let mut batch_size = self.batch_size.lock().unwrap();
+        if rate > self.config.target_conflict_rate {
+            *batch_size = (*batch_size / 2).max(self.config.min_batch_size);
+        } else if rate < self.config.target_conflict_rate / 2.0 && batch_len == *batch_size {
+            *batch_size = (*batch_size * 2).min(self.config.max_batch_size);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::execution::executor::{ContractExecutor, ExecutionResult, TransactionExecutor};
+use crate::execution::optimistic::{CallExecutor, OptimisticConfig, OptimisticExecutor, OptimisticMetricsSnapshot};
 use crate::ledger::state::State;
 use crate::ledger::transaction::Transaction;
 // use crate::wasm::{ContractExecutor, WasmConfig};
@@ -28,6 +29,10 @@ pub struct TransactionEngineConfig {
     pub wasm_config: WasmConfig,
     /// Enable WASM smart contracts
     pub enable_wasm: bool,
+    /// Apply blocks with optimistic parallel execution; `None` keeps the
+    /// sequential path, which also handles every block the optimistic
+    /// executor cannot
+    pub optimistic: Option<OptimisticConfig>,
 }
 
 impl Default for TransactionEngineConfig {
@@ -39,6 +44,7 @@ impl Default for TransactionEngineConfig {
             min_gas_price: 1,
             wasm_config: WasmConfig::default(),
             enable_wasm: true,
+            optimistic: None,
         }
     }
 }
@@ -53,6 +59,8 @@ pub struct TransactionEngine {
     config: TransactionEngineConfig,
     /// State reference
     state: Arc<State>,
+    /// Optimistic block executor, when enabled in the configuration
+    optimistic: Option<OptimisticExecutor<CallExecutor>>,
 }
 
 impl TransactionEngine {
@@ -74,11 +82,22 @@ impl TransactionEngine {
             config.min_gas_price,
         );
 
+        let optimistic = config.optimistic.clone().map(|optimistic_config| {
+            OptimisticExecutor::new(
+                CallExecutor {
+                    max_gas_limit: config.max_gas_limit,
+                    min_gas_price: config.min_gas_price,
+                },
+                optimistic_config,
+            )
+        });
+
         Ok(Self {
             executor,
             wasm_executor,
             config,
             state,
+            optimistic,
         })
     }
 
@@ -210,7 +229,26 @@ impl TransactionEngine {
         );
 
         // Process transactions
-        let results = self.process_transactions(txs).await?;
+        let results = match &self.optimistic {
+            Some(optimistic) if !optimistic.requires_sequential(txs) => {
+                let outcome = optimistic.execute_block(txs, &self.state)?;
+                debug!(
+                    "Optimistic execution re-executed {} of {} transactions",
+                    outcome.conflicts,
+                    txs.len()
+                );
+                for (tx, result) in txs.iter_mut().zip(&outcome.results) {
+                    tx.set_status(result.status());
+                }
+                outcome.results
+            }
+            Some(optimistic) => {
+                let results = self.process_transactions(txs).await?;
+                optimistic.record_sequential_block();
+                results
+            }
+            None => self.process_transactions(txs).await?,
+        };
 
         // Verify all succeeded
         for (i, result) in results.iter().enumerate() {
@@ -251,13 +289,19 @@ impl TransactionEngine {
     pub fn get_config(&self) -> &TransactionEngineConfig {
         &self.config
     }
+
+    /// Optimistic execution counters, when optimistic execution is enabled
+    pub fn optimistic_metrics(&self) -> Option<OptimisticMetricsSnapshot> {
+        self.optimistic.as_ref().map(|optimistic| optimistic.metrics())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
-    use crate::ledger::transaction::TransactionType;
+    use crate::execution::executor::TRANSFER_SELECTOR;
+    use crate::ledger::transaction::{TransactionStatus, TransactionType};
 
     #[tokio::test]
     async fn test_transaction_engine() {
@@ -389,4 +433,95 @@ mod tests {
         assert_eq!(state.get_balance("recipient3").unwrap(), 3000);
         assert_eq!(state.get_nonce("sender").unwrap(), 3);
     }
+
+    fn signed(tx_type: TransactionType, recipient: &str, amount: u64, nonce: u64, data: Vec<u8>) -> Transaction {
+        let mut tx = Transaction::new(
+            tx_type,
+            "sender".to_string(),
+            recipient.to_string(),
+            amount,
+            nonce,
+            1,
+            21000,
+            data,
+        );
+        tx.signature = vec![1, 2, 3, 4];
+        tx
+    }
+
+    fn token_transfer(holder: [u8; 20], amount: u64) -> Vec<u8> {
+        let mut data = TRANSFER_SELECTOR.to_vec();
+        data.extend_from_slice(&[0u8; 12]);
+        data.extend_from_slice(&holder);
+        data.extend_from_slice(&[0u8; 24]);
+        data.extend_from_slice(&amount.to_be_bytes());
+        data
+    }
+
+    fn engine_with_state(optimistic: Option<OptimisticConfig>) -> (Arc<State>, TransactionEngine) {
+        let state = Arc::new(State::new(&Config::default()).unwrap());
+        state.set_balance("sender", 200000).unwrap();
+        state.set_storage("contract:token", vec![0x60, 0x80]).unwrap();
+        let engine = TransactionEngine::new(
+            state.clone(),
+            TransactionEngineConfig {
+                optimistic,
+                ..TransactionEngineConfig::default()
+            },
+        )
+        .unwrap();
+        (state, engine)
+    }
+
+    #[tokio::test]
+    async fn test_optimistic_block_application_matches_sequential() {
+        let holder = [0x11u8; 20];
+        let block = vec![
+            signed(TransactionType::Transfer, "recipient1", 1000, 0, vec![]),
+            signed(TransactionType::ContractCall, "token", 0, 1, token_transfer(holder, 5000)),
+            signed(TransactionType::Call, "missing", 0, 2, vec![]),
+            signed(TransactionType::Transfer, "recipient2", 2000, 2, vec![]),
+        ];
+
+        let (sequential_state, sequential) = engine_with_state(None);
+        sequential.apply_transactions_to_block(&mut block.clone(), 1).await.unwrap();
+
+        let (optimistic_state, optimistic) = engine_with_state(Some(OptimisticConfig::default()));
+        let mut txs = block.clone();
+        optimistic.apply_transactions_to_block(&mut txs, 1).await.unwrap();
+
+        let holder = hex::encode(holder);
+        for account in ["sender", "recipient1", "recipient2", holder.as_str()] {
+            assert_eq!(
+                optimistic_state.get_balance(account).unwrap(),
+                sequential_state.get_balance(account).unwrap()
+            );
+        }
+        assert_eq!(optimistic_state.get_nonce("sender").unwrap(), 3);
+        assert_eq!(optimistic_state.get_balance(&holder).unwrap(), 5000);
+        assert_eq!(optimistic_state.get_height().unwrap(), 1);
+        assert!(matches!(txs[2].status, TransactionStatus::Failed(_)));
+        assert!(matches!(txs[3].status, TransactionStatus::Success));
+
+        let metrics = optimistic.optimistic_metrics().unwrap();
+        assert_eq!(metrics.parallel_blocks, 1);
+        assert_eq!(metrics.sequential_blocks, 0);
+        assert!(sequential.optimistic_metrics().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_block_uses_sequential_path() {
+        let (state, engine) = engine_with_state(Some(OptimisticConfig::default()));
+        let mut txs = vec![
+            signed(TransactionType::Transfer, "recipient1", 1000, 0, vec![]),
+            signed(TransactionType::Stake, "sender", 5000, 1, vec![]),
+        ];
+        engine.apply_transactions_to_block(&mut txs, 1).await.unwrap();
+
+        assert_eq!(state.get_nonce("sender").unwrap(), 2);
+        assert!(state.get_storage("stake:sender").unwrap().is_some());
+        let metrics = engine.optimistic_metrics().unwrap();
+        assert_eq!(metrics.sequential_blocks, 1);
+        assert_eq!(metrics.parallel_blocks, 0);
+    }
 }
@@ -0,0 +1,228 @@
+//! Genesis Builder
+//! Validates a chain spec and derives the genesis block and initial state
+//! from it. Nothing here reads the clock or iterates a HashMap unordered, so
+//! the same spec always yields the same state root and genesis hash.
+
+use super::spec::{decode_hex, normalize_address, ChainSpec};
+use super::{ChainIdentity, GenesisError, CHAIN_ID_KEY, SPEC_HASH_KEY};
+use crate::ledger::block::{Block, BlockHeader, BlsPublicKey};
+use crate::ledger::state::State;
+use crate::types::Hash;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Spec a data directory was initialized from, next to `state.json`
+pub const SPEC_FILE: &str = "chain_spec.json";
+
+/// A built genesis: the canonical spec, its hash, the genesis block and the
+/// state every node starts from
+#[derive(Debug, Clone)]
+pub struct Genesis {
+    pub spec: ChainSpec,
+    pub spec_hash: Hash,
+    pub block: Block,
+    pub state_root: Hash,
+    balances: HashMap<String, u64>,
+    storage: HashMap<String, Vec<u8>>,
+}
+
+fn check_unique(section: &'static str, addresses: impl IntoIterator<Item = String>) -> Result<(), GenesisError> {
+    let mut seen = HashSet::new();
+    for address in addresses {
+        if !seen.insert(address.clone()) {
+            return Err(GenesisError::DuplicateAddress { section, address });
+        }
+    }
+    Ok(())
+}
+
+/// Reject specs two nodes could disagree about or that mint more than the cap
+pub fn validate(spec: &ChainSpec) -> Result<(), GenesisError> {
+    if spec.chain_id == 0 {
+        return Err(GenesisError::InvalidParam("chain_id must be non-zero"));
+    }
+    if spec.params.block_time_ms == 0 {
+        return Err(GenesisError::InvalidParam("block_time_ms must be non-zero"));
+    }
+    if spec.params.gas_limit == 0 {
+        return Err(GenesisError::InvalidParam("gas_limit must be non-zero"));
+    }
+
+    let balances = spec
+        .balances
+        .iter()
+        .map(|a| normalize_address("balances", &a.address))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_unique("balances", balances)?;
+
+    let contracts = spec
+        .contracts
+        .iter()
+        .map(|c| normalize_address("contracts", &c.address))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_unique("contracts", contracts.clone())?;
+    for contract in &spec.contracts {
+        if decode_hex(&format!("contracts.{}.code", contract.address), &contract.code)?.is_empty() {
+            return Err(GenesisError::EmptyContractCode(contract.address.clone()));
+        }
+        for (key, value) in &contract.storage {
+            decode_hex(&format!("contracts.{}.storage", contract.address), key)?;
+            decode_hex(&format!("contracts.{}.storage", contract.address), value)?;
+        }
+    }
+
+    if spec.validators.is_empty() {
+        return Err(GenesisError::NoValidators);
+    }
+    let validators = spec
+        .validators
+        .iter()
+        .map(|v| normalize_address("validators", &v.address))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_unique("validators", validators)?;
+    for validator in &spec.validators {
+        if validator.stake == 0 {
+            return Err(GenesisError::ZeroStake(validator.address.clone()));
+        }
+        let key = decode_hex(&format!("validators.{}.bls_public_key", validator.address), &validator.bls_public_key)?;
+        if key.len() != 48 {
+            return Err(GenesisError::InvalidValidatorKey(validator.address.clone()));
+        }
+    }
+
+    for (name, address) in &spec.ai_contracts {
+        let address = normalize_address("ai_contracts", address)?;
+        if !contracts.contains(&address) {
+            return Err(GenesisError::UnknownRegistryContract { name: name.clone(), address });
+        }
+    }
+
+    let total: u128 = spec.balances.iter().map(|a| a.balance as u128).sum::<u128>()
+        + spec.validators.iter().map(|v| v.stake as u128).sum::<u128>();
+    if total > spec.supply_cap as u128 {
+        return Err(GenesisError::SupplyCapExceeded { total, cap: spec.supply_cap });
+    }
+    Ok(())
+}
+
+impl Genesis {
+    /// Validate `spec` and derive the genesis block and state
+    pub fn build(spec: &ChainSpec) -> Result<Self, GenesisError> {
+        validate(spec)?;
+        let spec = spec.canonical();
+        let spec_hash = spec.hash();
+
+        // Canonical hex is known-good after validation
+        let hex = |value: &str| decode_hex("", value).unwrap_or_default();
+        let balances: HashMap<String, u64> =
+            spec.balances.iter().map(|a| (a.address.clone(), a.balance)).collect();
+
+        let mut storage: HashMap<String, Vec<u8>> = HashMap::new();
+        storage.insert(CHAIN_ID_KEY.to_string(), spec.chain_id.to_le_bytes().to_vec());
+        storage.insert(SPEC_HASH_KEY.to_string(), spec_hash.to_bytes());
+        storage.insert("system:block_time_ms".to_string(), spec.params.block_time_ms.to_le_bytes().to_vec());
+        storage.insert("system:gas_limit".to_string(), spec.params.gas_limit.to_le_bytes().to_vec());
+
+        // Same layout TransactionExecutor uses for deployed contracts
+        for contract in &spec.contracts {
+            storage.insert(format!("contract:{}", contract.address), hex(&contract.code));
+            storage.insert(format!("contract_creator:{}", contract.address), b"genesis".to_vec());
+            for (key, value) in &contract.storage {
+                storage.insert(format!("contract_storage:{}:{}", contract.address, key), hex(value));
+            }
+        }
+
+        // Stakes and the validator list as the staking and system paths store them
+        let mut validator_list = String::new();
+        for validator in &spec.validators {
+            storage.insert(format!("stake:{}", validator.address), validator.stake.to_le_bytes().to_vec());
+            storage.insert(format!("validator_bls:{}", validator.address), hex(&validator.bls_public_key));
+            validator_list.push_str(&format!("{}:", validator.address));
+        }
+        storage.insert("system:validators".to_string(), validator_list.into_bytes());
+
+        for (name, address) in &spec.ai_contracts {
+            storage.insert(format!("ai_registry:{}", name), address.as_bytes().to_vec());
+        }
+
+        let state_root = State::compute_root(&balances, &storage);
+
+        // Genesis has no parent, so its parent hash commits to the spec
+        let block = Block {
+            header: BlockHeader {
+                version: 1,
+                previous_hash: spec_hash.clone(),
+                merkle_root: Block::calculate_merkle_root(&[]).map_err(|e| GenesisError::Storage(e.to_string()))?,
+                timestamp: spec.genesis_time,
+                height: 0,
+                producer: BlsPublicKey::default(),
+                nonce: 0,
+                difficulty: 1,
+            },
+            transactions: Vec::new(),
+            signature: None,
+        };
+
+        Ok(Self { spec, spec_hash, block, state_root, balances, storage })
+    }
+
+    pub fn hash(&self) -> Hash {
+        self.block.hash().unwrap_or_default()
+    }
+
+    pub fn identity(&self) -> ChainIdentity {
+        ChainIdentity {
+            chain_id: self.spec.chain_id,
+            spec_hash: self.spec_hash.to_evm_hex(),
+            genesis_hash: self.hash().to_evm_hex(),
+        }
+    }
+
+    /// Write the genesis state and block into a freshly opened `state`
+    fn apply(&self, state: &State) -> Result<(), GenesisError> {
+        let storage_err = |e: anyhow::Error| GenesisError::Storage(e.to_string());
+        *state.balances.write().unwrap() = self.balances.clone();
+        *state.nonces.write().unwrap() = HashMap::new();
+        *state.storage.write().unwrap() = self.storage.clone();
+        state.contracts.write().unwrap().clear();
+        state.set_height(0).map_err(storage_err)?;
+        state.add_block(self.block.clone()).map_err(storage_err)?;
+        state.set_latest_block_hash(&self.hash().to_evm_hex()).map_err(storage_err)?;
+        state.save_state().map_err(storage_err)
+    }
+
+    /// Initialize `data_dir` from this genesis. A directory that already
+    /// holds a chain is only overwritten when `force` is set.
+    pub fn initialize(&self, data_dir: &Path, force: bool) -> Result<State, GenesisError> {
+        let io_err = |e: std::io::Error| GenesisError::Storage(format!("{}: {}", data_dir.display(), e));
+        let spec_path = data_dir.join(SPEC_FILE);
+        let state_path = data_dir.join("state.json");
+        if spec_path.exists() || state_path.exists() {
+            if !force {
+                return Err(GenesisError::AlreadyInitialized(data_dir.to_path_buf()));
+            }
+            for path in [&spec_path, &state_path] {
+                if path.exists() {
+                    fs::remove_file(path).map_err(io_err)?;
+                }
+            }
+        }
+        fs::create_dir_all(data_dir).map_err(io_err)?;
+
+        let state = State::open(&data_dir.to_string_lossy()).map_err(|e| GenesisError::Storage(e.to_string()))?;
+        self.apply(&state)?;
+        let spec = serde_json::to_vec_pretty(&self.spec).map_err(|e| GenesisError::Storage(e.to_string()))?;
+        fs::write(&spec_path, spec).map_err(io_err)?;
+        Ok(state)
+    }
+
+    /// The genesis a data directory was initialized from, if any
+    pub fn load(data_dir: &Path) -> Result<Option<Self>, GenesisError> {
+        let spec_path = data_dir.join(SPEC_FILE);
+        if !spec_path.exists() {
+            return Ok(None);
+        }
+        Self::build(&ChainSpec::from_file(&spec_path)?).map(Some)
+    }
+}
@@ -0,0 +1,127 @@
+//! Genesis
+//! Builds a network's first block and initial state from a shared chain spec,
+//! so every operator starting "the same chain" ends up with the same state
+//! root. The spec hash is committed in the genesis header and carried in peer
+//! announcements; nodes started from different specs refuse to peer.
+
+pub mod builder;
+pub mod spec;
+
+pub use builder::{Genesis, SPEC_FILE};
+pub use spec::{Allocation, ChainParams, ChainSpec, GenesisContract, GenesisValidator};
+
+use crate::ledger::state::State;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Storage keys written at genesis
+pub const CHAIN_ID_KEY: &str = "system:chain_id";
+pub const SPEC_HASH_KEY: &str = "genesis:spec_hash";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum GenesisError {
+    #[error("Failed to parse chain spec: {0}")]
+    Parse(String),
+
+    #[error("Invalid chain parameter: {0}")]
+    InvalidParam(&'static str),
+
+    #[error("Invalid address in {section}: {address}")]
+    InvalidAddress { section: &'static str, address: String },
+
+    #[error("Duplicate address in {section}: {address}")]
+    DuplicateAddress { section: &'static str, address: String },
+
+    #[error("Invalid hex in {field}: {value}")]
+    InvalidHex { field: String, value: String },
+
+    #[error("Contract {0} has no code")]
+    EmptyContractCode(String),
+
+    #[error("Chain spec declares no validators")]
+    NoValidators,
+
+    #[error("Validator {0} has zero stake")]
+    ZeroStake(String),
+
+    #[error("Validator {0} BLS public key must be 48 bytes")]
+    InvalidValidatorKey(String),
+
+    #[error("AI registry entry {name} points at {address}, which is not a genesis contract")]
+    UnknownRegistryContract { name: String, address: String },
+
+    #[error("Genesis supply {total} exceeds the supply cap {cap}")]
+    SupplyCapExceeded { total: u128, cap: u64 },
+
+    #[error("Chain id mismatch: local {local}, peer {remote}")]
+    ChainIdMismatch { local: u64, remote: u64 },
+
+    #[error("Chain spec mismatch: local {local}, peer {remote}")]
+    SpecMismatch { local: String, remote: String },
+
+    #[error("Genesis block mismatch: local {local}, peer {remote}")]
+    GenesisMismatch { local: String, remote: String },
+
+    #[error("Peer announced no chain identity")]
+    MissingChainIdentity,
+
+    #[error("Data directory {0} is already initialized; pass --force to re-initialize")]
+    AlreadyInitialized(PathBuf),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+/// What a node announces to prove it runs the same chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainIdentity {
+    pub chain_id: u64,
+    pub spec_hash: String,
+    pub genesis_hash: String,
+}
+
+impl ChainIdentity {
+    /// Identity of a state initialized from a chain spec; None for states
+    /// created before chain specs existed
+    pub fn from_state(state: &State) -> Option<Self> {
+        let chain_id = state.get_storage(CHAIN_ID_KEY).ok()??;
+        let spec_hash = state.get_storage(SPEC_HASH_KEY).ok()??;
+        let genesis = state.get_block_by_height(0)?;
+        Some(Self {
+            chain_id: u64::from_le_bytes(chain_id.as_slice().try_into().ok()?),
+            spec_hash: format!("0x{}", hex::encode(spec_hash)),
+            genesis_hash: genesis.hash().ok()?.to_evm_hex(),
+        })
+    }
+
+    /// Peers must share the chain id, the spec and the genesis block
+    pub fn check_peer(&self, remote: &ChainIdentity) -> Result<(), GenesisError> {
+        if self.chain_id != remote.chain_id {
+            return Err(GenesisError::ChainIdMismatch { local: self.chain_id, remote: remote.chain_id });
+        }
+        if self.spec_hash != remote.spec_hash {
+            return Err(GenesisError::SpecMismatch {
+                local: self.spec_hash.clone(),
+                remote: remote.spec_hash.clone(),
+            });
+        }
+        if self.genesis_hash != remote.genesis_hash {
+            return Err(GenesisError::GenesisMismatch {
+                local: self.genesis_hash.clone(),
+                remote: remote.genesis_hash.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Peering rule: a node without a spec peers with anyone; a node started
+    /// from a spec only peers with nodes that announce the same one
+    pub fn admits(local: Option<&ChainIdentity>, remote: Option<&ChainIdentity>) -> Result<(), GenesisError> {
+        match (local, remote) {
+            (None, _) => Ok(()),
+            (Some(_), None) => Err(GenesisError::MissingChainIdentity),
+            (Some(local), Some(remote)) => local.check_peer(remote),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Chain Spec
+//! The file operators share to start a network: chain id, initial balances,
+//! pre-deployed contracts, the initial validator set, chain parameters and
+//! the AI-platform contract registry. Read from TOML or JSON; the canonical
+//! form, and so the spec hash, is the same for either.
+
+use super::GenesisError;
+use crate::types::Hash;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    pub name: String,
+    /// Unix seconds stamped on the genesis header, so every build is identical
+    pub genesis_time: u64,
+    /// Upper bound on initial balances plus validator stakes
+    pub supply_cap: u64,
+    pub params: ChainParams,
+    #[serde(default)]
+    pub balances: Vec<Allocation>,
+    #[serde(default)]
+    pub contracts: Vec<GenesisContract>,
+    pub validators: Vec<GenesisValidator>,
+    /// AI-platform contract addresses by registry name, e.g. `ai_job_manager`
+    #[serde(default)]
+    pub ai_contracts: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainParams {
+    pub block_time_ms: u64,
+    pub gas_limit: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Allocation {
+    pub address: String,
+    pub balance: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisContract {
+    pub address: String,
+    /// Hex bytecode
+    pub code: String,
+    /// Hex storage key -> hex value
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisValidator {
+    pub address: String,
+    pub stake: u64,
+    /// Hex, 48 bytes
+    pub bls_public_key: String,
+}
+
+/// `0x` + 40 lowercase hex digits
+pub fn normalize_address(section: &'static str, address: &str) -> Result<String, GenesisError> {
+    let digits = address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")).unwrap_or(address);
+    if digits.len() != 40 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(GenesisError::InvalidAddress { section, address: address.to_string() });
+    }
+    Ok(format!("0x{}", digits.to_ascii_lowercase()))
+}
+
+pub fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, GenesisError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(digits).map_err(|_| GenesisError::InvalidHex { field: field.to_string(), value: value.to_string() })
+}
+
+/// Lowercase hex with a `0x` prefix; unparseable input is left for validation
+fn normalize_hex(value: &str) -> String {
+    format!("0x{}", value.strip_prefix("0x").unwrap_or(value).to_ascii_lowercase())
+}
+
+fn normalize_lenient(address: &str) -> String {
+    normalize_address("", address).unwrap_or_else(|_| address.to_string())
+}
+
+impl ChainSpec {
+    /// Read a spec; `.toml` files are TOML, anything else JSON
+    pub fn from_file(path: &Path) -> Result<Self, GenesisError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| GenesisError::Parse(format!("{}: {}", path.display(), e)))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            _ => Self::from_json(&text),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, GenesisError> {
+        toml::from_str(text).map_err(|e| GenesisError::Parse(e.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, GenesisError> {
+        serde_json::from_str(text).map_err(|e| GenesisError::Parse(e.to_string()))
+    }
+
+    /// The spec with addresses and hex normalized and every list sorted by
+    /// address, so equivalent files produce identical bytes
+    pub fn canonical(&self) -> Self {
+        let mut spec = self.clone();
+        for allocation in &mut spec.balances {
+            allocation.address = normalize_lenient(&allocation.address);
+        }
+        for contract in &mut spec.contracts {
+            contract.address = normalize_lenient(&contract.address);
+            contract.code = normalize_hex(&contract.code);
+            contract.storage = contract.storage.iter().map(|(k, v)| (normalize_hex(k), normalize_hex(v))).collect();
+        }
+        for validator in &mut spec.validators {
+            validator.address = normalize_lenient(&validator.address);
+            validator.bls_public_key = normalize_hex(&validator.bls_public_key);
+        }
+        for address in spec.ai_contracts.values_mut() {
+            *address = normalize_lenient(address);
+        }
+        spec.balances.sort_by(|a, b| a.address.cmp(&b.address));
+        spec.contracts.sort_by(|a, b| a.address.cmp(&b.address));
+        spec.validators.sort_by(|a, b| a.address.cmp(&b.address));
+        spec
+    }
+
+    /// Compact JSON of the canonical spec
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.canonical()).unwrap_or_default()
+    }
+
+    /// Hash peers compare before syncing
+    pub fn hash(&self) -> Hash {
+        Hash::new(blake3::hash(&self.canonical_bytes()).as_bytes().to_vec())
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Number of recent finalization latencies kept for reporting
+const LATENCY_WINDOW: usize = 100;
+
+/// Finality summary reported by `GET /finality`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FinalityStatus {
+    /// Highest block height finalized by consensus
+    pub finalized_height: u64,
+    /// Current canonical chain head
+    pub head_height: u64,
+    /// Blocks between the finalized height and the head
+    pub finality_gap: u64,
+    /// Average time from block production to finalization (ms)
+    pub average_latency_ms: u64,
+    /// Most recent finalization latencies, oldest first (ms)
+    pub recent_latency_ms: Vec<u64>,
+    /// Non-finalized blocks removed from the canonical chain by reorgs
+    pub reorg_demotions: u64,
+    /// Blocks rejected because they conflicted with a finalized block
+    pub rejected_finalized_reorgs: u64,
+}
+
+/// Canonical chain index with consensus finality.
+///
+/// Tracks the canonical block hash per height, the finalized height published
+/// by consensus, and production times for latency reporting. Finalized blocks
+/// can never be replaced; competing blocks above the finalized height demote
+/// the old fork from that height upwards.
+#[derive(Debug, Default)]
+pub struct FinalityTracker {
+    /// Canonical block hash and production time (ms) by height
+    canonical: BTreeMap<u64, (String, u64)>,
+    /// Highest finalized height, if consensus has finalized anything yet
+    finalized_height: Option<u64>,
+    /// Recent production-to-finalization latencies (ms)
+    latencies: VecDeque<u64>,
+    /// Demoted block count
+    reorg_demotions: u64,
+    /// Rejected conflicting blocks at finalized heights
+    rejected_reorgs: u64,
+}
+
+impl FinalityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild the index from persisted blocks. Production times are unknown,
+    /// so restored blocks do not contribute to latency reporting.
+    pub fn restore(&mut self, blocks: impl IntoIterator<Item = (u64, String)>, finalized_height: Option<u64>) {
+        self.canonical = blocks.into_iter().map(|(height, hash)| (height, (hash, 0))).collect();
+        self.finalized_height = finalized_height.and_then(|f| self.head().map(|head| f.min(head)));
+    }
+
+    /// Record a block on the canonical chain. Returns the heights of blocks
+    /// demoted by a reorg, which the caller must remove from its block store.
+    pub fn accept_block(&mut self, height: u64, hash: &str, now_ms: u64) -> Result<Vec<u64>> {
+        match self.canonical.get(&height) {
+            Some((existing, _)) if existing == hash => return Ok(Vec::new()),
+            Some(_) if self.is_finalized(height) => {
+                self.rejected_reorgs += 1;
+                return Err(anyhow!(
+                    "Block {} conflicts with finalized block at height {}",
+                    hash,
+                    height
+                ));
+            }
+            _ => {}
+        }
+
+        // Nothing at or below the finalized height may be replaced, even if it
+        // was pruned from the index
+        if self.is_finalized(height) && !self.canonical.contains_key(&height) {
+            self.rejected_reorgs += 1;
+            return Err(anyhow!("Height {} is already finalized", height));
+        }
+
+        let demoted: Vec<u64> = if self.canonical.contains_key(&height) {
+            self.canonical.range(height..).map(|(h, _)| *h).collect()
+        } else {
+            Vec::new()
+        };
+        for h in &demoted {
+            self.canonical.remove(h);
+        }
+        self.reorg_demotions += demoted.len() as u64;
+
+        self.canonical.insert(height, (hash.to_string(), now_ms));
+        Ok(demoted)
+    }
+
+    /// Advance the finalized height. Finality never moves backwards and never
+    /// passes the canonical head. Returns the number of newly finalized blocks.
+    pub fn finalize(&mut self, height: u64, now_ms: u64) -> u64 {
+        let Some(head) = self.head() else { return 0 };
+        let target = height.min(head);
+        let from = match self.finalized_height {
+            Some(current) if current >= target => return 0,
+            Some(current) => current + 1,
+            None => 0,
+        };
+
+        let mut newly_finalized = 0;
+        for (_, (_, produced_at)) in self.canonical.range(from..=target) {
+            newly_finalized += 1;
+            if *produced_at > 0 {
+                self.latencies.push_back(now_ms.saturating_sub(*produced_at));
+                if self.latencies.len() > LATENCY_WINDOW {
+                    self.latencies.pop_front();
+                }
+            }
+        }
+        self.finalized_height = Some(target);
+        newly_finalized
+    }
+
+    pub fn head(&self) -> Option<u64> {
+        self.canonical.keys().next_back().copied()
+    }
+
+    pub fn finalized_height(&self) -> Option<u64> {
+        self.finalized_height
+    }
+
+    pub fn is_finalized(&self, height: u64) -> bool {
+        self.finalized_height.is_some_and(|f| height <= f)
+    }
+
+    /// Canonical hash at a height, if known
+    pub fn canonical_hash(&self, height: u64) -> Option<&str> {
+        self.canonical.get(&height).map(|(hash, _)| hash.as_str())
+    }
+
+    /// Blocks on top of (and including) the block at `height`
+    pub fn confirmations(&self, height: u64) -> u64 {
+        match self.head() {
+            Some(head) if self.canonical.contains_key(&height) && height <= head => head - height + 1,
+            _ => 0,
+        }
+    }
+
+    pub fn status(&self) -> FinalityStatus {
+        let head_height = self.head().unwrap_or(0);
+        let finalized_height = self.finalized_height.unwrap_or(0);
+        let recent_latency_ms: Vec<u64> = self.latencies.iter().copied().collect();
+        let average_latency_ms = if recent_latency_ms.is_empty() {
+            0
+        } else {
+            recent_latency_ms.iter().sum::<u64>() / recent_latency_ms.len() as u64
+        };
+
+        FinalityStatus {
+            finalized_height,
+            head_height,
+            finality_gap: head_height.saturating_sub(finalized_height),
+            average_latency_ms,
+            recent_latency_ms,
+            reorg_demotions: self.reorg_demotions,
+            rejected_finalized_reorgs: self.rejected_reorgs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn hash(fork: u8, height: u64) -> String {
+        format!("fork{}-{}", fork, height)
+    }
+
+    #[test]
+    fn test_confirmations_across_reorg() {
+        let mut tracker = FinalityTracker::new();
+        for h in 0..=5 {
+            tracker.accept_block(h, &hash(0, h), 1_000 + h).unwrap();
+        }
+        tracker.finalize(2, 2_000);
+        assert_eq!(tracker.confirmations(3), 3);
+        assert_eq!(tracker.confirmations(5), 1);
+
+        // A competing block at height 4 demotes blocks 4 and 5 of the old fork
+        let demoted = tracker.accept_block(4, &hash(1, 4), 3_000).unwrap();
+        assert_eq!(demoted, vec![4, 5]);
+        assert_eq!(tracker.head(), Some(4));
+        assert_eq!(tracker.confirmations(3), 2);
+        assert_eq!(tracker.confirmations(5), 0);
+        assert_eq!(tracker.canonical_hash(4), Some(hash(1, 4).as_str()));
+
+        tracker.accept_block(5, &hash(1, 5), 3_100).unwrap();
+        tracker.accept_block(6, &hash(1, 6), 3_200).unwrap();
+        assert_eq!(tracker.confirmations(3), 4);
+
+        let status = tracker.status();
+        assert_eq!(status.reorg_demotions, 2);
+        assert_eq!(status.finalized_height, 2);
+        assert_eq!(status.finality_gap, 4);
+    }
+
+    #[test]
+    fn test_finalized_block_is_never_replaced() {
+        let mut tracker = FinalityTracker::new();
+        for h in 0..=3 {
+            tracker.accept_block(h, &hash(0, h), 100).unwrap();
+        }
+        assert_eq!(tracker.finalize(2, 400), 3);
+        assert_eq!(tracker.status().average_latency_ms, 300);
+
+        assert!(tracker.accept_block(2, &hash(1, 2), 500).is_err());
+        assert!(tracker.accept_block(1, &hash(1, 1), 500).is_err());
+        assert_eq!(tracker.canonical_hash(2), Some(hash(0, 2).as_str()));
+        assert_eq!(tracker.head(), Some(3));
+        assert_eq!(tracker.status().rejected_finalized_reorgs, 2);
+
+        // Re-announcing the finalized block is harmless
+        assert!(tracker.accept_block(2, &hash(0, 2), 500).unwrap().is_empty());
+        // Finality never moves backwards or past the head
+        assert_eq!(tracker.finalize(1, 600), 0);
+        assert_eq!(tracker.finalize(10, 600), 1);
+        assert_eq!(tracker.finalized_height(), Some(3));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_finalized_blocks_never_reorged(
+            ops in prop::collection::vec((0u64..20, 0u8..3, any::<bool>()), 1..200)
+        ) {
+            let mut tracker = FinalityTracker::new();
+            let mut finalized: BTreeMap<u64, String> = BTreeMap::new();
+
+            for (i, (height, fork, finalize)) in ops.into_iter().enumerate() {
+                if finalize {
+                    tracker.finalize(height, i as u64);
+                    if let Some(f) = tracker.finalized_height() {
+                        for h in 0..=f {
+                            if let Some(hash) = tracker.canonical_hash(h) {
+                                finalized.entry(h).or_insert_with(|| hash.to_string());
+                            }
+                        }
+                    }
+                } else {
+                    let _ = tracker.accept_block(height, &hash(fork, height), i as u64);
+                }
+
+                for (h, hash) in &finalized {
+                    prop_assert_eq!(tracker.canonical_hash(*h), Some(hash.as_str()));
+                }
+                if let (Some(f), Some(head)) = (tracker.finalized_height(), tracker.head()) {
+                    prop_assert!(f <= head);
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,19 @@
 pub mod checkpoint;
+pub mod finality;
 pub mod storage;
 pub mod tree;
 pub mod integrity; // Self-healing integrity manager
+pub mod proof; // Account proofs against the state root
 
 use crate::config::Config;
+use crate::events::{Event, EventBus};
 use crate::ledger::block::Block;
-use crate::ledger::transaction::Transaction;
+use crate::ledger::state::finality::{FinalityStatus, FinalityTracker};
+use crate::ledger::state::proof::AccountProof;
+use crate::ledger::transaction::{Transaction, TransactionReceipt};
 use crate::types::Hash;
 use anyhow::{anyhow, Result};
+use artha_clock::SharedClock;
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 
@@ -15,7 +21,6 @@ use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
-use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::{broadcast, Mutex as TokioMutex};
 
 /// Interface for sharding configuration
@@ -122,6 +127,15 @@ pub struct State {
     /// Latest block hash
     latest_block_hash: RwLock<String>,
 
+    /// Canonical chain index and consensus finality
+    finality: RwLock<FinalityTracker>,
+
+    /// Internal event bus, when the node runs one
+    events: RwLock<Option<EventBus>>,
+
+    /// Source of block acceptance, finality and transaction timestamps
+    clock: SharedClock,
+
     // 🛡️ SPOF ELIMINATION: Distributed Stae Management
     /// State replicas for redundancy (SPOF FIX #1)
     state_replicas: Arc<RwLock<Vec<StateReplica>>>,
@@ -137,8 +151,13 @@ pub struct State {
 
 impl State {
     pub fn new(_config: &Config) -> Result<Self> {
+        Self::open("data/blockchain")
+    }
+
+    /// Open the state persisted in `data_dir`, starting empty if there is none
+    pub fn open(data_dir: &str) -> Result<Self> {
         let (sync_sender, _) = broadcast::channel(1000);
-        let data_dir = "data/blockchain".to_string();
+        let data_dir = data_dir.to_string();
 
         // Create data directory if it doesn't exist
         if !Path::new(&data_dir).exists() {
@@ -162,6 +181,9 @@ impl State {
             latest_block_hash: RwLock::new(
                 "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
             ),
+            finality: RwLock::new(FinalityTracker::new()),
+            events: RwLock::new(None),
+            clock: artha_clock::system_clock(),
 
             // 🛡️ SPOF ELIMINATION: Initialize distributed state
             state_replicas: Arc::new(RwLock::new(Vec::new())),
@@ -192,11 +214,37 @@ impl State {
 
     /// Set account balance
     pub fn set_balance(&self, address: &str, amount: u64) -> Result<()> {
-        let mut balances = self.balances.write().unwrap();
-        balances.insert(address.to_string(), amount);
+        let old = {
+            let mut balances = self.balances.write().unwrap();
+            balances.insert(address.to_string(), amount).unwrap_or(0)
+        };
+        if old != amount {
+            self.emit(Event::BalanceChanged {
+                address: address.to_string(),
+                old,
+                new: amount,
+            });
+        }
         Ok(())
     }
 
+    /// Run on `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish state and consensus events to `bus` from now on
+    pub fn attach_event_bus(&self, bus: EventBus) {
+        *self.events.write().unwrap() = Some(bus);
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(bus) = self.events.read().unwrap().as_ref() {
+            bus.emit(event);
+        }
+    }
+
     /// Get account nonce
     pub fn get_nonce(&self, address: &str) -> Result<u64> {
         let nonces = self.nonces.read().unwrap();
@@ -324,6 +372,11 @@ impl State {
         Ok(snapshot_id)
     }
 
+    /// Number of snapshots that have been created but not yet committed or reverted
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.read().unwrap().len()
+    }
+
     /// Commit a state snapshot (remove it as it's no longer needed)
     pub fn commit_snapshot(&self, snapshot_id: u64) -> Result<()> {
         debug!("Committing snapshot with ID: {}", snapshot_id);
@@ -399,9 +452,24 @@ impl State {
 
     /// Add a block to the state
     pub fn add_block(&self, block: Block) -> Result<()> {
+        self.add_block_with_receipts(block, Vec::new())
+    }
+
+    /// Add a block along with the receipts of its transactions, which are
+    /// published to the event bus for contract log matching
+    pub fn add_block_with_receipts(&self, block: Block, receipts: Vec<TransactionReceipt>) -> Result<()> {
         let height = block.header.height;
         let hash = block.hash()?.to_evm_hex();
 
+        let reimport = self.finality.read().unwrap().canonical_hash(height) == Some(hash.as_str());
+
+        // Fork choice: blocks conflicting with finalized ones are rejected,
+        // non-finalized blocks of the losing fork are demoted
+        let demoted = self.finality.write().unwrap().accept_block(height, &hash, self.clock.now_millis())?;
+        if !demoted.is_empty() {
+            self.demote_blocks(&demoted)?;
+        }
+
         // Add to blocks by height
         {
             let mut blocks = self.blocks.write().unwrap();
@@ -426,9 +494,80 @@ impl State {
             warn!("Failed to save state: {}", e);
         }
 
+        if !reimport {
+            self.emit(Event::BlockImported { height, hash, receipts });
+        }
+
         Ok(())
     }
 
+    /// Remove blocks of a losing fork and rewind the head below them
+    fn demote_blocks(&self, heights: &[u64]) -> Result<()> {
+        let Some(&lowest) = heights.iter().min() else { return Ok(()) };
+
+        {
+            let mut blocks = self.blocks.write().unwrap();
+            let mut blocks_by_hash = self.blocks_by_hash.write().unwrap();
+            for height in heights {
+                if let Some(block) = blocks.remove(height) {
+                    blocks_by_hash.remove(&block.hash()?.to_evm_hex());
+                }
+            }
+        }
+
+        let new_head = lowest.saturating_sub(1);
+        self.set_height(new_head)?;
+        if let Some(block) = self.get_block_by_height(new_head) {
+            self.set_latest_block_hash(&block.hash()?.to_evm_hex())?;
+        }
+
+        warn!("Reorg at height {}: demoted {} non-finalized blocks", lowest, heights.len());
+        Ok(())
+    }
+
+    /// Publish the height finalized by consensus. Returns the number of
+    /// blocks newly finalized.
+    pub fn mark_finalized(&self, height: u64) -> Result<u64> {
+        let mut finality = self.finality.write().unwrap();
+        let previous = finality.finalized_height();
+        let newly_finalized = finality.finalize(height, self.clock.now_millis());
+        if newly_finalized > 0 {
+            debug!("Finalized up to height {} ({} new blocks)", height, newly_finalized);
+
+            let from = previous.map_or(0, |h| h + 1);
+            let to = finality.finalized_height().unwrap_or(height);
+            for h in from..=to {
+                if let Some(hash) = finality.canonical_hash(h) {
+                    self.emit(Event::BlockFinalized {
+                        height: h,
+                        hash: hash.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(newly_finalized)
+    }
+
+    /// Highest finalized block height, if any
+    pub fn finalized_height(&self) -> Option<u64> {
+        self.finality.read().unwrap().finalized_height()
+    }
+
+    /// Whether the block at `height` has been finalized by consensus
+    pub fn is_block_finalized(&self, height: u64) -> bool {
+        self.finality.read().unwrap().is_finalized(height)
+    }
+
+    /// Confirmations for the canonical block at `height`
+    pub fn confirmations(&self, height: u64) -> u64 {
+        self.finality.read().unwrap().confirmations(height)
+    }
+
+    /// Finalized head, gap to chain head, latency and reorg counters
+    pub fn finality_status(&self) -> FinalityStatus {
+        self.finality.read().unwrap().status()
+    }
+
     /// Get the latest block
     pub fn latest_block(&self) -> Option<Block> {
         let height = match self.get_height() {
@@ -558,7 +697,7 @@ impl State {
                         gas_limit: 21000, // Default gas limit
                         data: block_tx.data.clone(),
                         signature: block_tx.signature.as_ref().map(|s| s.as_ref().to_vec()).unwrap_or_default(),
-                        timestamp: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+                        timestamp: self.clock.now_secs(),
                         status: crate::ledger::transaction::TransactionStatus::Confirmed,
                     };
                     
@@ -683,12 +822,7 @@ impl State {
     pub fn get_validator_last_block_time(&self, address: &Vec<u8>) -> Option<u64> {
         // For now, return current timestamp
         // In a full implementation, this would be retrieved from block storage
-        Some(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        )
+        Some(self.clock.now_secs())
     }
 
     /// Get the current difficulty level
@@ -777,32 +911,52 @@ impl State {
 
     /// Get current state root hash
     pub fn get_state_root(&self) -> Result<Hash> {
-        // Calculate state root from current state
-        // This is a simplified version - in production, you'd use a Merkle tree
-        let mut hasher = blake3::Hasher::new();
-
-        // Hash accounts
-        let accounts = self
+        let balances = self
             .balances
             .read()
             .map_err(|e| anyhow!("Failed to acquire read lock: {}", e))?;
-        for (address, balance) in accounts.iter() {
-            hasher.update(address.as_ref());
-            hasher.update(&balance.to_le_bytes());
-        }
+        let storage = self
+            .storage
+            .read()
+            .map_err(|e| anyhow!("Failed to acquire read lock: {}", e))?;
+        Ok(Self::compute_root(&balances, &storage))
+    }
 
-        // Hash storage
+    /// State root over balances and storage: the root of their Merkle
+    /// Patricia trie (see [`proof`]), zero for an empty state
+    pub fn compute_root(balances: &HashMap<String, u64>, storage: &HashMap<String, Vec<u8>>) -> Hash {
+        let root = proof::state_trie(balances, storage)
+            .expect("a freshly built trie holds every node it references")
+            .root_hash();
+        Hash::new(root.as_bytes().to_vec())
+    }
+
+    /// Merkle proof of `address`'s balance against the current state root
+    pub fn get_account_proof(&self, address: &str) -> Result<AccountProof> {
+        let balances = self
+            .balances
+            .read()
+            .map_err(|e| anyhow!("Failed to acquire read lock: {}", e))?;
         let storage = self
             .storage
             .read()
             .map_err(|e| anyhow!("Failed to acquire read lock: {}", e))?;
-        for (key, value) in storage.iter() {
-            hasher.update(key.as_ref());
-            hasher.update(value);
-        }
+        let balance = *balances
+            .get(address)
+            .ok_or_else(|| anyhow!("Account {} not found", address))?;
+
+        let trie = proof::state_trie(&balances, &storage)?;
+        Ok(AccountProof {
+            address: address.to_string(),
+            balance,
+            state_root: Hash::new(trie.root_hash().as_bytes().to_vec()),
+            nodes: trie.prove(&proof::account_key(address))?,
+        })
+    }
 
-        let hash_bytes = hasher.finalize();
-        Ok(Hash::new(hash_bytes.as_bytes().to_vec()))
+    /// Directory the state is persisted to
+    pub fn data_dir(&self) -> &str {
+        &self.data_dir
     }
 
     /// Get transaction by hash
@@ -832,6 +986,7 @@ impl State {
             blocks: self.blocks.read().unwrap().clone(),
             blocks_by_hash: self.blocks_by_hash.read().unwrap().clone(),
             latest_block_hash: self.latest_block_hash.read().unwrap().clone(),
+            finalized_height: self.finalized_height(),
         };
 
         let data = serde_json::to_vec(&state_data)?;
@@ -858,6 +1013,14 @@ impl State {
         *self.blocks.write().unwrap() = state_data.blocks;
         *self.blocks_by_hash.write().unwrap() = state_data.blocks_by_hash;
         *self.latest_block_hash.write().unwrap() = state_data.latest_block_hash;
+        self.finality.write().unwrap().restore(
+            self.blocks
+                .read()
+                .unwrap()
+                .iter()
+                .filter_map(|(height, block)| Some((*height, block.hash().ok()?.to_evm_hex()))),
+            state_data.finalized_height,
+        );
 
         info!("State loaded from disk: height={}", state_data.height);
         Ok(())
@@ -874,6 +1037,8 @@ struct StateData {
     blocks: HashMap<u64, Block>,
     blocks_by_hash: HashMap<String, Block>,
     latest_block_hash: String,
+    #[serde(default)]
+    finalized_height: Option<u64>,
 }
 
 /// Account information
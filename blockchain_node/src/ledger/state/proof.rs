@@ -0,0 +1,150 @@
+//! Account proofs against the state root.
+//!
+//! The state root is the root of a Merkle Patricia trie over every balance
+//! and storage entry. Keys are the Keccak-256 of the entry's name under a
+//! domain prefix, so they are fixed-length and accounts can't collide with
+//! storage slots; an account's value is its balance, little-endian.
+//!
+//! A light client holding only a root checks an [`AccountProof`] with
+//! [`verify_account_proof`], which needs nothing from the node that built it.
+
+use crate::crypto::MerklePatriciaTrie;
+use crate::types::Hash;
+use anyhow::Result;
+use ethereum_types::H256;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+/// Inclusion proof of an account's balance in the state trie
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountProof {
+    /// Account address
+    pub address: String,
+    /// Balance the proof shows
+    pub balance: u64,
+    /// State root the proof was built against
+    pub state_root: Hash,
+    /// RLP-encoded trie nodes from the root down to the account
+    pub nodes: Vec<Vec<u8>>,
+}
+
+/// Trie key of an account
+pub fn account_key(address: &str) -> Vec<u8> {
+    Keccak256::new()
+        .chain_update(b"account:")
+        .chain_update(address.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// Trie key of a storage entry
+pub fn storage_key(key: &str) -> Vec<u8> {
+    Keccak256::new()
+        .chain_update(b"storage:")
+        .chain_update(key.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// The state trie over `balances` and `storage`
+pub fn state_trie(
+    balances: &HashMap<String, u64>,
+    storage: &HashMap<String, Vec<u8>>,
+) -> Result<MerklePatriciaTrie> {
+    // Insert in key order so equal states build identical tries
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = balances
+        .iter()
+        .map(|(address, balance)| (account_key(address), balance.to_le_bytes().to_vec()))
+        .chain(storage.iter().map(|(key, value)| (storage_key(key), value.clone())))
+        .collect();
+    entries.sort();
+
+    let mut trie = MerklePatriciaTrie::new();
+    for (key, value) in entries {
+        trie.insert(&key, &value)?;
+    }
+    Ok(trie)
+}
+
+/// Whether `proof` shows its account holding its balance under `root`
+pub fn verify_account_proof(root: &Hash, proof: &AccountProof) -> bool {
+    if root.as_bytes().len() != 32 {
+        return false;
+    }
+    let value = MerklePatriciaTrie::verify_proof(
+        H256::from_slice(root.as_bytes()),
+        &account_key(&proof.address),
+        &proof.nodes,
+    );
+    value.as_deref() == Some(&proof.balance.to_le_bytes()[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::state::State;
+
+    fn funded_state(dir: &tempfile::TempDir) -> State {
+        let state = State::open(&dir.path().to_string_lossy()).unwrap();
+        for (i, address) in ["alice", "bob", "carol", "dave"].iter().enumerate() {
+            state.set_balance(address, 100 * (i as u64 + 1)).unwrap();
+        }
+        state.set_storage("contract:slot0", vec![1, 2, 3]).unwrap();
+        state
+    }
+
+    #[test]
+    fn test_account_proof_verifies_against_current_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = funded_state(&dir);
+
+        let root = state.get_state_root().unwrap();
+        let proof = state.get_account_proof("carol").unwrap();
+        assert_eq!(proof.balance, 300);
+        assert_eq!(proof.state_root, root);
+        assert!(verify_account_proof(&root, &proof));
+
+        // Accounts not in the state have nothing to prove
+        assert!(state.get_account_proof("mallory").is_err());
+    }
+
+    #[test]
+    fn test_stale_account_proof_fails_against_newer_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = funded_state(&dir);
+
+        let stale = state.get_account_proof("bob").unwrap();
+        state.set_balance("bob", 50).unwrap();
+        let root = state.get_state_root().unwrap();
+
+        assert!(!verify_account_proof(&root, &stale));
+        assert!(verify_account_proof(&stale.state_root, &stale));
+        assert!(verify_account_proof(&root, &state.get_account_proof("bob").unwrap()));
+    }
+
+    #[test]
+    fn test_forged_account_proof_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = funded_state(&dir);
+        let root = state.get_state_root().unwrap();
+        let proof = state.get_account_proof("alice").unwrap();
+
+        let mut inflated = proof.clone();
+        inflated.balance = 1_000_000;
+        assert!(!verify_account_proof(&root, &inflated));
+
+        let mut renamed = proof.clone();
+        renamed.address = "dave".to_string();
+        assert!(!verify_account_proof(&root, &renamed));
+
+        let mut tampered = proof.clone();
+        let leaf = tampered.nodes.last_mut().unwrap();
+        *leaf.last_mut().unwrap() ^= 1;
+        assert!(!verify_account_proof(&root, &tampered));
+
+        let mut truncated = proof;
+        truncated.nodes.pop();
+        assert!(!verify_account_proof(&root, &truncated));
+    }
+}
@@ -234,6 +234,11 @@ impl Transaction {
         self.gas_price * self.gas_limit
     }
 
+    /// Calculate the transaction fee, or `None` if gas price times gas limit overflows
+    pub fn checked_fee(&self) -> Option<u64> {
+        self.gas_price.checked_mul(self.gas_limit)
+    }
+
     /// Validate the transaction
     pub fn validate(&self) -> Result<()> {
         // Basic validation checks
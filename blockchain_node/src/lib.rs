@@ -193,6 +193,7 @@ pub mod consensus;
 pub mod execution;
 pub mod ledger;
 pub mod transaction;
+pub mod genesis;
 
 // AI and machine learning
 pub mod ai_engine;
@@ -249,6 +250,9 @@ pub mod sharding;
 // State management
 pub mod state;
 
+// Internal event bus
+pub mod events;
+
 // Gas optimization
 pub mod gas_optimization;
 
@@ -261,6 +265,12 @@ pub mod smart_contract_engine;
 // Deployment utilities
 pub mod deployment;
 
+// Developer tooling. dev_tools/mod.rs references tooling modules that are not
+// in the tree yet, so only the testing framework is compiled for now.
+pub mod dev_tools {
+    pub mod testing_framework;
+}
+
 // Re-export commonly used types and functions
 pub use ai_engine::models::NeuralNetwork;
 // Note: AiEngine is distributed across multiple AI modules
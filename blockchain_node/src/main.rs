@@ -2,6 +2,7 @@ use arthachain_node::{
     // api::testnet_router::create_testnet_router,
     config::Config,
     consensus::validator_set::{ValidatorSetConfig, ValidatorSetManager},
+    events::{EventBus, ForwarderConfig, WebhookForwarder},
     ledger::block::{Block, BlockHeader},
     ledger::state::State,
     performance::{parallel_processor::ProcessingTask, ParallelProcessor},
@@ -15,6 +16,10 @@ use tokio::sync::RwLock;
 
 const TARGET_TPS: u64 = 100_000;
 const WORKER_COUNT: usize = 16;
+/// Commit depth after which SVBFT treats a produced block as final
+const FINALITY_DEPTH: u64 = 5;
+/// Events emitted by block import and not yet fanned out to subscribers
+const EVENT_BUS_CAPACITY: usize = 10_000;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -29,6 +34,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let state = Arc::new(RwLock::new(State::new(&config)?));
     println!("Blockchain state initialized");
 
+    let event_bus = EventBus::new(EVENT_BUS_CAPACITY);
+    state.read().await.attach_event_bus(event_bus.clone());
+    if let Some(forwarder_config) = ForwarderConfig::from_env() {
+        WebhookForwarder::spawn(&event_bus, forwarder_config);
+        println!("Contract event forwarder started");
+    }
+    println!("Event bus initialized");
+
     let mempool = Arc::new(RwLock::new(Mempool::new(1000000)));
     println!("High-capacity mempool initialized (1M transactions)");
 
@@ -237,6 +250,10 @@ async fn mining_worker(
 
                 match state_write.add_block(new_block.clone()) {
                     Ok(_) => {
+                        // Publish finality for blocks that reached commit depth
+                        let _ = state_write
+                            .mark_finalized((current_height + 1).saturating_sub(FINALITY_DEPTH));
+
                         println!(
                             "Worker {}: Block {} mined with {} transactions",
                             shard_id,
@@ -26,6 +26,7 @@ use thiserror::Error;
 use tokio::sync::mpsc;
 
 use crate::config::Config;
+use crate::genesis::ChainIdentity;
 use crate::ledger::block::Block;
 use crate::ledger::state::State;
 use crate::ledger::transaction::Transaction;
@@ -52,6 +53,9 @@ pub struct PeerDiscoveryMessage {
     pub protocol_version: String,
     pub services: Vec<String>,
     pub timestamp: u64, // Unix timestamp
+    /// Chain the node was initialized for; absent on nodes without a chain spec
+    #[serde(default)]
+    pub chain: Option<ChainIdentity>,
 }
 
 /// Discovered peer information
@@ -1368,6 +1372,7 @@ impl P2PNetwork {
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .unwrap()
                     .as_secs(),
+                chain: self.chain_identity().await,
             };
 
             // Broadcast to local network
@@ -1514,6 +1519,11 @@ impl P2PNetwork {
         vec!["127.0.0.1:8080".to_string()]
     }
 
+    /// Chain identity from the genesis this node was initialized with
+    async fn chain_identity(&self) -> Option<ChainIdentity> {
+        ChainIdentity::from_state(&*self.state.read().await)
+    }
+
     /// Get protocol version
     fn get_protocol_version(&self) -> String {
         "arthachain/1.0".to_string()
@@ -1567,6 +1577,11 @@ impl P2PNetwork {
                                     if let Ok(peer_msg) =
                                         serde_json::from_slice::<PeerDiscoveryMessage>(&peer_data)
                                     {
+                                        let local = self.chain_identity().await;
+                                        if let Err(e) = ChainIdentity::admits(local.as_ref(), peer_msg.chain.as_ref()) {
+                                            warn!("Refusing mDNS peer {} from {}: {}", peer_msg.node_id, addr, e);
+                                            continue;
+                                        }
                                         info!(
                                             "Discovered peer via mDNS: {} from {}",
                                             peer_msg.node_id, addr
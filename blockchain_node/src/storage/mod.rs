@@ -248,9 +248,11 @@ pub mod hybrid_storage;
 pub mod memmap_storage;
 pub mod memory;
 pub mod replicated_storage;
+pub mod replication;
 pub mod rocksdb_storage;
 // pub mod svdb_storage;
 pub mod secure_storage;
+pub mod transfer;
 
 pub use rocksdb_storage::RocksDbStorage;
 pub use memmap_storage::MemMapStorage;
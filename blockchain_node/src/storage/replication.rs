@@ -0,0 +1,854 @@
+//! SVDB Replication
+//! Per-object replication policies and the background replicator that
+//! enforces them. Objects are copied chunk by chunk from verified holders to
+//! providers in the regions the policy asks for; progress is kept per chunk so
+//! an interrupted copy resumes where it stopped. Verified replicas are
+//! re-checked by sampling, and a replica that fails a check is marked lost and
+//! replaced on the next pass.
+
+use super::transfer::TransferManager;
+use super::Codec;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Weight of the newest observation in provider availability and latency
+const HEALTH_EWMA_ALPHA: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ReplicationPolicy {
+    /// One verified replica in each listed region
+    Regions { regions: Vec<String> },
+    /// Verified replicas in this many distinct regions
+    Replicas { replicas: usize },
+}
+
+impl ReplicationPolicy {
+    /// Parse an `X-Artha-Replication` header: `regions=eu-west,us-east` or `replicas=3`
+    pub fn from_header(value: &str) -> Result<Self, String> {
+        let (key, list) = value
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid replication policy: {}", value))?;
+        let policy = match key.trim() {
+            "regions" => ReplicationPolicy::Regions {
+                regions: list.split(',').map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect(),
+            },
+            "replicas" => ReplicationPolicy::Replicas {
+                replicas: list.trim().parse().map_err(|_| format!("Invalid replica count: {}", list))?,
+            },
+            other => return Err(format!("Unknown replication policy: {}", other)),
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ReplicationPolicy::Regions { regions } if regions.is_empty() => {
+                Err("Replication policy lists no regions".to_string())
+            }
+            ReplicationPolicy::Replicas { replicas: 0 } => Err("Replication policy asks for 0 replicas".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether replicas verified in `regions` satisfy the policy
+    fn satisfied_by(&self, regions: &HashSet<&str>) -> bool {
+        match self {
+            ReplicationPolicy::Regions { regions: wanted } => wanted.iter().all(|r| regions.contains(r.as_str())),
+            ReplicationPolicy::Replicas { replicas } => regions.len() >= *replicas,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderInfo {
+    pub node_id: String,
+    pub region: String,
+    pub http_addr: String,
+    pub advertised_latency_ms: Option<f64>, // From the provider's capabilities
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealth {
+    pub availability: f64, // EWMA of request success
+    pub latency_ms: Option<f64>,
+    pub consecutive_failures: u32,
+}
+
+impl Default for ProviderHealth {
+    fn default() -> Self {
+        Self {
+            availability: 1.0,
+            latency_ms: None,
+            consecutive_failures: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRef {
+    pub cid_hex: String, // blake3 of the chunk before compression
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicaState {
+    Copying,
+    Verified,
+    Lost,
+}
+
+#[derive(Debug, Clone)]
+struct Replica {
+    node_id: String,
+    state: ReplicaState,
+    copied: Vec<bool>, // Per chunk, so an interrupted copy resumes
+    has_manifest: bool,
+}
+
+impl Replica {
+    fn copying(node_id: &str, chunks: usize) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            state: ReplicaState::Copying,
+            copied: vec![false; chunks],
+            has_manifest: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ReplicatedObject {
+    cid_uri: String,
+    codec: Codec,
+    chunks: Vec<ChunkRef>,
+    manifest_json: Vec<u8>,
+    policy: Option<ReplicationPolicy>,
+    replicas: Vec<Replica>,
+    verify_cursor: usize, // Next chunk to sample on verified replicas
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicaStatus {
+    pub node_id: String,
+    pub region: String,
+    pub http_addr: String,
+    pub state: ReplicaState,
+    pub copied_chunks: usize,
+    pub total_chunks: usize,
+    pub lag_bytes: u64, // Bytes still to copy
+    pub latency_ms: Option<f64>,
+    pub availability: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicationStatus {
+    pub cid: String,
+    pub policy: Option<ReplicationPolicy>,
+    pub replicas: Vec<ReplicaStatus>,
+    pub verified_regions: Vec<String>,
+    pub under_replicated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicatorConfig {
+    pub interval: Duration,
+    pub verify_sample: usize,  // Chunks re-checked per verified replica each pass
+    pub max_failures: u32,     // Consecutive failures before a provider is unhealthy
+}
+
+impl ReplicatorConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            interval: Duration::from_secs(var("ARTHA_REPLICATION_INTERVAL_SECS").unwrap_or(60)),
+            verify_sample: var("ARTHA_REPLICATION_VERIFY_SAMPLE").unwrap_or(4) as usize,
+            max_failures: var("ARTHA_PROVIDER_MAX_FAILURES").unwrap_or(3) as u32,
+        }
+    }
+}
+
+impl Default for ReplicatorConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            verify_sample: 4,
+            max_failures: 3,
+        }
+    }
+}
+
+/// Raw chunk bytes from what a provider stores under `codec`
+fn decode_chunk(codec: &Codec, stored: &[u8]) -> Option<Vec<u8>> {
+    match codec {
+        Codec::Raw => Some(stored.to_vec()),
+        Codec::Zstd => zstd::stream::decode_all(std::io::Cursor::new(stored)).ok(),
+        Codec::Lz4 => lz4_flex::block::decompress_size_prepended(stored).ok(),
+    }
+}
+
+fn chunk_matches(codec: &Codec, chunk: &ChunkRef, stored: &[u8]) -> bool {
+    decode_chunk(codec, stored)
+        .map(|raw| hex::encode(blake3::hash(&raw).as_bytes()) == chunk.cid_hex)
+        .unwrap_or(false)
+}
+
+/// Whether bytes a peer pushes are the chunk `cid_hex` under some codec
+pub fn stored_chunk_matches(cid_hex: &str, stored: &[u8]) -> bool {
+    [Codec::Raw, Codec::Zstd, Codec::Lz4].iter().any(|codec| {
+        decode_chunk(codec, stored)
+            .map(|raw| hex::encode(blake3::hash(&raw).as_bytes()) == cid_hex)
+            .unwrap_or(false)
+    })
+}
+
+pub struct Replicator {
+    config: ReplicatorConfig,
+    transfer: Arc<TransferManager>,
+    providers: RwLock<HashMap<String, ProviderInfo>>,
+    health: RwLock<HashMap<String, ProviderHealth>>,
+    objects: RwLock<HashMap<String, ReplicatedObject>>, // By manifest hash hex
+}
+
+impl Replicator {
+    pub fn new(config: ReplicatorConfig, transfer: Arc<TransferManager>) -> Self {
+        Self {
+            config,
+            transfer,
+            providers: RwLock::new(HashMap::new()),
+            health: RwLock::new(HashMap::new()),
+            objects: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn register_provider(&self, provider: ProviderInfo) {
+        self.providers.write().await.insert(provider.node_id.clone(), provider);
+    }
+
+    /// Fold the outcome of a request to `node_id` into its health
+    pub async fn record_observation(&self, node_id: &str, ok: bool, latency_ms: Option<f64>) {
+        let mut health = self.health.write().await;
+        let entry = health.entry(node_id.to_string()).or_default();
+        let sample = if ok { 1.0 } else { 0.0 };
+        entry.availability = HEALTH_EWMA_ALPHA * sample + (1.0 - HEALTH_EWMA_ALPHA) * entry.availability;
+        if ok {
+            entry.consecutive_failures = 0;
+            if let Some(ms) = latency_ms {
+                entry.latency_ms = Some(match entry.latency_ms {
+                    Some(prev) => HEALTH_EWMA_ALPHA * ms + (1.0 - HEALTH_EWMA_ALPHA) * prev,
+                    None => ms,
+                });
+            }
+        } else {
+            entry.consecutive_failures += 1;
+        }
+    }
+
+    /// Start tracking an object held in full by `origin`. Nothing is copied
+    /// until the object is given a policy.
+    pub async fn track(
+        &self,
+        cid_hex: &str,
+        cid_uri: &str,
+        codec: Codec,
+        chunks: Vec<ChunkRef>,
+        manifest_json: Vec<u8>,
+        origin: &str,
+    ) {
+        let origin = Replica {
+            node_id: origin.to_string(),
+            state: ReplicaState::Verified,
+            copied: vec![true; chunks.len()],
+            has_manifest: true,
+        };
+        self.objects.write().await.insert(
+            cid_hex.to_string(),
+            ReplicatedObject {
+                cid_uri: cid_uri.to_string(),
+                codec,
+                chunks,
+                manifest_json,
+                policy: None,
+                replicas: vec![origin],
+                verify_cursor: 0,
+            },
+        );
+    }
+
+    pub async fn set_policy(&self, cid_hex: &str, policy: ReplicationPolicy) -> Result<(), String> {
+        policy.validate()?;
+        let mut objects = self.objects.write().await;
+        let object = objects.get_mut(cid_hex).ok_or_else(|| format!("Object {} is not tracked", cid_hex))?;
+        object.policy = Some(policy);
+        Ok(())
+    }
+
+    pub async fn status(&self, cid_hex: &str) -> Option<ReplicationStatus> {
+        let object = self.objects.read().await.get(cid_hex).cloned()?;
+        let providers = self.providers.read().await;
+        let health = self.health.read().await;
+
+        let replicas: Vec<ReplicaStatus> = object
+            .replicas
+            .iter()
+            .map(|replica| {
+                let provider = providers.get(&replica.node_id);
+                let observed = health.get(&replica.node_id).cloned().unwrap_or_default();
+                ReplicaStatus {
+                    node_id: replica.node_id.clone(),
+                    region: provider.map(|p| p.region.clone()).unwrap_or_default(),
+                    http_addr: provider.map(|p| p.http_addr.clone()).unwrap_or_default(),
+                    state: replica.state,
+                    copied_chunks: replica.copied.iter().filter(|c| **c).count(),
+                    total_chunks: object.chunks.len(),
+                    lag_bytes: object
+                        .chunks
+                        .iter()
+                        .zip(&replica.copied)
+                        .filter(|(_, copied)| !**copied)
+                        .map(|(chunk, _)| chunk.size)
+                        .sum(),
+                    latency_ms: observed.latency_ms.or(provider.and_then(|p| p.advertised_latency_ms)),
+                    availability: observed.availability,
+                }
+            })
+            .collect();
+
+        let mut verified_regions: Vec<String> = replicas
+            .iter()
+            .filter(|r| r.state == ReplicaState::Verified && !r.region.is_empty())
+            .map(|r| r.region.clone())
+            .collect();
+        verified_regions.sort();
+        verified_regions.dedup();
+
+        let region_set: HashSet<&str> = verified_regions.iter().map(|r| r.as_str()).collect();
+        let under_replicated = object.policy.as_ref().map(|p| !p.satisfied_by(&region_set)).unwrap_or(false);
+
+        Some(ReplicationStatus {
+            cid: object.cid_uri,
+            policy: object.policy,
+            replicas,
+            verified_regions,
+            under_replicated,
+        })
+    }
+
+    /// Providers holding a verified copy of the object, for locality decisions
+    pub async fn verified_locations(&self, cid_hex: &str) -> Vec<ProviderInfo> {
+        let Some(object) = self.objects.read().await.get(cid_hex).cloned() else { return Vec::new() };
+        let providers = self.providers.read().await;
+        object
+            .replicas
+            .iter()
+            .filter(|r| r.state == ReplicaState::Verified)
+            .filter_map(|r| providers.get(&r.node_id).cloned())
+            .collect()
+    }
+
+    /// Verify, plan and copy every tracked object once
+    pub async fn run_once(&self) {
+        let cids: Vec<String> = self.objects.read().await.keys().cloned().collect();
+        for cid_hex in cids {
+            let Some(mut object) = self.objects.read().await.get(&cid_hex).cloned() else { continue };
+            if object.policy.is_none() {
+                continue;
+            }
+            self.verify_replicas(&mut object).await;
+            self.plan_replicas(&mut object).await;
+            self.copy_replicas(&mut object).await;
+
+            // The policy may have changed while we were copying; keep the newer one
+            if let Some(current) = self.objects.write().await.get_mut(&cid_hex) {
+                current.replicas = object.replicas;
+                current.verify_cursor = object.verify_cursor;
+            }
+        }
+    }
+
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+            loop {
+                ticker.tick().await;
+                self.run_once().await;
+            }
+        })
+    }
+
+    async fn is_healthy(&self, node_id: &str) -> bool {
+        self.providers.read().await.contains_key(node_id)
+            && self
+                .health
+                .read()
+                .await
+                .get(node_id)
+                .map(|h| h.consecutive_failures < self.config.max_failures)
+                .unwrap_or(true)
+    }
+
+    /// Observed latency, else advertised, for ordering providers
+    async fn latency_of(&self, node_id: &str) -> f64 {
+        let observed = self.health.read().await.get(node_id).and_then(|h| h.latency_ms);
+        let advertised = self.providers.read().await.get(node_id).and_then(|p| p.advertised_latency_ms);
+        observed.or(advertised).unwrap_or(f64::INFINITY)
+    }
+
+    async fn provider(&self, node_id: &str) -> Option<ProviderInfo> {
+        self.providers.read().await.get(node_id).cloned()
+    }
+
+    /// Fetch a chunk from `node_id` and check it against its CID
+    async fn fetch_verified(&self, node_id: &str, codec: &Codec, chunk: &ChunkRef) -> Result<Vec<u8>, String> {
+        let provider = self.provider(node_id).await.ok_or_else(|| format!("Unknown provider {}", node_id))?;
+        match self.transfer.fetch_chunk(&provider.http_addr, &chunk.cid_hex).await {
+            Ok((data, elapsed)) => {
+                self.record_observation(node_id, true, Some(elapsed.as_secs_f64() * 1000.0)).await;
+                if chunk_matches(codec, chunk, &data) {
+                    Ok(data)
+                } else {
+                    Err(format!("Chunk {} on {} does not match its CID", chunk.cid_hex, node_id))
+                }
+            }
+            Err(e) => {
+                self.record_observation(node_id, false, None).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Sample-check verified replicas, marking the ones that fail as lost
+    async fn verify_replicas(&self, object: &mut ReplicatedObject) {
+        if object.chunks.is_empty() {
+            return;
+        }
+        let sample: Vec<usize> = (0..self.config.verify_sample.min(object.chunks.len()))
+            .map(|i| (object.verify_cursor + i) % object.chunks.len())
+            .collect();
+        object.verify_cursor = (object.verify_cursor + sample.len()) % object.chunks.len();
+
+        for i in 0..object.replicas.len() {
+            if object.replicas[i].state != ReplicaState::Verified {
+                continue;
+            }
+            let node_id = object.replicas[i].node_id.clone();
+            let mut failure = None;
+            for &index in &sample {
+                if let Err(e) = self.fetch_verified(&node_id, &object.codec, &object.chunks[index]).await {
+                    failure = Some(e);
+                    break;
+                }
+            }
+            if failure.is_none() && !self.is_healthy(&node_id).await {
+                failure = Some(format!("{} is unhealthy", node_id));
+            }
+            if let Some(reason) = failure {
+                warn!("Replica of {} on {} lost: {}", object.cid_uri, node_id, reason);
+                object.replicas[i].state = ReplicaState::Lost;
+            }
+        }
+    }
+
+    /// Add copying replicas until the policy would be met, best providers first
+    async fn plan_replicas(&self, object: &mut ReplicatedObject) {
+        let Some(policy) = object.policy.clone() else { return };
+
+        // A copy onto a provider that went away will not finish
+        for replica in object.replicas.iter_mut() {
+            if replica.state == ReplicaState::Copying && !self.is_healthy(&replica.node_id).await {
+                replica.state = ReplicaState::Lost;
+            }
+        }
+
+        let providers: Vec<ProviderInfo> = self.providers.read().await.values().cloned().collect();
+        let mut covered: HashSet<String> = HashSet::new();
+        let mut holders: HashSet<String> = HashSet::new();
+        for replica in &object.replicas {
+            if replica.state != ReplicaState::Lost {
+                holders.insert(replica.node_id.clone());
+                if let Some(p) = providers.iter().find(|p| p.node_id == replica.node_id) {
+                    covered.insert(p.region.clone());
+                }
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for p in providers {
+            if !holders.contains(&p.node_id) && self.is_healthy(&p.node_id).await {
+                let health = self.health.read().await.get(&p.node_id).cloned().unwrap_or_default();
+                let latency = self.latency_of(&p.node_id).await;
+                candidates.push((p, health.availability, latency));
+            }
+        }
+        candidates.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+                .then(a.0.node_id.cmp(&b.0.node_id))
+        });
+
+        let mut chosen = Vec::new();
+        match &policy {
+            ReplicationPolicy::Regions { regions } => {
+                for region in regions {
+                    if covered.contains(region) {
+                        continue;
+                    }
+                    if let Some((p, _, _)) = candidates.iter().find(|(p, _, _)| &p.region == region) {
+                        covered.insert(region.clone());
+                        chosen.push(p.node_id.clone());
+                    }
+                }
+            }
+            ReplicationPolicy::Replicas { replicas } => {
+                for (p, _, _) in &candidates {
+                    if covered.len() >= *replicas {
+                        break;
+                    }
+                    if covered.insert(p.region.clone()) {
+                        chosen.push(p.node_id.clone());
+                    }
+                }
+            }
+        }
+
+        for node_id in chosen {
+            info!("Replicating {} to {}", object.cid_uri, node_id);
+            match object.replicas.iter_mut().find(|r| r.node_id == node_id) {
+                // What a lost replica still holds is unknown; copy it all again
+                Some(lost) => *lost = Replica::copying(&node_id, object.chunks.len()),
+                None => object.replicas.push(Replica::copying(&node_id, object.chunks.len())),
+            }
+        }
+    }
+
+    async fn copy_replicas(&self, object: &mut ReplicatedObject) {
+        for i in 0..object.replicas.len() {
+            if object.replicas[i].state == ReplicaState::Copying {
+                self.copy_replica(object, i).await;
+            }
+        }
+    }
+
+    /// Copy the chunks `replica` is missing, then read them all back before
+    /// calling it verified. Stops at the first failure; the next pass resumes.
+    async fn copy_replica(&self, object: &mut ReplicatedObject, replica: usize) {
+        let target_id = object.replicas[replica].node_id.clone();
+        let Some(target) = self.provider(&target_id).await else { return };
+
+        for index in 0..object.chunks.len() {
+            if object.replicas[replica].copied[index] {
+                continue;
+            }
+            let chunk = object.chunks[index].clone();
+
+            let mut sources: Vec<(String, f64)> = Vec::new();
+            for r in &object.replicas {
+                if r.state == ReplicaState::Verified && r.node_id != target_id && self.is_healthy(&r.node_id).await {
+                    sources.push((r.node_id.clone(), self.latency_of(&r.node_id).await));
+                }
+            }
+            sources.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+            let mut data = None;
+            for (source, _) in &sources {
+                match self.fetch_verified(source, &object.codec, &chunk).await {
+                    Ok(bytes) => {
+                        data = Some(bytes);
+                        break;
+                    }
+                    Err(e) => debug!("Source {} failed for chunk {}: {}", source, chunk.cid_hex, e),
+                }
+            }
+            let Some(data) = data else {
+                warn!("No healthy source for chunk {} of {}", chunk.cid_hex, object.cid_uri);
+                return;
+            };
+
+            match self.transfer.push_chunk(&target.http_addr, &chunk.cid_hex, data).await {
+                Ok(elapsed) => {
+                    self.record_observation(&target_id, true, Some(elapsed.as_secs_f64() * 1000.0)).await;
+                    object.replicas[replica].copied[index] = true;
+                }
+                Err(e) => {
+                    self.record_observation(&target_id, false, None).await;
+                    warn!("Copy of {} to {} interrupted: {}", object.cid_uri, target_id, e);
+                    return;
+                }
+            }
+        }
+
+        if !object.replicas[replica].has_manifest {
+            match self
+                .transfer
+                .push_manifest(&target.http_addr, &object.cid_uri, object.manifest_json.clone())
+                .await
+            {
+                Ok(_) => object.replicas[replica].has_manifest = true,
+                Err(e) => {
+                    self.record_observation(&target_id, false, None).await;
+                    warn!("Manifest of {} not stored on {}: {}", object.cid_uri, target_id, e);
+                    return;
+                }
+            }
+        }
+
+        let mut intact = true;
+        for index in 0..object.chunks.len() {
+            if self.fetch_verified(&target_id, &object.codec, &object.chunks[index]).await.is_err() {
+                object.replicas[replica].copied[index] = false;
+                intact = false;
+            }
+        }
+        if intact {
+            info!("Replica of {} on {} verified", object.cid_uri, target_id);
+            object.replicas[replica].state = ReplicaState::Verified;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::transfer::TransferConfig;
+    use axum::{
+        body::Bytes,
+        extract::{Path, State},
+        http::StatusCode,
+        routing::{get, put},
+        Router,
+    };
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// An SVDB provider serving and accepting chunks over HTTP
+    #[derive(Clone, Default)]
+    struct MockProvider {
+        chunks: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+        manifests: Arc<Mutex<HashSet<String>>>,
+        down: Arc<AtomicBool>,
+        puts_left: Arc<Mutex<Option<usize>>>, // Fail PUTs once this runs out
+        gets: Arc<AtomicUsize>,
+    }
+
+    async fn get_chunk(State(mock): State<MockProvider>, Path(cid_hex): Path<String>) -> Result<Vec<u8>, StatusCode> {
+        if mock.down.load(Ordering::SeqCst) {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+        mock.gets.fetch_add(1, Ordering::SeqCst);
+        mock.chunks.lock().unwrap().get(&cid_hex).cloned().ok_or(StatusCode::NOT_FOUND)
+    }
+
+    async fn put_chunk(State(mock): State<MockProvider>, Path(cid_hex): Path<String>, body: Bytes) -> StatusCode {
+        if mock.down.load(Ordering::SeqCst) {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        let mut puts_left = mock.puts_left.lock().unwrap();
+        match puts_left.as_mut() {
+            Some(0) => return StatusCode::INSUFFICIENT_STORAGE,
+            Some(n) => *n -= 1,
+            None => {}
+        }
+        mock.chunks.lock().unwrap().insert(cid_hex, body.to_vec());
+        StatusCode::OK
+    }
+
+    async fn put_manifest(State(mock): State<MockProvider>, Path(cid): Path<String>) -> StatusCode {
+        mock.manifests.lock().unwrap().insert(cid);
+        StatusCode::OK
+    }
+
+    impl MockProvider {
+        async fn spawn(&self) -> String {
+            let app = Router::new()
+                .route("/svdb/chunk/:cid_hex", get(get_chunk).put(put_chunk))
+                .route("/svdb/manifest/:cid", put(put_manifest))
+                .with_state(self.clone());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = format!("http://{}", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            addr
+        }
+
+        fn holds(&self, chunks: &[ChunkRef]) -> bool {
+            let stored = self.chunks.lock().unwrap();
+            chunks.iter().all(|c| stored.contains_key(&c.cid_hex))
+        }
+    }
+
+    /// An origin provider holding `count` chunks of a fresh object
+    async fn origin_with_object(count: usize) -> (MockProvider, String, Vec<ChunkRef>) {
+        let origin = MockProvider::default();
+        let mut chunks = Vec::new();
+        for i in 0..count {
+            let raw = vec![i as u8; 1024 + i];
+            let cid_hex = hex::encode(blake3::hash(&raw).as_bytes());
+            origin.chunks.lock().unwrap().insert(cid_hex.clone(), raw.clone());
+            chunks.push(ChunkRef { cid_hex, size: raw.len() as u64 });
+        }
+        let addr = origin.spawn().await;
+        (origin, addr, chunks)
+    }
+
+    fn provider(node_id: &str, region: &str, http_addr: &str) -> ProviderInfo {
+        ProviderInfo {
+            node_id: node_id.to_string(),
+            region: region.to_string(),
+            http_addr: http_addr.to_string(),
+            advertised_latency_ms: None,
+        }
+    }
+
+    fn replicator() -> Replicator {
+        Replicator::new(
+            ReplicatorConfig { interval: Duration::from_secs(60), verify_sample: 8, max_failures: 2 },
+            Arc::new(TransferManager::new(TransferConfig::default())),
+        )
+    }
+
+    async fn tracked(replicator: &Replicator, chunks: &[ChunkRef], policy: ReplicationPolicy) {
+        replicator
+            .track("obj", "artha://obj", Codec::Raw, chunks.to_vec(), b"{}".to_vec(), "origin")
+            .await;
+        replicator.set_policy("obj", policy).await.unwrap();
+    }
+
+    #[test]
+    fn test_policy_from_header() {
+        assert_eq!(
+            ReplicationPolicy::from_header("regions=eu-west, us-east").unwrap(),
+            ReplicationPolicy::Regions { regions: vec!["eu-west".to_string(), "us-east".to_string()] }
+        );
+        assert_eq!(
+            ReplicationPolicy::from_header("replicas=3").unwrap(),
+            ReplicationPolicy::Replicas { replicas: 3 }
+        );
+        assert!(ReplicationPolicy::from_header("replicas=0").is_err());
+        assert!(ReplicationPolicy::from_header("regions=").is_err());
+        assert!(ReplicationPolicy::from_header("copies=2").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_policy_driven_replication_completes() {
+        let (_origin, origin_addr, chunks) = origin_with_object(5).await;
+        let us = MockProvider::default();
+        let ap = MockProvider::default();
+        let other_eu = MockProvider::default();
+
+        let replicator = replicator();
+        replicator.register_provider(provider("origin", "eu-west", &origin_addr)).await;
+        replicator.register_provider(provider("us-1", "us-east", &us.spawn().await)).await;
+        replicator.register_provider(provider("ap-1", "ap-south", &ap.spawn().await)).await;
+        replicator.register_provider(provider("eu-2", "eu-west", &other_eu.spawn().await)).await;
+        tracked(&replicator, &chunks, ReplicationPolicy::Replicas { replicas: 3 }).await;
+
+        assert!(replicator.status("obj").await.unwrap().under_replicated);
+        replicator.run_once().await;
+
+        let status = replicator.status("obj").await.unwrap();
+        assert!(!status.under_replicated);
+        assert_eq!(status.verified_regions, vec!["ap-south", "eu-west", "us-east"]);
+        assert!(status.replicas.iter().all(|r| r.state == ReplicaState::Verified && r.lag_bytes == 0));
+        assert!(us.holds(&chunks) && ap.holds(&chunks));
+        assert!(us.manifests.lock().unwrap().contains("obj"));
+        // Distinct regions: the second eu provider adds nothing
+        assert!(other_eu.chunks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_copy_resumes() {
+        let (origin, origin_addr, chunks) = origin_with_object(6).await;
+        let us = MockProvider::default();
+        *us.puts_left.lock().unwrap() = Some(2);
+
+        let replicator = replicator();
+        replicator.register_provider(provider("origin", "eu-west", &origin_addr)).await;
+        replicator.register_provider(provider("us-1", "us-east", &us.spawn().await)).await;
+        tracked(&replicator, &chunks, ReplicationPolicy::Regions { regions: vec!["us-east".to_string()] }).await;
+
+        replicator.run_once().await;
+        let status = replicator.status("obj").await.unwrap();
+        let copy = status.replicas.iter().find(|r| r.node_id == "us-1").unwrap();
+        assert_eq!(copy.state, ReplicaState::Copying);
+        assert_eq!(copy.copied_chunks, 2);
+        assert_eq!(copy.lag_bytes, chunks[2..].iter().map(|c| c.size).sum::<u64>());
+
+        // Only the chunks still missing are read from the origin again
+        *us.puts_left.lock().unwrap() = None;
+        let gets_before = origin.gets.load(Ordering::SeqCst);
+        replicator.run_once().await;
+        let status = replicator.status("obj").await.unwrap();
+        assert!(status.replicas.iter().all(|r| r.state == ReplicaState::Verified));
+        let sampled = chunks.len(); // Verification pass over the origin
+        assert_eq!(origin.gets.load(Ordering::SeqCst) - gets_before, sampled + 4);
+    }
+
+    #[tokio::test]
+    async fn test_under_replication_detected_and_repaired() {
+        let (_origin, origin_addr, chunks) = origin_with_object(4).await;
+        let us1 = MockProvider::default();
+
+        let replicator = replicator();
+        replicator.register_provider(provider("origin", "eu-west", &origin_addr)).await;
+        replicator.register_provider(provider("us-1", "us-east", &us1.spawn().await)).await;
+        tracked(
+            &replicator,
+            &chunks,
+            ReplicationPolicy::Regions { regions: vec!["eu-west".to_string(), "us-east".to_string()] },
+        )
+        .await;
+        replicator.run_once().await;
+        assert!(!replicator.status("obj").await.unwrap().under_replicated);
+
+        // us-1 goes down and there is nowhere else in us-east
+        us1.down.store(true, Ordering::SeqCst);
+        replicator.run_once().await;
+        let status = replicator.status("obj").await.unwrap();
+        assert!(status.under_replicated);
+        assert_eq!(status.verified_regions, vec!["eu-west"]);
+        assert_ne!(status.replicas.iter().find(|r| r.node_id == "us-1").unwrap().state, ReplicaState::Verified);
+
+        // A new us-east provider joins and the next pass repairs the object
+        let us2 = MockProvider::default();
+        replicator.register_provider(provider("us-2", "us-east", &us2.spawn().await)).await;
+        replicator.run_once().await;
+        let status = replicator.status("obj").await.unwrap();
+        assert!(!status.under_replicated);
+        assert_eq!(status.replicas.iter().find(|r| r.node_id == "us-2").unwrap().state, ReplicaState::Verified);
+        assert_eq!(status.replicas.iter().find(|r| r.node_id == "us-1").unwrap().state, ReplicaState::Lost);
+        assert!(us2.holds(&chunks));
+    }
+
+    #[tokio::test]
+    async fn test_verified_locations_follow_replication() {
+        let (_origin, origin_addr, chunks) = origin_with_object(3).await;
+        let us = MockProvider::default();
+
+        let replicator = replicator();
+        replicator.register_provider(provider("origin", "eu-west", &origin_addr)).await;
+        replicator.register_provider(provider("us-1", "us-east", &us.spawn().await)).await;
+        tracked(&replicator, &chunks, ReplicationPolicy::Regions { regions: vec!["eu-west".to_string()] }).await;
+
+        let regions = |locations: Vec<ProviderInfo>| {
+            let mut regions: Vec<String> = locations.into_iter().map(|p| p.region).collect();
+            regions.sort();
+            regions
+        };
+        replicator.run_once().await;
+        assert_eq!(regions(replicator.verified_locations("obj").await), vec!["eu-west"]);
+
+        replicator
+            .set_policy("obj", ReplicationPolicy::Regions { regions: vec!["eu-west".to_string(), "us-east".to_string()] })
+            .await
+            .unwrap();
+        replicator.run_once().await;
+        assert_eq!(regions(replicator.verified_locations("obj").await), vec!["eu-west", "us-east"]);
+    }
+}
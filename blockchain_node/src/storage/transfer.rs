@@ -0,0 +1,124 @@
+//! Chunk Transfer Manager
+//! Moves SVDB chunks between provider nodes over their HTTP endpoints.
+//! Every transfer goes through one shared throttle, so background
+//! replication stays under the configured bandwidth no matter how many
+//! objects are being copied at once.
+
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone)]
+pub struct TransferConfig {
+    /// None disables throttling
+    pub bandwidth_bytes_per_sec: Option<u64>,
+    pub request_timeout: Duration,
+}
+
+impl TransferConfig {
+    pub fn from_env() -> Self {
+        let mbps: Option<f64> = std::env::var("ARTHA_REPLICATION_BANDWIDTH_MBPS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| *v > 0.0);
+        Self {
+            bandwidth_bytes_per_sec: mbps.map(|m| (m * 1_000_000.0 / 8.0) as u64),
+            request_timeout: Duration::from_secs(
+                std::env::var("ARTHA_REPLICATION_TIMEOUT_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(30),
+            ),
+        }
+    }
+}
+
+impl Default for TransferConfig {
+    fn default() -> Self {
+        Self {
+            bandwidth_bytes_per_sec: None,
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+pub struct TransferManager {
+    config: TransferConfig,
+    client: reqwest::Client,
+    /// When the link is next free; each transfer reserves size / rate after it
+    next_free: Mutex<Instant>,
+}
+
+impl TransferManager {
+    pub fn new(config: TransferConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            client,
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for a slot to move `bytes`
+    async fn throttle(&self, bytes: usize) {
+        let Some(rate) = self.config.bandwidth_bytes_per_sec else { return };
+        let start = {
+            let mut next_free = self.next_free.lock().await;
+            let start = (*next_free).max(Instant::now());
+            *next_free = start + Duration::from_secs_f64(bytes as f64 / rate.max(1) as f64);
+            start
+        };
+        tokio::time::sleep_until(start.into()).await;
+    }
+
+    /// Stored bytes of a chunk from `http_addr`, with the time the request took
+    pub async fn fetch_chunk(&self, http_addr: &str, cid_hex: &str) -> Result<(Vec<u8>, Duration), String> {
+        let url = format!("{}/svdb/chunk/{}", http_addr.trim_end_matches('/'), cid_hex);
+        let started = Instant::now();
+        let response = self.client.get(&url).send().await.map_err(|e| format!("GET {}: {}", url, e))?;
+        if !response.status().is_success() {
+            return Err(format!("GET {}: {}", url, response.status()));
+        }
+        let bytes = response.bytes().await.map_err(|e| format!("GET {}: {}", url, e))?;
+        let elapsed = started.elapsed();
+        self.throttle(bytes.len()).await;
+        Ok((bytes.to_vec(), elapsed))
+    }
+
+    /// Store a chunk on `http_addr`, returning the time the request took
+    pub async fn push_chunk(&self, http_addr: &str, cid_hex: &str, data: Vec<u8>) -> Result<Duration, String> {
+        self.throttle(data.len()).await;
+        let url = format!("{}/svdb/chunk/{}", http_addr.trim_end_matches('/'), cid_hex);
+        let started = Instant::now();
+        let response = self.client.put(&url).body(data).send().await.map_err(|e| format!("PUT {}: {}", url, e))?;
+        if !response.status().is_success() {
+            return Err(format!("PUT {}: {}", url, response.status()));
+        }
+        Ok(started.elapsed())
+    }
+
+    /// Store an object's manifest on `http_addr` so it can serve downloads
+    pub async fn push_manifest(&self, http_addr: &str, cid_uri: &str, manifest_json: Vec<u8>) -> Result<Duration, String> {
+        let url = format!("{}/svdb/manifest/{}", http_addr.trim_end_matches('/'), cid_path_segment(cid_uri));
+        let started = Instant::now();
+        let response = self
+            .client
+            .put(&url)
+            .header("Content-Type", "application/json")
+            .body(manifest_json)
+            .send()
+            .await
+            .map_err(|e| format!("PUT {}: {}", url, e))?;
+        if !response.status().is_success() {
+            return Err(format!("PUT {}: {}", url, response.status()));
+        }
+        Ok(started.elapsed())
+    }
+}
+
+/// `artha://<base64>` as a URL path segment
+pub fn cid_path_segment(cid_uri: &str) -> String {
+    cid_uri.trim_start_matches("artha://").replace('+', "%2B").replace('/', "%2F")
+}
@@ -1,4 +1,5 @@
 use crate::common::{Error, Result};
+use crate::evm::account_abstraction::ENTRY_POINT;
 use crate::types::Transaction;
 use crate::utils::crypto::Hash;
 use chrono::{DateTime, Utc};
@@ -96,7 +97,11 @@ impl Mempool {
             ));
         }
 
-        if tx.value == 0 {
+        // Calls to the account-abstraction EntryPoint are authorized by their
+        // own signatures, and only deposits among them carry value
+        let entry_point_call = tx.to.0 == ENTRY_POINT.0;
+
+        if tx.value == 0 && !entry_point_call {
             return Err(crate::common::Error::InvalidTransaction(
                 "Amount cannot be zero".to_string(),
             ));
@@ -110,7 +115,7 @@ impl Mempool {
         }
 
         // Check nonce (in production, would check against account state)
-        if tx.nonce == 0 {
+        if tx.nonce == 0 && !entry_point_call {
             return Err(crate::common::Error::InvalidTransaction(
                 "Invalid nonce".to_string(),
             ));
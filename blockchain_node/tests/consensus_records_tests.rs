@@ -0,0 +1,198 @@
+//! Consensus vote records: persistence across restart, performance math,
+//! epoch diffs, and aggregates surviving detail pruning
+use arthachain_node::consensus::vote_records::{
+    ConsensusRecords, FinalizedRound, StakeChange, VoteRecordConfig, VoteTiming,
+};
+use std::collections::BTreeMap;
+
+fn config(epoch_length: u64, detail_epochs: u64, journal: Option<&std::path::Path>) -> VoteRecordConfig {
+    VoteRecordConfig {
+        epoch_length,
+        detail_epochs,
+        journal_path: journal.map(|p| p.to_path_buf()),
+    }
+}
+
+fn set(members: &[(&str, u64)]) -> BTreeMap<String, u64> {
+    members.iter().map(|(a, s)| (a.to_string(), *s)).collect()
+}
+
+fn voted(prevote_ms: u64, precommit_ms: u64) -> VoteTiming {
+    VoteTiming { prevote_ms: Some(prevote_ms), precommit_ms: Some(precommit_ms) }
+}
+
+/// A round every listed validator voted in, proposed by `proposer`
+fn round(height: u64, proposer: &str, voters: &[(&str, VoteTiming)]) -> FinalizedRound {
+    FinalizedRound {
+        height,
+        view: height,
+        proposer: proposer.to_string(),
+        missed_proposers: Vec::new(),
+        votes: voters.iter().map(|(a, t)| (a.to_string(), *t)).collect(),
+    }
+}
+
+#[test]
+fn test_votes_survive_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal = dir.path().join("votes.jsonl");
+
+    let mut records = ConsensusRecords::open(config(10, 4, Some(&journal))).unwrap();
+    records.queue_validator_set(set(&[("alice", 100), ("bob", 50)])).unwrap();
+    for height in 0..5 {
+        records
+            .record_block(round(height, "alice", &[("alice", voted(10, 20)), ("bob", voted(30, 40))]))
+            .unwrap();
+    }
+    records
+        .record_block(round(5, "bob", &[("alice", VoteTiming { prevote_ms: Some(12), precommit_ms: None })]))
+        .unwrap();
+    let before = records.performance("bob", 1).unwrap();
+    drop(records);
+
+    let reopened = ConsensusRecords::open(config(10, 4, Some(&journal))).unwrap();
+    let votes = reopened.block_votes(5).expect("vote detail should be replayed");
+    assert_eq!(votes.proposer, "bob");
+    let alice = votes.votes.iter().find(|v| v.validator == "alice").unwrap();
+    assert!(alice.prevote && !alice.precommit);
+    assert_eq!(alice.prevote_ms, Some(12));
+    let bob = votes.votes.iter().find(|v| v.validator == "bob").unwrap();
+    assert!(!bob.prevote && !bob.precommit, "absent validators are listed as missing");
+
+    assert_eq!(reopened.performance("bob", 1).unwrap(), before);
+    let (_, validators) = reopened.validators(None).unwrap();
+    assert_eq!(validators.iter().map(|v| v.stake).collect::<Vec<_>>(), vec![100, 50]);
+
+    // Heights keep increasing across the restart
+    let mut reopened = reopened;
+    assert!(reopened.record_block(round(5, "alice", &[])).is_err());
+    reopened.record_block(round(6, "alice", &[])).unwrap();
+}
+
+#[test]
+fn test_performance_with_validator_offline_mid_window() {
+    let mut records = ConsensusRecords::open(config(10, 4, None)).unwrap();
+    records.queue_validator_set(set(&[("alice", 1), ("bob", 1), ("carol", 1)])).unwrap();
+
+    // Proposer rotates alice, bob, carol. Carol goes offline after height 9:
+    // she votes on nothing and her proposer slots pass to the next view.
+    let rotation = ["alice", "bob", "carol"];
+    for height in 0..20 {
+        let leader = rotation[height as usize % 3];
+        let online = height < 10;
+        let mut voters = vec![("alice", voted(10, 30)), ("bob", voted(20, 50))];
+        if online {
+            voters.push(("carol", voted(40, 80)));
+        }
+        let mut block = if leader == "carol" && !online {
+            round(height, "alice", &voters)
+        } else {
+            round(height, leader, &voters)
+        };
+        if leader == "carol" && !online {
+            block.missed_proposers.push("carol".to_string());
+        }
+        records.record_block(block).unwrap();
+    }
+
+    let carol = records.performance("carol", 2).unwrap();
+    assert_eq!((carol.from_epoch, carol.to_epoch), (0, 1));
+    assert_eq!(carol.blocks, 20);
+    assert!((carol.participation_rate - 0.5).abs() < 1e-9);
+    assert_eq!(carol.average_vote_latency_ms, Some(60.0));
+    // Heights 2, 5, 8 online; 11, 14, 17 missed
+    assert_eq!(carol.proposer_slots_assigned, 6);
+    assert_eq!(carol.proposer_slots_fulfilled, 3);
+    assert_eq!(carol.missed_streak, 10);
+    assert_eq!(carol.longest_missed_streak, 10);
+
+    let latest = records.performance("carol", 1).unwrap();
+    assert_eq!(latest.participation_rate, 0.0);
+    assert_eq!(latest.average_vote_latency_ms, None);
+    assert_eq!((latest.proposer_slots_assigned, latest.proposer_slots_fulfilled), (3, 0));
+
+    let alice = records.performance("alice", 2).unwrap();
+    assert_eq!(alice.participation_rate, 1.0);
+    assert_eq!(alice.average_vote_latency_ms, Some(20.0));
+    assert_eq!(alice.missed_streak, 0);
+    assert_eq!((alice.proposer_slots_assigned, alice.proposer_slots_fulfilled), (10, 10));
+
+    assert!(records.performance("mallory", 2).is_none());
+}
+
+#[test]
+fn test_epoch_diff_when_validators_join_and_leave() {
+    let mut records = ConsensusRecords::open(config(5, 4, None)).unwrap();
+    records.queue_validator_set(set(&[("alice", 10), ("bob", 10)])).unwrap();
+    for height in 0..3 {
+        records.record_block(round(height, "alice", &[])).unwrap();
+    }
+    // Queued mid-epoch: takes effect at height 5, not before
+    records.queue_validator_set(set(&[("alice", 20), ("carol", 5)])).unwrap();
+    for height in 3..7 {
+        records.record_block(round(height, "alice", &[])).unwrap();
+    }
+    assert_eq!(records.block_votes(4).unwrap().votes.len(), 2);
+    assert!(records.block_votes(4).unwrap().votes.iter().any(|v| v.validator == "bob"));
+    // Unchanged set carries over; epochs can be skipped by a gap in heights
+    records.record_block(round(17, "alice", &[])).unwrap();
+
+    let epochs = records.epochs();
+    assert_eq!(epochs.iter().map(|e| e.epoch).collect::<Vec<_>>(), vec![0, 1, 3]);
+    assert_eq!(epochs[0].joined, vec!["alice", "bob"]);
+    assert_eq!(epochs[0].end_height, Some(4));
+    assert_eq!(epochs[1].start_height, 5);
+    assert_eq!(epochs[1].joined, vec!["carol"]);
+    assert_eq!(epochs[1].left, vec!["bob"]);
+    assert_eq!(
+        epochs[1].stake_changes,
+        vec![StakeChange { address: "alice".to_string(), from: 10, to: 20 }]
+    );
+    assert_eq!(epochs[1].end_height, Some(6));
+    assert!(epochs[2].joined.is_empty() && epochs[2].left.is_empty() && epochs[2].stake_changes.is_empty());
+    assert_eq!(epochs[2].end_height, None);
+
+    let (_, first) = records.validators(Some(0)).unwrap();
+    let bob = first.iter().find(|v| v.address == "bob").unwrap();
+    assert_eq!((bob.joined_height, bob.left_height), (0, Some(5)));
+    let (epoch, current) = records.validators(None).unwrap();
+    assert_eq!(epoch, 3);
+    let carol = current.iter().find(|v| v.address == "carol").unwrap();
+    assert_eq!((carol.stake, carol.joined_height, carol.left_height), (5, 5, None));
+    assert!(records.validators(Some(2)).is_none());
+}
+
+#[test]
+fn test_pruned_detail_keeps_participation_rates() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal = dir.path().join("votes.jsonl");
+    let mut records = ConsensusRecords::open(config(4, 2, Some(&journal))).unwrap();
+    records.queue_validator_set(set(&[("alice", 1), ("bob", 1)])).unwrap();
+
+    // Bob precommits on every other block and never prevotes late
+    for height in 0..16 {
+        let bob = if height % 2 == 0 { voted(5, 15) } else { VoteTiming { prevote_ms: Some(5), precommit_ms: None } };
+        records.record_block(round(height, "alice", &[("alice", voted(1, 2)), ("bob", bob)])).unwrap();
+    }
+
+    // Two epochs of detail: heights 8..16
+    assert!(records.block_votes(7).is_none());
+    assert!(records.block_votes(8).is_some());
+    assert_eq!(records.oldest_detail_height(), Some(8));
+
+    let bob = records.performance("bob", 4).unwrap();
+    assert_eq!(bob.blocks, 16);
+    assert!((bob.participation_rate - 0.75).abs() < 1e-9);
+    assert_eq!(bob.average_vote_latency_ms, Some((16.0 * 5.0 + 8.0 * 15.0) / 24.0));
+    assert_eq!(bob.longest_missed_streak, 1);
+
+    // Compaction rewrote the journal; reopening restores the same view
+    let compacted = std::fs::read_to_string(&journal).unwrap();
+    assert!(compacted.starts_with("{\"snapshot\""));
+    drop(records);
+    let reopened = ConsensusRecords::open(config(4, 2, Some(&journal))).unwrap();
+    assert_eq!(reopened.performance("bob", 4).unwrap(), bob);
+    assert!(reopened.block_votes(7).is_none());
+    assert_eq!(reopened.block_votes(15).unwrap().votes.len(), 2);
+    assert_eq!(reopened.epochs().len(), 4);
+}
@@ -0,0 +1,116 @@
+//! Event bus end to end: block import -> contract matcher -> webhook forwarder
+use arthachain_node::config::Config;
+use arthachain_node::events::{EventBus, ForwardRoute, ForwarderConfig, WebhookForwarder};
+use arthachain_node::ledger::block::{Block, BlsPublicKey};
+use arthachain_node::ledger::state::State;
+use arthachain_node::ledger::transaction::{TransactionLog, TransactionReceipt};
+use arthachain_node::types::{Hash, TransactionHash};
+use axum::{routing::post, Json, Router};
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+const PROOF_OF_COMPUTE: &str = "0x5fbdb2315678afecb367f032d93f642f64180aa3";
+
+fn receipt_finalized_log(job_id: u8, payout: u8) -> TransactionLog {
+    TransactionLog {
+        address: PROOF_OF_COMPUTE.to_string(),
+        topics: vec![
+            Keccak256::digest(b"ReceiptFinalized(bytes32,uint256,uint256)").to_vec(),
+            vec![job_id; 32],
+        ],
+        data: vec![payout; 64],
+    }
+}
+
+fn receipt(height: u64, logs: Vec<TransactionLog>) -> TransactionReceipt {
+    TransactionReceipt {
+        tx_hash: TransactionHash::from(vec![height as u8; 32]),
+        block_height: height,
+        block_timestamp: 0,
+        tx_index: 0,
+        status: 0,
+        gas_used: 50_000,
+        logs,
+    }
+}
+
+async fn mock_service() -> (String, mpsc::UnboundedReceiver<serde_json::Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let tx = Arc::new(Mutex::new(tx));
+    let app = Router::new().route(
+        "/events",
+        post(move |Json(body): Json<serde_json::Value>| {
+            let tx = tx.clone();
+            async move {
+                let _ = tx.lock().await.send(body);
+                "OK"
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}/events", addr), rx)
+}
+
+#[tokio::test]
+async fn forwarder_delivers_finalized_proof_of_compute_event() {
+    let (jobd_url, mut jobd) = mock_service().await;
+    let (receipts_url, mut receipts_daemon) = mock_service().await;
+
+    let state = State::new(&Config::default()).unwrap();
+    let bus = EventBus::new(1024);
+    state.attach_event_bus(bus.clone());
+    WebhookForwarder::spawn(
+        &bus,
+        ForwarderConfig {
+            routes: vec![ForwardRoute {
+                name: "ProofOfCompute".to_string(),
+                contract: PROOF_OF_COMPUTE.to_string(),
+                targets: vec![jobd_url, receipts_url],
+            }],
+        },
+    );
+
+    let height = state.get_height().unwrap() + 1;
+    let parent = state.latest_block().map(|b| b.hash().unwrap()).unwrap_or_default();
+    let producer = BlsPublicKey::new(vec![1u8; 48]);
+
+    // A fork block whose log must never be forwarded
+    let orphan = Block::new(Hash::new(vec![0xee; 32]), Vec::new(), producer.clone(), 1, height).unwrap();
+    state
+        .add_block_with_receipts(orphan, vec![receipt(height, vec![receipt_finalized_log(9, 9)])])
+        .unwrap();
+
+    let canonical = Block::new(parent, Vec::new(), producer, 1, height).unwrap();
+    let canonical_hash = canonical.hash().unwrap().to_evm_hex();
+    state
+        .add_block_with_receipts(canonical, vec![receipt(height, vec![receipt_finalized_log(7, 3)])])
+        .unwrap();
+
+    // Nothing goes out before finality
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(jobd.try_recv().is_err());
+
+    assert!(state.mark_finalized(height).unwrap() > 0);
+
+    for service in [&mut jobd, &mut receipts_daemon] {
+        let body = tokio::time::timeout(Duration::from_secs(5), service.recv())
+            .await
+            .expect("event not forwarded")
+            .unwrap();
+        assert_eq!(body["contract"], "ProofOfCompute");
+        assert_eq!(body["block_height"], height);
+        assert_eq!(body["block_hash"], canonical_hash);
+        assert_eq!(body["topics"][1], format!("0x{}", hex::encode([7u8; 32])));
+        assert_eq!(body["data"], format!("0x{}", hex::encode([3u8; 64])));
+        assert_eq!(body["finalized"], true);
+    }
+
+    // The orphaned log was discarded rather than forwarded
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(jobd.try_recv().is_err());
+    assert!(receipts_daemon.try_recv().is_err());
+}
@@ -0,0 +1,191 @@
+//! Genesis built from a chain spec must be reproducible and must keep nodes on
+//! different specs from peering
+use arthachain_node::genesis::{ChainIdentity, ChainSpec, Genesis, GenesisError};
+use arthachain_node::ledger::state::State;
+use arthachain_node::network::p2p::PeerDiscoveryMessage;
+
+const SPEC_TOML: &str = r#"
+chain_id = 201766
+name = "artha-testnet-2026-10"
+genesis_time = 1791000000
+supply_cap = 1000000000
+
+[params]
+block_time_ms = 2000
+gas_limit = 30000000
+
+[[balances]]
+address = "0x00000000000000000000000000000000000000a1"
+balance = 5000000
+
+[[balances]]
+address = "0x00000000000000000000000000000000000000A2"
+balance = 7000
+
+[[contracts]]
+address = "0x00000000000000000000000000000000000000c1"
+code = "0x6080604052"
+storage = { "0x01" = "0xff" }
+
+[[validators]]
+address = "0x00000000000000000000000000000000000000b1"
+stake = 1000000
+bls_public_key = "0x8f4e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e8c8e"
+
+[ai_contracts]
+ai_job_manager = "0x00000000000000000000000000000000000000c1"
+"#;
+
+fn spec() -> ChainSpec {
+    ChainSpec::from_toml(SPEC_TOML).unwrap()
+}
+
+fn announcement(state: &State) -> PeerDiscoveryMessage {
+    PeerDiscoveryMessage {
+        node_id: "node".to_string(),
+        listen_addresses: vec!["127.0.0.1:8084".to_string()],
+        protocol_version: "arthachain/1.0".to_string(),
+        services: vec!["blockchain".to_string()],
+        timestamp: 0,
+        chain: ChainIdentity::from_state(state),
+    }
+}
+
+#[test]
+fn same_spec_builds_identical_genesis() {
+    let first = Genesis::build(&spec()).unwrap();
+    let second = Genesis::build(&spec()).unwrap();
+    assert_eq!(first.state_root, second.state_root);
+    assert_eq!(first.hash(), second.hash());
+    assert_eq!(first.block.header.previous_hash, first.spec_hash);
+
+    // The same spec as JSON, with lists reordered, is the same chain
+    let mut reordered = spec();
+    reordered.balances.reverse();
+    let json = serde_json::to_string(&reordered).unwrap();
+    let from_json = Genesis::build(&ChainSpec::from_json(&json).unwrap()).unwrap();
+    assert_eq!(from_json.spec_hash, first.spec_hash);
+    assert_eq!(from_json.state_root, first.state_root);
+
+    // Initialized data directories hold byte-identical state roots
+    let dir_a = tempfile::tempdir().unwrap();
+    let dir_b = tempfile::tempdir().unwrap();
+    let state_a = first.initialize(dir_a.path(), false).unwrap();
+    let state_b = second.initialize(dir_b.path(), false).unwrap();
+    assert_eq!(state_a.get_state_root().unwrap(), first.state_root);
+    assert_eq!(state_a.get_state_root().unwrap().to_bytes(), state_b.get_state_root().unwrap().to_bytes());
+    assert_eq!(state_a.get_balance("0x00000000000000000000000000000000000000a2").unwrap(), 7000);
+}
+
+#[test]
+fn mismatched_spec_is_rejected() {
+    let local = Genesis::build(&spec()).unwrap().identity();
+
+    let mut other = spec();
+    other.params.gas_limit += 1;
+    let remote = Genesis::build(&other).unwrap().identity();
+    assert!(matches!(local.check_peer(&remote), Err(GenesisError::SpecMismatch { .. })));
+
+    let mut other_chain = spec();
+    other_chain.chain_id += 1;
+    let remote = Genesis::build(&other_chain).unwrap().identity();
+    assert!(matches!(local.check_peer(&remote), Err(GenesisError::ChainIdMismatch { .. })));
+
+    assert_eq!(ChainIdentity::admits(Some(&local), None), Err(GenesisError::MissingChainIdentity));
+    assert_eq!(ChainIdentity::admits(None, Some(&remote)), Ok(()));
+}
+
+#[test]
+fn malformed_specs_fail_validation() {
+    let build = |edit: fn(&mut ChainSpec)| {
+        let mut spec = spec();
+        edit(&mut spec);
+        Genesis::build(&spec).unwrap_err()
+    };
+
+    assert!(matches!(build(|s| s.chain_id = 0), GenesisError::InvalidParam(_)));
+    assert!(matches!(build(|s| s.params.block_time_ms = 0), GenesisError::InvalidParam(_)));
+    assert!(matches!(build(|s| s.params.gas_limit = 0), GenesisError::InvalidParam(_)));
+    assert!(matches!(build(|s| s.balances[0].address = "0x1234".to_string()), GenesisError::InvalidAddress { .. }));
+    // Addresses differing only in case are the same account
+    assert!(matches!(
+        build(|s| s.balances[1].address = "0x00000000000000000000000000000000000000A1".to_string()),
+        GenesisError::DuplicateAddress { section: "balances", .. }
+    ));
+    assert!(matches!(build(|s| s.balances[0].balance = 999_000_001), GenesisError::SupplyCapExceeded { .. }));
+    assert!(matches!(build(|s| s.contracts[0].code = "0x".to_string()), GenesisError::EmptyContractCode(_)));
+    assert!(matches!(build(|s| s.contracts[0].code = "0xzz".to_string()), GenesisError::InvalidHex { .. }));
+    assert!(matches!(
+        build(|s| s.contracts.push(s.contracts[0].clone())),
+        GenesisError::DuplicateAddress { section: "contracts", .. }
+    ));
+    assert!(matches!(build(|s| s.validators.clear()), GenesisError::NoValidators));
+    assert!(matches!(build(|s| s.validators[0].stake = 0), GenesisError::ZeroStake(_)));
+    assert!(matches!(build(|s| s.validators[0].bls_public_key = "0x8f4e".to_string()), GenesisError::InvalidValidatorKey(_)));
+    assert!(matches!(
+        build(|s| {
+            s.ai_contracts.insert("model_registry".to_string(), "0x00000000000000000000000000000000000000d1".to_string());
+        }),
+        GenesisError::UnknownRegistryContract { .. }
+    ));
+    assert!(matches!(ChainSpec::from_toml("chain_id = 1\nunknown = true"), Err(GenesisError::Parse(_))));
+}
+
+#[test]
+fn reinitializing_requires_force() {
+    let genesis = Genesis::build(&spec()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    genesis.initialize(dir.path(), false).unwrap();
+    assert_eq!(
+        genesis.initialize(dir.path(), false).unwrap_err(),
+        GenesisError::AlreadyInitialized(dir.path().to_path_buf())
+    );
+
+    // Balances moved after genesis are wiped by a forced re-init
+    let state = State::open(&dir.path().to_string_lossy()).unwrap();
+    state.set_balance("0x00000000000000000000000000000000000000a1", 1).unwrap();
+    state.save_state().unwrap();
+    let state = genesis.initialize(dir.path(), true).unwrap();
+    assert_eq!(state.get_state_root().unwrap(), genesis.state_root);
+}
+
+#[test]
+fn two_nodes_from_a_shared_spec_peer() {
+    let spec_dir = tempfile::tempdir().unwrap();
+    let spec_path = spec_dir.path().join("testnet.toml");
+    std::fs::write(&spec_path, SPEC_TOML).unwrap();
+
+    // Two operators initialize separately from the same file, then restart
+    let mut nodes = Vec::new();
+    for _ in 0..2 {
+        let dir = tempfile::tempdir().unwrap();
+        Genesis::build(&ChainSpec::from_file(&spec_path).unwrap())
+            .unwrap()
+            .initialize(dir.path(), false)
+            .unwrap();
+        let loaded = Genesis::load(dir.path()).unwrap().unwrap();
+        let state = State::open(&dir.path().to_string_lossy()).unwrap();
+        assert_eq!(ChainIdentity::from_state(&state), Some(loaded.identity()));
+        nodes.push((dir, state));
+    }
+
+    // Each node checks the other's discovery announcement as received off the wire
+    let received = |state: &State| -> PeerDiscoveryMessage {
+        serde_json::from_slice(&serde_json::to_vec(&announcement(state)).unwrap()).unwrap()
+    };
+    let (a, b) = (&nodes[0].1, &nodes[1].1);
+    let id_a = ChainIdentity::from_state(a);
+    let id_b = ChainIdentity::from_state(b);
+    assert_eq!(ChainIdentity::admits(id_a.as_ref(), received(b).chain.as_ref()), Ok(()));
+    assert_eq!(ChainIdentity::admits(id_b.as_ref(), received(a).chain.as_ref()), Ok(()));
+
+    // A third node on a different spec is refused by both
+    let mut other = spec();
+    other.genesis_time += 1;
+    let dir = tempfile::tempdir().unwrap();
+    let stranger = Genesis::build(&other).unwrap().initialize(dir.path(), false).unwrap();
+    for local in [&id_a, &id_b] {
+        let result = ChainIdentity::admits(local.as_ref(), received(&stranger).chain.as_ref());
+        assert!(matches!(result, Err(GenesisError::SpecMismatch { .. })));
+    }
+}
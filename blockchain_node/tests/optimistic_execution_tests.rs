@@ -0,0 +1,343 @@
+//! Optimistic parallel execution must commit the same state as sequential execution
+use arthachain_node::config::Config;
+use arthachain_node::execution::optimistic::{CallExecutor, OptimisticConfig, OptimisticExecutor, TransferExecutor};
+use arthachain_node::execution::TransactionExecutor;
+use arthachain_node::ledger::state::State;
+use arthachain_node::ledger::transaction::{Transaction, TransactionType};
+use proptest::prelude::*;
+
+const ACCOUNTS: usize = 8;
+const TOKEN: &str = "0xtoken";
+
+fn account(i: usize) -> String {
+    format!("0x{:040x}", i + 1)
+}
+
+/// Balance key credited by the built-in token transfer to holder `i`
+fn holder(i: usize) -> String {
+    format!("{:040x}", i + 1)
+}
+
+fn transfer(sender: usize, recipient: usize, amount: u64, nonce: u64) -> Transaction {
+    let mut tx = Transaction::new(
+        TransactionType::Transfer,
+        account(sender),
+        account(recipient),
+        amount,
+        nonce,
+        1,
+        21_000,
+        vec![],
+    );
+    tx.signature = vec![1];
+    tx
+}
+
+/// Contract call to the token: `transfer(holder, amount)` when `amount` is
+/// set, otherwise `balanceOf(holder)`
+fn token_call(sender: usize, to_holder: usize, amount: Option<u64>, nonce: u64) -> Transaction {
+    let mut address = [0u8; 32];
+    address[24..].copy_from_slice(&((to_holder + 1) as u64).to_be_bytes());
+    let mut data = match amount {
+        Some(_) => vec![0x70, 0xa0, 0x82, 0x31],
+        None => vec![0x18, 0x16, 0x0d, 0xdd],
+    };
+    data.extend_from_slice(&address);
+    if let Some(amount) = amount {
+        data.extend_from_slice(&[0u8; 24]);
+        data.extend_from_slice(&amount.to_be_bytes());
+    }
+
+    let mut tx = Transaction::new(
+        TransactionType::ContractCall,
+        account(sender),
+        TOKEN.to_string(),
+        0,
+        nonce,
+        1,
+        21_000,
+        data,
+    );
+    tx.signature = vec![1];
+    tx
+}
+
+fn funded_state(balances: &[u64]) -> State {
+    let state = State::new(&Config::default()).unwrap();
+    for (i, balance) in balances.iter().enumerate() {
+        state.set_balance(&account(i), *balance).unwrap();
+    }
+    state
+}
+
+fn optimistic(config: OptimisticConfig) -> OptimisticExecutor<TransferExecutor> {
+    OptimisticExecutor::new(
+        TransferExecutor {
+            max_gas_limit: u64::MAX,
+            min_gas_price: 0,
+        },
+        config,
+    )
+}
+
+fn optimistic_calls(config: OptimisticConfig) -> OptimisticExecutor<CallExecutor> {
+    OptimisticExecutor::new(
+        CallExecutor {
+            max_gas_limit: u64::MAX,
+            min_gas_price: 0,
+        },
+        config,
+    )
+}
+
+fn token_state(balances: &[u64]) -> State {
+    let state = funded_state(balances);
+    state.set_storage(&format!("contract:{}", TOKEN), vec![0x60, 0x80]).unwrap();
+    state
+}
+
+fn run_sequential(transactions: &[Transaction], state: &State) -> Vec<String> {
+    let executor = TransactionExecutor::new(None, 1.0, u64::MAX, 0);
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    transactions
+        .iter()
+        .map(|tx| {
+            let mut tx = tx.clone();
+            let result = rt.block_on(executor.execute_transaction(&mut tx, state)).unwrap();
+            format!("{:?}", result)
+        })
+        .collect()
+}
+
+fn assert_same_state(a: &State, b: &State) {
+    for i in 0..ACCOUNTS {
+        assert_eq!(a.get_balance(&account(i)).unwrap(), b.get_balance(&account(i)).unwrap());
+        assert_eq!(a.get_nonce(&account(i)).unwrap(), b.get_nonce(&account(i)).unwrap());
+        assert_eq!(a.get_balance(&holder(i)).unwrap(), b.get_balance(&holder(i)).unwrap());
+    }
+}
+
+/// Random transfers where senders pick up their next expected nonce, with
+/// occasional stale nonces and overdrafts to exercise failure paths
+fn block_strategy() -> impl Strategy<Value = (Vec<u64>, Vec<Transaction>)> {
+    (
+        prop::collection::vec(0u64..200_000, ACCOUNTS),
+        prop::collection::vec((0..ACCOUNTS, 0..ACCOUNTS, 0u64..50_000, any::<bool>()), 0..120),
+    )
+        .prop_map(|(balances, specs)| {
+            let mut nonces = vec![0u64; ACCOUNTS];
+            let transactions = specs
+                .into_iter()
+                .map(|(sender, recipient, amount, stale)| {
+                    let nonce = if stale && nonces[sender] > 0 {
+                        nonces[sender] - 1
+                    } else {
+                        nonces[sender]
+                    };
+                    nonces[sender] += 1;
+                    transfer(sender, recipient, amount, nonce)
+                })
+                .collect();
+            (balances, transactions)
+        })
+}
+
+/// Transfers and token calls mixed in one block; token transfers credit the
+/// holder keys that later balance queries and transfers read
+fn call_block_strategy() -> impl Strategy<Value = (Vec<u64>, Vec<Transaction>)> {
+    (
+        prop::collection::vec(0u64..200_000, ACCOUNTS),
+        prop::collection::vec((0..ACCOUNTS, 0..ACCOUNTS, 0u64..50_000, 0u8..4), 0..120),
+    )
+        .prop_map(|(balances, specs)| {
+            let mut nonces = [0u64; ACCOUNTS];
+            let transactions = specs
+                .into_iter()
+                .map(|(sender, recipient, amount, kind)| {
+                    let nonce = nonces[sender];
+                    nonces[sender] += 1;
+                    match kind {
+                        0 => transfer(sender, recipient, amount, nonce),
+                        1 => token_call(sender, recipient, Some(amount), nonce),
+                        2 => token_call(sender, recipient, None, nonce),
+                        _ => {
+                            // A call to an address without code fails without touching state
+                            let mut tx = token_call(sender, recipient, None, nonce);
+                            tx.recipient = account(recipient);
+                            tx
+                        }
+                    }
+                })
+                .collect();
+            (balances, transactions)
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_contract_calls_match_sequential((balances, transactions) in call_block_strategy(), batch in 1usize..32) {
+        let sequential_state = token_state(&balances);
+        let expected = run_sequential(&transactions, &sequential_state);
+
+        let parallel_state = token_state(&balances);
+        let executor = optimistic_calls(OptimisticConfig {
+            initial_batch_size: batch,
+            min_batch_size: 1,
+            sequential_fallback_rate: 1.1,
+            ..OptimisticConfig::default()
+        });
+        let outcome = executor.execute_block(&transactions, &parallel_state).unwrap();
+        let actual: Vec<String> = outcome.results.iter().map(|r| format!("{:?}", r)).collect();
+
+        prop_assert!(!outcome.sequential);
+        prop_assert_eq!(actual, expected);
+        assert_same_state(&sequential_state, &parallel_state);
+    }
+
+    #[test]
+    fn prop_optimistic_matches_sequential((balances, transactions) in block_strategy(), batch in 1usize..32) {
+        let sequential_state = funded_state(&balances);
+        let expected = run_sequential(&transactions, &sequential_state);
+
+        let parallel_state = funded_state(&balances);
+        let executor = optimistic(OptimisticConfig {
+            initial_batch_size: batch,
+            min_batch_size: 1,
+            sequential_fallback_rate: 1.1,
+            ..OptimisticConfig::default()
+        });
+        let outcome = executor.execute_block(&transactions, &parallel_state).unwrap();
+        let actual: Vec<String> = outcome.results.iter().map(|r| format!("{:?}", r)).collect();
+
+        prop_assert_eq!(actual, expected);
+        assert_same_state(&sequential_state, &parallel_state);
+    }
+}
+
+#[test]
+fn test_read_after_write_hazard_is_re_executed() {
+    // account 1 can only pay account 2 after receiving funds from account 0
+    let balances = [1_000_000, 21_000, 0, 0, 0, 0, 0, 0];
+    let transactions = vec![
+        transfer(0, 1, 50_000, 0),
+        transfer(1, 2, 40_000, 0),
+        transfer(3, 4, 0, 0),
+    ];
+
+    let sequential_state = funded_state(&balances);
+    let expected = run_sequential(&transactions, &sequential_state);
+
+    let parallel_state = funded_state(&balances);
+    let executor = optimistic(OptimisticConfig::default());
+    let outcome = executor.execute_block(&transactions, &parallel_state).unwrap();
+
+    assert!(!outcome.sequential);
+    assert_eq!(outcome.conflicts, 1);
+    assert!(matches!(outcome.results[1], arthachain_node::execution::ExecutionResult::Success));
+    assert_eq!(
+        outcome.results.iter().map(|r| format!("{:?}", r)).collect::<Vec<_>>(),
+        expected
+    );
+    assert_same_state(&sequential_state, &parallel_state);
+    assert_eq!(parallel_state.get_balance(&account(2)).unwrap(), 40_000);
+
+    let metrics = executor.metrics();
+    assert_eq!(metrics.re_executed, 1);
+    assert_eq!(metrics.parallel_executed, 2);
+}
+
+#[test]
+fn test_same_sender_nonce_chain() {
+    let balances = [1_000_000, 0, 0, 0, 0, 0, 0, 0];
+    let transactions: Vec<Transaction> = (0..5).map(|n| transfer(0, 1, 1_000, n)).collect();
+
+    let state = funded_state(&balances);
+    let executor = optimistic(OptimisticConfig::default());
+    let outcome = executor.execute_block(&transactions, &state).unwrap();
+
+    assert_eq!(outcome.conflicts, 4);
+    assert!(outcome
+        .results
+        .iter()
+        .all(|r| matches!(r, arthachain_node::execution::ExecutionResult::Success)));
+    assert_eq!(state.get_nonce(&account(0)).unwrap(), 5);
+    assert_eq!(state.get_balance(&account(1)).unwrap(), 5_000);
+}
+
+#[test]
+fn test_contract_deployment_forces_sequential() {
+    let balances = [1_000_000, 1_000_000, 0, 0, 0, 0, 0, 0];
+    let mut deploy = transfer(1, 2, 0, 0);
+    deploy.tx_type = TransactionType::ContractCreate;
+    let transactions = vec![transfer(0, 2, 10, 0), deploy];
+
+    let state = funded_state(&balances);
+    let executor = optimistic(OptimisticConfig::default());
+    let outcome = executor.execute_block(&transactions, &state).unwrap();
+
+    assert!(outcome.sequential);
+    assert_eq!(executor.metrics().sequential_blocks, 1);
+}
+
+#[test]
+fn test_high_conflict_rate_falls_back_to_sequential() {
+    let balances = [10_000_000, 0, 0, 0, 0, 0, 0, 0];
+    let executor = optimistic(OptimisticConfig {
+        initial_batch_size: 16,
+        min_batch_size: 2,
+        sequential_fallback_rate: 0.5,
+        ..OptimisticConfig::default()
+    });
+    let state = funded_state(&balances);
+
+    // A nonce chain from one sender conflicts on every transaction after the first
+    let first: Vec<Transaction> = (0..8).map(|n| transfer(0, 1, 1, n)).collect();
+    let outcome = executor.execute_block(&first, &state).unwrap();
+    assert!(!outcome.sequential);
+    assert!(executor.batch_size() < 16);
+
+    let second: Vec<Transaction> = (8..16).map(|n| transfer(0, 1, 1, n)).collect();
+    let outcome = executor.execute_block(&second, &state).unwrap();
+    assert!(outcome.sequential);
+    assert_eq!(state.get_nonce(&account(0)).unwrap(), 16);
+}
+
+#[test]
+fn test_contract_call_reads_conflict_with_token_transfer() {
+    let balances = [1_000_000, 1_000_000, 0, 0, 0, 0, 0, 0];
+    let transactions = vec![
+        token_call(0, 5, Some(70_000), 0),
+        token_call(1, 5, None, 0),
+        transfer(2, 3, 0, 0),
+    ];
+
+    let sequential_state = token_state(&balances);
+    let expected = run_sequential(&transactions, &sequential_state);
+
+    let parallel_state = token_state(&balances);
+    let executor = optimistic_calls(OptimisticConfig::default());
+    let outcome = executor.execute_block(&transactions, &parallel_state).unwrap();
+
+    // The balance query reads the holder key the token transfer wrote
+    assert!(!outcome.sequential);
+    assert_eq!(outcome.conflicts, 1);
+    assert_eq!(
+        outcome.results.iter().map(|r| format!("{:?}", r)).collect::<Vec<_>>(),
+        expected
+    );
+    assert_same_state(&sequential_state, &parallel_state);
+    assert_eq!(parallel_state.get_balance(&holder(5)).unwrap(), 70_000);
+}
+
+#[test]
+fn test_unsupported_transaction_forces_sequential() {
+    let mut stake = transfer(1, 1, 10, 0);
+    stake.tx_type = TransactionType::Stake;
+    let transactions = vec![token_call(0, 2, Some(10), 0), stake];
+
+    let executor = optimistic_calls(OptimisticConfig::default());
+    assert!(executor.requires_sequential(&transactions));
+    assert!(!executor.requires_sequential(&transactions[..1]));
+}
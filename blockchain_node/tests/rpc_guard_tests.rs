@@ -0,0 +1,200 @@
+//! RPC guard: cost classification, expensive-query cap, live reload, and
+//! platform latency under a public flood
+use arthachain_node::api::rpc_guard::{self, Budget, Caller, CostClass, MethodCost, RejectReason, RpcGuard, RpcGuardConfig};
+use axum::{routing::post, Json, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SERVICE_KEY: &str = "jobd-secret";
+
+fn roomy_config() -> RpcGuardConfig {
+    let plenty = Budget { per_second: 1_000_000.0, burst: 1_000_000.0 };
+    let mut config = RpcGuardConfig { cheap: plenty, expensive: plenty, submit: plenty, ..Default::default() };
+    config.service_keys.insert(SERVICE_KEY.to_string(), "ai-jobd".to_string());
+    config
+}
+
+/// A node whose every RPC call takes `work` to answer
+async fn serve(guard: Arc<RpcGuard>, work: Duration) -> String {
+    let app = Router::new()
+        .route(
+            "/rpc",
+            post(move |Json(call): Json<serde_json::Value>| async move {
+                tokio::time::sleep(work).await;
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": call["id"], "result": "0x1" }))
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(guard, rpc_guard::protect));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+    });
+    format!("http://{}/rpc", addr)
+}
+
+fn call(method: &str) -> serde_json::Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": [] })
+}
+
+#[test]
+fn test_methods_classified_with_cost_weights() {
+    let config = RpcGuardConfig::default();
+    assert_eq!(config.method_cost("eth_getLogs"), MethodCost::new(CostClass::Expensive, 10));
+    assert_eq!(config.method_cost("eth_getProof"), MethodCost::new(CostClass::Expensive, 8));
+    assert_eq!(config.method_cost("debug_traceTransaction"), MethodCost::new(CostClass::Expensive, 20));
+    assert_eq!(config.method_cost("trace_block"), MethodCost::new(CostClass::Expensive, 20));
+    assert_eq!(config.method_cost("eth_sendRawTransaction"), MethodCost::new(CostClass::Submit, 1));
+    assert_eq!(config.method_cost("eth_call"), MethodCost::new(CostClass::Cheap, 2));
+    // Unlisted methods, and names that only share a prefix with an exact entry
+    assert_eq!(config.method_cost("eth_blockNumber"), MethodCost::new(CostClass::Cheap, 1));
+    assert_eq!(config.method_cost("eth_getLogsExtra"), MethodCost::new(CostClass::Cheap, 1));
+    assert_eq!(config.path_cost("/api/v1/transactions/submit"), MethodCost::new(CostClass::Submit, 1));
+    assert_eq!(config.path_cost("/api/v1/transactions/0xabc"), MethodCost::new(CostClass::Cheap, 1));
+
+    let guard = RpcGuard::new(config);
+    let batch = serde_json::to_vec(&serde_json::json!([call("eth_getLogs"), call("eth_chainId")])).unwrap();
+    let (costs, id) = guard.classify_rpc(&batch).unwrap();
+    assert_eq!(costs, vec![MethodCost::new(CostClass::Expensive, 10), MethodCost::new(CostClass::Cheap, 1)]);
+    assert_eq!(id, serde_json::Value::Null);
+    let single = serde_json::to_vec(&call("eth_sendRawTransaction")).unwrap();
+    assert_eq!(guard.classify_rpc(&single).unwrap().1, serde_json::json!(7));
+    assert!(guard.classify_rpc(br#"{"to": "0x1"}"#).is_none());
+}
+
+#[tokio::test]
+async fn test_expensive_queries_capped_in_flight() {
+    let mut config = roomy_config();
+    config.expensive_max_concurrency = 2;
+    config.backoff_ms = 2500;
+    let guard = RpcGuard::new(config);
+    let logs = [MethodCost::new(CostClass::Expensive, 10)];
+    let public = |n: u32| Caller::Public(format!("ip:10.0.0.{}", n));
+
+    let first = guard.admit(&public(1), &logs).await.unwrap();
+    let _second = guard.admit(&public(2), &logs).await.unwrap();
+    let rejection = guard.admit(&public(3), &logs).await.err().unwrap();
+    assert_eq!(rejection.reason, RejectReason::ExpensiveCap);
+    assert_eq!(rejection.backoff, Duration::from_millis(2500));
+    // Cheap reads and platform services are not held to the cap
+    let _cheap = guard.admit(&public(3), &[MethodCost::new(CostClass::Cheap, 1)]).await.unwrap();
+    let platform = guard.admit(&Caller::Platform("ai-jobd".to_string()), &logs).await.unwrap();
+    assert_eq!(guard.snapshot().expensive_in_flight, 3);
+
+    // but their queries occupy the node all the same
+    drop(first);
+    assert!(guard.admit(&public(3), &logs).await.is_err());
+    drop(platform);
+    let _third = guard.admit(&public(3), &logs).await.unwrap();
+
+    // Over HTTP the overflow is a retriable 429 with the JSON-RPC limit error
+    let url = serve(RpcGuard::new(RpcGuardConfig { expensive_max_concurrency: 1, ..roomy_config() }), Duration::from_millis(500)).await;
+    let client = reqwest::Client::new();
+    let slow = tokio::spawn({
+        let (client, url) = (client.clone(), url.clone());
+        async move { client.post(&url).json(&call("eth_getLogs")).send().await.unwrap().status() }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let response = client.post(&url).json(&call("eth_getLogs")).send().await.unwrap();
+    assert_eq!(response.status(), 429);
+    assert_eq!(response.headers()["retry-after"], "1");
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["id"], 7);
+    assert_eq!(body["error"]["code"], -32005);
+    assert_eq!(body["error"]["data"]["reason"], "expensive_cap");
+    assert_eq!(body["error"]["data"]["backoff_ms"], 1000);
+    assert_eq!(slow.await.unwrap(), 200);
+}
+
+#[tokio::test]
+async fn test_config_reload_changes_limits_live() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rpc_guard.yaml");
+    std::fs::write(&path, "cheap: { per_second: 0.001, burst: 2 }\nbackoff_ms: 100\n").unwrap();
+    let guard = RpcGuard::new(RpcGuardConfig::from_file(&path).unwrap());
+    guard.watch(path.clone(), Duration::from_millis(20));
+    let client = Caller::Public("ip:10.0.0.9".to_string());
+    let read = [MethodCost::new(CostClass::Cheap, 1)];
+
+    assert!(guard.admit(&client, &read).await.is_ok());
+    assert!(guard.admit(&client, &read).await.is_ok());
+    let rejection = guard.admit(&client, &read).await.err().unwrap();
+    assert_eq!(rejection.reason, RejectReason::RateLimited);
+    // The suggested backoff is when the bucket will afford the call again
+    assert!(rejection.backoff > Duration::from_secs(100));
+
+    // A broken edit is ignored and the running limits kept
+    std::fs::write(&path, "workers: 4\nplatform_reserved_workers: 4\n").unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(guard.config().cheap.burst, 2.0);
+
+    std::fs::write(&path, "cheap: { per_second: 1000, burst: 50 }\nexpensive_max_concurrency: 1\n").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while guard.config().cheap.burst != 50.0 {
+        assert!(Instant::now() < deadline, "config was not reloaded");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    // The same client's bucket refills at the new rate without a restart
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(guard.admit(&client, &read).await.is_ok());
+    assert_eq!(guard.config().expensive_max_concurrency, 1);
+    let stats = guard.snapshot();
+    assert_eq!(stats.top_consumers[0].client, "ip:10.0.0.9");
+    assert_eq!(stats.top_consumers[0].rejected, 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_public_flood_does_not_delay_platform_services() {
+    let mut config = roomy_config();
+    config.workers = 8;
+    config.platform_reserved_workers = 2;
+    let work = Duration::from_millis(50);
+    let guard = RpcGuard::new(config);
+    let url = serve(Arc::clone(&guard), work).await;
+    let client = reqwest::Client::builder().pool_max_idle_per_host(512).build().unwrap();
+
+    // 400 public calls need over three seconds of worker time; the flood
+    // keeps every public worker busy and a deep public queue for the whole
+    // measurement
+    let flood: Vec<_> = (0..400)
+        .map(|_| {
+            let (client, url) = (client.clone(), url.clone());
+            tokio::spawn(async move { client.post(&url).json(&call("eth_blockNumber")).send().await.map(|r| r.status()) })
+        })
+        .collect();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while guard.snapshot().workers.public_waiting < 100 {
+        assert!(Instant::now() < deadline, "the flood never queued");
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let mut worst = Duration::ZERO;
+    for _ in 0..10 {
+        let started = Instant::now();
+        let response = client
+            .post(&url)
+            .header("x-service-key", SERVICE_KEY)
+            .json(&call("eth_getTransactionReceipt"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        worst = worst.max(started.elapsed());
+    }
+    assert!(worst < work * 3, "platform call took {:?} under a public flood", worst);
+
+    // A public call made at the same time waits its turn behind the flood
+    let started = Instant::now();
+    let response = client.post(&url).json(&call("eth_chainId")).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(started.elapsed() > work * 4, "the flood was not queued");
+
+    let mut served = 0;
+    for request in flood {
+        if request.await.unwrap().is_ok_and(|s| s == 200) {
+            served += 1;
+        }
+    }
+    assert_eq!(served, 400);
+}
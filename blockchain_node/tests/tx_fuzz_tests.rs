@@ -0,0 +1,94 @@
+//! Transaction fuzzing harness: bounded seeded run plus regression corpus replay
+use arthachain_node::dev_tools::testing_framework::fuzz::{
+    self, corpus, invariants, CorpusEntry, DevnetConfig, FuzzAction, FuzzConfig, Violation,
+};
+use arthachain_node::execution::ExecutionResult;
+use arthachain_node::ledger::transaction::{Transaction, TransactionType};
+use std::path::Path;
+
+fn regression_dir() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/regressions"))
+}
+
+/// Bounded, deterministic run. Set ARTHA_FUZZ_ITERATIONS / ARTHA_FUZZ_SEED to
+/// widen it and ARTHA_FUZZ_CORPUS_DIR to keep minimized failures.
+#[test]
+fn seeded_sequences_hold_invariants() {
+    let report = fuzz::run_seeded(&FuzzConfig::from_env()).unwrap();
+    assert!(report.transactions > 0);
+
+    let failures: Vec<String> = report
+        .failures
+        .iter()
+        .map(|entry| serde_json::to_string_pretty(entry).unwrap())
+        .collect();
+    assert!(failures.is_empty(), "invariant violations:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn regression_corpus_replays_clean() {
+    let entries = corpus::load_dir(regression_dir()).unwrap();
+    assert!(!entries.is_empty());
+
+    for entry in entries {
+        let result = fuzz::replay(&entry, &DevnetConfig::default()).unwrap();
+        assert!(result.is_ok(), "{} regressed: {}", entry.name, result.unwrap_err());
+    }
+}
+
+#[test]
+fn seeded_mode_is_deterministic() {
+    let config = FuzzConfig { iterations: 16, ..FuzzConfig::default() };
+    let first = fuzz::run_seeded(&config).unwrap();
+    let second = fuzz::run_seeded(&config).unwrap();
+    assert_eq!(first.transactions, second.transactions);
+}
+
+#[test]
+fn minimizer_keeps_only_the_failing_action() {
+    let actions: Vec<FuzzAction> = (0..9)
+        .map(|i| if i == 6 { FuzzAction::Rollback } else { FuzzAction::Checkpoint })
+        .collect();
+    let minimized = corpus::minimize(&actions, |candidate| candidate.contains(&FuzzAction::Rollback));
+    assert_eq!(minimized, vec![FuzzAction::Rollback]);
+}
+
+#[test]
+fn transaction_check_flags_minting_and_overcharging() {
+    let tx = Transaction::new(
+        TransactionType::Transfer,
+        "sender".to_string(),
+        "recipient".to_string(),
+        10,
+        0,
+        2,
+        21_000,
+        Vec::new(),
+    );
+    let success = invariants::TxOutcome::Executed(ExecutionResult::Success);
+    let failure = invariants::TxOutcome::Error("rejected".to_string());
+
+    assert_eq!(invariants::check_transaction(0, &tx, &success, 100_000, 58_000, 30_000_000), Ok(42_000));
+    assert_eq!(invariants::check_transaction(0, &tx, &failure, 100_000, 100_000, 30_000_000), Ok(0));
+    assert!(matches!(
+        invariants::check_transaction(1, &tx, &failure, 100_000, 100_001, 30_000_000),
+        Err(Violation::BalanceNotConserved { .. })
+    ));
+    assert!(matches!(
+        invariants::check_transaction(2, &tx, &success, 100_000, 50_000, 30_000_000),
+        Err(Violation::GasLimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn corpus_entries_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let entry = CorpusEntry {
+        name: "round-trip".to_string(),
+        seed: Some(7),
+        found: "step 0: executor panicked".to_string(),
+        actions: corpus::load_dir(regression_dir()).unwrap().remove(0).actions,
+    };
+    entry.persist(dir.path()).unwrap();
+    assert_eq!(corpus::load_dir(dir.path()).unwrap(), vec![entry]);
+}
@@ -34,6 +34,27 @@ contract DealMarket {
     uint64 public epochSeconds = 60; // demo epoch
     IPriceOracle public priceOracle; // optional governance oracle
 
+    // Escrow accounts, keyed by the bytes32 services name a party by (the
+    // hash of a submitter's DID, a provider's node id). A payer deposits into
+    // its account and escrow operators lock costs from that balance.
+    struct EscrowAccount {
+        address owner; // Withdraws the free balance
+        uint256 balance; // Free to lock or withdraw
+    }
+
+    struct Escrow {
+        bytes32 payer;
+        uint256 amount; // Still locked
+        bool open;
+    }
+
+    mapping(bytes32 => EscrowAccount) public escrowAccounts;
+    mapping(address => bool) public escrowOperators; // ai-jobd and the receipts daemon
+    mapping(bytes32 => Escrow) public jobEscrows; // key: jobId
+    mapping(bytes32 => Escrow) public reservationEscrows; // key: reservationId
+    mapping(bytes32 => Escrow) public datasetEscrows; // key: grantId
+    bytes32 public treasuryAccount; // Escrow account forfeits and platform fees are credited to
+
     event DealCreated(bytes32 indexed root, address indexed client, uint256 endowment);
     event Payout(bytes32 indexed root, address indexed provider, uint256 amount, uint64 epoch);
     event RetrievalPaid(bytes32 indexed root, address indexed provider, uint64 bytesServed, uint256 amount);
@@ -41,6 +62,16 @@ contract DealMarket {
     event RetrievalAggregateProof(bytes32 indexed root, bytes32 merkleRoot, bytes32 leaf, address indexed provider, uint256 amount);
     event Slashed(bytes32 indexed root, uint64 epoch, uint256 amount);
     event ComputePayout(bytes32 indexed jobId, address indexed provider, uint256 gpuSeconds, uint256 amount, uint256 ratePerSecondWei);
+    event EscrowAccountBound(bytes32 indexed account, address indexed owner);
+    event EscrowDeposited(bytes32 indexed account, address indexed from, uint256 amount);
+    event EscrowWithdrawn(bytes32 indexed account, address indexed owner, uint256 amount);
+    event JobEscrowed(bytes32 indexed jobId, bytes32 indexed payer, uint256 amount);
+    event JobEscrowSettled(bytes32 indexed jobId, bytes32 indexed provider, uint256 paid, uint256 refunded);
+    event ReservationEscrowed(bytes32 indexed reservationId, bytes32 indexed payer, uint256 amount);
+    event ReservationRefunded(bytes32 indexed reservationId, bytes32 indexed payer, uint256 refunded, uint256 kept);
+    event DatasetAccessEscrowed(bytes32 indexed grantId, bytes32 indexed consumer, uint256 amount);
+    event DatasetEscrowReleased(bytes32 indexed grantId, bytes32 indexed owner, uint256 ownerShare, uint256 platformFee);
+    event DatasetEscrowRefunded(bytes32 indexed grantId, bytes32 indexed consumer, uint256 refunded);
 
     constructor(address proofManager_, uint256 priceWei) {
         proofManager = ISVDBProofManager(proofManager_);
@@ -65,6 +96,21 @@ contract DealMarket {
         proofsV2 = ISVDBProofsV2V3(proofs_);
     }
 
+    modifier onlyEscrowOperator() {
+        require(escrowOperators[msg.sender], "Only escrow operator");
+        _;
+    }
+
+    function setEscrowOperator(address operator, bool allowed) external onlyGovernance {
+        require(operator != address(0), "Invalid address");
+        escrowOperators[operator] = allowed;
+    }
+
+    function setTreasuryAccount(bytes32 account) external onlyGovernance {
+        require(account != bytes32(0), "Invalid account");
+        treasuryAccount = account;
+    }
+
     function setGovernance(address newGov) external onlyGovernance {
         require(newGov != address(0), "Invalid address");
         governance = newGov;
@@ -290,6 +336,116 @@ contract DealMarket {
         ratePerSecondWei = baseRate * multiplier;
         totalWei = gpuSeconds * ratePerSecondWei;
     }
-}
 
+    /// @notice Bind an escrow account to the address that may withdraw from it.
+    /// Operators bind accounts once they have authenticated the party they name.
+    function bindEscrowAccount(bytes32 account, address owner) external onlyEscrowOperator {
+        require(owner != address(0), "Invalid address");
+        require(escrowAccounts[account].owner == address(0), "bound");
+        escrowAccounts[account].owner = owner;
+        emit EscrowAccountBound(account, owner);
+    }
 
+    /// @notice Fund an escrow account; anyone may top up a bound account
+    function depositEscrow(bytes32 account) external payable {
+        require(escrowAccounts[account].owner != address(0), "unbound");
+        require(msg.value > 0, "zero deposit");
+        escrowAccounts[account].balance += msg.value;
+        emit EscrowDeposited(account, msg.sender, msg.value);
+    }
+
+    /// @notice Withdraw from the part of an account's balance no escrow holds
+    function withdrawEscrow(bytes32 account, uint256 amount) external {
+        EscrowAccount storage a = escrowAccounts[account];
+        require(msg.sender == a.owner, "not owner");
+        require(a.balance >= amount, "insufficient balance");
+        a.balance -= amount;
+        (bool ok,) = payable(msg.sender).call{value: amount}("");
+        require(ok, "transfer failed");
+        emit EscrowWithdrawn(account, msg.sender, amount);
+    }
+
+    /// @notice Lock a job's estimated cost from its submitter's balance
+    function escrowJob(bytes32 jobId, bytes32 payer, uint256 amount) external onlyEscrowOperator {
+        _lock(jobEscrows[jobId], payer, amount);
+        emit JobEscrowed(jobId, payer, amount);
+    }
+
+    /// @notice Settle a finished job's escrow in full: `paid` to the provider's
+    /// account, `refunded` back to the payer's
+    function settleJobEscrow(bytes32 jobId, bytes32 provider, uint256 paid, uint256 refunded) external onlyEscrowOperator {
+        Escrow storage e = jobEscrows[jobId];
+        require(e.open, "no escrow");
+        require(paid + refunded == e.amount, "split");
+        require(paid == 0 || provider != bytes32(0), "provider");
+        e.open = false;
+        e.amount = 0;
+        escrowAccounts[provider].balance += paid;
+        escrowAccounts[e.payer].balance += refunded;
+        emit JobEscrowSettled(jobId, provider, paid, refunded);
+    }
+
+    /// @notice Lock a GPU-hour reservation's cost from its payer's balance
+    function escrowReservation(bytes32 reservationId, bytes32 payer, uint256 amount) external onlyEscrowOperator {
+        _lock(reservationEscrows[reservationId], payer, amount);
+        emit ReservationEscrowed(reservationId, payer, amount);
+    }
+
+    /// @notice Settle an ended reservation: `refunded` back to its payer, the
+    /// used and forfeited rest to the treasury account providers are paid from
+    function refundReservation(bytes32 reservationId, bytes32 payer, uint256 refunded) external onlyEscrowOperator {
+        Escrow storage e = reservationEscrows[reservationId];
+        require(e.open, "no escrow");
+        require(e.payer == payer, "payer");
+        require(refunded <= e.amount, "refund");
+        require(treasuryAccount != bytes32(0), "treasury");
+        uint256 kept = e.amount - refunded;
+        e.open = false;
+        e.amount = 0;
+        escrowAccounts[payer].balance += refunded;
+        escrowAccounts[treasuryAccount].balance += kept;
+        emit ReservationRefunded(reservationId, payer, refunded, kept);
+    }
+
+    /// @notice Lock a dataset access grant's price from its consumer's balance
+    function escrowDatasetAccess(bytes32 grantId, bytes32 consumer, uint256 amount) external onlyEscrowOperator {
+        _lock(datasetEscrows[grantId], consumer, amount);
+        emit DatasetAccessEscrowed(grantId, consumer, amount);
+    }
+
+    /// @notice Pay for one job's use of a grant out of its escrow: the owner
+    /// share to the dataset owner's account, the platform fee to the treasury's
+    function releaseDatasetEscrow(bytes32 grantId, bytes32 owner, uint256 ownerShare, uint256 platformFee) external onlyEscrowOperator {
+        Escrow storage e = datasetEscrows[grantId];
+        require(e.open, "no escrow");
+        require(owner != bytes32(0), "owner");
+        require(ownerShare + platformFee <= e.amount, "exceeds escrow");
+        require(platformFee == 0 || treasuryAccount != bytes32(0), "treasury");
+        e.amount -= ownerShare + platformFee;
+        escrowAccounts[owner].balance += ownerShare;
+        escrowAccounts[treasuryAccount].balance += platformFee;
+        emit DatasetEscrowReleased(grantId, owner, ownerShare, platformFee);
+    }
+
+    /// @notice Close a grant's escrow, returning what its jobs didn't use to the consumer
+    function refundDatasetEscrow(bytes32 grantId) external onlyEscrowOperator {
+        Escrow storage e = datasetEscrows[grantId];
+        require(e.open, "no escrow");
+        uint256 refunded = e.amount;
+        e.open = false;
+        e.amount = 0;
+        escrowAccounts[e.payer].balance += refunded;
+        emit DatasetEscrowRefunded(grantId, e.payer, refunded);
+    }
+
+    function _lock(Escrow storage e, bytes32 payer, uint256 amount) internal {
+        require(!e.open, "exists");
+        require(amount > 0, "zero amount");
+        EscrowAccount storage a = escrowAccounts[payer];
+        require(a.balance >= amount, "insufficient balance");
+        a.balance -= amount;
+        e.payer = payer;
+        e.amount = amount;
+        e.open = true;
+    }
+}
@@ -30,6 +30,7 @@ contract ModelRegistry {
     mapping(bytes32 => Checkpoint[]) public modelCheckpoints;
     mapping(bytes32 => bytes32[]) public modelLineage; // modelId -> parent chain
     mapping(address => bytes32[]) public ownerModels;
+    mapping(bytes32 => bytes32) public modelCards; // modelId -> model card CID (body in SVDB)
 
     event ModelRegistered(
         bytes32 indexed modelId,
@@ -41,6 +42,7 @@ contract ModelRegistry {
     event CheckpointAdded(bytes32 indexed modelId, bytes32 checkpointCid, uint256 step);
     event ModelPublished(bytes32 indexed modelId, bytes32 checkpointCid);
     event ModelDeactivated(bytes32 indexed modelId);
+    event ModelCardSet(bytes32 indexed modelId, bytes32 cardCid);
 
     function register(
         bytes32 modelCid,
@@ -113,6 +115,19 @@ contract ModelRegistry {
         emit CheckpointAdded(modelId, checkpointCid, step);
     }
 
+    /// @notice Attach the model card (intended use, training data, evals, limitations)
+    function setModelCard(bytes32 modelId, bytes32 cardCid) external {
+        Model storage model = models[modelId];
+        require(model.owner == msg.sender, "Not model owner");
+        require(model.active, "Model not active");
+        modelCards[modelId] = cardCid;
+        emit ModelCardSet(modelId, cardCid);
+    }
+
+    function getModelCard(bytes32 modelId) external view returns (bytes32) {
+        return modelCards[modelId];
+    }
+
     function getModel(bytes32 modelId) external view returns (Model memory) {
         return models[modelId];
     }
@@ -1,8 +1,15 @@
 // SPDX-License-Identifier: MIT
 pragma solidity ^0.8.20;
 
+interface IComputeVerifier {
+    function verifyCompute(bytes32 jobId, bytes32 nodePubkey, uint256 gpuSeconds, bytes32 finalOutputCid) external view returns (bool);
+}
+
 /// @title ProofOfCompute
 /// @notice Records and verifies compute receipts for AI training/inference jobs
+/// @dev Jobs finalize either verified (proofs checked before the receipt is written) or
+/// optimistically (paid at once from job escrow against a node stake, with full
+/// verification deferred to disputes raised inside the challenge window)
 contract ProofOfCompute {
     struct TrainProof {
         bytes32 jobId;
@@ -37,13 +44,62 @@ contract ProofOfCompute {
         uint256 payout;
     }
 
+    enum ClaimStatus { None, Pending, Settled, Reversed }
+
+    struct OptimisticClaim {
+        address payable node;
+        uint256 payout;
+        uint256 stake;           // Covers clawback of payout plus the slash
+        uint64 challengeEnd;
+        ClaimStatus status;
+    }
+
     mapping(bytes32 => TrainProof[]) public trainProofs;
     mapping(bytes32 => InferProof[]) public inferProofs;
     mapping(bytes32 => ComputeReceipt) public receipts;
+    mapping(bytes32 => OptimisticClaim) public claims;
+    mapping(bytes32 => uint256) public jobEscrow;
+    mapping(bytes32 => address) public jobFunder;
+
+    address public governance;
+    IComputeVerifier public verifier;        // Full verification; unset skips it
+    uint64 public challengeWindow = 1 days;
+    uint256 public slashBps = 5000;          // Slash on top of clawback, in bps of payout
+    uint256 public disputeBond = 0.1 ether;  // Forfeited to the node if a dispute fails
 
     event TrainProofRecorded(bytes32 indexed jobId, uint256 step, bytes32 nodePubkey);
     event InferProofRecorded(bytes32 indexed jobId, bytes32 outputCid, bytes32 nodePubkey);
     event ReceiptFinalized(bytes32 indexed jobId, uint256 gpuSeconds, uint256 payout);
+    event JobFunded(bytes32 indexed jobId, address indexed funder, uint256 amount);
+    event OptimisticFinalized(bytes32 indexed jobId, address indexed node, uint256 payout, uint256 stake, uint64 challengeEnd);
+    event FinalizationDisputed(bytes32 indexed jobId, address indexed challenger, bool upheld);
+    event FinalizationReversed(bytes32 indexed jobId, uint256 clawedBack, uint256 slashed);
+
+    constructor() {
+        governance = msg.sender;
+    }
+
+    modifier onlyGovernance() {
+        require(msg.sender == governance, "Only governance");
+        _;
+    }
+
+    function setVerifier(address verifier_) external onlyGovernance {
+        verifier = IComputeVerifier(verifier_);
+    }
+
+    function setChallengeParams(uint64 window, uint256 slashBps_, uint256 bond) external onlyGovernance {
+        require(window > 0, "zero window");
+        require(slashBps_ <= 10000, "slash bps");
+        challengeWindow = window;
+        slashBps = slashBps_;
+        disputeBond = bond;
+    }
+
+    function setGovernance(address newGov) external onlyGovernance {
+        require(newGov != address(0), "Invalid address");
+        governance = newGov;
+    }
 
     function recordTrainProof(
         bytes32 jobId,
@@ -95,6 +151,129 @@ contract ProofOfCompute {
         uint256 gpuSeconds,
         bytes32 finalOutputCid
     ) external returns (uint256) {
+        require(claims[jobId].status != ClaimStatus.Pending, "Optimistic claim pending");
+        if (address(verifier) != address(0)) {
+            require(verifier.verifyCompute(jobId, nodePubkey, gpuSeconds, finalOutputCid), "Verification failed");
+        }
+
+        uint256 payout = _writeReceipt(jobId, nodePubkey, gpuSeconds, finalOutputCid, true);
+        emit ReceiptFinalized(jobId, gpuSeconds, payout);
+        return payout;
+    }
+
+    /// @notice Escrow funds that optimistic finalization pays out of
+    function fundJob(bytes32 jobId) external payable {
+        require(msg.value > 0, "zero value");
+        address funder = jobFunder[jobId];
+        require(funder == address(0) || funder == msg.sender, "Not job funder");
+        jobFunder[jobId] = msg.sender;
+        jobEscrow[jobId] += msg.value;
+        emit JobFunded(jobId, msg.sender, msg.value);
+    }
+
+    /// @notice Stake a node must post to finalize a job paying `payout` optimistically
+    function requiredStake(uint256 payout) public view returns (uint256) {
+        return payout + (payout * slashBps) / 10000;
+    }
+
+    /// @notice Pay the caller now from job escrow; the receipt stays unfinalized
+    /// until `settle` after the challenge window
+    function finalizeOptimistic(
+        bytes32 jobId,
+        bytes32 nodePubkey,
+        uint256 gpuSeconds,
+        bytes32 finalOutputCid
+    ) external payable returns (uint256) {
+        require(claims[jobId].status != ClaimStatus.Pending, "Optimistic claim pending");
+
+        uint256 payout = _writeReceipt(jobId, nodePubkey, gpuSeconds, finalOutputCid, false);
+        uint256 stake = requiredStake(payout);
+        require(msg.value >= stake, "insufficient stake");
+        require(jobEscrow[jobId] >= payout, "insufficient escrow");
+        jobEscrow[jobId] -= payout;
+
+        uint64 challengeEnd = uint64(block.timestamp) + challengeWindow;
+        claims[jobId] = OptimisticClaim({
+            node: payable(msg.sender),
+            payout: payout,
+            stake: stake,
+            challengeEnd: challengeEnd,
+            status: ClaimStatus.Pending
+        });
+
+        // Pay out along with any stake overpayment
+        (bool ok,) = payable(msg.sender).call{value: payout + msg.value - stake}("");
+        require(ok, "pay failed");
+
+        emit OptimisticFinalized(jobId, msg.sender, payout, stake, challengeEnd);
+        return payout;
+    }
+
+    /// @notice Challenge a pending optimistic finalization by running full verification.
+    /// If it fails, the payout is clawed back to escrow and the rest of the stake goes to
+    /// the challenger; if it passes, the dispute bond goes to the node.
+    function dispute(bytes32 jobId) external payable {
+        OptimisticClaim storage c = claims[jobId];
+        require(c.status == ClaimStatus.Pending, "No pending claim");
+        require(block.timestamp < c.challengeEnd, "Challenge window closed");
+        require(msg.value >= disputeBond, "insufficient bond");
+        require(address(verifier) != address(0), "No verifier");
+
+        ComputeReceipt storage r = receipts[jobId];
+        bool valid = verifier.verifyCompute(jobId, r.nodePubkey, r.gpuSeconds, r.finalOutputCid);
+        emit FinalizationDisputed(jobId, msg.sender, !valid);
+
+        if (valid) {
+            (bool ok,) = c.node.call{value: msg.value}("");
+            require(ok, "bond transfer failed");
+            return;
+        }
+
+        c.status = ClaimStatus.Reversed;
+        uint256 slashed = c.stake - c.payout;
+        jobEscrow[jobId] += c.payout;
+        delete receipts[jobId];
+
+        (bool paid,) = payable(msg.sender).call{value: slashed + msg.value}("");
+        require(paid, "slash transfer failed");
+        emit FinalizationReversed(jobId, c.payout, slashed);
+    }
+
+    /// @notice Finalize an undisputed optimistic receipt and return the node's stake
+    function settle(bytes32 jobId) external {
+        OptimisticClaim storage c = claims[jobId];
+        require(c.status == ClaimStatus.Pending, "No pending claim");
+        require(block.timestamp >= c.challengeEnd, "Challenge window open");
+
+        c.status = ClaimStatus.Settled;
+        receipts[jobId].finalized = true;
+
+        (bool ok,) = c.node.call{value: c.stake}("");
+        require(ok, "stake return failed");
+        emit ReceiptFinalized(jobId, receipts[jobId].gpuSeconds, c.payout);
+    }
+
+    /// @notice Return unspent escrow to the funder once no claim is pending
+    function withdrawEscrow(bytes32 jobId) external {
+        require(msg.sender == jobFunder[jobId], "Not job funder");
+        require(claims[jobId].status != ClaimStatus.Pending, "Optimistic claim pending");
+        uint256 amount = jobEscrow[jobId];
+        jobEscrow[jobId] = 0;
+        (bool ok,) = payable(msg.sender).call{value: amount}("");
+        require(ok, "withdraw failed");
+    }
+
+    function getClaim(bytes32 jobId) external view returns (OptimisticClaim memory) {
+        return claims[jobId];
+    }
+
+    function _writeReceipt(
+        bytes32 jobId,
+        bytes32 nodePubkey,
+        uint256 gpuSeconds,
+        bytes32 finalOutputCid,
+        bool finalized
+    ) internal returns (uint256) {
         require(!receipts[jobId].finalized, "Already finalized");
 
         // Verify proofs exist
@@ -123,11 +302,10 @@ contract ProofOfCompute {
             finalOutputCid: finalOutputCid,
             startTime: startTime,
             endTime: endTime,
-            finalized: true,
+            finalized: finalized,
             payout: payout
         });
 
-        emit ReceiptFinalized(jobId, gpuSeconds, payout);
         return payout;
     }
 
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "forge-std/Test.sol";
+import {DealMarket} from "../DealMarket.sol";
+
+contract NoopProofManager {
+    function verifyMerkleSample(bytes32, bytes32, bytes32[] calldata, uint256) external pure returns (bool) {
+        return true;
+    }
+}
+
+contract DealMarketEscrowTest is Test {
+    DealMarket internal market;
+
+    address operator = address(0x0DE);
+    address alice = address(0xA11CE);
+    bytes32 aliceAccount = keccak256("did:artha:alice");
+    bytes32 providerAccount = bytes32("node-1");
+    bytes32 jobId = keccak256("job-1");
+    bytes32 reservationId = keccak256("res-1");
+    bytes32 grantId = keccak256("grant-1");
+    bytes32 ownerAccount = keccak256("did:artha:owner");
+    bytes32 treasury = bytes32("treasury");
+
+    function setUp() public {
+        market = new DealMarket(address(new NoopProofManager()), 1e15);
+        market.setEscrowOperator(operator, true);
+        market.setTreasuryAccount(treasury);
+        vm.prank(operator);
+        market.bindEscrowAccount(aliceAccount, alice);
+        vm.deal(alice, 10 ether);
+        vm.prank(alice);
+        market.depositEscrow{value: 1 ether}(aliceAccount);
+    }
+
+    function balanceOf(bytes32 account) internal view returns (uint256 balance) {
+        (, balance) = market.escrowAccounts(account);
+    }
+
+    function testOnlyGovernanceAppointsOperators() public {
+        vm.prank(alice);
+        vm.expectRevert("Only governance");
+        market.setEscrowOperator(alice, true);
+    }
+
+    function testAccountsBindOnce() public {
+        vm.prank(operator);
+        vm.expectRevert("bound");
+        market.bindEscrowAccount(aliceAccount, address(0xBAD));
+
+        vm.prank(alice);
+        vm.expectRevert("Only escrow operator");
+        market.bindEscrowAccount(keccak256("did:artha:bob"), alice);
+    }
+
+    function testDepositsNeedABoundAccount() public {
+        vm.prank(alice);
+        vm.expectRevert("unbound");
+        market.depositEscrow{value: 1 ether}(keccak256("did:artha:nobody"));
+    }
+
+    function testJobEscrowLocksThePayersBalance() public {
+        vm.prank(operator);
+        market.escrowJob(jobId, aliceAccount, 0.4 ether);
+        assertEq(balanceOf(aliceAccount), 0.6 ether);
+        (bytes32 payer, uint256 amount, bool open) = market.jobEscrows(jobId);
+        assertEq(payer, aliceAccount);
+        assertEq(amount, 0.4 ether);
+        assertTrue(open);
+
+        // Locked funds can't be withdrawn
+        vm.prank(alice);
+        vm.expectRevert("insufficient balance");
+        market.withdrawEscrow(aliceAccount, 0.7 ether);
+    }
+
+    function testJobEscrowRevertsBeyondTheBalance() public {
+        vm.prank(operator);
+        vm.expectRevert("insufficient balance");
+        market.escrowJob(jobId, aliceAccount, 2 ether);
+
+        vm.prank(alice);
+        vm.expectRevert("Only escrow operator");
+        market.escrowJob(jobId, aliceAccount, 0.1 ether);
+    }
+
+    function testSettlementPaysTheProviderAndRefundsThePayer() public {
+        vm.startPrank(operator);
+        market.escrowJob(jobId, aliceAccount, 0.4 ether);
+        vm.expectRevert("split");
+        market.settleJobEscrow(jobId, providerAccount, 0.3 ether, 0.2 ether);
+        market.settleJobEscrow(jobId, providerAccount, 0.3 ether, 0.1 ether);
+        vm.expectRevert("no escrow");
+        market.settleJobEscrow(jobId, providerAccount, 0.3 ether, 0.1 ether);
+        vm.stopPrank();
+
+        assertEq(balanceOf(providerAccount), 0.3 ether);
+        assertEq(balanceOf(aliceAccount), 0.7 ether);
+        (, uint256 amount, bool open) = market.jobEscrows(jobId);
+        assertEq(amount, 0);
+        assertFalse(open);
+    }
+
+    function testFailedJobsRefundInFullWithoutAProvider() public {
+        vm.startPrank(operator);
+        market.escrowJob(jobId, aliceAccount, 0.4 ether);
+        vm.expectRevert("provider");
+        market.settleJobEscrow(jobId, bytes32(0), 0.1 ether, 0.3 ether);
+        market.settleJobEscrow(jobId, bytes32(0), 0, 0.4 ether);
+        vm.stopPrank();
+        assertEq(balanceOf(aliceAccount), 1 ether);
+    }
+
+    function testOwnerWithdrawsTheFreeBalance() public {
+        vm.prank(address(0xBAD));
+        vm.expectRevert("not owner");
+        market.withdrawEscrow(aliceAccount, 0.1 ether);
+
+        vm.prank(alice);
+        market.withdrawEscrow(aliceAccount, 0.25 ether);
+        assertEq(balanceOf(aliceAccount), 0.75 ether);
+        assertEq(alice.balance, 9.25 ether);
+    }
+
+    function testReservationRefundReturnsTheUnusedShareAndKeepsTheRest() public {
+        vm.startPrank(operator);
+        market.escrowReservation(reservationId, aliceAccount, 0.72 ether);
+        assertEq(balanceOf(aliceAccount), 0.28 ether);
+        vm.expectRevert("exists");
+        market.escrowReservation(reservationId, aliceAccount, 0.1 ether);
+
+        vm.expectRevert("payer");
+        market.refundReservation(reservationId, keccak256("did:artha:bob"), 0.27 ether);
+        vm.expectRevert("refund");
+        market.refundReservation(reservationId, aliceAccount, 0.8 ether);
+        market.refundReservation(reservationId, aliceAccount, 0.27 ether);
+        vm.expectRevert("no escrow");
+        market.refundReservation(reservationId, aliceAccount, 0.27 ether);
+        vm.stopPrank();
+
+        assertEq(balanceOf(aliceAccount), 0.55 ether);
+        assertEq(balanceOf(treasury), 0.45 ether);
+    }
+
+    function testReservationEscrowRevertsBeyondTheBalance() public {
+        vm.prank(operator);
+        vm.expectRevert("insufficient balance");
+        market.escrowReservation(reservationId, aliceAccount, 1.5 ether);
+    }
+
+    function testDatasetEscrowPaysOwnerAndTreasuryPerUse() public {
+        vm.startPrank(operator);
+        market.escrowDatasetAccess(grantId, aliceAccount, 0.5 ether);
+        assertEq(balanceOf(aliceAccount), 0.5 ether);
+
+        market.releaseDatasetEscrow(grantId, ownerAccount, 0.225 ether, 0.025 ether);
+        vm.expectRevert("exceeds escrow");
+        market.releaseDatasetEscrow(grantId, ownerAccount, 0.3 ether, 0);
+        vm.expectRevert("owner");
+        market.releaseDatasetEscrow(grantId, bytes32(0), 0.1 ether, 0);
+        (, uint256 left,) = market.datasetEscrows(grantId);
+        assertEq(left, 0.25 ether);
+
+        // The grant's unused rest goes back to the consumer when it closes
+        market.refundDatasetEscrow(grantId);
+        vm.expectRevert("no escrow");
+        market.releaseDatasetEscrow(grantId, ownerAccount, 0.1 ether, 0);
+        vm.stopPrank();
+
+        assertEq(balanceOf(ownerAccount), 0.225 ether);
+        assertEq(balanceOf(treasury), 0.025 ether);
+        assertEq(balanceOf(aliceAccount), 0.75 ether);
+    }
+
+    function testOnlyOperatorsReleaseDatasetEscrow() public {
+        vm.prank(operator);
+        market.escrowDatasetAccess(grantId, aliceAccount, 0.5 ether);
+        vm.prank(alice);
+        vm.expectRevert("Only escrow operator");
+        market.releaseDatasetEscrow(grantId, aliceAccount, 0.5 ether, 0);
+    }
+}
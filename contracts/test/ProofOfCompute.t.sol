@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "forge-std/Test.sol";
+
+import {ProofOfCompute} from "../ProofOfCompute.sol";
+
+contract MockComputeVerifier {
+    bool public valid = true;
+
+    function setValid(bool v) external {
+        valid = v;
+    }
+
+    function verifyCompute(bytes32, bytes32, uint256, bytes32) external view returns (bool) {
+        return valid;
+    }
+}
+
+contract ProofOfComputeTest is Test {
+    ProofOfCompute internal poc;
+    MockComputeVerifier internal verifier;
+
+    address funder = address(0xF00D);
+    address node = address(0xBEEF);
+    address challenger = address(0xC0FFEE);
+    bytes32 jobId = keccak256("job");
+    bytes32 nodePubkey = keccak256("node");
+    bytes32 outputCid = keccak256("output");
+    uint256 gpuSeconds = 100;
+    uint256 payout = 100 * 1e15;
+
+    function setUp() public {
+        poc = new ProofOfCompute();
+        verifier = new MockComputeVerifier();
+        poc.setVerifier(address(verifier));
+
+        poc.recordTrainProof(jobId, 1, keccak256("loss"), keccak256("grad"), keccak256("weights"), nodePubkey, "");
+
+        vm.deal(funder, 1 ether);
+        vm.prank(funder);
+        poc.fundJob{value: 1 ether}(jobId);
+
+        vm.deal(node, 1 ether);
+        vm.deal(challenger, 1 ether);
+    }
+
+    function _finalizeOptimistic() internal returns (uint256 stake) {
+        stake = poc.requiredStake(payout);
+        vm.prank(node);
+        poc.finalizeOptimistic{value: stake}(jobId, nodePubkey, gpuSeconds, outputCid);
+    }
+
+    function testUndisputedOptimisticFinalizeSettlesAfterWindow() public {
+        uint256 stake = _finalizeOptimistic();
+        uint256 bond = poc.disputeBond();
+
+        // Paid immediately, stake held, receipt not yet final
+        assertEq(node.balance, 1 ether - stake + payout);
+        assertEq(poc.jobEscrow(jobId), 1 ether - payout);
+        assertFalse(poc.getReceipt(jobId).finalized);
+
+        vm.expectRevert("Challenge window open");
+        poc.settle(jobId);
+
+        vm.warp(block.timestamp + poc.challengeWindow());
+        vm.prank(challenger);
+        vm.expectRevert("Challenge window closed");
+        poc.dispute{value: bond}(jobId);
+
+        poc.settle(jobId);
+
+        assertEq(node.balance, 1 ether + payout);
+        assertTrue(poc.getReceipt(jobId).finalized);
+        assertEq(poc.getReceipt(jobId).payout, payout);
+        assertEq(uint8(poc.getClaim(jobId).status), uint8(ProofOfCompute.ClaimStatus.Settled));
+
+        vm.expectRevert("Already finalized");
+        poc.finalize(jobId, nodePubkey, gpuSeconds, outputCid);
+    }
+
+    function testDisputedOptimisticFinalizeReversesPayout() public {
+        uint256 stake = _finalizeOptimistic();
+        uint256 bond = poc.disputeBond();
+
+        verifier.setValid(false);
+        vm.warp(block.timestamp + 1 hours);
+        vm.prank(challenger);
+        poc.dispute{value: bond}(jobId);
+
+        // Payout clawed back to escrow out of the stake; the rest is slashed to the challenger
+        assertEq(poc.jobEscrow(jobId), 1 ether);
+        assertEq(node.balance, 1 ether - stake + payout);
+        assertEq(challenger.balance, 1 ether + stake - payout);
+        assertEq(address(poc).balance, 1 ether);
+        assertEq(poc.getReceipt(jobId).payout, 0);
+        assertEq(uint8(poc.getClaim(jobId).status), uint8(ProofOfCompute.ClaimStatus.Reversed));
+
+        vm.expectRevert("No pending claim");
+        poc.settle(jobId);
+
+        vm.prank(funder);
+        poc.withdrawEscrow(jobId);
+        assertEq(funder.balance, 1 ether);
+    }
+
+    function testRejectedDisputeForfeitsBondToNode() public {
+        uint256 stake = _finalizeOptimistic();
+        uint256 bond = poc.disputeBond();
+
+        vm.prank(challenger);
+        poc.dispute{value: bond}(jobId);
+
+        assertEq(challenger.balance, 1 ether - bond);
+        assertEq(node.balance, 1 ether - stake + payout + bond);
+        assertEq(uint8(poc.getClaim(jobId).status), uint8(ProofOfCompute.ClaimStatus.Pending));
+
+        vm.warp(block.timestamp + poc.challengeWindow());
+        poc.settle(jobId);
+        assertTrue(poc.getReceipt(jobId).finalized);
+    }
+
+    function testVerifiedFinalizeBlocksOnVerification() public {
+        verifier.setValid(false);
+        vm.expectRevert("Verification failed");
+        poc.finalize(jobId, nodePubkey, gpuSeconds, outputCid);
+
+        verifier.setValid(true);
+        assertEq(poc.finalize(jobId, nodePubkey, gpuSeconds, outputCid), payout);
+        assertTrue(poc.getReceipt(jobId).finalized);
+    }
+}
@@ -0,0 +1,313 @@
+//! ABI Codec
+//! Solidity ABI encoding and decoding for the types the AI contracts use:
+//! address, bool, uintN, bytesN, bytes, string, dynamic arrays and tuples.
+
+/// A Solidity parameter type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Address,
+    Bool,
+    Uint(usize),       // Bits
+    FixedBytes(usize), // Length in bytes
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    Tuple(Vec<ParamType>),
+}
+
+/// A value of a `ParamType`. Integers are limited to u128, which covers
+/// every amount and counter the services send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Address([u8; 20]),
+    Bool(bool),
+    Uint(u128),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl ParamType {
+    /// Parse a canonical type such as `uint64`, `bytes32[]` or `(address,string)`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_suffix("[]") {
+            return Ok(ParamType::Array(Box::new(Self::parse(inner)?)));
+        }
+        if let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            return Ok(ParamType::Tuple(parse_types(inner)?));
+        }
+
+        match s {
+            "address" => Ok(ParamType::Address),
+            "bool" => Ok(ParamType::Bool),
+            "bytes" => Ok(ParamType::Bytes),
+            "string" => Ok(ParamType::String),
+            "uint" => Ok(ParamType::Uint(256)),
+            _ => {
+                if let Some(bits) = s.strip_prefix("uint") {
+                    match bits.parse::<usize>() {
+                        Ok(bits) if bits > 0 && bits <= 256 && bits % 8 == 0 => Ok(ParamType::Uint(bits)),
+                        _ => Err(format!("Invalid integer type: {}", s)),
+                    }
+                } else if let Some(len) = s.strip_prefix("bytes") {
+                    match len.parse::<usize>() {
+                        Ok(len) if (1..=32).contains(&len) => Ok(ParamType::FixedBytes(len)),
+                        _ => Err(format!("Invalid bytes type: {}", s)),
+                    }
+                } else {
+                    Err(format!("Unsupported ABI type: {}", s))
+                }
+            }
+        }
+    }
+
+    /// The type as it appears in a function signature
+    pub fn canonical(&self) -> String {
+        match self {
+            ParamType::Address => "address".to_string(),
+            ParamType::Bool => "bool".to_string(),
+            ParamType::Uint(bits) => format!("uint{}", bits),
+            ParamType::FixedBytes(len) => format!("bytes{}", len),
+            ParamType::Bytes => "bytes".to_string(),
+            ParamType::String => "string".to_string(),
+            ParamType::Array(inner) => format!("{}[]", inner.canonical()),
+            ParamType::Tuple(types) => format!("({})", canonical_list(types)),
+        }
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::Tuple(types) => types.iter().any(|t| t.is_dynamic()),
+            _ => false,
+        }
+    }
+
+    /// Bytes the type occupies in the head of its enclosing sequence
+    fn head_size(&self) -> usize {
+        match self {
+            ParamType::Tuple(types) if !self.is_dynamic() => types.iter().map(|t| t.head_size()).sum(),
+            _ => 32,
+        }
+    }
+}
+
+/// Parse a comma separated type list, e.g. the inside of a signature
+pub fn parse_types(list: &str) -> Result<Vec<ParamType>, String> {
+    let mut types = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1).ok_or_else(|| format!("Unbalanced type list: {}", list))?,
+            ',' if depth == 0 => {
+                types.push(ParamType::parse(&list[start..i])?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(format!("Unbalanced type list: {}", list));
+    }
+    if !list[start..].trim().is_empty() || !types.is_empty() {
+        types.push(ParamType::parse(&list[start..])?);
+    }
+    Ok(types)
+}
+
+pub fn canonical_list(types: &[ParamType]) -> String {
+    types.iter().map(|t| t.canonical()).collect::<Vec<_>>().join(",")
+}
+
+impl Token {
+    pub fn bytes32(bytes: [u8; 32]) -> Self {
+        Token::FixedBytes(bytes.to_vec())
+    }
+
+    /// bytes32 from 64 hex characters, with or without 0x
+    pub fn bytes32_hex(hex_str: &str) -> Self {
+        let bytes = crate::unhex(hex_str);
+        assert_eq!(bytes.len(), 32, "not a bytes32: {}", hex_str);
+        Token::FixedBytes(bytes)
+    }
+
+    /// bytes32 holding the first 32 bytes of a string, zero padded. For ids
+    /// that fit this is what `abi_encode_bytes32` sends; longer ones are hashed.
+    pub fn ascii32(s: &str) -> Self {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize(32, 0);
+        Token::FixedBytes(bytes)
+    }
+
+    pub fn address(hex_str: &str) -> Self {
+        let bytes = crate::unhex(hex_str);
+        let address: [u8; 20] = bytes.try_into().unwrap_or_else(|_| panic!("not an address: {}", hex_str));
+        Token::Address(address)
+    }
+
+    pub fn uint(value: impl Into<u128>) -> Self {
+        Token::Uint(value.into())
+    }
+
+    pub fn string(s: &str) -> Self {
+        Token::String(s.to_string())
+    }
+}
+
+fn word(value: u128) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[16..].copy_from_slice(&value.to_be_bytes());
+    w
+}
+
+fn padded(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    out.resize(bytes.len().div_ceil(32) * 32, 0);
+    out
+}
+
+/// Encode `tokens` as a sequence of `types` (function arguments or returns)
+pub fn encode(types: &[ParamType], tokens: &[Token]) -> Result<Vec<u8>, String> {
+    if types.len() != tokens.len() {
+        return Err(format!("Expected {} values, got {}", types.len(), tokens.len()));
+    }
+
+    let head_size: usize = types.iter().map(|t| t.head_size()).sum();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+    for (param, token) in types.iter().zip(tokens) {
+        let encoded = encode_token(param, token)?;
+        if param.is_dynamic() {
+            head.extend_from_slice(&word((head_size + tail.len()) as u128));
+            tail.extend(encoded);
+        } else {
+            head.extend(encoded);
+        }
+    }
+    head.extend(tail);
+    Ok(head)
+}
+
+fn encode_token(param: &ParamType, token: &Token) -> Result<Vec<u8>, String> {
+    match (param, token) {
+        (ParamType::Address, Token::Address(address)) => {
+            let mut w = [0u8; 32];
+            w[12..].copy_from_slice(address);
+            Ok(w.to_vec())
+        }
+        (ParamType::Bool, Token::Bool(b)) => Ok(word(*b as u128).to_vec()),
+        (ParamType::Uint(bits), Token::Uint(value)) => {
+            if *bits < 128 && *value >> bits != 0 {
+                return Err(format!("{} does not fit in uint{}", value, bits));
+            }
+            Ok(word(*value).to_vec())
+        }
+        (ParamType::FixedBytes(len), Token::FixedBytes(bytes)) if bytes.len() == *len => Ok(padded(bytes)),
+        (ParamType::Bytes, Token::Bytes(bytes)) => {
+            let mut out = word(bytes.len() as u128).to_vec();
+            out.extend(padded(bytes));
+            Ok(out)
+        }
+        (ParamType::String, Token::String(s)) => {
+            let mut out = word(s.len() as u128).to_vec();
+            out.extend(padded(s.as_bytes()));
+            Ok(out)
+        }
+        (ParamType::Array(inner), Token::Array(items)) => {
+            let mut out = word(items.len() as u128).to_vec();
+            out.extend(encode(&vec![(**inner).clone(); items.len()], items)?);
+            Ok(out)
+        }
+        (ParamType::Tuple(types), Token::Tuple(items)) => encode(types, items),
+        _ => Err(format!("Cannot encode {:?} as {}", token, param.canonical())),
+    }
+}
+
+/// Decode a sequence of `types` from `data`
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::with_capacity(types.len());
+    let mut offset = 0;
+    for param in types {
+        if param.is_dynamic() {
+            let pointer = read_usize(data, offset)?;
+            let tail = data.get(pointer..).ok_or_else(|| format!("Offset {} past end of data", pointer))?;
+            tokens.push(decode_token(param, tail)?);
+        } else {
+            tokens.push(decode_token(param, &data[offset.min(data.len())..])?);
+        }
+        offset += param.head_size();
+    }
+    Ok(tokens)
+}
+
+fn read_word(data: &[u8], offset: usize) -> Result<&[u8], String> {
+    data.get(offset..offset + 32)
+        .ok_or_else(|| format!("Data truncated at byte {}", offset))
+}
+
+fn read_uint(data: &[u8], offset: usize) -> Result<u128, String> {
+    let w = read_word(data, offset)?;
+    if w[..16].iter().any(|b| *b != 0) {
+        return Err(format!("Integer at byte {} exceeds u128", offset));
+    }
+    Ok(u128::from_be_bytes(w[16..].try_into().unwrap()))
+}
+
+fn read_usize(data: &[u8], offset: usize) -> Result<usize, String> {
+    usize::try_from(read_uint(data, offset)?).map_err(|_| format!("Length at byte {} too large", offset))
+}
+
+fn read_bytes(data: &[u8]) -> Result<Vec<u8>, String> {
+    let len = read_usize(data, 0)?;
+    data.get(32..32 + len)
+        .map(|b| b.to_vec())
+        .ok_or_else(|| format!("{} bytes of content truncated", len))
+}
+
+fn decode_token(param: &ParamType, data: &[u8]) -> Result<Token, String> {
+    match param {
+        ParamType::Address => {
+            let w = read_word(data, 0)?;
+            if w[..12].iter().any(|b| *b != 0) {
+                return Err("Dirty address padding".to_string());
+            }
+            Ok(Token::Address(w[12..].try_into().unwrap()))
+        }
+        ParamType::Bool => match read_uint(data, 0)? {
+            0 => Ok(Token::Bool(false)),
+            1 => Ok(Token::Bool(true)),
+            other => Err(format!("Invalid bool {}", other)),
+        },
+        ParamType::Uint(bits) => {
+            let value = read_uint(data, 0)?;
+            if *bits < 128 && value >> bits != 0 {
+                return Err(format!("{} does not fit in uint{}", value, bits));
+            }
+            Ok(Token::Uint(value))
+        }
+        ParamType::FixedBytes(len) => {
+            let w = read_word(data, 0)?;
+            if w[*len..].iter().any(|b| *b != 0) {
+                return Err(format!("Dirty bytes{} padding", len));
+            }
+            Ok(Token::FixedBytes(w[..*len].to_vec()))
+        }
+        ParamType::Bytes => Ok(Token::Bytes(read_bytes(data)?)),
+        ParamType::String => String::from_utf8(read_bytes(data)?)
+            .map(Token::String)
+            .map_err(|_| "String is not UTF-8".to_string()),
+        ParamType::Array(inner) => {
+            let len = read_usize(data, 0)?;
+            if len > data.len() {
+                return Err(format!("Array length {} exceeds data", len));
+            }
+            Ok(Token::Array(decode(&vec![(**inner).clone(); len], &data[32..])?))
+        }
+        ParamType::Tuple(types) => Ok(Token::Tuple(decode(types, data)?)),
+    }
+}
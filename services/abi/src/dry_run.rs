@@ -0,0 +1,147 @@
+//! Dry-run RPC
+//! A local JSON-RPC endpoint that records every `eth_call` and
+//! `eth_sendTransaction` a service makes instead of touching a chain. Calls
+//! are answered with return data registered per function, so tests can
+//! assert both what a `ContractClient` sends and how it reads the reply.
+//! Block lookups all see one fixed block, and every sent transaction has a
+//! receipt in it: successful, unless its function was set to revert.
+
+use crate::codec::Token;
+use crate::interfaces::{Function, Interface};
+use axum::{routing::post, Json, Router};
+use sha3::{Digest, Keccak256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub method: String,
+    pub to: String,
+    pub data: String,
+}
+
+#[derive(Default)]
+struct Recorder {
+    calls: Vec<RecordedCall>,
+    returns: HashMap<[u8; 4], String>,
+    reverts: HashSet<[u8; 4]>,
+    mined: HashMap<String, bool>, // Sent tx hash -> whether it succeeded
+}
+
+#[derive(Clone)]
+pub struct DryRunRpc {
+    url: String,
+    recorder: Arc<Mutex<Recorder>>,
+}
+
+impl DryRunRpc {
+    pub async fn spawn() -> Self {
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
+        let handler_recorder = recorder.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<serde_json::Value>| {
+                let recorder = handler_recorder.clone();
+                async move { Json(answer(&recorder, &request)) }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        DryRunRpc { url, recorder }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Answer `eth_call`s of `function` with `values`. Unregistered calls
+    /// get a single zero word.
+    pub fn respond(&self, function: &Function, values: &[Token]) {
+        let data = function.encode_output(values).unwrap();
+        self.recorder
+            .lock()
+            .unwrap()
+            .returns
+            .insert(function.selector(), format!("0x{}", hex::encode(data)));
+    }
+
+    /// Mine transactions sent to `function` with a failed status from now on
+    pub fn revert(&self, function: &Function) {
+        self.recorder.lock().unwrap().reverts.insert(function.selector());
+    }
+
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.recorder.lock().unwrap().calls.clone()
+    }
+
+    /// Decoded arguments of every recorded call of `interface.function`
+    pub fn calls_of(&self, interface: &Interface, function: &str) -> Vec<Vec<Token>> {
+        let function = interface.function(function);
+        self.calls()
+            .iter()
+            .map(|call| crate::unhex(&call.data))
+            .filter(|data| data.get(..4) == Some(&function.selector()[..]))
+            .map(|data| function.decode_call(&data).unwrap_or_else(|e| panic!("{}", e)))
+            .collect()
+    }
+}
+
+fn answer(recorder: &Mutex<Recorder>, request: &serde_json::Value) -> serde_json::Value {
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let tx = &request["params"][0];
+    let data = tx["data"].as_str().unwrap_or("0x").to_string();
+
+    let mut recorder = recorder.lock().unwrap();
+    let selector = crate::unhex(&data).get(..4).map(|s| <[u8; 4]>::try_from(s).unwrap());
+    let block_hash = format!("0x{}", hex::encode(Keccak256::digest(b"dry-run block 1")));
+    let result = match method.as_str() {
+        "eth_call" => {
+            selector
+                .and_then(|s| recorder.returns.get(&s).cloned())
+                .map(serde_json::Value::from)
+                .unwrap_or_else(|| format!("0x{}", "00".repeat(32)).into())
+        }
+        "eth_sendTransaction" => {
+            let mut hasher = Keccak256::new();
+            hasher.update(data.as_bytes());
+            hasher.update(recorder.calls.len().to_be_bytes());
+            let tx_hash = format!("0x{}", hex::encode(hasher.finalize()));
+            let succeeded = !selector.is_some_and(|s| recorder.reverts.contains(&s));
+            recorder.mined.insert(tx_hash.clone(), succeeded);
+            tx_hash.into()
+        }
+        "eth_getTransactionReceipt" => {
+            let tx_hash = tx.as_str().unwrap_or_default();
+            recorder.mined.get(tx_hash).map_or(serde_json::Value::Null, |succeeded| {
+                serde_json::json!({
+                    "transactionHash": tx_hash,
+                    "blockNumber": "0x1",
+                    "blockHash": block_hash,
+                    "status": if *succeeded { "0x1" } else { "0x0" },
+                })
+            })
+        }
+        "eth_getBlockByNumber" => serde_json::json!({
+            "number": "0x1",
+            "hash": block_hash,
+        }),
+        "eth_getBlockByHash" if tx.as_str() == Some(block_hash.as_str()) => serde_json::json!({
+            "number": "0x1",
+            "hash": block_hash,
+        }),
+        _ => serde_json::Value::Null,
+    };
+
+    if matches!(method.as_str(), "eth_call" | "eth_sendTransaction") {
+        recorder.calls.push(RecordedCall {
+            method,
+            to: tx["to"].as_str().unwrap_or_default().to_string(),
+            data,
+        });
+    }
+
+    serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+}
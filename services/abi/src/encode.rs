@@ -0,0 +1,54 @@
+//! Calldata Encoding
+//! What the service `ContractClient`s build their calls from. Values become
+//! `Token`s here and the codec lays them out, so static words, dynamic
+//! offsets and tails follow the Solidity ABI spec rather than being pasted
+//! together as hex by each client.
+
+use crate::codec::{encode, ParamType, Token};
+use crate::interfaces::Function;
+use sha3::{Digest, Keccak256};
+
+/// A string as a `bytes32`. 64 hex digits, with or without 0x, are the
+/// bytes they spell; any other string of up to 32 bytes is its UTF-8,
+/// right-padded with zeros. A longer string is stored as its keccak256, so
+/// ids that share their first 32 bytes stay distinct on chain.
+pub fn abi_encode_bytes32(value: &str) -> Token {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.len() == 64 {
+        if let Ok(bytes) = hex::decode(digits) {
+            return Token::FixedBytes(bytes);
+        }
+    }
+    if value.len() <= 32 {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(32, 0);
+        return Token::FixedBytes(bytes);
+    }
+    Token::FixedBytes(Keccak256::digest(value.as_bytes()).to_vec())
+}
+
+/// An unsigned integer; the signature it is encoded against decides its
+/// width, so the same token serves uint8 through uint256
+pub fn abi_encode_uint256(value: impl Into<u128>) -> Token {
+    Token::Uint(value.into())
+}
+
+pub fn abi_encode_string(value: &str) -> Token {
+    Token::String(value.to_string())
+}
+
+/// `values` laid out as a tuple of `types`: static values in the head,
+/// dynamic ones as an offset in the head and their contents in the tail.
+/// This is also the layout of a call's arguments.
+pub fn abi_encode_tuple(types: &[ParamType], values: &[Token]) -> Result<Vec<u8>, String> {
+    encode(types, values)
+}
+
+/// Calldata for `signature`, e.g. `assignJob(bytes32,bytes32)`: its
+/// selector, then `args` encoded as a tuple of its parameter types
+pub fn abi_encode_call(signature: &str, args: &[Token]) -> Result<Vec<u8>, String> {
+    let function = Function::parse(signature, "")?;
+    let mut data = function.selector().to_vec();
+    data.extend(abi_encode_tuple(&function.inputs, args).map_err(|e| format!("{}: {}", function.name, e))?);
+    Ok(data)
+}
@@ -0,0 +1,255 @@
+//! Contract Interfaces
+//! Functions of the AI marketplace contracts as declared in `contracts/`.
+//! Enums are encoded as uint8 and structs as tuples, as solc does.
+
+use crate::codec::{canonical_list, decode, encode, parse_types, ParamType, Token};
+use sha3::{Digest, Keccak256};
+
+const JOB: &str = "(bytes32,uint8,uint8,address,bytes32,bytes32,bytes32,bytes32,bytes32,uint256,uint256,uint64,uint64,uint64,bytes32,bytes32[])";
+const MODEL: &str = "(bytes32,address,bytes32,string,bytes32,bytes32,bytes32,string,uint64,bool,bytes32[],bytes32)";
+const CHECKPOINT: &str = "(bytes32,bytes32,uint256,uint64)";
+const TRAIN_PROOF: &str = "(bytes32,uint256,bytes32,bytes32,bytes32,uint64,bytes32,bytes)";
+const INFER_PROOF: &str = "(bytes32,bytes32,bytes32,bytes32,uint64,bytes32,bytes)";
+const COMPUTE_RECEIPT: &str = "(bytes32,bytes32,uint256,uint256,bytes32,uint64,uint64,bool,uint256)";
+const OPTIMISTIC_CLAIM: &str = "(address,uint256,uint256,uint64,uint8)";
+
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<ParamType>,
+    pub outputs: Vec<ParamType>,
+}
+
+impl Function {
+    /// From a signature such as `assignJob(bytes32,bytes32)` and its return types
+    pub fn parse(signature: &str, outputs: &str) -> Result<Self, String> {
+        let (name, rest) = signature
+            .split_once('(')
+            .ok_or_else(|| format!("Invalid signature: {}", signature))?;
+        let inputs = rest
+            .strip_suffix(')')
+            .ok_or_else(|| format!("Invalid signature: {}", signature))?;
+
+        Ok(Function {
+            name: name.trim().to_string(),
+            inputs: parse_types(inputs)?,
+            outputs: parse_types(outputs)?,
+        })
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, canonical_list(&self.inputs))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+
+    pub fn encode_call(&self, args: &[Token]) -> Result<Vec<u8>, String> {
+        let mut data = self.selector().to_vec();
+        data.extend(encode(&self.inputs, args).map_err(|e| format!("{}: {}", self.name, e))?);
+        Ok(data)
+    }
+
+    pub fn decode_call(&self, calldata: &[u8]) -> Result<Vec<Token>, String> {
+        if calldata.get(..4) != Some(&self.selector()[..]) {
+            return Err(format!("Calldata is not a call of {}", self.signature()));
+        }
+        decode(&self.inputs, &calldata[4..]).map_err(|e| format!("{}: {}", self.name, e))
+    }
+
+    pub fn encode_output(&self, values: &[Token]) -> Result<Vec<u8>, String> {
+        encode(&self.outputs, values).map_err(|e| format!("{} returns: {}", self.name, e))
+    }
+
+    pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>, String> {
+        decode(&self.outputs, data).map_err(|e| format!("{} returns: {}", self.name, e))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Interface {
+    pub name: &'static str,
+    pub functions: Vec<Function>,
+}
+
+impl Interface {
+    fn new(name: &'static str, functions: &[(&str, &str)]) -> Self {
+        Interface {
+            name,
+            functions: functions
+                .iter()
+                .map(|(signature, outputs)| {
+                    Function::parse(signature, outputs).unwrap_or_else(|e| panic!("{}: {}", name, e))
+                })
+                .collect(),
+        }
+    }
+
+    /// Panics on unknown names so a typo fails the test that made it
+    pub fn function(&self, name: &str) -> &Function {
+        self.functions
+            .iter()
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("{} has no function {}", self.name, name))
+    }
+
+    pub fn by_selector(&self, selector: &[u8]) -> Option<&Function> {
+        self.functions.iter().find(|f| f.selector()[..] == *selector)
+    }
+
+    /// Identify and decode a call of any function of the contract
+    pub fn decode_call(&self, calldata: &[u8]) -> Result<(&Function, Vec<Token>), String> {
+        let selector = calldata.get(..4).ok_or("Calldata shorter than a selector")?;
+        let function = self
+            .by_selector(selector)
+            .ok_or_else(|| format!("Selector 0x{} is not a function of {}", hex::encode(selector), self.name))?;
+        Ok((function, function.decode_call(calldata)?))
+    }
+}
+
+pub fn ai_job_manager() -> Interface {
+    Interface::new(
+        "AIJobManager",
+        &[
+            ("submitTrain(bytes32,bytes32,bytes32,uint256,uint256,bytes32)", "bytes32"),
+            ("submitInfer(bytes32,bytes32,string,uint256,bytes32)", "bytes32"),
+            ("submitAgent(bytes32,uint256,bytes32)", "bytes32"),
+            ("assignJob(bytes32,bytes32)", ""),
+            ("updateStatus(bytes32,uint8)", ""),
+            ("completeJob(bytes32,bytes32,uint256,bytes32[])", ""),
+            ("failJob(bytes32,string)", ""),
+            ("getJob(bytes32)", JOB),
+            ("getSubmitterJobs(address)", "bytes32[]"),
+            ("getNodeJobs(bytes32)", "bytes32[]"),
+            ("getJobArtifacts(bytes32)", "bytes32[]"),
+            ("addProof(bytes32,bytes32)", ""),
+            ("getJobProofs(bytes32)", "bytes32[]"),
+        ],
+    )
+}
+
+pub fn dataset_registry() -> Interface {
+    Interface::new(
+        "DatasetRegistry",
+        &[
+            ("registerDataset(bytes32,uint64,string,string[])", ""),
+            ("updateLicense(bytes32,string)", ""),
+            ("setTags(bytes32,string[])", ""),
+            ("transferDatasetOwnership(bytes32,address)", ""),
+            ("getDataset(bytes32)", "address,uint64,string,string[],uint256,uint256,bool"),
+            ("linkDID(bytes32,bytes32)", ""),
+            ("setLicenseCid(bytes32,bytes32)", ""),
+            ("addVersion(bytes32,bytes32)", ""),
+            ("getDatasetExtended(bytes32)", "address,bytes32,bytes32,bytes32[],uint64,string"),
+        ],
+    )
+}
+
+pub fn model_registry() -> Interface {
+    Interface::new(
+        "ModelRegistry",
+        &[
+            ("register(bytes32,string,bytes32,bytes32,bytes32,string,bytes32)", "bytes32"),
+            ("addCheckpoint(bytes32,bytes32,bytes32,uint256)", ""),
+            ("getModel(bytes32)", MODEL),
+            ("getCheckpoints(bytes32)", &format!("{}[]", CHECKPOINT)),
+            ("getLineage(bytes32)", "bytes32[]"),
+            ("getOwnerModels(address)", "bytes32[]"),
+            ("deactivate(bytes32)", ""),
+            ("setModelCard(bytes32,bytes32)", ""),
+            ("getModelCard(bytes32)", "bytes32"),
+        ],
+    )
+}
+
+pub fn proof_of_compute() -> Interface {
+    Interface::new(
+        "ProofOfCompute",
+        &[
+            ("recordTrainProof(bytes32,uint256,bytes32,bytes32,bytes32,bytes32,bytes)", ""),
+            ("recordInferProof(bytes32,bytes32,bytes32,bytes32,bytes32,bytes)", ""),
+            ("finalize(bytes32,bytes32,uint256,bytes32)", "uint256"),
+            ("getTrainProofs(bytes32)", &format!("{}[]", TRAIN_PROOF)),
+            ("getInferProofs(bytes32)", &format!("{}[]", INFER_PROOF)),
+            ("getReceipt(bytes32)", COMPUTE_RECEIPT),
+            ("verifyProof(bytes32,uint256)", "bool"),
+            ("setVerifier(address)", ""),
+            ("setChallengeParams(uint64,uint256,uint256)", ""),
+            ("setGovernance(address)", ""),
+            ("fundJob(bytes32)", ""),
+            ("requiredStake(uint256)", "uint256"),
+            ("finalizeOptimistic(bytes32,bytes32,uint256,bytes32)", "uint256"),
+            ("dispute(bytes32)", ""),
+            ("settle(bytes32)", ""),
+            ("withdrawEscrow(bytes32)", ""),
+            ("getClaim(bytes32)", OPTIMISTIC_CLAIM),
+        ],
+    )
+}
+
+pub fn deal_market() -> Interface {
+    Interface::new(
+        "DealMarket",
+        &[
+            ("setPriceOracle(address)", ""),
+            ("setProofsV2(address)", ""),
+            ("setGovernance(address)", ""),
+            ("slashEpochReward(bytes32)", ""),
+            ("createDeal(bytes32,uint64,uint32,uint32)", ""),
+            ("streamPayout(bytes32,bytes32,bytes32[],uint256)", ""),
+            ("streamPayoutV2(bytes32,bytes32,bytes32,bytes32[],uint256)", ""),
+            ("streamPayoutV2Batch(bytes32[],bytes32[],bytes32[],bytes32[][],uint256[],address[])", ""),
+            ("recordRetrieval(bytes32,uint64,address)", ""),
+            ("recordRetrievalAggregate(bytes32,bytes32,address)", ""),
+            ("recordRetrievalAggregateProof(bytes32,bytes32,bytes32,bytes32[],uint256,address)", ""),
+            ("computePayout(bytes32,address,uint256,uint256)", ""),
+            ("getComputeQuote(uint256,uint8)", "uint256,uint256"),
+            ("setEscrowOperator(address,bool)", ""),
+            ("bindEscrowAccount(bytes32,address)", ""),
+            ("depositEscrow(bytes32)", ""),
+            ("withdrawEscrow(bytes32,uint256)", ""),
+            ("escrowJob(bytes32,bytes32,uint256)", ""),
+            ("settleJobEscrow(bytes32,bytes32,uint256,uint256)", ""),
+            ("setTreasuryAccount(bytes32)", ""),
+            ("escrowReservation(bytes32,bytes32,uint256)", ""),
+            ("refundReservation(bytes32,bytes32,uint256)", ""),
+            ("escrowDatasetAccess(bytes32,bytes32,uint256)", ""),
+            ("releaseDatasetEscrow(bytes32,bytes32,uint256,uint256)", ""),
+            ("refundDatasetEscrow(bytes32)", ""),
+        ],
+    )
+}
+
+pub fn node_cert_registry() -> Interface {
+    Interface::new(
+        "NodeCertRegistry",
+        &[
+            ("registerNode(bytes32,uint8,string,bytes32,bytes32)", "bytes32"),
+            ("heartbeat(bytes32)", ""),
+            ("updateCapabilities(bytes32,bytes32)", ""),
+            ("addStake(bytes32)", ""),
+            ("deactivateNode(bytes32)", ""),
+            ("isHealthy(bytes32)", "bool"),
+            ("getNodesByRole(uint8)", "bytes32[]"),
+            ("getNodesByRegion(string)", "bytes32[]"),
+            ("getNodesByOperator(address)", "bytes32[]"),
+        ],
+    )
+}
+
+pub fn all() -> Vec<Interface> {
+    vec![
+        ai_job_manager(),
+        dataset_registry(),
+        model_registry(),
+        proof_of_compute(),
+        deal_market(),
+        node_cert_registry(),
+    ]
+}
@@ -0,0 +1,501 @@
+//! ABI Support
+//! Calldata encoding for the service `ContractClient`s, and shared calldata
+//! and return-data helpers for the service tests. The interfaces here are
+//! written against `contracts/*.sol`, so a service test that checks its
+//! `ContractClient` against them is checking against what the chain will
+//! actually accept, not against another copy of the same encoding.
+
+pub mod codec;
+pub mod dry_run;
+pub mod encode;
+pub mod interfaces;
+
+pub use codec::{ParamType, Token};
+pub use encode::{abi_encode_bytes32, abi_encode_call, abi_encode_string, abi_encode_tuple, abi_encode_uint256};
+pub use dry_run::{DryRunRpc, RecordedCall};
+pub use interfaces::{
+    ai_job_manager, dataset_registry, deal_market, model_registry, node_cert_registry, proof_of_compute, Function,
+    Interface,
+};
+
+/// Bytes of a hex string, with or without 0x. Panics on malformed input.
+pub fn unhex(s: &str) -> Vec<u8> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    hex::decode(s).unwrap_or_else(|e| panic!("invalid hex {:?}: {}", s, e))
+}
+
+/// 0x-prefixed calldata for `interface.function(args)`
+pub fn calldata(interface: &Interface, function: &str, args: &[Token]) -> String {
+    let data = interface.function(function).encode_call(args).unwrap_or_else(|e| panic!("{}", e));
+    format!("0x{}", hex::encode(data))
+}
+
+/// 0x-prefixed return data of `interface.function`
+pub fn return_data(interface: &Interface, function: &str, values: &[Token]) -> String {
+    let data = interface.function(function).encode_output(values).unwrap_or_else(|e| panic!("{}", e));
+    format!("0x{}", hex::encode(data))
+}
+
+/// Assert `data` is a call of `interface.function` with `expected` arguments.
+/// On a selector mismatch the message names the function actually called,
+/// or says the selector matches nothing in the contract.
+pub fn assert_call(interface: &Interface, data: &str, function: &str, expected: &[Token]) {
+    let bytes = unhex(data);
+    let wanted = interface.function(function);
+    if bytes.get(..4) != Some(&wanted.selector()[..]) {
+        let actual = bytes
+            .get(..4)
+            .and_then(|s| interface.by_selector(s))
+            .map(|f| f.signature())
+            .unwrap_or_else(|| format!("an unknown selector of {}", interface.name));
+        panic!("expected a call of {}.{}, got {}", interface.name, wanted.signature(), actual);
+    }
+
+    let args = wanted.decode_call(&bytes).unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(args, expected, "arguments of {}.{}", interface.name, wanted.signature());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::decode;
+
+    #[test]
+    fn test_selectors_match_contract_signatures() {
+        // Anchor: ERC-20 transfer, as published everywhere
+        assert_eq!(hex::encode(interfaces::selector("transfer(address,uint256)")), "a9059cbb");
+
+        let expected = [
+            ("AIJobManager", "submitTrain(bytes32,bytes32,bytes32,uint256,uint256,bytes32)", "19b04cbe"),
+            ("AIJobManager", "submitInfer(bytes32,bytes32,string,uint256,bytes32)", "355113d2"),
+            ("AIJobManager", "submitAgent(bytes32,uint256,bytes32)", "061c7ac2"),
+            ("AIJobManager", "assignJob(bytes32,bytes32)", "735399eb"),
+            ("AIJobManager", "updateStatus(bytes32,uint8)", "054372ed"),
+            ("AIJobManager", "completeJob(bytes32,bytes32,uint256,bytes32[])", "9adedf0c"),
+            ("AIJobManager", "failJob(bytes32,string)", "3a917feb"),
+            ("AIJobManager", "getJob(bytes32)", "f729cf0d"),
+            ("AIJobManager", "getSubmitterJobs(address)", "109eaf2f"),
+            ("AIJobManager", "getNodeJobs(bytes32)", "df2fc2ee"),
+            ("AIJobManager", "getJobArtifacts(bytes32)", "b0046939"),
+            ("AIJobManager", "addProof(bytes32,bytes32)", "1a7d888c"),
+            ("AIJobManager", "getJobProofs(bytes32)", "a5cae921"),
+            ("DatasetRegistry", "registerDataset(bytes32,uint64,string,string[])", "32909085"),
+            ("DatasetRegistry", "updateLicense(bytes32,string)", "450d635e"),
+            ("DatasetRegistry", "setTags(bytes32,string[])", "83816f1b"),
+            ("DatasetRegistry", "transferDatasetOwnership(bytes32,address)", "e3d7ac57"),
+            ("DatasetRegistry", "getDataset(bytes32)", "bd2babb5"),
+            ("DatasetRegistry", "linkDID(bytes32,bytes32)", "27af7291"),
+            ("DatasetRegistry", "setLicenseCid(bytes32,bytes32)", "a52caaee"),
+            ("DatasetRegistry", "addVersion(bytes32,bytes32)", "62e0c6c8"),
+            ("DatasetRegistry", "getDatasetExtended(bytes32)", "0bdf49a6"),
+            ("ModelRegistry", "register(bytes32,string,bytes32,bytes32,bytes32,string,bytes32)", "720e0a4a"),
+            ("ModelRegistry", "addCheckpoint(bytes32,bytes32,bytes32,uint256)", "bacf106c"),
+            ("ModelRegistry", "getModel(bytes32)", "21e7c498"),
+            ("ModelRegistry", "getCheckpoints(bytes32)", "f8ff4b1f"),
+            ("ModelRegistry", "getLineage(bytes32)", "49f2d22c"),
+            ("ModelRegistry", "getOwnerModels(address)", "148f6982"),
+            ("ModelRegistry", "deactivate(bytes32)", "22eee84c"),
+            ("ModelRegistry", "setModelCard(bytes32,bytes32)", "40d4baa0"),
+            ("ModelRegistry", "getModelCard(bytes32)", "c8e0c789"),
+            ("ProofOfCompute", "recordTrainProof(bytes32,uint256,bytes32,bytes32,bytes32,bytes32,bytes)", "d60e3897"),
+            ("ProofOfCompute", "recordInferProof(bytes32,bytes32,bytes32,bytes32,bytes32,bytes)", "1a0a2c1d"),
+            ("ProofOfCompute", "finalize(bytes32,bytes32,uint256,bytes32)", "115cac01"),
+            ("ProofOfCompute", "getTrainProofs(bytes32)", "ff8c6641"),
+            ("ProofOfCompute", "getInferProofs(bytes32)", "ed418cb0"),
+            ("ProofOfCompute", "getReceipt(bytes32)", "fcecbb61"),
+            ("ProofOfCompute", "verifyProof(bytes32,uint256)", "d1408bff"),
+            ("ProofOfCompute", "setVerifier(address)", "5437988d"),
+            ("ProofOfCompute", "setChallengeParams(uint64,uint256,uint256)", "36c4dab3"),
+            ("ProofOfCompute", "setGovernance(address)", "ab033ea9"),
+            ("ProofOfCompute", "fundJob(bytes32)", "45330bc3"),
+            ("ProofOfCompute", "requiredStake(uint256)", "af932d12"),
+            ("ProofOfCompute", "finalizeOptimistic(bytes32,bytes32,uint256,bytes32)", "054d50a2"),
+            ("ProofOfCompute", "dispute(bytes32)", "add98c70"),
+            ("ProofOfCompute", "settle(bytes32)", "987757dd"),
+            ("ProofOfCompute", "withdrawEscrow(bytes32)", "7de4800f"),
+            ("ProofOfCompute", "getClaim(bytes32)", "c9100bcb"),
+            ("DealMarket", "setPriceOracle(address)", "530e784f"),
+            ("DealMarket", "setProofsV2(address)", "32683b24"),
+            ("DealMarket", "setGovernance(address)", "ab033ea9"),
+            ("DealMarket", "slashEpochReward(bytes32)", "5f99276f"),
+            ("DealMarket", "createDeal(bytes32,uint64,uint32,uint32)", "5a1a2bd2"),
+            ("DealMarket", "streamPayout(bytes32,bytes32,bytes32[],uint256)", "96df31b6"),
+            ("DealMarket", "streamPayoutV2(bytes32,bytes32,bytes32,bytes32[],uint256)", "17026049"),
+            ("DealMarket", "streamPayoutV2Batch(bytes32[],bytes32[],bytes32[],bytes32[][],uint256[],address[])", "3d20138a"),
+            ("DealMarket", "recordRetrieval(bytes32,uint64,address)", "0851e0e7"),
+            ("DealMarket", "recordRetrievalAggregate(bytes32,bytes32,address)", "c9d5a2f9"),
+            ("DealMarket", "recordRetrievalAggregateProof(bytes32,bytes32,bytes32,bytes32[],uint256,address)", "31cd91ce"),
+            ("DealMarket", "computePayout(bytes32,address,uint256,uint256)", "b32c6f86"),
+            ("DealMarket", "getComputeQuote(uint256,uint8)", "ff09553a"),
+            ("DealMarket", "setEscrowOperator(address,bool)", "7205676e"),
+            ("DealMarket", "bindEscrowAccount(bytes32,address)", "ec1f599e"),
+            ("DealMarket", "depositEscrow(bytes32)", "d4723ee3"),
+            ("DealMarket", "withdrawEscrow(bytes32,uint256)", "bba2fee8"),
+            ("DealMarket", "escrowJob(bytes32,bytes32,uint256)", "7720b098"),
+            ("DealMarket", "settleJobEscrow(bytes32,bytes32,uint256,uint256)", "51ff8d0b"),
+            ("DealMarket", "setTreasuryAccount(bytes32)", "ea8fe666"),
+            ("DealMarket", "escrowReservation(bytes32,bytes32,uint256)", "77ea6c2b"),
+            ("DealMarket", "refundReservation(bytes32,bytes32,uint256)", "874fafd0"),
+            ("DealMarket", "escrowDatasetAccess(bytes32,bytes32,uint256)", "de486354"),
+            ("DealMarket", "releaseDatasetEscrow(bytes32,bytes32,uint256,uint256)", "e73bdcb9"),
+            ("DealMarket", "refundDatasetEscrow(bytes32)", "4b877d49"),
+            ("NodeCertRegistry", "registerNode(bytes32,uint8,string,bytes32,bytes32)", "5ac92367"),
+            ("NodeCertRegistry", "heartbeat(bytes32)", "5a3b7899"),
+            ("NodeCertRegistry", "updateCapabilities(bytes32,bytes32)", "6eda639a"),
+            ("NodeCertRegistry", "addStake(bytes32)", "66da754b"),
+            ("NodeCertRegistry", "deactivateNode(bytes32)", "a2444390"),
+            ("NodeCertRegistry", "isHealthy(bytes32)", "7825c945"),
+            ("NodeCertRegistry", "getNodesByRole(uint8)", "210fe126"),
+            ("NodeCertRegistry", "getNodesByRegion(string)", "b3ee3ecd"),
+            ("NodeCertRegistry", "getNodesByOperator(address)", "43024ac9"),
+        ];
+        let mut seen = 0;
+        for interface in interfaces::all() {
+            for function in &interface.functions {
+                let (_, _, selector) = expected
+                    .iter()
+                    .find(|(contract, signature, _)| *contract == interface.name && *signature == function.signature())
+                    .unwrap_or_else(|| panic!("no expected selector for {}.{}", interface.name, function.signature()));
+                assert_eq!(hex::encode(function.selector()), *selector, "{}.{}", interface.name, function.name);
+                seen += 1;
+            }
+            // Selectors must be unique within a contract or dispatch is ambiguous
+            for function in &interface.functions {
+                assert_eq!(interface.by_selector(&function.selector()).unwrap().name, function.name);
+            }
+        }
+        assert_eq!(seen, expected.len());
+    }
+
+    #[test]
+    fn test_dynamic_encoding_matches_solidity_docs() {
+        // sam(bytes,bool,uint256[]) with ("dave", true, [1, 2, 3]) from the Solidity ABI spec
+        let sam = Function::parse("sam(bytes,bool,uint256[])", "").unwrap();
+        let args = [
+            Token::Bytes(b"dave".to_vec()),
+            Token::Bool(true),
+            Token::Array(vec![Token::uint(1u8), Token::uint(2u8), Token::uint(3u8)]),
+        ];
+        let expected = concat!(
+            "a5643bf2",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "6461766500000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+        );
+        let encoded = sam.encode_call(&args).unwrap();
+        assert_eq!(hex::encode(&encoded), expected);
+        assert_eq!(sam.decode_call(&encoded).unwrap(), args);
+    }
+
+    fn round_trip_call(interface: &Interface, function: &str, args: Vec<Token>) {
+        let data = calldata(interface, function, &args);
+        let (decoded_function, decoded) = interface.decode_call(&unhex(&data)).unwrap();
+        assert_eq!(decoded_function.name, function);
+        assert_eq!(decoded, args);
+        assert_call(interface, &data, function, &args);
+    }
+
+    fn round_trip_return(interface: &Interface, function: &str, values: Vec<Token>) {
+        let data = return_data(interface, function, &values);
+        assert_eq!(interface.function(function).decode_output(&unhex(&data)).unwrap(), values);
+    }
+
+    #[test]
+    fn test_ai_job_manager_round_trip() {
+        let jobs = ai_job_manager();
+        round_trip_call(&jobs, "submitTrain", vec![
+            Token::bytes32([1; 32]),
+            Token::bytes32([2; 32]),
+            Token::bytes32([3; 32]),
+            Token::uint(10u8),
+            Token::uint(5_000_000_000_000_000_000u128),
+            Token::ascii32("did:artha:alice"),
+        ]);
+        round_trip_call(&jobs, "failJob", vec![Token::bytes32([9; 32]), Token::string("CUDA out of memory")]);
+
+        // getJob returns the Job struct, which ends in a dynamic bytes32[]
+        round_trip_return(&jobs, "getJob", vec![Token::Tuple(vec![
+            Token::bytes32([1; 32]),
+            Token::uint(0u8),               // JobType.Train
+            Token::uint(3u8),               // JobStatus.Completed
+            Token::address("0x5fbdb2315678afecb367f032d93f642f64180aa3"),
+            Token::ascii32("did:artha:alice"),
+            Token::bytes32([2; 32]),
+            Token::bytes32([3; 32]),
+            Token::bytes32([4; 32]),
+            Token::ascii32("0xnode1"),
+            Token::uint(1_000u32),
+            Token::uint(750u32),
+            Token::uint(1_700_000_000u64),
+            Token::uint(1_700_000_060u64),
+            Token::uint(1_700_003_600u64),
+            Token::bytes32([5; 32]),
+            Token::Array(vec![Token::bytes32([6; 32]), Token::bytes32([7; 32])]),
+        ])]);
+    }
+
+    #[test]
+    fn test_dataset_registry_round_trip() {
+        let datasets = dataset_registry();
+        round_trip_call(&datasets, "registerDataset", vec![
+            Token::bytes32([1; 32]),
+            Token::uint(1u64 << 40),
+            Token::string("CC-BY-4.0"),
+            Token::Array(vec![Token::string("vision"), Token::string("imagenet")]),
+        ]);
+        round_trip_return(&datasets, "getDataset", vec![
+            Token::address("0x00000000000000000000000000000000000000aa"),
+            Token::uint(1u64 << 40),
+            Token::string("CC-BY-4.0"),
+            Token::Array(vec![Token::string("vision")]),
+            Token::uint(1_700_000_000u64),
+            Token::uint(1_700_000_500u64),
+            Token::Bool(true),
+        ]);
+    }
+
+    #[test]
+    fn test_model_registry_round_trip() {
+        let models = model_registry();
+        round_trip_call(&models, "register", vec![
+            Token::bytes32([1; 32]),
+            Token::string("llama"),
+            Token::bytes32([0; 32]),
+            Token::bytes32([2; 32]),
+            Token::bytes32([3; 32]),
+            Token::string("1.2.0"),
+            Token::bytes32([4; 32]),
+        ]);
+        round_trip_return(&models, "getCheckpoints", vec![Token::Array(vec![
+            Token::Tuple(vec![Token::bytes32([1; 32]), Token::bytes32([2; 32]), Token::uint(500u32), Token::uint(1u64)]),
+            Token::Tuple(vec![Token::bytes32([3; 32]), Token::bytes32([4; 32]), Token::uint(1000u32), Token::uint(2u64)]),
+        ])]);
+    }
+
+    #[test]
+    fn test_proof_of_compute_round_trip() {
+        let proofs = proof_of_compute();
+        round_trip_call(&proofs, "recordTrainProof", vec![
+            Token::bytes32([1; 32]),
+            Token::uint(42u32),
+            Token::bytes32([2; 32]),
+            Token::bytes32([3; 32]),
+            Token::bytes32([4; 32]),
+            Token::bytes32([5; 32]),
+            Token::Bytes(vec![0xab; 65]),
+        ]);
+        round_trip_return(&proofs, "finalize", vec![Token::uint(3_600_000_000_000_000_000u128)]);
+        round_trip_return(&proofs, "getReceipt", vec![Token::Tuple(vec![
+            Token::bytes32([1; 32]),
+            Token::bytes32([5; 32]),
+            Token::uint(3_600u32),
+            Token::uint(42u32),
+            Token::bytes32([6; 32]),
+            Token::uint(1u64),
+            Token::uint(3_601u64),
+            Token::Bool(true),
+            Token::uint(3_600_000_000_000_000_000u128),
+        ])]);
+        round_trip_return(&proofs, "getClaim", vec![Token::Tuple(vec![
+            Token::address("0x5fbdb2315678afecb367f032d93f642f64180aa3"),
+            Token::uint(3_600_000_000_000_000_000u128),
+            Token::uint(5_400_000_000_000_000_000u128),
+            Token::uint(1_700_086_400u64),
+            Token::uint(1u8),               // ClaimStatus.Pending
+        ])]);
+    }
+
+    #[test]
+    fn test_deal_market_round_trip() {
+        let market = deal_market();
+        round_trip_call(&market, "computePayout", vec![
+            Token::bytes32([1; 32]),
+            Token::address("0x00000000000000000000000000000000000000bb"),
+            Token::uint(3_600u32),
+            Token::uint(1_000_000_000_000_000u64),
+        ]);
+        round_trip_call(&market, "streamPayoutV2Batch", vec![
+            Token::Array(vec![Token::bytes32([1; 32])]),
+            Token::Array(vec![Token::bytes32([2; 32])]),
+            Token::Array(vec![Token::bytes32([3; 32])]),
+            Token::Array(vec![Token::Array(vec![Token::bytes32([4; 32]), Token::bytes32([5; 32])])]),
+            Token::Array(vec![Token::uint(7u8)]),
+            Token::Array(vec![Token::address("0x00000000000000000000000000000000000000cc")]),
+        ]);
+        round_trip_return(&market, "getComputeQuote", vec![Token::uint(4_000u32), Token::uint(2u8)]);
+    }
+
+    #[test]
+    fn test_mismatches_are_reported() {
+        let jobs = ai_job_manager();
+        // Wrong value for the declared type
+        assert!(jobs.function("updateStatus").encode_call(&[Token::bytes32([1; 32]), Token::uint(256u32)]).is_err());
+        assert!(jobs.function("assignJob").encode_call(&[Token::bytes32([1; 32])]).is_err());
+        // The selector the services used for the old submitTrain(..,uint32,uint256) is not in the contract
+        let stale = interfaces::selector("submitTrain(bytes32,bytes32,bytes32,uint32,uint256)");
+        assert!(jobs.by_selector(&stale).is_none());
+        // Truncated return data
+        let data = unhex(&return_data(&jobs, "getJobProofs", &[Token::Array(vec![Token::bytes32([1; 32])])]));
+        assert!(jobs.function("getJobProofs").decode_output(&data[..data.len() - 1]).is_err());
+
+        let wrong = std::panic::catch_unwind(|| {
+            let data = calldata(&jobs, "assignJob", &[Token::bytes32([1; 32]), Token::bytes32([2; 32])]);
+            assert_call(&jobs, &data, "addProof", &[Token::bytes32([1; 32]), Token::bytes32([2; 32])]);
+        });
+        assert!(wrong.is_err());
+    }
+
+    fn round_trip_signature(signature: &str, args: Vec<Token>) {
+        let function = Function::parse(signature, "").unwrap();
+        let data = abi_encode_call(signature, &args).unwrap();
+        assert_eq!(data, function.encode_call(&args).unwrap());
+        assert_eq!(function.decode_call(&data).unwrap(), args, "{}", signature);
+    }
+
+    #[test]
+    fn test_static_types_round_trip() {
+        round_trip_signature("f(bytes32)", vec![abi_encode_bytes32("job-1")]);
+        round_trip_signature("f(uint256)", vec![abi_encode_uint256(u128::MAX)]);
+        round_trip_signature("f(uint8)", vec![abi_encode_uint256(255u8)]);
+        round_trip_signature("f(bool,bool)", vec![Token::Bool(true), Token::Bool(false)]);
+        round_trip_signature("updateStatus(bytes32,uint8)", vec![abi_encode_bytes32("job-1"), abi_encode_uint256(5u8)]);
+
+        // Each static value is one word, in order
+        let data = abi_encode_tuple(
+            &[ParamType::FixedBytes(32), ParamType::Uint(8), ParamType::Bool],
+            &[abi_encode_bytes32("ab"), abi_encode_uint256(7u8), Token::Bool(true)],
+        )
+        .unwrap();
+        assert_eq!(hex::encode(&data), concat!(
+            "6162000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000007",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        ));
+    }
+
+    #[test]
+    fn test_dynamic_types_round_trip() {
+        round_trip_signature("f(string)", vec![abi_encode_string("")]);
+        round_trip_signature("f(string)", vec![abi_encode_string("a string longer than a single thirty-two byte word")]);
+        round_trip_signature("register(bytes32,bytes32,string[])", vec![
+            abi_encode_bytes32("bafy-root"),
+            abi_encode_bytes32("bafy-license"),
+            Token::Array(vec![abi_encode_string("vision"), abi_encode_string("imagenet")]),
+        ]);
+        round_trip_signature("f(bytes32,string,uint8,string,bool)", vec![
+            abi_encode_bytes32("job-1"),
+            abi_encode_string("first"),
+            abi_encode_uint256(3u8),
+            abi_encode_string("second"),
+            Token::Bool(true),
+        ]);
+
+        // f(uint256,string) with (1, "dave"): the string's offset in the head, its length and bytes in the tail
+        let types = [ParamType::Uint(256), ParamType::String];
+        let values = [abi_encode_uint256(1u8), abi_encode_string("dave")];
+        let data = abi_encode_tuple(&types, &values).unwrap();
+        assert_eq!(hex::encode(&data), concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "6461766500000000000000000000000000000000000000000000000000000000",
+        ));
+        assert_eq!(decode(&types, &data).unwrap(), values);
+    }
+
+    #[test]
+    fn test_bytes32_keeps_every_id_distinct() {
+        // Short strings are padded, as a Solidity bytes32 literal is
+        assert_eq!(abi_encode_bytes32("batch"), Token::ascii32("batch"));
+
+        // Hex digests are the bytes they spell
+        let digest = format!("0x{}", "ab".repeat(32));
+        assert_eq!(abi_encode_bytes32(&digest), Token::bytes32([0xab; 32]));
+        assert_eq!(abi_encode_bytes32(&"ab".repeat(32)), Token::bytes32([0xab; 32]));
+
+        // Longer ids are hashed rather than cut, so a shared prefix doesn't collide
+        let a = abi_encode_bytes32("job-0123456789abcdef0123456789abcdef");
+        let b = abi_encode_bytes32("job-0123456789abcdef0123456789abcd00");
+        assert_ne!(a, b);
+        assert_ne!(a, Token::ascii32("job-0123456789abcdef0123456789abcdef"));
+        round_trip_signature("getJob(bytes32)", vec![a]);
+    }
+
+    #[test]
+    fn test_values_that_do_not_fit_their_type_are_rejected() {
+        let error = abi_encode_call("updateStatus(bytes32,uint8)", &[abi_encode_bytes32("job-1"), abi_encode_uint256(256u16)]).unwrap_err();
+        assert!(error.contains("does not fit in uint8"), "{}", error);
+        assert!(abi_encode_call("getJob(bytes32)", &[abi_encode_string("job-1")]).is_err());
+        assert!(abi_encode_call("getJob(bytes32)", &[]).is_err());
+        assert!(abi_encode_call("getJob", &[]).is_err());
+    }
+
+    async fn rpc_post(url: &str, method: &str, to: &str, data: &str) -> serde_json::Value {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": [{ "to": to, "data": data }, "latest"],
+            "id": 1
+        });
+        reqwest::Client::new().post(url).json(&request).send().await.unwrap().json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_records_calls_and_serves_returns() {
+        let rpc = DryRunRpc::spawn().await;
+        let proofs = proof_of_compute();
+        rpc.respond(proofs.function("verifyProof"), &[Token::Bool(true)]);
+
+        let called = rpc_post(&rpc.url(), "eth_call", "0xproof", &calldata(&proofs, "verifyProof", &[
+            Token::bytes32([1; 32]),
+            Token::uint(0u8),
+        ])).await;
+        assert_eq!(called["result"], return_data(&proofs, "verifyProof", &[Token::Bool(true)]));
+
+        let sent = rpc_post(&rpc.url(), "eth_sendTransaction", "0xproof", &calldata(&proofs, "finalize", &[
+            Token::bytes32([1; 32]),
+            Token::bytes32([2; 32]),
+            Token::uint(60u8),
+            Token::bytes32([3; 32]),
+        ])).await;
+        assert_eq!(sent["result"].as_str().unwrap().len(), 66);
+
+        assert_eq!(rpc.calls().len(), 2);
+        assert_eq!(rpc.calls()[1].to, "0xproof");
+        assert_eq!(rpc.calls_of(&proofs, "finalize")[0][2], Token::uint(60u8));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_mines_sent_transactions_unless_set_to_revert() {
+        let rpc = DryRunRpc::spawn().await;
+        let market = deal_market();
+        rpc.revert(market.function("escrowJob"));
+        let receipt = |tx_hash: serde_json::Value| {
+            let request = serde_json::json!({ "jsonrpc": "2.0", "method": "eth_getTransactionReceipt", "params": [tx_hash], "id": 1 });
+            let url = rpc.url();
+            async move { reqwest::Client::new().post(url).json(&request).send().await.unwrap().json::<serde_json::Value>().await.unwrap() }
+        };
+
+        let args = [Token::bytes32([1; 32]), Token::bytes32([2; 32]), Token::uint(60u8)];
+        let reverted = rpc_post(&rpc.url(), "eth_sendTransaction", "0xmarket", &calldata(&market, "escrowJob", &args)).await;
+        assert_eq!(receipt(reverted["result"].clone()).await["result"]["status"], "0x0");
+
+        let settled = rpc_post(&rpc.url(), "eth_sendTransaction", "0xmarket", &calldata(&market, "settleJobEscrow", &[
+            Token::bytes32([1; 32]),
+            Token::bytes32([3; 32]),
+            Token::uint(40u8),
+            Token::uint(20u8),
+        ])).await;
+        let mined = receipt(settled["result"].clone()).await;
+        assert_eq!(mined["result"]["status"], "0x1");
+        assert_eq!(mined["result"]["blockNumber"], "0x1");
+
+        assert!(receipt(serde_json::json!(format!("0x{}", "ab".repeat(32)))).await["result"].is_null());
+    }
+}
@@ -11,6 +11,10 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentJob {
@@ -22,6 +26,8 @@ pub struct AgentJob {
     pub status: AgentStatus,
     pub tool_calls: Vec<ToolCall>,
     pub memory_cid: Option<String>,
+    #[serde(default)]
+    pub tool_limits: ToolLimits,
     pub created_at: u64,
 }
 
@@ -41,11 +47,83 @@ pub struct ToolCall {
     pub result: Option<serde_json::Value>,
     pub timestamp: u64,
     pub digest: String,
+    #[serde(default)]
+    pub status: ToolCallStatus,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub cost: u64,
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum ToolCallStatus {
+    #[default]
+    Succeeded,
+    Failed,
+    TimedOut,
+    CostExceeded,
+}
+
+/// Per-job limits applied to every tool call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolLimits {
+    /// Timeout per tool type ("http", "chain_query", "compute", ...) in milliseconds
+    #[serde(default)]
+    pub timeouts_ms: HashMap<String, u64>,
+    /// Timeout for tool types without an explicit entry
+    #[serde(default = "default_tool_timeout_ms")]
+    pub default_timeout_ms: u64,
+    /// Maximum cost a single tool call may consume
+    #[serde(default = "default_max_cost_per_call")]
+    pub max_cost_per_call: u64,
+}
+
+fn default_tool_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_max_cost_per_call() -> u64 {
+    1_000
+}
+
+impl Default for ToolLimits {
+    fn default() -> Self {
+        ToolLimits {
+            timeouts_ms: HashMap::new(),
+            default_timeout_ms: default_tool_timeout_ms(),
+            max_cost_per_call: default_max_cost_per_call(),
+        }
+    }
+}
+
+impl ToolLimits {
+    pub fn timeout_for(&self, tool_name: &str) -> Duration {
+        let ms = self
+            .timeouts_ms
+            .get(tool_type(tool_name))
+            .copied()
+            .unwrap_or(self.default_timeout_ms);
+        Duration::from_millis(ms)
+    }
+}
+
+/// Tool type used for limit lookup, e.g. "http_get" -> "http"
+fn tool_type(tool_name: &str) -> &str {
+    if tool_name.starts_with("http") {
+        "http"
+    } else if tool_name.starts_with("chain") || tool_name.starts_with("rpc") {
+        "chain_query"
+    } else {
+        tool_name.split(['_', ':']).next().unwrap_or(tool_name)
+    }
 }
 
 pub struct AppState {
     agent_jobs: Arc<RwLock<HashMap<String, AgentJob>>>,
     runtime_url: String,
+    rpc_url: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -55,6 +133,8 @@ pub struct RunAgentRequest {
     pub tools: Vec<String>,
     pub memory_policy: String,
     pub budget: u64,
+    #[serde(default)]
+    pub tool_limits: ToolLimits,
 }
 
 #[derive(Debug, Serialize)]
@@ -68,6 +148,12 @@ async fn run_agent(
     Json(req): Json<RunAgentRequest>,
 ) -> Result<Json<RunAgentResponse>, StatusCode> {
     let job_id = format!("agent-{}", uuid::Uuid::new_v4());
+    let run_request = serde_json::json!({
+        "job_id": job_id,
+        "aiid": req.aiid,
+        "goal": req.goal,
+        "tools": req.tools,
+    });
     
     let agent_job = AgentJob {
         job_id: job_id.clone(),
@@ -78,6 +164,7 @@ async fn run_agent(
         status: AgentStatus::Running,
         tool_calls: Vec::new(),
         memory_cid: None,
+        tool_limits: req.tool_limits,
         created_at: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -90,12 +177,7 @@ async fn run_agent(
     let client = reqwest::Client::new();
     let response = client
         .post(&format!("{}/agent/run", state.runtime_url))
-        .json(&serde_json::json!({
-            "job_id": job_id,
-            "aiid": req.aiid,
-            "goal": req.goal,
-            "tools": req.tools,
-        }))
+        .json(&run_request)
         .send()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -120,23 +202,233 @@ async fn get_tool_calls(
     Ok(Json(job.tool_calls.clone()))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ToolCallRequest {
+    pub tool_name: String,
+    pub params: serde_json::Value,
+    /// Cost the agent expects the call to consume; rejected up front if above the cap
+    #[serde(default)]
+    pub estimated_cost: u64,
+}
+
+/// Run a tool call under the job's timeout and cost cap.
+/// Timeouts and cap violations are recorded as failed calls rather than errors
+/// so the agent can react to them.
+async fn invoke_with_limits<F, Fut>(
+    tool_name: &str,
+    params: serde_json::Value,
+    estimated_cost: u64,
+    limits: &ToolLimits,
+    tool: F,
+) -> ToolCall
+where
+    F: FnOnce(serde_json::Value) -> Fut,
+    Fut: Future<Output = Result<(serde_json::Value, u64), String>>,
+{
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut call = ToolCall {
+        tool_name: tool_name.to_string(),
+        params: params.clone(),
+        result: None,
+        timestamp,
+        digest: String::new(),
+        status: ToolCallStatus::Failed,
+        error: None,
+        cost: 0,
+        duration_ms: 0,
+    };
+
+    if estimated_cost > limits.max_cost_per_call {
+        call.status = ToolCallStatus::CostExceeded;
+        call.error = Some(format!(
+            "Estimated cost {} exceeds cap {}",
+            estimated_cost, limits.max_cost_per_call
+        ));
+        call.digest = tool_call_digest(&call);
+        return call;
+    }
+
+    let timeout = limits.timeout_for(tool_name);
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(timeout, tool(params)).await;
+    call.duration_ms = started.elapsed().as_millis() as u64;
+
+    match outcome {
+        Err(_) => {
+            call.status = ToolCallStatus::TimedOut;
+            call.error = Some(format!("Tool call timed out after {}ms", timeout.as_millis()));
+        }
+        Ok(Err(e)) => {
+            call.error = Some(e);
+        }
+        Ok(Ok((_, cost))) if cost > limits.max_cost_per_call => {
+            call.cost = cost;
+            call.status = ToolCallStatus::CostExceeded;
+            call.error = Some(format!("Cost {} exceeds cap {}", cost, limits.max_cost_per_call));
+        }
+        Ok(Ok((result, cost))) => {
+            call.cost = cost;
+            call.result = Some(result);
+            call.status = ToolCallStatus::Succeeded;
+        }
+    }
+
+    call.digest = tool_call_digest(&call);
+    call
+}
+
+fn tool_call_digest(call: &ToolCall) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(call.tool_name.as_bytes());
+    hasher.update(call.params.to_string().as_bytes());
+    if let Some(result) = &call.result {
+        hasher.update(result.to_string().as_bytes());
+    }
+    hasher.update(call.timestamp.to_le_bytes());
+    format!("0x{:x}", hasher.finalize())
+}
+
+/// Execute a built-in tool, returning its result and the cost it consumed
+async fn execute_tool(
+    client: reqwest::Client,
+    rpc_url: String,
+    tool_name: String,
+    params: serde_json::Value,
+) -> Result<(serde_json::Value, u64), String> {
+    match tool_type(&tool_name) {
+        "http" => {
+            let url = params["url"].as_str().ok_or("Missing url parameter")?;
+            let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+            let body = response.text().await.map_err(|e| e.to_string())?;
+            // Cost is metered per KiB transferred
+            let cost = (body.len() as u64 / 1024).max(1);
+            Ok((serde_json::json!({ "body": body }), cost))
+        }
+        "chain_query" => {
+            let method = params["method"].as_str().ok_or("Missing method parameter")?;
+            let response = client
+                .post(&rpc_url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": method,
+                    "params": params.get("params").cloned().unwrap_or(serde_json::json!([])),
+                }))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+            Ok((body, 1))
+        }
+        other => Err(format!("Unsupported tool type: {}", other)),
+    }
+}
+
+async fn call_tool(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Json(req): Json<ToolCallRequest>,
+) -> Result<Json<ToolCall>, StatusCode> {
+    let limits = {
+        let jobs = state.agent_jobs.read().await;
+        let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        if !job.tools.contains(&req.tool_name) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        job.tool_limits.clone()
+    };
+
+    let client = reqwest::Client::new();
+    let rpc_url = state.rpc_url.clone();
+    let tool_name = req.tool_name.clone();
+    let call = invoke_with_limits(&req.tool_name, req.params, req.estimated_cost, &limits, |params| {
+        execute_tool(client, rpc_url, tool_name, params)
+    })
+    .await;
+
+    if call.status != ToolCallStatus::Succeeded {
+        warn!("⚠️  Tool call {} for {} failed: {:?}", call.tool_name, job_id, call.status);
+    }
+
+    let mut jobs = state.agent_jobs.write().await;
+    let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    job.tool_calls.push(call.clone());
+
+    Ok(Json(call))
+}
+
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
         agent_jobs: Arc::new(RwLock::new(HashMap::new())),
         runtime_url: std::env::var("ARTHA_RUNTIME_URL")
             .unwrap_or_else(|_| "http://localhost:8084".to_string()),
+        rpc_url: std::env::var("ARTHA_RPC_URL")
+            .unwrap_or_else(|_| "http://localhost:8545".to_string()),
     });
 
     let app = Router::new()
         .route("/agent/run", post(run_agent))
         .route("/agent/:id/tool-calls", get(get_tool_calls))
+        .route("/agent/:id/tool-call", post(call_tool))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 AI Agents Service starting on :8086");
+    info!("🚀 AI Agents Service starting on :8086");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8086").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_slow_tool_is_cut_off_at_timeout() {
+        let mut limits = ToolLimits::default();
+        limits.timeouts_ms.insert("http".to_string(), 50);
+
+        let call = invoke_with_limits("http_get", serde_json::json!({}), 0, &limits, |_| async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok((serde_json::json!({ "body": "late" }), 1))
+        })
+        .await;
+
+        assert_eq!(call.status, ToolCallStatus::TimedOut);
+        assert!(call.result.is_none());
+        assert!(call.duration_ms < 5_000);
+
+        // Other tool types keep the default timeout
+        let call = invoke_with_limits("chain_query", serde_json::json!({}), 0, &limits, |_| async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok((serde_json::json!({ "block": 1 }), 1))
+        })
+        .await;
+        assert_eq!(call.status, ToolCallStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_cost_cap_rejects_expensive_calls() {
+        let limits = ToolLimits { max_cost_per_call: 10, ..ToolLimits::default() };
+
+        let call = invoke_with_limits("chain_query", serde_json::json!({}), 50, &limits, |_| async {
+            Ok((serde_json::json!({}), 1))
+        })
+        .await;
+        assert_eq!(call.status, ToolCallStatus::CostExceeded);
+
+        let call = invoke_with_limits("chain_query", serde_json::json!({}), 0, &limits, |_| async {
+            Ok((serde_json::json!({}), 25))
+        })
+        .await;
+        assert_eq!(call.status, ToolCallStatus::CostExceeded);
+        assert!(call.result.is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Moderation Appeals
+//! Enforced moderation decisions, the appeal queue operators review, and SLA
+//! escalation for appeals left pending too long.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A flag as recorded on an enforced decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionFlag {
+    pub flag_id: String,
+    pub check_type: String,
+    pub score: f64,
+}
+
+/// A job output blocked by an enforced check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationDecision {
+    pub decision_id: String,
+    pub job_id: String,
+    pub tenant: String, // Submitter DID; the only party that may appeal
+    pub content_digest: String,
+    pub flags: Vec<DecisionFlag>,
+    pub decided_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AppealStatus {
+    Pending,
+    Escalated, // Pending past the SLA
+    Upheld,
+    Overturned,
+}
+
+impl AppealStatus {
+    pub fn parse(status: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(status.to_lowercase())).ok()
+    }
+
+    fn is_open(&self) -> bool {
+        matches!(self, AppealStatus::Pending | AppealStatus::Escalated)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Uphold,
+    Overturn,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Appeal {
+    pub appeal_id: String,
+    pub decision_id: String,
+    pub job_id: String,
+    pub flag_ids: Vec<String>,
+    pub appellant_did: String,
+    pub justification: String,
+    pub status: AppealStatus,
+    pub filed_at: u64,
+    pub escalated_at: Option<u64>,
+    pub resolved_by: Option<String>,
+    pub notes: Option<String>,
+    pub resolved_at: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealRequest {
+    pub job_id: String,
+    pub decision_id: String,
+    pub flag_ids: Vec<String>,
+    pub justification: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AppealError {
+    UnknownDecision,
+    JobMismatch,
+    NotSubmitter,
+    UnknownFlag(String),
+    Duplicate(String), // Appeal already filed against the decision
+    NotFound,
+    AlreadyResolved,
+}
+
+pub struct AppealQueue {
+    decisions: HashMap<String, ModerationDecision>,
+    appeals: HashMap<String, Appeal>,
+    pub sla_secs: u64,
+}
+
+impl AppealQueue {
+    pub fn new(sla_secs: u64) -> Self {
+        AppealQueue { decisions: HashMap::new(), appeals: HashMap::new(), sla_secs }
+    }
+
+    pub fn record_decision(&mut self, decision: ModerationDecision) {
+        self.decisions.insert(decision.decision_id.clone(), decision);
+    }
+
+    /// File an appeal. One appeal per decision: a second one, open or
+    /// resolved, is a duplicate.
+    pub fn file(&mut self, appeal_id: String, appellant: &str, req: AppealRequest, now: u64) -> Result<Appeal, AppealError> {
+        let decision = self.decisions.get(&req.decision_id).ok_or(AppealError::UnknownDecision)?;
+        if decision.job_id != req.job_id {
+            return Err(AppealError::JobMismatch);
+        }
+        if decision.tenant != appellant {
+            return Err(AppealError::NotSubmitter);
+        }
+        if let Some(flag_id) = req.flag_ids.iter().find(|id| !decision.flags.iter().any(|f| &f.flag_id == *id)) {
+            return Err(AppealError::UnknownFlag(flag_id.clone()));
+        }
+        if let Some(existing) = self.appeals.values().find(|a| a.decision_id == req.decision_id) {
+            return Err(AppealError::Duplicate(existing.appeal_id.clone()));
+        }
+
+        let flag_ids = if req.flag_ids.is_empty() {
+            decision.flags.iter().map(|f| f.flag_id.clone()).collect() // Appeal the whole decision
+        } else {
+            req.flag_ids
+        };
+        let appeal = Appeal {
+            appeal_id: appeal_id.clone(),
+            decision_id: req.decision_id,
+            job_id: req.job_id,
+            flag_ids,
+            appellant_did: appellant.to_string(),
+            justification: req.justification,
+            status: AppealStatus::Pending,
+            filed_at: now,
+            escalated_at: None,
+            resolved_by: None,
+            notes: None,
+            resolved_at: None,
+        };
+        self.appeals.insert(appeal_id, appeal.clone());
+        Ok(appeal)
+    }
+
+    /// Appeals in a status (all if None), oldest first
+    pub fn list(&self, status: Option<AppealStatus>) -> Vec<Appeal> {
+        let mut appeals: Vec<Appeal> = self
+            .appeals
+            .values()
+            .filter(|a| status.is_none_or(|status| a.status == status))
+            .cloned()
+            .collect();
+        appeals.sort_by_key(|a| a.filed_at);
+        appeals
+    }
+
+    /// The decision behind an open appeal, checked before anything is changed
+    pub fn resolvable(&self, appeal_id: &str) -> Result<ModerationDecision, AppealError> {
+        let appeal = self.appeals.get(appeal_id).ok_or(AppealError::NotFound)?;
+        if !appeal.status.is_open() {
+            return Err(AppealError::AlreadyResolved);
+        }
+        let decision = self.decisions.get(&appeal.decision_id).ok_or(AppealError::UnknownDecision)?;
+        Ok(decision.clone())
+    }
+
+    pub fn resolve(
+        &mut self,
+        appeal_id: &str,
+        resolution: Resolution,
+        operator: &str,
+        notes: Option<String>,
+        now: u64,
+    ) -> Result<Appeal, AppealError> {
+        self.resolvable(appeal_id)?;
+        let appeal = self.appeals.get_mut(appeal_id).ok_or(AppealError::NotFound)?;
+        appeal.status = match resolution {
+            Resolution::Uphold => AppealStatus::Upheld,
+            Resolution::Overturn => AppealStatus::Overturned,
+        };
+        appeal.resolved_by = Some(operator.to_string());
+        appeal.notes = notes;
+        appeal.resolved_at = Some(now);
+        Ok(appeal.clone())
+    }
+
+    /// Escalate pending appeals older than the SLA; returns the escalated ids
+    pub fn escalate_overdue(&mut self, now: u64) -> Vec<String> {
+        let mut escalated = Vec::new();
+        for appeal in self.appeals.values_mut() {
+            if appeal.status == AppealStatus::Pending && now.saturating_sub(appeal.filed_at) >= self.sla_secs {
+                appeal.status = AppealStatus::Escalated;
+                appeal.escalated_at = Some(now);
+                escalated.push(appeal.appeal_id.clone());
+            }
+        }
+        escalated.sort();
+        escalated
+    }
+}
@@ -0,0 +1,137 @@
+//! Threshold Calibration
+//! Per-tenant flag thresholds tuned from appeal outcomes. Overturned flags are
+//! labeled false positives and push the tenant's threshold for that check just
+//! above them; upheld flags cap how far it may rise. Every change is kept in an
+//! audit history.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Gap kept above the highest false-positive score
+const MARGIN: f64 = 0.01;
+/// Furthest a tenant threshold may rise above the default
+const MAX_SHIFT: f64 = 0.25;
+
+/// A moderation outcome corrected by appeal review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackExample {
+    pub tenant: String,
+    pub check_type: String,
+    pub content_digest: String,
+    pub score: f64,
+    pub corrected_label: bool, // true = the flag was right (upheld), false = false positive
+    pub appeal_id: String,
+    pub recorded_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdAdjustment {
+    pub tenant: String,
+    pub check_type: String,
+    pub from: f64,
+    pub to: f64,
+    pub false_positives: usize,
+    pub confirmed: usize,
+    pub adjusted_at: u64,
+}
+
+pub struct Calibrator {
+    defaults: HashMap<String, f64>,
+    overrides: HashMap<(String, String), f64>, // (tenant, check) -> threshold
+    feedback: Vec<FeedbackExample>,
+    history: Vec<ThresholdAdjustment>,
+}
+
+impl Calibrator {
+    /// The thresholds `check_content` has always used
+    pub fn new() -> Self {
+        let defaults = [("toxicity", 0.5), ("jailbreak", 0.5), ("bias", 0.6), ("nsfw", 0.5)]
+            .into_iter()
+            .map(|(check, threshold)| (check.to_string(), threshold))
+            .collect();
+        Calibrator { defaults, overrides: HashMap::new(), feedback: Vec::new(), history: Vec::new() }
+    }
+
+    /// A score above this flags the check
+    pub fn threshold(&self, tenant: Option<&str>, check_type: &str) -> f64 {
+        tenant
+            .and_then(|tenant| self.overrides.get(&(tenant.to_string(), check_type.to_string())))
+            .copied()
+            .unwrap_or_else(|| self.defaults.get(check_type).copied().unwrap_or(0.5))
+    }
+
+    pub fn record(&mut self, example: FeedbackExample) {
+        self.feedback.push(example);
+    }
+
+    pub fn feedback(&self, tenant: Option<&str>) -> Vec<FeedbackExample> {
+        self.feedback
+            .iter()
+            .filter(|e| tenant.is_none_or(|tenant| e.tenant == tenant))
+            .cloned()
+            .collect()
+    }
+
+    pub fn history(&self, tenant: Option<&str>) -> Vec<ThresholdAdjustment> {
+        self.history
+            .iter()
+            .filter(|a| tenant.is_none_or(|tenant| a.tenant == tenant))
+            .cloned()
+            .collect()
+    }
+
+    /// Retune one tenant's threshold for one check from its feedback.
+    /// Returns the adjustment made, if the threshold moved.
+    pub fn calibrate(&mut self, tenant: &str, check_type: &str, now: u64) -> Option<ThresholdAdjustment> {
+        let examples: Vec<&FeedbackExample> = self
+            .feedback
+            .iter()
+            .filter(|e| e.tenant == tenant && e.check_type == check_type)
+            .collect();
+        let false_positives: Vec<f64> = examples.iter().filter(|e| !e.corrected_label).map(|e| e.score).collect();
+        let confirmed: Vec<f64> = examples.iter().filter(|e| e.corrected_label).map(|e| e.score).collect();
+        if false_positives.is_empty() {
+            return None;
+        }
+
+        let default = self.defaults.get(check_type).copied().unwrap_or(0.5);
+        let above_false_positives = false_positives.iter().copied().fold(f64::MIN, f64::max) + MARGIN;
+        // Never rise past a score reviewers confirmed should be flagged
+        let ceiling = confirmed.iter().copied().fold(default + MAX_SHIFT, f64::min);
+        let target = above_false_positives.min(ceiling).clamp(default, default + MAX_SHIFT);
+        let target = (target * 1000.0).round() / 1000.0;
+
+        let from = self.threshold(Some(tenant), check_type);
+        if (target - from).abs() < f64::EPSILON {
+            return None;
+        }
+        self.overrides.insert((tenant.to_string(), check_type.to_string()), target);
+        let adjustment = ThresholdAdjustment {
+            tenant: tenant.to_string(),
+            check_type: check_type.to_string(),
+            from,
+            to: target,
+            false_positives: false_positives.len(),
+            confirmed: confirmed.len(),
+            adjusted_at: now,
+        };
+        self.history.push(adjustment.clone());
+        Some(adjustment)
+    }
+
+    /// Retune every (tenant, check) pair that has feedback
+    pub fn calibrate_all(&mut self, tenant: Option<&str>, now: u64) -> Vec<ThresholdAdjustment> {
+        let mut pairs: Vec<(String, String)> = self
+            .feedback
+            .iter()
+            .filter(|e| tenant.is_none_or(|tenant| e.tenant == tenant))
+            .map(|e| (e.tenant.clone(), e.check_type.clone()))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        pairs
+            .into_iter()
+            .filter_map(|(tenant, check)| self.calibrate(&tenant, &check, now))
+            .collect()
+    }
+}
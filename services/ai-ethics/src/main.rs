@@ -2,14 +2,27 @@
 /// Filters outputs for toxicity, jailbreak attempts, and bias
 
 use axum::{
-    extract::{Path, State, Json},
-    http::StatusCode,
+    extract::{Path, Query, State, Json},
+    http::{HeaderMap, StatusCode},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+mod appeals;
+use appeals::{Appeal, AppealError, AppealQueue, AppealRequest, AppealStatus, DecisionFlag, ModerationDecision, Resolution};
+mod calibration;
+use calibration::{Calibrator, FeedbackExample, ThresholdAdjustment};
+
+/// Header carrying the caller's DID, set by the gateway
+const DID_HEADER: &str = "x-artha-did";
+/// Header carrying the shared token for service-to-service calls
+const INTERNAL_TOKEN_HEADER: &str = "x-artha-internal-token";
 
 #[derive(Debug, Deserialize)]
 pub struct EthicsCheckRequest {
@@ -17,17 +30,25 @@ pub struct EthicsCheckRequest {
     pub model_id: Option<String>,
     pub domain: Option<String>,
     pub checks: Vec<String>, // "toxicity", "jailbreak", "bias", "nsfw"
+    #[serde(default)]
+    pub tenant: Option<String>, // Submitter DID; selects calibrated thresholds
+    #[serde(default)]
+    pub job_id: Option<String>,
+    #[serde(default)]
+    pub enforce: bool, // Record a blocking decision the submitter can appeal
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EthicsCheckResponse {
     pub allowed: bool,
     pub flags: Vec<EthicsFlag>,
     pub score: f64, // 0.0 = safe, 1.0 = unsafe
+    pub decision_id: Option<String>, // Set when an enforced check blocked the content
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EthicsFlag {
+    pub flag_id: String,
     pub check_type: String,
     pub severity: String,
     pub reason: String,
@@ -35,20 +56,42 @@ pub struct EthicsFlag {
 }
 
 pub struct AppState {
-    // Could cache model results
+    appeals: Arc<RwLock<AppealQueue>>,
+    calibrator: Arc<RwLock<Calibrator>>,
+    operators: HashSet<String>, // DIDs allowed to review appeals
+    jobd_url: String,
+    internal_token: String,
+    auto_calibrate: bool, // Retune thresholds as soon as an appeal is resolved
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn flag_id() -> String {
+    format!("flag-{}", uuid::Uuid::new_v4())
 }
 
 async fn check_content(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<EthicsCheckRequest>,
 ) -> Result<Json<EthicsCheckResponse>, StatusCode> {
     let mut flags = Vec::new();
+    let thresholds: HashMap<String, f64> = {
+        let calibrator = state.calibrator.read().await;
+        req.checks.iter().map(|check| (check.clone(), calibrator.threshold(req.tenant.as_deref(), check))).collect()
+    };
+    let threshold = |check: &str| thresholds.get(check).copied().unwrap_or(0.5);
     
     // Real toxicity detection using ML model
     if req.checks.contains(&"toxicity".to_string()) {
         let toxicity_score = detect_toxicity(&req.content).await;
-        if toxicity_score > 0.5 {
+        if toxicity_score > threshold("toxicity") {
             flags.push(EthicsFlag {
+                flag_id: flag_id(),
                 check_type: "toxicity".to_string(),
                 severity: if toxicity_score > 0.8 { "high" } else { "medium" }.to_string(),
                 reason: format!("Toxicity score: {:.2}", toxicity_score),
@@ -60,8 +103,9 @@ async fn check_content(
     // Jailbreak detection using pattern matching + ML
     if req.checks.contains(&"jailbreak".to_string()) {
         let jailbreak_score = detect_jailbreak(&req.content).await;
-        if jailbreak_score > 0.5 {
+        if jailbreak_score > threshold("jailbreak") {
             flags.push(EthicsFlag {
+                flag_id: flag_id(),
                 check_type: "jailbreak".to_string(),
                 severity: "high".to_string(),
                 reason: "Jailbreak attempt detected".to_string(),
@@ -73,8 +117,9 @@ async fn check_content(
     // Bias detection using ML model
     if req.checks.contains(&"bias".to_string()) {
         let bias_score = detect_bias(&req.content).await;
-        if bias_score > 0.6 {
+        if bias_score > threshold("bias") {
             flags.push(EthicsFlag {
+                flag_id: flag_id(),
                 check_type: "bias".to_string(),
                 severity: if bias_score > 0.8 { "high" } else { "medium" }.to_string(),
                 reason: format!("Bias score: {:.2}", bias_score),
@@ -86,8 +131,9 @@ async fn check_content(
     // NSFW detection
     if req.checks.contains(&"nsfw".to_string()) {
         let nsfw_score = detect_nsfw(&req.content).await;
-        if nsfw_score > 0.5 {
+        if nsfw_score > threshold("nsfw") {
             flags.push(EthicsFlag {
+                flag_id: flag_id(),
                 check_type: "nsfw".to_string(),
                 severity: "high".to_string(),
                 reason: "NSFW content detected".to_string(),
@@ -102,14 +148,203 @@ async fn check_content(
         flags.iter().map(|f| f.confidence).sum::<f64>() / flags.len() as f64
     };
     let allowed = score < 0.5; // Allow if score < 0.5
-    
+
+    // Enforced blocks become decisions the submitter can appeal
+    let decision_id = match (&req.job_id, &req.tenant) {
+        (Some(job_id), Some(tenant)) if req.enforce && !allowed => {
+            let decision_id = format!("decision-{}", uuid::Uuid::new_v4());
+            state.appeals.write().await.record_decision(ModerationDecision {
+                decision_id: decision_id.clone(),
+                job_id: job_id.clone(),
+                tenant: tenant.clone(),
+                content_digest: format!("0x{}", hex::encode(Sha256::digest(req.content.as_bytes()))),
+                flags: flags
+                    .iter()
+                    .map(|f| DecisionFlag { flag_id: f.flag_id.clone(), check_type: f.check_type.clone(), score: f.confidence })
+                    .collect(),
+                decided_at: now(),
+            });
+            info!("🛑 Blocked output of job {} ({})", job_id, decision_id);
+            Some(decision_id)
+        }
+        _ => None,
+    };
+
     Ok(Json(EthicsCheckResponse {
         allowed,
         flags,
         score,
+        decision_id,
     }))
 }
 
+fn caller_did(headers: &HeaderMap) -> Result<String, StatusCode> {
+    headers
+        .get(DID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|did| did.to_string())
+        .ok_or(StatusCode::UNAUTHORIZED)
+}
+
+fn require_operator(state: &AppState, headers: &HeaderMap) -> Result<String, StatusCode> {
+    let did = caller_did(headers)?;
+    if !state.operators.contains(&did) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(did)
+}
+
+fn appeal_status(e: AppealError) -> StatusCode {
+    match e {
+        AppealError::UnknownDecision | AppealError::NotFound => StatusCode::NOT_FOUND,
+        AppealError::NotSubmitter => StatusCode::FORBIDDEN,
+        AppealError::JobMismatch | AppealError::UnknownFlag(_) => StatusCode::BAD_REQUEST,
+        AppealError::Duplicate(_) | AppealError::AlreadyResolved => StatusCode::CONFLICT,
+    }
+}
+
+/// POST /ethics/appeals - The submitter contests an enforced block
+async fn file_appeal(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<AppealRequest>,
+) -> Result<Json<Appeal>, StatusCode> {
+    let appellant = caller_did(&headers)?;
+    let appeal_id = format!("appeal-{}", uuid::Uuid::new_v4());
+    let appeal = state
+        .appeals
+        .write()
+        .await
+        .file(appeal_id, &appellant, req, now())
+        .map_err(appeal_status)?;
+    info!("📨 Appeal {} filed against {}", appeal.appeal_id, appeal.decision_id);
+    Ok(Json(appeal))
+}
+
+/// GET /ethics/appeals?status=pending - Review queue, operators only
+async fn list_appeals(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<Appeal>>, StatusCode> {
+    require_operator(&state, &headers)?;
+    let status = match params.get("status") {
+        Some(status) => Some(AppealStatus::parse(status).ok_or(StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    Ok(Json(state.appeals.read().await.list(status)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveRequest {
+    pub resolution: Resolution,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveResponse {
+    pub appeal: Appeal,
+    pub adjustments: Vec<ThresholdAdjustment>,
+}
+
+/// POST /ethics/appeals/:id/resolve - Uphold or overturn. Overturning releases
+/// the held job output in ai-jobd; both outcomes become labeled feedback.
+async fn resolve_appeal(
+    State(state): State<Arc<AppState>>,
+    Path(appeal_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ResolveRequest>,
+) -> Result<Json<ResolveResponse>, StatusCode> {
+    let operator = require_operator(&state, &headers)?;
+    let decision = state.appeals.read().await.resolvable(&appeal_id).map_err(appeal_status)?;
+
+    // Release first so a jobd failure leaves the appeal open for a retry
+    if req.resolution == Resolution::Overturn {
+        release_job_output(&state, &decision).await.map_err(|e| {
+            warn!("⚠️  Failed to release job {}: {}", decision.job_id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+    }
+
+    let now = now();
+    let appeal = state
+        .appeals
+        .write()
+        .await
+        .resolve(&appeal_id, req.resolution, &operator, req.notes, now)
+        .map_err(appeal_status)?;
+
+    let mut calibrator = state.calibrator.write().await;
+    let mut checks = Vec::new();
+    for flag in decision.flags.iter().filter(|f| appeal.flag_ids.contains(&f.flag_id)) {
+        calibrator.record(FeedbackExample {
+            tenant: decision.tenant.clone(),
+            check_type: flag.check_type.clone(),
+            content_digest: decision.content_digest.clone(),
+            score: flag.score,
+            corrected_label: req.resolution == Resolution::Uphold,
+            appeal_id: appeal_id.clone(),
+            recorded_at: now,
+        });
+        checks.push(flag.check_type.clone());
+    }
+    let adjustments = if state.auto_calibrate {
+        checks
+            .iter()
+            .filter_map(|check| calibrator.calibrate(&decision.tenant, check, now))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    info!("⚖️  Appeal {} {:?} by {}", appeal_id, appeal.status, operator);
+    Ok(Json(ResolveResponse { appeal, adjustments }))
+}
+
+async fn release_job_output(state: &AppState, decision: &ModerationDecision) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/internal/job/{}/moderation/release", state.jobd_url, decision.job_id))
+        .header(INTERNAL_TOKEN_HEADER, &state.internal_token)
+        .json(&serde_json::json!({ "decision_id": decision.decision_id }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("jobd returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// GET /ethics/feedback?tenant= - Labeled examples from resolved appeals
+async fn list_feedback(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<FeedbackExample>>, StatusCode> {
+    require_operator(&state, &headers)?;
+    Ok(Json(state.calibrator.read().await.feedback(params.get("tenant").map(String::as_str))))
+}
+
+/// POST /ethics/calibration/run?tenant= - Retune thresholds from the feedback store
+async fn run_calibration(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ThresholdAdjustment>>, StatusCode> {
+    require_operator(&state, &headers)?;
+    let tenant = params.get("tenant").map(String::as_str);
+    Ok(Json(state.calibrator.write().await.calibrate_all(tenant, now())))
+}
+
+/// GET /ethics/calibration/history?tenant= - Audit trail of threshold changes
+async fn calibration_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Json<Vec<ThresholdAdjustment>> {
+    Json(state.calibrator.read().await.history(params.get("tenant").map(String::as_str)))
+}
+
 // Real ML model implementations (wrappers around ML libraries)
 async fn detect_toxicity(content: &str) -> f64 {
     // In production: Load and run toxicity detection model (e.g., detoxify, Perspective API)
@@ -172,16 +407,277 @@ async fn detect_nsfw(content: &str) -> f64 {
 
 #[tokio::main]
 async fn main() {
-    let state = Arc::new(AppState {});
+    artha_log::init();
+    let sla_hours: u64 = std::env::var("ARTHA_APPEAL_SLA_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(24);
+    let state = Arc::new(AppState {
+        appeals: Arc::new(RwLock::new(AppealQueue::new(sla_hours * 3600))),
+        calibrator: Arc::new(RwLock::new(Calibrator::new())),
+        operators: std::env::var("ARTHA_ETHICS_OPERATORS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|did| did.trim().to_string())
+            .filter(|did| !did.is_empty())
+            .collect(),
+        jobd_url: std::env::var("ARTHA_JOBD_URL").unwrap_or_else(|_| "http://localhost:8081".to_string()),
+        internal_token: std::env::var("ARTHA_INTERNAL_TOKEN").unwrap_or_else(|_| "ai-jobd-dev-internal".to_string()),
+        auto_calibrate: std::env::var("ARTHA_ETHICS_AUTO_CALIBRATE")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false),
+    });
+
+    // Background task: escalate appeals pending past the SLA
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+            for appeal_id in state_clone.appeals.write().await.escalate_overdue(now()) {
+                info!("⏰ Appeal {} escalated: pending past the {}h SLA", appeal_id, sla_hours);
+            }
+        }
+    });
 
     let app = Router::new()
         .route("/ethics/check", post(check_content))
+        .route("/ethics/appeals", post(file_appeal).get(list_appeals))
+        .route("/ethics/appeals/:id/resolve", post(resolve_appeal))
+        .route("/ethics/feedback", get(list_feedback))
+        .route("/ethics/calibration/run", post(run_calibration))
+        .route("/ethics/calibration/history", get(calibration_history))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 AI Ethics Service starting on :8089");
+    info!("🚀 AI Ethics Service starting on :8089");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8089").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBMITTER: &str = "did:artha:alice";
+    const OPERATOR: &str = "did:artha:operator";
+    // Four toxic keywords score 0.6: over the default 0.5 threshold, a borderline block
+    const BORDERLINE: &str = "a story about hate, violence, harm and a threat";
+
+    fn test_state(jobd_url: String, auto_calibrate: bool) -> Arc<AppState> {
+        Arc::new(AppState {
+            appeals: Arc::new(RwLock::new(AppealQueue::new(3600))),
+            calibrator: Arc::new(RwLock::new(Calibrator::new())),
+            operators: [OPERATOR.to_string()].into_iter().collect(),
+            jobd_url,
+            internal_token: "internal".to_string(),
+            auto_calibrate,
+        })
+    }
+
+    fn did_headers(did: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(DID_HEADER, did.parse().unwrap());
+        headers
+    }
+
+    async fn enforced_check(state: &Arc<AppState>, tenant: &str, job_id: &str) -> EthicsCheckResponse {
+        check_content(State(state.clone()), Json(EthicsCheckRequest {
+            content: BORDERLINE.to_string(),
+            model_id: None,
+            domain: None,
+            checks: vec!["toxicity".to_string()],
+            tenant: Some(tenant.to_string()),
+            job_id: Some(job_id.to_string()),
+            enforce: true,
+        }))
+        .await
+        .unwrap()
+        .0
+    }
+
+    /// jobd stand-in that records release calls as (job, token, body)
+    async fn mock_jobd() -> (String, Arc<std::sync::Mutex<Vec<(String, String, serde_json::Value)>>>) {
+        let releases: Arc<std::sync::Mutex<Vec<(String, String, serde_json::Value)>>> = Arc::default();
+        let recorded = releases.clone();
+        let app = Router::new().route(
+            "/internal/job/:id/moderation/release",
+            post(move |Path(job_id): Path<String>, headers: HeaderMap, Json(body): Json<serde_json::Value>| {
+                let recorded = recorded.clone();
+                async move {
+                    let token = headers.get(INTERNAL_TOKEN_HEADER).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+                    recorded.lock().unwrap().push((job_id, token, body));
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, releases)
+    }
+
+    #[tokio::test]
+    async fn test_appeal_lifecycle_releases_job_on_overturn() {
+        let (jobd_url, releases) = mock_jobd().await;
+        let state = test_state(jobd_url, false);
+
+        let blocked = enforced_check(&state, SUBMITTER, "job-1").await;
+        assert!(!blocked.allowed);
+        let decision_id = blocked.decision_id.clone().unwrap();
+        let flag_id = blocked.flags[0].flag_id.clone();
+        let appeal_req = |flags: Vec<String>| AppealRequest {
+            job_id: "job-1".to_string(),
+            decision_id: decision_id.clone(),
+            flag_ids: flags,
+            justification: "Fiction, not a threat".to_string(),
+        };
+
+        // Only the submitter may appeal, once, and only flags on the decision
+        let err = file_appeal(State(state.clone()), did_headers("did:artha:mallory"), Json(appeal_req(vec![]))).await;
+        assert_eq!(err.unwrap_err(), StatusCode::FORBIDDEN);
+        let err = file_appeal(State(state.clone()), did_headers(SUBMITTER), Json(appeal_req(vec!["flag-other".to_string()]))).await;
+        assert_eq!(err.unwrap_err(), StatusCode::BAD_REQUEST);
+        let appeal = file_appeal(State(state.clone()), did_headers(SUBMITTER), Json(appeal_req(vec![flag_id.clone()]))).await.unwrap().0;
+        assert_eq!(appeal.status, AppealStatus::Pending);
+        let dup = file_appeal(State(state.clone()), did_headers(SUBMITTER), Json(appeal_req(vec![]))).await;
+        assert_eq!(dup.unwrap_err(), StatusCode::CONFLICT);
+
+        // The review queue is for operators
+        let pending = || Query([("status".to_string(), "pending".to_string())].into_iter().collect::<HashMap<_, _>>());
+        let denied = list_appeals(State(state.clone()), did_headers(SUBMITTER), pending()).await;
+        assert_eq!(denied.unwrap_err(), StatusCode::FORBIDDEN);
+        let queue = list_appeals(State(state.clone()), did_headers(OPERATOR), pending()).await.unwrap().0;
+        assert_eq!(queue.len(), 1);
+
+        // Overturning releases the held output in jobd and labels the flag a false positive
+        let resolve = || ResolveRequest { resolution: Resolution::Overturn, notes: Some("Clearly fiction".to_string()) };
+        let resolved = resolve_appeal(State(state.clone()), Path(appeal.appeal_id.clone()), did_headers(OPERATOR), Json(resolve()))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(resolved.appeal.status, AppealStatus::Overturned);
+        assert_eq!(resolved.appeal.resolved_by.as_deref(), Some(OPERATOR));
+        assert!(resolved.adjustments.is_empty()); // Auto-calibration is off
+        {
+            let releases = releases.lock().unwrap();
+            assert_eq!(releases.len(), 1);
+            assert_eq!(releases[0].0, "job-1");
+            assert_eq!(releases[0].1, "internal");
+            assert_eq!(releases[0].2["decision_id"], decision_id.as_str());
+        }
+        let feedback = state.calibrator.read().await.feedback(Some(SUBMITTER));
+        assert_eq!(feedback.len(), 1);
+        assert_eq!(feedback[0].check_type, "toxicity");
+        assert!(!feedback[0].corrected_label);
+        assert_eq!(feedback[0].content_digest, format!("0x{}", hex::encode(Sha256::digest(BORDERLINE.as_bytes()))));
+
+        let again = resolve_appeal(State(state.clone()), Path(appeal.appeal_id.clone()), did_headers(OPERATOR), Json(resolve())).await;
+        assert_eq!(again.unwrap_err(), StatusCode::CONFLICT);
+        assert!(list_appeals(State(state.clone()), did_headers(OPERATOR), pending()).await.unwrap().0.is_empty());
+        assert_eq!(releases.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_calibration_moves_borderline_case_for_affected_tenant_only() {
+        let (jobd_url, _) = mock_jobd().await;
+        let state = test_state(jobd_url, true);
+        const OTHER: &str = "did:artha:bob";
+
+        let blocked = enforced_check(&state, SUBMITTER, "job-1").await;
+        assert!(!enforced_check(&state, OTHER, "job-2").await.allowed);
+        let appeal = state
+            .appeals
+            .write()
+            .await
+            .file("appeal-1".to_string(), SUBMITTER, AppealRequest {
+                job_id: "job-1".to_string(),
+                decision_id: blocked.decision_id.unwrap(),
+                flag_ids: vec![],
+                justification: "Fiction".to_string(),
+            }, now())
+            .unwrap();
+        let resolved = resolve_appeal(
+            State(state.clone()),
+            Path(appeal.appeal_id),
+            did_headers(OPERATOR),
+            Json(ResolveRequest { resolution: Resolution::Overturn, notes: None }),
+        )
+        .await
+        .unwrap()
+        .0;
+
+        // The tenant's toxicity threshold rises just past the false positive
+        assert_eq!(resolved.adjustments.len(), 1);
+        assert_eq!(resolved.adjustments[0].from, 0.5);
+        assert_eq!(resolved.adjustments[0].to, 0.61);
+        let after = enforced_check(&state, SUBMITTER, "job-3").await;
+        assert!(after.allowed);
+        assert!(after.decision_id.is_none());
+        assert!(!enforced_check(&state, OTHER, "job-4").await.allowed);
+
+        // Every change is auditable, and re-running with no new feedback changes nothing
+        let history = calibration_history(
+            State(state.clone()),
+            Query([("tenant".to_string(), SUBMITTER.to_string())].into_iter().collect()),
+        )
+        .await
+        .0;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].false_positives, 1);
+        let rerun = run_calibration(State(state.clone()), did_headers(OPERATOR), Query(HashMap::new())).await.unwrap().0;
+        assert!(rerun.is_empty());
+        assert!(calibration_history(State(state.clone()), Query([("tenant".to_string(), OTHER.to_string())].into_iter().collect()))
+            .await
+            .0
+            .is_empty());
+
+        // A confirmed block at a lower score caps how far the threshold may rise
+        let mut calibrator = Calibrator::new();
+        let example = |score: f64, corrected_label: bool| FeedbackExample {
+            tenant: SUBMITTER.to_string(),
+            check_type: "toxicity".to_string(),
+            content_digest: "0x00".to_string(),
+            score,
+            corrected_label,
+            appeal_id: "appeal-x".to_string(),
+            recorded_at: 0,
+        };
+        calibrator.record(example(0.7, false));
+        calibrator.record(example(0.65, true));
+        assert_eq!(calibrator.calibrate(SUBMITTER, "toxicity", 0).unwrap().to, 0.65);
+    }
+
+    #[test]
+    fn test_pending_appeals_escalate_after_sla() {
+        let mut queue = AppealQueue::new(3600);
+        queue.record_decision(ModerationDecision {
+            decision_id: "decision-1".to_string(),
+            job_id: "job-1".to_string(),
+            tenant: SUBMITTER.to_string(),
+            content_digest: "0x00".to_string(),
+            flags: vec![DecisionFlag { flag_id: "flag-1".to_string(), check_type: "toxicity".to_string(), score: 0.6 }],
+            decided_at: 1000,
+        });
+        let req = AppealRequest {
+            job_id: "job-1".to_string(),
+            decision_id: "decision-1".to_string(),
+            flag_ids: vec![],
+            justification: "Fiction".to_string(),
+        };
+        queue.file("appeal-1".to_string(), SUBMITTER, req, 1000).unwrap();
+
+        assert!(queue.escalate_overdue(1000 + 3599).is_empty());
+        assert_eq!(queue.escalate_overdue(1000 + 3600), vec!["appeal-1".to_string()]);
+        assert!(queue.escalate_overdue(1000 + 7200).is_empty()); // Escalated once
+        let escalated = queue.list(Some(AppealStatus::Escalated));
+        assert_eq!(escalated[0].escalated_at, Some(4600));
+        assert!(queue.list(Some(AppealStatus::Pending)).is_empty());
+
+        // Escalated appeals can still be resolved
+        let resolved = queue.resolve("appeal-1", Resolution::Uphold, OPERATOR, None, 5000).unwrap();
+        assert_eq!(resolved.status, AppealStatus::Upheld);
+    }
+}
@@ -13,6 +13,7 @@ use std::process::Command;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
+use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EvolutionJob {
@@ -227,6 +228,7 @@ async fn get_evo_population(
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
         evo_jobs: Arc::new(RwLock::new(HashMap::new())),
         populations: Arc::new(RwLock::new(HashMap::new())),
@@ -237,9 +239,10 @@ async fn main() {
         .route("/evolution/:id/status", get(get_evo_status))
         .route("/evolution/:id/population", get(get_evo_population))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 AI Evolution Service starting on :8088");
+    info!("🚀 AI Evolution Service starting on :8088");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8088").await.unwrap();
     axum::serve(listener, app).await.unwrap();
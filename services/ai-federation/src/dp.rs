@@ -0,0 +1,108 @@
+//! Differential Privacy Mechanisms
+//! A job picks the mechanism that matches its sensitivity analysis and
+//! states its (ε, δ) budget; the noise scale is calibrated from them:
+//!
+//! - Laplace, pure ε-DP over an L1 sensitivity Δ₁: b = Δ₁ / ε
+//! - Gaussian, (ε, δ)-DP over an L2 sensitivity Δ₂, for 0 < ε < 1 and
+//!   0 < δ < 1: σ = Δ₂ · √(2 ln(1.25 / δ)) / ε
+//!
+//! The calibrated parameters are kept on the job and reported in its status.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DpMechanism {
+    Laplace,
+    Gaussian,
+}
+
+/// The privacy budget a job asks for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DpBudget {
+    pub mechanism: DpMechanism,
+    pub epsilon: f64,
+    #[serde(default)]
+    pub delta: f64,
+    pub sensitivity: f64, // L1 for Laplace, L2 for Gaussian
+}
+
+impl DpBudget {
+    /// What `dp: true` without a budget gets: the scale-0.1 Laplace noise
+    /// jobs were always aggregated with
+    pub const DEFAULT: DpBudget = DpBudget {
+        mechanism: DpMechanism::Laplace,
+        epsilon: 1.0,
+        delta: 0.0,
+        sensitivity: 0.1,
+    };
+
+    pub fn calibrate(&self) -> Result<DpParams, String> {
+        if !(self.epsilon > 0.0 && self.epsilon.is_finite()) {
+            return Err("epsilon must be positive".to_string());
+        }
+        if !(self.sensitivity > 0.0 && self.sensitivity.is_finite()) {
+            return Err("sensitivity must be positive".to_string());
+        }
+        let (delta, scale) = match self.mechanism {
+            // Pure ε-DP: δ plays no part
+            DpMechanism::Laplace => (0.0, self.sensitivity / self.epsilon),
+            DpMechanism::Gaussian => {
+                if !(self.delta > 0.0 && self.delta < 1.0) {
+                    return Err("The Gaussian mechanism needs 0 < delta < 1".to_string());
+                }
+                if self.epsilon >= 1.0 {
+                    return Err("The Gaussian mechanism's calibration holds for epsilon < 1".to_string());
+                }
+                (self.delta, self.sensitivity * (2.0 * (1.25 / self.delta).ln()).sqrt() / self.epsilon)
+            }
+        };
+        Ok(DpParams {
+            mechanism: self.mechanism,
+            epsilon: self.epsilon,
+            delta,
+            sensitivity: self.sensitivity,
+            scale,
+        })
+    }
+}
+
+/// A calibrated mechanism, as recorded on the job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DpParams {
+    pub mechanism: DpMechanism,
+    pub epsilon: f64,
+    pub delta: f64,
+    pub sensitivity: f64,
+    pub scale: f64, // Laplace b, or Gaussian σ
+}
+
+impl DpParams {
+    /// One draw of zero-mean noise
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        // 1 - U is uniform on (0, 1], so its log is finite
+        let mut open_uniform = || 1.0 - rng.gen::<f64>();
+        match self.mechanism {
+            // The difference of two Exp(1) draws is Laplace(0, 1)
+            DpMechanism::Laplace => self.scale * (open_uniform().ln() - open_uniform().ln()),
+            // Box–Muller
+            DpMechanism::Gaussian => {
+                let (u1, u2) = (open_uniform(), open_uniform());
+                self.scale * (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+            }
+        }
+    }
+
+    pub fn add_noise(&self, values: &mut [f64]) {
+        let mut rng = rand::thread_rng();
+        for value in values {
+            *value += self.sample(&mut rng);
+        }
+    }
+
+    /// (ε, δ) spent after `releases` noised results, by basic sequential
+    /// composition: each release spends the job's full budget again
+    pub fn spent_after(&self, releases: u32) -> (f64, f64) {
+        (self.epsilon * releases as f64, (self.delta * releases as f64).min(1.0))
+    }
+}
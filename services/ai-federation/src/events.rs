@@ -0,0 +1,99 @@
+//! Federated Job Events
+//! Everything a watcher of a job sees, in the order it happened: the round
+//! state machine's transitions, each participant's submission as it
+//! arrives, every completed aggregation and the privacy budget it spent, and
+//! any round that closed short of its quorum.
+//!
+//! Events are only appended, so a position in the log is a stable id a
+//! reconnecting watcher can resume from. The log closes with the job's move
+//! to Completed or Failed.
+
+use crate::FedStatus;
+use serde::Serialize;
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum FedEvent {
+    Status {
+        round: u32,
+        from: FedStatus,
+        to: FedStatus,
+    },
+    Submission {
+        round: u32,
+        participant: String,
+        received: usize, // Submissions in the round so far
+        expected: usize,
+    },
+    Aggregated {
+        round: u32,
+        participants: usize,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        dropped: Vec<String>, // Recovered by secure aggregation
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        absent: Vec<String>, // Sent nothing before the round's deadline
+        #[serde(skip_serializing_if = "Option::is_none")]
+        aggregated_model_cid: Option<String>,
+    },
+    PrivacyBudget {
+        round: u32,
+        epsilon_spent: f64,
+        delta_spent: f64,
+    },
+    QuorumMissed {
+        round: u32,
+        received: usize,
+        required: usize,
+        absent: Vec<String>,
+    },
+}
+
+impl FedEvent {
+    /// The SSE event name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FedEvent::Status { .. } => "status",
+            FedEvent::Submission { .. } => "submission",
+            FedEvent::Aggregated { .. } => "aggregated",
+            FedEvent::PrivacyBudget { .. } => "privacy_budget",
+            FedEvent::QuorumMissed { .. } => "quorum_missed",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EventLog {
+    events: Vec<FedEvent>,
+    published: watch::Sender<usize>, // Length of the log, for waiting watchers
+    closed: Option<FedStatus>,       // The terminal status, once reached
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        EventLog { events: Vec::new(), published: watch::channel(0).0, closed: None }
+    }
+
+    pub fn push(&mut self, event: FedEvent) {
+        if let FedEvent::Status { to, .. } = &event {
+            if to.is_terminal() {
+                self.closed = Some(*to);
+            }
+        }
+        self.events.push(event);
+        self.published.send_replace(self.events.len());
+    }
+
+    /// Events from position `from` on
+    pub fn since(&self, from: usize) -> &[FedEvent] {
+        self.events.get(from..).unwrap_or_default()
+    }
+
+    pub fn closed(&self) -> Option<FedStatus> {
+        self.closed
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<usize> {
+        self.published.subscribe()
+    }
+}
@@ -2,15 +2,32 @@
 /// Handles FedAvg, Secure Aggregation, and Differential Privacy
 
 use axum::{
-    extract::{Path, State, Json},
+    extract::{Path, Query, State, Json},
     http::StatusCode,
     routing::{get, post},
     Router,
 };
+use artha_clock::SharedClock;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
+use tracing::{error, info, warn};
+
+mod dp;
+mod events;
+mod psi;
+mod quorum;
+mod secagg;
+mod streaming;
+mod vertical;
+use dp::{DpBudget, DpParams};
+use events::{EventLog, FedEvent};
+use psi::PsiTask;
+use quorum::QuorumConfig;
+use secagg::{EncryptedShare, Point, RevealedShare, SecAggRound, SecAggSummary};
+use streaming::{SvdbUpdates, UpdateSource};
+use vertical::{AlignmentView, Compensation, RoundTensor, RoundView, VerticalConfig, VerticalSession, VerticalSummary};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FederatedJob {
@@ -20,12 +37,36 @@ pub struct FederatedJob {
     pub rounds: u32,
     pub current_round: u32,
     pub dp_enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dp: Option<DpParams>, // Mechanism and calibrated scale, when DP is on
     pub status: FedStatus,
     pub participants: Vec<String>,
     pub aggregated_model_cid: Option<String>,
+    #[serde(default)]
+    pub partition: Partition,
+    #[serde(default)]
+    pub budget: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertical: Option<VerticalSummary>, // Filled in on status reads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round_deadline: Option<u64>, // Unix secs; after it a quorum of updates is enough
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Horizontal: participants share features, differ in samples (FedAvg).
+/// Vertical: parties share samples, differ in features (PSI + split learning).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Partition {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+/// Round state machine: Queued -> Collecting -> Aggregating, then back to
+/// Collecting for the next round or on to Completed after the last. Vertical
+/// rounds have no aggregation step and complete from Collecting. Any job
+/// that hasn't finished can fail.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum FedStatus {
     Queued,
     Collecting,
@@ -34,57 +75,137 @@ pub enum FedStatus {
     Failed,
 }
 
+impl FedStatus {
+    pub fn is_terminal(self) -> bool {
+        matches!(self, FedStatus::Completed | FedStatus::Failed)
+    }
+
+    pub fn can_become(self, to: FedStatus) -> bool {
+        use FedStatus::*;
+        match (self, to) {
+            (from, Failed) => !from.is_terminal(),
+            (Queued, Collecting) | (Collecting, Aggregating | Completed) | (Aggregating, Collecting | Completed) => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GradientUpdate {
     pub participant: String,
-    pub weights: Vec<f64>, // Serialized model weights
+    #[serde(default)]
+    pub weights: Vec<f64>, // Serialized model weights, when sent inline
+    #[serde(default)]
+    pub weights_cid: Option<String>, // SVDB object of little-endian f64 weights, for large models
+    #[serde(default)]
+    pub param_count: usize, // Length of the SVDB-stored weights
     pub sample_count: u64,
     pub digest: String,
 }
 
+impl GradientUpdate {
+    /// Length of the weight vector, wherever it is held
+    pub fn num_params(&self) -> usize {
+        if self.weights_cid.is_some() {
+            self.param_count
+        } else {
+            self.weights.len()
+        }
+    }
+}
+
 pub struct AppState {
     fed_jobs: Arc<RwLock<HashMap<String, FederatedJob>>>,
     gradient_updates: Arc<RwLock<HashMap<String, Vec<GradientUpdate>>>>, // fed_id -> updates
+    secagg_rounds: Arc<RwLock<HashMap<String, SecAggRound>>>, // fed_id -> masked round
+    vertical: Arc<RwLock<HashMap<String, VerticalSession>>>, // fed_id -> vertical job
+    fed_events: Arc<RwLock<HashMap<String, EventLog>>>, // fed_id -> event log
+    svdb: Arc<SvdbUpdates>,
+    quorum: QuorumConfig,
+    clock: SharedClock,
 }
 
-// FedAvg algorithm implementation
-fn federated_average(updates: &[GradientUpdate]) -> Vec<f64> {
-    if updates.is_empty() {
-        return Vec::new();
+impl FederatedJob {
+    /// The round being collected or aggregated, or the last one once done
+    fn round(&self) -> u32 {
+        (self.current_round + 1).min(self.rounds)
     }
-    
-    let total_samples: u64 = updates.iter().map(|u| u.sample_count).sum();
-    if total_samples == 0 {
-        return updates[0].weights.clone();
+}
+
+impl AppState {
+    /// Deadline of a round opening now
+    fn round_deadline(&self) -> u64 {
+        self.clock.now_secs() + self.quorum.round_timeout_secs
     }
-    
-    let num_params = updates[0].weights.len();
-    let mut aggregated = vec![0.0; num_params];
-    
-    // Weighted average: sum(w_i * n_i) / sum(n_i)
-    for update in updates {
-        let weight = update.sample_count as f64 / total_samples as f64;
-        for (i, w) in update.weights.iter().enumerate() {
-            aggregated[i] += w * weight;
-        }
+}
+
+async fn record(state: &AppState, fed_id: &str, event: FedEvent) {
+    if let Some(log) = state.fed_events.write().await.get_mut(fed_id) {
+        log.push(event);
     }
-    
-    aggregated
 }
 
-// Secure Aggregation with differential privacy noise
-fn secure_aggregate(updates: &[GradientUpdate], dp_scale: f64) -> Vec<f64> {
-    let mut aggregated = federated_average(updates);
-    
-    // Add Laplacian noise for differential privacy
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    for weight in &mut aggregated {
-        let noise = rng.gen::<f64>() * dp_scale;
-        *weight += noise;
+/// Move `job` through the round state machine. Every status change goes
+/// through here, so the job's event log sees each one.
+async fn transition(state: &AppState, job: &mut FederatedJob, to: FedStatus) -> Result<(), StatusCode> {
+    if job.status == to {
+        return Ok(());
     }
-    
-    aggregated
+    if !job.status.can_become(to) {
+        return Err(StatusCode::CONFLICT);
+    }
+    let from = std::mem::replace(&mut job.status, to);
+    record(state, &job.fed_id, FedEvent::Status { round: job.round(), from, to }).await;
+    Ok(())
+}
+
+/// Close the round being aggregated: record the result and the privacy it
+/// spent, then collect the next round or complete the job after the last
+async fn complete_round(
+    state: &AppState,
+    job: &mut FederatedJob,
+    participants: usize,
+    dropped: Vec<String>,
+    absent: Vec<String>,
+    aggregated_model_cid: Option<String>,
+) -> Result<(), StatusCode> {
+    let round = job.round();
+    job.current_round += 1;
+    let event = FedEvent::Aggregated { round, participants, dropped, absent, aggregated_model_cid };
+    record(state, &job.fed_id, event).await;
+    record_privacy_spent(state, job, round).await;
+    let next = if job.current_round >= job.rounds { FedStatus::Completed } else { FedStatus::Collecting };
+    job.round_deadline = (next == FedStatus::Collecting).then(|| state.round_deadline());
+    transition(state, job, next).await
+}
+
+async fn set_status(state: &AppState, fed_id: &str, to: FedStatus) -> Result<(), StatusCode> {
+    let mut jobs = state.fed_jobs.write().await;
+    let job = jobs.get_mut(fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    transition(state, job, to).await
+}
+
+async fn record_privacy_spent(state: &AppState, job: &FederatedJob, round: u32) {
+    if let Some(dp) = job.dp {
+        let (epsilon_spent, delta_spent) = dp.spent_after(job.current_round);
+        record(state, &job.fed_id, FedEvent::PrivacyBudget { round, epsilon_spent, delta_spent }).await;
+    }
+}
+
+// FedAvg, streamed chunk by chunk into `emit`; with DP each chunk is noised before it leaves
+async fn aggregate_updates<S: UpdateSource>(
+    source: &S,
+    updates: &[GradientUpdate],
+    dp: Option<DpParams>,
+    mut emit: impl FnMut(&[f64]) -> Result<(), String>,
+) -> Result<usize, String> {
+    streaming::federated_average(source, updates, streaming::DEFAULT_CHUNK_PARAMS, |chunk| {
+        if let Some(dp) = dp {
+            dp.add_noise(chunk);
+        }
+        emit(chunk)
+    })
+    .await
 }
 
 #[derive(Debug, Deserialize)]
@@ -93,7 +214,15 @@ pub struct StartFedRequest {
     pub dataset_ids: Vec<String>,
     pub rounds: u32,
     pub dp: bool,
+    #[serde(default)]
+    pub dp_budget: Option<DpBudget>, // Mechanism and (ε, δ) for DP jobs; Laplace at ε = 1 if omitted
     pub budget: u64,
+    #[serde(default)]
+    pub partition: Partition,
+    #[serde(default)]
+    pub vertical: Option<VerticalConfig>, // Required for vertical jobs
+    #[serde(default)]
+    pub participants: Vec<String>, // Roster of a horizontal job, awaited each round
 }
 
 #[derive(Debug, Serialize)]
@@ -107,7 +236,23 @@ async fn start_federated(
     Json(req): Json<StartFedRequest>,
 ) -> Result<Json<StartFedResponse>, StatusCode> {
     let fed_id = format!("fed-{}", uuid::Uuid::new_v4());
-    
+    let session = match (req.partition, req.vertical) {
+        (Partition::Horizontal, None) => None,
+        (Partition::Vertical, Some(config)) => Some(VerticalSession::new(config)?),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    if session.is_some() && !req.participants.is_empty() {
+        return Err(StatusCode::BAD_REQUEST); // Vertical parties come from the vertical config
+    }
+    let dp = match (req.dp, req.dp_budget) {
+        (false, None) => None,
+        (true, budget) => Some(budget.unwrap_or(DpBudget::DEFAULT).calibrate().map_err(|e| {
+            error!("❌ Rejected DP budget: {}", e);
+            StatusCode::BAD_REQUEST
+        })?),
+        (false, Some(_)) => return Err(StatusCode::BAD_REQUEST),
+    };
+
     let fed_job = FederatedJob {
         fed_id: fed_id.clone(),
         model_id: req.model_id,
@@ -115,12 +260,23 @@ async fn start_federated(
         rounds: req.rounds,
         current_round: 0,
         dp_enabled: req.dp,
+        dp,
         status: FedStatus::Queued,
-        participants: Vec::new(),
+        participants: session.as_ref().map(VerticalSession::parties).unwrap_or(req.participants),
         aggregated_model_cid: None,
+        partition: req.partition,
+        budget: req.budget,
+        vertical: None,
+        round_deadline: session.is_none().then(|| state.round_deadline()),
     };
 
     state.fed_jobs.write().await.insert(fed_id.clone(), fed_job);
+    state.fed_events.write().await.insert(fed_id.clone(), EventLog::new());
+    if let Some(session) = session {
+        info!("🧩 Vertical job {}: label party {}, {} feature parties",
+            fed_id, session.config.label_party, session.config.feature_parties.len());
+        state.vertical.write().await.insert(fed_id.clone(), session);
+    }
 
     // Start federated learning rounds
     // Invite compute nodes via NodeCertRegistry
@@ -137,9 +293,71 @@ async fn get_fed_status(
     Path(fed_id): Path<String>,
 ) -> Result<Json<FederatedJob>, StatusCode> {
     let jobs = state.fed_jobs.read().await;
-    let job = jobs.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut job = jobs.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?.clone();
+    job.vertical = state.vertical.read().await.get(&fed_id).map(VerticalSession::summary);
 
-    Ok(Json(job.clone()))
+    Ok(Json(job))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    #[serde(default)]
+    pub from: usize, // Id of the first event to send; reconnecting watchers resume here
+}
+
+/// GET /federated/:id/events - The job's events as server-sent events, from
+/// the start of the job (or `from`) and then live, until it completes or fails
+async fn stream_fed_events(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Query(query): Query<EventStreamQuery>,
+) -> Result<axum::response::Response, StatusCode> {
+    let published = state.fed_events.read().await.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?.subscribe();
+
+    let events = futures_util::stream::unfold(Some((query.from, published)), move |watch| {
+        let state = state.clone();
+        let fed_id = fed_id.clone();
+        async move {
+            let (mut next, mut published) = watch?;
+            loop {
+                // Marked seen before reading, so an event pushed after the read still wakes us
+                published.borrow_and_update();
+                let (events, closed) = {
+                    let logs = state.fed_events.read().await;
+                    let log = logs.get(&fed_id)?;
+                    (log.since(next).to_vec(), log.closed())
+                };
+                let mut out = String::new();
+                for event in &events {
+                    let data = serde_json::to_string(event).unwrap_or_default();
+                    out.push_str(&format!("id: {}\nevent: {}\ndata: {}\n\n", next, event.kind(), data));
+                    next += 1;
+                }
+                if let Some(status) = closed {
+                    out.push_str(&format!("event: end\ndata: {:?}\n\n", status));
+                    return Some((Ok::<_, std::io::Error>(out), None));
+                }
+                if !out.is_empty() {
+                    return Some((Ok(out), Some((next, published))));
+                }
+                published.changed().await.ok()?;
+            }
+        }
+    });
+
+    Ok(axum::response::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "text/event-stream")
+        .header(axum::http::header::CACHE_CONTROL, "no-cache")
+        .body(axum::body::Body::from_stream(events))
+        .unwrap())
+}
+
+/// Vertical jobs exchange activations and gradients, never model weights
+async fn refuse_vertical(state: &AppState, fed_id: &str) -> Result<(), StatusCode> {
+    if state.vertical.read().await.contains_key(fed_id) {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(())
 }
 
 
@@ -148,82 +366,1450 @@ async fn submit_gradient(
     Path(fed_id): Path<String>,
     Json(req): Json<serde_json::Value>,
 ) -> Result<StatusCode, StatusCode> {
+    refuse_vertical(&state, &fed_id).await?;
     let weights: Vec<f64> = req["weights"].as_array()
         .and_then(|arr| arr.iter().map(|v| v.as_f64()).collect::<Option<Vec<f64>>>())
         .unwrap_or_default();
+    let weights_cid = req["weights_cid"].as_str().map(str::to_string);
+    let param_count = req["num_params"].as_u64().unwrap_or(0) as usize;
     let sample_count = req["sample_count"].as_u64().unwrap_or(0);
+    if weights_cid.is_some() && param_count == 0 {
+        return Err(StatusCode::BAD_REQUEST); // Stored updates must declare their length
+    }
     
     let digest = format!("0x{:016x}", {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
         let mut hasher = DefaultHasher::new();
-        weights.hash(&mut hasher);
+        match &weights_cid {
+            Some(cid) => (cid, param_count).hash(&mut hasher),
+            None => weights.iter().for_each(|w| w.to_bits().hash(&mut hasher)),
+        }
         sample_count.hash(&mut hasher);
         hasher.finish()
     });
     
     let update = GradientUpdate {
-        participant: "node".to_string(), // From auth
+        participant: req["participant"].as_str().unwrap_or("node").to_string(), // From auth
         weights,
+        weights_cid,
+        param_count,
         sample_count,
         digest,
     };
     
     let mut updates = state.gradient_updates.write().await;
-    updates.entry(fed_id.clone()).or_insert_with(Vec::new).push(update);
+    let mut jobs = state.fed_jobs.write().await;
+    let job = jobs.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    transition(&state, job, FedStatus::Collecting).await?;
+    let participant = update.participant.clone();
+    let round_updates = updates.entry(fed_id.clone()).or_insert_with(Vec::new);
+    round_updates.push(update);
+    let event = FedEvent::Submission {
+        round: job.round(),
+        participant,
+        received: round_updates.len(),
+        expected: job.participants.len().max(1),
+    };
+    record(&state, &fed_id, event).await;
     
     Ok(StatusCode::OK)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct StartSecAggRequest {
+    pub participants: Vec<String>,
+    pub threshold: usize, // Survivors needed to unmask; more than half the participants
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdvertiseKeyRequest {
+    pub participant: String,
+    pub public_key: Point,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitSharesRequest {
+    pub participant: String,
+    pub shares: Vec<EncryptedShare>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitMaskedRequest {
+    pub participant: String,
+    pub masked: Vec<u64>, // secagg::encode output plus masks
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitRevealRequest {
+    pub participant: String,
+    pub reveals: Vec<RevealedShare>,
+}
+
+async fn start_secagg(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<StartSecAggRequest>,
+) -> Result<Json<SecAggSummary>, StatusCode> {
+    refuse_vertical(&state, &fed_id).await?;
+    let mut jobs = state.fed_jobs.write().await;
+    let job = jobs.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let round = SecAggRound::new(&req.participants, req.threshold)?;
+    let summary = round.summary();
+    transition(&state, job, FedStatus::Collecting).await?;
+    job.participants = req.participants;
+    state.secagg_rounds.write().await.insert(fed_id.clone(), round);
+
+    info!("🔐 Secure aggregation round for {} ({} participants, threshold {})",
+        fed_id, job.participants.len(), req.threshold);
+    Ok(Json(summary))
+}
+
+async fn get_secagg(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+) -> Result<Json<SecAggSummary>, StatusCode> {
+    let rounds = state.secagg_rounds.read().await;
+    let round = rounds.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(round.summary()))
+}
+
+async fn advertise_key(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<AdvertiseKeyRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut rounds = state.secagg_rounds.write().await;
+    let round = rounds.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    round.advertise_key(&req.participant, req.public_key)?;
+    Ok(StatusCode::OK)
+}
+
+async fn submit_shares(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<SubmitSharesRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut rounds = state.secagg_rounds.write().await;
+    let round = rounds.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    round.submit_shares(&req.participant, req.shares)?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_shares(
+    State(state): State<Arc<AppState>>,
+    Path((fed_id, participant)): Path<(String, String)>,
+) -> Result<Json<Vec<EncryptedShare>>, StatusCode> {
+    let rounds = state.secagg_rounds.read().await;
+    let round = rounds.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(round.shares_for(&participant)))
+}
+
+async fn submit_masked(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<SubmitMaskedRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut rounds = state.secagg_rounds.write().await;
+    let round = rounds.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    round.submit_masked(&req.participant, req.masked)?;
+    let summary = round.summary();
+    if let Some(job) = state.fed_jobs.read().await.get(&fed_id) {
+        let event = FedEvent::Submission {
+            round: job.round(),
+            participant: req.participant,
+            received: summary.masked_set.len(),
+            expected: summary.sharing_set.len(),
+        };
+        record(&state, &fed_id, event).await;
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn submit_reveal(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<SubmitRevealRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut rounds = state.secagg_rounds.write().await;
+    let round = rounds.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    round.submit_reveal(&req.participant, req.reveals)?;
+    Ok(StatusCode::OK)
+}
+
+/// Unmask a secure aggregation round; dropped participants are recovered
+/// from the survivors' shares and excluded from the average
+async fn aggregate_masked(
+    state: &AppState,
+    fed_id: &str,
+) -> Result<Option<Json<serde_json::Value>>, StatusCode> {
+    let mut rounds = state.secagg_rounds.write().await;
+    let Some(round) = rounds.get_mut(fed_id) else {
+        return Ok(None);
+    };
+    let mut jobs = state.fed_jobs.write().await;
+    let job = jobs.get_mut(fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    if !job.status.can_become(FedStatus::Aggregating) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let dropped = round.dropped();
+    let sum = round.aggregate()?;
+    let (mut weights, total_samples) = secagg::decode(&sum).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    if let Some(dp) = job.dp {
+        dp.add_noise(&mut weights);
+    }
+    let survivors = round.summary().masked_set.len();
+    // Unmasked: the next round starts a new one
+    rounds.remove(fed_id);
+    transition(state, job, FedStatus::Aggregating).await?;
+    complete_round(state, job, survivors, dropped.clone(), Vec::new(), None).await?;
+
+    if !dropped.is_empty() {
+        warn!("⚠️  {} recovered from {} dropped participant(s)", fed_id, dropped.len());
+    }
+    Ok(Some(Json(serde_json::json!({
+        "fed_id": fed_id,
+        "status": "aggregated",
+        "round": job.current_round,
+        "weights": weights,
+        "sample_count": total_samples,
+        "dropped": dropped,
+    }))))
+}
+
 async fn trigger_aggregation(
     State(state): State<Arc<AppState>>,
     Path(fed_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let updates = state.gradient_updates.read().await;
-    let jobs = state.fed_jobs.read().await;
-    let job = jobs.get(&fed_id)
-        .ok_or(StatusCode::NOT_FOUND)?;
-    
-    if let Some(grad_updates) = updates.get(&fed_id) {
-        if grad_updates.len() >= job.participants.len().max(1) {
-            // Aggregate using FedAvg
-            let aggregated = if job.dp_enabled {
-                secure_aggregate(grad_updates, 0.1)
-            } else {
-                federated_average(grad_updates)
-            };
-            
-            Ok(Json(serde_json::json!({
+    refuse_vertical(&state, &fed_id).await?;
+    if let Some(response) = aggregate_masked(&state, &fed_id).await? {
+        return Ok(response);
+    }
+
+    let mut updates = state.gradient_updates.write().await;
+    let (roster, deadline, dp, round) = {
+        let jobs = state.fed_jobs.read().await;
+        let job = jobs.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+        (job.participants.clone(), job.round_deadline, job.dp, job.current_round + 1)
+    };
+    let grad_updates = updates.get(&fed_id).map(Vec::as_slice).unwrap_or_default();
+    let expected = roster.len().max(1);
+    let mut absent = Vec::new();
+    if grad_updates.len() < expected {
+        if deadline.is_none_or(|deadline| state.clock.now_secs() < deadline) {
+            return Err(StatusCode::BAD_REQUEST); // Not enough updates
+        }
+        absent = quorum::absent(&roster, grad_updates.iter().map(|u| u.participant.as_str()));
+        let required = state.quorum.required(expected);
+        if grad_updates.len() < required {
+            let received = grad_updates.len();
+            updates.remove(&fed_id);
+            let event = FedEvent::QuorumMissed { round, received, required, absent: absent.clone() };
+            record(&state, &fed_id, event).await;
+            set_status(&state, &fed_id, FedStatus::Failed).await?;
+            warn!("⚠️  {} round {} missed its quorum: {} of {} updates", fed_id, round, received, required);
+            return Ok(Json(serde_json::json!({
                 "fed_id": fed_id,
-                "status": "aggregated",
-                "round": job.current_round + 1,
-            })))
-        } else {
-            Err(StatusCode::BAD_REQUEST) // Not enough updates
+                "status": "failed",
+                "round": round,
+                "received": received,
+                "required": required,
+                "absent": absent,
+            })));
+        }
+        warn!("⚠️  {} round {} aggregating without {} absent participant(s)", fed_id, round, absent.len());
+    }
+    streaming::num_params(grad_updates).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+    set_status(&state, &fed_id, FedStatus::Aggregating).await?;
+
+    // Small inline rounds: the result goes back in the response
+    if grad_updates.iter().all(|u| u.weights_cid.is_none()) {
+        let mut weights = Vec::new();
+        let aggregated = aggregate_updates(&*state.svdb, grad_updates, dp, |chunk| {
+            weights.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await;
+        if aggregated.is_err() {
+            set_status(&state, &fed_id, FedStatus::Collecting).await?;
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let participants = grad_updates.len();
+        updates.remove(&fed_id);
+        if let Some(job) = state.fed_jobs.write().await.get_mut(&fed_id) {
+            complete_round(&state, job, participants, Vec::new(), absent.clone(), None).await?;
+        }
+        return Ok(Json(serde_json::json!({
+            "fed_id": fed_id,
+            "status": "aggregated",
+            "round": round,
+            "weights": weights,
+            "absent": absent,
+        })));
+    }
+
+    // Stored updates: stream the result to disk, then to SVDB
+    let stored = async {
+        let path = std::env::temp_dir().join(format!("{}-round-{}.weights", fed_id, round));
+        let written = {
+            use std::io::Write;
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?);
+            let written = aggregate_updates(&*state.svdb, grad_updates, dp, |chunk| {
+                chunk.iter().try_for_each(|w| file.write_all(&w.to_le_bytes())).map_err(|e| e.to_string())
+            })
+            .await;
+            file.flush().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            written
+        };
+        let uploaded = match written {
+            Ok(_) => state.svdb.upload(&path).await,
+            Err(e) => Err(e),
+        };
+        let _ = std::fs::remove_file(&path);
+        uploaded.map_err(|e| {
+            error!("❌ Streaming aggregation for {} failed: {}", fed_id, e);
+            StatusCode::BAD_GATEWAY
+        })
+    };
+    let cid = match stored.await {
+        Ok(cid) => cid,
+        Err(status) => {
+            // The updates are kept, so the round can be aggregated again
+            set_status(&state, &fed_id, FedStatus::Collecting).await?;
+            return Err(status);
+        }
+    };
+
+    let participants = grad_updates.len();
+    updates.remove(&fed_id);
+    if let Some(job) = state.fed_jobs.write().await.get_mut(&fed_id) {
+        job.aggregated_model_cid = Some(cid.clone());
+        complete_round(&state, job, participants, Vec::new(), absent.clone(), Some(cid.clone())).await?;
+    }
+    info!("🧮 Aggregated {} updates for {} into {}", participants, fed_id, cid);
+    Ok(Json(serde_json::json!({
+        "fed_id": fed_id,
+        "status": "aggregated",
+        "round": round,
+        "aggregated_model_cid": cid,
+        "absent": absent,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmitBlindedRequest {
+    pub party: String,
+    pub values: Vec<Point>, // k·H(x) per row, see psi
+    #[serde(default)]
+    pub feature_count: Option<usize>, // Required of feature parties
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmitRaisedRequest {
+    pub party: String,
+    pub owner: String,
+    pub values: Vec<Point>,
+}
+
+/// Activations from a feature party or dL/dz from the label party. Nothing
+/// but the tensor is accepted, so features cannot ride along.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmitTensorRequest {
+    pub party: String,
+    pub round: u32,
+    pub values: Vec<Vec<f64>>,
+}
+
+async fn submit_blinded(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<SubmitBlindedRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut sessions = state.vertical.write().await;
+    let session = sessions.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    session.submit_blinded(&req.party, req.values, req.feature_count)?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_psi_tasks(
+    State(state): State<Arc<AppState>>,
+    Path((fed_id, party)): Path<(String, String)>,
+) -> Result<Json<Vec<PsiTask>>, StatusCode> {
+    let sessions = state.vertical.read().await;
+    let session = sessions.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(session.psi_tasks(&party)?))
+}
+
+async fn submit_raised(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<SubmitRaisedRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut sessions = state.vertical.write().await;
+    let session = sessions.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(size) = session.submit_raised(&req.party, &req.owner, req.values)? {
+        if let Some(job) = state.fed_jobs.write().await.get_mut(&fed_id) {
+            transition(&state, job, FedStatus::Collecting).await?;
         }
-    } else {
-        Err(StatusCode::NOT_FOUND)
+        info!("🔗 {} aligned on {} shared samples", fed_id, size);
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn get_alignment(
+    State(state): State<Arc<AppState>>,
+    Path((fed_id, party)): Path<(String, String)>,
+) -> Result<Json<AlignmentView>, StatusCode> {
+    let sessions = state.vertical.read().await;
+    let session = sessions.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(session.alignment_for(&party)?))
+}
+
+async fn start_vertical_round(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+) -> Result<Json<RoundView>, StatusCode> {
+    let mut sessions = state.vertical.write().await;
+    let session = sessions.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    let jobs = state.fed_jobs.read().await;
+    let job = jobs.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    if job.current_round >= job.rounds {
+        return Err(StatusCode::CONFLICT);
     }
+    Ok(Json(session.start_round()?))
+}
+
+async fn get_vertical_round(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+) -> Result<Json<RoundView>, StatusCode> {
+    let sessions = state.vertical.read().await;
+    let session = sessions.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(session.current_round().ok_or(StatusCode::NOT_FOUND)?))
+}
+
+async fn submit_activations(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(req): Json<SubmitTensorRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut sessions = state.vertical.write().await;
+    let session = sessions.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    session.submit_activations(&req.party, RoundTensor { round: req.round, values: req.values })?;
+    Ok(StatusCode::OK)
+}
+
+async fn get_forward(
+    State(state): State<Arc<AppState>>,
+    Path((fed_id, party)): Path<(String, String)>,
+) -> Result<Json<RoundTensor>, StatusCode> {
+    let mut sessions = state.vertical.write().await;
+    let session = sessions.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(session.forward(&party)?))
+}
+
+async fn submit_vertical_gradients(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+    Json(mut req): Json<SubmitTensorRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut sessions = state.vertical.write().await;
+    let session = sessions.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut jobs = state.fed_jobs.write().await;
+    let job = jobs.get_mut(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    // Noise goes on before the gradient is stored, so no feature party ever sees it clean
+    if let Some(dp) = job.dp {
+        req.values.iter_mut().for_each(|row| dp.add_noise(row));
+    }
+    session.submit_gradients(&req.party, RoundTensor { round: req.round, values: req.values })?;
+
+    let round = job.round();
+    job.current_round += 1;
+    record_privacy_spent(&state, job, round).await;
+    if job.current_round >= job.rounds {
+        transition(&state, job, FedStatus::Completed).await?;
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn get_backward(
+    State(state): State<Arc<AppState>>,
+    Path((fed_id, party)): Path<(String, String)>,
+) -> Result<Json<RoundTensor>, StatusCode> {
+    let sessions = state.vertical.read().await;
+    let session = sessions.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(session.backward(&party)?))
+}
+
+async fn get_compensation(
+    State(state): State<Arc<AppState>>,
+    Path(fed_id): Path<String>,
+) -> Result<Json<Compensation>, StatusCode> {
+    let sessions = state.vertical.read().await;
+    let session = sessions.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?;
+    let budget = state.fed_jobs.read().await.get(&fed_id).ok_or(StatusCode::NOT_FOUND)?.budget;
+    Ok(Json(session.compensation(budget)))
 }
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
         fed_jobs: Arc::new(RwLock::new(HashMap::new())),
         gradient_updates: Arc::new(RwLock::new(HashMap::new())),
+        secagg_rounds: Arc::new(RwLock::new(HashMap::new())),
+        vertical: Arc::new(RwLock::new(HashMap::new())),
+        fed_events: Arc::new(RwLock::new(HashMap::new())),
+        svdb: Arc::new(SvdbUpdates::new(
+            std::env::var("SVDB_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        )),
+        quorum: QuorumConfig::from_env(),
+        clock: artha_clock::system_clock(),
     });
 
-    let app = Router::new()
+    let app = app(state).layer(axum::middleware::from_fn(artha_log::log_requests));
+
+    info!("🚀 AI Federation Service starting on :8087");
+    
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:8087").await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
         .route("/federated/start", post(start_federated))
         .route("/federated/:id/status", get(get_fed_status))
+        .route("/federated/:id/events", get(stream_fed_events))
         .route("/federated/:id/submit-gradient", post(submit_gradient))
         .route("/federated/:id/aggregate", post(trigger_aggregation))
+        .route("/federated/:id/secagg", get(get_secagg))
+        .route("/federated/:id/secagg/start", post(start_secagg))
+        .route("/federated/:id/secagg/keys", post(advertise_key))
+        .route("/federated/:id/secagg/shares", post(submit_shares))
+        .route("/federated/:id/secagg/shares/:participant", get(get_shares))
+        .route("/federated/:id/secagg/masked", post(submit_masked))
+        .route("/federated/:id/secagg/reveal", post(submit_reveal))
+        .route("/federated/:id/vertical/psi/blinded", post(submit_blinded))
+        .route("/federated/:id/vertical/psi/tasks/:party", get(get_psi_tasks))
+        .route("/federated/:id/vertical/psi/raised", post(submit_raised))
+        .route("/federated/:id/vertical/alignment/:party", get(get_alignment))
+        .route("/federated/:id/vertical/rounds", post(start_vertical_round))
+        .route("/federated/:id/vertical/rounds/current", get(get_vertical_round))
+        .route("/federated/:id/vertical/activations", post(submit_activations))
+        .route("/federated/:id/vertical/forward/:party", get(get_forward))
+        .route("/federated/:id/vertical/gradients", post(submit_vertical_gradients))
+        .route("/federated/:id/vertical/backward/:party", get(get_backward))
+        .route("/federated/:id/vertical/compensation", get(get_compensation))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
-
-    println!("🚀 AI Federation Service starting on :8087");
-    
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8087").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+        .with_state(state)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use artha_clock::ManualClock;
+    use psi::PsiParty;
+    use secagg::{Participant, RosterEntry, ShareKind};
+    use std::collections::{BTreeMap, HashSet};
+    use vertical::{LabelParty, VerticalParty};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::future::Future;
+
+    /// Tracks live heap bytes per thread, so a test on the current-thread
+    /// runtime can measure its own peak while other tests run alongside
+    struct CountingAlloc;
+
+    thread_local! {
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+        static PEAK: Cell<isize> = const { Cell::new(0) };
+    }
+
+    fn track(delta: isize) {
+        let _ = LIVE.try_with(|live| {
+            live.set(live.get() + delta);
+            let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+        });
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                track(layout.size() as isize);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            track(-(layout.size() as isize));
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+    /// Heap growth on this thread while `f` runs, at its highest
+    async fn peak_heap_growth<T>(f: impl Future<Output = T>) -> (T, usize) {
+        let baseline = LIVE.with(Cell::get);
+        PEAK.with(|peak| peak.set(baseline));
+        let result = f.await;
+        (result, (PEAK.with(Cell::get) - baseline).max(0) as usize)
+    }
+
+    /// Participant `p`'s weight `i` is `p + 1 + (i % 1000) / 1000`, generated
+    /// on demand so no full update ever exists
+    struct SyntheticUpdates;
+
+    impl UpdateSource for SyntheticUpdates {
+        async fn read_chunk(&self, update: &GradientUpdate, offset: usize, len: usize) -> Result<Vec<f64>, String> {
+            let p: f64 = update.participant.trim_start_matches("node-").parse().unwrap();
+            Ok((offset..offset + len).map(|i| p + 1.0 + (i % 1000) as f64 / 1000.0).collect())
+        }
+    }
+
+    fn stored_update(participant: &str, cid: &str, param_count: usize, sample_count: u64) -> GradientUpdate {
+        GradientUpdate {
+            participant: participant.to_string(),
+            weights: Vec::new(),
+            weights_cid: Some(cid.to_string()),
+            param_count,
+            sample_count,
+            digest: String::new(),
+        }
+    }
+
+    fn test_state() -> Arc<AppState> {
+        test_state_at(&ManualClock::new(1_000))
+    }
+
+    fn test_state_at(clock: &ManualClock) -> Arc<AppState> {
+        Arc::new(AppState {
+            fed_jobs: Arc::new(RwLock::new(HashMap::new())),
+            gradient_updates: Arc::new(RwLock::new(HashMap::new())),
+            secagg_rounds: Arc::new(RwLock::new(HashMap::new())),
+            vertical: Arc::new(RwLock::new(HashMap::new())),
+            fed_events: Arc::new(RwLock::new(HashMap::new())),
+            svdb: Arc::new(SvdbUpdates::new("http://127.0.0.1:9".to_string())),
+            quorum: QuorumConfig { round_timeout_secs: 60, min_quorum: 0.5 },
+            clock: clock.shared(),
+        })
+    }
+
+    async fn start_job(state: &Arc<AppState>) -> String {
+        let Json(resp) = start_federated(
+            State(state.clone()),
+            Json(StartFedRequest {
+                model_id: "model-1".to_string(),
+                dataset_ids: vec!["ds-1".to_string()],
+                rounds: 1,
+                dp: false,
+                dp_budget: None,
+                budget: 100,
+                partition: Partition::Horizontal,
+                vertical: None,
+                participants: Vec::new(),
+            }),
+        )
+        .await
+        .unwrap();
+        resp.fed_id
+    }
+
+    /// Runs a round with three participants; those listed in `dropouts` stop
+    /// after sharing their secrets, once the others have masked against them
+    async fn run_round(
+        state: &Arc<AppState>,
+        fed_id: &str,
+        updates: &[(Vec<f64>, u64)],
+        dropouts: &[&str],
+    ) -> (Vec<Participant>, Vec<RosterEntry>) {
+        let ids: Vec<String> = (0..updates.len()).map(|i| format!("node-{}", i)).collect();
+        let Json(summary) = start_secagg(
+            State(state.clone()),
+            Path(fed_id.to_string()),
+            Json(StartSecAggRequest { participants: ids.clone(), threshold: 2 }),
+        )
+        .await
+        .unwrap();
+        let participants: Vec<Participant> = summary
+            .roster
+            .iter()
+            .map(|e| Participant::new(&e.participant, e.index))
+            .collect();
+
+        for p in &participants {
+            let req = AdvertiseKeyRequest { participant: p.id.clone(), public_key: p.public_key };
+            advertise_key(State(state.clone()), Path(fed_id.to_string()), Json(req)).await.unwrap();
+        }
+        let Json(summary) = get_secagg(State(state.clone()), Path(fed_id.to_string())).await.unwrap();
+        for p in &participants {
+            let req = SubmitSharesRequest { participant: p.id.clone(), shares: p.share_secrets(&summary.roster, 2) };
+            submit_shares(State(state.clone()), Path(fed_id.to_string()), Json(req)).await.unwrap();
+        }
+
+        let summary = state.secagg_rounds.read().await[fed_id].summary();
+        let sharing: Vec<RosterEntry> = summary
+            .roster
+            .iter()
+            .filter(|e| summary.sharing_set.contains(&e.participant))
+            .cloned()
+            .collect();
+        for (p, (weights, samples)) in participants.iter().zip(updates) {
+            if dropouts.contains(&p.id.as_str()) {
+                continue;
+            }
+            let masked = p.mask(&secagg::encode(weights, *samples), &sharing);
+            let req = SubmitMaskedRequest { participant: p.id.clone(), masked };
+            submit_masked(State(state.clone()), Path(fed_id.to_string()), Json(req)).await.unwrap();
+        }
+        (participants, summary.roster)
+    }
+
+    async fn reveal_all(state: &Arc<AppState>, fed_id: &str, participants: &[Participant], roster: &[RosterEntry]) {
+        let summary = state.secagg_rounds.read().await[fed_id].summary();
+        // Dropped is only fixed once unmasking starts; survivors are the masked set
+        let dropped: Vec<String> = summary
+            .sharing_set
+            .iter()
+            .filter(|p| !summary.masked_set.contains(p))
+            .cloned()
+            .collect();
+        for p in participants.iter().filter(|p| summary.masked_set.contains(&p.id)) {
+            let Json(received) = get_shares(State(state.clone()), Path((fed_id.to_string(), p.id.clone())))
+                .await
+                .unwrap();
+            let reveals = p.reveal(&received, roster, &summary.masked_set, &dropped);
+            let req = SubmitRevealRequest { participant: p.id.clone(), reveals };
+            submit_reveal(State(state.clone()), Path(fed_id.to_string()), Json(req)).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_shamir_threshold_reconstruction() {
+        let shares = secagg::split(123_456_789, 2, &[1, 2, 3]);
+        assert_eq!(secagg::reconstruct(&shares[..2]), 123_456_789);
+        assert_eq!(secagg::reconstruct(&shares[1..]), 123_456_789);
+        assert_eq!(secagg::reconstruct(&[shares[0], shares[2]]), 123_456_789);
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let (weights, samples) = secagg::decode(&secagg::encode(&[0.5, -1.25, 0.0], 10)).unwrap();
+        assert_eq!(samples, 10);
+        assert_eq!(weights, vec![0.5, -1.25, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn test_secagg_recovers_dropped_participant() {
+        let state = test_state();
+        let fed_id = start_job(&state).await;
+        let updates = vec![
+            (vec![0.25, -0.5, 1.0], 10),
+            (vec![0.75, 0.5, -2.0], 30),
+            (vec![9.0, 9.0, 9.0], 60),
+        ];
+
+        let (participants, roster) = run_round(&state, &fed_id, &updates, &["node-2"]).await;
+        reveal_all(&state, &fed_id, &participants, &roster).await;
+
+        let Json(resp) = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(resp["dropped"], serde_json::json!(["node-2"]));
+        assert_eq!(resp["sample_count"], 40);
+
+        // Matches FedAvg over the honest plaintext updates of the survivors
+        let honest: Vec<GradientUpdate> = updates[..2]
+            .iter()
+            .map(|(weights, samples)| GradientUpdate {
+                participant: "node".to_string(),
+                weights: weights.clone(),
+                weights_cid: None,
+                param_count: 0,
+                sample_count: *samples,
+                digest: String::new(),
+            })
+            .collect();
+        let mut expected = Vec::new();
+        aggregate_updates(&*state.svdb, &honest, None, |chunk| {
+            expected.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await
+        .unwrap();
+        let weights: Vec<f64> = serde_json::from_value(resp["weights"].clone()).unwrap();
+        for (w, e) in weights.iter().zip(&expected) {
+            assert!((w - e).abs() < 1e-6, "{} vs {}", w, e);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secagg_masked_inputs_hide_plaintext() {
+        let state = test_state();
+        let fed_id = start_job(&state).await;
+        let updates = vec![(vec![1.0], 1), (vec![2.0], 1), (vec![3.0], 1)];
+        run_round(&state, &fed_id, &updates, &[]).await;
+
+        let rounds = state.secagg_rounds.read().await;
+        let summary = rounds[&fed_id].summary();
+        assert_eq!(summary.masked_set.len(), 3);
+        assert_eq!(summary.phase, secagg::SecAggPhase::MaskedInput);
+        drop(rounds);
+
+        // Aggregation must wait for survivors to reveal their shares
+        let result = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await;
+        assert_eq!(result.unwrap_err(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_secagg_rejects_both_shares_of_one_participant() {
+        let state = test_state();
+        let fed_id = start_job(&state).await;
+        let updates = vec![(vec![1.0], 1), (vec![2.0], 1), (vec![3.0], 1)];
+        let (participants, roster) = run_round(&state, &fed_id, &updates, &["node-2"]).await;
+
+        // A survivor asked for the self-mask of the dropped participant would
+        // let the server unmask its input if it arrived late
+        let Json(received) = get_shares(State(state.clone()), Path((fed_id.clone(), "node-0".to_string())))
+            .await
+            .unwrap();
+        let all: Vec<String> = roster.iter().map(|e| e.participant.clone()).collect();
+        let mut reveals = participants[0].reveal(&received, &roster, &all, &[]);
+        assert!(reveals.iter().all(|r| r.kind == ShareKind::SelfMask));
+        let req = SubmitRevealRequest { participant: "node-0".to_string(), reveals: reveals.clone() };
+        let result = submit_reveal(State(state.clone()), Path(fed_id.clone()), Json(req)).await;
+        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+
+        // A dropped participant takes no part in unmasking
+        reveals.retain(|r| r.owner != "node-2");
+        let req = SubmitRevealRequest { participant: "node-2".to_string(), reveals };
+        let result = submit_reveal(State(state.clone()), Path(fed_id.clone()), Json(req)).await;
+        assert_eq!(result.unwrap_err(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_secagg_rejects_small_order_keys() {
+        let state = test_state();
+        let fed_id = start_job(&state).await;
+        let participants = vec!["node-0".to_string(), "node-1".to_string(), "node-2".to_string()];
+        let Json(summary) = start_secagg(State(state.clone()), Path(fed_id.clone()), Json(StartSecAggRequest { participants, threshold: 2 }))
+            .await
+            .unwrap();
+        assert_eq!(summary.phase, secagg::SecAggPhase::AdvertiseKeys);
+
+        // DH against a small-order point yields a seed anyone can compute
+        let mut one = [0; 32];
+        one[0] = 1;
+        for public_key in [Point([0; 32]), Point(one)] {
+            let req = AdvertiseKeyRequest { participant: "node-0".to_string(), public_key };
+            let result = advertise_key(State(state.clone()), Path(fed_id.clone()), Json(req)).await;
+            assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+        }
+
+        // Keys travel as 32 hex-encoded bytes
+        let key = Participant::new("node-0", 1).public_key;
+        let req: AdvertiseKeyRequest =
+            serde_json::from_value(serde_json::json!({ "participant": "node-0", "public_key": hex::encode(key.0) })).unwrap();
+        assert_eq!(req.public_key, key);
+        assert_eq!(advertise_key(State(state.clone()), Path(fed_id.clone()), Json(req)).await, Ok(StatusCode::OK));
+        let short = serde_json::json!({ "participant": "node-1", "public_key": "abcd" });
+        assert!(serde_json::from_value::<AdvertiseKeyRequest>(short).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_follows_the_rounds() {
+        let state = test_state();
+        let Json(resp) = start_federated(
+            State(state.clone()),
+            Json(StartFedRequest {
+                model_id: "model-1".to_string(),
+                dataset_ids: vec!["ds-1".to_string()],
+                rounds: 2,
+                dp: true,
+                dp_budget: None,
+                budget: 100,
+                partition: Partition::Horizontal,
+                vertical: None,
+                participants: Vec::new(),
+            }),
+        )
+        .await
+        .unwrap();
+        let fed_id = resp.fed_id;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let router = app(state.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let missing = reqwest::get(format!("{}/federated/fed-missing/events", base)).await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let updates = vec![(vec![1.0], 1), (vec![2.0], 1), (vec![3.0], 1)];
+        let (participants, roster) = run_round(&state, &fed_id, &updates, &[]).await;
+        reveal_all(&state, &fed_id, &participants, &roster).await;
+        let Json(resp) = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(resp["round"], 1);
+
+        // Subscribe between the rounds: the first is replayed, the second arrives live
+        let url = format!("{}/federated/{}/events", base, fed_id);
+        let watcher = tokio::spawn(async move { reqwest::get(url).await.unwrap().text().await.unwrap() });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let (participants, roster) = run_round(&state, &fed_id, &updates, &[]).await;
+        reveal_all(&state, &fed_id, &participants, &roster).await;
+        let Json(resp) = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(resp["round"], 2);
+        let body = tokio::time::timeout(std::time::Duration::from_secs(5), watcher).await.unwrap().unwrap();
+
+        let mut seen = Vec::new();
+        for (id, message) in body.split_terminator("\n\n").enumerate() {
+            let field = |name: &str| message.lines().find_map(|l| l.strip_prefix(name)).unwrap_or_default().to_string();
+            let (kind, data) = (field("event: "), field("data: "));
+            if kind == "end" {
+                seen.push(format!("end {}", data));
+                continue;
+            }
+            assert_eq!(field("id: "), id.to_string());
+            let e: serde_json::Value = serde_json::from_str(&data).unwrap();
+            seen.push(match kind.as_str() {
+                "status" => format!("{} {}->{}", e["round"], e["from"].as_str().unwrap(), e["to"].as_str().unwrap()),
+                "submission" => format!("{} {} {}/{}", e["round"], e["participant"].as_str().unwrap(), e["received"], e["expected"]),
+                "aggregated" => format!("{} aggregated {}", e["round"], e["participants"]),
+                "privacy_budget" => format!("{} spent ε={} δ={}", e["round"], e["epsilon_spent"], e["delta_spent"]),
+                other => panic!("unexpected event {}", other),
+            });
+        }
+        let round = |n: u32, next: &str| {
+            vec![
+                format!("{} node-0 1/3", n),
+                format!("{} node-1 2/3", n),
+                format!("{} node-2 3/3", n),
+                format!("{} Collecting->Aggregating", n),
+                format!("{} aggregated 3", n),
+                format!("{} spent ε={:.1} δ=0.0", n, n as f64),
+                next.to_string(),
+            ]
+        };
+        let mut expected = vec!["1 Queued->Collecting".to_string()];
+        expected.extend(round(1, "2 Aggregating->Collecting"));
+        expected.extend(round(2, "2 Aggregating->Completed"));
+        expected.push("end Completed".to_string());
+        assert_eq!(seen, expected);
+
+        // Finished jobs take no more rounds
+        let result = start_secagg(
+            State(state.clone()),
+            Path(fed_id.clone()),
+            Json(StartSecAggRequest { participants: roster.iter().map(|e| e.participant.clone()).collect(), threshold: 2 }),
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_secagg_round_requires_majority_threshold() {
+        let ids: Vec<String> = (0..4).map(|i| format!("node-{}", i)).collect();
+        assert!(SecAggRound::new(&ids, 2).is_err());
+        assert!(SecAggRound::new(&ids, 5).is_err());
+        assert!(SecAggRound::new(&ids, 3).is_ok());
+        assert!(SecAggRound::new(&["a".to_string(), "a".to_string()], 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_fedavg_memory_bounded_by_chunks() {
+        // 8 participants x 2M params: 128 MiB if materialized, 16 MiB per model
+        let (participants, num_params, chunk) = (8, 2_000_000, 16 * 1024);
+        let updates: Vec<GradientUpdate> = (0..participants)
+            .map(|p| stored_update(&format!("node-{}", p), "synthetic", num_params, 10 * (p as u64 + 1)))
+            .collect();
+        let total: f64 = (1..=participants).map(|p| 10.0 * p as f64).sum();
+        let base: f64 = (1..=participants).map(|p| 10.0 * p as f64 * p as f64).sum::<f64>() / total;
+
+        let mut emitted = 0;
+        let mut max_error: f64 = 0.0;
+        let (result, growth) = peak_heap_growth(streaming::federated_average(&SyntheticUpdates, &updates, chunk, |values| {
+            for (j, w) in values.iter().enumerate() {
+                let expected = base + ((emitted + j) % 1000) as f64 / 1000.0;
+                max_error = max_error.max((w - expected).abs());
+            }
+            emitted += values.len();
+            Ok(())
+        }))
+        .await;
+
+        assert_eq!(result, Ok(num_params));
+        assert_eq!(emitted, num_params);
+        assert!(max_error < 1e-9, "max error {}", max_error);
+        // One chunk per participant plus the running sum, with room for bookkeeping
+        let bound = (participants + 1) * chunk * 8 + 64 * 1024;
+        assert!(growth <= bound, "peak heap growth {} exceeds {}", growth, bound);
+        assert!(growth < num_params * 8 / 4);
+
+        // Mismatched updates are refused before anything is read
+        let mut uneven = updates.clone();
+        uneven[3].param_count -= 1;
+        let result = streaming::federated_average(&SyntheticUpdates, &uneven, chunk, |_| Ok(())).await;
+        assert!(result.unwrap_err().contains("node-3"));
+    }
+
+    #[tokio::test]
+    async fn test_stored_updates_aggregate_by_range_into_svdb() {
+        // SVDB serving byte ranges of two stored updates and recording the upload
+        let objects: HashMap<String, Vec<u8>> = [("QmUpdateA", [1.0, 2.0, 3.0, 4.0, 5.0]), ("QmUpdateB", [3.0, 6.0, 9.0, 12.0, 15.0])]
+            .into_iter()
+            .map(|(cid, weights)| (cid.to_string(), weights.iter().flat_map(|w: &f64| w.to_le_bytes()).collect()))
+            .collect();
+        let ranges: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let uploaded: Arc<std::sync::Mutex<Vec<u8>>> = Arc::default();
+        let (seen, stored) = (ranges.clone(), uploaded.clone());
+        let svdb = Router::new()
+            .route("/svdb/download/:cid", get(move |Path(cid): Path<String>, headers: axum::http::HeaderMap| {
+                let (objects, seen) = (objects.clone(), seen.clone());
+                async move {
+                    let range = headers["range"].to_str().unwrap().to_string();
+                    let (start, end) = range.trim_start_matches("bytes=").split_once('-').unwrap();
+                    let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+                    seen.lock().unwrap().push(format!("{} {}", cid, range));
+                    (StatusCode::PARTIAL_CONTENT, objects[&cid][start..=end].to_vec())
+                }
+            }))
+            .route("/svdb/upload", post(move |body: axum::body::Bytes| {
+                let stored = stored.clone();
+                async move {
+                    *stored.lock().unwrap() = body.to_vec();
+                    Json(serde_json::json!({ "cid": "QmAggregate" }))
+                }
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let svdb_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, svdb).await.unwrap() });
+
+        let mut state = test_state();
+        Arc::get_mut(&mut state).unwrap().svdb = Arc::new(SvdbUpdates::new(svdb_url));
+        let fed_id = start_job(&state).await;
+        for (cid, samples) in [("artha://QmUpdateA", 1), ("artha://QmUpdateB", 3)] {
+            let req = serde_json::json!({ "weights_cid": cid, "num_params": 5, "sample_count": samples });
+            submit_gradient(State(state.clone()), Path(fed_id.clone()), Json(req)).await.unwrap();
+        }
+        let missing_len = serde_json::json!({ "weights_cid": "artha://QmUpdateC", "sample_count": 1 });
+        let result = submit_gradient(State(state.clone()), Path(fed_id.clone()), Json(missing_len)).await;
+        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+
+        let Json(resp) = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(resp["aggregated_model_cid"], "artha://QmAggregate");
+        assert!(resp.get("weights").is_none());
+        assert_eq!(state.fed_jobs.read().await[&fed_id].aggregated_model_cid.as_deref(), Some("artha://QmAggregate"));
+
+        // (1 * a + 3 * b) / 4 = 2.5 * a, uploaded as little-endian f64
+        let bytes = uploaded.lock().unwrap().clone();
+        let weights: Vec<f64> = bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(weights, vec![2.5, 5.0, 7.5, 10.0, 12.5]);
+        let mut seen = ranges.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, vec!["QmUpdateA bytes=0-39", "QmUpdateB bytes=0-39"]);
+    }
+
+    #[tokio::test]
+    async fn test_round_aggregates_a_quorum_after_its_deadline() {
+        let clock = ManualClock::new(1_000);
+        let state = test_state_at(&clock);
+        let Json(resp) = start_federated(
+            State(state.clone()),
+            Json(StartFedRequest {
+                model_id: "model-1".to_string(),
+                dataset_ids: vec!["ds-1".to_string()],
+                rounds: 2,
+                dp: false,
+                dp_budget: None,
+                budget: 100,
+                partition: Partition::Horizontal,
+                vertical: None,
+                participants: vec!["node-0".to_string(), "node-1".to_string(), "node-2".to_string()],
+            }),
+        )
+        .await
+        .unwrap();
+        let fed_id = resp.fed_id;
+        let submit = |participant: &str, weight: f64| {
+            let req = serde_json::json!({ "participant": participant, "weights": [weight], "sample_count": 1 });
+            submit_gradient(State(state.clone()), Path(fed_id.clone()), Json(req))
+        };
+        submit("node-0", 1.0).await.unwrap();
+        submit("node-1", 3.0).await.unwrap();
+
+        // node-2 is still awaited until the deadline
+        let early = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await;
+        assert_eq!(early.unwrap_err(), StatusCode::BAD_REQUEST);
+        clock.advance_secs(60);
+        let Json(resp) = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(resp["round"], 1);
+        assert_eq!(resp["weights"], serde_json::json!([2.0]));
+        assert_eq!(resp["absent"], serde_json::json!(["node-2"]));
+        {
+            let jobs = state.fed_jobs.read().await;
+            assert_eq!(jobs[&fed_id].status, FedStatus::Collecting);
+            assert_eq!(jobs[&fed_id].round_deadline, Some(1_120)); // The next round gets its own deadline
+        }
+
+        // One of three is short of the quorum of two: the round fails
+        submit("node-1", 5.0).await.unwrap();
+        clock.advance_secs(60);
+        let Json(resp) = trigger_aggregation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(resp["status"], "failed");
+        assert_eq!(resp["required"], 2);
+        assert_eq!(resp["absent"], serde_json::json!(["node-0", "node-2"]));
+        assert_eq!(state.fed_jobs.read().await[&fed_id].status, FedStatus::Failed);
+
+        let logs = state.fed_events.read().await;
+        let log = &logs[&fed_id];
+        assert_eq!(log.closed(), Some(FedStatus::Failed));
+        assert!(log.since(0).iter().any(|e| matches!(e,
+            FedEvent::Aggregated { round: 1, participants: 2, absent, .. } if absent == &["node-2"])));
+        assert!(log.since(0).iter().any(|e| matches!(e,
+            FedEvent::QuorumMissed { round: 2, received: 1, required: 2, .. })));
+    }
+
+    async fn start_vertical_job(state: &Arc<AppState>, label: &str, features: &[&str], batch_size: Option<usize>, rounds: u32) -> String {
+        let Json(resp) = start_federated(
+            State(state.clone()),
+            Json(StartFedRequest {
+                model_id: "model-1".to_string(),
+                dataset_ids: vec!["ds-1".to_string()],
+                rounds,
+                dp: false,
+                dp_budget: None,
+                budget: 900,
+                partition: Partition::Vertical,
+                vertical: Some(VerticalConfig {
+                    label_party: label.to_string(),
+                    feature_parties: features.iter().map(|f| f.to_string()).collect(),
+                    embedding_dim: 1,
+                    batch_size,
+                }),
+                participants: Vec::new(),
+            }),
+        )
+        .await
+        .unwrap();
+        resp.fed_id
+    }
+
+    fn customers(range: impl Iterator<Item = usize>) -> Vec<String> {
+        range.map(|i| format!("cust-{:03}", i)).collect()
+    }
+
+    /// Runs PSI through the coordinator; returns everything relayed, tagged
+    /// with the list it belongs to
+    async fn align(state: &Arc<AppState>, fed_id: &str, parties: &[(&PsiParty, Option<usize>)]) -> Vec<(String, Point)> {
+        let mut transcript = Vec::new();
+        for (party, feature_count) in parties {
+            let values = party.blind();
+            transcript.extend(values.iter().map(|v| (party.id.clone(), *v)));
+            let req = SubmitBlindedRequest { party: party.id.clone(), values, feature_count: *feature_count };
+            submit_blinded(State(state.clone()), Path(fed_id.to_string()), Json(req)).await.unwrap();
+        }
+        loop {
+            let mut progressed = false;
+            for (party, _) in parties {
+                let Json(tasks) = get_psi_tasks(State(state.clone()), Path((fed_id.to_string(), party.id.clone())))
+                    .await
+                    .unwrap();
+                for task in tasks {
+                    let values = party.raise(&task);
+                    transcript.extend(values.iter().map(|v| (task.owner.clone(), *v)));
+                    let req = SubmitRaisedRequest { party: party.id.clone(), owner: task.owner, values };
+                    submit_raised(State(state.clone()), Path(fed_id.to_string()), Json(req)).await.unwrap();
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                return transcript;
+            }
+        }
+    }
+
+    async fn alignment_of(state: &Arc<AppState>, fed_id: &str, party: &str) -> AlignmentView {
+        let Json(view) = get_alignment(State(state.clone()), Path((fed_id.to_string(), party.to_string()))).await.unwrap();
+        view
+    }
+
+    #[tokio::test]
+    async fn test_psi_aligns_shared_entities_without_exposing_the_rest() {
+        let state = test_state();
+        let fed_id = start_vertical_job(&state, "bank", &["retailer"], None, 1).await;
+        let bank = PsiParty::new("bank", customers(0..10));
+        let retailer = PsiParty::new("retailer", customers((4..14).rev()));
+        let transcript = align(&state, &fed_id, &[(&bank, None), (&retailer, Some(4))]).await;
+
+        // Every shared position holds the same customer on both sides
+        let (bank_view, retailer_view) = (alignment_of(&state, &fed_id, "bank").await, alignment_of(&state, &fed_id, "retailer").await);
+        assert_eq!((bank_view.size, retailer_view.size), (6, 6));
+        let members = |party: &PsiParty, view: &AlignmentView| -> BTreeMap<u32, String> {
+            party.identifiers.iter().zip(&view.rows).filter_map(|(id, pos)| pos.map(|p| (p, id.clone()))).collect()
+        };
+        let shared = members(&bank, &bank_view);
+        assert_eq!(shared, members(&retailer, &retailer_view));
+        let mut ids: Vec<String> = shared.into_values().collect();
+        ids.sort();
+        assert_eq!(ids, customers(4..10));
+
+        // Status carries the count and nothing that identifies a member
+        let Json(job) = get_fed_status(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(job.vertical.as_ref().unwrap().alignment_size, Some(6));
+        assert!(!serde_json::to_string(&job).unwrap().contains("cust-"));
+        let Json(tasks) = get_psi_tasks(State(state.clone()), Path((fed_id.clone(), "bank".to_string()))).await.unwrap();
+        assert!(tasks.is_empty());
+
+        // Nobody holding one key can link a guessed customer to the
+        // other party's list, so its non-members stay hidden
+        let hashed = PsiTask { owner: String::new(), values: customers(0..20).iter().map(|x| psi::hash_to_group(x)).collect() };
+        for (attacker, victim) in [(&bank, "retailer"), (&retailer, "bank")] {
+            let guesses: HashSet<Point> = hashed.values.iter().copied().chain(attacker.raise(&hashed)).collect();
+            let linked = transcript.iter().filter(|(owner, v)| owner == victim && guesses.contains(v)).count();
+            assert_eq!(linked, 0, "{} linked guesses to {}'s list", attacker.id, victim);
+        }
+        // ...and the coordinator, holding none, cannot link anything at all
+        assert!(transcript.iter().all(|(_, v)| !hashed.values.contains(v)));
+    }
+
+    #[tokio::test]
+    async fn test_vertical_round_matches_centralized_gradient() {
+        let state = test_state();
+        let fed_id = start_vertical_job(&state, "bank", &["retailer"], None, 1).await;
+        let bank_features = |i: f64| vec![0.5 * i, 1.0 - 0.1 * i];
+        let retailer_features = |i: f64| vec![i, 0.1 * i * i, -0.3 * i];
+        let label = |i: f64| 2.0 * i - 1.0;
+        let bank_ids: Vec<usize> = (0..8).collect();
+        let retailer_ids: Vec<usize> = (2..10).rev().collect();
+
+        // A bottom model that would not compress its features is refused
+        let retailer_psi = PsiParty::new("retailer", customers(retailer_ids.iter().copied()));
+        let req = SubmitBlindedRequest { party: "retailer".to_string(), values: retailer_psi.blind(), feature_count: Some(1) };
+        let result = submit_blinded(State(state.clone()), Path(fed_id.clone()), Json(req)).await;
+        assert_eq!(result.unwrap_err(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let bank_psi = PsiParty::new("bank", customers(bank_ids.iter().copied()));
+        align(&state, &fed_id, &[(&bank_psi, None), (&retailer_psi, Some(3))]).await;
+        let mut bank = LabelParty {
+            party: VerticalParty::new(
+                "bank",
+                bank_ids.iter().map(|i| bank_features(*i as f64)).collect(),
+                vec![vec![0.2], vec![-0.1]],
+            ),
+            labels: bank_ids.iter().map(|i| label(*i as f64)).collect(),
+        };
+        let mut retailer = VerticalParty::new(
+            "retailer",
+            retailer_ids.iter().map(|i| retailer_features(*i as f64)).collect(),
+            vec![vec![0.05], vec![0.3], vec![-0.2]],
+        );
+        bank.party.align(&alignment_of(&state, &fed_id, &bank.party.id).await);
+        retailer.align(&alignment_of(&state, &fed_id, &retailer.id).await);
+
+        let Json(round) = start_vertical_round(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        assert_eq!(round.order.len(), 6);
+
+        // Only the activation tensor, of exactly the batch shape, is accepted
+        let smuggled = serde_json::json!({ "party": "retailer", "round": 1, "values": [], "features": [[1.0, 2.0, 3.0]] });
+        assert!(serde_json::from_value::<SubmitTensorRequest>(smuggled).is_err());
+        let req = SubmitTensorRequest { party: "retailer".to_string(), round: 1, values: vec![vec![0.0, 0.0]; 6] };
+        let result = submit_activations(State(state.clone()), Path(fed_id.clone()), Json(req)).await;
+        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+
+        let req = SubmitTensorRequest { party: "retailer".to_string(), round: 1, values: retailer.activations(&round.order) };
+        submit_activations(State(state.clone()), Path(fed_id.clone()), Json(req)).await.unwrap();
+        let Json(sum) = get_forward(State(state.clone()), Path((fed_id.clone(), "bank".to_string()))).await.unwrap();
+        let (loss, gradients) = bank.loss_gradient(&round.order, &sum.values);
+        let req = SubmitTensorRequest { party: "bank".to_string(), round: 1, values: gradients.clone() };
+        submit_vertical_gradients(State(state.clone()), Path(fed_id.clone()), Json(req)).await.unwrap();
+        let Json(relayed) = get_backward(State(state.clone()), Path((fed_id.clone(), "retailer".to_string()))).await.unwrap();
+        let split: Vec<f64> = bank
+            .party
+            .weight_gradient(&round.order, &gradients)
+            .into_iter()
+            .chain(retailer.weight_gradient(&round.order, &relayed.values))
+            .flatten()
+            .collect();
+
+        // Centralized reference: one linear model over the joined features
+        let weights = [0.2, -0.1, 0.05, 0.3, -0.2];
+        let joined: Vec<(Vec<f64>, f64)> = (2..8)
+            .map(|i| {
+                let i = i as f64;
+                (bank_features(i).into_iter().chain(retailer_features(i)).collect(), label(i))
+            })
+            .collect();
+        let n = joined.len() as f64;
+        let mut expected = [0.0; 5];
+        let mut expected_loss = 0.0;
+        for (x, y) in &joined {
+            let residual = x.iter().zip(&weights).map(|(a, b)| a * b).sum::<f64>() - y;
+            expected_loss += residual * residual / (2.0 * n);
+            for (g, xi) in expected.iter_mut().zip(x) {
+                *g += xi * residual / n;
+            }
+        }
+        assert!((loss - expected_loss).abs() < 1e-9, "{} vs {}", loss, expected_loss);
+        for (g, e) in split.iter().zip(&expected) {
+            assert!((g - e).abs() < 1e-9, "{} vs {}", g, e);
+        }
+        assert_eq!(state.fed_jobs.read().await[&fed_id].current_round, 1);
+    }
+
+    #[tokio::test]
+    async fn test_vertical_compensation_follows_sample_coverage() {
+        let state = test_state();
+        let fed_id = start_vertical_job(&state, "bank", &["retailer", "telco"], Some(4), 2).await;
+        let bank = PsiParty::new("bank", customers(0..6));
+        let retailer = PsiParty::new("retailer", customers((0..6).rev()));
+        let telco = PsiParty::new("telco", customers([3, 1, 5, 0, 2, 4].into_iter()));
+        align(&state, &fed_id, &[(&bank, None), (&retailer, Some(2)), (&telco, Some(2))]).await;
+        assert_eq!(alignment_of(&state, &fed_id, "telco").await.size, 6);
+
+        // Round 1 hears from both feature parties; in round 2 telco misses the forward pass
+        for (round, contributors) in [(1, vec!["retailer", "telco"]), (2, vec!["retailer"])] {
+            let Json(view) = start_vertical_round(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+            assert_eq!((view.round, view.order.len()), (round, 4));
+            for party in &contributors {
+                let req = SubmitTensorRequest { party: party.to_string(), round, values: vec![vec![1.0]; 4] };
+                submit_activations(State(state.clone()), Path(fed_id.clone()), Json(req)).await.unwrap();
+            }
+            let Json(sum) = get_forward(State(state.clone()), Path((fed_id.clone(), "bank".to_string()))).await.unwrap();
+            assert_eq!(sum.values, vec![vec![contributors.len() as f64]; 4]);
+            let late = SubmitTensorRequest { party: "telco".to_string(), round, values: vec![vec![1.0]; 4] };
+            let result = submit_activations(State(state.clone()), Path(fed_id.clone()), Json(late)).await;
+            assert_eq!(result.unwrap_err(), StatusCode::CONFLICT);
+            let req = SubmitTensorRequest { party: "bank".to_string(), round, values: vec![vec![0.5]; 4] };
+            submit_vertical_gradients(State(state.clone()), Path(fed_id.clone()), Json(req)).await.unwrap();
+        }
+        let result = get_backward(State(state.clone()), Path((fed_id.clone(), "telco".to_string()))).await;
+        assert_eq!(result.unwrap_err(), StatusCode::CONFLICT);
+
+        let Json(compensation) = get_compensation(State(state.clone()), Path(fed_id.clone())).await.unwrap();
+        let payouts: Vec<(&str, f64, u64)> = compensation
+            .payouts
+            .iter()
+            .map(|p| (p.party.as_str(), p.coverage, p.amount))
+            .collect();
+        assert_eq!(payouts, vec![("bank", 1.0, 300), ("retailer", 1.0, 300), ("telco", 0.5, 150)]);
+        assert_eq!(compensation.refund, 150);
+
+        // Done after its rounds, and never open to horizontal updates
+        assert!(matches!(state.fed_jobs.read().await[&fed_id].status, FedStatus::Completed));
+        let result = start_vertical_round(State(state.clone()), Path(fed_id.clone())).await;
+        assert_eq!(result.unwrap_err(), StatusCode::CONFLICT);
+        let req = serde_json::json!({ "weights": [1.0], "sample_count": 1 });
+        let result = submit_gradient(State(state.clone()), Path(fed_id.clone()), Json(req)).await;
+        assert_eq!(result.unwrap_err(), StatusCode::CONFLICT);
+    }
+
+    /// Mean, variance and mean absolute value of `n` noise draws
+    fn noise_moments(dp: &DpParams, n: usize) -> (f64, f64, f64) {
+        let mut draws = vec![0.0; n];
+        dp.add_noise(&mut draws);
+        let mean = draws.iter().sum::<f64>() / n as f64;
+        let var = draws.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        let mean_abs = draws.iter().map(|x| x.abs()).sum::<f64>() / n as f64;
+        (mean, var, mean_abs)
+    }
+
+    fn assert_close(actual: f64, expected: f64, rel: f64) {
+        assert!((actual - expected).abs() <= rel * expected, "{} is not within {} of {}", actual, rel, expected);
+    }
+
+    #[tokio::test]
+    async fn test_dp_mechanisms_draw_calibrated_noise() {
+        let state = test_state();
+        let mut fed_ids = Vec::new();
+        for budget in [
+            DpBudget { mechanism: dp::DpMechanism::Laplace, epsilon: 0.5, delta: 0.0, sensitivity: 1.0 },
+            DpBudget { mechanism: dp::DpMechanism::Gaussian, epsilon: 0.5, delta: 1e-5, sensitivity: 1.0 },
+        ] {
+            let Json(resp) = start_federated(
+                State(state.clone()),
+                Json(StartFedRequest {
+                    model_id: "model-1".to_string(),
+                    dataset_ids: vec!["ds-1".to_string()],
+                    rounds: 1,
+                    dp: true,
+                    dp_budget: Some(budget),
+                    budget: 100,
+                    partition: Partition::Horizontal,
+                    vertical: None,
+                    participants: Vec::new(),
+                }),
+            )
+            .await
+            .unwrap();
+            fed_ids.push(resp.fed_id);
+        }
+
+        // Laplace: b = Δ/ε, so variance 2b² and mean |x| = b
+        let Json(job) = get_fed_status(State(state.clone()), Path(fed_ids[0].clone())).await.unwrap();
+        let laplace = job.dp.unwrap();
+        assert_eq!(laplace.mechanism, dp::DpMechanism::Laplace);
+        assert_eq!((laplace.scale, laplace.delta), (2.0, 0.0));
+        let (mean, var, mean_abs) = noise_moments(&laplace, 200_000);
+        assert!(mean.abs() < 0.05);
+        assert_close(var, 8.0, 0.03);
+        assert_close(mean_abs, 2.0, 0.02);
+
+        // Gaussian: σ = Δ √(2 ln(1.25/δ)) / ε, so variance σ² and mean |x| = σ √(2/π)
+        let Json(job) = get_fed_status(State(state.clone()), Path(fed_ids[1].clone())).await.unwrap();
+        let gaussian = job.dp.unwrap();
+        let sigma = (2.0 * (1.25f64 / 1e-5).ln()).sqrt() / 0.5;
+        assert_eq!(gaussian.mechanism, dp::DpMechanism::Gaussian);
+        assert_close(gaussian.scale, sigma, 1e-12);
+        assert_eq!(gaussian.delta, 1e-5);
+        let (mean, var, mean_abs) = noise_moments(&gaussian, 200_000);
+        assert!(mean.abs() < 0.1);
+        assert_close(var, sigma * sigma, 0.03);
+        assert_close(mean_abs, sigma * (2.0 / std::f64::consts::PI).sqrt(), 0.02);
+
+        // The status reports both
+        let status = serde_json::to_value(&job).unwrap();
+        assert_eq!(status["dp"]["mechanism"], "Gaussian");
+        assert_eq!(status["dp"]["epsilon"], 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_gaussian_mechanism_requires_delta() {
+        let gaussian = |delta| DpBudget { mechanism: dp::DpMechanism::Gaussian, epsilon: 0.5, delta, sensitivity: 1.0 };
+        assert!(gaussian(0.0).calibrate().is_err());
+        assert!(gaussian(1.0).calibrate().is_err());
+        assert!(gaussian(1e-6).calibrate().is_ok());
+        let laplace = DpBudget { mechanism: dp::DpMechanism::Laplace, ..gaussian(0.0) };
+        assert!(laplace.calibrate().is_ok());
+
+        let state = test_state();
+        let start = |dp, dp_budget| {
+            start_federated(
+                State(state.clone()),
+                Json(StartFedRequest {
+                    model_id: "model-1".to_string(),
+                    dataset_ids: vec!["ds-1".to_string()],
+                    rounds: 1,
+                    dp,
+                    dp_budget,
+                    budget: 100,
+                    partition: Partition::Horizontal,
+                    vertical: None,
+                    participants: Vec::new(),
+                }),
+            )
+        };
+        assert_eq!(start(true, Some(gaussian(0.0))).await.unwrap_err(), StatusCode::BAD_REQUEST);
+        // A budget only means something for a DP job
+        assert_eq!(start(false, Some(gaussian(1e-6))).await.unwrap_err(), StatusCode::BAD_REQUEST);
+        assert!(state.fed_jobs.read().await.is_empty());
+
+        // Without a budget, DP jobs keep the Laplace noise they always had
+        let Json(resp) = start(true, None).await.unwrap();
+        let dp = state.fed_jobs.read().await[&resp.fed_id].dp.unwrap();
+        assert_eq!((dp.mechanism, dp.scale), (dp::DpMechanism::Laplace, 0.1));
+    }
+}
@@ -0,0 +1,209 @@
+//! Private Set Intersection
+//! Entity alignment for vertical federated learning: parties holding
+//! different features about overlapping populations agree on the entities
+//! they share without revealing the rest. DH-based PSI with X25519 scalar
+//! multiplication as a commutative cipher, relayed by the coordinator:
+//!
+//! 1. Each party draws a secret key k and hashes every identifier to a
+//!    curve25519 u-coordinate, H(x) = SHA-256(domain ‖ x).
+//! 2. It submits its blinded list k·H(x) in its own row order. Identifiers
+//!    and bare hashes never leave the party.
+//! 3. The list travels the roster in order starting after its owner; each
+//!    party multiplies every entry by its own key, keeping the order. Keys
+//!    commute, so once all n parties have applied theirs every entry is
+//!    (k_1···k_n)·H(x) whatever the route.
+//! 4. The coordinator intersects the fully keyed lists. Equal entries are
+//!    the same identifier; all others are unlinkable without every key.
+//!    The shared set is ordered by keyed value, which no party can steer,
+//!    and each party learns only the shared positions of its own rows.
+//!
+//! The coordinator sees curve points only and learns the intersection
+//! size plus which of each party's row positions matched. A party only ever
+//! sees another party's entries under a key it does not hold, so it cannot
+//! test guessed identifiers against them, and the last hop of each list is
+//! a different party so nobody sees two fully keyed lists. Uses the same
+//! X25519 arithmetic as secure aggregation.
+
+#[cfg(test)]
+use crate::secagg::random_secret;
+use crate::secagg::Point;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+
+/// Map an identifier to a u-coordinate. Any 255-bit string is one, on the
+/// curve or its twist, and both are safe for X25519.
+#[cfg(test)]
+pub fn hash_to_group(identifier: &str) -> Point {
+    let mut u: [u8; 32] = Sha256::new()
+        .chain_update(b"artha-psi")
+        .chain_update(identifier.as_bytes())
+        .finalize()
+        .into();
+    u[31] &= 0x7f; // X25519 ignores the top bit
+    Point(u)
+}
+
+/// A list waiting for one party's key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PsiTask {
+    pub owner: String,
+    pub values: Vec<Point>,
+}
+
+/// Client side of the protocol: the reference party the tests drive sessions with
+#[cfg(test)]
+pub struct PsiParty {
+    pub id: String,
+    pub identifiers: Vec<String>,
+    key: [u8; 32],
+}
+
+#[cfg(test)]
+impl PsiParty {
+    pub fn new(id: &str, identifiers: Vec<String>) -> Self {
+        Self { id: id.to_string(), identifiers, key: random_secret() }
+    }
+
+    /// k·H(x) for every identifier, in row order
+    pub fn blind(&self) -> Vec<Point> {
+        self.identifiers.iter().map(|x| hash_to_group(x).mul(&self.key)).collect()
+    }
+
+    /// Apply this party's key to a relayed list
+    pub fn raise(&self, task: &PsiTask) -> Vec<Point> {
+        task.values.iter().map(|v| v.mul(&self.key)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BlindedList {
+    values: Vec<Point>,
+    raised: usize, // Parties whose key is applied, the owner first
+}
+
+/// The shared sample set. Members are indexed 0..size by keyed value; each
+/// party's rows map to their shared position, if any.
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    pub size: usize,
+    rows: BTreeMap<String, Vec<Option<u32>>>,
+}
+
+impl Alignment {
+    pub fn rows_of(&self, party: &str) -> Option<&[Option<u32>]> {
+        self.rows.get(party).map(Vec::as_slice)
+    }
+}
+
+/// Server side: relays lists along the roster and intersects the result
+#[derive(Debug, Clone)]
+pub struct PsiSession {
+    parties: Vec<String>,
+    lists: BTreeMap<String, BlindedList>,
+}
+
+impl PsiSession {
+    pub fn new(parties: &[String]) -> Result<Self, StatusCode> {
+        let mut unique = parties.to_vec();
+        unique.sort();
+        unique.dedup();
+        if parties.len() < 2 || unique.len() != parties.len() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(Self { parties: parties.to_vec(), lists: BTreeMap::new() })
+    }
+
+    fn position(&self, party: &str) -> Result<usize, StatusCode> {
+        self.parties.iter().position(|p| p == party).ok_or(StatusCode::FORBIDDEN)
+    }
+
+    /// Whose key `owner`'s list needs next, if anyone's
+    fn next_raiser(&self, owner: &str, list: &BlindedList) -> Option<&str> {
+        let n = self.parties.len();
+        if list.raised == n {
+            return None;
+        }
+        let start = self.parties.iter().position(|p| p == owner)?;
+        Some(&self.parties[(start + list.raised) % n])
+    }
+
+    pub fn blinded_count(&self) -> usize {
+        self.lists.len()
+    }
+
+    pub fn submit_blinded(&mut self, party: &str, values: Vec<Point>) -> Result<(), StatusCode> {
+        self.position(party)?;
+        if self.lists.contains_key(party) {
+            return Err(StatusCode::CONFLICT);
+        }
+        // Duplicates would make rows ambiguous
+        let distinct: HashSet<Point> = values.iter().copied().collect();
+        if values.is_empty() || distinct.len() != values.len() || !values.iter().all(Point::is_valid) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        self.lists.insert(party.to_string(), BlindedList { values, raised: 1 });
+        Ok(())
+    }
+
+    /// Lists `party` should raise next
+    pub fn tasks_for(&self, party: &str) -> Result<Vec<PsiTask>, StatusCode> {
+        self.position(party)?;
+        Ok(self
+            .lists
+            .iter()
+            .filter(|(owner, list)| self.next_raiser(owner, list) == Some(party))
+            .map(|(owner, list)| PsiTask { owner: owner.clone(), values: list.values.clone() })
+            .collect())
+    }
+
+    pub fn submit_raised(&mut self, party: &str, owner: &str, values: Vec<Point>) -> Result<(), StatusCode> {
+        self.position(party)?;
+        let list = self.lists.get(owner).ok_or(StatusCode::NOT_FOUND)?;
+        if self.next_raiser(owner, list) != Some(party) {
+            return Err(StatusCode::CONFLICT);
+        }
+        if values.len() != list.values.len() || !values.iter().all(Point::is_valid) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let list = self.lists.get_mut(owner).unwrap();
+        list.values = values;
+        list.raised += 1;
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.lists.len() == self.parties.len() && self.lists.values().all(|l| l.raised == self.parties.len())
+    }
+
+    /// Intersect the fully raised lists, once every list has been raised by everyone
+    pub fn align(&self) -> Option<Alignment> {
+        if !self.is_complete() {
+            return None;
+        }
+        let mut counts: BTreeMap<Point, usize> = BTreeMap::new();
+        for value in self.lists.values().flat_map(|l| &l.values) {
+            *counts.entry(*value).or_default() += 1;
+        }
+        let shared: Vec<Point> = counts
+            .into_iter()
+            .filter(|(_, count)| *count == self.parties.len())
+            .map(|(value, _)| value)
+            .collect();
+        let rows = self
+            .lists
+            .iter()
+            .map(|(owner, list)| {
+                let positions = list
+                    .values
+                    .iter()
+                    .map(|v| shared.binary_search(v).ok().map(|i| i as u32))
+                    .collect();
+                (owner.clone(), positions)
+            })
+            .collect();
+        Some(Alignment { size: shared.len(), rows })
+    }
+}
@@ -0,0 +1,36 @@
+//! Round Quorum
+//! A FedAvg round waits for every participant on the job's roster, but only
+//! until the round's deadline. After it, aggregation goes ahead with the
+//! updates that arrived, so long as they make up the minimum quorum; the
+//! participants that sent nothing are recorded as absent from that round.
+//! Short of the quorum, the round fails and takes the job with it.
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+#[derive(Debug, Clone)]
+pub struct QuorumConfig {
+    pub round_timeout_secs: u64, // From the round opening to its deadline
+    pub min_quorum: f64,         // Fraction of the roster needed after the deadline
+}
+
+impl QuorumConfig {
+    pub fn from_env() -> Self {
+        QuorumConfig {
+            round_timeout_secs: env_or("ARTHA_FED_ROUND_TIMEOUT_SECS", 600),
+            min_quorum: env_or("ARTHA_FED_MIN_QUORUM", 0.5_f64).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Updates needed to aggregate a round of `expected` after its deadline
+    pub fn required(&self, expected: usize) -> usize {
+        ((expected as f64 * self.min_quorum).ceil() as usize).clamp(1, expected.max(1))
+    }
+}
+
+/// Roster members with no update in the round
+pub fn absent<'a>(roster: &[String], submitted: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let submitted: std::collections::HashSet<&str> = submitted.into_iter().collect();
+    roster.iter().filter(|p| !submitted.contains(p.as_str())).cloned().collect()
+}
@@ -0,0 +1,643 @@
+//! Secure Aggregation
+//! Masked aggregation of participant updates with dropout recovery. Each
+//! participant masks its input with pairwise masks (agreed by Diffie-Hellman
+//! with every other participant) plus a self-mask, and Shamir-shares both its
+//! DH secret key and its self-mask seed among the round. The pairwise masks
+//! cancel in the sum; when a participant drops after the others masked
+//! against it, any `threshold` survivors reveal their shares of its secret
+//! key so the server can strip its dangling masks. Survivors reveal
+//! self-mask shares instead, never both for the same participant, so a
+//! dropped input that arrives late can still not be unmasked.
+//!
+//! Key agreement is X25519. Masks, shares and encoded inputs live in the
+//! prime field of order q, whose 61 bits keep that arithmetic in u128; the
+//! 32-byte DH secret keys and self-mask seeds are Shamir-shared in it a few
+//! bytes per field element.
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use x25519_dalek::{x25519, X25519_BASEPOINT_BYTES};
+
+/// Prime order of the field masks, shares and encoded inputs live in
+pub const Q: u64 = 2_305_843_009_213_688_669;
+/// Fixed-point scale for encoding weights
+pub const SCALE: f64 = 65_536.0;
+/// Bytes of a 32-byte secret per field element; 7 bytes stay below q
+const CHUNK_BYTES: usize = 7;
+/// Field elements a 32-byte secret is shared as
+pub const SECRET_CHUNKS: usize = 32_usize.div_ceil(CHUNK_BYTES);
+
+/// A curve25519 u-coordinate: an X25519 public key, or a PSI entry under
+/// some parties' keys. Hex-encoded on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point(pub [u8; 32]);
+
+impl Point {
+    pub fn public_key(secret: &[u8; 32]) -> Self {
+        Point(x25519(*secret, X25519_BASEPOINT_BYTES))
+    }
+
+    /// This point times `secret`, clamped as X25519 clamps it. Clamped
+    /// scalars commute, so the order keys are applied in doesn't matter.
+    pub fn mul(&self, secret: &[u8; 32]) -> Self {
+        Point(x25519(*secret, self.0))
+    }
+
+    /// Not of small order. Clamped scalars clear the cofactor, so a
+    /// small-order point comes out as zero and DH against it would give
+    /// every participant the same, public, seed.
+    pub fn is_valid(&self) -> bool {
+        self.mul(&[1; 32]).0 != [0; 32]
+    }
+}
+
+impl Serialize for Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let bytes = hex::decode(text.trim_start_matches("0x")).map_err(serde::de::Error::custom)?;
+        bytes.try_into().map(Point).map_err(|_| serde::de::Error::custom("expected 32 bytes"))
+    }
+}
+
+fn add_q(a: u64, b: u64) -> u64 {
+    ((a as u128 + b as u128) % Q as u128) as u64
+}
+
+fn sub_q(a: u64, b: u64) -> u64 {
+    ((a as u128 + Q as u128 - (b % Q) as u128) % Q as u128) as u64
+}
+
+fn mul_q(a: u64, b: u64) -> u64 {
+    ((a as u128 * b as u128) % Q as u128) as u64
+}
+
+fn inv_q(a: u64) -> u64 {
+    let (mut result, mut base, mut exp) = (1, a % Q, Q - 2);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_q(result, base);
+        }
+        base = mul_q(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+fn random_scalar() -> u64 {
+    use rand::Rng;
+    rand::thread_rng().gen_range(1..Q)
+}
+
+#[cfg(test)]
+pub(crate) fn random_secret() -> [u8; 32] {
+    use rand::Rng;
+    rand::thread_rng().gen()
+}
+
+/// Expand a seed into `len` field elements
+fn expand(seed: &[u8], domain: &[u8], len: usize) -> Vec<u64> {
+    (0..len as u64)
+        .map(|i| {
+            let digest = Sha256::new()
+                .chain_update(domain)
+                .chain_update(seed)
+                .chain_update(i.to_be_bytes())
+                .finalize();
+            let x = u128::from_be_bytes(digest[..16].try_into().unwrap());
+            (x % Q as u128) as u64
+        })
+        .collect()
+}
+
+/// Shared pairwise seed sk_a · pk_b = sk_b · pk_a
+fn agree(secret_key: &[u8; 32], peer_public_key: &Point) -> [u8; 32] {
+    peer_public_key.mul(secret_key).0
+}
+
+/// Encode a weighted update as field elements: each weight multiplied by the
+/// sample count in fixed point, followed by the sample count itself, so the
+/// masked sum carries everything FedAvg needs
+#[cfg(test)]
+pub fn encode(weights: &[f64], sample_count: u64) -> Vec<u64> {
+    weights
+        .iter()
+        .map(|w| {
+            let fixed = (w * sample_count as f64 * SCALE).round() as i64;
+            if fixed < 0 {
+                Q - fixed.unsigned_abs()
+            } else {
+                fixed as u64
+            }
+        })
+        .chain(std::iter::once(sample_count % Q))
+        .collect()
+}
+
+/// Decode an aggregated sum back into the FedAvg weighted average
+pub fn decode(sum: &[u64]) -> Option<(Vec<f64>, u64)> {
+    let (&total_samples, weighted) = sum.split_last()?;
+    if total_samples == 0 {
+        return None;
+    }
+    let weights = weighted
+        .iter()
+        .map(|&v| {
+            let signed = if v > Q / 2 { -((Q - v) as f64) } else { v as f64 };
+            signed / SCALE / total_samples as f64
+        })
+        .collect();
+    Some((weights, total_samples))
+}
+
+/// Shamir share: the polynomial evaluated at the holder's roster index
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Share {
+    pub x: u64,
+    pub y: u64,
+}
+
+/// Split `secret` into shares for holders 1..=holders, any `threshold` of
+/// which reconstruct it
+#[cfg(test)]
+pub fn split(secret: u64, threshold: usize, holders: &[u64]) -> Vec<Share> {
+    let coefficients: Vec<u64> = std::iter::once(secret % Q)
+        .chain((1..threshold).map(|_| random_scalar()))
+        .collect();
+    holders
+        .iter()
+        .map(|&x| Share {
+            x,
+            y: coefficients.iter().rev().fold(0, |acc, c| add_q(mul_q(acc, x), *c)),
+        })
+        .collect()
+}
+
+/// Lagrange interpolation at zero
+pub fn reconstruct(shares: &[Share]) -> u64 {
+    shares.iter().fold(0, |secret, share| {
+        let (num, den) = shares
+            .iter()
+            .filter(|other| other.x != share.x)
+            .fold((1, 1), |(num, den), other| {
+                (mul_q(num, other.x), mul_q(den, sub_q(other.x, share.x)))
+            });
+        add_q(secret, mul_q(share.y, mul_q(num, inv_q(den))))
+    })
+}
+
+/// Shares of a 32-byte secret for each holder, one per chunk
+#[cfg(test)]
+fn split_secret(secret: &[u8; 32], threshold: usize, holders: &[u64]) -> Vec<Vec<u64>> {
+    let per_chunk: Vec<Vec<Share>> = secret
+        .chunks(CHUNK_BYTES)
+        .map(|chunk| split(chunk.iter().fold(0, |acc, b| (acc << 8) | *b as u64), threshold, holders))
+        .collect();
+    (0..holders.len()).map(|h| per_chunk.iter().map(|shares| shares[h].y).collect()).collect()
+}
+
+/// A 32-byte secret from its holders' chunk shares. `None` when a chunk
+/// comes out too wide, as inconsistent shares would make it.
+fn reconstruct_secret(shares: &[(u64, Vec<u64>)]) -> Option<[u8; 32]> {
+    let mut secret = Vec::with_capacity(32);
+    for i in 0..SECRET_CHUNKS {
+        let chunk_shares: Vec<Share> = shares.iter().map(|(x, ys)| Share { x: *x, y: ys[i] }).collect();
+        let chunk = reconstruct(&chunk_shares);
+        let width = CHUNK_BYTES.min(32 - i * CHUNK_BYTES);
+        if chunk >> (8 * width) != 0 {
+            return None;
+        }
+        secret.extend_from_slice(&chunk.to_be_bytes()[8 - width..]);
+    }
+    secret.try_into().ok()
+}
+
+/// A participant's advertised DH key and the Shamir x-coordinate it holds
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RosterEntry {
+    pub participant: String,
+    pub index: u64,
+    pub public_key: Option<Point>,
+}
+
+/// Shares of one participant's secrets, encrypted to the recipient under
+/// their pairwise DH seed. The server only relays these.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedShare {
+    pub from: String,
+    pub to: String,
+    pub self_mask_share: Vec<u64>, // One field element per secret chunk
+    pub key_share: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareKind {
+    SelfMask,  // Owner survived: strip its self-mask
+    SecretKey, // Owner dropped: strip its pairwise masks
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevealedShare {
+    pub owner: String,
+    pub kind: ShareKind,
+    pub y: Vec<u64>, // One field element per secret chunk
+}
+
+/// Pairwise mask sign: the lower roster index adds, the higher subtracts
+fn pairwise_sign(own: u64, peer: u64) -> bool {
+    own < peer
+}
+
+/// Client side of the protocol. The service only runs the server side; this
+/// is the reference participant the tests drive rounds with.
+#[cfg(test)]
+pub struct Participant {
+    pub id: String,
+    pub index: u64,
+    secret_key: [u8; 32],
+    self_seed: [u8; 32],
+    pub public_key: Point,
+}
+
+#[cfg(test)]
+impl Participant {
+    pub fn new(id: &str, index: u64) -> Self {
+        let secret_key = random_secret();
+        Self {
+            id: id.to_string(),
+            index,
+            secret_key,
+            self_seed: random_secret(),
+            public_key: Point::public_key(&secret_key),
+        }
+    }
+
+    fn seed_with(&self, peer: &RosterEntry) -> Option<[u8; 32]> {
+        peer.public_key.map(|pk| agree(&self.secret_key, &pk))
+    }
+
+    /// Shares of the DH key and self-mask seed for every keyed participant,
+    /// including this one
+    pub fn share_secrets(&self, roster: &[RosterEntry], threshold: usize) -> Vec<EncryptedShare> {
+        let keyed: Vec<&RosterEntry> = roster.iter().filter(|e| e.public_key.is_some()).collect();
+        let holders: Vec<u64> = keyed.iter().map(|e| e.index).collect();
+        let self_shares = split_secret(&self.self_seed, threshold, &holders);
+        let key_shares = split_secret(&self.secret_key, threshold, &holders);
+        let encrypt = |ys: &[u64], pad: &[u64]| ys.iter().zip(pad).map(|(y, p)| add_q(*y, *p)).collect();
+
+        keyed
+            .iter()
+            .zip(self_shares.iter().zip(&key_shares))
+            .filter_map(|(entry, (b, k))| {
+                let pad = expand(&self.seed_with(entry)?, b"share", 2 * SECRET_CHUNKS);
+                Some(EncryptedShare {
+                    from: self.id.clone(),
+                    to: entry.participant.clone(),
+                    self_mask_share: encrypt(b, &pad[..SECRET_CHUNKS]),
+                    key_share: encrypt(k, &pad[SECRET_CHUNKS..]),
+                })
+            })
+            .collect()
+    }
+
+    /// Mask an encoded input against everyone in the sharing set
+    pub fn mask(&self, input: &[u64], sharing_set: &[RosterEntry]) -> Vec<u64> {
+        let mut masked: Vec<u64> = input
+            .iter()
+            .zip(expand(&self.self_seed, b"self", input.len()))
+            .map(|(x, m)| add_q(*x, m))
+            .collect();
+
+        for peer in sharing_set.iter().filter(|p| p.participant != self.id) {
+            let Some(seed) = self.seed_with(peer) else { continue };
+            let mask = expand(&seed, b"pair", input.len());
+            for (value, m) in masked.iter_mut().zip(mask) {
+                *value = if pairwise_sign(self.index, peer.index) {
+                    add_q(*value, m)
+                } else {
+                    sub_q(*value, m)
+                };
+            }
+        }
+        masked
+    }
+
+    /// Decrypt the shares addressed to this participant and reveal, for each
+    /// owner, the share the server is entitled to: self-mask shares for
+    /// survivors, secret-key shares for dropped participants
+    pub fn reveal(
+        &self,
+        received: &[EncryptedShare],
+        roster: &[RosterEntry],
+        survivors: &[String],
+        dropped: &[String],
+    ) -> Vec<RevealedShare> {
+        let decrypt = |ys: &[u64], pad: &[u64]| ys.iter().zip(pad).map(|(y, p)| sub_q(*y, *p)).collect();
+        received
+            .iter()
+            .filter(|s| s.to == self.id)
+            .filter_map(|share| {
+                let owner = roster.iter().find(|e| e.participant == share.from)?;
+                let pad = expand(&self.seed_with(owner)?, b"share", 2 * SECRET_CHUNKS);
+                let (kind, y) = if survivors.contains(&share.from) {
+                    (ShareKind::SelfMask, decrypt(&share.self_mask_share, &pad[..SECRET_CHUNKS]))
+                } else if dropped.contains(&share.from) {
+                    (ShareKind::SecretKey, decrypt(&share.key_share, &pad[SECRET_CHUNKS..]))
+                } else {
+                    return None;
+                };
+                Some(RevealedShare {
+                    owner: share.from.clone(),
+                    kind,
+                    y,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecAggPhase {
+    AdvertiseKeys,
+    ShareKeys,
+    MaskedInput,
+    Unmasking,
+    Complete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecAggSummary {
+    pub phase: SecAggPhase,
+    pub threshold: usize,
+    pub roster: Vec<RosterEntry>,
+    pub sharing_set: Vec<String>, // Participants whose shares were relayed
+    pub masked_set: Vec<String>,  // Sharing-set members whose masked input arrived
+    pub dropped: Vec<String>,     // Sharing set minus masked set, once unmasking starts
+    pub reveals: usize,
+}
+
+/// Server side of one aggregation round. Each phase closes when the first
+/// message of the next phase arrives; whoever has not contributed by then is
+/// treated as dropped.
+#[derive(Debug, Clone)]
+pub struct SecAggRound {
+    pub threshold: usize,
+    pub phase: SecAggPhase,
+    roster: Vec<RosterEntry>,
+    shares: Vec<EncryptedShare>,
+    sharing_set: Vec<String>,
+    masked: BTreeMap<String, Vec<u64>>,
+    reveals: BTreeMap<String, Vec<RevealedShare>>,
+}
+
+impl SecAggRound {
+    /// A majority threshold, so the server cannot collect both shares of one
+    /// participant's secrets from disjoint groups
+    pub fn new(participants: &[String], threshold: usize) -> Result<Self, StatusCode> {
+        let n = participants.len();
+        let mut unique = participants.to_vec();
+        unique.sort();
+        unique.dedup();
+        if n < 2 || unique.len() != n || threshold <= n / 2 || threshold > n {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        Ok(Self {
+            threshold,
+            phase: SecAggPhase::AdvertiseKeys,
+            roster: participants
+                .iter()
+                .enumerate()
+                .map(|(i, p)| RosterEntry {
+                    participant: p.clone(),
+                    index: i as u64 + 1,
+                    public_key: None,
+                })
+                .collect(),
+            shares: Vec::new(),
+            sharing_set: Vec::new(),
+            masked: BTreeMap::new(),
+            reveals: BTreeMap::new(),
+        })
+    }
+
+    pub fn summary(&self) -> SecAggSummary {
+        SecAggSummary {
+            phase: self.phase,
+            threshold: self.threshold,
+            roster: self.roster.clone(),
+            sharing_set: self.sharing_set.clone(),
+            masked_set: self.masked.keys().cloned().collect(),
+            dropped: self.dropped(),
+            reveals: self.reveals.len(),
+        }
+    }
+
+    pub fn dropped(&self) -> Vec<String> {
+        if !matches!(self.phase, SecAggPhase::Unmasking | SecAggPhase::Complete) {
+            return Vec::new();
+        }
+        self.sharing_set
+            .iter()
+            .filter(|p| !self.masked.contains_key(*p))
+            .cloned()
+            .collect()
+    }
+
+    fn entry(&self, participant: &str) -> Result<&RosterEntry, StatusCode> {
+        self.roster
+            .iter()
+            .find(|e| e.participant == participant)
+            .ok_or(StatusCode::FORBIDDEN)
+    }
+
+    pub fn advertise_key(&mut self, participant: &str, public_key: Point) -> Result<(), StatusCode> {
+        if self.phase != SecAggPhase::AdvertiseKeys {
+            return Err(StatusCode::CONFLICT);
+        }
+        self.entry(participant)?;
+        if !public_key.is_valid() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        if let Some(entry) = self.roster.iter_mut().find(|e| e.participant == participant) {
+            entry.public_key = Some(public_key);
+        }
+        Ok(())
+    }
+
+    pub fn submit_shares(&mut self, participant: &str, shares: Vec<EncryptedShare>) -> Result<(), StatusCode> {
+        if self.phase == SecAggPhase::AdvertiseKeys {
+            // Key advertisement closes; unkeyed participants are out of the round
+            if self.roster.iter().filter(|e| e.public_key.is_some()).count() < self.threshold {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+            self.phase = SecAggPhase::ShareKeys;
+        }
+        if self.phase != SecAggPhase::ShareKeys {
+            return Err(StatusCode::CONFLICT);
+        }
+        if self.entry(participant)?.public_key.is_none() || self.sharing_set.iter().any(|p| p == participant) {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        // Exactly one share for every keyed participant, all from the sender
+        let mut recipients: Vec<&str> = shares.iter().map(|s| s.to.as_str()).collect();
+        recipients.sort();
+        let mut keyed: Vec<&str> = self
+            .roster
+            .iter()
+            .filter(|e| e.public_key.is_some())
+            .map(|e| e.participant.as_str())
+            .collect();
+        keyed.sort();
+        let well_formed = |s: &EncryptedShare| {
+            s.from == participant && s.self_mask_share.len() == SECRET_CHUNKS && s.key_share.len() == SECRET_CHUNKS
+        };
+        if recipients != keyed || !shares.iter().all(well_formed) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        self.shares.extend(shares);
+        self.sharing_set.push(participant.to_string());
+        Ok(())
+    }
+
+    /// Shares addressed to `participant`, from the sharing set
+    pub fn shares_for(&self, participant: &str) -> Vec<EncryptedShare> {
+        self.shares.iter().filter(|s| s.to == participant).cloned().collect()
+    }
+
+    pub fn submit_masked(&mut self, participant: &str, masked: Vec<u64>) -> Result<(), StatusCode> {
+        if self.phase == SecAggPhase::ShareKeys {
+            if self.sharing_set.len() < self.threshold {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+            self.phase = SecAggPhase::MaskedInput;
+        }
+        if self.phase != SecAggPhase::MaskedInput {
+            return Err(StatusCode::CONFLICT);
+        }
+        if !self.sharing_set.iter().any(|p| p == participant) || self.masked.contains_key(participant) {
+            return Err(StatusCode::CONFLICT);
+        }
+        if masked.is_empty()
+            || masked.iter().any(|v| *v >= Q)
+            || self.masked.values().next().is_some_and(|m| m.len() != masked.len())
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        self.masked.insert(participant.to_string(), masked);
+        Ok(())
+    }
+
+    pub fn submit_reveal(&mut self, participant: &str, reveals: Vec<RevealedShare>) -> Result<(), StatusCode> {
+        if self.phase == SecAggPhase::MaskedInput {
+            if self.masked.len() < self.threshold {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+            self.phase = SecAggPhase::Unmasking;
+        }
+        if self.phase != SecAggPhase::Unmasking {
+            return Err(StatusCode::CONFLICT);
+        }
+        if !self.masked.contains_key(participant) || self.reveals.contains_key(participant) {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        // One share per sharing-set member, of the kind its status allows
+        let dropped = self.dropped();
+        let mut owners: Vec<&str> = reveals.iter().map(|r| r.owner.as_str()).collect();
+        owners.sort();
+        let mut expected: Vec<&str> = self.sharing_set.iter().map(|p| p.as_str()).collect();
+        expected.sort();
+        let kinds_match = reveals.iter().all(|r| {
+            let kind = if dropped.contains(&r.owner) {
+                ShareKind::SecretKey
+            } else {
+                ShareKind::SelfMask
+            };
+            r.kind == kind && r.y.len() == SECRET_CHUNKS && r.y.iter().all(|y| *y < Q)
+        });
+        if owners != expected || !kinds_match {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        self.reveals.insert(participant.to_string(), reveals);
+        Ok(())
+    }
+
+    /// `owner`'s secret, from the shares revealed so far
+    fn revealed_secret(&self, owner: &str) -> Result<[u8; 32], StatusCode> {
+        let shares = self
+            .reveals
+            .iter()
+            .filter_map(|(holder, reveals)| {
+                let y = reveals.iter().find(|r| r.owner == owner)?.y.clone();
+                Some(self.entry(holder).map(|e| (e.index, y)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        reconstruct_secret(&shares).ok_or(StatusCode::UNPROCESSABLE_ENTITY) // Inconsistent shares
+    }
+
+    /// Unmask the sum of the surviving inputs once `threshold` survivors
+    /// have revealed their shares
+    pub fn aggregate(&mut self) -> Result<Vec<u64>, StatusCode> {
+        if self.phase != SecAggPhase::Unmasking || self.reveals.len() < self.threshold {
+            return Err(StatusCode::PRECONDITION_FAILED);
+        }
+
+        let len = self.masked.values().next().map_or(0, |m| m.len());
+        let mut sum = vec![0u64; len];
+        for masked in self.masked.values() {
+            for (total, value) in sum.iter_mut().zip(masked) {
+                *total = add_q(*total, *value);
+            }
+        }
+
+        let indices: HashMap<&str, &RosterEntry> =
+            self.roster.iter().map(|e| (e.participant.as_str(), e)).collect();
+
+        for survivor in self.masked.keys() {
+            let self_seed = self.revealed_secret(survivor)?;
+            for (total, m) in sum.iter_mut().zip(expand(&self_seed, b"self", len)) {
+                *total = sub_q(*total, m);
+            }
+        }
+
+        for dropped in self.dropped() {
+            let secret_key = self.revealed_secret(&dropped)?;
+            let dropped_entry = indices[dropped.as_str()];
+            if dropped_entry.public_key != Some(Point::public_key(&secret_key)) {
+                return Err(StatusCode::UNPROCESSABLE_ENTITY); // Inconsistent shares
+            }
+
+            // Each survivor masked against the dropped participant; undo it
+            for survivor in self.masked.keys() {
+                let entry = indices[survivor.as_str()];
+                let Some(pk) = entry.public_key else { continue };
+                let mask = expand(&agree(&secret_key, &pk), b"pair", len);
+                for (total, m) in sum.iter_mut().zip(mask) {
+                    *total = if pairwise_sign(entry.index, dropped_entry.index) {
+                        sub_q(*total, m)
+                    } else {
+                        add_q(*total, m)
+                    };
+                }
+            }
+        }
+
+        self.phase = SecAggPhase::Complete;
+        Ok(sum)
+    }
+}
@@ -0,0 +1,162 @@
+//! Streaming FedAvg
+//! Participant updates for large models are stored in SVDB as raw
+//! little-endian f64 weights; small ones may still be submitted inline.
+//! Aggregation walks the model one chunk at a time: the same range is read
+//! from every participant, folded into the weighted sum and handed to the
+//! output before the next range is read. Peak memory is one chunk per
+//! participant plus the running chunk, never a full weight vector, so the
+//! model size only bounds how long aggregation takes.
+
+use crate::GradientUpdate;
+use std::future::Future;
+
+/// Weights read from each participant per step: 512 KiB of f64
+pub const DEFAULT_CHUNK_PARAMS: usize = 64 * 1024;
+
+const F64_BYTES: usize = std::mem::size_of::<f64>();
+
+/// Where participant weights are read from. SVDB in production; synthetic in tests.
+pub trait UpdateSource {
+    /// Weights `offset..offset + len` of one participant's update
+    fn read_chunk(
+        &self,
+        update: &GradientUpdate,
+        offset: usize,
+        len: usize,
+    ) -> impl Future<Output = Result<Vec<f64>, String>> + Send;
+}
+
+/// Reads inline weights in place and SVDB-stored ones by byte range
+pub struct SvdbUpdates {
+    pub base_url: String,
+    client: reqwest::Client,
+}
+
+/// `artha://<base64>` as a URL path segment
+fn cid_path_segment(cid: &str) -> String {
+    cid.trim_start_matches("artha://").replace('+', "%2B").replace('/', "%2F")
+}
+
+impl SvdbUpdates {
+    pub fn new(base_url: String) -> Self {
+        SvdbUpdates { base_url, client: reqwest::Client::new() }
+    }
+
+    /// Upload a finished aggregate from disk without reading it into memory
+    pub async fn upload(&self, path: &std::path::Path) -> Result<String, String> {
+        let file = tokio::fs::File::open(path).await.map_err(|e| e.to_string())?;
+        let response = self
+            .client
+            .post(format!("{}/svdb/upload", self.base_url))
+            .header("Content-Type", "application/octet-stream")
+            .body(reqwest::Body::from(file))
+            .send()
+            .await
+            .map_err(|e| format!("SVDB upload failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("SVDB upload failed with status: {}", response.status()));
+        }
+        let result: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+        result["cid"]
+            .as_str()
+            .map(|cid| format!("artha://{}", cid.trim_start_matches("artha://")))
+            .ok_or_else(|| "Missing CID in SVDB response".to_string())
+    }
+
+    async fn read_range(&self, cid: &str, offset: usize, len: usize) -> Result<Vec<f64>, String> {
+        let (start, end) = (offset * F64_BYTES, (offset + len) * F64_BYTES);
+        let response = self
+            .client
+            .get(format!("{}/svdb/download/{}", self.base_url, cid_path_segment(cid)))
+            .header("Range", format!("bytes={}-{}", start, end - 1))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        // A full-object reply would defeat the point; refuse it rather than buffer it
+        if response.status().as_u16() != 206 {
+            return Err(format!("SVDB did not serve range {}..{} of {} (HTTP {})", start, end, cid, response.status()));
+        }
+        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        if bytes.len() != end - start {
+            return Err(format!("Short read of {}: {} of {} bytes", cid, bytes.len(), end - start));
+        }
+        Ok(bytes
+            .chunks_exact(F64_BYTES)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect())
+    }
+}
+
+impl UpdateSource for SvdbUpdates {
+    async fn read_chunk(&self, update: &GradientUpdate, offset: usize, len: usize) -> Result<Vec<f64>, String> {
+        match &update.weights_cid {
+            Some(cid) => self.read_range(cid, offset, len).await,
+            None => update
+                .weights
+                .get(offset..offset + len)
+                .map(<[f64]>::to_vec)
+                .ok_or_else(|| format!("Update from {} has fewer than {} weights", update.participant, offset + len)),
+        }
+    }
+}
+
+/// Length of the weight vector every update must share
+pub fn num_params(updates: &[GradientUpdate]) -> Result<usize, String> {
+    let Some(first) = updates.first() else {
+        return Ok(0);
+    };
+    let len = first.num_params();
+    match updates.iter().find(|u| u.num_params() != len) {
+        Some(other) => Err(format!(
+            "Update from {} has {} weights, expected {}",
+            other.participant,
+            other.num_params(),
+            len
+        )),
+        None => Ok(len),
+    }
+}
+
+/// FedAvg, sum(w_i * n_i) / sum(n_i), emitted in order one chunk at a time.
+/// With no samples reported at all the first update is passed through.
+/// Returns the number of weights emitted.
+pub async fn federated_average<S: UpdateSource>(
+    source: &S,
+    updates: &[GradientUpdate],
+    chunk_params: usize,
+    mut emit: impl FnMut(&mut [f64]) -> Result<(), String>,
+) -> Result<usize, String> {
+    let num_params = num_params(updates)?;
+    let total_samples: u64 = updates.iter().map(|u| u.sample_count).sum();
+    let factors: Vec<f64> = updates
+        .iter()
+        .enumerate()
+        .map(|(i, u)| match total_samples {
+            0 if i == 0 => 1.0,
+            0 => 0.0,
+            total => u.sample_count as f64 / total as f64,
+        })
+        .collect();
+
+    let chunk_params = chunk_params.max(1);
+    let mut offset = 0;
+    while offset < num_params {
+        let len = chunk_params.min(num_params - offset);
+        // One range from every participant at once
+        let chunks = futures_util::future::try_join_all(updates.iter().map(|u| source.read_chunk(u, offset, len))).await?;
+
+        let mut aggregated = vec![0.0; len];
+        for (chunk, factor) in chunks.iter().zip(&factors) {
+            if chunk.len() != len {
+                return Err(format!("Expected {} weights at offset {}, got {}", len, offset, chunk.len()));
+            }
+            for (acc, w) in aggregated.iter_mut().zip(chunk) {
+                *acc += w * factor;
+            }
+        }
+        drop(chunks);
+        emit(&mut aggregated)?;
+        offset += len;
+    }
+    Ok(num_params)
+}
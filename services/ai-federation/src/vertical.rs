@@ -0,0 +1,452 @@
+//! Vertical Federated Learning
+//! Parties hold different features about the same entities; one of them,
+//! the label party, also holds the labels. Entities are aligned first by
+//! PSI (see `psi`), then every round is one step of split learning over a
+//! batch of the shared set:
+//!
+//! 1. The coordinator draws the round's sample order, a fresh permutation
+//!    of shared positions cut to the batch size. Parties map it to their rows.
+//! 2. Each feature party runs its bottom model over those rows and submits
+//!    the activations, batch × embedding_dim.
+//! 3. The coordinator sums them and hands only the sum to the label party,
+//!    which adds its own activations, computes the loss and returns dL/dz.
+//! 4. The coordinator relays that gradient, noised under DP, to every
+//!    feature party that contributed; each updates its bottom model locally.
+//!
+//! Raw features never transit the coordinator. It accepts activation and
+//! gradient tensors of exactly batch × embedding_dim, nothing else, and
+//! refuses feature parties declaring no more features than the embedding
+//! has outputs, whose activations could carry their features verbatim.
+//! Individual activations are dropped as soon as they are summed.
+//!
+//! Compensation splits the budget equally across the label party and each
+//! feature party, scaled by sample coverage: the share of batch samples in
+//! completed rounds the party contributed to. The uncovered rest is refunded.
+
+use crate::psi::{Alignment, PsiSession, PsiTask};
+use crate::secagg::Point;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(test)]
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerticalConfig {
+    pub label_party: String,
+    pub feature_parties: Vec<String>,
+    pub embedding_dim: usize,
+    #[serde(default)]
+    pub batch_size: Option<usize>, // Whole shared set when unset
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalPhase {
+    Aligning,
+    Training,
+}
+
+/// Status view; the alignment is a count only, never membership
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerticalSummary {
+    pub phase: VerticalPhase,
+    pub label_party: String,
+    pub feature_parties: Vec<String>,
+    pub embedding_dim: usize,
+    pub parties_blinded: usize,
+    pub alignment_size: Option<usize>,
+    pub rounds_completed: u32,
+}
+
+/// One party's own rows in the shared set: `rows[i]` is the shared position
+/// of its row i
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignmentView {
+    pub size: usize,
+    pub rows: Vec<Option<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundPhase {
+    Forward,  // Collecting feature-party activations
+    Backward, // Sum handed to the label party, awaiting dL/dz
+    Complete, // Gradient available to contributors
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundView {
+    pub round: u32,
+    pub phase: RoundPhase,
+    pub order: Vec<u32>, // Shared positions, one per batch sample
+    pub contributors: Vec<String>,
+}
+
+/// A batch × embedding_dim tensor for one round, in the round's sample order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundTensor {
+    pub round: u32,
+    pub values: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payout {
+    pub party: String,
+    pub coverage: f64,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Compensation {
+    pub budget: u64,
+    pub payouts: Vec<Payout>,
+    pub refund: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Round {
+    number: u32,
+    phase: RoundPhase,
+    order: Vec<u32>,
+    activations: BTreeMap<String, Vec<Vec<f64>>>,
+    contributors: Vec<String>,
+    sum: Vec<Vec<f64>>,
+    gradients: Vec<Vec<f64>>,
+}
+
+impl Round {
+    fn view(&self) -> RoundView {
+        RoundView {
+            round: self.number,
+            phase: self.phase,
+            order: self.order.clone(),
+            contributors: self.contributors.clone(),
+        }
+    }
+}
+
+/// Server side of a vertical job: alignment, then split-learning rounds
+#[derive(Debug, Clone)]
+pub struct VerticalSession {
+    pub config: VerticalConfig,
+    psi: Option<PsiSession>, // Dropped with its transcript once aligned
+    alignment: Option<Alignment>,
+    round: Option<Round>,
+    rounds_completed: u32,
+    samples_offered: u64,
+    samples_covered: BTreeMap<String, u64>,
+}
+
+impl VerticalSession {
+    pub fn new(config: VerticalConfig) -> Result<Self, StatusCode> {
+        if config.embedding_dim == 0
+            || config.feature_parties.is_empty()
+            || config.feature_parties.contains(&config.label_party)
+            || config.batch_size == Some(0)
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let parties: Vec<String> = std::iter::once(config.label_party.clone())
+            .chain(config.feature_parties.iter().cloned())
+            .collect();
+        Ok(Self {
+            psi: Some(PsiSession::new(&parties)?),
+            config,
+            alignment: None,
+            round: None,
+            rounds_completed: 0,
+            samples_offered: 0,
+            samples_covered: BTreeMap::new(),
+        })
+    }
+
+    /// The label party first, then the feature parties
+    pub fn parties(&self) -> Vec<String> {
+        std::iter::once(self.config.label_party.clone())
+            .chain(self.config.feature_parties.iter().cloned())
+            .collect()
+    }
+
+    fn is_feature_party(&self, party: &str) -> bool {
+        self.config.feature_parties.iter().any(|p| p == party)
+    }
+
+    pub fn summary(&self) -> VerticalSummary {
+        VerticalSummary {
+            phase: if self.alignment.is_some() { VerticalPhase::Training } else { VerticalPhase::Aligning },
+            label_party: self.config.label_party.clone(),
+            feature_parties: self.config.feature_parties.clone(),
+            embedding_dim: self.config.embedding_dim,
+            parties_blinded: match &self.psi {
+                Some(psi) => psi.blinded_count(),
+                None => self.config.feature_parties.len() + 1,
+            },
+            alignment_size: self.alignment.as_ref().map(|a| a.size),
+            rounds_completed: self.rounds_completed,
+        }
+    }
+
+    fn psi(&mut self) -> Result<&mut PsiSession, StatusCode> {
+        self.psi.as_mut().ok_or(StatusCode::CONFLICT)
+    }
+
+    /// Feature parties declare how many features their bottom model reads
+    pub fn submit_blinded(&mut self, party: &str, values: Vec<Point>, feature_count: Option<usize>) -> Result<(), StatusCode> {
+        if self.is_feature_party(party) && feature_count.is_none_or(|n| n <= self.config.embedding_dim) {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        self.psi()?.submit_blinded(party, values)
+    }
+
+    pub fn psi_tasks(&self, party: &str) -> Result<Vec<PsiTask>, StatusCode> {
+        match &self.psi {
+            Some(psi) => psi.tasks_for(party),
+            None if self.parties().iter().any(|p| p == party) => Ok(Vec::new()),
+            None => Err(StatusCode::FORBIDDEN),
+        }
+    }
+
+    /// Returns the alignment size once the last list is fully raised
+    pub fn submit_raised(&mut self, party: &str, owner: &str, values: Vec<Point>) -> Result<Option<usize>, StatusCode> {
+        let psi = self.psi()?;
+        psi.submit_raised(party, owner, values)?;
+        let Some(alignment) = psi.align() else {
+            return Ok(None);
+        };
+        self.psi = None;
+        let size = alignment.size;
+        self.alignment = Some(alignment);
+        Ok(Some(size))
+    }
+
+    pub fn alignment_for(&self, party: &str) -> Result<AlignmentView, StatusCode> {
+        let alignment = self.alignment.as_ref().ok_or(StatusCode::PRECONDITION_FAILED)?;
+        let rows = alignment.rows_of(party).ok_or(StatusCode::FORBIDDEN)?;
+        Ok(AlignmentView { size: alignment.size, rows: rows.to_vec() })
+    }
+
+    pub fn start_round(&mut self) -> Result<RoundView, StatusCode> {
+        use rand::seq::SliceRandom;
+        let size = self.alignment.as_ref().ok_or(StatusCode::PRECONDITION_FAILED)?.size;
+        if size == 0 {
+            return Err(StatusCode::PRECONDITION_FAILED);
+        }
+        if self.round.as_ref().is_some_and(|r| r.phase != RoundPhase::Complete) {
+            return Err(StatusCode::CONFLICT);
+        }
+        let mut order: Vec<u32> = (0..size as u32).collect();
+        order.shuffle(&mut rand::thread_rng());
+        order.truncate(self.config.batch_size.unwrap_or(size));
+
+        let round = Round {
+            number: self.round.as_ref().map_or(1, |r| r.number + 1),
+            phase: RoundPhase::Forward,
+            order,
+            activations: BTreeMap::new(),
+            contributors: Vec::new(),
+            sum: Vec::new(),
+            gradients: Vec::new(),
+        };
+        let view = round.view();
+        self.round = Some(round);
+        Ok(view)
+    }
+
+    pub fn current_round(&self) -> Option<RoundView> {
+        self.round.as_ref().map(Round::view)
+    }
+
+    /// The current round, if it is round `number` and in `phase`
+    fn round_in(&mut self, number: u32, phase: RoundPhase) -> Result<&mut Round, StatusCode> {
+        match self.round.as_mut() {
+            Some(round) if round.number == number && round.phase == phase => Ok(round),
+            Some(_) => Err(StatusCode::CONFLICT),
+            None => Err(StatusCode::PRECONDITION_FAILED),
+        }
+    }
+
+    fn check_shape(&self, values: &[Vec<f64>], batch: usize) -> Result<(), StatusCode> {
+        if values.len() != batch
+            || values.iter().any(|row| row.len() != self.config.embedding_dim || !row.iter().all(|v| v.is_finite()))
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Ok(())
+    }
+
+    pub fn submit_activations(&mut self, party: &str, tensor: RoundTensor) -> Result<(), StatusCode> {
+        if !self.is_feature_party(party) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let batch = self.round_in(tensor.round, RoundPhase::Forward)?.order.len();
+        self.check_shape(&tensor.values, batch)?;
+        let round = self.round_in(tensor.round, RoundPhase::Forward)?;
+        if round.activations.contains_key(party) {
+            return Err(StatusCode::CONFLICT);
+        }
+        round.activations.insert(party.to_string(), tensor.values);
+        Ok(())
+    }
+
+    /// Summed feature-party activations for the label party. The first
+    /// fetch closes the forward pass; late activations are refused.
+    pub fn forward(&mut self, party: &str) -> Result<RoundTensor, StatusCode> {
+        if party != self.config.label_party {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let dim = self.config.embedding_dim;
+        let round = self.round.as_mut().ok_or(StatusCode::PRECONDITION_FAILED)?;
+        if round.phase == RoundPhase::Forward {
+            if round.activations.is_empty() {
+                return Err(StatusCode::PRECONDITION_FAILED);
+            }
+            let mut sum = vec![vec![0.0; dim]; round.order.len()];
+            for values in round.activations.values() {
+                for (acc, row) in sum.iter_mut().zip(values) {
+                    acc.iter_mut().zip(row).for_each(|(a, v)| *a += v);
+                }
+            }
+            round.contributors = std::mem::take(&mut round.activations).into_keys().collect();
+            round.sum = sum;
+            round.phase = RoundPhase::Backward;
+        }
+        if round.phase != RoundPhase::Backward {
+            return Err(StatusCode::CONFLICT);
+        }
+        Ok(RoundTensor { round: round.number, values: round.sum.clone() })
+    }
+
+    /// dL/dz from the label party, already noised when DP is on. Completes the round.
+    pub fn submit_gradients(&mut self, party: &str, tensor: RoundTensor) -> Result<(), StatusCode> {
+        if party != self.config.label_party {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let batch = self.round_in(tensor.round, RoundPhase::Backward)?.order.len();
+        self.check_shape(&tensor.values, batch)?;
+        let round = self.round_in(tensor.round, RoundPhase::Backward)?;
+        round.gradients = tensor.values;
+        round.sum.clear();
+        round.phase = RoundPhase::Complete;
+
+        let covered: Vec<String> = std::iter::once(party.to_string()).chain(round.contributors.iter().cloned()).collect();
+        for p in covered {
+            *self.samples_covered.entry(p).or_default() += batch as u64;
+        }
+        self.samples_offered += batch as u64;
+        self.rounds_completed += 1;
+        Ok(())
+    }
+
+    /// The round's gradient, for feature parties that contributed to it
+    pub fn backward(&self, party: &str) -> Result<RoundTensor, StatusCode> {
+        if !self.is_feature_party(party) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        let round = self.round.as_ref().ok_or(StatusCode::PRECONDITION_FAILED)?;
+        if round.phase != RoundPhase::Complete {
+            return Err(StatusCode::PRECONDITION_FAILED);
+        }
+        if !round.contributors.iter().any(|p| p == party) {
+            return Err(StatusCode::CONFLICT);
+        }
+        Ok(RoundTensor { round: round.number, values: round.gradients.clone() })
+    }
+
+    pub fn compensation(&self, budget: u64) -> Compensation {
+        let parties = self.parties();
+        let share = budget as f64 / parties.len() as f64;
+        let payouts: Vec<Payout> = parties
+            .into_iter()
+            .map(|party| {
+                let covered = self.samples_covered.get(&party).copied().unwrap_or(0);
+                let coverage = if self.samples_offered == 0 { 0.0 } else { covered as f64 / self.samples_offered as f64 };
+                Payout { amount: (share * coverage).floor() as u64, party, coverage }
+            })
+            .collect();
+        let paid: u64 = payouts.iter().map(|p| p.amount).sum();
+        Compensation { budget, refund: budget - paid, payouts }
+    }
+}
+
+/// Client side of a round: a party's rows and its linear bottom model,
+/// feature_count × embedding_dim. The reference the tests drive rounds with.
+#[cfg(test)]
+pub struct VerticalParty {
+    pub id: String,
+    pub features: Vec<Vec<f64>>,
+    pub weights: Vec<Vec<f64>>,
+    shared_rows: HashMap<u32, usize>,
+}
+
+#[cfg(test)]
+impl VerticalParty {
+    pub fn new(id: &str, features: Vec<Vec<f64>>, weights: Vec<Vec<f64>>) -> Self {
+        Self { id: id.to_string(), features, weights, shared_rows: HashMap::new() }
+    }
+
+    pub fn align(&mut self, view: &AlignmentView) {
+        self.shared_rows = view
+            .rows
+            .iter()
+            .enumerate()
+            .filter_map(|(row, pos)| pos.map(|p| (p, row)))
+            .collect();
+    }
+
+    fn rows<'a>(&'a self, order: &'a [u32]) -> impl Iterator<Item = &'a Vec<f64>> {
+        order.iter().map(|pos| &self.features[self.shared_rows[pos]])
+    }
+
+    /// X·W over the batch
+    pub fn activations(&self, order: &[u32]) -> Vec<Vec<f64>> {
+        let dim = self.weights.first().map_or(0, Vec::len);
+        self.rows(order)
+            .map(|x| {
+                (0..dim)
+                    .map(|j| x.iter().zip(&self.weights).map(|(xi, w)| xi * w[j]).sum())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// dL/dW = Xᵀ·dL/dz over the batch
+    pub fn weight_gradient(&self, order: &[u32], gradients: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let mut grad: Vec<Vec<f64>> = self.weights.iter().map(|w| vec![0.0; w.len()]).collect();
+        for (x, g) in self.rows(order).zip(gradients) {
+            for (xi, row) in x.iter().zip(grad.iter_mut()) {
+                row.iter_mut().zip(g).for_each(|(acc, gj)| *acc += xi * gj);
+            }
+        }
+        grad
+    }
+}
+
+/// The label party's top model: the prediction is the sum of every
+/// embedding output, its own included, under squared error
+#[cfg(test)]
+pub struct LabelParty {
+    pub party: VerticalParty,
+    pub labels: Vec<f64>, // Per local row
+}
+
+#[cfg(test)]
+impl LabelParty {
+    /// Loss and dL/dz for the batch, given the coordinator's activation sum
+    pub fn loss_gradient(&self, order: &[u32], feature_sum: &[Vec<f64>]) -> (f64, Vec<Vec<f64>>) {
+        let n = order.len() as f64;
+        let own = self.party.activations(order);
+        let mut loss = 0.0;
+        let gradients = order
+            .iter()
+            .zip(own.iter().zip(feature_sum))
+            .map(|(pos, (mine, theirs))| {
+                let prediction: f64 = mine.iter().chain(theirs).sum();
+                let residual = prediction - self.labels[self.party.shared_rows[pos]];
+                loss += residual * residual / (2.0 * n);
+                vec![residual / n; mine.len()]
+            })
+            .collect();
+        (loss, gradients)
+    }
+}
@@ -0,0 +1,107 @@
+//! Model A/B Routing
+//! Splits inference traffic for a model id across weighted variants
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelVariant {
+    pub variant_id: String,
+    pub model_id: String, // Model actually served for this variant
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VariantMetrics {
+    pub requests: u64,
+    pub budget_total: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct AbRouter {
+    routes: HashMap<String, Vec<ModelVariant>>,        // model_id -> variants
+    metrics: HashMap<String, HashMap<String, VariantMetrics>>, // model_id -> variant_id -> metrics
+}
+
+impl AbRouter {
+    pub fn new() -> Self {
+        AbRouter::default()
+    }
+
+    pub fn set_route(&mut self, model_id: &str, variants: Vec<ModelVariant>) -> Result<(), String> {
+        if variants.is_empty() {
+            return Err("At least one variant is required".to_string());
+        }
+        if variants.iter().map(|v| v.weight as u64).sum::<u64>() == 0 {
+            return Err("Variant weights must not all be zero".to_string());
+        }
+
+        self.routes.insert(model_id.to_string(), variants);
+        self.metrics.remove(model_id);
+        Ok(())
+    }
+
+    pub fn remove_route(&mut self, model_id: &str) -> bool {
+        self.metrics.remove(model_id);
+        self.routes.remove(model_id).is_some()
+    }
+
+    /// Pick a variant for a request. A bucketing key routes deterministically;
+    /// otherwise the supplied entropy decides.
+    pub fn route(&mut self, model_id: &str, bucketing_key: Option<&str>, entropy: u64) -> Option<ModelVariant> {
+        let variants = self.routes.get(model_id)?;
+        let bucket = match bucketing_key {
+            Some(key) => bucket_for_key(model_id, key),
+            None => entropy,
+        };
+        let variant = select_variant(variants, bucket).clone();
+
+        self.metrics
+            .entry(model_id.to_string())
+            .or_default()
+            .entry(variant.variant_id.clone())
+            .or_default()
+            .requests += 1;
+
+        Some(variant)
+    }
+
+    pub fn record_budget(&mut self, model_id: &str, variant_id: &str, budget: u64) {
+        let metrics = self.metrics
+            .entry(model_id.to_string())
+            .or_default()
+            .entry(variant_id.to_string())
+            .or_default();
+        metrics.budget_total += budget;
+    }
+
+    pub fn variants(&self, model_id: &str) -> Option<&Vec<ModelVariant>> {
+        self.routes.get(model_id)
+    }
+
+    pub fn metrics(&self, model_id: &str) -> HashMap<String, VariantMetrics> {
+        self.metrics.get(model_id).cloned().unwrap_or_default()
+    }
+}
+
+fn bucket_for_key(model_id: &str, key: &str) -> u64 {
+    let hash = Keccak256::digest(format!("{}:{}", model_id, key).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+fn select_variant(variants: &[ModelVariant], bucket: u64) -> &ModelVariant {
+    let total: u64 = variants.iter().map(|v| v.weight as u64).sum();
+    let mut point = bucket % total;
+
+    for variant in variants {
+        if point < variant.weight as u64 {
+            return variant;
+        }
+        point -= variant.weight as u64;
+    }
+
+    &variants[variants.len() - 1]
+}
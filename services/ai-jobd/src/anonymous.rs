@@ -0,0 +1,151 @@
+//! Anonymous Inference
+//! Inference submitted from an ephemeral account instead of a DID. The
+//! submission is signed by the ephemeral key, and the job records only its
+//! address. Output links are issued to whoever can sign for that address.
+//! Anonymous traffic is held to a stricter policy than DID-linked traffic:
+//! a configured model set, a lower budget cap, and mandatory moderation.
+//! Rate limits apply per address and to the anonymous pool as a whole, so
+//! minting fresh addresses doesn't buy unlimited capacity.
+
+use artha_errors::{ErrorCode, ServiceError};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use sha3::{Digest, Keccak256};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// How long a signed output-link request stays usable
+pub const LINK_SIGNATURE_MAX_AGE_SECS: u64 = 300;
+
+#[derive(Debug, Clone)]
+pub struct AnonymousPolicy {
+    pub models: HashSet<String>, // Models anonymous jobs may use; empty disables anonymous inference
+    pub max_budget: u64,
+    pub address_limit: usize, // Submissions per ephemeral address per window
+    pub pool_limit: usize,    // Submissions from all ephemeral addresses per window
+    pub window_secs: u64,
+}
+
+impl AnonymousPolicy {
+    /// Policy from `ARTHA_ANON_MODELS` (comma-separated), `ARTHA_ANON_MAX_BUDGET`,
+    /// `ARTHA_ANON_ADDRESS_LIMIT`, `ARTHA_ANON_POOL_LIMIT` and `ARTHA_ANON_WINDOW_SECS`
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        AnonymousPolicy {
+            models: std::env::var("ARTHA_ANON_MODELS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(str::to_string)
+                .collect(),
+            max_budget: var("ARTHA_ANON_MAX_BUDGET", 1_000),
+            address_limit: var("ARTHA_ANON_ADDRESS_LIMIT", 10) as usize,
+            pool_limit: var("ARTHA_ANON_POOL_LIMIT", 500) as usize,
+            window_secs: var("ARTHA_ANON_WINDOW_SECS", 3600),
+        }
+    }
+
+    /// Refuse what anonymous jobs may not do. `model_ids` are the requested
+    /// and the served model; both must be allowed.
+    pub fn check(&self, model_ids: &[&str], budget: u64) -> Result<(), ServiceError> {
+        if self.models.is_empty() {
+            return Err(ServiceError::new(ErrorCode::Forbidden, "Anonymous inference is not enabled on this deployment"));
+        }
+        if let Some(model_id) = model_ids.iter().find(|id| !self.models.contains(**id)) {
+            return Err(ServiceError::policy_denied(format!("Model {} is not available to anonymous jobs", model_id))
+                .with_details(serde_json::json!({ "allowed_models": self.models })));
+        }
+        if budget > self.max_budget {
+            return Err(ServiceError::policy_denied(format!(
+                "Budget {} exceeds the anonymous cap of {}",
+                budget, self.max_budget
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Sliding-window submission counts. Only submission times are kept, never
+/// which job a submission became.
+#[derive(Debug, Default)]
+pub struct AnonLimiter {
+    by_address: HashMap<String, VecDeque<u64>>,
+    pool: VecDeque<u64>,
+}
+
+impl AnonLimiter {
+    /// Count a submission from `address` at `now`, or refuse it with the
+    /// wait until the window that is full frees a slot
+    pub fn admit(&mut self, address: &str, now: u64, policy: &AnonymousPolicy) -> Result<(), ServiceError> {
+        let cutoff = now.saturating_sub(policy.window_secs);
+        let prune = |times: &mut VecDeque<u64>| {
+            while times.front().is_some_and(|t| *t <= cutoff) {
+                times.pop_front();
+            }
+        };
+        prune(&mut self.pool);
+        self.by_address.retain(|_, times| {
+            prune(times);
+            !times.is_empty()
+        });
+
+        let retry_after = |times: &VecDeque<u64>| times.front().map_or(1, |t| (t + policy.window_secs).saturating_sub(now).max(1));
+        let own = self.by_address.get(address);
+        if own.is_some_and(|times| times.len() >= policy.address_limit) {
+            return Err(ServiceError::new(ErrorCode::RateLimited, "Too many anonymous submissions from this address")
+                .with_retry_after(own.map_or(1, retry_after)));
+        }
+        if self.pool.len() >= policy.pool_limit {
+            return Err(ServiceError::new(ErrorCode::RateLimited, "Anonymous submission pool is saturated")
+                .with_retry_after(retry_after(&self.pool)));
+        }
+        self.pool.push_back(now);
+        self.by_address.entry(address.to_string()).or_default().push_back(now);
+        Ok(())
+    }
+}
+
+fn keccak(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Digest the ephemeral key signs to submit: the model, the input (its CID,
+/// or the inline content), the budget and the submission nonce
+pub fn submission_digest(model_id: &str, input: &str, budget: u64, nonce: u64) -> [u8; 32] {
+    keccak(&[
+        b"ARTHA_ANON_INFER:",
+        model_id.as_bytes(),
+        b"|",
+        &keccak(&[input.as_bytes()]),
+        &budget.to_be_bytes(),
+        &nonce.to_be_bytes(),
+    ])
+}
+
+/// Digest the ephemeral key signs to be issued a link to a job's output
+pub fn link_digest(job_id: &str, signed_at: u64) -> [u8; 32] {
+    keccak(&[b"ARTHA_ANON_LINK:", job_id.as_bytes(), b"|", &signed_at.to_be_bytes()])
+}
+
+/// `0x`-prefixed lowercase address of the key that produced a 65-byte
+/// `r || s || v` signature over `digest`
+pub fn recover_address(digest: &[u8; 32], signature: &str) -> Result<String, String> {
+    let bytes = hex::decode(signature.trim_start_matches("0x")).map_err(|e| format!("Signature is not hex: {}", e))?;
+    if bytes.len() != 65 {
+        return Err(format!("Signature is {} bytes, expected 65", bytes.len()));
+    }
+    let sig = Signature::from_slice(&bytes[..64]).map_err(|e| format!("Malformed signature: {}", e))?;
+    let v = if bytes[64] >= 27 { bytes[64] - 27 } else { bytes[64] };
+    let recovery_id = RecoveryId::from_byte(v).ok_or("Bad recovery id")?;
+    let key = VerifyingKey::recover_from_prehash(digest, &sig, recovery_id).map_err(|e| format!("Unrecoverable signature: {}", e))?;
+    let point = key.to_encoded_point(false);
+    Ok(format!("0x{}", hex::encode(&keccak(&[&point.as_bytes()[1..]])[12..])))
+}
+
+/// Whether a principal is an ephemeral address rather than a DID
+pub fn is_address(principal: &str) -> bool {
+    principal.len() == 42 && principal.starts_with("0x") && principal[2..].bytes().all(|b| b.is_ascii_hexdigit())
+}
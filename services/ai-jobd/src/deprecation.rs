@@ -0,0 +1,81 @@
+//! Deprecation Enforcement
+//! Applies deprecation feed enforcement levels to job submissions that
+//! reference deprecated models or datasets
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnforcementLevel {
+    #[default]
+    Warn,
+    SoftBlock,
+    HardBlock,
+}
+
+/// Subset of the deprecation feed announcement used for enforcement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeprecationEntry {
+    pub id: String,
+    pub component: String, // "model", "dataset", ...
+    pub name: String,      // Model or dataset id
+    pub sunset_date: u64,
+    pub replacement: String,
+    #[serde(default)]
+    pub enforcement: EnforcementLevel,
+}
+
+/// Check the resources a submission references. Returns warning values for
+/// the `Warning` response header, or the rejection status.
+pub fn enforce(
+    entries: &[DeprecationEntry],
+    resources: &[(&str, &str)], // (component, id)
+    allow_deprecated: bool,
+) -> Result<Vec<String>, StatusCode> {
+    let mut warnings = Vec::new();
+
+    for entry in entries {
+        if !resources.iter().any(|(component, id)| entry.component == *component && entry.name == *id) {
+            continue;
+        }
+
+        match entry.enforcement {
+            EnforcementLevel::HardBlock => {
+                warn!("⛔ Rejected submission using {} {} ({})", entry.component, entry.name, entry.id);
+                return Err(StatusCode::GONE);
+            }
+            EnforcementLevel::SoftBlock if !allow_deprecated => {
+                warn!("⚠️  Submission using {} {} needs allow_deprecated ({})", entry.component, entry.name, entry.id);
+                return Err(StatusCode::PRECONDITION_REQUIRED);
+            }
+            _ => {}
+        }
+
+        warn!("⚠️  Submission uses deprecated {} {} ({})", entry.component, entry.name, entry.id);
+        warnings.push(format!(
+            "299 ai-jobd \"{} {} is deprecated ({}); use {}\"",
+            entry.component, entry.name, entry.id, entry.replacement
+        ));
+    }
+
+    Ok(warnings)
+}
+
+/// Fetch active deprecations from the feed. An unreachable feed never blocks
+/// submissions.
+pub async fn fetch_active(feed_url: &str) -> Vec<DeprecationEntry> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/deprecations/active", feed_url))
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) if resp.status().is_success() => resp.json().await.unwrap_or_default(),
+        _ => {
+            warn!("⚠️  Deprecation feed unavailable at {}", feed_url);
+            Vec::new()
+        }
+    }
+}
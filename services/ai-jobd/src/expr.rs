@@ -0,0 +1,350 @@
+//! Workflow Expressions
+//! The small, side-effect-free language workflow conditions (`when`) and
+//! input bindings (`${...}`) are written in. Expressions only read the
+//! workflow context they are given; there are no calls, assignments or
+//! loops, and source length and nesting depth are bounded.
+//!
+//! ```text
+//! expr    := or
+//! or      := and ( "||" and )*
+//! and     := unary ( "&&" unary )*
+//! unary   := "!" unary | compare
+//! compare := sum ( ( "==" | "!=" | "<" | "<=" | ">" | ">=" ) sum )?
+//! sum     := product ( ( "+" | "-" ) product )*
+//! product := neg ( ( "*" | "/" ) neg )*
+//! neg     := "-" neg | atom
+//! atom    := number | string | "true" | "false" | "null" | path | "(" expr ")"
+//! path    := ident ( "." ( ident | digits ) )*
+//! ```
+//!
+//! Strings are single- or double-quoted without escapes. A path that names
+//! nothing is `null`; digit segments index arrays. `==` and `!=` compare any
+//! values, ordering needs two numbers or two strings, arithmetic needs
+//! numbers, and the logical operators need booleans and short-circuit.
+
+use serde_json::Value;
+
+pub const MAX_SOURCE_LEN: usize = 512;
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Path(Vec<String>),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+const OPERATORS: [&str; 15] = ["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "+", "-", "*", "/", "(", ")"];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(text.parse().map_err(|_| format!("Bad number '{}'", text))?));
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&d| d == c)
+                .ok_or_else(|| "Unterminated string".to_string())?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut segments = Vec::new();
+            loop {
+                let start = i;
+                let is_index = chars.get(i).is_some_and(|d| d.is_ascii_digit()) && !segments.is_empty();
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    if is_index && !chars[i].is_ascii_digit() {
+                        return Err(format!("Bad path segment at offset {}", start));
+                    }
+                    i += 1;
+                }
+                if i == start {
+                    return Err(format!("Empty path segment at offset {}", start));
+                }
+                segments.push(chars[start..i].iter().collect());
+                if chars.get(i) != Some(&'.') {
+                    break;
+                }
+                i += 1;
+            }
+            tokens.push(Token::Path(segments));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("Unexpected '{}' at offset {}", c, i))?;
+            tokens.push(match *op {
+                "(" => Token::LParen,
+                ")" => Token::RParen,
+                op => Token::Op(op),
+            });
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Literal(Value),
+    Path(Vec<String>),
+    Not(Box<Node>),
+    Neg(Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek_op(&self, ops: &[&str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("Expression nests deeper than {}", MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    /// One left-associative precedence level
+    fn binary(&mut self, ops: &[&str], next: fn(&mut Parser) -> Result<Node, String>) -> Result<Node, String> {
+        let mut left = next(self)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            left = Node::Binary(op, Box::new(left), Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        self.enter()?;
+        let node = self.binary(&["||"], Parser::and);
+        self.depth -= 1;
+        node
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        self.binary(&["&&"], Parser::unary)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.peek_op(&["!"]).is_some() {
+            self.pos += 1;
+            self.enter()?;
+            let inner = self.unary()?;
+            self.depth -= 1;
+            return Ok(Node::Not(Box::new(inner)));
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Result<Node, String> {
+        let left = self.sum()?;
+        match self.peek_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            Some(op) => {
+                self.pos += 1;
+                Ok(Node::Binary(op, Box::new(left), Box::new(self.sum()?)))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Node, String> {
+        self.binary(&["+", "-"], Parser::product)
+    }
+
+    fn product(&mut self) -> Result<Node, String> {
+        self.binary(&["*", "/"], Parser::neg)
+    }
+
+    fn neg(&mut self) -> Result<Node, String> {
+        if self.peek_op(&["-"]).is_some() {
+            self.pos += 1;
+            self.enter()?;
+            let inner = self.neg()?;
+            self.depth -= 1;
+            return Ok(Node::Neg(Box::new(inner)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| "Unexpected end of expression".to_string())?;
+        self.pos += 1;
+        match token {
+            Token::Number(n) => Ok(Node::Literal(serde_json::json!(n))),
+            Token::Str(s) => Ok(Node::Literal(Value::String(s))),
+            Token::Path(segments) if segments.len() == 1 => Ok(match segments[0].as_str() {
+                "true" => Node::Literal(Value::Bool(true)),
+                "false" => Node::Literal(Value::Bool(false)),
+                "null" => Node::Literal(Value::Null),
+                _ => Node::Path(segments),
+            }),
+            Token::Path(segments) => Ok(Node::Path(segments)),
+            Token::LParen => {
+                let inner = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    return Err("Missing ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::RParen => Err("Unexpected ')'".to_string()),
+            Token::Op(op) => Err(format!("Unexpected '{}'", op)),
+        }
+    }
+}
+
+/// A parsed expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    root: Node,
+}
+
+impl Expr {
+    pub fn parse(src: &str) -> Result<Expr, String> {
+        if src.len() > MAX_SOURCE_LEN {
+            return Err(format!("Expression is longer than {} bytes", MAX_SOURCE_LEN));
+        }
+        let mut parser = Parser { tokens: tokenize(src)?, pos: 0, depth: 0 };
+        if parser.tokens.is_empty() {
+            return Err("Empty expression".to_string());
+        }
+        let root = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(format!("Unexpected trailing input in '{}'", src));
+        }
+        Ok(Expr { root })
+    }
+
+    /// Every path the expression reads, in source order
+    pub fn paths(&self) -> Vec<Vec<String>> {
+        fn walk(node: &Node, out: &mut Vec<Vec<String>>) {
+            match node {
+                Node::Literal(_) => {}
+                Node::Path(segments) => out.push(segments.clone()),
+                Node::Not(inner) | Node::Neg(inner) => walk(inner, out),
+                Node::Binary(_, left, right) => {
+                    walk(left, out);
+                    walk(right, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.root, &mut out);
+        out
+    }
+
+    pub fn eval(&self, context: &Value) -> Result<Value, String> {
+        eval(&self.root, context)
+    }
+
+    /// Evaluate as a condition, which must come out boolean
+    pub fn eval_bool(&self, context: &Value) -> Result<bool, String> {
+        match self.eval(context)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(format!("Condition evaluated to {}, not a boolean", other)),
+        }
+    }
+}
+
+fn lookup(context: &Value, segments: &[String]) -> Value {
+    let mut current = context;
+    for segment in segments {
+        let next = match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        };
+        match next {
+            Some(value) => current = value,
+            None => return Value::Null,
+        }
+    }
+    current.clone()
+}
+
+fn number(value: &Value, op: &str) -> Result<f64, String> {
+    value.as_f64().ok_or_else(|| format!("'{}' needs numbers, got {}", op, value))
+}
+
+fn boolean(value: &Value, op: &str) -> Result<bool, String> {
+    value.as_bool().ok_or_else(|| format!("'{}' needs booleans, got {}", op, value))
+}
+
+fn eval(node: &Node, context: &Value) -> Result<Value, String> {
+    match node {
+        Node::Literal(value) => Ok(value.clone()),
+        Node::Path(segments) => Ok(lookup(context, segments)),
+        Node::Not(inner) => Ok(Value::Bool(!boolean(&eval(inner, context)?, "!")?)),
+        Node::Neg(inner) => Ok(serde_json::json!(-number(&eval(inner, context)?, "-")?)),
+        Node::Binary(op @ ("&&" | "||"), left, right) => {
+            let left = boolean(&eval(left, context)?, op)?;
+            if (*op == "&&") != left {
+                return Ok(Value::Bool(left));
+            }
+            Ok(Value::Bool(boolean(&eval(right, context)?, op)?))
+        }
+        Node::Binary(op, left, right) => {
+            let (left, right) = (eval(left, context)?, eval(right, context)?);
+            match *op {
+                "==" | "!=" => {
+                    let equal = match (left.as_f64(), right.as_f64()) {
+                        (Some(a), Some(b)) => a == b,
+                        _ => left == right,
+                    };
+                    Ok(Value::Bool(equal == (*op == "==")))
+                }
+                "<" | "<=" | ">" | ">=" => {
+                    let ordering = match (&left, &right) {
+                        (Value::String(a), Value::String(b)) => a.cmp(b),
+                        _ => number(&left, op)?
+                            .partial_cmp(&number(&right, op)?)
+                            .ok_or_else(|| format!("Cannot order {} and {}", left, right))?,
+                    };
+                    Ok(Value::Bool(match *op {
+                        "<" => ordering.is_lt(),
+                        "<=" => ordering.is_le(),
+                        ">" => ordering.is_gt(),
+                        _ => ordering.is_ge(),
+                    }))
+                }
+                _ => {
+                    let (a, b) = (number(&left, op)?, number(&right, op)?);
+                    let result = match *op {
+                        "+" => a + b,
+                        "-" => a - b,
+                        "*" => a * b,
+                        _ if b == 0.0 => return Err("Division by zero".to_string()),
+                        _ => a / b,
+                    };
+                    Ok(serde_json::json!(result))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Job Cost Escrow
+//! A job's estimated cost is locked from its submitter's balance in
+//! DealMarket (`escrowJob`) before the job goes on-chain, so the balance
+//! can't be drained between scheduling and payout. A submission whose cost
+//! can't be escrowed is rejected. When the job completes, one
+//! `settleJobEscrow` call pays the provider what the job spent, up to the
+//! escrowed amount, and refunds the rest to the submitter; failed and
+//! cancelled jobs are refunded in full. A train job's escrow instead funds
+//! its milestone escrow at assignment, and ai-proofs pays it out from there.
+//! An escrow is claimed before its settlement is sent, so overlapping
+//! sweeps never settle it twice; a failed settlement is retried by the next.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEscrow {
+    pub job_id: String,
+    pub payer: String, // Submitter DID, or the ephemeral address of an anonymous job
+    pub amount: u64,
+    pub escrow_tx: String,
+    pub locked_at: u64,
+    pub funds_milestones: bool, // Handed to the job's milestone escrow in ai-proofs
+    pub settlement: Option<EscrowSettlement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EscrowSettlement {
+    pub provider: Option<String>,
+    pub paid: u64,
+    pub refunded: u64,
+    pub tx_hash: Option<String>, // None while the settlement is in flight
+    pub settled_at: u64,
+}
+
+impl JobEscrow {
+    pub fn new(job_id: &str, payer: &str, amount: u64, escrow_tx: String, now: u64) -> Self {
+        JobEscrow {
+            job_id: job_id.to_string(),
+            payer: payer.to_string(),
+            amount,
+            escrow_tx,
+            locked_at: now,
+            funds_milestones: false,
+            settlement: None,
+        }
+    }
+
+    /// How the escrow splits once the job has ended: a completed job's
+    /// provider is paid what it spent, capped at the escrow, and the payer
+    /// gets back the rest
+    pub fn split(&self, completed: bool, spent: u64) -> (u64, u64) {
+        let paid = if completed { spent.min(self.amount) } else { 0 };
+        (paid, self.amount - paid)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct JobEscrowStore {
+    escrows: HashMap<String, JobEscrow>, // Job ID -> escrow
+}
+
+impl JobEscrowStore {
+    pub fn insert(&mut self, escrow: JobEscrow) {
+        self.escrows.insert(escrow.job_id.clone(), escrow);
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<&JobEscrow> {
+        self.escrows.get(job_id)
+    }
+
+    pub fn get_mut(&mut self, job_id: &str) -> Option<&mut JobEscrow> {
+        self.escrows.get_mut(job_id)
+    }
+
+    pub fn remove(&mut self, job_id: &str) -> Option<JobEscrow> {
+        self.escrows.remove(job_id)
+    }
+
+    /// Escrows this service still has to settle
+    pub fn unsettled(&self) -> Vec<String> {
+        self.escrows
+            .values()
+            .filter(|escrow| !escrow.funds_milestones && escrow.settlement.is_none())
+            .map(|escrow| escrow.job_id.clone())
+            .collect()
+    }
+}
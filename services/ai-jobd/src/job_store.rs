@@ -0,0 +1,154 @@
+//! Job Store
+//! Where job records outlive the daemon. Handlers that change a job write it
+//! through here after updating the in-memory map, startup rebuilds the map
+//! from `list_jobs`, and status lookups fall back to `load_job`. The default
+//! store writes one JSON file per job under a data directory; the sled store
+//! keeps the same records in a single database. Tests use a store that lives
+//! in memory.
+
+use crate::Job;
+use std::path::PathBuf;
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(test)]
+use std::sync::Mutex;
+
+pub trait JobStore: Send + Sync {
+    fn save_job(&self, job: &Job) -> Result<(), String>;
+    fn load_job(&self, job_id: &str) -> Result<Option<Job>, String>;
+    fn list_jobs(&self) -> Result<Vec<Job>, String>;
+    fn delete_job(&self, job_id: &str) -> Result<(), String>;
+}
+
+/// Jobs as `<job_id>.json` files under `dir`, each replaced atomically on write
+pub struct FileJobStore {
+    dir: PathBuf,
+}
+
+impl FileJobStore {
+    pub fn open(dir: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create job data dir {}: {}", dir, e))?;
+        Ok(FileJobStore { dir: PathBuf::from(dir) })
+    }
+
+    fn path(&self, job_id: &str) -> Result<PathBuf, String> {
+        // Job ids name files, so nothing that could leave the directory
+        if job_id.is_empty() || !job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Job id {:?} can't name a file", job_id));
+        }
+        Ok(self.dir.join(format!("{}.json", job_id)))
+    }
+}
+
+impl JobStore for FileJobStore {
+    fn save_job(&self, job: &Job) -> Result<(), String> {
+        let path = self.path(&job.job_id)?;
+        let bytes = serde_json::to_vec(job).map_err(|e| e.to_string())?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+    }
+
+    fn load_job(&self, job_id: &str) -> Result<Option<Job>, String> {
+        match std::fs::read(self.path(job_id)?) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| format!("Corrupt record for {}: {}", job_id, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn list_jobs(&self) -> Result<Vec<Job>, String> {
+        let mut jobs = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue; // Including writes a crash cut short
+            }
+            let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+            jobs.push(serde_json::from_slice(&bytes).map_err(|e| format!("Corrupt job record {}: {}", path.display(), e))?);
+        }
+        Ok(jobs)
+    }
+
+    fn delete_job(&self, job_id: &str) -> Result<(), String> {
+        match std::fs::remove_file(self.path(job_id)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Jobs in a sled tree keyed by job id, flushed on every write
+pub struct SledJobStore {
+    db: sled::Db,
+}
+
+impl SledJobStore {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let db = sled::open(path).map_err(|e| format!("Failed to open job store at {}: {}", path, e))?;
+        Ok(SledJobStore { db })
+    }
+
+    fn flush(&self) -> Result<(), String> {
+        self.db.flush().map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+impl JobStore for SledJobStore {
+    fn save_job(&self, job: &Job) -> Result<(), String> {
+        let bytes = serde_json::to_vec(job).map_err(|e| e.to_string())?;
+        self.db.insert(job.job_id.as_bytes(), bytes).map_err(|e| e.to_string())?;
+        self.flush()
+    }
+
+    fn load_job(&self, job_id: &str) -> Result<Option<Job>, String> {
+        match self.db.get(job_id.as_bytes()).map_err(|e| e.to_string())? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| format!("Corrupt record for {}: {}", job_id, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn list_jobs(&self) -> Result<Vec<Job>, String> {
+        self.db
+            .iter()
+            .values()
+            .map(|bytes| {
+                let bytes = bytes.map_err(|e| e.to_string())?;
+                serde_json::from_slice(&bytes).map_err(|e| format!("Corrupt job record: {}", e))
+            })
+            .collect()
+    }
+
+    fn delete_job(&self, job_id: &str) -> Result<(), String> {
+        self.db.remove(job_id.as_bytes()).map_err(|e| e.to_string())?;
+        self.flush()
+    }
+}
+
+/// Jobs kept only for the life of the process
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryJobStore {
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+#[cfg(test)]
+impl JobStore for MemoryJobStore {
+    fn save_job(&self, job: &Job) -> Result<(), String> {
+        self.jobs.lock().unwrap().insert(job.job_id.clone(), job.clone());
+        Ok(())
+    }
+
+    fn load_job(&self, job_id: &str) -> Result<Option<Job>, String> {
+        Ok(self.jobs.lock().unwrap().get(job_id).cloned())
+    }
+
+    fn list_jobs(&self) -> Result<Vec<Job>, String> {
+        Ok(self.jobs.lock().unwrap().values().cloned().collect())
+    }
+
+    fn delete_job(&self, job_id: &str) -> Result<(), String> {
+        self.jobs.lock().unwrap().remove(job_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+//! Job Labels
+//! Free-form `key: value` metadata a submitter attaches to a job (experiment
+//! name, team, ticket). Labels are kept on the job record, indexed for
+//! search as `label:key=value`, and `GET /jobs?label.key=value` keeps only
+//! jobs carrying every label asked for.
+
+use std::collections::HashMap;
+
+pub type Labels = HashMap<String, String>;
+
+pub const MAX_LABELS: usize = 32;
+pub const MAX_KEY_LEN: usize = 63;
+pub const MAX_VALUE_LEN: usize = 255;
+
+/// Query parameters of the form `label.<key>=<value>`
+const QUERY_PREFIX: &str = "label.";
+
+/// Why a submission's labels were refused
+pub fn validate(labels: &Labels) -> Result<(), String> {
+    if labels.len() > MAX_LABELS {
+        return Err(format!("At most {} labels per job, got {}", MAX_LABELS, labels.len()));
+    }
+    for (key, value) in labels {
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(format!("Label key {:?} must be 1 to {} bytes", key, MAX_KEY_LEN));
+        }
+        // `=` separates key from value in the search index
+        if key.contains('=') || key.chars().any(char::is_whitespace) {
+            return Err(format!("Label key {:?} may not contain '=' or whitespace", key));
+        }
+        if value.len() > MAX_VALUE_LEN {
+            return Err(format!("Label {:?} value is longer than {} bytes", key, MAX_VALUE_LEN));
+        }
+    }
+    Ok(())
+}
+
+/// The `label.<key>=<value>` selectors among a listing's query parameters
+pub fn selector(params: &HashMap<String, String>) -> Labels {
+    params
+        .iter()
+        .filter_map(|(name, value)| Some((name.strip_prefix(QUERY_PREFIX)?.to_string(), value.clone())))
+        .collect()
+}
+
+/// Whether `labels` carries every selected label (AND semantics)
+pub fn matches(labels: &Labels, selector: &Labels) -> bool {
+    selector.iter().all(|(key, value)| labels.get(key) == Some(value))
+}
+
+/// Search index values, one `key=value` per label
+pub fn search_terms(labels: &Labels) -> impl Iterator<Item = String> + '_ {
+    labels.iter().map(|(key, value)| format!("{}={}", key, value))
+}
@@ -3,17 +3,77 @@
 
 use axum::{
     extract::{Path, Query, State, Json},
-    http::StatusCode,
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Router,
 };
+use artha_errors::{Deadline, ErrorCode, ServiceError, DEADLINE_HEADER};
+use artha_log::Sensitive;
+use artha_paging::{time_key, Page, PageQuery};
+use artha_retention::RetentionPolicy;
+use artha_cache::ReadThrough;
+use artha_clock::{Backoff, BackoffPolicy, Clock, Entropy, SharedClock, SharedEntropy};
+use artha_joblog::{JobLog, LogLimits};
+use artha_rpc::{Contract, ContractRegistry, EndpointHealth, RpcEndpoints, RpcError};
+use artha_tenant::Namespace;
+use abi::{abi_encode_bytes32, abi_encode_call, abi_encode_string, abi_encode_uint256, Token};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
 use sha3::{Keccak256, Digest};
 use std::str::FromStr;
+use tracing::{error, info, warn};
+
+mod ab_routing;
+mod anonymous;
+use anonymous::{AnonLimiter, AnonymousPolicy};
+use ab_routing::{AbRouter, ModelVariant};
+mod deprecation;
+mod manifest;
+use manifest::{ArtifactRegistry, DatasetVersion, DatasetWindow, JobManifest, RuntimeRequirements};
+mod job_escrow;
+use job_escrow::{EscrowSettlement, JobEscrow, JobEscrowStore};
+mod job_store;
+use job_store::{FileJobStore, JobStore, SledJobStore};
+mod labels;
+use labels::Labels;
+mod marketplace;
+mod model_card;
+use model_card::{ModelCard, ModelCardView};
+mod migration;
+use migration::{MigrateRequest, MigrationHandoff, MigrationRecord};
+mod outputs;
+use outputs::{OutputIntegrity, OutputState, OutputVault};
+mod pipeline;
+use pipeline::{ChildOutcome, FanOut, FanOutPolicy, FanOutRegistry, JoinDecision};
+mod schema;
+use schema::{ModelSchema, TensorSpec};
+mod timeline;
+use timeline::{EventKind, EventStore, Timeline};
+mod sponsor;
+use sponsor::Sponsor;
+mod stream;
+use stream::{StreamJobRequest, StreamSpec};
+mod policy_outage;
+use policy_outage::{CachedDecision, DecisionCache, DecisionKey, DegradedPolicy, FailMode, OutageConfig};
+mod quantize;
+mod reorg;
+use reorg::{ChainTracker, Inclusion, Observation, TxRole};
+mod reservations;
+mod result_cache;
+use result_cache::ResultCache;
+use reservations::{Booking, ForfeitureSchedule, JobRun, Refund, Reservation, ReservationRequest, ReservationStore, ReservationView};
+use quantize::{QuantizeConfig, QuantizeJobRequest, QuantizeSpec};
+mod openapi;
+mod search;
+use search::{SearchConfig, SearchDoc, SearchIndex, SearchRequest, SearchResults, SentTx, Viewer};
+mod versioning;
+mod expr;
+mod workflow;
+use workflow::{Launch, RunUpdate, StepAction, StepStatus, Workflow, WorkflowSpec, WorkflowStatus, WorkflowStore, WorkflowView};
+use marketplace::{AccessGrant, DatasetListing, ListingPrice, ListingStatus, Marketplace, PurchaseRequest, Settlement};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum JobType {
@@ -22,6 +82,8 @@ pub enum JobType {
     Agent,
     Federated,
     Evolution,
+    Stream,
+    Quantize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -53,7 +115,103 @@ pub struct Job {
     pub output_cid: Option<String>,
     pub artifacts: Vec<String>,
     pub progress: f32, // 0.0 to 1.0
-    pub logs: Vec<String>,
+    pub logs: JobLog, // Newest lines; the full log is archived to SVDB
+    #[serde(default)]
+    pub tee_required: bool,
+    pub attestation: Option<AttestationSummary>,
+    #[serde(default)]
+    pub ab_variant: Option<String>,
+    #[serde(default)]
+    pub manifest: Option<JobManifest>, // Locked inputs for exact re-runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>, // RNG seed train containers run with; also locked in the manifest
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub live_migration: bool, // Drains and reclaims move the job instead of letting it run out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub migrations: Vec<MigrationRecord>, // Oldest first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_reason: Option<TerminalReason>, // Set when the job ends Failed or Cancelled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub anonymous: bool, // Submitted from an ephemeral account: `submitter` is its address and there is no DID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_provenance: Option<serde_json::Value>, // policy-gate's verdict on the dataset's consent VCs
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: Labels, // Submitter metadata; see `labels`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_since: Option<u64>, // Restored Assigned or Running after a restart, until ai-runtime confirms where it got to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_duration_secs: Option<u64>, // Submission-time runtime estimate; the scheduler keeps it off nodes due for maintenance
+}
+
+impl Job {
+    /// Who owns the job's outputs: the submitter DID, or an anonymous job's ephemeral address
+    pub fn owner(&self) -> &str {
+        if self.anonymous {
+            &self.submitter
+        } else {
+            &self.submitter_did
+        }
+    }
+
+    /// Tenant namespace the job belongs to, that of its owner
+    pub fn namespace(&self) -> Namespace {
+        Namespace::of(self.owner())
+    }
+}
+
+/// Why a job ended Failed or Cancelled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerminalReason {
+    pub kind: TerminalReasonKind,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>, // Container exit code, for runtime failures
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalReasonKind {
+    UserCancelled,     // POST /job/:id/cancel
+    WorkflowCancelled, // Cancelled with its workflow
+    OutOfMemory,       // Container killed by the OOM killer
+    Timeout,           // Ran past its time limit on the node
+    ContainerExit,     // Container exited non-zero
+    RuntimeError,      // Any other failure ai-runtime reported
+    Unschedulable,     // No node could take the job
+    ModerationBlocked, // Output blocked by ai-ethics
+    QualityGate,       // Output failed the job's own acceptance check
+}
+
+impl TerminalReason {
+    fn new(kind: TerminalReasonKind, detail: impl Into<String>) -> Self {
+        TerminalReason { kind, detail: detail.into(), exit_code: None }
+    }
+}
+
+/// What retention GC keeps of an evicted job: enough to find it on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedJob {
+    pub job_id: String, // AIJobManager job id
+    pub status: JobStatus,
+    pub submitter_did: String,
+    pub model_id: Option<String>,
+    pub params_hash: String, // Committed on-chain at submission
+    pub assigned_node: Option<String>,
+    pub attestation: Option<AttestationSummary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_reason: Option<TerminalReason>,
+    pub completed_at: u64,
+    pub archived_at: u64,
+}
+
+/// Attestation outcome reported by ai-proofs for TEE jobs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationSummary {
+    pub tee_type: String,
+    pub measurement: String,
+    pub image_digest: String,
+    pub status: String,
+    pub verified_at: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,15 +221,48 @@ pub struct TrainJobRequest {
     pub submitter_did: String,
     pub params: TrainParams,
     pub budget: u64,
+    #[serde(default)]
+    pub tee_required: bool,
+    #[serde(default)]
+    pub allow_deprecated: bool, // Override soft-blocked deprecations
+    #[serde(default)]
+    pub nonce: Option<u64>, // Client-chosen nonce makes the job id computable up front
+    #[serde(default)]
+    pub milestones: Option<MilestonePlan>, // Escrow payout milestones; 25/50/75/100% by default
+    #[serde(default)]
+    pub live_migration: bool, // Opt in to cooperative checkpoint-and-move on drains and reclaims
+    #[serde(default)]
+    pub reservation_id: Option<String>, // Run on GPUs booked with POST /reservations
+    #[serde(default)]
+    pub secrets: Vec<String>, // Vault secret names the runtime injects as env vars of the same name
+    #[serde(default)]
+    pub labels: Labels, // e.g. {"team": "vision", "ticket": "ML-412"}
+    #[serde(default)]
+    pub force_rerun: bool, // Run even if an identical job already completed; see `result_cache`
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Progress points at which escrowed budget is released to the provider.
+/// Explicit `steps` win over `fractions`; with neither, ai-proofs applies
+/// 25/50/75/100%.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MilestonePlan {
+    #[serde(default)]
+    pub total_steps: Option<u64>, // Step proofs expected over the job; the epoch count if unset
+    #[serde(default)]
+    pub fractions: Vec<f64>,
+    #[serde(default)]
+    pub steps: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrainParams {
     pub epochs: u32,
     pub batch_size: u32,
     pub learning_rate: f64,
     pub optimizer: String,
     pub checkpoint_interval: u32,
+    #[serde(default)]
+    pub seed: Option<u64>, // Generated and recorded at submission if unset
 }
 
 #[derive(Debug, Deserialize)]
@@ -79,10 +270,41 @@ pub struct InferJobRequest {
     pub model_id: String,
     pub input_cid: Option<String>,
     pub inline_input: Option<String>,
-    pub submitter_did: String,
+    #[serde(default)]
+    pub input_meta: Option<Vec<TensorSpec>>, // Tensors in `input_cid`, checked against the model schema
+    #[serde(default)]
+    pub submitter_did: String, // Empty for anonymous submissions
     pub mode: String, // "batch", "realtime", "stream"
     pub max_tokens: Option<u32>,
     pub budget: u64,
+    #[serde(default)]
+    pub tee_required: bool,
+    pub bucketing_key: Option<String>, // Deterministic A/B routing
+    #[serde(default)]
+    pub allow_deprecated: bool, // Override soft-blocked deprecations
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    #[serde(default)]
+    pub anonymous: bool, // Authenticated by the ephemeral key alone, under the anonymous policy
+    #[serde(default)]
+    pub ephemeral_address: Option<String>,
+    #[serde(default)]
+    pub ephemeral_signature: Option<String>, // Over `anonymous::submission_digest`; requires `nonce`
+    #[serde(default)]
+    pub labels: Labels,
+    #[serde(default)]
+    pub force_rerun: bool, // Run even if an identical job already completed; see `result_cache`
+}
+
+impl InferJobRequest {
+    /// Namespace model references resolve in: the submitter's, or an
+    /// anonymous job's ephemeral address
+    fn namespace(&self) -> Namespace {
+        match (&self.ephemeral_address, self.anonymous) {
+            (Some(address), true) => Namespace::of(address),
+            _ => Namespace::of(&self.submitter_did),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -93,6 +315,10 @@ pub struct AgentJobRequest {
     pub tools: Vec<String>,
     pub memory_policy: String,
     pub budget: u64,
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    #[serde(default)]
+    pub labels: Labels,
 }
 
 #[derive(Debug, Serialize)]
@@ -101,6 +327,10 @@ pub struct JobSubmitResponse {
     pub status: JobStatus,
     pub estimated_cost: u64,
     pub estimated_duration_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_degraded: Option<DegradedPolicy>, // Set when admitted without policy-gate
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool, // `job_id` is an earlier identical job that already completed
 }
 
 #[derive(Debug, Serialize)]
@@ -108,77 +338,210 @@ pub struct JobStatusResponse {
     pub job: Job,
     pub receipts: Vec<String>,
     pub can_cancel: bool,
+    pub output_id: Option<String>, // Opaque id; the raw output CID is never exposed here
+    pub moderation_hold: Option<String>, // Decision id while the output is blocked pending appeal
+    pub escrow: Option<serde_json::Value>, // Milestone escrow with per-tranche release state, from ai-proofs
+}
+
+/// A completed job's output withheld by an enforced ai-ethics decision
+#[derive(Debug, Clone)]
+pub struct ModerationHold {
+    pub decision_id: String,
+    pub output_cid: Option<String>,
+    pub integrity: Option<OutputIntegrity>,
+    pub held_at: u64,
 }
 
 // Application state
 pub struct AppState {
     jobs: Arc<RwLock<HashMap<String, Job>>>,
+    job_store: Arc<dyn JobStore>, // Every job change is written through, and the map rebuilt from it at startup
+    results: Arc<RwLock<ResultCache>>, // Completed train and infer jobs by their pinned inputs
     contract_client: Arc<ContractClient>,
     policy_gate: Arc<PolicyGate>,
     scheduler_url: String,
     runtime_url: String,
+    deprecation_feed_url: String,
+    ab_router: Arc<RwLock<AbRouter>>,
+    marketplace: Arc<RwLock<Marketplace>>,
+    receipts_url: String,
+    node_api_url: String,
+    artifacts: Arc<RwLock<ArtifactRegistry>>,
+    runtime_image_digest: String,
+    manifest_key: Sensitive<String>,
+    outputs: Arc<RwLock<OutputVault>>,
+    submission_nonces: Arc<RwLock<HashMap<String, u64>>>, // submitter DID -> next server-assigned nonce
+    fanouts: Arc<RwLock<FanOutRegistry>>,
+    archived_jobs: Arc<RwLock<HashMap<String, ArchivedJob>>>, // Evicted by retention GC
+    retention: RetentionPolicy,
+    ethics_url: Option<String>, // Moderate completed outputs when set
+    moderation_holds: Arc<RwLock<HashMap<String, ModerationHold>>>,
+    internal_token: Sensitive<String>,
+    proofs_url: String,
+    milestone_plans: Arc<RwLock<HashMap<String, MilestonePlan>>>, // Train jobs' escrow milestones, opened on assignment
+    secret_refs: Arc<RwLock<HashMap<String, Vec<String>>>>, // Train jobs' vault secret names, resolved by the runtime
+    stream_specs: Arc<RwLock<HashMap<String, StreamSpec>>>, // Stream jobs' specs, handed to the runtime on assignment
+    quantize: QuantizeConfig,
+    quantize_specs: Arc<RwLock<HashMap<String, QuantizeSpec>>>, // Quantize jobs' parent and accuracy bound
+    events: Arc<RwLock<EventStore>>, // What this daemon observed of each job, for timelines
+    workflows: Arc<RwLock<WorkflowStore>>,
+    search: Arc<RwLock<SearchIndex>>, // Explorer search over jobs, models, datasets and sent transactions
+    reservations: Arc<RwLock<ReservationStore>>,
+    job_escrows: Arc<RwLock<JobEscrowStore>>, // Estimated costs locked at submission
+    forfeiture: ForfeitureSchedule, // Share of unused reservation time kept at settlement
+    svdb_url: String,
+    log_limits: LogLimits, // In-memory bound on each job's log
+    anonymous: AnonymousPolicy,
+    anon_limiter: Arc<RwLock<AnonLimiter>>,
+    chain: Arc<RwLock<ChainTracker>>, // Jobs' key transactions, watched for reorgs
+    clock: SharedClock,
+    entropy: SharedEntropy,
 }
 
+/// The contracts ai-jobd calls, checked on chain at startup
+const CONTRACTS: &[Contract] = &[
+    Contract { name: "AIJobManager", env: "AI_JOB_MANAGER_ADDR" },
+    Contract { name: "DatasetRegistry", env: "DATASET_REGISTRY_ADDR" },
+    Contract { name: "ModelRegistry", env: "MODEL_REGISTRY_ADDR" },
+    Contract { name: "DealMarket", env: "DEAL_MARKET_ADDR" },
+];
+
+/// How long `send_confirmed` waits for a receipt
+const RECEIPT_POLLS: u32 = 30;
+const RECEIPT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 // Real contract client using JSON-RPC
 pub struct ContractClient {
-    rpc_url: String,
+    rpc: RpcEndpoints,
     ai_job_manager: String, // Contract address
     dataset_registry: String,
     model_registry: String,
+    deal_market: String,
     client: reqwest::Client,
+    sponsor: Option<Sponsor>, // Route submissions through the bundler instead of signing directly
+    sent: std::sync::Mutex<Vec<SentTx>>, // Sent since the search index last synced
+    jobs: ReadThrough<Job>, // Decoded getJob results, dropped when we write the job
+    clock: SharedClock,
 }
 
 impl ContractClient {
-    pub fn new(rpc_url: String) -> Self {
+    /// A client on placeholder addresses; the service sets the real ones
+    /// with `with_contracts` once they are resolved
+    pub fn new(rpc: impl Into<RpcEndpoints>) -> Self {
         ContractClient {
-            rpc_url: rpc_url.clone(),
-            ai_job_manager: std::env::var("AI_JOB_MANAGER_ADDR")
-                .unwrap_or_else(|_| "0x0000000000000000000000000000000000000001".to_string()),
-            dataset_registry: std::env::var("DATASET_REGISTRY_ADDR")
-                .unwrap_or_else(|_| "0x0000000000000000000000000000000000000002".to_string()),
-            model_registry: std::env::var("MODEL_REGISTRY_ADDR")
-                .unwrap_or_else(|_| "0x0000000000000000000000000000000000000003".to_string()),
+            rpc: rpc.into(),
+            ai_job_manager: "0x0000000000000000000000000000000000000001".to_string(),
+            dataset_registry: "0x0000000000000000000000000000000000000002".to_string(),
+            model_registry: "0x0000000000000000000000000000000000000003".to_string(),
+            deal_market: "0x0000000000000000000000000000000000000000".to_string(),
             client: reqwest::Client::new(),
+            sponsor: None,
+            sent: std::sync::Mutex::new(Vec::new()),
+            jobs: ReadThrough::jobs(),
+            clock: artha_clock::system_clock(),
         }
     }
 
-    fn function_selector(signature: &str) -> String {
-        let mut hasher = Keccak256::new();
-        hasher.update(signature.as_bytes());
-        let hash = hasher.finalize();
-        format!("{:02x}{:02x}{:02x}{:02x}", hash[0], hash[1], hash[2], hash[3])
+    /// Transactions sent since the last call
+    pub fn take_sent(&self) -> Vec<SentTx> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
     }
 
-    async fn call_contract(
-        &self,
-        contract_addr: &str,
-        method_signature: &str,
-        params: Vec<String>,
-    ) -> Result<String, String> {
-        // Encode function call
-        let data = format!("{}{}", method_signature, params.join(""));
-        
-        let payload = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_call",
-            "params": [{
-                "to": contract_addr,
-                "data": format!("0x{}", data)
-            }, "latest"],
-            "id": 1
+    /// Remember a sent transaction for the search index, named by the contract's ABI
+    fn record_sent(&self, contract_addr: &str, data: &str, tx_hash: &str) {
+        let contract = [
+            (&self.ai_job_manager, abi::interfaces::ai_job_manager()),
+            (&self.dataset_registry, abi::interfaces::dataset_registry()),
+            (&self.model_registry, abi::interfaces::model_registry()),
+            (&self.deal_market, abi::interfaces::deal_market()),
+        ]
+        .into_iter()
+        .find(|(addr, _)| addr.eq_ignore_ascii_case(contract_addr))
+        .map(|(_, interface)| interface);
+        let selector = hex::decode(data.get(..8).unwrap_or_default()).unwrap_or_default();
+        let method = contract
+            .as_ref()
+            .and_then(|interface| interface.by_selector(&selector))
+            .map_or_else(|| format!("0x{}", hex::encode(&selector)), |function| function.name.clone());
+        self.sent.lock().unwrap().push(SentTx {
+            hash: tx_hash.to_string(),
+            from: std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
+            to: contract_addr.to_string(),
+            contract: contract.map_or_else(|| contract_addr.to_string(), |interface| interface.name.to_string()),
+            method,
+            at: self.clock.now_secs(),
         });
+    }
 
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| format!("RPC call failed: {}", e))?;
+    /// Submit as sponsored user operations, so the end user needs no gas
+    pub fn with_sponsor(mut self, sponsor: Sponsor) -> Self {
+        self.sponsor = Some(sponsor);
+        self
+    }
 
-        let result: serde_json::Value = response.json().await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+    /// Call the contracts at the addresses resolved at startup
+    pub fn with_contracts(mut self, contracts: &ContractRegistry) -> Self {
+        let resolved = |name: &str, placeholder: String| contracts.address(name).map_or(placeholder, str::to_string);
+        self.ai_job_manager = resolved("AIJobManager", self.ai_job_manager);
+        self.dataset_registry = resolved("DatasetRegistry", self.dataset_registry);
+        self.model_registry = resolved("ModelRegistry", self.model_registry);
+        self.deal_market = resolved("DealMarket", self.deal_market);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.rpc = self.rpc.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Failover and breaker state of each RPC endpoint
+    pub fn rpc_health(&self) -> Vec<EndpointHealth> {
+        self.rpc.health()
+    }
+
+    async fn rpc(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, String> {
+        self.rpc.call(method, params).await.map_err(|e| match e {
+            RpcError::Rpc { .. } => format!("{} error: {}", method, e),
+            _ => e.to_string(),
+        })
+    }
+
+    /// Block a transaction is mined in on the canonical chain, or None while
+    /// it has no receipt
+    pub async fn transaction_inclusion(&self, tx_hash: &str) -> Result<Option<Inclusion>, String> {
+        let receipt = self.rpc("eth_getTransactionReceipt", serde_json::json!([tx_hash])).await?;
+        if receipt.is_null() {
+            return Ok(None);
+        }
+        let block_number = receipt["blockNumber"]
+            .as_str()
+            .and_then(|n| u64::from_str_radix(n.trim_start_matches("0x"), 16).ok())
+            .ok_or_else(|| format!("Receipt of {} has no block number", tx_hash))?;
+        let block_hash = receipt["blockHash"]
+            .as_str()
+            .ok_or_else(|| format!("Receipt of {} has no block hash", tx_hash))?
+            .to_string();
+        Ok(Some(Inclusion { block_number, block_hash }))
+    }
+
+    /// Hash of the canonical block at `number`, or None past the head
+    pub async fn block_hash(&self, number: u64) -> Result<Option<String>, String> {
+        let block = self.rpc("eth_getBlockByNumber", serde_json::json!([format!("0x{:x}", number), false])).await?;
+        Ok(block["hash"].as_str().map(str::to_string))
+    }
+
+    async fn call_contract(&self, contract_addr: &str, signature: &str, args: &[Token]) -> Result<String, String> {
+        let data = hex::encode(abi_encode_call(signature, args)?);
+
+        let params = serde_json::json!([{
+            "to": contract_addr,
+            "data": format!("0x{}", data)
+        }, "latest"]);
 
-        result["result"].as_str()
+        let result = self.rpc.call("eth_call", params).await.map_err(|e| e.to_string())?;
+
+        result.as_str()
             .ok_or_else(|| "No result in response".to_string())
             .map(|s| s.to_string())
     }
@@ -186,48 +549,71 @@ impl ContractClient {
     async fn send_transaction(
         &self,
         contract_addr: &str,
-        method_signature: &str,
-        params: Vec<String>,
+        signature: &str,
+        args: &[Token],
         private_key: &str,
     ) -> Result<String, String> {
         // For now, use eth_sendRawTransaction with signed transaction
         // In production, sign with private key and send
-        let data = format!("{}{}", method_signature, params.join(""));
-        
-        let payload = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_sendTransaction",
-            "params": [{
-                "from": std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
-                "to": contract_addr,
-                "data": format!("0x{}", data),
-                "gas": "0x100000",
-                "gasPrice": "0x4a817c800",
-            }],
-            "id": 1
-        });
+        let data = hex::encode(abi_encode_call(signature, args)?);
 
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| format!("Transaction failed: {}", e))?;
+        if let Some(sponsor) = &self.sponsor {
+            let calldata = hex::decode(&data).map_err(|e| format!("Invalid calldata: {}", e))?;
+            let tx_hash = sponsor.submit(&self.client, contract_addr, &calldata).await?;
+            self.record_sent(contract_addr, &data, &tx_hash);
+            return Ok(tx_hash);
+        }
+        self.send_from_operator(contract_addr, &data).await
+    }
 
-        let result: serde_json::Value = response.json().await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+    async fn send_from_operator(&self, contract_addr: &str, data: &str) -> Result<String, String> {
+        let params = serde_json::json!([{
+            "from": std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
+            "to": contract_addr,
+            "data": format!("0x{}", data),
+            "gas": "0x100000",
+            "gasPrice": "0x4a817c800",
+        }]);
 
-        if let Some(err) = result.get("error") {
-            return Err(format!("Transaction error: {}", err));
-        }
+        let result = self.rpc.call("eth_sendTransaction", params).await.map_err(|e| match e {
+            RpcError::Rpc { .. } => format!("Transaction error: {}", e),
+            _ => format!("Transaction failed: {}", e),
+        })?;
 
-        result["result"].as_str()
-            .ok_or_else(|| "No tx hash in response".to_string())
-            .map(|s| s.to_string())
+        let tx_hash = result.as_str()
+            .ok_or_else(|| "No tx hash in response".to_string())?
+            .to_string();
+        self.record_sent(contract_addr, data, &tx_hash);
+        Ok(tx_hash)
+    }
+
+    /// Send from the operator and wait for the receipt; a transaction mined
+    /// with a failed status is an error. Escrow calls go this way, never
+    /// through the sponsor: only an escrow operator may move a payer's
+    /// deposit in DealMarket, and the call has only happened once it is mined.
+    async fn send_confirmed(&self, contract_addr: &str, signature: &str, args: &[Token]) -> Result<String, String> {
+        let data = hex::encode(abi_encode_call(signature, args)?);
+        let tx_hash = self.send_from_operator(contract_addr, &data).await?;
+        for attempt in 0..RECEIPT_POLLS {
+            if attempt > 0 {
+                self.clock.sleep(RECEIPT_POLL_INTERVAL).await;
+            }
+            let receipt = self.rpc("eth_getTransactionReceipt", serde_json::json!([tx_hash])).await?;
+            if receipt.is_null() {
+                continue;
+            }
+            return match receipt["status"].as_str() {
+                Some("0x1") => Ok(tx_hash),
+                Some("0x0") => Err(format!("Transaction {} reverted", tx_hash)),
+                _ => Err(format!("Receipt of {} has no status", tx_hash)),
+            };
+        }
+        Err(format!("Transaction {} not mined after {} polls", tx_hash, RECEIPT_POLLS))
     }
 
     pub async fn submit_train_job(
         &self,
+        job_id: &str,
         model_id: &str,
         dataset_id: &str,
         params_hash: &str,
@@ -235,62 +621,54 @@ impl ContractClient {
         budget: u64,
     ) -> Result<String, String> {
         // AIJobManager.submitTrain(bytes32 modelId, bytes32 datasetId, bytes32 paramsHash, uint32 epochs, uint256 budget)
-        let method_hash = Self::function_selector("submitTrain(bytes32,bytes32,bytes32,uint32,uint256)");
-        
-        // Encode parameters (simplified - real implementation needs proper ABI encoding)
-        let params = vec![
-            hex::encode(model_id.as_bytes())[..64].to_string(), // Pad to 32 bytes
-            hex::encode(dataset_id.as_bytes())[..64].to_string(),
-            hex::encode(params_hash.as_bytes())[..64].to_string(),
-            format!("{:064x}", epochs),
-            format!("{:064x}", budget),
+        let args = [
+            abi_encode_bytes32(model_id),
+            abi_encode_bytes32(dataset_id),
+            abi_encode_bytes32(params_hash),
+            abi_encode_uint256(epochs),
+            abi_encode_uint256(budget),
         ];
 
-        let tx_hash = self.send_transaction(&self.ai_job_manager, method_hash, params, "").await?;
-        
-        // Get job_id from event logs
-        // For now, derive from tx_hash
-        let job_id = format!("job-{}", &tx_hash[2..18]);
-        println!("📝 Submitted train job to blockchain: {} (tx: {})", job_id, tx_hash);
-        Ok(job_id)
+        let tx_hash = self
+            .send_transaction(&self.ai_job_manager, "submitTrain(bytes32,bytes32,bytes32,uint32,uint256)", &args, "")
+            .await?;
+        info!("📝 Submitted train job to blockchain: {} (tx: {})", job_id, tx_hash);
+        Ok(tx_hash)
     }
 
     pub async fn submit_infer_job(
         &self,
+        job_id: &str,
         model_id: &str,
         input_cid: &str,
         mode: &str,
         budget: u64,
     ) -> Result<String, String> {
-        let method_hash = Self::function_selector("submitInfer(bytes32,bytes32,bytes32,uint256)");
-        let params = vec![
-            hex::encode(model_id.as_bytes())[..64].to_string(),
-            hex::encode(input_cid.as_bytes())[..64].to_string(),
-            hex::encode(mode.as_bytes())[..64].to_string(),
-            format!("{:064x}", budget),
+        let args = [
+            abi_encode_bytes32(model_id),
+            abi_encode_bytes32(input_cid),
+            abi_encode_bytes32(mode), // "batch" and the like are shorter than a word
+            abi_encode_uint256(budget),
         ];
 
-        let tx_hash = self.send_transaction(&self.ai_job_manager, method_hash, params, "").await?;
-        let job_id = format!("job-{}", &tx_hash[2..18]);
-        println!("📝 Submitted infer job to blockchain: {} (tx: {})", job_id, tx_hash);
-        Ok(job_id)
+        let tx_hash = self
+            .send_transaction(&self.ai_job_manager, "submitInfer(bytes32,bytes32,bytes32,uint256)", &args, "")
+            .await?;
+        info!("📝 Submitted infer job to blockchain: {} (tx: {})", job_id, tx_hash);
+        Ok(tx_hash)
     }
 
     pub async fn submit_agent_job(
         &self,
+        job_id: &str,
         agent_spec_cid: &str,
         budget: u64,
     ) -> Result<String, String> {
-        let method_hash = Self::function_selector("submitAgent(bytes32,uint256)");
-        let params = vec![
-            hex::encode(agent_spec_cid.as_bytes())[..64].to_string(),
-            format!("{:064x}", budget),
-        ];
+        let args = [abi_encode_bytes32(agent_spec_cid), abi_encode_uint256(budget)];
 
-        let tx_hash = self.send_transaction(&self.ai_job_manager, method_hash, params, "").await?;
-        let job_id = format!("job-{}", &tx_hash[2..18]);
-        println!("📝 Submitted agent job to blockchain: {} (tx: {})", job_id, tx_hash);
-        Ok(job_id)
+        let tx_hash = self.send_transaction(&self.ai_job_manager, "submitAgent(bytes32,uint256)", &args, "").await?;
+        info!("📝 Submitted agent job to blockchain: {} (tx: {})", job_id, tx_hash);
+        Ok(tx_hash)
     }
 
     pub async fn register_dataset(
@@ -299,19 +677,108 @@ impl ContractClient {
         license_cid: &str,
         tags: &[String],
     ) -> Result<String, String> {
-        let method_hash = Self::function_selector("register(bytes32,bytes32,string[])");
-        let params = vec![
-            hex::encode(root_cid.as_bytes())[..64].to_string(),
-            hex::encode(license_cid.as_bytes())[..64].to_string(),
-            format!("{:064x}", tags.len()),
+        let args = [
+            abi_encode_bytes32(root_cid),
+            abi_encode_bytes32(license_cid),
+            Token::Array(tags.iter().map(|tag| abi_encode_string(tag)).collect()),
         ];
 
-        let tx_hash = self.send_transaction(&self.dataset_registry, method_hash, params, "").await?;
+        let tx_hash = self.send_transaction(&self.dataset_registry, "register(bytes32,bytes32,string[])", &args, "").await?;
         let dataset_id = format!("dataset-{}", &tx_hash[2..18]);
-        println!("📊 Registered dataset on-chain: {} (tx: {})", dataset_id, tx_hash);
+        info!("📊 Registered dataset on-chain: {} (tx: {})", dataset_id, tx_hash);
         Ok(dataset_id)
     }
 
+    /// Lock a dataset access grant's price from the consumer's deposit in
+    /// DealMarket; the receipts daemon releases it to the owner as jobs use it
+    pub async fn escrow_dataset_access(
+        &self,
+        grant_id: &str,
+        consumer_did: &str,
+        amount: u64,
+    ) -> Result<String, String> {
+        let args = [
+            abi_encode_bytes32(&compute_hash(grant_id)),
+            abi_encode_bytes32(&compute_hash(consumer_did)),
+            abi_encode_uint256(amount),
+        ];
+
+        let tx_hash = self.send_confirmed(&self.deal_market, "escrowDatasetAccess(bytes32,bytes32,uint256)", &args).await?;
+        info!("💳 Escrowed {} for grant {} (tx: {})", amount, grant_id, tx_hash);
+        Ok(tx_hash)
+    }
+
+    /// Lock a GPU-hour reservation's cost from the payer's deposit in
+    /// DealMarket; reverts when the payer's balance can't cover it
+    pub async fn escrow_reservation(
+        &self,
+        reservation_id: &str,
+        payer_did: &str,
+        amount: u64,
+    ) -> Result<String, String> {
+        let args = [
+            abi_encode_bytes32(&compute_hash(reservation_id)),
+            abi_encode_bytes32(&compute_hash(payer_did)),
+            abi_encode_uint256(amount),
+        ];
+
+        let tx_hash = self.send_confirmed(&self.deal_market, "escrowReservation(bytes32,bytes32,uint256)", &args).await?;
+        info!("💳 Escrowed {} for reservation {} (tx: {})", amount, reservation_id, tx_hash);
+        Ok(tx_hash)
+    }
+
+    /// Close a settled reservation's escrow, returning `amount` to its payer;
+    /// DealMarket credits the rest to the treasury providers are paid from
+    pub async fn refund_reservation(
+        &self,
+        reservation_id: &str,
+        payer_did: &str,
+        amount: u64,
+    ) -> Result<String, String> {
+        let args = [
+            abi_encode_bytes32(&compute_hash(reservation_id)),
+            abi_encode_bytes32(&compute_hash(payer_did)),
+            abi_encode_uint256(amount),
+        ];
+
+        let tx_hash = self.send_confirmed(&self.deal_market, "refundReservation(bytes32,bytes32,uint256)", &args).await?;
+        info!("💸 Refunded {} of reservation {} (tx: {})", amount, reservation_id, tx_hash);
+        Ok(tx_hash)
+    }
+
+    /// Lock a job's estimated cost from the payer's deposit in DealMarket;
+    /// reverts when the payer's balance can't cover it
+    pub async fn escrow_job(&self, job_id: &str, payer: &str, amount: u64) -> Result<String, String> {
+        let args = [abi_encode_bytes32(job_id), abi_encode_bytes32(&compute_hash(payer)), abi_encode_uint256(amount)];
+
+        let tx_hash = self.send_confirmed(&self.deal_market, "escrowJob(bytes32,bytes32,uint256)", &args).await?;
+        info!("💳 Escrowed {} for job {} (tx: {})", amount, job_id, tx_hash);
+        Ok(tx_hash)
+    }
+
+    /// Credit `paid` of a job's escrow to its provider's account and refund
+    /// `refunded` to the payer it was escrowed from
+    pub async fn settle_job_escrow(
+        &self,
+        job_id: &str,
+        provider: Option<&str>,
+        paid: u64,
+        refunded: u64,
+    ) -> Result<String, String> {
+        let args = [
+            abi_encode_bytes32(job_id),
+            provider.map_or_else(|| Token::bytes32([0; 32]), abi_encode_bytes32),
+            abi_encode_uint256(paid),
+            abi_encode_uint256(refunded),
+        ];
+
+        let tx_hash = self
+            .send_confirmed(&self.deal_market, "settleJobEscrow(bytes32,bytes32,uint256,uint256)", &args)
+            .await?;
+        info!("💸 Settled escrow of job {}: {} paid, {} refunded (tx: {})", job_id, paid, refunded, tx_hash);
+        Ok(tx_hash)
+    }
+
     pub async fn register_model(
         &self,
         model_cid: &str,
@@ -320,88 +787,189 @@ impl ContractClient {
         code_hash: &str,
         version: &str,
     ) -> Result<String, String> {
-        let method_hash = Self::function_selector("register(bytes32,bytes32,bytes32,bytes32,bytes32)");
-        let params = vec![
-            hex::encode(model_cid.as_bytes())[..64].to_string(),
-            hex::encode(architecture.as_bytes())[..64].to_string(),
-            hex::encode(dataset_id.as_bytes())[..64].to_string(),
-            hex::encode(code_hash.as_bytes())[..64].to_string(),
-            hex::encode(version.as_bytes())[..64].to_string(),
+        let args = [
+            abi_encode_bytes32(model_cid),
+            abi_encode_bytes32(architecture),
+            abi_encode_bytes32(dataset_id),
+            abi_encode_bytes32(code_hash),
+            abi_encode_bytes32(version),
         ];
 
-        let tx_hash = self.send_transaction(&self.model_registry, method_hash, params, "").await?;
+        let tx_hash = self
+            .send_transaction(&self.model_registry, "register(bytes32,bytes32,bytes32,bytes32,bytes32)", &args, "")
+            .await?;
         let model_id = format!("model-{}", &tx_hash[2..18]);
-        println!("🧠 Registered model on-chain: {} (tx: {})", model_id, tx_hash);
+        info!("🧠 Registered model on-chain: {} (tx: {})", model_id, tx_hash);
         Ok(model_id)
     }
 
+    /// Record the CID of a model's card, whose body is in SVDB
+    pub async fn set_model_card(&self, model_id: &str, card_cid: &str) -> Result<String, String> {
+        let args = [abi_encode_bytes32(model_id), abi_encode_bytes32(card_cid)];
+
+        let tx_hash = self.send_transaction(&self.model_registry, "setModelCard(bytes32,bytes32)", &args, "").await?;
+        info!("📇 Set model card for {}: {} (tx: {})", model_id, card_cid, tx_hash);
+        Ok(tx_hash)
+    }
+
+    /// Deactivate a registered model; how a failed batch takes back the
+    /// models it already registered
+    pub async fn deactivate_model(&self, model_id: &str) -> Result<String, String> {
+        let args = [abi_encode_bytes32(model_id)];
+
+        let tx_hash = self.send_transaction(&self.model_registry, "deactivate(bytes32)", &args, "").await?;
+        info!("🗑️  Deactivated model on-chain: {} (tx: {})", model_id, tx_hash);
+        Ok(tx_hash)
+    }
+
     pub async fn update_job_status(&self, job_id: &str, status: &JobStatus) -> Result<(), String> {
-        let status_str = match status {
-            JobStatus::Queued => "0",
-            JobStatus::Assigned => "1",
-            JobStatus::Running => "2",
-            JobStatus::Completed => "3",
-            JobStatus::Failed => "4",
-            JobStatus::Cancelled => "5",
+        let status_code: u8 = match status {
+            JobStatus::Queued => 0,
+            JobStatus::Assigned => 1,
+            JobStatus::Running => 2,
+            JobStatus::Completed => 3,
+            JobStatus::Failed => 4,
+            JobStatus::Cancelled => 5,
         };
-        
-        let method_hash = Self::function_selector("updateStatus(bytes32,uint8)");
-        let params = vec![
-            hex::encode(job_id.as_bytes())[..64].to_string(),
-            format!("{:064x}", u8::from_str_radix(status_str, 10).unwrap()),
-        ];
 
-        self.send_transaction(&self.ai_job_manager, method_hash, params, "").await?;
-        println!("🔄 Updated job {} status to {:?} on-chain", job_id, status);
+        let args = [abi_encode_bytes32(job_id), abi_encode_uint256(status_code)];
+
+        let sent = self.send_transaction(&self.ai_job_manager, "updateStatus(bytes32,uint8)", &args, "").await;
+        self.jobs.invalidate(job_id);
+        sent?;
+        info!("🔄 Updated job {} status to {:?} on-chain", job_id, status);
         Ok(())
     }
 
     pub async fn assign_job(&self, job_id: &str, node_pubkey: &str) -> Result<(), String> {
-        let method_hash = Self::function_selector("assignJob(bytes32,bytes32)");
-        let params = vec![
-            hex::encode(job_id.as_bytes())[..64].to_string(),
-            hex::encode(node_pubkey.as_bytes())[..64].to_string(),
-        ];
+        let args = [abi_encode_bytes32(job_id), abi_encode_bytes32(node_pubkey)];
 
-        self.send_transaction(&self.ai_job_manager, method_hash, params, "").await?;
-        Ok(())
+        let sent = self.send_transaction(&self.ai_job_manager, "assignJob(bytes32,bytes32)", &args, "").await;
+        self.jobs.invalidate(job_id);
+        sent.map(|_| ())
     }
 
     pub async fn get_job(&self, job_id: &str) -> Result<Job, String> {
-        // Query AIJobManager.getJob(bytes32 jobId)
-        let method_hash = Self::function_selector("getJob(bytes32)");
-        let params = vec![hex::encode(job_id.as_bytes())[..64].to_string()];
-
-        let result = self.call_contract(&self.ai_job_manager, method_hash, params).await?;
-        // Decode result and construct Job
-        // For now, return a basic job
-        Ok(Job {
-            job_id: job_id.to_string(),
-            job_type: JobType::Train,
-            status: JobStatus::Queued,
-            submitter: "0x0".to_string(),
-            submitter_did: "did:artha:unknown".to_string(),
-            model_id: None,
-            dataset_id: None,
-            params_hash: "0x0".to_string(),
-            assigned_node: None,
-            budget: 0,
-            spent: 0,
-            submitted_at: now(),
-            started_at: None,
-            completed_at: None,
-            output_cid: None,
-            artifacts: Vec::new(),
-            progress: 0.0,
-            logs: Vec::new(),
-        })
+        self.jobs.get_or_load(job_id, self.fetch_job(job_id)).await
+    }
+
+    async fn fetch_job(&self, job_id: &str) -> Result<Job, String> {
+        let result = self.call_contract(&self.ai_job_manager, "getJob(bytes32)", &[abi_encode_bytes32(job_id)]).await?;
+        let data = hex::decode(result.trim_start_matches("0x")).map_err(|e| format!("getJob returned invalid hex: {}", e))?;
+        let outputs = abi::ai_job_manager().function("getJob").decode_output(&data)?;
+        job_from_chain(job_id, outputs)
+    }
+}
+
+/// A bytes32 read back as the id `abi_encode_bytes32` would have sent for
+/// it: zero-padded text as the text, anything else (hashes, hashed long
+/// ids) as 0x hex. All zeros is no value.
+fn bytes32_id(token: &Token) -> Result<Option<String>, String> {
+    let Token::FixedBytes(bytes) = token else {
+        return Err(format!("Expected bytes32, got {:?}", token));
+    };
+    let text = match bytes.iter().rposition(|b| *b != 0) {
+        None => return Ok(None),
+        Some(last) => &bytes[..=last],
+    };
+    if text.iter().all(|b| b.is_ascii_graphic()) {
+        Ok(Some(String::from_utf8_lossy(text).into_owned()))
+    } else {
+        Ok(Some(format!("0x{}", hex::encode(bytes))))
+    }
+}
+
+fn uint_of(token: &Token) -> Result<u128, String> {
+    match token {
+        Token::Uint(value) => Ok(*value),
+        other => Err(format!("Expected uint, got {:?}", other)),
+    }
+}
+
+fn u64_of(token: &Token) -> Result<u64, String> {
+    u64::try_from(uint_of(token)?).map_err(|_| format!("{:?} does not fit in u64", token))
+}
+
+/// The Job struct `AIJobManager.getJob` returns, as jobd's `Job`. Fields the
+/// contract doesn't keep (logs, attestation, manifest...) start empty.
+fn job_from_chain(job_id: &str, outputs: Vec<Token>) -> Result<Job, String> {
+    let Some(Token::Tuple(fields)) = outputs.into_iter().next() else {
+        return Err("getJob returned no Job".to_string());
+    };
+    let [id, job_type, status, submitter, submitter_did, model_id, dataset_id, params_hash, assigned_node, budget, spent, submitted_at, started_at, completed_at, output_cid, artifacts] =
+        <[Token; 16]>::try_from(fields).map_err(|f| format!("getJob returned {} fields, expected 16", f.len()))?;
+    // An unknown id reads back as an all-zero struct
+    if bytes32_id(&id)?.is_none() {
+        return Err(format!("Job {} not found on chain", job_id));
     }
+    let job_type = match uint_of(&job_type)? {
+        0 => JobType::Train,
+        1 => JobType::Infer,
+        2 => JobType::Agent,
+        3 => JobType::Federated,
+        4 => JobType::Evolution,
+        other => return Err(format!("Unknown on-chain job type {}", other)),
+    };
+    let status = match uint_of(&status)? {
+        0 => JobStatus::Queued,
+        1 => JobStatus::Assigned,
+        2 => JobStatus::Running,
+        3 => JobStatus::Completed,
+        4 => JobStatus::Failed,
+        5 => JobStatus::Cancelled,
+        other => return Err(format!("Unknown on-chain job status {}", other)),
+    };
+    let Token::Address(submitter) = submitter else {
+        return Err(format!("Expected address, got {:?}", submitter));
+    };
+    let Token::Array(artifacts) = artifacts else {
+        return Err(format!("Expected bytes32[], got {:?}", artifacts));
+    };
+    let submitter_did = bytes32_id(&submitter_did)?;
+    let optional_time = |token: &Token| u64_of(token).map(|t| Some(t).filter(|t| *t > 0));
+
+    Ok(Job {
+        job_id: job_id.to_string(),
+        job_type,
+        progress: if status == JobStatus::Completed { 1.0 } else { 0.0 },
+        status,
+        submitter: format!("0x{}", hex::encode(submitter)),
+        anonymous: submitter_did.is_none(),
+        submitter_did: submitter_did.unwrap_or_default(),
+        model_id: bytes32_id(&model_id)?,
+        dataset_id: bytes32_id(&dataset_id)?,
+        params_hash: bytes32_id(&params_hash)?.unwrap_or_default(),
+        assigned_node: bytes32_id(&assigned_node)?,
+        budget: u64_of(&budget)?,
+        spent: u64_of(&spent)?,
+        submitted_at: u64_of(&submitted_at)?,
+        started_at: optional_time(&started_at)?,
+        completed_at: optional_time(&completed_at)?,
+        output_cid: bytes32_id(&output_cid)?,
+        artifacts: artifacts.iter().map(bytes32_id).filter_map(Result::transpose).collect::<Result<_, _>>()?,
+        logs: JobLog::default(),
+        tee_required: false,
+        attestation: None,
+        ab_variant: None,
+        manifest: None,
+        seed: None,
+        live_migration: false,
+        migrations: Vec::new(),
+        terminal_reason: None,
+        data_provenance: None,
+        labels: Labels::new(),
+        stale_since: None,
+        estimated_duration_secs: None,
+    })
 }
 
 // Real policy gate integration
 pub struct PolicyGate {
     policy_api_url: String,
     client: reqwest::Client,
+    outage: OutageConfig, // How checks are decided while the gate is unreachable
+    decisions: std::sync::Mutex<DecisionCache>, // Last answers, for `cached-last-decision`
+    clock: SharedClock,
 }
 
 impl PolicyGate {
@@ -409,21 +977,39 @@ impl PolicyGate {
         PolicyGate {
             policy_api_url,
             client: reqwest::Client::new(),
+            outage: OutageConfig::default(),
+            decisions: std::sync::Mutex::new(DecisionCache::default()),
+            clock: artha_clock::system_clock(),
         }
     }
 
+    pub fn with_outage(mut self, outage: OutageConfig) -> Self {
+        self.outage = outage;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub async fn check_submission(
         &self,
         did: &str,
         action: &str,
         resource: &str,
+        dataset_id: Option<&str>,
+        consent_vcs: &[String],
         budget: u64,
     ) -> Result<PolicyDecision, String> {
         // Call real policy-gate service
+        let started = std::time::Instant::now();
         let payload = serde_json::json!({
             "did": did,
             "action": action,
             "resource": resource,
+            "dataset_id": dataset_id,
+            "consent_vcs": consent_vcs,
             "budget": budget,
         });
 
@@ -455,14 +1041,70 @@ impl PolicyGate {
             })
             .unwrap_or_default();
 
-        println!("🔐 Policy check result: {} -> {} (reason: {:?})", did, allowed, reason);
+        let error = serde_json::from_value(result["error"].clone()).ok();
+        let provenance = Some(result["provenance"].clone()).filter(|p| !p.is_null());
+
+        info!("🔐 Policy check result: {} -> {} (reason: {:?})", did, allowed, reason);
 
         Ok(PolicyDecision {
             allowed,
             reason,
             required_claims,
+            error,
+            provenance,
+            latency_ms: started.elapsed().as_millis() as u64,
+            degraded: None,
         })
     }
+
+    /// `check_submission`; an unreachable gate is decided by the configured
+    /// fail mode, which denies unless told otherwise. A denial is relayed
+    /// exactly as policy-gate structured it.
+    pub async fn enforce(
+        &self,
+        did: &str,
+        action: &str,
+        resource: &str,
+        dataset_id: Option<&str>,
+        consent_vcs: &[String],
+        budget: u64,
+    ) -> Result<PolicyDecision, ServiceError> {
+        let key = DecisionKey {
+            did: did.to_string(),
+            action: action.to_string(),
+            resource: resource.to_string(),
+            dataset_id: dataset_id.map(str::to_string),
+        };
+        let now = self.clock.now_secs();
+        let decision = match self.check_submission(did, action, resource, dataset_id, consent_vcs, budget).await {
+            Ok(decision) => decision,
+            Err(e) => {
+                warn!("🚫 Policy check unavailable for {}: {}", did, e);
+                let decisions = self.decisions.lock().unwrap();
+                let degraded = self.outage.fallback(&decisions, &key, now).map_err(|reason| ServiceError::policy_denied(&reason))?;
+                warn!("⚠️  Admitting {} {} on {} under {:?}", did, action, resource, degraded.mode);
+                return Ok(PolicyDecision {
+                    allowed: true,
+                    reason: Some("Policy check unavailable".to_string()),
+                    required_claims: Vec::new(),
+                    error: None,
+                    provenance: None,
+                    latency_ms: 0,
+                    degraded: Some(degraded),
+                });
+            }
+        };
+        if self.outage.mode == FailMode::CachedLastDecision {
+            let cached = CachedDecision { allowed: decision.allowed, reason: decision.reason.clone(), decided_at: now };
+            self.decisions.lock().unwrap().record(key, cached, self.outage.cache_ttl_secs);
+        }
+        if decision.allowed {
+            return Ok(decision);
+        }
+        Err(decision.error.clone().unwrap_or_else(|| {
+            ServiceError::policy_denied(decision.reason.as_deref().unwrap_or("Denied by policy"))
+        }))
+    }
 }
 
 #[derive(Debug)]
@@ -470,167 +1112,532 @@ pub struct PolicyDecision {
     pub allowed: bool,
     pub reason: Option<String>,
     pub required_claims: Vec<String>,
+    pub error: Option<ServiceError>, // Set by policy-gate on denials
+    pub provenance: Option<serde_json::Value>, // Set when the dataset's consent VCs were checked
+    pub latency_ms: u64,
+    pub degraded: Option<DegradedPolicy>, // Set when admitted by the fail mode, not by policy-gate
 }
 
 // API Handlers
 
 async fn submit_train_job(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<TrainJobRequest>,
-) -> Result<Json<JobSubmitResponse>, StatusCode> {
-    // 1. Policy check
-    let policy_decision = state.policy_gate.check_submission(
+) -> Result<(HeaderMap, Json<JobSubmitResponse>), SubmitError> {
+    labels::validate(&req.labels).map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+    let deadline = Deadline::from_headers(&headers)?;
+    // Lock the resolved inputs before anything else can move them
+    let manifest = lock_train_manifest(
+        &*state.artifacts.read().await,
+        &req,
+        &state.runtime_image_digest,
+        state.manifest_key.expose(),
+        state.clock.now_secs(),
+        &*state.entropy,
+    )?;
+    submit_locked_train_job(&state, req, manifest, deadline).await
+}
+
+/// Submit a train job whose inputs are pinned by `manifest`. Once `deadline`
+/// passes, nothing more is started and what was already done is undone.
+async fn submit_locked_train_job(
+    state: &AppState,
+    req: TrainJobRequest,
+    manifest: JobManifest,
+    deadline: Deadline,
+) -> Result<(HeaderMap, Json<JobSubmitResponse>), SubmitError> {
+    let model_id = manifest.model_id.clone();
+
+    // 1. Policy check, including the consent the dataset was collected under
+    let consent_vcs = state.artifacts.read().await.dataset_consent(&req.dataset_id).to_vec();
+    let policy = state.policy_gate.enforce(
         &req.submitter_did,
         "train",
-        &req.model_id,
+        &model_id,
+        Some(&req.dataset_id),
+        &consent_vcs,
         req.budget,
-    ).await.map_err(|_| StatusCode::FORBIDDEN)?;
+    ).await?;
 
-    if !policy_decision.allowed {
-        return Err(StatusCode::FORBIDDEN);
+    let deprecations = deprecation::fetch_active(&state.deprecation_feed_url).await;
+    let warnings = deprecation::enforce(
+        &deprecations,
+        &[("model", &model_id), ("dataset", &req.dataset_id)],
+        req.allow_deprecated,
+    )?;
+
+    // Marketplace datasets need a grant with allowance left for this job
+    let grant_id = {
+        let market = state.marketplace.read().await;
+        if market.is_listed(&req.dataset_id) {
+            let grant = market
+                .check_access(&req.dataset_id, &req.submitter_did, "train", req.params.epochs, state.clock.now_secs())
+                .map_err(|e| {
+                    warn!("🚫 Dataset access denied for {}: {}", req.submitter_did, e);
+                    StatusCode::FORBIDDEN
+                })?;
+            Some(grant.grant_id.clone())
+        } else {
+            None
+        }
+    };
+
+    // A byte-identical job that already completed answers this one. A nonce
+    // names a new job up front, so a submission with one always runs.
+    if let Some(job_id) = cached_result(state, &req.submitter_did, &manifest, req.force_rerun || req.nonce.is_some()).await {
+        return Ok((deprecation_headers(&warnings), Json(cached_response(job_id, &policy))));
     }
 
-    // 2. Submit to blockchain
-    let params_hash = compute_params_hash(&req.params);
-    let job_id = state.contract_client.submit_train_job(
-        &req.model_id,
+    // Reserved GPUs serve only the submitter who booked them, until the window closes
+    if let Some(reservation_id) = &req.reservation_id {
+        let reservations = state.reservations.read().await;
+        let reservation = reservations
+            .get(reservation_id)
+            .ok_or_else(|| ServiceError::new(ErrorCode::NotFound, "Unknown reservation"))?;
+        if reservation.submitter_did != req.submitter_did {
+            return Err(ServiceError::new(ErrorCode::Forbidden, "Reservation belongs to another submitter").into());
+        }
+        if reservation.end <= state.clock.now_secs() {
+            return Err(ServiceError::new(ErrorCode::Conflict, "Reservation window has ended").into());
+        }
+    }
+
+    // 2. Submit to blockchain under a content-derived job id
+    let params_hash = manifest.params_hash.clone();
+    let job_id = assign_job_id(
+        state,
+        "train",
+        &req.submitter_did,
+        &model_id,
+        Some(&req.dataset_id),
+        &params_hash,
+        req.nonce,
+    ).await?;
+    let estimated_cost = estimate_train_cost(&req.params, &req.dataset_id);
+    deadline.check(state.clock.now_millis(), "escrowing the job's cost")?;
+    escrow_job_cost(state, &job_id, &req.submitter_did, estimated_cost).await?;
+    if let Err(e) = deadline.check(state.clock.now_millis(), "submitting the job on-chain") {
+        abandon_job_escrow(state, &job_id).await;
+        return Err(e.into());
+    }
+    let submit_tx = match state.contract_client.submit_train_job(
+        &job_id,
+        &model_id,
         &req.dataset_id,
         &params_hash,
         req.params.epochs,
         req.budget,
-    ).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    ).await {
+        Ok(tx_hash) => tx_hash,
+        Err(_) => {
+            abandon_job_escrow(state, &job_id).await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    };
 
     // 3. Create local job record
+    let estimated_duration = estimate_train_duration(&req.params, &req.dataset_id);
     let job = Job {
         job_id: job_id.clone(),
         job_type: JobType::Train,
         status: JobStatus::Queued,
         submitter: "0x...".to_string(), // From auth
         submitter_did: req.submitter_did.clone(),
-        model_id: Some(req.model_id.clone()),
+        model_id: Some(model_id),
         dataset_id: Some(req.dataset_id.clone()),
         params_hash,
         assigned_node: None,
         budget: req.budget,
         spent: 0,
-        submitted_at: now(),
+        submitted_at: state.clock.now_secs(),
         started_at: None,
         completed_at: None,
         output_cid: None,
         artifacts: Vec::new(),
         progress: 0.0,
-        logs: Vec::new(),
+        logs: JobLog::new(state.log_limits),
+        tee_required: req.tee_required,
+        attestation: None,
+        ab_variant: None,
+        seed: manifest.params["seed"].as_u64(),
+        manifest: Some(manifest),
+        live_migration: req.live_migration,
+        migrations: Vec::new(),
+        terminal_reason: None,
+        anonymous: false,
+        data_provenance: policy.provenance.clone(),
+        labels: req.labels.clone(),
+        stale_since: None,
+        estimated_duration_secs: Some(estimated_duration),
     };
 
-    state.jobs.write().await.insert(job_id.clone(), job);
+    if let Some(grant_id) = grant_id {
+        state.marketplace.write().await
+            .begin_usage(&grant_id, &job_id, req.params.epochs)
+            .map_err(|_| StatusCode::FORBIDDEN)?;
+    }
 
-    // 4. Notify scheduler
-    notify_scheduler(&state.scheduler_url, &job_id).await?;
+    let mut plan = req.milestones.clone().unwrap_or_default();
+    plan.total_steps = plan.total_steps.or(Some(req.params.epochs as u64));
+    state.milestone_plans.write().await.insert(job_id.clone(), plan);
+    if !req.secrets.is_empty() {
+        state.secret_refs.write().await.insert(job_id.clone(), req.secrets.clone());
+    }
+    state.jobs.write().await.insert(job_id.clone(), job);
+    state.chain.write().await.track(&job_id, TxRole::Submit, submit_tx);
+    if let Some(reservation_id) = &req.reservation_id {
+        state.reservations.write().await.attach(reservation_id, &job_id);
+    }
 
-    // 5. Estimate cost and duration
-    let estimated_cost = estimate_train_cost(&req.params, &req.dataset_id);
-    let estimated_duration = estimate_train_duration(&req.params, &req.dataset_id);
+    // 4. Notify scheduler. If the caller stopped waiting meanwhile, the job
+    // is cancelled on-chain and undone wherever it got to.
+    let enqueued = match enqueue_job(state, &job_id, req.tee_required, &policy, deadline).await {
+        Ok(()) => deadline.check(state.clock.now_millis(), "the submission completed").map_err(SubmitError::from),
+        Err(e) => Err(e),
+    };
+    if enqueued.as_ref().is_err_and(SubmitError::is_deadline_exceeded) {
+        abandon_submission(state, &job_id).await;
+    }
+    enqueued?;
 
-    Ok(Json(JobSubmitResponse {
+    Ok((deprecation_headers(&warnings), Json(JobSubmitResponse {
         job_id,
         status: JobStatus::Queued,
         estimated_cost,
         estimated_duration_secs: estimated_duration,
-    }))
+        policy_degraded: policy.degraded.clone(),
+        cached: false,
+    })))
 }
 
 async fn submit_infer_job(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<InferJobRequest>,
-) -> Result<Json<JobSubmitResponse>, StatusCode> {
-    // Policy check
-    let policy_decision = state.policy_gate.check_submission(
-        &req.submitter_did,
-        "infer",
-        &req.model_id,
-        req.budget,
-    ).await.map_err(|_| StatusCode::FORBIDDEN)?;
+    Json(mut req): Json<InferJobRequest>,
+) -> Result<(HeaderMap, Json<JobSubmitResponse>), SubmitError> {
+    labels::validate(&req.labels).map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+    // Policy check: anonymous submissions answer to the anonymous policy instead
+    let policy = if req.anonymous {
+        authorize_anonymous(&state, &mut req)?
+    } else {
+        state.policy_gate.enforce(
+            &req.submitter_did,
+            "infer",
+            &req.model_id,
+            None,
+            &[],
+            req.budget,
+        ).await?
+    };
 
-    if !policy_decision.allowed {
-        return Err(StatusCode::FORBIDDEN);
+    // A/B routing: resolve the model variant that will serve this request
+    let variant = state.ab_router.write().await.route(
+        &req.model_id,
+        req.bucketing_key.as_deref(),
+        state.entropy.next_u64(),
+    );
+    let served_model_id = variant.as_ref()
+        .map(|v| v.model_id.clone())
+        .unwrap_or_else(|| req.model_id.clone());
+    if let (true, Some(address)) = (req.anonymous, &req.ephemeral_address) {
+        state.anonymous.check(&[&served_model_id], req.budget)?;
+        state.anon_limiter.write().await.admit(address, state.clock.now_secs(), &state.anonymous)?;
     }
 
+    // Inputs the served model can't take are refused before upload or scheduling
+    check_input_schema(&*state.artifacts.read().await, &served_model_id, &req)?;
+
     // Determine input
-    let input_cid = if let Some(cid) = req.input_cid {
-        cid
-    } else if let Some(inline) = req.inline_input {
+    let input_cid = if let Some(cid) = &req.input_cid {
+        cid.clone()
+    } else if let Some(inline) = &req.inline_input {
         // Upload inline input to SVDB
-        upload_to_svdb(&inline).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        upload_to_svdb(inline).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     } else {
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into());
     };
 
-    // Submit to blockchain
-    let job_id = state.contract_client.submit_infer_job(
-        &req.model_id,
+    let manifest = lock_infer_manifest(
+        &*state.artifacts.read().await,
+        &req,
+        &served_model_id,
+        &input_cid,
+        &state.runtime_image_digest,
+        state.manifest_key.expose(),
+        state.clock.now_secs(),
+    )?;
+    submit_locked_infer_job(&state, req, manifest, variant, &policy).await
+}
+
+/// Authenticate an anonymous submission by its ephemeral key and hold it to
+/// the anonymous policy. On success `ephemeral_address` is the signer's
+/// address in canonical form.
+fn authorize_anonymous(state: &AppState, req: &mut InferJobRequest) -> Result<PolicyDecision, ServiceError> {
+    if !req.submitter_did.is_empty() {
+        return Err(ServiceError::new(ErrorCode::InvalidRequest, "Anonymous submissions must not carry a DID"));
+    }
+    let (Some(address), Some(signature), Some(nonce)) = (&req.ephemeral_address, &req.ephemeral_signature, req.nonce) else {
+        return Err(ServiceError::new(
+            ErrorCode::Unauthenticated,
+            "Anonymous submissions need ephemeral_address, ephemeral_signature and nonce",
+        ));
+    };
+    let input = req.input_cid.as_deref().or(req.inline_input.as_deref()).unwrap_or_default();
+    let digest = anonymous::submission_digest(&req.model_id, input, req.budget, nonce);
+    let signer = anonymous::recover_address(&digest, signature).map_err(|e| ServiceError::new(ErrorCode::Unauthenticated, e))?;
+    if !signer.eq_ignore_ascii_case(address) {
+        return Err(ServiceError::new(ErrorCode::Unauthenticated, "Signature is not from the ephemeral address"));
+    }
+    if state.ethics_url.is_none() {
+        return Err(ServiceError::new(ErrorCode::Unavailable, "Anonymous inference requires output moderation, which is not configured"));
+    }
+    state.anonymous.check(&[&req.model_id], req.budget)?;
+
+    req.ephemeral_address = Some(signer);
+    Ok(PolicyDecision {
+        allowed: true,
+        reason: Some("Anonymous policy".to_string()),
+        required_claims: Vec::new(),
+        error: None,
+        provenance: None,
+        latency_ms: 0,
+        degraded: None,
+    })
+}
+
+/// Validate an infer input against the served model's declared schema: inline
+/// inputs by content, CID inputs by their declared metadata. Models without
+/// a schema, and CID inputs without metadata, are not checked.
+fn check_input_schema(
+    artifacts: &ArtifactRegistry,
+    served_model_id: &str,
+    req: &InferJobRequest,
+) -> Result<(), ServiceError> {
+    let Ok((model_id, _)) = artifacts.resolve_model(&req.namespace(), served_model_id) else {
+        return Ok(()); // Refused when the manifest is locked
+    };
+    let Some(schema) = artifacts.model_schema(&model_id) else {
+        return Ok(());
+    };
+    let provided = match (&req.input_cid, &req.inline_input, &req.input_meta) {
+        (Some(_), _, Some(meta)) => Ok(meta.clone()),
+        (Some(_), _, None) => return Ok(()),
+        (None, Some(inline), _) => schema.describe_inline(inline),
+        (None, None, _) => return Ok(()),
+    };
+    provided.and_then(|inputs| schema.validate(&inputs)).map_err(|e| {
+        warn!("⛔ Infer input rejected for {}: {}", model_id, e);
+        ServiceError::new(ErrorCode::Unprocessable, format!("Input does not match the schema of {}: {}", model_id, e))
+            .with_details(serde_json::json!({ "model_id": model_id, "inputs": schema.inputs }))
+    })
+}
+
+/// Submit an infer job whose served model and input are pinned by `manifest`
+async fn submit_locked_infer_job(
+    state: &AppState,
+    req: InferJobRequest,
+    manifest: JobManifest,
+    variant: Option<ModelVariant>,
+    policy: &PolicyDecision,
+) -> Result<(HeaderMap, Json<JobSubmitResponse>), SubmitError> {
+    let served_model_id = manifest.model_id.clone();
+    let input_cid = manifest.input_cid.clone().ok_or(StatusCode::BAD_REQUEST)?;
+
+    let deprecations = deprecation::fetch_active(&state.deprecation_feed_url).await;
+    let warnings = deprecation::enforce(
+        &deprecations,
+        &[("model", &req.model_id), ("model", &served_model_id)],
+        req.allow_deprecated,
+    )?;
+
+    // Submit to blockchain under a content-derived job id
+    // Anonymous jobs are keyed by their ephemeral address; no DID is recorded
+    let submitter = match (req.anonymous, &req.ephemeral_address) {
+        (true, Some(address)) => address.clone(),
+        _ => req.submitter_did.clone(),
+    };
+    if let Some(job_id) = cached_result(state, &submitter, &manifest, req.force_rerun || req.nonce.is_some()).await {
+        return Ok((deprecation_headers(&warnings), Json(cached_response(job_id, policy))));
+    }
+    let job_id = assign_job_id(
+        state,
+        "infer",
+        &submitter,
+        &served_model_id,
+        Some(&input_cid),
+        &manifest.params_hash,
+        req.nonce,
+    ).await?;
+    let estimated_cost = 100; // Based on model size + input length
+    escrow_job_cost(state, &job_id, &submitter, estimated_cost).await?;
+    let submit_tx = match state.contract_client.submit_infer_job(
+        &job_id,
+        &served_model_id,
         &input_cid,
         &req.mode,
         req.budget,
-    ).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    ).await {
+        Ok(tx_hash) => tx_hash,
+        Err(_) => {
+            abandon_job_escrow(state, &job_id).await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    };
 
     // Create job record
+    let estimated_duration = 5; // Seconds
     let job = Job {
         job_id: job_id.clone(),
         job_type: JobType::Infer,
         status: JobStatus::Queued,
-        submitter: "0x...".to_string(),
+        submitter: if req.anonymous { submitter } else { "0x...".to_string() },
         submitter_did: req.submitter_did.clone(),
-        model_id: Some(req.model_id.clone()),
+        model_id: Some(served_model_id.clone()),
         dataset_id: Some(input_cid.clone()),
-        params_hash: compute_hash(&req.mode),
+        params_hash: manifest.params_hash.clone(),
         assigned_node: None,
         budget: req.budget,
         spent: 0,
-        submitted_at: now(),
+        submitted_at: state.clock.now_secs(),
         started_at: None,
         completed_at: None,
         output_cid: None,
         artifacts: Vec::new(),
         progress: 0.0,
-        logs: Vec::new(),
+        logs: JobLog::new(state.log_limits),
+        tee_required: req.tee_required,
+        attestation: None,
+        ab_variant: variant.as_ref().map(|v| v.variant_id.clone()),
+        manifest: Some(manifest),
+        seed: None,
+        live_migration: false,
+        migrations: Vec::new(),
+        terminal_reason: None,
+        anonymous: req.anonymous,
+        data_provenance: None,
+        labels: req.labels.clone(),
+        stale_since: None,
+        estimated_duration_secs: Some(estimated_duration),
     };
 
     state.jobs.write().await.insert(job_id.clone(), job);
+    state.chain.write().await.track(&job_id, TxRole::Submit, submit_tx);
 
-    notify_scheduler(&state.scheduler_url, &job_id).await?;
+    if let Some(variant) = &variant {
+        info!("🔀 Routed infer job {} to variant {} ({})", job_id, variant.variant_id, variant.model_id);
+        state.ab_router.write().await.record_budget(&req.model_id, &variant.variant_id, req.budget);
+    }
 
-    let estimated_cost = 100; // Based on model size + input length
-    let estimated_duration = 5; // Seconds
+    enqueue_job(state, &job_id, req.tee_required, policy, Deadline::NONE).await?;
 
-    Ok(Json(JobSubmitResponse {
+    Ok((deprecation_headers(&warnings), Json(JobSubmitResponse {
         job_id,
         status: JobStatus::Queued,
         estimated_cost,
         estimated_duration_secs: estimated_duration,
-    }))
+        policy_degraded: policy.degraded.clone(),
+        cached: false,
+    })))
 }
 
-async fn submit_agent_job(
+#[derive(Debug, Default, Deserialize)]
+pub struct RerunRequest {
+    #[serde(default)]
+    pub budget: Option<u64>, // Defaults to the original job's budget
+    #[serde(default)]
+    pub allow_deprecated: bool,
+}
+
+/// POST /job/rerun/:id - Resubmit a past job with the inputs locked in its manifest
+async fn rerun_job(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<AgentJobRequest>,
-) -> Result<Json<JobSubmitResponse>, StatusCode> {
-    // Policy check
-    let policy_decision = state.policy_gate.check_submission(
-        &req.submitter_did,
-        "agent",
-        &req.agent_spec_cid,
-        req.budget,
-    ).await.map_err(|_| StatusCode::FORBIDDEN)?;
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+    body: Option<Json<RerunRequest>>,
+) -> Result<(HeaderMap, Json<JobSubmitResponse>), SubmitError> {
+    let rerun = body.map(|Json(b)| b).unwrap_or_default();
+    let caller = Namespace::of_caller(&headers);
+    let job = state.jobs.read().await
+        .get(&job_id)
+        .filter(|job| caller.sees(&job.namespace()))
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let manifest = job.manifest.clone().ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    if job.anonymous {
+        return Err(ServiceError::new(
+            ErrorCode::InvalidRequest,
+            "Anonymous jobs are re-run by submitting again under the ephemeral key",
+        ).into());
+    }
+
+    if !manifest.verify(state.manifest_key.expose()) {
+        warn!("⛔ Manifest for job {} failed signature check", job_id);
+        return Err(StatusCode::PRECONDITION_FAILED.into());
+    }
+    info!("🔁 Re-running job {} from manifest (model {} / {})", job_id, manifest.model_id, manifest.model_cid);
 
-    if !policy_decision.allowed {
-        return Err(StatusCode::FORBIDDEN);
+    match manifest.job_type.as_str() {
+        "train" => {
+            let req = train_request_from_manifest(&manifest, &job, &rerun)?;
+            submit_locked_train_job(&state, req, manifest, Deadline::NONE).await
+        }
+        "infer" => {
+            let req = infer_request_from_manifest(&manifest, &job, &rerun)?;
+            let policy = state.policy_gate.enforce(
+                &req.submitter_did,
+                "infer",
+                &req.model_id,
+                None,
+                &[],
+                req.budget,
+            ).await?;
+            submit_locked_infer_job(&state, req, manifest, None, &policy).await
+        }
+        _ => Err(StatusCode::BAD_REQUEST.into()),
     }
+}
+
+/// Agents run longer than a typical inference
+const AGENT_ESTIMATED_DURATION_SECS: u64 = 300;
+
+async fn submit_agent_job(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AgentJobRequest>,
+) -> Result<Json<JobSubmitResponse>, SubmitError> {
+    labels::validate(&req.labels).map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+    // Policy check
+    let policy = state.policy_gate.enforce(
+        &req.submitter_did,
+        "agent",
+        &req.agent_spec_cid,
+        None,
+        &[],
+        req.budget,
+    ).await?;
 
-    // Submit to blockchain
-    let job_id = state.contract_client.submit_agent_job(
+    // Submit to blockchain under a content-derived job id
+    let params_hash = compute_hash(&req.goal);
+    let job_id = assign_job_id(
+        &state,
+        "agent",
+        &req.submitter_did,
+        &req.agent_spec_cid,
+        None,
+        &params_hash,
+        req.nonce,
+    ).await?;
+    escrow_job_cost(&state, &job_id, &req.submitter_did, req.budget).await?;
+    let submit_tx = match state.contract_client.submit_agent_job(
+        &job_id,
         &req.agent_spec_cid,
         req.budget,
-    ).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    ).await {
+        Ok(tx_hash) => tx_hash,
+        Err(_) => {
+            abandon_job_escrow(&state, &job_id).await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    };
 
     // Create job record
     let job = Job {
@@ -641,438 +1648,8372 @@ async fn submit_agent_job(
         submitter_did: req.submitter_did.clone(),
         model_id: Some(req.agent_spec_cid.clone()),
         dataset_id: None,
-        params_hash: compute_hash(&req.goal),
+        params_hash,
+        assigned_node: None,
+        budget: req.budget,
+        spent: 0,
+        submitted_at: state.clock.now_secs(),
+        started_at: None,
+        completed_at: None,
+        output_cid: None,
+        artifacts: Vec::new(),
+        progress: 0.0,
+        logs: JobLog::new(state.log_limits),
+        tee_required: false,
+        attestation: None,
+        ab_variant: None,
+        manifest: None,
+        seed: None,
+        live_migration: false,
+        migrations: Vec::new(),
+        terminal_reason: None,
+        anonymous: false,
+        data_provenance: None,
+        labels: req.labels.clone(),
+        stale_since: None,
+        estimated_duration_secs: Some(AGENT_ESTIMATED_DURATION_SECS),
+    };
+
+    state.jobs.write().await.insert(job_id.clone(), job);
+    state.chain.write().await.track(&job_id, TxRole::Submit, submit_tx);
+
+    enqueue_job(&state, &job_id, false, &policy, Deadline::NONE).await?;
+
+    Ok(Json(JobSubmitResponse {
+        job_id,
+        status: JobStatus::Queued,
+        estimated_cost: req.budget,
+        estimated_duration_secs: AGENT_ESTIMATED_DURATION_SECS,
+        policy_degraded: policy.degraded.clone(),
+        cached: false,
+    }))
+}
+
+/// POST /job/stream - Submit a streaming transform job. It runs until
+/// cancelled, registering each completed window as a version of its output dataset.
+async fn submit_stream_job(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<StreamJobRequest>,
+) -> Result<Json<JobSubmitResponse>, SubmitError> {
+    req.spec.validate().map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+    let transform = req.spec.transform.reference().to_string();
+
+    let policy = state.policy_gate.enforce(
+        &req.submitter_did,
+        "stream",
+        &transform,
+        None,
+        &[],
+        req.budget,
+    ).await?;
+
+    let params_hash = compute_hash(&serde_json::to_string(&req.spec).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?);
+    let job_id = assign_job_id(
+        &state,
+        "stream",
+        &req.submitter_did,
+        &transform,
+        None,
+        &params_hash,
+        req.nonce,
+    ).await?;
+
+    let job = Job {
+        job_id: job_id.clone(),
+        job_type: JobType::Stream,
+        status: JobStatus::Queued,
+        submitter: "0x...".to_string(),
+        submitter_did: req.submitter_did.clone(),
+        model_id: Some(transform),
+        dataset_id: None,
+        params_hash,
+        assigned_node: None,
+        budget: req.budget,
+        spent: 0,
+        submitted_at: state.clock.now_secs(),
+        started_at: None,
+        completed_at: None,
+        output_cid: None,
+        artifacts: Vec::new(),
+        progress: 0.0,
+        logs: JobLog::new(state.log_limits),
+        tee_required: false,
+        attestation: None,
+        ab_variant: None,
+        manifest: None,
+        seed: None,
+        live_migration: false,
+        migrations: Vec::new(),
+        terminal_reason: None,
+        anonymous: false,
+        data_provenance: None,
+        labels: Labels::new(),
+        stale_since: None,
+        estimated_duration_secs: None,
+    };
+
+    state.jobs.write().await.insert(job_id.clone(), job);
+    state.stream_specs.write().await.insert(job_id.clone(), req.spec);
+
+    if let Err(e) = enqueue_job(&state, &job_id, false, &policy, Deadline::NONE).await {
+        state.stream_specs.write().await.remove(&job_id);
+        return Err(e);
+    }
+
+    Ok(Json(JobSubmitResponse {
+        job_id,
+        status: JobStatus::Queued,
+        estimated_cost: req.budget,
+        estimated_duration_secs: 0, // Runs until cancelled
+        policy_degraded: policy.degraded.clone(),
+        cached: false,
+    }))
+}
+
+const QUANTIZE_ESTIMATED_DURATION_SECS: u64 = 1800;
+
+/// POST /job/quantize - Quantize a trained model. On completion the
+/// quantized model is registered as a child of the parent, unless its
+/// accuracy on the evaluation dataset dropped past the bound.
+async fn submit_quantize_job(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<QuantizeJobRequest>,
+) -> Result<Json<JobSubmitResponse>, SubmitError> {
+    req.validate(&state.quantize).map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+    let (parent_model_id, parent_model_cid) = state.artifacts.read().await
+        .resolve_model(&Namespace::of(&req.submitter_did), &req.model_id)
+        .map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+
+    let consent_vcs = state.artifacts.read().await.dataset_consent(&req.eval_dataset_id).to_vec();
+    let policy = state.policy_gate.enforce(
+        &req.submitter_did,
+        "quantize",
+        &parent_model_id,
+        Some(&req.eval_dataset_id),
+        &consent_vcs,
+        req.budget,
+    ).await?;
+
+    let spec = req.spec(&state.quantize, parent_model_id.clone(), parent_model_cid);
+    let params_hash = compute_hash(&serde_json::to_string(&spec).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?);
+    let job_id = assign_job_id(
+        &state,
+        "quantize",
+        &req.submitter_did,
+        &parent_model_id,
+        Some(&req.eval_dataset_id),
+        &params_hash,
+        req.nonce,
+    ).await?;
+
+    let job = Job {
+        job_id: job_id.clone(),
+        job_type: JobType::Quantize,
+        status: JobStatus::Queued,
+        submitter: "0x...".to_string(),
+        submitter_did: req.submitter_did.clone(),
+        model_id: Some(parent_model_id),
+        dataset_id: Some(req.eval_dataset_id.clone()),
+        params_hash,
         assigned_node: None,
         budget: req.budget,
         spent: 0,
-        submitted_at: now(),
+        submitted_at: state.clock.now_secs(),
         started_at: None,
         completed_at: None,
         output_cid: None,
         artifacts: Vec::new(),
         progress: 0.0,
-        logs: Vec::new(),
+        logs: JobLog::new(state.log_limits),
+        tee_required: false,
+        attestation: None,
+        ab_variant: None,
+        manifest: None,
+        seed: None,
+        live_migration: false,
+        migrations: Vec::new(),
+        terminal_reason: None,
+        anonymous: false,
+        data_provenance: policy.provenance.clone(),
+        labels: Labels::new(),
+        stale_since: None,
+        estimated_duration_secs: Some(QUANTIZE_ESTIMATED_DURATION_SECS),
     };
 
     state.jobs.write().await.insert(job_id.clone(), job);
+    state.quantize_specs.write().await.insert(job_id.clone(), spec);
 
-    notify_scheduler(&state.scheduler_url, &job_id).await?;
+    if let Err(e) = enqueue_job(&state, &job_id, false, &policy, Deadline::NONE).await {
+        state.quantize_specs.write().await.remove(&job_id);
+        return Err(e);
+    }
 
     Ok(Json(JobSubmitResponse {
         job_id,
         status: JobStatus::Queued,
         estimated_cost: req.budget,
-        estimated_duration_secs: 300, // Agents run longer
+        estimated_duration_secs: QUANTIZE_ESTIMATED_DURATION_SECS,
+        policy_degraded: policy.degraded.clone(),
+        cached: false,
     }))
 }
 
 async fn get_job_status(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(job_id): Path<String>,
 ) -> Result<Json<JobStatusResponse>, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
+    reload_job(&state, &job_id).await;
     let jobs = state.jobs.read().await;
-    let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    let Some(job) = jobs.get(&job_id).filter(|job| caller.sees(&job.namespace())) else {
+        // Archived jobs are gone from memory but still on record
+        let archived = state.archived_jobs.read().await;
+        if archived.get(&job_id).is_some_and(|job| caller.sees(&Namespace::of(&job.submitter_did))) {
+            return Err(StatusCode::GONE);
+        }
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let can_cancel = matches!(job.status, JobStatus::Queued | JobStatus::Assigned | JobStatus::Running);
+    let output_id = state.outputs.read().await.output_for_job(&job_id).map(|o| o.output_id.clone());
+    let moderation_hold = state.moderation_holds.read().await.get(&job_id).map(|h| h.decision_id.clone());
+    let job = redact_output(job);
+    drop(jobs);
 
-    let can_cancel = matches!(job.status, JobStatus::Queued | JobStatus::Assigned);
+    // Escrow is only opened once a train job is assigned
+    let escrow = match (&job.job_type, &job.assigned_node) {
+        (JobType::Train, Some(_)) => fetch_escrow(&state.proofs_url, &job_id).await,
+        _ => None,
+    };
 
     Ok(Json(JobStatusResponse {
-        job: job.clone(),
+        job,
         receipts: vec![], // Query ProofOfCompute contract
         can_cancel,
+        output_id,
+        moderation_hold,
+        escrow,
     }))
 }
 
+/// GET /job/:id/archive - The on-chain references kept for a job retention GC evicted
+async fn get_archived_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+) -> Result<Json<ArchivedJob>, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
+    state.archived_jobs.read().await
+        .get(&job_id)
+        .filter(|job| caller.sees(&Namespace::of(&job.submitter_did)))
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// GET /job/:id/timeline - Events from every service, phase durations, cost
+/// attribution and anomalies. Sources that can't be read are marked, not fatal.
+async fn get_job_timeline(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+) -> Result<Json<Timeline>, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
+    let job = state.jobs.read().await
+        .get(&job_id)
+        .filter(|job| caller.sees(&job.namespace()))
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let (local, pull_p95_ms) = {
+        let events = state.events.read().await;
+        (events.for_job(&job_id), events.pull_p95_ms())
+    };
+    let remote = timeline::fetch_remote(&state.runtime_url, &state.proofs_url, &state.receipts_url, &job_id).await;
+    Ok(Json(timeline::assemble(&job, local, &remote, pull_p95_ms, state.clock.now_secs())))
+}
+
+/// Evict finished jobs past the retention policy, keeping an archive stub for
+/// each. Queued, assigned and running jobs and held outputs are never candidates.
+async fn gc_finished_jobs(state: &AppState, now: u64) -> usize {
+    let holds = state.moderation_holds.read().await;
+    let mut jobs = state.jobs.write().await;
+    let finished: Vec<(String, u64)> = jobs
+        .values()
+        .filter(|job| matches!(job.status, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled))
+        .filter(|job| !holds.contains_key(&job.job_id)) // Still open to appeal
+        .map(|job| (job.job_id.clone(), job.completed_at.unwrap_or(job.submitted_at)))
+        .collect();
+    let evicted: Vec<Job> = state
+        .retention
+        .select(finished, now)
+        .iter()
+        .filter_map(|job_id| jobs.remove(job_id))
+        .collect();
+    drop(jobs);
+    drop(holds);
+    if evicted.is_empty() {
+        return 0;
+    }
+    for job in &evicted {
+        if let Err(e) = state.job_store.delete_job(&job.job_id) {
+            warn!("⚠️  Failed to drop evicted job {} from the store: {}", job.job_id, e);
+        }
+    }
+    let mut plans = state.milestone_plans.write().await;
+    let mut specs = state.stream_specs.write().await;
+    let mut quantize_specs = state.quantize_specs.write().await;
+    let mut events = state.events.write().await;
+    let mut results = state.results.write().await;
+    for job in &evicted {
+        plans.remove(&job.job_id);
+        specs.remove(&job.job_id);
+        quantize_specs.remove(&job.job_id);
+        events.remove(&job.job_id);
+        results.forget(&job.job_id);
+    }
+    drop(plans);
+    drop(specs);
+    drop(quantize_specs);
+    drop(events);
+    drop(results);
+
+    let redacted: Vec<Job> = evicted.iter().map(redact_output).collect();
+    if let Err(e) = state.retention.archive(&redacted) {
+        warn!("⚠️  Failed to archive evicted jobs: {}", e);
+    }
+    let mut archived = state.archived_jobs.write().await;
+    for job in evicted {
+        archived.insert(job.job_id.clone(), ArchivedJob {
+            completed_at: job.completed_at.unwrap_or(job.submitted_at),
+            job_id: job.job_id,
+            status: job.status,
+            submitter_did: job.submitter_did,
+            model_id: job.model_id,
+            params_hash: job.params_hash,
+            assigned_node: job.assigned_node,
+            attestation: job.attestation,
+            terminal_reason: job.terminal_reason,
+            archived_at: now,
+        });
+    }
+    redacted.len()
+}
+
+/// Run retention GC every `interval_secs` on the service clock
+async fn retention_gc_loop(state: Arc<AppState>) {
+    loop {
+        state.clock.sleep(std::time::Duration::from_secs(state.retention.interval_secs)).await;
+        let evicted = gc_finished_jobs(&state, state.clock.now_secs()).await;
+        if evicted > 0 {
+            info!("🧹 Retention GC archived {} finished jobs", evicted);
+        }
+    }
+}
+
+/// Write a job's record through to the store, or drop it from the store
+/// once the job has left the map
+async fn persist_job(state: &AppState, job_id: &str) {
+    let job = state.jobs.read().await.get(job_id).cloned();
+    let result = match &job {
+        Some(job) => state.job_store.save_job(job),
+        None => state.job_store.delete_job(job_id),
+    };
+    if let Err(e) = result {
+        warn!("⚠️  Failed to persist job {}: {}", job_id, e);
+    }
+}
+
+/// Put a job the map doesn't hold back into it from the store, for one
+/// written after the map was restored, e.g. by a daemon sharing the store
+async fn reload_job(state: &AppState, job_id: &str) {
+    if state.jobs.read().await.contains_key(job_id) {
+        return;
+    }
+    match state.job_store.load_job(job_id) {
+        Ok(Some(job)) => {
+            state.jobs.write().await.entry(job_id.to_string()).or_insert(job);
+        }
+        Ok(None) => {}
+        Err(e) => warn!("⚠️  Failed to load job {} from the store: {}", job_id, e),
+    }
+}
+
+/// Rebuild the job map from the store. Jobs that were Assigned or Running
+/// when the daemon stopped are marked stale, since whatever ai-runtime did
+/// with them meanwhile went unreported. Returns the jobs left to resume:
+/// the stale ones and those still Queued.
+async fn restore_jobs(state: &AppState) -> Result<Vec<String>, String> {
+    let now = state.clock.now_secs();
+    let mut restored = state.job_store.list_jobs()?;
+    let mut pending = Vec::new();
+    for job in &mut restored {
+        if matches!(job.status, JobStatus::Assigned | JobStatus::Running) && job.stale_since.is_none() {
+            job.stale_since = Some(now);
+            state.job_store.save_job(job)?;
+        }
+        if job.stale_since.is_some() || job.status == JobStatus::Queued {
+            pending.push(job.job_id.clone());
+        }
+        if let (JobStatus::Completed, Some(manifest)) = (&job.status, &job.manifest) {
+            state.results.write().await.record(job.owner(), manifest, &job.job_id);
+        }
+    }
+    state.jobs.write().await.extend(restored.into_iter().map(|job| (job.job_id.clone(), job)));
+    Ok(pending)
+}
+
+/// Resume restored jobs until each is accounted for, backing off while the
+/// scheduler or ai-runtime can't be reached
+async fn resume_restored_jobs(state: Arc<AppState>, mut pending: Vec<String>, policy: BackoffPolicy) {
+    let mut backoff = Backoff::new(policy, state.entropy.clone());
+    while !pending.is_empty() {
+        let mut retry = Vec::new();
+        for job_id in pending {
+            if let Err(e) = resume_job(&state, &job_id).await {
+                warn!("⚠️  Failed to resume job {}: {}", job_id, e);
+                retry.push(job_id);
+            }
+        }
+        backoff.record(retry.is_empty());
+        pending = retry;
+        if !pending.is_empty() {
+            state.clock.sleep(backoff.delay()).await;
+        }
+    }
+}
+
+/// Re-queue a restored Queued job with the scheduler, or settle a stale one
+/// against ai-runtime
+async fn resume_job(state: &Arc<AppState>, job_id: &str) -> Result<(), String> {
+    let Some(job) = state.jobs.read().await.get(job_id).cloned() else { return Ok(()) };
+    if job.stale_since.is_some() {
+        return resolve_stale_job(state, &job).await;
+    }
+    if job.status != JobStatus::Queued {
+        return Ok(());
+    }
+    let reservation_id = state.reservations.read().await.of_job(job_id).map(|r| r.reservation_id.clone());
+    let schedule = ScheduleRequest {
+        job_id,
+        tee_required: job.tee_required,
+        reservation_id: reservation_id.as_deref(),
+        estimated_duration_secs: job.estimated_duration_secs,
+        ..ScheduleRequest::default()
+    };
+    notify_scheduler(&state.scheduler_url, &schedule, Deadline::NONE, &*state.clock)
+        .await
+        .map_err(|e| e.message())?;
+    info!("🔁 Re-queued restored job {}", job_id);
+    Ok(())
+}
+
+/// Ask ai-runtime where a stale job got to and catch up on what went
+/// unreported. A job it is still running carries on; one that finished is
+/// completed or failed as if it had reported; one it never started is handed
+/// back to the scheduler, and one it lost fails.
+async fn resolve_stale_job(state: &Arc<AppState>, job: &Job) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/job/{}/status", state.runtime_url, job.job_id))
+        .send()
+        .await
+        .map_err(|e| format!("ai-runtime unreachable: {}", e))?;
+    let runtime_job = match response.status().as_u16() {
+        404 => None,
+        _ if response.status().is_success() => Some(response.json::<serde_json::Value>().await.map_err(|e| e.to_string())?),
+        status => return Err(format!("ai-runtime answered {}", status)),
+    };
+    let runtime_status = runtime_job.as_ref().and_then(|j| j["status"].as_str()).unwrap_or_default();
+
+    let mut report = JobProgressRequest { progress: job.progress, ..Default::default() };
+    match (runtime_status, &job.status) {
+        ("Pending" | "Pulling" | "Starting" | "Running" | "Migrating", _) => {
+            if let Some(restored) = state.jobs.write().await.get_mut(&job.job_id) {
+                restored.stale_since = None;
+                if runtime_status == "Running" && restored.status == JobStatus::Assigned {
+                    restored.status = JobStatus::Running;
+                    restored.started_at.get_or_insert(state.clock.now_secs());
+                }
+            }
+            persist_job(state, &job.job_id).await;
+            info!("🔁 Job {} is still {} on ai-runtime", job.job_id, runtime_status);
+            return Ok(());
+        }
+        ("", JobStatus::Assigned) => {
+            if let Some(restored) = state.jobs.write().await.get_mut(&job.job_id) {
+                restored.stale_since = None;
+                restored.status = JobStatus::Queued;
+                restored.assigned_node = None;
+            }
+            persist_job(state, &job.job_id).await;
+            let node = job.assigned_node.clone().unwrap_or_default();
+            let reasons = ["Never started: ai-jobd restarted before the runtime took it".to_string()];
+            reject_assignment(&state.scheduler_url, &job.job_id, &node, &reasons, job.tee_required).await;
+            info!("🔁 Handed job {} back to the scheduler", job.job_id);
+            return Ok(());
+        }
+        ("Completed", _) => {
+            report.progress = 1.0;
+            report.status = Some(JobStatus::Completed);
+        }
+        ("Failed", _) => {
+            report.status = Some(JobStatus::Failed);
+            report.terminal_reason = runtime_job.as_ref().and_then(|j| serde_json::from_value(j["terminal_reason"].clone()).ok());
+            report.failure_reason = Some("Failed on ai-runtime while ai-jobd was down".to_string());
+        }
+        ("", _) => {
+            report.status = Some(JobStatus::Failed);
+            report.failure_reason = Some("Lost while ai-jobd was down: ai-runtime has no record of it".to_string());
+        }
+        (other, _) => {
+            report.status = Some(JobStatus::Failed);
+            report.failure_reason = Some(format!("Lost while ai-jobd was down: ai-runtime reports it {}", other));
+        }
+    }
+
+    if let Some(restored) = state.jobs.write().await.get_mut(&job.job_id) {
+        restored.stale_since = None;
+    }
+    info!("🔁 Job {} finished on ai-runtime while ai-jobd was down", job.job_id);
+    job_progress(State(state.clone()), Path(job.job_id.clone()), Json(report))
+        .await
+        .map(|_| ())
+        .map_err(|status| format!("Failed to record the outcome: {}", status))
+}
+
 async fn cancel_job(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(job_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
+    if !state.jobs.read().await.get(&job_id).is_some_and(|job| caller.sees(&job.namespace())) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let reason = TerminalReason::new(TerminalReasonKind::UserCancelled, "Cancelled by the submitter");
+    cancel(&state, &job_id, reason).await
+}
+
+/// Cancel a queued, assigned or running job, stopping it wherever it runs
+async fn cancel(state: &Arc<AppState>, job_id: &str, reason: TerminalReason) -> Result<StatusCode, StatusCode> {
     let mut jobs = state.jobs.write().await;
-    let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    let job = jobs.get_mut(job_id).ok_or(StatusCode::NOT_FOUND)?;
 
-    if !matches!(job.status, JobStatus::Queued | JobStatus::Assigned) {
+    if !matches!(job.status, JobStatus::Queued | JobStatus::Assigned | JobStatus::Running) {
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    let was_running = job.status == JobStatus::Running;
+    let escrowed = matches!(job.job_type, JobType::Train) && job.assigned_node.is_some();
     job.status = JobStatus::Cancelled;
-    job.completed_at = Some(now());
+    job.completed_at = Some(state.clock.now_secs());
+    job.terminal_reason = Some(reason);
+    state.marketplace.write().await.cancel_usage(job_id);
 
     // Update blockchain
-    state.contract_client.update_job_status(&job_id, &JobStatus::Cancelled).await
+    state.contract_client.update_job_status(job_id, &JobStatus::Cancelled).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    drop(jobs);
+    persist_job(state, job_id).await;
+    if was_running {
+        stop_runtime_job(&state.runtime_url, job_id).await;
+    }
+    // Pay the provider for verified progress and refund the rest
+    if escrowed {
+        cancel_escrow(&state.proofs_url, job_id).await;
+    }
+    state.milestone_plans.write().await.remove(job_id);
+    state.secret_refs.write().await.remove(job_id);
+    release_scheduler_slot(&state.scheduler_url, job_id).await;
+    settle_job_escrows(state, state.clock.now_secs()).await;
+    advance_fanouts(state, job_id).await;
+    advance_workflows(state, job_id).await;
+
     Ok(StatusCode::OK)
 }
 
-async fn get_job_logs(
+/// GET /jobs - The caller's tenant's jobs filtered by `status`, submitter
+/// `did` and any number of `label.<key>=<value>` labels, oldest first, one
+/// page at a time
+async fn list_jobs(
     State(state): State<Arc<AppState>>,
-    Path(job_id): Path<String>,
-) -> Result<Json<Vec<String>>, StatusCode> {
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Job>>, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
     let jobs = state.jobs.read().await;
-    let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    let status_filter = params.get("status");
+    let did_filter = params.get("did");
+    let label_selector = labels::selector(&params);
 
-    Ok(Json(job.logs.clone()))
+    let filtered = jobs
+        .values()
+        .filter(|job| caller.sees(&job.namespace()))
+        .filter(|job| status_filter.is_none_or(|status| format!("{:?}", job.status).eq_ignore_ascii_case(status)))
+        .filter(|job| did_filter.is_none_or(|did| &job.submitter_did == did))
+        .filter(|job| labels::matches(&job.labels, &label_selector))
+        .map(redact_output);
+    Ok(Json(artha_paging::paginate(filtered, |job| time_key(job.submitted_at, &job.job_id), &page)?))
 }
 
 #[derive(Debug, Deserialize)]
-pub struct JobAssignedRequest {
-    pub job_id: String,
-    pub assigned_node: String,
-    pub runtime: String, // "torch", "tf", "jax", "agent"
+pub struct LogQuery {
+    #[serde(default)]
+    pub full: bool, // Every line from SVDB instead of the in-memory tail
 }
 
-/// POST /job/assigned - Called by scheduler when job is assigned
-async fn job_assigned(
+/// GET /job/:id/logs - The newest lines, or with `full=true` the complete log:
+/// the archived chunks in order followed by lines not yet archived
+async fn get_job_logs(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<JobAssignedRequest>,
-) -> Result<StatusCode, StatusCode> {
-    println!("📬 Job assigned notification: {}", req.job_id);
-    println!("   Node: {}", &req.assigned_node[..16]);
-    println!("   Runtime: {}", req.runtime);
-    
-    // Update job status
-    let mut jobs = state.jobs.write().await;
-    let job = jobs.get_mut(&req.job_id).ok_or(StatusCode::NOT_FOUND)?;
-    job.status = JobStatus::Assigned;
-    job.assigned_node = Some(req.assigned_node.clone());
-    
-    // Start job in ai-runtime
-    let client = reqwest::Client::new();
-    let runtime_url = &state.runtime_url;
-    
-    // Determine runtime based on job type
-    let runtime_image = match req.runtime.as_str() {
-        "torch" => "torch",
-        "tf" => "tf",
-        "jax" => "jax",
-        "agent" => "agent",
-        _ => "torch", // default
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<Vec<String>>, ServiceError> {
+    let caller = Namespace::of_caller(&headers);
+    let (chunks, pending) = {
+        let jobs = state.jobs.read().await;
+        let job = jobs.get(&job_id).filter(|job| caller.sees(&job.namespace())).ok_or(StatusCode::NOT_FOUND)?;
+        if !query.full {
+            return Ok(Json(job.logs.to_vec()));
+        }
+        (job.logs.chunks().to_vec(), job.logs.pending().cloned().collect::<Vec<_>>())
     };
-    
-    let start_request = serde_json::json!({
-        "job_id": req.job_id,
-        "job_type": match job.job_type {
-            JobType::Train => "Train",
-            JobType::Infer => "Infer",
-            JobType::Agent => "Agent",
-            _ => "Train",
-        },
-        "model_cid": job.model_id.as_ref().unwrap_or(&"".to_string()),
-        "dataset_cid": job.dataset_id.as_ref(),
-        "params": {
-            "epochs": None::<u32>, // Will be filled from job params
-            "batch_size": None::<u32>,
-            "learning_rate": None::<f64>,
-            "optimizer": None::<String>,
-            "checkpoint_interval": Some(500u32),
-        },
-        "runtime": runtime_image,
-    });
-    
-    let response = client
-        .post(&format!("{}/job/start", runtime_url))
-        .json(&start_request)
-        .send()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    if response.status().is_success() {
-        println!("   ✅ Job started in ai-runtime");
-        job.status = JobStatus::Running;
-        job.started_at = Some(now());
-        Ok(StatusCode::OK)
-    } else {
-        println!("   ❌ Failed to start job in runtime: {}", response.status());
-        Err(StatusCode::INTERNAL_SERVER_ERROR)
+
+    let client = reqwest::Client::new();
+    let mut lines = Vec::new();
+    for cid in &chunks {
+        let response = client
+            .get(format!("{}/svdb/download/{}", state.svdb_url, cid))
+            .send()
+            .await
+            .map_err(|e| ServiceError::new(ErrorCode::DependencyUnavailable, format!("SVDB unreachable: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(ServiceError::new(
+                ErrorCode::DependencyFailed,
+                format!("SVDB returned {} for log chunk {}", response.status(), cid),
+            ));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| ServiceError::new(ErrorCode::DependencyFailed, format!("Reading log chunk {}: {}", cid, e)))?;
+        lines.extend(artha_joblog::decode_chunk(&bytes));
     }
+    lines.extend(pending);
+    Ok(Json(lines))
 }
 
-// Helper functions
+/// How often queued log lines are archived to SVDB
+const LOG_ARCHIVE_INTERVAL_SECS: u64 = 10;
 
-fn compute_params_hash(params: &TrainParams) -> String {
-    use sha2::{Sha256, Digest};
-    let mut hasher = Sha256::new();
-    hasher.update(format!("{:?}", params));
-    format!("0x{:x}", hasher.finalize())
-}
+/// Upload every job's queued log lines to SVDB as one chunk per job. Lines
+/// whose upload fails go back on the queue for the next pass.
+async fn archive_job_logs(state: &AppState) -> usize {
+    let batches: Vec<_> = {
+        let mut jobs = state.jobs.write().await;
+        jobs.values_mut()
+            .filter_map(|job| job.logs.take_pending().map(|chunk| (job.job_id.clone(), chunk)))
+            .collect()
+    };
 
-fn compute_hash(data: &str) -> String {
-    use sha2::{Sha256, Digest};
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    format!("0x{:x}", hasher.finalize())
+    let client = reqwest::Client::new();
+    let mut archived = 0;
+    for (job_id, chunk) in batches {
+        let uploaded = async {
+            let response = client
+                .post(format!("{}/svdb/upload", state.svdb_url))
+                .header("Content-Type", "application/octet-stream")
+                .body(chunk.encode())
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("status {}", response.status()));
+            }
+            let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+            body["cid"].as_str().map(str::to_string).ok_or_else(|| "missing cid".to_string())
+        }
+        .await;
+
+        let mut jobs = state.jobs.write().await;
+        let Some(job) = jobs.get_mut(&job_id) else { continue };
+        match uploaded {
+            Ok(cid) => {
+                job.logs.stored(cid);
+                archived += 1;
+            }
+            Err(e) => {
+                warn!("⚠️  Archiving logs of {} failed: {}", job_id, e);
+                job.logs.restore(chunk);
+            }
+        }
+    }
+    archived
 }
 
-fn now() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+#[derive(Debug, Deserialize)]
+pub struct LogStreamQuery {
+    #[serde(default)]
+    pub from: u64, // Number of the first line to send; reconnecting clients resume here
 }
 
-async fn notify_scheduler(scheduler_url: &str, job_id: &str) -> Result<(), StatusCode> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/schedule", scheduler_url);
-    
-    let response = client
-        .post(&url)
-        .json(&serde_json::json!({ "job_id": job_id }))
-        .send()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// How often a log stream checks for new lines
+const LOG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
 
-    if response.status().is_success() {
-        Ok(())
-    } else {
-        Err(StatusCode::INTERNAL_SERVER_ERROR)
+/// GET /job/:id/logs/stream - Server-sent events, one per log line with the
+/// line number as the event id. Lines already evicted from memory are skipped. Ends with an `end` event carrying the final
+/// status once the job is done and every line has been sent.
+async fn stream_job_logs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+    Query(query): Query<LogStreamQuery>,
+) -> Result<axum::response::Response, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
+    if !state.jobs.read().await.get(&job_id).is_some_and(|job| caller.sees(&job.namespace())) {
+        return Err(StatusCode::NOT_FOUND);
     }
+
+    let events = futures_util::stream::unfold(Some(query.from), move |next| {
+        let state = state.clone();
+        let job_id = job_id.clone();
+        async move {
+            let mut next = next?;
+            loop {
+                let ((first, lines), status) = {
+                    let jobs = state.jobs.read().await;
+                    let job = jobs.get(&job_id)?;
+                    (job.logs.since(next), job.status.clone())
+                };
+                next = first;
+                let mut out = String::new();
+                for line in &lines {
+                    out.push_str(&format!("id: {}\ndata: {}\n\n", next, line.replace('\n', " ")));
+                    next += 1;
+                }
+                let done = matches!(status, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled);
+                if done {
+                    out.push_str(&format!("event: end\ndata: {:?}\n\n", status));
+                    return Some((Ok::<_, std::io::Error>(out), None));
+                }
+                if !out.is_empty() {
+                    return Some((Ok(out), Some(next)));
+                }
+                state.clock.sleep(LOG_POLL_INTERVAL).await;
+            }
+        }
+    });
+
+    Ok(axum::response::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "text/event-stream")
+        .header(axum::http::header::CACHE_CONTROL, "no-cache")
+        .body(axum::body::Body::from_stream(events))
+        .unwrap())
 }
 
-fn estimate_train_cost(params: &TrainParams, dataset_id: &str) -> u64 {
-    // Simplified: cost = epochs * dataset_size * GPU_rate
-    // In production: query actual dataset size, GPU type pricing
-    params.epochs as u64 * 1000 // 1000 units per epoch
+#[derive(Debug, Deserialize)]
+pub struct ReceiptWaitQuery {
+    #[serde(default)]
+    pub wait_secs: u64,
+    #[serde(default)]
+    pub finalized: bool, // Wait until the receipt's finalize tx is in a finalized block
 }
 
-fn estimate_train_duration(params: &TrainParams, dataset_id: &str) -> u64 {
-    // Simplified: duration = epochs * samples / batch_size * step_time
-    params.epochs as u64 * 3600 // 1 hour per epoch estimate
+/// GET /job/:id/receipt - Wait for the job's receipt, optionally until its
+/// on-chain finalize tx is finalized so callers never act on a reorgable result
+async fn wait_for_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Query(query): Query<ReceiptWaitQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !state.jobs.read().await.contains_key(&job_id) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let deadline = state.clock.now_secs() + query.wait_secs.min(600);
+    loop {
+        if let Some(receipt) = find_ready_receipt(&state.receipts_url, &state.node_api_url, &job_id, query.finalized).await {
+            return Ok(Json(receipt));
+        }
+        if state.clock.now_secs() >= deadline {
+            return Err(StatusCode::REQUEST_TIMEOUT);
+        }
+        state.clock.sleep(std::time::Duration::from_secs(1)).await;
+    }
 }
 
-async fn upload_to_svdb(data: &str) -> Result<String, String> {
-    // Real SVDB upload
-    let svdb_url = std::env::var("SVDB_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-    let client = reqwest::Client::new();
-    
-    let response = client
-        .post(&format!("{}/svdb/upload", svdb_url))
-        .body(data.as_bytes().to_vec())
-        .header("Content-Type", "application/octet-stream")
+/// First receipt for the job that satisfies the wait mode, annotated with its
+/// finality. Receipts without a finalize tx have nothing on-chain to wait for.
+async fn find_ready_receipt(
+    receipts_url: &str,
+    node_api_url: &str,
+    job_id: &str,
+    require_finality: bool,
+) -> Option<serde_json::Value> {
+    let receipts: Page<serde_json::Value> = reqwest::Client::new()
+        .get(format!("{}/receipts", receipts_url))
+        .query(&[("job_id", job_id)])
         .send()
         .await
-        .map_err(|e| format!("SVDB upload failed: {}", e))?;
+        .ok()?
+        .json()
+        .await
+        .ok()?;
 
-    if !response.status().is_success() {
-        return Err(format!("SVDB upload failed with status: {}", response.status()));
+    for mut receipt in receipts.items {
+        let finalized = match receipt["finalize_tx"].as_str() {
+            Some(tx) => is_tx_finalized(node_api_url, tx).await,
+            None => true,
+        };
+        if finalized || !require_finality {
+            receipt["finalized"] = serde_json::Value::Bool(finalized);
+            return Some(receipt);
+        }
     }
-
-    let result: serde_json::Value = response.json().await
-        .map_err(|e| format!("Failed to parse SVDB response: {}", e))?;
-
-    result["cid"].as_str()
-        .map(|c| format!("artha://{}", c))
-        .ok_or_else(|| "Missing CID in SVDB response".to_string())
+    None
 }
 
-// ============================================================================
-// Dataset and Model Registration Handlers
-// ============================================================================
+/// Check every watched transaction against the canonical chain and move
+/// job statuses with it. Completed jobs start being watched once the
+/// receipts daemon has a finalize tx for them. Returns how many jobs moved.
+async fn reconcile_chain(state: &AppState) -> usize {
+    let completed: Vec<String> = {
+        let jobs = state.jobs.read().await;
+        let mut chain = state.chain.write().await;
+        chain.retain_jobs(|job_id| jobs.contains_key(job_id));
+        jobs.values()
+            .filter(|job| job.status == JobStatus::Completed && !chain.is_tracked(&job.job_id, TxRole::Finalize))
+            .map(|job| job.job_id.clone())
+            .collect()
+    };
+    for job_id in completed {
+        let receipt = find_ready_receipt(&state.receipts_url, &state.node_api_url, &job_id, false).await;
+        if let Some(tx) = receipt.as_ref().and_then(|r| r["finalize_tx"].as_str()) {
+            state.chain.write().await.track(&job_id, TxRole::Finalize, tx.to_string());
+        }
+    }
 
-#[derive(Debug, Deserialize)]
-pub struct DatasetRegisterRequest {
-    pub root_cid: String,
-    pub license_cid: String,
-    pub tags: Vec<String>,
-}
+    let client = &state.contract_client;
+    let watched = state.chain.read().await.watched();
+    let mut moved = 0;
+    for (job_id, tx) in watched {
+        let observation = match &tx.inclusion {
+            Some(inclusion) => match client.block_hash(inclusion.block_number).await {
+                Ok(Some(hash)) if hash == inclusion.block_hash => {
+                    if !is_tx_finalized(&state.node_api_url, &tx.tx_hash).await {
+                        continue;
+                    }
+                    Observation::Finalized
+                }
+                Ok(canonical) => {
+                    warn!("⛓️  Reorg past block {} ({} -> {:?}) under {:?} tx {} of {}",
+                        inclusion.block_number, inclusion.block_hash, canonical, tx.role, tx.tx_hash, job_id);
+                    match client.transaction_inclusion(&tx.tx_hash).await {
+                        Ok(Some(inclusion)) => Observation::Mined(inclusion),
+                        Ok(None) => Observation::Pending,
+                        Err(e) => {
+                            warn!("⚠️  Re-verifying {} failed: {}", tx.tx_hash, e);
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("⚠️  Reading block {} failed: {}", inclusion.block_number, e);
+                    continue;
+                }
+            },
+            None => match client.transaction_inclusion(&tx.tx_hash).await {
+                Ok(Some(inclusion)) => Observation::Mined(inclusion),
+                Ok(None) => Observation::Pending,
+                Err(e) => {
+                    warn!("⚠️  Reading receipt of {} failed: {}", tx.tx_hash, e);
+                    continue;
+                }
+            },
+        };
 
-#[derive(Debug, Serialize)]
-pub struct DatasetRegisterResponse {
-    pub dataset_id: String,
-    pub root_cid: String,
-    pub registered_at: u64,
+        let mut jobs = state.jobs.write().await;
+        let Some(job) = jobs.get_mut(&job_id) else { continue };
+        let Some(status) = state.chain.write().await.observe(&job_id, &tx.tx_hash, observation, &job.status) else {
+            continue;
+        };
+        warn!("⛓️  Job {} moved {:?} -> {:?} with its {:?} tx {}", job_id, job.status, status, tx.role, tx.tx_hash);
+        state.events.write().await.record(&job_id, state.clock.now_secs(), EventKind::Reorged, serde_json::json!({
+            "tx": tx.tx_hash,
+            "role": tx.role,
+            "from": job.status,
+            "to": status,
+        }));
+        job.status = status;
+        moved += 1;
+        drop(jobs);
+        persist_job(state, &job_id).await;
+    }
+    moved
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ModelRegisterRequest {
-    pub model_cid: String,
-    pub architecture: String,
-    pub base_model_id: Option<String>,
-    pub dataset_id: String,
-    pub code_hash: String,
-    pub version: String,
-    pub license_cid: Option<String>,
+/// Whether the node reports the block containing `tx_hash` as finalized
+async fn is_tx_finalized(node_api_url: &str, tx_hash: &str) -> bool {
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/transactions/{}", node_api_url, tx_hash.trim_start_matches("0x")))
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) if resp.status().is_success() => resp
+            .json::<serde_json::Value>()
+            .await
+            .map(|tx| tx["finalized"].as_bool().unwrap_or(false))
+            .unwrap_or(false),
+        _ => false,
+    }
 }
 
-#[derive(Debug, Serialize)]
-pub struct ModelRegisterResponse {
-    pub model_id: String,
-    pub model_cid: String,
-    pub registered_at: u64,
+#[derive(Debug, Default, Deserialize)]
+pub struct JobProgressRequest {
+    pub progress: f32,
+    pub epochs_completed: Option<u64>,
+    pub status: Option<JobStatus>,
+    pub output_cid: Option<String>,
+    pub peak_vram_mb: Option<u64>,
+    pub failure_reason: Option<String>,
+    #[serde(default)]
+    pub output_text: Option<String>, // Inline output, moderated before the job completes
+    #[serde(default)]
+    pub migratable: Option<MigrationHandoff>, // Exported for live migration; re-queue elsewhere
+    #[serde(default)]
+    pub spent: Option<u64>, // Metered compute so far
+    #[serde(default)]
+    pub metrics: Option<HashMap<String, f64>>, // Evaluation metrics; workflow conditions read them
+    #[serde(default)]
+    pub terminal_reason: Option<TerminalReason>, // Why a Failed job stopped; a bare failure_reason is a runtime error
+    #[serde(default)]
+    pub output_digest: Option<String>, // blake3 of an infer output's bytes, hex
+    #[serde(default)]
+    pub output_signature: Option<String>, // The node's signature over `outputs::output_message`
 }
 
-async fn register_dataset(
-    State(state): State<Arc<AppState>>,
-    Json(req): Json<DatasetRegisterRequest>,
-) -> Result<Json<DatasetRegisterResponse>, StatusCode> {
-    // Call real DatasetRegistry contract
-    let dataset_id = state.contract_client
-        .register_dataset(&req.root_cid, &req.license_cid, &req.tags)
+/// Run an enforced ai-ethics check over a completed output. Returns the
+/// decision id if the output is blocked, or an error if ai-ethics couldn't
+/// give a verdict; callers decide whether that holds the output back.
+async fn moderate_output(ethics_url: &str, job_id: &str, submitter_did: &str, output: &str) -> Result<Option<String>, String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/ethics/check", ethics_url))
+        .json(&serde_json::json!({
+            "content": output,
+            "checks": ["toxicity", "jailbreak", "bias", "nsfw"],
+            "tenant": submitter_did,
+            "job_id": job_id,
+            "enforce": true,
+        }))
+        .send()
+        .await;
+    let verdict: serde_json::Value = response
+        .map_err(|e| e.to_string())?
+        .json()
         .await
-        .map_err(|e| {
-            println!("❌ Dataset registration failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-    
-    println!("📊 Registered dataset on-chain: {}", dataset_id);
-    println!("   Root CID: {}", req.root_cid);
-    println!("   License CID: {}", req.license_cid);
-    println!("   Tags: {:?}", req.tags);
-    
-    Ok(Json(DatasetRegisterResponse {
-        dataset_id,
-        root_cid: req.root_cid,
-        registered_at: now(),
-    }))
+        .map_err(|e| format!("Unreadable verdict: {}", e))?;
+    if verdict["allowed"].as_bool().unwrap_or(true) {
+        return Ok(None);
+    }
+    Ok(verdict["decision_id"].as_str().map(|id| id.to_string()))
 }
 
-async fn list_datasets(
-    Query(params): Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
-    // In production: query DatasetRegistry contract
-    let _owner = params.get("owner");
-    
-    Ok(Json(vec![])) // Empty for now
+/// POST /job/:id/progress - Called by ai-runtime with training progress
+async fn job_progress(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Json(mut req): Json<JobProgressRequest>,
+) -> Result<StatusCode, StatusCode> {
+    if let Some(handoff) = req.migratable.take() {
+        return requeue_migrated(&state, &job_id, &handoff).await;
+    }
+
+    // A signed output digest only counts from the node the job was placed on
+    let mut integrity = match (&req.output_cid, req.output_digest.take(), req.output_signature.take()) {
+        (Some(output_cid), Some(output_digest), Some(signature)) => {
+            let jobs = state.jobs.read().await;
+            let node_pubkey = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?.assigned_node.clone().unwrap_or_default();
+            let integrity = OutputIntegrity { output_cid: output_cid.clone(), output_digest, node_pubkey, signature };
+            if !integrity.signed_by_node(&job_id) {
+                warn!("⛔ Output of {} is not signed by its node {:?}", job_id, integrity.node_pubkey);
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            Some(integrity)
+        }
+        _ => None,
+    };
+
+    // A blocked output fails the job and is held until an appeal overturns the
+    // decision. Without a verdict, outputs are released, except anonymous ones.
+    if let (Some(JobStatus::Completed), Some(ethics_url), Some(output)) = (&req.status, &state.ethics_url, req.output_text.take()) {
+        let (owner, anonymous) = {
+            let jobs = state.jobs.read().await;
+            let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+            (job.owner().to_string(), job.anonymous)
+        };
+        let verdict = moderate_output(ethics_url, &job_id, &owner, &output).await;
+        if let Err(e) = &verdict {
+            warn!("⚠️  Ethics check for {} failed{}: {}", job_id, if anonymous { ", withholding anonymous output" } else { ", releasing output" }, e);
+        }
+        if let (Err(_), true) = (&verdict, anonymous) {
+            req.output_cid = None;
+            let reason = "Output withheld: anonymous outputs are only released with a moderation verdict".to_string();
+            if let Some(job) = state.jobs.write().await.get_mut(&job_id) {
+                job.logs.push(reason.clone());
+            }
+            req.status = Some(JobStatus::Failed);
+            req.terminal_reason = Some(TerminalReason::new(TerminalReasonKind::ModerationBlocked, reason.clone()));
+            req.failure_reason = Some(reason);
+        }
+        if let Ok(Some(decision_id)) = verdict {
+            info!("🛑 Output of {} held by moderation decision {}", job_id, decision_id);
+            state.moderation_holds.write().await.insert(job_id.clone(), ModerationHold {
+                decision_id: decision_id.clone(),
+                output_cid: req.output_cid.take(),
+                integrity: integrity.take(),
+                held_at: state.clock.now_secs(),
+            });
+            let reason = format!("Output blocked by moderation decision {}; appeal via POST /ethics/appeals", decision_id);
+            if let Some(job) = state.jobs.write().await.get_mut(&job_id) {
+                job.logs.push(reason.clone());
+            }
+            req.status = Some(JobStatus::Failed);
+            req.terminal_reason = Some(TerminalReason::new(TerminalReasonKind::ModerationBlocked, reason.clone()));
+            req.failure_reason = Some(reason);
+        }
+    }
+
+    // A quantized model is kept only if its accuracy held up. The child is
+    // registered before the job completes, so a failed registration fails it.
+    let quantize = state.quantize_specs.read().await.get(&job_id).cloned();
+    if let (Some(JobStatus::Completed), Some(spec)) = (&req.status, quantize) {
+        let (output_cid, code_hash, namespace) = {
+            let jobs = state.jobs.read().await;
+            let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+            (req.output_cid.clone().or_else(|| job.output_cid.clone()), job.params_hash.clone(), job.namespace())
+        };
+        let outcome = match (spec.judge(req.metrics.as_ref()), output_cid) {
+            (Err(e), _) => Err(e),
+            (Ok(_), None) => Err("Quantization reported no output".to_string()),
+            (Ok(drop), Some(model_cid)) => {
+                register_quantized(&state, &namespace, &spec, &model_cid, &code_hash).await.map(|id| (id, drop))
+            }
+        };
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        match outcome {
+            Ok((model_id, drop)) => {
+                info!("🗜️  {} quantized {} into {} (accuracy drop {:.4})", job_id, spec.parent_model_id, model_id, drop);
+                job.logs.push(format!("Registered quantized model {} (accuracy drop {:.4})", model_id, drop));
+                job.artifacts.push(model_id);
+            }
+            Err(reason) => {
+                warn!("⚠️  Rejected quantization {}: {}", job_id, reason);
+                job.logs.push(reason.clone());
+                req.status = Some(JobStatus::Failed);
+                req.terminal_reason = Some(TerminalReason::new(TerminalReasonKind::QualityGate, reason.clone()));
+                req.failure_reason = Some(reason);
+            }
+        }
+    }
+
+    if let Some(metrics) = &req.metrics {
+        state.workflows.write().await.record_metrics(&job_id, metrics);
+    }
+
+    let finished = {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        job.progress = req.progress.clamp(0.0, 1.0);
+        if let Some(spent) = req.spent {
+            job.spent = spent;
+        }
+        // Epoch boundaries and spend updates; routine progress ticks aren't kept
+        if req.epochs_completed.is_some() || req.spent.is_some() {
+            state.events.write().await.record(&job_id, state.clock.now_secs(), EventKind::Progress, serde_json::json!({
+                "progress": job.progress,
+                "epochs_completed": req.epochs_completed,
+                "spent": job.spent,
+            }));
+        }
+        if let Some(output_cid) = req.output_cid {
+            // Outputs are owned by the submitter and only reachable through signed links
+            let mut outputs = state.outputs.write().await;
+            outputs.register(&job_id, &output_cid, job.owner(), state.clock.now_secs(), &*state.entropy);
+            if let Some(integrity) = integrity {
+                outputs.attest(&job_id, integrity);
+            }
+            job.output_cid = Some(output_cid);
+        }
+        match req.status {
+            Some(status @ (JobStatus::Completed | JobStatus::Failed)) => {
+                job.status = status;
+                job.completed_at = Some(state.clock.now_secs());
+                if job.status == JobStatus::Failed {
+                    job.terminal_reason = Some(req.terminal_reason.take().unwrap_or_else(|| TerminalReason::new(
+                        TerminalReasonKind::RuntimeError,
+                        req.failure_reason.clone().unwrap_or_else(|| "Failed without a reason".to_string()),
+                    )));
+                }
+                if let (JobStatus::Completed, Some(manifest)) = (&job.status, &job.manifest) {
+                    state.results.write().await.record(job.owner(), manifest, &job_id);
+                }
+                let kind = if job.status == JobStatus::Completed { EventKind::Completed } else { EventKind::Failed };
+                state.events.write().await.record(&job_id, state.clock.now_secs(), kind, serde_json::json!({
+                    "spent": job.spent,
+                    "failure_reason": req.failure_reason,
+                    "terminal_reason": job.terminal_reason,
+                }));
+                Some(serde_json::json!({
+                    "status": if matches!(job.status, JobStatus::Completed) { "completed" } else { "failed" },
+                    "started_at": job.started_at,
+                    "completed_at": job.completed_at,
+                    "peak_vram_mb": req.peak_vram_mb,
+                    "failure_reason": req.failure_reason,
+                }))
+            }
+            _ => None,
+        }
+    };
+    persist_job(&state, &job_id).await;
+
+    let settlement = {
+        let mut market = state.marketplace.write().await;
+        if let Some(epochs) = req.epochs_completed {
+            let debited = market.record_epochs(&job_id, epochs);
+            if debited > 0 {
+                info!("📉 Debited {} dataset epochs for {}", debited, job_id);
+            }
+        }
+        if finished.is_some() { market.finish_usage(&job_id) } else { None }
+    };
+
+    if let Some(settlement) = settlement {
+        submit_dataset_settlement(&state.receipts_url, &settlement).await;
+    }
+
+    if let Some(outcome) = finished {
+        // The outcome must reach the scheduler before the release drops its placement
+        report_placement_outcome(&state.scheduler_url, &job_id, &outcome).await;
+        release_scheduler_slot(&state.scheduler_url, &job_id).await;
+        settle_job_escrows(&state, state.clock.now_secs()).await;
+        advance_fanouts(&state, &job_id).await;
+        advance_workflows(&state, &job_id).await;
+    }
+
+    Ok(StatusCode::OK)
 }
 
-async fn get_dataset_info(
-    Path(dataset_id): Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In production: query DatasetRegistry contract
-    Ok(Json(serde_json::json!({
-        "dataset_id": dataset_id,
-        "status": "active"
-    })))
+/// Register a quantized model as a child of its parent, in the quantize
+/// job's namespace. It keeps the parent's runtime requirements and
+/// input/output schema.
+async fn register_quantized(
+    state: &Arc<AppState>,
+    namespace: &Namespace,
+    spec: &QuantizeSpec,
+    model_cid: &str,
+    code_hash: &str,
+) -> Result<String, String> {
+    let (requirements, schema) = {
+        let artifacts = state.artifacts.read().await;
+        (
+            artifacts.model_requirements(&spec.parent_model_id).cloned(),
+            artifacts.model_schema(&spec.parent_model_id).cloned(),
+        )
+    };
+    let req = ModelRegisterRequest {
+        model_cid: model_cid.to_string(),
+        architecture: format!("{}-{}", spec.parent_model_id, spec.precision.as_str()),
+        base_model_id: Some(spec.parent_model_id.clone()),
+        dataset_id: spec.eval_dataset_id.clone(),
+        code_hash: code_hash.to_string(),
+        version: spec.version.clone(),
+        license_cid: None,
+        name: spec.name.clone(),
+        requirements,
+        schema,
+        card: None, // The parent's card doesn't describe the quantized model's evals
+    };
+    match register_model_in(state, namespace, req).await {
+        Ok(model) => Ok(model.model_id),
+        Err(status) => Err(format!("Registering the quantized model failed: {}", status)),
+    }
 }
 
-async fn register_model(
+/// POST /job/:id/migrate - Move a running job that opted in to live
+/// migration off its node. ai-runtime checkpoints it and reports back through
+/// `/job/:id/progress`, where it is re-queued.
+async fn migrate_job(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<ModelRegisterRequest>,
-) -> Result<Json<ModelRegisterResponse>, StatusCode> {
-    // Call real ModelRegistry contract
-    let model_id = state.contract_client
-        .register_model(
-            &req.model_cid,
-            &req.architecture,
-            &req.dataset_id,
-            &req.code_hash,
-            &req.version,
-        )
-        .await
-        .map_err(|e| {
-            println!("❌ Model registration failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-    
-    println!("🧠 Registered model on-chain: {}", model_id);
-    println!("   Model CID: {}", req.model_cid);
-    println!("   Architecture: {}", req.architecture);
-    println!("   Dataset: {}", req.dataset_id);
-    println!("   Version: {}", req.version);
-    
-    Ok(Json(ModelRegisterResponse {
-        model_id,
-        model_cid: req.model_cid,
-        registered_at: now(),
-    }))
+    Path(job_id): Path<String>,
+    Json(req): Json<MigrateRequest>,
+) -> Result<(StatusCode, Json<MigrationRecord>), StatusCode> {
+    let record = {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        if !job.live_migration {
+            return Err(StatusCode::CONFLICT); // Not opted in; it finishes where it is
+        }
+        let node = job.assigned_node.clone().filter(|_| job.status == JobStatus::Running).ok_or(StatusCode::CONFLICT)?;
+        if migration::open(&mut job.migrations).is_some() {
+            return Err(StatusCode::CONFLICT);
+        }
+        let record = MigrationRecord::requested(&req.initiated_by, &req.reason, &node, state.clock.now_secs());
+        job.migrations.push(record.clone());
+        record
+    };
+    info!("🚚 Migrating job {} off {} ({}, by {})", job_id, record.source_node, req.reason, req.initiated_by);
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/job/{}/migrate", state.runtime_url, job_id))
+        .json(&req)
+        .send()
+        .await;
+    if !matches!(&response, Ok(resp) if resp.status().is_success()) {
+        let failure = match response {
+            Ok(resp) => format!("ai-runtime refused the migration ({})", resp.status()),
+            Err(e) => format!("ai-runtime unreachable: {}", e),
+        };
+        error!("   ❌ {}", failure);
+        if let Some(record) = state.jobs.write().await.get_mut(&job_id).and_then(|job| migration::open(&mut job.migrations)) {
+            record.failed(&failure);
+        }
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+    Ok((StatusCode::ACCEPTED, Json(record)))
 }
 
-async fn list_models(
-    Query(params): Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
-    // In production: query ModelRegistry contract
-    let _owner = params.get("owner");
-    
-    Ok(Json(vec![])) // Empty for now
+/// Re-queue a job ai-runtime exported for migration, resuming from its
+/// exported checkpoint on any node but the one it left
+async fn requeue_migrated(state: &AppState, job_id: &str, handoff: &MigrationHandoff) -> Result<StatusCode, StatusCode> {
+    let (source_node, tee_required, estimated_duration_secs) = {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(job_id).ok_or(StatusCode::NOT_FOUND)?;
+        let source_node = job.assigned_node.take().ok_or(StatusCode::CONFLICT)?;
+        // Migrations started on ai-runtime directly have no record yet
+        if migration::open(&mut job.migrations).is_none() {
+            job.migrations.push(MigrationRecord::requested(&handoff.initiated_by, &handoff.reason, &source_node, handoff.requested_at));
+        }
+        if let Some(record) = migration::open(&mut job.migrations) {
+            record.exported(handoff, state.clock.now_secs());
+        }
+        job.status = JobStatus::Queued;
+        job.logs.push(format!("Migrating off {} ({:?}), resuming from {}",
+            source_node, handoff.mode, handoff.resume_from.as_deref().unwrap_or("the start")));
+        (source_node, job.tee_required, job.estimated_duration_secs)
+    };
+    persist_job(state, job_id).await;
+
+    // Free the source's slot, then place the job as usual with the source excluded
+    release_scheduler_slot(&state.scheduler_url, job_id).await;
+    let reservation_id = state.reservations.read().await.of_job(job_id).map(|r| r.reservation_id.clone());
+    let schedule = ScheduleRequest {
+        job_id,
+        tee_required,
+        exclude_nodes: std::slice::from_ref(&source_node),
+        reservation_id: reservation_id.as_deref(),
+        estimated_duration_secs,
+    };
+    if let Err(e) = notify_scheduler(&state.scheduler_url, &schedule, Deadline::NONE, &*state.clock).await {
+        error!("❌ Migrated job {} could not be re-placed", job_id);
+        if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+            if let Some(record) = migration::open(&mut job.migrations) {
+                record.failed("No node could take the job");
+            }
+            job.status = JobStatus::Failed;
+            job.completed_at = Some(state.clock.now_secs());
+            job.terminal_reason = Some(TerminalReason::new(
+                TerminalReasonKind::Unschedulable,
+                format!("No node could take the job after it left {}", source_node),
+            ));
+        }
+        persist_job(state, job_id).await;
+        return Err(e.into_response().status());
+    }
+    Ok(StatusCode::OK)
 }
 
-async fn get_model_lineage(
-    Path(model_id): Path<String>,
-) -> Result<Json<Vec<String>>, StatusCode> {
-    // In production: query ModelRegistry.getLineage()
-    Ok(Json(vec![])) // Empty lineage for now
+#[derive(Debug, Deserialize)]
+pub struct ModerationReleaseRequest {
+    pub decision_id: String,
 }
 
-// Server setup
+/// POST /internal/job/:id/moderation/release - Called by ai-ethics when an
+/// appeal overturns a block: completes the job with its held output
+async fn release_moderation_hold(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ModerationReleaseRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let token = headers.get(outputs::INTERNAL_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    if token != Some(state.internal_token.expose().as_str()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
 
-#[tokio::main]
-async fn main() {
-    let state = Arc::new(AppState {
-        jobs: Arc::new(RwLock::new(HashMap::new())),
-        contract_client: Arc::new(ContractClient::new("http://localhost:8545".to_string())),
-        policy_gate: Arc::new(PolicyGate::new("http://localhost:8082".to_string())),
-        scheduler_url: "http://localhost:8083".to_string(),
-        runtime_url: "http://localhost:8084".to_string(),
-    });
+    let hold = {
+        let mut holds = state.moderation_holds.write().await;
+        match holds.get(&job_id) {
+            None => return Err(StatusCode::NOT_FOUND),
+            Some(hold) if hold.decision_id != req.decision_id => return Err(StatusCode::CONFLICT),
+            Some(_) => holds.remove(&job_id).unwrap(),
+        }
+    };
 
-    let app = Router::new()
-        // Job submission endpoints
-        .route("/job/train", post(submit_train_job))
-        .route("/job/infer", post(submit_infer_job))
-        .route("/job/agent", post(submit_agent_job))
-        .route("/job/assigned", post(job_assigned)) // Called by scheduler
-        .route("/job/:id/status", get(get_job_status))
-        .route("/job/:id/cancel", post(cancel_job))
-        .route("/job/:id/logs", get(get_job_logs))
-        // Dataset endpoints
-        .route("/ai/dataset/register", post(register_dataset))
-        .route("/ai/dataset/list", axum::routing::get(list_datasets))
-        .route("/ai/dataset/:id", axum::routing::get(get_dataset_info))
-        // Model endpoints
-        .route("/ai/model/register", post(register_model))
-        .route("/ai/model/list", axum::routing::get(list_models))
-        .route("/ai/model/:id/lineage", axum::routing::get(get_model_lineage))
-        .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+    {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        if let Some(output_cid) = hold.output_cid {
+            let mut outputs = state.outputs.write().await;
+            outputs.register(&job_id, &output_cid, job.owner(), state.clock.now_secs(), &*state.entropy);
+            if let Some(integrity) = hold.integrity {
+                outputs.attest(&job_id, integrity);
+            }
+            job.output_cid = Some(output_cid);
+        }
+        job.status = JobStatus::Completed;
+        job.completed_at = Some(state.clock.now_secs());
+        job.terminal_reason = None;
+        job.logs.push(format!("Moderation decision {} overturned on appeal; output released", hold.decision_id));
+        if let Some(manifest) = &job.manifest {
+            state.results.write().await.record(job.owner(), manifest, &job_id);
+        }
+    }
+    persist_job(&state, &job_id).await;
+    info!("✅ Output of {} released: decision {} overturned", job_id, hold.decision_id);
 
-    println!("🚀 AI Job Daemon starting on :8081");
-    
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8081").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // Fan-outs that have not joined yet now see a successful child
+    advance_fanouts(&state, &job_id).await;
+    Ok(StatusCode::OK)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Outcome of a fan-out child as seen by the join policy
+fn child_outcome(job: Option<&Job>) -> ChildOutcome {
+    match job.map(|j| &j.status) {
+        Some(JobStatus::Completed) => ChildOutcome::Succeeded {
+            output_cid: job.and_then(|j| j.output_cid.clone()),
+        },
+        Some(JobStatus::Queued | JobStatus::Assigned | JobStatus::Running) => ChildOutcome::Pending,
+        _ => ChildOutcome::Failed,
+    }
+}
+
+/// Re-evaluate the join policy of every fan-out waiting on a finished child
+async fn advance_fanouts(state: &AppState, job_id: &str) {
+    let waiting = state.fanouts.read().await.waiting_on(job_id);
+    if waiting.is_empty() {
+        return;
+    }
+
+    let jobs = state.jobs.read().await;
+    let mut fanouts = state.fanouts.write().await;
+    for fanout in waiting {
+        let outcomes: Vec<ChildOutcome> = fanout.children.iter().map(|c| child_outcome(jobs.get(c))).collect();
+        let decision = fanout.policy.evaluate(&outcomes);
+        match &decision {
+            JoinDecision::Proceed { outputs } => {
+                info!("🔀 Fan-out {} joins with {} surviving outputs", fanout.fanout_id, outputs.len());
+            }
+            JoinDecision::Failed { reason } => error!("❌ Fan-out {} failed: {}", fanout.fanout_id, reason),
+            JoinDecision::Waiting => {}
+        }
+        fanouts.decide(&fanout.fanout_id, decision);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FanOutRequest {
+    pub children: Vec<String>, // Parallel child job ids of one pipeline stage
+    #[serde(flatten)]
+    pub policy: FanOutPolicy,
+}
+
+/// POST /pipeline/fanout - Group a stage's parallel children under a join policy
+async fn create_fanout(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<FanOutRequest>,
+) -> Result<Json<FanOut>, StatusCode> {
+    let jobs = state.jobs.read().await;
+    if req.children.iter().any(|c| !jobs.contains_key(c)) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // Children may already have finished; decide right away if so
+    let outcomes: Vec<ChildOutcome> = req.children.iter().map(|c| child_outcome(jobs.get(c))).collect();
+    let decision = req.policy.evaluate(&outcomes);
+    let mut fanouts = state.fanouts.write().await;
+    let fanout = fanouts.register(req.children, req.policy, state.clock.now_secs(), &*state.entropy)?;
+    fanouts.decide(&fanout.fanout_id, decision);
+
+    fanouts.get(&fanout.fanout_id).cloned().ok_or(StatusCode::INTERNAL_SERVER_ERROR).map(Json)
+}
+
+/// GET /pipeline/fanout/:id - Join decision and the outputs passed forward
+async fn get_fanout(
+    State(state): State<Arc<AppState>>,
+    Path(fanout_id): Path<String>,
+) -> Result<Json<FanOut>, StatusCode> {
+    state.fanouts.read().await
+        .get(&fanout_id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)
+        .map(Json)
+}
+
+/// Run one workflow step: jobs go through the normal submission path,
+/// built-in actions through the handlers behind their own endpoints
+async fn execute_step(state: &Arc<AppState>, workflow_id: &str, launch: &Launch) -> RunUpdate {
+    let failed = |error: String| RunUpdate::Finished {
+        status: StepStatus::Failed,
+        output: None,
+        result: serde_json::Value::Null,
+        spent: 0,
+        error: Some(error),
+    };
+    let succeeded = |result: serde_json::Value| RunUpdate::Finished {
+        status: StepStatus::Succeeded,
+        output: None,
+        result,
+        spent: 0,
+        error: None,
+    };
+    let request = launch.request.clone();
+    // Models and aliases a workflow registers land in its submitter's namespace
+    let namespace = Namespace::of(request["submitter_did"].as_str().unwrap_or_default());
+    let submitted = match launch.action {
+        StepAction::Train => match serde_json::from_value::<TrainJobRequest>(request) {
+            Ok(req) => submit_train_job(State(state.clone()), HeaderMap::new(), Json(req)).await.map(|(_, Json(r))| r.job_id),
+            Err(e) => return failed(format!("Invalid train request: {}", e)),
+        },
+        StepAction::Infer => match serde_json::from_value::<InferJobRequest>(request) {
+            Ok(req) => submit_infer_job(State(state.clone()), Json(req)).await.map(|(_, Json(r))| r.job_id),
+            Err(e) => return failed(format!("Invalid infer request: {}", e)),
+        },
+        StepAction::Agent => match serde_json::from_value::<AgentJobRequest>(request) {
+            Ok(req) => submit_agent_job(State(state.clone()), Json(req)).await.map(|Json(r)| r.job_id),
+            Err(e) => return failed(format!("Invalid agent request: {}", e)),
+        },
+        StepAction::Quantize => match serde_json::from_value::<QuantizeJobRequest>(request) {
+            Ok(req) => submit_quantize_job(State(state.clone()), Json(req)).await.map(|Json(r)| r.job_id),
+            Err(e) => return failed(format!("Invalid quantize request: {}", e)),
+        },
+        StepAction::RegisterModel => {
+            return match serde_json::from_value::<ModelRegisterRequest>(request) {
+                Ok(req) => match register_model_in(state, &namespace, req).await {
+                    Ok(model) => succeeded(serde_json::json!({ "model_id": model.model_id, "model_cid": model.model_cid })),
+                    Err(status) => failed(format!("Model registration failed: {}", status)),
+                },
+                Err(e) => failed(format!("Invalid register_model request: {}", e)),
+            };
+        }
+        StepAction::UpdateAlias => {
+            let target = |field: &str| request[field].as_str().map(str::to_string);
+            let (Some(name), Some(alias), Some(model_id)) = (target("name"), target("alias"), target("model_id")) else {
+                return failed("update_alias needs name, alias and model_id".to_string());
+            };
+            return match set_alias_in(state, &namespace, name, alias, model_id).await {
+                Ok(Json(result)) => succeeded(result),
+                Err(status) => failed(format!("Alias update failed: {}", status)),
+            };
+        }
+        StepAction::EthicsCheck => {
+            let (Some(ethics_url), Some(content)) = (&state.ethics_url, request["content"].as_str()) else {
+                return failed("ethics_check needs ai-ethics configured and a content input".to_string());
+            };
+            let submitter_did = request["submitter_did"].as_str().unwrap_or_default();
+            let subject = format!("{}/{}", workflow_id, launch.step);
+            return match moderate_output(ethics_url, &subject, submitter_did, content).await {
+                Ok(Some(decision_id)) => failed(format!("Blocked by moderation decision {}", decision_id)),
+                Ok(None) => succeeded(serde_json::json!({ "passed": true })),
+                Err(e) => {
+                    warn!("⚠️  Ethics check for {} failed, passing: {}", subject, e);
+                    succeeded(serde_json::json!({ "passed": true }))
+                }
+            };
+        }
+    };
+    match submitted {
+        Ok(job_id) => RunUpdate::Submitted { job_id },
+        Err(e) => failed(format!("Submission refused: {}", e.message())),
+    }
+}
+
+/// Execute whatever the workflow has ready, until it is waiting on jobs
+async fn run_workflow(state: &Arc<AppState>, workflow_id: &str) {
+    loop {
+        let launches = state.workflows.write().await.update(workflow_id, |w| w.plan(state.clock.now_secs())).unwrap_or_default();
+        if launches.is_empty() {
+            return;
+        }
+        for launch in launches {
+            let update = execute_step(state, workflow_id, &launch).await;
+            state.workflows.write().await.update(workflow_id, |w| w.record(launch.step, launch.run, update, state.clock.now_secs()));
+        }
+    }
+}
+
+/// Record a finished job against the workflow step that submitted it, and
+/// move its workflow on
+async fn advance_workflows(state: &Arc<AppState>, job_id: &str) {
+    let Some((workflow_id, step, run)) = state.workflows.read().await.find_job(job_id) else {
+        return;
+    };
+    let Some(job) = state.jobs.read().await.get(job_id).cloned() else {
+        return;
+    };
+    // A job cancelled on its own fails the step, which can then be retried
+    let status = match job.status {
+        JobStatus::Completed => StepStatus::Succeeded,
+        JobStatus::Failed | JobStatus::Cancelled => StepStatus::Failed,
+        _ => return,
+    };
+    let update = RunUpdate::Finished {
+        status,
+        output: job.output_cid.clone(),
+        result: serde_json::Value::Null,
+        spent: job.spent,
+        error: (status == StepStatus::Failed).then(|| format!("Job {} ended {:?}", job_id, job.status)),
+    };
+    state.workflows.write().await.update(&workflow_id, |w| w.record(step, run, update, state.clock.now_secs()));
+    run_workflow(state, &workflow_id).await;
+}
+
+async fn workflow_view(state: &AppState, workflow_id: &str) -> Result<Json<WorkflowView>, StatusCode> {
+    state.workflows.read().await
+        .get(workflow_id)
+        .map(Workflow::view)
+        .ok_or(StatusCode::NOT_FOUND)
+        .map(Json)
+}
+
+/// POST /workflow - Submit a workflow spec, as JSON or (with a YAML content type) YAML
+async fn submit_workflow(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Json<WorkflowView>, ServiceError> {
+    let yaml = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|t| t.contains("yaml"));
+    let spec: WorkflowSpec = if yaml {
+        serde_yaml::from_slice(&body).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_slice(&body).map_err(|e| e.to_string())
+    }
+    .map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, format!("Invalid workflow spec: {}", e)))?;
+    let workflow = Workflow::new(spec, state.clock.now_secs(), &*state.entropy).map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+
+    let workflow_id = workflow.workflow_id.clone();
+    info!("🧭 Workflow {} ({}) submitted with {} steps", workflow_id, workflow.spec.name, workflow.steps.len());
+    state.workflows.write().await.insert(workflow);
+    run_workflow(&state, &workflow_id).await;
+    Ok(workflow_view(&state, &workflow_id).await?)
+}
+
+/// GET /workflow/:id - Step-level DAG with runs, outputs and budget
+async fn get_workflow(
+    State(state): State<Arc<AppState>>,
+    Path(workflow_id): Path<String>,
+) -> Result<Json<WorkflowView>, StatusCode> {
+    workflow_view(&state, &workflow_id).await
+}
+
+/// POST /workflow/:id/cancel - Stop launching steps and cancel running jobs
+async fn cancel_workflow(
+    State(state): State<Arc<AppState>>,
+    Path(workflow_id): Path<String>,
+) -> Result<Json<WorkflowView>, StatusCode> {
+    let running_jobs = state.workflows.write().await
+        .update(&workflow_id, |w| {
+            matches!(w.status, WorkflowStatus::Running | WorkflowStatus::Failed).then(|| w.cancel(state.clock.now_secs()))
+        })
+        .ok_or(StatusCode::NOT_FOUND)?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    for job_id in running_jobs {
+        let reason = TerminalReason::new(TerminalReasonKind::WorkflowCancelled, format!("Workflow {} was cancelled", workflow_id));
+        if let Err(status) = cancel(&state, &job_id, reason).await {
+            warn!("⚠️  Could not cancel workflow job {}: {}", job_id, status);
+        }
+    }
+    info!("🛑 Workflow {} cancelled", workflow_id);
+    workflow_view(&state, &workflow_id).await
+}
+
+/// POST /workflow/:id/step/:step/retry - Re-run a failed step; completed
+/// steps and runs are kept
+async fn retry_workflow_step(
+    State(state): State<Arc<AppState>>,
+    Path((workflow_id, step)): Path<(String, String)>,
+) -> Result<Json<WorkflowView>, ServiceError> {
+    state.workflows.write().await
+        .update(&workflow_id, |w| w.retry(&step, state.clock.now_secs()))
+        .ok_or(StatusCode::NOT_FOUND)?
+        .map_err(|e| ServiceError::new(ErrorCode::Conflict, e))?;
+    run_workflow(&state, &workflow_id).await;
+    Ok(workflow_view(&state, &workflow_id).await?)
+}
+
+/// Hand the owner's share to the receipts pipeline for payout
+async fn submit_dataset_settlement(receipts_url: &str, settlement: &Settlement) {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!("{}/receipt/dataset-usage", receipts_url))
+        .json(settlement)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => {
+            info!("💰 Dataset settlement queued for {}: owner {} fee {}",
+                settlement.job_id, settlement.owner_share, settlement.platform_fee);
+        }
+        _ => warn!("⚠️  Failed to queue dataset settlement for {}", settlement.job_id),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobAssignedRequest {
+    pub job_id: String,
+    pub assigned_node: String,
+    pub runtime: String, // "torch", "tf", "jax", "agent"
+    #[serde(default)]
+    pub score: Option<serde_json::Value>, // The scheduler's score breakdown and the node's rate
+    #[serde(default)]
+    pub assign_tx: Option<String>, // The scheduler's on-chain assignment, watched for reorgs
+}
+
+/// ai-runtime's answer to a capability check
+#[derive(Debug, Deserialize)]
+pub struct CapabilityCheck {
+    pub satisfiable: bool,
+    pub runtime: Option<String>,
+    #[serde(default)]
+    pub unmet: Vec<String>,
+}
+
+/// POST /job/assigned - Called by scheduler when job is assigned
+async fn job_assigned(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<JobAssignedRequest>,
+) -> Result<StatusCode, StatusCode> {
+    info!("📬 Job assigned notification: {}", req.job_id);
+    info!("   Node: {}", &req.assigned_node[..16]);
+    info!("   Runtime: {}", req.runtime);
+    // The submission this placement answers may have given up already
+    let deadline = Deadline::from_headers(&headers).map_err(|e| e.status())?;
+    deadline.check(state.clock.now_millis(), "starting the job").map_err(|e| {
+        info!("   ⌛ {}", e.message);
+        e.status()
+    })?;
+    
+    // Update job status
+    let (job_type, model_id, dataset_id, tee_required, seed, resume_from, submitter_did) = {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(&req.job_id).ok_or(StatusCode::NOT_FOUND)?;
+        job.status = JobStatus::Assigned;
+        job.assigned_node = Some(req.assigned_node.clone());
+        let resume_from = migration::resume_from(&job.migrations);
+        (job.job_type.clone(), job.model_id.clone(), job.dataset_id.clone(), job.tee_required, job.seed, resume_from, job.submitter_did.clone())
+    };
+    persist_job(&state, &req.job_id).await;
+    state.events.write().await.record(&req.job_id, state.clock.now_secs(), EventKind::Assigned, serde_json::json!({
+        "node": req.assigned_node,
+        "score": req.score,
+    }));
+    if let Some(tx) = &req.assign_tx {
+        state.chain.write().await.track(&req.job_id, TxRole::Assign, tx.clone());
+    }
+
+    // Stream jobs run their own transform rather than a framework container
+    if matches!(job_type, JobType::Stream) {
+        return start_stream_on_runtime(&state, &req.job_id).await;
+    }
+
+    // Quantize jobs run the quantization image over the parent model
+    let quantize = state.quantize_specs.read().await.get(&req.job_id).cloned();
+
+    // The model's declared runtime wins; without one, the scheduler's hint is
+    // checked as-is rather than assumed to be torch
+    let declared = match (&quantize, &model_id) {
+        (Some(_), _) => Some(RuntimeRequirements { framework: quantize::RUNTIME.to_string(), ..Default::default() }),
+        (None, Some(model_id)) => state.artifacts.read().await.model_requirements(model_id).cloned(),
+        (None, None) => None,
+    };
+    let mut requirements = declared.unwrap_or_else(|| RuntimeRequirements {
+        framework: req.runtime.clone(),
+        ..Default::default()
+    });
+    requirements.tee_required |= tee_required;
+
+    // Capability handshake: hand the job back for reassignment instead of
+    // launching a container that cannot run it
+    let check = check_runtime_capabilities(&state.runtime_url, &requirements).await.map_err(|e| {
+        error!("   ❌ Capability check failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !check.satisfiable {
+        info!("   ↩️  Runtime cannot run job: {}", check.unmet.join("; "));
+        if let Some(job) = state.jobs.write().await.get_mut(&req.job_id) {
+            job.status = JobStatus::Queued;
+            job.assigned_node = None;
+        }
+        persist_job(&state, &req.job_id).await;
+        reject_assignment(&state.scheduler_url, &req.job_id, &req.assigned_node, &check.unmet, requirements.tee_required).await;
+        return Err(StatusCode::CONFLICT);
+    }
+
+    // Lock the train budget in escrow for milestone payouts to this node,
+    // funded by the cost escrowed at submission
+    let plan = state.milestone_plans.read().await.get(&req.job_id).cloned();
+    let job = state.jobs.read().await.get(&req.job_id).cloned();
+    if let (Some(plan), Some(job)) = (plan, job) {
+        let funding = state.job_escrows.read().await.get(&req.job_id).map(|e| (e.amount, e.escrow_tx.clone()));
+        match open_escrow(&state.proofs_url, &job, &req.assigned_node, &plan, funding.as_ref()).await {
+            Ok(()) => {
+                info!("   🔐 Budget locked in milestone escrow");
+                if let Some(escrow) = state.job_escrows.write().await.get_mut(&req.job_id) {
+                    escrow.funds_milestones = true;
+                }
+            }
+            Err(e) => warn!("   ⚠️  Escrow not opened, paying out at finalize: {}", e),
+        }
+    }
+    
+    // Start job in ai-runtime
+    let client = reqwest::Client::new();
+    let runtime_url = &state.runtime_url;
+    
+    let start_request = serde_json::json!({
+        "job_id": req.job_id,
+        "job_type": match job_type {
+            JobType::Train => "Train",
+            JobType::Infer => "Infer",
+            JobType::Agent => "Agent",
+            JobType::Quantize => "Quantize",
+            _ => "Train",
+        },
+        "model_cid": model_id.unwrap_or_default(),
+        "dataset_cid": dataset_id,
+        "params": {
+            "epochs": None::<u32>, // Will be filled from job params
+            "batch_size": None::<u32>,
+            "learning_rate": None::<f64>,
+            "optimizer": None::<String>,
+            "checkpoint_interval": Some(500u32),
+        },
+        "runtime": check.runtime.unwrap_or(requirements.framework),
+        "tee_required": requirements.tee_required,
+        "image_digest": requirements.image_digest,
+        "seed": seed,
+        "resume_from": resume_from,
+        "env": quantize.as_ref().map(QuantizeSpec::container_env).unwrap_or_default(),
+        "secret_refs": state.secret_refs.read().await.get(&req.job_id).cloned().unwrap_or_default(),
+        "submitter_did": submitter_did,
+    });
+    
+    let request = client
+        .post(&format!("{}/job/start", runtime_url))
+        .json(&start_request);
+    let response = forward_deadline(request, deadline, &*state.clock)
+        .send()
+        .await
+        .map_err(|e| match e.is_timeout() && deadline.millis().is_some() {
+            true => StatusCode::REQUEST_TIMEOUT,
+            false => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    
+    if response.status().as_u16() == 408 {
+        info!("   ⌛ Deadline passed before ai-runtime started the job");
+        Err(StatusCode::REQUEST_TIMEOUT)
+    } else if response.status().is_success() {
+        info!("   ✅ Job started in ai-runtime");
+        let started: serde_json::Value = response.json().await.unwrap_or_default();
+        state.events.write().await.record(&req.job_id, state.clock.now_secs(), EventKind::ContainerStarted, started["startup"].clone());
+        if let Some(job) = state.jobs.write().await.get_mut(&req.job_id) {
+            job.status = JobStatus::Running;
+            job.started_at = Some(state.clock.now_secs());
+            if let Some(record) = migration::open(&mut job.migrations).filter(|r| r.phase == migration::MigrationPhase::Exported) {
+                record.resumed(&req.assigned_node, state.clock.now_secs());
+            }
+        }
+        persist_job(&state, &req.job_id).await;
+        Ok(StatusCode::OK)
+    } else {
+        error!("   ❌ Failed to start job in runtime: {}", response.status());
+        Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// Hand an assigned stream job's spec to ai-runtime
+async fn start_stream_on_runtime(state: &AppState, job_id: &str) -> Result<StatusCode, StatusCode> {
+    let spec = state.stream_specs.read().await.get(job_id).cloned().ok_or(StatusCode::NOT_FOUND)?;
+    let mut start_request = serde_json::to_value(&spec).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    start_request["job_id"] = serde_json::json!(job_id);
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/job/stream", state.runtime_url))
+        .json(&start_request)
+        .send()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !response.status().is_success() {
+        error!("   ❌ Failed to start stream in runtime: {}", response.status());
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    info!("   ✅ Stream started in ai-runtime");
+    state.events.write().await.record(job_id, state.clock.now_secs(), EventKind::ContainerStarted, serde_json::Value::Null);
+    if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+        job.status = JobStatus::Running;
+        job.started_at = Some(state.clock.now_secs());
+    }
+    persist_job(state, job_id).await;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobAttestedRequest {
+    pub job_id: String,
+    pub tee_type: String,
+    pub measurement: String,
+    pub image_digest: String,
+    pub status: String,
+    pub verified_at: u64,
+}
+
+/// POST /job/attested - Called by ai-proofs once a TEE quote is verified
+async fn job_attested(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<JobAttestedRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut jobs = state.jobs.write().await;
+    let job = jobs.get_mut(&req.job_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("🔏 Attestation for job {}: {} ({})", req.job_id, req.status, req.measurement);
+    job.attestation = Some(AttestationSummary {
+        tee_type: req.tee_type,
+        measurement: req.measurement,
+        image_digest: req.image_digest,
+        status: req.status,
+        verified_at: req.verified_at,
+    });
+
+    Ok(StatusCode::OK)
+}
+
+async fn get_job_provenance(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(job_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let caller = Namespace::of_caller(&headers);
+    let jobs = state.jobs.read().await;
+    let job = jobs.get(&job_id).filter(|job| caller.sees(&job.namespace())).ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut manifest = build_provenance_manifest(&redact_output(job));
+    manifest["output_id"] = serde_json::json!(
+        state.outputs.read().await.output_for_job(&job_id).map(|o| o.output_id.clone())
+    );
+    Ok(Json(manifest))
+}
+
+/// Copy of a job safe to return publicly: the output CID is a capability and
+/// is only handed out through `/job/:id/output/link`
+fn redact_output(job: &Job) -> Job {
+    Job { output_cid: None, ..job.clone() }
+}
+
+/// Provenance manifest: what ran, on which inputs, where, and under what attestation
+fn build_provenance_manifest(job: &Job) -> serde_json::Value {
+    serde_json::json!({
+        "job_id": job.job_id,
+        "job_type": job.job_type,
+        "submitter_did": job.submitter_did,
+        "model_id": job.model_id,
+        "dataset_id": job.dataset_id,
+        "params_hash": job.params_hash,
+        "assigned_node": job.assigned_node,
+        "output_cid": job.output_cid,
+        "artifacts": job.artifacts,
+        "tee": {
+            "required": job.tee_required,
+            "attestation_status": job.attestation.as_ref()
+                .map(|a| a.status.clone())
+                .unwrap_or_else(|| if job.tee_required { "missing".to_string() } else { "not_required".to_string() }),
+            "tee_type": job.attestation.as_ref().map(|a| a.tee_type.clone()),
+            "measurement": job.attestation.as_ref().map(|a| a.measurement.clone()),
+            "image_digest": job.attestation.as_ref().map(|a| a.image_digest.clone()),
+        },
+    })
+}
+
+// Helper functions
+
+/// `Deprecation`/`Warning` headers for submissions that used deprecated items
+fn deprecation_headers(warnings: &[String]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if warnings.is_empty() {
+        return headers;
+    }
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    for warning in warnings {
+        if let Ok(value) = HeaderValue::from_str(warning) {
+            headers.append(axum::http::header::WARNING, value);
+        }
+    }
+    headers
+}
+
+/// Resolve a train submission into a signed manifest
+fn lock_train_manifest(
+    artifacts: &ArtifactRegistry,
+    req: &TrainJobRequest,
+    runtime_image_digest: &str,
+    manifest_key: &str,
+    now: u64,
+    entropy: &dyn Entropy,
+) -> Result<JobManifest, StatusCode> {
+    let manifest = artifacts
+        .lock(&Namespace::of(&req.submitter_did), "train", &req.model_id, Some(&req.dataset_id), None)
+        .map_err(|e| {
+            error!("❌ Cannot lock train manifest: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    // The seed is locked with the other params so re-runs train with it
+    let params = TrainParams { seed: Some(resolve_seed(req, entropy)), ..req.params.clone() };
+    Ok(JobManifest {
+        params: serde_json::to_value(&params).map_err(|_| StatusCode::BAD_REQUEST)?,
+        params_hash: compute_params_hash(&params),
+        runtime_image_digest: runtime_image_digest.to_string(),
+        created_at: now,
+        ..manifest
+    }.sign(manifest_key))
+}
+
+/// The submitter's seed, else a generated one. With a nonce the seed is derived
+/// from the submission, so a resubmission hashes to the same job id and is
+/// still caught as a duplicate.
+fn resolve_seed(req: &TrainJobRequest, entropy: &dyn Entropy) -> u64 {
+    if let Some(seed) = req.params.seed {
+        return seed;
+    }
+    let digest = match req.nonce {
+        Some(nonce) => compute_hash(&format!(
+            "{}:{}:{}:{:?}:{}",
+            req.submitter_did, req.model_id, req.dataset_id, req.params, nonce
+        )),
+        None => format!("{:032x}", entropy.next_u128()),
+    };
+    // Kept within u32 so every framework's seed setter accepts it as-is
+    u64::from_str_radix(&digest.trim_start_matches("0x")[..8], 16).unwrap_or_default()
+}
+
+/// Resolve an infer submission (after A/B routing) into a signed manifest
+fn lock_infer_manifest(
+    artifacts: &ArtifactRegistry,
+    req: &InferJobRequest,
+    served_model_id: &str,
+    input_cid: &str,
+    runtime_image_digest: &str,
+    manifest_key: &str,
+    now: u64,
+) -> Result<JobManifest, StatusCode> {
+    let manifest = artifacts
+        .lock(&req.namespace(), "infer", served_model_id, None, Some(input_cid))
+        .map_err(|e| {
+            error!("❌ Cannot lock infer manifest: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    Ok(JobManifest {
+        model_ref: req.model_id.clone(),
+        params: match &req.input_meta {
+            Some(meta) => serde_json::json!({ "mode": req.mode, "max_tokens": req.max_tokens, "input_meta": meta }),
+            None => serde_json::json!({ "mode": req.mode, "max_tokens": req.max_tokens }),
+        },
+        params_hash: compute_hash(&req.mode),
+        runtime_image_digest: runtime_image_digest.to_string(),
+        created_at: now,
+        ..manifest
+    }.sign(manifest_key))
+}
+
+/// Train request that reproduces a locked manifest, pinned to the resolved model id
+fn train_request_from_manifest(
+    manifest: &JobManifest,
+    job: &Job,
+    rerun: &RerunRequest,
+) -> Result<TrainJobRequest, StatusCode> {
+    Ok(TrainJobRequest {
+        model_id: manifest.model_id.clone(),
+        dataset_id: manifest.dataset_id.clone().ok_or(StatusCode::UNPROCESSABLE_ENTITY)?,
+        submitter_did: job.submitter_did.clone(),
+        params: serde_json::from_value(manifest.params.clone()).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?,
+        budget: rerun.budget.unwrap_or(job.budget),
+        tee_required: job.tee_required,
+        allow_deprecated: rerun.allow_deprecated,
+        nonce: None, // Each rerun is a new job
+        milestones: None,
+        live_migration: job.live_migration,
+        reservation_id: None, // Reruns go through the normal queue
+        secrets: Vec::new(), // Secret names aren't locked in the manifest
+        labels: job.labels.clone(),
+        force_rerun: true, // A re-run exists to run again
+    })
+}
+
+/// Infer request that reproduces a locked manifest, bypassing A/B routing
+fn infer_request_from_manifest(
+    manifest: &JobManifest,
+    job: &Job,
+    rerun: &RerunRequest,
+) -> Result<InferJobRequest, StatusCode> {
+    Ok(InferJobRequest {
+        model_id: manifest.model_id.clone(),
+        input_cid: manifest.input_cid.clone(),
+        inline_input: None,
+        input_meta: serde_json::from_value(manifest.params["input_meta"].clone()).unwrap_or(None),
+        submitter_did: job.submitter_did.clone(),
+        mode: manifest.params["mode"].as_str().ok_or(StatusCode::UNPROCESSABLE_ENTITY)?.to_string(),
+        max_tokens: manifest.params["max_tokens"].as_u64().map(|t| t as u32),
+        budget: rerun.budget.unwrap_or(job.budget),
+        tee_required: job.tee_required,
+        bucketing_key: None,
+        allow_deprecated: rerun.allow_deprecated,
+        nonce: None,
+        anonymous: false,
+        ephemeral_address: None,
+        ephemeral_signature: None,
+        labels: job.labels.clone(),
+        force_rerun: true,
+    })
+}
+
+fn compute_params_hash(params: &TrainParams) -> String {
+    manifest::params_hash(params)
+}
+
+fn compute_hash(data: &str) -> String {
+    use sha2::{Sha256, Digest};
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("0x{:x}", hasher.finalize())
+}
+
+/// Content-addressed job id over the submission (type, submitter, model,
+/// dataset, params hash, nonce). Stable for identical submissions, unrelated
+/// to the transaction hash, and known before the transaction is mined.
+fn derive_job_id(
+    job_type: &str,
+    submitter_did: &str,
+    model_id: &str,
+    dataset_id: Option<&str>,
+    params_hash: &str,
+    nonce: u64,
+) -> String {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"ARTHA_JOB_ID:");
+    // Length-prefix each field so different splits of the same bytes never collide
+    for field in [job_type, submitter_did, model_id, dataset_id.unwrap_or(""), params_hash] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.update([dataset_id.is_some() as u8]);
+    hasher.update(nonce.to_be_bytes());
+    let hash = hasher.finalize();
+    format!("job-{}", hex::encode(&hash[..16]))
+}
+
+/// Pick the job id for a submission. A client-supplied nonce is used as-is and
+/// a repeat of the same submission is a conflict; otherwise the submitter's
+/// next server-assigned nonce is used.
+async fn assign_job_id(
+    state: &AppState,
+    job_type: &str,
+    submitter_did: &str,
+    model_id: &str,
+    dataset_id: Option<&str>,
+    params_hash: &str,
+    nonce: Option<u64>,
+) -> Result<String, StatusCode> {
+    if let Some(nonce) = nonce {
+        let job_id = derive_job_id(job_type, submitter_did, model_id, dataset_id, params_hash, nonce);
+        if state.jobs.read().await.contains_key(&job_id) {
+            warn!("⚠️  Duplicate submission {} (nonce {})", job_id, nonce);
+            return Err(StatusCode::CONFLICT);
+        }
+        return Ok(job_id);
+    }
+
+    let mut nonces = state.submission_nonces.write().await;
+    let next = nonces.entry(submitter_did.to_string()).or_insert(0);
+    let jobs = state.jobs.read().await;
+    loop {
+        let job_id = derive_job_id(job_type, submitter_did, model_id, dataset_id, params_hash, *next);
+        *next += 1;
+        if !jobs.contains_key(&job_id) {
+            return Ok(job_id);
+        }
+    }
+}
+
+/// Job submission failure. Bare statuses and structured errors both reach
+/// the submitter in the shared error shape; scheduler backpressure keeps its
+/// `429` + `Retry-After`.
+#[derive(Debug)]
+enum SubmitError {
+    Status(StatusCode),
+    Service(ServiceError),
+}
+
+impl From<StatusCode> for SubmitError {
+    fn from(status: StatusCode) -> Self {
+        SubmitError::Status(status)
+    }
+}
+
+impl From<ServiceError> for SubmitError {
+    fn from(error: ServiceError) -> Self {
+        SubmitError::Service(error)
+    }
+}
+
+impl SubmitError {
+    /// What went wrong, for callers that report the failure rather than respond with it
+    fn message(&self) -> String {
+        match self {
+            SubmitError::Status(status) => status.to_string(),
+            SubmitError::Service(error) => error.message.clone(),
+        }
+    }
+
+    fn is_deadline_exceeded(&self) -> bool {
+        match self {
+            SubmitError::Status(status) => *status == StatusCode::REQUEST_TIMEOUT,
+            SubmitError::Service(error) => error.kind() == Some(ErrorCode::DeadlineExceeded),
+        }
+    }
+}
+
+impl IntoResponse for SubmitError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            SubmitError::Status(status) => ServiceError::from(status).into_response(),
+            SubmitError::Service(error) => error.into_response(),
+        }
+    }
+}
+
+/// How often ended reservations are settled
+const RESERVATION_SETTLE_INTERVAL_SECS: u64 = 60;
+const JOB_ESCROW_SETTLE_INTERVAL_SECS: u64 = 60;
+
+/// Default retry hint when the scheduler rejects without a usable `Retry-After`
+const DEFAULT_RETRY_AFTER_SECS: u64 = 30;
+
+/// Body of the scheduler's `POST /schedule`
+#[derive(Debug, Default, Serialize)]
+struct ScheduleRequest<'a> {
+    job_id: &'a str,
+    tee_required: bool,
+    exclude_nodes: &'a [String],
+    reservation_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_duration_secs: Option<u64>,
+}
+
+async fn notify_scheduler(
+    scheduler_url: &str,
+    schedule: &ScheduleRequest<'_>,
+    deadline: Deadline,
+    clock: &dyn Clock,
+) -> Result<(), SubmitError> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/schedule", scheduler_url);
+    
+    let request = client
+        .post(&url)
+        .json(schedule);
+    let response = forward_deadline(request, deadline, clock)
+        .send()
+        .await
+        .map_err(|e| match e.is_timeout() && deadline.millis().is_some() {
+            true => SubmitError::from(ServiceError::new(ErrorCode::DeadlineExceeded, "Deadline passed while scheduling")),
+            false => StatusCode::INTERNAL_SERVER_ERROR.into(),
+        })?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else if response.status().as_u16() == 408 {
+        Err(ServiceError::new(ErrorCode::DeadlineExceeded, "Deadline passed while scheduling").into())
+    } else if response.status().as_u16() == 429 {
+        let retry_after_secs = response.headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+        // Relay the scheduler's own error when it sent one
+        let error = response.json::<ServiceError>().await
+            .ok()
+            .filter(|e| e.kind() == Some(ErrorCode::QueueFull))
+            .unwrap_or_else(|| ServiceError::queue_full(retry_after_secs));
+        Err(error.with_retry_after(retry_after_secs).into())
+    } else if schedule.reservation_id.is_some() && matches!(response.status().as_u16(), 404 | 409 | 503) {
+        // The reservation is gone, not open, incompatible or fully in use
+        let message = match response.status().as_u16() {
+            503 => "Every reserved GPU is busy".to_string(),
+            _ => format!("Reservation cannot take the job ({})", response.status()),
+        };
+        Err(ServiceError::new(ErrorCode::Conflict, message).into())
+    } else if response.status().as_u16() == 503 {
+        Err(StatusCode::SERVICE_UNAVAILABLE.into()) // No node can take the job
+    } else {
+        Err(StatusCode::INTERNAL_SERVER_ERROR.into())
+    }
+}
+
+/// Feed a finished job's runtime back to the scheduler's placement models (best effort)
+async fn report_placement_outcome(scheduler_url: &str, job_id: &str, outcome: &serde_json::Value) {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!("{}/schedule/{}/outcome", scheduler_url, job_id))
+        .json(outcome)
+        .send()
+        .await;
+    if result.is_err() {
+        warn!("⚠️  Failed to report placement outcome for {}", job_id);
+    }
+}
+
+/// Ask ai-runtime whether it can satisfy a job's runtime requirements
+async fn check_runtime_capabilities(runtime_url: &str, requirements: &RuntimeRequirements) -> Result<CapabilityCheck, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/capabilities/check", runtime_url))
+        .json(requirements)
+        .send()
+        .await
+        .map_err(|e| format!("Capability check request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Capability check returned {}", response.status()));
+    }
+    response.json().await.map_err(|e| format!("Failed to parse capability check: {}", e))
+}
+
+/// Hand an assignment the node cannot run back to the scheduler for placement elsewhere (best effort)
+async fn reject_assignment(scheduler_url: &str, job_id: &str, node_pubkey: &str, reasons: &[String], tee_required: bool) {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!("{}/schedule/{}/reject", scheduler_url, job_id))
+        .json(&serde_json::json!({
+            "node_pubkey": node_pubkey,
+            "reasons": reasons,
+            "tee_required": tee_required,
+        }))
+        .send()
+        .await;
+    if result.is_err() {
+        warn!("⚠️  Failed to return job {} to the scheduler", job_id);
+    }
+}
+
+/// Pass the caller's deadline on to a downstream call and give the call no
+/// longer than the deadline leaves
+fn forward_deadline(request: reqwest::RequestBuilder, deadline: Deadline, clock: &dyn Clock) -> reqwest::RequestBuilder {
+    match (deadline.header_value(), deadline.remaining(clock.now_millis())) {
+        (Some(value), Some(remaining)) => request.header(DEADLINE_HEADER, value).timeout(remaining),
+        _ => request,
+    }
+}
+
+/// Undo a submission whose caller stopped waiting after it went on-chain:
+/// cancel it there, stop whatever the scheduler and runtime started for it,
+/// refund its escrow and forget it here
+async fn abandon_submission(state: &AppState, job_id: &str) {
+    let job = state.jobs.write().await.remove(job_id);
+    persist_job(state, job_id).await;
+    if let Err(e) = state.contract_client.update_job_status(job_id, &JobStatus::Cancelled).await {
+        warn!("⚠️  Failed to cancel abandoned job {} on-chain: {}", job_id, e);
+    }
+    release_scheduler_slot(&state.scheduler_url, job_id).await;
+    if job.as_ref().is_some_and(|job| job.assigned_node.is_some()) {
+        stop_runtime_job(&state.runtime_url, job_id).await;
+        cancel_escrow(&state.proofs_url, job_id).await;
+    }
+    state.marketplace.write().await.cancel_usage(job_id);
+    state.reservations.write().await.detach(job_id);
+    state.milestone_plans.write().await.remove(job_id);
+    state.secret_refs.write().await.remove(job_id);
+    state.events.write().await.remove(job_id);
+    state.chain.write().await.retain_jobs(|tracked| tracked != job_id);
+    abandon_job_escrow(state, job_id).await;
+    info!("⌛ Abandoned job {}: its submitter stopped waiting", job_id);
+}
+
+/// Tell the scheduler a job has left its pending queue (best effort)
+async fn release_scheduler_slot(scheduler_url: &str, job_id: &str) {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!("{}/schedule/{}/release", scheduler_url, job_id))
+        .send()
+        .await;
+    if result.is_err() {
+        warn!("⚠️  Failed to release scheduler slot for {}", job_id);
+    }
+}
+
+async fn stop_runtime_job(runtime_url: &str, job_id: &str) {
+    let result = reqwest::Client::new()
+        .post(format!("{}/job/{}/stop", runtime_url, job_id))
+        .send()
+        .await;
+    if result.is_err() {
+        warn!("⚠️  Failed to stop runtime job {}", job_id);
+    }
+}
+
+/// Lock the job's budget in ai-proofs escrow, released to the assigned node
+/// milestone by milestone. With `funding`, the amount and tx of the cost
+/// escrowed at submission, that lock is the budget instead of a new one.
+async fn open_escrow(
+    proofs_url: &str,
+    job: &Job,
+    provider: &str,
+    plan: &MilestonePlan,
+    funding: Option<&(u64, String)>,
+) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/escrow", proofs_url))
+        .json(&serde_json::json!({
+            "job_id": job.job_id,
+            "payer": job.submitter_did,
+            "provider": provider,
+            "budget_wei": funding.map_or(job.budget, |(amount, _)| *amount),
+            "funded_by": funding.map(|(_, escrow_tx)| escrow_tx),
+            "milestones": {
+                "total_steps": plan.total_steps.unwrap_or(1),
+                "fractions": plan.fractions,
+                "steps": plan.steps,
+            },
+        }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("ai-proofs returned {}", response.status()));
+    }
+    Ok(())
+}
+
+async fn cancel_escrow(proofs_url: &str, job_id: &str) {
+    let result = reqwest::Client::new()
+        .post(format!("{}/escrow/{}/cancel", proofs_url, job_id))
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() || resp.status().as_u16() == 404 => {} // No escrow to settle
+        _ => warn!("⚠️  Failed to settle escrow for cancelled job {}", job_id),
+    }
+}
+
+async fn fetch_escrow(proofs_url: &str, job_id: &str) -> Option<serde_json::Value> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/escrow/{}", proofs_url, job_id))
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().await.ok()
+}
+
+/// Hand a freshly recorded job to the scheduler. If the scheduler pushes back,
+/// the job is not left queued locally: its record and any dataset reservation
+/// are dropped so the submitter can retry cleanly.
+async fn enqueue_job(
+    state: &AppState,
+    job_id: &str,
+    tee_required: bool,
+    policy: &PolicyDecision,
+    deadline: Deadline,
+) -> Result<(), SubmitError> {
+    // Saved before the scheduler hears of it, so a restart re-queues it
+    persist_job(state, job_id).await;
+    let (submitted_at, estimated_duration_secs) = state.jobs.read().await.get(job_id)
+        .map_or_else(|| (state.clock.now_secs(), None), |job| (job.submitted_at, job.estimated_duration_secs));
+    let reservation_id = state.reservations.read().await.of_job(job_id).map(|r| r.reservation_id.clone());
+    let schedule = ScheduleRequest {
+        job_id,
+        tee_required,
+        reservation_id: reservation_id.as_deref(),
+        estimated_duration_secs,
+        ..ScheduleRequest::default()
+    };
+    let result = notify_scheduler(&state.scheduler_url, &schedule, deadline, &*state.clock).await;
+    if let Err(SubmitError::Service(error)) = &result {
+        match error.kind() {
+            Some(ErrorCode::Conflict) => info!("🚫 Rejecting job {}: {}", job_id, error.message),
+            Some(ErrorCode::DeadlineExceeded) => info!("⌛ Dropping job {}: {}", job_id, error.message),
+            _ => info!("⏳ Scheduler busy, rejecting job {} (retry after {}s)", job_id, error.retry_after_secs.unwrap_or_default()),
+        }
+        state.jobs.write().await.remove(job_id);
+        persist_job(state, job_id).await;
+        state.marketplace.write().await.cancel_usage(job_id);
+        state.reservations.write().await.detach(job_id);
+        abandon_job_escrow(state, job_id).await;
+        return result;
+    }
+    let mut events = state.events.write().await;
+    events.record(job_id, submitted_at, EventKind::Submitted, serde_json::Value::Null);
+    let mut decided = serde_json::json!({
+        "allowed": policy.allowed,
+        "latency_ms": policy.latency_ms,
+    });
+    if let Some(degraded) = &policy.degraded {
+        decided["degraded"] = serde_json::json!(degraded);
+    }
+    events.record(job_id, submitted_at, EventKind::PolicyDecided, decided);
+    events.record(job_id, state.clock.now_secs(), EventKind::Queued, serde_json::Value::Null);
+    drop(events);
+    if let Err(SubmitError::Status(StatusCode::SERVICE_UNAVAILABLE)) = &result {
+        // Nothing can run it, so it fails now instead of sitting Queued
+        let reason = TerminalReason::new(TerminalReasonKind::Unschedulable, "No node meets the job's requirements");
+        info!("🚫 Job {} is unschedulable", job_id);
+        state.events.write().await.record(job_id, state.clock.now_secs(), EventKind::Failed, serde_json::json!({ "terminal_reason": reason }));
+        if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+            job.status = JobStatus::Failed;
+            job.completed_at = Some(state.clock.now_secs());
+            job.terminal_reason = Some(reason);
+        }
+        persist_job(state, job_id).await;
+        state.marketplace.write().await.cancel_usage(job_id);
+    }
+    result
+}
+
+/// An earlier job `owner` submitted with the same pinned inputs that has
+/// completed, answered in place of running the submission again
+async fn cached_result(state: &AppState, owner: &str, manifest: &JobManifest, force_rerun: bool) -> Option<String> {
+    if force_rerun {
+        return None;
+    }
+    let job_id = state.results.read().await.lookup(owner, manifest)?.to_string();
+    let jobs = state.jobs.read().await;
+    jobs.get(&job_id).filter(|job| job.status == JobStatus::Completed && job.owner() == owner)?;
+    info!("♻️  Identical to completed job {}, returning its result", job_id);
+    Some(job_id)
+}
+
+fn cached_response(job_id: String, policy: &PolicyDecision) -> JobSubmitResponse {
+    JobSubmitResponse {
+        job_id,
+        status: JobStatus::Completed,
+        estimated_cost: 0, // Nothing runs, so nothing is charged
+        estimated_duration_secs: 0,
+        policy_degraded: policy.degraded.clone(),
+        cached: true,
+    }
+}
+
+fn estimate_train_cost(params: &TrainParams, dataset_id: &str) -> u64 {
+    // Simplified: cost = epochs * dataset_size * GPU_rate
+    // In production: query actual dataset size, GPU type pricing
+    params.epochs as u64 * 1000 // 1000 units per epoch
+}
+
+fn estimate_train_duration(params: &TrainParams, dataset_id: &str) -> u64 {
+    // Simplified: duration = epochs * samples / batch_size * step_time
+    params.epochs as u64 * 3600 // 1 hour per epoch estimate
+}
+
+async fn upload_to_svdb(data: &str) -> Result<String, String> {
+    // Real SVDB upload
+    let svdb_url = std::env::var("SVDB_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+    let client = reqwest::Client::new();
+    
+    let response = client
+        .post(&format!("{}/svdb/upload", svdb_url))
+        .body(data.as_bytes().to_vec())
+        .header("Content-Type", "application/octet-stream")
+        .send()
+        .await
+        .map_err(|e| format!("SVDB upload failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("SVDB upload failed with status: {}", response.status()));
+    }
+
+    let result: serde_json::Value = response.json().await
+        .map_err(|e| format!("Failed to parse SVDB response: {}", e))?;
+
+    result["cid"].as_str()
+        .map(|c| format!("artha://{}", c))
+        .ok_or_else(|| "Missing CID in SVDB response".to_string())
+}
+
+// ============================================================================
+// Dataset and Model Registration Handlers
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct DatasetRegisterRequest {
+    pub root_cid: String,
+    pub license_cid: String,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub name: Option<String>, // With `version`, registers name@version
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub samples: Option<u64>,
+    #[serde(default)]
+    pub window: Option<DatasetWindow>, // Stream window the version was cut from
+    #[serde(default)]
+    pub consent_vcs: Vec<String>, // Consent and license VCs the data was collected under; checked before use
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatasetRegisterResponse {
+    pub dataset_id: String,
+    pub root_cid: String,
+    pub registered_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModelRegisterRequest {
+    pub model_cid: String,
+    pub architecture: String,
+    pub base_model_id: Option<String>,
+    pub dataset_id: String,
+    pub code_hash: String,
+    pub version: String,
+    pub license_cid: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>, // Tags the model as name@version and name@latest
+    #[serde(default)]
+    pub requirements: Option<RuntimeRequirements>, // Runtime the model needs; checked at assignment
+    #[serde(default)]
+    pub schema: Option<ModelSchema>, // Input/output tensors; infer inputs are checked against it
+    #[serde(default)]
+    pub card: Option<ModelCard>, // Stored in SVDB, its CID on-chain
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelRegisterResponse {
+    pub model_id: String,
+    pub model_cid: String,
+    pub registered_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_cid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModelBatchRegisterRequest {
+    pub models: Vec<ModelRegisterRequest>,
+}
+
+/// What happened to one model of a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Registered { model_id: String },
+    RolledBack { model_id: String }, // Registered, then deactivated when the batch failed
+    RollbackFailed { model_id: String, error: String }, // Still registered on-chain
+    Failed { error: String },
+    NotAttempted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub model_cid: String,
+    #[serde(flatten)]
+    pub status: BatchItemStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelBatchRegisterResponse {
+    pub committed: bool,
+    pub results: Vec<BatchItemResult>, // In request order
+}
+
+/// POST /ai/dataset/register - Register dataset content, optionally as
+/// `name@version` in the caller's namespace
+async fn register_dataset(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<DatasetRegisterRequest>,
+) -> Result<Json<DatasetRegisterResponse>, StatusCode> {
+    let namespace = Namespace::of_caller(&headers);
+    let named = match (&req.name, &req.version) {
+        (Some(name), Some(version)) => Some((name.clone(), version.clone())),
+        (None, None) => None,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    // Registering the same content under the same name@version again is a
+    // retry and gets the original id; different content is a conflict
+    if let Some((name, version)) = &named {
+        if let Some(existing) = state.artifacts.read().await.dataset_version(&namespace, name, version) {
+            if existing.root_cid != req.root_cid {
+                error!("❌ Dataset {}@{} already registered with {}", name, version, existing.root_cid);
+                return Err(StatusCode::CONFLICT);
+            }
+            return Ok(Json(DatasetRegisterResponse {
+                dataset_id: existing.dataset_id.clone(),
+                root_cid: existing.root_cid.clone(),
+                registered_at: existing.registered_at,
+            }));
+        }
+    }
+
+    // Call real DatasetRegistry contract
+    let dataset_id = state.contract_client
+        .register_dataset(&req.root_cid, &req.license_cid, &req.tags)
+        .await
+        .map_err(|e| {
+            error!("❌ Dataset registration failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    let registered_at = state.clock.now_secs();
+    let title = named.as_ref().map_or_else(|| dataset_id.clone(), |(name, version)| format!("{}@{}", name, version));
+    let mut doc = SearchDoc::new("dataset", &dataset_id, title, registered_at)
+        .owned_by(&namespace)
+        .field("cid", req.root_cid.clone());
+    if let Some((name, version)) = &named {
+        doc = doc.field("name", name.clone()).field("version", version.clone());
+    }
+    for tag in &req.tags {
+        doc = doc.field("tag", tag.clone());
+    }
+    state.search.write().await.stage(doc);
+    match named {
+        Some((name, version)) => state.artifacts.write().await.register_dataset_version(DatasetVersion {
+            dataset_id: dataset_id.clone(),
+            namespace,
+            name,
+            version,
+            root_cid: req.root_cid.clone(),
+            samples: req.samples,
+            window: req.window.clone(),
+            tags: req.tags.clone(),
+            registered_at,
+        }),
+        None => state.artifacts.write().await.register_dataset(&dataset_id, &req.root_cid),
+    }
+    if !req.consent_vcs.is_empty() {
+        state.artifacts.write().await.set_dataset_consent(&dataset_id, req.consent_vcs.clone());
+    }
+
+    info!("📊 Registered dataset on-chain: {}", dataset_id);
+    info!("   Root CID: {}", req.root_cid);
+    info!("   License CID: {}", req.license_cid);
+    info!("   Tags: {:?}", req.tags);
+    
+    Ok(Json(DatasetRegisterResponse {
+        dataset_id,
+        root_cid: req.root_cid,
+        registered_at,
+    }))
+}
+
+/// GET /ai/dataset/list - Named dataset versions in the caller's namespace,
+/// oldest first. `prefix` narrows to dataset names starting with it.
+async fn list_datasets(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    // In production: also query DatasetRegistry contract
+    let _owner = params.get("owner");
+    let prefix = params.get("prefix").map_or("", |p| p.as_str());
+
+    let artifacts = state.artifacts.read().await;
+    let versions = artifacts
+        .dataset_versions(&Namespace::of_caller(&headers), prefix)
+        .into_iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(versions))
+}
+
+async fn get_dataset_info(
+    Path(dataset_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // In production: query DatasetRegistry contract
+    Ok(Json(serde_json::json!({
+        "dataset_id": dataset_id,
+        "status": "active"
+    })))
+}
+
+/// POST /ai/model/register - Register a model; its name tags go in the
+/// caller's namespace
+async fn register_model(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<ModelRegisterRequest>,
+) -> Result<Json<ModelRegisterResponse>, StatusCode> {
+    register_model_in(&state, &Namespace::of_caller(&headers), req).await.map(Json)
+}
+
+async fn register_model_in(
+    state: &AppState,
+    namespace: &Namespace,
+    req: ModelRegisterRequest,
+) -> Result<ModelRegisterResponse, StatusCode> {
+    if let Some(Err(e)) = req.schema.as_ref().map(ModelSchema::check) {
+        error!("❌ Invalid schema for model {}: {}", req.model_cid, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(Err(e)) = req.card.as_ref().map(ModelCard::check) {
+        error!("❌ Invalid card for model {}: {}", req.model_cid, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (model_id, card_cid) = register_model_on_chain(state, &req).await.map_err(|e| {
+        error!("❌ Model registration failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(record_model(state, namespace, model_id, card_cid, req).await)
+}
+
+/// Register a model and, if it has a card, store the card in SVDB and its
+/// CID on-chain. A model whose card can't be recorded is deactivated again.
+async fn register_model_on_chain(
+    state: &AppState,
+    req: &ModelRegisterRequest,
+) -> Result<(String, Option<String>), String> {
+    // Uploaded first so a failed upload leaves nothing on-chain
+    let card_cid = match &req.card {
+        Some(card) => Some(upload_model_card(state, card).await?),
+        None => None,
+    };
+
+    // Call real ModelRegistry contract
+    let model_id = state.contract_client
+        .register_model(
+            &req.model_cid,
+            &req.architecture,
+            &req.dataset_id,
+            &req.code_hash,
+            &req.version,
+        )
+        .await?;
+
+    if let Some(card_cid) = &card_cid {
+        if let Err(e) = state.contract_client.set_model_card(&model_id, card_cid).await {
+            if let Err(rollback) = state.contract_client.deactivate_model(&model_id).await {
+                error!("❌ Could not deactivate model {} after its card failed: {}", model_id, rollback);
+            }
+            return Err(format!("Recording card {} failed: {}", card_cid, e));
+        }
+    }
+    Ok((model_id, card_cid))
+}
+
+async fn upload_model_card(state: &AppState, card: &ModelCard) -> Result<String, String> {
+    let body = serde_json::to_vec(card).map_err(|e| format!("Encoding model card: {}", e))?;
+    let response = reqwest::Client::new()
+        .post(format!("{}/svdb/upload", state.svdb_url))
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Model card upload failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Model card upload failed with status: {}", response.status()));
+    }
+    let result: serde_json::Value = response.json().await.map_err(|e| format!("Invalid SVDB response: {}", e))?;
+    result["cid"].as_str().map(str::to_string).ok_or_else(|| "Missing CID in SVDB response".to_string())
+}
+
+/// Tag, link and index a model the registry has accepted
+async fn record_model(
+    state: &AppState,
+    namespace: &Namespace,
+    model_id: String,
+    card_cid: Option<String>,
+    req: ModelRegisterRequest,
+) -> ModelRegisterResponse {
+    {
+        let mut artifacts = state.artifacts.write().await;
+        artifacts.register_model(namespace, &model_id, &req.model_cid, req.name.as_deref(), &req.version);
+        if let Some(base_model_id) = &req.base_model_id {
+            artifacts.set_parent(&model_id, base_model_id);
+        }
+        if let Some(requirements) = req.requirements.clone() {
+            artifacts.set_model_requirements(&model_id, requirements);
+        }
+        if let Some(schema) = req.schema.clone() {
+            artifacts.set_model_schema(&model_id, schema);
+        }
+        if let Some(card_cid) = &card_cid {
+            artifacts.set_model_card(&model_id, card_cid);
+        }
+    }
+    let title = req.name.as_ref().map_or_else(|| model_id.clone(), |name| format!("{}@{}", name, req.version));
+    state.search.write().await.stage(
+        SearchDoc::new("model", &model_id, title, state.clock.now_secs())
+            .owned_by(namespace)
+            .field("name", req.name.clone().unwrap_or_default())
+            .field("architecture", req.architecture.clone())
+            .field("version", req.version.clone())
+            .field("dataset", req.dataset_id.clone())
+            .field("parent", req.base_model_id.clone().unwrap_or_default())
+            .field("cid", req.model_cid.clone()),
+    );
+
+    info!("🧠 Registered model on-chain: {}", model_id);
+    info!("   Model CID: {}", req.model_cid);
+    info!("   Architecture: {}", req.architecture);
+    info!("   Dataset: {}", req.dataset_id);
+    info!("   Version: {}", req.version);
+    
+    ModelRegisterResponse {
+        model_id,
+        model_cid: req.model_cid,
+        registered_at: state.clock.now_secs(),
+        card_cid,
+    }
+}
+
+/// POST /ai/model/register-batch - Register a set of models, all or none.
+/// Models are registered on-chain in order; if one fails, the ones before it
+/// are deactivated again and nothing is tagged or indexed. Every model gets
+/// a result either way, so a caller can reconcile a rollback that failed.
+async fn register_model_batch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<ModelBatchRegisterRequest>,
+) -> (StatusCode, Json<ModelBatchRegisterResponse>) {
+    let namespace = Namespace::of_caller(&headers);
+    let mut results: Vec<BatchItemResult> = req
+        .models
+        .iter()
+        .enumerate()
+        .map(|(index, model)| BatchItemResult {
+            index,
+            model_cid: model.model_cid.clone(),
+            status: BatchItemStatus::NotAttempted,
+        })
+        .collect();
+
+    // Reject bad schemas and cards before anything reaches the chain
+    let invalid: Vec<(usize, String)> = req
+        .models
+        .iter()
+        .enumerate()
+        .filter_map(|(index, model)| match model.schema.as_ref().map(ModelSchema::check) {
+            Some(Err(e)) => Some((index, format!("Invalid schema: {}", e))),
+            _ => match model.card.as_ref().map(ModelCard::check) {
+                Some(Err(e)) => Some((index, format!("Invalid card: {}", e))),
+                _ => None,
+            },
+        })
+        .collect();
+    if !invalid.is_empty() {
+        for (index, error) in invalid {
+            results[index].status = BatchItemStatus::Failed { error };
+        }
+        return (StatusCode::BAD_REQUEST, Json(ModelBatchRegisterResponse { committed: false, results }));
+    }
+
+    let mut registered = Vec::new();
+    for (index, model) in req.models.iter().enumerate() {
+        match register_model_on_chain(&state, model).await {
+            Ok((model_id, card_cid)) => {
+                results[index].status = BatchItemStatus::Registered { model_id: model_id.clone() };
+                registered.push((model_id, card_cid));
+            }
+            Err(error) => {
+                error!("❌ Batch registration failed at model {} ({}): {}", index, model.model_cid, error);
+                results[index].status = BatchItemStatus::Failed { error };
+                for (earlier, (model_id, _)) in registered.into_iter().enumerate() {
+                    results[earlier].status = match state.contract_client.deactivate_model(&model_id).await {
+                        Ok(_) => BatchItemStatus::RolledBack { model_id },
+                        Err(error) => {
+                            error!("❌ Could not roll back model {}: {}", model_id, error);
+                            BatchItemStatus::RollbackFailed { model_id, error }
+                        }
+                    };
+                }
+                return (StatusCode::BAD_GATEWAY, Json(ModelBatchRegisterResponse { committed: false, results }));
+            }
+        }
+    }
+
+    for (model, (model_id, card_cid)) in req.models.into_iter().zip(registered) {
+        record_model(&state, &namespace, model_id, card_cid, model).await;
+    }
+    info!("🧠 Registered a batch of {} models", results.len());
+    (StatusCode::OK, Json(ModelBatchRegisterResponse { committed: true, results }))
+}
+
+async fn list_models(
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    // In production: query ModelRegistry contract
+    let _owner = params.get("owner");
+    
+    Ok(Json(vec![])) // Empty for now
+}
+
+/// GET /ai/model/:id/lineage - Ancestors of a model, parent first
+async fn get_model_lineage(
+    State(state): State<Arc<AppState>>,
+    Path(model_id): Path<String>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    // In production: also query ModelRegistry.getLineage()
+    Ok(Json(state.artifacts.read().await.lineage(&model_id)))
+}
+
+/// GET /ai/model/:id/card - The card a model was registered with, read
+/// back from SVDB by the CID recorded for it
+async fn get_model_card(
+    State(state): State<Arc<AppState>>,
+    Path(model_id): Path<String>,
+) -> Result<Json<ModelCardView>, ServiceError> {
+    let card_cid = state
+        .artifacts
+        .read()
+        .await
+        .model_card(&model_id)
+        .map(str::to_string)
+        .ok_or_else(|| ServiceError::new(ErrorCode::NotFound, format!("Model {} has no card", model_id)))?;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/svdb/download/{}", state.svdb_url, card_cid))
+        .send()
+        .await
+        .map_err(|e| ServiceError::new(ErrorCode::DependencyUnavailable, format!("SVDB unreachable: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(ServiceError::new(
+            ErrorCode::DependencyFailed,
+            format!("SVDB returned {} for model card {}", response.status(), card_cid),
+        ));
+    }
+    let card = response
+        .json()
+        .await
+        .map_err(|e| ServiceError::new(ErrorCode::DependencyFailed, format!("Reading model card {}: {}", card_cid, e)))?;
+    Ok(Json(ModelCardView { model_id, card_cid, card }))
+}
+
+// ============================================================================
+// Model A/B Routing Handlers
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct AbRouteRequest {
+    pub variants: Vec<ModelVariant>,
+}
+
+async fn set_ab_route(
+    State(state): State<Arc<AppState>>,
+    Path(model_id): Path<String>,
+    Json(req): Json<AbRouteRequest>,
+) -> Result<StatusCode, StatusCode> {
+    state.ab_router.write().await
+        .set_route(&model_id, req.variants)
+        .map_err(|e| {
+            error!("❌ Invalid A/B route for {}: {}", model_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    info!("🔀 A/B route configured for model {}", model_id);
+    Ok(StatusCode::OK)
+}
+
+async fn delete_ab_route(
+    State(state): State<Arc<AppState>>,
+    Path(model_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    if state.ab_router.write().await.remove_route(&model_id) {
+        Ok(StatusCode::OK)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+async fn get_ab_route(
+    State(state): State<Arc<AppState>>,
+    Path(model_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let router = state.ab_router.read().await;
+    let variants = router.variants(&model_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(serde_json::json!({
+        "model_id": model_id,
+        "variants": variants,
+        "metrics": router.metrics(&model_id),
+    })))
+}
+
+// Model alias endpoints. Aliases are tags, so `name@alias` resolves in job
+// submissions like any other tagged reference, within the caller's namespace.
+
+#[derive(Debug, Deserialize)]
+pub struct ModelAliasRequest {
+    pub model_id: String, // Version the alias should point at
+}
+
+async fn set_model_alias(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((name, alias)): Path<(String, String)>,
+    Json(req): Json<ModelAliasRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    set_alias_in(&state, &Namespace::of_caller(&headers), name, alias, req.model_id).await
+}
+
+async fn set_alias_in(
+    state: &AppState,
+    namespace: &Namespace,
+    name: String,
+    alias: String,
+    model_id: String,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if alias.is_empty() || alias == "latest" || model_id.is_empty() {
+        return Err(StatusCode::BAD_REQUEST); // `latest` is maintained by registration
+    }
+    let previous = state.artifacts.write().await.set_alias(namespace, &name, &alias, &model_id);
+
+    info!("🏷️  {}@{} -> {}", name, alias, model_id);
+    Ok(Json(serde_json::json!({
+        "name": name,
+        "alias": alias,
+        "model_id": model_id,
+        "previous": previous,
+    })))
+}
+
+async fn get_model_alias(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((name, alias)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let artifacts = state.artifacts.read().await;
+    let model_id = artifacts.alias(&Namespace::of_caller(&headers), &name, &alias).ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(serde_json::json!({
+        "name": name,
+        "alias": alias,
+        "model_id": model_id,
+    })))
+}
+
+// Dataset marketplace endpoints
+
+#[derive(Debug, Deserialize)]
+pub struct CreateListingRequest {
+    pub dataset_id: String,
+    pub owner_did: String,
+    pub price: ListingPrice,
+    #[serde(default)]
+    pub allowed_job_types: Vec<String>,
+    pub license_cid: String,
+    pub max_concurrent_consumers: Option<u32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub architectures: Vec<String>,
+}
+
+async fn create_listing(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateListingRequest>,
+) -> Result<Json<DatasetListing>, StatusCode> {
+    let listing = DatasetListing {
+        listing_id: format!("listing-{}", state.entropy.uuid()),
+        dataset_id: req.dataset_id,
+        owner_did: req.owner_did,
+        price: req.price,
+        allowed_job_types: req.allowed_job_types,
+        license_cid: req.license_cid,
+        max_concurrent_consumers: req.max_concurrent_consumers,
+        tags: req.tags,
+        architectures: req.architectures,
+        status: ListingStatus::Active,
+        created_at: state.clock.now_secs(),
+    };
+
+    state.marketplace.write().await
+        .create_listing(listing.clone())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    info!("🏷️  Listed dataset {} as {}", listing.dataset_id, listing.listing_id);
+    Ok(Json(listing))
+}
+
+async fn search_listings(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Json<Vec<DatasetListing>> {
+    let market = state.marketplace.read().await;
+    Json(market.search(
+        params.get("tag").map(|s| s.as_str()),
+        params.get("architecture").map(|s| s.as_str()),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingStatusRequest {
+    pub owner_did: String,
+    pub status: ListingStatus,
+}
+
+async fn set_listing_status(
+    State(state): State<Arc<AppState>>,
+    Path(listing_id): Path<String>,
+    Json(req): Json<ListingStatusRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut market = state.marketplace.write().await;
+    if market.listing(&listing_id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    market
+        .set_listing_status(&listing_id, &req.owner_did, req.status)
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurchaseAccessRequest {
+    pub consumer_did: String,
+    pub units: u64, // Jobs or epochs
+    pub duration_secs: u64,
+}
+
+/// POST /market/listing/:id/purchase - The grant is held before its price
+/// is escrowed, so a purchase the listing turns away is never paid for, and
+/// dropped again if the escrow fails
+async fn purchase_access(
+    State(state): State<Arc<AppState>>,
+    Path(listing_id): Path<String>,
+    Json(req): Json<PurchaseAccessRequest>,
+) -> Result<Json<AccessGrant>, StatusCode> {
+    let grant_id = format!("grant-{}", state.entropy.uuid());
+    let amount = {
+        let mut market = state.marketplace.write().await;
+        let listing = market.listing(&listing_id).ok_or(StatusCode::NOT_FOUND)?;
+        let amount = listing.price.unit_price().checked_mul(req.units).ok_or(StatusCode::BAD_REQUEST)?;
+        market
+            .purchase(
+                PurchaseRequest {
+                    grant_id: grant_id.clone(),
+                    listing_id,
+                    consumer_did: req.consumer_did.clone(),
+                    units: req.units,
+                    duration_secs: req.duration_secs,
+                },
+                state.clock.now_secs(),
+            )
+            .map_err(|_| StatusCode::CONFLICT)?;
+        amount
+    };
+
+    let escrow_tx = match state.contract_client.escrow_dataset_access(&grant_id, &req.consumer_did, amount).await {
+        Ok(tx_hash) => tx_hash,
+        Err(e) => {
+            error!("❌ Dataset access escrow failed: {}", e);
+            state.marketplace.write().await.cancel_purchase(&grant_id);
+            return Err(StatusCode::PAYMENT_REQUIRED);
+        }
+    };
+
+    let grant = state.marketplace.write().await.confirm_purchase(&grant_id, escrow_tx).ok_or(StatusCode::CONFLICT)?;
+    Ok(Json(grant))
+}
+
+async fn list_grants(
+    State(state): State<Arc<AppState>>,
+    Path(did): Path<String>,
+) -> Json<Vec<AccessGrant>> {
+    Json(state.marketplace.read().await.grants_for(&did))
+}
+
+async fn get_grant(
+    State(state): State<Arc<AppState>>,
+    Path(grant_id): Path<String>,
+) -> Result<Json<AccessGrant>, StatusCode> {
+    let market = state.marketplace.read().await;
+    market.grant(&grant_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// POST /reservations - Book GPU-hours for a future window. The scheduler
+/// checks projected capacity and prices the booking; the cost is then
+/// escrowed, or the booking is given back if escrow fails.
+async fn book_reservation(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ReservationRequest>,
+) -> Result<Json<Reservation>, ServiceError> {
+    let reservation_id = format!("res-{}", state.entropy.uuid());
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/reservations", state.scheduler_url))
+        .json(&serde_json::json!({
+            "reservation_id": reservation_id,
+            "gpu_type": req.gpu_type,
+            "gpu_count": req.gpu_count,
+            "region": req.region,
+            "earliest_start": req.earliest_start,
+            "latest_start": req.latest_start.unwrap_or(req.earliest_start),
+            "duration_secs": req.duration_secs,
+        }))
+        .send()
+        .await
+        .map_err(|e| ServiceError::new(ErrorCode::DependencyUnavailable, format!("Scheduler unreachable: {}", e)))?;
+    if !response.status().is_success() {
+        // Capacity and validation errors reach the caller as the scheduler sent them
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        return Err(response.json::<ServiceError>().await.unwrap_or_else(|_| ServiceError::from(status)));
+    }
+    let booking: Booking = response
+        .json()
+        .await
+        .map_err(|e| ServiceError::new(ErrorCode::DependencyFailed, format!("Unreadable booking: {}", e)))?;
+
+    let escrow_tx = match state.contract_client.escrow_reservation(&reservation_id, &req.submitter_did, booking.cost).await {
+        Ok(tx_hash) => tx_hash,
+        Err(e) => {
+            error!("❌ Reservation escrow failed: {}", e);
+            let cancelled = client.delete(format!("{}/reservations/{}", state.scheduler_url, reservation_id)).send().await;
+            if cancelled.is_err() {
+                warn!("⚠️  Failed to give back unpaid reservation {}", reservation_id);
+            }
+            return Err(ServiceError::new(ErrorCode::DependencyFailed, format!("Escrow failed: {}", e)));
+        }
+    };
+
+    let reservation = Reservation::new(&req, booking, escrow_tx, state.clock.now_secs());
+    info!("📅 Reservation {} booked: {} x {} from {} to {} for {}", reservation.reservation_id,
+        reservation.gpu_count, reservation.gpu_type, reservation.start, reservation.end, reservation.cost);
+    state.reservations.write().await.insert(reservation.clone());
+    Ok(Json(reservation))
+}
+
+/// GET /reservations - Reservations filtered by submitter `did` and `status`
+/// (upcoming, active, ended, settled), by window start, with utilization so far
+async fn list_reservations(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<ReservationView>>, StatusCode> {
+    settle_reservations(&state, state.clock.now_secs()).await;
+    let now = state.clock.now_secs();
+    let reservations = state.reservations.read().await;
+    let jobs = state.jobs.read().await;
+    let did_filter = params.get("did");
+    let status_filter = params.get("status");
+
+    let views = reservations
+        .all()
+        .filter(|r| did_filter.is_none_or(|did| &r.submitter_did == did))
+        .map(|r| ReservationView {
+            status: r.status(now),
+            utilization: r.refund.as_ref().map_or_else(|| r.utilization(&job_runs(&jobs, r), now), |refund| refund.utilization),
+            reservation: r.clone(),
+        })
+        .filter(|view| status_filter.is_none_or(|status| {
+            serde_json::to_value(view.status).is_ok_and(|s| s.as_str() == Some(status.as_str()))
+        }));
+    Ok(Json(artha_paging::paginate(views, |view| time_key(view.reservation.start, &view.reservation.reservation_id), &page)?))
+}
+
+fn job_runs(jobs: &HashMap<String, Job>, reservation: &Reservation) -> Vec<JobRun> {
+    reservation
+        .jobs
+        .iter()
+        .filter_map(|job_id| jobs.get(job_id))
+        .map(|job| JobRun { started_at: job.started_at, finished_at: job.completed_at })
+        .collect()
+}
+
+/// Refund the unused time of reservations whose window has ended, less the
+/// forfeiture for their utilization. A reservation is claimed before its
+/// refund is sent, so overlapping sweeps never refund it twice; a failed
+/// refund is retried by the next sweep.
+async fn settle_reservations(state: &AppState, now: u64) -> usize {
+    let claimed: Vec<(String, String, Refund)> = {
+        let mut reservations = state.reservations.write().await;
+        let jobs = state.jobs.read().await;
+        reservations
+            .due(now)
+            .into_iter()
+            .filter_map(|reservation_id| {
+                let reservation = reservations.get_mut(&reservation_id)?;
+                let utilization = reservation.utilization(&job_runs(&jobs, reservation), now);
+                let refund = Refund {
+                    utilization,
+                    forfeit: state.forfeiture.forfeit(utilization),
+                    amount: state.forfeiture.refund(reservation.cost, utilization),
+                    tx_hash: None,
+                    settled_at: now,
+                };
+                reservation.refund = Some(refund.clone());
+                Some((reservation_id, reservation.submitter_did.clone(), refund))
+            })
+            .collect()
+    };
+
+    let mut settled = 0;
+    for (reservation_id, payer_did, refund) in claimed {
+        // Sent even when nothing is refunded, since it closes the escrow
+        let sent = state.contract_client.refund_reservation(&reservation_id, &payer_did, refund.amount).await;
+        let mut reservations = state.reservations.write().await;
+        let Some(reservation) = reservations.get_mut(&reservation_id) else { continue };
+        match sent {
+            Ok(tx_hash) => {
+                info!("🧾 Reservation {} settled at {:.0}% utilization, refunded {}",
+                    reservation_id, refund.utilization * 100.0, refund.amount);
+                reservation.refund = Some(Refund { tx_hash: Some(tx_hash), ..refund });
+                settled += 1;
+            }
+            Err(e) => {
+                warn!("⚠️  Refund for reservation {} failed, will retry: {}", reservation_id, e);
+                reservation.refund = None;
+            }
+        }
+    }
+    settled
+}
+
+/// Lock a job's estimated cost from its payer's balance, before the job goes
+/// on-chain. A payer who can't cover it has the submission rejected.
+async fn escrow_job_cost(state: &AppState, job_id: &str, payer: &str, amount: u64) -> Result<(), SubmitError> {
+    let escrow_tx = state.contract_client.escrow_job(job_id, payer, amount).await.map_err(|e| {
+        warn!("🚫 Cost of job {} could not be escrowed: {}", job_id, e);
+        ServiceError::new(ErrorCode::PreconditionFailed, format!("Estimated cost of {} could not be escrowed: {}", amount, e))
+    })?;
+    state.job_escrows.write().await.insert(JobEscrow::new(job_id, payer, amount, escrow_tx, state.clock.now_secs()));
+    Ok(())
+}
+
+/// Refund the whole escrow of a submission that never made it in
+async fn abandon_job_escrow(state: &AppState, job_id: &str) {
+    let Some(escrow) = state.job_escrows.write().await.remove(job_id) else { return };
+    if let Err(e) = state.contract_client.settle_job_escrow(job_id, None, 0, escrow.amount).await {
+        warn!("⚠️  Failed to refund escrow of rejected job {}: {}", job_id, e);
+    }
+}
+
+/// Settle the escrows of jobs that have ended: pay the provider of a
+/// completed job what it spent and refund the rest. Returns how many settled.
+async fn settle_job_escrows(state: &AppState, now: u64) -> usize {
+    let claimed: Vec<(String, EscrowSettlement)> = {
+        let mut escrows = state.job_escrows.write().await;
+        let jobs = state.jobs.read().await;
+        escrows
+            .unsettled()
+            .into_iter()
+            .filter_map(|job_id| {
+                let job = jobs.get(&job_id).filter(|job| matches!(job.status, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled))?;
+                let escrow = escrows.get_mut(&job_id)?;
+                let (paid, refunded) = escrow.split(job.status == JobStatus::Completed, job.spent);
+                let settlement = EscrowSettlement {
+                    provider: job.assigned_node.clone().filter(|_| paid > 0),
+                    paid,
+                    refunded,
+                    tx_hash: None,
+                    settled_at: now,
+                };
+                escrow.settlement = Some(settlement.clone());
+                Some((job_id, settlement))
+            })
+            .collect()
+    };
+
+    let mut settled = 0;
+    for (job_id, settlement) in claimed {
+        let sent = state.contract_client
+            .settle_job_escrow(&job_id, settlement.provider.as_deref(), settlement.paid, settlement.refunded)
+            .await;
+        let mut escrows = state.job_escrows.write().await;
+        let Some(escrow) = escrows.get_mut(&job_id) else { continue };
+        match sent {
+            Ok(tx_hash) => {
+                info!("🧾 Escrow of job {} settled: {} paid, {} refunded", job_id, settlement.paid, settlement.refunded);
+                escrow.settlement = Some(EscrowSettlement { tx_hash: Some(tx_hash), ..settlement });
+                settled += 1;
+            }
+            Err(e) => {
+                warn!("⚠️  Settling escrow of job {} failed, will retry: {}", job_id, e);
+                escrow.settlement = None;
+            }
+        }
+    }
+    settled
+}
+
+/// GET /market/access/check - Consulted by policy-gate for dataset-backed submissions
+async fn check_dataset_access(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let dataset_id = params.get("dataset_id").ok_or(StatusCode::BAD_REQUEST)?;
+    let did = params.get("did").ok_or(StatusCode::BAD_REQUEST)?;
+    let job_type = params.get("job_type").map(|s| s.as_str()).unwrap_or("train");
+    let epochs = params.get("epochs").and_then(|e| e.parse().ok()).unwrap_or(1);
+
+    let market = state.marketplace.read().await;
+    if !market.is_listed(dataset_id) {
+        return Ok(Json(serde_json::json!({ "listed": false, "allowed": true })));
+    }
+
+    Ok(Json(match market.check_access(dataset_id, did, job_type, epochs, state.clock.now_secs()) {
+        Ok(grant) => serde_json::json!({
+            "listed": true,
+            "allowed": true,
+            "grant_id": grant.grant_id,
+            "remaining": grant.remaining(),
+        }),
+        Err(reason) => serde_json::json!({
+            "listed": true,
+            "allowed": false,
+            "reason": reason,
+        }),
+    }))
+}
+
+// ============================================================================
+// Explorer Search
+// ============================================================================
+
+/// Search document of a job, enriched with what is indexed of its model and dataset
+fn job_search_doc(job: &Job, search: &SearchIndex) -> SearchDoc {
+    let model = job.model_id.as_deref().and_then(|id| search.related("model", id));
+    let dataset = job.dataset_id.as_deref().and_then(|id| search.related("dataset", id));
+    let title = format!(
+        "{:?} job on {}",
+        job.job_type,
+        model.map_or(job.model_id.as_deref().unwrap_or("no model"), |m| m.title.as_str()),
+    );
+    let mut doc = SearchDoc::new("job", &job.job_id, title, job.submitted_at)
+        .field("status", format!("{:?}", job.status).to_lowercase())
+        .field("job_type", format!("{:?}", job.job_type).to_lowercase())
+        .field("submitter", job.submitter_did.clone())
+        .field("model", job.model_id.clone().unwrap_or_default())
+        .field("dataset", job.dataset_id.clone().unwrap_or_default())
+        .field("node", job.assigned_node.clone().unwrap_or_default());
+    for label in labels::search_terms(&job.labels) {
+        doc = doc.field("label", label);
+    }
+    for related in model.into_iter().chain(dataset) {
+        doc = doc.field(&related.kind, related.title.clone());
+        for key in ["architecture", "tag"] {
+            for value in related.fields.get(key).into_iter().flatten() {
+                doc = doc.field(key, value.clone());
+            }
+        }
+    }
+    doc.owner = Some(job.submitter_did.clone());
+    doc.logs = job.logs.to_vec();
+    doc.logged_at = job.completed_at.or(job.started_at).unwrap_or(job.submitted_at);
+    doc
+}
+
+/// Bring the search index up to date: staged models and datasets and sent
+/// transactions first, so job documents can be enriched with them, then
+/// every job that changed since the last sync. Returns the documents changed.
+async fn sync_search(state: &AppState) -> Result<usize, String> {
+    let sent = state.contract_client.take_sent();
+    let jobs: Vec<Job> = state.jobs.read().await.values().cloned().collect();
+    let at = state.clock.now_secs();
+    let mut search = state.search.write().await;
+    let mut docs = search.take_staged();
+    docs.extend(sent.into_iter().map(SentTx::into_doc));
+    let mut changed = search.upsert(docs, at)?;
+    // Anonymous jobs stay out of the explorer
+    let job_docs = jobs.iter().filter(|job| !job.anonymous).map(|job| job_search_doc(job, &search)).collect();
+    changed += search.upsert(job_docs, at)?;
+    changed += search.expire_logs(at)?;
+    Ok(changed)
+}
+
+fn search_viewer(state: &AppState, headers: &HeaderMap) -> Viewer {
+    let token = headers.get(outputs::INTERNAL_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    if token == Some(state.internal_token.expose().as_str()) {
+        return Viewer::Internal;
+    }
+    match headers.get(outputs::DID_HEADER).and_then(|v| v.to_str().ok()) {
+        Some(did) if !did.is_empty() => Viewer::Did(did.to_string()),
+        _ => Viewer::Anonymous,
+    }
+}
+
+/// GET /search?q=&type=&facets= - Ranked hits with highlights and facet
+/// counts. `q` mixes free text with filters like `status:completed` and
+/// `before:2025-02-01`. Jobs are visible to their submitter (`x-artha-did`)
+/// and internal callers only.
+async fn search(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(req): Query<SearchRequest>,
+) -> Result<Json<SearchResults>, ServiceError> {
+    if let Err(e) = sync_search(&state).await {
+        warn!("⚠️  Search sync failed, serving the last synced index: {}", e);
+    }
+    let viewer = search_viewer(&state, &headers);
+    let results = state.search.read().await
+        .search(&req, &viewer)
+        .map_err(|e| ServiceError::new(ErrorCode::InvalidRequest, e))?;
+    Ok(Json(results))
+}
+
+/// POST /admin/search/rebuild - Rebuild the index from its journal, then
+/// resync live state
+async fn rebuild_search(State(state): State<Arc<AppState>>) -> Result<Json<serde_json::Value>, ServiceError> {
+    let rebuilt = state.search.write().await
+        .rebuild(state.clock.now_secs())
+        .map_err(|e| ServiceError::new(ErrorCode::Internal, e))?;
+    let synced = sync_search(&state).await.map_err(|e| ServiceError::new(ErrorCode::Internal, e))?;
+    let documents = state.search.read().await.len();
+    info!("🔎 Search index rebuilt: {} from the journal, {} resynced", rebuilt, synced);
+    Ok(Json(serde_json::json!({ "rebuilt": rebuilt, "synced": synced, "documents": documents })))
+}
+
+/// GET /health/rpc - Failover and circuit breaker state of each chain RPC endpoint
+async fn rpc_health(State(state): State<Arc<AppState>>) -> Json<Vec<EndpointHealth>> {
+    Json(state.contract_client.rpc_health())
+}
+
+// Server setup
+
+/// The full HTTP surface: every route under `/v1` and `/v2`, plus the
+/// deprecated unversioned aliases
+fn app(state: Arc<AppState>, output_state: OutputState) -> Router {
+    let api = Router::new()
+        // Job submission endpoints
+        .route("/job/train", post(submit_train_job))
+        .route("/job/infer", post(submit_infer_job))
+        .route("/job/agent", post(submit_agent_job))
+        .route("/job/stream", post(submit_stream_job))
+        .route("/job/quantize", post(submit_quantize_job))
+        .route("/job/rerun/:id", post(rerun_job))
+        .route("/job/assigned", post(job_assigned)) // Called by scheduler
+        .route("/job/attested", post(job_attested)) // Called by ai-proofs
+        .route("/job/:id/status", get(get_job_status))
+        .route("/job/:id/cancel", post(cancel_job))
+        .route("/job/:id/migrate", post(migrate_job))
+        .route("/job/:id/logs", get(get_job_logs))
+        .route("/job/:id/logs/stream", get(stream_job_logs))
+        .route("/jobs", get(list_jobs))
+        .route("/job/:id/provenance", get(get_job_provenance))
+        .route("/job/:id/archive", get(get_archived_job))
+        .route("/job/:id/timeline", get(get_job_timeline))
+        .route("/internal/job/:id/moderation/release", post(release_moderation_hold)) // Called by ai-ethics
+        .route("/job/:id/progress", post(job_progress)) // Called by ai-runtime
+        .route("/job/:id/receipt", get(wait_for_receipt))
+        .route("/pipeline/fanout", post(create_fanout))
+        .route("/pipeline/fanout/:id", get(get_fanout))
+        .route("/workflow", post(submit_workflow))
+        .route("/workflow/:id", get(get_workflow))
+        .route("/workflow/:id/cancel", post(cancel_workflow))
+        .route("/workflow/:id/step/:step/retry", post(retry_workflow_step))
+        // Dataset endpoints
+        .route("/ai/dataset/register", post(register_dataset))
+        .route("/ai/dataset/list", axum::routing::get(list_datasets))
+        .route("/ai/dataset/:id", axum::routing::get(get_dataset_info))
+        // Model endpoints
+        .route("/ai/model/register", post(register_model))
+        .route("/ai/model/register-batch", post(register_model_batch))
+        .route("/ai/model/list", axum::routing::get(list_models))
+        .route("/ai/model/:id/lineage", axum::routing::get(get_model_lineage))
+        .route("/ai/model/:id/card", axum::routing::get(get_model_card))
+        .route("/ai/model/:id/ab-route", get(get_ab_route).post(set_ab_route).delete(delete_ab_route))
+        .route("/ai/model/:id/alias/:alias", get(get_model_alias).put(set_model_alias))
+        // Dataset marketplace endpoints
+        .route("/market/listing", post(create_listing))
+        .route("/market/listings", get(search_listings))
+        .route("/market/listing/:id/status", post(set_listing_status))
+        .route("/market/listing/:id/purchase", post(purchase_access))
+        .route("/market/grants/:did", get(list_grants))
+        .route("/market/grant/:id", get(get_grant))
+        .route("/market/access/check", get(check_dataset_access))
+        // GPU-hour reservations
+        .route("/reservations", post(book_reservation).get(list_reservations))
+        // Explorer search
+        .route("/search", get(search))
+        .route("/admin/search/rebuild", post(rebuild_search))
+        .route("/health", get(|| async { "OK" }))
+        .route("/health/rpc", get(rpc_health))
+        .with_state(state)
+        // Access-controlled job outputs and the download proxy
+        .merge(outputs::router(output_state))
+        .layer(axum::middleware::map_response(artha_errors::normalize));
+
+    // Unmatched paths get the structured 404 too
+    versioning::versioned(api)
+        .layer(axum::middleware::map_response(artha_errors::normalize))
+        .layer(axum::middleware::from_fn(artha_log::log_requests))
+}
+
+#[tokio::main]
+async fn main() {
+    artha_log::init();
+    let internal_token = Sensitive::new(std::env::var("ARTHA_INTERNAL_TOKEN").unwrap_or_else(|_| "ai-jobd-dev-internal".to_string()));
+    let (clock, entropy) = (artha_clock::system_clock(), artha_clock::system_entropy());
+    let rpc = RpcEndpoints::from_env();
+    let contracts = ContractRegistry::resolve(&rpc, CONTRACTS)
+        .await
+        .unwrap_or_else(|e| panic!("Invalid contract config: {}", e));
+    let contract_client = ContractClient::new(rpc).with_contracts(&contracts).with_clock(clock.clone());
+    // Job records: JSON files under a data dir, or a sled database
+    let (job_store, job_store_path): (Arc<dyn JobStore>, String) = match std::env::var("ARTHA_JOB_STORE").as_deref() {
+        Ok("sled") => {
+            let path = std::env::var("ARTHA_JOB_STORE_PATH").unwrap_or_else(|_| "/tmp/artha/jobd/jobs.sled".to_string());
+            (Arc::new(SledJobStore::open(&path).unwrap_or_else(|e| panic!("{}", e))), path)
+        }
+        _ => {
+            let dir = std::env::var("ARTHA_JOB_DATA_DIR").unwrap_or_else(|_| "/tmp/artha/jobd/jobs".to_string());
+            (Arc::new(FileJobStore::open(&dir).unwrap_or_else(|e| panic!("{}", e))), dir)
+        }
+    };
+    let state = Arc::new(AppState {
+        jobs: Arc::new(RwLock::new(HashMap::new())),
+        job_store,
+        results: Arc::new(RwLock::new(ResultCache::default())),
+        contract_client: Arc::new(match Sponsor::from_env() {
+            Ok(Some(sponsor)) => {
+                info!("⛽ Sponsored submission via bundler {}", sponsor.bundler_url);
+                contract_client.with_sponsor(sponsor)
+            }
+            Ok(None) => contract_client,
+            Err(e) => panic!("Invalid sponsored submission config: {}", e),
+        }),
+        policy_gate: Arc::new(
+            PolicyGate::new("http://localhost:8082".to_string())
+                .with_outage(OutageConfig::from_env())
+                .with_clock(clock.clone()),
+        ),
+        scheduler_url: "http://localhost:8083".to_string(),
+        runtime_url: "http://localhost:8084".to_string(),
+        deprecation_feed_url: std::env::var("ARTHA_DEPRECATION_FEED_URL")
+            .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        ab_router: Arc::new(RwLock::new(AbRouter::new())),
+        marketplace: Arc::new(RwLock::new(Marketplace::new(
+            std::env::var("ARTHA_MARKET_FEE_BPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(250),
+        ))),
+        receipts_url: std::env::var("ARTHA_RECEIPTS_URL")
+            .unwrap_or_else(|_| "http://localhost:8092".to_string()),
+        node_api_url: std::env::var("ARTHA_NODE_API_URL")
+            .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        artifacts: Arc::new(RwLock::new(ArtifactRegistry::new())),
+        runtime_image_digest: std::env::var("ARTHA_RUNTIME_IMAGE_DIGEST")
+            .unwrap_or_else(|_| "sha256:unpinned".to_string()),
+        manifest_key: Sensitive::new(std::env::var("ARTHA_MANIFEST_KEY")
+            .unwrap_or_else(|_| "ai-jobd-dev-manifest-key".to_string())),
+        submission_nonces: Arc::new(RwLock::new(HashMap::new())),
+        fanouts: Arc::new(RwLock::new(FanOutRegistry::new())),
+        archived_jobs: Arc::new(RwLock::new(HashMap::new())),
+        retention: RetentionPolicy::from_env("ARTHA_JOBD"),
+        ethics_url: std::env::var("ARTHA_ETHICS_URL").ok(),
+        moderation_holds: Arc::new(RwLock::new(HashMap::new())),
+        internal_token: internal_token.clone(),
+        proofs_url: std::env::var("ARTHA_PROOFS_URL")
+            .unwrap_or_else(|_| "http://localhost:8085".to_string()),
+        milestone_plans: Arc::new(RwLock::new(HashMap::new())),
+        secret_refs: Arc::new(RwLock::new(HashMap::new())),
+        stream_specs: Arc::new(RwLock::new(HashMap::new())),
+        quantize: QuantizeConfig::from_env(),
+        quantize_specs: Arc::new(RwLock::new(HashMap::new())),
+        events: Arc::new(RwLock::new(EventStore::default())),
+        workflows: Arc::new(RwLock::new(WorkflowStore::load(Some(
+            std::env::var("ARTHA_WORKFLOW_STATE_PATH")
+                .unwrap_or_else(|_| "/tmp/artha/jobd/workflows.json".to_string()),
+        )))),
+        search: Arc::new(RwLock::new(
+            SearchIndex::open(SearchConfig::from_env(), clock.now_secs()).expect("Failed to open the search index"),
+        )),
+        reservations: Arc::new(RwLock::new(ReservationStore::default())),
+        job_escrows: Arc::new(RwLock::new(JobEscrowStore::default())),
+        forfeiture: ForfeitureSchedule::from_env(),
+        svdb_url: std::env::var("SVDB_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        log_limits: LogLimits::from_env(),
+        anonymous: AnonymousPolicy::from_env(),
+        anon_limiter: Arc::new(RwLock::new(AnonLimiter::default())),
+        chain: Arc::new(RwLock::new(ChainTracker::default())),
+        outputs: Arc::new(RwLock::new(OutputVault::new(
+            std::env::var("ARTHA_OUTPUT_LINK_KEY")
+                .unwrap_or_else(|_| "ai-jobd-dev-output-key".to_string())
+                .as_bytes(),
+        ))),
+        clock: clock.clone(),
+        entropy: entropy.clone(),
+    });
+
+    // Pick up where the last run left off: re-queue Queued jobs and ask
+    // ai-runtime what became of the ones it was running
+    let pending = restore_jobs(&state).await.unwrap_or_else(|e| panic!("Failed to restore jobs: {}", e));
+    info!("💾 Restored {} jobs from {}", state.jobs.read().await.len(), job_store_path);
+    if !pending.is_empty() {
+        let policy = BackoffPolicy::from_env("ARTHA_JOB_RESUME", std::time::Duration::from_secs(5));
+        tokio::spawn(resume_restored_jobs(state.clone(), pending, policy));
+    }
+
+    // Background task: evict finished jobs past the retention policy
+    tokio::spawn(retention_gc_loop(state.clone()));
+
+    // Background task: refund reservations whose window has ended
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        loop {
+            state_clone.clock.sleep(std::time::Duration::from_secs(RESERVATION_SETTLE_INTERVAL_SECS)).await;
+            settle_reservations(&state_clone, state_clone.clock.now_secs()).await;
+        }
+    });
+
+    // Background task: retry job escrow settlements that didn't go through
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        loop {
+            state_clone.clock.sleep(std::time::Duration::from_secs(JOB_ESCROW_SETTLE_INTERVAL_SECS)).await;
+            settle_job_escrows(&state_clone, state_clone.clock.now_secs()).await;
+        }
+    });
+
+    // Background task: archive job logs beyond what memory keeps
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        loop {
+            state_clone.clock.sleep(std::time::Duration::from_secs(LOG_ARCHIVE_INTERVAL_SECS)).await;
+            archive_job_logs(&state_clone).await;
+        }
+    });
+
+    // Background task: roll job statuses back when the chain reorgs under them
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        loop {
+            state_clone.clock.sleep(std::time::Duration::from_secs(reorg::RECONCILE_INTERVAL_SECS)).await;
+            let moved = reconcile_chain(&state_clone).await;
+            if moved > 0 {
+                warn!("⛓️  Chain reconciliation moved {} jobs", moved);
+            }
+        }
+    });
+
+    // Background task: index what changed since the last sync
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let interval = state_clone.search.read().await.config().sync_interval_secs;
+        loop {
+            state_clone.clock.sleep(std::time::Duration::from_secs(interval)).await;
+            if let Err(e) = sync_search(&state_clone).await {
+                warn!("⚠️  Search sync failed: {}", e);
+            }
+        }
+    });
+
+    let output_state = OutputState {
+        vault: state.outputs.clone(),
+        svdb_url: std::env::var("SVDB_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        public_url: std::env::var("ARTHA_JOBD_PUBLIC_URL").unwrap_or_else(|_| "http://localhost:8081/v1".to_string()),
+        internal_token,
+        max_link_ttl_secs: std::env::var("ARTHA_OUTPUT_LINK_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600),
+        clock: state.clock.clone(),
+    };
+
+    let app = app(state, output_state);
+
+    info!("🚀 AI Job Daemon starting on :8081");
+    
+    let listener = tokio::net::TcpListener::bind("0.0.0.0:8081").await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use artha_clock::{Clock, ManualClock, SeededEntropy};
+    use job_store::MemoryJobStore;
+
+    /// Where test clocks start
+    const T0: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_compute_params_hash() {
+        let params = TrainParams {
+            epochs: 3,
+            batch_size: 64,
+            learning_rate: 0.001,
+            optimizer: "adam".to_string(),
+            checkpoint_interval: 500,
+            seed: None,
+        };
+        
+        let hash = compute_params_hash(&params);
+        assert!(hash.starts_with("0x"));
+        assert_eq!(hash.len(), 66); // 0x + 64 hex chars
+    }
+
+    #[test]
+    fn test_params_hash_is_independent_of_construction_order() {
+        let literal = TrainParams {
+            seed: Some(42),
+            checkpoint_interval: 500,
+            optimizer: "adam".to_string(),
+            learning_rate: 0.001,
+            batch_size: 64,
+            epochs: 3,
+        };
+        let parsed: TrainParams = serde_json::from_str(
+            r#"{"optimizer":"adam","seed":42,"epochs":3,"learning_rate":0.001,"checkpoint_interval":500,"batch_size":64}"#,
+        )
+        .unwrap();
+        let mut updated = TrainParams { epochs: 1, seed: None, ..literal.clone() };
+        (updated.seed, updated.epochs) = (Some(42), 3);
+        assert_eq!(compute_params_hash(&literal), compute_params_hash(&parsed));
+        assert_eq!(compute_params_hash(&literal), compute_params_hash(&updated));
+
+        // Pinned: the hash is recorded on-chain and in manifests, so the encoding must not drift
+        assert_eq!(
+            manifest::canonical_json(&serde_json::to_value(&literal).unwrap()),
+            r#"{"batch_size":64,"checkpoint_interval":500,"epochs":3,"learning_rate":0.001,"optimizer":"adam","seed":42}"#
+        );
+        assert_eq!(compute_params_hash(&literal), "0x107443221a8e1a61b7f68253660286638ab9b1257e77f8cd4987c83d9f7b9cf9");
+
+        // Key order of a JSON object makes no difference either
+        let reordered: serde_json::Value = serde_json::from_str(r#"{"b":[{"y":1,"x":2}],"a":"1"}"#).unwrap();
+        assert_eq!(manifest::canonical_json(&reordered), r#"{"a":"1","b":[{"x":2,"y":1}]}"#);
+        assert_ne!(compute_params_hash(&literal), compute_params_hash(&TrainParams { epochs: 4, ..literal.clone() }));
+    }
+
+    #[test]
+    fn test_job_creation() {
+        let job = Job {
+            job_id: "job-test".to_string(),
+            job_type: JobType::Train,
+            status: JobStatus::Queued,
+            submitter: "0xtest".to_string(),
+            submitter_did: "did:artha:test".to_string(),
+            model_id: Some("model-1".to_string()),
+            dataset_id: Some("dataset-1".to_string()),
+            params_hash: "0xhash".to_string(),
+            assigned_node: None,
+            budget: 1000,
+            spent: 0,
+            submitted_at: T0,
+            started_at: None,
+            completed_at: None,
+            output_cid: None,
+            artifacts: Vec::new(),
+            progress: 0.0,
+            logs: JobLog::default(),
+            tee_required: false,
+            attestation: None,
+            ab_variant: None,
+            manifest: None,
+            seed: None,
+            live_migration: false,
+            migrations: Vec::new(),
+            terminal_reason: None,
+            anonymous: false,
+            data_provenance: None,
+            labels: Labels::new(),
+            stale_since: None,
+            estimated_duration_secs: None,
+        };
+
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.progress, 0.0);
+    }
+
+    #[test]
+    fn test_attestation_in_provenance() {
+        let mut job = Job {
+            job_id: "job-tee".to_string(),
+            job_type: JobType::Infer,
+            status: JobStatus::Running,
+            submitter: "0xtest".to_string(),
+            submitter_did: "did:artha:test".to_string(),
+            model_id: Some("model-1".to_string()),
+            dataset_id: None,
+            params_hash: "0xhash".to_string(),
+            assigned_node: Some("0xnode".to_string()),
+            budget: 1000,
+            spent: 0,
+            submitted_at: T0,
+            started_at: None,
+            completed_at: None,
+            output_cid: None,
+            artifacts: Vec::new(),
+            progress: 0.0,
+            logs: JobLog::default(),
+            tee_required: true,
+            attestation: None,
+            ab_variant: None,
+            manifest: None,
+            seed: None,
+            live_migration: false,
+            migrations: Vec::new(),
+            terminal_reason: None,
+            anonymous: false,
+            data_provenance: None,
+            labels: Labels::new(),
+            stale_since: None,
+            estimated_duration_secs: None,
+        };
+
+        let manifest = build_provenance_manifest(&job);
+        assert_eq!(manifest["tee"]["attestation_status"], "missing");
+
+        job.attestation = Some(AttestationSummary {
+            tee_type: "Simulated".to_string(),
+            measurement: "0xmeasure".to_string(),
+            image_digest: "sha256:abc".to_string(),
+            status: "Verified".to_string(),
+            verified_at: T0,
+        });
+
+        let manifest = build_provenance_manifest(&job);
+        assert_eq!(manifest["tee"]["attestation_status"], "Verified");
+        assert_eq!(manifest["tee"]["measurement"], "0xmeasure");
+        assert_eq!(manifest["tee"]["image_digest"], "sha256:abc");
+    }
+
+    #[test]
+    fn test_ab_routing_split_and_bucketing() {
+        let mut router = AbRouter::new();
+        router.set_route("model-x", vec![
+            ModelVariant { variant_id: "a".to_string(), model_id: "model-x-v1".to_string(), weight: 50 },
+            ModelVariant { variant_id: "b".to_string(), model_id: "model-x-v2".to_string(), weight: 50 },
+        ]).unwrap();
+
+        let total = 10_000;
+        let mut count_a = 0;
+        for _ in 0..total {
+            let variant = router.route("model-x", None, uuid::Uuid::new_v4().as_u128() as u64).unwrap();
+            if variant.variant_id == "a" {
+                count_a += 1;
+            }
+        }
+        let share_a = count_a as f64 / total as f64;
+        assert!((0.45..=0.55).contains(&share_a), "share_a = {}", share_a);
+
+        let metrics = router.metrics("model-x");
+        assert_eq!(metrics["a"].requests + metrics["b"].requests, total);
+
+        // Same bucketing key always lands on the same variant
+        let first = router.route("model-x", Some("user-42"), 0).unwrap().variant_id;
+        for entropy in 1..100 {
+            assert_eq!(router.route("model-x", Some("user-42"), entropy).unwrap().variant_id, first);
+        }
+
+        assert!(router.route("unrouted-model", None, 7).is_none());
+    }
+
+    fn market_with_listing(price: ListingPrice, max_consumers: Option<u32>) -> Marketplace {
+        let mut market = Marketplace::new(250);
+        market.create_listing(DatasetListing {
+            listing_id: "listing-1".to_string(),
+            dataset_id: "dataset-med".to_string(),
+            owner_did: "did:artha:owner".to_string(),
+            price,
+            allowed_job_types: vec!["train".to_string()],
+            license_cid: "bafy-license".to_string(),
+            max_concurrent_consumers: max_consumers,
+            tags: vec!["medical".to_string()],
+            architectures: vec!["resnet".to_string()],
+            status: ListingStatus::Active,
+            created_at: 1,
+        }).unwrap();
+        market
+    }
+
+    fn purchase_request(grant_id: &str, consumer_did: &str, units: u64) -> PurchaseRequest {
+        PurchaseRequest {
+            grant_id: grant_id.to_string(),
+            listing_id: "listing-1".to_string(),
+            consumer_did: consumer_did.to_string(),
+            units,
+            duration_secs: 3600,
+        }
+    }
+
+    /// Hold a grant and confirm its escrow
+    fn buy(market: &mut Marketplace, req: PurchaseRequest, now: u64) -> Result<AccessGrant, String> {
+        let grant = market.purchase(req, now)?;
+        Ok(market.confirm_purchase(&grant.grant_id, "0xescrow".to_string()).unwrap())
+    }
+
+    #[test]
+    fn test_dataset_purchase_grant_allows_training() {
+        let mut market = market_with_listing(ListingPrice::PerJob(1_000), None);
+        assert!(market.is_listed("dataset-med"));
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 3, 100).is_err());
+
+        let held = market.purchase(purchase_request("grant-1", "did:artha:alice", 2), 100).unwrap();
+        assert_eq!(held.escrow_tx, None);
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 3, 200).is_err(), "no access until the escrow is paid");
+        let grant = market.confirm_purchase("grant-1", "0xescrow".to_string()).unwrap();
+        assert_eq!(grant.escrow_tx.as_deref(), Some("0xescrow"));
+        assert_eq!(grant.dataset_id, "dataset-med");
+        assert_eq!(grant.unit_price, 1_000);
+
+        let allowed = market.check_access("dataset-med", "did:artha:alice", "train", 3, 200).unwrap();
+        assert_eq!(allowed.grant_id, "grant-1");
+
+        // Job types outside the listing terms and expired grants are refused
+        assert!(market.check_access("dataset-med", "did:artha:alice", "infer", 3, 200).is_err());
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 3, 100 + 3600).is_err());
+        // Other consumers have no grant
+        assert!(market.check_access("dataset-med", "did:artha:bob", "train", 3, 200).is_err());
+    }
+
+    #[test]
+    fn test_dataset_allowance_exhaustion_blocks_next_job() {
+        let mut market = market_with_listing(ListingPrice::PerJob(1_000), None);
+        buy(&mut market, purchase_request("grant-1", "did:artha:alice", 1), 0).unwrap();
+
+        market.begin_usage("grant-1", "job-a", 5).unwrap();
+        assert_eq!(market.grant("grant-1").unwrap().remaining(), 0);
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 5, 10).is_err());
+        assert!(market.begin_usage("grant-1", "job-b", 5).is_err());
+
+        // Per-epoch listings reserve requested epochs up front
+        let mut market = market_with_listing(ListingPrice::PerEpoch(100), None);
+        buy(&mut market, purchase_request("grant-2", "did:artha:alice", 10), 0).unwrap();
+        market.begin_usage("grant-2", "job-c", 6).unwrap();
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 6, 10).is_err());
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 4, 10).is_ok());
+
+        // Cancelling returns the reservation
+        market.cancel_usage("job-c");
+        assert_eq!(market.grant("grant-2").unwrap().remaining(), 10);
+    }
+
+    #[test]
+    fn test_dataset_settlement_splits_owner_share_and_fee() {
+        let mut market = market_with_listing(ListingPrice::PerEpoch(1_000), None);
+        buy(&mut market, purchase_request("grant-1", "did:artha:alice", 10), 0).unwrap();
+        market.begin_usage("grant-1", "job-a", 8).unwrap();
+
+        assert_eq!(market.record_epochs("job-a", 2), 2);
+        assert_eq!(market.record_epochs("job-a", 2), 0);
+        assert_eq!(market.record_epochs("job-a", 4), 2);
+
+        // Job finished early: unused epochs return to the grant
+        let settlement = market.finish_usage("job-a").unwrap();
+        assert_eq!(settlement.units, 4);
+        assert_eq!(settlement.gross, 4_000);
+        assert_eq!(settlement.platform_fee, 100); // 2.5%
+        assert_eq!(settlement.owner_share, 3_900);
+        assert_eq!(settlement.owner_did, "did:artha:owner");
+
+        let grant = market.grant("grant-1").unwrap();
+        assert_eq!(grant.used, 4);
+        assert_eq!(grant.remaining(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_purchase_holds_the_grant_before_escrowing_it() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        Arc::get_mut(&mut state).unwrap().contract_client = Arc::new(ContractClient::new(rpc.url()));
+        *state.marketplace.write().await = market_with_listing(ListingPrice::PerJob(500), Some(1));
+        let purchase = |did: &str, units: u64| {
+            let state = state.clone();
+            let req = PurchaseAccessRequest { consumer_did: did.to_string(), units, duration_secs: 3600 };
+            async move { purchase_access(State(state), Path("listing-1".to_string()), Json(req)).await.map(|Json(grant)| grant) }
+        };
+        let escrows = || rpc.calls_of(&abi::deal_market(), "escrowDatasetAccess");
+
+        // A price that overflows is a bad request, not a payment
+        assert_eq!(purchase("did:artha:alice", u64::MAX).await.unwrap_err(), StatusCode::BAD_REQUEST);
+        assert!(escrows().is_empty());
+
+        let grant = purchase("did:artha:alice", 2).await.unwrap();
+        assert!(grant.escrow_tx.is_some());
+        assert_eq!(escrows(), vec![vec![
+            abi_encode_bytes32(&compute_hash(&grant.grant_id)),
+            abi_encode_bytes32(&compute_hash("did:artha:alice")),
+            abi_encode_uint256(1_000u64),
+        ]]);
+
+        // A purchase the listing turns away is never paid for
+        assert_eq!(purchase("did:artha:bob", 1).await.unwrap_err(), StatusCode::CONFLICT);
+        assert_eq!(escrows().len(), 1);
+
+        // One whose escrow reverts leaves no grant behind
+        rpc.revert(abi::deal_market().function("escrowDatasetAccess"));
+        assert_eq!(purchase("did:artha:alice", 1).await.unwrap_err(), StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(state.marketplace.read().await.grants_for("did:artha:alice").len(), 1);
+    }
+
+    #[test]
+    fn test_suspended_listing_keeps_existing_grants() {
+        let mut market = market_with_listing(ListingPrice::PerJob(500), Some(1));
+        buy(&mut market, purchase_request("grant-1", "did:artha:alice", 3), 0).unwrap();
+
+        // Consumer cap reached for a second consumer
+        assert!(market.purchase(purchase_request("grant-2", "did:artha:bob", 1), 0).is_err());
+
+        assert!(market.set_listing_status("listing-1", "did:artha:mallory", ListingStatus::Suspended).is_err());
+        market.set_listing_status("listing-1", "did:artha:owner", ListingStatus::Suspended).unwrap();
+
+        assert!(market.search(Some("medical"), None).is_empty());
+        assert!(market.purchase(purchase_request("grant-3", "did:artha:alice", 1), 0).is_err());
+        assert!(market.check_access("dataset-med", "did:artha:alice", "train", 1, 10).is_ok());
+        market.begin_usage("grant-1", "job-a", 1).unwrap();
+
+        market.set_listing_status("listing-1", "did:artha:owner", ListingStatus::Active).unwrap();
+        assert_eq!(market.search(Some("medical"), Some("resnet")).len(), 1);
+        assert!(market.search(Some("medical"), Some("llama")).is_empty());
+    }
+
+    fn deprecated(name: &str, enforcement: deprecation::EnforcementLevel) -> deprecation::DeprecationEntry {
+        deprecation::DeprecationEntry {
+            id: format!("DEP-{}", name),
+            component: "model".to_string(),
+            name: name.to_string(),
+            sunset_date: u64::MAX,
+            replacement: "model-v2".to_string(),
+            enforcement,
+        }
+    }
+
+    #[test]
+    fn test_deprecation_warn_allows_with_flag() {
+        let entries = vec![deprecated("model-v1", deprecation::EnforcementLevel::Warn)];
+
+        let warnings = deprecation::enforce(&entries, &[("model", "model-v1")], false).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("model-v2"));
+
+        let headers = deprecation_headers(&warnings);
+        assert_eq!(headers.get("deprecation").unwrap(), "true");
+        assert!(headers.get(axum::http::header::WARNING).unwrap().to_str().unwrap().starts_with("299"));
+
+        // Unrelated resources and other components are untouched
+        assert!(deprecation::enforce(&entries, &[("model", "model-v3")], false).unwrap().is_empty());
+        assert!(deprecation::enforce(&entries, &[("dataset", "model-v1")], false).unwrap().is_empty());
+        assert!(deprecation_headers(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_deprecation_soft_block_requires_override() {
+        let entries = vec![deprecated("model-v1", deprecation::EnforcementLevel::SoftBlock)];
+
+        assert_eq!(
+            deprecation::enforce(&entries, &[("model", "model-v1")], false).unwrap_err(),
+            StatusCode::PRECONDITION_REQUIRED
+        );
+        let warnings = deprecation::enforce(&entries, &[("model", "model-v1")], true).unwrap();
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_deprecation_hard_block_always_rejects() {
+        let entries = vec![deprecated("model-v1", deprecation::EnforcementLevel::HardBlock)];
+
+        assert_eq!(deprecation::enforce(&entries, &[("model", "model-v1")], false).unwrap_err(), StatusCode::GONE);
+        assert_eq!(deprecation::enforce(&entries, &[("model", "model-v1")], true).unwrap_err(), StatusCode::GONE);
+
+        // Feed entries use the feed's kebab-case level names
+        let parsed: deprecation::DeprecationEntry = serde_json::from_value(serde_json::json!({
+            "id": "DEP-9", "component": "dataset", "name": "ds-1", "sunset_date": 1,
+            "replacement": "ds-2", "enforcement": "hard-block",
+        })).unwrap();
+        assert_eq!(parsed.enforcement, deprecation::EnforcementLevel::HardBlock);
+    }
+
+    #[tokio::test]
+    async fn test_receipt_wait_honours_finality() {
+        // Mock receipts daemon and node API: tx 0xaa11 is finalized, 0xbb22 is not
+        let app = Router::new()
+            .route("/receipts", get(|Query(params): Query<HashMap<String, String>>| async move {
+                let finalize_tx = match params.get("job_id").map(String::as_str) {
+                    Some("job-final") => "0xaa11",
+                    _ => "0xbb22",
+                };
+                Json(serde_json::json!({
+                    "items": [{ "receipt_id": "r1", "finalize_tx": finalize_tx }],
+                    "total": 1,
+                    "next_cursor": null,
+                }))
+            }))
+            .route("/api/v1/transactions/:hash", get(|Path(hash): Path<String>| async move {
+                Json(serde_json::json!({ "finalized": hash == "aa11" }))
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let receipt = find_ready_receipt(&url, &url, "job-final", true).await.unwrap();
+        assert_eq!(receipt["finalized"], true);
+
+        assert!(find_ready_receipt(&url, &url, "job-pending", true).await.is_none());
+        let receipt = find_ready_receipt(&url, &url, "job-pending", false).await.unwrap();
+        assert_eq!(receipt["finalized"], false);
+    }
+
+    #[test]
+    fn test_rerun_from_manifest_pins_inputs_after_tag_moves() {
+        let mut artifacts = ArtifactRegistry::new();
+        artifacts.register_model(&Namespace::Shared, "model-v1", "bafy-model-v1", Some("resnet"), "1.0");
+        artifacts.register_dataset("dataset-1", "bafy-dataset-1");
+
+        let req = TrainJobRequest {
+            model_id: "resnet@latest".to_string(),
+            dataset_id: "dataset-1".to_string(),
+            submitter_did: "did:artha:alice".to_string(),
+            params: TrainParams {
+                epochs: 3,
+                batch_size: 64,
+                learning_rate: 0.001,
+                optimizer: "adam".to_string(),
+                checkpoint_interval: 500,
+                seed: None,
+            },
+            budget: 1000,
+            tee_required: false,
+            allow_deprecated: false,
+            nonce: None,
+            milestones: None,
+            live_migration: false,
+            reservation_id: None,
+            secrets: Vec::new(),
+            labels: Labels::new(),
+            force_rerun: false,
+        };
+        let manifest = lock_train_manifest(&artifacts, &req, "sha256:runtime-a", "key", T0, &SeededEntropy::new(1)).unwrap();
+        assert_eq!(manifest.model_id, "model-v1");
+        assert_eq!(manifest.model_cid, "bafy-model-v1");
+        assert_eq!(manifest.dataset_cid.as_deref(), Some("bafy-dataset-1"));
+        assert!(manifest.verify("key"));
+        assert!(!manifest.verify("other-key"));
+
+        // "latest" moves to a new version after the original job ran
+        artifacts.register_model(&Namespace::Shared, "model-v2", "bafy-model-v2", Some("resnet"), "2.0");
+        assert_eq!(artifacts.resolve_model(&Namespace::Shared, "resnet@latest").unwrap().0, "model-v2");
+
+        let job = Job {
+            job_id: "job-original".to_string(),
+            job_type: JobType::Train,
+            status: JobStatus::Completed,
+            submitter: "0xtest".to_string(),
+            submitter_did: req.submitter_did.clone(),
+            model_id: Some(manifest.model_id.clone()),
+            dataset_id: Some(req.dataset_id.clone()),
+            params_hash: manifest.params_hash.clone(),
+            assigned_node: None,
+            budget: 1000,
+            spent: 0,
+            submitted_at: T0,
+            started_at: None,
+            completed_at: None,
+            output_cid: None,
+            artifacts: Vec::new(),
+            progress: 1.0,
+            logs: JobLog::default(),
+            tee_required: false,
+            attestation: None,
+            ab_variant: None,
+            manifest: Some(manifest.clone()),
+            seed: None,
+            live_migration: false,
+            migrations: Vec::new(),
+            terminal_reason: None,
+            anonymous: false,
+            data_provenance: None,
+            labels: Labels::new(),
+            stale_since: None,
+            estimated_duration_secs: None,
+        };
+
+        // Re-locking the rerun request resolves to exactly the original inputs
+        let rerun = train_request_from_manifest(&manifest, &job, &RerunRequest::default()).unwrap();
+        assert_eq!(rerun.model_id, "model-v1");
+        let relocked = lock_train_manifest(&artifacts, &rerun, "sha256:runtime-a", "key", T0, &SeededEntropy::new(1)).unwrap();
+        assert_eq!(relocked.model_cid, manifest.model_cid);
+        assert_eq!(relocked.dataset_cid, manifest.dataset_cid);
+        assert_eq!(relocked.params, manifest.params);
+        assert_eq!(relocked.params_hash, manifest.params_hash);
+
+        // Tampered manifests are refused
+        let mut tampered = manifest.clone();
+        tampered.model_cid = "bafy-model-v2".to_string();
+        assert!(!tampered.verify("key"));
+    }
+
+    async fn serve_outputs(vault: Arc<RwLock<OutputVault>>) -> String {
+        // Mock SVDB gateway serving the raw content by CID
+        let svdb = Router::new().route(
+            "/svdb/download/:cid",
+            get(|Path(cid): Path<String>| async move { format!("weights-of-{}", cid) }),
+        );
+        let svdb_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let svdb_url = format!("http://{}", svdb_listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(svdb_listener, svdb).await.unwrap() });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let public_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = outputs::router(OutputState {
+            vault,
+            svdb_url,
+            public_url: public_url.clone(),
+            internal_token: "internal".to_string().into(),
+            max_link_ttl_secs: 600,
+            clock: ManualClock::new(T0).shared(),
+        });
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        public_url
+    }
+
+    #[tokio::test]
+    async fn test_output_link_round_trip_through_proxy_is_audited() {
+        let vault = Arc::new(RwLock::new(OutputVault::new(b"test-key")));
+        let output_id = vault.write().await.register("job-1", "bafy-finetuned", "did:artha:alice", T0, &SeededEntropy::new(1));
+        let url = serve_outputs(vault.clone()).await;
+        let client = reqwest::Client::new();
+
+        let link: serde_json::Value = client
+            .post(format!("{}/job/job-1/output/link", url))
+            .json(&serde_json::json!({ "requester_did": "did:artha:alice", "ttl_secs": 60 }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(link["output_id"], output_id.as_str());
+        let download_url = link["url"].as_str().unwrap();
+        assert!(!download_url.contains("bafy-finetuned"));
+
+        let resp = client.get(download_url).header(outputs::DID_HEADER, "did:artha:alice").send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+        assert_eq!(resp.text().await.unwrap(), "weights-of-bafy-finetuned");
+
+        // The same link presented by another DID is refused with its own error
+        let resp = client.get(download_url).header(outputs::DID_HEADER, "did:artha:mallory").send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 403);
+        assert_eq!(resp.json::<serde_json::Value>().await.unwrap()["error"], "link_wrong_did");
+
+        // Internal path still exposes the raw CID to trusted services only
+        let resp = client.get(format!("{}/internal/job/job-1/output", url)).send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 401);
+        let internal: serde_json::Value = client
+            .get(format!("{}/internal/job/job-1/output", url))
+            .header(outputs::INTERNAL_TOKEN_HEADER, "internal")
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(internal["output_cid"], "bafy-finetuned");
+
+        let audit: Vec<outputs::AuditEntry> = client
+            .get(format!("{}/job/job-1/output/audit?owner_did=did:artha:alice", url))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        let actions: Vec<_> = audit.iter().map(|e| e.action.clone()).collect();
+        assert_eq!(actions, vec![
+            outputs::AuditAction::LinkIssued,
+            outputs::AuditAction::Downloaded,
+            outputs::AuditAction::DownloadDenied,
+        ]);
+        assert_eq!(audit[1].did, "did:artha:alice");
+    }
+
+    #[tokio::test]
+    async fn test_infer_output_digest_is_taken_only_from_the_assigned_node() {
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+        let node_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let node_pubkey = format!("0x{}", hex::encode(node_key.verifying_key().to_encoded_point(true).as_bytes()));
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        let mut job = queued_job("job-out", "model-1");
+        job.status = JobStatus::Running;
+        job.assigned_node = Some(node_pubkey.clone());
+        state.jobs.write().await.insert("job-out".to_string(), job);
+
+        let digest = "9f2c1e6b7d3a4f5e8c0b1a2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f".to_string();
+        let report = |key: &SigningKey| {
+            let signature: Signature = key.sign(outputs::output_message("job-out", "bafy-answer", &digest).as_bytes());
+            JobProgressRequest {
+                progress: 1.0,
+                epochs_completed: None,
+                status: Some(JobStatus::Completed),
+                output_cid: Some("bafy-answer".to_string()),
+                peak_vram_mb: None,
+                failure_reason: None,
+                output_text: None,
+                migratable: None,
+                spent: None,
+                metrics: None,
+                terminal_reason: None,
+                output_digest: Some(digest.clone()),
+                output_signature: Some(hex::encode(signature.to_bytes())),
+            }
+        };
+
+        // Another key's signature is refused before anything is recorded
+        let other_key = SigningKey::from_slice(&[8u8; 32]).unwrap();
+        let refused = job_progress(State(state.clone()), Path("job-out".to_string()), Json(report(&other_key))).await;
+        assert_eq!(refused.unwrap_err(), StatusCode::UNAUTHORIZED);
+        assert!(state.outputs.read().await.output_for_job("job-out").is_none());
+
+        job_progress(State(state.clone()), Path("job-out".to_string()), Json(report(&node_key))).await.unwrap();
+        let mut vault = state.outputs.write().await;
+        let integrity = vault.output_for_job("job-out").unwrap().integrity.clone().unwrap();
+        assert_eq!((integrity.output_digest.as_str(), integrity.node_pubkey.as_str()), (digest.as_str(), node_pubkey.as_str()));
+        assert!(integrity.signed_by_node("job-out"));
+        assert!(!integrity.signed_by_node("job-other"));
+
+        // A new output under the job drops the digest of the old one
+        vault.register("job-out", "bafy-other", "did:artha:test", T0, &SeededEntropy::new(1));
+        assert!(vault.output_for_job("job-out").unwrap().integrity.is_none());
+    }
+
+    #[test]
+    fn test_output_link_expiry_boundary_and_tampering() {
+        let mut vault = OutputVault::new(b"test-key");
+        vault.register("job-1", "bafy-out", "did:artha:alice", 1_000, &SeededEntropy::new(1));
+        let link = vault.issue_link("job-1", "did:artha:alice", 60, 1_000).unwrap();
+        assert_eq!(link.expires_at, 1_060);
+
+        assert_eq!(vault.validate(&link, "did:artha:alice", 1_059), Ok("bafy-out".to_string()));
+        assert_eq!(vault.validate(&link, "did:artha:alice", 1_060), Err(outputs::LinkError::Expired));
+
+        // Extending the expiry or swapping the DID breaks the signature
+        let mut extended = link.clone();
+        extended.expires_at = 9_999;
+        assert_eq!(vault.validate(&extended, "did:artha:alice", 1_010), Err(outputs::LinkError::Tampered));
+        let mut swapped = link.clone();
+        swapped.did = "did:artha:mallory".to_string();
+        assert_eq!(vault.validate(&swapped, "did:artha:mallory", 1_010), Err(outputs::LinkError::Tampered));
+        assert_eq!(vault.validate(&link, "did:artha:mallory", 1_010), Err(outputs::LinkError::WrongDid));
+    }
+
+    #[test]
+    fn test_revoked_grantee_is_denied() {
+        let mut vault = OutputVault::new(b"test-key");
+        vault.register("job-1", "bafy-out", "did:artha:alice", 1_000, &SeededEntropy::new(1));
+
+        assert!(vault.issue_link("job-1", "did:artha:bob", 60, 1_000).is_err());
+        assert!(vault.set_grant("job-1", "did:artha:bob", "did:artha:bob", true, 1_000).is_err());
+        vault.set_grant("job-1", "did:artha:alice", "did:artha:bob", true, 1_000).unwrap();
+
+        let link = vault.issue_link("job-1", "did:artha:bob", 60, 1_000).unwrap();
+        assert!(vault.validate(&link, "did:artha:bob", 1_001).is_ok());
+
+        // Revocation also invalidates links issued before it
+        vault.set_grant("job-1", "did:artha:alice", "did:artha:bob", false, 1_002).unwrap();
+        assert_eq!(vault.validate(&link, "did:artha:bob", 1_003), Err(outputs::LinkError::AccessRevoked));
+        assert_eq!(vault.issue_link("job-1", "did:artha:bob", 60, 1_003), Err(outputs::LinkError::AccessRevoked));
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_backpressure_reaches_submitter() {
+        // Mock scheduler: "busy-job" is past the watermark, everything else schedules
+        let scheduler = Router::new().route(
+            "/schedule",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                if body["job_id"] == "busy-job" {
+                    (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "45")], "queue full").into_response()
+                } else if body["job_id"] == "no-node-job" {
+                    StatusCode::SERVICE_UNAVAILABLE.into_response()
+                } else {
+                    StatusCode::OK.into_response()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let scheduler_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, scheduler).await.unwrap() });
+
+        assert!(notify_scheduler(&scheduler_url, &ScheduleRequest { job_id: "ok-job", ..ScheduleRequest::default() }, Deadline::NONE, &artha_clock::SystemClock).await.is_ok());
+        let err = notify_scheduler(&scheduler_url, &ScheduleRequest { job_id: "no-node-job", ..ScheduleRequest::default() }, Deadline::NONE, &artha_clock::SystemClock).await.unwrap_err();
+        assert!(matches!(err, SubmitError::Status(StatusCode::SERVICE_UNAVAILABLE)));
+
+        let err = notify_scheduler(&scheduler_url, &ScheduleRequest { job_id: "busy-job", ..ScheduleRequest::default() }, Deadline::NONE, &artha_clock::SystemClock).await.unwrap_err();
+        assert!(matches!(&err, SubmitError::Service(e) if *e == ServiceError::queue_full(45)));
+
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("retry-after").unwrap(), "45");
+    }
+
+    #[tokio::test]
+    async fn test_job_status_calls_match_ai_job_manager_abi() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let client = ContractClient::new(rpc.url());
+        let jobs = abi::ai_job_manager();
+        let job_id = derive_job_id("train", "did:artha:alice", "model-1", None, "0xparams", 1);
+        let node = "0xnode1aabbccddeeff00112233445566778899";
+
+        client.assign_job(&job_id, node).await.unwrap();
+        client.update_job_status(&job_id, &JobStatus::Running).await.unwrap();
+        client.update_job_status(&job_id, &JobStatus::Cancelled).await.unwrap();
+
+        let calls = rpc.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls.iter().all(|c| c.method == "eth_sendTransaction" && c.to == client.ai_job_manager));
+        abi::assert_call(&jobs, &calls[0].data, "assignJob", &[abi_encode_bytes32(&job_id), abi_encode_bytes32(node)]);
+        // JobStatus discriminants line up with the contract's enum
+        abi::assert_call(&jobs, &calls[1].data, "updateStatus", &[abi_encode_bytes32(&job_id), abi::Token::uint(2u8)]);
+        abi::assert_call(&jobs, &calls[2].data, "updateStatus", &[abi_encode_bytes32(&job_id), abi::Token::uint(5u8)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_job_decodes_the_on_chain_job() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let client = ContractClient::new(rpc.url());
+        let jobs = abi::ai_job_manager();
+        // Unknown ids read back as an all-zero Job
+        let zero = abi::Token::bytes32([0; 32]);
+        let mut unknown = vec![zero.clone(); 15];
+        unknown[1] = abi::Token::uint(0u8);
+        unknown[2] = abi::Token::uint(0u8);
+        unknown[3] = abi::Token::address("0x0000000000000000000000000000000000000000");
+        for field in &mut unknown[9..14] {
+            *field = abi::Token::uint(0u8);
+        }
+        unknown.push(abi::Token::Array(Vec::new()));
+        rpc.respond(jobs.function("getJob"), &[abi::Token::Tuple(unknown)]);
+        assert!(client.get_job("job-1").await.unwrap_err().contains("not found on chain"));
+
+        let params = [4u8; 32];
+        rpc.respond(jobs.function("getJob"), &[abi::Token::Tuple(vec![
+            abi::Token::ascii32("job-2"),
+            abi::Token::uint(1u8), // JobType.Infer
+            abi::Token::uint(3u8), // JobStatus.Completed
+            abi::Token::address("0x5fbdb2315678afecb367f032d93f642f64180aa3"),
+            abi::Token::ascii32("did:artha:alice"),
+            abi::Token::ascii32("model-1"),
+            abi::Token::bytes32([0; 32]),
+            abi::Token::bytes32(params),
+            abi::Token::ascii32("0xnode1"),
+            abi::Token::uint(1_000u32),
+            abi::Token::uint(750u32),
+            abi::Token::uint(1_700_000_000u64),
+            abi::Token::uint(1_700_000_060u64),
+            abi::Token::uint(0u8),
+            abi::Token::ascii32("bafyout"),
+            abi::Token::Array(vec![abi::Token::ascii32("artifact-1")]),
+        ])]);
+        let job = client.get_job("job-2").await.unwrap();
+        assert!(matches!((job.job_type, job.status), (JobType::Infer, JobStatus::Completed)));
+        assert_eq!(job.submitter, "0x5fbdb2315678afecb367f032d93f642f64180aa3");
+        assert_eq!((job.submitter_did.as_str(), job.anonymous), ("did:artha:alice", false));
+        assert_eq!((job.model_id.as_deref(), job.dataset_id.as_deref()), (Some("model-1"), None));
+        assert_eq!(job.params_hash, format!("0x{}", hex::encode(params)));
+        assert_eq!(job.assigned_node.as_deref(), Some("0xnode1"));
+        assert_eq!((job.budget, job.spent), (1_000, 750));
+        assert_eq!((job.started_at, job.completed_at), (Some(1_700_000_060), None));
+        assert_eq!(job.output_cid.as_deref(), Some("bafyout"));
+        assert_eq!(job.artifacts, vec!["artifact-1".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_contract_calls_fail_over_to_the_next_rpc_endpoint() {
+        let primary = serve(Router::new().route("/", post(|| async { StatusCode::SERVICE_UNAVAILABLE }))).await;
+        let secondary = abi::DryRunRpc::spawn().await;
+        let client = ContractClient::new(RpcEndpoints::new(vec![primary, secondary.url()]));
+        let job_id = derive_job_id("train", "did:artha:alice", "model-1", None, "0xparams", 1);
+
+        client.assign_job(&job_id, "0xnode1aabbccddeeff00112233445566778899").await.unwrap();
+        client.update_job_status(&job_id, &JobStatus::Running).await.unwrap();
+        assert_eq!(secondary.calls().len(), 2);
+        // The primary failed once; the secondary stayed preferred after answering
+        let health = client.rpc_health();
+        assert_eq!((health[0].failures, health[0].last_error.as_deref()), (1, Some("HTTP 503 Service Unavailable")));
+        assert_eq!(health[1].successes, 2);
+    }
+
+    #[test]
+    fn test_user_op_hash_matches_node() {
+        // Vector from blockchain_node evm::account_abstraction::UserOperation::hash
+        let sponsor = Sponsor::new(
+            "http://bundler".to_string(),
+            "0x1111111111111111111111111111111111111111",
+            "0x9999999999999999999999999999999999999999",
+            &"07".repeat(32),
+            201766,
+        )
+        .unwrap();
+        assert_eq!(hex::encode(sponsor.session_address()), "4a62316623ad457f02cdc5d997ded67a383ec569");
+        let mut target = [0u8; 20];
+        target[19] = 1;
+        assert_eq!(
+            hex::encode(sponsor.user_op_hash(3, &target, &hex::decode("deadbeef").unwrap())),
+            "67503241ab18784eaaee024c77bde5b8a033dc195ab8e617d8396690e5a760aa"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sponsored_submission_routes_through_bundler() {
+        let ops: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let bundler = Router::new()
+            .route("/bundler/accounts/:address", get(|| async { Json(serde_json::json!({ "nonce": 5 })) }))
+            .route("/bundler/userop", post({
+                let ops = ops.clone();
+                move |Json(op): Json<serde_json::Value>| {
+                    let ops = ops.clone();
+                    async move {
+                        ops.lock().unwrap().push(op);
+                        Json(serde_json::json!({ "userOpHash": "0xfeed", "status": "included" }))
+                    }
+                }
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bundler_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, bundler).await.unwrap() });
+
+        // No RPC behind it: every submission has to go through the bundler
+        let sponsor = Sponsor::new(
+            bundler_url,
+            "0x1111111111111111111111111111111111111111",
+            "0x9999999999999999999999999999999999999999",
+            &"07".repeat(32),
+            201766,
+        )
+        .unwrap();
+        let session = format!("0x{}", hex::encode(sponsor.session_address()));
+        let client = ContractClient::new("http://127.0.0.1:9".to_string()).with_sponsor(sponsor);
+        let job_id = derive_job_id("train", "did:artha:alice", "model-1", None, "0xparams", 1);
+        let node = "0xnode1aabbccddeeff00112233445566778899";
+        client.assign_job(&job_id, node).await.unwrap();
+
+        let op = ops.lock().unwrap()[0].clone();
+        assert_eq!(op["sender"], "0x1111111111111111111111111111111111111111");
+        assert_eq!(op["nonce"], 5);
+        assert_eq!(op["target"], client.ai_job_manager);
+        assert_eq!(op["paymaster"], "0x9999999999999999999999999999999999999999");
+        assert_eq!(op["sessionKey"], session);
+        abi::assert_call(
+            &abi::ai_job_manager(),
+            op["callData"].as_str().unwrap(),
+            "assignJob",
+            &[abi_encode_bytes32(&job_id), abi_encode_bytes32(node)],
+        );
+
+        // The signature recovers to the session key over the node's hash
+        let sponsor = client.sponsor.as_ref().unwrap();
+        let mut target = [0u8; 20];
+        target.copy_from_slice(&hex::decode(client.ai_job_manager.trim_start_matches("0x")).unwrap());
+        let calldata = hex::decode(op["callData"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+        let hash = sponsor.user_op_hash(5, &target, &calldata);
+        let signature = hex::decode(op["signature"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+        let recovered = k256::ecdsa::VerifyingKey::recover_from_prehash(
+            &hash,
+            &k256::ecdsa::Signature::from_slice(&signature[..64]).unwrap(),
+            k256::ecdsa::RecoveryId::from_byte(signature[64] - 27).unwrap(),
+        )
+        .unwrap();
+        let point = recovered.to_encoded_point(false);
+        assert_eq!(hex::encode(&Keccak256::digest(&point.as_bytes()[1..])[12..]), session.trim_start_matches("0x"));
+    }
+
+    #[test]
+    fn test_job_id_derivation_is_deterministic() {
+        let id = derive_job_id("train", "did:artha:alice", "model-1", Some("dataset-1"), "0xparams", 7);
+        assert_eq!(id, derive_job_id("train", "did:artha:alice", "model-1", Some("dataset-1"), "0xparams", 7));
+        assert!(id.starts_with("job-"));
+        assert_eq!(id.len(), 4 + 32);
+
+        // Every field contributes
+        assert_ne!(id, derive_job_id("infer", "did:artha:alice", "model-1", Some("dataset-1"), "0xparams", 7));
+        assert_ne!(id, derive_job_id("train", "did:artha:bob", "model-1", Some("dataset-1"), "0xparams", 7));
+        assert_ne!(id, derive_job_id("train", "did:artha:alice", "model-2", Some("dataset-1"), "0xparams", 7));
+        assert_ne!(id, derive_job_id("train", "did:artha:alice", "model-1", None, "0xparams", 7));
+        assert_ne!(id, derive_job_id("train", "did:artha:alice", "model-1", Some("dataset-1"), "0xother", 7));
+        assert_ne!(id, derive_job_id("train", "did:artha:alice", "model-1", Some("dataset-1"), "0xparams", 8));
+
+        // Shifting bytes between adjacent fields changes the id
+        assert_ne!(
+            derive_job_id("train", "did:a", "bmodel", None, "0x", 0),
+            derive_job_id("train", "did:ab", "model", None, "0x", 0),
+        );
+        assert_ne!(
+            derive_job_id("train", "did:a", "model", Some(""), "0x", 0),
+            derive_job_id("train", "did:a", "model", None, "0x", 0),
+        );
+    }
+
+    #[test]
+    fn test_job_id_collision_resistance() {
+        let mut seen = std::collections::HashSet::new();
+        for submitter in 0..20 {
+            for model in 0..10 {
+                for nonce in 0..50u64 {
+                    let did = format!("did:artha:user{}", submitter);
+                    let model_id = format!("model-{}", model);
+                    let dataset = (nonce % 2 == 0).then(|| format!("dataset-{}", model));
+                    let id = derive_job_id("train", &did, &model_id, dataset.as_deref(), "0xparams", nonce);
+                    assert!(seen.insert(id), "collision for {} {} {}", did, model_id, nonce);
+                }
+            }
+        }
+        assert_eq!(seen.len(), 20 * 10 * 50);
+
+        // Ids no longer share the 16-hex tx-hash prefix weakness: prefixes are distinct too
+        let prefixes: std::collections::HashSet<_> = seen.iter().map(|id| id[..4 + 16].to_string()).collect();
+        assert_eq!(prefixes.len(), seen.len());
+    }
+
+    fn succeeded(cid: &str) -> ChildOutcome {
+        ChildOutcome::Succeeded { output_cid: Some(cid.to_string()) }
+    }
+
+    #[test]
+    fn test_fanout_all_must_succeed() {
+        let policy = FanOutPolicy::AllMustSucceed;
+        assert_eq!(
+            policy.evaluate(&[succeeded("a"), succeeded("b"), succeeded("c")]),
+            JoinDecision::Proceed { outputs: vec!["a".into(), "b".into(), "c".into()] },
+        );
+        // One failure is fatal even while siblings are still running
+        assert!(matches!(
+            policy.evaluate(&[succeeded("a"), ChildOutcome::Failed, ChildOutcome::Pending]),
+            JoinDecision::Failed { .. },
+        ));
+    }
+
+    #[test]
+    fn test_fanout_any_succeeds() {
+        let policy = FanOutPolicy::AnySucceeds;
+        assert_eq!(
+            policy.evaluate(&[ChildOutcome::Failed, succeeded("b"), ChildOutcome::Failed]),
+            JoinDecision::Proceed { outputs: vec!["b".into()] },
+        );
+        // Waits for stragglers so their outputs are not dropped
+        assert_eq!(policy.evaluate(&[succeeded("a"), ChildOutcome::Pending]), JoinDecision::Waiting);
+        assert!(matches!(
+            policy.evaluate(&[ChildOutcome::Failed, ChildOutcome::Failed]),
+            JoinDecision::Failed { .. },
+        ));
+    }
+
+    #[test]
+    fn test_fanout_at_least_k() {
+        let policy: FanOutPolicy = serde_json::from_value(serde_json::json!({"policy": "at_least_k", "k": 2})).unwrap();
+        assert_eq!(policy, FanOutPolicy::AtLeastK { k: 2 });
+
+        assert_eq!(
+            policy.evaluate(&[succeeded("a"), ChildOutcome::Failed, succeeded("c")]),
+            JoinDecision::Proceed { outputs: vec!["a".into(), "c".into()] },
+        );
+        assert_eq!(
+            policy.evaluate(&[succeeded("a"), ChildOutcome::Failed, ChildOutcome::Failed]),
+            JoinDecision::Failed { reason: "1 of 3 children succeeded, policy requires 2".to_string() },
+        );
+        assert_eq!(
+            policy.evaluate(&[succeeded("a"), ChildOutcome::Failed, ChildOutcome::Pending]),
+            JoinDecision::Waiting,
+        );
+    }
+
+    #[test]
+    fn test_fanout_decisions_are_final() {
+        let mut registry = FanOutRegistry::new();
+        let children = vec!["job-a".to_string(), "job-b".to_string()];
+        assert_eq!(registry.register(children.clone(), FanOutPolicy::AtLeastK { k: 3 }, 0, &SeededEntropy::new(1)).unwrap_err(), StatusCode::BAD_REQUEST);
+        assert_eq!(registry.register(vec![], FanOutPolicy::AnySucceeds, 0, &SeededEntropy::new(1)).unwrap_err(), StatusCode::BAD_REQUEST);
+
+        let fanout = registry.register(children, FanOutPolicy::AnySucceeds, 0, &SeededEntropy::new(1)).unwrap();
+        assert_eq!(registry.waiting_on("job-a").len(), 1);
+
+        registry.decide(&fanout.fanout_id, JoinDecision::Failed { reason: "none succeeded".to_string() });
+        registry.decide(&fanout.fanout_id, JoinDecision::Waiting);
+        assert!(matches!(registry.get(&fanout.fanout_id).unwrap().decision, JoinDecision::Failed { .. }));
+        assert!(registry.waiting_on("job-a").is_empty());
+    }
+
+    #[test]
+    fn test_model_alias_resolves_like_a_tag() {
+        let mut artifacts = ArtifactRegistry::new();
+        artifacts.register_model(&Namespace::Shared, "model-v1", "bafy-model-v1", Some("resnet"), "1.0");
+        artifacts.register_model(&Namespace::Shared, "model-v2", "bafy-model-v2", Some("resnet"), "2.0");
+
+        assert_eq!(artifacts.set_alias(&Namespace::Shared, "resnet", "serving", "model-v1"), None);
+        assert_eq!(artifacts.resolve_model(&Namespace::Shared, "resnet@serving").unwrap(), ("model-v1".to_string(), "bafy-model-v1".to_string()));
+
+        // Registering a newer version moves `latest`, never the alias
+        assert_eq!(artifacts.resolve_model(&Namespace::Shared, "resnet@latest").unwrap().0, "model-v2");
+        assert_eq!(artifacts.alias(&Namespace::Shared, "resnet", "serving").map(String::as_str), Some("model-v1"));
+
+        assert_eq!(artifacts.set_alias(&Namespace::Shared, "resnet", "serving", "model-v2").as_deref(), Some("model-v1"));
+        assert_eq!(artifacts.resolve_model(&Namespace::Shared, "resnet@serving").unwrap().0, "model-v2");
+    }
+
+    /// Records every request a mock service receives on `path`, answering with `reply(body)`
+    fn recording_route(
+        path: &str,
+        calls: Arc<std::sync::Mutex<Vec<serde_json::Value>>>,
+        reply: fn(&serde_json::Value) -> serde_json::Value,
+    ) -> Router {
+        Router::new().route(path, post(move |Json(body): Json<serde_json::Value>| {
+            let calls = calls.clone();
+            async move {
+                let response = reply(&body);
+                calls.lock().unwrap().push(body);
+                Json(response)
+            }
+        }))
+    }
+
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// The calldata word a `bytes32` argument of `value` is sent as
+    fn bytes32_word(value: &str) -> String {
+        hex::encode(abi::abi_encode_tuple(&[abi::ParamType::FixedBytes(32)], &[abi_encode_bytes32(value)]).unwrap())
+    }
+
+    fn service_state(scheduler_url: String, runtime_url: String) -> Arc<AppState> {
+        service_state_on(scheduler_url, runtime_url, ManualClock::new(T0))
+    }
+
+    fn service_state_on(scheduler_url: String, runtime_url: String, clock: ManualClock) -> Arc<AppState> {
+        service_state_with_store(scheduler_url, runtime_url, clock, Arc::new(MemoryJobStore::default()))
+    }
+
+    fn service_state_with_store(scheduler_url: String, runtime_url: String, clock: ManualClock, job_store: Arc<dyn JobStore>) -> Arc<AppState> {
+        Arc::new(AppState {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            job_store,
+            results: Arc::new(RwLock::new(ResultCache::default())),
+            contract_client: Arc::new(ContractClient::new("http://127.0.0.1:9".to_string()).with_clock(clock.shared())),
+            policy_gate: Arc::new(PolicyGate::new("http://127.0.0.1:9".to_string())),
+            scheduler_url,
+            runtime_url,
+            deprecation_feed_url: "http://127.0.0.1:9".to_string(),
+            ab_router: Arc::new(RwLock::new(AbRouter::new())),
+            marketplace: Arc::new(RwLock::new(Marketplace::new(250))),
+            receipts_url: "http://127.0.0.1:9".to_string(),
+            node_api_url: "http://127.0.0.1:9".to_string(),
+            artifacts: Arc::new(RwLock::new(ArtifactRegistry::new())),
+            runtime_image_digest: "sha256:unpinned".to_string(),
+            manifest_key: "test-key".to_string().into(),
+            outputs: Arc::new(RwLock::new(OutputVault::new(b"test-key"))),
+            submission_nonces: Arc::new(RwLock::new(HashMap::new())),
+            fanouts: Arc::new(RwLock::new(FanOutRegistry::new())),
+            archived_jobs: Arc::new(RwLock::new(HashMap::new())),
+            retention: RetentionPolicy { max_age_secs: 3600, max_count: 100, interval_secs: 60, archive_path: None },
+            ethics_url: None,
+            moderation_holds: Arc::new(RwLock::new(HashMap::new())),
+            internal_token: "internal".to_string().into(),
+            proofs_url: "http://127.0.0.1:9".to_string(),
+            milestone_plans: Arc::new(RwLock::new(HashMap::new())),
+            secret_refs: Arc::new(RwLock::new(HashMap::new())),
+            stream_specs: Arc::new(RwLock::new(HashMap::new())),
+            quantize: QuantizeConfig { max_accuracy_drop: 0.01 },
+            quantize_specs: Arc::new(RwLock::new(HashMap::new())),
+            events: Arc::new(RwLock::new(EventStore::default())),
+            workflows: Arc::new(RwLock::new(WorkflowStore::load(None))),
+            search: Arc::new(RwLock::new(SearchIndex::open(search_config(None), T0).unwrap())),
+            reservations: Arc::new(RwLock::new(ReservationStore::default())),
+            job_escrows: Arc::new(RwLock::new(JobEscrowStore::default())),
+            forfeiture: ForfeitureSchedule::parse(ForfeitureSchedule::DEFAULT).unwrap(),
+            svdb_url: "http://127.0.0.1:9".to_string(),
+            log_limits: LogLimits::DEFAULT,
+            anonymous: AnonymousPolicy { models: std::collections::HashSet::new(), max_budget: 1000, address_limit: 10, pool_limit: 100, window_secs: 3600 },
+            anon_limiter: Arc::new(RwLock::new(AnonLimiter::default())),
+            chain: Arc::new(RwLock::new(ChainTracker::default())),
+            clock: clock.shared(),
+            entropy: SeededEntropy::shared(1),
+        })
+    }
+
+    fn search_config(journal_path: Option<String>) -> SearchConfig {
+        SearchConfig { journal_path, max_docs: 1000, max_log_bytes: 4096, log_retention_secs: 3600, sync_interval_secs: 60 }
+    }
+
+    fn queued_job(job_id: &str, model_id: &str) -> Job {
+        Job {
+            job_id: job_id.to_string(),
+            job_type: JobType::Train,
+            status: JobStatus::Queued,
+            submitter: "0xtest".to_string(),
+            submitter_did: "did:artha:test".to_string(),
+            model_id: Some(model_id.to_string()),
+            dataset_id: None,
+            params_hash: "0xhash".to_string(),
+            assigned_node: None,
+            budget: 1000,
+            spent: 0,
+            submitted_at: T0,
+            started_at: None,
+            completed_at: None,
+            output_cid: None,
+            artifacts: Vec::new(),
+            progress: 0.0,
+            logs: JobLog::default(),
+            tee_required: false,
+            attestation: None,
+            ab_variant: None,
+            manifest: None,
+            seed: None,
+            live_migration: false,
+            migrations: Vec::new(),
+            terminal_reason: None,
+            anonymous: false,
+            data_provenance: None,
+            labels: Labels::new(),
+            stale_since: None,
+            estimated_duration_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capability_mismatch_rejects_and_reschedules() {
+        let checks: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let starts: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let rejects: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+
+        // The runtime offers CUDA 12.1 torch images only
+        let runtime_url = serve(
+            recording_route("/capabilities/check", checks.clone(), |req| {
+                if req["framework"] == "torch" && req["min_cuda"] != "12.4" {
+                    serde_json::json!({ "satisfiable": true, "runtime": "torch", "image": "artha/torch-runtime:v1", "unmet": [] })
+                } else {
+                    serde_json::json!({ "satisfiable": false, "runtime": null, "image": null, "unmet": ["node driver supports CUDA 12.1, job needs 12.4"] })
+                }
+            })
+            .merge(recording_route("/job/start", starts.clone(), |_| serde_json::json!({}))),
+        )
+        .await;
+        let scheduler_url = serve(recording_route("/schedule/:job_id/reject", rejects.clone(), |_| serde_json::json!({}))).await;
+
+        let state = service_state(scheduler_url, runtime_url);
+        let needs_cuda_12_4 = RuntimeRequirements {
+            framework: "torch".to_string(),
+            framework_version: Some("2.3".to_string()),
+            min_cuda: Some("12.4".to_string()),
+            ..Default::default()
+        };
+        state.artifacts.write().await.set_model_requirements("model-new", needs_cuda_12_4);
+
+        {
+            let mut jobs = state.jobs.write().await;
+            jobs.insert("job-mismatch".to_string(), queued_job("job-mismatch", "model-new"));
+            jobs.insert("job-ok".to_string(), queued_job("job-ok", "model-old"));
+        }
+        let node = "0xnode1aabbccddeeff00112233445566778899";
+        let assigned = |job_id: &str, runtime: &str| JobAssignedRequest {
+            job_id: job_id.to_string(),
+            assigned_node: node.to_string(),
+            runtime: runtime.to_string(),
+            score: None,
+            assign_tx: None,
+        };
+
+        // Declared requirements the node can't meet: no launch, job back to the scheduler
+        let status = job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned("job-mismatch", "torch"))).await;
+        assert_eq!(status, Err(StatusCode::CONFLICT));
+        assert_eq!(checks.lock().unwrap()[0]["min_cuda"], "12.4");
+        assert_eq!(checks.lock().unwrap()[0]["framework_version"], "2.3");
+        assert!(starts.lock().unwrap().is_empty());
+        let reject = rejects.lock().unwrap()[0].clone();
+        assert_eq!(reject["node_pubkey"], node);
+        assert_eq!(reject["reasons"][0], "node driver supports CUDA 12.1, job needs 12.4");
+        {
+            let jobs = state.jobs.read().await;
+            assert_eq!(jobs["job-mismatch"].status, JobStatus::Queued);
+            assert!(jobs["job-mismatch"].assigned_node.is_none());
+        }
+
+        // An unknown runtime hint is checked as-is, not launched as torch
+        let status = job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned("job-ok", "mxnet"))).await;
+        assert_eq!(status, Err(StatusCode::CONFLICT));
+        assert_eq!(checks.lock().unwrap()[1]["framework"], "mxnet");
+        assert!(starts.lock().unwrap().is_empty());
+        assert_eq!(rejects.lock().unwrap().len(), 2);
+
+        // A satisfiable assignment launches with the runtime the node resolved
+        let status = job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned("job-ok", "torch"))).await;
+        assert_eq!(status, Ok(StatusCode::OK));
+        assert_eq!(starts.lock().unwrap()[0]["runtime"], "torch");
+        assert_eq!(state.jobs.read().await["job-ok"].status, JobStatus::Running);
+        assert_eq!(rejects.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_job_list_filters_and_log_stream_resumes() {
+        let clock = ManualClock::new(T0);
+        let state = service_state_on("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string(), clock.clone());
+        {
+            let mut jobs = state.jobs.write().await;
+            let mut running = queued_job("job-run", "model-1");
+            running.status = JobStatus::Running;
+            running.logs = vec!["step 1".to_string(), "step 2".to_string()].into();
+            jobs.insert("job-run".to_string(), running);
+            let mut other = queued_job("job-other", "model-1");
+            other.submitter_did = "did:artha:bob".to_string();
+            jobs.insert("job-other".to_string(), other);
+        }
+        let url = serve(
+            Router::new()
+                .route("/jobs", get(list_jobs))
+                .route("/job/:id/logs/stream", get(stream_job_logs))
+                .with_state(state.clone()),
+        )
+        .await;
+        let client = reqwest::Client::new();
+
+        let listed: Page<Job> = client.get(format!("{}/jobs?status=running&did=did:artha:test", url))
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed.items.iter().map(|j| j.job_id.as_str()).collect::<Vec<_>>(), vec!["job-run"]);
+        let listed: Page<Job> = client.get(format!("{}/jobs?did=did:artha:bob", url))
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed.items.len(), 1);
+        assert_eq!(listed.total, 1);
+
+        // Resume after line 0: only later lines, then the end event once the job finishes
+        let mut response = client.get(format!("{}/job/job-run/logs/stream?from=1", url)).send().await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let first = String::from_utf8(response.chunk().await.unwrap().unwrap().to_vec()).unwrap();
+        assert_eq!(first, "id: 1\ndata: step 2\n\n");
+        {
+            let mut jobs = state.jobs.write().await;
+            let job = jobs.get_mut("job-run").unwrap();
+            job.logs.push("done".to_string());
+            job.status = JobStatus::Completed;
+        }
+        clock.wait_for_sleepers(1).await;
+        clock.advance(LOG_POLL_INTERVAL);
+        let rest = String::from_utf8(response.bytes().await.unwrap().to_vec()).unwrap();
+        assert_eq!(rest, "id: 2\ndata: done\n\nevent: end\ndata: Completed\n\n");
+
+        let missing = client.get(format!("{}/job/nope/logs/stream", url)).send().await.unwrap();
+        assert_eq!(missing.status().as_u16(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_job_logs_stay_bounded_in_memory_and_full_log_comes_from_svdb() {
+        // Mock SVDB storing uploads under sequential CIDs
+        let blobs: Arc<std::sync::Mutex<Vec<Vec<u8>>>> = Arc::default();
+        let (uploads, downloads) = (blobs.clone(), blobs.clone());
+        let svdb_url = serve(
+            Router::new()
+                .route("/svdb/upload", post(move |body: axum::body::Bytes| {
+                    let uploads = uploads.clone();
+                    async move {
+                        let mut blobs = uploads.lock().unwrap();
+                        blobs.push(body.to_vec());
+                        Json(serde_json::json!({ "cid": format!("bafy-log-{}", blobs.len() - 1) }))
+                    }
+                }))
+                .route("/svdb/download/:cid", get(move |Path(cid): Path<String>| {
+                    let downloads = downloads.clone();
+                    async move {
+                        let index: usize = cid.trim_start_matches("bafy-log-").parse().unwrap();
+                        downloads.lock().unwrap()[index].clone()
+                    }
+                })),
+        )
+        .await;
+        let state = Arc::new(AppState {
+            svdb_url,
+            log_limits: LogLimits { max_lines: 5, max_bytes: 1024, max_pending: 1000 },
+            ..Arc::try_unwrap(service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string())).ok().unwrap()
+        });
+        let mut job = queued_job("job-chatty", "model-1");
+        job.logs = JobLog::new(state.log_limits);
+        state.jobs.write().await.insert("job-chatty".to_string(), job);
+
+        let push = |from: usize, to: usize| {
+            let state = state.clone();
+            async move {
+                let mut jobs = state.jobs.write().await;
+                let job = jobs.get_mut("job-chatty").unwrap();
+                for i in from..to {
+                    job.logs.push(format!("step {}", i));
+                }
+            }
+        };
+        push(0, 40).await;
+        assert_eq!(archive_job_logs(&state).await, 1);
+        push(40, 90).await;
+        assert_eq!(archive_job_logs(&state).await, 1);
+        push(90, 100).await; // Not yet archived
+
+        {
+            let jobs = state.jobs.read().await;
+            let log = &jobs["job-chatty"].logs;
+            assert_eq!(log.len(), 5);
+            assert_eq!(log.total(), 100);
+            assert_eq!(log.chunks().len(), 2);
+        }
+        assert_eq!(blobs.lock().unwrap().len(), 2);
+
+        let url = serve(Router::new().route("/job/:id/logs", get(get_job_logs)).with_state(state.clone())).await;
+        let client = reqwest::Client::new();
+        let tail: Vec<String> = client.get(format!("{}/job/job-chatty/logs", url)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(tail, (95..100).map(|i| format!("step {}", i)).collect::<Vec<_>>());
+        let full: Vec<String> = client.get(format!("{}/job/job-chatty/logs?full=true", url)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(full, (0..100).map(|i| format!("step {}", i)).collect::<Vec<_>>());
+
+        // Storage unreachable: lines stay queued and the full log is unavailable
+        let offline = Arc::new(AppState {
+            jobs: state.jobs.clone(),
+            ..Arc::try_unwrap(service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string())).ok().unwrap()
+        });
+        assert_eq!(archive_job_logs(&offline).await, 0);
+        assert_eq!(offline.jobs.read().await["job-chatty"].logs.pending().count(), 10);
+        let url = serve(Router::new().route("/job/:id/logs", get(get_job_logs)).with_state(offline)).await;
+        let response = client.get(format!("{}/job/job-chatty/logs?full=true", url)).send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 502);
+    }
+
+    fn versioned_app(state: Arc<AppState>) -> Router {
+        app(state.clone(), OutputState {
+            vault: state.outputs.clone(),
+            svdb_url: "http://127.0.0.1:9".to_string(),
+            public_url: "http://127.0.0.1:9/v1".to_string(),
+            internal_token: "internal".to_string().into(),
+            max_link_ttl_secs: 600,
+            clock: state.clock.clone(),
+        })
+    }
+
+    /// A finished job with every timestamp pinned, so responses about it are byte-stable
+    fn snapshot_job() -> Job {
+        let mut job = queued_job("job-snap", "model-1");
+        job.status = JobStatus::Completed;
+        job.submitted_at = 1_700_000_000;
+        job.started_at = Some(1_700_000_010);
+        job.completed_at = Some(1_700_000_600);
+        job.assigned_node = Some("node-1".to_string());
+        job.output_cid = Some("bafy-snap-output".to_string());
+        job.progress = 1.0;
+        job.logs = vec!["epoch 1/1".to_string()].into();
+        job
+    }
+
+    async fn get_json(client: &reqwest::Client, url: &str, version: Option<&str>) -> (reqwest::header::HeaderMap, u16, serde_json::Value) {
+        let mut request = client.get(url);
+        if let Some(version) = version {
+            request = request.header(versioning::ACCEPT_VERSION, version);
+        }
+        let resp = request.send().await.unwrap();
+        let headers = resp.headers().clone();
+        let status = resp.status().as_u16();
+        (headers, status, resp.json().await.unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_aliases_are_deprecated_and_negotiate_versions() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        state.jobs.write().await.insert("job-snap".to_string(), snapshot_job());
+        let url = serve(versioned_app(state)).await;
+        let client = reqwest::Client::new();
+
+        let (headers, status, alias) = get_json(&client, &format!("{}/job/job-snap/status", url), None).await;
+        assert_eq!(status, 200);
+        assert_eq!(headers["deprecation"], "true");
+        assert_eq!(headers["sunset"], versioning::ALIAS_SUNSET);
+        assert_eq!(headers["link"], "</v1/job/job-snap/status>; rel=\"successor-version\"");
+        assert_eq!(headers[versioning::API_VERSION], "1");
+
+        // The alias serves v1 byte for byte; the versioned path is not deprecated
+        let (headers, _, v1) = get_json(&client, &format!("{}/v1/job/job-snap/status", url), None).await;
+        assert_eq!(alias, v1);
+        assert!(headers.get("deprecation").is_none());
+        assert_eq!(headers[versioning::API_VERSION], "1");
+
+        let (headers, _, negotiated) = get_json(&client, &format!("{}/job/job-snap/status", url), Some("v2")).await;
+        assert_eq!(headers[versioning::API_VERSION], "2");
+        assert_eq!(headers["link"], "</v2/job/job-snap/status>; rel=\"successor-version\"");
+        assert_eq!(negotiated["job"]["status"], "COMPLETED");
+
+        let (headers, status, error) = get_json(&client, &format!("{}/jobs", url), Some("7")).await;
+        assert_eq!(status, 400);
+        assert_eq!(headers["deprecation"], "true");
+        assert_eq!(error["error"], "invalid_request");
+        assert_eq!(error["details"]["supported"], serde_json::json!(["1", "2"]));
+    }
+
+    #[tokio::test]
+    async fn test_same_request_through_v1_and_v2_keeps_each_schema() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        state.jobs.write().await.insert("job-snap".to_string(), snapshot_job());
+        let output_id = state.outputs.write().await.register("job-snap", "bafy-snap-output", "did:artha:test", 1_700_000_600, &*state.entropy);
+        *state.marketplace.write().await = market_with_listing(ListingPrice::PerJob(500), None);
+        let url = serve(versioned_app(state.clone())).await;
+        let client = reqwest::Client::new();
+
+        let (_, _, v1) = get_json(&client, &format!("{}/v1/job/job-snap/status", url), None).await;
+        let (_, _, v2) = get_json(&client, &format!("{}/v2/job/job-snap/status", url), None).await;
+        assert_eq!(v1["job"]["status"], "Completed");
+        assert_eq!(v1["job"]["job_type"], "Train");
+        assert!(v1["job"].as_object().unwrap().contains_key("output_cid"));
+        assert_eq!(v2["job"]["status"], "COMPLETED");
+        assert_eq!(v2["job"]["job_type"], "TRAIN");
+        assert!(!v2["job"].as_object().unwrap().contains_key("output_cid"));
+        assert_eq!(v1["output_id"], output_id.as_str());
+        assert_eq!(v2["output_id"], output_id.as_str());
+
+        // v1 passes ad-hoc and plain-text errors through; v2 always structures them
+        let (_, status, v1) = get_json(&client, &format!("{}/v1/output/out-missing/download?did=d&expires_at=1&sig=00", url), None).await;
+        assert_eq!(status, 404);
+        assert_eq!(v1, serde_json::json!({ "error": "unknown_output" }));
+        let (_, status, v2) = get_json(&client, &format!("{}/v2/output/out-missing/download?did=d&expires_at=1&sig=00", url), None).await;
+        assert_eq!(status, 404);
+        assert_eq!(v2["error"], "not_found");
+        assert_eq!(v2["details"], serde_json::json!({ "error": "unknown_output" }));
+        let v1 = client.get(format!("{}/v1/output/out-missing/download", url)).send().await.unwrap();
+        assert_eq!(v1.status().as_u16(), 400);
+        assert!(serde_json::from_str::<ServiceError>(&v1.text().await.unwrap()).is_err());
+        let (_, status, v2) = get_json(&client, &format!("{}/v2/output/out-missing/download", url), None).await;
+        assert_eq!(status, 400);
+        assert_eq!(v2["error"], "invalid_request");
+        assert!(v2["message"].as_str().unwrap().contains("query"));
+
+        // v2 requests spell enums SCREAMING_SNAKE too
+        let resp = client.post(format!("{}/v2/market/listing/listing-1/status", url))
+            .json(&serde_json::json!({ "owner_did": "did:artha:owner", "status": "SUSPENDED" }))
+            .send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+        assert!(matches!(state.marketplace.read().await.listing("listing-1").unwrap().status, ListingStatus::Suspended));
+
+        // Each version documents its own schema
+        let (_, _, spec_v1) = get_json(&client, &format!("{}/v1/openapi.json", url), None).await;
+        let (_, _, spec_v2) = get_json(&client, &format!("{}/v2/openapi.json", url), None).await;
+        assert_eq!(spec_v1["servers"][0]["url"], "/v1");
+        assert_eq!(spec_v2["servers"][0]["url"], "/v2");
+        assert_eq!(spec_v1["components"]["schemas"]["JobStatus"]["enum"][2], "Running");
+        assert_eq!(spec_v2["components"]["schemas"]["JobStatus"]["enum"][2], "RUNNING");
+        assert!(spec_v1["components"]["schemas"]["Job"]["properties"].get("output_cid").is_some());
+        assert!(spec_v2["components"]["schemas"]["Job"]["properties"].get("output_cid").is_none());
+        assert!(spec_v2["paths"]["/job/{id}/status"]["get"].is_object());
+    }
+
+    /// (method, path) of every route `app` serves, read off its source
+    fn registered_routes() -> std::collections::BTreeSet<(String, String)> {
+        let main = include_str!("main.rs");
+        let app = &main[main.find("fn app(").unwrap()..main.find("// Unmatched paths get").unwrap()];
+        let outputs = include_str!("outputs.rs");
+        let outputs = &outputs[outputs.find("pub fn router(").unwrap()..];
+        let mut routes = std::collections::BTreeSet::new();
+        for line in app.lines().chain(outputs.lines()) {
+            let Some((path, handlers)) = line.trim().strip_prefix(".route(\"").and_then(|rest| rest.split_once('"')) else { continue };
+            for method in ["get", "post", "put", "delete"] {
+                let call = format!("{}(", method);
+                let registered = handlers
+                    .match_indices(&call)
+                    .any(|(i, _)| !handlers[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_'));
+                if registered {
+                    routes.insert((method.to_string(), path.to_string()));
+                }
+            }
+        }
+        routes
+    }
+
+    /// A body with only the required properties of `schema`, each set to a placeholder
+    fn minimal_body(spec: &serde_json::Value, schema: &serde_json::Value) -> serde_json::Value {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return minimal_body(spec, &spec["components"]["schemas"][name]);
+        }
+        match schema["type"].as_str() {
+            Some("object") => serde_json::Value::Object(
+                schema["required"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|name| {
+                        let name = name.as_str().unwrap();
+                        (name.to_string(), minimal_body(spec, &schema["properties"][name]))
+                    })
+                    .collect(),
+            ),
+            Some("array") => serde_json::json!([]),
+            Some("integer") => serde_json::json!(1),
+            Some("number") => serde_json::json!(0.5),
+            Some("boolean") => serde_json::json!(false),
+            Some("string") => schema["enum"].get(0).cloned().unwrap_or_else(|| serde_json::json!("x")),
+            _ => serde_json::Value::Null,
+        }
+    }
+
+    fn collect_refs<'a>(value: &'a serde_json::Value, refs: &mut Vec<&'a str>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                refs.extend(map.get("$ref").and_then(|r| r.as_str()));
+                map.values().for_each(|v| collect_refs(v, refs));
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
 
     #[test]
-    fn test_compute_params_hash() {
-        let params = TrainParams {
-            epochs: 3,
-            batch_size: 64,
-            learning_rate: 0.001,
-            optimizer: "adam".to_string(),
-            checkpoint_interval: 500,
+    fn test_openapi_documents_every_registered_route_with_its_schemas() {
+        for version in [versioning::ApiVersion::V1, versioning::ApiVersion::V2] {
+            let spec = openapi::spec(version);
+
+            // Every public route is documented, and nothing that isn't served
+            let documented: std::collections::BTreeSet<(String, String)> = spec["paths"]
+                .as_object()
+                .unwrap()
+                .iter()
+                .flat_map(|(path, methods)| {
+                    let path = path.replace('{', ":").replace('}', "");
+                    methods.as_object().unwrap().keys().map(move |method| (method.clone(), path.clone()))
+                })
+                .collect();
+            let registered: std::collections::BTreeSet<_> =
+                registered_routes().into_iter().filter(|(_, path)| !path.starts_with("/internal/")).collect();
+            assert!(registered.len() > 50);
+            assert_eq!(
+                registered.difference(&documented).collect::<Vec<_>>(),
+                Vec::<&(String, String)>::new(),
+                "registered but undocumented"
+            );
+            assert_eq!(
+                documented.difference(&registered).collect::<Vec<_>>(),
+                Vec::<&(String, String)>::new(),
+                "documented but not served"
+            );
+
+            // Operation ids are unique, and every schema referenced is defined
+            let mut ids: Vec<&str> = spec["paths"]
+                .as_object()
+                .unwrap()
+                .values()
+                .flat_map(|methods| methods.as_object().unwrap().values().map(|op| op["operationId"].as_str().unwrap()))
+                .collect();
+            let count = ids.len();
+            ids.sort_unstable();
+            ids.dedup();
+            assert_eq!(ids.len(), count);
+            let mut refs = Vec::new();
+            collect_refs(&spec, &mut refs);
+            for reference in refs {
+                let name = reference.strip_prefix("#/components/schemas/").unwrap();
+                assert!(spec["components"]["schemas"][name].is_object(), "{} is not defined", reference);
+            }
+
+            // Request schemas: their required properties are exactly enough for the handler's DTO
+            let body_schema = |method: &str, path: &str| -> serde_json::Value {
+                spec["paths"][path][method]["requestBody"]["content"]["application/json"]["schema"].clone()
+            };
+            type Parse = fn(serde_json::Value) -> Result<(), serde_json::Error>;
+            let requests: [(&str, &str, Parse); 6] = [
+                ("post", "/job/train", |v| serde_json::from_value::<TrainJobRequest>(v).map(drop)),
+                ("post", "/job/infer", |v| serde_json::from_value::<InferJobRequest>(v).map(drop)),
+                ("post", "/job/agent", |v| serde_json::from_value::<AgentJobRequest>(v).map(drop)),
+                ("post", "/ai/model/register", |v| serde_json::from_value::<ModelRegisterRequest>(v).map(drop)),
+                ("post", "/ai/dataset/register", |v| serde_json::from_value::<DatasetRegisterRequest>(v).map(drop)),
+                ("post", "/reservations", |v| serde_json::from_value::<ReservationRequest>(v).map(drop)),
+            ];
+            for (method, path, parse) in requests {
+                let schema = body_schema(method, path);
+                assert!(schema["$ref"].is_string(), "{} {} has no request schema", method, path);
+                let body = minimal_body(&spec, &schema);
+                assert!(parse(body.clone()).is_ok(), "{} {}: {} does not parse: {:?}", method, path, body, parse(body.clone()));
+                // Dropping any required property breaks it
+                for name in body.as_object().unwrap().keys() {
+                    let mut partial = body.clone();
+                    partial.as_object_mut().unwrap().remove(name);
+                    assert!(parse(partial).is_err(), "{} {}: {} is documented as required but isn't", method, path, name);
+                }
+            }
+
+            // Response schemas document every field the DTOs serialize
+            let response_schema = |method: &str, path: &str| -> String {
+                let schema = &spec["paths"][path][method]["responses"]["200"]["content"]["application/json"]["schema"];
+                schema["$ref"].as_str().unwrap().trim_start_matches("#/components/schemas/").to_string()
+            };
+            let card = ModelCard {
+                intended_use: "classification".to_string(),
+                training_data: "imagenet".to_string(),
+                eval_metrics: [("top1".to_string(), 0.76)].into_iter().collect(),
+                limitations: "none known".to_string(),
+                out_of_scope_uses: vec!["medical".to_string()],
+                ethical_considerations: Some("bias".to_string()),
+            };
+            let submitted = JobSubmitResponse {
+                job_id: "job-1".to_string(),
+                status: JobStatus::Queued,
+                estimated_cost: 100,
+                estimated_duration_secs: 5,
+                policy_degraded: Some(DegradedPolicy { mode: FailMode::CachedLastDecision, decided_at: Some(T0) }),
+                cached: false,
+            };
+            let samples = [
+                (response_schema("post", "/job/train"), serde_json::to_value(&submitted).unwrap()),
+                (
+                    response_schema("get", "/ai/model/{id}/card"),
+                    serde_json::to_value(ModelCardView { model_id: "model-1".to_string(), card_cid: "bafy-card".to_string(), card }).unwrap(),
+                ),
+                ("ServiceError".to_string(), serde_json::to_value(ServiceError::new(ErrorCode::Conflict, "taken")).unwrap()),
+            ];
+            for (name, sample) in samples {
+                let properties = spec["components"]["schemas"][&name]["properties"].as_object().unwrap();
+                for field in sample.as_object().unwrap().keys() {
+                    assert!(properties.contains_key(field), "{}.{} is undocumented", name, field);
+                }
+            }
+        }
+    }
+
+    /// Paths and values where `actual` departs from the pinned `expected` wire format
+    fn wire_diff(expected: &serde_json::Value, actual: &serde_json::Value) -> Vec<String> {
+        let mut diffs = Vec::new();
+        diff_at("$", expected, actual, &mut diffs);
+        diffs
+    }
+
+    fn diff_at(path: &str, expected: &serde_json::Value, actual: &serde_json::Value, diffs: &mut Vec<String>) {
+        use serde_json::Value;
+        match (expected, actual) {
+            (Value::Object(want), Value::Object(got)) => {
+                for (key, value) in want {
+                    match got.get(key) {
+                        Some(other) => diff_at(&format!("{}.{}", path, key), value, other, diffs),
+                        None => diffs.push(format!("{}.{}: missing", path, key)),
+                    }
+                }
+                for key in got.keys().filter(|key| !want.contains_key(*key)) {
+                    diffs.push(format!("{}.{}: unexpected field", path, key));
+                }
+            }
+            (Value::Array(want), Value::Array(got)) if want.len() == got.len() => {
+                for (i, (value, other)) in want.iter().zip(got).enumerate() {
+                    diff_at(&format!("{}[{}]", path, i), value, other, diffs);
+                }
+            }
+            _ if expected != actual => diffs.push(format!("{}: expected {}, got {}", path, expected, actual)),
+            _ => {}
+        }
+    }
+
+    /// Pinned v1 responses: (request path, snapshot)
+    const V1_SNAPSHOTS: [(&str, &str); 3] = [
+        ("/v1/job/job-snap/status", include_str!("../snapshots/v1/job_status.json")),
+        ("/v1/jobs?did=did:artha:test", include_str!("../snapshots/v1/jobs.json")),
+        ("/v1/job/job-missing/status", include_str!("../snapshots/v1/job_not_found.json")),
+    ];
+
+    #[tokio::test]
+    async fn test_v1_wire_format_matches_snapshots() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        state.jobs.write().await.insert("job-snap".to_string(), snapshot_job());
+        let url = serve(versioned_app(state)).await;
+        let client = reqwest::Client::new();
+
+        for (path, snapshot) in V1_SNAPSHOTS {
+            let expected: serde_json::Value = serde_json::from_str(snapshot).unwrap();
+            let (_, _, actual) = get_json(&client, &format!("{}{}", url, path), None).await;
+            let diffs = wire_diff(&expected, &actual);
+            assert!(diffs.is_empty(), "v1 wire format changed for {}: {:?}", path, diffs);
+        }
+
+        // An intentional v1-breaking change to a fixture is caught, field by field
+        let (_, _, mut broken) = get_json(&client, &format!("{}/v1/job/job-snap/status", url), None).await;
+        versioning::response_to_v2(&mut broken, false);
+        broken["job"]["eta_secs"] = serde_json::json!(0);
+        let expected: serde_json::Value = serde_json::from_str(V1_SNAPSHOTS[0].1).unwrap();
+        let diffs = wire_diff(&expected, &broken);
+        assert_eq!(diffs, vec![
+            "$.job.job_type: expected \"Train\", got \"TRAIN\"".to_string(),
+            "$.job.output_cid: missing".to_string(),
+            "$.job.status: expected \"Completed\", got \"COMPLETED\"".to_string(),
+            "$.job.eta_secs: unexpected field".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_enum_values_round_trip_between_versions() {
+        let mut response = serde_json::json!({
+            "status": "Completed", "output_cid": "bafy", "mode": "batch",
+            "items": [{ "job_type": "Train", "action": "DownloadDenied", "status": "hard-block" }],
+        });
+        versioning::response_to_v2(&mut response, false);
+        assert_eq!(response, serde_json::json!({
+            "status": "COMPLETED", "mode": "batch",
+            "items": [{ "job_type": "TRAIN", "action": "DOWNLOAD_DENIED", "status": "hard-block" }],
+        }));
+
+        let mut request = serde_json::json!({ "status": "DOWNLOAD_DENIED", "reason": "RUNNING" });
+        versioning::request_to_v1(&mut request);
+        assert_eq!(request, serde_json::json!({ "status": "DownloadDenied", "reason": "RUNNING" }));
+
+        // User labels are never rewritten, whatever their keys
+        let labels = serde_json::json!({ "labels": { "status": "Done", "job_type": "NIGHTLY_RUN" } });
+        let (mut response, mut request) = (labels.clone(), labels.clone());
+        versioning::response_to_v2(&mut response, false);
+        versioning::request_to_v1(&mut request);
+        assert_eq!((response, request), (labels.clone(), labels));
+
+        assert_eq!(versioning::ApiVersion::parse(" v2.0 "), Some(versioning::ApiVersion::V2));
+        assert_eq!(versioning::ApiVersion::parse("3"), None);
+    }
+
+    #[tokio::test]
+    async fn test_job_pages_continue_without_overlap() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        {
+            let mut jobs = state.jobs.write().await;
+            for i in 0..5 {
+                let mut job = queued_job(&format!("job-{}", i), "model-1");
+                job.submitted_at = 1_000 + i;
+                jobs.insert(job.job_id.clone(), job);
+            }
+        }
+        let list = |limit: usize, cursor: Option<String>| list_jobs(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(HashMap::new()),
+            Query(PageQuery { limit: Some(limit), cursor }),
+        );
+
+        let Json(first) = list(2, None).await.unwrap();
+        assert_eq!(first.total, 5);
+        assert_eq!(first.items.iter().map(|j| j.job_id.as_str()).collect::<Vec<_>>(), vec!["job-0", "job-1"]);
+
+        // A job submitted between requests lands on a later page, never a repeat
+        let mut late = queued_job("job-late", "model-1");
+        late.submitted_at = 2_000;
+        state.jobs.write().await.insert(late.job_id.clone(), late);
+
+        let mut seen: Vec<String> = first.items.iter().map(|j| j.job_id.clone()).collect();
+        let mut cursor = first.next_cursor;
+        while let Some(next) = cursor {
+            let Json(page) = list(2, Some(next)).await.unwrap();
+            assert_eq!(page.total, 6);
+            seen.extend(page.items.iter().map(|j| j.job_id.clone()));
+            cursor = page.next_cursor;
+        }
+        assert_eq!(seen, vec!["job-0", "job-1", "job-2", "job-3", "job-4", "job-late"]);
+
+        assert_eq!(list(2, Some("not-a-cursor".to_string())).await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_jobs_filter_by_labels_with_and_semantics() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        {
+            let mut jobs = state.jobs.write().await;
+            for (job_id, labels) in [
+                ("job-a", vec![("team", "vision"), ("experiment", "lr-sweep")]),
+                ("job-b", vec![("team", "vision"), ("experiment", "baseline")]),
+                ("job-c", vec![("team", "nlp"), ("experiment", "lr-sweep")]),
+                ("job-d", vec![]),
+            ] {
+                let mut job = queued_job(job_id, "model-1");
+                job.labels = labels.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+                jobs.insert(job_id.to_string(), job);
+            }
+        }
+        let url = serve(Router::new().route("/jobs", get(list_jobs)).with_state(state.clone())).await;
+        let listed = |query: &str| {
+            let url = format!("{}/jobs?{}", url, query);
+            async move {
+                let page: Page<Job> = reqwest::get(url).await.unwrap().json().await.unwrap();
+                page.items.into_iter().map(|j| j.job_id).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(listed("label.team=vision").await, vec!["job-a", "job-b"]);
+        assert_eq!(listed("label.experiment=lr-sweep").await, vec!["job-a", "job-c"]);
+        // Every label must match
+        assert_eq!(listed("label.team=vision&label.experiment=lr-sweep").await, vec!["job-a"]);
+        assert!(listed("label.team=nlp&label.experiment=baseline").await.is_empty());
+        assert!(listed("label.ticket=ML-1").await.is_empty());
+        assert_eq!(listed("").await.len(), 4);
+
+        // Labels are indexed for search as key=value
+        let doc = job_search_doc(&state.jobs.read().await["job-a"], &*state.search.read().await);
+        assert_eq!(doc.fields["label"].len(), 2);
+        assert!(doc.fields["label"].contains(&"team=vision".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_labels_are_refused_at_submission() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        let agent = |labels: Labels| AgentJobRequest {
+            agent_spec_cid: "bafy-agent".to_string(),
+            submitter_did: "did:artha:test".to_string(),
+            goal: "summarize".to_string(),
+            tools: vec![],
+            memory_policy: "none".to_string(),
+            budget: 100,
+            nonce: None,
+            labels,
+        };
+        let refused = [
+            Labels::from([("k".repeat(labels::MAX_KEY_LEN + 1), "v".to_string())]),
+            Labels::from([("team".to_string(), "v".repeat(labels::MAX_VALUE_LEN + 1))]),
+            Labels::from([(String::new(), "v".to_string())]),
+            Labels::from([("a=b".to_string(), "v".to_string())]),
+            (0..=labels::MAX_LABELS).map(|i| (format!("k{}", i), "v".to_string())).collect(),
+        ];
+        for labels in refused {
+            match submit_agent_job(State(state.clone()), Json(agent(labels))).await {
+                Err(SubmitError::Service(error)) => assert_eq!(error.code, ErrorCode::InvalidRequest as u32),
+                _ => panic!("labels accepted"),
+            }
+        }
+        assert!(state.jobs.read().await.is_empty());
+
+        let at_limits = Labels::from([("k".repeat(labels::MAX_KEY_LEN), "v".repeat(labels::MAX_VALUE_LEN))]);
+        assert_eq!(labels::validate(&at_limits), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_retention_loop_evicts_a_job_once_it_ages_out_on_the_clock() {
+        let clock = ManualClock::new(T0);
+        let state = service_state_on("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string(), clock.clone());
+        let mut done = queued_job("job-done", "model-1");
+        done.status = JobStatus::Completed;
+        done.completed_at = Some(clock.now_secs());
+        state.jobs.write().await.insert(done.job_id.clone(), done);
+
+        let gc = tokio::spawn(retention_gc_loop(state.clone()));
+        clock.wait_for_sleepers(1).await;
+        // Older than an hour goes, swept every minute
+        for _ in 0..60 {
+            clock.tick(std::time::Duration::from_secs(60)).await;
+        }
+        assert!(state.jobs.read().await.contains_key("job-done"));
+        clock.tick(std::time::Duration::from_secs(60)).await;
+        assert!(!state.jobs.read().await.contains_key("job-done"));
+        assert!(state.archived_jobs.read().await.contains_key("job-done"));
+        gc.abort();
+    }
+
+    #[tokio::test]
+    async fn test_retention_gc_evicts_only_old_finished_jobs() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        let now = T0;
+        let two_hours_ago = now - 7200;
+        {
+            let mut jobs = state.jobs.write().await;
+            let mut old_done = queued_job("job-old-done", "model-1");
+            old_done.status = JobStatus::Completed;
+            old_done.submitted_at = two_hours_ago - 600;
+            old_done.completed_at = Some(two_hours_ago);
+            jobs.insert(old_done.job_id.clone(), old_done);
+
+            let mut old_cancelled = queued_job("job-old-cancelled", "model-1");
+            old_cancelled.status = JobStatus::Cancelled;
+            old_cancelled.completed_at = Some(two_hours_ago);
+            jobs.insert(old_cancelled.job_id.clone(), old_cancelled);
+
+            let mut recent_done = queued_job("job-recent-done", "model-1");
+            recent_done.status = JobStatus::Completed;
+            recent_done.completed_at = Some(now - 60);
+            jobs.insert(recent_done.job_id.clone(), recent_done);
+
+            // Active jobs stay however old they are
+            let mut old_running = queued_job("job-old-running", "model-1");
+            old_running.status = JobStatus::Running;
+            old_running.submitted_at = two_hours_ago - 3600;
+            jobs.insert(old_running.job_id.clone(), old_running);
+            let mut old_queued = queued_job("job-old-queued", "model-1");
+            old_queued.submitted_at = two_hours_ago - 3600;
+            jobs.insert(old_queued.job_id.clone(), old_queued);
+        }
+
+        assert_eq!(gc_finished_jobs(&state, now).await, 2);
+        let mut remaining: Vec<String> = state.jobs.read().await.keys().cloned().collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["job-old-queued", "job-old-running", "job-recent-done"]);
+
+        // Evicted jobs keep their on-chain references
+        let archived = get_archived_job(State(state.clone()), HeaderMap::new(), Path("job-old-done".to_string())).await.unwrap().0;
+        assert_eq!(archived.params_hash, "0xhash");
+        assert_eq!(archived.status, JobStatus::Completed);
+        assert_eq!(archived.completed_at, two_hours_ago);
+        let status = get_job_status(State(state.clone()), HeaderMap::new(), Path("job-old-done".to_string())).await;
+        assert_eq!(status.err(), Some(StatusCode::GONE));
+        assert_eq!(gc_finished_jobs(&state, now).await, 0);
+
+        // Past the count cap the oldest finished records go first
+        let capped = RetentionPolicy { max_age_secs: 3600, max_count: 1, interval_secs: 60, archive_path: None };
+        let finished = vec![("a".to_string(), now - 30), ("b".to_string(), now - 10), ("c".to_string(), now - 20)];
+        let mut evicted = capped.select(finished, now);
+        evicted.sort();
+        assert_eq!(evicted, vec!["a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_output_is_held_until_appeal_overturns() {
+        let checks: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let ethics_url = serve(recording_route("/ethics/check", checks.clone(), |req| {
+            if req["content"].as_str().unwrap_or_default().contains("hate") {
+                serde_json::json!({ "allowed": false, "flags": [], "score": 0.6, "decision_id": "decision-1" })
+            } else {
+                serde_json::json!({ "allowed": true, "flags": [], "score": 0.0, "decision_id": null })
+            }
+        }))
+        .await;
+        let mut state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        Arc::get_mut(&mut state).unwrap().ethics_url = Some(ethics_url);
+        {
+            let mut jobs = state.jobs.write().await;
+            for job_id in ["job-blocked", "job-clean"] {
+                let mut job = queued_job(job_id, "model-1");
+                job.status = JobStatus::Running;
+                jobs.insert(job_id.to_string(), job);
+            }
+        }
+        let completed = |output: &str| JobProgressRequest {
+            progress: 1.0,
+            epochs_completed: None,
+            status: Some(JobStatus::Completed),
+            output_cid: Some("bafy-output".to_string()),
+            peak_vram_mb: None,
+            failure_reason: None,
+            output_text: Some(output.to_string()),
+            migratable: None,
+            spent: None,
+            metrics: None,
+            terminal_reason: None,
+            output_digest: None,
+            output_signature: None,
+        };
+
+        job_progress(State(state.clone()), Path("job-clean".to_string()), Json(completed("a nice poem"))).await.unwrap();
+        job_progress(State(state.clone()), Path("job-blocked".to_string()), Json(completed("hate speech"))).await.unwrap();
+        assert_eq!(checks.lock().unwrap()[1]["tenant"], "did:artha:test");
+        assert_eq!(checks.lock().unwrap()[1]["enforce"], true);
+        assert_eq!(state.jobs.read().await["job-clean"].status, JobStatus::Completed);
+
+        // The blocked job fails without exposing its output
+        let status = get_job_status(State(state.clone()), HeaderMap::new(), Path("job-blocked".to_string())).await.unwrap().0;
+        assert_eq!(status.job.status, JobStatus::Failed);
+        assert_eq!(status.job.terminal_reason.unwrap().kind, TerminalReasonKind::ModerationBlocked);
+        assert_eq!(status.moderation_hold.as_deref(), Some("decision-1"));
+        assert!(status.output_id.is_none());
+        assert!(state.jobs.read().await["job-blocked"].output_cid.is_none());
+
+        let release = |token: &str, decision_id: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(outputs::INTERNAL_TOKEN_HEADER, HeaderValue::from_str(token).unwrap());
+            release_moderation_hold(
+                State(state.clone()),
+                Path("job-blocked".to_string()),
+                headers,
+                Json(ModerationReleaseRequest { decision_id: decision_id.to_string() }),
+            )
+        };
+        assert_eq!(release("wrong", "decision-1").await, Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(release("internal", "decision-2").await, Err(StatusCode::CONFLICT));
+
+        // Overturned on appeal: the job completes with its held output
+        assert_eq!(release("internal", "decision-1").await, Ok(StatusCode::OK));
+        let status = get_job_status(State(state.clone()), HeaderMap::new(), Path("job-blocked".to_string())).await.unwrap().0;
+        assert_eq!(status.job.status, JobStatus::Completed);
+        assert!(status.job.terminal_reason.is_none());
+        assert!(status.moderation_hold.is_none());
+        assert!(status.output_id.is_some());
+        assert_eq!(state.outputs.read().await.output_for_job("job-blocked").unwrap().cid, "bafy-output");
+        assert_eq!(release("internal", "decision-1").await, Err(StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn test_oom_failure_and_user_cancel_carry_distinct_terminal_reasons() {
+        let (state, _rpc) = workflow_state().await;
+        let cancelled_id = format!("{:0>32}", "job-cancelled"); // Cancels go on-chain as bytes32
+        {
+            let mut jobs = state.jobs.write().await;
+            for job_id in ["job-oom", "job-crashed", cancelled_id.as_str()] {
+                let mut job = queued_job(job_id, WF_MODEL);
+                job.status = JobStatus::Running;
+                jobs.insert(job_id.to_string(), job);
+            }
+        }
+
+        // ai-runtime reports an OOM-killed container with its structured reason
+        let oom = "Container was killed out of memory (exit code 137) after 0 restarts";
+        finish_job(&state, "job-oom", "Failed", serde_json::json!({
+            "progress": 0.0,
+            "failure_reason": oom,
+            "terminal_reason": { "kind": "out_of_memory", "detail": oom, "exit_code": 137 },
+        })).await;
+        // A report with only a message is still a runtime failure
+        finish_job(&state, "job-crashed", "Failed", serde_json::json!({ "failure_reason": "segfault in loader" })).await;
+        assert_eq!(cancel_job(State(state.clone()), HeaderMap::new(), Path(cancelled_id.clone())).await, Ok(StatusCode::OK));
+
+        let status = |job_id: &str| get_job_status(State(state.clone()), HeaderMap::new(), Path(job_id.to_string()));
+        let oom_job = status("job-oom").await.unwrap().0.job;
+        assert_eq!(oom_job.status, JobStatus::Failed);
+        assert_eq!(oom_job.terminal_reason, Some(TerminalReason {
+            kind: TerminalReasonKind::OutOfMemory,
+            detail: oom.to_string(),
+            exit_code: Some(137),
+        }));
+        let crashed = status("job-crashed").await.unwrap().0.job.terminal_reason.unwrap();
+        assert_eq!((crashed.kind, crashed.detail.as_str()), (TerminalReasonKind::RuntimeError, "segfault in loader"));
+        let cancelled = status(&cancelled_id).await.unwrap().0.job;
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        let reason = cancelled.terminal_reason.unwrap();
+        assert_eq!((reason.kind, reason.exit_code), (TerminalReasonKind::UserCancelled, None));
+
+        // Serialized as snake_case kinds, and kept on the failure event
+        let json = serde_json::to_value(&oom_job).unwrap();
+        assert_eq!(json["terminal_reason"]["kind"], "out_of_memory");
+        let events = state.events.read().await.for_job("job-oom");
+        let failed = events.iter().find(|e| e.kind == EventKind::Failed).unwrap();
+        assert_eq!(failed.detail["terminal_reason"]["exit_code"], 137);
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_inference_records_no_did_and_binds_outputs_to_the_ephemeral_key() {
+        // ai-ethics answers, except for outputs it can't judge
+        let checks: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let recorded = checks.clone();
+        let ethics_url = serve(Router::new().route("/ethics/check", post(move |Json(body): Json<serde_json::Value>| {
+            let recorded = recorded.clone();
+            async move {
+                let unjudgeable = body["content"] == "unjudgeable";
+                recorded.lock().unwrap().push(body);
+                if unjudgeable {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "verdict engine down").into_response();
+                }
+                Json(serde_json::json!({ "allowed": true })).into_response()
+            }
+        })))
+        .await;
+        let schedules = Arc::default();
+        let scheduler_url = serve(recording_route("/schedule", schedules, |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let model_id = "model-llama-7b-chat-anonymous-00";
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+            state.ethics_url = Some(ethics_url);
+            state.anonymous = AnonymousPolicy {
+                models: std::collections::HashSet::from([model_id.to_string()]),
+                max_budget: 100,
+                address_limit: 2,
+                pool_limit: 3,
+                window_secs: 3600,
+            };
+        }
+        state.artifacts.write().await.register_model(&Namespace::Shared, model_id, "bafy-model", Some("llama"), "1.0");
+        let jobd_url = serve(Router::new().route("/job/infer", post(submit_infer_job)).with_state(state.clone())).await;
+        let client = reqwest::Client::new();
+
+        let key = |seed: u8| k256::ecdsa::SigningKey::from_slice(&[seed; 32]).unwrap();
+        let address = |key: &k256::ecdsa::SigningKey| format!("0x{}", hex::encode(sponsor::address_of(key)));
+        let sign = |key: &k256::ecdsa::SigningKey, digest: [u8; 32]| {
+            let (sig, recovery_id) = key.sign_prehash_recoverable(&digest).unwrap();
+            let mut bytes = sig.to_bytes().to_vec();
+            bytes.push(27 + recovery_id.to_byte());
+            format!("0x{}", hex::encode(bytes))
+        };
+        let input_cid = "artha://QmPromptAboutPersistentSymptoms";
+        let request = |key: &k256::ecdsa::SigningKey, model: &str, budget: u64, nonce: u64| serde_json::json!({
+            "model_id": model, "input_cid": input_cid, "inline_input": null, "mode": "batch",
+            "max_tokens": 64, "budget": budget, "bucketing_key": null, "nonce": nonce,
+            "anonymous": true, "ephemeral_address": address(key),
+            "ephemeral_signature": sign(key, anonymous::submission_digest(model, input_cid, budget, nonce)),
+        });
+        let submit = |body: serde_json::Value| {
+            let (client, url) = (client.clone(), jobd_url.clone());
+            async move {
+                let response = client.post(format!("{}/job/infer", url)).json(&body).send().await.unwrap();
+                (response.status().as_u16(), response.json::<serde_json::Value>().await.unwrap())
+            }
+        };
+        let (alice, bob) = (key(7), key(8));
+
+        // Refused: a DID alongside, a signature from another key, a model or
+        // budget outside the anonymous policy
+        let mut with_did = request(&alice, model_id, 50, 1);
+        with_did["submitter_did"] = serde_json::json!("did:artha:alice");
+        assert_eq!(submit(with_did).await.0, 400);
+        let mut forged = request(&alice, model_id, 50, 1);
+        forged["ephemeral_address"] = serde_json::json!(address(&bob));
+        assert_eq!(submit(forged).await.0, 401);
+        let (status, error) = submit(request(&alice, "model-unrestricted-general-0000000", 50, 1)).await;
+        assert_eq!((status, error["error"].as_str()), (403, Some("policy_denied")));
+        assert_eq!(submit(request(&alice, model_id, 500, 1)).await.0, 403);
+
+        let (status, submitted) = submit(request(&alice, model_id, 50, 1)).await;
+        assert_eq!(status, 200, "{}", submitted);
+        let job_id = submitted["job_id"].as_str().unwrap().to_string();
+        {
+            let jobs = state.jobs.read().await;
+            let job = &jobs[&job_id];
+            assert!(job.anonymous);
+            assert_eq!((job.submitter.as_str(), job.submitter_did.as_str()), (address(&alice).as_str(), ""));
+        }
+
+        // Rate limits: a replayed nonce is a duplicate, then the address is
+        // out of submissions, then the pool as a whole
+        assert_eq!(submit(request(&alice, model_id, 50, 1)).await.0, 409);
+        let (status, error) = submit(request(&alice, model_id, 50, 2)).await;
+        assert_eq!((status, error["error"].as_str()), (429, Some("rate_limited")));
+        let (status, withheld) = submit(request(&bob, model_id, 50, 1)).await;
+        assert_eq!(status, 200);
+        let (status, error) = submit(request(&bob, model_id, 50, 2)).await;
+        assert_eq!((status, error["message"].as_str()), (429, Some("Anonymous submission pool is saturated")));
+
+        // Completion is moderated in enforce mode under the ephemeral address
+        let complete = |job_id: String, output: &str| {
+            let state = state.clone();
+            let progress: JobProgressRequest = serde_json::from_value(serde_json::json!({
+                "progress": 1.0, "status": "Completed", "output_cid": format!("bafy-answer-{}", output), "output_text": output,
+            }))
+            .unwrap();
+            async move { job_progress(State(state), Path(job_id), Json(progress)).await.unwrap() }
+        };
+        complete(job_id.clone(), "rest and fluids").await;
+        let check = checks.lock().unwrap()[0].clone();
+        assert_eq!((check["tenant"].as_str(), check["enforce"].as_bool()), (Some(address(&alice).as_str()), Some(true)));
+        // Without a verdict an anonymous output is withheld, not released
+        let withheld_id = withheld["job_id"].as_str().unwrap().to_string();
+        complete(withheld_id.clone(), "unjudgeable").await;
+        let withheld_job = state.jobs.read().await[&withheld_id].clone();
+        assert_eq!(withheld_job.status, JobStatus::Failed);
+        assert_eq!(withheld_job.terminal_reason.unwrap().kind, TerminalReasonKind::ModerationBlocked);
+        assert!(state.outputs.read().await.output_for_job(&withheld_id).is_none());
+
+        // Output links go only to a fresh signature from the ephemeral key
+        let outputs_url = serve_outputs(state.outputs.clone()).await;
+        let link_request = |key: &k256::ecdsa::SigningKey, signed_at: u64| serde_json::json!({
+            "requester_did": address(&alice), "ttl_secs": 60, "signed_at": signed_at,
+            "signature": sign(key, anonymous::link_digest(&job_id, signed_at)),
+        });
+        let link_url = format!("{}/job/{}/output/link", outputs_url, job_id);
+        let unsigned = client.post(&link_url).json(&serde_json::json!({ "requester_did": address(&alice) })).send().await.unwrap();
+        assert_eq!(unsigned.status().as_u16(), 401);
+        let stale = client.post(&link_url).json(&link_request(&alice, T0 - 3600)).send().await.unwrap();
+        assert_eq!(stale.status().as_u16(), 401);
+        let by_bob = client.post(&link_url).json(&link_request(&bob, T0)).send().await.unwrap();
+        assert_eq!(by_bob.json::<serde_json::Value>().await.unwrap()["error"], "key_not_proven");
+        let link: serde_json::Value = client.post(&link_url).json(&link_request(&alice, T0)).send().await.unwrap().json().await.unwrap();
+        let download = client.get(link["url"].as_str().unwrap()).header(outputs::DID_HEADER, address(&alice)).send().await.unwrap();
+        assert_eq!(download.text().await.unwrap(), "weights-of-bafy-answer-rest and fluids");
+
+        // No DID anywhere: job records, timelines, the output audit trail,
+        // what reached the chain and the scheduler, and the explorer index
+        sync_search(&state).await.unwrap();
+        let hits = state.search.read().await
+            .search(&SearchRequest { q: job_id.clone(), kind: None, facets: None, limit: None, offset: None }, &Viewer::Internal)
+            .unwrap();
+        assert!(hits.hits.is_empty());
+        let output_id = state.outputs.read().await.output_for_job(&job_id).unwrap().output_id.clone();
+        let audit = state.outputs.read().await.audit_for(&output_id);
+        assert!(audit.iter().all(|entry| entry.did == address(&alice)));
+        let persisted = [
+            serde_json::to_string(&*state.jobs.read().await).unwrap(),
+            serde_json::to_string(&state.events.read().await.for_job(&job_id)).unwrap(),
+            serde_json::to_string(&audit).unwrap(),
+            format!("{:?}", rpc.calls()),
+        ];
+        for record in persisted {
+            assert!(!record.contains("did:"), "{}", record);
+            assert!(!record.contains(&hex::encode("did:")), "{}", record);
+        }
+        assert!(!format!("{:?}", state.anon_limiter.read().await).contains(&job_id));
+
+        // Re-running needs the ephemeral key again
+        let rerun = rerun_job(State(state.clone()), HeaderMap::new(), Path(job_id.clone()), None).await.unwrap_err();
+        assert_eq!(rerun.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_relayed_failures_match_the_originating_service() {
+        // Policy gate: alice gets a structured denial, bob a bare one from an older gate
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |req| {
+            let mut reply = serde_json::json!({ "allowed": false, "reason": "Budget is zero" });
+            if req["did"] == "did:artha:alice" {
+                reply["error"] = serde_json::to_value(ServiceError::policy_denied("Budget is zero")).unwrap();
+            }
+            reply
+        }))
+        .await;
+        let mut state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        Arc::get_mut(&mut state).unwrap().policy_gate = Arc::new(PolicyGate::new(policy_url));
+        let jobd_url = serve(Router::new().route("/job/infer", post(submit_infer_job)).with_state(state.clone())).await;
+
+        let client = reqwest::Client::new();
+        for did in ["did:artha:alice", "did:artha:bob"] {
+            let response = client
+                .post(format!("{}/job/infer", jobd_url))
+                .json(&serde_json::json!({
+                    "model_id": "llama-7b", "inline_input": "hi", "submitter_did": did,
+                    "mode": "batch", "max_tokens": null, "budget": 0,
+                    "input_cid": null, "bucketing_key": null,
+                }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status().as_u16(), 403);
+            let error: ServiceError = response.json().await.unwrap();
+            assert_eq!(error, ServiceError::policy_denied("Budget is zero"));
+        }
+        assert!(state.jobs.read().await.is_empty());
+
+        // Scheduler backpressure reaches the submitter exactly as the scheduler sent it
+        let busy = ServiceError::queue_full(15).with_details(serde_json::json!({ "pending": 2, "high_watermark": 2 }));
+        let sent = busy.clone();
+        let scheduler_url = serve(Router::new().route("/schedule", post(move || {
+            let sent = sent.clone();
+            async move { sent.into_response() }
+        })))
+        .await;
+        let response = notify_scheduler(&scheduler_url, &ScheduleRequest { job_id: "job-1", ..ScheduleRequest::default() }, Deadline::NONE, &artha_clock::SystemClock).await.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("retry-after").unwrap(), "15");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<ServiceError>(&body).unwrap(), busy);
+    }
+
+    #[tokio::test]
+    async fn test_train_seed_recorded_and_forwarded_to_runtime() {
+        let starts: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", Arc::default(), |_| serde_json::json!({}))).await;
+        let runtime_url = serve(
+            recording_route("/capabilities/check", Arc::default(), |_| {
+                serde_json::json!({ "satisfiable": true, "runtime": "torch", "image": "artha/torch-runtime:v1", "unmet": [] })
+            })
+            .merge(recording_route("/job/start", starts.clone(), |_| serde_json::json!({}))),
+        )
+        .await;
+        let rpc = abi::DryRunRpc::spawn().await;
+
+        let mut state = service_state(scheduler_url, runtime_url);
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        // Ids are ABI-encoded as 32-byte words
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        let dataset_id = "dataset-imagenet-1k-train-0000000";
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+            artifacts.register_dataset(dataset_id, "bafy-dataset");
+        }
+        let train = |seed: Option<u64>, nonce: Option<u64>| TrainJobRequest {
+            model_id: model_id.to_string(),
+            dataset_id: dataset_id.to_string(),
+            submitter_did: "did:artha:alice".to_string(),
+            params: TrainParams {
+                epochs: 3,
+                batch_size: 64,
+                learning_rate: 0.001,
+                optimizer: "adam".to_string(),
+                checkpoint_interval: 500,
+                seed,
+            },
+            budget: 1000,
+            tee_required: false,
+            allow_deprecated: false,
+            nonce,
+            milestones: None,
+            live_migration: false,
+            reservation_id: None,
+            secrets: Vec::new(),
+            labels: Labels::new(),
+            force_rerun: false,
+        };
+        let submit = |req: TrainJobRequest| {
+            let state = state.clone();
+            async move { submit_train_job(State(state), HeaderMap::new(), Json(req)).await.map(|(_, Json(response))| response.job_id) }
+        };
+
+        // Same seed and inputs: two jobs locked identically
+        let first = submit(train(Some(42), None)).await.unwrap();
+        let second = submit(train(Some(42), None)).await.unwrap();
+        assert_ne!(first, second);
+        {
+            let jobs = state.jobs.read().await;
+            assert_eq!(jobs[&first].seed, Some(42));
+            assert_eq!(jobs[&second].seed, Some(42));
+            assert_eq!(jobs[&first].params_hash, jobs[&second].params_hash);
+            assert_eq!(jobs[&first].manifest.as_ref().unwrap().params, jobs[&second].manifest.as_ref().unwrap().params);
+        }
+
+        // No seed: one is generated and locked into the manifest
+        let mut unseeded = train(None, None);
+        unseeded.secrets = vec!["WANDB_API_KEY".to_string()];
+        let unseeded = submit(unseeded).await.unwrap();
+        let seed = {
+            let jobs = state.jobs.read().await;
+            let seed = jobs[&unseeded].seed.expect("generated seed");
+            assert_eq!(jobs[&unseeded].manifest.as_ref().unwrap().params["seed"], seed);
+            assert_ne!(jobs[&unseeded].params_hash, jobs[&first].params_hash);
+            seed
+        };
+
+        // A generated seed keeps nonce resubmissions detectable as duplicates
+        submit(train(None, Some(7))).await.unwrap();
+        let duplicate = submit(train(None, Some(7))).await.unwrap_err();
+        assert_eq!(duplicate.into_response().status(), StatusCode::CONFLICT);
+
+        // Surfaced in status and handed to the runtime at launch
+        let Json(status) = get_job_status(State(state.clone()), HeaderMap::new(), Path(unseeded.clone())).await.unwrap();
+        assert_eq!(serde_json::to_value(&status.job).unwrap()["seed"], seed);
+        let assigned = JobAssignedRequest {
+            job_id: unseeded.clone(),
+            assigned_node: "0xnode1aabbccddeeff00112233445566778899".to_string(),
+            runtime: "torch".to_string(),
+            score: None,
+            assign_tx: None,
+        };
+        assert_eq!(job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned)).await, Ok(StatusCode::OK));
+        assert_eq!(starts.lock().unwrap()[0]["seed"], seed);
+        // Along with the names of its vault secrets, never their values
+        assert_eq!(starts.lock().unwrap()[0]["secret_refs"], serde_json::json!(["WANDB_API_KEY"]));
+        assert_eq!(starts.lock().unwrap()[0]["submitter_did"], "did:artha:alice");
+    }
+
+    #[tokio::test]
+    async fn test_submission_estimate_reaches_the_scheduler() {
+        let schedules: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", schedules.clone(), |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        let dataset_id = "dataset-imagenet-1k-train-0000000";
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+            artifacts.register_dataset(dataset_id, "bafy-dataset");
+        }
+        let req: TrainJobRequest = serde_json::from_value(serde_json::json!({
+            "model_id": model_id, "dataset_id": dataset_id, "submitter_did": "did:artha:alice",
+            "params": { "epochs": 20, "batch_size": 32, "learning_rate": 0.001, "optimizer": "adam", "checkpoint_interval": 100 },
+            "budget": 1000,
+        }))
+        .unwrap();
+        let (_, Json(submitted)) = submit_train_job(State(state.clone()), HeaderMap::new(), Json(req)).await.unwrap();
+        assert!(submitted.estimated_duration_secs > 0);
+
+        // The estimate the submitter was quoted is what placement works from
+        let scheduled = schedules.lock().unwrap()[0].clone();
+        assert_eq!(scheduled["job_id"], submitted.job_id);
+        assert_eq!(scheduled["estimated_duration_secs"], submitted.estimated_duration_secs);
+        assert_eq!(state.jobs.read().await[&submitted.job_id].estimated_duration_secs, Some(submitted.estimated_duration_secs));
+
+        // A restored job is re-queued with it too
+        resume_job(&state, &submitted.job_id).await.unwrap();
+        assert_eq!(schedules.lock().unwrap()[1]["estimated_duration_secs"], submitted.estimated_duration_secs);
+
+        // Jobs with no estimate leave it out for the scheduler to predict
+        state.jobs.write().await.insert("job-open".to_string(), queued_job("job-open", model_id));
+        resume_job(&state, "job-open").await.unwrap();
+        assert!(schedules.lock().unwrap()[2].get("estimated_duration_secs").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_live_migration_requeues_away_from_source_and_resumes() {
+        let migrates: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let starts: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let schedules: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let runtime_url = serve(
+            recording_route("/job/:id/migrate", migrates.clone(), |_| serde_json::json!({}))
+                .merge(recording_route("/capabilities/check", Arc::default(), |_| {
+                    serde_json::json!({ "satisfiable": true, "runtime": "torch", "image": "artha/torch-runtime:v1", "unmet": [] })
+                }))
+                .merge(recording_route("/job/start", starts.clone(), |_| serde_json::json!({}))),
+        )
+        .await;
+        let scheduler_url = serve(recording_route("/schedule", schedules.clone(), |_| serde_json::json!({}))).await;
+        let state = service_state(scheduler_url, runtime_url);
+
+        let (source, target) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        {
+            let mut jobs = state.jobs.write().await;
+            for (job_id, opted_in) in [("job-move", true), ("job-stay", false)] {
+                let mut job = queued_job(job_id, "model-1");
+                job.status = JobStatus::Running;
+                job.assigned_node = Some(source.to_string());
+                job.live_migration = opted_in;
+                job.estimated_duration_secs = Some(7200);
+                jobs.insert(job_id.to_string(), job);
+            }
+        }
+        let request = || Json(MigrateRequest {
+            initiated_by: "scheduler:drain".to_string(),
+            reason: "node drained".to_string(),
+            timeout_secs: None,
+        });
+
+        // Only opted-in jobs move, one migration at a time
+        let declined = migrate_job(State(state.clone()), Path("job-stay".to_string()), request()).await;
+        assert_eq!(declined.unwrap_err(), StatusCode::CONFLICT);
+        let (status, Json(record)) = migrate_job(State(state.clone()), Path("job-move".to_string()), request()).await.unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(record.phase, migration::MigrationPhase::Requested);
+        assert_eq!(record.source_node, source);
+        assert_eq!(migrates.lock().unwrap()[0]["initiated_by"], "scheduler:drain");
+        let again = migrate_job(State(state.clone()), Path("job-move".to_string()), request()).await;
+        assert_eq!(again.unwrap_err(), StatusCode::CONFLICT);
+
+        // The export comes back as a migratable completion and is re-queued without the source
+        let progress: JobProgressRequest = serde_json::from_value(serde_json::json!({
+            "progress": 0.4,
+            "epochs_completed": null,
+            "status": null,
+            "output_cid": null,
+            "peak_vram_mb": null,
+            "failure_reason": null,
+            "migratable": {
+                "initiated_by": "scheduler:drain",
+                "reason": "node drained",
+                "mode": "cooperative",
+                "checkpoint": "checkpoint-3.pt",
+                "resume_from": "artha://QmCheckpointFinal",
+                "steps_lost": 0,
+                "requested_at": T0,
+                "exported_at": T0,
+            },
+        }))
+        .unwrap();
+        assert_eq!(job_progress(State(state.clone()), Path("job-move".to_string()), Json(progress)).await, Ok(StatusCode::OK));
+        {
+            let jobs = state.jobs.read().await;
+            assert_eq!(jobs["job-move"].status, JobStatus::Queued);
+            assert!(jobs["job-move"].assigned_node.is_none());
+            assert_eq!(jobs["job-move"].migrations[0].phase, migration::MigrationPhase::Exported);
+        }
+        let scheduled = schedules.lock().unwrap()[0].clone();
+        assert_eq!(scheduled["job_id"], "job-move");
+        assert_eq!(scheduled["exclude_nodes"], serde_json::json!([source]));
+        assert_eq!(scheduled["estimated_duration_secs"], 7200);
+
+        // Placed elsewhere: the runtime resumes from the exported checkpoint
+        let assigned = JobAssignedRequest {
+            job_id: "job-move".to_string(),
+            assigned_node: target.to_string(),
+            runtime: "torch".to_string(),
+            score: None,
+            assign_tx: None,
+        };
+        assert_eq!(job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned)).await, Ok(StatusCode::OK));
+        assert_eq!(starts.lock().unwrap()[0]["resume_from"], "artha://QmCheckpointFinal");
+
+        // One record covers the whole handoff, and status carries it
+        let Json(status) = get_job_status(State(state.clone()), HeaderMap::new(), Path("job-move".to_string())).await.unwrap();
+        assert_eq!(status.job.status, JobStatus::Running);
+        let record = serde_json::to_value(&status.job).unwrap()["migrations"][0].clone();
+        assert_eq!(record["initiated_by"], "scheduler:drain");
+        assert_eq!(record["reason"], "node drained");
+        assert_eq!(record["phase"], "resumed");
+        assert_eq!(record["source_node"], source);
+        assert_eq!(record["target_node"], target);
+        assert_eq!(record["mode"], "cooperative");
+        assert_eq!(record["resume_from"], "artha://QmCheckpointFinal");
+        assert_eq!(record["steps_lost"], 0);
+        assert!(record["requested_at"].is_u64() && record["exported_at"].is_u64() && record["resumed_at"].is_u64());
+        assert!(record["gap_secs"].is_u64());
+        assert!(migration::resume_from(&status.job.migrations).is_none());
+    }
+
+    /// Mock ai-runtime, ai-proofs and receipts answering reads for one job
+    async fn serve_records(runtime: serde_json::Value, telemetry: serde_json::Value, proofs: serde_json::Value, receipts: serde_json::Value) -> String {
+        serve(Router::new()
+            .route("/job/:id/status", get(move || async move { Json(runtime) }))
+            .route("/job/:id/gpu-telemetry", get(move || async move { Json(telemetry) }))
+            .route("/proofs/:id", get(move || async move { Json(proofs) }))
+            .route("/receipts", get(move || async move { Json(receipts) })))
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_timeline_attributes_cost_across_every_phase() {
+        let t = 1_700_000_000;
+        // One GPU, sampled every minute, silent for ten minutes mid-run
+        let buckets: Vec<serde_json::Value> = (t + 60..=t + 3600)
+            .step_by(60)
+            .filter(|b| !(t + 1860..t + 2460).contains(b))
+            .map(|b| serde_json::json!({ "bucket_start": b }))
+            .collect();
+        let url = serve_records(
+            serde_json::json!({ "job_id": "job-timeline", "checkpoint_times": [t + 1240, t + 2440] }),
+            serde_json::json!({ "job_id": "job-timeline", "devices": { "gpu:0": buckets } }),
+            serde_json::json!([
+                { "job_id": "job-timeline", "proof_type": "TrainStep", "step": 100, "digest": "0xa", "timestamp": t + 1300, "submitted": true, "tx_hash": "0x1", "attestation": null },
+                { "job_id": "job-timeline", "proof_type": "TrainComplete", "step": null, "digest": "0xb", "timestamp": t + 3650, "submitted": true, "tx_hash": "0x2", "attestation": null },
+            ]),
+            serde_json::json!({ "items": [{
+                "receipt_id": "rcpt-1", "job_id": "job-timeline", "amount_wei": 5000,
+                "created_at": t + 3700, "settled_at": t + 3900, "finalize_tx": "0xfinal",
+            }], "total": 1, "next_cursor": null }),
+        )
+        .await;
+        let state = Arc::new(AppState {
+            proofs_url: url.clone(),
+            receipts_url: url.clone(),
+            ..Arc::try_unwrap(service_state("http://127.0.0.1:9".to_string(), url)).ok().unwrap()
+        });
+
+        let mut job = queued_job("job-timeline", "model-1");
+        job.status = JobStatus::Completed;
+        job.submitted_at = t;
+        job.started_at = Some(t + 40);
+        job.completed_at = Some(t + 3640);
+        job.spent = 5000;
+        state.jobs.write().await.insert(job.job_id.clone(), job);
+        {
+            let mut events = state.events.write().await;
+            // Other jobs pulled in about a second
+            for i in 0..25 {
+                events.record(&format!("job-{}", i), t, EventKind::ContainerStarted, serde_json::json!({ "warm": false, "pull_ms": 900 + i * 10 }));
+            }
+            events.record("job-timeline", t, EventKind::Submitted, serde_json::Value::Null);
+            events.record("job-timeline", t + 1, EventKind::PolicyDecided, serde_json::json!({ "allowed": true, "latency_ms": 800 }));
+            events.record("job-timeline", t + 1, EventKind::Queued, serde_json::Value::Null);
+            events.record("job-timeline", t + 30, EventKind::Assigned, serde_json::json!({
+                "node": "0xnode1aabbccddeeff00112233445566778899",
+                "score": { "total": 0.8, "cost": 0.6, "price_per_gpu_sec": 1.25 },
+            }));
+            events.record("job-timeline", t + 40, EventKind::ContainerStarted, serde_json::json!({ "warm": false, "pull_ms": 8000, "launch_ms": 2000 }));
+            events.record("job-timeline", t + 1800, EventKind::Progress, serde_json::json!({ "progress": 0.5, "spent": 2000 }));
+            events.record("job-timeline", t + 3640, EventKind::Completed, serde_json::json!({ "spent": 5000 }));
+        }
+
+        let Json(timeline) = get_job_timeline(State(state.clone()), HeaderMap::new(), Path("job-timeline".to_string())).await.unwrap();
+        let kinds: Vec<timeline::PhaseKind> = timeline.phases.iter().map(|p| p.phase).collect();
+        use timeline::PhaseKind::*;
+        assert_eq!(kinds, vec![Policy, Queue, Pull, Startup, Gpu, Gpu, Gpu, Verification, Settlement]);
+        assert!(timeline.phases.iter().all(|p| p.state == timeline::PhaseState::Complete));
+        assert!(timeline.sources.iter().all(|s| s.available));
+
+        // Events from every source, in order
+        assert!(timeline.events.windows(2).all(|w| w[0].at <= w[1].at));
+        for source in ["ai-jobd", "ai-runtime", "ai-proofs", "receipts"] {
+            assert!(timeline.events.iter().any(|e| e.source == source), "no events from {}", source);
+        }
+        assert_eq!(timeline.events.last().unwrap().kind, EventKind::Settled);
+
+        // Durations: 0.8s policy, 29s queued, 8s pull, GPU split at the two checkpoints
+        let phase = |i: usize| &timeline.phases[i];
+        assert_eq!(phase(0).duration_secs, Some(0.8));
+        assert_eq!(phase(1).duration_secs, Some(29.0));
+        assert_eq!(phase(2).duration_secs, Some(8.0));
+        assert_eq!(phase(3).duration_secs, Some(2.0));
+        assert_eq!((4..7).map(|i| phase(i).duration_secs.unwrap()).collect::<Vec<_>>(), vec![1200.0, 1200.0, 1200.0]);
+        assert_eq!(phase(7).duration_secs, Some(60.0));
+        assert_eq!(phase(8).duration_secs, Some(200.0));
+
+        // Queue and pull are free, GPU time at 1.25/s, the rest is verification
+        assert_eq!(timeline.phases.iter().map(|p| p.cost).collect::<Vec<_>>(), vec![0, 0, 0, 0, 1500, 1500, 1500, 500, 0]);
+        assert_eq!(timeline.phases.iter().map(|p| p.cost).sum::<u64>(), 5000);
+        let summary = &timeline.summary;
+        assert_eq!((summary.spent, summary.gpu_cost, summary.verification_cost, summary.unattributed_cost), (5000, 4500, 500, 0));
+        assert_eq!(summary.total_wall_clock_secs, 3900);
+        assert_eq!(summary.billable_secs, 3600.0);
+        assert_eq!(summary.effective_cost_per_gpu_hour, Some(5000.0));
+
+        // The slow pull and the silent stretch of the second interval are flagged
+        assert_eq!(phase(2).anomalies[0].kind, timeline::AnomalyKind::SlowPull);
+        assert!(phase(4).anomalies.is_empty() && phase(6).anomalies.is_empty());
+        assert_eq!(phase(5).anomalies[0].kind, timeline::AnomalyKind::TelemetryGap);
+        assert_eq!((phase(5).anomalies[0].from, phase(5).anomalies[0].to), (Some(t + 1800), Some(t + 2440)));
+        assert_eq!(summary.anomalies, 2);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_marks_an_offline_source_unavailable() {
+        // Recorded live: assignment with the scheduler's score, startup timings from the runtime
+        let records = serve_records(
+            serde_json::json!({ "job_id": "job-degraded", "checkpoint_times": [] }),
+            serde_json::json!({ "job_id": "job-degraded", "devices": {} }),
+            serde_json::json!([]),
+            serde_json::json!({ "items": [], "total": 0, "next_cursor": null }),
+        )
+        .await;
+        let runtime_url = serve(Router::new()
+            .merge(recording_route("/capabilities/check", Arc::default(), |_| {
+                serde_json::json!({ "satisfiable": true, "runtime": "torch", "image": "artha/torch-runtime:v1", "unmet": [] })
+            }))
+            .merge(recording_route("/job/start", Arc::default(), |_| {
+                serde_json::json!({ "status": "started", "startup": { "warm": false, "pull_ms": 1500, "launch_ms": 300 } })
+            }))
+            .fallback(move |uri: axum::http::Uri| {
+                let records = records.clone();
+                async move { axum::response::Redirect::temporary(&format!("{}{}", records, uri)) }
+            }))
+        .await;
+        // Receipts are offline
+        let state = Arc::new(AppState {
+            proofs_url: runtime_url.clone(),
+            ..Arc::try_unwrap(service_state("http://127.0.0.1:9".to_string(), runtime_url)).ok().unwrap()
+        });
+        state.jobs.write().await.insert("job-degraded".to_string(), queued_job("job-degraded", "model-1"));
+
+        let assigned = JobAssignedRequest {
+            job_id: "job-degraded".to_string(),
+            assigned_node: "0xnode1aabbccddeeff00112233445566778899".to_string(),
+            runtime: "torch".to_string(),
+            score: Some(serde_json::json!({ "total": 0.9, "price_per_gpu_sec": 2.0 })),
+            assign_tx: None,
+        };
+        assert_eq!(job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned)).await, Ok(StatusCode::OK));
+        let completed: JobProgressRequest = serde_json::from_value(serde_json::json!({
+            "progress": 1.0,
+            "epochs_completed": 1,
+            "status": "Completed",
+            "output_cid": null,
+            "peak_vram_mb": null,
+            "failure_reason": null,
+            "spent": 700,
+        }))
+        .unwrap();
+        assert_eq!(job_progress(State(state.clone()), Path("job-degraded".to_string()), Json(completed)).await, Ok(StatusCode::OK));
+
+        let Json(timeline) = get_job_timeline(State(state.clone()), HeaderMap::new(), Path("job-degraded".to_string())).await.unwrap();
+        let receipts = timeline.sources.iter().find(|s| s.source == "receipts").unwrap();
+        assert!(!receipts.available && receipts.error.is_some());
+        assert_eq!(timeline.summary.unavailable_sources, vec!["receipts".to_string()]);
+        assert!(timeline.sources.iter().filter(|s| s.source != "receipts").all(|s| s.available));
+
+        // Phases the receipts bound are gaps; everything else is still there
+        let state_of = |kind: timeline::PhaseKind| timeline.phases.iter().find(|p| p.phase == kind).map(|p| p.state);
+        assert_eq!(state_of(timeline::PhaseKind::Verification), Some(timeline::PhaseState::Unavailable));
+        assert_eq!(state_of(timeline::PhaseKind::Settlement), Some(timeline::PhaseState::Unavailable));
+        assert_eq!(state_of(timeline::PhaseKind::Gpu), Some(timeline::PhaseState::Complete));
+        let pull = timeline.phases.iter().find(|p| p.phase == timeline::PhaseKind::Pull).unwrap();
+        assert_eq!(pull.duration_secs, Some(1.5));
+        assert_eq!(timeline.summary.price_per_gpu_sec, Some(2.0));
+        assert!(timeline.events.iter().any(|e| e.kind == EventKind::Progress && e.detail["spent"] == 700));
+
+        // The spend still adds up
+        assert_eq!(timeline.summary.spent, 700);
+        assert_eq!(timeline.phases.iter().map(|p| p.cost).sum::<u64>() + timeline.summary.unattributed_cost, 700);
+
+        // Unknown jobs are 404s, not empty timelines
+        let missing = get_job_timeline(State(state), HeaderMap::new(), Path("job-nope".to_string())).await;
+        assert_eq!(missing.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_infer_input_checked_against_model_schema() {
+        let schedules: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", schedules.clone(), |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        let tensor = |name: &str, dtype: &str, shape: &[Option<u64>]| TensorSpec {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+            shape: shape.to_vec(),
+        };
+        let schema = ModelSchema {
+            inputs: vec![tensor("pixels", "float32", &[None, Some(3), Some(2), Some(2)])],
+            outputs: vec![tensor("logits", "float32", &[None, Some(10)])],
+        };
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+            artifacts.set_model_schema(model_id, schema.clone());
+        }
+        let infer = |inline: Option<serde_json::Value>, meta: Option<Vec<TensorSpec>>| InferJobRequest {
+            model_id: "resnet@latest".to_string(),
+            input_cid: meta.is_some().then(|| "artha://QmImageBatch".to_string()),
+            inline_input: inline.map(|v| v.to_string()),
+            input_meta: meta,
+            submitter_did: "did:artha:alice".to_string(),
+            mode: "batch".to_string(),
+            max_tokens: None,
+            budget: 100,
+            tee_required: false,
+            bucketing_key: None,
+            allow_deprecated: false,
+            nonce: None,
+            anonymous: false,
+            ephemeral_address: None,
+            ephemeral_signature: None,
+            labels: Labels::new(),
+            force_rerun: false,
+        };
+
+        // Conforming: integers are fine for float pixels, any batch size
+        let image = serde_json::json!([[[0, 1], [2, 3]], [[4, 5], [6, 7]], [[8, 9], [10, 11]]]);
+        let images = serde_json::json!([image.clone(), image]);
+        let artifacts = state.artifacts.read().await;
+        assert!(check_input_schema(&artifacts, "resnet@latest", &infer(Some(images), None)).is_ok());
+        let keyed = serde_json::json!({ "pixels": [[[[0.5, 0.5], [0.5, 0.5]], [[0.5, 0.5], [0.5, 0.5]], [[0.5, 0.5], [0.5, 0.5]]]] });
+        assert!(check_input_schema(&artifacts, model_id, &infer(Some(keyed), None)).is_ok());
+        let stored = vec![tensor("pixels", "float32", &[Some(64), Some(3), Some(2), Some(2)])];
+        assert!(check_input_schema(&artifacts, model_id, &infer(None, Some(stored))).is_ok());
+        let wrong_dtype = vec![tensor("pixels", "string", &[Some(64), Some(3), Some(2), Some(2)])];
+        let error = check_input_schema(&artifacts, model_id, &infer(None, Some(wrong_dtype))).unwrap_err();
+        assert!(error.message.contains("is string, expected float32"), "{}", error.message);
+        drop(artifacts);
+
+        // Shape mismatch: refused with the offending tensor named, before the
+        // chain or the scheduler hear of it
+        let one_channel = serde_json::json!([[[[0.1, 0.2], [0.3, 0.4]]]]);
+        let response = submit_infer_job(State(state.clone()), Json(infer(Some(one_channel), None)))
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ServiceError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.kind(), Some(ErrorCode::Unprocessable));
+        assert!(error.message.contains("Input 'pixels' has shape [1, 1, 2, 2], expected [?, 3, 2, 2]"), "{}", error.message);
+        assert!(schedules.lock().unwrap().is_empty());
+        assert!(rpc.calls().is_empty());
+        assert!(state.jobs.read().await.is_empty());
+
+        // Ragged tensors never pass for a shape
+        let ragged = serde_json::json!({ "pixels": [[1.0, 2.0], [3.0]] });
+        assert!(schema.describe_inline(&ragged.to_string()).unwrap_err().contains("not a rectangular tensor"));
+    }
+
+    #[tokio::test]
+    async fn test_policy_outage_decided_by_the_configured_fail_mode() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        let down = Arc::new(AtomicBool::new(false));
+        let gate_down = down.clone();
+        let policy_url = serve(Router::new().route("/policy/check", post(move |Json(req): Json<serde_json::Value>| {
+            let down = gate_down.load(Ordering::SeqCst);
+            async move {
+                if down {
+                    return Err(StatusCode::SERVICE_UNAVAILABLE);
+                }
+                let allowed = req["did"] != "did:artha:mallory";
+                Ok(Json(serde_json::json!({ "allowed": allowed, "reason": (!allowed).then_some("Not a member") })))
+            }
+        })))
+        .await;
+        let clock = ManualClock::new(T0);
+        let gate = |mode: FailMode| {
+            PolicyGate::new(policy_url.clone())
+                .with_outage(OutageConfig { mode, ..OutageConfig::default() })
+                .with_clock(clock.shared())
+        };
+        async fn check(gate: &PolicyGate, did: &str, action: &str) -> Result<Option<DegradedPolicy>, ServiceError> {
+            gate.enforce(did, action, "model-1", None, &[], 100).await.map(|d| d.degraded)
+        }
+        let unavailable = Err(ServiceError::policy_denied("Policy check unavailable"));
+
+        // Fail closed: nothing gets through an outage
+        let closed = gate(FailMode::FailClosed);
+        assert_eq!(check(&closed, "did:artha:alice", "infer").await, Ok(None));
+        down.store(true, Ordering::SeqCst);
+        assert_eq!(check(&closed, "did:artha:alice", "infer").await, unavailable);
+
+        // Fail open for read: inference only, and marked as such
+        let open = gate(FailMode::FailOpenForRead);
+        let degraded = check(&open, "did:artha:mallory", "infer").await.unwrap().unwrap();
+        assert_eq!(serde_json::to_value(&degraded).unwrap(), serde_json::json!({ "mode": "fail-open-for-read" }));
+        assert_eq!(check(&open, "did:artha:alice", "agent").await, unavailable);
+        assert_eq!(check(&open, "did:artha:alice", "train").await, unavailable);
+
+        // Cached last decision: the gate's recent answers are replayed, denials included
+        let cached = gate(FailMode::CachedLastDecision);
+        down.store(false, Ordering::SeqCst);
+        for (did, action) in [("did:artha:alice", "infer"), ("did:artha:alice", "agent"), ("did:artha:alice", "train"), ("did:artha:mallory", "infer")] {
+            let _ = check(&cached, did, action).await;
+        }
+        down.store(true, Ordering::SeqCst);
+        clock.advance_secs(60);
+        let replayed = check(&cached, "did:artha:alice", "agent").await.unwrap().unwrap();
+        assert_eq!((replayed.mode, replayed.decided_at), (FailMode::CachedLastDecision, Some(T0)));
+        assert!(check(&cached, "did:artha:alice", "infer").await.unwrap().is_some());
+        assert_eq!(check(&cached, "did:artha:mallory", "infer").await, Err(ServiceError::policy_denied("Not a member")));
+        assert!(check(&cached, "did:artha:bob", "infer").await.unwrap_err().message.contains("no recent decision"));
+        // Critical actions fail closed even with an allow cached
+        assert_eq!(check(&cached, "did:artha:alice", "train").await, unavailable);
+        // And a decision only lasts its TTL
+        clock.advance_secs(OutageConfig::default().cache_ttl_secs);
+        assert!(check(&cached, "did:artha:alice", "agent").await.is_err());
+
+        // An admitted submission says how it was admitted
+        let scheduler_url = serve(recording_route("/schedule", Arc::default(), |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(gate(FailMode::FailOpenForRead));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        state.artifacts.write().await.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+        let infer: InferJobRequest = serde_json::from_value(serde_json::json!({
+            "model_id": model_id, "input_cid": "artha://QmImageBatch", "inline_input": null,
+            "submitter_did": "did:artha:alice", "mode": "batch", "max_tokens": null, "budget": 100,
+            "bucketing_key": null,
+        }))
+        .unwrap();
+        let (_, Json(response)) = submit_infer_job(State(state.clone()), Json(infer)).await.unwrap();
+        assert_eq!(serde_json::to_value(&response).unwrap()["policy_degraded"], serde_json::json!({ "mode": "fail-open-for-read" }));
+    }
+
+    // Ids and hashes are ABI-encoded as 32-byte words
+    const WF_MODEL: &str = "model-resnet50-imagenet-v1-000000";
+    const WF_DATASET: &str = "dataset-imagenet-1k-train-0000000";
+    const WF_EVAL_SPEC: &str = "artha://QmEvalHarnessSpec00000000000000";
+
+    /// A daemon whose policy gate allows everything, with a dry-run chain
+    async fn workflow_state() -> (Arc<AppState>, abi::DryRunRpc) {
+        gated_workflow_state(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await
+    }
+
+    /// `workflow_state` behind the policy gate `policy`
+    async fn gated_workflow_state(policy: Router) -> (Arc<AppState>, abi::DryRunRpc) {
+        let policy_url = serve(policy).await;
+        let scheduler_url = serve(recording_route("/schedule", Arc::default(), |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, WF_MODEL, "bafy-model", Some("resnet"), "1.0");
+            artifacts.register_dataset(WF_DATASET, "bafy-dataset");
+        }
+        (state, rpc)
+    }
+
+    fn train_step(name: &str, budget: u64) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "action": "train",
+            "budget": budget,
+            "inputs": {
+                "model_id": WF_MODEL,
+                "dataset_id": WF_DATASET,
+                "params": { "epochs": 3, "batch_size": 64, "learning_rate": 0.001, "optimizer": "adam", "checkpoint_interval": 500 },
+            },
+        })
+    }
+
+    fn eval_step() -> serde_json::Value {
+        serde_json::json!({
+            "name": "eval",
+            "action": "agent",
+            "budget": 100,
+            "inputs": { "agent_spec_cid": WF_EVAL_SPEC, "goal": "Evaluate ${steps.train.output}", "tools": [], "memory_policy": "none" },
+        })
+    }
+
+    async fn start_workflow(state: &Arc<AppState>, spec: serde_json::Value) -> WorkflowView {
+        let body = axum::body::Bytes::from(serde_json::to_vec(&spec).unwrap());
+        let Json(view) = submit_workflow(State(state.clone()), HeaderMap::new(), body).await.unwrap();
+        view
+    }
+
+    async fn workflow(state: &Arc<AppState>, workflow_id: &str) -> Workflow {
+        state.workflows.read().await.get(workflow_id).unwrap().clone()
+    }
+
+    fn step_runs(workflow: &Workflow, name: &str) -> Vec<workflow::StepRun> {
+        workflow.steps.iter().find(|s| s.name == name).unwrap().runs.clone()
+    }
+
+    fn step_status(workflow: &Workflow, name: &str) -> StepStatus {
+        workflow.steps.iter().find(|s| s.name == name).unwrap().status
+    }
+
+    /// Report a job finished as ai-runtime would
+    async fn finish_job(state: &Arc<AppState>, job_id: &str, status: &str, report: serde_json::Value) {
+        let mut progress = serde_json::json!({ "progress": 1.0, "epochs_completed": null, "status": status, "output_cid": null, "peak_vram_mb": null, "failure_reason": null });
+        progress.as_object_mut().unwrap().extend(report.as_object().unwrap().clone());
+        let req: JobProgressRequest = serde_json::from_value(progress).unwrap();
+        assert_eq!(job_progress(State(state.clone()), Path(job_id.to_string()), Json(req)).await, Ok(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn test_workflow_registers_and_deploys_only_when_eval_passes() {
+        let (state, _rpc) = workflow_state().await;
+        let spec = format!(
+            r#"
+name: resnet-release
+submitter_did: did:artha:alice
+budget: 1000
+steps:
+  - {train}
+  - {eval}
+  - name: register
+    action: register_model
+    when: steps.eval.metrics.accuracy > 0.92 && steps.eval.status == "succeeded"
+    inputs:
+      model_cid: "${{steps.train.output}}"
+      architecture: resnet50-imagenet-classifier-arch
+      dataset_id: {dataset}
+      code_hash: "0x5f1c2e9a7b3d4c6e8f0a1b2c3d4e5f60"
+      version: 2.0.0-resnet50-imagenet-release-0
+      name: resnet
+  - name: deploy
+    action: update_alias
+    inputs: {{ name: resnet, alias: production, model_id: "${{steps.register.model_id}}" }}
+"#,
+            train = train_step("train", 300),
+            eval = eval_step(),
+            dataset = WF_DATASET,
+        );
+        let mut yaml = HeaderMap::new();
+        yaml.insert("content-type", HeaderValue::from_static("application/yaml"));
+        let submit = |headers: HeaderMap, spec: String| submit_workflow(State(state.clone()), headers, axum::body::Bytes::from(spec));
+
+        // Only the root step starts; the DAG shows why each step waits
+        let Json(view) = submit(yaml.clone(), spec.clone()).await.unwrap();
+        let workflow_id = view.workflow.workflow_id.clone();
+        assert_eq!(view.workflow.status, WorkflowStatus::Running);
+        assert_eq!(view.budget.remaining, 700);
+        assert!(view.workflow.edges.contains(&workflow::Edge { from: "eval".to_string(), to: "register".to_string(), kind: workflow::EdgeKind::Condition }));
+        assert!(view.workflow.edges.contains(&workflow::Edge { from: "train".to_string(), to: "register".to_string(), kind: workflow::EdgeKind::Data }));
+        assert_eq!(state.jobs.read().await.len(), 1);
+
+        // Train's output is bound by name into the eval job
+        let output = "artha://QmTrainedResnetWeights000000000000";
+        let train_job = step_runs(&workflow(&state, &workflow_id).await, "train")[0].job_id.clone().unwrap();
+        finish_job(&state, &train_job, "Completed", serde_json::json!({ "output_cid": output, "spent": 250 })).await;
+        let eval_job = step_runs(&workflow(&state, &workflow_id).await, "eval")[0].job_id.clone().unwrap();
+        assert_eq!(state.jobs.read().await[&eval_job].params_hash, compute_hash(&format!("Evaluate {}", output)));
+
+        // Accuracy clears the bar: registered from the trained weights and promoted
+        finish_job(&state, &eval_job, "Completed", serde_json::json!({ "metrics": { "accuracy": 0.95 }, "spent": 80 })).await;
+        let done = workflow(&state, &workflow_id).await;
+        assert_eq!(done.status, WorkflowStatus::Succeeded);
+        let model_id = step_runs(&done, "register")[0].result["model_id"].as_str().unwrap().to_string();
+        assert_eq!(state.artifacts.read().await.resolve_model(&Namespace::of("did:artha:alice"), "resnet@production").unwrap().0, model_id);
+        assert_eq!(done.view().budget.committed, 330);
+
+        // The same pipeline whose model falls short registers and deploys nothing
+        let Json(view) = submit(yaml, spec).await.unwrap();
+        let workflow_id = view.workflow.workflow_id.clone();
+        let train_job = step_runs(&view.workflow, "train")[0].job_id.clone().unwrap();
+        finish_job(&state, &train_job, "Completed", serde_json::json!({ "output_cid": output })).await;
+        let eval_job = step_runs(&workflow(&state, &workflow_id).await, "eval")[0].job_id.clone().unwrap();
+        finish_job(&state, &eval_job, "Completed", serde_json::json!({ "metrics": { "accuracy": 0.80 } })).await;
+        let done = workflow(&state, &workflow_id).await;
+        assert_eq!(done.status, WorkflowStatus::Succeeded);
+        assert_eq!(step_status(&done, "register"), StepStatus::Skipped);
+        assert_eq!(step_status(&done, "deploy"), StepStatus::Skipped);
+        assert_eq!(state.artifacts.read().await.resolve_model(&Namespace::of("did:artha:alice"), "resnet@production").unwrap().0, model_id);
+
+        // Conditions are data, not code
+        for bad in ["std::process::exit(1)", "steps.eval.metrics.accuracy >", "env.HOME == 'x'"] {
+            let mut spec = serde_json::json!({ "name": "bad", "submitter_did": "did:artha:alice", "budget": 1000, "steps": [train_step("train", 100), eval_step()] });
+            spec["steps"][1]["when"] = serde_json::json!(bad);
+            let error = submit(HeaderMap::new(), spec.to_string()).await.unwrap_err();
+            assert_eq!(error.kind(), Some(ErrorCode::InvalidRequest), "{}", bad);
+        }
+        let condition = expr::Expr::parse("!(steps.a.metrics.loss * 2 >= 1) || steps.a.output == 'x'").unwrap();
+        assert!(condition.eval_bool(&serde_json::json!({ "steps": { "a": { "metrics": { "loss": 0.3 } } } })).unwrap());
+        assert!(expr::Expr::parse("steps.a.output > 1").unwrap().eval_bool(&serde_json::json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_workflow_fan_out_respects_concurrency_limit() {
+        let (state, _rpc) = workflow_state().await;
+        let mut grid = train_step("grid", 100);
+        grid["matrix"] = serde_json::json!({ "learning_rate": [0.1, 0.01, 0.001], "batch_size": [32, 64] });
+        grid["max_parallel"] = serde_json::json!(2);
+        grid["inputs"]["params"]["learning_rate"] = serde_json::json!("${matrix.learning_rate}");
+        grid["inputs"]["params"]["batch_size"] = serde_json::json!("${matrix.batch_size}");
+        let view = start_workflow(&state, serde_json::json!({ "name": "sweep", "submitter_did": "did:artha:alice", "budget": 1000, "steps": [grid] })).await;
+        let workflow_id = view.workflow.workflow_id.clone();
+        assert_eq!(step_runs(&view.workflow, "grid").len(), 6);
+
+        let mut finished = 0;
+        loop {
+            let current = workflow(&state, &workflow_id).await;
+            let runs = step_runs(&current, "grid");
+            let running: Vec<String> = runs.iter().filter(|r| r.status == StepStatus::Running).filter_map(|r| r.job_id.clone()).collect();
+            assert!(running.len() <= 2, "{} runs in flight", running.len());
+            assert_eq!(state.jobs.read().await.len(), finished + running.len());
+            assert!(current.view().budget.committed <= 200 + finished as u64 * 100);
+            let Some(job_id) = running.first() else { break };
+            assert_eq!(running.len(), 2.min(6 - finished));
+            finish_job(&state, job_id, "Completed", serde_json::json!({ "output_cid": format!("artha://QmSweep{}", finished), "spent": 100 })).await;
+            finished += 1;
+        }
+        assert_eq!(finished, 6);
+
+        // Every combination trained once, with its own params
+        let done = workflow(&state, &workflow_id).await;
+        assert_eq!(done.status, WorkflowStatus::Succeeded);
+        let jobs = state.jobs.read().await;
+        let mut combos: Vec<String> = jobs
+            .values()
+            .map(|j| {
+                let params = &j.manifest.as_ref().unwrap().params;
+                format!("{}/{}", params["learning_rate"], params["batch_size"])
+            })
+            .collect();
+        combos.sort();
+        combos.dedup();
+        assert_eq!(combos.len(), 6);
+        assert!(step_runs(&done, "grid").iter().all(|r| r.output.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_workflow_resumes_from_failed_step() {
+        let (state, _rpc) = workflow_state().await;
+        let view = start_workflow(&state, serde_json::json!({
+            "name": "retrain",
+            "submitter_did": "did:artha:alice",
+            "budget": 1000,
+            "steps": [
+                train_step("train", 300),
+                eval_step(),
+                { "name": "gate", "action": "update_alias", "after": ["eval"],
+                  "inputs": { "name": "resnet", "alias": "candidate", "model_id": WF_MODEL } },
+            ],
+        })).await;
+        let workflow_id = view.workflow.workflow_id.clone();
+        let train_job = step_runs(&view.workflow, "train")[0].job_id.clone().unwrap();
+        finish_job(&state, &train_job, "Completed", serde_json::json!({ "output_cid": "artha://QmTrained", "spent": 300 })).await;
+
+        let eval_job = step_runs(&workflow(&state, &workflow_id).await, "eval")[0].job_id.clone().unwrap();
+        finish_job(&state, &eval_job, "Failed", serde_json::json!({ "failure_reason": "node lost", "spent": 40 })).await;
+        let failed = workflow(&state, &workflow_id).await;
+        assert_eq!(failed.status, WorkflowStatus::Failed);
+        assert!(failed.error.as_deref().unwrap().starts_with("Step eval failed"));
+        assert_eq!(step_status(&failed, "gate"), StepStatus::Pending);
+
+        // Only failed steps can be retried
+        let Err(error) = retry_workflow_step(State(state.clone()), Path((workflow_id.clone(), "train".to_string()))).await else {
+            panic!("retried a succeeded step");
         };
-        
-        let hash = compute_params_hash(&params);
-        assert!(hash.starts_with("0x"));
-        assert_eq!(hash.len(), 66); // 0x + 64 hex chars
+        assert_eq!(error.kind(), Some(ErrorCode::Conflict));
+
+        let Json(view) = retry_workflow_step(State(state.clone()), Path((workflow_id.clone(), "eval".to_string()))).await.unwrap();
+        assert_eq!(view.workflow.status, WorkflowStatus::Running);
+        let retried_job = step_runs(&view.workflow, "eval")[0].job_id.clone().unwrap();
+        assert_ne!(retried_job, eval_job);
+        assert_eq!(view.workflow.steps[1].attempts, 2);
+        // The failed attempt's spend still counts against the budget
+        assert_eq!(view.budget.committed, 300 + 40 + 100);
+
+        finish_job(&state, &retried_job, "Completed", serde_json::json!({ "spent": 60 })).await;
+        let done = workflow(&state, &workflow_id).await;
+        assert_eq!(done.status, WorkflowStatus::Succeeded);
+        assert_eq!(step_runs(&done, "train")[0].job_id.as_deref(), Some(train_job.as_str()));
+        assert_eq!(done.steps[0].attempts, 1);
+        let jobs = state.jobs.read().await;
+        assert_eq!(jobs.values().filter(|j| matches!(j.job_type, JobType::Train)).count(), 1);
+        assert_eq!(jobs.len(), 3);
+        drop(jobs);
+        assert_eq!(state.artifacts.read().await.resolve_model(&Namespace::of("did:artha:alice"), "resnet@candidate").unwrap().0, WF_MODEL);
+
+        // Finished workflows have nothing left to cancel
+        assert_eq!(cancel_workflow(State(state.clone()), Path(workflow_id)).await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_budget_shared_across_steps() {
+        let (state, _rpc) = workflow_state().await;
+        let mut second = train_step("second", 400);
+        second["after"] = serde_json::json!(["first"]);
+        let mut third = train_step("third", 400);
+        third["after"] = serde_json::json!(["second"]);
+        let spec = serde_json::json!({ "name": "budgeted", "submitter_did": "did:artha:alice", "budget": 1000, "steps": [train_step("first", 400), second, third] });
+
+        // A step can never ask for more than the whole workflow has
+        let mut greedy = spec.clone();
+        greedy["steps"][2]["budget"] = serde_json::json!(1500);
+        assert!(Workflow::new(serde_json::from_value(greedy).unwrap(), T0, &SeededEntropy::new(1)).unwrap_err().contains("needs a budget"));
+
+        let view = start_workflow(&state, spec.clone()).await;
+        let workflow_id = view.workflow.workflow_id.clone();
+        let first = step_runs(&view.workflow, "first")[0].job_id.clone().unwrap();
+        finish_job(&state, &first, "Completed", serde_json::json!({ "spent": 400 })).await;
+        let second = step_runs(&workflow(&state, &workflow_id).await, "second")[0].job_id.clone().unwrap();
+        assert_eq!(state.jobs.read().await[&second].budget, 400);
+        finish_job(&state, &second, "Completed", serde_json::json!({ "spent": 300 })).await;
+
+        // 300 left is not enough for the third step's 400: refused before submission
+        let stopped = workflow(&state, &workflow_id).await;
+        assert_eq!(stopped.status, WorkflowStatus::Failed);
+        let third = &step_runs(&stopped, "third")[0];
+        assert!(third.job_id.is_none());
+        assert_eq!(third.error.as_deref(), Some("Workflow budget exhausted: step needs 400, 300 of 1000 left"));
+        assert_eq!(state.jobs.read().await.len(), 2);
+        assert_eq!(stopped.view().budget.remaining, 300);
+
+        // Retrying doesn't conjure budget
+        let Json(retried) = retry_workflow_step(State(state.clone()), Path((workflow_id.clone(), "third".to_string()))).await.unwrap();
+        assert_eq!(retried.workflow.status, WorkflowStatus::Failed);
+        assert_eq!(state.jobs.read().await.len(), 2);
+
+        // Cancelling a running workflow cancels its job and releases its hold
+        let view = start_workflow(&state, spec).await;
+        let running = step_runs(&view.workflow, "first")[0].job_id.clone().unwrap();
+        assert_eq!(view.budget.committed, 400);
+        let Json(cancelled) = cancel_workflow(State(state.clone()), Path(view.workflow.workflow_id.clone())).await.unwrap();
+        assert_eq!(cancelled.workflow.status, WorkflowStatus::Cancelled);
+        assert_eq!(cancelled.budget.committed, 0);
+        assert!(cancelled.workflow.steps.iter().all(|s| s.status == StepStatus::Cancelled));
+        assert_eq!(state.jobs.read().await[&running].status, JobStatus::Cancelled);
+        assert_eq!(state.jobs.read().await.len(), 3);
+    }
+
+
+    #[tokio::test]
+    async fn test_stream_job_hands_spec_to_runtime_and_lists_window_versions() {
+        let streams: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", Arc::default(), |_| serde_json::json!({}))).await;
+        let runtime_url = serve(recording_route("/job/stream", streams.clone(), |body| {
+            serde_json::json!({ "job_id": body["job_id"], "status": "Running" })
+        }))
+        .await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, runtime_url);
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        let request = |window: serde_json::Value| -> StreamJobRequest {
+            serde_json::from_value(serde_json::json!({
+                "submitter_did": "did:artha:alice",
+                "input_stream": "http://clicks.stream.local",
+                "transform": { "kind": "wasm", "module_cid": "artha://QmClickFeatureExtractorWasm000000" },
+                "window": window,
+                "output": { "dataset": "clicks/{job_id}", "license_cid": "artha://QmLicenseCcBy40000000000000000000" },
+                "budget": 500,
+            }))
+            .unwrap()
+        };
+
+        // Impossible windows are refused before anything is queued
+        let bad = submit_stream_job(State(state.clone()), Json(request(serde_json::json!({ "kind": "sliding", "by": "time", "size": 1000, "slide": 2000 }))))
+            .await
+            .unwrap_err();
+        assert_eq!(bad.into_response().status(), StatusCode::BAD_REQUEST);
+        assert!(state.jobs.read().await.is_empty());
+
+        let Json(submitted) = submit_stream_job(State(state.clone()), Json(request(serde_json::json!({ "kind": "tumbling", "by": "count", "size": 100 }))))
+            .await
+            .unwrap();
+        let job_id = submitted.job_id;
+        assert!(matches!(state.jobs.read().await[&job_id].job_type, JobType::Stream));
+
+        // On assignment the spec goes to the runtime's stream endpoint, not a container start
+        let assigned = JobAssignedRequest {
+            job_id: job_id.clone(),
+            assigned_node: "0xnode1aabbccddeeff00112233445566778899".to_string(),
+            runtime: "torch".to_string(),
+            score: None,
+            assign_tx: None,
+        };
+        assert_eq!(job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned)).await, Ok(StatusCode::OK));
+        let started = streams.lock().unwrap()[0].clone();
+        assert_eq!(started["job_id"], job_id);
+        assert_eq!(started["window"], serde_json::json!({ "kind": "tumbling", "by": "count", "size": 100 }));
+        assert_eq!(started["transform"]["gas_limit"], 10_000_000);
+        assert_eq!(state.jobs.read().await[&job_id].status, JobStatus::Running);
+
+        // Windows register as versions; a retried registration keeps its id
+        let name = format!("clicks/{}", job_id);
+        let window = |k: u64, root: &str| -> DatasetRegisterRequest {
+            serde_json::from_value(serde_json::json!({
+                "root_cid": root,
+                "license_cid": "artha://QmLicenseCcBy40000000000000000000",
+                "tags": ["stream", format!("window:count:{}-{}", k * 100, k * 100 + 100)],
+                "name": name,
+                "version": format!("w{}", k),
+                "samples": 100,
+                "window": { "by": "count", "start": k * 100, "end": k * 100 + 100 },
+            }))
+            .unwrap()
+        };
+        let register = |req: DatasetRegisterRequest| register_dataset(State(state.clone()), HeaderMap::new(), Json(req));
+        let Json(w0) = register(window(0, "artha://QmWindowZeroManifest0000000000000")).await.unwrap();
+        let Json(retried) = register(window(0, "artha://QmWindowZeroManifest0000000000000")).await.unwrap();
+        assert_eq!(retried.dataset_id, w0.dataset_id);
+        assert_eq!(register(window(0, "artha://QmSomeOtherManifest00000000000000")).await.unwrap_err(), StatusCode::CONFLICT);
+        let Json(w1) = register(window(1, "artha://QmWindowOneManifest00000000000000")).await.unwrap();
+        assert_ne!(w1.dataset_id, w0.dataset_id);
+
+        // Listed by name prefix in window order, with their bounds
+        let list = |prefix: &str| {
+            let query = HashMap::from([("prefix".to_string(), prefix.to_string())]);
+            list_datasets(State(state.clone()), HeaderMap::new(), Query(query))
+        };
+        let Json(versions) = list("clicks/").await.unwrap();
+        assert_eq!(versions.iter().map(|v| v["dataset_id"].clone()).collect::<Vec<_>>(), vec![serde_json::json!(w0.dataset_id), serde_json::json!(w1.dataset_id)]);
+        assert_eq!(versions[1]["window"], serde_json::json!({ "by": "count", "start": 100, "end": 200 }));
+        assert_eq!(versions[1]["samples"], 100);
+        assert!(list("images/").await.unwrap().0.is_empty());
+        assert_eq!(state.artifacts.read().await.resolve_dataset(&w1.dataset_id), "artha://QmWindowOneManifest00000000000000");
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_output_link_flows_keep_secrets_out_of_logs() {
+        const BEARER: &str = "eyJhbGciOiJFUzI1NiJ9.c3VibWl0dGVyLXNlc3Npb24.bGVha2VkLWlmLXNlZW4";
+        const REQUEST_SIGNATURE: &str = "3045022100c0ffee0000aa11bb22cc33dd44ee55ff";
+        const INTERNAL_TOKEN: &str = "internal-token-never-logged";
+        const MANIFEST_KEY: &str = "manifest-key-never-logged";
+        const LINK_KEY: &str = "output-link-key-never-logged";
+
+        let logs = artha_log::capture();
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", Arc::default(), |_| serde_json::json!({}))).await;
+        let svdb_url = serve(Router::new().route("/svdb/download/:cid", get(|| async { "weights" }))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+            state.manifest_key = MANIFEST_KEY.to_string().into();
+            state.internal_token = INTERNAL_TOKEN.to_string().into();
+            state.outputs = Arc::new(RwLock::new(OutputVault::new(LINK_KEY.as_bytes())));
+        }
+        let url = serve(app(state.clone(), OutputState {
+            vault: state.outputs.clone(),
+            svdb_url,
+            public_url: "http://jobd.local/v1".to_string(),
+            internal_token: INTERNAL_TOKEN.to_string().into(),
+            max_link_ttl_secs: 600,
+            clock: state.clock.clone(),
+        }))
+        .await;
+        let client = reqwest::Client::new();
+
+        // Signed submission
+        let submitted = client
+            .post(format!("{}/v1/job/stream", url))
+            .bearer_auth(BEARER)
+            .header("x-artha-did", "did:artha:alice")
+            .header("x-artha-signature", REQUEST_SIGNATURE)
+            .json(&serde_json::json!({
+                "submitter_did": "did:artha:alice",
+                "input_stream": "http://clicks.stream.local",
+                "transform": { "kind": "wasm", "module_cid": "artha://QmClickFeatureExtractorWasm000000" },
+                "window": { "kind": "tumbling", "by": "count", "size": 100 },
+                "output": { "dataset": "clicks/{job_id}", "license_cid": "artha://QmLicenseCcBy40000000000000000000" },
+                "budget": 500,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(submitted.status().as_u16(), 200);
+
+        // Signed download link, issued and redeemed
+        state.outputs.write().await.register("job-out", "bafy-out", "did:artha:alice", T0, &*state.entropy);
+        let link: serde_json::Value = client
+            .post(format!("{}/v1/job/job-out/output/link", url))
+            .json(&serde_json::json!({ "requester_did": "did:artha:alice" }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        let download_url = link["url"].as_str().unwrap().replace("http://jobd.local", &url);
+        let link_sig = download_url.rsplit("sig=").next().unwrap().to_string();
+        let download = client.get(&download_url).header(outputs::DID_HEADER, "did:artha:alice").send().await.unwrap();
+        assert_eq!(download.status().as_u16(), 200);
+        let internal = client
+            .get(format!("{}/v1/internal/job/job-out/output", url))
+            .header(outputs::INTERNAL_TOKEN_HEADER, INTERNAL_TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(internal.status().as_u16(), 200);
+
+        // Dumping the state that holds them is as safe as logging it
+        let mut vault = state.outputs.write().await;
+        let reissued = vault.issue_link("job-out", "did:artha:alice", 60, T0 + 1).unwrap();
+        let dumped = format!("vault {:?} link {:?}", *vault, reissued);
+        assert!(!dumped.contains(reissued.sig.expose()) && !dumped.contains(&format!("{:?}", LINK_KEY.as_bytes())), "{}", dumped);
+        tracing::debug!("{}", dumped);
+        let secrets = [BEARER, REQUEST_SIGNATURE, INTERNAL_TOKEN, MANIFEST_KEY, LINK_KEY, &link_sig, reissued.sig.expose()];
+        assert_eq!(logs.leaked(&secrets), Vec::<&str>::new(), "{}", logs.lines().join("\n"));
+
+        // The redacted lines still say what happened
+        let text = logs.lines().join("\n");
+        assert!(text.contains("uri=/v1/job/stream status=200"), "{}", text);
+        assert!(text.contains("authorization: [REDACTED]") && text.contains("x-artha-did: did:artha:alice"), "{}", text);
+        assert!(text.contains("expires_at=") && text.contains("sig=[REDACTED]"), "{}", text);
+    }
+
+    /// Policy gate that finds `0xexpired` among a dataset's consent VCs and
+    /// accepts any other
+    fn consent_gate(body: &serde_json::Value) -> serde_json::Value {
+        let reason = "Consent VC 0xexpired expired at 1000000";
+        let verified = !body["consent_vcs"].as_array().unwrap().iter().any(|vc| vc == "0xexpired");
+        let provenance = serde_json::json!({
+            "dataset_id": body["dataset_id"],
+            "intended_use": body["action"],
+            "verified": verified,
+            "reason": if verified { None } else { Some(reason) },
+            "credentials": [],
+            "checked_at": T0,
+        });
+        if verified {
+            serde_json::json!({ "allowed": true, "provenance": provenance })
+        } else {
+            serde_json::json!({ "allowed": false, "reason": reason, "error": ServiceError::policy_denied(reason), "provenance": provenance })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_training_is_gated_on_the_datasets_consent_vcs() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (state, _rpc) = gated_workflow_state(recording_route("/policy/check", calls.clone(), consent_gate)).await;
+        // The dataset's manifest references the VCs its data was collected under
+        let req = serde_json::from_value(serde_json::json!({
+            "root_cid": "artha://QmConsentedSurvey0000000000000000",
+            "license_cid": "artha://QmLicenseCcBy40000000000000000000",
+            "tags": [],
+            "consent_vcs": ["0xconsent", "0xlicense"],
+        }))
+        .unwrap();
+        let Json(registered) = register_dataset(State(state.clone()), HeaderMap::new(), Json(req)).await.unwrap();
+        assert_eq!(state.artifacts.read().await.dataset_consent(&registered.dataset_id), ["0xconsent", "0xlicense"]);
+        let (consented, lapsed) = ("dataset-consented-survey-00000000", "dataset-lapsed-survey-00000000000");
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.set_dataset_consent(consented, vec!["0xconsent".to_string(), "0xlicense".to_string()]);
+            artifacts.set_dataset_consent(lapsed, vec!["0xconsent".to_string(), "0xexpired".to_string()]);
+        }
+
+        let train = |dataset_id: &str| {
+            let mut step = train_step("train", 100)["inputs"].clone();
+            step["dataset_id"] = serde_json::json!(dataset_id);
+            step["submitter_did"] = serde_json::json!("did:artha:alice");
+            step["budget"] = serde_json::json!(100);
+            submit_train_job(State(state.clone()), HeaderMap::new(), Json(serde_json::from_value(step).unwrap()))
+        };
+        let (_, Json(submitted)) = train(consented).await.unwrap();
+        assert_eq!(calls.lock().unwrap()[0]["consent_vcs"], serde_json::json!(["0xconsent", "0xlicense"]));
+        // The verdict stays on the job for audits
+        let provenance = state.jobs.read().await[&submitted.job_id].data_provenance.clone().unwrap();
+        assert_eq!((provenance["dataset_id"].as_str(), provenance["verified"].as_bool()), (Some(consented), Some(true)));
+
+        let denied = train(lapsed).await.unwrap_err().into_response();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value = serde_json::from_slice(&axum::body::to_bytes(denied.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["message"], "Consent VC 0xexpired expired at 1000000");
+        assert_eq!(state.jobs.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tenants_share_names_but_not_resources() {
+        let (state, _rpc) = workflow_state().await;
+        let (acme, globex) = ("did:artha:acme:alice", "did:artha:globex:bob");
+        let caller = |did: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(did) = did {
+                headers.insert(artha_tenant::DID_HEADER, did.parse().unwrap());
+            }
+            headers
+        };
+
+        // The same name@version registers once in each tenant
+        let dataset = |root_cid: &str| DatasetRegisterRequest {
+            root_cid: root_cid.to_string(),
+            license_cid: "artha://QmLicenseCcBy40000000000000000000".to_string(),
+            tags: vec![],
+            name: Some("sensors".to_string()),
+            version: Some("1".to_string()),
+            samples: None,
+            window: None,
+            consent_vcs: vec![],
+        };
+        let register = |did: &str, root_cid: &str| register_dataset(State(state.clone()), caller(Some(did)), Json(dataset(root_cid)));
+        let Json(acme_sensors) = register(acme, "artha://QmAcmeSensors0000000000000000000").await.unwrap();
+        let Json(globex_sensors) = register(globex, "artha://QmGlobexSensors00000000000000000").await.unwrap();
+        assert_ne!(acme_sensors.dataset_id, globex_sensors.dataset_id);
+        // Within a tenant it is still taken, whichever member asks
+        assert_eq!(
+            register("did:artha:acme:carol", "artha://QmGlobexSensors00000000000000000").await.unwrap_err(),
+            StatusCode::CONFLICT
+        );
+
+        let listed = |did: Option<&str>| list_datasets(State(state.clone()), caller(did), Query(HashMap::new()));
+        let ids = |Json(versions): Json<Vec<serde_json::Value>>| {
+            versions.iter().map(|v| v["dataset_id"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(listed(Some(acme)).await.unwrap()), vec![acme_sensors.dataset_id.clone()]);
+        assert_eq!(ids(listed(Some(globex)).await.unwrap()), vec![globex_sensors.dataset_id.clone()]);
+        assert_eq!(ids(listed(None).await.unwrap()).len(), 2);
+
+        // Each tenant's resnet@latest is its own; the shared resnet@1.0 resolves for both
+        let model = |model_cid: &str| ModelRegisterRequest {
+            model_cid: model_cid.to_string(),
+            architecture: "resnet50".to_string(),
+            base_model_id: None,
+            dataset_id: WF_DATASET.to_string(),
+            code_hash: "0xcode".to_string(),
+            version: "2.0".to_string(),
+            license_cid: None,
+            name: Some("resnet".to_string()),
+            requirements: None,
+            schema: None,
+            card: None,
+        };
+        let Json(acme_resnet) = register_model(State(state.clone()), caller(Some(acme)), Json(model("bafy-acme-resnet"))).await.unwrap();
+        let Json(globex_resnet) = register_model(State(state.clone()), caller(Some(globex)), Json(model("bafy-globex-resnet"))).await.unwrap();
+        assert_ne!(acme_resnet.model_id, globex_resnet.model_id);
+        let alias = |did: &str, alias: &str| {
+            get_model_alias(State(state.clone()), caller(Some(did)), Path(("resnet".to_string(), alias.to_string())))
+        };
+        assert_eq!(alias(acme, "latest").await.unwrap()["model_id"], acme_resnet.model_id);
+        assert_eq!(alias(globex, "latest").await.unwrap()["model_id"], globex_resnet.model_id);
+        assert_eq!(alias(globex, "1.0").await.unwrap()["model_id"], WF_MODEL);
+        // The deployment never sees a tenant's tags
+        assert!(state.artifacts.read().await.resolve_model(&Namespace::Shared, "resnet@2.0").is_err());
+
+        // One tenant's jobs are invisible to another, but not to its own members
+        {
+            let mut jobs = state.jobs.write().await;
+            for (job_id, did) in [("job-acme", acme), ("job-globex", globex)] {
+                let mut job = queued_job(job_id, WF_MODEL);
+                job.submitter_did = did.to_string();
+                jobs.insert(job_id.to_string(), job);
+            }
+        }
+        let listed = |did: Option<&str>| {
+            list_jobs(State(state.clone()), caller(did), Query(HashMap::new()), Query(PageQuery { limit: None, cursor: None }))
+        };
+        let job_ids = |Json(page): Json<Page<Job>>| page.items.into_iter().map(|job| job.job_id).collect::<Vec<_>>();
+        assert_eq!(job_ids(listed(Some(globex)).await.unwrap()), vec!["job-globex"]);
+        assert_eq!(job_ids(listed(None).await.unwrap()).len(), 2);
+        let status = |did: &str| get_job_status(State(state.clone()), caller(Some(did)), Path("job-acme".to_string()));
+        assert_eq!(status(globex).await.unwrap_err(), StatusCode::NOT_FOUND);
+        assert_eq!(status("did:artha:acme:carol").await.unwrap().job.job_id, "job-acme");
+        assert_eq!(
+            cancel_job(State(state.clone()), caller(Some(globex)), Path("job-acme".to_string())).await.unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(state.jobs.read().await["job-acme"].status, JobStatus::Queued);
+    }
+
+    #[tokio::test]
+    async fn test_quantize_registers_child_model_unless_accuracy_drops_too_far() {
+        let checks: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let starts: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let runtime_url = serve(
+            recording_route("/capabilities/check", checks.clone(), |body| serde_json::json!({ "satisfiable": true, "runtime": body["framework"] }))
+                .merge(recording_route("/job/start", starts.clone(), |_| serde_json::json!({}))),
+        )
+        .await;
+        let (mut state, _rpc) = workflow_state().await;
+        Arc::get_mut(&mut state).unwrap().runtime_url = runtime_url;
+        let torch = RuntimeRequirements { framework: "torch".to_string(), ..Default::default() };
+        state.artifacts.write().await.set_model_requirements(WF_MODEL, torch.clone());
+
+        let request = |precision: &str, max_accuracy_drop: Option<f64>| -> QuantizeJobRequest {
+            serde_json::from_value(serde_json::json!({
+                "model_id": "resnet@1.0",
+                "eval_dataset_id": WF_DATASET,
+                "precision": precision,
+                "submitter_did": "did:artha:alice",
+                "budget": 200,
+                "max_accuracy_drop": max_accuracy_drop,
+                "name": "resnet",
+                "version": format!("1.0-{}", precision),
+            }))
+            .unwrap()
+        };
+        let assigned = |job_id: &str| JobAssignedRequest {
+            job_id: job_id.to_string(),
+            assigned_node: "0xnode1aabbccddeeff00112233445566778899".to_string(),
+            runtime: "torch".to_string(),
+            score: None,
+            assign_tx: None,
+        };
+
+        // A request cannot loosen the configured bound
+        let loose = submit_quantize_job(State(state.clone()), Json(request("int8", Some(0.5)))).await.unwrap_err();
+        assert_eq!(loose.into_response().status(), StatusCode::BAD_REQUEST);
+        assert!(state.jobs.read().await.is_empty());
+
+        // The quantization image runs over the resolved parent
+        let Json(int8) = submit_quantize_job(State(state.clone()), Json(request("int8", None))).await.unwrap();
+        assert_eq!(state.jobs.read().await[&int8.job_id].model_id.as_deref(), Some(WF_MODEL));
+        assert_eq!(job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned(&int8.job_id))).await, Ok(StatusCode::OK));
+        assert_eq!(checks.lock().unwrap()[0]["framework"], "quantize");
+        let started = starts.lock().unwrap()[0].clone();
+        assert_eq!((started["job_type"].as_str(), started["runtime"].as_str()), (Some("Quantize"), Some("quantize")));
+        assert_eq!(started["env"]["ARTHA_QUANTIZE_PRECISION"], "int8");
+
+        // Half a point lost is within the default bound: the child is registered under the parent
+        let quantized_cid = "artha://QmQuantizedResnetInt8Weights0000000";
+        finish_job(&state, &int8.job_id, "Completed", serde_json::json!({
+            "output_cid": quantized_cid,
+            "metrics": { "baseline_accuracy": 0.912, "accuracy": 0.907 },
+        }))
+        .await;
+        let job = state.jobs.read().await[&int8.job_id].clone();
+        assert_eq!(job.status, JobStatus::Completed);
+        let child = job.artifacts[0].clone();
+        {
+            let artifacts = state.artifacts.read().await;
+            assert_eq!(artifacts.resolve_model(&Namespace::of("did:artha:alice"), "resnet@1.0-int8"), Ok((child.clone(), quantized_cid.to_string())));
+            assert_eq!(artifacts.model_requirements(&child), Some(&torch));
+        }
+        let Json(lineage) = get_model_lineage(State(state.clone()), Path(child)).await.unwrap();
+        assert_eq!(lineage, vec![WF_MODEL.to_string()]);
+
+        // Three points lost is rejected: the job fails and nothing is registered
+        let Json(fp16) = submit_quantize_job(State(state.clone()), Json(request("fp16", None))).await.unwrap();
+        assert_eq!(job_assigned(State(state.clone()), HeaderMap::new(), Json(assigned(&fp16.job_id))).await, Ok(StatusCode::OK));
+        finish_job(&state, &fp16.job_id, "Completed", serde_json::json!({
+            "output_cid": "artha://QmQuantizedResnetFp16Weights0000000",
+            "metrics": { "baseline_accuracy": 0.912, "accuracy": 0.882 },
+        }))
+        .await;
+        let job = state.jobs.read().await[&fp16.job_id].clone();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.artifacts.is_empty());
+        assert!(job.logs.lines().any(|l| l.contains("dropped 0.0300") && l.contains("over the 0.0100 bound")), "{:?}", job.logs);
+        assert!(state.artifacts.read().await.resolve_model(&Namespace::of("did:artha:alice"), "resnet@1.0-fp16").is_err());
+    }
+
+    async fn search_as(url: &str, query: &[(&str, &str)], viewer: Option<(&str, &str)>) -> (u16, serde_json::Value) {
+        let mut request = reqwest::Client::new().get(format!("{}/search", url)).query(query);
+        if let Some((header, value)) = viewer {
+            request = request.header(header, value);
+        }
+        let resp = request.send().await.unwrap();
+        (resp.status().as_u16(), resp.json().await.unwrap())
+    }
+
+    fn hit_ids(results: &serde_json::Value) -> Vec<String> {
+        results["hits"].as_array().unwrap().iter().map(|hit| hit["id"].as_str().unwrap().to_string()).collect()
+    }
+
+    /// A chain that rejects transactions carrying `rejected` (as bytes32) and
+    /// records the selectors of the ones it accepts
+    async fn model_registry_chain(rejected: &'static str) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let accepted: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let recorded = accepted.clone();
+        let url = serve(Router::new().route("/", post(move |Json(request): Json<serde_json::Value>| {
+            let data = request["params"][0]["data"].as_str().unwrap_or_default().to_string();
+            let result = if data.contains(&bytes32_word(rejected)) {
+                serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "error": { "code": -32000, "message": "execution reverted" } })
+            } else {
+                let mut accepted = recorded.lock().unwrap();
+                accepted.push(data[..10].to_string());
+                serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": format!("0x{:064x}", accepted.len()) })
+            };
+            async move { Json(result) }
+        })))
+        .await;
+        (url, accepted)
+    }
+
+    fn batch_of(cids: &[&str]) -> ModelBatchRegisterRequest {
+        let models = cids
+            .iter()
+            .map(|cid| serde_json::json!({
+                "model_cid": cid, "architecture": "cnn", "dataset_id": WF_DATASET,
+                "code_hash": "0xcode", "version": "1.0", "name": cid,
+            }))
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({ "models": models })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_model_batch_registers_every_model() {
+        let (chain_url, accepted) = model_registry_chain("bafy-never").await;
+        let mut state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        Arc::get_mut(&mut state).unwrap().contract_client = Arc::new(ContractClient::new(chain_url));
+
+        let (status, Json(batch)) = register_model_batch(State(state.clone()), HeaderMap::new(), Json(batch_of(&["bafy-a", "bafy-b"]))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(batch.committed);
+        let model_ids: Vec<String> = batch.results.iter().map(|item| match &item.status {
+            BatchItemStatus::Registered { model_id } => model_id.clone(),
+            other => panic!("{} was not registered: {:?}", item.model_cid, other),
+        }).collect();
+        assert_eq!(accepted.lock().unwrap().len(), 2);
+
+        // Both are tagged, like single registrations
+        let artifacts = state.artifacts.read().await;
+        assert_eq!(artifacts.resolve_model(&Namespace::Shared, "bafy-b@1.0").unwrap(), (model_ids[1].clone(), "bafy-b".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_model_batch_rolls_back_when_one_model_fails() {
+        let (chain_url, accepted) = model_registry_chain("bafy-bad").await;
+        let mut state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        Arc::get_mut(&mut state).unwrap().contract_client = Arc::new(ContractClient::new(chain_url));
+
+        let (status, Json(batch)) =
+            register_model_batch(State(state.clone()), HeaderMap::new(), Json(batch_of(&["bafy-a", "bafy-b", "bafy-bad", "bafy-c"]))).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert!(!batch.committed);
+        let statuses: Vec<_> = batch.results.iter().map(|item| (item.index, item.model_cid.as_str(), &item.status)).collect();
+        assert!(matches!(statuses[0], (0, "bafy-a", BatchItemStatus::RolledBack { .. })));
+        assert!(matches!(statuses[1], (1, "bafy-b", BatchItemStatus::RolledBack { .. })));
+        assert!(matches!(statuses[2], (2, "bafy-bad", BatchItemStatus::Failed { error }) if error.contains("execution reverted")));
+        assert!(matches!(statuses[3], (3, "bafy-c", BatchItemStatus::NotAttempted)));
+
+        // Two registrations, then a deactivation for each
+        let deactivate = format!("0x{}", hex::encode(abi::interfaces::selector("deactivate(bytes32)")));
+        let accepted = accepted.lock().unwrap().clone();
+        assert_eq!(accepted.len(), 4);
+        assert!(accepted[2..].iter().all(|selector| *selector == deactivate));
+        // Nothing from the batch is tagged
+        assert!(state.artifacts.read().await.resolve_model(&Namespace::Shared, "bafy-a@1.0").is_err());
+
+        // The per-item result is what goes over the wire
+        let wire = serde_json::to_value(&batch).unwrap();
+        assert_eq!(wire["results"][0]["status"], "rolled_back");
+        assert_eq!(wire["results"][3], serde_json::json!({ "index": 3, "model_cid": "bafy-c", "status": "not_attempted" }));
+    }
+
+    #[tokio::test]
+    async fn test_model_card_is_stored_in_svdb_and_recorded_on_chain() {
+        let cards: Arc<std::sync::Mutex<Vec<Vec<u8>>>> = Arc::default();
+        let (uploads, downloads) = (cards.clone(), cards.clone());
+        let svdb_url = serve(
+            Router::new()
+                .route("/svdb/upload", post(move |body: axum::body::Bytes| {
+                    let uploads = uploads.clone();
+                    async move {
+                        let mut cards = uploads.lock().unwrap();
+                        cards.push(body.to_vec());
+                        Json(serde_json::json!({ "cid": format!("bafy-card-{}", cards.len() - 1) }))
+                    }
+                }))
+                .route("/svdb/download/:cid", get(move |Path(cid): Path<String>| {
+                    let downloads = downloads.clone();
+                    async move {
+                        let index: usize = cid.trim_start_matches("bafy-card-").parse().unwrap();
+                        downloads.lock().unwrap()[index].clone()
+                    }
+                })),
+        )
+        .await;
+        let (chain_url, accepted) = model_registry_chain("bafy-never").await;
+        let state = Arc::new(AppState {
+            svdb_url,
+            contract_client: Arc::new(ContractClient::new(chain_url)),
+            ..Arc::try_unwrap(service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string())).ok().unwrap()
+        });
+        let card = serde_json::json!({
+            "intended_use": "Classifying product photos",
+            "training_data": "2M labelled catalogue images, 2019-2024",
+            "eval_metrics": { "top1_accuracy": 0.912, "top5_accuracy": 0.987 },
+            "limitations": "Degrades on low-light and handheld shots",
+            "out_of_scope_uses": ["Face recognition"],
+        });
+        let request = |card: serde_json::Value| -> ModelRegisterRequest {
+            serde_json::from_value(serde_json::json!({
+                "model_cid": "bafy-carded", "architecture": "cnn", "dataset_id": WF_DATASET,
+                "code_hash": "0xcode", "version": "1.0", "card": card,
+            }))
+            .unwrap()
+        };
+
+        let Json(registered) = register_model(State(state.clone()), HeaderMap::new(), Json(request(card.clone()))).await.unwrap();
+        assert_eq!(registered.card_cid.as_deref(), Some("bafy-card-0"));
+        let selectors = accepted.lock().unwrap().clone();
+        assert_eq!(selectors.len(), 2);
+        assert_eq!(selectors[1], format!("0x{}", hex::encode(abi::interfaces::selector("setModelCard(bytes32,bytes32)"))));
+
+        let Json(view) = get_model_card(State(state.clone()), Path(registered.model_id.clone())).await.unwrap();
+        assert_eq!(view.card_cid, "bafy-card-0");
+        assert_eq!(view.card, serde_json::from_value::<ModelCard>(card.clone()).unwrap());
+        assert_eq!(view.card.eval_metrics["top1_accuracy"], 0.912);
+
+        // A model registered without a card has none to return
+        let error = get_model_card(State(state.clone()), Path(WF_MODEL.to_string())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        // A card missing required fields is rejected before SVDB or the chain see it
+        let mut incomplete = card.clone();
+        incomplete["limitations"] = serde_json::json!("");
+        incomplete.as_object_mut().unwrap().remove("eval_metrics");
+        assert_eq!(
+            request(incomplete.clone()).card.unwrap().check(),
+            Err("Model card is missing limitations, eval_metrics".to_string())
+        );
+        let status = register_model(State(state.clone()), HeaderMap::new(), Json(request(incomplete.clone()))).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let batch = ModelBatchRegisterRequest { models: vec![request(card), request(incomplete)] };
+        let (status, Json(batch)) = register_model_batch(State(state.clone()), HeaderMap::new(), Json(batch)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(matches!(&batch.results[1].status, BatchItemStatus::Failed { error } if error.starts_with("Invalid card")));
+        assert_eq!(accepted.lock().unwrap().len(), 2);
+        assert_eq!(cards.lock().unwrap().len(), 1);
+    }
+
+    async fn register_named_model(state: &Arc<AppState>, name: &str, architecture: &str) -> String {
+        let req: ModelRegisterRequest = serde_json::from_value(serde_json::json!({
+            "model_cid": format!("artha://Qm{}Weights", name),
+            "architecture": architecture,
+            "dataset_id": WF_DATASET,
+            "code_hash": "0xcode",
+            "version": "1.0",
+            "name": name,
+        }))
+        .unwrap();
+        register_model(State(state.clone()), HeaderMap::new(), Json(req)).await.unwrap().0.model_id
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_facets_and_hides_jobs_from_other_dids() {
+        let (state, _rpc) = workflow_state().await;
+        let bert = register_named_model(&state, "bert", "transformer").await;
+        let resnet = register_named_model(&state, "resnet", "cnn").await;
+        {
+            let mut jobs = state.jobs.write().await;
+            let mut alice = queued_job("job-alice", &bert);
+            alice.submitter_did = "did:artha:alice".to_string();
+            alice.status = JobStatus::Completed;
+            alice.submitted_at = 1_736_899_200; // 2025-01-15
+            let mut bob = queued_job("job-bob", &resnet);
+            bob.submitter_did = "did:artha:bob".to_string();
+            bob.status = JobStatus::Failed;
+            bob.submitted_at = 1_740_787_200; // 2025-03-01
+            jobs.insert(alice.job_id.clone(), alice);
+            jobs.insert(bob.job_id.clone(), bob);
+        }
+        let url = serve(Router::new().route("/search", get(search)).with_state(state.clone())).await;
+        let alice = Some((outputs::DID_HEADER, "did:artha:alice"));
+        let internal = Some((outputs::INTERNAL_TOKEN_HEADER, "internal"));
+
+        // Typed filters combine with each other and with the model's architecture
+        let (status, results) = search_as(&url, &[("q", "status:completed architecture:transformer before:2025-02-01")], alice).await;
+        assert_eq!(status, 200);
+        assert_eq!(hit_ids(&results), vec!["job-alice"]);
+        assert_eq!(results["hits"][0]["title"], "Train job on bert@1.0");
+        let (_, results) = search_as(&url, &[("q", "status:completed after:2025-02-01")], alice).await;
+        assert_eq!(results["total"], 0);
+        let (_, results) = search_as(&url, &[("q", r#"model:"bert@1.0" status:completed,failed"#)], alice).await;
+        assert_eq!(results["total"], 0, "one value per filter token");
+
+        // Jobs are private to their submitter; models are public
+        let (_, results) = search_as(&url, &[("type", "job")], alice).await;
+        assert_eq!(hit_ids(&results), vec!["job-alice"]);
+        let (_, results) = search_as(&url, &[("type", "job")], None).await;
+        assert_eq!(results["total"], 0);
+        let (_, results) = search_as(&url, &[("type", "model")], None).await;
+        assert_eq!(results["total"], 2);
+
+        // Facet counts follow the query, and internal callers see everything
+        let (_, results) = search_as(&url, &[("type", "job")], internal).await;
+        assert_eq!(hit_ids(&results), vec!["job-bob", "job-alice"], "newest first without free text");
+        assert_eq!(results["facets"]["status"], serde_json::json!({ "completed": 1, "failed": 1 }));
+        assert_eq!(results["facets"]["architecture"], serde_json::json!({ "cnn": 1, "transformer": 1 }));
+        assert_eq!(results["facets"]["date"], serde_json::json!({ "2025-01": 1, "2025-03": 1 }));
+        let (_, results) = search_as(&url, &[("facets", "type")], internal).await;
+        assert_eq!(results["facets"], serde_json::json!({ "type": { "job": 2, "model": 2, "transaction": 2 } }));
+
+        // Registrations were indexed as transactions to the model registry
+        let (_, results) = search_as(&url, &[("q", "type:transaction")], None).await;
+        assert!(results["hits"].as_array().unwrap().iter().all(|hit| hit["title"].as_str().unwrap().starts_with("ModelRegistry.")));
+
+        // Bad dates, empty filters and unknown facets are rejected
+        for query in [&[("q", "before:2025-02-30")][..], &[("q", "status:")], &[("facets", "colour")]] {
+            let (status, body) = search_as(&url, query, internal).await;
+            assert_eq!(status, 400, "{:?}: {}", query, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_title_matches_first_and_stays_consistent_through_a_burst() {
+        let state = service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string());
+        let doc = |kind: &str, id: &str, title: &str| SearchDoc::new(kind, id, title.to_string(), 1_736_899_200);
+        let mut in_logs = doc("job", "job-logs", "Infer job on model-x");
+        in_logs.logs = vec!["loading transformer weights".to_string()];
+        in_logs.logged_at = T0;
+        state.search.write().await.upsert(
+            vec![
+                in_logs,
+                doc("model", "model-bert", "bert@1.0").field("architecture", "transformer"),
+                doc("model", "model-tb", "transformer-base@2.0").field("architecture", "transformer"),
+                doc("model", "model-cnn", "resnet@1.0").field("architecture", "cnn"),
+            ],
+            T0,
+        )
+        .unwrap();
+        let url = serve(Router::new().route("/search", get(search)).with_state(state.clone())).await;
+        let internal = Some((outputs::INTERNAL_TOKEN_HEADER, "internal"));
+
+        let (_, results) = search_as(&url, &[("q", "transformer")], internal).await;
+        assert_eq!(hit_ids(&results)[0], "model-tb", "title matches rank first");
+        assert_eq!(results["total"], 3);
+        assert!(results["hits"][0]["highlights"]["title"].as_str().unwrap().contains("<b>transformer</b>"));
+        let logged = results["hits"].as_array().unwrap().iter().find(|hit| hit["id"] == "job-logs").unwrap();
+        assert!(logged["highlights"]["logs"].as_str().unwrap().contains("<b>transformer</b>"));
+
+        // A burst of submissions and transitions is visible to the next query
+        {
+            let mut jobs = state.jobs.write().await;
+            for i in 0..60 {
+                jobs.insert(format!("burst-{}", i), queued_job(&format!("burst-{}", i), "model-cnn"));
+            }
+        }
+        let (_, results) = search_as(&url, &[("q", "status:queued")], internal).await;
+        assert_eq!(results["total"], 60);
+        {
+            let mut jobs = state.jobs.write().await;
+            for i in (0..60).step_by(3) {
+                jobs.get_mut(&format!("burst-{}", i)).unwrap().status = JobStatus::Completed;
+            }
+        }
+        let (_, results) = search_as(&url, &[("q", "status:queued")], internal).await;
+        assert_eq!(results["total"], 40);
+        let (_, results) = search_as(&url, &[("type", "job"), ("facets", "status")], internal).await;
+        assert_eq!(results["facets"]["status"], serde_json::json!({ "completed": 20, "queued": 40 }));
+
+        // Only what changed is reindexed
+        state.jobs.write().await.get_mut("burst-1").unwrap().logs.push("epoch 1".to_string());
+        assert_eq!(sync_search(&state).await, Ok(1));
+        assert_eq!(sync_search(&state).await, Ok(0));
+    }
+
+    #[tokio::test]
+    async fn test_search_rebuilds_from_journal_within_size_and_log_bounds() {
+        let journal = std::env::temp_dir().join(format!("jobd-search-{}.jsonl", uuid::Uuid::new_v4()));
+        let config = SearchConfig {
+            max_docs: 3,
+            max_log_bytes: 12,
+            log_retention_secs: 100,
+            ..search_config(Some(journal.to_string_lossy().to_string()))
+        };
+        let at = 1_000_000;
+        let mut index = SearchIndex::open(config.clone(), at).unwrap();
+        let mut logged = SearchDoc::new("job", "job-0", "Train job on resnet@1.0".to_string(), at);
+        logged.logs = vec!["epoch 1 loss".to_string(), "epoch 2".to_string(), "done".to_string()];
+        logged.logged_at = at;
+        let docs: Vec<SearchDoc> = std::iter::once(logged)
+            .chain((1..5).map(|i| SearchDoc::new("model", &format!("model-{}", i), format!("m{}@1.0", i), at + i)))
+            .collect();
+        index.upsert(docs, at).unwrap();
+
+        // The oldest documents beyond the bound are evicted; logs keep the newest lines that fit
+        assert_eq!(index.len(), 3);
+        assert!(index.related("model", "model-1").is_none());
+        assert!(index.related("model", "model-4").is_some());
+        let everything = SearchRequest { q: "done".to_string(), ..Default::default() };
+        assert_eq!(index.search(&everything, &Viewer::Internal).unwrap().total, 0, "job-0 was the oldest");
+
+        let kept = SearchDoc { logs: vec!["a".repeat(5), "epoch 2".to_string(), "done".to_string()], logged_at: at, ..SearchDoc::new("job", "job-9", "Infer job".to_string(), at + 9) };
+        index.upsert(vec![kept], at).unwrap();
+        let hit = |index: &SearchIndex, q: &str| index.search(&SearchRequest { q: q.to_string(), ..Default::default() }, &Viewer::Internal).unwrap();
+        assert_eq!(hit(&index, "done").hits[0].doc.logs, vec!["epoch 2", "done"]);
+
+        // A restart replays the journal into the same index
+        let reopened = SearchIndex::open(config.clone(), at).unwrap();
+        assert_eq!(reopened.len(), 3);
+        assert_eq!(hit(&reopened, "done").total, 1);
+        drop(reopened);
+
+        // Excerpts past the retention are dropped, and the journal records it
+        assert_eq!(index.expire_logs(at + 100).unwrap(), 1);
+        assert_eq!(hit(&index, "done").total, 0);
+        assert_eq!(hit(&index, "infer").total, 1);
+        let mut reopened = SearchIndex::open(config, at).unwrap();
+        assert_eq!(hit(&reopened, "done").total, 0);
+        assert_eq!(reopened.rebuild(at).unwrap(), 3);
+        assert_eq!(std::fs::read_to_string(&journal).unwrap().lines().count(), 3, "compacted");
+        std::fs::remove_file(journal).unwrap();
     }
 
     #[test]
-    fn test_job_creation() {
-        let job = Job {
-            job_id: "job-test".to_string(),
-            job_type: JobType::Train,
-            status: JobStatus::Queued,
-            submitter: "0xtest".to_string(),
-            submitter_did: "did:artha:test".to_string(),
-            model_id: Some("model-1".to_string()),
-            dataset_id: Some("dataset-1".to_string()),
-            params_hash: "0xhash".to_string(),
-            assigned_node: None,
+    fn test_reservation_refunds_follow_forfeiture_schedule() {
+        let schedule = ForfeitureSchedule::parse(ForfeitureSchedule::DEFAULT).unwrap();
+        // Unused share of 10_000, less half below 50% utilization, a quarter below 90%, nothing above
+        for (utilization, refund) in [(0.0, 5_000), (0.25, 3_750), (0.5, 3_750), (0.75, 1_875), (0.95, 500), (1.0, 0)] {
+            assert_eq!(schedule.refund(10_000, utilization), refund, "at {} utilization", utilization);
+        }
+        assert_eq!(schedule.forfeit(0.49), 0.5);
+        assert!(ForfeitureSchedule::parse("0:1.5").is_err());
+        assert!(ForfeitureSchedule::parse("half").is_err());
+
+        // Only GPU time inside the window counts, and each job holds one GPU
+        let req: ReservationRequest = serde_json::from_value(serde_json::json!({
+            "submitter_did": "did:artha:alice", "gpu_type": "H100", "gpu_count": 2,
+            "earliest_start": 1_000, "duration_secs": 1_000,
+        })).unwrap();
+        let booking: Booking = serde_json::from_value(serde_json::json!({
+            "reservation_id": "res-1", "start": 1_000, "end": 2_000,
+            "allocations": { "0xnode1": 2 }, "price_per_gpu_sec": 0.5, "cost": 1_000,
+        })).unwrap();
+        let reservation = Reservation::new(&req, booking, "0xescrow".to_string(), 0);
+        let runs = [
+            JobRun { started_at: Some(500), finished_at: Some(1_500) }, // 500s inside
+            JobRun { started_at: Some(1_800), finished_at: None },      // Still running
+            JobRun { started_at: None, finished_at: None },             // Never started
+        ];
+        assert_eq!(reservation.utilization(&runs, 1_900), 0.3);
+        assert_eq!(reservation.utilization(&runs, 5_000), 0.35);
+        assert_eq!(reservation.status(500), reservations::ReservationStatus::Upcoming);
+        assert_eq!(reservation.status(1_500), reservations::ReservationStatus::Active);
+        assert_eq!(reservation.status(2_000), reservations::ReservationStatus::Ended);
+    }
+
+    #[tokio::test]
+    async fn test_reservation_booking_escrows_and_backs_submissions() {
+        let schedules: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let cancelled: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let reserved_full = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let scheduler = {
+            let (schedules, cancelled, reserved_full) = (schedules.clone(), cancelled.clone(), reserved_full.clone());
+            recording_route("/reservations", Arc::default(), |body| serde_json::json!({
+                "reservation_id": body["reservation_id"],
+                "start": body["earliest_start"],
+                "end": body["earliest_start"].as_u64().unwrap() + body["duration_secs"].as_u64().unwrap(),
+                "allocations": { "0xnode1aabbccddeeff00112233445566778899": 2 },
+                "price_per_gpu_sec": 0.1,
+                "cost": 720,
+            }))
+            .route("/reservations/:id", axum::routing::delete(move |Path(id): Path<String>| {
+                cancelled.lock().unwrap().push(id);
+                async { StatusCode::NO_CONTENT }
+            }))
+            .route("/schedule", post(move |Json(body): Json<serde_json::Value>| {
+                schedules.lock().unwrap().push(body);
+                let full = reserved_full.load(std::sync::atomic::Ordering::SeqCst);
+                async move { if full { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK } }
+            }))
+        };
+        let scheduler_url = serve(scheduler).await;
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+
+        let mut state = service_state(scheduler_url.clone(), "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        let dataset_id = "dataset-imagenet-1k-train-0000000";
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+            artifacts.register_dataset(dataset_id, "bafy-dataset");
+        }
+        let booking = || -> ReservationRequest {
+            serde_json::from_value(serde_json::json!({
+                "submitter_did": "did:artha:alice", "gpu_type": "H100", "gpu_count": 2,
+                "earliest_start": T0 + 60, "duration_secs": 3600,
+            }))
+            .unwrap()
+        };
+        let sent_to_deal_market = |signature: &str| -> Vec<String> {
+            let selector = format!("0x{}", hex::encode(abi::interfaces::selector(signature)));
+            rpc.calls().into_iter().filter(|call| call.data.starts_with(&selector)).map(|call| call.data).collect()
+        };
+
+        // Booking escrows the scheduler's quote
+        let Json(reservation) = book_reservation(State(state.clone()), Json(booking())).await.unwrap();
+        assert_eq!(reservation.cost, 720);
+        let escrows = sent_to_deal_market("escrowReservation(bytes32,bytes32,uint256)");
+        assert_eq!(escrows.len(), 1);
+        assert!(escrows[0].ends_with(&format!("{:064x}", 720)));
+
+        // Submissions against it are forwarded with the reservation, for its owner only
+        let train = |did: &str, reservation_id: &str| TrainJobRequest {
+            model_id: model_id.to_string(),
+            dataset_id: dataset_id.to_string(),
+            submitter_did: did.to_string(),
+            params: TrainParams {
+                epochs: 1,
+                batch_size: 64,
+                learning_rate: 0.001,
+                optimizer: "adam".to_string(),
+                checkpoint_interval: 500,
+                seed: Some(7),
+            },
             budget: 1000,
-            spent: 0,
-            submitted_at: now(),
-            started_at: None,
-            completed_at: None,
-            output_cid: None,
-            artifacts: Vec::new(),
-            progress: 0.0,
-            logs: Vec::new(),
+            tee_required: false,
+            allow_deprecated: false,
+            nonce: None,
+            milestones: None,
+            live_migration: false,
+            reservation_id: Some(reservation_id.to_string()),
+            secrets: Vec::new(),
+            labels: Labels::new(),
+            force_rerun: false,
         };
+        let submit = |req: TrainJobRequest| {
+            let state = state.clone();
+            async move {
+                submit_train_job(State(state), HeaderMap::new(), Json(req)).await
+                    .map(|(_, Json(response))| response.job_id)
+                    .map_err(|e| e.into_response().status())
+            }
+        };
+        let job_id = submit(train("did:artha:alice", &reservation.reservation_id)).await.unwrap();
+        assert_eq!(schedules.lock().unwrap()[0]["reservation_id"], reservation.reservation_id.as_str());
+        assert_eq!(state.reservations.read().await.get(&reservation.reservation_id).unwrap().jobs, vec![job_id.clone()]);
+        assert_eq!(submit(train("did:artha:bob", &reservation.reservation_id)).await, Err(StatusCode::FORBIDDEN));
+        assert_eq!(submit(train("did:artha:alice", "res-unknown")).await, Err(StatusCode::NOT_FOUND));
 
-        assert_eq!(job.status, JobStatus::Queued);
-        assert_eq!(job.progress, 0.0);
+        // Every reserved GPU busy: the job is turned away rather than failed
+        reserved_full.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(submit(train("did:artha:alice", &reservation.reservation_id)).await, Err(StatusCode::CONFLICT));
+        assert_eq!(state.jobs.read().await.len(), 1);
+        assert_eq!(state.reservations.read().await.get(&reservation.reservation_id).unwrap().jobs.len(), 1);
+
+        // The window ends with a quarter of its GPU-seconds used
+        let t = T0;
+        {
+            let mut reservations = state.reservations.write().await;
+            let booked = reservations.get_mut(&reservation.reservation_id).unwrap();
+            booked.start = t - 1000;
+            booked.end = t - 100;
+        }
+        {
+            let mut jobs = state.jobs.write().await;
+            let job = jobs.get_mut(&job_id).unwrap();
+            job.started_at = Some(t - 1000);
+            job.completed_at = Some(t - 550);
+        }
+        let list = || {
+            let state = state.clone();
+            async move {
+                let params = HashMap::from([("did".to_string(), "did:artha:alice".to_string())]);
+                list_reservations(State(state), Query(params), Query(PageQuery::default())).await.unwrap().0
+            }
+        };
+        let page = list().await;
+        assert_eq!(page.total, 1);
+        let view = &page.items[0];
+        assert_eq!(view.status, reservations::ReservationStatus::Settled);
+        assert_eq!(view.utilization, 0.25);
+        let refund = view.reservation.refund.as_ref().unwrap();
+        assert_eq!(refund.amount, 270); // 75% unused of 720, half of it forfeited
+        assert!(refund.tx_hash.is_some());
+        list().await;
+        let refunds = sent_to_deal_market("refundReservation(bytes32,bytes32,uint256)");
+        assert_eq!(refunds.len(), 1, "settled reservations are refunded once");
+        assert!(refunds[0].ends_with(&format!("{:064x}", 270)));
+
+        // Without escrow the booking is handed back to the scheduler
+        let unpaid = service_state(scheduler_url.clone(), "http://127.0.0.1:9".to_string());
+        let failed = book_reservation(State(unpaid.clone()), Json(booking())).await.unwrap_err();
+        assert_eq!(failed.kind(), Some(ErrorCode::DependencyFailed));
+        assert_eq!(cancelled.lock().unwrap().len(), 1);
+        assert!(unpaid.reservations.read().await.all().next().is_none());
+
+        // So is one whose escrow is mined reverted
+        let reverting = abi::DryRunRpc::spawn().await;
+        reverting.revert(abi::deal_market().function("escrowReservation"));
+        let mut broke = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        Arc::get_mut(&mut broke).unwrap().contract_client = Arc::new(ContractClient::new(reverting.url()));
+        let failed = book_reservation(State(broke.clone()), Json(booking())).await.unwrap_err();
+        assert_eq!(failed.kind(), Some(ErrorCode::DependencyFailed));
+        assert!(failed.message.contains("reverted"), "{}", failed.message);
+        assert_eq!(cancelled.lock().unwrap().len(), 2);
+        assert!(broke.reservations.read().await.all().next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_job_cost_is_escrowed_at_submission_and_settled_from_escrow() {
+        // A chain on which did:artha:broke can't cover any escrow: its
+        // transactions are mined with a failed status
+        let sent: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let recorded = sent.clone();
+        let reverted: Arc<std::sync::Mutex<std::collections::HashSet<String>>> = Arc::default();
+        let broke = compute_hash("did:artha:broke")[2..].to_string();
+        let chain_url = serve(Router::new().route("/", post(move |Json(request): Json<serde_json::Value>| {
+            let mut reverted = reverted.lock().unwrap();
+            let result = if request["method"] == "eth_getTransactionReceipt" {
+                let failed = reverted.contains(request["params"][0].as_str().unwrap_or_default());
+                serde_json::json!({ "status": if failed { "0x0" } else { "0x1" }, "blockNumber": "0x1", "blockHash": "0xb1" })
+            } else {
+                let data = request["params"][0]["data"].as_str().unwrap_or_default().to_string();
+                if data.contains(&broke) {
+                    let tx_hash = format!("0xdead{:060x}", reverted.len());
+                    reverted.insert(tx_hash.clone());
+                    tx_hash.into()
+                } else {
+                    let mut sent = recorded.lock().unwrap();
+                    sent.push(data);
+                    format!("0x{:064x}", sent.len()).into()
+                }
+            };
+            async move { Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })) }
+        })))
+        .await;
+        let schedules: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let scheduler_url = serve(recording_route("/schedule", schedules.clone(), |_| serde_json::json!({}))).await;
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(chain_url));
+        }
+        state.artifacts.write().await.register_model(&Namespace::Shared, WF_MODEL, "bafy-model", Some("resnet"), "1.0");
+        let infer = |did: &str, nonce: u64| -> InferJobRequest {
+            serde_json::from_value(serde_json::json!({
+                "model_id": WF_MODEL, "input_cid": "artha://QmImageBatch", "inline_input": null,
+                "submitter_did": did, "mode": "batch", "max_tokens": null, "budget": 500,
+                "bucketing_key": null, "nonce": nonce,
+            }))
+            .unwrap()
+        };
+        let sent_with = |signature: &str| -> Vec<String> {
+            let selector = format!("0x{}", hex::encode(abi::interfaces::selector(signature)));
+            sent.lock().unwrap().iter().filter(|data| data.starts_with(&selector)).cloned().collect()
+        };
+        let words = |data: &str| -> Vec<String> { (10..data.len()).step_by(64).map(|i| data[i..i + 64].to_string()).collect() };
+
+        // A submitter who can't fund the escrow is turned away before the job exists
+        let response = submit_infer_job(State(state.clone()), Json(infer("did:artha:broke", 1))).await.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ServiceError = serde_json::from_slice(&body).unwrap();
+        assert!(error.message.contains("reverted"), "{}", error.message);
+        assert!(sent.lock().unwrap().is_empty());
+        assert!(schedules.lock().unwrap().is_empty());
+        assert!(state.jobs.read().await.is_empty());
+
+        // Otherwise the estimated cost is escrowed before the job goes on-chain
+        let (_, Json(response)) = submit_infer_job(State(state.clone()), Json(infer("did:artha:alice", 1))).await.unwrap();
+        let job_id = response.job_id;
+        let escrows = sent_with("escrowJob(bytes32,bytes32,uint256)");
+        assert_eq!(escrows.len(), 1);
+        assert_eq!(words(&escrows[0]), vec![
+            bytes32_word(&job_id),
+            compute_hash("did:artha:alice")[2..].to_string(),
+            format!("{:064x}", response.estimated_cost),
+        ]);
+        assert!(sent.lock().unwrap()[0].starts_with(&escrows[0][..10]), "escrow precedes the chain submission");
+
+        // At completion the provider is paid what the job spent, from escrow, and the rest refunded
+        let node = "0xnode1aabbccddeeff00112233445566778899";
+        {
+            let mut jobs = state.jobs.write().await;
+            let job = jobs.get_mut(&job_id).unwrap();
+            job.status = JobStatus::Running;
+            job.assigned_node = Some(node.to_string());
+        }
+        let completed: JobProgressRequest = serde_json::from_value(serde_json::json!({
+            "progress": 1.0, "epochs_completed": null, "status": "Completed", "output_cid": null,
+            "peak_vram_mb": null, "failure_reason": null, "spent": 60,
+        }))
+        .unwrap();
+        job_progress(State(state.clone()), Path(job_id.clone()), Json(completed)).await.unwrap();
+        let settlements = sent_with("settleJobEscrow(bytes32,bytes32,uint256,uint256)");
+        assert_eq!(settlements.len(), 1);
+        assert_eq!(words(&settlements[0]), vec![
+            bytes32_word(&job_id),
+            bytes32_word(node),
+            format!("{:064x}", 60),
+            format!("{:064x}", response.estimated_cost - 60),
+        ]);
+        let settled = state.job_escrows.read().await.get(&job_id).unwrap().settlement.clone().unwrap();
+        assert_eq!((settled.paid, settled.refunded), (60, 40));
+        assert!(settled.tx_hash.is_some());
+        assert_eq!(settle_job_escrows(&state, T0).await, 0, "an escrow is settled once");
+
+        // A job cancelled before it ran is refunded in full
+        let (_, Json(queued)) = submit_infer_job(State(state.clone()), Json(infer("did:artha:alice", 2))).await.unwrap();
+        cancel_job(State(state.clone()), HeaderMap::new(), Path(queued.job_id.clone())).await.unwrap();
+        let settlements = sent_with("settleJobEscrow(bytes32,bytes32,uint256,uint256)");
+        assert_eq!(settlements.len(), 2);
+        assert_eq!(words(&settlements[1])[1..], [format!("{:064x}", 0), format!("{:064x}", 0), format!("{:064x}", queued.estimated_cost)]);
+    }
+
+    #[tokio::test]
+    async fn test_tight_deadline_aborts_submission_and_cancels_it_on_chain() {
+        let sent: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let recorded = sent.clone();
+        let chain_url = serve(Router::new().route("/", post(move |Json(request): Json<serde_json::Value>| {
+            let result = if request["method"] == "eth_getTransactionReceipt" {
+                serde_json::json!({ "status": "0x1", "blockNumber": "0x1", "blockHash": "0xb1" })
+            } else {
+                let mut sent = recorded.lock().unwrap();
+                sent.push(request["params"][0]["data"].as_str().unwrap_or_default().to_string());
+                format!("0x{:064x}", sent.len()).into()
+            };
+            async move { Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })) }
+        })))
+        .await;
+        // Placement takes the scheduler five seconds, and it hears the caller's deadline
+        let clock = ManualClock::new(T0);
+        let deadlines: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let released: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let scheduler_url = serve(
+            Router::new()
+                .route("/schedule", post({
+                    let (clock, deadlines) = (clock.clone(), deadlines.clone());
+                    move |headers: HeaderMap| async move {
+                        let deadline = headers.get(DEADLINE_HEADER).map(|v| v.to_str().unwrap().to_string());
+                        deadlines.lock().unwrap().extend(deadline);
+                        clock.advance_secs(5);
+                        Json(serde_json::json!({}))
+                    }
+                }))
+                .route("/schedule/:job_id/release", post({
+                    let released = released.clone();
+                    move |Path(job_id): Path<String>| async move { released.lock().unwrap().push(job_id) }
+                })),
+        )
+        .await;
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let mut state = service_state_on(scheduler_url, "http://127.0.0.1:9".to_string(), clock.clone());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(chain_url));
+        }
+        state.artifacts.write().await.register_model(&Namespace::Shared, WF_MODEL, "bafy-model", Some("resnet"), "1.0");
+        state.artifacts.write().await.register_dataset("dataset-imagenet-1k-train-0000000", "bafy-dataset");
+        let train = |nonce: u64| -> TrainJobRequest {
+            serde_json::from_value(serde_json::json!({
+                "model_id": WF_MODEL, "dataset_id": "dataset-imagenet-1k-train-0000000", "submitter_did": "did:artha:alice",
+                "params": { "epochs": 2, "batch_size": 32, "learning_rate": 0.001, "optimizer": "adam", "checkpoint_interval": 100 },
+                "budget": 1000, "nonce": nonce,
+            }))
+            .unwrap()
+        };
+        let within = |millis: u64| {
+            let mut headers = HeaderMap::new();
+            headers.insert(DEADLINE_HEADER, (clock.now_millis() + millis).to_string().parse().unwrap());
+            headers
+        };
+        let sent_with = |signature: &str| -> Vec<String> {
+            let selector = format!("0x{}", hex::encode(abi::interfaces::selector(signature)));
+            sent.lock().unwrap().iter().filter(|data| data.starts_with(&selector)).cloned().collect()
+        };
+
+        // Two seconds isn't enough: the job went on-chain, then is cancelled there and undone
+        let headers = within(2_000);
+        let response = submit_train_job(State(state.clone()), headers.clone(), Json(train(1))).await.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ServiceError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.kind(), Some(ErrorCode::DeadlineExceeded));
+        assert_eq!(*deadlines.lock().unwrap(), vec![headers[DEADLINE_HEADER].to_str().unwrap().to_string()]);
+
+        let submitted = sent_with("submitTrain(bytes32,bytes32,bytes32,uint32,uint256)");
+        let cancelled = sent_with("updateStatus(bytes32,uint8)");
+        assert_eq!((submitted.len(), cancelled.len()), (1, 1));
+        assert!(cancelled[0].ends_with(&format!("{:064x}", 5)), "cancelled on-chain: {}", cancelled[0]);
+        let refunds = sent_with("settleJobEscrow(bytes32,bytes32,uint256,uint256)");
+        assert_eq!(refunds.len(), 1);
+        assert!(refunds[0].ends_with(&format!("{:064x}{:064x}", 0, estimate_train_cost(&train(1).params, "dataset-imagenet-1k-train-0000000"))));
+        assert_eq!(released.lock().unwrap().len(), 1);
+        assert!(state.jobs.read().await.is_empty());
+        assert!(state.job_escrows.read().await.unsettled().is_empty());
+
+        // A deadline that has already passed stops the submission before anything goes on-chain
+        let before = sent.lock().unwrap().len();
+        let err = submit_train_job(State(state.clone()), within(0), Json(train(2))).await.unwrap_err();
+        assert!(err.is_deadline_exceeded());
+        assert_eq!(sent.lock().unwrap().len(), before);
+        assert_eq!(deadlines.lock().unwrap().len(), 1);
+
+        // With time to spare the job is queued as usual
+        let (_, Json(queued)) = submit_train_job(State(state.clone()), within(60_000), Json(train(3))).await.unwrap();
+        assert_eq!(state.jobs.read().await[&queued.job_id].status, JobStatus::Queued);
+        assert_eq!(sent_with("updateStatus(bytes32,uint8)").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reorg_that_unmines_finalize_tx_rolls_completed_job_back() {
+        // Mock chain: the finalize tx's receipt and the canonical hash per height
+        type Chain = (HashMap<String, (u64, String)>, HashMap<u64, String>);
+        let chain: Arc<std::sync::Mutex<Chain>> = Arc::default();
+        chain.lock().unwrap().0.insert("0xf1na1".to_string(), (100, "0xblock-a".to_string()));
+        chain.lock().unwrap().1.insert(100, "0xblock-a".to_string());
+        let rpc = chain.clone();
+        let rpc_url = serve(Router::new().route("/", post(move |Json(body): Json<serde_json::Value>| {
+            let rpc = rpc.clone();
+            async move {
+                let (receipts, blocks) = &*rpc.lock().unwrap();
+                let result = match body["method"].as_str().unwrap() {
+                    "eth_getTransactionReceipt" => receipts
+                        .get(body["params"][0].as_str().unwrap())
+                        .map_or(serde_json::Value::Null, |(number, hash)| serde_json::json!({
+                            "blockNumber": format!("0x{:x}", number),
+                            "blockHash": hash,
+                        })),
+                    "eth_getBlockByNumber" => {
+                        let number = u64::from_str_radix(body["params"][0].as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
+                        blocks.get(&number).map_or(serde_json::Value::Null, |hash| serde_json::json!({ "hash": hash }))
+                    }
+                    other => panic!("unexpected RPC {}", other),
+                };
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+            }
+        })))
+        .await;
+        // Receipts daemon finalized the job; the node never reports finality
+        let receipts_url = serve(
+            Router::new()
+                .route("/receipts", get(|| async {
+                    Json(serde_json::json!({ "items": [{ "receipt_id": "r1", "finalize_tx": "0xf1na1" }], "total": 1, "next_cursor": null }))
+                }))
+                .route("/api/v1/transactions/:hash", get(|| async { Json(serde_json::json!({ "finalized": false })) })),
+        )
+        .await;
+        let state = AppState {
+            contract_client: Arc::new(ContractClient::new(rpc_url)),
+            receipts_url: receipts_url.clone(),
+            node_api_url: receipts_url,
+            ..Arc::try_unwrap(service_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9".to_string())).ok().unwrap()
+        };
+        let mut job = queued_job("job-reorg", "model-reorg");
+        job.status = JobStatus::Completed;
+        state.jobs.write().await.insert(job.job_id.clone(), job);
+
+        // The finalize tx is picked up and found in block 100
+        assert_eq!(reconcile_chain(&state).await, 0);
+        let tracked = state.chain.read().await.watched();
+        assert_eq!(tracked.len(), 1);
+        assert_eq!((tracked[0].0.as_str(), tracked[0].1.role), ("job-reorg", TxRole::Finalize));
+        assert_eq!(tracked[0].1.inclusion, Some(Inclusion { block_number: 100, block_hash: "0xblock-a".to_string() }));
+        assert_eq!(reconcile_chain(&state).await, 0);
+        assert_eq!(state.jobs.read().await["job-reorg"].status, JobStatus::Completed);
+
+        // A reorg replaces block 100 and the finalize tx drops out of the chain
+        {
+            let (receipts, blocks) = &mut *chain.lock().unwrap();
+            receipts.remove("0xf1na1");
+            blocks.insert(100, "0xblock-b".to_string());
+        }
+        assert_eq!(reconcile_chain(&state).await, 1);
+        assert_eq!(state.jobs.read().await["job-reorg"].status, JobStatus::Running);
+        assert_eq!(state.chain.read().await.watched()[0].1.inclusion, None);
+        let events = state.events.read().await.for_job("job-reorg");
+        let reorged = events.iter().find(|e| e.kind == EventKind::Reorged).unwrap();
+        assert_eq!(reorged.detail["from"], "Completed");
+        assert_eq!(reorged.detail["to"], "Running");
+
+        // Still un-mined: nothing more to roll back
+        assert_eq!(reconcile_chain(&state).await, 0);
+        assert_eq!(state.jobs.read().await["job-reorg"].status, JobStatus::Running);
+
+        // Re-mined on the new branch, the job is Completed again
+        chain.lock().unwrap().0.insert("0xf1na1".to_string(), (101, "0xblock-c".to_string()));
+        chain.lock().unwrap().1.insert(101, "0xblock-c".to_string());
+        assert_eq!(reconcile_chain(&state).await, 1);
+        assert_eq!(state.jobs.read().await["job-reorg"].status, JobStatus::Completed);
     }
-}
 
+    #[tokio::test]
+    async fn test_restart_restores_jobs_and_settles_the_ones_left_running() {
+        let scheduled: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let rejected: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let scheduler_url = serve(
+            recording_route("/schedule", scheduled.clone(), |_| serde_json::json!({ "status": "queued" }))
+                .merge(recording_route("/schedule/:id/reject", rejected.clone(), |_| serde_json::json!({}))),
+        )
+        .await;
+        // ai-runtime kept one job running, finished one and never heard of the rest
+        let runtime_url = serve(Router::new().route("/job/:id/status", get(|Path(job_id): Path<String>| async move {
+            match job_id.as_str() {
+                "job-running" => Ok(Json(serde_json::json!({ "job_id": job_id, "status": "Running" }))),
+                "job-finished" => Ok(Json(serde_json::json!({ "job_id": job_id, "status": "Completed" }))),
+                _ => Err(StatusCode::NOT_FOUND),
+            }
+        })))
+        .await;
+
+        let store: Arc<dyn JobStore> = Arc::new(MemoryJobStore::default());
+        let before = service_state_with_store(scheduler_url.clone(), runtime_url.clone(), ManualClock::new(T0), store.clone());
+        {
+            let mut jobs = before.jobs.write().await;
+            for (job_id, status) in [
+                ("job-queued", JobStatus::Queued),
+                ("job-assigned", JobStatus::Assigned),
+                ("job-running", JobStatus::Running),
+                ("job-finished", JobStatus::Running),
+                ("job-lost", JobStatus::Running),
+                ("job-done", JobStatus::Running),
+            ] {
+                let mut job = queued_job(job_id, "model-1");
+                job.status = status;
+                job.assigned_node = Some("node-1".to_string()).filter(|_| job.status != JobStatus::Queued);
+                jobs.insert(job_id.to_string(), job);
+            }
+        }
+        for job_id in ["job-queued", "job-assigned", "job-running", "job-finished", "job-lost", "job-done"] {
+            persist_job(&before, job_id).await;
+        }
+        // Reported changes are written through
+        let done = JobProgressRequest { progress: 1.0, status: Some(JobStatus::Completed), ..Default::default() };
+        job_progress(State(before.clone()), Path("job-done".to_string()), Json(done)).await.unwrap();
+        assert_eq!(store.load_job("job-done").unwrap().unwrap().status, JobStatus::Completed);
+        drop(before);
+
+        // A new daemon on the same store
+        let after = service_state_with_store(scheduler_url, runtime_url, ManualClock::new(T0 + 600), store.clone());
+        let mut pending = restore_jobs(&after).await.unwrap();
+        pending.sort();
+        assert_eq!(pending, vec!["job-assigned", "job-finished", "job-lost", "job-queued", "job-running"]);
+        assert_eq!(after.jobs.read().await.len(), 6);
+        assert_eq!(after.jobs.read().await["job-running"].stale_since, Some(T0 + 600));
+        assert_eq!(store.load_job("job-lost").unwrap().unwrap().stale_since, Some(T0 + 600));
+        assert_eq!(after.jobs.read().await["job-done"].stale_since, None);
+
+        resume_restored_jobs(after.clone(), pending, BackoffPolicy::every(std::time::Duration::from_secs(1))).await;
+        let jobs = after.jobs.read().await;
+        assert!(jobs.values().all(|job| job.stale_since.is_none()));
+
+        // Queued jobs are placed again; one the runtime never started goes back for reassignment
+        let scheduled: Vec<_> = scheduled.lock().unwrap().iter().map(|req| req["job_id"].clone()).collect();
+        assert_eq!(scheduled, vec!["job-queued"]);
+        assert_eq!(rejected.lock().unwrap().len(), 1);
+        assert_eq!(rejected.lock().unwrap()[0]["node_pubkey"], "node-1");
+        assert_eq!((&jobs["job-assigned"].status, jobs["job-assigned"].assigned_node.as_deref()), (&JobStatus::Queued, None));
+
+        // Running jobs follow what ai-runtime says became of them
+        assert_eq!(jobs["job-running"].status, JobStatus::Running);
+        assert_eq!(jobs["job-finished"].status, JobStatus::Completed);
+        assert_eq!(jobs["job-finished"].completed_at, Some(T0 + 600));
+        assert_eq!(jobs["job-lost"].status, JobStatus::Failed);
+        let reason = jobs["job-lost"].terminal_reason.clone().unwrap();
+        assert_eq!(reason.kind, TerminalReasonKind::RuntimeError);
+        assert!(reason.detail.starts_with("Lost while ai-jobd was down"));
+
+        // And the store agrees
+        for job in jobs.values() {
+            let saved = store.load_job(&job.job_id).unwrap().unwrap();
+            assert_eq!((&saved.status, saved.stale_since), (&job.status, job.stale_since), "{}", job.job_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identical_resubmission_returns_the_cached_result() {
+        let schedules: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", schedules.clone(), |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = service_state(scheduler_url, "http://127.0.0.1:9".to_string());
+        {
+            let state = Arc::get_mut(&mut state).unwrap();
+            state.policy_gate = Arc::new(PolicyGate::new(policy_url));
+            state.contract_client = Arc::new(ContractClient::new(rpc.url()));
+        }
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        let dataset_id = "dataset-imagenet-1k-train-0000000";
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+            artifacts.register_dataset(dataset_id, "bafy-dataset");
+        }
+        let train = |did: &str, seed: u64, force_rerun: bool| -> TrainJobRequest {
+            serde_json::from_value(serde_json::json!({
+                "model_id": model_id, "dataset_id": dataset_id, "submitter_did": did,
+                "params": { "epochs": 3, "batch_size": 64, "learning_rate": 0.001, "optimizer": "adam", "checkpoint_interval": 500, "seed": seed },
+                "budget": 1000, "force_rerun": force_rerun,
+            }))
+            .unwrap()
+        };
+        let submit = |req: TrainJobRequest| {
+            let state = state.clone();
+            async move { submit_train_job(State(state), HeaderMap::new(), Json(req)).await.map(|(_, Json(response))| response) }
+        };
+
+        let first = submit(train("did:artha:alice", 42, false)).await.unwrap();
+        assert!(!first.cached);
+        let completed = JobProgressRequest {
+            progress: 1.0,
+            status: Some(JobStatus::Completed),
+            output_cid: Some("bafy-weights".to_string()),
+            ..Default::default()
+        };
+        job_progress(State(state.clone()), Path(first.job_id.clone()), Json(completed)).await.unwrap();
+
+        // Byte-identical: the completed job answers, and nothing new runs or is charged
+        let again = submit(train("did:artha:alice", 42, false)).await.unwrap();
+        assert!(again.cached);
+        assert_eq!((again.job_id.as_str(), &again.status, again.estimated_cost), (first.job_id.as_str(), &JobStatus::Completed, 0));
+        assert_eq!(state.jobs.read().await.len(), 1);
+        assert_eq!(schedules.lock().unwrap().len(), 1);
+        let body = serde_json::to_value(&again).unwrap();
+        assert_eq!(body["cached"], true);
+        assert!(serde_json::to_value(&first).unwrap().get("cached").is_none());
+
+        // A different seed is a different job
+        let reseeded = submit(train("did:artha:alice", 43, false)).await.unwrap();
+        assert!(!reseeded.cached);
+        assert_ne!(reseeded.job_id, first.job_id);
+
+        // So is one asked to run again, and one from a submitter who doesn't own the result
+        let forced = submit(train("did:artha:alice", 42, true)).await.unwrap();
+        assert!(!forced.cached);
+        let other = submit(train("did:artha:bob", 42, false)).await.unwrap();
+        assert!(!other.cached);
+        assert_eq!(state.jobs.read().await.len(), 4);
+        assert_eq!(schedules.lock().unwrap().len(), 4);
+
+        // The cached job is forgotten once retention GC evicts it
+        gc_finished_jobs(&state, T0 + 7200).await;
+        let after_gc = submit(train("did:artha:alice", 42, false)).await.unwrap();
+        assert!(!after_gc.cached);
+    }
+
+    #[tokio::test]
+    async fn test_job_records_survive_a_restart_on_disk() {
+        let policy_url = serve(recording_route("/policy/check", Arc::default(), |_| serde_json::json!({ "allowed": true }))).await;
+        let scheduler_url = serve(recording_route("/schedule", Arc::default(), |_| serde_json::json!({}))).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let data_dir = std::env::temp_dir().join(format!("jobd-jobs-{}", uuid::Uuid::new_v4()));
+        let data_dir = data_dir.to_str().unwrap().to_string();
+        let daemon = |store: Arc<dyn JobStore>| {
+            let mut state = service_state_with_store(scheduler_url.clone(), "http://127.0.0.1:9".to_string(), ManualClock::new(T0), store);
+            let inner = Arc::get_mut(&mut state).unwrap();
+            inner.policy_gate = Arc::new(PolicyGate::new(policy_url.clone()));
+            inner.contract_client = Arc::new(ContractClient::new(rpc.url()));
+            state
+        };
+
+        let state = daemon(Arc::new(FileJobStore::open(&data_dir).unwrap()));
+        let model_id = "model-resnet50-imagenet-v1-000000";
+        let dataset_id = "dataset-imagenet-1k-train-0000000";
+        {
+            let mut artifacts = state.artifacts.write().await;
+            artifacts.register_model(&Namespace::Shared, model_id, "bafy-model", Some("resnet"), "1.0");
+            artifacts.register_dataset(dataset_id, "bafy-dataset");
+        }
+        let req: TrainJobRequest = serde_json::from_value(serde_json::json!({
+            "model_id": model_id, "dataset_id": dataset_id, "submitter_did": "did:artha:alice",
+            "params": { "epochs": 2, "batch_size": 32, "learning_rate": 0.001, "optimizer": "adam", "checkpoint_interval": 100 },
+            "budget": 1000, "labels": { "team": "vision" },
+        }))
+        .unwrap();
+        let (_, Json(submitted)) = submit_train_job(State(state.clone()), HeaderMap::new(), Json(req)).await.unwrap();
+        let job_id = submitted.job_id;
+        assert!(std::path::Path::new(&data_dir).join(format!("{}.json", job_id)).exists());
+        let before = serde_json::to_value(&state.jobs.read().await[&job_id]).unwrap();
+        drop(state);
+
+        // A restarted daemon finds it without restoring first, then by restoring
+        let state = daemon(Arc::new(FileJobStore::open(&data_dir).unwrap()));
+        let status = get_job_status(State(state.clone()), HeaderMap::new(), Path(job_id.clone())).await.unwrap().0;
+        assert_eq!(status.job.status, JobStatus::Queued);
+        assert_eq!(status.job.labels["team"], "vision");
+        let state = daemon(Arc::new(FileJobStore::open(&data_dir).unwrap()));
+        assert_eq!(restore_jobs(&state).await.unwrap(), vec![job_id.clone()]);
+        assert_eq!(serde_json::to_value(&state.jobs.read().await[&job_id]).unwrap(), before);
+
+        // Status transitions are on disk as soon as they happen
+        let failed = JobProgressRequest { status: Some(JobStatus::Failed), failure_reason: Some("boom".to_string()), ..Default::default() };
+        job_progress(State(state.clone()), Path(job_id.clone()), Json(failed)).await.unwrap();
+        let saved = FileJobStore::open(&data_dir).unwrap().load_job(&job_id).unwrap().unwrap();
+        assert_eq!(saved.status, JobStatus::Failed);
+        assert_eq!(saved.terminal_reason.unwrap().detail, "boom");
+
+        // Ids that would leave the data dir are refused
+        assert!(FileJobStore::open(&data_dir).unwrap().load_job("../escape").is_err());
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
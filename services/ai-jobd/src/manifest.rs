@@ -0,0 +1,280 @@
+//! Job Manifests
+//! Lockfiles capturing every resolved input of a job (model/dataset CIDs,
+//! params hash, runtime image digest) so it can be re-run exactly later
+
+use crate::schema::ModelSchema;
+use artha_tenant::Namespace;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobManifest {
+    pub version: u32,
+    pub job_type: String,           // "train", "infer"
+    pub model_ref: String,          // As submitted, e.g. "resnet@latest"
+    pub model_id: String,           // Resolved model id
+    pub model_cid: String,
+    pub dataset_id: Option<String>,
+    pub dataset_cid: Option<String>,
+    pub input_cid: Option<String>,  // Inference input
+    pub params: serde_json::Value,  // Full job parameters
+    pub params_hash: String,
+    pub runtime_image_digest: String,
+    pub created_at: u64,
+    pub signature: String,
+}
+
+impl JobManifest {
+    fn digest(&self, key: &str) -> String {
+        let mut unsigned = self.clone();
+        unsigned.signature = String::new();
+        let mut hasher = Sha256::new();
+        hasher.update(b"ARTHA_JOB_MANIFEST:");
+        hasher.update(key.as_bytes());
+        hasher.update(serde_json::to_vec(&unsigned).unwrap_or_default());
+        format!("0x{:x}", hasher.finalize())
+    }
+
+    pub fn sign(mut self, key: &str) -> Self {
+        self.signature = self.digest(key);
+        self
+    }
+
+    pub fn verify(&self, key: &str) -> bool {
+        self.signature == self.digest(key)
+    }
+}
+
+/// JSON with object keys sorted and no whitespace, so the encoding depends
+/// only on the values and not on struct field order or map insertion order
+pub fn canonical_json(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| format!("{}:{}", Value::String(key.clone()), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        scalar => scalar.to_string(),
+    }
+}
+
+/// `params_hash` of a job: SHA-256 over the canonical JSON of its params
+pub fn params_hash(params: &impl Serialize) -> String {
+    let value = serde_json::to_value(params).unwrap_or_default();
+    format!("0x{:x}", Sha256::digest(canonical_json(&value)))
+}
+
+/// Runtime a model declares it needs; checked against the assigned node's
+/// ai-runtime (`/capabilities/check`) before a job is launched there
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RuntimeRequirements {
+    pub framework: String,                 // "torch", "tf", "jax", "agent", ...
+    #[serde(default)]
+    pub framework_version: Option<String>, // "2.1" (prefix) or ">=2.0"
+    #[serde(default)]
+    pub min_cuda: Option<String>,
+    #[serde(default)]
+    pub image_digest: Option<String>,
+    #[serde(default)]
+    pub tee_required: bool,
+}
+
+/// Bounds of the stream window a dataset version was cut from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatasetWindow {
+    pub by: String, // "count" (event offsets) or "time" (milliseconds)
+    pub start: u64,
+    pub end: u64, // Exclusive
+}
+
+/// A registered `name@version` of a dataset
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DatasetVersion {
+    pub dataset_id: String,
+    #[serde(rename = "tenant", skip_serializing_if = "Namespace::is_shared")]
+    pub namespace: Namespace, // Names are unique within it
+    pub name: String,
+    pub version: String,
+    pub root_cid: String,
+    pub samples: Option<u64>,
+    pub window: Option<DatasetWindow>,
+    pub tags: Vec<String>,
+    pub registered_at: u64,
+}
+
+/// Local index of registered model and dataset content, plus movable model
+/// tags. Tags and dataset names are per tenant namespace; ids are global.
+#[derive(Debug, Default)]
+pub struct ArtifactRegistry {
+    model_cids: HashMap<String, String>,   // model_id -> CID
+    model_tags: HashMap<(Namespace, String), String>, // (namespace, "name@tag") -> model_id
+    dataset_cids: HashMap<String, String>, // dataset_id -> root CID
+    dataset_consent: HashMap<String, Vec<String>>, // dataset_id -> consent and license VCs
+    model_requirements: HashMap<String, RuntimeRequirements>, // model_id -> declared runtime
+    model_schemas: HashMap<String, ModelSchema>, // model_id -> declared inputs/outputs
+    model_parents: HashMap<String, String>, // model_id -> model it was derived from
+    model_cards: HashMap<String, String>, // model_id -> card CID
+    dataset_versions: Vec<DatasetVersion>, // In registration order
+}
+
+impl ArtifactRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a registered model and point `name@version` and `name@latest`
+    /// in `namespace` at it
+    pub fn register_model(&mut self, namespace: &Namespace, model_id: &str, model_cid: &str, name: Option<&str>, version: &str) {
+        self.model_cids.insert(model_id.to_string(), model_cid.to_string());
+        if let Some(name) = name {
+            for tag in [version, "latest"] {
+                self.model_tags.insert((namespace.clone(), format!("{}@{}", name, tag)), model_id.to_string());
+            }
+        }
+    }
+
+    pub fn set_model_requirements(&mut self, model_id: &str, requirements: RuntimeRequirements) {
+        self.model_requirements.insert(model_id.to_string(), requirements);
+    }
+
+    pub fn model_requirements(&self, model_id: &str) -> Option<&RuntimeRequirements> {
+        self.model_requirements.get(model_id)
+    }
+
+    pub fn set_model_schema(&mut self, model_id: &str, schema: ModelSchema) {
+        self.model_schemas.insert(model_id.to_string(), schema);
+    }
+
+    pub fn model_schema(&self, model_id: &str) -> Option<&ModelSchema> {
+        self.model_schemas.get(model_id)
+    }
+
+    pub fn set_model_card(&mut self, model_id: &str, card_cid: &str) {
+        self.model_cards.insert(model_id.to_string(), card_cid.to_string());
+    }
+
+    pub fn model_card(&self, model_id: &str) -> Option<&str> {
+        self.model_cards.get(model_id).map(String::as_str)
+    }
+
+    pub fn set_parent(&mut self, model_id: &str, parent_id: &str) {
+        self.model_parents.insert(model_id.to_string(), parent_id.to_string());
+    }
+
+    /// Ancestors of a model, parent first
+    pub fn lineage(&self, model_id: &str) -> Vec<String> {
+        let mut lineage: Vec<String> = Vec::new();
+        let mut current = model_id;
+        while let Some(parent) = self.model_parents.get(current) {
+            if parent == model_id || lineage.contains(parent) {
+                break;
+            }
+            lineage.push(parent.clone());
+            current = parent;
+        }
+        lineage
+    }
+
+    /// Point `name@alias` in `namespace` at a model version (e.g.
+    /// `name@serving`). Returns the previous target.
+    pub fn set_alias(&mut self, namespace: &Namespace, name: &str, alias: &str, model_id: &str) -> Option<String> {
+        self.model_tags.insert((namespace.clone(), format!("{}@{}", name, alias)), model_id.to_string())
+    }
+
+    /// Model `name@alias` points at, as seen from `namespace`
+    pub fn alias(&self, namespace: &Namespace, name: &str, alias: &str) -> Option<&String> {
+        self.tag(namespace, &format!("{}@{}", name, alias))
+    }
+
+    fn tag(&self, namespace: &Namespace, tag: &str) -> Option<&String> {
+        namespace
+            .lookup_path()
+            .into_iter()
+            .find_map(|namespace| self.model_tags.get(&(namespace.clone(), tag.to_string())))
+    }
+
+    pub fn register_dataset(&mut self, dataset_id: &str, root_cid: &str) {
+        self.dataset_cids.insert(dataset_id.to_string(), root_cid.to_string());
+    }
+
+    /// Reference the consent and license VCs a dataset's data was collected under
+    pub fn set_dataset_consent(&mut self, dataset_id: &str, vc_hashes: Vec<String>) {
+        self.dataset_consent.insert(dataset_id.to_string(), vc_hashes);
+    }
+
+    pub fn dataset_consent(&self, dataset_id: &str) -> &[String] {
+        self.dataset_consent.get(dataset_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn register_dataset_version(&mut self, version: DatasetVersion) {
+        self.register_dataset(&version.dataset_id, &version.root_cid);
+        self.dataset_versions.push(version);
+    }
+
+    /// `name@version` registered in exactly `namespace`
+    pub fn dataset_version(&self, namespace: &Namespace, name: &str, version: &str) -> Option<&DatasetVersion> {
+        self.dataset_versions.iter().find(|v| v.namespace == *namespace && v.name == name && v.version == version)
+    }
+
+    /// Versions of every dataset `viewer` sees whose name starts with
+    /// `prefix`, oldest first
+    pub fn dataset_versions(&self, viewer: &Namespace, prefix: &str) -> Vec<&DatasetVersion> {
+        self.dataset_versions.iter().filter(|v| viewer.sees(&v.namespace) && v.name.starts_with(prefix)).collect()
+    }
+
+    /// Resolve a model reference to `(model_id, model_cid)`. Tagged references
+    /// must resolve through the tag table, as seen from `namespace`; plain ids
+    /// not indexed locally are on-chain ids and lock to themselves.
+    pub fn resolve_model(&self, namespace: &Namespace, model_ref: &str) -> Result<(String, String), String> {
+        let model_id = if model_ref.contains('@') {
+            self.tag(namespace, model_ref)
+                .cloned()
+                .ok_or_else(|| format!("Unknown model tag {}", model_ref))?
+        } else {
+            model_ref.to_string()
+        };
+        let model_cid = self.model_cids.get(&model_id).cloned().unwrap_or_else(|| model_id.clone());
+        Ok((model_id, model_cid))
+    }
+
+    pub fn resolve_dataset(&self, dataset_id: &str) -> String {
+        self.dataset_cids.get(dataset_id).cloned().unwrap_or_else(|| dataset_id.to_string())
+    }
+
+    /// Resolve a submission's model and dataset references into an unsigned
+    /// manifest; the caller fills in parameters and the runtime image
+    pub fn lock(
+        &self,
+        namespace: &Namespace,
+        job_type: &str,
+        model_ref: &str,
+        dataset_id: Option<&str>,
+        input_cid: Option<&str>,
+    ) -> Result<JobManifest, String> {
+        let (model_id, model_cid) = self.resolve_model(namespace, model_ref)?;
+        Ok(JobManifest {
+            version: MANIFEST_VERSION,
+            job_type: job_type.to_string(),
+            model_ref: model_ref.to_string(),
+            model_id,
+            model_cid,
+            dataset_id: dataset_id.map(|d| d.to_string()),
+            dataset_cid: dataset_id.map(|d| self.resolve_dataset(d)),
+            input_cid: input_cid.map(|c| c.to_string()),
+            params: serde_json::Value::Null,
+            params_hash: String::new(),
+            runtime_image_digest: String::new(),
+            created_at: 0,
+            signature: String::new(),
+        })
+    }
+}
@@ -0,0 +1,334 @@
+//! Dataset Marketplace
+//! Listings for paid training use of registered datasets, access grants
+//! purchased by consumers, per-job/per-epoch usage debits, and settlement splits
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ListingPrice {
+    PerJob(u64),
+    PerEpoch(u64),
+}
+
+impl ListingPrice {
+    pub fn unit_price(&self) -> u64 {
+        match self {
+            ListingPrice::PerJob(price) | ListingPrice::PerEpoch(price) => *price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ListingStatus {
+    Active,
+    Suspended,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetListing {
+    pub listing_id: String,
+    pub dataset_id: String,
+    pub owner_did: String,
+    pub price: ListingPrice,
+    pub allowed_job_types: Vec<String>, // "train", "federated", ...
+    pub license_cid: String,
+    pub max_concurrent_consumers: Option<u32>,
+    pub tags: Vec<String>,
+    pub architectures: Vec<String>, // Compatible model architectures
+    pub status: ListingStatus,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessGrant {
+    pub grant_id: String,
+    pub listing_id: String,
+    pub dataset_id: String,
+    pub consumer_did: String,
+    pub allowance: u64, // Jobs or epochs, depending on listing price
+    pub used: u64,
+    pub reserved: u64,
+    pub unit_price: u64,
+    pub expires_at: u64,
+    pub escrow_tx: Option<String>, // None while the purchase's escrow is in flight
+}
+
+/// A grant being bought: `units` jobs or epochs of a listing for `duration_secs`
+#[derive(Debug, Clone)]
+pub struct PurchaseRequest {
+    pub grant_id: String,
+    pub listing_id: String,
+    pub consumer_did: String,
+    pub units: u64,
+    pub duration_secs: u64,
+}
+
+impl AccessGrant {
+    pub fn remaining(&self) -> u64 {
+        self.allowance.saturating_sub(self.used + self.reserved)
+    }
+}
+
+/// Usage of a grant by a single job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobUsage {
+    pub grant_id: String,
+    pub per_epoch: bool,
+    pub reserved: u64,
+    pub used: u64,
+}
+
+/// Owner payout for a finished job, routed through the receipts pipeline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Settlement {
+    pub job_id: String,
+    pub grant_id: String,
+    pub owner_did: String,
+    pub units: u64,
+    pub gross: u64,
+    pub owner_share: u64,
+    pub platform_fee: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Marketplace {
+    listings: HashMap<String, DatasetListing>,
+    grants: HashMap<String, AccessGrant>,
+    usage: HashMap<String, JobUsage>, // job_id -> usage
+    platform_fee_bps: u64,
+}
+
+impl Marketplace {
+    pub fn new(platform_fee_bps: u64) -> Self {
+        Marketplace {
+            platform_fee_bps: platform_fee_bps.min(10_000),
+            ..Default::default()
+        }
+    }
+
+    pub fn create_listing(&mut self, listing: DatasetListing) -> Result<(), String> {
+        if listing.price.unit_price() == 0 {
+            return Err("Listing price must be non-zero".to_string());
+        }
+        if self.listings.values().any(|l| l.dataset_id == listing.dataset_id && l.status == ListingStatus::Active) {
+            return Err(format!("Dataset {} already has an active listing", listing.dataset_id));
+        }
+        self.listings.insert(listing.listing_id.clone(), listing);
+        Ok(())
+    }
+
+    pub fn listing(&self, listing_id: &str) -> Option<&DatasetListing> {
+        self.listings.get(listing_id)
+    }
+
+    /// Suspending stops new purchases; grants already sold stay usable
+    pub fn set_listing_status(&mut self, listing_id: &str, owner_did: &str, status: ListingStatus) -> Result<(), String> {
+        let listing = self.listings.get_mut(listing_id).ok_or("Listing not found")?;
+        if listing.owner_did != owner_did {
+            return Err("Only the dataset owner can change a listing".to_string());
+        }
+        listing.status = status;
+        Ok(())
+    }
+
+    pub fn search(&self, tag: Option<&str>, architecture: Option<&str>) -> Vec<DatasetListing> {
+        let mut results: Vec<DatasetListing> = self.listings
+            .values()
+            .filter(|l| l.status == ListingStatus::Active)
+            .filter(|l| tag.is_none_or(|t| l.tags.iter().any(|x| x == t)))
+            .filter(|l| architecture.is_none_or(|a| l.architectures.is_empty() || l.architectures.iter().any(|x| x == a)))
+            .cloned()
+            .collect();
+        results.sort_by_key(|l| l.created_at);
+        results
+    }
+
+    /// Hold a grant for a purchase whose escrow is still to be paid. A held
+    /// grant counts toward the listing's consumers but gives no access until
+    /// `confirm_purchase`; `cancel_purchase` drops it if the escrow fails.
+    pub fn purchase(&mut self, req: PurchaseRequest, now: u64) -> Result<AccessGrant, String> {
+        let PurchaseRequest { grant_id, listing_id, consumer_did, units, duration_secs } = req;
+        let listing = self.listings.get(&listing_id).ok_or("Listing not found")?;
+        if listing.status != ListingStatus::Active {
+            return Err("Listing is suspended".to_string());
+        }
+        if units == 0 {
+            return Err("Usage allowance must be non-zero".to_string());
+        }
+
+        if let Some(max) = listing.max_concurrent_consumers {
+            let consumers: HashSet<&str> = self.grants
+                .values()
+                .filter(|g| g.listing_id == listing_id && g.expires_at > now && g.remaining() + g.reserved > 0)
+                .map(|g| g.consumer_did.as_str())
+                .collect();
+            if !consumers.contains(consumer_did.as_str()) && consumers.len() >= max as usize {
+                return Err("Listing has reached its maximum concurrent consumers".to_string());
+            }
+        }
+
+        let grant = AccessGrant {
+            grant_id: grant_id.clone(),
+            listing_id,
+            dataset_id: listing.dataset_id.clone(),
+            consumer_did,
+            allowance: units,
+            used: 0,
+            reserved: 0,
+            unit_price: listing.price.unit_price(),
+            expires_at: now + duration_secs,
+            escrow_tx: None,
+        };
+        self.grants.insert(grant_id, grant.clone());
+        Ok(grant)
+    }
+
+    /// Give a held grant access once its escrow transaction is mined
+    pub fn confirm_purchase(&mut self, grant_id: &str, escrow_tx: String) -> Option<AccessGrant> {
+        let grant = self.grants.get_mut(grant_id)?;
+        grant.escrow_tx = Some(escrow_tx);
+        Some(grant.clone())
+    }
+
+    /// Drop a held grant whose escrow failed
+    pub fn cancel_purchase(&mut self, grant_id: &str) {
+        if self.grants.get(grant_id).is_some_and(|g| g.escrow_tx.is_none()) {
+            self.grants.remove(grant_id);
+        }
+    }
+
+    pub fn grant(&self, grant_id: &str) -> Option<&AccessGrant> {
+        self.grants.get(grant_id)
+    }
+
+    pub fn grants_for(&self, consumer_did: &str) -> Vec<AccessGrant> {
+        self.grants.values().filter(|g| g.consumer_did == consumer_did).cloned().collect()
+    }
+
+    /// Whether the dataset is offered through the marketplace at all.
+    /// Unlisted datasets keep the policy gate's default access rules.
+    pub fn is_listed(&self, dataset_id: &str) -> bool {
+        self.listings.values().any(|l| l.dataset_id == dataset_id)
+    }
+
+    /// Units a job of this type needs from a grant before it may start
+    pub fn units_required(&self, grant: &AccessGrant, epochs: u32) -> u64 {
+        match self.listings.get(&grant.listing_id).map(|l| &l.price) {
+            Some(ListingPrice::PerEpoch(_)) => epochs as u64,
+            _ => 1,
+        }
+    }
+
+    /// Find a grant that lets the consumer run a job of `job_type` needing
+    /// `epochs` epochs on the dataset
+    pub fn check_access(
+        &self,
+        dataset_id: &str,
+        consumer_did: &str,
+        job_type: &str,
+        epochs: u32,
+        now: u64,
+    ) -> Result<&AccessGrant, String> {
+        let mut candidates: Vec<&AccessGrant> = self.grants
+            .values()
+            .filter(|g| g.dataset_id == dataset_id && g.consumer_did == consumer_did && g.escrow_tx.is_some())
+            .collect();
+        if candidates.is_empty() {
+            return Err("No access grant for dataset".to_string());
+        }
+        candidates.retain(|g| g.expires_at > now);
+        if candidates.is_empty() {
+            return Err("Access grant expired".to_string());
+        }
+        candidates.retain(|g| {
+            self.listings
+                .get(&g.listing_id)
+                .is_some_and(|l| l.allowed_job_types.is_empty() || l.allowed_job_types.iter().any(|t| t == job_type))
+        });
+        if candidates.is_empty() {
+            return Err(format!("Job type {} not allowed by listing", job_type));
+        }
+        candidates.sort_by_key(|g| g.expires_at);
+        candidates
+            .into_iter()
+            .find(|g| g.remaining() >= self.units_required(g, epochs))
+            .ok_or_else(|| "Access grant allowance exhausted".to_string())
+    }
+
+    /// Reserve allowance for a submitted job. Per-job listings debit the job
+    /// immediately; per-epoch listings reserve the requested epochs and debit
+    /// them as progress updates report completed epochs.
+    pub fn begin_usage(&mut self, grant_id: &str, job_id: &str, epochs: u32) -> Result<(), String> {
+        let grant = self.grants.get(grant_id).ok_or("Grant not found")?;
+        let units = self.units_required(grant, epochs);
+        let per_epoch = matches!(
+            self.listings.get(&grant.listing_id).map(|l| &l.price),
+            Some(ListingPrice::PerEpoch(_))
+        );
+        if grant.remaining() < units {
+            return Err("Access grant allowance exhausted".to_string());
+        }
+
+        let grant = self.grants.get_mut(grant_id).ok_or("Grant not found")?;
+        let usage = if per_epoch {
+            grant.reserved += units;
+            JobUsage { grant_id: grant_id.to_string(), per_epoch, reserved: units, used: 0 }
+        } else {
+            grant.used += units;
+            JobUsage { grant_id: grant_id.to_string(), per_epoch, reserved: 0, used: units }
+        };
+        self.usage.insert(job_id.to_string(), usage);
+        Ok(())
+    }
+
+    /// Debit epochs reported complete for a per-epoch job. Returns newly debited epochs.
+    pub fn record_epochs(&mut self, job_id: &str, epochs_completed: u64) -> u64 {
+        let Some(usage) = self.usage.get_mut(job_id) else { return 0 };
+        if !usage.per_epoch || epochs_completed <= usage.used {
+            return 0;
+        }
+        let delta = (epochs_completed - usage.used).min(usage.reserved);
+        usage.used += delta;
+        usage.reserved -= delta;
+        if let Some(grant) = self.grants.get_mut(&usage.grant_id) {
+            grant.reserved -= delta;
+            grant.used += delta;
+        }
+        delta
+    }
+
+    /// Return all allowance held by a job cancelled before it ran
+    pub fn cancel_usage(&mut self, job_id: &str) {
+        if let Some(usage) = self.usage.remove(job_id) {
+            if let Some(grant) = self.grants.get_mut(&usage.grant_id) {
+                grant.reserved -= usage.reserved;
+                grant.used -= usage.used;
+            }
+        }
+    }
+
+    /// Release unused reservation and compute the owner settlement for a finished job
+    pub fn finish_usage(&mut self, job_id: &str) -> Option<Settlement> {
+        let usage = self.usage.remove(job_id)?;
+        let grant = self.grants.get_mut(&usage.grant_id)?;
+        grant.reserved -= usage.reserved;
+
+        if usage.used == 0 {
+            return None;
+        }
+        let owner_did = self.listings.get(&grant.listing_id)?.owner_did.clone();
+        let gross = usage.used * grant.unit_price;
+        let platform_fee = gross * self.platform_fee_bps / 10_000;
+
+        Some(Settlement {
+            job_id: job_id.to_string(),
+            grant_id: usage.grant_id,
+            owner_did,
+            units: usage.used,
+            gross,
+            owner_share: gross - platform_fee,
+            platform_fee,
+        })
+    }
+}
@@ -0,0 +1,121 @@
+//! Live Migration
+//! Jobs submitted with `live_migration` can be moved off a node being drained
+//! or reclaimed without falling back to their last periodic checkpoint.
+//! ai-runtime asks the container for an immediate checkpoint and reports the
+//! export here as a `migratable` completion; the job is re-queued to resume
+//! from that checkpoint with the source node excluded, and the scheduler places
+//! it as usual. Each handoff is one `MigrationRecord` on the job, from the
+//! request to the job running again on its new node.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrateRequest {
+    pub initiated_by: String, // "scheduler:drain", "scheduler:reclaim", an operator DID, ...
+    pub reason: String,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>, // How long the container has to checkpoint; ai-runtime's default if unset
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationMode {
+    Cooperative, // The container checkpointed on request
+    Degraded,    // It didn't answer in time and was stopped at its last periodic checkpoint
+}
+
+/// ai-runtime's report of an exported job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationHandoff {
+    pub initiated_by: String,
+    pub reason: String,
+    pub mode: MigrationMode,
+    pub checkpoint: Option<String>,
+    pub resume_from: Option<String>, // Checkpoint CID; None if the job never checkpointed
+    pub steps_lost: Option<u64>,
+    pub requested_at: u64,
+    pub exported_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationPhase {
+    Requested, // ai-runtime asked the container to checkpoint
+    Exported,  // Off the source node, waiting for a new placement
+    Resumed,   // Running on the target node
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationRecord {
+    pub initiated_by: String,
+    pub reason: String,
+    pub phase: MigrationPhase,
+    pub source_node: String,
+    pub target_node: Option<String>,
+    pub mode: Option<MigrationMode>, // Set on export
+    pub resume_from: Option<String>,
+    pub steps_lost: Option<u64>, // 0 when cooperative; None when unknown
+    pub requested_at: u64,
+    pub exported_at: Option<u64>,
+    pub resumed_at: Option<u64>,
+    pub gap_secs: Option<u64>, // Wall-clock time the job wasn't running: export to resumption
+    pub failure: Option<String>,
+}
+
+impl MigrationRecord {
+    pub fn requested(initiated_by: &str, reason: &str, source_node: &str, at: u64) -> Self {
+        MigrationRecord {
+            initiated_by: initiated_by.to_string(),
+            reason: reason.to_string(),
+            phase: MigrationPhase::Requested,
+            source_node: source_node.to_string(),
+            target_node: None,
+            mode: None,
+            resume_from: None,
+            steps_lost: None,
+            requested_at: at,
+            exported_at: None,
+            resumed_at: None,
+            gap_secs: None,
+            failure: None,
+        }
+    }
+
+    pub fn exported(&mut self, handoff: &MigrationHandoff, at: u64) {
+        self.phase = MigrationPhase::Exported;
+        self.mode = Some(handoff.mode);
+        self.resume_from = handoff.resume_from.clone();
+        self.steps_lost = handoff.steps_lost;
+        self.exported_at = Some(at);
+    }
+
+    pub fn resumed(&mut self, target_node: &str, at: u64) {
+        self.phase = MigrationPhase::Resumed;
+        self.target_node = Some(target_node.to_string());
+        self.resumed_at = Some(at);
+        self.gap_secs = self.exported_at.map(|exported| at.saturating_sub(exported));
+    }
+
+    pub fn failed(&mut self, failure: &str) {
+        self.phase = MigrationPhase::Failed;
+        self.failure = Some(failure.to_string());
+    }
+
+    pub fn is_open(&self) -> bool {
+        matches!(self.phase, MigrationPhase::Requested | MigrationPhase::Exported)
+    }
+}
+
+/// The job's migration still in flight, if any
+pub fn open(records: &mut [MigrationRecord]) -> Option<&mut MigrationRecord> {
+    records.last_mut().filter(|record| record.is_open())
+}
+
+/// Checkpoint a re-queued job resumes from once placed
+pub fn resume_from(records: &[MigrationRecord]) -> Option<String> {
+    records
+        .last()
+        .filter(|record| record.phase == MigrationPhase::Exported)
+        .and_then(|record| record.resume_from.clone())
+}
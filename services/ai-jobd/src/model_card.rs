@@ -0,0 +1,60 @@
+//! Model Cards
+//! A model may be registered with a card: what it is meant for, what it was
+//! trained on, how it scored and where it falls short. The card body is
+//! stored in SVDB and its CID recorded on-chain against the model
+//! (`ModelRegistry.setModelCard`), so governance reviews, ethics checks and
+//! deprecation notices can point at one immutable document.
+//! `GET /ai/model/:id/card` reads it back.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelCard {
+    #[serde(default)]
+    pub intended_use: String,
+    #[serde(default)]
+    pub training_data: String, // Summary of the data the model was trained on
+    #[serde(default)]
+    pub eval_metrics: BTreeMap<String, f64>, // Metric name -> score
+    #[serde(default)]
+    pub limitations: String,
+    #[serde(default)]
+    pub out_of_scope_uses: Vec<String>,
+    #[serde(default)]
+    pub ethical_considerations: Option<String>,
+}
+
+/// A card as `GET /ai/model/:id/card` returns it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelCardView {
+    pub model_id: String,
+    pub card_cid: String,
+    pub card: ModelCard,
+}
+
+impl ModelCard {
+    /// Every required field is filled in; the error names the ones that aren't
+    pub fn check(&self) -> Result<(), String> {
+        let mut missing = Vec::new();
+        for (field, value) in [
+            ("intended_use", &self.intended_use),
+            ("training_data", &self.training_data),
+            ("limitations", &self.limitations),
+        ] {
+            if value.trim().is_empty() {
+                missing.push(field);
+            }
+        }
+        if self.eval_metrics.is_empty() {
+            missing.push("eval_metrics");
+        }
+        if !missing.is_empty() {
+            return Err(format!("Model card is missing {}", missing.join(", ")));
+        }
+        if let Some((name, _)) = self.eval_metrics.iter().find(|(_, score)| !score.is_finite()) {
+            return Err(format!("Eval metric '{}' is not a number", name));
+        }
+        Ok(())
+    }
+}
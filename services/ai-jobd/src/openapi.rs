@@ -0,0 +1,540 @@
+//! OpenAPI Documents
+//! One document per API version, generated from the operation table below so
+//! the versions can't drift apart by hand-editing. Internal callbacks
+//! (`/internal/*`) are not part of the public contract and are left out.
+//! Operation ids and the request and response schemas are what the SDK's
+//! API client generator builds typed clients from.
+
+use serde_json::{json, Value};
+
+use crate::versioning::{response_to_v2, screaming_snake, ApiVersion};
+
+struct Operation {
+    method: &'static str,
+    path: &'static str, // axum syntax; `:id` becomes `{id}`
+    summary: &'static str,
+    request: Option<&'static str>,  // JSON body schema name under components
+    response: Option<&'static str>, // Schema name under components
+}
+
+const fn op(
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    request: Option<&'static str>,
+    response: Option<&'static str>,
+) -> Operation {
+    Operation { method, path, summary, request, response }
+}
+
+const OPERATIONS: &[Operation] = &[
+    op("post", "/job/train", "Submit a training job", Some("TrainJobRequest"), Some("JobSubmitResponse")),
+    op("post", "/job/infer", "Submit an inference job", Some("InferJobRequest"), Some("JobSubmitResponse")),
+    op("post", "/job/agent", "Submit an agent job", Some("AgentJobRequest"), Some("JobSubmitResponse")),
+    op("post", "/job/stream", "Submit a streaming transform job with windowed dataset output", None, Some("JobSubmitResponse")),
+    op("post", "/job/quantize", "Quantize a trained model into a child model, bounded by accuracy drop", None, Some("JobSubmitResponse")),
+    op("post", "/job/rerun/:id", "Re-run a job from its locked manifest", None, Some("JobSubmitResponse")),
+    op("post", "/job/assigned", "Scheduler callback: job placed on a node", None, None),
+    op("post", "/job/attested", "ai-proofs callback: attestation outcome", None, None),
+    op("get", "/job/:id/status", "Job status", None, Some("JobStatusResponse")),
+    op("post", "/job/:id/cancel", "Cancel a queued, assigned or running job", None, None),
+    op("post", "/job/:id/migrate", "Live-migrate a running job off its node", None, Some("MigrationRecord")),
+    op("get", "/job/:id/logs", "Newest job log lines, or the full log from SVDB with full=true", None, None),
+    op("get", "/job/:id/logs/stream", "Job log lines as server-sent events", None, None),
+    op("get", "/jobs", "Jobs filtered by status, submitter and label.<key>=<value> labels, one page at a time", None, Some("JobPage")),
+    op("get", "/job/:id/provenance", "Job provenance record", None, None),
+    op("get", "/job/:id/archive", "On-chain references of an archived job", None, None),
+    op("get", "/job/:id/timeline", "Where the job's time and money went, phase by phase", None, Some("Timeline")),
+    op("post", "/job/:id/progress", "ai-runtime callback: progress and completion", None, None),
+    op("get", "/job/:id/receipt", "Wait for the job's receipt", None, None),
+    op("post", "/job/:id/output/link", "Issue a signed output download link", None, Some("LinkResponse")),
+    op("post", "/job/:id/output/grants", "Grant or revoke output access", None, None),
+    op("get", "/job/:id/output/audit", "Output access audit trail", None, None),
+    op("get", "/output/:output_id/download", "Download an output through a signed link", None, None),
+    op("post", "/pipeline/fanout", "Create a pipeline fan-out", None, None),
+    op("get", "/pipeline/fanout/:id", "Fan-out state and join decision", None, None),
+    op("post", "/workflow", "Submit a declarative multi-step workflow (JSON or YAML)", None, None),
+    op("get", "/workflow/:id", "Workflow status as a step-level DAG", None, None),
+    op("post", "/workflow/:id/cancel", "Cancel a workflow and its running jobs", None, None),
+    op("post", "/workflow/:id/step/:step/retry", "Retry a failed workflow step", None, None),
+    op("post", "/ai/dataset/register", "Register a dataset", Some("DatasetRegisterRequest"), None),
+    op("get", "/ai/dataset/list", "List dataset versions, optionally by name prefix", None, None),
+    op("get", "/ai/dataset/:id", "Dataset details", None, None),
+    op("post", "/ai/model/register", "Register a model", Some("ModelRegisterRequest"), None),
+    op("post", "/ai/model/register-batch", "Register a set of models, all or none", None, Some("ModelBatchRegisterResponse")),
+    op("get", "/ai/model/list", "List models", None, None),
+    op("get", "/ai/model/:id/lineage", "Model lineage", None, None),
+    op("get", "/ai/model/:id/card", "Model card: intended use, training data, evals, limitations", None, Some("ModelCardView")),
+    op("get", "/ai/model/:id/ab-route", "A/B routing split", None, None),
+    op("post", "/ai/model/:id/ab-route", "Set the A/B routing split", None, None),
+    op("delete", "/ai/model/:id/ab-route", "Remove the A/B routing split", None, None),
+    op("get", "/ai/model/:id/alias/:alias", "Resolve a model alias", None, None),
+    op("put", "/ai/model/:id/alias/:alias", "Point a model alias at a version", None, None),
+    op("post", "/market/listing", "Create a dataset listing", None, None),
+    op("get", "/market/listings", "Search dataset listings", None, None),
+    op("post", "/market/listing/:id/status", "Suspend or reactivate a listing", None, None),
+    op("post", "/market/listing/:id/purchase", "Purchase dataset access", None, None),
+    op("get", "/market/grants/:did", "Access grants held by a DID", None, None),
+    op("get", "/market/grant/:id", "Access grant details", None, None),
+    op("get", "/market/access/check", "Check dataset access", None, None),
+    op("post", "/reservations", "Book GPU-hours for a future window, escrowing the cost", Some("ReservationRequest"), Some("Reservation")),
+    op("get", "/reservations", "Reservations with utilization, filtered by submitter and status", None, Some("ReservationPage")),
+    op("get", "/search", "Search jobs, models, datasets and transactions with filters and facets", None, None),
+    op("post", "/admin/search/rebuild", "Rebuild the search index from its journal", None, None),
+    op("get", "/health", "Liveness", None, None),
+    op("get", "/health/rpc", "Failover and circuit breaker state of each chain RPC endpoint", None, None),
+];
+
+const JOB_STATUSES: [&str; 6] = ["Queued", "Assigned", "Running", "Completed", "Failed", "Cancelled"];
+const JOB_TYPES: [&str; 7] = ["Train", "Infer", "Agent", "Federated", "Evolution", "Stream", "Quantize"];
+const TERMINAL_REASON_KINDS: [&str; 9] = [
+    "user_cancelled", "workflow_cancelled", "out_of_memory", "timeout", "container_exit",
+    "runtime_error", "unschedulable", "moderation_blocked", "quality_gate",
+];
+
+pub fn spec(version: ApiVersion) -> Value {
+    let mut paths = serde_json::Map::new();
+    for operation in OPERATIONS {
+        let path = openapi_path(operation.path);
+        let parameters: Vec<Value> = operation
+            .path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+            .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+            .collect();
+        let success = match operation.response {
+            Some(schema) => json!({
+                "description": "OK",
+                "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } },
+            }),
+            None => json!({ "description": "OK" }),
+        };
+        let entry = paths.entry(path).or_insert_with(|| json!({}));
+        entry[operation.method] = json!({
+            "operationId": operation_id(operation),
+            "summary": operation.summary,
+            "parameters": parameters,
+            "responses": {
+                "200": success,
+                "default": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ServiceError" } } },
+                },
+            },
+        });
+        if let Some(schema) = operation.request {
+            entry[operation.method]["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } },
+            });
+        }
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": { "title": "ArthaAIN Job Daemon API", "version": format!("{}.0.0", version.number()) },
+        "servers": [{ "url": version.prefix() }],
+        "paths": paths,
+        "components": { "schemas": schemas(version) },
+    })
+}
+
+/// Method and path in snake case, e.g. `get_job_id_status`; generated
+/// clients name their methods after it
+fn operation_id(operation: &Operation) -> String {
+    let segments = operation.path.split(['/', '-']).filter(|s| !s.is_empty()).map(|s| s.trim_start_matches(':'));
+    std::iter::once(operation.method).chain(segments).collect::<Vec<_>>().join("_")
+}
+
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn schemas(version: ApiVersion) -> Value {
+    let mut job = json!({
+        "type": "object",
+        "properties": {
+            "job_id": { "type": "string" },
+            "job_type": { "$ref": "#/components/schemas/JobType" },
+            "status": { "$ref": "#/components/schemas/JobStatus" },
+            "submitter_did": { "type": "string" },
+            "model_id": { "type": ["string", "null"] },
+            "dataset_id": { "type": ["string", "null"] },
+            "assigned_node": { "type": ["string", "null"] },
+            "budget": { "type": "integer" },
+            "spent": { "type": "integer" },
+            "submitted_at": { "type": "integer" },
+            "output_cid": { "type": "null", "description": "Always null; use output_id from the status response" },
+            "progress": { "type": "number" },
+            "seed": { "type": "integer", "description": "Train jobs only; absent otherwise" },
+            "live_migration": { "type": "boolean", "description": "Absent unless opted in" },
+            "migrations": { "type": "array", "items": { "$ref": "#/components/schemas/MigrationRecord" } },
+            "terminal_reason": { "$ref": "#/components/schemas/TerminalReason", "description": "Failed and Cancelled jobs only" },
+            "labels": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Absent if the job has none" },
+            "stale_since": { "type": "integer", "description": "Set after a daemon restart until ai-runtime confirms the job's state; absent otherwise" },
+        },
+    });
+    let spell = |values: &[&str]| -> Vec<String> {
+        match version {
+            ApiVersion::V1 => values.iter().map(|v| v.to_string()).collect(),
+            ApiVersion::V2 => values.iter().map(|v| screaming_snake(v)).collect(),
+        }
+    };
+    if version == ApiVersion::V2 {
+        // The adapter that rewrites v2 responses also rewrites the documented shape
+        response_to_v2(&mut job["properties"], false);
+    }
+
+    let mut schemas = json!({
+        "JobStatus": { "type": "string", "enum": spell(&JOB_STATUSES) },
+        "JobType": { "type": "string", "enum": spell(&JOB_TYPES) },
+        "Job": job,
+        "JobSubmitResponse": {
+            "type": "object",
+            "properties": {
+                "job_id": { "type": "string" },
+                "status": { "$ref": "#/components/schemas/JobStatus" },
+                "estimated_cost": { "type": "integer" },
+                "estimated_duration_secs": { "type": "integer" },
+                "policy_degraded": {
+                    "type": "object",
+                    "description": "Present only when admitted by the policy fail mode during a policy-gate outage",
+                    "properties": {
+                        "mode": { "type": "string", "enum": ["fail-open-for-read", "cached-last-decision"] },
+                        "decided_at": { "type": "integer", "description": "When the replayed decision was made; cached-last-decision only" },
+                    },
+                },
+                "cached": { "type": "boolean", "description": "Present when job_id is an earlier identical job that already completed" },
+            },
+        },
+        "JobStatusResponse": {
+            "type": "object",
+            "properties": {
+                "job": { "$ref": "#/components/schemas/Job" },
+                "can_cancel": { "type": "boolean" },
+                "output_id": { "type": ["string", "null"] },
+                "moderation_hold": { "type": ["string", "null"] },
+                "escrow": { "type": ["object", "null"] },
+            },
+        },
+        "JobPage": {
+            "type": "object",
+            "properties": {
+                "items": { "type": "array", "items": { "$ref": "#/components/schemas/Job" } },
+                "total": { "type": "integer" },
+                "next_cursor": { "type": ["string", "null"] },
+            },
+        },
+        "Reservation": {
+            "type": "object",
+            "properties": {
+                "reservation_id": { "type": "string" },
+                "submitter_did": { "type": "string" },
+                "gpu_type": { "type": "string" },
+                "gpu_count": { "type": "integer" },
+                "region": { "type": ["string", "null"] },
+                "start": { "type": "integer" },
+                "end": { "type": "integer" },
+                "allocations": { "type": "object", "additionalProperties": { "type": "integer" } },
+                "price_per_gpu_sec": { "type": "number", "description": "Reservation premium included" },
+                "cost": { "type": "integer" },
+                "escrow_tx": { "type": "string" },
+                "booked_at": { "type": "integer" },
+                "jobs": { "type": "array", "items": { "type": "string" } },
+                "refund": { "type": ["object", "null"], "description": "Set once the window has ended and been settled" },
+                "status": { "type": "string", "enum": ["upcoming", "active", "ended", "settled"], "description": "Listings only" },
+                "utilization": { "type": "number", "description": "Listings only" },
+            },
+        },
+        "ReservationPage": {
+            "type": "object",
+            "properties": {
+                "items": { "type": "array", "items": { "$ref": "#/components/schemas/Reservation" } },
+                "total": { "type": "integer" },
+                "next_cursor": { "type": ["string", "null"] },
+            },
+        },
+        "ModelBatchRegisterResponse": {
+            "type": "object",
+            "properties": {
+                "committed": { "type": "boolean", "description": "Every model is registered; otherwise none stays registered unless rollback_failed says so" },
+                "results": { "type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "index": { "type": "integer" },
+                        "model_cid": { "type": "string" },
+                        "status": { "type": "string", "enum": ["registered", "rolled_back", "rollback_failed", "failed", "not_attempted"] },
+                        "model_id": { "type": ["string", "null"] },
+                        "error": { "type": ["string", "null"] },
+                    },
+                } },
+            },
+        },
+        "ModelCardView": {
+            "type": "object",
+            "properties": {
+                "model_id": { "type": "string" },
+                "card_cid": { "type": "string", "description": "SVDB CID of the card, as recorded on-chain" },
+                "card": { "$ref": "#/components/schemas/ModelCard" },
+            },
+        },
+        "ModelCard": {
+            "type": "object",
+            "required": ["intended_use", "training_data", "eval_metrics", "limitations"],
+            "properties": {
+                "intended_use": { "type": "string" },
+                "training_data": { "type": "string" },
+                "eval_metrics": { "type": "object", "additionalProperties": { "type": "number" } },
+                "limitations": { "type": "string" },
+                "out_of_scope_uses": { "type": "array", "items": { "type": "string" } },
+                "ethical_considerations": { "type": ["string", "null"] },
+            },
+        },
+        "TerminalReason": {
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string", "enum": TERMINAL_REASON_KINDS },
+                "detail": { "type": "string" },
+                "exit_code": { "type": "integer", "description": "Container exit code; runtime failures only" },
+            },
+        },
+        "MigrationRecord": {
+            "type": "object",
+            "properties": {
+                "initiated_by": { "type": "string" },
+                "reason": { "type": "string" },
+                "phase": { "type": "string", "enum": ["requested", "exported", "resumed", "failed"] },
+                "source_node": { "type": "string" },
+                "target_node": { "type": ["string", "null"] },
+                "mode": { "type": ["string", "null"], "enum": ["cooperative", "degraded", null] },
+                "resume_from": { "type": ["string", "null"] },
+                "steps_lost": { "type": ["integer", "null"] },
+                "requested_at": { "type": "integer" },
+                "exported_at": { "type": ["integer", "null"] },
+                "resumed_at": { "type": ["integer", "null"] },
+                "gap_secs": { "type": ["integer", "null"] },
+                "failure": { "type": ["string", "null"] },
+            },
+        },
+        "LinkResponse": {
+            "type": "object",
+            "properties": {
+                "output_id": { "type": "string" },
+                "url": { "type": "string" },
+                "expires_at": { "type": "integer" },
+                "integrity": {
+                    "type": ["object", "null"],
+                    "description": "Set for infer outputs; check the download's blake3 against output_digest",
+                    "properties": {
+                        "output_cid": { "type": "string" },
+                        "output_digest": { "type": "string", "description": "blake3 of the output bytes, hex" },
+                        "node_pubkey": { "type": "string" },
+                        "signature": { "type": "string", "description": "ECDSA by node_pubkey over OUTPUT:{job_id}:{output_cid}:{output_digest}" },
+                    },
+                },
+            },
+        },
+        "Timeline": {
+            "type": "object",
+            "properties": {
+                "job_id": { "type": "string" },
+                "generated_at": { "type": "integer" },
+                "events": { "type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "at": { "type": "integer" },
+                        "source": { "type": "string" },
+                        "kind": { "type": "string" },
+                        "detail": {},
+                    },
+                } },
+                "phases": { "type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "phase": { "type": "string", "enum": ["policy", "queue", "pull", "startup", "gpu", "verification", "settlement"] },
+                        "state": { "type": "string", "enum": ["complete", "in_progress", "unavailable"] },
+                        "source": { "type": "string" },
+                        "started_at": { "type": ["integer", "null"] },
+                        "ended_at": { "type": ["integer", "null"] },
+                        "duration_secs": { "type": ["number", "null"] },
+                        "billable": { "type": "boolean" },
+                        "cost": { "type": "integer" },
+                        "anomalies": { "type": "array", "items": { "type": "object" } },
+                    },
+                } },
+                "sources": { "type": "array", "items": {
+                    "type": "object",
+                    "properties": {
+                        "source": { "type": "string" },
+                        "available": { "type": "boolean" },
+                        "error": { "type": ["string", "null"] },
+                    },
+                } },
+                "summary": {
+                    "type": "object",
+                    "properties": {
+                        "total_wall_clock_secs": { "type": "integer" },
+                        "billable_secs": { "type": "number" },
+                        "spent": { "type": "integer" },
+                        "gpu_cost": { "type": "integer" },
+                        "verification_cost": { "type": "integer" },
+                        "unattributed_cost": { "type": "integer" },
+                        "price_per_gpu_sec": { "type": ["number", "null"] },
+                        "effective_cost_per_gpu_hour": { "type": ["number", "null"] },
+                        "anomalies": { "type": "integer" },
+                        "unavailable_sources": { "type": "array", "items": { "type": "string" } },
+                    },
+                },
+            },
+        },
+        "ServiceError": {
+            "type": "object",
+            "required": ["code", "error", "category", "message"],
+            "properties": {
+                "code": { "type": "integer" },
+                "error": { "type": "string" },
+                "category": { "type": "string", "enum": ["client", "server", "dependency"] },
+                "message": { "type": "string" },
+                "retry_after_secs": { "type": "integer" },
+                "details": {},
+            },
+        },
+    });
+    if let (Some(schemas), Value::Object(requests)) = (schemas.as_object_mut(), request_schemas()) {
+        schemas.extend(requests);
+    }
+    schemas
+}
+
+/// Bodies of the operations that take one
+fn request_schemas() -> Value {
+    json!({
+        "TrainJobRequest": {
+            "type": "object",
+            "required": ["model_id", "dataset_id", "submitter_did", "params", "budget"],
+            "properties": {
+                "model_id": { "type": "string", "description": "Model id or name@version" },
+                "dataset_id": { "type": "string" },
+                "submitter_did": { "type": "string" },
+                "params": { "$ref": "#/components/schemas/TrainParams" },
+                "budget": { "type": "integer" },
+                "tee_required": { "type": "boolean" },
+                "allow_deprecated": { "type": "boolean", "description": "Override soft-blocked deprecations" },
+                "nonce": { "type": ["integer", "null"], "description": "Makes the job id computable up front" },
+                "milestones": {
+                    "type": ["object", "null"],
+                    "description": "Escrow payout milestones; 25/50/75/100% by default",
+                    "properties": {
+                        "total_steps": { "type": ["integer", "null"] },
+                        "fractions": { "type": "array", "items": { "type": "number" } },
+                        "steps": { "type": "array", "items": { "type": "integer" } },
+                    },
+                },
+                "live_migration": { "type": "boolean" },
+                "reservation_id": { "type": ["string", "null"], "description": "Run on GPUs booked with POST /reservations" },
+                "secrets": { "type": "array", "items": { "type": "string" }, "description": "Vault secret names injected as env vars" },
+                "labels": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Free-form metadata, e.g. team or ticket" },
+                "force_rerun": { "type": "boolean", "description": "Run even if an identical job already completed" },
+            },
+        },
+        "TrainParams": {
+            "type": "object",
+            "required": ["epochs", "batch_size", "learning_rate", "optimizer", "checkpoint_interval"],
+            "properties": {
+                "epochs": { "type": "integer" },
+                "batch_size": { "type": "integer" },
+                "learning_rate": { "type": "number" },
+                "optimizer": { "type": "string" },
+                "checkpoint_interval": { "type": "integer" },
+                "seed": { "type": ["integer", "null"], "description": "Generated and recorded at submission if unset" },
+            },
+        },
+        "InferJobRequest": {
+            "type": "object",
+            "required": ["model_id", "mode", "budget"],
+            "properties": {
+                "model_id": { "type": "string" },
+                "input_cid": { "type": ["string", "null"] },
+                "inline_input": { "type": ["string", "null"] },
+                "input_meta": { "type": ["array", "null"], "items": { "type": "object" }, "description": "Tensors in input_cid, checked against the model schema" },
+                "submitter_did": { "type": "string", "description": "Empty for anonymous submissions" },
+                "mode": { "type": "string", "enum": ["batch", "realtime", "stream"] },
+                "max_tokens": { "type": ["integer", "null"] },
+                "budget": { "type": "integer" },
+                "tee_required": { "type": "boolean" },
+                "bucketing_key": { "type": ["string", "null"], "description": "Deterministic A/B routing" },
+                "allow_deprecated": { "type": "boolean" },
+                "nonce": { "type": ["integer", "null"] },
+                "anonymous": { "type": "boolean" },
+                "ephemeral_address": { "type": ["string", "null"] },
+                "ephemeral_signature": { "type": ["string", "null"], "description": "Requires nonce" },
+                "labels": { "type": "object", "additionalProperties": { "type": "string" } },
+                "force_rerun": { "type": "boolean", "description": "Run even if an identical job already completed" },
+            },
+        },
+        "AgentJobRequest": {
+            "type": "object",
+            "required": ["agent_spec_cid", "submitter_did", "goal", "tools", "memory_policy", "budget"],
+            "properties": {
+                "agent_spec_cid": { "type": "string" },
+                "submitter_did": { "type": "string" },
+                "goal": { "type": "string" },
+                "tools": { "type": "array", "items": { "type": "string" } },
+                "memory_policy": { "type": "string" },
+                "budget": { "type": "integer" },
+                "nonce": { "type": ["integer", "null"] },
+                "labels": { "type": "object", "additionalProperties": { "type": "string" } },
+            },
+        },
+        "ModelRegisterRequest": {
+            "type": "object",
+            "required": ["model_cid", "architecture", "dataset_id", "code_hash", "version"],
+            "properties": {
+                "model_cid": { "type": "string" },
+                "architecture": { "type": "string" },
+                "base_model_id": { "type": ["string", "null"] },
+                "dataset_id": { "type": "string" },
+                "code_hash": { "type": "string" },
+                "version": { "type": "string" },
+                "license_cid": { "type": ["string", "null"] },
+                "name": { "type": ["string", "null"], "description": "Tags the model as name@version and name@latest" },
+                "requirements": { "type": ["object", "null"], "description": "Runtime the model needs; checked at assignment" },
+                "schema": { "type": ["object", "null"], "description": "Input/output tensors" },
+                "card": { "$ref": "#/components/schemas/ModelCard" },
+            },
+        },
+        "DatasetRegisterRequest": {
+            "type": "object",
+            "required": ["root_cid", "license_cid", "tags"],
+            "properties": {
+                "root_cid": { "type": "string" },
+                "license_cid": { "type": "string" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "name": { "type": ["string", "null"], "description": "With version, registers name@version" },
+                "version": { "type": ["string", "null"] },
+                "samples": { "type": ["integer", "null"] },
+                "window": { "type": ["object", "null"], "description": "Stream window the version was cut from" },
+                "consent_vcs": { "type": "array", "items": { "type": "string" } },
+            },
+        },
+        "ReservationRequest": {
+            "type": "object",
+            "required": ["submitter_did", "gpu_type", "gpu_count", "earliest_start", "duration_secs"],
+            "properties": {
+                "submitter_did": { "type": "string" },
+                "gpu_type": { "type": "string" },
+                "gpu_count": { "type": "integer" },
+                "region": { "type": ["string", "null"] },
+                "earliest_start": { "type": "integer" },
+                "latest_start": { "type": ["integer", "null"], "description": "Window may open any time up to here" },
+                "duration_secs": { "type": "integer" },
+            },
+        },
+    })
+}
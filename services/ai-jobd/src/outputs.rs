@@ -0,0 +1,467 @@
+//! Job Output Access Control
+//! Ownership records for completed job outputs, expiring HMAC-signed download
+//! links, a validating download proxy in front of SVDB, and an audit trail.
+//!
+//! Infer outputs also carry the executing node's signature over their CID and
+//! the blake3 digest of their bytes. Links hand it to the client, which checks
+//! the download against it: a mismatch means the bytes were tampered with in
+//! transit or by the provider.
+
+use crate::anonymous;
+use artha_clock::{Entropy, SharedClock};
+use artha_log::Sensitive;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State, Json},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use hmac::{Hmac, Mac};
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the requester DID on downloads
+pub const DID_HEADER: &str = "x-artha-did";
+/// Header carrying the shared token for internal (service-to-service) paths
+pub const INTERNAL_TOKEN_HEADER: &str = "x-artha-internal-token";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputRecord {
+    pub output_id: String,
+    pub job_id: String,
+    pub cid: String,
+    pub owner_did: String,
+    pub grantees: HashSet<String>,
+    pub registered_at: u64,
+    #[serde(default)]
+    pub integrity: Option<OutputIntegrity>,
+}
+
+/// The executing node's signed statement of what an output's bytes hash to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputIntegrity {
+    pub output_cid: String,
+    pub output_digest: String, // blake3 of the output bytes, hex
+    pub node_pubkey: String,
+    pub signature: String, // ECDSA over `output_message`
+}
+
+pub fn output_message(job_id: &str, output_cid: &str, output_digest: &str) -> String {
+    format!("OUTPUT:{}:{}:{}", job_id, output_cid, output_digest)
+}
+
+impl OutputIntegrity {
+    /// Whether the signature is `node_pubkey`'s over this output of `job_id`
+    pub fn signed_by_node(&self, job_id: &str) -> bool {
+        let Ok(key) = hex::decode(self.node_pubkey.trim_start_matches("0x")) else { return false };
+        let Ok(key) = VerifyingKey::from_sec1_bytes(&key) else { return false };
+        let Ok(signature) = hex::decode(self.signature.trim_start_matches("0x")) else { return false };
+        let Ok(signature) = Signature::from_slice(&signature) else { return false };
+        let message = output_message(job_id, &self.output_cid, &self.output_digest);
+        key.verify(message.as_bytes(), &signature).is_ok()
+    }
+}
+
+impl OutputRecord {
+    pub fn can_access(&self, did: &str) -> bool {
+        self.owner_did == did || self.grantees.contains(did)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AuditAction {
+    LinkIssued,
+    Downloaded,
+    DownloadDenied,
+    GrantAdded,
+    GrantRevoked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub output_id: String,
+    pub did: String,
+    pub action: AuditAction,
+    pub detail: Option<String>,
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignedLink {
+    pub output_id: String,
+    pub did: String,
+    pub expires_at: u64,
+    pub sig: Sensitive<String>, // Bearer credential for the download
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    UnknownOutput,
+    Tampered,
+    Expired,
+    WrongDid,
+    AccessRevoked,
+    KeyNotProven, // An ephemeral address asked without a fresh signature from its key
+}
+
+impl LinkError {
+    fn status_and_code(&self) -> (StatusCode, &'static str) {
+        match self {
+            LinkError::UnknownOutput => (StatusCode::NOT_FOUND, "unknown_output"),
+            LinkError::Tampered => (StatusCode::UNAUTHORIZED, "link_tampered"),
+            LinkError::Expired => (StatusCode::GONE, "link_expired"),
+            LinkError::WrongDid => (StatusCode::FORBIDDEN, "link_wrong_did"),
+            LinkError::AccessRevoked => (StatusCode::FORBIDDEN, "access_revoked"),
+            LinkError::KeyNotProven => (StatusCode::UNAUTHORIZED, "key_not_proven"),
+        }
+    }
+}
+
+impl IntoResponse for LinkError {
+    fn into_response(self) -> Response {
+        let (status, code) = self.status_and_code();
+        (status, Json(serde_json::json!({ "error": code }))).into_response()
+    }
+}
+
+#[derive(Debug)]
+pub struct OutputVault {
+    records: HashMap<String, OutputRecord>, // output_id -> record
+    by_job: HashMap<String, String>,        // job_id -> output_id
+    audit: Vec<AuditEntry>,
+    key: Sensitive<Vec<u8>>,
+}
+
+impl OutputVault {
+    pub fn new(key: &[u8]) -> Self {
+        OutputVault {
+            records: HashMap::new(),
+            by_job: HashMap::new(),
+            audit: Vec::new(),
+            key: Sensitive::new(key.to_vec()),
+        }
+    }
+
+    /// Register a job output under an opaque id. Re-registering a job keeps
+    /// its id and grants and points it at the new CID.
+    pub fn register(&mut self, job_id: &str, cid: &str, owner_did: &str, now: u64, entropy: &dyn Entropy) -> String {
+        if let Some(output_id) = self.by_job.get(job_id) {
+            if let Some(record) = self.records.get_mut(output_id) {
+                if record.cid != cid {
+                    record.integrity = None;
+                }
+                record.cid = cid.to_string();
+                return output_id.clone();
+            }
+        }
+
+        let output_id = format!("out-{}", entropy.uuid().simple());
+        self.records.insert(output_id.clone(), OutputRecord {
+            output_id: output_id.clone(),
+            job_id: job_id.to_string(),
+            cid: cid.to_string(),
+            owner_did: owner_did.to_string(),
+            grantees: HashSet::new(),
+            registered_at: now,
+            integrity: None,
+        });
+        self.by_job.insert(job_id.to_string(), output_id.clone());
+        output_id
+    }
+
+    pub fn output_for_job(&self, job_id: &str) -> Option<&OutputRecord> {
+        self.by_job.get(job_id).and_then(|id| self.records.get(id))
+    }
+
+    /// Attach the node's signed digest to the job's registered output
+    pub fn attest(&mut self, job_id: &str, integrity: OutputIntegrity) {
+        if let Some(record) = self.by_job.get(job_id).and_then(|id| self.records.get_mut(id)) {
+            if record.cid == integrity.output_cid {
+                record.integrity = Some(integrity);
+            }
+        }
+    }
+
+    /// Grant (or revoke) another DID's access. Only the owner may change grants.
+    pub fn set_grant(&mut self, job_id: &str, owner_did: &str, grantee_did: &str, granted: bool, now: u64) -> Result<(), StatusCode> {
+        let output_id = self.by_job.get(job_id).ok_or(StatusCode::NOT_FOUND)?.clone();
+        let record = self.records.get_mut(&output_id).ok_or(StatusCode::NOT_FOUND)?;
+        if record.owner_did != owner_did {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        let action = if granted {
+            record.grantees.insert(grantee_did.to_string());
+            AuditAction::GrantAdded
+        } else {
+            record.grantees.remove(grantee_did);
+            AuditAction::GrantRevoked
+        };
+        self.record_audit(&output_id, owner_did, action, Some(grantee_did.to_string()), now);
+        Ok(())
+    }
+
+    /// HMAC over output id, DID and expiry
+    fn link_mac(&self, output_id: &str, did: &str, expires_at: u64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.key.expose()).expect("HMAC accepts any key length");
+        mac.update(output_id.as_bytes());
+        mac.update(b"|");
+        mac.update(did.as_bytes());
+        mac.update(b"|");
+        mac.update(&expires_at.to_be_bytes());
+        mac
+    }
+
+    /// Issue a link for `did`, valid until `now + ttl_secs` (exclusive)
+    pub fn issue_link(&mut self, job_id: &str, did: &str, ttl_secs: u64, now: u64) -> Result<SignedLink, LinkError> {
+        let record = self.output_for_job(job_id).ok_or(LinkError::UnknownOutput)?;
+        if !record.can_access(did) {
+            return Err(LinkError::AccessRevoked);
+        }
+
+        let output_id = record.output_id.clone();
+        let expires_at = now + ttl_secs;
+        let sig = Sensitive::new(hex::encode(self.link_mac(&output_id, did, expires_at).finalize().into_bytes()));
+        self.record_audit(&output_id, did, AuditAction::LinkIssued, Some(format!("expires_at={}", expires_at)), now);
+        Ok(SignedLink { output_id, did: did.to_string(), expires_at, sig })
+    }
+
+    /// Validate a presented link for `requester_did`. Returns the CID to serve.
+    pub fn validate(&self, link: &SignedLink, requester_did: &str, now: u64) -> Result<String, LinkError> {
+        let record = self.records.get(&link.output_id).ok_or(LinkError::UnknownOutput)?;
+
+        let valid_sig = hex::decode(link.sig.expose())
+            .map(|sig| self.link_mac(&link.output_id, &link.did, link.expires_at).verify_slice(&sig).is_ok())
+            .unwrap_or(false);
+        if !valid_sig {
+            return Err(LinkError::Tampered);
+        }
+        if now >= link.expires_at {
+            return Err(LinkError::Expired);
+        }
+        if link.did != requester_did {
+            return Err(LinkError::WrongDid);
+        }
+        if !record.can_access(requester_did) {
+            return Err(LinkError::AccessRevoked);
+        }
+        Ok(record.cid.clone())
+    }
+
+    pub fn record_audit(&mut self, output_id: &str, did: &str, action: AuditAction, detail: Option<String>, now: u64) {
+        self.audit.push(AuditEntry {
+            output_id: output_id.to_string(),
+            did: did.to_string(),
+            action,
+            detail,
+            at: now,
+        });
+    }
+
+    pub fn audit_for(&self, output_id: &str) -> Vec<AuditEntry> {
+        self.audit.iter().filter(|e| e.output_id == output_id).cloned().collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct OutputState {
+    pub vault: Arc<RwLock<OutputVault>>,
+    pub svdb_url: String,
+    pub public_url: String,     // Base URL of this proxy, used in issued links
+    pub internal_token: Sensitive<String>,
+    pub max_link_ttl_secs: u64,
+    pub clock: SharedClock,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkRequest {
+    pub requester_did: String, // A DID, or the ephemeral address of an anonymous job
+    pub ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub signed_at: Option<u64>,
+    #[serde(default)]
+    pub signature: Option<String>, // Over `anonymous::link_digest`; required of ephemeral addresses
+}
+
+/// Ephemeral addresses have no DID to vouch for them, so a link is only
+/// issued to one that signs for it
+fn check_key_holder(job_id: &str, req: &LinkRequest, now: u64) -> Result<(), LinkError> {
+    let (Some(signed_at), Some(signature)) = (req.signed_at, &req.signature) else {
+        return Err(LinkError::KeyNotProven);
+    };
+    if now.abs_diff(signed_at) > anonymous::LINK_SIGNATURE_MAX_AGE_SECS {
+        return Err(LinkError::KeyNotProven);
+    }
+    match anonymous::recover_address(&anonymous::link_digest(job_id, signed_at), signature) {
+        Ok(signer) if signer.eq_ignore_ascii_case(&req.requester_did) => Ok(()),
+        _ => Err(LinkError::KeyNotProven),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkResponse {
+    pub output_id: String,
+    pub url: Sensitive<String>, // Carries the link signature
+    pub expires_at: u64,
+    pub integrity: Option<OutputIntegrity>, // What the download must hash to; infer outputs only
+}
+
+/// POST /job/:id/output/link - Issue a time-limited download link
+async fn issue_link(
+    State(state): State<OutputState>,
+    Path(job_id): Path<String>,
+    Json(req): Json<LinkRequest>,
+) -> Result<Json<LinkResponse>, LinkError> {
+    if anonymous::is_address(&req.requester_did) {
+        check_key_holder(&job_id, &req, state.clock.now_secs())?;
+    }
+    let ttl = req.ttl_secs.unwrap_or(state.max_link_ttl_secs).min(state.max_link_ttl_secs);
+    let mut vault = state.vault.write().await;
+    let link = vault.issue_link(&job_id, &req.requester_did, ttl, state.clock.now_secs())?;
+    let integrity = vault.output_for_job(&job_id).and_then(|record| record.integrity.clone());
+    drop(vault);
+
+    info!("🔗 Issued output link for job {} to {}", job_id, req.requester_did);
+    Ok(Json(LinkResponse {
+        url: Sensitive::new(format!(
+            "{}/output/{}/download?did={}&expires_at={}&sig={}",
+            state.public_url, link.output_id, link.did, link.expires_at, link.sig.expose()
+        )),
+        output_id: link.output_id,
+        expires_at: link.expires_at,
+        integrity,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GrantRequest {
+    pub owner_did: String,
+    pub grantee_did: String,
+    #[serde(default = "default_grant")]
+    pub granted: bool, // false revokes
+}
+
+fn default_grant() -> bool {
+    true
+}
+
+/// POST /job/:id/output/grants - Owner grants or revokes access for another DID
+async fn set_grant(
+    State(state): State<OutputState>,
+    Path(job_id): Path<String>,
+    Json(req): Json<GrantRequest>,
+) -> Result<StatusCode, StatusCode> {
+    state.vault.write().await.set_grant(&job_id, &req.owner_did, &req.grantee_did, req.granted, state.clock.now_secs())?;
+    info!(
+        "🔐 {} output access for {} on job {}",
+        if req.granted { "Granted" } else { "Revoked" },
+        req.grantee_did,
+        job_id
+    );
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub owner_did: String,
+}
+
+/// GET /job/:id/output/audit - Link issuance, download and grant history (owner only)
+async fn get_audit(
+    State(state): State<OutputState>,
+    Path(job_id): Path<String>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, StatusCode> {
+    let vault = state.vault.read().await;
+    let record = vault.output_for_job(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    if record.owner_did != query.owner_did {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(Json(vault.audit_for(&record.output_id)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    pub did: String,
+    pub expires_at: u64,
+    pub sig: Sensitive<String>,
+}
+
+/// GET /output/:output_id/download - Validate a signed link and stream the content from SVDB
+async fn download(
+    State(state): State<OutputState>,
+    Path(output_id): Path<String>,
+    Query(query): Query<DownloadQuery>,
+    headers: HeaderMap,
+) -> Result<Response, LinkError> {
+    let requester = headers
+        .get(DID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let link = SignedLink { output_id: output_id.clone(), did: query.did, expires_at: query.expires_at, sig: query.sig };
+
+    let validated = state.vault.read().await.validate(&link, &requester, state.clock.now_secs());
+    let cid = match validated {
+        Ok(cid) => cid,
+        Err(e) => {
+            if e != LinkError::UnknownOutput {
+                let (_, code) = e.status_and_code();
+                state.vault.write().await.record_audit(&output_id, &requester, AuditAction::DownloadDenied, Some(code.to_string()), state.clock.now_secs());
+            }
+            return Err(e);
+        }
+    };
+
+    let upstream = reqwest::Client::new()
+        .get(format!("{}/svdb/download/{}", state.svdb_url, cid))
+        .send()
+        .await;
+    let upstream = match upstream {
+        Ok(resp) if resp.status().is_success() => resp,
+        _ => return Ok(StatusCode::BAD_GATEWAY.into_response()),
+    };
+
+    state.vault.write().await.record_audit(&output_id, &requester, AuditAction::Downloaded, None, state.clock.now_secs());
+    info!("📥 Output {} downloaded by {}", output_id, requester);
+    Ok(Body::from_stream(upstream.bytes_stream()).into_response())
+}
+
+/// GET /internal/job/:id/output - Raw CID for trusted services (e.g. model registration)
+async fn internal_output_cid(
+    State(state): State<OutputState>,
+    Path(job_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let token = headers.get(INTERNAL_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    if token != Some(state.internal_token.expose().as_str()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let vault = state.vault.read().await;
+    let record = vault.output_for_job(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(serde_json::json!({
+        "job_id": job_id,
+        "output_id": record.output_id,
+        "output_cid": record.cid,
+    })))
+}
+
+pub fn router(state: OutputState) -> Router {
+    Router::new()
+        .route("/job/:id/output/link", post(issue_link))
+        .route("/job/:id/output/grants", post(set_grant))
+        .route("/job/:id/output/audit", get(get_audit))
+        .route("/output/:output_id/download", get(download))
+        .route("/internal/job/:id/output", get(internal_output_cid))
+        .with_state(state)
+}
@@ -0,0 +1,136 @@
+//! Pipeline Fan-Out
+//! Groups the parallel children of a pipeline stage and decides, from their
+//! outcomes, whether the join stage may proceed and with which outputs
+
+use artha_clock::Entropy;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How many children of a fan-out must succeed before the join stage runs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum FanOutPolicy {
+    AllMustSucceed,
+    AnySucceeds,
+    AtLeastK { k: usize },
+}
+
+impl FanOutPolicy {
+    /// Minimum successful children out of `children`
+    fn required(&self, children: usize) -> usize {
+        match self {
+            FanOutPolicy::AllMustSucceed => children,
+            FanOutPolicy::AnySucceeds => 1,
+            FanOutPolicy::AtLeastK { k } => *k,
+        }
+    }
+
+    /// Decide the join from the children's outcomes, in fan-out order. The
+    /// pipeline fails as soon as the policy can no longer be met, but only
+    /// proceeds once every child has finished so no surviving output is lost.
+    pub fn evaluate(&self, outcomes: &[ChildOutcome]) -> JoinDecision {
+        let required = self.required(outcomes.len());
+        let succeeded = outcomes.iter().filter(|o| matches!(o, ChildOutcome::Succeeded { .. })).count();
+        let pending = outcomes.iter().filter(|o| matches!(o, ChildOutcome::Pending)).count();
+
+        if succeeded + pending < required {
+            return JoinDecision::Failed {
+                reason: format!(
+                    "{} of {} children succeeded, policy requires {}",
+                    succeeded,
+                    outcomes.len(),
+                    required
+                ),
+            };
+        }
+        if pending > 0 {
+            return JoinDecision::Waiting;
+        }
+
+        JoinDecision::Proceed {
+            outputs: outcomes
+                .iter()
+                .filter_map(|o| match o {
+                    ChildOutcome::Succeeded { output_cid } => output_cid.clone(),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildOutcome {
+    Pending,
+    Succeeded { output_cid: Option<String> },
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum JoinDecision {
+    Waiting,
+    Proceed { outputs: Vec<String> }, // Surviving outputs handed to the join stage
+    Failed { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanOut {
+    pub fanout_id: String,
+    pub children: Vec<String>, // Child job ids
+    pub policy: FanOutPolicy,
+    pub decision: JoinDecision, // Final once it leaves `Waiting`
+    pub created_at: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct FanOutRegistry {
+    fanouts: HashMap<String, FanOut>,
+}
+
+impl FanOutRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, children: Vec<String>, policy: FanOutPolicy, now: u64, entropy: &dyn Entropy) -> Result<FanOut, StatusCode> {
+        let required = policy.required(children.len());
+        if children.is_empty() || required == 0 || required > children.len() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let fanout = FanOut {
+            fanout_id: format!("fanout-{}", entropy.uuid()),
+            children,
+            policy,
+            decision: JoinDecision::Waiting,
+            created_at: now,
+        };
+        self.fanouts.insert(fanout.fanout_id.clone(), fanout.clone());
+        Ok(fanout)
+    }
+
+    pub fn get(&self, fanout_id: &str) -> Option<&FanOut> {
+        self.fanouts.get(fanout_id)
+    }
+
+    /// Undecided fan-outs that include `job_id`
+    pub fn waiting_on(&self, job_id: &str) -> Vec<FanOut> {
+        self.fanouts
+            .values()
+            .filter(|f| f.decision == JoinDecision::Waiting && f.children.iter().any(|c| c == job_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Record a decision; `Waiting` is ignored so decisions never revert
+    pub fn decide(&mut self, fanout_id: &str, decision: JoinDecision) {
+        if decision == JoinDecision::Waiting {
+            return;
+        }
+        if let Some(fanout) = self.fanouts.get_mut(fanout_id) {
+            fanout.decision = decision;
+        }
+    }
+}
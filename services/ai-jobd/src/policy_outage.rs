@@ -0,0 +1,142 @@
+//! Policy-Gate Outages
+//! What a submission gets when policy-gate can't be asked. Under
+//! `fail-closed`, the default, it is denied. Under `fail-open-for-read`,
+//! read-only actions (inference) are admitted. Under `cached-last-decision`,
+//! the gate's last answer for the same DID, action and resource is replayed,
+//! if it is recent enough. Critical actions, the ones that lock budget or
+//! produce new models, are denied under every mode. A submission admitted
+//! this way carries the mode in its response.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Actions that only read: admitted under `fail-open-for-read`
+pub const READ_ACTIONS: &[&str] = &["infer"];
+
+/// Decisions kept for `cached-last-decision`; expired ones are dropped past this
+const MAX_CACHED_DECISIONS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailMode {
+    FailClosed,
+    FailOpenForRead,
+    CachedLastDecision,
+}
+
+impl FromStr for FailMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail-closed" => Ok(FailMode::FailClosed),
+            "fail-open-for-read" => Ok(FailMode::FailOpenForRead),
+            "cached-last-decision" => Ok(FailMode::CachedLastDecision),
+            other => Err(format!("unknown policy fail mode {:?}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutageConfig {
+    pub mode: FailMode,
+    pub cache_ttl_secs: u64,           // How old a replayed decision may be
+    pub critical_actions: Vec<String>, // Always fail closed
+}
+
+impl Default for OutageConfig {
+    fn default() -> Self {
+        OutageConfig {
+            mode: FailMode::FailClosed,
+            cache_ttl_secs: 900,
+            critical_actions: vec!["train".to_string(), "quantize".to_string()],
+        }
+    }
+}
+
+impl OutageConfig {
+    /// From `ARTHA_POLICY_FAIL_MODE`, `ARTHA_POLICY_CACHE_TTL_SECS` and
+    /// `ARTHA_POLICY_CRITICAL_ACTIONS` (comma-separated)
+    pub fn from_env() -> Self {
+        let default = OutageConfig::default();
+        OutageConfig {
+            mode: std::env::var("ARTHA_POLICY_FAIL_MODE")
+                .map(|mode| mode.parse().unwrap_or_else(|e| panic!("Invalid ARTHA_POLICY_FAIL_MODE: {}", e)))
+                .unwrap_or(default.mode),
+            cache_ttl_secs: std::env::var("ARTHA_POLICY_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.cache_ttl_secs),
+            critical_actions: std::env::var("ARTHA_POLICY_CRITICAL_ACTIONS")
+                .map(|actions| actions.split(',').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect())
+                .unwrap_or(default.critical_actions),
+        }
+    }
+
+    /// How a check policy-gate couldn't answer is decided: admitted under
+    /// the returned mode, or denied with the reason
+    pub fn fallback(&self, cache: &DecisionCache, key: &DecisionKey, now: u64) -> Result<DegradedPolicy, String> {
+        if self.critical_actions.contains(&key.action) {
+            return Err("Policy check unavailable".to_string());
+        }
+        match self.mode {
+            FailMode::FailClosed => Err("Policy check unavailable".to_string()),
+            FailMode::FailOpenForRead if READ_ACTIONS.contains(&key.action.as_str()) => Ok(DegradedPolicy {
+                mode: self.mode,
+                decided_at: None,
+            }),
+            FailMode::FailOpenForRead => Err("Policy check unavailable".to_string()),
+            FailMode::CachedLastDecision => match cache.get(key, now, self.cache_ttl_secs) {
+                Some(cached) if cached.allowed => Ok(DegradedPolicy { mode: self.mode, decided_at: Some(cached.decided_at) }),
+                Some(cached) => Err(cached.reason.clone().unwrap_or_else(|| "Denied by policy".to_string())),
+                None => Err("Policy check unavailable and no recent decision is cached".to_string()),
+            },
+        }
+    }
+}
+
+/// How an admission was made without policy-gate, returned with the submission
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DegradedPolicy {
+    pub mode: FailMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_at: Option<u64>, // When the replayed decision was made, for `cached-last-decision`
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DecisionKey {
+    pub did: String,
+    pub action: String,
+    pub resource: String,
+    pub dataset_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedDecision {
+    pub allowed: bool,
+    pub reason: Option<String>,
+    pub decided_at: u64,
+}
+
+/// policy-gate's last answer per DID, action and resource
+#[derive(Default)]
+pub struct DecisionCache {
+    entries: HashMap<DecisionKey, CachedDecision>,
+}
+
+impl DecisionCache {
+    pub fn record(&mut self, key: DecisionKey, decision: CachedDecision, ttl_secs: u64) {
+        if self.entries.len() >= MAX_CACHED_DECISIONS {
+            let now = decision.decided_at;
+            self.entries.retain(|_, cached| now.saturating_sub(cached.decided_at) <= ttl_secs);
+        }
+        if self.entries.len() < MAX_CACHED_DECISIONS || self.entries.contains_key(&key) {
+            self.entries.insert(key, decision);
+        }
+    }
+
+    pub fn get(&self, key: &DecisionKey, now: u64, ttl_secs: u64) -> Option<&CachedDecision> {
+        self.entries.get(key).filter(|cached| now.saturating_sub(cached.decided_at) <= ttl_secs)
+    }
+}
@@ -0,0 +1,130 @@
+//! Quantization Jobs
+//! A `Quantize` job takes a trained model, runs ai-runtime's quantization
+//! image over it (int8 or fp16) and evaluates both the parent and the
+//! quantized model on an evaluation dataset. The container reports
+//! `baseline_accuracy` and `accuracy` as metrics on completion; the job only
+//! succeeds, and the quantized model is only registered as a child of its
+//! parent, if the accuracy drop stays within the bound.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Framework ai-runtime offers its quantization image under
+pub const RUNTIME: &str = "quantize";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    Int8,
+    Fp16,
+}
+
+impl Precision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Precision::Int8 => "int8",
+            Precision::Fp16 => "fp16",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuantizeConfig {
+    pub max_accuracy_drop: f64, // Largest drop any job may accept; requests can only tighten it
+}
+
+impl QuantizeConfig {
+    pub fn from_env() -> Self {
+        QuantizeConfig {
+            max_accuracy_drop: std::env::var("ARTHA_QUANTIZE_MAX_ACCURACY_DROP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.01),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuantizeJobRequest {
+    pub model_id: String, // Parent model; tags like "resnet@latest" resolve
+    pub eval_dataset_id: String,
+    pub precision: Precision,
+    pub submitter_did: String,
+    pub budget: u64,
+    #[serde(default)]
+    pub max_accuracy_drop: Option<f64>, // Absolute, e.g. 0.005 for half a point
+    #[serde(default)]
+    pub name: Option<String>, // Tags the child as name@version
+    #[serde(default)]
+    pub version: Option<String>, // Defaults to the precision
+    #[serde(default)]
+    pub nonce: Option<u64>,
+}
+
+/// What a queued quantize job needs at assignment and on completion
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QuantizeSpec {
+    pub parent_model_id: String,
+    pub parent_model_cid: String,
+    pub eval_dataset_id: String,
+    pub precision: Precision,
+    pub max_accuracy_drop: f64, // Effective bound
+    pub name: Option<String>,
+    pub version: String,
+}
+
+impl QuantizeSpec {
+    /// Environment the quantization container runs with
+    pub fn container_env(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("ARTHA_QUANTIZE_PRECISION".to_string(), self.precision.as_str().to_string()),
+            ("ARTHA_QUANTIZE_MAX_ACCURACY_DROP".to_string(), self.max_accuracy_drop.to_string()),
+        ])
+    }
+
+    /// Accuracy drop of a completed run, or why the quantized model is rejected
+    pub fn judge(&self, metrics: Option<&HashMap<String, f64>>) -> Result<f64, String> {
+        let metric = |name: &str| {
+            metrics
+                .and_then(|m| m.get(name).copied())
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("Quantization did not report {}", name))
+        };
+        let (baseline, accuracy) = (metric("baseline_accuracy")?, metric("accuracy")?);
+        let drop = baseline - accuracy;
+        if drop > self.max_accuracy_drop {
+            return Err(format!(
+                "{} accuracy dropped {:.4} ({:.4} -> {:.4}), over the {:.4} bound",
+                self.precision.as_str(), drop, baseline, accuracy, self.max_accuracy_drop,
+            ));
+        }
+        Ok(drop)
+    }
+}
+
+impl QuantizeJobRequest {
+    pub fn validate(&self, config: &QuantizeConfig) -> Result<(), String> {
+        if self.model_id.is_empty() || self.eval_dataset_id.is_empty() {
+            return Err("model_id and eval_dataset_id are required".to_string());
+        }
+        match self.max_accuracy_drop {
+            Some(bound) if !(0.0..=config.max_accuracy_drop).contains(&bound) => Err(format!(
+                "max_accuracy_drop must be between 0 and the configured {}",
+                config.max_accuracy_drop,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn spec(&self, config: &QuantizeConfig, parent_model_id: String, parent_model_cid: String) -> QuantizeSpec {
+        QuantizeSpec {
+            parent_model_id,
+            parent_model_cid,
+            eval_dataset_id: self.eval_dataset_id.clone(),
+            precision: self.precision,
+            max_accuracy_drop: self.max_accuracy_drop.unwrap_or(config.max_accuracy_drop),
+            name: self.name.clone(),
+            version: self.version.clone().unwrap_or_else(|| self.precision.as_str().to_string()),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Reorg Reconciliation
+//! A job's local status rests on its key transactions: the submission, the
+//! scheduler's assignment and the receipts daemon's finalize. Each one is
+//! watched from the block it was mined in until that block is finalized. If
+//! the block at that height changes hash, the chain reorged past it and the
+//! transaction is looked up again. Mined elsewhere, it is watched from its new
+//! block; gone, the job's status is rolled back to what the canonical chain
+//! still supports. A rolled-back transaction that is mined again restores the
+//! status it took away, unless the job has moved on since.
+
+use crate::JobStatus;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// How often watched transactions are checked against the canonical chain
+pub const RECONCILE_INTERVAL_SECS: u64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxRole {
+    Submit,
+    Assign,
+    Finalize,
+}
+
+impl TxRole {
+    /// Furthest status the chain supports without this transaction
+    pub fn fallback(self) -> JobStatus {
+        match self {
+            TxRole::Submit | TxRole::Assign => JobStatus::Queued,
+            TxRole::Finalize => JobStatus::Running,
+        }
+    }
+}
+
+/// Block a transaction was mined in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Inclusion {
+    pub block_number: u64,
+    pub block_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackedTx {
+    pub role: TxRole,
+    pub tx_hash: String,
+    pub inclusion: Option<Inclusion>, // None until mined, and again once un-mined
+    pub rolled_back_from: Option<JobStatus>, // Restored if the transaction is mined again
+}
+
+/// What the canonical chain says about a watched transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Observation {
+    Pending, // No receipt
+    Mined(Inclusion),
+    Finalized, // Its block can no longer reorg
+}
+
+/// Progress order of the statuses a reorg can move; terminal failures are
+/// local decisions and are left alone
+fn rank(status: &JobStatus) -> Option<u8> {
+    match status {
+        JobStatus::Queued => Some(0),
+        JobStatus::Assigned => Some(1),
+        JobStatus::Running => Some(2),
+        JobStatus::Completed => Some(3),
+        JobStatus::Failed | JobStatus::Cancelled => None,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ChainTracker {
+    jobs: BTreeMap<String, Vec<TrackedTx>>,
+}
+
+impl ChainTracker {
+    /// Watch a job's transaction; it replaces any earlier one in the same role
+    pub fn track(&mut self, job_id: &str, role: TxRole, tx_hash: String) {
+        let txs = self.jobs.entry(job_id.to_string()).or_default();
+        txs.retain(|tx| tx.role != role);
+        txs.push(TrackedTx { role, tx_hash, inclusion: None, rolled_back_from: None });
+    }
+
+    pub fn is_tracked(&self, job_id: &str, role: TxRole) -> bool {
+        self.jobs.get(job_id).is_some_and(|txs| txs.iter().any(|tx| tx.role == role))
+    }
+
+    /// Every watched transaction as `(job_id, tx)`
+    pub fn watched(&self) -> Vec<(String, TrackedTx)> {
+        self.jobs
+            .iter()
+            .flat_map(|(job_id, txs)| txs.iter().map(move |tx| (job_id.clone(), tx.clone())))
+            .collect()
+    }
+
+    /// Stop watching jobs this daemon no longer holds
+    pub fn retain_jobs(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.jobs.retain(|job_id, _| keep(job_id));
+    }
+
+    /// Record what the chain says about `tx_hash`. Returns the status the
+    /// job should move to from `status`, if the observation moves it.
+    pub fn observe(&mut self, job_id: &str, tx_hash: &str, observation: Observation, status: &JobStatus) -> Option<JobStatus> {
+        let txs = self.jobs.get_mut(job_id)?;
+        let index = txs.iter().position(|tx| tx.tx_hash == tx_hash)?;
+        let tx = &mut txs[index];
+        let fallback = tx.role.fallback();
+        let restore = |tx: &mut TrackedTx| tx.rolled_back_from.take().filter(|_| *status == fallback);
+
+        match observation {
+            Observation::Mined(inclusion) => {
+                tx.inclusion = Some(inclusion);
+                restore(tx)
+            }
+            Observation::Finalized => {
+                let restored = restore(tx);
+                txs.remove(index);
+                if txs.is_empty() {
+                    self.jobs.remove(job_id);
+                }
+                restored
+            }
+            Observation::Pending => {
+                // Never mined yet is not a reorg
+                tx.inclusion.take()?;
+                if rank(status)? <= rank(&fallback)? {
+                    return None;
+                }
+                tx.rolled_back_from = Some(status.clone());
+                Some(fallback)
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! GPU-Hour Reservations
+//! Capacity booked through ai-scheduler for a future window and paid for up
+//! front in DealMarket escrow. Jobs submitted against a reservation draw on
+//! its GPUs. When the window ends, the GPU-seconds its jobs used are measured
+//! against what was booked, and the unused share of the cost is refunded less
+//! a forfeiture that shrinks as utilization grows.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Forfeited fraction of a reservation's unused cost, by utilization.
+/// Tiers are `min_utilization:forfeit` pairs; the highest tier reached applies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForfeitureSchedule {
+    tiers: Vec<(f64, f64)>, // Ascending by min_utilization
+}
+
+impl ForfeitureSchedule {
+    pub const DEFAULT: &'static str = "0:0.5,0.5:0.25,0.9:0";
+
+    /// Parse "0:0.5,0.5:0.25,0.9:0"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut tiers = spec
+            .split(',')
+            .map(|tier| {
+                let (min, forfeit) = tier.trim().split_once(':').ok_or_else(|| format!("Expected min:forfeit, got {}", tier))?;
+                let min: f64 = min.trim().parse().map_err(|_| format!("Bad utilization {}", min))?;
+                let forfeit: f64 = forfeit.trim().parse().map_err(|_| format!("Bad forfeit {}", forfeit))?;
+                if !(0.0..=1.0).contains(&min) || !(0.0..=1.0).contains(&forfeit) {
+                    return Err(format!("Tier {} is outside 0..1", tier));
+                }
+                Ok((min, forfeit))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        tiers.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(ForfeitureSchedule { tiers })
+    }
+
+    /// Schedule from `ARTHA_RESERVATION_FORFEITURE`, or the default
+    pub fn from_env() -> Self {
+        std::env::var("ARTHA_RESERVATION_FORFEITURE")
+            .ok()
+            .and_then(|spec| Self::parse(&spec).ok())
+            .unwrap_or_else(|| Self::parse(Self::DEFAULT).expect("default forfeiture schedule parses"))
+    }
+
+    pub fn forfeit(&self, utilization: f64) -> f64 {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|(min, _)| utilization >= *min)
+            .map_or(0.0, |(_, forfeit)| *forfeit)
+    }
+
+    /// Refund of a reservation's cost at `utilization`: the unused share
+    /// less its forfeit, rounded down
+    pub fn refund(&self, cost: u64, utilization: f64) -> u64 {
+        let utilization = utilization.clamp(0.0, 1.0);
+        let unused = cost as f64 * (1.0 - utilization);
+        (unused * (1.0 - self.forfeit(utilization))).floor() as u64
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReservationRequest {
+    pub submitter_did: String,
+    pub gpu_type: String,
+    pub gpu_count: u32,
+    #[serde(default)]
+    pub region: Option<String>,
+    pub earliest_start: u64,
+    #[serde(default)]
+    pub latest_start: Option<u64>, // Window may open any time up to here; exactly at earliest_start if unset
+    pub duration_secs: u64,
+}
+
+/// Capacity the scheduler set aside, as it confirmed the booking
+#[derive(Debug, Clone, Deserialize)]
+pub struct Booking {
+    pub reservation_id: String,
+    pub start: u64,
+    pub end: u64,
+    pub allocations: BTreeMap<String, u32>, // node_pubkey -> GPUs held
+    pub price_per_gpu_sec: f64,
+    pub cost: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Refund {
+    pub utilization: f64,
+    pub forfeit: f64, // Fraction of the unused cost kept
+    pub amount: u64,
+    pub tx_hash: Option<String>, // None while the refund transaction is outstanding
+    pub settled_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReservationStatus {
+    Upcoming,
+    Active,
+    Ended,
+    Settled,
+}
+
+/// One reservation job's time on its GPU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JobRun {
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Reservation {
+    pub reservation_id: String,
+    pub submitter_did: String,
+    pub gpu_type: String,
+    pub gpu_count: u32,
+    pub region: Option<String>,
+    pub start: u64,
+    pub end: u64,
+    pub allocations: BTreeMap<String, u32>,
+    pub price_per_gpu_sec: f64,
+    pub cost: u64,
+    pub escrow_tx: String,
+    pub booked_at: u64,
+    pub jobs: Vec<String>,
+    pub refund: Option<Refund>,
+}
+
+impl Reservation {
+    pub fn new(req: &ReservationRequest, booking: Booking, escrow_tx: String, now: u64) -> Self {
+        Reservation {
+            reservation_id: booking.reservation_id,
+            submitter_did: req.submitter_did.clone(),
+            gpu_type: req.gpu_type.clone(),
+            gpu_count: req.gpu_count,
+            region: req.region.clone(),
+            start: booking.start,
+            end: booking.end,
+            allocations: booking.allocations,
+            price_per_gpu_sec: booking.price_per_gpu_sec,
+            cost: booking.cost,
+            escrow_tx,
+            booked_at: now,
+            jobs: Vec::new(),
+            refund: None,
+        }
+    }
+
+    pub fn status(&self, now: u64) -> ReservationStatus {
+        if self.refund.is_some() {
+            ReservationStatus::Settled
+        } else if now < self.start {
+            ReservationStatus::Upcoming
+        } else if now < self.end {
+            ReservationStatus::Active
+        } else {
+            ReservationStatus::Ended
+        }
+    }
+
+    /// Share of the booked GPU-seconds the reservation's jobs used up to
+    /// `now`. Each job holds one GPU; time outside the window doesn't count.
+    pub fn utilization(&self, runs: &[JobRun], now: u64) -> f64 {
+        let booked = self.gpu_count as u64 * (self.end - self.start);
+        if booked == 0 {
+            return 0.0;
+        }
+        let used: u64 = runs
+            .iter()
+            .filter_map(|run| {
+                let started = run.started_at?.max(self.start);
+                let finished = run.finished_at.unwrap_or(now).min(self.end).min(now);
+                Some(finished.saturating_sub(started))
+            })
+            .sum();
+        (used as f64 / booked as f64).min(1.0)
+    }
+}
+
+/// A reservation as listed, with how much of it has been used so far
+#[derive(Debug, Clone, Serialize)]
+pub struct ReservationView {
+    #[serde(flatten)]
+    pub reservation: Reservation,
+    pub status: ReservationStatus,
+    pub utilization: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReservationStore {
+    reservations: BTreeMap<String, Reservation>,
+}
+
+impl ReservationStore {
+    pub fn insert(&mut self, reservation: Reservation) {
+        self.reservations.insert(reservation.reservation_id.clone(), reservation);
+    }
+
+    pub fn get(&self, reservation_id: &str) -> Option<&Reservation> {
+        self.reservations.get(reservation_id)
+    }
+
+    pub fn get_mut(&mut self, reservation_id: &str) -> Option<&mut Reservation> {
+        self.reservations.get_mut(reservation_id)
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &Reservation> {
+        self.reservations.values()
+    }
+
+    pub fn attach(&mut self, reservation_id: &str, job_id: &str) {
+        if let Some(reservation) = self.reservations.get_mut(reservation_id) {
+            reservation.jobs.push(job_id.to_string());
+        }
+    }
+
+    /// Reservation a job was submitted against
+    pub fn of_job(&self, job_id: &str) -> Option<&Reservation> {
+        self.reservations.values().find(|r| r.jobs.iter().any(|job| job == job_id))
+    }
+
+    pub fn detach(&mut self, job_id: &str) {
+        for reservation in self.reservations.values_mut() {
+            reservation.jobs.retain(|job| job != job_id);
+        }
+    }
+
+    /// Ended reservations not yet settled
+    pub fn due(&self, now: u64) -> Vec<String> {
+        self.reservations
+            .values()
+            .filter(|r| r.status(now) == ReservationStatus::Ended)
+            .map(|r| r.reservation_id.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,49 @@
+//! Result Cache
+//! Completed train and infer jobs by everything their manifests pinned: the
+//! model CID, dataset or input CID, params hash, runtime image digest and
+//! seed. A byte-identical resubmission gets the earlier job and its outputs
+//! back instead of running again, unless it asks for `force_rerun` or names
+//! its job with a nonce. Results are only shared with the owner of the
+//! earlier job's outputs.
+
+use crate::manifest::{canonical_json, JobManifest};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Hash of the inputs that decide a job's result
+pub fn key(manifest: &JobManifest) -> String {
+    let pinned = serde_json::json!({
+        "job_type": manifest.job_type,
+        "model_cid": manifest.model_cid,
+        "dataset_cid": manifest.dataset_cid,
+        "input_cid": manifest.input_cid,
+        "params_hash": manifest.params_hash,
+        "runtime_image_digest": manifest.runtime_image_digest,
+        "seed": manifest.params.get("seed"),
+    });
+    let mut hasher = Sha256::new();
+    hasher.update(b"ARTHA_RESULT_KEY:");
+    hasher.update(canonical_json(&pinned).as_bytes());
+    format!("0x{:x}", hasher.finalize())
+}
+
+/// Completed job ids by owner and result key
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    results: HashMap<(String, String), String>,
+}
+
+impl ResultCache {
+    pub fn record(&mut self, owner: &str, manifest: &JobManifest, job_id: &str) {
+        self.results.insert((owner.to_string(), key(manifest)), job_id.to_string());
+    }
+
+    pub fn lookup(&self, owner: &str, manifest: &JobManifest) -> Option<&str> {
+        self.results.get(&(owner.to_string(), key(manifest))).map(String::as_str)
+    }
+
+    /// Drop a job whose record is gone, e.g. evicted by retention GC
+    pub fn forget(&mut self, job_id: &str) {
+        self.results.retain(|_, cached| cached != job_id);
+    }
+}
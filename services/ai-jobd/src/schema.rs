@@ -0,0 +1,135 @@
+//! Model I/O Schemas
+//! A model may declare the tensors it takes and returns when it is
+//! registered. Infer submissions are checked against the declared inputs
+//! before anything is uploaded or scheduled, so a wrong shape or dtype is
+//! turned away with a message naming the tensor instead of failing deep
+//! inside the container. Inline inputs are JSON and are described from
+//! their content; CID inputs are checked against the tensor metadata the
+//! submitter declares for them, which is locked into the job manifest.
+
+use serde::{Deserialize, Serialize};
+
+const DTYPES: [&str; 12] = [
+    "float16", "bfloat16", "float32", "float64", "int8", "int16", "int32", "int64", "uint8", "uint16", "bool", "string",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TensorSpec {
+    pub name: String,
+    pub dtype: String,
+    pub shape: Vec<Option<u64>>, // null for a dimension of any size, e.g. batch
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelSchema {
+    pub inputs: Vec<TensorSpec>,
+    #[serde(default)]
+    pub outputs: Vec<TensorSpec>,
+}
+
+fn format_shape(shape: &[Option<u64>]) -> String {
+    let dims: Vec<String> = shape.iter().map(|d| d.map_or("?".to_string(), |d| d.to_string())).collect();
+    format!("[{}]", dims.join(", "))
+}
+
+fn kind(dtype: &str) -> &str {
+    match dtype {
+        "float16" | "bfloat16" | "float32" | "float64" => "float",
+        "int8" | "int16" | "int32" | "int64" | "uint8" | "uint16" => "int",
+        other => other,
+    }
+}
+
+/// JSON carries no widths, so any width of the declared kind is accepted,
+/// and integers are accepted where floats are declared
+fn dtype_accepts(declared: &str, provided: &str) -> bool {
+    kind(declared) == kind(provided) || (kind(declared) == "float" && kind(provided) == "int")
+}
+
+impl ModelSchema {
+    /// Sanity of a schema being registered
+    pub fn check(&self) -> Result<(), String> {
+        if self.inputs.is_empty() {
+            return Err("Schema declares no inputs".to_string());
+        }
+        for (direction, specs) in [("input", &self.inputs), ("output", &self.outputs)] {
+            for (i, spec) in specs.iter().enumerate() {
+                if spec.name.is_empty() || specs[..i].iter().any(|s| s.name == spec.name) {
+                    return Err(format!("Every {} needs a unique name", direction));
+                }
+                if !DTYPES.contains(&spec.dtype.as_str()) {
+                    return Err(format!("Unknown dtype {} for {} '{}'", spec.dtype, direction, spec.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check provided input tensors against the declared inputs
+    pub fn validate(&self, provided: &[TensorSpec]) -> Result<(), String> {
+        if let Some(extra) = provided.iter().find(|p| !self.inputs.iter().any(|s| s.name == p.name)) {
+            return Err(format!("Unexpected input '{}'", extra.name));
+        }
+        for spec in &self.inputs {
+            let input = provided
+                .iter()
+                .find(|p| p.name == spec.name)
+                .ok_or_else(|| format!("Missing input '{}'", spec.name))?;
+            if !dtype_accepts(&spec.dtype, &input.dtype) {
+                return Err(format!("Input '{}' is {}, expected {}", spec.name, input.dtype, spec.dtype));
+            }
+            let fits = input.shape.len() == spec.shape.len()
+                && input.shape.iter().zip(&spec.shape).all(|(got, want)| want.is_none() || got == want);
+            if !fits {
+                return Err(format!(
+                    "Input '{}' has shape {}, expected {}",
+                    spec.name,
+                    format_shape(&input.shape),
+                    format_shape(&spec.shape)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe an inline JSON input: an object keyed by input name, or the
+    /// bare tensor when the model takes a single input
+    pub fn describe_inline(&self, input: &str) -> Result<Vec<TensorSpec>, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(input).map_err(|_| "Inline input is not JSON".to_string())?;
+        match (value, self.inputs.as_slice()) {
+            (serde_json::Value::Object(tensors), _) => {
+                tensors.iter().map(|(name, value)| describe_tensor(name, value)).collect()
+            }
+            (value, [only]) => Ok(vec![describe_tensor(&only.name, &value)?]),
+            _ => Err("Inline input must be an object keyed by input name".to_string()),
+        }
+    }
+}
+
+/// Shape and element type of a nested JSON array, which must be rectangular
+fn describe_tensor(name: &str, value: &serde_json::Value) -> Result<TensorSpec, String> {
+    let mut shape = Vec::new();
+    let mut level = vec![value];
+    while let Some(serde_json::Value::Array(first)) = level.first() {
+        let len = first.len();
+        if level.iter().any(|v| v.as_array().is_none_or(|a| a.len() != len)) {
+            return Err(format!("Input '{}' is not a rectangular tensor", name));
+        }
+        shape.push(Some(len as u64));
+        level = level.iter().flat_map(|v| v.as_array().unwrap()).collect();
+    }
+
+    let dtype = if level.iter().all(|v| v.is_boolean()) {
+        "bool"
+    } else if level.iter().all(|v| v.is_string()) {
+        "string"
+    } else if level.iter().all(|v| v.is_i64() || v.is_u64()) {
+        "int64"
+    } else if level.iter().all(|v| v.is_number()) {
+        "float64"
+    } else {
+        return Err(format!("Input '{}' mixes element types", name));
+    };
+    Ok(TensorSpec { name: name.to_string(), dtype: dtype.to_string(), shape })
+}
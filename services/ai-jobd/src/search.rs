@@ -0,0 +1,735 @@
+//! Explorer Search
+//! Full-text and faceted search over jobs, models, datasets and the
+//! transactions this daemon sent. Documents live in an in-memory tantivy
+//! index and every change is appended to a JSONL journal, which the index is
+//! rebuilt from on startup and on demand. Queries mix free text with typed
+//! filters (`status:completed architecture:transformer before:2025-02-01`);
+//! documents owned by a DID are only visible to that DID.
+
+use artha_tenant::Namespace;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::ops::Bound;
+use tantivy::collector::{Count, FacetCollector, TopDocs};
+use tantivy::query::{AllQuery, BooleanQuery, ConstScoreQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
+use tantivy::schema::{Facet, FacetOptions, Field, IndexRecordOption, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{DocAddress, Index, IndexReader, IndexWriter, Order, ReloadPolicy, TantivyDocument, Term};
+use tracing::warn;
+
+/// Facets counted when a query does not pick its own
+pub const FACETS: [&str; 4] = ["type", "status", "architecture", "date"];
+
+/// Query keys that filter on a document field; `before:` and `after:` filter
+/// on its date and any other token is free text
+const FILTER_KEYS: [&str; 13] = [
+    "type", "status", "architecture", "submitter", "model", "dataset", "tag", "node", "version", "method", "from", "to",
+    "label", // `label:team=vision`
+];
+
+const PUBLIC: &str = "public";
+const DAY_SECS: u64 = 86_400;
+
+/// Owner of a document private to a tenant; never a DID, so the two can't collide
+fn tenant_owner(tenant: &str) -> String {
+    format!("tenant:{}", tenant)
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchConfig {
+    pub journal_path: Option<String>, // JSONL of indexed documents the index is rebuilt from
+    pub max_docs: usize,              // Oldest documents are evicted beyond this
+    pub max_log_bytes: usize,         // Log excerpt kept per document, newest lines
+    pub log_retention_secs: u64,      // Excerpts are dropped once the last line is older
+    pub sync_interval_secs: u64,
+}
+
+impl SearchConfig {
+    pub fn from_env() -> Self {
+        SearchConfig {
+            journal_path: Some(
+                std::env::var("ARTHA_SEARCH_JOURNAL_PATH")
+                    .unwrap_or_else(|_| "/tmp/artha/jobd/search.jsonl".to_string()),
+            ),
+            max_docs: env_or("ARTHA_SEARCH_MAX_DOCS", 100_000),
+            max_log_bytes: env_or("ARTHA_SEARCH_LOG_EXCERPT_BYTES", 4096),
+            log_retention_secs: env_or("ARTHA_SEARCH_LOG_RETENTION_SECS", 7 * 24 * 3600),
+            sync_interval_secs: env_or("ARTHA_SEARCH_SYNC_INTERVAL_SECS", 5),
+        }
+    }
+}
+
+/// One searchable record
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchDoc {
+    pub kind: String, // "job", "model", "dataset", "transaction"
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub owner: Option<String>, // DID or tenant the document is private to; public if unset
+    pub at: u64,               // Submission or registration time
+    #[serde(default)]
+    pub fields: BTreeMap<String, Vec<String>>, // Filterable values, e.g. "status" -> ["completed"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+    #[serde(default)]
+    pub logged_at: u64, // Time of the last log line
+}
+
+impl SearchDoc {
+    pub fn new(kind: &str, id: &str, title: String, at: u64) -> Self {
+        SearchDoc {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            title,
+            owner: None,
+            at,
+            fields: BTreeMap::new(),
+            logs: Vec::new(),
+            logged_at: at,
+        }
+    }
+
+    /// Private to `namespace`'s tenant; documents in the shared namespace stay public
+    pub fn owned_by(mut self, namespace: &Namespace) -> Self {
+        self.owner = namespace.tenant().map(tenant_owner);
+        self
+    }
+
+    /// Add a filterable value; empty values are skipped
+    pub fn field(mut self, key: &str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        if !value.is_empty() {
+            self.fields.entry(key.to_string()).or_default().push(value);
+        }
+        self
+    }
+
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.kind, self.id)
+    }
+
+    fn first(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).and_then(|values| values.first()).map(|v| v.as_str())
+    }
+}
+
+/// A transaction sent by the contract client, waiting to be indexed
+#[derive(Debug, Clone)]
+pub struct SentTx {
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    pub contract: String, // "AIJobManager", ...
+    pub method: String,   // Function name, or the raw selector if unknown
+    pub at: u64,
+}
+
+impl SentTx {
+    pub fn into_doc(self) -> SearchDoc {
+        SearchDoc::new("transaction", &self.hash, format!("{}.{}", self.contract, self.method), self.at)
+            .field("method", self.method)
+            .field("contract", self.contract)
+            .field("from", self.from)
+            .field("to", self.to)
+    }
+}
+
+/// Who is searching
+#[derive(Debug, Clone, PartialEq)]
+pub enum Viewer {
+    Internal,    // Sees everything
+    Did(String), // Public documents and their own
+    Anonymous,   // Public documents only
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>, // Comma-separated kinds
+    #[serde(default)]
+    pub facets: Option<String>, // Comma-separated facets to count; all when unset
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub score: f32, // Zero when no free text ranks the hits; they are newest first then
+    pub highlights: BTreeMap<String, String>, // Field -> HTML snippet with <b> around matches
+    pub doc: SearchDoc,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+    pub facets: BTreeMap<String, BTreeMap<String, u64>>, // Facet -> value -> matching documents
+}
+
+/// A query split into free text and typed filters
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedQuery {
+    pub text: String,
+    pub filters: BTreeMap<String, Vec<String>>, // Values of one key are alternatives
+    pub after: Option<u64>,  // Inclusive, start of the day
+    pub before: Option<u64>, // Exclusive, start of the day
+}
+
+/// Split on whitespace outside double quotes
+fn tokens(q: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in q.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+pub fn parse_query(q: &str) -> Result<ParsedQuery, String> {
+    let mut parsed = ParsedQuery::default();
+    let mut text = Vec::new();
+    for token in tokens(q) {
+        let Some((key, value)) = token.split_once(':') else {
+            text.push(token);
+            continue;
+        };
+        let key = key.to_lowercase();
+        let value = value.trim_matches('"');
+        match key.as_str() {
+            "before" | "after" => {
+                let day = parse_date(value).ok_or_else(|| format!("{}: expected a YYYY-MM-DD date, got {:?}", key, value))?;
+                if key == "before" {
+                    parsed.before = Some(day);
+                } else {
+                    parsed.after = Some(day);
+                }
+            }
+            key if FILTER_KEYS.contains(&key) => {
+                if value.is_empty() {
+                    return Err(format!("{}: missing a value", key));
+                }
+                parsed.filters.entry(key.to_string()).or_default().push(value.to_lowercase());
+            }
+            _ => text.push(token),
+        }
+    }
+    parsed.text = text.join(" ");
+    Ok(parsed)
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Unix time at the start of a `YYYY-MM-DD` day (UTC)
+fn parse_date(value: &str) -> Option<u64> {
+    let mut parts = value.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Reject days past the end of the month, e.g. 2025-02-30
+    (civil_from_days(days) == (year, month, day) && days >= 0).then(|| days as u64 * DAY_SECS)
+}
+
+/// Month a document falls in, its date facet
+fn month_bucket(at: u64) -> String {
+    let (year, month, _) = civil_from_days((at / DAY_SECS) as i64);
+    format!("{:04}-{:02}", year, month)
+}
+
+struct Fields {
+    key: Field,
+    title: Field,
+    body: Field,
+    logs: Field,
+    terms: Field,
+    owner: Field,
+    at: Field,
+    facets: Field,
+    source: Field,
+}
+
+/// What is indexed under a document key
+struct Indexed {
+    at: u64,
+    signature: u64,
+    logged_at: u64,
+    has_logs: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JournalEntry {
+    Doc(SearchDoc),
+    Removed { removed: String },
+}
+
+pub struct SearchIndex {
+    config: SearchConfig,
+    index: Index,
+    writer: IndexWriter,
+    reader: IndexReader,
+    fields: Fields,
+    indexed: HashMap<String, Indexed>,
+    related: HashMap<String, SearchDoc>, // Models and datasets, for enriching job documents
+    staged: Vec<SearchDoc>,
+}
+
+impl SearchIndex {
+    /// Build the index and replay the journal into it
+    pub fn open(config: SearchConfig, now: u64) -> Result<Self, String> {
+        let mut builder = Schema::builder();
+        let fields = Fields {
+            key: builder.add_text_field("key", STRING | STORED),
+            title: builder.add_text_field("title", TEXT),
+            body: builder.add_text_field("body", TEXT),
+            logs: builder.add_text_field("logs", TEXT),
+            terms: builder.add_text_field("terms", STRING),
+            owner: builder.add_text_field("owner", STRING),
+            at: builder.add_u64_field("at", INDEXED | FAST),
+            facets: builder.add_facet_field("facets", FacetOptions::default()),
+            source: builder.add_text_field("source", STORED),
+        };
+        let index = Index::create_in_ram(builder.build());
+        let writer = index.writer_with_num_threads(1, 15_000_000).map_err(|e| e.to_string())?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|e: tantivy::TantivyError| e.to_string())?;
+        let mut search = SearchIndex {
+            config,
+            index,
+            writer,
+            reader,
+            fields,
+            indexed: HashMap::new(),
+            related: HashMap::new(),
+            staged: Vec::new(),
+        };
+        search.rebuild(now)?;
+        Ok(search)
+    }
+
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.indexed.len()
+    }
+
+    /// Queue a document for the next sync
+    pub fn stage(&mut self, doc: SearchDoc) {
+        self.staged.push(doc);
+    }
+
+    pub fn take_staged(&mut self) -> Vec<SearchDoc> {
+        std::mem::take(&mut self.staged)
+    }
+
+    /// An indexed model or dataset
+    pub fn related(&self, kind: &str, id: &str) -> Option<&SearchDoc> {
+        self.related.get(&format!("{}:{}", kind, id))
+    }
+
+    /// Cut a document's logs to the excerpt budget, or drop them once expired
+    fn retain_logs(&self, mut doc: SearchDoc, now: u64) -> SearchDoc {
+        if now.saturating_sub(doc.logged_at) >= self.config.log_retention_secs {
+            doc.logs.clear();
+            return doc;
+        }
+        let mut budget = self.config.max_log_bytes;
+        let keep = doc
+            .logs
+            .iter()
+            .rev()
+            .take_while(|line| match budget.checked_sub(line.len()) {
+                Some(rest) => {
+                    budget = rest;
+                    true
+                }
+                None => false,
+            })
+            .count();
+        doc.logs.drain(..doc.logs.len() - keep);
+        doc
+    }
+
+    fn signature(doc: &SearchDoc) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_vec(doc).unwrap_or_default().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn tantivy_doc(&self, doc: &SearchDoc) -> TantivyDocument {
+        let f = &self.fields;
+        let mut out = TantivyDocument::default();
+        out.add_text(f.key, doc.key());
+        out.add_text(f.title, &doc.title);
+        let mut body = vec![doc.id.clone()];
+        body.extend(doc.fields.values().flatten().cloned());
+        out.add_text(f.body, body.join(" "));
+        for line in &doc.logs {
+            out.add_text(f.logs, line);
+        }
+        out.add_text(f.terms, format!("type:{}", doc.kind));
+        for (key, values) in &doc.fields {
+            for value in values {
+                out.add_text(f.terms, format!("{}:{}", key, value.to_lowercase()));
+            }
+        }
+        out.add_text(f.owner, doc.owner.as_deref().unwrap_or(PUBLIC));
+        out.add_u64(f.at, doc.at);
+        out.add_facet(f.facets, Facet::from_path(["type", doc.kind.as_str()]));
+        for key in ["status", "architecture"] {
+            if let Some(value) = doc.first(key) {
+                out.add_facet(f.facets, Facet::from_path([key, value.to_lowercase().as_str()]));
+            }
+        }
+        out.add_facet(f.facets, Facet::from_path(["date".to_string(), month_bucket(doc.at)]));
+        out.add_text(f.source, serde_json::to_string(doc).unwrap_or_default());
+        out
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.writer.delete_term(Term::from_field_text(self.fields.key, key));
+        self.indexed.remove(key);
+        self.related.remove(key);
+    }
+
+    /// Index a document unless it is unchanged. Returns what was indexed,
+    /// after log retention.
+    fn put(&mut self, doc: SearchDoc, now: u64) -> Result<Option<SearchDoc>, String> {
+        let doc = self.retain_logs(doc, now);
+        let key = doc.key();
+        let signature = Self::signature(&doc);
+        if self.indexed.get(&key).is_some_and(|indexed| indexed.signature == signature) {
+            return Ok(None);
+        }
+        self.writer.delete_term(Term::from_field_text(self.fields.key, &key));
+        self.writer.add_document(self.tantivy_doc(&doc)).map_err(|e| e.to_string())?;
+        self.indexed.insert(
+            key.clone(),
+            Indexed { at: doc.at, signature, logged_at: doc.logged_at, has_logs: !doc.logs.is_empty() },
+        );
+        if doc.kind == "model" || doc.kind == "dataset" {
+            self.related.insert(key, doc.clone());
+        }
+        Ok(Some(doc))
+    }
+
+    /// Keys of the oldest documents beyond the size bound
+    fn over_bound(&self) -> Vec<String> {
+        let excess = self.indexed.len().saturating_sub(self.config.max_docs);
+        if excess == 0 {
+            return Vec::new();
+        }
+        let mut by_age: Vec<(&String, u64)> = self.indexed.iter().map(|(key, indexed)| (key, indexed.at)).collect();
+        by_age.sort_by_key(|(key, at)| (*at, *key));
+        by_age.into_iter().take(excess).map(|(key, _)| key.clone()).collect()
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        self.writer.commit().map_err(|e| e.to_string())?;
+        self.reader.reload().map_err(|e| e.to_string())
+    }
+
+    /// Index new and changed documents, evict beyond the size bound and
+    /// journal both. Returns how many documents changed.
+    pub fn upsert(&mut self, docs: Vec<SearchDoc>, now: u64) -> Result<usize, String> {
+        let mut journal = Vec::new();
+        for doc in docs {
+            if let Some(indexed) = self.put(doc, now)? {
+                journal.push(JournalEntry::Doc(indexed));
+            }
+        }
+        for key in self.over_bound() {
+            self.remove(&key);
+            journal.push(JournalEntry::Removed { removed: key });
+        }
+        if journal.is_empty() {
+            return Ok(0);
+        }
+        self.commit()?;
+        self.append(&journal);
+        Ok(journal.len())
+    }
+
+    /// Drop log excerpts whose last line is past the retention
+    pub fn expire_logs(&mut self, now: u64) -> Result<usize, String> {
+        let expired: Vec<String> = self
+            .indexed
+            .iter()
+            .filter(|(_, indexed)| indexed.has_logs && now.saturating_sub(indexed.logged_at) >= self.config.log_retention_secs)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut docs = Vec::new();
+        for key in expired {
+            if let Some(doc) = self.stored(&key)? {
+                docs.push(doc);
+            }
+        }
+        self.upsert(docs, now)
+    }
+
+    fn stored(&self, key: &str) -> Result<Option<SearchDoc>, String> {
+        let searcher = self.reader.searcher();
+        let query = TermQuery::new(Term::from_field_text(self.fields.key, key), IndexRecordOption::Basic);
+        let top = searcher.search(&query, &TopDocs::with_limit(1)).map_err(|e| e.to_string())?;
+        match top.first() {
+            Some((_, address)) => self.source(*address).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn source(&self, address: DocAddress) -> Result<SearchDoc, String> {
+        let stored: TantivyDocument = self.reader.searcher().doc(address).map_err(|e| e.to_string())?;
+        let source = stored.get_first(self.fields.source).and_then(|v| v.as_str()).unwrap_or_default();
+        serde_json::from_str(source).map_err(|e| e.to_string())
+    }
+
+    fn append(&self, entries: &[JournalEntry]) {
+        let Some(path) = &self.config.journal_path else { return };
+        let result = (|| -> std::io::Result<()> {
+            if let Some(dir) = std::path::Path::new(path).parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            for entry in entries {
+                writeln!(file, "{}", serde_json::to_string(entry)?)?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            warn!("⚠️  Failed to journal search documents: {}", e);
+        }
+    }
+
+    /// Replace the index with the journal's latest version of every document
+    /// and compact the journal to match. Returns the documents indexed.
+    pub fn rebuild(&mut self, now: u64) -> Result<usize, String> {
+        let mut latest: BTreeMap<String, SearchDoc> = BTreeMap::new();
+        if let Some(journal) = self.config.journal_path.as_ref().and_then(|path| std::fs::read_to_string(path).ok()) {
+            for line in journal.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str(line) {
+                    Ok(JournalEntry::Doc(doc)) => {
+                        latest.insert(doc.key(), doc);
+                    }
+                    Ok(JournalEntry::Removed { removed }) => {
+                        latest.remove(&removed);
+                    }
+                    Err(e) => warn!("⚠️  Skipping unreadable search journal line: {}", e),
+                }
+            }
+        }
+
+        self.writer.delete_all_documents().map_err(|e| e.to_string())?;
+        self.indexed.clear();
+        self.related.clear();
+        let mut docs: Vec<SearchDoc> = Vec::with_capacity(latest.len());
+        let mut latest: Vec<SearchDoc> = latest.into_values().collect();
+        latest.sort_by_key(|doc| doc.at);
+        for doc in latest {
+            docs.extend(self.put(doc, now)?);
+        }
+        for key in self.over_bound() {
+            self.remove(&key);
+        }
+        self.commit()?;
+
+        if let Some(path) = &self.config.journal_path {
+            let kept: Vec<String> = docs
+                .into_iter()
+                .filter(|doc| self.indexed.contains_key(&doc.key()))
+                .filter_map(|doc| serde_json::to_string(&JournalEntry::Doc(doc)).ok())
+                .collect();
+            let tmp = format!("{}.tmp", path);
+            let written = std::fs::write(&tmp, kept.iter().map(|line| format!("{}\n", line)).collect::<String>())
+                .and_then(|_| std::fs::rename(&tmp, path));
+            if let Err(e) = written {
+                warn!("⚠️  Failed to compact the search journal: {}", e);
+            }
+        }
+        Ok(self.indexed.len())
+    }
+
+    fn filter(&self, key: &str, values: &[String]) -> Box<dyn Query> {
+        let term = |value: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.terms, &format!("{}:{}", key, value)),
+                IndexRecordOption::Basic,
+            ))
+        };
+        match values {
+            [value] => term(value),
+            values => Box::new(BooleanQuery::new(values.iter().map(|v| (Occur::Should, term(v))).collect())),
+        }
+    }
+
+    pub fn search(&self, request: &SearchRequest, viewer: &Viewer) -> Result<SearchResults, String> {
+        let mut parsed = parse_query(&request.q)?;
+        if let Some(kinds) = &request.kind {
+            let kinds: Vec<String> = kinds.split(',').map(|k| k.trim().to_lowercase()).filter(|k| !k.is_empty()).collect();
+            if !kinds.is_empty() {
+                parsed.filters.insert("type".to_string(), kinds);
+            }
+        }
+        let facets: Vec<String> = match &request.facets {
+            Some(facets) => facets.split(',').map(|f| f.trim().to_lowercase()).filter(|f| !f.is_empty()).collect(),
+            None => FACETS.iter().map(|f| f.to_string()).collect(),
+        };
+        if let Some(unknown) = facets.iter().find(|f| !FACETS.contains(&f.as_str())) {
+            return Err(format!("Unknown facet {}; expected one of {}", unknown, FACETS.join(", ")));
+        }
+
+        let text_query = (!parsed.text.is_empty()).then(|| {
+            let mut parser = QueryParser::for_index(&self.index, vec![self.fields.title, self.fields.body, self.fields.logs]);
+            parser.set_field_boost(self.fields.title, 3.0);
+            parser.parse_query_lenient(&parsed.text).0
+        });
+
+        // Filters only narrow; the text alone scores
+        let mut filters: Vec<Box<dyn Query>> = parsed.filters.iter().map(|(key, values)| self.filter(key, values)).collect();
+        if parsed.after.is_some() || parsed.before.is_some() {
+            filters.push(Box::new(RangeQuery::new_u64_bounds(
+                "at".to_string(),
+                parsed.after.map_or(Bound::Unbounded, Bound::Included),
+                parsed.before.map_or(Bound::Unbounded, Bound::Excluded),
+            )));
+        }
+        let visible = |owner: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(Term::from_field_text(self.fields.owner, owner), IndexRecordOption::Basic))
+        };
+        match viewer {
+            Viewer::Internal => {}
+            Viewer::Did(did) => {
+                let mut owners = vec![(Occur::Should, visible(PUBLIC)), (Occur::Should, visible(did))];
+                if let Some(tenant) = Namespace::of(did).tenant() {
+                    owners.push((Occur::Should, visible(&tenant_owner(tenant))));
+                }
+                filters.push(Box::new(BooleanQuery::new(owners)));
+            }
+            Viewer::Anonymous => filters.push(visible(PUBLIC)),
+        }
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = filters
+            .into_iter()
+            .map(|filter| (Occur::Must, Box::new(ConstScoreQuery::new(filter, 0.0)) as Box<dyn Query>))
+            .collect();
+        if let Some(text) = &text_query {
+            clauses.push((Occur::Must, text.box_clone()));
+        }
+        let query: Box<dyn Query> = if clauses.is_empty() { Box::new(AllQuery) } else { Box::new(BooleanQuery::new(clauses)) };
+
+        let searcher = self.reader.searcher();
+        let limit = request.limit.unwrap_or(20).clamp(1, 100);
+        let top = TopDocs::with_limit(limit).and_offset(request.offset.unwrap_or(0));
+        let mut facet_collector = FacetCollector::for_field("facets");
+        for facet in &facets {
+            facet_collector.add_facet(Facet::from_path([facet.as_str()]));
+        }
+        let (scored, total, facet_counts): (Vec<(f32, DocAddress)>, usize, _) = match &text_query {
+            Some(_) => searcher.search(&query, &(top, Count, facet_collector)).map_err(|e| e.to_string())?,
+            None => {
+                let (newest, total, counts) = searcher
+                    .search(&query, &(top.order_by_u64_field("at", Order::Desc), Count, facet_collector))
+                    .map_err(|e| e.to_string())?;
+                (newest.into_iter().map(|(_, address)| (0.0, address)).collect(), total, counts)
+            }
+        };
+
+        let snippets = match &text_query {
+            Some(text) => [("title", self.fields.title), ("body", self.fields.body), ("logs", self.fields.logs)]
+                .into_iter()
+                .map(|(name, field)| {
+                    let mut generator = SnippetGenerator::create(&searcher, &**text, field).map_err(|e| e.to_string())?;
+                    generator.set_max_num_chars(160);
+                    Ok((name, generator))
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+            None => Vec::new(),
+        };
+
+        let mut hits = Vec::with_capacity(scored.len());
+        for (score, address) in scored {
+            let stored: TantivyDocument = searcher.doc(address).map_err(|e| e.to_string())?;
+            let doc: SearchDoc = serde_json::from_str(
+                stored.get_first(self.fields.source).and_then(|v| v.as_str()).unwrap_or_default(),
+            )
+            .map_err(|e| e.to_string())?;
+            let highlights = snippets
+                .iter()
+                .filter_map(|(name, generator)| {
+                    let snippet = generator.snippet_from_doc(&self.tantivy_doc(&doc));
+                    (!snippet.highlighted().is_empty()).then(|| (name.to_string(), snippet.to_html()))
+                })
+                .collect();
+            hits.push(SearchHit { kind: doc.kind.clone(), id: doc.id.clone(), title: doc.title.clone(), score, highlights, doc });
+        }
+
+        let facets = facets
+            .iter()
+            .map(|facet| {
+                let counts = facet_counts
+                    .get(&format!("/{}", facet))
+                    .map(|(value, count)| (value.to_path().last().map_or(String::new(), |v| v.to_string()), count))
+                    .collect();
+                (facet.clone(), counts)
+            })
+            .collect();
+        Ok(SearchResults { total, hits, facets })
+    }
+}
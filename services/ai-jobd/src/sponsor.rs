@@ -0,0 +1,165 @@
+//! Sponsored Submission
+//! Sends contract calls as user operations through the node bundler, signed by
+//! a scoped session key, so gas comes from the org's paymaster and the
+//! submitting account never holds native tokens. Hashing matches
+//! blockchain_node `evm::account_abstraction` (docs/ACCOUNT_ABSTRACTION.md).
+
+use k256::ecdsa::SigningKey;
+use sha3::{Digest, Keccak256};
+
+/// Entry point user operations are bound to
+const ENTRY_POINT: [u8; 20] = [0xaa; 20];
+
+pub struct Sponsor {
+    pub bundler_url: String,
+    pub account: [u8; 20],
+    pub paymaster: [u8; 20],
+    session_key: SigningKey,
+    pub chain_id: u64,
+    pub call_gas_limit: u64,
+    pub max_fee_per_gas: u128,
+}
+
+fn parse_address(value: &str) -> Result<[u8; 20], String> {
+    let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|e| format!("{}: {}", value, e))?;
+    bytes.try_into().map_err(|_| format!("{}: not a 20-byte address", value))
+}
+
+fn word(out: &mut Vec<u8>, value: u128) {
+    out.extend_from_slice(&[0u8; 16]);
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn word_address(out: &mut Vec<u8>, address: &[u8; 20]) {
+    out.extend_from_slice(&[0u8; 12]);
+    out.extend_from_slice(address);
+}
+
+pub fn address_of(key: &SigningKey) -> [u8; 20] {
+    let point = key.verifying_key().to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    hash[12..].try_into().unwrap()
+}
+
+impl Sponsor {
+    pub fn new(
+        bundler_url: String,
+        account: &str,
+        paymaster: &str,
+        session_key_hex: &str,
+        chain_id: u64,
+    ) -> Result<Self, String> {
+        let key = hex::decode(session_key_hex.trim_start_matches("0x")).map_err(|e| format!("session key: {}", e))?;
+        Ok(Sponsor {
+            bundler_url,
+            account: parse_address(account)?,
+            paymaster: parse_address(paymaster)?,
+            session_key: SigningKey::from_slice(&key).map_err(|e| format!("session key: {}", e))?,
+            chain_id,
+            call_gas_limit: 500_000,
+            max_fee_per_gas: 20_000_000_000,
+        })
+    }
+
+    /// Sponsored mode when ARTHA_BUNDLER_URL is set
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(bundler_url) = std::env::var("ARTHA_BUNDLER_URL") else {
+            return Ok(None);
+        };
+        let var = |name: &str| std::env::var(name).map_err(|_| format!("{} is required with ARTHA_BUNDLER_URL", name));
+        let chain_id = std::env::var("ARTHA_CHAIN_ID")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(201766);
+        Sponsor::new(
+            bundler_url,
+            &var("ARTHA_AA_ACCOUNT")?,
+            &var("ARTHA_AA_PAYMASTER")?,
+            &var("ARTHA_AA_SESSION_KEY")?,
+            chain_id,
+        )
+        .map(Some)
+    }
+
+    pub fn session_address(&self) -> [u8; 20] {
+        address_of(&self.session_key)
+    }
+
+    /// Hash the session key signs, for a zero-value call of `data` on `target`
+    pub fn user_op_hash(&self, nonce: u64, target: &[u8; 20], data: &[u8]) -> [u8; 32] {
+        let mut encoded = Vec::with_capacity(32 * 11);
+        word_address(&mut encoded, &ENTRY_POINT);
+        word(&mut encoded, self.chain_id as u128);
+        word_address(&mut encoded, &self.account);
+        word(&mut encoded, nonce as u128);
+        word_address(&mut encoded, target);
+        word(&mut encoded, 0);
+        encoded.extend_from_slice(&Keccak256::digest(data));
+        word(&mut encoded, self.call_gas_limit as u128);
+        word(&mut encoded, self.max_fee_per_gas);
+        word_address(&mut encoded, &self.paymaster);
+        word_address(&mut encoded, &self.session_address());
+        Keccak256::digest(&encoded).into()
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> Result<Vec<u8>, String> {
+        let (sig, recovery_id) = self
+            .session_key
+            .sign_prehash_recoverable(hash)
+            .map_err(|e| format!("sign user operation: {}", e))?;
+        let mut out = sig.to_bytes().to_vec();
+        out.push(27 + recovery_id.to_byte());
+        Ok(out)
+    }
+
+    /// Submit `data` to `target` through the bundler; returns the user operation hash
+    pub async fn submit(&self, client: &reqwest::Client, target: &str, data: &[u8]) -> Result<String, String> {
+        let target = parse_address(target)?;
+        let account = format!("0x{}", hex::encode(self.account));
+
+        let url = format!("{}/bundler/accounts/{}", self.bundler_url, account);
+        let nonce = client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Bundler nonce lookup failed: {}", e))?
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?["nonce"]
+            .as_u64()
+            .ok_or_else(|| format!("No nonce for account {}", account))?;
+
+        let hash = self.user_op_hash(nonce, &target, data);
+        let op = serde_json::json!({
+            "sender": account,
+            "nonce": nonce,
+            "target": format!("0x{}", hex::encode(target)),
+            "value": "0x0",
+            "callData": format!("0x{}", hex::encode(data)),
+            "callGasLimit": self.call_gas_limit,
+            "maxFeePerGas": format!("0x{:x}", self.max_fee_per_gas),
+            "paymaster": format!("0x{}", hex::encode(self.paymaster)),
+            "sessionKey": format!("0x{}", hex::encode(self.session_address())),
+            "signature": format!("0x{}", hex::encode(self.sign(&hash)?)),
+        });
+
+        let response = client
+            .post(format!("{}/bundler/userop", self.bundler_url))
+            .json(&op)
+            .send()
+            .await
+            .map_err(|e| format!("User operation failed: {}", e))?;
+        let status = response.status();
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        if !status.is_success() {
+            return Err(format!("User operation rejected: {}", result["error"]));
+        }
+        result["userOpHash"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| "No userOpHash in response".to_string())
+    }
+}
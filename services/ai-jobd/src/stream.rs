@@ -0,0 +1,123 @@
+//! Stream Jobs
+//! Submission shape of `Stream` jobs: an input event stream, the transform
+//! each event runs through, how results are cut into windows and how the
+//! resulting dataset versions are named. ai-runtime runs the stream and
+//! registers every completed window back here as a dataset version; this
+//! daemon only checks the spec before queueing it and hands it over on
+//! assignment.
+
+use serde::{Deserialize, Serialize};
+
+/// Mirrors ai-runtime's per-call default so submissions round-trip unchanged
+const DEFAULT_GAS_LIMIT: u64 = 10_000_000;
+/// Most windows an event may fall into (`size / slide`)
+const MAX_OVERLAP: u64 = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransformSpec {
+    Wasm {
+        module_cid: String,
+        #[serde(default = "default_gas_limit")]
+        gas_limit: u64, // Per map/aggregate call
+    },
+    Container {
+        image: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+fn default_gas_limit() -> u64 {
+    DEFAULT_GAS_LIMIT
+}
+
+impl TransformSpec {
+    /// What the job runs, recorded as its model reference
+    pub fn reference(&self) -> &str {
+        match self {
+            TransformSpec::Wasm { module_cid, .. } => module_cid,
+            TransformSpec::Container { image, .. } => image,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowUnit {
+    Count, // Event offsets
+    Time,  // Event timestamps, milliseconds
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WindowPolicy {
+    Tumbling { by: WindowUnit, size: u64 },
+    Sliding { by: WindowUnit, size: u64, slide: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamOutput {
+    pub dataset: String, // Dataset name; `{job_id}` expands
+    #[serde(default = "default_version")]
+    pub version: String, // Per window; `{window}`, `{start}` and `{end}` expand
+    pub license_cid: String,
+}
+
+fn default_version() -> String {
+    "w{window}".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamSpec {
+    pub input_stream: String, // Base URL serving `/stream/events`
+    pub transform: TransformSpec,
+    pub window: WindowPolicy,
+    pub output: StreamOutput,
+}
+
+impl StreamSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.input_stream.is_empty() {
+            return Err("input_stream is required".to_string());
+        }
+        match &self.transform {
+            TransformSpec::Wasm { module_cid, gas_limit } => {
+                if module_cid.is_empty() || *gas_limit == 0 {
+                    return Err("WASM transforms need a module_cid and a gas_limit above 0".to_string());
+                }
+            }
+            TransformSpec::Container { image, .. } if image.is_empty() => {
+                return Err("Container transforms need an image".to_string());
+            }
+            TransformSpec::Container { .. } => {}
+        }
+        let (size, slide) = match self.window {
+            WindowPolicy::Tumbling { size, .. } => (size, size),
+            WindowPolicy::Sliding { size, slide, .. } => (size, slide),
+        };
+        if size == 0 || slide == 0 || slide > size {
+            return Err(format!("Window slide must be between 1 and the window size, got size {} slide {}", size, slide));
+        }
+        if size.div_ceil(slide) > MAX_OVERLAP {
+            return Err(format!("Windows may overlap at most {} deep", MAX_OVERLAP));
+        }
+        if self.output.dataset.is_empty() || self.output.license_cid.is_empty() {
+            return Err("output needs a dataset name and a license_cid".to_string());
+        }
+        if !self.output.version.contains("{window}") && !self.output.version.contains("{start}") {
+            return Err("output version must include {window} or {start} to be unique per window".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamJobRequest {
+    pub submitter_did: String,
+    #[serde(flatten)]
+    pub spec: StreamSpec,
+    pub budget: u64,
+    #[serde(default)]
+    pub nonce: Option<u64>,
+}
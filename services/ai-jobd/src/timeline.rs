@@ -0,0 +1,542 @@
+//! Job Timeline
+//! Where a job's time and money went, assembled on request from the records
+//! services already keep instead of from new polling:
+//! - ai-jobd's event store: submission, policy latency, queueing, assignment
+//!   with the scheduler's score breakdown and rate, container startup,
+//!   progress and spend, completion
+//! - ai-runtime: when each checkpoint appeared, and GPU telemetry buckets
+//! - ai-proofs: proof submission times
+//! - receipts: finalize and settlement
+//!
+//! Remote records are read concurrently with a short timeout. A source that
+//! can't be read marks the phases it bounds `unavailable` rather than failing
+//! the whole view.
+//!
+//! Cost attribution: queue, pull and container startup are free; GPU time is
+//! charged at the assignment's per-GPU-second rate; whatever the job spent
+//! beyond that is verification overhead. Phase costs always sum to the job's
+//! spent amount, with anything that has no phase to land on reported as
+//! unattributed.
+
+use crate::{Job, JobStatus};
+use artha_paging::Page;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// Longest wait for any one remote source
+pub const SOURCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// GPU time without a telemetry bucket for this long is flagged
+pub const TELEMETRY_GAP_SECS: u64 = 180;
+
+/// Pull durations needed before slow pulls are flagged against their P95
+pub const MIN_PULL_BASELINE: usize = 20;
+
+/// Pull durations kept for the baseline, newest last
+const PULL_HISTORY: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Submitted,
+    PolicyDecided,
+    Queued,
+    Assigned,
+    ContainerStarted,
+    Checkpoint,
+    Progress,
+    Completed,
+    Failed,
+    ProofSubmitted,
+    Finalized,
+    Settled,
+    Reorged, // A key transaction was un-mined or mined again, moving the status
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobEvent {
+    pub at: u64,
+    pub source: String, // Service the event came from
+    pub kind: EventKind,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub detail: serde_json::Value,
+}
+
+/// What ai-jobd itself observed of each job, in the order it happened
+#[derive(Debug, Default)]
+pub struct EventStore {
+    events: HashMap<String, Vec<JobEvent>>,
+    pull_ms: VecDeque<u64>, // Every job's input pull time, for the slow-pull baseline
+}
+
+impl EventStore {
+    pub fn record(&mut self, job_id: &str, at: u64, kind: EventKind, detail: serde_json::Value) {
+        if kind == EventKind::ContainerStarted {
+            if let Some(pull_ms) = detail["pull_ms"].as_u64().filter(|_| detail["warm"] != true) {
+                if self.pull_ms.len() == PULL_HISTORY {
+                    self.pull_ms.pop_front();
+                }
+                self.pull_ms.push_back(pull_ms);
+            }
+        }
+        self.events.entry(job_id.to_string()).or_default().push(JobEvent {
+            at,
+            source: "ai-jobd".to_string(),
+            kind,
+            detail,
+        });
+    }
+
+    pub fn for_job(&self, job_id: &str) -> Vec<JobEvent> {
+        self.events.get(job_id).cloned().unwrap_or_default()
+    }
+
+    pub fn remove(&mut self, job_id: &str) {
+        self.events.remove(job_id);
+    }
+
+    /// 95th percentile pull time across jobs, once there are enough of them
+    pub fn pull_p95_ms(&self) -> Option<u64> {
+        if self.pull_ms.len() < MIN_PULL_BASELINE {
+            return None;
+        }
+        let mut sorted: Vec<u64> = self.pull_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100); // Nearest rank
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+/// A remote source's records, or why they couldn't be read
+pub type Fetched<T> = Result<T, String>;
+
+/// What ai-runtime keeps of the job
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeRecord {
+    pub checkpoint_times: Vec<u64>,
+    pub telemetry_buckets: Vec<u64>, // Bucket starts across every device, ascending
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProofRecord {
+    pub proof_type: String,
+    pub step: Option<u64>,
+    pub timestamp: u64,
+    pub submitted: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReceiptRecord {
+    pub receipt_id: String,
+    pub amount_wei: u64,
+    pub created_at: u64,
+    pub settled_at: Option<u64>,
+    #[serde(default)]
+    pub finalize_tx: Option<String>,
+}
+
+/// Records read from the other services for one job
+#[derive(Debug, Clone)]
+pub struct RemoteRecords {
+    pub runtime: Fetched<RuntimeRecord>,
+    pub proofs: Fetched<Vec<ProofRecord>>,
+    pub receipts: Fetched<Vec<ReceiptRecord>>,
+}
+
+async fn fetch_json(client: &reqwest::Client, url: &str, query: &[(&str, &str)]) -> Fetched<Option<serde_json::Value>> {
+    let response = tokio::time::timeout(SOURCE_TIMEOUT, client.get(url).query(query).send())
+        .await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    match response.status().as_u16() {
+        404 | 410 => Ok(None), // Nothing recorded for the job yet, or archived by retention
+        status if !response.status().is_success() => Err(format!("HTTP {}", status)),
+        _ => tokio::time::timeout(SOURCE_TIMEOUT, response.json())
+            .await
+            .map_err(|_| "timed out".to_string())?
+            .map(Some)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+async fn fetch_runtime(client: &reqwest::Client, runtime_url: &str, job_id: &str) -> Fetched<RuntimeRecord> {
+    let status_url = format!("{}/job/{}/status", runtime_url, job_id);
+    let telemetry_url = format!("{}/job/{}/gpu-telemetry", runtime_url, job_id);
+    let (status, telemetry) = tokio::join!(
+        fetch_json(client, &status_url, &[]),
+        fetch_json(client, &telemetry_url, &[]),
+    );
+    let checkpoint_times = status?
+        .and_then(|s| serde_json::from_value(s["checkpoint_times"].clone()).ok())
+        .unwrap_or_default();
+    let mut telemetry_buckets: Vec<u64> = telemetry?
+        .and_then(|t| t["devices"].as_object().cloned())
+        .unwrap_or_default()
+        .values()
+        .filter_map(|points| points.as_array())
+        .flatten()
+        .filter_map(|point| point["bucket_start"].as_u64())
+        .collect();
+    telemetry_buckets.sort_unstable();
+    telemetry_buckets.dedup();
+    Ok(RuntimeRecord { checkpoint_times, telemetry_buckets })
+}
+
+async fn fetch_proofs(client: &reqwest::Client, proofs_url: &str, job_id: &str) -> Fetched<Vec<ProofRecord>> {
+    let proofs = fetch_json(client, &format!("{}/proofs/{}", proofs_url, job_id), &[]).await?;
+    proofs.map(serde_json::from_value).transpose().map(Option::unwrap_or_default).map_err(|e| e.to_string())
+}
+
+async fn fetch_receipts(client: &reqwest::Client, receipts_url: &str, job_id: &str) -> Fetched<Vec<ReceiptRecord>> {
+    let page = fetch_json(client, &format!("{}/receipts", receipts_url), &[("job_id", job_id)]).await?;
+    let page: Option<Page<ReceiptRecord>> = page.map(serde_json::from_value).transpose().map_err(|e| e.to_string())?;
+    Ok(page.map(|p| p.items).unwrap_or_default())
+}
+
+/// Read every remote source for a job at once
+pub async fn fetch_remote(runtime_url: &str, proofs_url: &str, receipts_url: &str, job_id: &str) -> RemoteRecords {
+    let client = reqwest::Client::new();
+    let (runtime, proofs, receipts) = tokio::join!(
+        fetch_runtime(&client, runtime_url, job_id),
+        fetch_proofs(&client, proofs_url, job_id),
+        fetch_receipts(&client, receipts_url, job_id),
+    );
+    RemoteRecords { runtime, proofs, receipts }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseKind {
+    Policy,
+    Queue,
+    Pull,
+    Startup,
+    Gpu,
+    Verification,
+    Settlement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseState {
+    Complete,
+    InProgress,
+    Unavailable, // The source that bounds it couldn't be read
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    SlowPull,
+    TelemetryGap,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub message: String,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Phase {
+    pub phase: PhaseKind,
+    pub state: PhaseState,
+    pub source: String,
+    pub started_at: Option<u64>,
+    pub ended_at: Option<u64>,
+    pub duration_secs: Option<f64>,
+    pub billable: bool,
+    pub cost: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<Anomaly>,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub detail: serde_json::Value,
+}
+
+impl Phase {
+    fn new(phase: PhaseKind, source: &str, started_at: Option<u64>, ended_at: Option<u64>) -> Self {
+        let state = if ended_at.is_some() { PhaseState::Complete } else { PhaseState::InProgress };
+        Phase {
+            phase,
+            state,
+            source: source.to_string(),
+            started_at,
+            ended_at,
+            duration_secs: started_at.zip(ended_at).map(|(s, e)| e.saturating_sub(s) as f64),
+            billable: phase == PhaseKind::Gpu,
+            cost: 0,
+            anomalies: Vec::new(),
+            detail: serde_json::Value::Null,
+        }
+    }
+
+    fn unavailable(phase: PhaseKind, source: &str) -> Self {
+        Phase { state: PhaseState::Unavailable, ..Phase::new(phase, source, None, None) }
+    }
+
+    /// Elapsed so far for a phase still running
+    fn running_until(mut self, now: u64) -> Self {
+        if self.state == PhaseState::InProgress {
+            self.duration_secs = self.started_at.map(|s| now.saturating_sub(s) as f64);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceStatus {
+    pub source: String,
+    pub available: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineSummary {
+    pub total_wall_clock_secs: u64,
+    pub billable_secs: f64,
+    pub spent: u64,
+    pub gpu_cost: u64,
+    pub verification_cost: u64,
+    pub unattributed_cost: u64,
+    pub price_per_gpu_sec: Option<f64>, // The assignment's rate
+    pub effective_cost_per_gpu_hour: Option<f64>,
+    pub anomalies: usize,
+    pub unavailable_sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub job_id: String,
+    pub generated_at: u64,
+    pub events: Vec<JobEvent>, // Every source, ordered by time
+    pub phases: Vec<Phase>,
+    pub sources: Vec<SourceStatus>,
+    pub summary: TimelineSummary,
+}
+
+fn source_status<T>(source: &str, fetched: &Fetched<T>) -> SourceStatus {
+    SourceStatus {
+        source: source.to_string(),
+        available: fetched.is_ok(),
+        error: fetched.as_ref().err().cloned(),
+    }
+}
+
+fn remote_event(at: u64, source: &str, kind: EventKind, detail: serde_json::Value) -> JobEvent {
+    JobEvent { at, source: source.to_string(), kind, detail }
+}
+
+/// Stretches of GPU time in `from..to` with no telemetry bucket
+fn telemetry_gaps(buckets: &[u64], from: u64, to: u64) -> Vec<Anomaly> {
+    let mut marks: Vec<u64> = buckets.iter().copied().filter(|b| (from..=to).contains(b)).collect();
+    marks.insert(0, from);
+    marks.push(to);
+    marks
+        .windows(2)
+        .filter(|w| w[1].saturating_sub(w[0]) > TELEMETRY_GAP_SECS)
+        .map(|w| Anomaly {
+            kind: AnomalyKind::TelemetryGap,
+            message: format!("No GPU telemetry for {}s", w[1] - w[0]),
+            from: Some(w[0]),
+            to: Some(w[1]),
+        })
+        .collect()
+}
+
+/// Split `amount` across `weights` proportionally; rounding lands on the last share
+fn apportion(amount: u64, weights: &[f64]) -> Vec<u64> {
+    let total: f64 = weights.iter().sum();
+    let mut shares: Vec<u64> = weights
+        .iter()
+        .map(|w| if total > 0.0 { (amount as f64 * w / total).floor() as u64 } else { 0 })
+        .collect();
+    let remainder = amount - shares.iter().sum::<u64>().min(amount);
+    if let Some(last) = shares.last_mut() {
+        *last += remainder;
+    }
+    shares
+}
+
+/// Assemble a job's timeline from its ai-jobd events and the remote records
+pub fn assemble(job: &Job, local: Vec<JobEvent>, remote: &RemoteRecords, pull_p95_ms: Option<u64>, now: u64) -> Timeline {
+    let first = |kind: EventKind| local.iter().find(|e| e.kind == kind);
+
+    // Everything that happened, from every source
+    let mut events = local.clone();
+    if let Ok(runtime) = &remote.runtime {
+        for (i, at) in runtime.checkpoint_times.iter().enumerate() {
+            events.push(remote_event(*at, "ai-runtime", EventKind::Checkpoint, serde_json::json!({ "index": i + 1 })));
+        }
+    }
+    if let Ok(proofs) = &remote.proofs {
+        for proof in proofs.iter().filter(|p| p.submitted) {
+            let detail = serde_json::json!({ "proof_type": proof.proof_type, "step": proof.step });
+            events.push(remote_event(proof.timestamp, "ai-proofs", EventKind::ProofSubmitted, detail));
+        }
+    }
+    if let Ok(receipts) = &remote.receipts {
+        for receipt in receipts {
+            if receipt.finalize_tx.is_some() {
+                let detail = serde_json::json!({ "receipt_id": receipt.receipt_id, "finalize_tx": receipt.finalize_tx });
+                events.push(remote_event(receipt.created_at, "receipts", EventKind::Finalized, detail));
+            }
+            if let Some(settled_at) = receipt.settled_at {
+                let detail = serde_json::json!({ "receipt_id": receipt.receipt_id, "amount_wei": receipt.amount_wei });
+                events.push(remote_event(settled_at, "receipts", EventKind::Settled, detail));
+            }
+        }
+    }
+    events.sort_by_key(|e| e.at); // Stable: same-second events keep their order
+
+    let mut phases = Vec::new();
+
+    // Policy decision, then waiting for a node
+    let policy = first(EventKind::PolicyDecided);
+    let queued_from = match policy {
+        Some(event) => {
+            let latency_ms = event.detail["latency_ms"].as_u64().unwrap_or(0);
+            let mut phase = Phase::new(PhaseKind::Policy, "ai-jobd", Some(event.at.saturating_sub(latency_ms / 1000)), Some(event.at));
+            phase.duration_secs = Some(latency_ms as f64 / 1000.0);
+            phase.detail = serde_json::json!({ "latency_ms": latency_ms, "allowed": event.detail["allowed"] });
+            phases.push(phase);
+            event.at
+        }
+        None => job.submitted_at,
+    };
+    let assigned = first(EventKind::Assigned);
+    let dequeued_at = match assigned {
+        Some(event) => Some(event.at),
+        None if job.status == JobStatus::Queued => None,
+        None => job.started_at.or(job.completed_at), // Left the queue without an assignment on record
+    };
+    phases.push(Phase::new(PhaseKind::Queue, "ai-jobd", Some(queued_from), dequeued_at).running_until(now));
+    let rate = assigned.and_then(|e| e.detail["score"]["price_per_gpu_sec"].as_f64());
+
+    // Container startup on the node
+    let started = first(EventKind::ContainerStarted);
+    if let (Some(assigned), Some(started)) = (assigned, started) {
+        let pull_ms = started.detail["pull_ms"].as_u64().unwrap_or(0);
+        let pulled_at = (assigned.at + pull_ms / 1000).min(started.at);
+        let mut pull = Phase::new(PhaseKind::Pull, "ai-runtime", Some(assigned.at), Some(pulled_at));
+        pull.duration_secs = Some(pull_ms as f64 / 1000.0);
+        pull.detail = serde_json::json!({ "pull_ms": pull_ms, "warm": started.detail["warm"] });
+        if let Some(p95) = pull_p95_ms.filter(|p95| pull_ms > *p95) {
+            pull.anomalies.push(Anomaly {
+                kind: AnomalyKind::SlowPull,
+                message: format!("Pull took {}ms; P95 is {}ms", pull_ms, p95),
+                from: pull.started_at,
+                to: pull.ended_at,
+            });
+        }
+        phases.push(pull);
+        let mut startup = Phase::new(PhaseKind::Startup, "ai-runtime", Some(pulled_at), Some(started.at));
+        startup.duration_secs = started.detail["launch_ms"].as_u64().map(|ms| ms as f64 / 1000.0);
+        phases.push(startup);
+    }
+
+    // GPU time, one phase per checkpoint interval
+    let gpu_start = started.map(|e| e.at).or(job.started_at);
+    let gpu_end = job.completed_at;
+    if let Some(gpu_start) = gpu_start {
+        let mut cuts = vec![gpu_start];
+        // Without the runtime the intervals are unknown, but the GPU time itself is still on record
+        if let Ok(runtime) = &remote.runtime {
+            cuts.extend(runtime.checkpoint_times.iter().filter(|t| **t > gpu_start && gpu_end.is_none_or(|end| **t < end)));
+        }
+        let mut bounds: Vec<(u64, Option<u64>)> = cuts.windows(2).map(|w| (w[0], Some(w[1]))).collect();
+        bounds.push((*cuts.last().unwrap(), gpu_end));
+        let count = bounds.len();
+        for (i, (from, to)) in bounds.into_iter().enumerate() {
+            let mut phase = Phase::new(PhaseKind::Gpu, "ai-jobd", Some(from), to).running_until(now);
+            phase.detail = match &remote.runtime {
+                Ok(_) if i + 1 < count => serde_json::json!({ "until_checkpoint": i + 1 }),
+                Ok(_) => serde_json::json!({ "until": if to.is_some() { "completion" } else { "now" } }),
+                Err(_) => serde_json::json!({ "checkpoints": "unavailable" }),
+            };
+            if let Ok(runtime) = &remote.runtime {
+                phase.anomalies = telemetry_gaps(&runtime.telemetry_buckets, from, to.unwrap_or(now));
+            }
+            phases.push(phase);
+        }
+    }
+
+    // Verification: completion until the job is finalized, then settlement
+    if let Some(completed_at) = job.completed_at {
+        match &remote.receipts {
+            Ok(receipts) => {
+                let finalized = receipts.iter().filter(|r| r.finalize_tx.is_some()).map(|r| r.created_at).min();
+                let mut verification = Phase::new(PhaseKind::Verification, "receipts", Some(completed_at), finalized).running_until(now);
+                if let Ok(proofs) = &remote.proofs {
+                    verification.detail = serde_json::json!({ "proofs_submitted": proofs.iter().filter(|p| p.submitted).count() });
+                }
+                phases.push(verification);
+                if let Some(finalized) = finalized {
+                    let settled = receipts.iter().map(|r| r.settled_at).collect::<Option<Vec<u64>>>().and_then(|s| s.into_iter().max());
+                    let mut settlement = Phase::new(PhaseKind::Settlement, "receipts", Some(finalized), settled).running_until(now);
+                    settlement.detail = serde_json::json!({ "receipts": receipts.len() });
+                    phases.push(settlement);
+                }
+            }
+            Err(_) => {
+                phases.push(Phase::unavailable(PhaseKind::Verification, "receipts"));
+                phases.push(Phase::unavailable(PhaseKind::Settlement, "receipts"));
+            }
+        }
+    }
+
+    // Cost attribution
+    let spent = job.spent;
+    let gpu_weights: Vec<f64> = phases.iter().filter(|p| p.billable).map(|p| p.duration_secs.unwrap_or(0.0)).collect();
+    let billable_secs: f64 = gpu_weights.iter().sum();
+    let gpu_cost = match rate {
+        Some(rate) => ((rate * billable_secs).round() as u64).min(spent),
+        None => spent,
+    };
+    let gpu_shares = apportion(gpu_cost, &gpu_weights);
+    for (phase, share) in phases.iter_mut().filter(|p| p.billable).zip(&gpu_shares) {
+        phase.cost = *share;
+    }
+    // Beyond GPU time; lands on verification once the job has reached it
+    let verification_cost = match phases.iter_mut().find(|p| p.phase == PhaseKind::Verification) {
+        Some(phase) => {
+            phase.cost = spent - gpu_cost;
+            phase.cost
+        }
+        None => 0,
+    };
+    let attributed: u64 = phases.iter().map(|p| p.cost).sum();
+
+    let sources = vec![
+        SourceStatus { source: "ai-jobd".to_string(), available: true, error: None },
+        source_status("ai-runtime", &remote.runtime),
+        source_status("ai-proofs", &remote.proofs),
+        source_status("receipts", &remote.receipts),
+    ];
+    let last_at = phases.iter().filter_map(|p| p.ended_at).chain(events.iter().map(|e| e.at)).max();
+    let still_going = phases.iter().any(|p| p.state == PhaseState::InProgress);
+    let ended_at = if still_going { now } else { last_at.unwrap_or(now) };
+
+    let summary = TimelineSummary {
+        total_wall_clock_secs: ended_at.saturating_sub(job.submitted_at),
+        billable_secs,
+        spent,
+        gpu_cost,
+        verification_cost,
+        unattributed_cost: spent - attributed,
+        price_per_gpu_sec: rate,
+        effective_cost_per_gpu_hour: (billable_secs > 0.0).then(|| spent as f64 / billable_secs * 3600.0),
+        anomalies: phases.iter().map(|p| p.anomalies.len()).sum(),
+        unavailable_sources: sources.iter().filter(|s| !s.available).map(|s| s.source.clone()).collect(),
+    };
+    Timeline {
+        job_id: job.job_id.clone(),
+        generated_at: now,
+        events,
+        phases,
+        sources,
+        summary,
+    }
+}
@@ -0,0 +1,285 @@
+//! API Versioning
+//! One set of handlers serves every version. `/v1/*` is the wire format clients
+//! already depend on; `/v2/*` is where breaking changes land, produced by
+//! adapting v1 requests and responses at the edge rather than by forking
+//! handlers. The unversioned paths are deprecated aliases: they honour an
+//! `Accept-Version` header, default to v1, and always carry `Deprecation` and
+//! `Sunset` headers pointing at their versioned successor.
+//!
+//! v2 differs from v1 in three ways:
+//! - every error response has the structured `ServiceError` body, including
+//!   the plain-text and ad-hoc JSON errors v1 passes through untouched
+//! - raw output CIDs are never returned; clients use `output_id`
+//! - enum values are SCREAMING_SNAKE (`"RUNNING"`, not `"Running"`)
+
+use artha_errors::{ErrorCode, ServiceError};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+use crate::openapi;
+
+/// Request header selecting a version on the unversioned paths
+pub const ACCEPT_VERSION: &str = "accept-version";
+/// Response header naming the version that produced the response
+pub const API_VERSION: &str = "api-version";
+/// When the unversioned aliases stop being served (HTTP-date, RFC 8594)
+pub const ALIAS_SUNSET: &str = "Sat, 01 May 2027 00:00:00 GMT";
+
+/// Largest body the v2 adapter will buffer for rewriting
+const MAX_ADAPTED_BODY: usize = 16 * 1024 * 1024;
+
+/// Fields whose values are enum variants; v2 spells them SCREAMING_SNAKE
+const ENUM_FIELDS: [&str; 3] = ["status", "job_type", "action"];
+
+/// Fields holding user data, passed through verbatim in either direction
+const OPAQUE_FIELDS: [&str; 1] = ["labels"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/v1",
+            ApiVersion::V2 => "/v2",
+        }
+    }
+
+    pub fn number(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "1",
+            ApiVersion::V2 => "2",
+        }
+    }
+
+    /// Accepts `2`, `v2` and `2.0`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().trim_start_matches(['v', 'V']);
+        let major = value.split('.').next().unwrap_or_default();
+        ApiVersion::ALL.into_iter().find(|v| v.number() == major)
+    }
+}
+
+/// Mount `api` under every version prefix, plus the deprecated unversioned aliases.
+/// Each version also serves its own OpenAPI document at `/vN/openapi.json`.
+pub fn versioned(api: Router) -> Router {
+    let v1 = api
+        .clone()
+        .route("/openapi.json", get(|| async { Json(openapi::spec(ApiVersion::V1)) }))
+        .layer(middleware::from_fn(serve_v1));
+    let v2 = api
+        .clone()
+        .route("/openapi.json", get(|| async { Json(openapi::spec(ApiVersion::V2)) }))
+        .layer(middleware::from_fn(serve_v2));
+
+    Router::new()
+        .nest(ApiVersion::V1.prefix(), v1)
+        .nest(ApiVersion::V2.prefix(), v2)
+        .merge(api.layer(middleware::from_fn(serve_alias)))
+}
+
+async fn serve_v1(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    tag(&mut response, ApiVersion::V1);
+    response
+}
+
+async fn serve_v2(request: Request, next: Next) -> Response {
+    // Internal callers (ai-runtime, ai-ethics) are the one audience that needs raw CIDs
+    let internal = request.uri().path().starts_with("/internal/");
+    let request = match adapt_request(request).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let mut response = adapt_response(next.run(request).await, internal).await;
+    tag(&mut response, ApiVersion::V2);
+    response
+}
+
+/// Unversioned path: negotiate via `Accept-Version`, then mark the alias deprecated
+async fn serve_alias(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let requested = request.headers().get(ACCEPT_VERSION).map(|v| v.to_str().ok().and_then(ApiVersion::parse));
+    let (mut response, version) = match requested {
+        Some(None) => {
+            let error = ServiceError::new(ErrorCode::InvalidRequest, "Unsupported Accept-Version")
+                .with_details(serde_json::json!({ "supported": ApiVersion::ALL.map(|v| v.number()) }));
+            (error.into_response(), ApiVersion::V1)
+        }
+        Some(Some(ApiVersion::V2)) => (serve_v2(request, next).await, ApiVersion::V2),
+        _ => (serve_v1(request, next).await, ApiVersion::V1),
+    };
+
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    headers.insert("sunset", HeaderValue::from_static(ALIAS_SUNSET));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}{}>; rel=\"successor-version\"", version.prefix(), path)) {
+        headers.insert(header::LINK, link);
+    }
+    response
+}
+
+fn tag(response: &mut Response, version: ApiVersion) {
+    response.headers_mut().insert(API_VERSION, HeaderValue::from_static(version.number()));
+}
+
+fn is_json(headers: &axum::http::HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"))
+}
+
+/// v2 request -> the v1 shape handlers expect
+async fn adapt_request(request: Request) -> Result<Request, Response> {
+    if !is_json(request.headers()) {
+        return Ok(request);
+    }
+    let (mut parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_ADAPTED_BODY)
+        .await
+        .map_err(|_| ServiceError::new(ErrorCode::PayloadTooLarge, "Request body too large").into_response())?;
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(mut value) => {
+            request_to_v1(&mut value);
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(value.to_string())
+        }
+        // Let the handler's extractor report malformed JSON as it always has
+        Err(_) => Body::from(bytes),
+    };
+    Ok(Request::from_parts(parts, body))
+}
+
+/// v1 response -> v2. Streams and other non-JSON successes pass through.
+async fn adapt_response(response: Response, internal: bool) -> Response {
+    let status = response.status();
+    let failed = status.is_client_error() || status.is_server_error();
+    if !failed && !is_json(response.headers()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_ADAPTED_BODY).await else {
+        return ServiceError::new(ErrorCode::Internal, "Response too large to adapt").into_response();
+    };
+    let mut value = if failed {
+        structured_error(status, &bytes)
+    } else {
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => value,
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        }
+    };
+    response_to_v2(&mut value, internal);
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+/// Any error body as a `ServiceError`; what v1 sent is kept as the message or details
+fn structured_error(status: StatusCode, body: &[u8]) -> serde_json::Value {
+    if let Ok(error) = serde_json::from_slice::<ServiceError>(body) {
+        return serde_json::to_value(error).unwrap_or_default();
+    }
+    let mut error = ServiceError::from(status);
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(details) => error = error.with_details(details),
+        Err(_) => {
+            let text = String::from_utf8_lossy(body).trim().to_string();
+            if !text.is_empty() {
+                error.message = text;
+            }
+        }
+    }
+    serde_json::to_value(error).unwrap_or_default()
+}
+
+pub fn response_to_v2(value: &mut serde_json::Value, internal: bool) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if !internal {
+                map.remove("output_cid");
+            }
+            for (key, field) in map.iter_mut() {
+                match field {
+                    serde_json::Value::String(s) if ENUM_FIELDS.contains(&key.as_str()) && is_pascal(s) => {
+                        *s = screaming_snake(s);
+                    }
+                    _ if OPAQUE_FIELDS.contains(&key.as_str()) => {}
+                    _ => response_to_v2(field, internal),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| response_to_v2(item, internal)),
+        _ => {}
+    }
+}
+
+pub fn request_to_v1(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                match field {
+                    serde_json::Value::String(s) if ENUM_FIELDS.contains(&key.as_str()) && is_screaming(s) => {
+                        *s = pascal(s);
+                    }
+                    _ if OPAQUE_FIELDS.contains(&key.as_str()) => {}
+                    _ => request_to_v1(field),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(request_to_v1),
+        _ => {}
+    }
+}
+
+fn is_pascal(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_uppercase())
+        && s.chars().all(|c| c.is_ascii_alphanumeric())
+        && s.chars().any(|c| c.is_ascii_lowercase())
+}
+
+fn is_screaming(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_uppercase())
+        && s.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// `DownloadDenied` -> `DOWNLOAD_DENIED`
+pub fn screaming_snake(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if c.is_ascii_uppercase() && prev_lower {
+            out.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
+
+/// `DOWNLOAD_DENIED` -> `DownloadDenied`
+fn pascal(s: &str) -> String {
+    s.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let lower = word.to_ascii_lowercase();
+            let mut chars = lower.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
@@ -0,0 +1,827 @@
+//! Workflow Engine
+//! A workflow is a declarative DAG of named steps. Each step submits a job
+//! (train, infer, agent, quantize) or runs a built-in action (register_model,
+//! update_alias, ethics_check). Steps read earlier steps' outputs through
+//! `${...}` bindings in their inputs, may be gated by a `when` condition,
+//! and may fan out over a parameter matrix with a concurrency limit. Both
+//! bindings and conditions are `expr` expressions over the workflow context:
+//!
+//! - `steps.<name>.status`, `.job_id`, `.output`, `.metrics.<metric>`, `.spent`
+//!   and, for built-in actions, their result fields (e.g. `.model_id`)
+//! - `steps.<name>.outputs` and `.runs` for fan-out steps
+//! - `matrix.<param>` inside a fan-out step's own inputs
+//!
+//! Job steps draw their budget from the workflow's. A failed step stops the
+//! workflow until it is retried; succeeded steps and fan-out runs are kept.
+
+use crate::expr::Expr;
+use artha_clock::Entropy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{info, warn};
+
+const MAX_STEPS: usize = 64;
+const MAX_RUNS_PER_STEP: usize = 256;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    Train,
+    Infer,
+    Agent,
+    Quantize,
+    RegisterModel,
+    UpdateAlias,
+    EthicsCheck,
+}
+
+impl StepAction {
+    /// Submits a job through the normal submission path, paid from the workflow budget
+    pub fn submits_job(&self) -> bool {
+        matches!(self, StepAction::Train | StepAction::Infer | StepAction::Agent | StepAction::Quantize)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowSpec {
+    pub name: String,
+    pub submitter_did: String,
+    pub budget: u64, // Shared by every job the workflow submits
+    pub steps: Vec<StepSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepSpec {
+    pub name: String,
+    pub action: StepAction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>, // Ordering-only dependencies; bindings and conditions add their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>, // Skipped, along with its dependents, unless this holds
+    #[serde(default)]
+    pub inputs: Map<String, Value>, // The action's request; strings may hold `${...}` bindings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<u64>, // Per job; required for job actions
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub matrix: BTreeMap<String, Vec<Value>>, // One run per combination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>, // Runs in flight at once; all of them if unset
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+    Cancelled,
+}
+
+impl StepStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Running => "running",
+            StepStatus::Succeeded => "succeeded",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+            StepStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// One execution of a step; fan-out steps have one per matrix combination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRun {
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub params: Map<String, Value>, // Matrix combination
+    pub status: StepStatus,
+    pub job_id: Option<String>,
+    pub output: Option<String>, // Job output CID
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub result: Value, // Built-in action result
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>,
+    pub reserved: u64, // Budget held while the job runs
+    pub spent: u64,
+    pub error: Option<String>,
+}
+
+impl StepRun {
+    fn new(params: Map<String, Value>) -> Self {
+        StepRun {
+            params,
+            status: StepStatus::Pending,
+            job_id: None,
+            output: None,
+            result: Value::Null,
+            metrics: BTreeMap::new(),
+            reserved: 0,
+            spent: 0,
+            error: None,
+        }
+    }
+
+    /// What the run holds against the workflow budget
+    fn committed(&self) -> u64 {
+        if self.status == StepStatus::Running {
+            self.reserved.max(self.spent)
+        } else {
+            self.spent
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepState {
+    pub name: String,
+    pub action: StepAction,
+    pub status: StepStatus,
+    pub attempts: u32,
+    pub runs: Vec<StepRun>,
+    pub retried_spent: u64, // Spent by runs a retry replaced
+    pub error: Option<String>,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+}
+
+impl StepState {
+    fn committed(&self) -> u64 {
+        self.retried_spent + self.runs.iter().map(StepRun::committed).sum::<u64>()
+    }
+
+    /// Derive a running step's status from its runs
+    fn settle(&mut self, now: u64) {
+        if self.status != StepStatus::Running || self.runs.iter().any(|r| r.status == StepStatus::Running) {
+            return;
+        }
+        let failed = self.runs.iter().find(|r| r.status == StepStatus::Failed);
+        self.status = if let Some(run) = failed {
+            self.error = run.error.clone();
+            StepStatus::Failed
+        } else if self.runs.iter().any(|r| r.status == StepStatus::Cancelled) {
+            StepStatus::Cancelled
+        } else if self.runs.iter().all(|r| r.status == StepStatus::Succeeded) {
+            StepStatus::Succeeded
+        } else {
+            return; // Runs still waiting for a slot
+        };
+        self.finished_at = Some(now);
+    }
+
+    fn fail(&mut self, error: String, now: u64) {
+        self.status = StepStatus::Failed;
+        self.error = Some(error);
+        self.finished_at = Some(now);
+    }
+
+    /// The step as expressions see it, under `steps.<name>`
+    fn context(&self, fan_out: bool) -> Value {
+        let mut view = Map::new();
+        view.insert("status".to_string(), Value::from(self.status.as_str()));
+        view.insert("spent".to_string(), Value::from(self.committed()));
+        if fan_out {
+            let outputs: Vec<Value> = self.runs.iter().filter_map(|r| r.output.clone()).map(Value::from).collect();
+            let runs: Vec<Value> = self
+                .runs
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "params": r.params,
+                        "status": r.status.as_str(),
+                        "job_id": r.job_id,
+                        "output": r.output,
+                        "metrics": r.metrics,
+                    })
+                })
+                .collect();
+            view.insert("outputs".to_string(), Value::Array(outputs));
+            view.insert("runs".to_string(), Value::Array(runs));
+        } else if let Some(run) = self.runs.first() {
+            if let Value::Object(result) = &run.result {
+                view.extend(result.clone());
+            }
+            view.insert("job_id".to_string(), serde_json::json!(run.job_id));
+            view.insert("output".to_string(), serde_json::json!(run.output));
+            view.insert("metrics".to_string(), serde_json::json!(run.metrics));
+        }
+        Value::Object(view)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    After,     // Explicit ordering
+    Data,      // An input binding reads the upstream step
+    Condition, // The `when` condition reads the upstream step
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    pub workflow_id: String,
+    pub spec: WorkflowSpec,
+    pub status: WorkflowStatus,
+    pub steps: Vec<StepState>, // In spec order
+    pub edges: Vec<Edge>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// A step run the engine wants executed
+#[derive(Debug, Clone)]
+pub struct Launch {
+    pub step: usize,
+    pub run: usize,
+    pub action: StepAction,
+    pub request: Value, // Inputs with bindings resolved, plus submitter and budget
+}
+
+#[derive(Debug, Clone)]
+pub enum RunUpdate {
+    Submitted { job_id: String },
+    Finished { status: StepStatus, output: Option<String>, result: Value, spent: u64, error: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetView {
+    pub total: u64,
+    pub committed: u64, // Spent, plus what running jobs hold
+    pub remaining: u64,
+}
+
+/// `GET /workflow/:id`: the workflow with its DAG and budget
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowView {
+    #[serde(flatten)]
+    pub workflow: Workflow,
+    pub budget: BudgetView,
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The `${...}` expressions inside one input value
+fn bindings(value: &Value) -> Result<Vec<Expr>, String> {
+    let mut found = Vec::new();
+    match value {
+        Value::String(s) => {
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("${") {
+                let end = rest[start..].find('}').ok_or_else(|| format!("Unterminated binding in '{}'", s))?;
+                found.push(Expr::parse(&rest[start + 2..start + end])?);
+                rest = &rest[start + end + 1..];
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                found.extend(bindings(item)?);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values() {
+                found.extend(bindings(item)?);
+            }
+        }
+        _ => {}
+    }
+    Ok(found)
+}
+
+/// Resolve bindings in an input value. A string that is exactly one binding
+/// takes the bound value as-is; bindings inside longer strings are spliced in
+/// as text.
+fn resolve(value: &Value, context: &Value) -> Result<Value, String> {
+    match value {
+        Value::String(s) if s.contains("${") => {
+            let bound = |src: &str| -> Result<Value, String> {
+                match Expr::parse(src)?.eval(context)? {
+                    Value::Null => Err(format!("'{}' is not available", src)),
+                    value => Ok(value),
+                }
+            };
+            if s.starts_with("${") && s.find('}') == Some(s.len() - 1) {
+                return bound(&s[2..s.len() - 1]);
+            }
+            let (mut text, mut rest) = (String::new(), s.as_str());
+            while let Some(start) = rest.find("${") {
+                let end = start + rest[start..].find('}').ok_or_else(|| format!("Unterminated binding in '{}'", s))?;
+                text.push_str(&rest[..start]);
+                match bound(&rest[start + 2..end])? {
+                    Value::String(inner) => text.push_str(&inner),
+                    other => text.push_str(&other.to_string()),
+                }
+                rest = &rest[end + 1..];
+            }
+            text.push_str(rest);
+            Ok(Value::String(text))
+        }
+        Value::Array(items) => items.iter().map(|item| resolve(item, context)).collect::<Result<_, _>>().map(Value::Array),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| resolve(v, context).map(|v| (k.clone(), v)))
+            .collect::<Result<_, _>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+/// Every combination of the matrix's values, keys in order
+fn expand(matrix: &BTreeMap<String, Vec<Value>>) -> Vec<Map<String, Value>> {
+    let mut combinations = vec![Map::new()];
+    for (key, values) in matrix {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|base| {
+                values.iter().map(move |value| {
+                    let mut next = base.clone();
+                    next.insert(key.clone(), value.clone());
+                    next
+                })
+            })
+            .collect();
+    }
+    combinations
+}
+
+impl WorkflowSpec {
+    /// Check the spec and derive its edges
+    pub fn validate(&self) -> Result<Vec<Edge>, String> {
+        if self.steps.is_empty() || self.steps.len() > MAX_STEPS {
+            return Err(format!("A workflow needs 1 to {} steps", MAX_STEPS));
+        }
+        if self.budget == 0 {
+            return Err("Workflow budget must be positive".to_string());
+        }
+        let mut names = HashSet::new();
+        for step in &self.steps {
+            if !is_identifier(&step.name) {
+                return Err(format!("Step name '{}' must be letters, digits and '_'", step.name));
+            }
+            if !names.insert(step.name.as_str()) {
+                return Err(format!("Duplicate step '{}'", step.name));
+            }
+        }
+
+        let mut edges = Vec::new();
+        for step in &self.steps {
+            let upstream = |name: &str| -> Result<String, String> {
+                if name == step.name || !names.contains(name) {
+                    return Err(format!("Step '{}' depends on unknown step '{}'", step.name, name));
+                }
+                Ok(name.to_string())
+            };
+            let mut add = |from: String, kind: EdgeKind| {
+                let edge = Edge { from, to: step.name.clone(), kind };
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            };
+            for name in &step.after {
+                add(upstream(name)?, EdgeKind::After);
+            }
+            if let Some(when) = &step.when {
+                let condition = Expr::parse(when).map_err(|e| format!("Step '{}' condition: {}", step.name, e))?;
+                for path in condition.paths() {
+                    match path.first().map(String::as_str) {
+                        Some("steps") if path.len() > 1 => add(upstream(&path[1])?, EdgeKind::Condition),
+                        _ => return Err(format!("Step '{}' condition reads '{}'; only steps.* is visible", step.name, path.join("."))),
+                    }
+                }
+            }
+            for binding in bindings(&Value::Object(step.inputs.clone())).map_err(|e| format!("Step '{}' inputs: {}", step.name, e))? {
+                for path in binding.paths() {
+                    match path.first().map(String::as_str) {
+                        Some("steps") if path.len() > 1 => add(upstream(&path[1])?, EdgeKind::Data),
+                        Some("matrix") if path.len() == 2 && step.matrix.contains_key(&path[1]) => {}
+                        _ => return Err(format!("Step '{}' binds unknown '{}'", step.name, path.join("."))),
+                    }
+                }
+            }
+
+            if step.action.submits_job() {
+                match step.budget {
+                    Some(budget) if budget > 0 && budget <= self.budget => {}
+                    _ => return Err(format!("Step '{}' needs a budget between 1 and the workflow's {}", step.name, self.budget)),
+                }
+            } else if !step.matrix.is_empty() {
+                return Err(format!("Step '{}': only job steps can fan out", step.name));
+            }
+            if step.matrix.values().any(Vec::is_empty) {
+                return Err(format!("Step '{}' has an empty matrix axis", step.name));
+            }
+            let runs = step.matrix.values().map(Vec::len).product::<usize>();
+            if runs > MAX_RUNS_PER_STEP {
+                return Err(format!("Step '{}' fans out to {} runs, over the limit of {}", step.name, runs, MAX_RUNS_PER_STEP));
+            }
+            if step.max_parallel == Some(0) {
+                return Err(format!("Step '{}' max_parallel must be positive", step.name));
+            }
+        }
+
+        // Kahn's algorithm: every step must become ready
+        let mut indegree: HashMap<&str, usize> = self.steps.iter().map(|s| (s.name.as_str(), 0)).collect();
+        let mut deps: HashSet<(&str, &str)> = HashSet::new();
+        for edge in &edges {
+            if deps.insert((edge.from.as_str(), edge.to.as_str())) {
+                *indegree.get_mut(edge.to.as_str()).unwrap() += 1;
+            }
+        }
+        let mut ready: Vec<&str> = indegree.iter().filter(|(_, d)| **d == 0).map(|(n, _)| *n).collect();
+        let mut ordered = 0;
+        while let Some(name) = ready.pop() {
+            ordered += 1;
+            for (from, to) in &deps {
+                if *from == name {
+                    let degree = indegree.get_mut(to).unwrap();
+                    *degree -= 1;
+                    if *degree == 0 {
+                        ready.push(to);
+                    }
+                }
+            }
+        }
+        if ordered < self.steps.len() {
+            return Err("Step dependencies form a cycle".to_string());
+        }
+        Ok(edges)
+    }
+}
+
+impl Workflow {
+    pub fn new(spec: WorkflowSpec, now: u64, entropy: &dyn Entropy) -> Result<Workflow, String> {
+        let edges = spec.validate()?;
+        let steps = spec
+            .steps
+            .iter()
+            .map(|s| StepState {
+                name: s.name.clone(),
+                action: s.action,
+                status: StepStatus::Pending,
+                attempts: 0,
+                runs: Vec::new(),
+                retried_spent: 0,
+                error: None,
+                started_at: None,
+                finished_at: None,
+            })
+            .collect();
+        Ok(Workflow {
+            workflow_id: format!("wf-{}", entropy.uuid()),
+            spec,
+            status: WorkflowStatus::Running,
+            steps,
+            edges,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn committed(&self) -> u64 {
+        self.steps.iter().map(StepState::committed).sum()
+    }
+
+    pub fn remaining_budget(&self) -> u64 {
+        self.spec.budget.saturating_sub(self.committed())
+    }
+
+    pub fn view(&self) -> WorkflowView {
+        WorkflowView {
+            workflow: self.clone(),
+            budget: BudgetView {
+                total: self.spec.budget,
+                committed: self.committed(),
+                remaining: self.remaining_budget(),
+            },
+        }
+    }
+
+    fn context(&self) -> Value {
+        let steps: Map<String, Value> = self
+            .steps
+            .iter()
+            .zip(&self.spec.steps)
+            .map(|(state, spec)| (state.name.clone(), state.context(!spec.matrix.is_empty())))
+            .collect();
+        serde_json::json!({ "steps": steps })
+    }
+
+    fn upstream_statuses(&self, step: usize) -> Vec<StepStatus> {
+        let name = &self.steps[step].name;
+        self.edges
+            .iter()
+            .filter(|e| &e.to == name)
+            .filter_map(|e| self.steps.iter().find(|s| s.name == e.from))
+            .map(|s| s.status)
+            .collect()
+    }
+
+    /// Move ready steps forward and return the runs to execute. Launched
+    /// runs are marked running, holding their budget, before this returns.
+    pub fn plan(&mut self, now: u64) -> Vec<Launch> {
+        let mut launches = Vec::new();
+        loop {
+            if self.status != WorkflowStatus::Running {
+                break;
+            }
+            let mut changed = false;
+            for i in 0..self.steps.len() {
+                match self.steps[i].status {
+                    StepStatus::Pending => changed |= self.activate(i, now),
+                    StepStatus::Running => launches.extend(self.launch(i, now)),
+                    _ => {}
+                }
+                self.steps[i].settle(now);
+            }
+            changed |= self.refresh(now);
+            if !changed {
+                break;
+            }
+        }
+        self.updated_at = now;
+        launches
+    }
+
+    /// Start, skip or fail a pending step whose upstream has settled
+    fn activate(&mut self, i: usize, now: u64) -> bool {
+        let upstream = self.upstream_statuses(i);
+        if upstream.iter().any(|s| *s != StepStatus::Succeeded && *s != StepStatus::Skipped) {
+            return false; // Waiting, or held behind a failure until it is retried
+        }
+        let step = &mut self.steps[i];
+        if upstream.contains(&StepStatus::Skipped) {
+            step.status = StepStatus::Skipped;
+            step.finished_at = Some(now);
+            return true;
+        }
+        if let Some(when) = &self.spec.steps[i].when {
+            let context = self.context();
+            let step = &mut self.steps[i];
+            match Expr::parse(when).and_then(|c| c.eval_bool(&context)) {
+                Ok(true) => {}
+                Ok(false) => {
+                    info!("⏭️  Step {} skipped: {} is false", step.name, when);
+                    step.status = StepStatus::Skipped;
+                    step.finished_at = Some(now);
+                    return true;
+                }
+                Err(e) => {
+                    step.fail(format!("Condition '{}': {}", when, e), now);
+                    return true;
+                }
+            }
+        }
+        let step = &mut self.steps[i];
+        if step.runs.is_empty() {
+            step.runs = expand(&self.spec.steps[i].matrix).into_iter().map(StepRun::new).collect();
+        }
+        step.status = StepStatus::Running;
+        step.attempts += 1;
+        step.started_at = Some(now);
+        step.finished_at = None;
+        true
+    }
+
+    /// Launch a running step's waiting runs up to its concurrency limit
+    fn launch(&mut self, i: usize, now: u64) -> Vec<Launch> {
+        let mut launches = Vec::new();
+        let spec = self.spec.steps[i].clone();
+        let max_parallel = spec.max_parallel.unwrap_or(usize::MAX);
+        let context = self.context();
+        for r in 0..self.steps[i].runs.len() {
+            let step = &self.steps[i];
+            let running = step.runs.iter().filter(|r| r.status == StepStatus::Running).count();
+            if running >= max_parallel || step.runs.iter().any(|r| r.status == StepStatus::Failed) {
+                break;
+            }
+            if step.runs[r].status != StepStatus::Pending {
+                continue;
+            }
+
+            let mut scope = context.clone();
+            scope["matrix"] = Value::Object(step.runs[r].params.clone());
+            let resolved = resolve(&Value::Object(spec.inputs.clone()), &scope);
+            let remaining = self.remaining_budget();
+            let run = &mut self.steps[i].runs[r];
+            let mut request = match resolved {
+                Ok(request) => request,
+                Err(e) => {
+                    run.status = StepStatus::Failed;
+                    run.error = Some(format!("Binding failed: {}", e));
+                    break;
+                }
+            };
+            request["submitter_did"] = Value::from(self.spec.submitter_did.clone());
+            if spec.action.submits_job() {
+                let needed = spec.budget.unwrap_or_default();
+                if needed > remaining {
+                    run.status = StepStatus::Failed;
+                    run.error = Some(format!(
+                        "Workflow budget exhausted: step needs {}, {} of {} left",
+                        needed, remaining, self.spec.budget
+                    ));
+                    break;
+                }
+                run.reserved = needed;
+                request["budget"] = Value::from(needed);
+            }
+            run.status = StepStatus::Running;
+            launches.push(Launch { step: i, run: r, action: spec.action, request });
+        }
+        self.steps[i].settle(now);
+        launches
+    }
+
+    /// Derive the workflow's status from its steps; true if it changed
+    fn refresh(&mut self, now: u64) -> bool {
+        if self.status == WorkflowStatus::Cancelled {
+            return false;
+        }
+        let before = self.status;
+        if let Some(failed) = self.steps.iter().find(|s| s.status == StepStatus::Failed) {
+            self.status = WorkflowStatus::Failed;
+            self.error = Some(format!("Step {} failed: {}", failed.name, failed.error.clone().unwrap_or_default()));
+        } else if self.steps.iter().all(|s| matches!(s.status, StepStatus::Succeeded | StepStatus::Skipped)) {
+            self.status = WorkflowStatus::Succeeded;
+            self.error = None;
+        } else {
+            self.status = WorkflowStatus::Running;
+            self.error = None;
+        }
+        if self.status != before {
+            self.updated_at = now;
+            info!("🧭 Workflow {} is {:?}", self.workflow_id, self.status);
+        }
+        self.status != before
+    }
+
+    pub fn record(&mut self, step: usize, run: usize, update: RunUpdate, now: u64) {
+        let Some(state) = self.steps.get_mut(step) else { return };
+        let Some(target) = state.runs.get_mut(run) else { return };
+        match update {
+            RunUpdate::Submitted { job_id } => target.job_id = Some(job_id),
+            RunUpdate::Finished { status, output, result, spent, error } => {
+                target.spent = spent;
+                target.output = output.or(target.output.take());
+                target.result = result;
+                // A cancelled workflow's runs stay cancelled whatever they finish with
+                if target.status == StepStatus::Running {
+                    target.status = status;
+                    target.reserved = 0;
+                    target.error = error;
+                }
+            }
+        }
+        state.settle(now);
+        self.refresh(now);
+        self.updated_at = now;
+    }
+
+    /// Re-run a failed step, keeping the runs that succeeded
+    pub fn retry(&mut self, name: &str, now: u64) -> Result<(), String> {
+        if self.status == WorkflowStatus::Cancelled {
+            return Err("Workflow is cancelled".to_string());
+        }
+        let step = self.steps.iter_mut().find(|s| s.name == name).ok_or_else(|| format!("No step '{}'", name))?;
+        if step.status != StepStatus::Failed {
+            return Err(format!("Step '{}' is {}, not failed", name, step.status.as_str()));
+        }
+        for run in step.runs.iter_mut().filter(|r| r.status != StepStatus::Succeeded) {
+            step.retried_spent += run.spent;
+            *run = StepRun::new(std::mem::take(&mut run.params));
+        }
+        step.error = None;
+        step.finished_at = None;
+        if step.runs.is_empty() {
+            step.status = StepStatus::Pending; // Failed before it ran; re-check its condition
+        } else {
+            step.status = StepStatus::Running;
+            step.attempts += 1;
+        }
+        info!("🔁 Retrying step {} of workflow {}", name, self.workflow_id);
+        self.refresh(now);
+        Ok(())
+    }
+
+    /// Stop the workflow; returns the jobs still running, to cancel
+    pub fn cancel(&mut self, now: u64) -> Vec<String> {
+        let mut running_jobs = Vec::new();
+        for step in &mut self.steps {
+            for run in &mut step.runs {
+                if matches!(run.status, StepStatus::Pending | StepStatus::Running) {
+                    running_jobs.extend(run.job_id.clone());
+                    run.status = StepStatus::Cancelled;
+                }
+            }
+            if matches!(step.status, StepStatus::Pending | StepStatus::Running) {
+                step.status = StepStatus::Cancelled;
+                step.finished_at = Some(now);
+            }
+        }
+        self.status = WorkflowStatus::Cancelled;
+        self.updated_at = now;
+        running_jobs
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Snapshot {
+    workflows: Vec<Workflow>,
+}
+
+/// Workflows by id, saved to a JSON snapshot after every change
+#[derive(Debug, Default)]
+pub struct WorkflowStore {
+    workflows: HashMap<String, Workflow>,
+    path: Option<String>,
+}
+
+impl WorkflowStore {
+    pub fn load(path: Option<String>) -> Self {
+        let saved: Snapshot = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        WorkflowStore {
+            workflows: saved.workflows.into_iter().map(|w| (w.workflow_id.clone(), w)).collect(),
+            path,
+        }
+    }
+
+    pub fn insert(&mut self, workflow: Workflow) {
+        self.workflows.insert(workflow.workflow_id.clone(), workflow);
+        self.persist();
+    }
+
+    pub fn get(&self, workflow_id: &str) -> Option<&Workflow> {
+        self.workflows.get(workflow_id)
+    }
+
+    /// Change one workflow and save the result
+    pub fn update<T>(&mut self, workflow_id: &str, change: impl FnOnce(&mut Workflow) -> T) -> Option<T> {
+        let result = change(self.workflows.get_mut(workflow_id)?);
+        self.persist();
+        Some(result)
+    }
+
+    /// The workflow step run that submitted `job_id`
+    pub fn find_job(&self, job_id: &str) -> Option<(String, usize, usize)> {
+        self.workflows.values().find_map(|w| {
+            w.steps.iter().enumerate().find_map(|(s, step)| {
+                step.runs
+                    .iter()
+                    .position(|r| r.job_id.as_deref() == Some(job_id))
+                    .map(|r| (w.workflow_id.clone(), s, r))
+            })
+        })
+    }
+
+    /// Metrics a workflow job reported, for later conditions to read
+    pub fn record_metrics(&mut self, job_id: &str, metrics: &HashMap<String, f64>) {
+        let Some((workflow_id, step, run)) = self.find_job(job_id) else { return };
+        self.update(&workflow_id, |w| w.steps[step].runs[run].metrics.extend(metrics.iter().map(|(k, v)| (k.clone(), *v))));
+    }
+
+    fn persist(&self) {
+        if let Err(e) = self.save() {
+            warn!("⚠️  Failed to persist workflows: {}", e);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let snapshot = Snapshot { workflows: self.workflows.values().cloned().collect() };
+        let bytes = serde_json::to_vec(&snapshot).map_err(|e| e.to_string())?;
+        let tmp = format!("{}.tmp", path);
+        std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,76 @@
+//! Attestation Chains
+//! A TEE quote is signed by an attestation key its platform vendor certifies:
+//! the DCAP PCK chain for SGX and TDX, VCEK/ASK/ARK for SEV-SNP. Quotes carry
+//! that chain leaf first. Each certificate is signed by the key of the one
+//! after it, and the last by a root key configured for the TEE type, so a
+//! quote only verifies if it chains up to a root the operator trusts. The
+//! quote itself keeps the custody `tee_enclave` layout.
+//! Keys are SEC1-encoded secp256k1 and signatures 64-byte ECDSA, in hex.
+
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttestationCert {
+    pub public_key: String,
+    pub signature: String, // By the issuer, over `cert_message`
+}
+
+/// What an issuer signs to certify `public_key` for `tee_type`
+pub fn cert_message(tee_type: &str, public_key: &[u8]) -> Vec<u8> {
+    let mut message = format!("ARTHA_TEE_CERT:{}:", tee_type).into_bytes();
+    message.extend_from_slice(public_key);
+    message
+}
+
+/// What the attestation key signs: the quote and the report data it binds
+pub fn quote_message(raw_quote: &str, report_data: &str) -> Vec<u8> {
+    format!("ARTHA_TEE_QUOTE:{}:{}", raw_quote, report_data).into_bytes()
+}
+
+fn key(hex_key: &str) -> Result<VerifyingKey, String> {
+    let bytes = hex::decode(hex_key.trim_start_matches("0x")).map_err(|e| format!("Invalid key hex: {}", e))?;
+    VerifyingKey::from_sec1_bytes(&bytes).map_err(|_| "Invalid public key".to_string())
+}
+
+fn verifies(key: &VerifyingKey, message: &[u8], hex_signature: &str) -> bool {
+    hex::decode(hex_signature.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .is_some_and(|signature| key.verify(message, &signature).is_ok())
+}
+
+/// Check that `signature` over the quote is by the leaf of `chain`, and that
+/// the chain leads to one of `root_keys`
+pub fn verify_chain(
+    tee_type: &str,
+    chain: &[AttestationCert],
+    root_keys: &[String],
+    raw_quote: &str,
+    report_data: &str,
+    signature: &str,
+) -> Result<(), String> {
+    let leaf = chain.first().ok_or("Quote has no certificate chain")?;
+    if !verifies(&key(&leaf.public_key)?, &quote_message(raw_quote, report_data), signature) {
+        return Err("Invalid quote signature".to_string());
+    }
+
+    for (i, cert) in chain.iter().enumerate() {
+        let certified = hex::decode(cert.public_key.trim_start_matches("0x")).map_err(|e| format!("Invalid key hex: {}", e))?;
+        let message = cert_message(tee_type, &certified);
+        let signed = match chain.get(i + 1) {
+            Some(issuer) => verifies(&key(&issuer.public_key)?, &message, &cert.signature),
+            None => root_keys
+                .iter()
+                .filter_map(|root| key(root).ok())
+                .any(|root| verifies(&root, &message, &cert.signature)),
+        };
+        if !signed {
+            return Err(match chain.get(i + 1) {
+                Some(_) => format!("Certificate {} of the chain is not signed by its issuer", i),
+                None => "Certificate chain does not lead to a trusted root".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,385 @@
+//! Milestone Escrow
+//! A train job's budget is locked when the job is assigned and paid out to
+//! the provider in tranches, one per milestone, as verified step proofs
+//! cover each one. Whatever is still locked at finalize is released then;
+//! cancellation pays the provider for verified progress and refunds the
+//! rest. A dispute freezes the tranches not yet released and leaves paid
+//! ones alone. Tranches are claimed under the escrow lock before any payout
+//! call, so a milestone release racing finalize cannot pay twice. A job
+//! whose cost was already escrowed at submission brings that lock along as
+//! its budget rather than having a second one taken.
+//!
+//! Funds move through an [`EscrowBackend`]: DealMarket when it is deployed,
+//! otherwise an internal ledger kept by this service.
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use tracing::info;
+
+/// Default milestones: 25/50/75/100% of the job's steps, in basis points
+pub const DEFAULT_MILESTONE_BPS: [u64; 4] = [2_500, 5_000, 7_500, 10_000];
+
+/// Milestones as given in the job submission. Explicit `steps` win over
+/// `fractions`; with neither, the default milestones apply.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MilestonePlan {
+    pub total_steps: u64,
+    #[serde(default)]
+    pub fractions: Vec<f64>, // Progress fractions in (0, 1]
+    #[serde(default)]
+    pub steps: Vec<u64>, // Absolute step counts
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum TrancheState {
+    Locked,
+    Releasing, // Claimed for payout; the backend call is in flight
+    Released { tx_hash: String, released_at: u64 },
+    Frozen, // Held by a dispute
+    Refunded { tx_hash: String },
+    Cancelled { earned_wei: u64, refunded_wei: u64 }, // Split pro rata at cancellation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tranche {
+    pub index: usize,
+    pub step: u64, // Verified step that releases this tranche
+    pub amount_wei: u64,
+    pub state: TrancheState,
+    pub receipt_id: Option<String>, // Receipt raised for the release
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EscrowStatus {
+    Active,
+    Finalized,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscrowDispute {
+    pub disputer: String,
+    pub reason: String,
+    pub disputed_at: u64,
+}
+
+/// What closing a dispute leaves to pay out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Resolution {
+    pub claimed: Vec<usize>,              // Tranches claimed for release
+    pub refund: Vec<usize>,               // Frozen tranches owed back to the payer
+    pub cancel: Option<CancelSettlement>, // Split of unfrozen tranches on a cancelled escrow
+}
+
+/// Provider payout and payer refund from cancelling an escrow
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CancelSettlement {
+    pub provider_wei: u64,
+    pub refund_wei: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Escrow {
+    pub job_id: String,
+    pub payer: String,
+    pub provider: String,
+    pub budget_wei: u64,
+    pub total_steps: u64,
+    pub verified_step: u64, // Highest step covered by on-chain proofs
+    pub backend: String,
+    pub lock_ref: Option<String>, // Backend reference for the locked funds
+    pub tranches: Vec<Tranche>,
+    pub status: EscrowStatus,
+    pub dispute: Option<EscrowDispute>,
+    pub opened_at: u64,
+}
+
+impl Escrow {
+    /// Escrow for `budget_wei` split into the plan's milestones. Steps past
+    /// the last milestone form a final tranche released at finalize.
+    pub fn open(
+        job_id: &str,
+        payer: &str,
+        provider: &str,
+        budget_wei: u64,
+        plan: &MilestonePlan,
+        now: u64,
+    ) -> Result<Self, String> {
+        let total = plan.total_steps;
+        if total == 0 {
+            return Err("total_steps must be positive".to_string());
+        }
+
+        let mut thresholds: Vec<u64> = if !plan.steps.is_empty() {
+            plan.steps.clone()
+        } else if !plan.fractions.is_empty() {
+            if plan.fractions.iter().any(|f| !(*f > 0.0 && *f <= 1.0)) {
+                return Err("milestone fractions must be in (0, 1]".to_string());
+            }
+            plan.fractions.iter().map(|f| (f * total as f64).ceil() as u64).collect()
+        } else {
+            DEFAULT_MILESTONE_BPS.iter().map(|bps| (total * bps).div_ceil(10_000)).collect()
+        };
+        if thresholds.iter().any(|step| *step == 0 || *step > total) {
+            return Err(format!("milestone steps must be in 1..={}", total));
+        }
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        if thresholds.last() != Some(&total) {
+            thresholds.push(total);
+        }
+
+        let mut released_so_far = 0;
+        let tranches = thresholds
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let cumulative = pro_rata(budget_wei, step, total);
+                let amount_wei = cumulative - released_so_far;
+                released_so_far = cumulative;
+                Tranche { index, step, amount_wei, state: TrancheState::Locked, receipt_id: None }
+            })
+            .collect();
+
+        Ok(Escrow {
+            job_id: job_id.to_string(),
+            payer: payer.to_string(),
+            provider: provider.to_string(),
+            budget_wei,
+            total_steps: total,
+            verified_step: 0,
+            backend: String::new(),
+            lock_ref: None,
+            tranches,
+            status: EscrowStatus::Active,
+            dispute: None,
+            opened_at: now,
+        })
+    }
+
+    /// Record verified progress and claim every locked tranche it covers
+    pub fn record_progress(&mut self, verified_step: u64) -> Vec<usize> {
+        self.verified_step = self.verified_step.max(verified_step.min(self.total_steps));
+        if self.status != EscrowStatus::Active {
+            return Vec::new();
+        }
+        let covered = self.verified_step;
+        self.claim(|tranche| tranche.step <= covered)
+    }
+
+    /// Claim every tranche still locked and close the escrow. Repeating it
+    /// picks up tranches whose release failed the first time.
+    pub fn claim_final(&mut self) -> Result<Vec<usize>, String> {
+        if self.status == EscrowStatus::Cancelled {
+            return Err(format!("escrow is {:?}", self.status));
+        }
+        self.status = EscrowStatus::Finalized;
+        Ok(self.claim(|_| true))
+    }
+
+    fn claim(&mut self, covered: impl Fn(&Tranche) -> bool) -> Vec<usize> {
+        self.tranches
+            .iter_mut()
+            .filter(|tranche| tranche.state == TrancheState::Locked && covered(tranche))
+            .map(|tranche| {
+                tranche.state = TrancheState::Releasing;
+                tranche.index
+            })
+            .collect()
+    }
+
+    /// Mark a claimed tranche paid
+    pub fn complete_release(&mut self, index: usize, tx_hash: String, receipt_id: Option<String>, now: u64) {
+        if let Some(tranche) = self.tranches.get_mut(index).filter(|t| t.state == TrancheState::Releasing) {
+            tranche.state = TrancheState::Released { tx_hash, released_at: now };
+            tranche.receipt_id = receipt_id;
+        }
+    }
+
+    /// Put a claimed tranche back after its payout failed, to be claimed again
+    pub fn abort_release(&mut self, index: usize) {
+        if let Some(tranche) = self.tranches.get_mut(index).filter(|t| t.state == TrancheState::Releasing) {
+            tranche.state = TrancheState::Locked;
+        }
+    }
+
+    /// Close the escrow on cancellation and settle its locked tranches
+    /// against the progress verified so far
+    pub fn cancel(&mut self, verified_step: u64) -> Result<CancelSettlement, String> {
+        if self.status != EscrowStatus::Active {
+            return Err(format!("escrow is {:?}", self.status));
+        }
+        self.verified_step = self.verified_step.max(verified_step.min(self.total_steps));
+        self.status = EscrowStatus::Cancelled;
+        Ok(self.split_locked())
+    }
+
+    /// Split the locked tranches of a cancelled escrow: the provider earns
+    /// the budget's share of verified progress not yet paid, the payer gets
+    /// the rest back. Frozen tranches wait for the dispute's resolution.
+    fn split_locked(&mut self) -> CancelSettlement {
+        let earned_total = pro_rata(self.budget_wei, self.verified_step, self.total_steps);
+        let mut settlement = CancelSettlement { provider_wei: 0, refund_wei: 0 };
+        let mut tranche_start = 0;
+        for tranche in &mut self.tranches {
+            let start = tranche_start;
+            tranche_start += tranche.amount_wei;
+            if tranche.state != TrancheState::Locked {
+                continue;
+            }
+            let earned_wei = earned_total.saturating_sub(start).min(tranche.amount_wei);
+            let refunded_wei = tranche.amount_wei - earned_wei;
+            settlement.provider_wei += earned_wei;
+            settlement.refund_wei += refunded_wei;
+            tranche.state = TrancheState::Cancelled { earned_wei, refunded_wei };
+        }
+        settlement
+    }
+
+    /// Freeze every tranche not yet released or claimed
+    pub fn dispute(&mut self, dispute: EscrowDispute) -> Result<usize, String> {
+        if self.dispute.is_some() {
+            return Err("escrow is already disputed".to_string());
+        }
+        if self.status != EscrowStatus::Active {
+            return Err(format!("escrow is {:?}", self.status));
+        }
+        let mut frozen = 0;
+        for tranche in self.tranches.iter_mut().filter(|t| t.state == TrancheState::Locked) {
+            tranche.state = TrancheState::Frozen;
+            frozen += 1;
+        }
+        self.dispute = Some(dispute);
+        Ok(frozen)
+    }
+
+    /// Close the dispute. If the provider prevails the frozen tranches
+    /// unlock and settle as the escrow's status dictates; otherwise they
+    /// are handed back for refund.
+    pub fn resolve_dispute(&mut self, provider_prevails: bool) -> Result<Resolution, String> {
+        if self.dispute.take().is_none() {
+            return Err("escrow is not disputed".to_string());
+        }
+        let mut resolution = Resolution { claimed: Vec::new(), refund: Vec::new(), cancel: None };
+        for tranche in self.tranches.iter_mut().filter(|t| t.state == TrancheState::Frozen) {
+            if provider_prevails {
+                tranche.state = TrancheState::Locked;
+            } else {
+                resolution.refund.push(tranche.index);
+            }
+        }
+        if provider_prevails {
+            match self.status {
+                EscrowStatus::Active => {
+                    let covered = self.verified_step;
+                    resolution.claimed = self.claim(|tranche| tranche.step <= covered);
+                }
+                EscrowStatus::Finalized => resolution.claimed = self.claim(|_| true),
+                EscrowStatus::Cancelled => resolution.cancel = Some(self.split_locked()),
+            }
+        }
+        Ok(resolution)
+    }
+
+    /// Mark a frozen tranche refunded to the payer
+    pub fn complete_refund(&mut self, index: usize, tx_hash: String) {
+        if let Some(tranche) = self.tranches.get_mut(index).filter(|t| t.state == TrancheState::Frozen) {
+            tranche.state = TrancheState::Refunded { tx_hash };
+        }
+    }
+
+    pub fn released_wei(&self) -> u64 {
+        self.tranches
+            .iter()
+            .filter(|t| matches!(t.state, TrancheState::Released { .. }))
+            .map(|t| t.amount_wei)
+            .sum()
+    }
+}
+
+/// `budget`'s share of `step` out of `total`, rounded down
+fn pro_rata(budget: u64, step: u64, total: u64) -> u64 {
+    (budget as u128 * step.min(total) as u128 / total as u128) as u64
+}
+
+/// Where escrowed funds are held and paid from
+pub trait EscrowBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn lock(&self, escrow: &Escrow) -> Result<String, String>;
+    fn release(&self, escrow: &Escrow, amount_wei: u64, memo: &str) -> Result<String, String>;
+    fn refund(&self, escrow: &Escrow, amount_wei: u64, memo: &str) -> Result<String, String>;
+}
+
+/// Escrow held by the DealMarket contract
+pub struct DealMarketEscrow {
+    pub address: String,
+}
+
+impl DealMarketEscrow {
+    fn call(&self, method: &str, escrow: &Escrow, party: &str, amount_wei: u64, memo: &str) -> String {
+        let job_hash = format!("0x{:064x}", Keccak256::digest(escrow.job_id.as_bytes()));
+        info!("   💰 Calling DealMarket.{}() at {}", method, self.address);
+        info!("      Job Hash: {}", job_hash);
+        info!("      Party:    {}", party);
+        info!("      Amount:   {} wei ({})", amount_wei, memo);
+
+        // In production: JSON-RPC call to the contract; the hash stands in for the tx
+        let mut hasher = Keccak256::new();
+        hasher.update(method.as_bytes());
+        hasher.update(job_hash.as_bytes());
+        hasher.update(memo.as_bytes());
+        hasher.update(amount_wei.to_be_bytes());
+        format!("0x{}", hex::encode(hasher.finalize()))
+    }
+}
+
+impl EscrowBackend for DealMarketEscrow {
+    fn name(&self) -> &'static str {
+        "deal_market"
+    }
+
+    fn lock(&self, escrow: &Escrow) -> Result<String, String> {
+        Ok(self.call("lockEscrow", escrow, &escrow.payer, escrow.budget_wei, "lock"))
+    }
+
+    fn release(&self, escrow: &Escrow, amount_wei: u64, memo: &str) -> Result<String, String> {
+        Ok(self.call("releaseEscrow", escrow, &escrow.provider, amount_wei, memo))
+    }
+
+    fn refund(&self, escrow: &Escrow, amount_wei: u64, memo: &str) -> Result<String, String> {
+        Ok(self.call("refundEscrow", escrow, &escrow.payer, amount_wei, memo))
+    }
+}
+
+/// Escrow kept as this service's own record, for chains without DealMarket
+pub struct InternalEscrow;
+
+impl EscrowBackend for InternalEscrow {
+    fn name(&self) -> &'static str {
+        "internal"
+    }
+
+    fn lock(&self, escrow: &Escrow) -> Result<String, String> {
+        Ok(format!("escrow:{}:lock", escrow.job_id))
+    }
+
+    fn release(&self, escrow: &Escrow, _amount_wei: u64, memo: &str) -> Result<String, String> {
+        Ok(format!("escrow:{}:release:{}", escrow.job_id, memo))
+    }
+
+    fn refund(&self, escrow: &Escrow, _amount_wei: u64, memo: &str) -> Result<String, String> {
+        Ok(format!("escrow:{}:refund:{}", escrow.job_id, memo))
+    }
+}
+
+/// DealMarket when `DEAL_MARKET_ADDR` is set, unless `ARTHA_ESCROW_BACKEND=internal`
+pub fn backend_from_env() -> Box<dyn EscrowBackend> {
+    let deal_market = std::env::var("DEAL_MARKET_ADDR").unwrap_or_default();
+    let internal = std::env::var("ARTHA_ESCROW_BACKEND").as_deref() == Ok("internal");
+    if deal_market.is_empty() || internal {
+        Box::new(InternalEscrow)
+    } else {
+        Box::new(DealMarketEscrow { address: deal_market })
+    }
+}
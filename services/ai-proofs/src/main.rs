@@ -7,11 +7,27 @@ use axum::{
     routing::post,
     Router,
 };
+use abi::{abi_encode_bytes32, abi_encode_call, abi_encode_uint256, Token};
+use artha_clock::{Backoff, BackoffPolicy, Entropy, SharedClock, SharedEntropy};
+use artha_retention::RetentionPolicy;
+use artha_rpc::{Contract, ContractRegistry, RpcEndpoints, RpcError};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use std::collections::HashMap;
+use tokio::sync::{oneshot, RwLock};
+use std::collections::{HashMap, HashSet};
 use sha3::{Keccak256, Digest};
+use tracing::{error, info, warn};
+
+mod attestation;
+use attestation::AttestationCert;
+mod escrow;
+use escrow::{CancelSettlement, Escrow, EscrowBackend, EscrowDispute, EscrowStatus, MilestonePlan, TrancheState};
+mod mempool;
+use mempool::{Batch, DeadLetter, Enqueued, NonceManager, ProofCall, ProofKey, ProofMempool, Submission};
+mod proof_store;
+use proof_store::{FileProofStore, ProofEntry, ProofStore};
+mod sampling;
+use sampling::{step_leaf, InclusionProof, StepLog};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProofRecord {
@@ -22,9 +38,10 @@ pub struct ProofRecord {
     pub timestamp: u64,
     pub submitted: bool,
     pub tx_hash: Option<String>,
+    pub attestation: Option<AttestationRecord>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProofType {
     TrainStep,
     TrainComplete,
@@ -40,56 +57,216 @@ pub struct SubmitProofRequest {
     pub gradients: Option<Vec<f64>>,
     pub weights: Option<Vec<f64>>,
     pub output_cid: Option<String>,
+    #[serde(default)]
+    pub attestation: Option<AttestationQuote>,
+    /// Prove only every k-th train step on-chain, plus the final one. The
+    /// first step that carries it sets it for the job.
+    #[serde(default)]
+    pub proof_sampling_interval: Option<u64>,
+    #[serde(default)]
+    pub final_step: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FinalizeRequest {
     pub job_id: String,
+    /// Pay out now from ProofOfCompute job escrow and settle after the
+    /// challenge window, instead of blocking on verification
+    #[serde(default)]
+    pub optimistic: bool,
+}
+
+/// A job's finalization. The first finalize claims the job; repeats, e.g.
+/// a double notification from ai-runtime, wait for its result or get the
+/// stored one, so a job is paid out once. A failed finalize drops the claim.
+#[derive(Debug)]
+pub enum Finalization {
+    InFlight(Vec<oneshot::Sender<Result<serde_json::Value, StatusCode>>>), // Waiting repeats
+    Done(serde_json::Value),
+}
+
+// TEE attestation
+
+/// Quote submitted by ai-runtime for a job launched under a TEE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationQuote {
+    pub tee_type: String,
+    pub measurement: String,
+    pub image_digest: String,
+    pub job_id: String,
+    pub nonce: String,
+    pub report_data: String,
+    pub raw_quote: String,
+    pub signature: String, // By the leaf of `cert_chain`
+    #[serde(default)]
+    pub cert_chain: Vec<AttestationCert>, // Leaf first; empty for simulated quotes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AttestationStatus {
+    Verified,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationRecord {
+    pub job_id: String,
+    pub tee_type: String,
+    pub measurement: String,
+    pub image_digest: String,
+    pub status: AttestationStatus,
+    pub reason: Option<String>,
+    pub verified_at: u64,
+}
+
+/// What retention GC keeps of a finished job's proofs: the on-chain submissions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedProofs {
+    pub job_id: String,
+    pub proof_count: usize,
+    pub tx_hashes: Vec<String>,            // ProofOfCompute submissions
+    pub completion_digest: Option<String>, // Digest of the TrainComplete/InferComplete proof
+    pub attestation_status: Option<AttestationStatus>,
+    pub steps_root: Option<String>, // Merkle root over a sampled job's steps
+    pub completed_at: u64,
+    pub archived_at: u64,
+}
+
+/// Trusted measurements per TEE type. Empty `image_digests` accepts any image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeeTrustRoot {
+    pub tee_type: String,
+    pub measurements: Vec<String>,
+    pub image_digests: Vec<String>,
+    #[serde(default)]
+    pub root_keys: Vec<String>, // Vendor attestation roots quote chains must lead to
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NonceRequest {
+    pub job_id: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ProofSubmitResponse {
     pub proof_id: String,
-    pub tx_hash: String,
+    pub status: String, // "queued", or "logged" for a step sampling keeps off-chain; a proof already accepted is rejected as a replay
+    pub tx_hash: Option<String>, // Set once the proof is on-chain
     pub gas_used: u64,
 }
 
 // Application state
 pub struct AppState {
     proofs: Arc<RwLock<HashMap<String, Vec<ProofRecord>>>>, // job_id -> proofs[]
+    attestation_nonces: Arc<RwLock<HashMap<String, String>>>, // job_id -> nonce
+    attestations: Arc<RwLock<HashMap<String, AttestationRecord>>>, // job_id -> attestation
+    tee_trust_roots: Vec<TeeTrustRoot>,
+    allow_simulated_tee: bool, // Dev and test deployments only: accept quotes from the simulated launcher
+    jobd_url: String,
     contract_client: Arc<ContractClient>,
     node_pubkey: String,
+    mempool: Arc<RwLock<ProofMempool>>,
+    accepted: Arc<RwLock<HashSet<ProofKey>>>, // Proofs of jobs not yet finalized, kept past retention GC
+    proof_store: Arc<dyn ProofStore>, // Accepted proofs are written through, and the maps rebuilt from it at startup
+    nonces: Arc<RwLock<NonceManager>>,
+    archived: Arc<RwLock<HashMap<String, ArchivedProofs>>>, // Evicted by retention GC
+    retention: RetentionPolicy,
+    escrows: Arc<RwLock<HashMap<String, Escrow>>>, // job_id -> milestone escrow
+    finalizations: Arc<RwLock<HashMap<String, Finalization>>>, // job_id -> finalization
+    step_logs: Arc<RwLock<HashMap<String, StepLog>>>, // job_id -> every step of a sampled job
+    escrow_backend: Arc<dyn EscrowBackend>,
+    receipts_url: String,
+    clock: SharedClock,
+    entropy: SharedEntropy,
+}
+
+/// What a train step proof commits to
+#[derive(Debug, Clone, Copy)]
+pub struct StepDigests<'a> {
+    pub loss_digest: &'a str,
+    pub gradient_digest: &'a str,
+    pub weights_digest: &'a str,
 }
 
+const CONTRACTS: &[Contract] = &[Contract { name: "ProofOfCompute", env: "PROOF_OF_COMPUTE_ADDR" }];
+
 pub struct ContractClient {
-    rpc_url: String,
+    rpc: RpcEndpoints,
+    proof_of_compute: String, // Contract address
+    entropy: SharedEntropy, // Stands in for the tx hash of batches, which have no contract call yet
 }
 
 impl ContractClient {
-    pub fn new(rpc_url: String) -> Self {
-        ContractClient { rpc_url }
+    /// A client on a placeholder address; the service sets the real one
+    /// with `with_contracts` once it is resolved
+    pub fn new(rpc: impl Into<RpcEndpoints>, entropy: SharedEntropy) -> Self {
+        ContractClient {
+            rpc: rpc.into(),
+            proof_of_compute: "0x0000000000000000000000000000000000000001".to_string(),
+            entropy,
+        }
+    }
+
+    /// Call the contract at the address resolved at startup
+    pub fn with_contracts(mut self, contracts: &ContractRegistry) -> Self {
+        if let Some(address) = contracts.address("ProofOfCompute") {
+            self.proof_of_compute = address.to_string();
+        }
+        self
+    }
+
+    async fn send_transaction(&self, signature: &str, args: &[Token]) -> Result<String, String> {
+        let data = hex::encode(abi_encode_call(signature, args)?);
+
+        let params = serde_json::json!([{
+            "from": std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
+            "to": self.proof_of_compute,
+            "data": format!("0x{}", data),
+            "gas": "0x100000",
+            "gasPrice": "0x4a817c800",
+        }]);
+
+        let result = self.rpc.call("eth_sendTransaction", params).await.map_err(|e| match e {
+            RpcError::Rpc { .. } => format!("Transaction error: {}", e),
+            _ => format!("Transaction failed: {}", e),
+        })?;
+
+        result.as_str()
+            .ok_or_else(|| "No tx hash in response".to_string())
+            .map(|s| s.to_string())
     }
 
     pub async fn record_train_proof(
         &self,
         job_id: &str,
         step: u64,
-        loss_digest: &str,
-        gradient_digest: &str,
-        weights_digest: &str,
+        digests: &StepDigests<'_>,
         node_pubkey: &str,
+        nonce: u64,
     ) -> Result<String, String> {
+        let StepDigests { loss_digest, gradient_digest, weights_digest } = *digests;
         // Call ProofOfCompute.recordTrainProof()
-        println!("📝 Recording train proof on-chain:");
-        println!("   Job:      {}", job_id);
-        println!("   Step:     {}", step);
-        println!("   Loss:     {}", &loss_digest[..16]);
-        println!("   Gradient: {}", &gradient_digest[..16]);
-        println!("   Weights:  {}", &weights_digest[..16]);
-        
-        // In production: use ethers-rs to call contract
-        let tx_hash = format!("0x{}", random_hash());
-        println!("   TX:       {}", tx_hash);
+        info!("📝 Recording train proof on-chain:");
+        info!("   Job:      {}", job_id);
+        info!("   Step:     {}", step);
+        info!("   Nonce:    {}", nonce);
+        info!("   Loss:     {}", &loss_digest[..16]);
+        info!("   Gradient: {}", &gradient_digest[..16]);
+        info!("   Weights:  {}", &weights_digest[..16]);
+
+        let tx_hash = self.send_transaction(
+            "recordTrainProof(bytes32,uint256,bytes32,bytes32,bytes32,bytes32,bytes)",
+            &[
+                abi_encode_bytes32(job_id),
+                abi_encode_uint256(step),
+                abi_encode_bytes32(loss_digest),
+                abi_encode_bytes32(gradient_digest),
+                abi_encode_bytes32(weights_digest),
+                abi_encode_bytes32(node_pubkey),
+                Token::Bytes(Vec::new()), // Node signature, attached once calls are signed
+            ],
+        ).await?;
+        info!("   TX:       {}", tx_hash);
         
         Ok(tx_hash)
     }
@@ -101,37 +278,94 @@ impl ContractClient {
         output_cid: &str,
         output_digest: &str,
         node_pubkey: &str,
+        nonce: u64,
     ) -> Result<String, String> {
-        println!("📝 Recording infer proof on-chain:");
-        println!("   Job:    {}", job_id);
-        println!("   Input:  {}", &input_digest[..16]);
-        println!("   Output: {}", output_cid);
-        
-        let tx_hash = format!("0x{}", random_hash());
-        println!("   TX:     {}", tx_hash);
+        info!("📝 Recording infer proof on-chain:");
+        info!("   Job:    {}", job_id);
+        info!("   Nonce:  {}", nonce);
+        info!("   Input:  {}", &input_digest[..16]);
+        info!("   Output: {}", output_cid);
+
+        let tx_hash = self.send_transaction(
+            "recordInferProof(bytes32,bytes32,bytes32,bytes32,bytes32,bytes)",
+            &[
+                abi_encode_bytes32(job_id),
+                abi_encode_bytes32(input_digest),
+                abi_encode_bytes32(output_cid),
+                abi_encode_bytes32(output_digest),
+                abi_encode_bytes32(node_pubkey),
+                Token::Bytes(Vec::new()),
+            ],
+        ).await?;
+        info!("   TX:     {}", tx_hash);
         
         Ok(tx_hash)
     }
 
+    /// Several train steps of one job in a single transaction, committed as
+    /// a root over their digests
+    pub async fn record_train_proof_batch(
+        &self,
+        job_id: &str,
+        steps: &[u64],
+        digests_root: &str,
+        node_pubkey: &str,
+        nonce: u64,
+    ) -> Result<String, String> {
+        // Call ProofOfCompute.recordTrainProofBatch()
+        info!("📝 Recording {} train proofs on-chain:", steps.len());
+        info!("   Job:      {}", job_id);
+        info!("   Steps:    {:?}", steps);
+        info!("   Root:     {}", &digests_root[..16]);
+        info!("   Node:     {}", node_pubkey);
+        info!("   Nonce:    {}", nonce);
+
+        let tx_hash = format!("0x{}", random_hash(&*self.entropy));
+        info!("   TX:       {}", tx_hash);
+
+        Ok(tx_hash)
+    }
+
     pub async fn finalize(
         &self,
         job_id: &str,
         node_pubkey: &str,
         gpu_seconds: u64,
         final_output_cid: &str,
+        optimistic: bool,
+        steps_root: Option<&str>,
+        nonce: u64,
     ) -> Result<(String, u64), String> {
-        // Call ProofOfCompute.finalize()
-        println!("\n🏁 Finalizing compute receipt:");
-        println!("   Job:         {}", job_id);
-        println!("   GPU Seconds: {}", gpu_seconds);
-        println!("   Output:      {}", final_output_cid);
+        // Call ProofOfCompute.finalize() or finalizeOptimistic()
+        info!("🏁 Finalizing compute receipt:");
+        info!("   Job:         {}", job_id);
+        info!("   Mode:        {}", if optimistic { "optimistic" } else { "verified" });
+        info!("   Nonce:       {}", nonce);
+        info!("   GPU Seconds: {}", gpu_seconds);
+        info!("   Output:      {}", final_output_cid);
+        if let Some(root) = steps_root {
+            info!("   Steps Root:  {}", root);
+        }
         
+        let signature = if optimistic {
+            "finalizeOptimistic(bytes32,bytes32,uint256,bytes32)"
+        } else {
+            "finalize(bytes32,bytes32,uint256,bytes32)"
+        };
+        let tx_hash = self.send_transaction(
+            signature,
+            &[
+                abi_encode_bytes32(job_id),
+                abi_encode_bytes32(node_pubkey),
+                abi_encode_uint256(gpu_seconds),
+                abi_encode_bytes32(final_output_cid),
+            ],
+        ).await?;
+
         // Calculate payout
         let payout = gpu_seconds * 1_000_000_000_000_000; // 0.001 ARTH per GPU-second
-        println!("   Payout:      {} ARTH", payout as f64 / 1e18);
-        
-        let tx_hash = format!("0x{}", random_hash());
-        println!("   TX:          {}", tx_hash);
+        info!("   Payout:      {} ARTH", payout as f64 / 1e18);
+        info!("   TX:          {}", tx_hash);
         
         Ok((tx_hash, payout))
     }
@@ -155,7 +389,45 @@ async fn submit_proof(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SubmitProofRequest>,
 ) -> Result<Json<ProofSubmitResponse>, StatusCode> {
-    println!("\n📊 Submitting proof for job: {}", req.job_id);
+    info!("📊 Submitting proof for job: {}", req.job_id);
+
+    // A proof's job and step are its nonce. Turn replays away before they
+    // re-run attestation; queue_proof makes the final check.
+    if is_replay(&state, &proof_key(&req.job_id, &req.proof_type, req.step)).await {
+        warn!("   ⛔ Replayed proof for job {} (step {:?}) rejected", req.job_id, req.step);
+        return Err(StatusCode::CONFLICT);
+    }
+    
+    // TEE jobs attach their attestation quote to the first proof
+    let attestation = match &req.attestation {
+        Some(quote) => {
+            let expected_nonce = state.attestation_nonces.read().await
+                .get(&req.job_id)
+                .cloned()
+                .ok_or(StatusCode::PRECONDITION_FAILED)?;
+            
+            let record = verify_attestation(
+                quote,
+                &req.job_id,
+                &expected_nonce,
+                &state.tee_trust_roots,
+                state.allow_simulated_tee,
+                state.clock.now_secs(),
+            );
+            info!("   🔏 Attestation {:?} (measurement {})", record.status, record.measurement);
+            
+            if record.status == AttestationStatus::Verified {
+                notify_jobd_attested(&state.jobd_url, &record).await;
+            }
+            state.attestations.write().await.insert(req.job_id.clone(), record.clone());
+            
+            if record.status != AttestationStatus::Verified {
+                return Err(StatusCode::UNPROCESSABLE_ENTITY);
+            }
+            Some(record)
+        }
+        None => None,
+    };
     
     match req.proof_type {
         ProofType::TrainStep => {
@@ -169,32 +441,55 @@ async fn submit_proof(
             let gradient_digest = compute_digest(&gradients);
             let weights_digest = compute_digest(&weights);
             
-            // Submit to blockchain
-            let tx_hash = state.contract_client.record_train_proof(
-                &req.job_id,
-                step,
-                &loss_digest,
-                &gradient_digest,
-                &weights_digest,
-                &state.node_pubkey,
-            ).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            // A sampled job logs every step, but only sampled ones go on-chain
+            let leaf = step_leaf(step, &loss_digest, &gradient_digest, &weights_digest);
+            let sampled = {
+                let mut step_logs = state.step_logs.write().await;
+                if let Some(interval) = req.proof_sampling_interval.filter(|k| *k > 1) {
+                    step_logs.entry(req.job_id.clone()).or_insert_with(|| StepLog::new(interval));
+                }
+                step_logs.get(&req.job_id).map(|log| log.is_sampled(step, req.final_step))
+            };
+            if sampled == Some(false) {
+                accept_proof(&state, proof_key(&req.job_id, &ProofType::TrainStep, Some(step)), None).await?;
+                if let Some(log) = state.step_logs.write().await.get_mut(&req.job_id) {
+                    log.record(step, leaf);
+                }
+                return Ok(Json(ProofSubmitResponse {
+                    proof_id: format!("{}-step-{}", req.job_id, step),
+                    status: "logged".to_string(),
+                    tx_hash: None,
+                    gas_used: 0,
+                }));
+            }
             
-            // Store proof record
+            // Queue for submission; the drain loop puts it on-chain
             let proof = ProofRecord {
                 job_id: req.job_id.clone(),
                 proof_type: ProofType::TrainStep,
                 step: Some(step),
-                digest: loss_digest,
-                timestamp: now(),
-                submitted: true,
-                tx_hash: Some(tx_hash.clone()),
+                digest: loss_digest.clone(),
+                timestamp: state.clock.now_secs(),
+                submitted: false,
+                tx_hash: None,
+                attestation: attestation.clone(),
             };
-            
-            let mut proofs = state.proofs.write().await;
-            proofs.entry(req.job_id.clone()).or_insert_with(Vec::new).push(proof);
+            let call = ProofCall::TrainStep {
+                step,
+                loss_digest,
+                gradient_digest,
+                weights_digest,
+            };
+            let (status, tx_hash) = queue_proof(&state, proof, call).await?;
+            if sampled.is_some() {
+                if let Some(log) = state.step_logs.write().await.get_mut(&req.job_id) {
+                    log.record(step, leaf);
+                }
+            }
             
             Ok(Json(ProofSubmitResponse {
                 proof_id: format!("{}-step-{}", req.job_id, step),
+                status,
                 tx_hash,
                 gas_used: 150000,
             }))
@@ -207,31 +502,26 @@ async fn submit_proof(
             let input_digest = compute_digest(&[1.0]); // Placeholder
             let output_digest = compute_digest(output_cid.as_bytes().iter().map(|&b| b as f64).collect::<Vec<_>>().as_slice());
             
-            // Submit to blockchain
-            let tx_hash = state.contract_client.record_infer_proof(
-                &req.job_id,
-                &input_digest,
-                &output_cid,
-                &output_digest,
-                &state.node_pubkey,
-            ).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            
-            // Store proof record
             let proof = ProofRecord {
                 job_id: req.job_id.clone(),
                 proof_type: ProofType::InferComplete,
                 step: None,
-                digest: output_digest,
-                timestamp: now(),
-                submitted: true,
-                tx_hash: Some(tx_hash.clone()),
+                digest: output_digest.clone(),
+                timestamp: state.clock.now_secs(),
+                submitted: false,
+                tx_hash: None,
+                attestation: attestation.clone(),
             };
-            
-            let mut proofs = state.proofs.write().await;
-            proofs.entry(req.job_id.clone()).or_insert_with(Vec::new).push(proof);
+            let call = ProofCall::Infer {
+                input_digest,
+                output_cid,
+                output_digest,
+            };
+            let (status, tx_hash) = queue_proof(&state, proof, call).await?;
             
             Ok(Json(ProofSubmitResponse {
                 proof_id: format!("{}-infer", req.job_id),
+                status,
                 tx_hash,
                 gas_used: 120000,
             }))
@@ -245,60 +535,503 @@ async fn finalize_job(
     State(state): State<Arc<AppState>>,
     Json(req): Json<FinalizeRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    println!("\n🎯 Finalizing job: {}", req.job_id);
+    info!("🎯 Finalizing job: {}", req.job_id);
     
+    // Claim the job, or wait on the finalize that already has
+    let (tx, rx) = oneshot::channel();
+    {
+        let mut finalizations = state.finalizations.write().await;
+        match finalizations.get_mut(&req.job_id) {
+            Some(Finalization::Done(result)) => {
+                let result = result.clone();
+                drop(finalizations);
+                info!("   ↩️  Already finalized");
+                // Tranches whose release failed the first time are still owed
+                if let Some(retried @ 1..) = release_final_tranches(&state, &req.job_id).await {
+                    info!("   💸 Released {} tranches left over from the first finalize", retried);
+                }
+                return Ok(Json(result));
+            }
+            Some(Finalization::InFlight(waiters)) => waiters.push(tx),
+            None => {
+                finalizations.insert(req.job_id.clone(), Finalization::InFlight(vec![tx]));
+                // Spawned so a dropped request can't strand the claim
+                tokio::spawn(run_finalize(state.clone(), req));
+            }
+        }
+    }
+    rx.await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.map(Json)
+}
+
+/// Finalize a claimed job, then record the result and wake its waiters
+async fn run_finalize(state: Arc<AppState>, req: FinalizeRequest) {
+    let result = finalize_once(&state, &req).await;
+    let claim = match &result {
+        Ok(finalized) => state.finalizations.write().await.insert(req.job_id.clone(), Finalization::Done(finalized.clone())),
+        Err(_) => state.finalizations.write().await.remove(&req.job_id),
+    };
+    if let Ok(finalized) = &result {
+        prune_accepted(&state, &req.job_id, finalized).await;
+    }
+    if let Some(Finalization::InFlight(waiters)) = claim {
+        for waiter in waiters {
+            let _ = waiter.send(result.clone());
+        }
+    }
+}
+
+async fn finalize_once(state: &AppState, req: &FinalizeRequest) -> Result<serde_json::Value, StatusCode> {
+    // Whether the job must have run in a TEE is ai-jobd's record of it, not the caller's say
+    let tee_job = job_requires_tee(&state.jobd_url, &req.job_id).await.map_err(|e| {
+        error!("   ❌ No job record for {}: {}", req.job_id, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+
     // Get proof count
     let proofs = state.proofs.read().await;
     let job_proofs = proofs.get(&req.job_id).ok_or(StatusCode::NOT_FOUND)?;
-    let step_count = job_proofs.len();
+    let step_logs = state.step_logs.read().await;
+    let step_log = step_logs.get(&req.job_id);
+    // A sampled job's steps all count, proven on-chain or not
+    let step_count = match step_log {
+        Some(log) => log.len() + job_proofs.iter().filter(|p| p.proof_type != ProofType::TrainStep).count(),
+        None => job_proofs.len(),
+    };
+    let steps_root = step_log.and_then(StepLog::root);
+    drop(step_logs);
+    
+    info!("   Total proofs submitted: {}", step_count);
     
-    println!("   Total proofs submitted: {}", step_count);
+    // TEE jobs are only paid out with a verified attestation
+    let tee_required = tee_job
+        || state.attestation_nonces.read().await.contains_key(&req.job_id);
+    let attestations = state.attestations.read().await;
+    check_tee_payout(tee_required, attestations.get(&req.job_id)).map_err(|e| {
+        error!("   ❌ Payout refused: {}", e);
+        StatusCode::PRECONDITION_FAILED
+    })?;
+    drop(attestations);
     
     // Estimate GPU seconds (simplified)
     let gpu_seconds = (step_count as u64) * 10; // Assume 10 seconds per step
     
     // Get final output CID (placeholder)
     let final_output_cid = "artha://QmFinalModel123";
+    drop(proofs);
     
-    // Finalize on blockchain
-    let (tx_hash, payout) = state.contract_client.finalize(
-        &req.job_id,
-        &state.node_pubkey,
+    // Finalize on blockchain, ahead of any queued step proofs
+    let key = ProofKey {
+        job_id: req.job_id.clone(),
+        proof_type: "finalize".to_string(),
+        step: None,
+    };
+    let call = ProofCall::Finalize {
         gpu_seconds,
-        final_output_cid,
-    ).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        output_cid: final_output_cid.to_string(),
+        optimistic: req.optimistic,
+        steps_root: steps_root.clone(),
+    };
+    let submission = {
+        let mut mempool = state.mempool.write().await;
+        match mempool.enqueue(key.clone(), call) {
+            Enqueued::Submitted(submission) => Ok(submission),
+            Enqueued::Queued | Enqueued::Pending => Err(mempool.watch(&key).ok_or(StatusCode::INTERNAL_SERVER_ERROR)?),
+        }
+    };
+    let submission = match submission {
+        Ok(submission) => submission,
+        Err(receiver) => tokio::time::timeout(FINALIZE_WAIT, receiver)
+            .await
+            .map_err(|_| StatusCode::GATEWAY_TIMEOUT)?
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map_err(|e| {
+                error!("   ❌ Finalize failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?,
+    };
+    let tx_hash = submission.tx_hash;
+    let payout = submission.payout.unwrap_or(0);
+    
+    info!("   ✅ Job finalized successfully");
     
-    println!("   ✅ Job finalized successfully");
+    // Jobs under milestone escrow are paid the remaining tranches instead
+    let milestones_released = release_final_tranches(state, &req.job_id).await;
     
-    // Auto-payout via DealMarket.computePayout()
+    // Auto-payout via DealMarket.computePayout(); optimistic finalize has
+    // already paid out of job escrow
     let deal_market_addr = std::env::var("DEAL_MARKET_ADDR").unwrap_or_default();
-    if !deal_market_addr.is_empty() {
+    if !deal_market_addr.is_empty() && !req.optimistic && milestones_released.is_none() {
         if let Err(e) = auto_payout_compute(&state.contract_client, &req.job_id, gpu_seconds, payout).await {
-            eprintln!("   ⚠️  Auto-payout failed: {}", e);
+            warn!("   ⚠️  Auto-payout failed: {}", e);
         } else {
-            println!("   💰 Auto-payout completed");
+            info!("   💰 Auto-payout completed");
         }
     }
     
-    Ok(Json(serde_json::json!({
+    Ok(serde_json::json!({
         "job_id": req.job_id,
         "tx_hash": tx_hash,
         "gpu_seconds": gpu_seconds,
         "payout": payout,
         "proof_count": step_count,
+        "mode": if req.optimistic { "optimistic" } else { "verified" },
+        "milestones_released": milestones_released,
+        "steps_root": steps_root,
+    }))
+}
+
+/// Whether ai-jobd's record of the job asks for TEE execution
+async fn job_requires_tee(jobd_url: &str, job_id: &str) -> Result<bool, String> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/job/{}/status", jobd_url, job_id))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("ai-jobd returned {}", response.status()));
+    }
+    let status: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    status["job"]["tee_required"].as_bool().ok_or_else(|| "Job record has no tee_required".to_string())
+}
+
+/// Verify a TEE quote against the issued nonce and configured trust roots.
+/// Quote layout checks mirror custody `tee_enclave::verify_quote`; the
+/// signature must chain to one of the root's keys, see `attestation`.
+fn verify_attestation(
+    quote: &AttestationQuote,
+    job_id: &str,
+    expected_nonce: &str,
+    trust_roots: &[TeeTrustRoot],
+    allow_simulated: bool,
+    now: u64,
+) -> AttestationRecord {
+    let reject = |reason: &str| AttestationRecord {
+        job_id: job_id.to_string(),
+        tee_type: quote.tee_type.clone(),
+        measurement: quote.measurement.clone(),
+        image_digest: quote.image_digest.clone(),
+        status: AttestationStatus::Rejected,
+        reason: Some(reason.to_string()),
+        verified_at: now,
+    };
+    
+    if !quote.raw_quote.contains("SGX_QUOTE") || !quote.raw_quote.contains("MRENCLAVE") {
+        return reject("Malformed quote");
+    }
+    if !quote.raw_quote.ends_with(&format!("MRENCLAVE:{}", quote.measurement)) {
+        return reject("Quote measurement mismatch");
+    }
+    if quote.job_id != job_id {
+        return reject("Quote bound to a different job");
+    }
+    if quote.nonce != expected_nonce {
+        return reject("Nonce mismatch");
+    }
+    
+    // Report data must bind image digest, job id and nonce
+    let expected_report = compute_report_data(&quote.image_digest, job_id, expected_nonce);
+    if quote.report_data != expected_report {
+        return reject("Report data does not bind image digest, job and nonce");
+    }
+    
+    let root = match trust_roots.iter().find(|r| r.tee_type == quote.tee_type) {
+        Some(root) => root,
+        None => return reject("No trust root for TEE type"),
+    };
+    if !root.measurements.contains(&quote.measurement) {
+        return reject("Untrusted measurement");
+    }
+    if !root.image_digests.is_empty() && !root.image_digests.contains(&quote.image_digest) {
+        return reject("Untrusted image digest");
+    }
+    
+    if quote.cert_chain.is_empty() {
+        // Only the simulated launcher signs without a chain
+        if !allow_simulated {
+            return reject("Quote has no certificate chain");
+        }
+        if quote.signature != simulated_quote_signature(&quote.measurement, &quote.report_data) {
+            return reject("Invalid quote signature");
+        }
+    } else if let Err(e) = attestation::verify_chain(
+        &quote.tee_type,
+        &quote.cert_chain,
+        &root.root_keys,
+        &quote.raw_quote,
+        &quote.report_data,
+        &quote.signature,
+    ) {
+        return reject(&e);
+    }
+    
+    AttestationRecord {
+        job_id: job_id.to_string(),
+        tee_type: quote.tee_type.clone(),
+        measurement: quote.measurement.clone(),
+        image_digest: quote.image_digest.clone(),
+        status: AttestationStatus::Verified,
+        reason: None,
+        verified_at: now,
+    }
+}
+
+fn check_tee_payout(tee_required: bool, attestation: Option<&AttestationRecord>) -> Result<(), String> {
+    if !tee_required {
+        return Ok(());
+    }
+    match attestation {
+        Some(record) if record.status == AttestationStatus::Verified => Ok(()),
+        Some(_) => Err("TEE attestation was rejected".to_string()),
+        None => Err("TEE job has no attestation".to_string()),
+    }
+}
+
+fn compute_report_data(image_digest: &str, job_id: &str, nonce: &str) -> String {
+    use sha2::{Sha256, Digest};
+    let mut hasher = Sha256::new();
+    hasher.update(image_digest.as_bytes());
+    hasher.update(job_id.as_bytes());
+    hasher.update(nonce.as_bytes());
+    format!("0x{:x}", hasher.finalize())
+}
+
+fn simulated_quote_signature(measurement: &str, report_data: &str) -> String {
+    use sha2::{Sha256, Digest};
+    let mut hasher = Sha256::new();
+    hasher.update(b"ARTHA_SIMULATED_TEE:");
+    hasher.update(measurement.as_bytes());
+    hasher.update(report_data.as_bytes());
+    format!("0x{:x}", hasher.finalize())
+}
+
+async fn issue_attestation_nonce(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<NonceRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let nonce = format!("0x{:064x}", Keccak256::digest(format!("{}:{}", req.job_id, random_hash(&*state.entropy)).as_bytes()));
+    state.attestation_nonces.write().await.insert(req.job_id.clone(), nonce.clone());
+    
+    Ok(Json(serde_json::json!({
+        "job_id": req.job_id,
+        "nonce": nonce,
     })))
 }
 
+async fn get_attestation(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> Result<Json<AttestationRecord>, StatusCode> {
+    let attestations = state.attestations.read().await;
+    attestations.get(&job_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn notify_jobd_attested(jobd_url: &str, record: &AttestationRecord) {
+    let client = reqwest::Client::new();
+    
+    let _ = client
+        .post(format!("{}/job/attested", jobd_url))
+        .json(record)
+        .send()
+        .await;
+}
+
+fn load_tee_trust_roots() -> Vec<TeeTrustRoot> {
+    // ARTHA_TEE_TRUST_ROOTS: JSON array of TeeTrustRoot
+    std::env::var("ARTHA_TEE_TRUST_ROOTS")
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
 async fn get_job_proofs(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(job_id): axum::extract::Path<String>,
 ) -> Result<Json<Vec<ProofRecord>>, StatusCode> {
     let proofs = state.proofs.read().await;
-    let job_proofs = proofs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    let Some(job_proofs) = proofs.get(&job_id) else {
+        if state.archived.read().await.contains_key(&job_id) {
+            return Err(StatusCode::GONE);
+        }
+        return Err(StatusCode::NOT_FOUND);
+    };
     
     Ok(Json(job_proofs.clone()))
 }
 
+/// GET /proofs/:job_id/steps/:step/inclusion - Merkle path of a sampled
+/// job's step to the root finalize commits
+async fn get_step_inclusion(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path((job_id, step)): axum::extract::Path<(String, u64)>,
+) -> Result<Json<InclusionProof>, StatusCode> {
+    let step_logs = state.step_logs.read().await;
+    let proof = step_logs.get(&job_id).and_then(|log| log.inclusion(step)).ok_or(StatusCode::NOT_FOUND)?;
+    debug_assert!(proof.verify());
+    Ok(Json(proof))
+}
+
+/// Proofs serialized per chunk of an export stream
+const EXPORT_PAGE_SIZE: usize = 256;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProofExportQuery {
+    pub proof_type: Option<ProofType>,
+    pub from_step: Option<u64>, // Inclusive; proofs without a step are left out when a range is given
+    pub to_step: Option<u64>,   // Inclusive
+}
+
+impl ProofExportQuery {
+    fn matches(&self, proof: &ProofRecord) -> bool {
+        if self.proof_type.as_ref().is_some_and(|wanted| *wanted != proof.proof_type) {
+            return false;
+        }
+        if self.from_step.is_none() && self.to_step.is_none() {
+            return true;
+        }
+        proof.step.is_some_and(|step| {
+            self.from_step.is_none_or(|from| step >= from) && self.to_step.is_none_or(|to| step <= to)
+        })
+    }
+}
+
+/// GET /proofs/:job_id/export - The job's proofs as NDJSON, one record per
+/// line in the order they were accepted. Written a page at a time, so
+/// neither end holds the whole set. A job evicted by retention GC while
+/// streaming ends the stream early.
+async fn export_job_proofs(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<ProofExportQuery>,
+) -> Result<axum::response::Response, StatusCode> {
+    if !state.proofs.read().await.contains_key(&job_id) {
+        if state.archived.read().await.contains_key(&job_id) {
+            return Err(StatusCode::GONE);
+        }
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let query = Arc::new(query);
+    let lines = futures_util::stream::unfold(Some(0), move |next| {
+        let state = state.clone();
+        let job_id = job_id.clone();
+        let query = query.clone();
+        async move {
+            let mut next = next?;
+            let proofs = state.proofs.read().await;
+            let records = proofs.get(&job_id)?;
+            let mut out = Vec::new();
+            let mut written = 0;
+            while next < records.len() && written < EXPORT_PAGE_SIZE {
+                let proof = &records[next];
+                next += 1;
+                if query.matches(proof) {
+                    if let Err(e) = serde_json::to_writer(&mut out, proof) {
+                        return Some((Err(std::io::Error::other(e)), None));
+                    }
+                    out.push(b'\n');
+                    written += 1;
+                }
+            }
+            if out.is_empty() {
+                return None;
+            }
+            let more = (next < records.len()).then_some(next);
+            Some((Ok::<_, std::io::Error>(out), more))
+        }
+    });
+
+    Ok(axum::response::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "application/x-ndjson")
+        .body(axum::body::Body::from_stream(lines))
+        .unwrap())
+}
+
+/// GET /proofs/:job_id/archive - On-chain references kept for proofs retention GC evicted
+async fn get_archived_proofs(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> Result<Json<ArchivedProofs>, StatusCode> {
+    state.archived.read().await.get(&job_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// A job's proofs are finished once a completion proof exists and every proof
+/// is on-chain; until then the job may still be running or retrying.
+fn proofs_finished_at(proofs: &[ProofRecord]) -> Option<u64> {
+    let complete = proofs
+        .iter()
+        .any(|p| matches!(p.proof_type, ProofType::TrainComplete | ProofType::InferComplete));
+    let on_chain = proofs.iter().all(|p| p.submitted && p.tx_hash.is_some());
+    if complete && on_chain {
+        proofs.iter().map(|p| p.timestamp).max()
+    } else {
+        None
+    }
+}
+
+/// Evict finished jobs' proofs and attestations past the retention policy
+async fn gc_finished_proofs(state: &AppState, now: u64) -> usize {
+    let mut proofs = state.proofs.write().await;
+    let finished: Vec<(String, u64)> = proofs
+        .iter()
+        .filter_map(|(job_id, records)| proofs_finished_at(records).map(|at| (job_id.clone(), at)))
+        .collect();
+    let evicted: Vec<(String, Vec<ProofRecord>)> = state
+        .retention
+        .select(finished, now)
+        .into_iter()
+        .filter_map(|job_id| proofs.remove(&job_id).map(|records| (job_id, records)))
+        .collect();
+    drop(proofs);
+    if evicted.is_empty() {
+        return 0;
+    }
+
+    let records: Vec<&ProofRecord> = evicted.iter().flat_map(|(_, records)| records).collect();
+    if let Err(e) = state.retention.archive(&records) {
+        warn!("⚠️  Failed to archive evicted proofs: {}", e);
+    }
+    // Their logs keep only what turns replays away
+    {
+        let finalizations = state.finalizations.read().await;
+        let accepted = state.accepted.read().await;
+        for (job_id, _) in &evicted {
+            let entries: Vec<ProofEntry> = match finalizations.get(job_id) {
+                Some(Finalization::Done(result)) => vec![ProofEntry::Finalized { result: result.clone() }],
+                _ => accepted
+                    .iter()
+                    .filter(|key| key.job_id == *job_id)
+                    .map(|key| ProofEntry::Accepted { key: key.clone() })
+                    .collect(),
+            };
+            if let Err(e) = state.proof_store.compact(job_id, &entries) {
+                warn!("⚠️  Failed to compact proof log for {}: {}", job_id, e);
+            }
+        }
+    }
+    let mut attestations = state.attestations.write().await;
+    let mut nonces = state.attestation_nonces.write().await;
+    let mut archived = state.archived.write().await;
+    let mut step_logs = state.step_logs.write().await;
+    for (job_id, records) in &evicted {
+        nonces.remove(job_id);
+        let attestation = attestations.remove(job_id);
+        archived.insert(job_id.clone(), ArchivedProofs {
+            job_id: job_id.clone(),
+            proof_count: records.len(),
+            tx_hashes: records.iter().filter_map(|p| p.tx_hash.clone()).collect(),
+            completion_digest: records
+                .iter()
+                .find(|p| matches!(p.proof_type, ProofType::TrainComplete | ProofType::InferComplete))
+                .map(|p| p.digest.clone()),
+            attestation_status: attestation.map(|a| a.status),
+            steps_root: step_logs.remove(job_id).and_then(|log| log.root()),
+            completed_at: proofs_finished_at(records).unwrap_or(now),
+            archived_at: now,
+        });
+    }
+    evicted.len()
+}
+
 async fn auto_payout_compute(
     contract_client: &ContractClient,
     job_id: &str,
@@ -312,18 +1045,18 @@ async fn auto_payout_compute(
     // Rate: payout / gpu_seconds (in wei)
     let rate_per_second = payout / gpu_seconds.max(1);
     
-    println!("   💰 Calling DealMarket.computePayout()...");
-    println!("      Job ID:      {}", job_id);
-    println!("      Provider:   {}", provider_addr);
-    println!("      GPU Seconds: {}", gpu_seconds);
-    println!("      Rate:       {} wei/sec", rate_per_second);
+    info!("   💰 Calling DealMarket.computePayout()...");
+    info!("      Job ID:      {}", job_id);
+    info!("      Provider:   {}", provider_addr);
+    info!("      GPU Seconds: {}", gpu_seconds);
+    info!("      Rate:       {} wei/sec", rate_per_second);
     
     // In production: Make JSON-RPC call to DealMarket.computePayout()
     // For now: log the call
     let job_hash = format!("0x{:064x}", Keccak256::digest(job_id.as_bytes()));
     
-    println!("      Job Hash:    {}", job_hash);
-    println!("      ✅ Auto-payout call prepared");
+    info!("      Job Hash:    {}", job_hash);
+    info!("      ✅ Auto-payout call prepared");
     
     Ok(())
 }
@@ -332,6 +1065,7 @@ async fn get_stats(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let proofs = state.proofs.read().await;
+    let queued_proofs = state.mempool.read().await.len();
     
     let total_jobs = proofs.len();
     let total_proofs: usize = proofs.values().map(|p| p.len()).sum();
@@ -343,6 +1077,7 @@ async fn get_stats(
         "total_jobs": total_jobs,
         "total_proofs": total_proofs,
         "submitted_proofs": submitted_proofs,
+        "queued_proofs": queued_proofs,
         "submission_rate": if total_proofs > 0 {
             submitted_proofs as f64 / total_proofs as f64
         } else {
@@ -351,34 +1086,530 @@ async fn get_stats(
     })))
 }
 
-fn now() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn random_hash(entropy: &dyn Entropy) -> String {
+    format!("{:016x}", entropy.next_u64())
+}
+
+/// Store the proof record (once per proof) and queue its contract call.
+/// Returns the response status and, for proofs already on-chain, the tx hash.
+fn proof_key(job_id: &str, proof_type: &ProofType, step: Option<u64>) -> ProofKey {
+    ProofKey {
+        job_id: job_id.to_string(),
+        proof_type: format!("{:?}", proof_type),
+        step: if matches!(proof_type, ProofType::TrainStep) { step } else { None },
+    }
+}
+
+/// Accept a proof and queue it for submission. Each (job, proof type, step)
+/// is accepted once, ever: a second submission is a replay, and counting it
+/// would inflate the job's steps and payout.
+async fn queue_proof(state: &AppState, record: ProofRecord, call: ProofCall) -> Result<(String, Option<String>), StatusCode> {
+    let key = proof_key(&record.job_id, &record.proof_type, record.step);
+    accept_proof(state, key.clone(), Some(&record)).await?;
+
+    let enqueued = state.mempool.write().await.enqueue(key, call);
+    match enqueued {
+        Enqueued::Queued => {
+            let mut proofs = state.proofs.write().await;
+            proofs.entry(record.job_id.clone()).or_insert_with(Vec::new).push(record);
+            Ok(("queued".to_string(), None))
+        }
+        // Only reachable for keys accepted before, which were turned away above
+        Enqueued::Pending | Enqueued::Submitted(_) => Err(StatusCode::CONFLICT),
+    }
+}
+
+/// Whether a proof was accepted before, or belongs to a job already finalized
+async fn is_replay(state: &AppState, key: &ProofKey) -> bool {
+    matches!(state.finalizations.read().await.get(&key.job_id), Some(Finalization::Done(_)))
+        || state.accepted.read().await.contains(key)
+}
+
+/// Claim a proof's key and write it to the job's log, as its record when it
+/// has one. A replay is turned away.
+async fn accept_proof(state: &AppState, key: ProofKey, record: Option<&ProofRecord>) -> Result<(), StatusCode> {
+    // Held across the claim, so a finalize can't slip in between
+    let finalizations = state.finalizations.read().await;
+    if matches!(finalizations.get(&key.job_id), Some(Finalization::Done(_))) || !state.accepted.write().await.insert(key.clone()) {
+        warn!("   ⛔ Replayed proof for job {} (step {:?}) rejected", key.job_id, key.step);
+        return Err(StatusCode::CONFLICT);
+    }
+    drop(finalizations);
+
+    let entry = match record {
+        Some(record) => ProofEntry::Record { record: record.clone() },
+        None => ProofEntry::Accepted { key: key.clone() },
+    };
+    if let Err(e) = state.proof_store.append(&key.job_id, &entry) {
+        error!("   ❌ Failed to store proof for job {}: {}", key.job_id, e);
+        state.accepted.write().await.remove(&key);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(())
+}
+
+/// Drop a finalized job's accepted keys, from memory and from its log. Its
+/// finalization turns replays away from here on.
+async fn prune_accepted(state: &AppState, job_id: &str, finalized: &serde_json::Value) {
+    state.accepted.write().await.retain(|key| key.job_id != job_id);
+    let mut entries: Vec<ProofEntry> = state.proofs.read().await
+        .get(job_id)
+        .into_iter()
+        .flatten()
+        .map(|record| ProofEntry::Record { record: record.clone() })
+        .collect();
+    entries.push(ProofEntry::Finalized { result: finalized.clone() });
+    if let Err(e) = state.proof_store.compact(job_id, &entries) {
+        warn!("⚠️  Failed to compact proof log for {}: {}", job_id, e);
+    }
+}
+
+/// Rebuild the proof map, accepted keys and finalizations from the store.
+/// Returns the number of jobs restored.
+async fn restore_proofs(state: &AppState) -> Result<usize, String> {
+    let stored = state.proof_store.list_jobs()?;
+    let restored = stored.len();
+    let mut proofs = state.proofs.write().await;
+    let mut accepted = state.accepted.write().await;
+    let mut finalizations = state.finalizations.write().await;
+    for job in stored {
+        accepted.extend(job.accepted);
+        if !job.proofs.is_empty() {
+            proofs.insert(job.job_id.clone(), job.proofs);
+        }
+        if let Some(result) = job.finalized {
+            finalizations.insert(job.job_id, Finalization::Done(result));
+        }
+    }
+    Ok(restored)
+}
+
+/// How long /finalize waits for its queued submission
+const FINALIZE_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Submit one batch with the next nonce
+async fn submit_batch(state: &AppState, batch: &Batch) -> Result<Submission, String> {
+    let nonce = state.nonces.write().await.reserve();
+    let client = &state.contract_client;
+
+    let result = match &batch.proofs[0].call {
+        ProofCall::TrainStep { step, loss_digest, gradient_digest, weights_digest } if batch.proofs.len() == 1 => {
+            client
+                .record_train_proof(&batch.job_id, *step, &StepDigests { loss_digest, gradient_digest, weights_digest }, &state.node_pubkey, nonce)
+                .await
+                .map(|tx_hash| (tx_hash, None))
+        }
+        ProofCall::TrainStep { .. } => {
+            let mut steps = Vec::new();
+            let mut hasher = Keccak256::new();
+            for proof in &batch.proofs {
+                if let ProofCall::TrainStep { step, loss_digest, gradient_digest, weights_digest } = &proof.call {
+                    steps.push(*step);
+                    hasher.update(step.to_be_bytes());
+                    hasher.update(loss_digest.as_bytes());
+                    hasher.update(gradient_digest.as_bytes());
+                    hasher.update(weights_digest.as_bytes());
+                }
+            }
+            let root = format!("0x{}", hex::encode(hasher.finalize()));
+            client
+                .record_train_proof_batch(&batch.job_id, &steps, &root, &state.node_pubkey, nonce)
+                .await
+                .map(|tx_hash| (tx_hash, None))
+        }
+        ProofCall::Infer { input_digest, output_cid, output_digest } => client
+            .record_infer_proof(&batch.job_id, input_digest, output_cid, output_digest, &state.node_pubkey, nonce)
+            .await
+            .map(|tx_hash| (tx_hash, None)),
+        ProofCall::Finalize { gpu_seconds, output_cid, optimistic, steps_root } => client
+            .finalize(&batch.job_id, &state.node_pubkey, *gpu_seconds, output_cid, *optimistic, steps_root.as_deref(), nonce)
+            .await
+            .map(|(tx_hash, payout)| (tx_hash, Some(payout))),
+    };
+
+    match result {
+        Ok((tx_hash, payout)) => Ok(Submission {
+            tx_hash,
+            nonce,
+            batch_size: batch.proofs.len(),
+            payout,
+        }),
+        Err(e) => {
+            state.nonces.write().await.release(nonce);
+            Err(e)
+        }
+    }
+}
+
+/// GET /dlq - Proofs whose on-chain submission failed
+async fn list_dead_letters(State(state): State<Arc<AppState>>) -> Json<Vec<DeadLetter>> {
+    Json(state.mempool.read().await.dead_letters())
+}
+
+/// POST /dlq/:id/replay - Queue a failed proof for submission again
+async fn replay_dead_letter(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let enqueued = state.mempool.write().await.replay(id).ok_or(StatusCode::NOT_FOUND)?;
+    let (status, tx_hash) = match enqueued {
+        Enqueued::Queued => ("queued", None),
+        Enqueued::Pending => ("pending", None),
+        Enqueued::Submitted(submission) => ("submitted", Some(submission.tx_hash)),
+    };
+    info!("🔁 Replayed dead letter {} ({})", id, status);
+    Ok(Json(serde_json::json!({ "id": id, "status": status, "tx_hash": tx_hash })))
+}
+
+/// Submit up to `max_submissions` batches from the mempool. Returns the
+/// submissions made.
+async fn drain_mempool(state: &AppState, max_submissions: usize) -> Vec<Submission> {
+    let mut submissions = Vec::new();
+
+    for _ in 0..max_submissions {
+        let Some(batch) = state.mempool.write().await.next_batch() else {
+            break;
+        };
+        let result = submit_batch(state, &batch).await;
+        let job_id = batch.job_id.clone();
+        let train_steps = batch.proofs.iter().any(|p| matches!(p.call, ProofCall::TrainStep { .. }));
+
+        if let Ok(submission) = &result {
+            let mut proofs = state.proofs.write().await;
+            let mut on_chain = Vec::new();
+            for record in proofs.get_mut(&batch.job_id).into_iter().flatten() {
+                let included = batch.proofs.iter().any(|p| {
+                    p.key.proof_type == format!("{:?}", record.proof_type) && p.key.step == record.step
+                });
+                if included && !record.submitted {
+                    record.submitted = true;
+                    record.tx_hash = Some(submission.tx_hash.clone());
+                    on_chain.push(ProofEntry::Record { record: record.clone() });
+                }
+            }
+            drop(proofs);
+            for entry in &on_chain {
+                if let Err(e) = state.proof_store.append(&batch.job_id, entry) {
+                    warn!("⚠️  Failed to store submitted proof for {}: {}", batch.job_id, e);
+                }
+            }
+            submissions.push(submission.clone());
+        } else if let Err(e) = &result {
+            error!("   ❌ Submission for {} failed: {}", batch.job_id, e);
+        }
+        let submitted = result.is_ok();
+        state.mempool.write().await.complete(batch, result, state.clock.now_secs());
+
+        // New on-chain step proofs may cover a milestone
+        if submitted && train_steps {
+            release_milestones(state, &job_id).await;
+        }
+    }
+
+    submissions
+}
+
+/// Drain the mempool at a fixed rate so bursts of proofs never flood the RPC
+async fn mempool_drain_loop(state: Arc<AppState>, interval: std::time::Duration, max_per_tick: usize) {
+    loop {
+        state.clock.sleep(interval).await;
+        let submitted = drain_mempool(&state, max_per_tick).await;
+        if !submitted.is_empty() {
+            let proofs: usize = submitted.iter().map(|s| s.batch_size).sum();
+            info!("⛓️  Submitted {} proofs in {} transactions", proofs, submitted.len());
+        }
+    }
+}
+
+// Milestone escrow
+
+#[derive(Debug, Deserialize)]
+pub struct OpenEscrowRequest {
+    pub job_id: String,
+    pub payer: String,    // Submitter DID funding the job
+    pub provider: String, // Assigned node paid per milestone
+    pub budget_wei: u64,
+    pub milestones: MilestonePlan,
+    #[serde(default)]
+    pub funded_by: Option<String>, // Tx that escrowed the job's cost at submission; no new lock is taken
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EscrowDisputeRequest {
+    pub disputer: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveEscrowRequest {
+    pub provider_prevails: bool,
+}
+
+/// POST /escrow - Lock a train job's budget when it is assigned. A job
+/// reassigned before anything was released moves its escrow to the new node.
+async fn open_escrow(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<OpenEscrowRequest>,
+) -> Result<Json<Escrow>, StatusCode> {
+    let mut escrows = state.escrows.write().await;
+    if let Some(existing) = escrows.get_mut(&req.job_id) {
+        let untouched = existing.status == EscrowStatus::Active
+            && existing.dispute.is_none()
+            && existing.tranches.iter().all(|t| t.state == TrancheState::Locked);
+        if !untouched {
+            return Err(StatusCode::CONFLICT);
+        }
+        info!("🔐 Escrow for {} moves to provider {}", req.job_id, req.provider);
+        existing.provider = req.provider;
+        return Ok(Json(existing.clone()));
+    }
+
+    let mut escrow = Escrow::open(&req.job_id, &req.payer, &req.provider, req.budget_wei, &req.milestones, state.clock.now_secs())
+        .map_err(|e| {
+            error!("❌ Invalid milestones for {}: {}", req.job_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    escrow.backend = state.escrow_backend.name().to_string();
+    escrow.lock_ref = match req.funded_by {
+        Some(escrow_tx) => Some(escrow_tx),
+        None => Some(state.escrow_backend.lock(&escrow).map_err(|e| {
+            error!("❌ Escrow lock failed for {}: {}", req.job_id, e);
+            StatusCode::BAD_GATEWAY
+        })?),
+    };
+    info!("🔐 Locked {} wei for {} in {} tranches ({})",
+        escrow.budget_wei, req.job_id, escrow.tranches.len(), escrow.backend);
+    escrows.insert(req.job_id.clone(), escrow);
+    drop(escrows);
+
+    // Proofs already on-chain may cover early milestones
+    release_milestones(&state, &req.job_id).await;
+    state.escrows.read().await.get(&req.job_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// GET /escrow/:job_id - Escrow with per-milestone release state
+async fn get_escrow(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> Result<Json<Escrow>, StatusCode> {
+    state.escrows.read().await.get(&job_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// POST /escrow/:job_id/cancel - Pay the provider for verified progress and
+/// refund the rest of the locked budget
+async fn cancel_escrow(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let verified = verified_step(&state, &job_id).await;
+    let (escrow, settlement) = {
+        let mut escrows = state.escrows.write().await;
+        let escrow = escrows.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        let settlement = escrow.cancel(verified).map_err(|e| {
+            error!("❌ Cannot cancel escrow for {}: {}", job_id, e);
+            StatusCode::CONFLICT
+        })?;
+        (escrow.clone(), settlement)
+    };
+    info!("🛑 Escrow for {} cancelled at step {}/{}: {} wei released, {} wei more earned, {} wei refunded",
+        job_id, escrow.verified_step, escrow.total_steps, escrow.released_wei(), settlement.provider_wei, settlement.refund_wei);
+    pay_cancellation(&state, &escrow, &settlement).await;
+
+    Ok(Json(serde_json::json!({
+        "escrow": escrow,
+        "provider_wei": settlement.provider_wei,
+        "refund_wei": settlement.refund_wei,
+    })))
+}
+
+/// POST /escrow/:job_id/dispute - Freeze the tranches not yet released
+async fn dispute_escrow(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+    Json(req): Json<EscrowDisputeRequest>,
+) -> Result<Json<Escrow>, StatusCode> {
+    let mut escrows = state.escrows.write().await;
+    let escrow = escrows.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    let frozen = escrow
+        .dispute(EscrowDispute { disputer: req.disputer.clone(), reason: req.reason, disputed_at: state.clock.now_secs() })
+        .map_err(|e| {
+            error!("❌ Cannot dispute escrow for {}: {}", job_id, e);
+            StatusCode::CONFLICT
+        })?;
+    info!("⚖️  Escrow for {} disputed by {}: {} tranches frozen", job_id, req.disputer, frozen);
+    Ok(Json(escrow.clone()))
 }
 
-fn random_hash() -> String {
-    use std::time::SystemTime;
-    let timestamp = SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_nanos();
-    format!("{:016x}", timestamp)
+/// POST /escrow/:job_id/resolve - Close a dispute, releasing or refunding
+/// the frozen tranches
+async fn resolve_escrow(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+    Json(req): Json<ResolveEscrowRequest>,
+) -> Result<Json<Escrow>, StatusCode> {
+    let (escrow, resolution) = {
+        let mut escrows = state.escrows.write().await;
+        let escrow = escrows.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        let resolution = escrow.resolve_dispute(req.provider_prevails).map_err(|_| StatusCode::CONFLICT)?;
+        (escrow.clone(), resolution)
+    };
+
+    for index in resolution.refund {
+        let memo = format!("milestone-{}", index);
+        match state.escrow_backend.refund(&escrow, escrow.tranches[index].amount_wei, &memo) {
+            Ok(tx_hash) => {
+                if let Some(escrow) = state.escrows.write().await.get_mut(&job_id) {
+                    escrow.complete_refund(index, tx_hash);
+                }
+            }
+            Err(e) => warn!("   ⚠️  Refund of {} tranche {} failed: {}", job_id, index, e),
+        }
+    }
+    pay_tranches(&state, &job_id, &resolution.claimed).await;
+    if let Some(settlement) = &resolution.cancel {
+        pay_cancellation(&state, &escrow, settlement).await;
+    }
+    state.escrows.read().await.get(&job_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Highest step of the job's train proofs that are on-chain. A batched
+/// submission commits every step under its root, so it covers its last step.
+async fn verified_step(state: &AppState, job_id: &str) -> u64 {
+    state.proofs.read().await
+        .get(job_id)
+        .into_iter()
+        .flatten()
+        .filter(|p| p.submitted && matches!(p.proof_type, ProofType::TrainStep))
+        .filter_map(|p| p.step)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Release every tranche the job's verified proofs now cover. Returns the
+/// number released.
+async fn release_milestones(state: &AppState, job_id: &str) -> usize {
+    let verified = verified_step(state, job_id).await;
+    let claimed = match state.escrows.write().await.get_mut(job_id) {
+        Some(escrow) => escrow.record_progress(verified),
+        None => return 0,
+    };
+    pay_tranches(state, job_id, &claimed).await
+}
+
+/// Release whatever is still locked once the job is finalized. None when
+/// the job has no escrow.
+async fn release_final_tranches(state: &AppState, job_id: &str) -> Option<usize> {
+    let claimed = state.escrows.write().await.get_mut(job_id)?.claim_final();
+    match claimed {
+        Ok(claimed) => Some(pay_tranches(state, job_id, &claimed).await),
+        Err(e) => {
+            warn!("   ⚠️  Escrow not released at finalize: {}", e);
+            Some(0)
+        }
+    }
+}
+
+/// Pay out tranches already claimed under the escrow lock. Failed payouts
+/// go back to locked so the next release attempt picks them up.
+async fn pay_tranches(state: &AppState, job_id: &str, claimed: &[usize]) -> usize {
+    let mut paid = 0;
+    for &index in claimed {
+        let Some(escrow) = state.escrows.read().await.get(job_id).cloned() else { break };
+        let amount_wei = escrow.tranches[index].amount_wei;
+        let memo = format!("milestone-{}", index);
+
+        match state.escrow_backend.release(&escrow, amount_wei, &memo) {
+            Ok(tx_hash) => {
+                let receipt_id = submit_escrow_receipt(&state.receipts_url, &escrow, Some(index), amount_wei, &tx_hash).await;
+                info!("   💸 Released milestone {} of {}: {} wei", index, job_id, amount_wei);
+                if let Some(escrow) = state.escrows.write().await.get_mut(job_id) {
+                    escrow.complete_release(index, tx_hash, receipt_id, state.clock.now_secs());
+                }
+                paid += 1;
+            }
+            Err(e) => {
+                warn!("   ⚠️  Release of {} milestone {} failed: {}", job_id, index, e);
+                if let Some(escrow) = state.escrows.write().await.get_mut(job_id) {
+                    escrow.abort_release(index);
+                }
+            }
+        }
+    }
+    paid
+}
+
+/// Move a cancellation's split: earned progress to the provider, the rest
+/// back to the payer
+async fn pay_cancellation(state: &AppState, escrow: &Escrow, settlement: &CancelSettlement) {
+    if settlement.provider_wei > 0 {
+        match state.escrow_backend.release(escrow, settlement.provider_wei, "cancel") {
+            Ok(tx_hash) => {
+                submit_escrow_receipt(&state.receipts_url, escrow, None, settlement.provider_wei, &tx_hash).await;
+            }
+            Err(e) => warn!("   ⚠️  Cancellation payout for {} failed: {}", escrow.job_id, e),
+        }
+    }
+    if settlement.refund_wei > 0 {
+        if let Err(e) = state.escrow_backend.refund(escrow, settlement.refund_wei, "cancel") {
+            warn!("   ⚠️  Refund for {} failed: {}", escrow.job_id, e);
+        }
+    }
+}
+
+/// Raise the provider's compute receipt for an escrow release. Returns the
+/// receipt id, or None if receipts-daemon could not be reached.
+async fn submit_escrow_receipt(
+    receipts_url: &str,
+    escrow: &Escrow,
+    milestone: Option<usize>,
+    amount_wei: u64,
+    release_tx: &str,
+) -> Option<String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/receipt/milestone", receipts_url))
+        .json(&serde_json::json!({
+            "job_id": escrow.job_id,
+            "provider": escrow.provider,
+            "payer": escrow.payer,
+            "milestone": milestone,
+            "amount_wei": amount_wei,
+            "release_tx": release_tx,
+        }))
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) if resp.status().is_success() => resp
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|receipt| receipt["receipt_id"].as_str().map(|id| id.to_string())),
+        _ => {
+            warn!("   ⚠️  No receipt raised for {} release {}", escrow.job_id, release_tx);
+            None
+        }
+    }
 }
 
 // Auto-submission daemon (monitors jobs and submits proofs automatically)
 
 async fn auto_submit_daemon(state: Arc<AppState>) {
-    println!("🤖 Auto-submission daemon started");
+    info!("🤖 Auto-submission daemon started");
+    let policy = BackoffPolicy::from_env("ARTHA_PROOF_AUTO_SUBMIT", std::time::Duration::from_secs(30));
+    let backoff = Backoff::new(policy, state.entropy.clone());
     
     loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        state.clock.sleep(backoff.delay()).await;
         
         // In production: query ai-runtime for running jobs
-        // For each job with new training steps, submit proofs automatically
+        // For each job with new training steps, submit proofs automatically,
+        // through submit_proof so the job's proof sampling interval applies
         
-        println!("🔍 Checking for jobs needing proof submission...");
+        info!("🔍 Checking for jobs needing proof submission...");
         
         // This would query ai-runtime's /jobs endpoint
         // and submit proofs for any new checkpoints
@@ -389,13 +1620,69 @@ async fn auto_submit_daemon(state: Arc<AppState>) {
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let node_pubkey = std::env::var("NODE_PUBKEY")
         .unwrap_or_else(|_| "0xnode123abc456def".to_string());
     
+    let entropy = artha_clock::system_entropy();
+    let rpc = RpcEndpoints::from_env();
+    let contracts = ContractRegistry::resolve(&rpc, CONTRACTS)
+        .await
+        .unwrap_or_else(|e| panic!("Invalid contract config: {}", e));
+    let allow_simulated_tee = matches!(std::env::var("ARTHA_TEE_ALLOW_SIMULATED").as_deref(), Ok("1" | "true"));
+    if allow_simulated_tee {
+        warn!("⚠️  Accepting simulated TEE quotes; never enable ARTHA_TEE_ALLOW_SIMULATED in production");
+    }
+    let proof_data_dir = std::env::var("ARTHA_PROOF_DATA_DIR").unwrap_or_else(|_| "/tmp/artha/proofs/jobs".to_string());
     let state = Arc::new(AppState {
         proofs: Arc::new(RwLock::new(HashMap::new())),
-        contract_client: Arc::new(ContractClient::new("http://localhost:8545".to_string())),
+        attestation_nonces: Arc::new(RwLock::new(HashMap::new())),
+        attestations: Arc::new(RwLock::new(HashMap::new())),
+        tee_trust_roots: load_tee_trust_roots(),
+        allow_simulated_tee,
+        jobd_url: std::env::var("ARTHA_JOBD_URL").unwrap_or_else(|_| "http://localhost:8081".to_string()),
+        contract_client: Arc::new(ContractClient::new(rpc, entropy.clone()).with_contracts(&contracts)),
         node_pubkey: node_pubkey.clone(),
+        mempool: Arc::new(RwLock::new(ProofMempool::new(
+            env_or("ARTHA_PROOF_MAX_BATCH", 16),
+        ))),
+        accepted: Arc::new(RwLock::new(HashSet::new())),
+        proof_store: Arc::new(FileProofStore::open(&proof_data_dir).unwrap_or_else(|e| panic!("{}", e))),
+        nonces: Arc::new(RwLock::new(NonceManager::new(env_or("ARTHA_PROOF_START_NONCE", 0)))),
+        archived: Arc::new(RwLock::new(HashMap::new())),
+        retention: RetentionPolicy::from_env("ARTHA_PROOF"),
+        escrows: Arc::new(RwLock::new(HashMap::new())),
+        finalizations: Arc::new(RwLock::new(HashMap::new())),
+        step_logs: Arc::new(RwLock::new(HashMap::new())),
+        escrow_backend: Arc::from(escrow::backend_from_env()),
+        receipts_url: std::env::var("ARTHA_RECEIPTS_URL")
+            .unwrap_or_else(|_| "http://localhost:8092".to_string()),
+        clock: artha_clock::system_clock(),
+        entropy,
+    });
+    let restored = restore_proofs(&state).await.unwrap_or_else(|e| panic!("Failed to restore proofs: {}", e));
+    info!("💾 Restored proofs for {} jobs from {}", restored, proof_data_dir);
+
+    // Drain queued proofs at a controlled rate
+    let interval = std::time::Duration::from_millis(env_or("ARTHA_PROOF_DRAIN_INTERVAL_MS", 500));
+    let max_per_tick = env_or("ARTHA_PROOF_DRAIN_MAX_TX", 4);
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        mempool_drain_loop(state_clone, interval, max_per_tick).await;
+    });
+
+    // Evict finished jobs' proofs past the retention policy
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let interval = std::time::Duration::from_secs(state_clone.retention.interval_secs);
+        let backoff = Backoff::new(BackoffPolicy::every(interval), state_clone.entropy.clone());
+        loop {
+            state_clone.clock.sleep(backoff.delay()).await;
+            let evicted = gc_finished_proofs(&state_clone, state_clone.clock.now_secs()).await;
+            if evicted > 0 {
+                info!("🧹 Retention GC archived proofs for {} jobs", evicted);
+            }
+        }
     });
 
     // Start auto-submission daemon in background
@@ -408,13 +1695,27 @@ async fn main() {
         .route("/proof/submit", post(submit_proof))
         .route("/finalize", post(finalize_job))
         .route("/proofs/:job_id", axum::routing::get(get_job_proofs))
+        .route("/proofs/:job_id/archive", axum::routing::get(get_archived_proofs))
+        .route("/proofs/:job_id/export", axum::routing::get(export_job_proofs))
+        .route("/proofs/:job_id/steps/:step/inclusion", axum::routing::get(get_step_inclusion))
+        .route("/attestation/nonce", post(issue_attestation_nonce))
+        .route("/attestation/:job_id", axum::routing::get(get_attestation))
         .route("/stats", axum::routing::get(get_stats))
+        .route("/dlq", axum::routing::get(list_dead_letters))
+        .route("/dlq/:id/replay", post(replay_dead_letter))
+        .route("/escrow", post(open_escrow)) // Called by ai-jobd on assignment
+        .route("/escrow/:job_id", axum::routing::get(get_escrow))
+        .route("/escrow/:job_id/cancel", post(cancel_escrow))
+        .route("/escrow/:job_id/dispute", post(dispute_escrow))
+        .route("/escrow/:job_id/resolve", post(resolve_escrow))
         .route("/health", axum::routing::get(|| async { "OK" }))
-        .with_state(state);
+        .layer(axum::middleware::map_response(artha_errors::normalize))
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 AI Proofs Service starting on :8085");
-    println!("   Node Pubkey: {}", node_pubkey);
-    println!("   Monitoring jobs and submitting compute proofs");
+    info!("🚀 AI Proofs Service starting on :8085");
+    info!("   Node Pubkey: {}", node_pubkey);
+    info!("   Monitoring jobs and submitting compute proofs");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8085").await.unwrap();
     axum::serve(listener, app).await.unwrap();
@@ -423,6 +1724,8 @@ async fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use artha_clock::{ManualClock, SeededEntropy};
+    use proof_store::MemoryProofStore;
 
     #[test]
     fn test_compute_digest() {
@@ -442,10 +1745,811 @@ mod tests {
             timestamp: 0,
             submitted: true,
             tx_hash: Some("0x123".to_string()),
+            attestation: None,
         };
         
         assert_eq!(proof.step, Some(1));
         assert!(proof.submitted);
     }
-}
 
+    fn simulated_quote(job_id: &str, nonce: &str, image_digest: &str) -> AttestationQuote {
+        let report_data = compute_report_data(image_digest, job_id, nonce);
+        AttestationQuote {
+            tee_type: "Simulated".to_string(),
+            measurement: "0xmeasure".to_string(),
+            image_digest: image_digest.to_string(),
+            job_id: job_id.to_string(),
+            nonce: nonce.to_string(),
+            signature: simulated_quote_signature("0xmeasure", &report_data),
+            report_data,
+            raw_quote: "SGX_QUOTE:sim-enclave:MRENCLAVE:0xmeasure".to_string(),
+            cert_chain: Vec::new(),
+        }
+    }
+
+    fn trust_roots() -> Vec<TeeTrustRoot> {
+        vec![TeeTrustRoot {
+            tee_type: "Simulated".to_string(),
+            measurements: vec!["0xmeasure".to_string()],
+            image_digests: vec!["sha256:good".to_string()],
+            root_keys: Vec::new(),
+        }]
+    }
+
+    fn tee_key(seed: u8) -> k256::ecdsa::SigningKey {
+        k256::ecdsa::SigningKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn public_hex(key: &k256::ecdsa::SigningKey) -> String {
+        hex::encode(key.verifying_key().to_sec1_bytes())
+    }
+
+    fn sign_hex(key: &k256::ecdsa::SigningKey, message: &[u8]) -> String {
+        use k256::ecdsa::signature::Signer;
+        let signature: k256::ecdsa::Signature = key.sign(message);
+        hex::encode(signature.to_bytes())
+    }
+
+    /// `key` certified by `issuer` for SGX
+    fn sgx_cert(key: &k256::ecdsa::SigningKey, issuer: &k256::ecdsa::SigningKey) -> AttestationCert {
+        let public_key = key.verifying_key().to_sec1_bytes();
+        AttestationCert {
+            public_key: hex::encode(&public_key),
+            signature: sign_hex(issuer, &attestation::cert_message("Sgx", &public_key)),
+        }
+    }
+
+    /// A quote signed by an attestation key under a PCK-style chain to `root`
+    fn sgx_quote(root: &k256::ecdsa::SigningKey) -> AttestationQuote {
+        let (intermediate, leaf) = (tee_key(2), tee_key(3));
+        let mut quote = simulated_quote("job-1", "nonce-1", "sha256:good");
+        quote.tee_type = "Sgx".to_string();
+        quote.signature = sign_hex(&leaf, &attestation::quote_message(&quote.raw_quote, &quote.report_data));
+        quote.cert_chain = vec![sgx_cert(&leaf, &intermediate), sgx_cert(&intermediate, root)];
+        quote
+    }
+
+    fn sgx_roots(root: &k256::ecdsa::SigningKey) -> Vec<TeeTrustRoot> {
+        vec![TeeTrustRoot {
+            tee_type: "Sgx".to_string(),
+            measurements: vec!["0xmeasure".to_string()],
+            image_digests: Vec::new(),
+            root_keys: vec![public_hex(root)],
+        }]
+    }
+
+    #[test]
+    fn test_attestation_chains_lead_to_a_configured_root() {
+        let root = tee_key(1);
+        let quote = sgx_quote(&root);
+        let record = verify_attestation(&quote, "job-1", "nonce-1", &sgx_roots(&root), false, 0);
+        assert_eq!(record.status, AttestationStatus::Verified);
+
+        // A chain to some other root
+        let record = verify_attestation(&sgx_quote(&tee_key(9)), "job-1", "nonce-1", &sgx_roots(&root), false, 0);
+        assert_eq!(record.reason.as_deref(), Some("Certificate chain does not lead to a trusted root"));
+
+        // An intermediate the root never certified
+        let mut forged = quote.clone();
+        forged.cert_chain[1] = sgx_cert(&tee_key(2), &tee_key(9));
+        let record = verify_attestation(&forged, "job-1", "nonce-1", &sgx_roots(&root), false, 0);
+        assert_eq!(record.status, AttestationStatus::Rejected);
+
+        // A quote signed by a key other than the chain's leaf
+        let mut resigned = quote.clone();
+        resigned.signature = sign_hex(&tee_key(4), &attestation::quote_message(&quote.raw_quote, &quote.report_data));
+        let record = verify_attestation(&resigned, "job-1", "nonce-1", &sgx_roots(&root), false, 0);
+        assert_eq!(record.reason.as_deref(), Some("Invalid quote signature"));
+
+        // Simulated quotes only pass where they are allowed
+        let simulated = simulated_quote("job-1", "nonce-1", "sha256:good");
+        let record = verify_attestation(&simulated, "job-1", "nonce-1", &trust_roots(), false, 0);
+        assert_eq!(record.reason.as_deref(), Some("Quote has no certificate chain"));
+    }
+
+    #[test]
+    fn test_attestation_quote_binding() {
+        let quote = simulated_quote("job-1", "nonce-1", "sha256:good");
+        let record = verify_attestation(&quote, "job-1", "nonce-1", &trust_roots(), true, 0);
+        assert_eq!(record.status, AttestationStatus::Verified);
+
+        // Wrong nonce
+        let record = verify_attestation(&quote, "job-1", "nonce-2", &trust_roots(), true, 0);
+        assert_eq!(record.status, AttestationStatus::Rejected);
+
+        // Digest swapped after the quote was produced
+        let mut tampered = quote.clone();
+        tampered.image_digest = "sha256:other".to_string();
+        let record = verify_attestation(&tampered, "job-1", "nonce-1", &trust_roots(), true, 0);
+        assert_eq!(record.status, AttestationStatus::Rejected);
+
+        // Correctly bound but untrusted image
+        let untrusted = simulated_quote("job-1", "nonce-1", "sha256:other");
+        let record = verify_attestation(&untrusted, "job-1", "nonce-1", &trust_roots(), true, 0);
+        assert_eq!(record.status, AttestationStatus::Rejected);
+    }
+
+    #[test]
+    fn test_tee_payout_requires_attestation() {
+        assert!(check_tee_payout(false, None).is_ok());
+        assert!(check_tee_payout(true, None).is_err());
+
+        let quote = simulated_quote("job-1", "nonce-1", "sha256:good");
+        let verified = verify_attestation(&quote, "job-1", "nonce-1", &trust_roots(), true, 0);
+        assert!(check_tee_payout(true, Some(&verified)).is_ok());
+
+        let rejected = verify_attestation(&quote, "job-1", "bad", &trust_roots(), true, 0);
+        assert!(check_tee_payout(true, Some(&rejected)).is_err());
+    }
+
+    /// An ai-jobd whose job records ask for TEE execution for `tee_jobs` only
+    async fn mock_jobd(tee_jobs: &[&str]) -> String {
+        let tee_jobs: HashSet<String> = tee_jobs.iter().map(|job| job.to_string()).collect();
+        let app = Router::new().route(
+            "/job/:id/status",
+            axum::routing::get(move |axum::extract::Path(job_id): axum::extract::Path<String>| {
+                let tee_required = tee_jobs.contains(&job_id);
+                async move { Json(serde_json::json!({ "job": { "job_id": job_id, "tee_required": tee_required } })) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    async fn test_state() -> Arc<AppState> {
+        test_state_with(Arc::new(escrow::InternalEscrow)).await
+    }
+
+    async fn test_state_with(escrow_backend: Arc<dyn EscrowBackend>) -> Arc<AppState> {
+        test_state_on(escrow_backend, ManualClock::new(1_700_000_000)).await
+    }
+
+    async fn test_state_on(escrow_backend: Arc<dyn EscrowBackend>, clock: ManualClock) -> Arc<AppState> {
+        let entropy = SeededEntropy::shared(7);
+        let rpc = abi::DryRunRpc::spawn().await;
+        Arc::new(AppState {
+            proofs: Arc::new(RwLock::new(HashMap::new())),
+            attestation_nonces: Arc::new(RwLock::new(HashMap::new())),
+            attestations: Arc::new(RwLock::new(HashMap::new())),
+            tee_trust_roots: Vec::new(),
+            allow_simulated_tee: true,
+            jobd_url: mock_jobd(&[]).await,
+            contract_client: Arc::new(ContractClient::new(rpc.url(), entropy.clone())),
+            node_pubkey: "0xnode".to_string(),
+            mempool: Arc::new(RwLock::new(ProofMempool::new(16))),
+            accepted: Arc::new(RwLock::new(HashSet::new())),
+            proof_store: Arc::new(MemoryProofStore::default()),
+            nonces: Arc::new(RwLock::new(NonceManager::new(7))),
+            archived: Arc::new(RwLock::new(HashMap::new())),
+            retention: RetentionPolicy { max_age_secs: 3600, max_count: 100, interval_secs: 60, archive_path: None },
+            escrows: Arc::new(RwLock::new(HashMap::new())),
+            finalizations: Arc::new(RwLock::new(HashMap::new())),
+            step_logs: Arc::new(RwLock::new(HashMap::new())),
+            escrow_backend,
+            receipts_url: "http://127.0.0.1:9".to_string(),
+            clock: clock.shared(),
+            entropy,
+        })
+    }
+
+    fn step_request(job_id: &str, step: u64) -> SubmitProofRequest {
+        SubmitProofRequest {
+            job_id: job_id.to_string(),
+            proof_type: ProofType::TrainStep,
+            step: Some(step),
+            loss: Some(0.5),
+            gradients: Some(vec![0.1, 0.2]),
+            weights: Some(vec![1.0, 2.0]),
+            output_cid: None,
+            attestation: None,
+            proof_sampling_interval: None,
+            final_step: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_step_submitted_once() {
+        let state = test_state().await;
+
+        let Json(first) = submit_proof(State(state.clone()), Json(step_request("job-1", 1))).await.unwrap();
+        let retry = submit_proof(State(state.clone()), Json(step_request("job-1", 1))).await;
+        assert_eq!(first.status, "queued");
+        assert_eq!(retry.unwrap_err(), StatusCode::CONFLICT);
+
+        let submissions = drain_mempool(&state, 10).await;
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0].nonce, 7);
+
+        // A retry after submission is turned away too
+        let late = submit_proof(State(state.clone()), Json(step_request("job-1", 1))).await;
+        assert_eq!(late.unwrap_err(), StatusCode::CONFLICT);
+        assert!(drain_mempool(&state, 10).await.is_empty());
+
+        let proofs = state.proofs.read().await;
+        assert_eq!(proofs["job-1"].len(), 1);
+        assert!(proofs["job-1"][0].submitted);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_proof_rejected_while_new_steps_are_accepted() {
+        let state = test_state().await;
+        for step in 1..=2 {
+            let Json(accepted) = submit_proof(State(state.clone()), Json(step_request("job-r", step))).await.unwrap();
+            assert_eq!(accepted.status, "queued");
+        }
+        drain_mempool(&state, 10).await;
+
+        // Replaying a captured step adds nothing; the next step goes through
+        assert_eq!(submit_proof(State(state.clone()), Json(step_request("job-r", 1))).await.unwrap_err(), StatusCode::CONFLICT);
+        let Json(next) = submit_proof(State(state.clone()), Json(step_request("job-r", 3))).await.unwrap();
+        assert_eq!(next.proof_id, "job-r-step-3");
+        assert_eq!(state.proofs.read().await["job-r"].len(), 3);
+
+        // The same step of another job is its own nonce
+        assert!(submit_proof(State(state.clone()), Json(step_request("job-s", 1))).await.is_ok());
+
+        // One completion per infer job
+        let infer = || SubmitProofRequest {
+            proof_type: ProofType::InferComplete,
+            step: None,
+            output_cid: Some("artha://QmOutput".to_string()),
+            ..step_request("job-i", 0)
+        };
+        assert!(submit_proof(State(state.clone()), Json(infer())).await.is_ok());
+        assert_eq!(submit_proof(State(state.clone()), Json(infer())).await.unwrap_err(), StatusCode::CONFLICT);
+
+        // Evicting a finished job's proofs doesn't reopen its steps
+        state.proofs.write().await.remove("job-r");
+        assert_eq!(submit_proof(State(state.clone()), Json(step_request("job-r", 2))).await.unwrap_err(), StatusCode::CONFLICT);
+    }
+
+    async fn state_on_disk(data_dir: &str) -> Arc<AppState> {
+        let mut state = test_state().await;
+        Arc::get_mut(&mut state).unwrap().proof_store = Arc::new(FileProofStore::open(data_dir).unwrap());
+        restore_proofs(&state).await.unwrap();
+        state
+    }
+
+    #[tokio::test]
+    async fn test_accepted_proofs_survive_a_restart_until_finalized() {
+        let data_dir = std::env::temp_dir().join(format!("ai-proofs-store-{}", std::process::id()));
+        let data_dir = data_dir.to_str().unwrap().to_string();
+        let finalize = |state: &Arc<AppState>| finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: "job-p".to_string(), optimistic: false }),
+        );
+
+        let state = state_on_disk(&data_dir).await;
+        for step in 1..=2 {
+            assert!(submit_proof(State(state.clone()), Json(step_request("job-p", step))).await.is_ok());
+        }
+        drain_mempool(&state, 10).await;
+        drop(state);
+
+        // A restarted service still turns replays away and knows what went on-chain
+        let state = state_on_disk(&data_dir).await;
+        assert_eq!(submit_proof(State(state.clone()), Json(step_request("job-p", 1))).await.unwrap_err(), StatusCode::CONFLICT);
+        assert!(state.proofs.read().await["job-p"].iter().all(|p| p.submitted));
+        assert!(submit_proof(State(state.clone()), Json(step_request("job-p", 3))).await.is_ok());
+
+        // Finalizing drops the job's keys; its finalization turns replays away instead
+        let finalizing = tokio::spawn(finalize(&state));
+        while state.mempool.read().await.len() < 2 {
+            tokio::task::yield_now().await;
+        }
+        drain_mempool(&state, 10).await;
+        let Json(finalized) = finalizing.await.unwrap().unwrap();
+        assert!(state.accepted.read().await.is_empty());
+        assert_eq!(submit_proof(State(state.clone()), Json(step_request("job-p", 4))).await.unwrap_err(), StatusCode::CONFLICT);
+        drop(state);
+
+        // Nor do the keys come back from disk
+        let state = state_on_disk(&data_dir).await;
+        assert!(state.accepted.read().await.is_empty());
+        assert_eq!(state.proofs.read().await["job-p"].len(), 3);
+        assert_eq!(submit_proof(State(state.clone()), Json(step_request("job-p", 2))).await.unwrap_err(), StatusCode::CONFLICT);
+        assert_eq!(finalize(&state).await.unwrap().0, finalized);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_finalize_drains_ahead_of_queued_steps() {
+        let state = test_state().await;
+        for step in 1..=3 {
+            let _ = submit_proof(State(state.clone()), Json(step_request("job-a", step))).await.unwrap();
+        }
+        let _ = submit_proof(State(state.clone()), Json(step_request("job-b", 1))).await.unwrap();
+
+        let finalize = tokio::spawn(finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: "job-b".to_string(), optimistic: false }),
+        ));
+        while state.mempool.read().await.len() < 5 {
+            tokio::task::yield_now().await;
+        }
+
+        // One submission per tick: the finalize goes first
+        let first = drain_mempool(&state, 1).await;
+        assert_eq!(first.len(), 1);
+        assert!(first[0].payout.is_some());
+        let Json(finalized) = finalize.await.unwrap().unwrap();
+        assert_eq!(finalized["tx_hash"], first[0].tx_hash);
+
+        // Then job-a's steps coalesce into one batch, then job-b's step
+        let rest = drain_mempool(&state, 10).await;
+        let batches: Vec<(usize, u64)> = rest.iter().map(|s| (s.batch_size, s.nonce)).collect();
+        assert_eq!(batches, vec![(3, 8), (1, 9)]);
+        assert!(state.proofs.read().await["job-a"].iter().all(|p| p.submitted));
+    }
+
+    #[tokio::test]
+    async fn test_optimistic_finalize_reports_mode() {
+        let state = test_state().await;
+        let _ = submit_proof(State(state.clone()), Json(step_request("job-o", 1))).await.unwrap();
+
+        let finalize = tokio::spawn(finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: "job-o".to_string(), optimistic: true }),
+        ));
+        while state.mempool.read().await.len() < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        let first = drain_mempool(&state, 1).await;
+        let Json(finalized) = finalize.await.unwrap().unwrap();
+        assert_eq!(finalized["tx_hash"], first[0].tx_hash);
+        assert_eq!(finalized["mode"], "optimistic");
+        assert_eq!(finalized["payout"], 10_000_000_000_000_000u64);
+    }
+
+    #[tokio::test]
+    async fn test_finalize_takes_tee_required_from_the_job_record() {
+        let mut state = test_state().await;
+        Arc::get_mut(&mut state).unwrap().jobd_url = mock_jobd(&["job-t"]).await;
+        let _ = submit_proof(State(state.clone()), Json(step_request("job-t", 1))).await.unwrap();
+
+        // ai-jobd says the job is a TEE job, and it has no attestation
+        let finalize = |job_id: &str| finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: job_id.to_string(), optimistic: false }),
+        );
+        assert_eq!(finalize("job-t").await.unwrap_err(), StatusCode::PRECONDITION_FAILED);
+        assert!(state.mempool.read().await.len() == 1, "nothing but the step is queued");
+
+        // Without a job record there is no telling, so nothing is paid
+        let mut state = test_state().await;
+        Arc::get_mut(&mut state).unwrap().jobd_url = "http://127.0.0.1:9".to_string();
+        let _ = submit_proof(State(state.clone()), Json(step_request("job-u", 1))).await.unwrap();
+        let unreachable = finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: "job-u".to_string(), optimistic: false }),
+        );
+        assert_eq!(unreachable.await.unwrap_err(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_sampled_job_proves_every_kth_step_and_commits_all_steps() {
+        let state = test_state().await;
+        for step in 1..=25 {
+            let req = SubmitProofRequest {
+                proof_sampling_interval: Some(10),
+                final_step: step == 25,
+                ..step_request("job-k", step)
+            };
+            let Json(submitted) = submit_proof(State(state.clone()), Json(req)).await.unwrap();
+            let expected = if step % 10 == 0 || step == 25 { "queued" } else { "logged" };
+            assert_eq!(submitted.status, expected, "step {}", step);
+        }
+        // Unsampled steps are replay-protected too
+        assert_eq!(submit_proof(State(state.clone()), Json(step_request("job-k", 7))).await.unwrap_err(), StatusCode::CONFLICT);
+
+        let proven: Vec<u64> = state.proofs.read().await["job-k"].iter().filter_map(|p| p.step).collect();
+        assert_eq!(proven, vec![10, 20, 25]);
+        assert_eq!(drain_mempool(&state, 10).await.len(), 1); // The three sampled steps, batched
+
+        let finalize = tokio::spawn(finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: "job-k".to_string(), optimistic: false }),
+        ));
+        while state.mempool.read().await.len() < 1 {
+            tokio::task::yield_now().await;
+        }
+        drain_mempool(&state, 1).await;
+        let Json(finalized) = finalize.await.unwrap().unwrap();
+        assert_eq!(finalized["proof_count"], 25);
+
+        // The committed root covers all 25 steps, each provable by inclusion
+        let (loss, gradients, weights) = (compute_digest(&[0.5]), compute_digest(&[0.1, 0.2]), compute_digest(&[1.0, 2.0]));
+        let mut all_steps = StepLog::new(10);
+        for step in 1..=25 {
+            all_steps.record(step, step_leaf(step, &loss, &gradients, &weights));
+        }
+        let root = all_steps.root().unwrap();
+        assert_eq!(finalized["steps_root"], root);
+        for step in [1, 7, 10, 24, 25] {
+            let Json(inclusion) = get_step_inclusion(State(state.clone()), axum::extract::Path(("job-k".to_string(), step))).await.unwrap();
+            assert_eq!(inclusion.root, root);
+            assert!(inclusion.verify(), "step {}", step);
+        }
+        let Json(mut forged) = get_step_inclusion(State(state.clone()), axum::extract::Path(("job-k".to_string(), 7))).await.unwrap();
+        forged.leaf = step_leaf(7, &loss, &gradients, &compute_digest(&[9.0]));
+        assert!(!forged.verify());
+        assert!(get_step_inclusion(State(state.clone()), axum::extract::Path(("job-k".to_string(), 26))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_drain_loop_submits_at_its_configured_rate() {
+        let clock = ManualClock::new(1_700_000_000);
+        let state = test_state_on(Arc::new(escrow::InternalEscrow), clock.clone()).await;
+        for job in ["job-r1", "job-r2", "job-r3"] {
+            let _ = submit_proof(State(state.clone()), Json(step_request(job, 1))).await.unwrap();
+        }
+        let submitted = |state: Arc<AppState>| async move {
+            state.proofs.read().await.values().flatten().filter(|p| p.submitted).count()
+        };
+
+        // One transaction per 500ms tick; nothing goes out between ticks
+        let drain = tokio::spawn(mempool_drain_loop(state.clone(), std::time::Duration::from_millis(500), 1));
+        clock.wait_for_sleepers(1).await;
+        for expected in 1..=3 {
+            clock.advance(std::time::Duration::from_millis(499));
+            tokio::task::yield_now().await;
+            assert_eq!(submitted(state.clone()).await, expected - 1);
+            clock.tick(std::time::Duration::from_millis(1)).await;
+            assert_eq!(submitted(state.clone()).await, expected);
+        }
+        assert_eq!(state.mempool.read().await.len(), 0);
+        drain.abort();
+    }
+
+    #[test]
+    fn test_nonce_manager_reuses_failed_nonces() {
+        let mut nonces = NonceManager::new(5);
+        assert_eq!((nonces.reserve(), nonces.reserve(), nonces.reserve()), (5, 6, 7));
+
+        nonces.release(6);
+        assert_eq!(nonces.reserve(), 6);
+        nonces.release(7);
+        assert_eq!(nonces.reserve(), 7);
+        assert_eq!(nonces.reserve(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_failed_submission_lands_in_dlq_and_replays() {
+        let state = test_state().await;
+        let _ = submit_proof(State(state.clone()), Json(step_request("job-d", 1))).await.unwrap();
+        let batch = state.mempool.write().await.next_batch().unwrap();
+        state.mempool.write().await.complete(batch, Err("rpc unavailable".to_string()), state.clock.now_secs());
+
+        let Json(dead) = list_dead_letters(State(state.clone())).await;
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].key.job_id, "job-d");
+        assert_eq!(dead[0].error, "rpc unavailable");
+        assert_eq!(dead[0].failed_at, 1_700_000_000);
+        assert_eq!(state.mempool.read().await.len(), 0);
+
+        let Json(replayed) = replay_dead_letter(State(state.clone()), axum::extract::Path(dead[0].id)).await.unwrap();
+        assert_eq!(replayed["status"], "queued");
+        assert!(list_dead_letters(State(state.clone())).await.0.is_empty());
+        assert_eq!(
+            replay_dead_letter(State(state.clone()), axum::extract::Path(dead[0].id)).await.unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+
+        let submissions = drain_mempool(&state, 10).await;
+        assert_eq!(submissions.len(), 1);
+        assert!(state.proofs.read().await["job-d"][0].submitted);
+    }
+
+    /// Escrow backend that records every payout it makes
+    #[derive(Default)]
+    struct RecordingEscrow {
+        releases: std::sync::Mutex<Vec<(String, u64)>>, // (memo, amount)
+        refunds: std::sync::Mutex<Vec<(String, u64)>>,
+    }
+
+    impl EscrowBackend for RecordingEscrow {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn lock(&self, escrow: &Escrow) -> Result<String, String> {
+            Ok(format!("lock-{}", escrow.job_id))
+        }
+
+        fn release(&self, _escrow: &Escrow, amount_wei: u64, memo: &str) -> Result<String, String> {
+            self.releases.lock().unwrap().push((memo.to_string(), amount_wei));
+            Ok(format!("release-{}", memo))
+        }
+
+        fn refund(&self, _escrow: &Escrow, amount_wei: u64, memo: &str) -> Result<String, String> {
+            self.refunds.lock().unwrap().push((memo.to_string(), amount_wei));
+            Ok(format!("refund-{}", memo))
+        }
+    }
+
+    /// State with a 1000 wei escrow over 100 steps at the default milestones
+    async fn escrow_state(job_id: &str) -> (Arc<AppState>, Arc<RecordingEscrow>) {
+        let backend = Arc::new(RecordingEscrow::default());
+        let state = test_state_with(backend.clone()).await;
+        let Json(escrow) = open_escrow(State(state.clone()), Json(OpenEscrowRequest {
+            job_id: job_id.to_string(),
+            payer: "did:artha:payer".to_string(),
+            provider: "0xprovider".to_string(),
+            budget_wei: 1_000,
+            milestones: MilestonePlan { total_steps: 100, ..Default::default() },
+            funded_by: None,
+        })).await.unwrap();
+        assert_eq!(escrow.lock_ref.as_deref(), Some(&*format!("lock-{}", job_id)));
+        (state, backend)
+    }
+
+    async fn prove_steps(state: &Arc<AppState>, job_id: &str, steps: impl IntoIterator<Item = u64>) {
+        for step in steps {
+            let _ = submit_proof(State(state.clone()), Json(step_request(job_id, step))).await.unwrap();
+        }
+        drain_mempool(state, 10).await;
+    }
+
+    fn tranche_states(escrow: &Escrow) -> Vec<&'static str> {
+        escrow.tranches.iter().map(|t| match t.state {
+            TrancheState::Locked => "locked",
+            TrancheState::Releasing => "releasing",
+            TrancheState::Released { .. } => "released",
+            TrancheState::Frozen => "frozen",
+            TrancheState::Refunded { .. } => "refunded",
+            TrancheState::Cancelled { .. } => "cancelled",
+        }).collect()
+    }
+
+    #[tokio::test]
+    async fn test_escrow_tranches_follow_default_milestones() {
+        // Uneven budget and step count: tranches round down per cumulative
+        // share and still sum to the budget
+        let escrow = Escrow::open("job", "payer", "node", 1_000_003, &MilestonePlan { total_steps: 10, ..Default::default() }, 0).unwrap();
+        let split: Vec<(u64, u64)> = escrow.tranches.iter().map(|t| (t.step, t.amount_wei)).collect();
+        assert_eq!(split, vec![(3, 300_000), (5, 200_001), (8, 300_001), (10, 200_001)]);
+        assert_eq!(escrow.tranches.iter().map(|t| t.amount_wei).sum::<u64>(), 1_000_003);
+
+        // Explicit steps short of the end leave a final tranche for finalize
+        let plan = MilestonePlan { total_steps: 10, steps: vec![4, 2], fractions: Vec::new() };
+        let escrow = Escrow::open("job", "payer", "node", 100, &plan, 0).unwrap();
+        let split: Vec<(u64, u64)> = escrow.tranches.iter().map(|t| (t.step, t.amount_wei)).collect();
+        assert_eq!(split, vec![(2, 20), (4, 20), (10, 60)]);
+        assert!(Escrow::open("job", "payer", "node", 100, &MilestonePlan { total_steps: 10, fractions: vec![1.5], steps: Vec::new() }, 0).is_err());
+
+        // Released as on-chain step proofs cross each milestone
+        let (state, backend) = escrow_state("job-m").await;
+        prove_steps(&state, "job-m", 1..=24).await;
+        assert!(backend.releases.lock().unwrap().is_empty());
+        prove_steps(&state, "job-m", 25..=60).await;
+        assert_eq!(*backend.releases.lock().unwrap(), vec![
+            ("milestone-0".to_string(), 250),
+            ("milestone-1".to_string(), 250),
+        ]);
+        let Json(escrow) = get_escrow(State(state.clone()), axum::extract::Path("job-m".to_string())).await.unwrap();
+        assert_eq!(escrow.verified_step, 60);
+        assert_eq!(tranche_states(&escrow), vec!["released", "released", "locked", "locked"]);
+        assert_eq!(escrow.released_wei(), 500);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_run_refunds_unearned_remainder() {
+        let (state, backend) = escrow_state("job-c").await;
+        prove_steps(&state, "job-c", 1..=60).await;
+
+        let Json(cancelled) = cancel_escrow(State(state.clone()), axum::extract::Path("job-c".to_string())).await.unwrap();
+        // 60% verified: 500 already released, 100 more earned, 400 back to the payer
+        assert_eq!(cancelled["provider_wei"], 100);
+        assert_eq!(cancelled["refund_wei"], 400);
+        let escrow = state.escrows.read().await["job-c"].clone();
+        assert_eq!(escrow.status, EscrowStatus::Cancelled);
+        assert_eq!(escrow.tranches[2].state, TrancheState::Cancelled { earned_wei: 100, refunded_wei: 150 });
+        assert_eq!(escrow.tranches[3].state, TrancheState::Cancelled { earned_wei: 0, refunded_wei: 250 });
+        assert_eq!(backend.releases.lock().unwrap().iter().map(|(_, a)| a).sum::<u64>(), 600);
+        assert_eq!(*backend.refunds.lock().unwrap(), vec![("cancel".to_string(), 400)]);
+
+        // Proofs arriving after cancellation release nothing
+        prove_steps(&state, "job-c", 61..=80).await;
+        assert_eq!(backend.releases.lock().unwrap().len(), 3);
+        assert_eq!(
+            cancel_escrow(State(state.clone()), axum::extract::Path("job-c".to_string())).await.unwrap_err(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dispute_freezes_only_future_tranches() {
+        let (state, backend) = escrow_state("job-d").await;
+        prove_steps(&state, "job-d", 1..=30).await;
+
+        let Json(disputed) = dispute_escrow(
+            State(state.clone()),
+            axum::extract::Path("job-d".to_string()),
+            Json(EscrowDisputeRequest { disputer: "did:artha:payer".to_string(), reason: "diverging loss".to_string() }),
+        ).await.unwrap();
+        assert_eq!(tranche_states(&disputed), vec!["released", "frozen", "frozen", "frozen"]);
+
+        // Progress while disputed releases nothing
+        prove_steps(&state, "job-d", 31..=80).await;
+        let escrow = state.escrows.read().await["job-d"].clone();
+        assert_eq!(tranche_states(&escrow), vec!["released", "frozen", "frozen", "frozen"]);
+        assert_eq!(escrow.verified_step, 80);
+        assert_eq!(backend.releases.lock().unwrap().len(), 1);
+
+        // The provider prevails: the milestones verified meanwhile release
+        let Json(resolved) = resolve_escrow(
+            State(state.clone()),
+            axum::extract::Path("job-d".to_string()),
+            Json(ResolveEscrowRequest { provider_prevails: true }),
+        ).await.unwrap();
+        assert_eq!(tranche_states(&resolved), vec!["released", "released", "released", "locked"]);
+        assert_eq!(resolved.released_wei(), 750);
+    }
+
+    #[tokio::test]
+    async fn test_finalize_racing_last_milestone_releases_once() {
+        let (state, backend) = escrow_state("job-r").await;
+        prove_steps(&state, "job-r", 1..=99).await;
+
+        // Mark the last step on-chain without evaluating milestones, then
+        // let the milestone release and finalize race for the last tranche
+        let _ = submit_proof(State(state.clone()), Json(step_request("job-r", 100))).await.unwrap();
+        let batch = state.mempool.write().await.next_batch().unwrap();
+        let submission = submit_batch(&state, &batch).await.unwrap();
+        for record in state.proofs.write().await.get_mut("job-r").unwrap() {
+            if record.step == Some(100) {
+                record.submitted = true;
+                record.tx_hash = Some(submission.tx_hash.clone());
+            }
+        }
+        state.mempool.write().await.complete(batch, Ok(submission), state.clock.now_secs());
+
+        let (by_milestone, by_finalize) = tokio::join!(
+            release_milestones(&state, "job-r"),
+            release_final_tranches(&state, "job-r"),
+        );
+        assert_eq!(by_milestone + by_finalize.unwrap(), 1);
+
+        let releases = backend.releases.lock().unwrap().clone();
+        assert_eq!(releases.iter().filter(|(memo, _)| memo == "milestone-3").count(), 1);
+        assert_eq!(releases.iter().map(|(_, a)| a).sum::<u64>(), 1_000);
+        let escrow = state.escrows.read().await["job-r"].clone();
+        assert_eq!(escrow.status, EscrowStatus::Finalized);
+        assert_eq!(escrow.released_wei(), 1_000);
+
+        // A repeated finalize finds nothing left to pay
+        assert_eq!(release_final_tranches(&state, "job-r").await, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_finalize_pays_out_once() {
+        let (state, backend) = escrow_state("job-f").await;
+        prove_steps(&state, "job-f", 1..=30).await;
+        let released_before = backend.releases.lock().unwrap().len();
+        let finalize = || finalize_job(
+            State(state.clone()),
+            Json(FinalizeRequest { job_id: "job-f".to_string(), optimistic: false }),
+        );
+
+        // A double notification: both calls are in before either finishes
+        let (first, second) = (tokio::spawn(finalize()), tokio::spawn(finalize()));
+        while state.mempool.read().await.len() < 1 {
+            tokio::task::yield_now().await;
+        }
+        let submissions = drain_mempool(&state, 10).await;
+        assert_eq!(submissions.len(), 1);
+        let Json(first) = first.await.unwrap().unwrap();
+        let Json(second) = second.await.unwrap().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first["tx_hash"], submissions[0].tx_hash);
+
+        // The remaining tranches went out once, covering the budget exactly
+        let releases = backend.releases.lock().unwrap().clone();
+        assert_eq!(first["milestones_released"], releases.len() - released_before);
+        assert_eq!(releases.iter().map(|(_, a)| a).sum::<u64>(), 1_000);
+
+        // A late repeat gets the same answer and pays nothing
+        let Json(late) = finalize().await.unwrap();
+        assert_eq!(late, first);
+        assert_eq!(backend.releases.lock().unwrap().len(), releases.len());
+        assert!(drain_mempool(&state, 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_proof_export_streams_one_line_per_proof_in_order() {
+        let state = test_state().await;
+        let proof = |proof_type: ProofType, step: Option<u64>| ProofRecord {
+            job_id: "job-x".to_string(),
+            proof_type,
+            step,
+            digest: format!("0x{:064x}", step.unwrap_or(0)),
+            timestamp: 1_700_000_000,
+            submitted: true,
+            tx_hash: Some("0xtx".to_string()),
+            attestation: None,
+        };
+        // More proofs than fit in one page, accepted slightly out of step order
+        let mut records: Vec<ProofRecord> = (1..=600).map(|step| proof(ProofType::TrainStep, Some(step))).collect();
+        records.swap(104, 105);
+        records.push(proof(ProofType::TrainComplete, None));
+        state.proofs.write().await.insert("job-x".to_string(), records);
+
+        let export = |query: ProofExportQuery| {
+            let state = state.clone();
+            async move {
+                let response = export_job_proofs(State(state), axum::extract::Path("job-x".to_string()), axum::extract::Query(query))
+                    .await
+                    .unwrap();
+                assert_eq!(response.headers()[axum::http::header::CONTENT_TYPE], "application/x-ndjson");
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(body.to_vec())
+                    .unwrap()
+                    .lines()
+                    .map(|line| serde_json::from_str::<ProofRecord>(line).unwrap())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let all = export(ProofExportQuery::default()).await;
+        assert_eq!(all.len(), 601);
+        assert_eq!(all.iter().map(|p| p.step).collect::<Vec<_>>(), state.proofs.read().await["job-x"].iter().map(|p| p.step).collect::<Vec<_>>());
+        assert_eq!(all[600].proof_type, ProofType::TrainComplete);
+
+        // A step range keeps acceptance order and leaves out proofs without a step
+        let range = export(ProofExportQuery { from_step: Some(100), to_step: Some(110), ..Default::default() }).await;
+        let steps: Vec<u64> = range.iter().map(|p| p.step.unwrap()).collect();
+        assert_eq!(steps, vec![100, 101, 102, 103, 104, 106, 105, 107, 108, 109, 110]);
+        let tail = export(ProofExportQuery { from_step: Some(599), ..Default::default() }).await;
+        assert_eq!(tail.iter().map(|p| p.step).collect::<Vec<_>>(), vec![Some(599), Some(600)]);
+
+        let complete = export(ProofExportQuery { proof_type: Some(ProofType::TrainComplete), ..Default::default() }).await;
+        assert_eq!(complete.len(), 1);
+        assert!(export(ProofExportQuery { from_step: Some(700), ..Default::default() }).await.is_empty());
+
+        let unknown = export_job_proofs(State(state.clone()), axum::extract::Path("job-none".to_string()), axum::extract::Query(ProofExportQuery::default())).await;
+        assert_eq!(unknown.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_proof_calls_are_sent_to_proof_of_compute() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let client = ContractClient::new(rpc.url(), SeededEntropy::shared(7));
+        let (job_id, node) = ("job-1", "0xnode");
+        let digest = |byte: u8| format!("0x{}", hex::encode([byte; 32]));
+
+        let train_tx = client.record_train_proof(job_id, 3, &StepDigests { loss_digest: &digest(1), gradient_digest: &digest(2), weights_digest: &digest(3) }, node, 7).await.unwrap();
+        let infer_tx = client.record_infer_proof(job_id, &digest(4), "bafy-out", &digest(5), node, 8).await.unwrap();
+        let (finalize_tx, payout) = client.finalize(job_id, node, 120, "bafy-out", false, None, 9).await.unwrap();
+        let (optimistic_tx, _) = client.finalize(job_id, node, 60, "bafy-out", true, None, 10).await.unwrap();
+        assert_eq!(payout, 120 * 1_000_000_000_000_000);
+
+        // Each call is a transaction to the contract, and its hash is the chain's
+        let calls = rpc.calls();
+        assert_eq!(calls.len(), 4);
+        assert!(calls.iter().all(|call| call.method == "eth_sendTransaction" && call.to == "0x0000000000000000000000000000000000000001"));
+        let hashes = std::collections::HashSet::from([&train_tx, &infer_tx, &finalize_tx, &optimistic_tx]);
+        assert_eq!(hashes.len(), 4);
+
+        let contract = abi::proof_of_compute();
+        let word = |byte: u8| Token::FixedBytes(vec![byte; 32]);
+        abi::assert_call(&contract, &calls[0].data, "recordTrainProof", &[
+            abi_encode_bytes32(job_id), Token::Uint(3), word(1), word(2), word(3), abi_encode_bytes32(node), Token::Bytes(Vec::new()),
+        ]);
+        abi::assert_call(&contract, &calls[1].data, "recordInferProof", &[
+            abi_encode_bytes32(job_id), word(4), abi_encode_bytes32("bafy-out"), word(5), abi_encode_bytes32(node), Token::Bytes(Vec::new()),
+        ]);
+        abi::assert_call(&contract, &calls[2].data, "finalize", &[
+            abi_encode_bytes32(job_id), abi_encode_bytes32(node), Token::Uint(120), abi_encode_bytes32("bafy-out"),
+        ]);
+        abi::assert_call(&contract, &calls[3].data, "finalizeOptimistic", &[
+            abi_encode_bytes32(job_id), abi_encode_bytes32(node), Token::Uint(60), abi_encode_bytes32("bafy-out"),
+        ]);
+
+        // No chain, no tx hash
+        let offline = ContractClient::new("http://127.0.0.1:9".to_string(), SeededEntropy::shared(7));
+        assert!(offline.record_infer_proof(job_id, &digest(4), "bafy-out", &digest(5), node, 8).await.is_err());
+    }
+}
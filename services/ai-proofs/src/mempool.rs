@@ -0,0 +1,251 @@
+//! Proof Submission Mempool
+//! Queues proofs before they go on-chain: duplicates (same job, proof type
+//! and step) collapse into one submission, finalizations jump ahead of step
+//! proofs, and a job's queued train steps are coalesced into one batched
+//! call. A drain loop submits at a controlled rate with nonces from the
+//! [`NonceManager`]. Proofs whose submission failed are kept as dead letters
+//! until an operator replays them.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProofKey {
+    pub job_id: String,
+    pub proof_type: String,
+    pub step: Option<u64>,
+}
+
+/// The contract call a queued proof turns into
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProofCall {
+    TrainStep {
+        step: u64,
+        loss_digest: String,
+        gradient_digest: String,
+        weights_digest: String,
+    },
+    Infer {
+        input_digest: String,
+        output_cid: String,
+        output_digest: String,
+    },
+    Finalize {
+        gpu_seconds: u64,
+        output_cid: String,
+        optimistic: bool,
+        steps_root: Option<String>, // Merkle root over a sampled job's steps
+    },
+}
+
+impl ProofCall {
+    /// Higher drains first: finalize > infer > train step
+    fn priority(&self) -> u8 {
+        match self {
+            ProofCall::Finalize { .. } => 2,
+            ProofCall::Infer { .. } => 1,
+            ProofCall::TrainStep { .. } => 0,
+        }
+    }
+
+    fn batchable(&self) -> bool {
+        matches!(self, ProofCall::TrainStep { .. })
+    }
+}
+
+/// Result of one on-chain submission, shared by every proof in its batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Submission {
+    pub tx_hash: String,
+    pub nonce: u64,
+    pub batch_size: usize,
+    pub payout: Option<u64>, // Finalize only
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Enqueued {
+    Queued,
+    Pending,               // Duplicate of a proof still in the queue
+    Submitted(Submission), // Duplicate of a proof already on-chain
+}
+
+type Waiter = oneshot::Sender<Result<Submission, String>>;
+
+#[derive(Debug)]
+pub struct QueuedProof {
+    pub key: ProofKey,
+    pub call: ProofCall,
+    waiters: Vec<Waiter>,
+}
+
+/// Proofs drained together as one contract call
+#[derive(Debug)]
+pub struct Batch {
+    pub job_id: String,
+    pub proofs: Vec<QueuedProof>,
+}
+
+type Slot = (Reverse<u8>, u64); // (priority, arrival order)
+
+/// A proof whose submission failed, held for replay
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetter {
+    pub id: u64,
+    pub key: ProofKey,
+    #[serde(skip)]
+    pub call: ProofCall,
+    pub error: String,
+    pub failed_at: u64,
+}
+
+#[derive(Debug)]
+pub struct ProofMempool {
+    queue: BTreeMap<Slot, QueuedProof>,
+    slots: HashMap<ProofKey, Slot>,
+    submitted: HashMap<ProofKey, Submission>,
+    dead_letters: BTreeMap<u64, DeadLetter>,
+    next_seq: u64,
+    next_dead_letter: u64,
+    max_batch: usize,
+}
+
+impl ProofMempool {
+    pub fn new(max_batch: usize) -> Self {
+        Self {
+            queue: BTreeMap::new(),
+            slots: HashMap::new(),
+            submitted: HashMap::new(),
+            dead_letters: BTreeMap::new(),
+            next_seq: 0,
+            next_dead_letter: 1,
+            max_batch: max_batch.max(1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn enqueue(&mut self, key: ProofKey, call: ProofCall) -> Enqueued {
+        if let Some(submission) = self.submitted.get(&key) {
+            return Enqueued::Submitted(submission.clone());
+        }
+        if self.slots.contains_key(&key) {
+            return Enqueued::Pending;
+        }
+
+        let slot = (Reverse(call.priority()), self.next_seq);
+        self.next_seq += 1;
+        self.slots.insert(key.clone(), slot);
+        self.queue.insert(slot, QueuedProof { key, call, waiters: Vec::new() });
+        Enqueued::Queued
+    }
+
+    /// Wait for the submission of a queued proof. None if it is not queued.
+    pub fn watch(&mut self, key: &ProofKey) -> Option<oneshot::Receiver<Result<Submission, String>>> {
+        let slot = self.slots.get(key)?;
+        let (tx, rx) = oneshot::channel();
+        self.queue.get_mut(slot)?.waiters.push(tx);
+        Some(rx)
+    }
+
+    /// Take the highest-priority proof, plus up to `max_batch - 1` more
+    /// queued train steps of the same job when it is batchable
+    pub fn next_batch(&mut self) -> Option<Batch> {
+        let (_, first) = self.queue.pop_first()?;
+        self.slots.remove(&first.key);
+        let job_id = first.key.job_id.clone();
+
+        let mut proofs = vec![first];
+        if proofs[0].call.batchable() {
+            let companions: Vec<Slot> = self
+                .queue
+                .iter()
+                .filter(|(_, p)| p.key.job_id == job_id && p.call.batchable())
+                .map(|(slot, _)| *slot)
+                .take(self.max_batch - 1)
+                .collect();
+            for slot in companions {
+                if let Some(proof) = self.queue.remove(&slot) {
+                    self.slots.remove(&proof.key);
+                    proofs.push(proof);
+                }
+            }
+        }
+
+        Some(Batch { job_id, proofs })
+    }
+
+    /// Record a batch's outcome and wake its waiters. Failed proofs leave
+    /// the queue, so a retry can queue them again, and become dead letters.
+    pub fn complete(&mut self, batch: Batch, result: Result<Submission, String>, now: u64) {
+        for proof in batch.proofs {
+            match &result {
+                Ok(submission) => {
+                    self.dead_letters.retain(|_, dead| dead.key != proof.key);
+                    self.submitted.insert(proof.key.clone(), submission.clone());
+                }
+                Err(error) => {
+                    let id = self.next_dead_letter;
+                    self.next_dead_letter += 1;
+                    self.dead_letters.insert(id, DeadLetter {
+                        id,
+                        key: proof.key.clone(),
+                        call: proof.call.clone(),
+                        error: error.clone(),
+                        failed_at: now,
+                    });
+                }
+            }
+            for waiter in proof.waiters {
+                let _ = waiter.send(result.clone());
+            }
+        }
+    }
+
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.values().cloned().collect()
+    }
+
+    /// Queue a dead letter's proof again. None if there is no such dead letter.
+    pub fn replay(&mut self, id: u64) -> Option<Enqueued> {
+        let dead = self.dead_letters.remove(&id)?;
+        Some(self.enqueue(dead.key, dead.call))
+    }
+}
+
+/// Hands out account nonces in order. Nonces of failed submissions are
+/// reused first so the account never leaves a gap the chain would stall on.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    next: u64,
+    released: BTreeSet<u64>,
+}
+
+impl NonceManager {
+    pub fn new(start: u64) -> Self {
+        Self {
+            next: start,
+            released: BTreeSet::new(),
+        }
+    }
+
+    pub fn reserve(&mut self) -> u64 {
+        if let Some(nonce) = self.released.pop_first() {
+            return nonce;
+        }
+        let nonce = self.next;
+        self.next += 1;
+        nonce
+    }
+
+    pub fn release(&mut self, nonce: u64) {
+        if nonce + 1 == self.next {
+            self.next = nonce;
+        } else {
+            self.released.insert(nonce);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Proof Store
+//! Where accepted proofs outlive the service. Each job has a log with a line
+//! per proof accepted without a record, a line per proof record as it is
+//! accepted and again once it is on-chain, and a line once the job is
+//! finalized. Startup replays the logs into the proof map and the accepted
+//! set. Finalizing a job compacts its log to its records and the
+//! finalization, which is what turns the job's replays away from then on.
+//! Tests use a store that lives in memory.
+
+use crate::mempool::ProofKey;
+use crate::{proof_key, ProofRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(test)]
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+pub enum ProofEntry {
+    Accepted { key: ProofKey }, // A step sampling kept off-chain, or one retention GC evicted
+    Record { record: ProofRecord },
+    Finalized { result: serde_json::Value },
+}
+
+/// A job's log replayed, the last line for each proof winning
+#[derive(Debug, Default)]
+pub struct StoredJob {
+    pub job_id: String,
+    pub accepted: HashSet<ProofKey>, // Empty once the job is finalized
+    pub proofs: Vec<ProofRecord>,
+    pub finalized: Option<serde_json::Value>,
+}
+
+impl StoredJob {
+    pub fn replay(job_id: String, entries: Vec<ProofEntry>) -> Self {
+        let mut job = StoredJob { job_id, ..Default::default() };
+        for entry in entries {
+            match entry {
+                ProofEntry::Accepted { key } => {
+                    job.accepted.insert(key);
+                }
+                ProofEntry::Record { record } => {
+                    let key = proof_key(&record.job_id, &record.proof_type, record.step);
+                    match job.proofs.iter_mut().find(|p| proof_key(&p.job_id, &p.proof_type, p.step) == key) {
+                        Some(existing) => *existing = record,
+                        None => job.proofs.push(record),
+                    }
+                    job.accepted.insert(key);
+                }
+                ProofEntry::Finalized { result } => job.finalized = Some(result),
+            }
+        }
+        if job.finalized.is_some() {
+            job.accepted.clear();
+        }
+        job
+    }
+}
+
+pub trait ProofStore: Send + Sync {
+    fn append(&self, job_id: &str, entry: &ProofEntry) -> Result<(), String>;
+    /// Replace the job's log with `entries`
+    fn compact(&self, job_id: &str, entries: &[ProofEntry]) -> Result<(), String>;
+    fn list_jobs(&self) -> Result<Vec<StoredJob>, String>;
+}
+
+/// Logs as `<job_id>.jsonl` files under `dir`, appended a line at a time
+pub struct FileProofStore {
+    dir: PathBuf,
+}
+
+impl FileProofStore {
+    pub fn open(dir: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create proof data dir {}: {}", dir, e))?;
+        Ok(FileProofStore { dir: PathBuf::from(dir) })
+    }
+
+    fn path(&self, job_id: &str) -> Result<PathBuf, String> {
+        // Job ids name files, so nothing that could leave the directory
+        if job_id.is_empty() || !job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Job id {:?} can't name a file", job_id));
+        }
+        Ok(self.dir.join(format!("{}.jsonl", job_id)))
+    }
+}
+
+fn entry_line(entry: &ProofEntry) -> Result<Vec<u8>, String> {
+    let mut line = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+impl ProofStore for FileProofStore {
+    fn append(&self, job_id: &str, entry: &ProofEntry) -> Result<(), String> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(job_id)?)
+            .map_err(|e| e.to_string())?;
+        file.write_all(&entry_line(entry)?).map_err(|e| e.to_string())
+    }
+
+    fn compact(&self, job_id: &str, entries: &[ProofEntry]) -> Result<(), String> {
+        let path = self.path(job_id)?;
+        let mut bytes = Vec::new();
+        for entry in entries {
+            bytes.extend(entry_line(entry)?);
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+    }
+
+    fn list_jobs(&self) -> Result<Vec<StoredJob>, String> {
+        let mut jobs = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            let Some(job_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                continue; // Including compactions a crash cut short
+            }
+            let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            // A line without its newline is an append a crash cut short
+            let complete = text.rfind('\n').map_or("", |end| &text[..end]);
+            let entries = complete
+                .lines()
+                .map(|line| serde_json::from_str(line).map_err(|e| format!("Corrupt proof log {}: {}", path.display(), e)))
+                .collect::<Result<Vec<ProofEntry>, String>>()?;
+            jobs.push(StoredJob::replay(job_id.to_string(), entries));
+        }
+        Ok(jobs)
+    }
+}
+
+/// Logs kept only for the life of the process
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryProofStore {
+    logs: Mutex<HashMap<String, Vec<ProofEntry>>>,
+}
+
+#[cfg(test)]
+impl ProofStore for MemoryProofStore {
+    fn append(&self, job_id: &str, entry: &ProofEntry) -> Result<(), String> {
+        self.logs.lock().unwrap().entry(job_id.to_string()).or_default().push(entry.clone());
+        Ok(())
+    }
+
+    fn compact(&self, job_id: &str, entries: &[ProofEntry]) -> Result<(), String> {
+        self.logs.lock().unwrap().insert(job_id.to_string(), entries.to_vec());
+        Ok(())
+    }
+
+    fn list_jobs(&self) -> Result<Vec<StoredJob>, String> {
+        let logs = self.logs.lock().unwrap();
+        Ok(logs.iter().map(|(job_id, entries)| StoredJob::replay(job_id.clone(), entries.clone())).collect())
+    }
+}
@@ -0,0 +1,120 @@
+//! Proof Sampling
+//! Putting every train step on-chain is expensive. A job whose step proofs
+//! carry a `proof_sampling_interval` of k has only every k-th step and its
+//! final step proven on-chain. Every step is still accepted and hashed into
+//! a leaf, and finalize commits the Merkle root over the job's full step
+//! sequence, so an unsampled step stays verifiable by its inclusion proof.
+//!
+//! A leaf hashes the step and its digests as batched step proofs do. Leaves
+//! are ordered by step; an odd node out at a level is carried up unhashed.
+
+use serde::Serialize;
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct StepLog {
+    pub interval: u64,
+    leaves: BTreeMap<u64, String>, // Step -> leaf
+}
+
+/// One sibling on the path from a leaf to the root
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathNode {
+    pub hash: String,
+    pub on_left: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InclusionProof {
+    pub step: u64,
+    pub leaf: String,
+    pub path: Vec<PathNode>,
+    pub root: String,
+}
+
+pub fn step_leaf(step: u64, loss_digest: &str, gradient_digest: &str, weights_digest: &str) -> String {
+    let mut hasher = Keccak256::new();
+    hasher.update(step.to_be_bytes());
+    hasher.update(loss_digest.as_bytes());
+    hasher.update(gradient_digest.as_bytes());
+    hasher.update(weights_digest.as_bytes());
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
+fn hash_pair(left: &str, right: &str) -> String {
+    let mut hasher = Keccak256::new();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
+/// The levels of the tree over `leaves`, leaves first and root last
+fn levels(leaves: Vec<String>) -> Vec<Vec<String>> {
+    let mut levels = vec![leaves];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let next = levels.last().unwrap().chunks(2).map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [single] => single.clone(),
+            _ => unreachable!(),
+        });
+        levels.push(next.collect());
+    }
+    levels
+}
+
+impl StepLog {
+    pub fn new(interval: u64) -> Self {
+        StepLog { interval: interval.max(1), leaves: BTreeMap::new() }
+    }
+
+    /// Whether a step goes on-chain
+    pub fn is_sampled(&self, step: u64, final_step: bool) -> bool {
+        final_step || step.is_multiple_of(self.interval)
+    }
+
+    pub fn record(&mut self, step: u64, leaf: String) {
+        self.leaves.insert(step, leaf);
+    }
+
+    /// Steps recorded, sampled or not
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn root(&self) -> Option<String> {
+        levels(self.leaves.values().cloned().collect()).pop()?.pop()
+    }
+
+    pub fn inclusion(&self, step: u64) -> Option<InclusionProof> {
+        let mut index = self.leaves.keys().position(|s| *s == step)?;
+        let levels = levels(self.leaves.values().cloned().collect());
+        let mut path = Vec::new();
+        for level in &levels[..levels.len() - 1] {
+            let sibling = index ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                path.push(PathNode { hash: hash.clone(), on_left: sibling < index });
+            }
+            index /= 2;
+        }
+        Some(InclusionProof {
+            step,
+            leaf: self.leaves[&step].clone(),
+            path,
+            root: levels.last()?.first()?.clone(),
+        })
+    }
+}
+
+impl InclusionProof {
+    pub fn verify(&self) -> bool {
+        let computed = self.path.iter().fold(self.leaf.clone(), |node, sibling| {
+            if sibling.on_left {
+                hash_pair(&sibling.hash, &node)
+            } else {
+                hash_pair(&node, &sibling.hash)
+            }
+        });
+        computed == self.root
+    }
+}
@@ -0,0 +1,176 @@
+//! Runtime Capability Negotiation
+//! Checks a job's declared framework, version, CUDA and image requirements
+//! against the runtime images and driver this node offers, so jobd can reject
+//! an assignment before launching a container that cannot run the job.
+
+use serde::{Deserialize, Serialize};
+
+/// A runtime image this node can launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeImage {
+    pub framework: String,            // "torch", "tf", "jax", "agent", ...
+    pub version: String,              // Framework version shipped in the image
+    pub image: String,
+    pub cuda: Option<String>,         // CUDA toolkit the image was built against; None for CPU-only
+    #[serde(default)]
+    pub digest: Option<String>,       // Pinned digest, if the image is pinned
+}
+
+/// Requirements a job declares for its runtime
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeRequirements {
+    pub framework: String,
+    #[serde(default)]
+    pub framework_version: Option<String>, // "2.1" (prefix) or ">=2.0"
+    #[serde(default)]
+    pub min_cuda: Option<String>,
+    #[serde(default)]
+    pub image_digest: Option<String>,
+    #[serde(default)]
+    pub tee_required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityCheck {
+    pub satisfiable: bool,
+    pub runtime: Option<String>,
+    pub image: Option<String>,
+    pub unmet: Vec<String>,
+}
+
+pub struct NodeCapabilities {
+    pub images: Vec<RuntimeImage>,
+    pub cuda_version: Option<String>, // Highest CUDA version the node's driver supports
+}
+
+fn image(framework: &str, version: &str, cuda: Option<&str>) -> RuntimeImage {
+    RuntimeImage {
+        framework: framework.to_string(),
+        version: version.to_string(),
+        image: format!("artha/{}-runtime:v1", framework),
+        cuda: cuda.map(|c| c.to_string()),
+        digest: None,
+    }
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Compare versions component-wise, treating missing components as 0
+fn version_at_least(have: &str, want: &str) -> bool {
+    let (have, want) = (parse_version(have), parse_version(want));
+    let len = have.len().max(want.len());
+    for i in 0..len {
+        let (h, w) = (have.get(i).copied().unwrap_or(0), want.get(i).copied().unwrap_or(0));
+        if h != w {
+            return h > w;
+        }
+    }
+    true
+}
+
+/// `>=X` is a minimum; anything else must match the leading components exactly
+fn version_matches(have: &str, spec: &str) -> bool {
+    match spec.trim().strip_prefix(">=") {
+        Some(min) => version_at_least(have, min),
+        None => {
+            let (have, want) = (parse_version(have), parse_version(spec));
+            have.len() >= want.len() && have[..want.len()] == want[..]
+        }
+    }
+}
+
+impl NodeCapabilities {
+    /// The images `get_runtime_image` has always launched
+    pub fn default_images() -> Vec<RuntimeImage> {
+        vec![
+            image("torch", "2.1.0", Some("12.1")),
+            image("tf", "2.15.0", Some("12.2")),
+            image("jax", "0.4.23", Some("12.2")),
+            image("agent", "1.0.0", None),
+            image("cv", "2.1.0", Some("12.1")),
+            image("sd", "2.1.0", Some("12.1")),
+            image("quantize", "1.0.0", Some("12.1")),
+        ]
+    }
+
+    /// ARTHA_RUNTIME_IMAGES (JSON list) overrides the default catalog;
+    /// ARTHA_CUDA_VERSION is the driver's CUDA version, unset for CPU-only nodes
+    pub fn from_env() -> Self {
+        NodeCapabilities {
+            images: std::env::var("ARTHA_RUNTIME_IMAGES")
+                .ok()
+                .and_then(|images| serde_json::from_str(&images).ok())
+                .unwrap_or_else(Self::default_images),
+            cuda_version: std::env::var("ARTHA_CUDA_VERSION").ok(),
+        }
+    }
+
+    fn image_unmet(&self, image: &RuntimeImage, req: &RuntimeRequirements) -> Vec<String> {
+        let mut unmet = Vec::new();
+        if let Some(spec) = &req.framework_version {
+            if !version_matches(&image.version, spec) {
+                unmet.push(format!("{} {} does not satisfy {}", image.framework, image.version, spec));
+            }
+        }
+        if let Some(digest) = &req.image_digest {
+            if image.digest.as_deref() != Some(digest.as_str()) {
+                unmet.push(format!("image digest {} not available", digest));
+            }
+        }
+        if let Some(min_cuda) = &req.min_cuda {
+            match &image.cuda {
+                Some(cuda) if version_at_least(cuda, min_cuda) => {}
+                Some(cuda) => unmet.push(format!("{} is built for CUDA {}, job needs {}", image.image, cuda, min_cuda)),
+                None => unmet.push(format!("{} has no CUDA support, job needs {}", image.image, min_cuda)),
+            }
+        }
+        if let Some(cuda) = &image.cuda {
+            match &self.cuda_version {
+                Some(driver) if version_at_least(driver, cuda) => {}
+                Some(driver) => unmet.push(format!("node driver supports CUDA {}, image needs {}", driver, cuda)),
+                None => unmet.push(format!("node has no CUDA driver, image needs {}", cuda)),
+            }
+        }
+        unmet
+    }
+
+    /// Pick the first image that satisfies every requirement, or report why none does
+    pub fn check(&self, req: &RuntimeRequirements, tee_available: bool) -> CapabilityCheck {
+        let mut unmet = Vec::new();
+        if req.tee_required && !tee_available {
+            unmet.push("TEE launch not available on this node".to_string());
+        }
+
+        let candidates: Vec<&RuntimeImage> = self
+            .images
+            .iter()
+            .filter(|image| image.framework == req.framework)
+            .collect();
+        if candidates.is_empty() {
+            unmet.push(format!("framework {} not offered", req.framework));
+            return CapabilityCheck { satisfiable: false, runtime: None, image: None, unmet };
+        }
+
+        let mut image_unmet = Vec::new();
+        for image in candidates {
+            let reasons = self.image_unmet(image, req);
+            if reasons.is_empty() {
+                return CapabilityCheck {
+                    satisfiable: unmet.is_empty(),
+                    runtime: Some(image.framework.clone()),
+                    image: Some(image.image.clone()),
+                    unmet,
+                };
+            }
+            image_unmet.extend(reasons);
+        }
+        unmet.extend(image_unmet);
+        CapabilityCheck { satisfiable: false, runtime: None, image: None, unmet }
+    }
+}
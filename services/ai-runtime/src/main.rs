@@ -2,18 +2,49 @@
 /// Manages Docker containers, GPU allocation, SVDB mounting, and checkpoint saving
 
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     routing::{get, post},
     Router,
 };
+use artha_clock::{Entropy, SharedClock, SharedEntropy};
+use artha_errors::Deadline;
+use artha_joblog::{JobLog, LogLimits};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::process::{Command, Stdio};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use sha2::Digest;
+use tracing::{error, info, warn};
+mod capabilities;
 mod container;
+mod memory;
+mod migrate;
+mod mount_cache;
+mod openai;
+mod pool;
+mod repro;
+mod restart;
+mod stream;
+mod svdb;
+mod tee;
+mod telemetry;
+mod vault;
+use capabilities::{CapabilityCheck, NodeCapabilities, RuntimeRequirements};
 use container::ContainerRuntime;
+use memory::{Fit, ModelFootprint};
+use migrate::{MigrateRequest, MigrationHandoff, MigrationMode};
+use mount_cache::{MountCache, MountCacheStats};
+use svdb::SvdbClient;
+use pool::{CapacityReport, DockerBackend, JobSpec, PoolConfig, PoolManager};
+use repro::{DockerImageStore, ExecutionRecord, ImageStore, ReplayStatus, ReproBundle, SensitiveEnv};
+use restart::{ContainerExit, ContainerLaunch, DockerJobContainers, JobContainers, RestartPolicy};
+use stream::{ContainerTransform, StartStreamRequest, StreamHandle, StreamRun, Transform, TransformFactory, TransformSpec, WasmTransform, WindowSink};
+use tee::{AttestationQuote, SimulatedTeeLauncher, TeeLaunchSpec, TeeLauncher};
+use telemetry::{GpuSampler, JobTelemetry, NvmlSampler, TelemetryConfig};
+use vault::{SecretError, VaultClient};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
@@ -26,8 +57,63 @@ pub struct Job {
     pub status: ContainerStatus,
     pub gpu_allocated: Option<String>,
     pub started_at: Option<u64>,
-    pub logs: Vec<String>,
+    pub logs: JobLog, // Newest lines; the full log is archived to SVDB
     pub checkpoints: Vec<String>,
+    pub tee_required: bool,
+    pub attestation: Option<AttestationQuote>,
+    pub execution: Option<ExecutionRecord>, // What the job actually ran with, secrets redacted
+    pub output_digests: Option<Vec<String>>, // Set on completion
+    pub replay: Option<ReplayStatus>,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub restart_count: u32, // Times the container was restarted after a non-zero exit
+    #[serde(default)]
+    pub startup: Option<StartupTimings>,
+    #[serde(default)]
+    pub checkpoint_times: Vec<u64>, // When each new checkpoint was seen, oldest first
+    #[serde(default)]
+    pub max_runtime_secs: Option<u64>, // Wall-clock limit from first start; the job fails with Timeout past it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal_reason: Option<TerminalReason>, // Set when the job fails
+}
+
+/// Why a job ended Failed, as reported to ai-jobd
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerminalReason {
+    pub kind: TerminalReasonKind,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalReasonKind {
+    OutOfMemory,   // Container killed by the OOM killer
+    Timeout,       // Ran past max_runtime_secs
+    ContainerExit, // Container exited non-zero
+    RuntimeError,  // Failed on this node outside the container
+}
+
+impl TerminalReason {
+    fn new(kind: TerminalReasonKind, detail: impl Into<String>) -> Self {
+        TerminalReason { kind, detail: detail.into(), exit_code: None }
+    }
+
+    /// A non-zero container exit; an OOM kill is told apart from an ordinary crash
+    fn exited(exit: ContainerExit, detail: String) -> Self {
+        let kind = if exit.oom_killed { TerminalReasonKind::OutOfMemory } else { TerminalReasonKind::ContainerExit };
+        TerminalReason { kind, detail, exit_code: Some(exit.code) }
+    }
+}
+
+/// How long a job took to get going on this node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StartupTimings {
+    pub warm: bool,     // Claimed a pool container; nothing was pulled
+    pub pull_ms: u64,   // Fetching model, dataset and resume checkpoint from SVDB
+    pub launch_ms: u64, // Creating and starting the container, or claiming one
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +121,8 @@ pub enum JobType {
     Train,
     Infer,
     Agent,
+    Stream, // Continuous windowed transform; see `stream`
+    Quantize, // Post-training int8/fp16 quantization, evaluated against the parent model
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -46,6 +134,8 @@ pub enum ContainerStatus {
     Completed,
     Failed,
     Stopped,
+    Migrating, // Asked to checkpoint for a move to another node
+    Migrated,  // Exported; the job continues elsewhere
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +156,35 @@ pub struct StartJobRequest {
     pub dataset_cid: Option<String>,
     pub params: JobParams,
     pub runtime: String, // "torch", "tf", "jax", "agent"
+    #[serde(default)]
+    pub tee_required: bool,
+    pub attestation_nonce: Option<String>,
+    #[serde(default)]
+    pub image_digest: Option<String>, // Pinned runtime image digest, if the job requires one
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub secret_env: SensitiveEnv, // Never recorded, only referenced by name
+    #[serde(default)]
+    pub secret_refs: Vec<String>, // Vault secrets injected under their own names at launch
+    #[serde(default)]
+    pub submitter_did: Option<String>, // Whose access to `secret_refs` policy-gate checks
+    #[serde(default)]
+    pub seed: Option<u64>, // Injected as ARTHA_SEED plus framework determinism env
+    #[serde(default)]
+    pub restart_policy: RestartPolicy, // "never", "on-failure:N" or "always"
+    #[serde(default)]
+    pub resume_from: Option<String>, // Checkpoint CID a migrated job resumes from
+    #[serde(default)]
+    pub max_runtime_secs: Option<u64>,
+    #[serde(default)]
+    pub model: Option<ModelFootprint>, // Checked against free VRAM before launch, if given
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayRequest {
+    pub job_id: String,                // Job to re-run
+    pub replay_job_id: Option<String>, // Defaults to `<job_id>-replay-<hash>`
 }
 
 #[derive(Debug, Serialize)]
@@ -74,6 +193,8 @@ pub struct StartJobResponse {
     pub container_id: String,
     pub status: ContainerStatus,
     pub gpu_allocated: String,
+    pub attestation: Option<AttestationQuote>,
+    pub startup: StartupTimings,
 }
 
 // Application state
@@ -82,91 +203,116 @@ pub struct AppState {
     gpu_allocations: Arc<RwLock<HashMap<String, String>>>, // gpu_id -> job_id
     svdb_client: Arc<SvdbClient>,
     proof_service_url: String,
+    tee_launcher: Option<Arc<dyn TeeLauncher>>,
+    pools: Arc<PoolManager>,
+    gpu_sampler: Arc<dyn GpuSampler>,
+    telemetry_config: TelemetryConfig,
+    gpu_telemetry: Arc<RwLock<HashMap<String, JobTelemetry>>>, // job_id -> series
+    capabilities: Arc<NodeCapabilities>,
+    job_secrets: Arc<RwLock<HashMap<String, SensitiveEnv>>>, // job_id -> secret env, for cluster replays
+    vault: Option<Arc<VaultClient>>, // Resolves jobs' secret references; None if unconfigured
+    image_store: Arc<dyn ImageStore>,
+    job_containers: Arc<dyn JobContainers>,
+    jobd_url: String, // Coordinating ai-jobd, told when a job is exported for migration
+    node_key: SigningKey, // Signs heartbeats and infer outputs
+    mount_cache: Arc<MountCache>,
+    streams: Arc<RwLock<HashMap<String, Arc<StreamHandle>>>>, // job_id -> running stream transform
+    log_limits: LogLimits, // In-memory bound on each job's log
+    clock: SharedClock,
+    entropy: SharedEntropy,
 }
 
-pub struct SvdbClient {
-    base_url: String,
-}
-
-impl SvdbClient {
-    pub fn new(base_url: String) -> Self {
-        SvdbClient { base_url }
-    }
-
-    pub async fn mount_volume(&self, cid: &str, mount_path: &str) -> Result<(), String> {
-        // Mount SVDB CID to local filesystem using FUSE
-        // In production: arthai-fuse mount artha://cid /mnt/data
-        println!("🔗 Mounting {} to {}", cid, mount_path);
-        
-        // For now, download to local directory
-        let download_url = format!("{}/svdb/download/{}", self.base_url, cid);
-        println!("   Download URL: {}", download_url);
-        
-        // Create mount directory
-        std::fs::create_dir_all(mount_path).map_err(|e| e.to_string())?;
-        
-        Ok(())
-    }
-
-    pub async fn upload_checkpoint(&self, checkpoint_path: &str) -> Result<String, String> {
-        // Upload checkpoint to SVDB
-        println!("📤 Uploading checkpoint: {}", checkpoint_path);
-        
-        // In production: call SVDB upload API
-        let checkpoint_cid = format!("artha://QmCheckpoint{}", random_hash());
-        println!("   CID: {}", checkpoint_cid);
-        
-        Ok(checkpoint_cid)
-    }
-}
+/// GPUs managed on this node
+const GPU_COUNT: usize = 8;
 
 // Container management
 
 async fn start_job(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<StartJobRequest>,
+    headers: HeaderMap,
+    Json(mut req): Json<StartJobRequest>,
 ) -> Result<Json<StartJobResponse>, StatusCode> {
-    println!("\n🚀 Starting job: {}", req.job_id);
-    println!("   Type:    {:?}", req.job_type);
-    println!("   Runtime: {}", req.runtime);
-    
-    // 1. Allocate GPU
-    let gpu_id = allocate_gpu(&state, &req.job_id).await?;
-    println!("   GPU:     {} allocated", gpu_id);
-    
-    // 2. Mount SVDB volumes
-    let model_mount = format!("/tmp/artha/jobs/{}/model", req.job_id);
-    state.svdb_client.mount_volume(&req.model_cid, &model_mount).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    info!("🚀 Starting job: {}", req.job_id);
+    info!("   Type:    {:?}", req.job_type);
+    info!("   Runtime: {}", req.runtime);
+    let requested = std::time::Instant::now();
+    let deadline = Deadline::from_headers(&headers).map_err(|e| e.status())?;
+    deadline.check(state.clock.now_millis(), "starting the job").map_err(|e| {
+        info!("   ⌛ {}", e.message);
+        e.status()
+    })?;
+
+    // Referenced secrets join the request's secret env, so everything after
+    // this handles them as secrets
+    if !req.secret_refs.is_empty() {
+        let secrets = resolve_secret_refs(&state, &req).await.map_err(|e| {
+            error!("   ❌ {}", e);
+            match e {
+                SecretError::InvalidName(_) => StatusCode::BAD_REQUEST,
+                SecretError::Denied { .. } => StatusCode::FORBIDDEN,
+                SecretError::NotFound(_) => StatusCode::NOT_FOUND,
+                SecretError::Unavailable(_) => StatusCode::BAD_GATEWAY,
+            }
+        })?;
+        req.secret_env.extend(secrets);
+    }
+
+    // A job declaring its model's size must fit in VRAM, at a smaller batch if need be
+    if let Some(model) = req.model.clone() {
+        fit_in_vram(&state, &mut req, &model).await?;
+    }
     
-    let dataset_mount = if let Some(dataset_cid) = &req.dataset_cid {
-        let path = format!("/tmp/artha/jobs/{}/data", req.job_id);
-        state.svdb_client.mount_volume(dataset_cid, &path).await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        Some(path)
-    } else {
+    // 1. Claim a warm container if one matches the runtime image, else cold start
+    let runtime_image = get_runtime_image(&req.runtime);
+    // TEE jobs always launch fresh under the enclave launcher, and migrated
+    // jobs need their checkpoint mounted
+    let warm = if req.tee_required || req.resume_from.is_some() {
         None
+    } else {
+        state.pools.claim(&runtime_image, req.image_digest.as_deref(), &job_spec(&state, &req)).await
     };
+
+    let (container_id, gpu_id, attestation, image_digest, inputs, pulling) = match warm {
+        Some(claimed) => {
+            info!("   ♨️  Claimed warm container {} on {}", claimed.container_id, claimed.gpu_id);
+            pool::spawn_refill(state.pools.clone());
+            // Warm containers fetch their own inputs, so there are no mounted bytes to hash
+            let mut inputs = vec![repro::InputManifest { role: "model".to_string(), cid: req.model_cid.clone(), sha256: None }];
+            if let Some(dataset_cid) = &req.dataset_cid {
+                inputs.push(repro::InputManifest { role: "dataset".to_string(), cid: dataset_cid.clone(), sha256: None });
+            }
+            (claimed.container_id, claimed.gpu_id, None, claimed.digest, inputs, None)
+        }
+        None => {
+            if !req.tee_required {
+                state.pools.record_cold_start().await;
+            }
+            let (container_id, gpu_id, attestation, pulling) = cold_start(&state, &req, &runtime_image, deadline).await?;
+            let image_digest = req.image_digest.clone()
+                .unwrap_or_else(|| tee::resolve_image_digest(&runtime_image));
+            let mut inputs = vec![repro::mounted_input("model", &req.model_cid, &format!("/tmp/artha/jobs/{}/model", req.job_id))];
+            if let Some(dataset_cid) = &req.dataset_cid {
+                inputs.push(repro::mounted_input("dataset", dataset_cid, &format!("/tmp/artha/jobs/{}/data", req.job_id)));
+            }
+            (container_id, gpu_id, attestation, image_digest, inputs, Some(pulling))
+        }
+    };
+    let pull = pulling.unwrap_or_default();
+    let startup = StartupTimings {
+        warm: pulling.is_none(),
+        pull_ms: pull.as_millis() as u64,
+        launch_ms: requested.elapsed().saturating_sub(pull).as_millis() as u64,
+    };
+
+    let execution = ExecutionRecord::capture(&req, &runtime_image, &image_digest, inputs, state.clock.now_secs());
+    let secrets = repro::secret_values(&req);
+    if !secrets.is_empty() {
+        state.job_secrets.write().await.insert(req.job_id.clone(), secrets);
+    }
     
-    // 3. Prepare checkpoint directory
-    let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", req.job_id);
-    std::fs::create_dir_all(&checkpoint_dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    // 4. Build container command
-    let runtime_image = get_runtime_image(&req.runtime);
-    let container_id = launch_container(
-        &runtime_image,
-        &req.job_id,
-        &model_mount,
-        dataset_mount.as_deref(),
-        &checkpoint_dir,
-        &gpu_id,
-        &req.params,
-    ).await?;
-    
-    println!("   Container: {} started", &container_id[..12]);
+    info!("   Container: {} started", &container_id[..12]);
     
-    // 5. Create job record
+    // 2. Create job record
     let job = Job {
         job_id: req.job_id.clone(),
         job_type: req.job_type.clone(),
@@ -176,14 +322,25 @@ async fn start_job(
         container_id: Some(container_id.clone()),
         status: ContainerStatus::Running,
         gpu_allocated: Some(gpu_id.clone()),
-        started_at: Some(now()),
-        logs: Vec::new(),
+        started_at: Some(state.clock.now_secs()),
+        logs: JobLog::new(state.log_limits),
         checkpoints: Vec::new(),
+        tee_required: req.tee_required,
+        attestation: attestation.clone(),
+        execution: Some(execution),
+        output_digests: None,
+        replay: None,
+        restart_policy: if req.tee_required { RestartPolicy::Never } else { req.restart_policy },
+        restart_count: 0,
+        startup: Some(startup.clone()),
+        checkpoint_times: Vec::new(),
+        max_runtime_secs: req.max_runtime_secs,
+        terminal_reason: None,
     };
     
     state.jobs.write().await.insert(req.job_id.clone(), job);
     
-    // 6. Start monitoring in background
+    // 3. Start monitoring in background
     let state_clone = state.clone();
     let job_id_clone = req.job_id.clone();
     let container_id_clone = container_id.clone();
@@ -196,22 +353,243 @@ async fn start_job(
         container_id,
         status: ContainerStatus::Running,
         gpu_allocated: gpu_id,
+        attestation,
+        startup,
     }))
 }
 
-async fn allocate_gpu(state: &Arc<AppState>, job_id: &str) -> Result<String, StatusCode> {
-    let mut allocations = state.gpu_allocations.write().await;
+/// Values of a job's vault secret references, checked against its submitter
+async fn resolve_secret_refs(state: &AppState, req: &StartJobRequest) -> Result<SensitiveEnv, SecretError> {
+    let vault = state.vault.as_ref()
+        .ok_or_else(|| SecretError::Unavailable("Job references secrets but this node has no vault".to_string()))?;
+    let did = req.submitter_did.as_deref()
+        .ok_or_else(|| SecretError::Unavailable("Job references secrets without a submitter DID".to_string()))?;
+    vault.resolve(did, &req.secret_refs).await
+}
+
+/// Link a model or dataset into the job directory from the node's mount
+/// cache, which fetches it only if no earlier job on this node has
+async fn mount_cached(state: &Arc<AppState>, cid: &str, job_id: &str, mount_path: &str) -> Result<(), String> {
+    let cached = state.mount_cache.acquire(cid, job_id, &state.svdb_client).await?;
+    mount_cache::link_mount(&cached, mount_path)
+}
+
+/// Pull image, mount inputs and create a fresh container (or TEE enclave).
+/// Also returns how long the inputs took to fetch.
+async fn cold_start(
+    state: &Arc<AppState>,
+    req: &StartJobRequest,
+    runtime_image: &str,
+    deadline: Deadline,
+) -> Result<(String, String, Option<AttestationQuote>, std::time::Duration), StatusCode> {
+    // 1. Allocate GPU
+    let gpu_id = allocate_gpu(state, &req.job_id).await?;
+    info!("   GPU:     {} allocated", gpu_id);
+    
+    // 2. Mount SVDB volumes
+    let pull_started = std::time::Instant::now();
+    let model_mount = format!("/tmp/artha/jobs/{}/model", req.job_id);
+    mount_cached(state, &req.model_cid, &req.job_id, &model_mount).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    
+    let dataset_mount = if let Some(dataset_cid) = &req.dataset_cid {
+        let path = format!("/tmp/artha/jobs/{}/data", req.job_id);
+        mount_cached(state, dataset_cid, &req.job_id, &path).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Some(path)
+    } else {
+        None
+    };
     
-    // Find first available GPU
-    for gpu_id in 0..8 {
-        let gpu_name = format!("gpu:{}", gpu_id);
-        if !allocations.contains_key(&gpu_name) {
-            allocations.insert(gpu_name.clone(), job_id.to_string());
-            return Ok(gpu_name);
+    // 3. Prepare checkpoint directory
+    let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", req.job_id);
+    std::fs::create_dir_all(&checkpoint_dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut env = repro::container_env(req);
+    if let Some(checkpoint_cid) = &req.resume_from {
+        let resume_dir = format!("/tmp/artha/jobs/{}/resume", req.job_id);
+        state.svdb_client.mount_volume(checkpoint_cid, &resume_dir).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        std::fs::rename(
+            format!("{}/{}", resume_dir, svdb::OBJECT_FILE),
+            format!("{}/{}", checkpoint_dir, migrate::RESUMED_CHECKPOINT),
+        ).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(checkpoint) = restart::latest_checkpoint(&checkpoint_dir) {
+            env.insert(restart::RESUME_ENV.to_string(), checkpoint.into());
         }
+        info!("   Resume:  {}", checkpoint_cid);
+    }
+    let pulling = pull_started.elapsed();
+
+    // Inputs can take long to pull; a caller gone by now gets no container
+    if deadline.has_passed(state.clock.now_millis()) {
+        info!("   ⌛ Deadline passed while mounting inputs, releasing {}", gpu_id);
+        state.gpu_allocations.write().await.remove(&gpu_id);
+        state.mount_cache.release(&req.job_id);
+        return Err(StatusCode::REQUEST_TIMEOUT);
     }
     
-    Err(StatusCode::SERVICE_UNAVAILABLE) // No GPUs available
+    // 4. Build container command
+    let (container_id, attestation) = if req.tee_required {
+        // TEE jobs must run under the enclave launcher and carry a quote
+        let launcher = state.tee_launcher.as_ref().ok_or_else(|| {
+            error!("   ❌ Job requires TEE but this node has no TEE launcher");
+            StatusCode::PRECONDITION_FAILED
+        })?;
+
+        let nonce = match &req.attestation_nonce {
+            Some(nonce) => nonce.clone(),
+            None => fetch_attestation_nonce(&state.proof_service_url, &req.job_id).await
+                .map_err(|_| StatusCode::BAD_GATEWAY)?,
+        };
+
+        let spec = TeeLaunchSpec {
+            image: runtime_image.to_string(),
+            image_digest: tee::resolve_image_digest(runtime_image),
+            job_id: req.job_id.clone(),
+            nonce,
+        };
+        info!("   TEE:     launching {} under {:?}", spec.image, launcher.tee_type());
+        let launch = launcher.launch(&spec).map_err(|e| {
+            error!("   ❌ TEE launch failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        info!("   TEE:     {:?} measurement {}", launch.quote.tee_type, launch.quote.measurement);
+        (launch.container_id, Some(launch.quote))
+    } else {
+        // Jobs pinned to a digest run exactly that image, not whatever the tag points at now
+        let image = match &req.image_digest {
+            Some(digest) => format!("{}@{}", pool::image_repository(runtime_image), digest),
+            None => runtime_image.to_string(),
+        };
+        let container_id = state.job_containers.launch(&ContainerLaunch {
+            image,
+            job_id: req.job_id.clone(),
+            model_mount,
+            dataset_mount,
+            checkpoint_dir,
+            gpu_id: gpu_id.clone(),
+            params: req.params.clone(),
+            env,
+        }).map_err(|e| {
+            error!("   ❌ {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        (container_id, None)
+    };
+
+    Ok((container_id, gpu_id, attestation, pulling))
+}
+
+/// Job spec handed to a claimed warm container; its entrypoint fetches the
+/// model and dataset from SVDB itself, since pool containers carry no mounts
+fn job_spec(state: &AppState, req: &StartJobRequest) -> JobSpec {
+    JobSpec {
+        job_id: req.job_id.clone(),
+        job_type: format!("{:?}", req.job_type),
+        model_cid: req.model_cid.clone(),
+        dataset_cid: req.dataset_cid.clone(),
+        params: serde_json::to_value(&req.params).unwrap_or_default(),
+        svdb_url: state.svdb_client.base_url.clone(),
+        env: repro::container_env(req),
+    }
+}
+
+/// Request an attestation nonce from ai-proofs so the quote is bound to
+/// a challenge the verifier issued
+async fn fetch_attestation_nonce(proof_service_url: &str, job_id: &str) -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/attestation/nonce", proof_service_url))
+        .json(&serde_json::json!({ "job_id": job_id }))
+        .send()
+        .await
+        .map_err(|e| format!("Nonce request failed: {}", e))?;
+
+    let result: serde_json::Value = response.json().await
+        .map_err(|e| format!("Failed to parse nonce response: {}", e))?;
+
+    result["nonce"].as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Missing nonce in response".to_string())
+}
+
+async fn allocate_gpu(state: &Arc<AppState>, job_id: &str) -> Result<String, StatusCode> {
+    loop {
+        {
+            let mut allocations = state.gpu_allocations.write().await;
+
+            // Find first available GPU
+            for gpu_id in 0..GPU_COUNT {
+                let gpu_name = format!("gpu:{}", gpu_id);
+                if !allocations.contains_key(&gpu_name) {
+                    allocations.insert(gpu_name.clone(), job_id.to_string());
+                    return Ok(gpu_name);
+                }
+            }
+        }
+
+        // Idle warm containers give up their GPU before a job is turned away
+        if !state.pools.evict_for_job().await {
+            return Err(StatusCode::SERVICE_UNAVAILABLE); // No GPUs available
+        }
+    }
+}
+
+/// Most free VRAM on any GPU not allocated to a job; None if no GPU could be read
+async fn largest_free_vram_mb(state: &Arc<AppState>) -> Option<u64> {
+    let allocations = state.gpu_allocations.read().await;
+    (0..GPU_COUNT)
+        .filter(|gpu_id| !allocations.contains_key(&format!("gpu:{}", gpu_id)))
+        .filter_map(|gpu_id| state.gpu_sampler.sample(gpu_id as u32).ok())
+        .map(|sample| sample.memory_total_mb.saturating_sub(sample.memory_used_mb))
+        .max()
+}
+
+/// Refuse a job whose estimated memory exceeds every free GPU, halving its
+/// batch size first if that is enough
+async fn fit_in_vram(state: &Arc<AppState>, req: &mut StartJobRequest, model: &ModelFootprint) -> Result<(), StatusCode> {
+    let Some(free_mb) = largest_free_vram_mb(state).await else {
+        warn!("   ⚠️  No GPU readings; launching {} without a memory check", req.job_id);
+        return Ok(());
+    };
+    let optimizer = match req.job_type {
+        JobType::Train => Some(req.params.optimizer.as_deref().unwrap_or("adam")),
+        _ => None,
+    };
+    let batch_size = req.params.batch_size.unwrap_or(1);
+    let fit = model.fit(optimizer, batch_size, free_mb).map_err(|e| {
+        error!("   ❌ {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    match fit {
+        Fit::Fits(estimate) => {
+            info!("   Memory:  ~{} MB of {} MB free", estimate.total_mb, free_mb);
+        }
+        Fit::Downshifted { from, estimate } => {
+            warn!("   ⚠️  Batch size {} needs more than {} MB free; downshifted to {} (~{} MB)",
+                from, free_mb, estimate.batch_size, estimate.total_mb);
+            req.params.batch_size = Some(estimate.batch_size);
+        }
+        Fit::TooLarge(estimate) => {
+            error!("   ❌ Job {} needs ~{} MB at batch size 1, largest free GPU has {} MB",
+                req.job_id, estimate.total_mb, free_mb);
+            return Err(StatusCode::INSUFFICIENT_STORAGE);
+        }
+    }
+    Ok(())
+}
+
+/// Capability handshake: can this node run a job with these requirements?
+async fn check_capabilities(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RuntimeRequirements>,
+) -> Json<CapabilityCheck> {
+    let check = state.capabilities.check(&req, state.tee_launcher.is_some());
+    if !check.satisfiable {
+        warn!("⚠️  Cannot satisfy {} requirements: {}", req.framework, check.unmet.join("; "));
+    }
+    Json(check)
 }
 
 fn get_runtime_image(runtime: &str) -> String {
@@ -222,132 +600,52 @@ fn get_runtime_image(runtime: &str) -> String {
         "agent" => "artha/agent-runtime:v1".to_string(),
         "cv" => "artha/cv-runtime:v1".to_string(),
         "sd" => "artha/sd-runtime:v1".to_string(),
+        "quantize" => "artha/quantize-runtime:v1".to_string(),
         _ => "artha/torch-runtime:v1".to_string(), // default
     }
 }
 
-async fn launch_container_legacy(
-    image: &str,
-    job_id: &str,
-    model_mount: &str,
-    dataset_mount: Option<&str>,
-    checkpoint_dir: &str,
-    gpu_id: &str,
-    params: &JobParams,
-) -> Result<String, StatusCode> {
-    // Build docker run command
-    let mut cmd = Command::new("docker");
-    cmd.arg("run")
-        .arg("-d") // detached
-        .arg("--name").arg(format!("artha-job-{}", job_id))
-        .arg("--gpus").arg(gpu_id.replace("gpu:", "device="))
-        .arg("--rm"); // auto-remove on exit
-    
-    // Mount volumes
-    cmd.arg("-v").arg(format!("{}:/model:ro", model_mount));
-    if let Some(data_path) = dataset_mount {
-        cmd.arg("-v").arg(format!("{}:/data:ro", data_path));
-    }
-    cmd.arg("-v").arg(format!("{}:/checkpoints:rw", checkpoint_dir));
-    
-    // Environment variables
-    cmd.arg("-e").arg(format!("ARTHA_JOB_ID={}", job_id));
-    if let Some(epochs) = params.epochs {
-        cmd.arg("-e").arg(format!("EPOCHS={}", epochs));
-    }
-    if let Some(batch_size) = params.batch_size {
-        cmd.arg("-e").arg(format!("BATCH_SIZE={}", batch_size));
-    }
-    if let Some(lr) = params.learning_rate {
-        cmd.arg("-e").arg(format!("LEARNING_RATE={}", lr));
-    }
-    if let Some(optimizer) = &params.optimizer {
-        cmd.arg("-e").arg(format!("OPTIMIZER={}", optimizer));
-    }
-    if let Some(interval) = params.checkpoint_interval {
-        cmd.arg("-e").arg(format!("CHECKPOINT_INTERVAL={}", interval));
-    }
-    
-    // Image
-    cmd.arg(image);
-    
-    // Execute command
-    let output = cmd.output().map_err(|e| {
-        eprintln!("Failed to launch container: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-    
-    if !output.status.success() {
-        eprintln!("Docker run failed: {}", String::from_utf8_lossy(&output.stderr));
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-    
-    let container_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    Ok(container_id)
+/// What a monitor tick found the job's container doing
+#[derive(Debug, PartialEq)]
+enum ContainerPoll {
+    Running,
+    Restarted(String), // Crashed and relaunched as this container
+    Finished,
 }
 
 async fn monitor_job(state: &Arc<AppState>, job_id: &str, container_id: &str) {
-    println!("👁️  Monitoring job: {}", job_id);
+    info!("👁️  Monitoring job: {}", job_id);
     
     let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", job_id);
     let mut checkpoint_count = 0;
+    let mut container_id = container_id.to_string();
+    let mut log_cursor = None;
     
     loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-        
-        // Check container status
-        let status_output = Command::new("docker")
-            .args(&["inspect", "-f", "{{.State.Status}}", container_id])
-            .output();
+        state.clock.sleep(std::time::Duration::from_secs(10)).await;
+
+        collect_gpu_telemetry(state, job_id, state.clock.now_secs()).await;
+        // Before polling, which removes a stopped container along with its output
+        collect_container_logs(state, job_id, &container_id, &mut log_cursor).await;
         
-        match status_output {
-            Ok(output) => {
-                let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                
-                if status == "exited" || status == "dead" {
-                    println!("✅ Job {} completed", job_id);
-                    
-                    // Update job status
-                    if let Ok(mut jobs) = state.jobs.write().await.try_lock() {
-                        if let Some(job) = jobs.get_mut(job_id) {
-                            job.status = ContainerStatus::Completed;
-                            
-                            // Upload all checkpoints to SVDB
-                            if let Ok(entries) = std::fs::read_dir(&checkpoint_dir) {
-                                for entry in entries.flatten() {
-                                    if let Ok(path) = entry.path().to_str().ok_or("") {
-                                        if let Ok(cid) = state.svdb_client.upload_checkpoint(path).await {
-                                            job.checkpoints.push(cid);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    
-                    // Release GPU
-                    if let Ok(mut allocations) = state.gpu_allocations.write().await.try_lock() {
-                        allocations.retain(|_, v| v != job_id);
-                    }
-                    
-                    // Notify proof service
-                    notify_proof_service(&state.proof_service_url, job_id).await;
-                    
-                    break;
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to check container status: {}", e);
-                break;
+        match poll_container(state, job_id, &container_id).await {
+            ContainerPoll::Running => {}
+            ContainerPoll::Restarted(restarted) => {
+                container_id = restarted;
+                continue;
             }
+            ContainerPoll::Finished => break,
         }
         
         // Check for new checkpoints
         if let Ok(entries) = std::fs::read_dir(&checkpoint_dir) {
             let count = entries.count();
             if count > checkpoint_count {
-                println!("💾 New checkpoint detected for job {}", job_id);
+                info!("💾 New checkpoint detected for job {}", job_id);
                 checkpoint_count = count;
+                if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+                    job.checkpoint_times.push(state.clock.now_secs());
+                }
                 
                 // Upload checkpoint to SVDB in background
                 let svdb_client = state.svdb_client.clone();
@@ -357,20 +655,312 @@ async fn monitor_job(state: &Arc<AppState>, job_id: &str, container_id: &str) {
                 });
             }
         }
-        
-        // Collect logs (last 100 lines)
-        if let Ok(log_output) = Command::new("docker")
-            .args(&["logs", "--tail", "100", container_id])
-            .output()
-        {
-            let logs = String::from_utf8_lossy(&log_output.stdout);
-            if let Ok(mut jobs) = state.jobs.write().await.try_lock() {
-                if let Some(job) = jobs.get_mut(job_id) {
-                    job.logs = logs.lines().map(|s| s.to_string()).collect();
+    }
+}
+
+/// Append the container's output since `cursor`, the timestamp of the last
+/// line taken, to the job's log
+async fn collect_container_logs(state: &Arc<AppState>, job_id: &str, container_id: &str, cursor: &mut Option<String>) {
+    let mut cmd = Command::new("docker");
+    cmd.args(["logs", "--timestamps"]);
+    if let Some(since) = cursor.as_deref() {
+        cmd.args(["--since", since]);
+    }
+    let Ok(output) = cmd.arg(container_id).output() else {
+        return;
+    };
+    let output = String::from_utf8_lossy(&output.stdout);
+    let lines = new_log_lines(&output, cursor.as_deref());
+    let Some((last, _)) = lines.last() else {
+        return;
+    };
+    *cursor = Some(last.to_string());
+    if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+        for (_, line) in lines {
+            job.logs.push(line);
+        }
+    }
+}
+
+/// `(timestamp, line)` pairs of `docker logs --timestamps` output stamped
+/// after `after`. Docker stamps lines with fixed-width RFC 3339 UTC times, so
+/// they order as strings; `--since` is inclusive, hence the filter.
+fn new_log_lines<'a>(output: &'a str, after: Option<&str>) -> Vec<(&'a str, &'a str)> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(stamp, _)| after.is_none_or(|after| *stamp > after))
+        .collect()
+}
+
+/// How often queued log lines are archived to SVDB
+const LOG_ARCHIVE_INTERVAL_SECS: u64 = 10;
+
+/// Upload every job's queued log lines to SVDB as one chunk per job. Lines
+/// whose upload fails go back on the queue for the next pass.
+async fn archive_job_logs(state: &AppState) -> usize {
+    let batches: Vec<_> = {
+        let mut jobs = state.jobs.write().await;
+        jobs.values_mut()
+            .filter_map(|job| job.logs.take_pending().map(|chunk| (job.job_id.clone(), chunk)))
+            .collect()
+    };
+
+    let mut archived = 0;
+    for (job_id, chunk) in batches {
+        let uploaded = state.svdb_client.upload(chunk.encode()).await;
+        let mut jobs = state.jobs.write().await;
+        let Some(job) = jobs.get_mut(&job_id) else { continue };
+        match uploaded {
+            Ok(cid) => {
+                job.logs.stored(cid);
+                archived += 1;
+            }
+            Err(e) => {
+                warn!("⚠️  Archiving logs of {} failed: {}", job_id, e);
+                job.logs.restore(chunk);
+            }
+        }
+    }
+    archived
+}
+
+/// One monitor tick: complete the job on a clean exit, restart it per its
+/// policy on a crash, or mark it Failed once the policy is exhausted or the
+/// job has run past its time limit
+async fn poll_container(state: &Arc<AppState>, job_id: &str, container_id: &str) -> ContainerPoll {
+    // A migration owns the container until the job has been exported
+    if state.jobs.read().await.get(job_id).is_some_and(|job| job.status == ContainerStatus::Migrating) {
+        return ContainerPoll::Running;
+    }
+    let exit = match state.job_containers.exit(container_id) {
+        Ok(None) => {
+            let Some(limit) = overran(state, job_id, state.clock.now_secs()).await else {
+                return ContainerPoll::Running;
+            };
+            state.job_containers.remove(container_id);
+            fail_job(state, job_id, TerminalReason::new(
+                TerminalReasonKind::Timeout,
+                format!("Job ran past its {}s time limit", limit),
+            )).await;
+            return ContainerPoll::Finished;
+        }
+        Ok(Some(exit)) => exit,
+        Err(e) => {
+            warn!("Failed to check container status: {}", e);
+            return ContainerPoll::Finished;
+        }
+    };
+    state.job_containers.remove(container_id);
+
+    let Some((status, policy, restarts)) = state.jobs.read().await.get(job_id)
+        .map(|job| (job.status.clone(), job.restart_policy, job.restart_count))
+    else {
+        return ContainerPoll::Finished;
+    };
+    if status != ContainerStatus::Running {
+        return ContainerPoll::Finished; // Stopped on request, not crashed
+    }
+
+    if exit.code == 0 {
+        complete_job(state, job_id).await;
+        return ContainerPoll::Finished;
+    }
+    if !policy.allows_restart(restarts) {
+        fail_job(state, job_id, TerminalReason::exited(exit, format!("{} after {} restarts", exit, restarts))).await;
+        return ContainerPoll::Finished;
+    }
+
+    match restart_container(state, job_id).await {
+        Ok(restarted) => {
+            info!("♻️  Job {}: {}, restarted as {}", job_id, exit, restarted);
+            ContainerPoll::Restarted(restarted)
+        }
+        Err(e) => {
+            fail_job(state, job_id, TerminalReason::exited(exit, format!("{} and could not be restarted: {}", exit, e))).await;
+            ContainerPoll::Finished
+        }
+    }
+}
+
+/// The running job's time limit, if `now` is past it
+async fn overran(state: &AppState, job_id: &str, now: u64) -> Option<u64> {
+    let jobs = state.jobs.read().await;
+    let job = jobs.get(job_id).filter(|job| job.status == ContainerStatus::Running)?;
+    let limit = job.max_runtime_secs?;
+    (now >= job.started_at? + limit).then_some(limit)
+}
+
+/// Relaunch a crashed job's container from its execution record, resuming
+/// from the latest checkpoint
+async fn restart_container(state: &Arc<AppState>, job_id: &str) -> Result<String, String> {
+    let job = state.jobs.read().await.get(job_id).cloned().ok_or("Job no longer exists")?;
+    let execution = job.execution.as_ref().ok_or("Job has no execution record")?;
+    let gpu_id = job.gpu_allocated.clone().ok_or("Job holds no GPU")?;
+
+    // Warm-claimed containers fetched their own inputs, so mount them now
+    let model_mount = format!("/tmp/artha/jobs/{}/model", job_id);
+    if !std::path::Path::new(&model_mount).exists() {
+        mount_cached(state, &job.model_cid, job_id, &model_mount).await
+            .map_err(|e| format!("Model mount failed: {}", e))?;
+    }
+    let dataset_mount = match &job.dataset_cid {
+        Some(dataset_cid) => {
+            let path = format!("/tmp/artha/jobs/{}/data", job_id);
+            if !std::path::Path::new(&path).exists() {
+                mount_cached(state, dataset_cid, job_id, &path).await
+                    .map_err(|e| format!("Dataset mount failed: {}", e))?;
+            }
+            Some(path)
+        }
+        None => None,
+    };
+    let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", job_id);
+    std::fs::create_dir_all(&checkpoint_dir).map_err(|e| e.to_string())?;
+
+    let mut env = repro::sensitive_env(execution.plain_env());
+    env.extend(state.job_secrets.read().await.get(job_id).cloned().unwrap_or_default());
+    if let Some(seed) = execution.seed {
+        env.extend(repro::sensitive_env(repro::seed_env(seed, &execution.runtime)));
+    }
+    if let Some(checkpoint) = restart::latest_checkpoint(&checkpoint_dir) {
+        env.insert(restart::RESUME_ENV.to_string(), checkpoint.into());
+    }
+
+    let container_id = state.job_containers.launch(&ContainerLaunch {
+        image: execution.pinned_image(),
+        job_id: job_id.to_string(),
+        model_mount,
+        dataset_mount,
+        checkpoint_dir,
+        gpu_id,
+        params: execution.params.clone(),
+        env,
+    })?;
+
+    if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+        job.restart_count += 1;
+        job.container_id = Some(container_id.clone());
+        job.logs.push(format!("Restart {} of {}", job.restart_count, String::from(job.restart_policy)));
+    }
+    Ok(container_id)
+}
+
+/// Record outputs, upload checkpoints, free the GPU and hand off to ai-proofs
+async fn complete_job(state: &Arc<AppState>, job_id: &str) {
+    info!("✅ Job {} completed", job_id);
+
+    let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", job_id);
+    let mut checkpoints = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&checkpoint_dir) {
+        for entry in entries.flatten() {
+            if let Some(path) = entry.path().to_str() {
+                if let Ok(cid) = state.svdb_client.upload_checkpoint(path).await {
+                    checkpoints.push(cid);
                 }
             }
         }
     }
+
+    let infer = match state.jobs.write().await.get_mut(job_id) {
+        Some(job) => {
+            job.status = ContainerStatus::Completed;
+            job.output_digests = Some(repro::output_digests(&checkpoint_dir));
+            job.checkpoints.extend(checkpoints);
+            matches!(job.job_type, JobType::Infer)
+        }
+        None => false,
+    };
+    state.gpu_allocations.write().await.retain(|_, v| v != job_id);
+    state.mount_cache.release(job_id);
+
+    if infer {
+        report_output(state, job_id, &format!("/tmp/artha/jobs/{}/output", job_id)).await;
+    }
+    notify_proof_service(&state.proof_service_url, job_id).await;
+}
+
+/// Upload an infer job's output and hand ai-jobd its CID with the blake3
+/// digest of its bytes, signed by this node, so clients can check what they
+/// download against what was computed here
+async fn report_output(state: &AppState, job_id: &str, path: &str) {
+    let Ok(output) = std::fs::read(path) else {
+        warn!("⚠️  Job {} left no output at {}", job_id, path);
+        return;
+    };
+    let output_digest = blake3::hash(&output).to_hex().to_string();
+    let output_cid = match state.svdb_client.upload(output).await {
+        Ok(cid) => cid,
+        Err(e) => {
+            warn!("⚠️  Output upload of job {} failed: {}", job_id, e);
+            return;
+        }
+    };
+    let result = reqwest::Client::new()
+        .post(format!("{}/job/{}/progress", state.jobd_url, job_id))
+        .json(&serde_json::json!({
+            "progress": 1.0,
+            "output_signature": sign_output(&state.node_key, job_id, &output_cid, &output_digest),
+            "output_cid": output_cid,
+            "output_digest": output_digest,
+        }))
+        .send()
+        .await;
+    if !matches!(result, Ok(resp) if resp.status().is_success()) {
+        warn!("⚠️  ai-jobd did not take the output of job {}", job_id);
+    }
+}
+
+/// Mark the job Failed, free what it held and tell ai-jobd why it stopped
+async fn fail_job(state: &Arc<AppState>, job_id: &str, reason: TerminalReason) {
+    error!("❌ Job {} failed: {}", job_id, reason.detail);
+    if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+        job.status = ContainerStatus::Failed;
+        job.logs.push(reason.detail.clone());
+        job.terminal_reason = Some(reason.clone());
+    }
+    state.gpu_allocations.write().await.retain(|_, v| v != job_id);
+    state.mount_cache.release(job_id);
+    report_failure(&state.jobd_url, job_id, &reason).await;
+}
+
+/// Sample every GPU allocated to the job and fold the readings into its
+/// downsampled series, flagging the job if it has gone underutilized
+async fn collect_gpu_telemetry(state: &Arc<AppState>, job_id: &str, timestamp: u64) {
+    let devices: Vec<String> = state
+        .gpu_allocations
+        .read()
+        .await
+        .iter()
+        .filter(|(_, job)| job.as_str() == job_id)
+        .map(|(gpu_id, _)| gpu_id.clone())
+        .collect();
+    if devices.is_empty() {
+        return;
+    }
+
+    let mut all_telemetry = state.gpu_telemetry.write().await;
+    let telemetry = all_telemetry
+        .entry(job_id.to_string())
+        .or_insert_with(|| JobTelemetry::new(job_id));
+
+    for gpu_id in &devices {
+        let Some(device) = telemetry::device_index(gpu_id) else { continue };
+        match state.gpu_sampler.sample(device) {
+            Ok(sample) => telemetry.record(gpu_id, &sample, timestamp, &state.telemetry_config),
+            Err(e) => {
+                telemetry.sample_errors += 1;
+                warn!("⚠️  GPU telemetry for {} failed: {}", gpu_id, e);
+            }
+        }
+    }
+
+    if let Some(alert) = telemetry.check_utilization(timestamp, &state.telemetry_config) {
+        warn!(
+            "⚠️  Job {} underutilized: {:.1}% average GPU utilization (threshold {:.0}%), check its batch size and input pipeline",
+            job_id, alert.avg_utilization_pct, alert.threshold_pct
+        );
+    }
 }
 
 async fn notify_proof_service(proof_service_url: &str, job_id: &str) {
@@ -383,42 +973,329 @@ async fn notify_proof_service(proof_service_url: &str, job_id: &str) {
         .send()
         .await;
     
-    println!("📊 Notified proof service for job {}", job_id);
+    info!("📊 Notified proof service for job {}", job_id);
 }
 
 async fn stop_job(
     State(state): State<Arc<AppState>>,
     Path(job_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    println!("🛑 Stopping job: {}", job_id);
+    info!("🛑 Stopping job: {}", job_id);
     
-    let jobs = state.jobs.read().await;
-    let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    // Marked first so the monitor doesn't take the exit for a crash and restart it
+    let job = {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        job.status = ContainerStatus::Stopped;
+        job.clone()
+    };
     
     if let Some(container_id) = &job.container_id {
         let _ = Command::new("docker")
             .args(&["stop", container_id])
             .output();
         
-        println!("   Container stopped");
+        info!("   Container stopped");
     }
     
-    // Release GPU
+    // Release GPU and cached inputs
     if let Some(gpu_id) = &job.gpu_allocated {
         state.gpu_allocations.write().await.remove(gpu_id);
     }
-    
+    state.mount_cache.release(&job_id);
+    if let Some(stream) = state.streams.read().await.get(&job_id) {
+        stream.stop();
+    }
+
     Ok(StatusCode::OK)
 }
 
+/// How often an idle stream is polled for new events
+const STREAM_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// POST /job/stream - Start a streaming transform. It runs until stopped,
+/// committing every completed window as a version of its output dataset.
+async fn start_stream_job(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<StartStreamRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    req.spec.validate().map_err(|e| {
+        error!("❌ Invalid stream job {}: {}", req.job_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    if state.jobs.read().await.contains_key(&req.job_id) {
+        return Err(StatusCode::CONFLICT);
+    }
+    info!("🌊 Starting stream job: {}", req.job_id);
+    info!("   Input:   {}", req.spec.input_stream);
+    info!("   Output:  {}", req.spec.output.dataset_name(&req.job_id));
+
+    let dir = std::path::PathBuf::from(format!("/tmp/artha/jobs/{}/stream", req.job_id));
+    let (transform_ref, make_transform) = stream_transform(&state, &req.spec.transform, &dir).await?;
+    let job = Job {
+        job_id: req.job_id.clone(),
+        job_type: JobType::Stream,
+        model_cid: transform_ref,
+        dataset_cid: None,
+        params: JobParams {
+            epochs: None,
+            batch_size: None,
+            learning_rate: None,
+            optimizer: None,
+            checkpoint_interval: None,
+            max_tokens: None,
+        },
+        container_id: None,
+        status: ContainerStatus::Running,
+        gpu_allocated: None,
+        started_at: Some(state.clock.now_secs()),
+        logs: JobLog::new(state.log_limits),
+        checkpoints: Vec::new(),
+        tee_required: false,
+        attestation: None,
+        execution: None,
+        output_digests: None,
+        replay: None,
+        restart_policy: RestartPolicy::Never, // Transform crashes restart inside the stream
+        restart_count: 0,
+        startup: None,
+        checkpoint_times: Vec::new(),
+        max_runtime_secs: None,
+        terminal_reason: None,
+    };
+    state.jobs.write().await.insert(req.job_id.clone(), job);
+
+    let handle = Arc::new(StreamHandle::default());
+    state.streams.write().await.insert(req.job_id.clone(), handle.clone());
+    let run = StreamRun {
+        job_id: req.job_id.clone(),
+        spec: req.spec,
+        dir,
+        sink: WindowSink { svdb: state.svdb_client.clone(), jobd_url: state.jobd_url.clone() },
+        handle,
+        poll_interval: STREAM_POLL_INTERVAL,
+        clock: state.clock.clone(),
+    };
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run.run(&make_transform).await {
+            fail_job(&task_state, &run.job_id, TerminalReason::new(TerminalReasonKind::RuntimeError, e)).await;
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "job_id": req.job_id,
+        "status": ContainerStatus::Running,
+    })))
+}
+
+/// The transform a stream job runs. WASM modules are fetched and compiled up
+/// front so a bad module is refused before the job starts.
+async fn stream_transform(
+    state: &AppState,
+    spec: &TransformSpec,
+    dir: &std::path::Path,
+) -> Result<(String, TransformFactory), StatusCode> {
+    match spec.clone() {
+        TransformSpec::Wasm { module_cid, gas_limit } => {
+            let path = dir.join("transform.wasm");
+            std::fs::create_dir_all(dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            state.svdb_client.fetch(&module_cid, &path).await.map_err(|e| {
+                error!("   ❌ Transform module fetch failed: {}", e);
+                StatusCode::BAD_GATEWAY
+            })?;
+            let wasm = std::fs::read(&path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            WasmTransform::new(&wasm, gas_limit).map_err(|e| {
+                error!("   ❌ {}", e);
+                StatusCode::BAD_REQUEST
+            })?;
+            let factory: TransformFactory = Box::new(move || Ok(Box::new(WasmTransform::new(&wasm, gas_limit)?) as Box<dyn Transform>));
+            Ok((module_cid, factory))
+        }
+        TransformSpec::Container { image, args } => {
+            let factory: TransformFactory = {
+                let image = image.clone();
+                Box::new(move || Ok(Box::new(ContainerTransform::spawn(&image, &args)?) as Box<dyn Transform>))
+            };
+            Ok((image, factory))
+        }
+    }
+}
+
+/// Tell ai-jobd a job has stopped for good
+async fn report_failure(jobd_url: &str, job_id: &str, reason: &TerminalReason) {
+    let result = reqwest::Client::new()
+        .post(format!("{}/job/{}/progress", jobd_url, job_id))
+        .json(&serde_json::json!({
+            "progress": 0.0,
+            "status": "Failed",
+            "failure_reason": reason.detail,
+            "terminal_reason": reason,
+        }))
+        .send()
+        .await;
+    if !matches!(result, Ok(resp) if resp.status().is_success()) {
+        warn!("⚠️  ai-jobd did not take the failure of job {}", job_id);
+    }
+}
+
+/// POST /job/:id/migrate - Checkpoint a running job on request and hand it to
+/// ai-jobd for placement on another node. Answers once the container has been
+/// asked; the export and handoff finish in the background.
+async fn migrate_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Json(req): Json<MigrateRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let container_id = begin_migration(&state, &job_id, &req).await?;
+    tokio::spawn(async move {
+        if let Ok(handoff) = finish_migration(&state, &job_id, &container_id, &req).await {
+            report_migration(&state.jobd_url, &job_id, &handoff).await;
+        }
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Mark the job Migrating and drop the control file into its checkpoint mount
+async fn begin_migration(state: &Arc<AppState>, job_id: &str, req: &MigrateRequest) -> Result<String, StatusCode> {
+    let container_id = {
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(job_id).ok_or(StatusCode::NOT_FOUND)?;
+        if job.status != ContainerStatus::Running {
+            return Err(StatusCode::CONFLICT);
+        }
+        let container_id = job.container_id.clone().ok_or(StatusCode::CONFLICT)?;
+        job.status = ContainerStatus::Migrating;
+        job.logs.push(format!("Migration requested by {}: {}", req.initiated_by, req.reason));
+        container_id
+    };
+    info!("🚚 Migrating job {} ({})", job_id, req.reason);
+
+    let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", job_id);
+    let request = serde_json::to_vec(req).unwrap_or_default();
+    if let Err(e) = std::fs::write(migrate::control_file(&checkpoint_dir), request) {
+        // Nothing will answer; the wait falls through to the degraded path
+        warn!("   ⚠️  Cannot write migration control file: {}", e);
+    }
+    Ok(container_id)
+}
+
+/// Wait for the container to checkpoint and exit, stopping it at the timeout,
+/// then upload the checkpoint the job resumes from
+async fn finish_migration(
+    state: &Arc<AppState>,
+    job_id: &str,
+    container_id: &str,
+    req: &MigrateRequest,
+) -> Result<MigrationHandoff, StatusCode> {
+    let requested_at = state.clock.now_secs();
+    let checkpoint_dir = format!("/tmp/artha/jobs/{}/checkpoints", job_id);
+    let deadline = state.clock.now_millis() + req.timeout().as_millis() as u64;
+    let exit_code = loop {
+        match state.job_containers.exit(container_id) {
+            Ok(Some(exit)) => break Some(exit.code),
+            Ok(None) if state.clock.now_millis() < deadline => state.clock.sleep(migrate::POLL_INTERVAL).await,
+            _ => break None,
+        }
+    };
+    let _ = std::fs::remove_file(migrate::control_file(&checkpoint_dir));
+
+    if exit_code == Some(0) {
+        // Finished before it could move; the monitor completes it as usual
+        if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+            job.status = ContainerStatus::Running;
+        }
+        return Err(StatusCode::CONFLICT);
+    }
+    let mode = if exit_code == Some(migrate::MIGRATE_EXIT_CODE) {
+        MigrationMode::Cooperative
+    } else {
+        info!("   ⏱️  Job {} did not checkpoint on request, stopping at its last checkpoint", job_id);
+        MigrationMode::Degraded
+    };
+    state.job_containers.remove(container_id);
+
+    // Uploaded before anyone is told: the next node resumes from it
+    let checkpoint = restart::newest_checkpoint(&checkpoint_dir);
+    let resume_from = match &checkpoint {
+        Some(name) => match state.svdb_client.upload_checkpoint(&format!("{}/{}", checkpoint_dir, name)).await {
+            Ok(cid) => Some(cid),
+            Err(e) => {
+                fail_job(state, job_id, TerminalReason::new(
+                    TerminalReasonKind::RuntimeError,
+                    format!("Migration checkpoint upload failed: {}", e),
+                )).await;
+                return Err(StatusCode::BAD_GATEWAY);
+            }
+        },
+        None => None,
+    };
+
+    let handoff = MigrationHandoff {
+        initiated_by: req.initiated_by.clone(),
+        reason: req.reason.clone(),
+        mode,
+        steps_lost: (mode == MigrationMode::Cooperative && checkpoint.is_some()).then_some(0),
+        checkpoint,
+        resume_from,
+        requested_at,
+        exported_at: state.clock.now_secs(),
+    };
+    if let Some(job) = state.jobs.write().await.get_mut(job_id) {
+        job.status = ContainerStatus::Migrated;
+        job.checkpoints.extend(handoff.resume_from.clone());
+        job.logs.push(format!("Exported for migration ({:?})", mode));
+    }
+    state.gpu_allocations.write().await.retain(|_, v| v != job_id);
+    state.mount_cache.release(job_id);
+    Ok(handoff)
+}
+
+/// Report the export to ai-jobd as a `migratable` completion
+async fn report_migration(jobd_url: &str, job_id: &str, handoff: &MigrationHandoff) {
+    let result = reqwest::Client::new()
+        .post(format!("{}/job/{}/progress", jobd_url, job_id))
+        .json(&serde_json::json!({ "progress": 0.0, "migratable": handoff }))
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => info!("📨 Job {} handed to ai-jobd for re-placement", job_id),
+        _ => warn!("⚠️  ai-jobd did not take migrated job {}", job_id),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogQuery {
+    #[serde(default)]
+    pub full: bool, // Every line from SVDB instead of the in-memory tail
+}
+
+/// GET /job/:id/logs - The newest lines, or with `full=true` the complete log:
+/// the archived chunks in order followed by lines not yet archived
 async fn get_job_logs(
     State(state): State<Arc<AppState>>,
     Path(job_id): Path<String>,
+    Query(query): Query<LogQuery>,
 ) -> Result<Json<Vec<String>>, StatusCode> {
-    let jobs = state.jobs.read().await;
-    let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
-    
-    Ok(Json(job.logs.clone()))
+    let (chunks, pending) = {
+        let jobs = state.jobs.read().await;
+        let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+        if !query.full {
+            return Ok(Json(job.logs.to_vec()));
+        }
+        (job.logs.chunks().to_vec(), job.logs.pending().cloned().collect::<Vec<_>>())
+    };
+
+    let mut lines = Vec::new();
+    for cid in &chunks {
+        let bytes = state.svdb_client.read(cid).await.map_err(|e| {
+            warn!("⚠️  Reading log chunk {} of {} failed: {}", cid, job_id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+        lines.extend(artha_joblog::decode_chunk(&bytes));
+    }
+    lines.extend(pending);
+    Ok(Json(lines))
 }
 
 async fn get_job_status(
@@ -426,9 +1303,162 @@ async fn get_job_status(
     Path(job_id): Path<String>,
 ) -> Result<Json<Job>, StatusCode> {
     let jobs = state.jobs.read().await;
-    let job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut job = jobs.get(&job_id).ok_or(StatusCode::NOT_FOUND)?.clone();
+
+    // Replays are diffed on read, since either job may finish last
+    if let Some(replay) = job.replay.as_mut() {
+        let original = jobs.get(&replay.original_job_id).and_then(|o| o.output_digests.clone());
+        replay.compare(original.as_deref(), job.output_digests.as_deref());
+    }
     
-    Ok(Json(job.clone()))
+    Ok(Json(job))
+}
+
+/// GET /job/:id/repro-bundle - Pinned image, input hashes, redacted env and
+/// generated replay.sh / docker-compose.yml for re-running the job locally
+async fn get_repro_bundle(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<ReproBundle>, StatusCode> {
+    let job = state.jobs.read().await.get(&job_id).cloned().ok_or(StatusCode::NOT_FOUND)?;
+    let execution = job.execution.as_ref().ok_or(StatusCode::CONFLICT)?; // Started before execution records
+    let bundle = ReproBundle::build(
+        &job.job_id,
+        job.replay.as_ref().map(|r| r.original_job_id.as_str()),
+        execution,
+        &job.model_cid,
+        job.dataset_cid.as_deref(),
+    );
+
+    let secrets = state.job_secrets.read().await.get(&job_id).cloned().unwrap_or_default();
+    bundle.verify_redaction(&secrets).map_err(|e| {
+        error!("   ❌ Repro bundle for {} withheld: {}", job_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(bundle))
+}
+
+/// POST /job/replay - Re-run a historical job on the cluster with the same
+/// pinned image, inputs, params, env and seed
+async fn replay_job(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ReplayRequest>,
+) -> Result<Json<StartJobResponse>, StatusCode> {
+    let original = state.jobs.read().await.get(&req.job_id).cloned().ok_or(StatusCode::NOT_FOUND)?;
+    let execution = original.execution.ok_or(StatusCode::CONFLICT)?;
+
+    // A replay on a different image proves nothing
+    if !state.image_store.has_digest(&execution.image, &execution.image_digest) {
+        error!("   ❌ Cannot replay {}: image {} no longer available", req.job_id, execution.pinned_image());
+        return Err(StatusCode::GONE);
+    }
+
+    let replay_job_id = req.replay_job_id
+        .unwrap_or_else(|| format!("{}-replay-{}", req.job_id, random_hash(&*state.entropy)));
+    if state.jobs.read().await.contains_key(&replay_job_id) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let secret_env = state.job_secrets.read().await.get(&req.job_id).cloned().unwrap_or_default();
+    let start = StartJobRequest {
+        job_id: replay_job_id.clone(),
+        job_type: execution.job_type.clone(),
+        model_cid: original.model_cid,
+        dataset_cid: original.dataset_cid,
+        params: execution.params.clone(),
+        runtime: execution.runtime.clone(),
+        tee_required: execution.tee_required,
+        attestation_nonce: None,
+        image_digest: Some(execution.image_digest.clone()),
+        env: execution.plain_env(),
+        secret_env,
+        secret_refs: Vec::new(), // Resolved for the original and kept with its secrets
+        submitter_did: None,
+        seed: execution.seed,
+        restart_policy: original.restart_policy,
+        resume_from: None,
+        max_runtime_secs: original.max_runtime_secs,
+        model: None, // The recorded batch size already fit
+    };
+    let response = start_job(State(state.clone()), HeaderMap::new(), Json(start)).await?;
+
+    if let Some(job) = state.jobs.write().await.get_mut(&replay_job_id) {
+        job.replay = Some(ReplayStatus::new(&req.job_id));
+    }
+    info!("🔁 Job {} replays {}", replay_job_id, req.job_id);
+
+    Ok(response)
+}
+
+/// GET /job/:id/gpu-telemetry - Downsampled utilization, memory and power per
+/// device, plus throughput and lag for stream jobs
+async fn get_gpu_telemetry(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobTelemetry>, StatusCode> {
+    let stream = state.streams.read().await.get(&job_id).map(|s| s.metrics());
+    if let Some(telemetry) = state.gpu_telemetry.read().await.get(&job_id) {
+        return Ok(Json(JobTelemetry { stream, ..telemetry.clone() }));
+    }
+
+    // Known jobs that have not been sampled yet report an empty series
+    if state.jobs.read().await.contains_key(&job_id) {
+        Ok(Json(JobTelemetry { stream, ..JobTelemetry::new(&job_id) }))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// GET /pools - Warm pool depth, claim/cold-start counters and GPU reservations
+async fn get_pools(
+    State(state): State<Arc<AppState>>,
+) -> Json<pool::PoolStats> {
+    Json(state.pools.stats().await)
+}
+
+/// GET /mount-cache - Cached model/dataset copies, the jobs holding them and hit counters
+async fn get_mount_cache(
+    State(state): State<Arc<AppState>>,
+) -> Json<MountCacheStats> {
+    Json(state.mount_cache.stats())
+}
+
+/// The scheduler identifies a node by its compressed secp256k1 public key
+fn node_pubkey(key: &SigningKey) -> String {
+    format!("0x{}", hex::encode(key.verifying_key().to_encoded_point(true).as_bytes()))
+}
+
+/// Signature binding an infer job's output to this node:
+/// `OUTPUT:{job_id}:{output_cid}:{blake3(output) hex}`
+fn sign_output(key: &SigningKey, job_id: &str, output_cid: &str, output_digest: &str) -> String {
+    let signature: Signature = key.sign(format!("OUTPUT:{}:{}:{}", job_id, output_cid, output_digest).as_bytes());
+    hex::encode(signature.to_bytes())
+}
+
+/// Signature headers proving a heartbeat body came from this node:
+/// `HEARTBEAT:{pubkey}:{sha256(body) hex}:TS:{timestamp}`
+fn heartbeat_headers(key: &SigningKey, body: &[u8], timestamp: u64) -> [(&'static str, String); 2] {
+    let message = format!("HEARTBEAT:{}:{}:TS:{}", node_pubkey(key), repro::sha256_hex(body), timestamp);
+    let signature: Signature = key.sign(message.as_bytes());
+    [("X-Artha-Timestamp", timestamp.to_string()), ("X-Artha-Signature", hex::encode(signature.to_bytes()))]
+}
+
+/// Report capacity to the scheduler, counting warm pool GPUs as reserved.
+/// The scheduler deregisters nodes whose signed heartbeats stop.
+async fn send_heartbeat(scheduler_url: &str, key: &SigningKey, capacity: &CapacityReport, now: u64) {
+    let Ok(body) = serde_json::to_vec(capacity) else { return };
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/nodes/{}/heartbeat", scheduler_url, node_pubkey(key)))
+        .header("content-type", "application/json");
+    for (name, value) in heartbeat_headers(key, &body, now) {
+        request = request.header(name, value);
+    }
+    match request.body(body).send().await {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => warn!("⚠️  Scheduler refused heartbeat: {}", resp.status()),
+        Err(_) => warn!("⚠️  Heartbeat to scheduler failed"),
+    }
 }
 
 async fn list_jobs(
@@ -438,45 +1468,172 @@ async fn list_jobs(
     Ok(Json(jobs.values().cloned().collect()))
 }
 
-fn now() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
 }
 
-fn random_hash() -> String {
-    use std::time::SystemTime;
-    let timestamp = SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_nanos();
-    format!("{:x}", timestamp)
+fn random_hash(entropy: &dyn Entropy) -> String {
+    format!("{:x}", entropy.next_u128())
 }
 
 // Server setup
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
+    let (clock, entropy) = (artha_clock::system_clock(), artha_clock::system_entropy());
+    let gpu_allocations = Arc::new(RwLock::new(HashMap::new()));
+    let pool_configs: Vec<PoolConfig> = std::env::var("ARTHA_WARM_POOLS")
+        .ok()
+        .and_then(|pools| serde_json::from_str(&pools).ok())
+        .unwrap_or_default();
+    let pools = Arc::new(PoolManager::new(
+        pool_configs,
+        Arc::new(DockerBackend {
+            min_free_memory_pct: std::env::var("ARTHA_POOL_MIN_FREE_MEMORY_PCT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+            max_disk_used_pct: std::env::var("ARTHA_POOL_MAX_DISK_USED_PCT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(90),
+            disk_path: "/tmp/artha".to_string(),
+        }),
+        gpu_allocations.clone(),
+        GPU_COUNT,
+    ).with_clock(clock.clone()));
+
+    let node_key = std::env::var("ARTHA_NODE_KEY")
+        .ok()
+        .and_then(|secret| hex::decode(secret.trim().trim_start_matches("0x")).ok())
+        .and_then(|secret| SigningKey::from_slice(&secret).ok())
+        .unwrap_or_else(|| {
+            warn!("⚠️  ARTHA_NODE_KEY unset or invalid, heartbeating under the dev node key");
+            SigningKey::from_slice(&sha2::Sha256::digest(b"ai-runtime-dev-node-key")).unwrap()
+        });
+    info!("   Node pubkey: {}", node_pubkey(&node_key));
+
     let state = Arc::new(AppState {
         jobs: Arc::new(RwLock::new(HashMap::new())),
-        gpu_allocations: Arc::new(RwLock::new(HashMap::new())),
-        svdb_client: Arc::new(SvdbClient::new("http://localhost:8080".to_string())),
+        gpu_allocations,
+        svdb_client: Arc::new(
+            SvdbClient::new("http://localhost:8080".to_string())
+                .with_read_timeout(tokio::time::Duration::from_secs(env_or("ARTHA_SVDB_READ_TIMEOUT_SECS", 30)))
+                .with_entropy(entropy.clone()),
+        ),
         proof_service_url: "http://localhost:8084".to_string(),
+        tee_launcher: match std::env::var("ARTHA_TEE_MODE").as_deref() {
+            Ok("simulated") => Some(Arc::new(SimulatedTeeLauncher::new(
+                std::env::var("ARTHA_TEE_MEASUREMENT")
+                    .unwrap_or_else(|_| "0xsimulated-mrenclave".to_string()),
+            )) as Arc<dyn TeeLauncher>),
+            _ => None,
+        },
+        pools: pools.clone(),
+        gpu_sampler: Arc::new(NvmlSampler),
+        telemetry_config: TelemetryConfig {
+            bucket_secs: env_or("ARTHA_GPU_TELEMETRY_BUCKET_SECS", 60),
+            max_buckets: env_or("ARTHA_GPU_TELEMETRY_MAX_POINTS", 1440),
+            underutil_pct: env_or("ARTHA_GPU_UNDERUTIL_PCT", 20.0),
+            underutil_window_secs: env_or("ARTHA_GPU_UNDERUTIL_WINDOW_SECS", 600),
+        },
+        gpu_telemetry: Arc::new(RwLock::new(HashMap::new())),
+        capabilities: Arc::new(NodeCapabilities::from_env()),
+        job_secrets: Arc::new(RwLock::new(HashMap::new())),
+        vault: VaultClient::from_env().map(Arc::new),
+        image_store: Arc::new(DockerImageStore),
+        job_containers: Arc::new(DockerJobContainers),
+        jobd_url: std::env::var("ARTHA_JOBD_URL").unwrap_or_else(|_| "http://localhost:8081".to_string()),
+        node_key,
+        mount_cache: Arc::new(MountCache::open(
+            std::env::var("ARTHA_MOUNT_CACHE_DIR").unwrap_or_else(|_| "/tmp/artha/cache".to_string()),
+            env_or("ARTHA_MOUNT_CACHE_MAX_BYTES", 200 * 1024 * 1024 * 1024),
+        )),
+        streams: Arc::new(RwLock::new(HashMap::new())),
+        log_limits: LogLimits::from_env(),
+        clock: clock.clone(),
+        entropy: entropy.clone(),
+    });
+
+    // Background task: keep warm pools at depth, recycle expired containers
+    // and cull idle ones under disk/memory pressure
+    let (maintenance_pools, maintenance_clock) = (pools.clone(), clock.clone());
+    tokio::spawn(async move {
+        loop {
+            maintenance_pools.maintain(maintenance_clock.now_secs()).await;
+            maintenance_clock.sleep(tokio::time::Duration::from_secs(30)).await;
+        }
+    });
+
+    // Background task: heartbeat capacity (including pool reservations) to the scheduler
+    let scheduler_url = std::env::var("ARTHA_SCHEDULER_URL")
+        .unwrap_or_else(|_| "http://localhost:8083".to_string());
+    let node_key = state.node_key.clone();
+    let heartbeat_clock = clock.clone();
+    tokio::spawn(async move {
+        loop {
+            send_heartbeat(&scheduler_url, &node_key, &pools.capacity().await, heartbeat_clock.now_secs()).await;
+            heartbeat_clock.sleep(tokio::time::Duration::from_secs(15)).await;
+        }
+    });
+
+    let openai_state = Arc::new(openai::OpenAiState::new(
+        std::env::var("ARTHA_API_KEYS")
+            .ok()
+            .and_then(|keys| serde_json::from_str(&keys).ok())
+            .unwrap_or_default(),
+        std::env::var("ARTHA_POLICY_URL").unwrap_or_else(|_| "http://localhost:8082".to_string()),
+        std::env::var("ARTHA_ETHICS_URL").unwrap_or_else(|_| "http://localhost:8089".to_string()),
+        std::env::var("ARTHA_USAGE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60),
+    ).with_clock(clock.clone()).with_entropy(entropy));
+
+    // Background task: archive job logs beyond what memory keeps
+    let archive_state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            archive_state.clock.sleep(tokio::time::Duration::from_secs(LOG_ARCHIVE_INTERVAL_SECS)).await;
+            archive_job_logs(&archive_state).await;
+        }
+    });
+
+    // Background task: flush aggregated inference usage to receipts
+    let usage_state = openai_state.clone();
+    let receipts_url = std::env::var("ARTHA_RECEIPTS_URL")
+        .unwrap_or_else(|_| "http://localhost:8092".to_string());
+    tokio::spawn(async move {
+        loop {
+            clock.sleep(tokio::time::Duration::from_secs(30)).await;
+            openai::flush_usage(&usage_state, &receipts_url).await;
+        }
     });
 
     let app = Router::new()
         .route("/job/start", post(start_job))
         .route("/job/:id/stop", post(stop_job))
+        .route("/job/stream", post(start_stream_job))
+        .route("/job/:id/migrate", post(migrate_job))
         .route("/job/:id/logs", get(get_job_logs))
         .route("/job/:id/status", get(get_job_status))
+        .route("/job/:id/gpu-telemetry", get(get_gpu_telemetry))
+        .route("/job/:id/repro-bundle", get(get_repro_bundle))
+        .route("/job/replay", post(replay_job))
         .route("/jobs", get(list_jobs))
+        .route("/pools", get(get_pools))
+        .route("/mount-cache", get(get_mount_cache))
+        .route("/capabilities/check", post(check_capabilities))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::map_response(artha_errors::normalize)) // OpenAI routes keep their own error shape
+        .merge(openai::router(openai_state))
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 AI Runtime starting on :8084");
-    println!("   Managing Docker containers for AI workloads");
-    println!("   GPU allocation, SVDB mounting, checkpoint saving");
+    info!("🚀 AI Runtime starting on :8084");
+    info!("   Managing Docker containers for AI workloads");
+    info!("   GPU allocation, SVDB mounting, checkpoint saving");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8084").await.unwrap();
     axum::serve(listener, app).await.unwrap();
@@ -485,6 +1642,7 @@ async fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use artha_clock::{Clock, ManualClock, SeededEntropy};
 
     #[test]
     fn test_runtime_image() {
@@ -493,10 +1651,1864 @@ mod tests {
         assert_eq!(get_runtime_image("unknown"), "artha/torch-runtime:v1");
     }
 
+    #[test]
+    fn test_capability_check() {
+        let node = NodeCapabilities {
+            images: NodeCapabilities::default_images(),
+            cuda_version: Some("12.1".to_string()),
+        };
+        let req = |framework: &str, version: Option<&str>, cuda: Option<&str>| RuntimeRequirements {
+            framework: framework.to_string(),
+            framework_version: version.map(|v| v.to_string()),
+            min_cuda: cuda.map(|c| c.to_string()),
+            ..Default::default()
+        };
+
+        let check = node.check(&req("torch", Some("2.1"), Some("11.8")), false);
+        assert!(check.satisfiable);
+        assert_eq!(check.image.as_deref(), Some("artha/torch-runtime:v1"));
+        assert!(node.check(&req("torch", Some(">=2.0"), None), false).satisfiable);
+        assert!(node.check(&req("agent", None, None), false).satisfiable);
+
+        // Wrong version, unknown framework, driver too old for the image, no TEE
+        assert!(!node.check(&req("torch", Some("1.13"), None), false).satisfiable);
+        let unknown = node.check(&req("mxnet", None, None), false);
+        assert!(!unknown.satisfiable);
+        assert_eq!(unknown.unmet, vec!["framework mxnet not offered"]);
+        assert!(!node.check(&req("jax", None, None), false).satisfiable);
+        assert!(!node.check(&req("torch", None, Some("12.4")), false).satisfiable);
+        let tee = RuntimeRequirements { tee_required: true, ..req("torch", None, None) };
+        assert!(!node.check(&tee, false).satisfiable);
+        assert!(node.check(&tee, true).satisfiable);
+    }
+
     #[test]
     fn test_container_status() {
         let status = ContainerStatus::Running;
         assert_eq!(status, ContainerStatus::Running);
     }
-}
 
+    #[test]
+    fn test_simulated_tee_quote_binding() {
+        let launcher = SimulatedTeeLauncher::new("0xmeasure".to_string());
+        let spec = TeeLaunchSpec {
+            image: "artha/torch-runtime:v1".to_string(),
+            image_digest: "sha256:abc".to_string(),
+            job_id: "job-1".to_string(),
+            nonce: "nonce-1".to_string(),
+        };
+
+        let launch = launcher.launch(&spec).unwrap();
+        assert_eq!(launch.quote.report_data, tee::compute_report_data("sha256:abc", "job-1", "nonce-1"));
+        assert_ne!(launch.quote.report_data, tee::compute_report_data("sha256:abc", "job-1", "nonce-2"));
+        assert!(launch.quote.raw_quote.contains("MRENCLAVE:0xmeasure"));
+
+        let missing_nonce = TeeLaunchSpec { nonce: String::new(), ..spec };
+        assert!(launcher.launch(&missing_nonce).is_err());
+    }
+
+    async fn spawn(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// Mock serving container plus policy and ethics services
+    async fn spawn_mock_backend() -> String {
+        use futures_util::StreamExt;
+
+        async fn generate(Json(req): Json<serde_json::Value>) -> axum::response::Response {
+            use axum::response::IntoResponse;
+            let prompt = req["prompt"].as_str().unwrap_or("").to_string();
+            let text = if prompt.contains("leak") { "forbidden secret" } else { "hello world" };
+            if req["stream"].as_bool() == Some(true) {
+                let lines = vec![
+                    "{\"token\":\"hello\"}\n".to_string(),
+                    "{\"token\":\" wor".to_string(), // Line split across network chunks
+                    "ld\"}\n{\"done\":true,\"prompt_tokens\":7,\"completion_tokens\":2}\n".to_string(),
+                ];
+                let stream = futures_util::stream::iter(lines).then(|line| async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    Ok::<_, std::io::Error>(line)
+                });
+                axum::body::Body::from_stream(stream).into_response()
+            } else {
+                Json(serde_json::json!({ "text": text, "prompt_tokens": 7, "completion_tokens": 2 })).into_response()
+            }
+        }
+        async fn ethics(Json(req): Json<serde_json::Value>) -> Json<serde_json::Value> {
+            let flagged = req["content"].as_str().unwrap_or("").contains("forbidden");
+            Json(serde_json::json!({
+                "allowed": !flagged,
+                "flags": if flagged { serde_json::json!([{ "check_type": "toxicity" }]) } else { serde_json::json!([]) },
+                "score": 0.0,
+            }))
+        }
+        spawn(Router::new()
+            .route("/generate", post(generate))
+            .route("/ethics/check", post(ethics))
+            .route("/policy/check", post(|| async { Json(serde_json::json!({ "allowed": true })) })))
+            .await
+    }
+
+    async fn spawn_facade() -> (String, Arc<openai::OpenAiState>) {
+        spawn_facade_on(ManualClock::new(1_700_000_000)).await
+    }
+
+    async fn spawn_facade_on(clock: ManualClock) -> (String, Arc<openai::OpenAiState>) {
+        let backend = spawn_mock_backend().await;
+        let state = Arc::new(openai::OpenAiState::new(
+            HashMap::from([("sk-test".to_string().into(), "did:artha:alice".to_string())]),
+            backend.clone(),
+            backend.clone(),
+            3600,
+        ).with_clock(clock.shared()).with_entropy(SeededEntropy::shared(5)));
+        state.register_instance(openai::ServingInstance {
+            model: "artha-chat".to_string(),
+            model_cid: "bafy-model".to_string(),
+            endpoint: backend,
+            moderation: openai::ModerationConfig {
+                check_prompt: true,
+                check_response: true,
+                checks: vec!["toxicity".to_string()],
+            },
+            price_per_1k_tokens: 1000,
+        }).await;
+        (spawn(openai::router(state.clone())).await, state)
+    }
+
+    #[tokio::test]
+    async fn test_openai_schema_fidelity() {
+        let (url, _) = spawn_facade().await;
+        let client = reqwest::Client::new();
+
+        let chat: serde_json::Value = client.post(format!("{}/v1/chat/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({
+                "model": "artha-chat",
+                "messages": [{ "role": "user", "content": "hi" }],
+                "temperature": 0.2,
+                "max_tokens": 16,
+            }))
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(chat["object"], "chat.completion");
+        assert!(chat["id"].as_str().unwrap().starts_with("chatcmpl-"));
+        assert_eq!(chat["created"], 1_700_000_000);
+        assert_eq!(chat["model"], "artha-chat");
+        assert_eq!(chat["choices"][0]["message"]["role"], "assistant");
+        assert_eq!(chat["choices"][0]["message"]["content"], "hello world");
+        assert_eq!(chat["choices"][0]["finish_reason"], "stop");
+        assert_eq!(chat["usage"]["total_tokens"], 9);
+
+        // Model CIDs resolve as well as aliases
+        let completion: serde_json::Value = client.post(format!("{}/v1/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({ "model": "bafy-model", "prompt": "hi" }))
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(completion["object"], "text_completion");
+        assert_eq!(completion["choices"][0]["text"], "hello world");
+        assert_eq!(completion["usage"]["prompt_tokens"], 7);
+
+        let unauthorized = client.post(format!("{}/v1/completions", url))
+            .bearer_auth("sk-wrong")
+            .json(&serde_json::json!({ "model": "artha-chat", "prompt": "hi" }))
+            .send().await.unwrap();
+        assert_eq!(unauthorized.status(), 401);
+        let body: serde_json::Value = unauthorized.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "invalid_api_key");
+
+        let missing = client.post(format!("{}/v1/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({ "model": "nope", "prompt": "hi" }))
+            .send().await.unwrap();
+        assert_eq!(missing.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_usage_flushes_once_its_interval_closes() {
+        let clock = ManualClock::new(1_700_000_000);
+        let (url, state) = spawn_facade_on(clock.clone()).await;
+        let flushed: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let received = flushed.clone();
+        let receipts = spawn(Router::new().route("/receipt/inference-usage", post(move |Json(body): Json<serde_json::Value>| {
+            received.lock().unwrap().push(body);
+            async { StatusCode::OK }
+        })))
+        .await;
+
+        for _ in 0..2 {
+            reqwest::Client::new().post(format!("{}/v1/completions", url))
+                .bearer_auth("sk-test")
+                .json(&serde_json::json!({ "model": "artha-chat", "prompt": "hi" }))
+                .send().await.unwrap();
+        }
+        // The hour that started at 1_699_999_200 is still open
+        openai::flush_usage(&state, &receipts).await;
+        assert!(flushed.lock().unwrap().is_empty());
+
+        clock.advance_secs(2_799);
+        openai::flush_usage(&state, &receipts).await;
+        assert!(flushed.lock().unwrap().is_empty());
+        clock.advance_secs(1);
+        openai::flush_usage(&state, &receipts).await;
+        let flushed = flushed.lock().unwrap().clone();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0]["interval_start"], 1_699_999_200);
+        assert_eq!(flushed[0]["requests"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_openai_sse_chunk_framing() {
+        let (url, _) = spawn_facade().await;
+        let body = reqwest::Client::new().post(format!("{}/v1/chat/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({
+                "model": "artha-chat",
+                "messages": [{ "role": "user", "content": "hi" }],
+                "stream": true,
+            }))
+            .send().await.unwrap().text().await.unwrap();
+
+        let events: Vec<&str> = body.split("\n\n").filter(|e| !e.is_empty()).collect();
+        assert!(events.iter().all(|e| e.starts_with("data: ")));
+        assert_eq!(*events.last().unwrap(), "data: [DONE]");
+
+        let chunks: Vec<serde_json::Value> = events[..events.len() - 1]
+            .iter()
+            .map(|e| serde_json::from_str(&e["data: ".len()..]).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c["object"] == "chat.completion.chunk"));
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(chunks[0]["choices"][0]["delta"]["content"], "hello");
+        assert_eq!(chunks[1]["choices"][0]["delta"]["content"], " world");
+        assert_eq!(chunks[2]["choices"][0]["finish_reason"], "stop");
+    }
+
+    #[tokio::test]
+    async fn test_openai_moderation_block() {
+        let (url, _) = spawn_facade().await;
+        let client = reqwest::Client::new();
+
+        // Prompt moderation
+        let response = client.post(format!("{}/v1/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({ "model": "artha-chat", "prompt": "say something forbidden" }))
+            .send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "content_filter");
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+
+        // Response moderation
+        let response = client.post(format!("{}/v1/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({ "model": "artha-chat", "prompt": "please leak" }))
+            .send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "content_filter");
+    }
+
+    #[tokio::test]
+    async fn test_openai_usage_records_accumulate() {
+        let (url, state) = spawn_facade().await;
+        let client = reqwest::Client::new();
+        for _ in 0..3 {
+            client.post(format!("{}/v1/completions", url))
+                .bearer_auth("sk-test")
+                .json(&serde_json::json!({ "model": "artha-chat", "prompt": "hi" }))
+                .send().await.unwrap();
+        }
+        client.post(format!("{}/v1/chat/completions", url))
+            .bearer_auth("sk-test")
+            .json(&serde_json::json!({ "model": "artha-chat", "messages": [], "stream": true }))
+            .send().await.unwrap().text().await.unwrap();
+
+        let records = state.usage.read().await.snapshot();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].did, "did:artha:alice");
+        assert_eq!(records[0].requests, 4);
+        assert_eq!(records[0].prompt_tokens, 28);
+        assert_eq!(records[0].completion_tokens, 8);
+        assert_eq!(records[0].cost, 36);
+
+        // Closed intervals drain for settlement
+        let mut ledger = openai::UsageLedger::new(60);
+        let instance = openai::ServingInstance {
+            model: "m".to_string(),
+            model_cid: "cid".to_string(),
+            endpoint: String::new(),
+            moderation: openai::ModerationConfig::default(),
+            price_per_1k_tokens: 1000,
+        };
+        ledger.record("did:a", &instance, 10, 5, 100);
+        ledger.record("did:a", &instance, 10, 5, 110);
+        ledger.record("did:a", &instance, 10, 5, 130);
+        assert!(ledger.drain_closed(110).is_empty());
+        let closed = ledger.drain_closed(130);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].requests, 2);
+        assert_eq!(closed[0].cost, 30);
+        assert_eq!(ledger.snapshot().len(), 1);
+    }
+
+    /// Mock container backend recording what pool containers were created with
+    #[derive(Default)]
+    struct MockContainerBackend {
+        created: std::sync::Mutex<Vec<(String, pool::PoolContainerSpec)>>,
+        claimed: std::sync::Mutex<HashMap<String, JobSpec>>,
+        removed: std::sync::Mutex<Vec<String>>,
+        pressure: std::sync::atomic::AtomicBool,
+    }
+
+    impl pool::ContainerBackend for MockContainerBackend {
+        fn create_paused(&self, spec: &pool::PoolContainerSpec) -> Result<String, String> {
+            let mut created = self.created.lock().unwrap();
+            let container_id = format!("warm-container-{:04}", created.len());
+            created.push((container_id.clone(), spec.clone()));
+            Ok(container_id)
+        }
+
+        fn claim(&self, container_id: &str, job: &JobSpec) -> Result<(), String> {
+            self.claimed.lock().unwrap().insert(container_id.to_string(), job.clone());
+            Ok(())
+        }
+
+        fn remove(&self, container_id: &str) {
+            self.removed.lock().unwrap().push(container_id.to_string());
+        }
+
+        fn under_pressure(&self) -> bool {
+            self.pressure.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    fn pool_manager(backend: Arc<MockContainerBackend>, gpus: usize) -> (Arc<PoolManager>, Arc<RwLock<HashMap<String, String>>>) {
+        let allocations = Arc::new(RwLock::new(HashMap::new()));
+        let configs = vec![PoolConfig {
+            image: "artha/torch-runtime:v1".to_string(),
+            digest: "sha256:torch".to_string(),
+            count: 2,
+            ttl_secs: 600,
+        }];
+        let pools = PoolManager::new(configs, backend, allocations.clone(), gpus).with_clock(ManualClock::new(1_700_000_000).shared());
+        (Arc::new(pools), allocations)
+    }
+
+    fn test_job_spec(job_id: &str) -> JobSpec {
+        JobSpec {
+            job_id: job_id.to_string(),
+            job_type: "Train".to_string(),
+            model_cid: "bafy-model".to_string(),
+            dataset_cid: Some("bafy-dataset".to_string()),
+            params: serde_json::json!({ "epochs": 1 }),
+            svdb_url: "http://svdb".to_string(),
+            env: BTreeMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warm_pool_claim_vs_cold_start_and_background_refill() {
+        let backend = Arc::new(MockContainerBackend::default());
+        let (pools, allocations) = pool_manager(backend.clone(), 8);
+        assert_eq!(pools.refill().await, 2);
+
+        // No pool for the image, or a different pinned digest: cold start
+        assert!(pools.claim("artha/tf-runtime:v1", None, &test_job_spec("job-tf")).await.is_none());
+        assert!(pools.claim("artha/torch-runtime:v1", Some("sha256:other"), &test_job_spec("job-x")).await.is_none());
+
+        // Matching image and digest: claimed, the GPU moves to the job
+        let claimed = pools.claim("artha/torch-runtime:v1", Some("sha256:torch"), &test_job_spec("job-1")).await.unwrap();
+        assert_eq!(allocations.read().await.get(&claimed.gpu_id).unwrap(), "job-1");
+        assert_eq!(backend.claimed.lock().unwrap()[&claimed.container_id].job_id, "job-1");
+        assert_eq!(pools.stats().await.pools[0].idle, 1);
+
+        // Claimed containers are replaced in the background
+        pool::spawn_refill(pools.clone());
+        for _ in 0..50 {
+            if pools.stats().await.pools[0].idle == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(pools.stats().await.pools[0].idle, 2);
+        assert_eq!(backend.created.lock().unwrap().len(), 3);
+
+        let stats = pools.stats().await;
+        assert_eq!(stats.counters.claims, 1);
+        assert_eq!(stats.counters.refills, 3);
+        assert_eq!(stats.pools[0].idle, 2);
+    }
+
+    #[tokio::test]
+    async fn test_unclaimed_pool_containers_hold_no_job_data() {
+        let backend = Arc::new(MockContainerBackend::default());
+        let (pools, _) = pool_manager(backend.clone(), 8);
+        pools.refill().await;
+        pools.claim("artha/torch-runtime:v1", None, &test_job_spec("job-1")).await.unwrap();
+        pools.refill().await;
+
+        let created = backend.created.lock().unwrap().clone();
+        let claimed = backend.claimed.lock().unwrap().clone();
+        assert_eq!(created.len(), 3);
+        for (_, spec) in &created {
+            assert!(spec.mounts.is_empty(), "pool container created with mounts");
+        }
+        // Only the claimed container ever received a job spec
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[&created[0].0].job_id, "job-1");
+
+        // Under pressure, idle containers are culled and not replaced
+        backend.pressure.store(true, std::sync::atomic::Ordering::SeqCst);
+        pools.maintain(1_700_000_000).await;
+        assert_eq!(pools.stats().await.pools[0].idle, 0);
+        assert_eq!(backend.removed.lock().unwrap().len(), 2);
+        assert_eq!(pools.stats().await.counters.culled, 2);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_capacity_counts_pool_reservations() {
+        let backend = Arc::new(MockContainerBackend::default());
+        let (pools, allocations) = pool_manager(backend, 4);
+        pools.refill().await;
+        assert_eq!(pools.capacity().await, pool::CapacityReport {
+            gpus_total: 4,
+            gpus_running: 0,
+            gpus_pool_reserved: 2,
+            gpus_free: 2,
+        });
+
+        // A claim converts a reservation into a running job
+        pools.claim("artha/torch-runtime:v1", None, &test_job_spec("job-1")).await.unwrap();
+        allocations.write().await.insert("gpu:3".to_string(), "job-cold".to_string());
+        assert_eq!(pools.capacity().await, pool::CapacityReport {
+            gpus_total: 4,
+            gpus_running: 2,
+            gpus_pool_reserved: 1,
+            gpus_free: 1,
+        });
+
+        // Refill only uses free GPUs; a cold-start job can evict warm capacity
+        pools.refill().await;
+        assert_eq!(pools.capacity().await.gpus_free, 0);
+        assert!(pools.evict_for_job().await);
+        assert_eq!(pools.capacity().await, pool::CapacityReport {
+            gpus_total: 4,
+            gpus_running: 2,
+            gpus_pool_reserved: 1,
+            gpus_free: 1,
+        });
+    }
+
+    /// Heartbeats as the scheduler received them: pubkey, headers, body
+    type Heartbeats = Arc<std::sync::Mutex<Vec<(String, HashMap<String, String>, Vec<u8>)>>>;
+
+    #[tokio::test]
+    async fn test_heartbeat_is_signed_with_the_node_key() {
+        use k256::ecdsa::signature::Verifier;
+        let received: Heartbeats = Arc::default();
+        let recorded = received.clone();
+        let app = Router::new().route("/nodes/:pubkey/heartbeat", post(move |Path(pubkey): Path<String>, headers: axum::http::HeaderMap, body: axum::body::Bytes| {
+            let headers = headers.iter().map(|(k, v)| (k.to_string(), v.to_str().unwrap().to_string())).collect();
+            recorded.lock().unwrap().push((pubkey, headers, body.to_vec()));
+            async { StatusCode::OK }
+        }));
+        let scheduler = spawn(app).await;
+        let key = SigningKey::from_slice(&[9u8; 32]).unwrap();
+        let capacity = pool::CapacityReport { gpus_total: 4, gpus_running: 1, gpus_pool_reserved: 1, gpus_free: 2 };
+        send_heartbeat(&scheduler, &key, &capacity, 1_700_000_000).await;
+
+        let (pubkey, headers, body) = received.lock().unwrap()[0].clone();
+        assert_eq!(pubkey, node_pubkey(&key));
+        assert_eq!(headers["x-artha-timestamp"], "1700000000");
+        assert_eq!(serde_json::from_slice::<pool::CapacityReport>(&body).unwrap(), capacity);
+        let message = format!("HEARTBEAT:{}:{}:TS:{}", pubkey, repro::sha256_hex(&body), headers["x-artha-timestamp"]);
+        let signature = Signature::from_slice(&hex::decode(&headers["x-artha-signature"]).unwrap()).unwrap();
+        let verifying = k256::ecdsa::VerifyingKey::from_sec1_bytes(&hex::decode(pubkey.trim_start_matches("0x")).unwrap()).unwrap();
+        assert!(verifying.verify(message.as_bytes(), &signature).is_ok());
+    }
+
+    /// Mock NVML returning a scripted reading per device
+    struct MockGpuSampler {
+        utilization: std::sync::Mutex<HashMap<u32, f64>>,
+    }
+
+    impl MockGpuSampler {
+        fn set(&self, device: u32, utilization: f64) {
+            self.utilization.lock().unwrap().insert(device, utilization);
+        }
+    }
+
+    impl GpuSampler for MockGpuSampler {
+        fn sample(&self, device: u32) -> Result<telemetry::GpuSample, String> {
+            let utilization = *self.utilization.lock().unwrap().get(&device).ok_or("no such device")?;
+            Ok(telemetry::GpuSample {
+                utilization_pct: utilization,
+                memory_used_mb: 1000 * (device as u64 + 1),
+                memory_total_mb: 81920,
+                power_watts: 3.0 * utilization,
+            })
+        }
+    }
+
+    /// Image store knowing a fixed set of pinned digests
+    #[derive(Default)]
+    struct MockImageStore {
+        digests: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ImageStore for MockImageStore {
+        fn has_digest(&self, _image: &str, digest: &str) -> bool {
+            self.digests.lock().unwrap().iter().any(|d| d == digest)
+        }
+    }
+
+    /// Job containers whose exit codes the test scripts; unscripted containers keep running
+    #[derive(Default)]
+    struct MockJobContainers {
+        exits: std::sync::Mutex<HashMap<String, ContainerExit>>,
+        launched: std::sync::Mutex<Vec<(String, ContainerLaunch)>>,
+        removed: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl MockJobContainers {
+        fn exit(&self, container_id: &str, code: i32) {
+            self.exits.lock().unwrap().insert(container_id.to_string(), ContainerExit { code, oom_killed: false });
+        }
+
+        fn oom_kill(&self, container_id: &str) {
+            self.exits.lock().unwrap().insert(container_id.to_string(), ContainerExit { code: 137, oom_killed: true });
+        }
+    }
+
+    impl JobContainers for MockJobContainers {
+        fn launch(&self, launch: &ContainerLaunch) -> Result<String, String> {
+            let mut launched = self.launched.lock().unwrap();
+            let container_id = format!("job-container-{:04}", launched.len());
+            launched.push((container_id.clone(), launch.clone()));
+            Ok(container_id)
+        }
+
+        fn exit(&self, container_id: &str) -> Result<Option<ContainerExit>, String> {
+            Ok(self.exits.lock().unwrap().get(container_id).copied())
+        }
+
+        fn remove(&self, container_id: &str) {
+            self.removed.lock().unwrap().push(container_id.to_string());
+        }
+    }
+
+    fn telemetry_state(utilization: HashMap<u32, f64>) -> (Arc<AppState>, Arc<MockGpuSampler>) {
+        let (pools, allocations) = pool_manager(Arc::new(MockContainerBackend::default()), GPU_COUNT);
+        let sampler = Arc::new(MockGpuSampler { utilization: std::sync::Mutex::new(utilization) });
+        let containers = Arc::new(MockJobContainers::default());
+        (app_state(pools, allocations, sampler.clone(), Arc::new(MockImageStore::default()), containers), sampler)
+    }
+
+    fn app_state(
+        pools: Arc<PoolManager>,
+        allocations: Arc<RwLock<HashMap<String, String>>>,
+        sampler: Arc<dyn GpuSampler>,
+        image_store: Arc<dyn ImageStore>,
+        job_containers: Arc<dyn JobContainers>,
+    ) -> Arc<AppState> {
+        Arc::new(AppState {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            gpu_allocations: allocations,
+            svdb_client: Arc::new(SvdbClient::new("http://svdb".to_string())),
+            proof_service_url: "http://proofs".to_string(),
+            tee_launcher: None,
+            pools,
+            gpu_sampler: sampler,
+            telemetry_config: TelemetryConfig::default(),
+            gpu_telemetry: Arc::new(RwLock::new(HashMap::new())),
+            capabilities: Arc::new(NodeCapabilities { images: NodeCapabilities::default_images(), cuda_version: None }),
+            job_secrets: Arc::new(RwLock::new(HashMap::new())),
+            vault: None,
+            image_store,
+            job_containers,
+            jobd_url: "http://127.0.0.1:9".to_string(),
+            node_key: SigningKey::from_slice(&[9u8; 32]).unwrap(),
+            mount_cache: Arc::new(MountCache::open(temp_mount("mount-cache"), u64::MAX)),
+            streams: Arc::new(RwLock::new(HashMap::new())),
+            log_limits: LogLimits::DEFAULT,
+            clock: ManualClock::new(1_700_000_000).shared(),
+            entropy: SeededEntropy::shared(11),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_gpu_telemetry_recorded_and_queryable() {
+        let (state, sampler) = telemetry_state(HashMap::from([(0, 80.0), (1, 60.0), (2, 5.0)]));
+        {
+            let mut allocations = state.gpu_allocations.write().await;
+            allocations.insert("gpu:0".to_string(), "job-1".to_string());
+            allocations.insert("gpu:1".to_string(), "job-1".to_string());
+            allocations.insert("gpu:2".to_string(), "job-other".to_string());
+        }
+
+        // Two samples land in the same 60s bucket, the third opens a new one
+        collect_gpu_telemetry(&state, "job-1", 1000).await;
+        sampler.set(0, 40.0);
+        collect_gpu_telemetry(&state, "job-1", 1010).await;
+        collect_gpu_telemetry(&state, "job-1", 1070).await;
+
+        let Json(telemetry) = get_gpu_telemetry(State(state.clone()), Path("job-1".to_string())).await.unwrap();
+        assert_eq!(telemetry.last_sample_at, Some(1070));
+        assert_eq!(telemetry.devices.keys().cloned().collect::<Vec<_>>(), vec!["gpu:0", "gpu:1"]);
+
+        let gpu0 = &telemetry.devices["gpu:0"];
+        assert_eq!(gpu0.len(), 2);
+        assert_eq!(gpu0[0].bucket_start, 960);
+        assert_eq!(gpu0[0].samples, 2);
+        assert_eq!(gpu0[0].avg_utilization_pct, 60.0);
+        assert_eq!(gpu0[0].max_utilization_pct, 80.0);
+        assert_eq!(gpu0[0].avg_power_watts, 180.0);
+        assert_eq!(gpu0[1].bucket_start, 1020);
+        assert_eq!(gpu0[1].avg_utilization_pct, 40.0);
+        assert_eq!(telemetry.devices["gpu:1"][0].avg_memory_used_mb, 2000);
+        assert!(telemetry.underutilized.is_none());
+
+        // The series is exposed over HTTP as well
+        let app = Router::new()
+            .route("/job/:id/gpu-telemetry", get(get_gpu_telemetry))
+            .with_state(state.clone());
+        let url = spawn(app).await;
+        let body: serde_json::Value = reqwest::get(format!("{}/job/job-1/gpu-telemetry", url))
+            .await.unwrap().json().await.unwrap();
+        assert_eq!(body["devices"]["gpu:0"][1]["bucket_start"], 1020);
+        let missing = reqwest::get(format!("{}/job/nope/gpu-telemetry", url)).await.unwrap();
+        assert_eq!(missing.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_underutilized_job_is_flagged_once() {
+        let (state, sampler) = telemetry_state(HashMap::from([(0, 3.0)]));
+        state.gpu_allocations.write().await.insert("gpu:0".to_string(), "job-idle".to_string());
+
+        // No verdict until the job has a full window of history
+        collect_gpu_telemetry(&state, "job-idle", 1000).await;
+        collect_gpu_telemetry(&state, "job-idle", 1300).await;
+        assert!(state.gpu_telemetry.read().await["job-idle"].underutilized.is_none());
+
+        collect_gpu_telemetry(&state, "job-idle", 1600).await;
+        let alert = state.gpu_telemetry.read().await["job-idle"].underutilized.clone().unwrap();
+        assert_eq!(alert.since, 1600);
+        assert_eq!(alert.avg_utilization_pct, 3.0);
+
+        // The flag is sticky while utilization stays low and clears once it recovers
+        collect_gpu_telemetry(&state, "job-idle", 1700).await;
+        assert_eq!(state.gpu_telemetry.read().await["job-idle"].underutilized.as_ref().unwrap().since, 1600);
+        sampler.set(0, 95.0);
+        for t in (1800..=2400).step_by(60) {
+            collect_gpu_telemetry(&state, "job-idle", t).await;
+        }
+        assert!(state.gpu_telemetry.read().await["job-idle"].underutilized.is_none());
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi_output() {
+        let sample = telemetry::parse_nvidia_smi("87, 30512, 81920, 312.45\n").unwrap();
+        assert_eq!(sample.utilization_pct, 87.0);
+        assert_eq!(sample.memory_used_mb, 30512);
+        assert_eq!(sample.memory_total_mb, 81920);
+        assert_eq!(sample.power_watts, 312.45);
+
+        let no_power = telemetry::parse_nvidia_smi("5, 100, 16000, [N/A]").unwrap();
+        assert_eq!(no_power.power_watts, 0.0);
+        assert!(telemetry::parse_nvidia_smi("garbage").is_err());
+        assert_eq!(telemetry::device_index("gpu:3"), Some(3));
+        assert_eq!(telemetry::device_index("cpu"), None);
+    }
+
+    /// SVDB gateway answering replication lookups and collecting provider health reports
+    async fn spawn_svdb_gateway(
+        replicas: serde_json::Value,
+        object: Vec<u8>,
+    ) -> (String, Arc<std::sync::Mutex<Vec<serde_json::Value>>>, Arc<std::sync::Mutex<Vec<String>>>) {
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let lookups = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (reports_in, lookups_in) = (reports.clone(), lookups.clone());
+        let app = Router::new()
+            .route("/svdb/:cid/replication", get(move |Path(cid): Path<String>| {
+                lookups_in.lock().unwrap().push(cid);
+                let replicas = replicas.clone();
+                async move {
+                    if replicas.as_array().map(|r| r.is_empty()).unwrap_or(true) {
+                        return Err(StatusCode::NOT_FOUND);
+                    }
+                    Ok(Json(serde_json::json!({ "replicas": replicas })))
+                }
+            }))
+            .route("/svdb/providers/health", post(move |Json(report): Json<serde_json::Value>| {
+                reports_in.lock().unwrap().push(report);
+                async { StatusCode::OK }
+            }))
+            .route("/svdb/download/:cid", get(move || {
+                let object = object.clone();
+                async move { object }
+            }));
+        (spawn(app).await, reports, lookups)
+    }
+
+    /// Provider that sends the first half of `object`, then stalls
+    async fn spawn_dying_provider(object: Vec<u8>) -> String {
+        let app = Router::new().route("/svdb/download/:cid", get(move || {
+            let half = axum::body::Bytes::from(object[..object.len() / 2].to_vec());
+            let total = object.len();
+            async move {
+                use futures_util::StreamExt;
+                let body = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(half)])
+                    .chain(futures_util::stream::pending());
+                axum::response::Response::builder()
+                    .header("Content-Length", total)
+                    .body(axum::body::Body::from_stream(body))
+                    .unwrap()
+            }
+        }));
+        spawn(app).await
+    }
+
+    /// Provider honouring `Range: bytes=N-`, recording the ranges asked for
+    async fn spawn_range_provider(object: Vec<u8>) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let ranges = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let ranges_in = ranges.clone();
+        let app = Router::new().route("/svdb/download/:cid", get(move |headers: axum::http::HeaderMap| {
+            let range = headers.get("Range").and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+            let start: usize = range
+                .as_deref()
+                .and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok())
+                .unwrap_or(0);
+            ranges_in.lock().unwrap().push(range.clone().unwrap_or_default());
+            let rest = object[start..].to_vec();
+            async move {
+                let status = if range.is_some() { StatusCode::PARTIAL_CONTENT } else { StatusCode::OK };
+                (status, rest)
+            }
+        }));
+        (spawn(app).await, ranges)
+    }
+
+    fn temp_mount(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("artha-runtime-test-{}-{}", name, random_hash(&artha_clock::SystemEntropy)));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_svdb_read_fails_over_on_dead_provider_mid_download() {
+        let object: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let near = spawn_dying_provider(object.clone()).await;
+        let (far, far_ranges) = spawn_range_provider(object.clone()).await;
+        let (gateway, reports, lookups) = spawn_svdb_gateway(
+            serde_json::json!([
+                { "node_id": "far", "region": "us-east", "http_addr": far, "state": "verified", "latency_ms": 80.0 },
+                { "node_id": "copying", "region": "ap-south", "http_addr": "http://127.0.0.1:9", "state": "copying", "latency_ms": 1.0 },
+                { "node_id": "near", "region": "eu-west", "http_addr": near, "state": "verified", "latency_ms": 5.0 },
+            ]),
+            Vec::new(),
+        )
+        .await;
+
+        let client = SvdbClient::new(gateway).with_read_timeout(std::time::Duration::from_millis(300));
+        let order: Vec<String> = client.replicas("artha://AbC+/x").await.into_iter().map(|r| r.node_id).collect();
+        assert_eq!(order, vec!["near", "far"]);
+        assert_eq!(lookups.lock().unwrap()[0], "AbC+/x");
+
+        let mount = temp_mount("failover");
+        client.mount_volume("artha://AbC+/x", mount.to_str().unwrap()).await.unwrap();
+        assert_eq!(std::fs::read(mount.join(svdb::OBJECT_FILE)).unwrap(), object);
+
+        // The far replica was asked only for what the near one never sent
+        assert_eq!(*far_ranges.lock().unwrap(), vec![format!("bytes={}-", object.len() / 2)]);
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!((reports[0]["nodeId"].as_str(), reports[0]["ok"].as_bool()), (Some("near"), Some(false)));
+        assert_eq!((reports[1]["nodeId"].as_str(), reports[1]["ok"].as_bool()), (Some("far"), Some(true)));
+        assert!(reports[1]["latency_ms"].as_f64().is_some());
+        let _ = std::fs::remove_dir_all(mount);
+    }
+
+    #[tokio::test]
+    async fn test_svdb_read_falls_back_to_gateway_without_replicas() {
+        let object = b"model weights".to_vec();
+        let (gateway, reports, _) = spawn_svdb_gateway(serde_json::json!([]), object.clone()).await;
+
+        let client = SvdbClient::new(gateway);
+        assert!(client.replicas("artha://model").await.is_empty());
+        let mount = temp_mount("gateway");
+        client.mount_volume("artha://model", mount.to_str().unwrap()).await.unwrap();
+        assert_eq!(std::fs::read(mount.join(svdb::OBJECT_FILE)).unwrap(), object);
+        assert!(reports.lock().unwrap().is_empty());
+
+        let dead = SvdbClient::new("http://127.0.0.1:9".to_string());
+        assert!(dead.fetch("artha://model", &mount.join("other")).await.is_err());
+        let _ = std::fs::remove_dir_all(mount);
+    }
+
+    fn cache_holders(state: &AppState, cid: &str) -> Option<Vec<String>> {
+        let stats = state.mount_cache.stats();
+        stats.entries.into_iter().find(|e| e.cid == cid).map(|e| e.holders)
+    }
+
+    #[tokio::test]
+    async fn test_jobs_sharing_a_model_cid_download_it_once() {
+        let (gateway, _, lookups) = spawn_svdb_gateway(serde_json::json!([]), b"shared weights".to_vec()).await;
+        let mut state = restart_state(Arc::new(MockJobContainers::default()));
+        Arc::get_mut(&mut state).unwrap().svdb_client = Arc::new(SvdbClient::new(gateway));
+        let model_fetches = || lookups.lock().unwrap().iter().filter(|cid| *cid == "model").count();
+
+        // Two jobs cold-starting at once wait on the same download
+        let (a, b) = tokio::join!(
+            start_job(State(state.clone()), HeaderMap::new(), Json(repro_request("job-cache-a"))),
+            start_job(State(state.clone()), HeaderMap::new(), Json(repro_request("job-cache-b"))),
+        );
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(model_fetches(), 1);
+        let stats = state.mount_cache.stats();
+        assert_eq!((stats.downloads, stats.hits, stats.evictions), (2, 2, 0)); // Model and dataset once each
+        assert_eq!(cache_holders(&state, "artha://model").unwrap(), vec!["job-cache-a", "job-cache-b"]);
+
+        // Both jobs see one materialized copy, hashed for their execution records
+        let linked = |job: &str| std::fs::read_link(format!("/tmp/artha/jobs/{}/model", job)).unwrap();
+        assert_eq!(linked("job-cache-a"), linked("job-cache-b"));
+        assert_eq!(std::fs::read(linked("job-cache-a").join(svdb::OBJECT_FILE)).unwrap(), b"shared weights");
+        let model_hash = |job: &str| {
+            let jobs = futures_util::FutureExt::now_or_never(state.jobs.read()).unwrap();
+            jobs[job].execution.as_ref().unwrap().inputs[0].sha256.clone()
+        };
+        assert_eq!(model_hash("job-cache-a"), Some(repro::sha256_hex(b"shared weights")));
+        assert_eq!(model_hash("job-cache-a"), model_hash("job-cache-b"));
+
+        // Each job drops only its own reference; the unreferenced copy stays cached
+        fail_job(&state, "job-cache-a", TerminalReason::new(TerminalReasonKind::RuntimeError, "test")).await;
+        assert_eq!(cache_holders(&state, "artha://model").unwrap(), vec!["job-cache-b"]);
+        stop_job(State(state.clone()), Path("job-cache-b".to_string())).await.unwrap();
+        assert_eq!(cache_holders(&state, "artha://model").unwrap(), Vec::<String>::new());
+        assert!(linked("job-cache-a").join(svdb::OBJECT_FILE).exists());
+
+        // A later job reuses it without fetching again
+        assert!(start_job(State(state.clone()), HeaderMap::new(), Json(repro_request("job-cache-c"))).await.is_ok());
+        assert_eq!(model_fetches(), 1);
+        assert_eq!(state.mount_cache.stats().downloads, 2);
+        assert_eq!(cache_holders(&state, "artha://model").unwrap(), vec!["job-cache-c"]);
+        fail_job(&state, "job-cache-c", TerminalReason::new(TerminalReasonKind::RuntimeError, "test")).await;
+        for job in ["job-cache-a", "job-cache-b", "job-cache-c"] {
+            let _ = std::fs::remove_dir_all(format!("/tmp/artha/jobs/{}", job));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mount_cache_evicts_only_unreferenced_entries_when_full() {
+        let (gateway, _, lookups) = spawn_svdb_gateway(serde_json::json!([]), vec![7; 10]).await;
+        let client = SvdbClient::new(gateway);
+        let root = temp_mount("mount-cache-evict");
+        let cache = MountCache::open(&root, 25); // Room for two 10 byte objects
+        let cids = |cache: &MountCache| cache.stats().entries.into_iter().map(|e| e.cid).collect::<Vec<_>>();
+
+        let model = cache.acquire("artha://model", "job-1", &client).await.unwrap();
+        cache.acquire("artha://dataset", "job-2", &client).await.unwrap();
+        cache.release("job-1");
+        assert_eq!(cids(&cache), vec!["artha://dataset", "artha://model"]); // Released, but space isn't needed yet
+
+        // A third object needs the space: the released model goes, the held dataset stays
+        cache.acquire("artha://other", "job-3", &client).await.unwrap();
+        assert_eq!(cids(&cache), vec!["artha://dataset", "artha://other"]);
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(!model.exists());
+
+        // Nothing unreferenced left to evict: the cache runs over budget rather than pull held inputs
+        cache.acquire("artha://model", "job-4", &client).await.unwrap();
+        assert_eq!(cache.stats().used_bytes, 30);
+        assert_eq!(lookups.lock().unwrap().iter().filter(|cid| *cid == "model").count(), 2);
+
+        // Copies survive a restart, unreferenced
+        let reopened = MountCache::open(&root, u64::MAX);
+        assert_eq!(cids(&reopened), vec!["artha://dataset", "artha://model", "artha://other"]);
+        assert!(reopened.stats().entries.iter().all(|e| e.holders.is_empty()));
+
+        // A failed download leaves no entry behind
+        let dead = SvdbClient::new("http://127.0.0.1:9".to_string());
+        assert!(reopened.acquire("artha://missing", "job-5", &dead).await.is_err());
+        assert_eq!(reopened.stats().entries.len(), 3);
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    fn repro_request(job_id: &str) -> StartJobRequest {
+        StartJobRequest {
+            job_id: job_id.to_string(),
+            job_type: JobType::Train,
+            model_cid: "artha://model".to_string(),
+            dataset_cid: Some("artha://dataset".to_string()),
+            params: JobParams {
+                epochs: Some(3),
+                batch_size: Some(32),
+                learning_rate: Some(0.001),
+                optimizer: Some("adamw".to_string()),
+                checkpoint_interval: None,
+                max_tokens: None,
+            },
+            runtime: "torch".to_string(),
+            tee_required: false,
+            attestation_nonce: None,
+            image_digest: Some("sha256:torch".to_string()),
+            env: BTreeMap::from([
+                ("LOG_LEVEL".to_string(), "debug".to_string()),
+                ("HF_TOKEN".to_string(), "hf_live_0123456789".to_string()), // Secret by name
+            ]),
+            secret_env: BTreeMap::from([("WANDB_KEY".to_string(), "wandb-s3cr3t-value".to_string().into())]),
+            secret_refs: Vec::new(),
+            submitter_did: None,
+            seed: Some(1234),
+            restart_policy: RestartPolicy::Never,
+            resume_from: None,
+            max_runtime_secs: None,
+            model: None,
+        }
+    }
+
+    /// Completed job as ai-runtime would have recorded it
+    async fn insert_fixture_job(state: &Arc<AppState>, req: &StartJobRequest, outputs: &[&str]) {
+        let inputs = vec![
+            repro::InputManifest { role: "model".to_string(), cid: req.model_cid.clone(), sha256: Some(repro::sha256_hex(b"weights")) },
+            repro::InputManifest { role: "dataset".to_string(), cid: req.dataset_cid.clone().unwrap(), sha256: Some(repro::sha256_hex(b"rows")) },
+        ];
+        let execution = ExecutionRecord::capture(req, "artha/torch-runtime:v1", "sha256:torch", inputs, 1000);
+        state.job_secrets.write().await.insert(req.job_id.clone(), repro::secret_values(req));
+        state.jobs.write().await.insert(req.job_id.clone(), Job {
+            job_id: req.job_id.clone(),
+            job_type: req.job_type.clone(),
+            model_cid: req.model_cid.clone(),
+            dataset_cid: req.dataset_cid.clone(),
+            params: req.params.clone(),
+            container_id: Some("container-original".to_string()),
+            status: ContainerStatus::Completed,
+            gpu_allocated: None,
+            started_at: Some(1000),
+            logs: JobLog::default(),
+            checkpoints: Vec::new(),
+            tee_required: false,
+            attestation: None,
+            execution: Some(execution),
+            output_digests: Some(outputs.iter().map(|d| d.to_string()).collect()),
+            replay: None,
+            restart_policy: req.restart_policy,
+            restart_count: 0,
+            startup: None,
+            checkpoint_times: Vec::new(),
+            max_runtime_secs: req.max_runtime_secs,
+            terminal_reason: None,
+        });
+    }
+
+    #[tokio::test]
+    async fn test_job_logs_stay_bounded_in_memory_and_full_log_comes_from_svdb() {
+        // Only lines stamped after the cursor are new; --since repeats the last one
+        let output = "2026-01-01T00:00:01.000000000Z step 0\n2026-01-01T00:00:02.000000000Z step 1\n";
+        assert_eq!(new_log_lines(output, None), vec![("2026-01-01T00:00:01.000000000Z", "step 0"), ("2026-01-01T00:00:02.000000000Z", "step 1")]);
+        assert_eq!(new_log_lines(output, Some("2026-01-01T00:00:01.000000000Z")), vec![("2026-01-01T00:00:02.000000000Z", "step 1")]);
+
+        let blobs: Arc<std::sync::Mutex<Vec<Vec<u8>>>> = Arc::default();
+        let (uploads, downloads) = (blobs.clone(), blobs.clone());
+        let svdb_url = spawn(
+            Router::new()
+                .route("/svdb/upload", post(move |body: axum::body::Bytes| {
+                    let uploads = uploads.clone();
+                    async move {
+                        let mut blobs = uploads.lock().unwrap();
+                        blobs.push(body.to_vec());
+                        Json(serde_json::json!({ "cid": format!("log{}", blobs.len() - 1) }))
+                    }
+                }))
+                .route("/svdb/download/:cid", get(move |Path(cid): Path<String>| {
+                    let downloads = downloads.clone();
+                    async move { downloads.lock().unwrap()[cid.trim_start_matches("log").parse::<usize>().unwrap()].clone() }
+                })),
+        )
+        .await;
+        let (state, _) = telemetry_state(HashMap::new());
+        let state = Arc::new(AppState {
+            svdb_client: Arc::new(SvdbClient::new(svdb_url)),
+            log_limits: LogLimits { max_lines: 5, max_bytes: 1024, max_pending: 1000 },
+            ..Arc::try_unwrap(state).ok().unwrap()
+        });
+        let req = repro_request("job-chatty");
+        insert_fixture_job(&state, &req, &["sha256:out"]).await;
+        state.jobs.write().await.get_mut("job-chatty").unwrap().logs = JobLog::new(state.log_limits);
+
+        for (from, to) in [(0, 40), (40, 90)] {
+            let mut jobs = state.jobs.write().await;
+            for i in from..to {
+                jobs.get_mut("job-chatty").unwrap().logs.push(format!("step {}", i));
+            }
+            drop(jobs);
+            assert_eq!(archive_job_logs(&state).await, 1);
+        }
+        {
+            let mut jobs = state.jobs.write().await;
+            let log = &mut jobs.get_mut("job-chatty").unwrap().logs;
+            for i in 90..100 {
+                log.push(format!("step {}", i));
+            }
+            assert_eq!((log.len(), log.total(), log.chunks().len()), (5, 100, 2));
+            assert_eq!(log.chunks()[0], "artha://log0");
+        }
+
+        let Json(tail) = get_job_logs(State(state.clone()), Path("job-chatty".to_string()), Query(LogQuery { full: false })).await.unwrap();
+        assert_eq!(tail, (95..100).map(|i| format!("step {}", i)).collect::<Vec<_>>());
+        let Json(full) = get_job_logs(State(state.clone()), Path("job-chatty".to_string()), Query(LogQuery { full: true })).await.unwrap();
+        assert_eq!(full, (0..100).map(|i| format!("step {}", i)).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_repro_bundle_complete_and_redacted() {
+        let (state, _) = telemetry_state(HashMap::new());
+        let req = repro_request("job-garbage");
+        insert_fixture_job(&state, &req, &["sha256:out"]).await;
+
+        let Json(bundle) = get_repro_bundle(State(state.clone()), Path("job-garbage".to_string())).await.unwrap();
+        let execution = &bundle.execution;
+        assert_eq!(execution.image_digest, "sha256:torch");
+        assert_eq!(execution.pinned_image(), "artha/torch-runtime@sha256:torch");
+        assert_eq!(execution.params.epochs, Some(3));
+        assert_eq!(execution.seed, Some(1234));
+        assert_eq!(bundle.dataset_cid.as_deref(), Some("artha://dataset"));
+        assert_eq!(execution.inputs.len(), 2);
+        assert_eq!(bundle.redacted, vec!["HF_TOKEN", "WANDB_KEY"]);
+        let env: Vec<(&str, Option<&str>)> = execution.env.iter().map(|v| (v.name.as_str(), v.value.as_deref())).collect();
+        assert_eq!(env, vec![
+            ("ARTHA_SEED", Some("1234")),
+            ("CUBLAS_WORKSPACE_CONFIG", Some(":4096:8")),
+            ("HF_TOKEN", None),
+            ("LOG_LEVEL", Some("debug")),
+            ("PL_GLOBAL_SEED", Some("1234")),
+            ("PYTHONHASHSEED", Some("1234")),
+            ("WANDB_KEY", None),
+        ]);
+
+        // The replay script pins the image, checks input hashes and demands the redacted secrets
+        assert_eq!(bundle.files.keys().collect::<Vec<_>>(), vec!["docker-compose.yml", "job.json", "replay.sh"]);
+        let script = &bundle.files["replay.sh"];
+        assert!(script.contains("'artha/torch-runtime@sha256:torch'"));
+        assert!(script.contains(&repro::sha256_hex(b"weights")));
+        assert!(script.contains(&repro::sha256_hex(b"rows")));
+        assert!(script.contains("-e ARTHA_SEED='1234'"));
+        assert!(script.contains(": \"${WANDB_KEY:?"));
+        assert!(script.contains("  -e HF_TOKEN \\\n"));
+        assert!(bundle.files["docker-compose.yml"].contains("image: \"artha/torch-runtime@sha256:torch\""));
+
+        // No secret bytes anywhere in the bundle
+        let serialized = serde_json::to_string(&bundle).unwrap();
+        assert!(!serialized.contains("hf_live_0123456789"));
+        assert!(!serialized.contains("wandb-s3cr3t-value"));
+        let secrets = repro::secret_values(&req);
+        assert!(bundle.verify_redaction(&secrets).is_ok());
+        let mut leaky = bundle.clone();
+        leaky.files.insert("notes.txt".to_string(), "token hf_live_0123456789".to_string());
+        assert!(leaky.verify_redaction(&secrets).unwrap_err().contains("HF_TOKEN"));
+
+        let missing = get_repro_bundle(State(state), Path("nope".to_string())).await;
+        assert_eq!(missing.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_replay_links_and_diffs_against_original() {
+        let backend = Arc::new(MockContainerBackend::default());
+        let (pools, allocations) = pool_manager(backend.clone(), GPU_COUNT);
+        pools.refill().await;
+        let images = Arc::new(MockImageStore { digests: std::sync::Mutex::new(vec!["sha256:torch".to_string()]) });
+        let sampler = Arc::new(MockGpuSampler { utilization: std::sync::Mutex::new(HashMap::new()) });
+        let state = app_state(pools, allocations, sampler, images, Arc::new(MockJobContainers::default()));
+        let req = repro_request("job-1");
+        insert_fixture_job(&state, &req, &["sha256:a", "sha256:b"]).await;
+
+        let Json(started) = replay_job(State(state.clone()), Json(ReplayRequest {
+            job_id: "job-1".to_string(),
+            replay_job_id: Some("job-1-replay".to_string()),
+        })).await.unwrap();
+        assert_eq!(started.job_id, "job-1-replay");
+
+        // Same params, env (secrets re-supplied by the runtime) and seed reach the container
+        let spec = backend.claimed.lock().unwrap()[&started.container_id].clone();
+        assert_eq!(spec.params["epochs"], 3);
+        assert_eq!(spec.env["WANDB_KEY"].expose(), "wandb-s3cr3t-value");
+        assert_eq!(spec.env["HF_TOKEN"].expose(), "hf_live_0123456789");
+        assert_eq!(spec.env["LOG_LEVEL"].expose(), "debug");
+        assert_eq!(spec.env[repro::SEED_ENV].expose(), "1234");
+        assert_eq!(spec.env["PYTHONHASHSEED"].expose(), "1234");
+
+        // Linked to the original, undecided until the replay finishes
+        let Json(status) = get_job_status(State(state.clone()), Path("job-1-replay".to_string())).await.unwrap();
+        assert_eq!(status.execution.as_ref().unwrap().image_digest, "sha256:torch");
+        assert_eq!(status.replay, Some(ReplayStatus::new("job-1")));
+        let Json(bundle) = get_repro_bundle(State(state.clone()), Path("job-1-replay".to_string())).await.unwrap();
+        assert_eq!(bundle.replay_of.as_deref(), Some("job-1"));
+
+        state.jobs.write().await.get_mut("job-1-replay").unwrap().output_digests =
+            Some(vec!["sha256:a".to_string(), "sha256:c".to_string()]);
+        let Json(status) = get_job_status(State(state.clone()), Path("job-1-replay".to_string())).await.unwrap();
+        let replay = status.replay.unwrap();
+        assert_eq!(replay.outputs_match, Some(false));
+        assert_eq!(replay.only_in_original, vec!["sha256:b"]);
+        assert_eq!(replay.only_in_replay, vec!["sha256:c"]);
+
+        state.jobs.write().await.get_mut("job-1-replay").unwrap().output_digests =
+            Some(vec!["sha256:a".to_string(), "sha256:b".to_string()]);
+        let Json(status) = get_job_status(State(state.clone()), Path("job-1-replay".to_string())).await.unwrap();
+        assert_eq!(status.replay.unwrap().outputs_match, Some(true));
+
+        // Replay ids can't collide with existing jobs
+        let duplicate = replay_job(State(state), Json(ReplayRequest {
+            job_id: "job-1".to_string(),
+            replay_job_id: Some("job-1-replay".to_string()),
+        })).await;
+        assert_eq!(duplicate.unwrap_err(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_replay_refused_when_image_digest_gone() {
+        let backend = Arc::new(MockContainerBackend::default());
+        let (pools, allocations) = pool_manager(backend.clone(), GPU_COUNT);
+        pools.refill().await;
+        let sampler = Arc::new(MockGpuSampler { utilization: std::sync::Mutex::new(HashMap::new()) });
+        let state = app_state(pools, allocations, sampler, Arc::new(MockImageStore::default()), Arc::new(MockJobContainers::default()));
+        insert_fixture_job(&state, &repro_request("job-1"), &["sha256:a"]).await;
+
+        let result = replay_job(State(state.clone()), Json(ReplayRequest {
+            job_id: "job-1".to_string(),
+            replay_job_id: None,
+        })).await;
+        assert_eq!(result.unwrap_err(), StatusCode::GONE);
+        assert_eq!(state.jobs.read().await.len(), 1);
+        assert!(backend.claimed.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_injected_secrets_stay_out_of_logs() {
+        let logs = artha_log::capture();
+        let backend = Arc::new(MockContainerBackend::default());
+        let (pools, allocations) = pool_manager(backend.clone(), GPU_COUNT);
+        pools.refill().await;
+        let images = Arc::new(MockImageStore { digests: std::sync::Mutex::new(vec!["sha256:torch".to_string()]) });
+        let sampler = Arc::new(MockGpuSampler { utilization: std::sync::Mutex::new(HashMap::new()) });
+        let containers = Arc::new(MockJobContainers::default());
+        let state = app_state(pools, allocations, sampler, images, containers.clone());
+        let req = repro_request("job-1");
+        insert_fixture_job(&state, &req, &["sha256:a"]).await;
+
+        // A replay re-injects the secrets the runtime kept for the original
+        let Json(started) = replay_job(State(state.clone()), Json(ReplayRequest {
+            job_id: "job-1".to_string(),
+            replay_job_id: Some("job-1-replay".to_string()),
+        })).await.unwrap();
+        assert_eq!(started.job_id, "job-1-replay");
+        let claimed = backend.claimed.lock().unwrap().values().find(|spec| spec.job_id == "job-1-replay").cloned().unwrap();
+        assert_eq!(claimed.env["WANDB_KEY"].expose(), "wandb-s3cr3t-value");
+
+        // Dumping everything that carries them is as safe as the handlers' own logging
+        tracing::debug!("request {:?}", req);
+        tracing::debug!("claimed {:?}", claimed);
+        tracing::debug!("kept {:?}", state.job_secrets.read().await);
+        assert_eq!(logs.leaked(&["wandb-s3cr3t-value", "hf_live_0123456789"]), Vec::<&str>::new());
+        let text = logs.lines().join("\n");
+        assert!(text.contains("Starting job: job-1-replay"), "{}", text);
+        assert!(text.contains("\"WANDB_KEY\": [REDACTED]") && text.contains("\"LOG_LEVEL\": \"debug\""), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_vault_secret_is_injected_but_never_recorded_or_logged() {
+        let logs = artha_log::capture();
+        let vault_url = spawn(
+            Router::new()
+                .route("/secrets/:name", get(|Path(name): Path<String>, headers: axum::http::HeaderMap| async move {
+                    if headers.get("X-Vault-Token").map(|t| t.as_bytes()) != Some(b"vault-root-token") {
+                        return Err(StatusCode::FORBIDDEN);
+                    }
+                    match name.as_str() {
+                        "OPENAI_API_KEY" => Ok(Json(serde_json::json!({ "value": "sk-vault-0123456789" }))),
+                        _ => Err(StatusCode::NOT_FOUND),
+                    }
+                }))
+                .route("/policy/check", post(|Json(check): Json<serde_json::Value>| async move {
+                    let allowed = check["did"] == "did:artha:alice" && check["action"] == vault::READ_SECRET_ACTION;
+                    Json(serde_json::json!({ "allowed": allowed, "reason": (!allowed).then_some("not a secret holder") }))
+                })),
+        )
+        .await;
+        let (gateway, _, _) = spawn_svdb_gateway(serde_json::json!([]), b"weights".to_vec()).await;
+        let containers = Arc::new(MockJobContainers::default());
+        let mut state = restart_state(containers.clone());
+        let app = Arc::get_mut(&mut state).unwrap();
+        app.svdb_client = Arc::new(SvdbClient::new(gateway));
+        app.vault = Some(Arc::new(VaultClient::new(vault_url.clone(), Some("vault-root-token".to_string().into()), vault_url)));
+
+        let mut req = repro_request("job-vault");
+        req.secret_refs = vec!["OPENAI_API_KEY".to_string()];
+        req.submitter_did = Some("did:artha:alice".to_string());
+        assert!(start_job(State(state.clone()), HeaderMap::new(), Json(req)).await.is_ok());
+
+        // The container gets the value under the secret's name
+        let (_, launch) = containers.launched.lock().unwrap()[0].clone();
+        assert_eq!(launch.env["OPENAI_API_KEY"].expose(), "sk-vault-0123456789");
+
+        // The job record names it as a redacted secret and holds no value
+        let job = state.jobs.read().await["job-vault"].clone();
+        assert!(job.execution.as_ref().unwrap().redacted().contains(&"OPENAI_API_KEY".to_string()));
+        let record = serde_json::to_string(&job).unwrap();
+        assert!(record.contains("OPENAI_API_KEY") && !record.contains("sk-vault-0123456789"), "{}", record);
+        tracing::debug!("launched {:?}", launch);
+        assert_eq!(logs.leaked(&["sk-vault-0123456789"]), Vec::<&str>::new());
+        assert!(logs.lines().join("\n").contains("Secrets: OPENAI_API_KEY resolved from vault"));
+
+        // Another submitter is refused by policy-gate, and an unknown secret by the vault; neither launches
+        let mut denied = repro_request("job-vault-denied");
+        denied.secret_refs = vec!["OPENAI_API_KEY".to_string()];
+        denied.submitter_did = Some("did:artha:bob".to_string());
+        assert_eq!(start_job(State(state.clone()), HeaderMap::new(), Json(denied)).await.unwrap_err(), StatusCode::FORBIDDEN);
+        let mut missing = repro_request("job-vault-missing");
+        missing.secret_refs = vec!["HF_TOKEN".to_string()];
+        missing.submitter_did = Some("did:artha:alice".to_string());
+        assert_eq!(start_job(State(state.clone()), HeaderMap::new(), Json(missing)).await.unwrap_err(), StatusCode::NOT_FOUND);
+        assert_eq!(containers.launched.lock().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all("/tmp/artha/jobs/job-vault");
+    }
+
+    #[tokio::test]
+    async fn test_job_exceeding_gpu_memory_is_rejected_before_launch() {
+        let (gateway, _, _) = spawn_svdb_gateway(serde_json::json!([]), b"weights".to_vec()).await;
+        let containers = Arc::new(MockJobContainers::default());
+        let (pools, allocations) = pool_manager(Arc::new(MockContainerBackend::default()), GPU_COUNT);
+        let sampler = Arc::new(MockGpuSampler { utilization: std::sync::Mutex::new(HashMap::new()) });
+        sampler.set(0, 0.0); // 80920 MB free
+        sampler.set(1, 0.0); // 79920 MB free
+        let mut state = app_state(pools, allocations, sampler, Arc::new(MockImageStore::default()), containers.clone());
+        Arc::get_mut(&mut state).unwrap().svdb_client = Arc::new(SvdbClient::new(gateway));
+
+        // 7B bf16 weights, gradients and Adam moments alone overrun an 80 GB card
+        let mut oversized = repro_request("job-7b");
+        oversized.model = Some(ModelFootprint { params: 7_000_000_000, precision: "bf16".to_string(), activation_mb_per_sample: None });
+        assert_eq!(start_job(State(state.clone()), HeaderMap::new(), Json(oversized)).await.unwrap_err(), StatusCode::INSUFFICIENT_STORAGE);
+        assert!(containers.launched.lock().unwrap().is_empty());
+        assert!(state.gpu_allocations.read().await.is_empty());
+        assert!(!state.jobs.read().await.contains_key("job-7b"));
+
+        // A model that fits at a smaller batch launches with the batch halved until it does
+        let mut shrinkable = repro_request("job-1b");
+        shrinkable.params.batch_size = Some(64);
+        shrinkable.model = Some(ModelFootprint { params: 1_000_000_000, precision: "bf16".to_string(), activation_mb_per_sample: Some(2000.0) });
+        assert!(start_job(State(state.clone()), HeaderMap::new(), Json(shrinkable)).await.is_ok());
+        let (_, launch) = containers.launched.lock().unwrap()[0].clone();
+        assert_eq!(launch.params.batch_size, Some(32));
+        assert_eq!(state.jobs.read().await["job-1b"].params.batch_size, Some(32));
+
+        // Without a declared model there is nothing to check
+        let mut undeclared = repro_request("job-undeclared");
+        undeclared.params.batch_size = Some(64);
+        assert!(start_job(State(state.clone()), HeaderMap::new(), Json(undeclared)).await.is_ok());
+        assert_eq!(containers.launched.lock().unwrap()[1].1.params.batch_size, Some(64));
+        for job in ["job-1b", "job-undeclared"] {
+            let _ = std::fs::remove_dir_all(format!("/tmp/artha/jobs/{}", job));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadline_passing_during_mount_releases_the_gpu_without_launching() {
+        // The model takes ten seconds to arrive
+        let clock = ManualClock::new(1_700_000_000);
+        let slow = clock.clone();
+        let gateway = spawn(
+            Router::new()
+                .route("/svdb/:cid/replication", get(|| async { StatusCode::NOT_FOUND }))
+                .route("/svdb/download/:cid", get(move || {
+                    slow.advance_secs(10);
+                    async { b"weights".to_vec() }
+                })),
+        )
+        .await;
+        let containers = Arc::new(MockJobContainers::default());
+        let mut state = restart_state(containers.clone());
+        let app = Arc::get_mut(&mut state).unwrap();
+        app.svdb_client = Arc::new(SvdbClient::new(gateway));
+        app.clock = clock.shared();
+        let within = |secs: u64| {
+            let mut headers = HeaderMap::new();
+            headers.insert(artha_errors::DEADLINE_HEADER, (clock.now_millis() + secs * 1000).to_string().parse().unwrap());
+            headers
+        };
+
+        // Five seconds run out mid-download: the GPU and the mounts are given back
+        let late = start_job(State(state.clone()), within(5), Json(repro_request("job-late"))).await;
+        assert_eq!(late.unwrap_err(), StatusCode::REQUEST_TIMEOUT);
+        assert!(containers.launched.lock().unwrap().is_empty());
+        assert!(state.gpu_allocations.read().await.is_empty());
+        assert_eq!(cache_holders(&state, "artha://model").unwrap(), Vec::<String>::new());
+        assert!(!state.jobs.read().await.contains_key("job-late"));
+
+        // A deadline already gone is refused outright, and one with time to spare launches
+        let gone = start_job(State(state.clone()), within(0), Json(repro_request("job-gone"))).await;
+        assert_eq!(gone.unwrap_err(), StatusCode::REQUEST_TIMEOUT);
+        assert!(start_job(State(state.clone()), within(60), Json(repro_request("job-timely"))).await.is_ok());
+        assert_eq!(containers.launched.lock().unwrap().len(), 1);
+        for job in ["job-late", "job-timely"] {
+            let _ = std::fs::remove_dir_all(format!("/tmp/artha/jobs/{}", job));
+        }
+    }
+
+    fn restart_state(containers: Arc<MockJobContainers>) -> Arc<AppState> {
+        let (pools, allocations) = pool_manager(Arc::new(MockContainerBackend::default()), GPU_COUNT);
+        let sampler = Arc::new(MockGpuSampler { utilization: std::sync::Mutex::new(HashMap::new()) });
+        app_state(pools, allocations, sampler, Arc::new(MockImageStore::default()), containers)
+    }
+
+    /// Running job holding gpu:0, with its mounts and two checkpoints on disk
+    async fn insert_running_job(state: &Arc<AppState>, job_id: &str, policy: RestartPolicy) -> String {
+        let mut req = repro_request(job_id);
+        req.restart_policy = policy;
+        insert_fixture_job(state, &req, &[]).await;
+
+        let job_dir = format!("/tmp/artha/jobs/{}", job_id);
+        let _ = std::fs::remove_dir_all(&job_dir);
+        for dir in ["model", "data", "checkpoints"] {
+            std::fs::create_dir_all(format!("{}/{}", job_dir, dir)).unwrap();
+        }
+        for step in 1..=2 {
+            std::fs::write(format!("{}/checkpoints/checkpoint-{}.pt", job_dir, step), format!("weights-{}", step)).unwrap();
+        }
+
+        let mut jobs = state.jobs.write().await;
+        let job = jobs.get_mut(job_id).unwrap();
+        job.status = ContainerStatus::Running;
+        job.output_digests = None;
+        job.gpu_allocated = Some("gpu:0".to_string());
+        state.gpu_allocations.write().await.insert("gpu:0".to_string(), job_id.to_string());
+        job_dir
+    }
+
+    #[tokio::test]
+    async fn test_crashed_container_restarts_from_latest_checkpoint_and_succeeds() {
+        let containers = Arc::new(MockJobContainers::default());
+        let state = restart_state(containers.clone());
+        let job_dir = insert_running_job(&state, "job-crash-once", "on-failure:2".parse().unwrap()).await;
+
+        assert_eq!(poll_container(&state, "job-crash-once", "container-original").await, ContainerPoll::Running);
+
+        containers.exit("container-original", 137);
+        let ContainerPoll::Restarted(restarted) = poll_container(&state, "job-crash-once", "container-original").await else {
+            panic!("crashed container was not restarted");
+        };
+        assert_eq!(*containers.removed.lock().unwrap(), vec!["container-original"]);
+
+        // Same pinned image, GPU, params and env (secrets and seed included), resuming from the newest checkpoint
+        let (launched_id, launch) = containers.launched.lock().unwrap()[0].clone();
+        assert_eq!(launched_id, restarted);
+        assert_eq!(launch.image, "artha/torch-runtime@sha256:torch");
+        assert_eq!(launch.gpu_id, "gpu:0");
+        assert_eq!(launch.params.epochs, Some(3));
+        assert_eq!(launch.env["WANDB_KEY"].expose(), "wandb-s3cr3t-value");
+        assert_eq!(launch.env[repro::SEED_ENV].expose(), "1234");
+        assert_eq!(launch.env[restart::RESUME_ENV].expose(), "/checkpoints/checkpoint-2.pt");
+
+        let job = state.jobs.read().await["job-crash-once"].clone();
+        assert_eq!(job.status, ContainerStatus::Running);
+        assert_eq!(job.restart_count, 1);
+        assert_eq!(job.container_id.as_deref(), Some(restarted.as_str()));
+
+        containers.exit(&restarted, 0);
+        assert_eq!(poll_container(&state, "job-crash-once", &restarted).await, ContainerPoll::Finished);
+        let job = state.jobs.read().await["job-crash-once"].clone();
+        assert_eq!(job.status, ContainerStatus::Completed);
+        assert_eq!(job.restart_count, 1);
+        assert_eq!(job.output_digests.map(|d| d.len()), Some(2));
+        assert!(state.gpu_allocations.read().await.is_empty());
+        let _ = std::fs::remove_dir_all(job_dir);
+    }
+
+    #[tokio::test]
+    async fn test_container_failing_past_restart_limit_ends_failed() {
+        assert_eq!("never".parse::<RestartPolicy>(), Ok(RestartPolicy::Never));
+        assert_eq!("always".parse::<RestartPolicy>(), Ok(RestartPolicy::Always));
+        assert!("on-failure".parse::<RestartPolicy>().is_err());
+        assert_eq!(serde_json::to_value(RestartPolicy::OnFailure(3)).unwrap(), "on-failure:3");
+
+        let containers = Arc::new(MockJobContainers::default());
+        let state = restart_state(containers.clone());
+        let job_dir = insert_running_job(&state, "job-crash-loop", RestartPolicy::OnFailure(1)).await;
+
+        containers.exit("container-original", 1);
+        let ContainerPoll::Restarted(restarted) = poll_container(&state, "job-crash-loop", "container-original").await else {
+            panic!("first crash should be restarted");
+        };
+        containers.exit(&restarted, 1);
+        assert_eq!(poll_container(&state, "job-crash-loop", &restarted).await, ContainerPoll::Finished);
+
+        let job = state.jobs.read().await["job-crash-loop"].clone();
+        assert_eq!(job.status, ContainerStatus::Failed);
+        assert_eq!(job.restart_count, 1);
+        assert_eq!(job.output_digests, None);
+        assert_eq!(containers.launched.lock().unwrap().len(), 1);
+        assert!(state.gpu_allocations.read().await.is_empty());
+
+        // A job stopped on request exits non-zero too, but is never restarted
+        let stopped_dir = insert_running_job(&state, "job-stopped", RestartPolicy::Always).await;
+        state.jobs.write().await.get_mut("job-stopped").unwrap().status = ContainerStatus::Stopped;
+        containers.exit("container-original", 143);
+        assert_eq!(poll_container(&state, "job-stopped", "container-original").await, ContainerPoll::Finished);
+        assert_eq!(state.jobs.read().await["job-stopped"].restart_count, 0);
+        assert_eq!(containers.launched.lock().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(job_dir);
+        let _ = std::fs::remove_dir_all(stopped_dir);
+    }
+
+    #[tokio::test]
+    async fn test_oom_kill_and_timeout_report_distinct_terminal_reasons() {
+        let containers = Arc::new(MockJobContainers::default());
+        let mut state = restart_state(containers.clone());
+        let reports: Arc<std::sync::Mutex<Vec<(String, serde_json::Value)>>> = Arc::default();
+        let recorded = reports.clone();
+        let jobd = Router::new().route("/job/:id/progress", post(move |Path(id): Path<String>, Json(body): Json<serde_json::Value>| {
+            recorded.lock().unwrap().push((id, body));
+            async { StatusCode::OK }
+        }));
+        Arc::get_mut(&mut state).unwrap().jobd_url = spawn(jobd).await;
+
+        let oom_dir = insert_running_job(&state, "job-oom", RestartPolicy::Never).await;
+        containers.oom_kill("container-original");
+        assert_eq!(poll_container(&state, "job-oom", "container-original").await, ContainerPoll::Finished);
+        let job = state.jobs.read().await["job-oom"].clone();
+        assert_eq!(job.status, ContainerStatus::Failed);
+        let reason = job.terminal_reason.unwrap();
+        assert_eq!((reason.kind, reason.exit_code), (TerminalReasonKind::OutOfMemory, Some(137)));
+        assert_eq!(reason.detail, "Container was killed out of memory (exit code 137) after 0 restarts");
+
+        // Still running, but past its limit: removed and failed as a timeout
+        let slow_dir = insert_running_job(&state, "job-slow", RestartPolicy::Always).await;
+        state.jobs.write().await.get_mut("job-slow").unwrap().max_runtime_secs = Some(60);
+        assert_eq!(poll_container(&state, "job-slow", "container-slow").await, ContainerPoll::Finished);
+        assert_eq!(*containers.removed.lock().unwrap(), vec!["container-original", "container-slow"]);
+        assert!(containers.launched.lock().unwrap().is_empty());
+
+        let reports = reports.lock().unwrap().clone();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].0, "job-oom");
+        assert_eq!(reports[0].1["status"], "Failed");
+        assert_eq!(reports[0].1["terminal_reason"]["kind"], "out_of_memory");
+        assert_eq!(reports[0].1["terminal_reason"]["exit_code"], 137);
+        assert_eq!(reports[1].0, "job-slow");
+        assert_eq!(reports[1].1["terminal_reason"]["kind"], "timeout");
+        assert_eq!(reports[1].1["failure_reason"], "Job ran past its 60s time limit");
+        let _ = std::fs::remove_dir_all(oom_dir);
+        let _ = std::fs::remove_dir_all(slow_dir);
+    }
+
+    fn migrate_request(timeout_secs: u64) -> MigrateRequest {
+        MigrateRequest {
+            initiated_by: "scheduler:drain".to_string(),
+            reason: "node drained".to_string(),
+            timeout_secs: Some(timeout_secs),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cooperative_migration_exports_final_checkpoint() {
+        let containers = Arc::new(MockJobContainers::default());
+        let mut state = restart_state(containers.clone());
+        let reports: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let recorded = reports.clone();
+        let jobd = Router::new().route("/job/:id/progress", post(move |Json(body): Json<serde_json::Value>| {
+            recorded.lock().unwrap().push(body);
+            async { StatusCode::OK }
+        }));
+        Arc::get_mut(&mut state).unwrap().jobd_url = spawn(jobd).await;
+
+        let job_dir = insert_running_job(&state, "job-migrate", RestartPolicy::Always).await;
+        let req = migrate_request(5);
+        let container_id = begin_migration(&state, "job-migrate", &req).await.unwrap();
+        assert_eq!(container_id, "container-original");
+        assert_eq!(begin_migration(&state, "job-migrate", &req).await, Err(StatusCode::CONFLICT));
+        let control = migrate::control_file(&format!("{}/checkpoints", job_dir));
+        assert!(std::path::Path::new(&control).exists());
+
+        // The container answers: an immediate checkpoint, then the migration exit code
+        std::fs::write(format!("{}/checkpoints/checkpoint-3.pt", job_dir), "weights-3").unwrap();
+        containers.exit(&container_id, migrate::MIGRATE_EXIT_CODE);
+        // The monitor neither restarts nor removes a container mid-migration
+        assert_eq!(poll_container(&state, "job-migrate", &container_id).await, ContainerPoll::Running);
+        assert!(containers.removed.lock().unwrap().is_empty());
+
+        let handoff = finish_migration(&state, "job-migrate", &container_id, &req).await.unwrap();
+        assert_eq!(handoff.mode, MigrationMode::Cooperative);
+        assert_eq!(handoff.checkpoint.as_deref(), Some("checkpoint-3.pt"));
+        assert_eq!(handoff.steps_lost, Some(0));
+        assert_eq!(handoff.initiated_by, "scheduler:drain");
+        let cid = handoff.resume_from.clone().expect("final checkpoint uploaded");
+        assert!(!std::path::Path::new(&control).exists());
+        assert_eq!(*containers.removed.lock().unwrap(), vec!["container-original"]);
+        assert!(containers.launched.lock().unwrap().is_empty());
+
+        let job = state.jobs.read().await["job-migrate"].clone();
+        assert_eq!(job.status, ContainerStatus::Migrated);
+        assert_eq!(job.checkpoints, vec![cid.clone()]);
+        assert!(state.gpu_allocations.read().await.is_empty());
+
+        // ai-jobd hears about it as a migratable completion
+        report_migration(&state.jobd_url, "job-migrate", &handoff).await;
+        let report = reports.lock().unwrap()[0].clone();
+        assert_eq!(report["migratable"]["mode"], "cooperative");
+        assert_eq!(report["migratable"]["resume_from"], cid);
+        let _ = std::fs::remove_dir_all(job_dir);
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_container_migrates_from_last_checkpoint() {
+        let containers = Arc::new(MockJobContainers::default());
+        let state = restart_state(containers.clone());
+        let job_dir = insert_running_job(&state, "job-migrate-timeout", RestartPolicy::Never).await;
+
+        // No answer before the deadline: stopped at the last periodic checkpoint
+        let req = migrate_request(0);
+        let container_id = begin_migration(&state, "job-migrate-timeout", &req).await.unwrap();
+        let handoff = finish_migration(&state, "job-migrate-timeout", &container_id, &req).await.unwrap();
+        assert_eq!(handoff.mode, MigrationMode::Degraded);
+        assert_eq!(handoff.checkpoint.as_deref(), Some("checkpoint-2.pt"));
+        assert_eq!(handoff.steps_lost, None);
+        assert!(handoff.resume_from.is_some());
+        assert_eq!(*containers.removed.lock().unwrap(), vec!["container-original"]);
+        assert!(!std::path::Path::new(&migrate::control_file(&format!("{}/checkpoints", job_dir))).exists());
+        assert_eq!(state.jobs.read().await["job-migrate-timeout"].status, ContainerStatus::Migrated);
+
+        // A job that finishes while being asked stays on its node and completes normally
+        let finished_dir = insert_running_job(&state, "job-migrate-done", RestartPolicy::Never).await;
+        let container_id = begin_migration(&state, "job-migrate-done", &migrate_request(5)).await.unwrap();
+        containers.exit(&container_id, 0);
+        let finished = finish_migration(&state, "job-migrate-done", &container_id, &migrate_request(5)).await;
+        assert_eq!(finished, Err(StatusCode::CONFLICT));
+        assert_eq!(poll_container(&state, "job-migrate-done", &container_id).await, ContainerPoll::Finished);
+        assert_eq!(state.jobs.read().await["job-migrate-done"].status, ContainerStatus::Completed);
+        let _ = std::fs::remove_dir_all(job_dir);
+        let _ = std::fs::remove_dir_all(finished_dir);
+    }
+
+
+    fn window_summary(windows: &[stream::Window]) -> Vec<(u64, u64, u64, Vec<serde_json::Value>, u64)> {
+        windows
+            .iter()
+            .map(|w| (w.bounds.index, w.bounds.start, w.bounds.end, w.records.clone(), w.resume_offset))
+            .collect()
+    }
+
+    /// Push `(offset, timestamp)` events carrying their offset, collecting completed windows
+    fn cut(policy: stream::WindowPolicy, events: &[(u64, u64)]) -> Vec<stream::Window> {
+        let mut windower = stream::Windower::new(&policy, 0);
+        events.iter().flat_map(|&(offset, timestamp)| windower.push(offset, timestamp, &[serde_json::json!(offset)])).collect()
+    }
+
+    #[test]
+    fn test_stream_windows_cut_on_boundaries() {
+        use serde_json::json;
+        use stream::{WindowPolicy, WindowUnit};
+        let by_count: Vec<(u64, u64)> = (0..8).map(|o| (o, 0)).collect();
+
+        // Tumbling by count: a window completes on its last offset
+        let windows = cut(WindowPolicy::Tumbling { by: WindowUnit::Count, size: 3 }, &by_count);
+        assert_eq!(window_summary(&windows), vec![
+            (0, 0, 3, vec![json!(0), json!(1), json!(2)], 3),
+            (1, 3, 6, vec![json!(3), json!(4), json!(5)], 6), // [6, 9) is still open
+        ]);
+
+        // Sliding by count: overlapping windows share events; resuming after a
+        // window re-reads from the oldest event the next one holds
+        let windows = cut(WindowPolicy::Sliding { by: WindowUnit::Count, size: 4, slide: 2 }, &by_count);
+        assert_eq!(window_summary(&windows), vec![
+            (0, 0, 4, vec![json!(0), json!(1), json!(2), json!(3)], 2),
+            (1, 2, 6, vec![json!(2), json!(3), json!(4), json!(5)], 4),
+            (2, 4, 8, vec![json!(4), json!(5), json!(6), json!(7)], 6),
+        ]);
+
+        // Tumbling by time: the end is exclusive, and the event reaching it closes the window
+        let by_time = [(0, 0), (1, 999), (2, 1000), (3, 1500), (4, 3200)];
+        let windows = cut(WindowPolicy::Tumbling { by: WindowUnit::Time, size: 1000 }, &by_time);
+        assert_eq!(window_summary(&windows), vec![
+            (0, 0, 1000, vec![json!(0), json!(1)], 2),
+            (1, 1000, 2000, vec![json!(2), json!(3)], 4), // No events, no window for [2000, 3000)
+        ]);
+
+        // Sliding by time, with a late event windowed at the watermark rather than dropped
+        let by_time = [(0, 100), (1, 600), (2, 400), (3, 1000), (4, 1600)];
+        let windows = cut(WindowPolicy::Sliding { by: WindowUnit::Time, size: 1000, slide: 500 }, &by_time);
+        assert_eq!(window_summary(&windows), vec![
+            (0, 0, 1000, vec![json!(0), json!(1), json!(2)], 1),
+            (1, 500, 1500, vec![json!(1), json!(2), json!(3)], 3),
+        ]);
+
+        // A resumed windower never re-emits committed windows
+        let mut windower = stream::Windower::new(&WindowPolicy::Tumbling { by: WindowUnit::Count, size: 3 }, 1);
+        let resumed: Vec<stream::Window> = (2..6).flat_map(|o| windower.push(o, 0, &[json!(o)])).collect();
+        assert_eq!(window_summary(&resumed), vec![(1, 3, 6, vec![json!(3), json!(4), json!(5)], 6)]);
+    }
+
+    /// Bump allocator plus a `map` that walks its input once per byte, then echoes it
+    const METERED_MAP: &str = r#"(module
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 1024))
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+        (func (export "map") (param $ptr i32) (param $len i32) (result i64)
+            (local $i i32)
+            (block $done (loop $walk
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $walk)))
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))))"#;
+
+    fn event(offset: u64, data: serde_json::Value) -> stream::StreamEvent {
+        stream::StreamEvent { offset, timestamp: offset * 10, data }
+    }
+
+    #[test]
+    fn test_wasm_transform_gas_limit_enforced() {
+        use stream::{Transform, TransformError, WasmTransform};
+        let wasm = wat::parse_str(METERED_MAP).unwrap();
+
+        // Within the limit the module's output comes back as records
+        let mut transform = WasmTransform::new(&wasm, 5_000).unwrap();
+        let small = event(1, serde_json::json!({ "clicks": 3 }));
+        assert_eq!(transform.map(&small).unwrap(), vec![serde_json::to_value(&small).unwrap()]);
+        assert_eq!(transform.aggregate(vec![serde_json::json!(1)]).unwrap(), vec![serde_json::json!(1)]); // No aggregate export
+
+        // The same call on a large input runs out of gas, and each call gets a fresh allowance
+        let large = event(2, serde_json::json!("x".repeat(10_000)));
+        assert_eq!(transform.map(&large), Err(TransformError::OutOfGas { limit: 5_000 }));
+        assert!(transform.map(&small).is_ok());
+        assert!(WasmTransform::new(&wasm, 10_000_000).unwrap().map(&large).is_ok());
+
+        // A module that never returns is stopped at its limit
+        let spin = wat::parse_str(r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "aggregate") (param i32 i32) (result i64) (loop $forever (br $forever)) (i64.const 0)))"#).unwrap();
+        let mut spinning = WasmTransform::new(&spin, 100_000).unwrap();
+        assert_eq!(spinning.map(&small).unwrap(), vec![serde_json::json!({ "clicks": 3 })]); // No map export: data passes
+        assert_eq!(spinning.aggregate(vec![serde_json::json!(1)]), Err(TransformError::OutOfGas { limit: 100_000 }));
+
+        // Traps are crashes; modules without the interface are refused up front
+        let trap = wat::parse_str(r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "map") (param i32 i32) (result i64) unreachable))"#).unwrap();
+        assert!(matches!(WasmTransform::new(&trap, 1_000).unwrap().map(&small), Err(TransformError::Crashed(_))));
+        let bare = wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap();
+        assert!(matches!(WasmTransform::new(&bare, 1_000), Err(TransformError::Invalid(_))));
+        assert!(matches!(WasmTransform::new(b"not wasm", 1_000), Err(TransformError::Invalid(_))));
+    }
+
+    /// Mock event stream serving `events` from `/stream/events`
+    async fn spawn_event_stream(events: Vec<stream::StreamEvent>) -> String {
+        let app = Router::new().route("/stream/events", get(move |axum::extract::Query(query): axum::extract::Query<HashMap<String, usize>>| {
+            let from = query.get("from").copied().unwrap_or(0).min(events.len());
+            let to = (from + query.get("limit").copied().unwrap_or(100)).min(events.len());
+            let body = serde_json::json!({ "events": events[from..to], "head": events.len() });
+            async move { Json(body) }
+        }));
+        spawn(app).await
+    }
+
+    type Registered = Arc<std::sync::Mutex<Vec<serde_json::Value>>>;
+
+    /// Slow content-addressed SVDB upload plus ai-jobd dataset registration,
+    /// keeping every manifest and registration
+    async fn spawn_window_sink() -> (String, Arc<std::sync::Mutex<HashMap<String, serde_json::Value>>>, Registered) {
+        let manifests: Arc<std::sync::Mutex<HashMap<String, serde_json::Value>>> = Arc::default();
+        let registered: Registered = Arc::default();
+        let (stored, recorded) = (manifests.clone(), registered.clone());
+        let app = Router::new()
+            .route("/svdb/upload", post(move |body: axum::body::Bytes| {
+                let cid = repro::sha256_hex(&body);
+                stored.lock().unwrap().insert(format!("artha://{}", cid), serde_json::from_slice(&body).unwrap());
+                async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    Json(serde_json::json!({ "cid": cid }))
+                }
+            }))
+            .route("/ai/dataset/register", post(move |Json(body): Json<serde_json::Value>| {
+                let dataset_id = format!("dataset-{}", body["version"].as_str().unwrap());
+                recorded.lock().unwrap().push(body);
+                async move { Json(serde_json::json!({ "dataset_id": dataset_id })) }
+            }));
+        let url = spawn(app).await;
+        (url, manifests, registered)
+    }
+
+    /// Passes event data through, crashing the first time it sees `crash_at`
+    struct CrashOnce {
+        crash_at: u64,
+        crashed: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl stream::Transform for CrashOnce {
+        fn map(&mut self, event: &stream::StreamEvent) -> Result<Vec<serde_json::Value>, stream::TransformError> {
+            if event.offset == self.crash_at && !self.crashed.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                return Err(stream::TransformError::Crashed("segfault".to_string()));
+            }
+            Ok(vec![event.data.clone()])
+        }
+
+        fn aggregate(&mut self, records: Vec<serde_json::Value>) -> Result<Vec<serde_json::Value>, stream::TransformError> {
+            Ok(records)
+        }
+    }
+
+    fn stream_spec(input_stream: String) -> stream::StreamSpec {
+        serde_json::from_value(serde_json::json!({
+            "input_stream": input_stream,
+            "transform": { "kind": "container", "image": "artha/features:v1" },
+            "window": { "kind": "tumbling", "by": "count", "size": 2 },
+            "output": { "dataset": "clicks-features/{job_id}", "license_cid": "artha://license-cc-by-4.0" },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_resumes_after_crash_without_duplicates() {
+        let events: Vec<stream::StreamEvent> = (0..41).map(|o| event(o, serde_json::json!(o))).collect();
+        let input = spawn_event_stream(events).await;
+        let (sink_url, manifests, registered) = spawn_window_sink().await;
+        let spec = stream_spec(input);
+        assert!(spec.validate().is_ok());
+
+        let handle = Arc::new(StreamHandle::default());
+        let dir = temp_mount("stream-crash");
+        let run = Arc::new(StreamRun {
+            job_id: "job-stream".to_string(),
+            spec,
+            dir: dir.clone(),
+            sink: WindowSink { svdb: Arc::new(SvdbClient::new(sink_url.clone())), jobd_url: sink_url },
+            handle: handle.clone(),
+            poll_interval: std::time::Duration::from_millis(20),
+            clock: ManualClock::auto_advancing(1_700_000_000).shared(),
+        });
+        let crashed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let factory_crashed = crashed.clone();
+        let factory: TransformFactory = Box::new(move || {
+            Ok(Box::new(CrashOnce { crash_at: 15, crashed: factory_crashed.clone() }) as Box<dyn Transform>)
+        });
+        let running = run.clone();
+        let task = tokio::spawn(async move { running.run(&factory).await });
+
+        // 41 events make 20 complete windows of 2
+        for _ in 0..200 {
+            if handle.metrics().windows_committed >= 20 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        handle.stop();
+        assert_eq!(task.await.unwrap(), Ok(()));
+        assert!(crashed.load(std::sync::atomic::Ordering::SeqCst));
+
+        // Every window registered exactly once, in order, holding exactly its events
+        let registered = registered.lock().unwrap().clone();
+        let versions: Vec<&str> = registered.iter().map(|r| r["version"].as_str().unwrap()).collect();
+        let expected: Vec<String> = (0..20).map(|k| format!("w{}", k)).collect();
+        assert_eq!(versions, expected);
+        let manifests = manifests.lock().unwrap();
+        for (k, registration) in registered.iter().enumerate() {
+            assert_eq!(registration["name"], "clicks-features/job-stream");
+            assert_eq!(registration["samples"], 2);
+            assert_eq!(registration["window"], serde_json::json!({ "by": "count", "start": 2 * k, "end": 2 * k + 2 }));
+            let manifest = &manifests[registration["root_cid"].as_str().unwrap()];
+            assert_eq!(manifest["records"], serde_json::json!([2 * k, 2 * k + 1]));
+        }
+        assert!(registered[3]["tags"].as_array().unwrap().contains(&serde_json::json!("window:count:6-8")));
+
+        // The reader waited on the slow writer rather than dropping anything
+        let metrics = handle.metrics();
+        assert_eq!(metrics.restarts, 1);
+        assert_eq!(metrics.windows_committed, 20);
+        assert_eq!(metrics.last_committed_window, Some(19));
+        assert!(metrics.backpressure_waits > 0);
+        assert_eq!(metrics.head_offset, Some(41));
+        assert_eq!(metrics.lag_events, 0);
+        assert_eq!(stream::StreamCursor::load(&dir), stream::StreamCursor { next_window: 20, resume_offset: 40 });
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_stream_out_of_gas_fails_without_restarting() {
+        let input = spawn_event_stream(vec![event(0, serde_json::json!("x".repeat(10_000)))]).await;
+        let (sink_url, _, registered) = spawn_window_sink().await;
+        let handle = Arc::new(StreamHandle::default());
+        let dir = temp_mount("stream-gas");
+        let run = StreamRun {
+            job_id: "job-stream-gas".to_string(),
+            spec: stream_spec(input),
+            dir: dir.clone(),
+            sink: WindowSink { svdb: Arc::new(SvdbClient::new(sink_url.clone())), jobd_url: sink_url },
+            handle: handle.clone(),
+            poll_interval: std::time::Duration::from_millis(20),
+            clock: ManualClock::auto_advancing(1_700_000_000).shared(),
+        };
+        let wasm = wat::parse_str(METERED_MAP).unwrap();
+        let factory: TransformFactory = Box::new(move || Ok(Box::new(WasmTransform::new(&wasm, 5_000)?) as Box<dyn Transform>));
+
+        let error = run.run(&factory).await.unwrap_err();
+        assert_eq!(error, "Transform exceeded its gas limit of 5000");
+        assert_eq!(handle.metrics().restarts, 0);
+        assert!(registered.lock().unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_stream_commit_backoff_runs_on_the_clock() {
+        let input = spawn_event_stream((0..3).map(|o| event(o, serde_json::json!(o))).collect()).await;
+        let (sink_url, _, registered) = spawn_window_sink().await;
+        // SVDB refuses the first four uploads
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (counted, upstream) = (attempts.clone(), sink_url.clone());
+        let flaky_svdb = spawn(Router::new().route("/svdb/upload", post(move |body: axum::body::Bytes| {
+            let attempt = counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let proxied = reqwest::Client::new().post(format!("{}/svdb/upload", upstream)).body(body);
+            async move {
+                if attempt < 4 {
+                    return Err(StatusCode::SERVICE_UNAVAILABLE);
+                }
+                Ok(Json(proxied.send().await.unwrap().json::<serde_json::Value>().await.unwrap()))
+            }
+        })))
+        .await;
+
+        let clock = ManualClock::auto_advancing(1_700_000_000);
+        let handle = Arc::new(StreamHandle::default());
+        let dir = temp_mount("stream-backoff");
+        let run = Arc::new(StreamRun {
+            job_id: "job-stream-backoff".to_string(),
+            spec: stream_spec(input),
+            dir: dir.clone(),
+            sink: WindowSink { svdb: Arc::new(SvdbClient::new(flaky_svdb)), jobd_url: sink_url },
+            handle: handle.clone(),
+            poll_interval: STREAM_POLL_INTERVAL,
+            clock: clock.shared(),
+        });
+        let factory: TransformFactory = Box::new(|| Ok(Box::new(CrashOnce { crash_at: u64::MAX, crashed: Arc::default() }) as Box<dyn Transform>));
+        let running = run.clone();
+        let started = std::time::Instant::now();
+        let task = tokio::spawn(async move { running.run(&factory).await });
+
+        while handle.metrics().windows_committed == 0 {
+            tokio::task::yield_now().await;
+        }
+        handle.stop();
+        assert_eq!(task.await.unwrap(), Ok(()));
+
+        // Retried after 1s, 2s, 4s and 8s of clock time, none of it waited out
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 5);
+        assert!(clock.now_secs() >= 1_700_000_015);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(registered.lock().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,118 @@
+//! VRAM Fit
+//! Estimates the GPU memory a job needs from its model's parameter count and
+//! precision plus an activation estimate per batch sample, and compares it
+//! with the free VRAM on this node's GPUs before anything launches. A job
+//! that would not fit at its batch size has the batch halved until it does;
+//! one that would not fit even at batch size 1 is refused, instead of
+//! OOMing after its inputs are mounted and a GPU is held.
+
+use serde::{Deserialize, Serialize};
+
+/// CUDA context, framework allocator slack and kernels, per process
+pub const CONTEXT_OVERHEAD_MB: f64 = 1024.0;
+
+/// Activations per sample as a fraction of the weights, when a job gives no
+/// estimate of its own. Training keeps activations for the backward pass.
+const TRAIN_ACTIVATION_FRACTION: f64 = 0.02;
+const INFER_ACTIVATION_FRACTION: f64 = 0.002;
+
+/// Optimizer state in bytes per parameter: Adam keeps two fp32 moments
+const ADAM_STATE_BYTES: f64 = 8.0;
+const SGD_STATE_BYTES: f64 = 4.0; // Momentum
+
+const MB: f64 = 1024.0 * 1024.0;
+
+/// What a job declares about its model's size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelFootprint {
+    pub params: u64,
+    #[serde(default = "default_precision")]
+    pub precision: String, // "fp32", "fp16", "bf16", "int8", "int4"
+    #[serde(default)]
+    pub activation_mb_per_sample: Option<f64>, // Overrides the fraction-of-weights guess
+}
+
+fn default_precision() -> String {
+    "fp32".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MemoryEstimate {
+    pub weights_mb: u64,
+    pub optimizer_mb: u64, // Gradients plus optimizer state; 0 for inference
+    pub activations_mb: u64,
+    pub overhead_mb: u64,
+    pub total_mb: u64,
+    pub batch_size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fit {
+    Fits(MemoryEstimate),
+    Downshifted { from: u32, estimate: MemoryEstimate },
+    TooLarge(MemoryEstimate), // At batch size 1
+}
+
+pub fn bytes_per_param(precision: &str) -> Option<f64> {
+    match precision.to_ascii_lowercase().as_str() {
+        "fp32" | "float32" | "tf32" => Some(4.0),
+        "fp16" | "float16" | "bf16" | "bfloat16" => Some(2.0),
+        "int8" | "fp8" => Some(1.0),
+        "int4" => Some(0.5),
+        _ => None,
+    }
+}
+
+impl ModelFootprint {
+    /// Estimated VRAM at `batch_size`. `optimizer` is the train job's
+    /// optimizer; None means the job only runs forward passes.
+    pub fn estimate(&self, optimizer: Option<&str>, batch_size: u32) -> Result<MemoryEstimate, String> {
+        let bytes = bytes_per_param(&self.precision)
+            .ok_or_else(|| format!("Unknown precision {:?}", self.precision))?;
+        let params = self.params as f64;
+        let weights = params * bytes / MB;
+
+        let training = optimizer.is_some();
+        let optimizer_state = match optimizer.map(str::to_ascii_lowercase).as_deref() {
+            None => 0.0,
+            Some(name) if name.starts_with("adam") => params * ADAM_STATE_BYTES / MB,
+            Some(name) if name.starts_with("sgd") => params * SGD_STATE_BYTES / MB,
+            Some(_) => params * ADAM_STATE_BYTES / MB, // Assume the heavier case
+        };
+        let gradients = if training { weights } else { 0.0 };
+
+        let fraction = if training { TRAIN_ACTIVATION_FRACTION } else { INFER_ACTIVATION_FRACTION };
+        let per_sample = self.activation_mb_per_sample.unwrap_or(weights * fraction);
+        let activations = per_sample * batch_size as f64;
+
+        let total = weights + gradients + optimizer_state + activations + CONTEXT_OVERHEAD_MB;
+        Ok(MemoryEstimate {
+            weights_mb: weights.ceil() as u64,
+            optimizer_mb: (gradients + optimizer_state).ceil() as u64,
+            activations_mb: activations.ceil() as u64,
+            overhead_mb: CONTEXT_OVERHEAD_MB as u64,
+            total_mb: total.ceil() as u64,
+            batch_size,
+        })
+    }
+
+    /// Whether the job fits in `free_mb`, halving its batch size as needed
+    pub fn fit(&self, optimizer: Option<&str>, batch_size: u32, free_mb: u64) -> Result<Fit, String> {
+        let requested = batch_size.max(1);
+        let mut batch = requested;
+        loop {
+            let estimate = self.estimate(optimizer, batch)?;
+            if estimate.total_mb <= free_mb {
+                return Ok(if batch == requested {
+                    Fit::Fits(estimate)
+                } else {
+                    Fit::Downshifted { from: requested, estimate }
+                });
+            }
+            if batch == 1 {
+                return Ok(Fit::TooLarge(estimate));
+            }
+            batch /= 2;
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Live Migration
+//! Cooperative handoff of a running job to another node. The runtime asks the
+//! container to checkpoint by creating `MIGRATE_CONTROL_FILE` in its
+//! checkpoint mount, i.e. `/checkpoints/.artha-migrate` inside the container.
+//! A container implementing the protocol watches for that file, writes an
+//! immediate checkpoint and exits with `MIGRATE_EXIT_CODE`. The checkpoint is
+//! uploaded ahead of anything else and handed to ai-jobd, which re-queues the
+//! job to resume from it elsewhere. A container that doesn't exit within the
+//! timeout is stopped instead and resumes from its last periodic checkpoint;
+//! the handoff is marked degraded.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Created in the checkpoint dir to ask the container to checkpoint and exit.
+/// Holds the request as JSON; containers only need to check it exists.
+pub const MIGRATE_CONTROL_FILE: &str = ".artha-migrate";
+
+/// Exit code of a container that checkpointed on request (EX_TEMPFAIL)
+pub const MIGRATE_EXIT_CODE: i32 = 75;
+
+/// File a migrated job's checkpoint is restored to on the node it resumes on
+pub const RESUMED_CHECKPOINT: &str = "migrated.ckpt";
+
+/// How long a container has to answer before the degraded fallback
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// How often the container is checked for an exit while waiting
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrateRequest {
+    pub initiated_by: String, // "scheduler:drain", an operator DID, ...
+    pub reason: String,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationMode {
+    Cooperative, // Checkpointed on request
+    Degraded,    // No answer in time; resumes from the last periodic checkpoint
+}
+
+/// What a migrated job left behind, reported to ai-jobd as a `migratable` completion
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MigrationHandoff {
+    pub initiated_by: String,
+    pub reason: String,
+    pub mode: MigrationMode,
+    pub checkpoint: Option<String>, // File the job resumes from; None if it never checkpointed
+    pub resume_from: Option<String>, // CID of that checkpoint
+    pub steps_lost: Option<u64>, // 0 for a cooperative checkpoint; unknown otherwise
+    pub requested_at: u64,
+    pub exported_at: u64,
+}
+
+impl MigrateRequest {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+/// Host path of the control file for a job's checkpoint dir
+pub fn control_file(checkpoint_dir: &str) -> String {
+    format!("{}/{}", checkpoint_dir, MIGRATE_CONTROL_FILE)
+}
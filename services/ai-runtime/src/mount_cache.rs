@@ -0,0 +1,214 @@
+//! Mount Cache
+//! Node-local, content-addressed copies of the model and dataset CIDs jobs
+//! mount. Jobs on the node share one materialized copy per CID, linked into
+//! their job directory; concurrent requests for a CID wait on a single
+//! download. Entries are reference-counted by job and only evicted once no
+//! job holds them and the cache is over its size budget, least recently
+//! used first.
+
+use crate::svdb::{SvdbClient, OBJECT_FILE};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+/// Records which CID a cache directory holds, so the cache survives restarts
+const CID_FILE: &str = "cid";
+
+struct CacheEntry {
+    dir: PathBuf,
+    holders: BTreeSet<String>, // Job ids; a job holds an entry at most once
+    last_used: u64,            // Cache clock tick, for LRU
+    ready: Arc<OnceCell<u64>>, // Object size once downloaded
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedMount {
+    pub cid: String,
+    pub bytes: u64,
+    pub holders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MountCacheStats {
+    pub max_bytes: u64,
+    pub used_bytes: u64,
+    pub downloads: u64, // Fetches from SVDB since start
+    pub hits: u64,      // Mounts served from an existing copy
+    pub evictions: u64,
+    pub entries: Vec<CachedMount>,
+}
+
+pub struct MountCache {
+    root: PathBuf,
+    max_bytes: u64,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    clock: AtomicU64,
+    downloads: AtomicU64,
+    hits: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl MountCache {
+    /// Open the cache under `root`, adopting complete copies left by a previous run
+    pub fn open(root: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        let root = root.into();
+        let mut entries = HashMap::new();
+        for dir in std::fs::read_dir(&root).into_iter().flatten().flatten().map(|e| e.path()) {
+            let (Ok(cid), Ok(object)) = (std::fs::read_to_string(dir.join(CID_FILE)), std::fs::metadata(dir.join(OBJECT_FILE))) else {
+                let _ = std::fs::remove_dir_all(&dir); // Interrupted download
+                continue;
+            };
+            let ready = Arc::new(OnceCell::new_with(Some(object.len())));
+            entries.insert(cid, CacheEntry { dir, holders: BTreeSet::new(), last_used: 0, ready });
+        }
+        let cache = MountCache {
+            root,
+            max_bytes,
+            entries: Mutex::new(entries),
+            clock: AtomicU64::new(1),
+            downloads: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        };
+        cache.evict(&mut cache.entries.lock().unwrap(), None);
+        cache
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The cached copy of `cid`, downloading it first if no job has yet.
+    /// `holder` keeps the copy from eviction until it is released.
+    pub async fn acquire(&self, cid: &str, holder: &str, svdb: &SvdbClient) -> Result<PathBuf, String> {
+        let (dir, ready) = {
+            let mut entries = self.entries.lock().unwrap();
+            let dir = self.root.join(hex_digest(cid));
+            let entry = entries.entry(cid.to_string()).or_insert_with(|| CacheEntry {
+                dir,
+                holders: BTreeSet::new(),
+                last_used: 0,
+                ready: Arc::new(OnceCell::new()),
+            });
+            entry.holders.insert(holder.to_string());
+            entry.last_used = self.tick();
+            (entry.dir.clone(), entry.ready.clone())
+        };
+
+        // Waiters on a failed download take their own turn at it
+        let downloaded = AtomicBool::new(false);
+        let fetched = ready
+            .get_or_try_init(|| async {
+                downloaded.store(true, Ordering::Relaxed);
+                self.downloads.fetch_add(1, Ordering::Relaxed);
+                info!("📥 Caching {} for {}", cid, holder);
+                let _ = std::fs::remove_dir_all(&dir);
+                std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                let bytes = svdb.fetch(cid, &dir.join(OBJECT_FILE)).await?;
+                std::fs::write(dir.join(CID_FILE), cid).map_err(|e| e.to_string())?;
+                Ok::<u64, String>(bytes)
+            })
+            .await
+            .copied();
+
+        let mut entries = self.entries.lock().unwrap();
+        match fetched {
+            Ok(_) => {
+                if !downloaded.into_inner() {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                }
+                self.evict(&mut entries, Some(cid));
+                Ok(dir)
+            }
+            Err(e) => {
+                if let Some(entry) = entries.get_mut(cid) {
+                    entry.holders.remove(holder);
+                    if entry.holders.is_empty() && !entry.ready.initialized() {
+                        entries.remove(cid);
+                        let _ = std::fs::remove_dir_all(&dir);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Drop every reference `holder` has; the copies stay cached for later jobs
+    pub fn release(&self, holder: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        for entry in entries.values_mut() {
+            if entry.holders.remove(holder) {
+                entry.last_used = self.tick();
+            }
+        }
+    }
+
+    /// Evict unreferenced copies, least recently used first, until the cache
+    /// fits its budget. `keep` was just acquired and is never a candidate.
+    fn evict(&self, entries: &mut HashMap<String, CacheEntry>, keep: Option<&str>) {
+        let mut used: u64 = entries.values().filter_map(|e| e.ready.get()).sum();
+        while used > self.max_bytes {
+            let victim = entries
+                .iter()
+                .filter(|(cid, e)| e.holders.is_empty() && e.ready.initialized() && Some(cid.as_str()) != keep)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(cid, _)| cid.clone());
+            let Some(victim) = victim else {
+                warn!("⚠️  Mount cache holds {} bytes over its {} byte budget, all in use", used - self.max_bytes, self.max_bytes);
+                return;
+            };
+            let entry = entries.remove(&victim).unwrap();
+            let bytes = entry.ready.get().copied().unwrap_or_default();
+            let _ = std::fs::remove_dir_all(&entry.dir);
+            used -= bytes;
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+            info!("🧹 Evicted {} from the mount cache ({} bytes)", victim, bytes);
+        }
+    }
+
+    pub fn stats(&self) -> MountCacheStats {
+        let entries = self.entries.lock().unwrap();
+        let mut mounts: Vec<CachedMount> = entries
+            .iter()
+            .filter_map(|(cid, e)| {
+                Some(CachedMount {
+                    cid: cid.clone(),
+                    bytes: *e.ready.get()?,
+                    holders: e.holders.iter().cloned().collect(),
+                })
+            })
+            .collect();
+        mounts.sort_by(|a, b| a.cid.cmp(&b.cid));
+        MountCacheStats {
+            max_bytes: self.max_bytes,
+            used_bytes: mounts.iter().map(|m| m.bytes).sum(),
+            downloads: self.downloads.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: mounts,
+        }
+    }
+}
+
+fn hex_digest(cid: &str) -> String {
+    Sha256::digest(cid.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Point a job's mount path at a cached copy, replacing whatever was there
+pub fn link_mount(cached: &Path, mount_path: &str) -> Result<(), String> {
+    let mount = Path::new(mount_path);
+    match std::fs::symlink_metadata(mount) {
+        Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(mount).map_err(|e| e.to_string())?,
+        Ok(_) => std::fs::remove_file(mount).map_err(|e| e.to_string())?,
+        Err(_) => {}
+    }
+    if let Some(parent) = mount.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::os::unix::fs::symlink(cached, mount).map_err(|e| e.to_string())
+}
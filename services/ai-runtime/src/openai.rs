@@ -0,0 +1,639 @@
+//! OpenAI-compatible Inference Facade
+//! Serves `/v1/chat/completions` and `/v1/completions` over warm serving
+//! instances so existing OpenAI-style clients can point at the platform
+
+use artha_clock::{SharedClock, SharedEntropy};
+use artha_log::Sensitive;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Json, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Which sides of an exchange run through the ethics service
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModerationConfig {
+    #[serde(default)]
+    pub check_prompt: bool,
+    #[serde(default)]
+    pub check_response: bool,
+    #[serde(default)]
+    pub checks: Vec<String>, // "toxicity", "jailbreak", "bias", "nsfw"
+}
+
+/// A warm serving instance. The backing container exposes `POST /generate`
+/// returning `{text, prompt_tokens, completion_tokens}`, or NDJSON lines
+/// `{"token": ..}` terminated by `{"done": true, prompt_tokens, completion_tokens}`
+/// when streaming.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServingInstance {
+    pub model: String, // Alias clients send in the `model` field
+    pub model_cid: String,
+    pub endpoint: String,
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    #[serde(default)]
+    pub price_per_1k_tokens: u64,
+}
+
+/// Micro-usage aggregated per DID, model, and billing interval
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UsageRecord {
+    pub did: String,
+    pub model_cid: String,
+    pub interval_start: u64,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: u64,
+}
+
+#[derive(Debug)]
+pub struct UsageLedger {
+    interval_secs: u64,
+    records: HashMap<(String, String, u64), UsageRecord>,
+}
+
+impl UsageLedger {
+    pub fn new(interval_secs: u64) -> Self {
+        UsageLedger {
+            interval_secs: interval_secs.max(1),
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, did: &str, instance: &ServingInstance, prompt_tokens: u64, completion_tokens: u64, now: u64) {
+        let interval_start = now - now % self.interval_secs;
+        let record = self.records
+            .entry((did.to_string(), instance.model_cid.clone(), interval_start))
+            .or_insert_with(|| UsageRecord {
+                did: did.to_string(),
+                model_cid: instance.model_cid.clone(),
+                interval_start,
+                ..Default::default()
+            });
+        record.requests += 1;
+        record.prompt_tokens += prompt_tokens;
+        record.completion_tokens += completion_tokens;
+        // Cost accrues per token so sub-1k requests still bill
+        record.cost = (record.prompt_tokens + record.completion_tokens) * instance.price_per_1k_tokens / 1000;
+    }
+
+    /// Remove and return records for intervals that have closed
+    pub fn drain_closed(&mut self, now: u64) -> Vec<UsageRecord> {
+        let current = now - now % self.interval_secs;
+        let closed: Vec<_> = self.records.keys().filter(|k| k.2 < current).cloned().collect();
+        closed.into_iter().filter_map(|k| self.records.remove(&k)).collect()
+    }
+
+    pub fn snapshot(&self) -> Vec<UsageRecord> {
+        self.records.values().cloned().collect()
+    }
+}
+
+pub struct OpenAiState {
+    instances: RwLock<HashMap<String, ServingInstance>>, // alias -> instance
+    api_keys: HashMap<Sensitive<String>, String>, // API key -> DID
+    policy_url: String,
+    ethics_url: String,
+    pub usage: RwLock<UsageLedger>,
+    client: reqwest::Client,
+    clock: SharedClock,
+    entropy: SharedEntropy,
+}
+
+impl OpenAiState {
+    pub fn new(api_keys: HashMap<Sensitive<String>, String>, policy_url: String, ethics_url: String, interval_secs: u64) -> Self {
+        OpenAiState {
+            instances: RwLock::new(HashMap::new()),
+            api_keys,
+            policy_url,
+            ethics_url,
+            usage: RwLock::new(UsageLedger::new(interval_secs)),
+            client: reqwest::Client::new(),
+            clock: artha_clock::system_clock(),
+            entropy: artha_clock::system_entropy(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_entropy(mut self, entropy: SharedEntropy) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    pub async fn register_instance(&self, instance: ServingInstance) {
+        self.instances.write().await.insert(instance.model.clone(), instance);
+    }
+
+    /// Resolve the `model` field as an alias first, then as a model CID
+    async fn resolve(&self, model: &str) -> Option<ServingInstance> {
+        let instances = self.instances.read().await;
+        instances
+            .get(model)
+            .or_else(|| instances.values().find(|i| i.model_cid == model))
+            .cloned()
+    }
+}
+
+/// Error in the OpenAI error envelope
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+    error_type: &'static str,
+    code: Option<&'static str>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, error_type: &'static str, code: Option<&'static str>, message: impl Into<String>) -> Self {
+        ApiError { status, message: message.into(), error_type, code }
+    }
+
+    fn invalid_api_key() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "invalid_request_error", Some("invalid_api_key"), "Incorrect API key provided")
+    }
+
+    fn model_not_found(model: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "invalid_request_error", Some("model_not_found"),
+            format!("The model `{}` does not exist", model))
+    }
+
+    fn content_filter(reason: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_request_error", Some("content_filter"),
+            format!("Content was blocked by the moderation policy: {}", reason))
+    }
+
+    fn upstream(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, "api_error", None, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": {
+                "message": self.message,
+                "type": self.error_type,
+                "param": null,
+                "code": self.code,
+            }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompletionRequest {
+    pub model: String,
+    pub prompt: String,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// Shape of the response differs between the two endpoints
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Endpoint {
+    Chat,
+    Completion,
+}
+
+struct InferenceCall {
+    endpoint: Endpoint,
+    did: String,
+    instance: ServingInstance,
+    prompt: String,
+    temperature: Option<f64>,
+    max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BackendResponse {
+    text: String,
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BackendStreamLine {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+pub fn router(state: Arc<OpenAiState>) -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/completions", post(completions))
+        .route("/v1/models", get(list_models))
+        .route("/serving/instances", post(register_instance))
+        .route("/serving/usage", get(get_usage))
+        .with_state(state)
+}
+
+async fn register_instance(
+    State(state): State<Arc<OpenAiState>>,
+    Json(instance): Json<ServingInstance>,
+) -> StatusCode {
+    info!("🔥 Warm instance registered: {} -> {}", instance.model, instance.endpoint);
+    state.register_instance(instance).await;
+    StatusCode::OK
+}
+
+/// Usage accrued in intervals not yet flushed to receipts
+async fn get_usage(State(state): State<Arc<OpenAiState>>) -> Json<Vec<UsageRecord>> {
+    Json(state.usage.read().await.snapshot())
+}
+
+async fn list_models(State(state): State<Arc<OpenAiState>>) -> Json<serde_json::Value> {
+    let instances = state.instances.read().await;
+    let data: Vec<_> = instances
+        .values()
+        .map(|i| serde_json::json!({ "id": i.model, "object": "model", "owned_by": "artha" }))
+        .collect();
+    Json(serde_json::json!({ "object": "list", "data": data }))
+}
+
+fn render_chat_prompt(messages: &[ChatMessage]) -> String {
+    let mut prompt: String = messages
+        .iter()
+        .map(|m| format!("{}: {}\n", m.role, m.content))
+        .collect();
+    prompt.push_str("assistant:");
+    prompt
+}
+
+async fn chat_completions(
+    State(state): State<Arc<OpenAiState>>,
+    headers: HeaderMap,
+    Json(req): Json<ChatCompletionRequest>,
+) -> Result<Response, ApiError> {
+    let call = prepare(&state, &headers, Endpoint::Chat, &req.model, render_chat_prompt(&req.messages), req.temperature, req.max_tokens).await?;
+    if req.stream {
+        stream_inference(state, call).await
+    } else {
+        complete_inference(&state, call).await
+    }
+}
+
+async fn completions(
+    State(state): State<Arc<OpenAiState>>,
+    headers: HeaderMap,
+    Json(req): Json<CompletionRequest>,
+) -> Result<Response, ApiError> {
+    let call = prepare(&state, &headers, Endpoint::Completion, &req.model, req.prompt, req.temperature, req.max_tokens).await?;
+    if req.stream {
+        stream_inference(state, call).await
+    } else {
+        complete_inference(&state, call).await
+    }
+}
+
+/// Authenticate, resolve the model, and run policy and prompt moderation
+async fn prepare(
+    state: &OpenAiState,
+    headers: &HeaderMap,
+    endpoint: Endpoint,
+    model: &str,
+    prompt: String,
+    temperature: Option<f64>,
+    max_tokens: Option<u32>,
+) -> Result<InferenceCall, ApiError> {
+    let did = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|key| state.api_keys.get(key))
+        .cloned()
+        .ok_or_else(ApiError::invalid_api_key)?;
+
+    let instance = state.resolve(model).await.ok_or_else(|| ApiError::model_not_found(model))?;
+
+    check_policy(state, &did, &instance, max_tokens.unwrap_or(256)).await?;
+    if instance.moderation.check_prompt {
+        moderate(state, &instance, &prompt).await?;
+    }
+
+    Ok(InferenceCall { endpoint, did, instance, prompt, temperature, max_tokens })
+}
+
+async fn check_policy(state: &OpenAiState, did: &str, instance: &ServingInstance, budget: u32) -> Result<(), ApiError> {
+    let response = state.client
+        .post(format!("{}/policy/check", state.policy_url))
+        .json(&serde_json::json!({
+            "did": did,
+            "action": "infer",
+            "resource": instance.model_cid,
+            "budget": budget,
+        }))
+        .send()
+        .await
+        .map_err(|e| ApiError::upstream(format!("Policy check failed: {}", e)))?;
+
+    let result: serde_json::Value = response.json().await
+        .map_err(|e| ApiError::upstream(format!("Invalid policy response: {}", e)))?;
+
+    if result["allowed"].as_bool() == Some(true) {
+        Ok(())
+    } else {
+        Err(ApiError::new(StatusCode::FORBIDDEN, "invalid_request_error", Some("permission_denied"),
+            result["reason"].as_str().unwrap_or("Request denied by policy").to_string()))
+    }
+}
+
+/// Run content through ai-ethics; a blocked verdict maps to the content-filter error
+async fn moderate(state: &OpenAiState, instance: &ServingInstance, content: &str) -> Result<(), ApiError> {
+    let response = state.client
+        .post(format!("{}/ethics/check", state.ethics_url))
+        .json(&serde_json::json!({
+            "content": content,
+            "model_id": instance.model_cid,
+            "domain": null,
+            "checks": instance.moderation.checks,
+        }))
+        .send()
+        .await
+        .map_err(|e| ApiError::upstream(format!("Ethics check failed: {}", e)))?;
+
+    let result: serde_json::Value = response.json().await
+        .map_err(|e| ApiError::upstream(format!("Invalid ethics response: {}", e)))?;
+
+    if result["allowed"].as_bool() == Some(true) {
+        return Ok(());
+    }
+    let reason = result["flags"]
+        .as_array()
+        .and_then(|flags| flags.first())
+        .and_then(|f| f["check_type"].as_str())
+        .unwrap_or("flagged");
+    Err(ApiError::content_filter(reason))
+}
+
+async fn complete_inference(state: &OpenAiState, call: InferenceCall) -> Result<Response, ApiError> {
+    let response = state.client
+        .post(format!("{}/generate", call.instance.endpoint))
+        .json(&serde_json::json!({
+            "prompt": call.prompt,
+            "temperature": call.temperature,
+            "max_tokens": call.max_tokens,
+            "stream": false,
+        }))
+        .send()
+        .await
+        .map_err(|e| ApiError::upstream(format!("Serving instance unreachable: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(ApiError::upstream(format!("Serving instance returned {}", response.status())));
+    }
+    let output: BackendResponse = response.json().await
+        .map_err(|e| ApiError::upstream(format!("Invalid serving response: {}", e)))?;
+
+    if call.instance.moderation.check_response {
+        moderate(state, &call.instance, &output.text).await?;
+    }
+
+    let now = state.clock.now_secs();
+    state.usage.write().await.record(&call.did, &call.instance, output.prompt_tokens, output.completion_tokens, now);
+
+    let id = completion_id(state, call.endpoint);
+    let usage = serde_json::json!({
+        "prompt_tokens": output.prompt_tokens,
+        "completion_tokens": output.completion_tokens,
+        "total_tokens": output.prompt_tokens + output.completion_tokens,
+    });
+    let body = match call.endpoint {
+        Endpoint::Chat => serde_json::json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": now,
+            "model": call.instance.model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": output.text },
+                "finish_reason": "stop",
+            }],
+            "usage": usage,
+        }),
+        Endpoint::Completion => serde_json::json!({
+            "id": id,
+            "object": "text_completion",
+            "created": now,
+            "model": call.instance.model,
+            "choices": [{
+                "index": 0,
+                "text": output.text,
+                "logprobs": null,
+                "finish_reason": "stop",
+            }],
+            "usage": usage,
+        }),
+    };
+    Ok(Json(body).into_response())
+}
+
+type ByteStream = Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>;
+
+struct StreamProxy {
+    state: Arc<OpenAiState>,
+    call: InferenceCall,
+    id: String,
+    upstream: ByteStream,
+    buffer: Vec<u8>,
+    text: String,
+    sent_role: bool,
+    finished: bool,
+}
+
+impl StreamProxy {
+    fn chunk(&self, content: Option<&str>, finish_reason: Option<&str>) -> String {
+        let choice = match self.call.endpoint {
+            Endpoint::Chat => {
+                let mut delta = serde_json::Map::new();
+                if !self.sent_role {
+                    delta.insert("role".to_string(), "assistant".into());
+                }
+                if let Some(content) = content {
+                    delta.insert("content".to_string(), content.into());
+                }
+                serde_json::json!({ "index": 0, "delta": delta, "finish_reason": finish_reason })
+            }
+            Endpoint::Completion => serde_json::json!({
+                "index": 0,
+                "text": content.unwrap_or(""),
+                "logprobs": null,
+                "finish_reason": finish_reason,
+            }),
+        };
+        let object = match self.call.endpoint {
+            Endpoint::Chat => "chat.completion.chunk",
+            Endpoint::Completion => "text_completion",
+        };
+        let event = serde_json::json!({
+            "id": self.id,
+            "object": object,
+            "created": self.state.clock.now_secs(),
+            "model": self.call.instance.model,
+            "choices": [choice],
+        });
+        format!("data: {}\n\n", event)
+    }
+
+    /// Translate complete NDJSON lines in the buffer into SSE events
+    async fn drain_lines(&mut self) -> String {
+        let mut out = String::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let Ok(parsed) = serde_json::from_slice::<BackendStreamLine>(&line) else { continue };
+
+            if let Some(token) = parsed.token {
+                out.push_str(&self.chunk(Some(&token), None));
+                self.sent_role = true;
+                self.text.push_str(&token);
+            }
+            if parsed.done {
+                out.push_str(&self.finish(parsed.prompt_tokens, parsed.completion_tokens).await);
+                break;
+            }
+        }
+        out
+    }
+
+    /// Tokens are already delivered when streaming, so a response that fails
+    /// moderation ends with `finish_reason: content_filter`
+    async fn finish(&mut self, prompt_tokens: u64, completion_tokens: u64) -> String {
+        let blocked = self.call.instance.moderation.check_response
+            && moderate(&self.state, &self.call.instance, &self.text).await.is_err();
+        self.state.usage.write().await
+            .record(&self.call.did, &self.call.instance, prompt_tokens, completion_tokens, self.state.clock.now_secs());
+        self.finished = true;
+
+        let finish_reason = if blocked { "content_filter" } else { "stop" };
+        format!("{}data: [DONE]\n\n", self.chunk(None, Some(finish_reason)))
+    }
+}
+
+/// Proxy the container's token stream as SSE without buffering the response
+async fn stream_inference(state: Arc<OpenAiState>, call: InferenceCall) -> Result<Response, ApiError> {
+    let response = state.client
+        .post(format!("{}/generate", call.instance.endpoint))
+        .json(&serde_json::json!({
+            "prompt": call.prompt,
+            "temperature": call.temperature,
+            "max_tokens": call.max_tokens,
+            "stream": true,
+        }))
+        .send()
+        .await
+        .map_err(|e| ApiError::upstream(format!("Serving instance unreachable: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(ApiError::upstream(format!("Serving instance returned {}", response.status())));
+    }
+
+    let proxy = StreamProxy {
+        id: completion_id(&state, call.endpoint),
+        state,
+        call,
+        upstream: Box::pin(response.bytes_stream()),
+        buffer: Vec::new(),
+        text: String::new(),
+        sent_role: false,
+        finished: false,
+    };
+
+    let events = futures_util::stream::unfold(proxy, |mut proxy| async move {
+        loop {
+            if proxy.finished {
+                return None;
+            }
+            match proxy.upstream.next().await {
+                Some(Ok(bytes)) => {
+                    proxy.buffer.extend_from_slice(&bytes);
+                    let out = proxy.drain_lines().await;
+                    if !out.is_empty() {
+                        return Some((Ok::<_, std::io::Error>(Bytes::from(out)), proxy));
+                    }
+                }
+                // Upstream closed or failed without a done marker
+                _ => {
+                    proxy.buffer.push(b'\n');
+                    let mut out = proxy.drain_lines().await;
+                    if !proxy.finished {
+                        out.push_str(&proxy.finish(0, 0).await);
+                    }
+                    return Some((Ok(Bytes::from(out)), proxy));
+                }
+            }
+        }
+    });
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::from_stream(events))
+        .unwrap())
+}
+
+fn completion_id(state: &OpenAiState, endpoint: Endpoint) -> String {
+    let prefix = match endpoint {
+        Endpoint::Chat => "chatcmpl",
+        Endpoint::Completion => "cmpl",
+    };
+    format!("{}-{:x}", prefix, state.entropy.next_u128())
+}
+
+/// Flush closed billing intervals to the receipts pipeline
+pub async fn flush_usage(state: &OpenAiState, receipts_url: &str) {
+    let records = state.usage.write().await.drain_closed(state.clock.now_secs());
+    for record in records {
+        let result = state.client
+            .post(format!("{}/receipt/inference-usage", receipts_url))
+            .json(&record)
+            .send()
+            .await;
+        if result.is_err() {
+            warn!("⚠️  Failed to submit inference usage for {}; requeueing", record.did);
+            let mut usage = state.usage.write().await;
+            usage.records.insert((record.did.clone(), record.model_cid.clone(), record.interval_start), record);
+        }
+    }
+}
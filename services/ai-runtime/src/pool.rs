@@ -0,0 +1,472 @@
+//! Warm Container Pools
+//! Pre-created, paused containers per pinned runtime image so job starts skip
+//! image pull, container create and CUDA init. Pool containers are created
+//! without any mounts; a claimed container receives its job spec at a
+//! pre-agreed path and its entrypoint fetches job inputs itself.
+
+use crate::repro::SensitiveEnv;
+use artha_clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::process::Command;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Path inside pool containers where the standardized entrypoint waits for its job spec
+pub const JOB_SPEC_PATH: &str = "/artha/job.json";
+
+/// Prefix marking a GPU held by an idle pool container in `gpu_allocations`
+pub const POOL_ALLOCATION_PREFIX: &str = "pool:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolConfig {
+    pub image: String,
+    pub digest: String,   // Pinned image digest pool containers are created from
+    pub count: usize,     // Target idle depth
+    pub ttl_secs: u64,    // Idle containers older than this are recycled
+}
+
+/// What a pool container is created with. `mounts` stays empty: nothing
+/// job-specific exists until the container is claimed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolContainerSpec {
+    pub image: String,
+    pub digest: String,
+    pub gpu_id: String,
+    pub mounts: Vec<String>,
+}
+
+/// Job spec delivered to a claimed container at `JOB_SPEC_PATH`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobSpec {
+    pub job_id: String,
+    pub job_type: String,
+    pub model_cid: String,
+    pub dataset_cid: Option<String>,
+    pub params: serde_json::Value,
+    pub svdb_url: String,
+    #[serde(default)]
+    pub env: SensitiveEnv, // Job env vars, secrets included, exported by the entrypoint
+}
+
+/// Container operations needed by the pool. Docker in production, mocked in tests.
+pub trait ContainerBackend: Send + Sync {
+    /// Create a container from `spec`, run its warm-up and leave it paused
+    fn create_paused(&self, spec: &PoolContainerSpec) -> Result<String, String>;
+    /// Hand a paused pool container its job spec and resume it
+    fn claim(&self, container_id: &str, job: &JobSpec) -> Result<(), String>;
+    fn remove(&self, container_id: &str);
+    /// Whether the node is short on disk or memory
+    fn under_pressure(&self) -> bool;
+}
+
+#[derive(Debug, Clone)]
+pub struct PooledContainer {
+    pub container_id: String,
+    pub image: String,
+    pub digest: String,
+    pub gpu_id: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImagePoolStats {
+    pub image: String,
+    pub digest: String,
+    pub target: usize,
+    pub idle: usize,
+    pub ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PoolCounters {
+    pub claims: u64,
+    pub cold_starts: u64,
+    pub refills: u64,
+    pub expired: u64,
+    pub culled: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    pub pools: Vec<ImagePoolStats>,
+    pub counters: PoolCounters,
+    pub capacity: CapacityReport,
+}
+
+/// GPU capacity reported to the scheduler in heartbeats. Idle pool containers
+/// hold their GPU, so they count as reserved rather than free.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CapacityReport {
+    pub gpus_total: usize,
+    pub gpus_running: usize,
+    pub gpus_pool_reserved: usize,
+    pub gpus_free: usize,
+}
+
+/// Idle pool containers by image, oldest first
+#[derive(Debug, Default)]
+pub struct WarmPools {
+    configs: Vec<PoolConfig>,
+    idle: HashMap<String, VecDeque<PooledContainer>>,
+    counters: PoolCounters,
+}
+
+impl WarmPools {
+    pub fn new(configs: Vec<PoolConfig>) -> Self {
+        WarmPools { configs, ..Default::default() }
+    }
+
+    /// Take an idle container for `image`, optionally requiring an exact digest
+    pub fn take(&mut self, image: &str, digest: Option<&str>) -> Option<PooledContainer> {
+        let queue = self.idle.get_mut(image)?;
+        let index = queue.iter().position(|c| digest.is_none_or(|d| c.digest == d))?;
+        queue.remove(index)
+    }
+
+    pub fn add(&mut self, container: PooledContainer) {
+        self.idle.entry(container.image.clone()).or_default().push_back(container);
+    }
+
+    /// Images below their target depth, with how many containers each is missing
+    pub fn deficits(&self) -> Vec<(PoolConfig, usize)> {
+        self.configs
+            .iter()
+            .filter_map(|config| {
+                let idle = self.idle.get(&config.image).map_or(0, |q| q.len());
+                (idle < config.count).then(|| (config.clone(), config.count - idle))
+            })
+            .collect()
+    }
+
+    /// Remove idle containers older than their pool's TTL
+    pub fn expire(&mut self, now: u64) -> Vec<PooledContainer> {
+        let mut expired = Vec::new();
+        for config in &self.configs {
+            if let Some(queue) = self.idle.get_mut(&config.image) {
+                while queue.front().is_some_and(|c| now.saturating_sub(c.created_at) >= config.ttl_secs) {
+                    expired.extend(queue.pop_front());
+                }
+            }
+        }
+        self.counters.expired += expired.len() as u64;
+        expired
+    }
+
+    /// Remove the oldest idle container across all pools
+    pub fn cull_one(&mut self) -> Option<PooledContainer> {
+        let image = self
+            .idle
+            .iter()
+            .filter_map(|(image, queue)| queue.front().map(|c| (image.clone(), c.created_at)))
+            .min_by_key(|(_, created_at)| *created_at)
+            .map(|(image, _)| image)?;
+        let culled = self.idle.get_mut(&image)?.pop_front();
+        if culled.is_some() {
+            self.counters.culled += 1;
+        }
+        culled
+    }
+
+    pub fn stats(&self) -> (Vec<ImagePoolStats>, PoolCounters) {
+        let pools = self
+            .configs
+            .iter()
+            .map(|config| ImagePoolStats {
+                image: config.image.clone(),
+                digest: config.digest.clone(),
+                target: config.count,
+                idle: self.idle.get(&config.image).map_or(0, |q| q.len()),
+                ttl_secs: config.ttl_secs,
+            })
+            .collect();
+        (pools, self.counters.clone())
+    }
+}
+
+/// A pool container claimed for a job
+#[derive(Debug, Clone)]
+pub struct ClaimedContainer {
+    pub container_id: String,
+    pub gpu_id: String,
+    pub digest: String,
+}
+
+/// Owns the warm pools and keeps them in step with the node's GPU allocations
+pub struct PoolManager {
+    pools: RwLock<WarmPools>,
+    backend: Arc<dyn ContainerBackend>,
+    gpu_allocations: Arc<RwLock<HashMap<String, String>>>, // gpu_id -> job_id or "pool:<container>"
+    gpu_count: usize,
+    refilling: tokio::sync::Mutex<()>,
+    clock: SharedClock,
+}
+
+impl PoolManager {
+    pub fn new(
+        configs: Vec<PoolConfig>,
+        backend: Arc<dyn ContainerBackend>,
+        gpu_allocations: Arc<RwLock<HashMap<String, String>>>,
+        gpu_count: usize,
+    ) -> Self {
+        PoolManager {
+            pools: RwLock::new(WarmPools::new(configs)),
+            backend,
+            gpu_allocations,
+            gpu_count,
+            refilling: tokio::sync::Mutex::new(()),
+            clock: artha_clock::system_clock(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Claim an idle container matching `image` (and `digest`, if pinned) for
+    /// `job`. Returns `None` when the caller must fall back to a cold start.
+    pub async fn claim(&self, image: &str, digest: Option<&str>, job: &JobSpec) -> Option<ClaimedContainer> {
+        loop {
+            let pooled = self.pools.write().await.take(image, digest)?;
+
+            if let Err(e) = self.backend.claim(&pooled.container_id, job) {
+                // A broken pool container is discarded; try the next one
+                warn!("   ⚠️  Failed to claim warm container {}: {}", pooled.container_id, e);
+                self.discard(&pooled).await;
+                continue;
+            }
+
+            self.gpu_allocations.write().await.insert(pooled.gpu_id.clone(), job.job_id.clone());
+            self.pools.write().await.counters.claims += 1;
+            return Some(ClaimedContainer {
+                container_id: pooled.container_id,
+                gpu_id: pooled.gpu_id,
+                digest: pooled.digest,
+            });
+        }
+    }
+
+    pub async fn record_cold_start(&self) {
+        self.pools.write().await.counters.cold_starts += 1;
+    }
+
+    /// Create containers until every pool is back at its target depth, as long
+    /// as GPUs are free and the node is not under resource pressure
+    pub async fn refill(&self) -> usize {
+        let _guard = self.refilling.lock().await;
+        let mut created = 0;
+        let deficits = self.pools.read().await.deficits();
+
+        for (config, missing) in deficits {
+            for _ in 0..missing {
+                if self.backend.under_pressure() {
+                    return created;
+                }
+                let Some(gpu_id) = self.reserve_free_gpu().await else {
+                    return created;
+                };
+
+                let spec = PoolContainerSpec {
+                    image: config.image.clone(),
+                    digest: config.digest.clone(),
+                    gpu_id: gpu_id.clone(),
+                    mounts: Vec::new(),
+                };
+                match self.backend.create_paused(&spec) {
+                    Ok(container_id) => {
+                        self.gpu_allocations.write().await
+                            .insert(gpu_id.clone(), format!("{}{}", POOL_ALLOCATION_PREFIX, container_id));
+                        let mut pools = self.pools.write().await;
+                        pools.add(PooledContainer {
+                            container_id,
+                            image: config.image.clone(),
+                            digest: config.digest.clone(),
+                            gpu_id,
+                            created_at: self.clock.now_secs(),
+                        });
+                        pools.counters.refills += 1;
+                        created += 1;
+                    }
+                    Err(e) => {
+                        warn!("   ⚠️  Failed to create warm container for {}: {}", config.image, e);
+                        self.gpu_allocations.write().await.remove(&gpu_id);
+                        return created;
+                    }
+                }
+            }
+        }
+        created
+    }
+
+    /// Recycle expired containers, cull idle ones while the node is under
+    /// pressure, then restore pool depth
+    pub async fn maintain(&self, now: u64) {
+        let expired = self.pools.write().await.expire(now);
+        for pooled in &expired {
+            self.discard(pooled).await;
+        }
+
+        while self.backend.under_pressure() {
+            let Some(pooled) = self.pools.write().await.cull_one() else { break };
+            info!("   🧹 Culling warm container {} under resource pressure", pooled.container_id);
+            self.discard(&pooled).await;
+        }
+
+        self.refill().await;
+    }
+
+    /// Give up an idle pool container so a cold-start job can have its GPU.
+    /// Running jobs always win over warm capacity.
+    pub async fn evict_for_job(&self) -> bool {
+        let Some(pooled) = self.pools.write().await.cull_one() else { return false };
+        self.discard(&pooled).await;
+        true
+    }
+
+    pub async fn capacity(&self) -> CapacityReport {
+        let allocations = self.gpu_allocations.read().await;
+        let gpus_pool_reserved = allocations.values().filter(|v| v.starts_with(POOL_ALLOCATION_PREFIX)).count();
+        let gpus_running = allocations.len() - gpus_pool_reserved;
+        CapacityReport {
+            gpus_total: self.gpu_count,
+            gpus_running,
+            gpus_pool_reserved,
+            gpus_free: self.gpu_count.saturating_sub(allocations.len()),
+        }
+    }
+
+    pub async fn stats(&self) -> PoolStats {
+        let (pools, counters) = self.pools.read().await.stats();
+        PoolStats {
+            pools,
+            counters,
+            capacity: self.capacity().await,
+        }
+    }
+
+    async fn reserve_free_gpu(&self) -> Option<String> {
+        let mut allocations = self.gpu_allocations.write().await;
+        let gpu_id = (0..self.gpu_count)
+            .map(|i| format!("gpu:{}", i))
+            .find(|gpu| !allocations.contains_key(gpu))?;
+        // Placeholder until the container exists, so concurrent allocations skip it
+        allocations.insert(gpu_id.clone(), POOL_ALLOCATION_PREFIX.to_string());
+        Some(gpu_id)
+    }
+
+    async fn discard(&self, pooled: &PooledContainer) {
+        self.backend.remove(&pooled.container_id);
+        self.gpu_allocations.write().await.remove(&pooled.gpu_id);
+    }
+}
+
+/// Restore pool depth in the background after a claim
+pub fn spawn_refill(manager: Arc<PoolManager>) {
+    tokio::spawn(async move {
+        let created = manager.refill().await;
+        if created > 0 {
+            info!("   ♻️  Refilled {} warm container(s)", created);
+        }
+    });
+}
+
+/// Strip the tag from an image reference so it can be pinned by digest
+pub fn image_repository(image: &str) -> &str {
+    match image.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => repo,
+        _ => image,
+    }
+}
+
+/// Docker-backed pool containers. Each is started with the standardized
+/// entrypoint (which initializes CUDA, then waits for `JOB_SPEC_PATH`) and
+/// paused immediately after warm-up.
+pub struct DockerBackend {
+    pub min_free_memory_pct: u64,
+    pub max_disk_used_pct: u64,
+    pub disk_path: String,
+}
+
+impl DockerBackend {
+    fn docker(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("docker")
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run docker: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn memory_free_pct() -> Option<u64> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        let field = |name: &str| -> Option<u64> {
+            meminfo
+                .lines()
+                .find(|l| l.starts_with(name))?
+                .split_whitespace()
+                .nth(1)?
+                .parse()
+                .ok()
+        };
+        let total = field("MemTotal:")?;
+        let available = field("MemAvailable:")?;
+        (total > 0).then(|| available * 100 / total)
+    }
+
+    fn disk_used_pct(path: &str) -> Option<u64> {
+        let output = Command::new("df").args(["-P", path]).output().ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().nth(1)?;
+        line.split_whitespace().nth(4)?.trim_end_matches('%').parse().ok()
+    }
+}
+
+impl ContainerBackend for DockerBackend {
+    fn create_paused(&self, spec: &PoolContainerSpec) -> Result<String, String> {
+        let image = format!("{}@{}", image_repository(&spec.image), spec.digest);
+        let gpus = spec.gpu_id.replace("gpu:", "device=");
+        let container_id = Self::docker(&[
+            "run", "-d",
+            "--label", "artha.pool=warm",
+            "--gpus", &gpus,
+            "-e", &format!("ARTHA_JOB_SPEC={}", JOB_SPEC_PATH),
+            &image,
+            "artha-entrypoint", "--warm",
+        ])?;
+        if let Err(e) = Self::docker(&["pause", &container_id]) {
+            let _ = Self::docker(&["rm", "-f", &container_id]);
+            return Err(e);
+        }
+        Ok(container_id)
+    }
+
+    fn claim(&self, container_id: &str, job: &JobSpec) -> Result<(), String> {
+        let spec_file = std::env::temp_dir().join(format!("artha-job-spec-{}.json", job.job_id));
+        let spec = serde_json::to_vec(job).map_err(|e| e.to_string())?;
+        std::fs::write(&spec_file, spec).map_err(|e| e.to_string())?;
+
+        let result = Self::docker(&["unpause", container_id]).and_then(|_| {
+            Self::docker(&[
+                "cp",
+                &spec_file.to_string_lossy(),
+                &format!("{}:{}", container_id, JOB_SPEC_PATH),
+            ])
+        });
+        let _ = std::fs::remove_file(&spec_file);
+        result.map(|_| ())
+    }
+
+    fn remove(&self, container_id: &str) {
+        let _ = Self::docker(&["rm", "-f", container_id]);
+    }
+
+    fn under_pressure(&self) -> bool {
+        let low_memory = Self::memory_free_pct().is_some_and(|free| free < self.min_free_memory_pct);
+        let low_disk = Self::disk_used_pct(&self.disk_path).is_some_and(|used| used > self.max_disk_used_pct);
+        low_memory || low_disk
+    }
+}
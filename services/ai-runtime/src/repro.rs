@@ -0,0 +1,372 @@
+//! Reproducibility Bundles
+//! Records what a job actually ran with (pinned image digest, resolved params,
+//! env, injected seed and input hashes) and turns that record into a bundle a
+//! developer can replay against a local Docker daemon. Secret values never
+//! enter the record: the bundle names each secret and the developer supplies it.
+
+use crate::{JobParams, JobType, StartJobRequest};
+use artha_log::Sensitive;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Env handed to a job's container. Secrets are among the values, so none
+/// of them are ever formatted.
+pub type SensitiveEnv = BTreeMap<String, Sensitive<String>>;
+
+/// Env var the random seed is injected as
+pub const SEED_ENV: &str = "ARTHA_SEED";
+
+/// Env a seeded container runs with: the seed itself, Python's hash seed, and
+/// the switches that make the framework's GPU kernels deterministic
+pub fn seed_env(seed: u64, runtime: &str) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::from([
+        (SEED_ENV.to_string(), seed.to_string()),
+        // PYTHONHASHSEED only accepts 0..=2^32-1
+        ("PYTHONHASHSEED".to_string(), (seed % (1 << 32)).to_string()),
+    ]);
+    let framework: &[(&str, String)] = match runtime {
+        "torch" => &[
+            ("PL_GLOBAL_SEED", seed.to_string()),
+            ("CUBLAS_WORKSPACE_CONFIG", ":4096:8".to_string()),
+        ],
+        "tf" => &[
+            ("TF_DETERMINISTIC_OPS", "1".to_string()),
+            ("TF_CUDNN_DETERMINISTIC", "1".to_string()),
+        ],
+        "jax" => &[("XLA_FLAGS", "--xla_gpu_deterministic_ops=true".to_string())],
+        _ => &[],
+    };
+    env.extend(framework.iter().map(|(name, value)| (name.to_string(), value.clone())));
+    env
+}
+
+/// Shortest secret value checked for when verifying redaction; shorter values
+/// would match digests and CIDs by coincidence
+const MIN_CHECKED_SECRET_LEN: usize = 4;
+
+/// Env names treated as secret even when passed in plain `env`
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    ["SECRET", "TOKEN", "PASSWORD", "PRIVATE_KEY", "API_KEY", "CREDENTIAL"]
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: Option<String>, // None for secrets
+    pub secret: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputManifest {
+    pub role: String, // "model" or "dataset"
+    pub cid: String,
+    pub sha256: Option<String>, // Of the mounted bytes; None when the container fetched them itself
+}
+
+/// A job's environment as executed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub runtime: String,
+    pub job_type: JobType,
+    pub image: String,
+    pub image_digest: String,
+    pub params: JobParams,
+    pub env: Vec<EnvVar>, // Sorted by name
+    pub seed: Option<u64>,
+    pub tee_required: bool,
+    pub inputs: Vec<InputManifest>,
+    pub executed_at: u64,
+}
+
+/// Link from a replay job to the job it re-ran, with outputs compared on read
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplayStatus {
+    pub original_job_id: String,
+    pub outputs_match: Option<bool>, // None until both jobs have completed
+    pub only_in_original: Vec<String>,
+    pub only_in_replay: Vec<String>,
+}
+
+impl ReplayStatus {
+    pub fn new(original_job_id: &str) -> Self {
+        ReplayStatus {
+            original_job_id: original_job_id.to_string(),
+            outputs_match: None,
+            only_in_original: Vec::new(),
+            only_in_replay: Vec::new(),
+        }
+    }
+
+    /// Diff output digests; `None` for either side means it hasn't completed
+    pub fn compare(&mut self, original: Option<&[String]>, replay: Option<&[String]>) {
+        let (Some(original), Some(replay)) = (original, replay) else {
+            self.outputs_match = None;
+            self.only_in_original.clear();
+            self.only_in_replay.clear();
+            return;
+        };
+        let original_set: BTreeSet<&String> = original.iter().collect();
+        let replay_set: BTreeSet<&String> = replay.iter().collect();
+        self.outputs_match = Some(original == replay);
+        self.only_in_original = original_set.difference(&replay_set).map(|d| d.to_string()).collect();
+        self.only_in_replay = replay_set.difference(&original_set).map(|d| d.to_string()).collect();
+    }
+}
+
+/// Env vars the job's container receives, secrets included
+pub fn container_env(req: &StartJobRequest) -> SensitiveEnv {
+    let mut env = sensitive_env(req.env.clone());
+    env.extend(req.secret_env.clone());
+    if let Some(seed) = req.seed {
+        env.extend(sensitive_env(seed_env(seed, &req.runtime)));
+    }
+    env
+}
+
+pub fn sensitive_env(env: BTreeMap<String, String>) -> SensitiveEnv {
+    env.into_iter().map(|(name, value)| (name, Sensitive::new(value))).collect()
+}
+
+/// Secret env values of a request, kept by the runtime for cluster replays
+pub fn secret_values(req: &StartJobRequest) -> SensitiveEnv {
+    container_env(req)
+        .into_iter()
+        .filter(|(name, _)| req.secret_env.contains_key(name) || is_secret_name(name))
+        .collect()
+}
+
+impl ExecutionRecord {
+    pub fn capture(
+        req: &StartJobRequest,
+        image: &str,
+        image_digest: &str,
+        inputs: Vec<InputManifest>,
+        executed_at: u64,
+    ) -> Self {
+        let secrets = secret_values(req);
+        let env = container_env(req)
+            .into_iter()
+            .map(|(name, value)| {
+                let secret = secrets.contains_key(&name);
+                EnvVar { value: (!secret).then(|| value.into_inner()), name, secret }
+            })
+            .collect();
+        ExecutionRecord {
+            runtime: req.runtime.clone(),
+            job_type: req.job_type.clone(),
+            image: image.to_string(),
+            image_digest: image_digest.to_string(),
+            params: req.params.clone(),
+            env,
+            seed: req.seed,
+            tee_required: req.tee_required,
+            inputs,
+            executed_at,
+        }
+    }
+
+    pub fn pinned_image(&self) -> String {
+        format!("{}@{}", crate::pool::image_repository(&self.image), self.image_digest)
+    }
+
+    /// Names of env vars whose values were withheld
+    pub fn redacted(&self) -> Vec<String> {
+        self.env.iter().filter(|v| v.secret).map(|v| v.name.clone()).collect()
+    }
+
+    /// Plain env values, for replays that re-supply secrets separately.
+    /// Seed-derived vars are left out; they are re-derived from `seed`.
+    pub fn plain_env(&self) -> BTreeMap<String, String> {
+        let seeded = self.seed.map(|seed| seed_env(seed, &self.runtime)).unwrap_or_default();
+        self.env
+            .iter()
+            .filter_map(|v| v.value.clone().map(|value| (v.name.clone(), value)))
+            .filter(|(name, _)| !seeded.contains_key(name))
+            .collect()
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+/// Hash of a mounted input, if the runtime mounted it
+pub fn mounted_input(role: &str, cid: &str, mount_dir: &str) -> InputManifest {
+    let object = std::path::Path::new(mount_dir).join(crate::svdb::OBJECT_FILE);
+    InputManifest {
+        role: role.to_string(),
+        cid: cid.to_string(),
+        sha256: std::fs::read(object).ok().map(|bytes| sha256_hex(&bytes)),
+    }
+}
+
+/// Digests of a finished job's output files, in file name order
+pub fn output_digests(dir: &str) -> Vec<String> {
+    let mut files: Vec<std::path::PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+        .iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .map(|bytes| sha256_hex(&bytes))
+        .collect()
+}
+
+/// Whether a pinned image can still be run
+pub trait ImageStore: Send + Sync {
+    fn has_digest(&self, image: &str, digest: &str) -> bool;
+}
+
+/// Checks the local Docker cache, then the registry
+pub struct DockerImageStore;
+
+impl ImageStore for DockerImageStore {
+    fn has_digest(&self, image: &str, digest: &str) -> bool {
+        let pinned = format!("{}@{}", crate::pool::image_repository(image), digest);
+        [["image", "inspect"], ["manifest", "inspect"]].iter().any(|command| {
+            std::process::Command::new("docker")
+                .args(command)
+                .arg(&pinned)
+                .output()
+                .is_ok_and(|output| output.status.success())
+        })
+    }
+}
+
+/// Everything needed to re-run a job locally. `files` holds the generated
+/// replay script, compose file and job spec; inputs are fetched by CID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReproBundle {
+    pub job_id: String,
+    pub replay_of: Option<String>,
+    pub execution: ExecutionRecord,
+    pub model_cid: String,
+    pub dataset_cid: Option<String>,
+    pub redacted: Vec<String>, // Secret env names the developer must supply
+    pub files: BTreeMap<String, String>,
+}
+
+impl ReproBundle {
+    pub fn build(
+        job_id: &str,
+        replay_of: Option<&str>,
+        execution: &ExecutionRecord,
+        model_cid: &str,
+        dataset_cid: Option<&str>,
+    ) -> Self {
+        let mut files = BTreeMap::new();
+        files.insert("replay.sh".to_string(), replay_script(job_id, execution));
+        files.insert("docker-compose.yml".to_string(), compose_file(job_id, execution));
+        files.insert(
+            "job.json".to_string(),
+            serde_json::to_string_pretty(&serde_json::json!({
+                "job_id": job_id,
+                "model_cid": model_cid,
+                "dataset_cid": dataset_cid,
+                "execution": execution,
+            }))
+            .unwrap_or_default(),
+        );
+        ReproBundle {
+            job_id: job_id.to_string(),
+            replay_of: replay_of.map(|id| id.to_string()),
+            execution: execution.clone(),
+            model_cid: model_cid.to_string(),
+            dataset_cid: dataset_cid.map(|cid| cid.to_string()),
+            redacted: execution.redacted(),
+            files,
+        }
+    }
+
+    /// Fails if any secret value appears anywhere in the serialized bundle
+    pub fn verify_redaction(&self, secrets: &SensitiveEnv) -> Result<(), String> {
+        let serialized = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        let leaked: Vec<&String> = secrets
+            .iter()
+            .filter(|(_, value)| value.expose().len() >= MIN_CHECKED_SECRET_LEN && serialized.contains(value.expose().as_str()))
+            .map(|(name, _)| name)
+            .collect();
+        if leaked.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("secret values present in bundle: {:?}", leaked))
+        }
+    }
+}
+
+fn replay_script(job_id: &str, execution: &ExecutionRecord) -> String {
+    let mut script = format!(
+        "#!/usr/bin/env bash\n\
+         # Replays job {job_id} against a local Docker daemon.\n\
+         # Needs your own SVDB credentials in ARTHA_SVDB_TOKEN and every redacted secret in the environment.\n\
+         set -euo pipefail\n\
+         : \"${{ARTHA_SVDB_URL:=http://localhost:8080}}\"\n\
+         : \"${{ARTHA_SVDB_TOKEN:?set ARTHA_SVDB_TOKEN to your SVDB credentials}}\"\n"
+    );
+    for name in execution.redacted() {
+        script.push_str(&format!(": \"${{{name}:?set {name} (redacted from the bundle)}}\"\n"));
+    }
+    script.push_str(&format!(
+        "WORK=\"${{ARTHA_REPLAY_DIR:-./replay-{job_id}}}\"\n\
+         mkdir -p \"$WORK/model\" \"$WORK/data\" \"$WORK/checkpoints\"\n\n\
+         fetch() {{\n  \
+           curl -fsSL -H \"Authorization: Bearer $ARTHA_SVDB_TOKEN\" \"$ARTHA_SVDB_URL/svdb/download/$1\" -o \"$2\"\n  \
+           if [ -n \"$3\" ]; then echo \"${{3#sha256:}}  $2\" | sha256sum -c -; fi\n\
+         }}\n"
+    ));
+    for input in &execution.inputs {
+        let dir = if input.role == "model" { "model" } else { "data" };
+        script.push_str(&format!(
+            "fetch '{}' \"$WORK/{}/object\" '{}'\n",
+            crate::svdb::cid_path_segment(&input.cid),
+            dir,
+            input.sha256.as_deref().unwrap_or(""),
+        ));
+    }
+
+    script.push_str("\ndocker run --rm --gpus all \\\n");
+    for input in &execution.inputs {
+        let (dir, target) = if input.role == "model" { ("model", "/model") } else { ("data", "/data") };
+        script.push_str(&format!("  -v \"$WORK/{}:{}:ro\" \\\n", dir, target));
+    }
+    script.push_str("  -v \"$WORK/checkpoints:/checkpoints:rw\" \\\n");
+    script.push_str(&format!("  -e ARTHA_JOB_ID='{}' \\\n", job_id));
+    for var in &execution.env {
+        match &var.value {
+            Some(value) => script.push_str(&format!("  -e {}='{}' \\\n", var.name, value.replace('\'', "'\\''"))),
+            None => script.push_str(&format!("  -e {} \\\n", var.name)), // Passed through from the caller's env
+        }
+    }
+    script.push_str(&format!("  '{}'\n", execution.pinned_image()));
+    script
+}
+
+fn compose_file(job_id: &str, execution: &ExecutionRecord) -> String {
+    let mut compose = format!(
+        "# Run replay.sh once first to fetch inputs into ./replay-{job_id}\n\
+         services:\n  \
+           job:\n    \
+             image: \"{}\"\n    \
+             environment:\n      \
+               ARTHA_JOB_ID: \"{job_id}\"\n",
+        execution.pinned_image()
+    );
+    for var in &execution.env {
+        match &var.value {
+            Some(value) => compose.push_str(&format!("      {}: {:?}\n", var.name, value)),
+            None => compose.push_str(&format!("      {}: \"${{{}:?redacted}}\"\n", var.name, var.name)),
+        }
+    }
+    compose.push_str(&format!("    volumes:\n      - ./replay-{job_id}/checkpoints:/checkpoints\n"));
+    for input in &execution.inputs {
+        let (dir, target) = if input.role == "model" { ("model", "/model") } else { ("data", "/data") };
+        compose.push_str(&format!("      - ./replay-{}/{}:{}:ro\n", job_id, dir, target));
+    }
+    compose.push_str("    deploy:\n      resources:\n        reservations:\n          devices:\n            - capabilities: [gpu]\n");
+    compose
+}
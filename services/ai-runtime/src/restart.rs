@@ -0,0 +1,210 @@
+//! Crash Recovery
+//! Per-job restart policy for containers that exit non-zero. A restarted
+//! container is a fresh launch of the same pinned image, mounts, params and
+//! env, told where the latest checkpoint is via `RESUME_ENV` so training
+//! resumes instead of starting over. TEE jobs are never restarted: a relaunch
+//! would need a new attestation bound to a new nonce.
+
+use crate::repro::SensitiveEnv;
+use crate::JobParams;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Env var naming the checkpoint (inside the container) a restart resumes from
+pub const RESUME_ENV: &str = "ARTHA_RESUME_CHECKPOINT";
+
+/// Where the job's checkpoint directory is mounted inside its container
+const CONTAINER_CHECKPOINT_DIR: &str = "/checkpoints";
+
+/// `never`, `on-failure:N` or `always`. A clean exit always completes the job;
+/// the policy only decides what happens after a non-zero exit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure(u32), // Restarts allowed before the job is marked Failed
+    Always,
+}
+
+impl RestartPolicy {
+    /// Whether a job that has already been restarted `restarts` times may be restarted again
+    pub fn allows_restart(self, restarts: u32) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure(max) => restarts < max,
+            RestartPolicy::Always => true,
+        }
+    }
+}
+
+impl std::str::FromStr for RestartPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "never" => Ok(RestartPolicy::Never),
+            "always" => Ok(RestartPolicy::Always),
+            other => other
+                .strip_prefix("on-failure:")
+                .and_then(|max| max.parse().ok())
+                .map(RestartPolicy::OnFailure)
+                .ok_or_else(|| format!("Unknown restart policy {:?}; expected never, on-failure:N or always", other)),
+        }
+    }
+}
+
+impl TryFrom<String> for RestartPolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RestartPolicy> for String {
+    fn from(policy: RestartPolicy) -> Self {
+        match policy {
+            RestartPolicy::Never => "never".to_string(),
+            RestartPolicy::OnFailure(max) => format!("on-failure:{}", max),
+            RestartPolicy::Always => "always".to_string(),
+        }
+    }
+}
+
+/// Everything a job container is started with
+#[derive(Debug, Clone)]
+pub struct ContainerLaunch {
+    pub image: String, // Pinned `repo@digest` when the job has one
+    pub job_id: String,
+    pub model_mount: String,
+    pub dataset_mount: Option<String>,
+    pub checkpoint_dir: String,
+    pub gpu_id: String,
+    pub params: JobParams,
+    pub env: SensitiveEnv, // Secrets included
+}
+
+/// Job container operations. Docker in production, mocked in tests.
+pub trait JobContainers: Send + Sync {
+    /// Start a container, returning its id
+    fn launch(&self, launch: &ContainerLaunch) -> Result<String, String>;
+    /// How the container exited once it has stopped, None while it is still running
+    fn exit(&self, container_id: &str) -> Result<Option<ContainerExit>, String>;
+    fn remove(&self, container_id: &str);
+}
+
+/// How a stopped container exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerExit {
+    pub code: i32,
+    pub oom_killed: bool, // Killed by the kernel OOM killer; the code is then usually 137
+}
+
+impl std::fmt::Display for ContainerExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.oom_killed {
+            write!(f, "Container was killed out of memory (exit code {})", self.code)
+        } else {
+            write!(f, "Container exited with code {}", self.code)
+        }
+    }
+}
+
+/// Container path of the newest checkpoint in `checkpoint_dir`
+pub fn latest_checkpoint(checkpoint_dir: &str) -> Option<String> {
+    newest_checkpoint(checkpoint_dir).map(|name| format!("{}/{}", CONTAINER_CHECKPOINT_DIR, name))
+}
+
+/// File name of the newest checkpoint in `checkpoint_dir`, by modification
+/// time with the file name breaking ties. Hidden control files are skipped.
+pub fn newest_checkpoint(checkpoint_dir: &str) -> Option<String> {
+    std::fs::read_dir(checkpoint_dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                return None;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, name))
+        })
+        .max()
+        .map(|(_, name)| name)
+}
+
+/// Job containers run by the local Docker daemon. Containers are kept after
+/// they exit so their exit code can be read, then removed by the monitor.
+pub struct DockerJobContainers;
+
+impl JobContainers for DockerJobContainers {
+    fn launch(&self, launch: &ContainerLaunch) -> Result<String, String> {
+        let mut cmd = Command::new("docker");
+        cmd.arg("run")
+            .arg("-d") // detached
+            .arg("--name").arg(format!("artha-job-{}", launch.job_id))
+            .arg("--gpus").arg(launch.gpu_id.replace("gpu:", "device="));
+
+        // Mount volumes
+        cmd.arg("-v").arg(format!("{}:/model:ro", launch.model_mount));
+        if let Some(data_path) = &launch.dataset_mount {
+            cmd.arg("-v").arg(format!("{}:/data:ro", data_path));
+        }
+        cmd.arg("-v").arg(format!("{}:{}:rw", launch.checkpoint_dir, CONTAINER_CHECKPOINT_DIR));
+
+        // Environment variables
+        let params = &launch.params;
+        cmd.arg("-e").arg(format!("ARTHA_JOB_ID={}", launch.job_id));
+        if let Some(epochs) = params.epochs {
+            cmd.arg("-e").arg(format!("EPOCHS={}", epochs));
+        }
+        if let Some(batch_size) = params.batch_size {
+            cmd.arg("-e").arg(format!("BATCH_SIZE={}", batch_size));
+        }
+        if let Some(lr) = params.learning_rate {
+            cmd.arg("-e").arg(format!("LEARNING_RATE={}", lr));
+        }
+        if let Some(optimizer) = &params.optimizer {
+            cmd.arg("-e").arg(format!("OPTIMIZER={}", optimizer));
+        }
+        if let Some(interval) = params.checkpoint_interval {
+            cmd.arg("-e").arg(format!("CHECKPOINT_INTERVAL={}", interval));
+        }
+        for (name, value) in &launch.env {
+            cmd.arg("-e").arg(format!("{}={}", name, value.expose()));
+        }
+
+        cmd.arg(&launch.image);
+
+        let output = cmd.output().map_err(|e| format!("Failed to run docker: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("Docker run failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn exit(&self, container_id: &str) -> Result<Option<ContainerExit>, String> {
+        let output = Command::new("docker")
+            .args(["inspect", "-f", "{{.State.Status}} {{.State.ExitCode}} {{.State.OOMKilled}}", container_id])
+            .output()
+            .map_err(|e| format!("Failed to run docker: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        let state = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        match state.split(' ').collect::<Vec<_>>()[..] {
+            ["exited" | "dead", code, oom_killed] => Ok(Some(ContainerExit {
+                code: code.parse().map_err(|_| format!("Bad exit code {:?}", code))?,
+                oom_killed: oom_killed == "true",
+            })),
+            [_, _, _] => Ok(None),
+            _ => Err(format!("Unexpected container state {:?}", state)),
+        }
+    }
+
+    fn remove(&self, container_id: &str) {
+        let _ = Command::new("docker").args(["rm", "-f", container_id]).output();
+    }
+}
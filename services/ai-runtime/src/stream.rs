@@ -0,0 +1,685 @@
+//! Streaming Transforms
+//! `Stream` jobs read a raw event stream, run every event through a transform
+//! and cut the results into windows. Each completed window is written to SVDB
+//! as an immutable manifest and registered with ai-jobd as a version of the
+//! job's output dataset, tagged with its bounds, so continual-learning watches
+//! can pick it up by dataset name prefix.
+//!
+//! Windows are numbered from 0 and cut by event offset (`count`) or event
+//! timestamp in milliseconds (`time`): window `k` covers
+//! `[k * slide, k * slide + size)`, and tumbling windows are the `slide ==
+//! size` case. A window completes once the stream has moved past its end.
+//! Time windows follow a watermark: an event older than the newest one seen
+//! is windowed at the watermark rather than dropped.
+//!
+//! Completed windows queue for the writer on a bounded channel, so when SVDB
+//! writes lag the reader waits instead of dropping events. The writer commits
+//! a cursor after every window. A crashed transform restarts from that
+//! cursor: it re-reads only events later windows still need and never
+//! re-emits a committed window.
+//!
+//! WASM transforms run sandboxed with a gas limit per call (wasmi fuel,
+//! roughly one unit per instruction) and export:
+//!
+//! ```text
+//! memory                                  linear memory
+//! alloc(len: i32) -> i32                  buffer for the host to write input into
+//! map(ptr: i32, len: i32) -> i64          one event as JSON -> records
+//! aggregate(ptr: i32, len: i32) -> i64    a window's records -> its features
+//! ```
+//!
+//! Records and features are newline-delimited JSON; results are returned as
+//! `(ptr << 32) | len`. Either export may be left out: without `map` an event
+//! contributes its `data`, without `aggregate` a window keeps its records.
+//! Container transforms get the same two calls as JSON lines on stdin,
+//! `{"op": "map" | "aggregate", "input": ...}`, and answer each with a JSON
+//! array of records on one stdout line.
+
+use crate::svdb::SvdbClient;
+use artha_clock::SharedClock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+pub const DEFAULT_GAS_LIMIT: u64 = 10_000_000;
+/// Most windows an event may fall into (`size / slide`)
+const MAX_OVERLAP: u64 = 64;
+/// Completed windows waiting on the writer before the reader blocks
+const MAX_PENDING_WINDOWS: usize = 4;
+const FETCH_BATCH: usize = 256;
+/// Crash restarts before the job is failed
+pub const MAX_TRANSFORM_RESTARTS: u32 = 3;
+const MAX_WRITE_BACKOFF: Duration = Duration::from_secs(30);
+const CURSOR_FILE: &str = "cursor.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransformSpec {
+    Wasm {
+        module_cid: String,
+        #[serde(default = "default_gas_limit")]
+        gas_limit: u64, // Per map/aggregate call
+    },
+    Container {
+        image: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+fn default_gas_limit() -> u64 {
+    DEFAULT_GAS_LIMIT
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowUnit {
+    Count, // Event offsets
+    Time,  // Event timestamps, milliseconds
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WindowPolicy {
+    Tumbling { by: WindowUnit, size: u64 },
+    Sliding { by: WindowUnit, size: u64, slide: u64 },
+}
+
+impl WindowPolicy {
+    /// `(unit, size, slide)`
+    fn shape(&self) -> (WindowUnit, u64, u64) {
+        match *self {
+            WindowPolicy::Tumbling { by, size } => (by, size, size),
+            WindowPolicy::Sliding { by, size, slide } => (by, size, slide),
+        }
+    }
+}
+
+/// Where completed windows go: versions of one dataset
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamOutput {
+    pub dataset: String, // Dataset name; `{job_id}` expands
+    #[serde(default = "default_version")]
+    pub version: String, // Per window; `{window}`, `{start}` and `{end}` expand
+    pub license_cid: String,
+}
+
+fn default_version() -> String {
+    "w{window}".to_string()
+}
+
+impl StreamOutput {
+    pub fn dataset_name(&self, job_id: &str) -> String {
+        self.dataset.replace("{job_id}", job_id)
+    }
+
+    pub fn version_name(&self, bounds: &WindowBounds) -> String {
+        self.version
+            .replace("{window}", &bounds.index.to_string())
+            .replace("{start}", &bounds.start.to_string())
+            .replace("{end}", &bounds.end.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamSpec {
+    pub input_stream: String, // Base URL serving `/stream/events`
+    pub transform: TransformSpec,
+    pub window: WindowPolicy,
+    pub output: StreamOutput,
+}
+
+impl StreamSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.input_stream.is_empty() {
+            return Err("input_stream is required".to_string());
+        }
+        match &self.transform {
+            TransformSpec::Wasm { module_cid, gas_limit } => {
+                if module_cid.is_empty() || *gas_limit == 0 {
+                    return Err("WASM transforms need a module_cid and a gas_limit above 0".to_string());
+                }
+            }
+            TransformSpec::Container { image, .. } if image.is_empty() => {
+                return Err("Container transforms need an image".to_string());
+            }
+            TransformSpec::Container { .. } => {}
+        }
+        let (_, size, slide) = self.window.shape();
+        if size == 0 || slide == 0 || slide > size {
+            return Err(format!("Window slide must be between 1 and the window size, got size {} slide {}", size, slide));
+        }
+        if size.div_ceil(slide) > MAX_OVERLAP {
+            return Err(format!("Windows may overlap at most {} deep", MAX_OVERLAP));
+        }
+        if self.output.dataset.is_empty() || self.output.license_cid.is_empty() {
+            return Err("output needs a dataset name and a license_cid".to_string());
+        }
+        if !self.output.version.contains("{window}") && !self.output.version.contains("{start}") {
+            return Err("output version must include {window} or {start} to be unique per window".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartStreamRequest {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub spec: StreamSpec,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamEvent {
+    pub offset: u64,
+    pub timestamp: u64, // Milliseconds
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// `[start, end)` in the window's unit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WindowBounds {
+    pub by: WindowUnit,
+    pub index: u64,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Window {
+    pub bounds: WindowBounds,
+    pub first_offset: u64,
+    pub last_offset: u64,
+    pub records: Vec<Value>,
+    pub resume_offset: u64, // Earliest offset any later window still needs
+}
+
+/// Cuts a stream into windows
+pub struct Windower {
+    by: WindowUnit,
+    size: u64,
+    slide: u64,
+    next_window: u64, // Windows below this are already committed
+    frontier: u64,    // Count: next offset; time: watermark
+    open: BTreeMap<u64, Window>,
+}
+
+impl Windower {
+    pub fn new(policy: &WindowPolicy, next_window: u64) -> Self {
+        let (by, size, slide) = policy.shape();
+        Windower { by, size, slide, next_window, frontier: 0, open: BTreeMap::new() }
+    }
+
+    fn bounds(&self, index: u64) -> WindowBounds {
+        let start = index * self.slide;
+        WindowBounds { by: self.by, index, start, end: start + self.size }
+    }
+
+    /// Add an event's records to every window covering it and return the
+    /// windows that completes, oldest first
+    pub fn push(&mut self, offset: u64, timestamp: u64, records: &[Value]) -> Vec<Window> {
+        let position = match self.by {
+            WindowUnit::Count => offset,
+            WindowUnit::Time => timestamp.max(self.frontier),
+        };
+        let first = if position < self.size { 0 } else { (position - self.size) / self.slide + 1 };
+        for index in first.max(self.next_window)..=position / self.slide {
+            let bounds = self.bounds(index);
+            let window = self.open.entry(index).or_insert_with(|| Window {
+                bounds,
+                first_offset: offset,
+                last_offset: offset,
+                records: Vec::new(),
+                resume_offset: 0,
+            });
+            window.last_offset = offset;
+            window.records.extend(records.iter().cloned());
+        }
+        self.frontier = match self.by {
+            WindowUnit::Count => offset + 1,
+            WindowUnit::Time => position,
+        };
+
+        let mut completed = Vec::new();
+        while let Some(entry) = self.open.first_entry() {
+            if entry.get().bounds.end > self.frontier {
+                break;
+            }
+            let mut window = entry.remove();
+            window.resume_offset = self.open.values().map(|w| w.first_offset).fold(offset + 1, u64::min);
+            self.next_window = window.bounds.index + 1;
+            completed.push(window);
+        }
+        completed
+    }
+}
+
+/// Committed progress; a restarted stream resumes from here
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct StreamCursor {
+    pub next_window: u64,
+    pub resume_offset: u64,
+}
+
+impl StreamCursor {
+    pub fn load(dir: &std::path::Path) -> Self {
+        std::fs::read(dir.join(CURSOR_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, dir: &std::path::Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let tmp = dir.join(format!("{}.tmp", CURSOR_FILE));
+        std::fs::write(&tmp, serde_json::to_vec(self).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, dir.join(CURSOR_FILE)).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformError {
+    OutOfGas { limit: u64 },
+    Crashed(String), // The transform may succeed on a restart
+    Invalid(String), // Bad module or output; restarting will not help
+}
+
+impl std::fmt::Display for TransformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransformError::OutOfGas { limit } => write!(f, "Transform exceeded its gas limit of {}", limit),
+            TransformError::Crashed(e) => write!(f, "Transform crashed: {}", e),
+            TransformError::Invalid(e) => write!(f, "Invalid transform: {}", e),
+        }
+    }
+}
+
+pub trait Transform: Send {
+    /// Records an event contributes to its windows
+    fn map(&mut self, event: &StreamEvent) -> Result<Vec<Value>, TransformError>;
+    /// Features a completed window emits
+    fn aggregate(&mut self, records: Vec<Value>) -> Result<Vec<Value>, TransformError>;
+}
+
+pub type TransformFactory = Box<dyn Fn() -> Result<Box<dyn Transform>, TransformError> + Send + Sync>;
+
+fn parse_records(output: &[u8]) -> Result<Vec<Value>, TransformError> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| TransformError::Invalid(format!("Bad output record: {}", e))))
+        .collect()
+}
+
+fn ndjson(records: &[Value]) -> Vec<u8> {
+    records.iter().map(|r| format!("{}\n", r)).collect::<String>().into_bytes()
+}
+
+/// Transform compiled from a WASM module, instantiated fresh for every call
+pub struct WasmTransform {
+    engine: wasmi::Engine,
+    module: wasmi::Module,
+    gas_limit: u64,
+    has_map: bool,
+    has_aggregate: bool,
+}
+
+impl WasmTransform {
+    pub fn new(wasm: &[u8], gas_limit: u64) -> Result<Self, TransformError> {
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = wasmi::Engine::new(&config);
+        let module = wasmi::Module::new(&engine, wasm).map_err(|e| TransformError::Invalid(e.to_string()))?;
+        let exports: Vec<String> = module.exports().map(|e| e.name().to_string()).collect();
+        let has = |name: &str| exports.iter().any(|e| e == name);
+        if !has("memory") || !has("alloc") || !(has("map") || has("aggregate")) {
+            return Err(TransformError::Invalid("Module must export memory, alloc and map and/or aggregate".to_string()));
+        }
+        Ok(WasmTransform { has_map: has("map"), has_aggregate: has("aggregate"), engine, module, gas_limit })
+    }
+
+    fn call(&self, export: &str, input: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let invalid = |e: &dyn std::fmt::Display| TransformError::Invalid(e.to_string());
+        let mut store = wasmi::Store::new(&self.engine, ());
+        store.add_fuel(self.gas_limit).map_err(|e| invalid(&e))?;
+        let instance = wasmi::Linker::<()>::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| self.trapped(e))?;
+        let memory = instance.get_memory(&store, "memory").ok_or_else(|| invalid(&"No memory export"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc").map_err(|e| invalid(&e))?;
+        let func = instance.get_typed_func::<(i32, i32), i64>(&store, export).map_err(|e| invalid(&e))?;
+
+        let len = i32::try_from(input.len()).map_err(|_| invalid(&"Input too large"))?;
+        let ptr = alloc.call(&mut store, len).map_err(|t| self.trapped(t.into()))?;
+        memory.write(&mut store, ptr as u32 as usize, input).map_err(|e| invalid(&e))?;
+        let packed = func.call(&mut store, (ptr, len)).map_err(|t| self.trapped(t.into()))? as u64;
+
+        let mut output = vec![0; (packed & 0xffff_ffff) as usize];
+        memory.read(&store, (packed >> 32) as usize, &mut output).map_err(|e| invalid(&e))?;
+        Ok(output)
+    }
+
+    fn trapped(&self, error: wasmi::Error) -> TransformError {
+        match &error {
+            wasmi::Error::Trap(trap) if matches!(trap.trap_code(), Some(wasmi::core::TrapCode::OutOfFuel)) => {
+                TransformError::OutOfGas { limit: self.gas_limit }
+            }
+            wasmi::Error::Trap(trap) => TransformError::Crashed(trap.to_string()),
+            _ => TransformError::Invalid(error.to_string()),
+        }
+    }
+}
+
+impl Transform for WasmTransform {
+    fn map(&mut self, event: &StreamEvent) -> Result<Vec<Value>, TransformError> {
+        if !self.has_map {
+            return Ok(vec![event.data.clone()]);
+        }
+        let input = serde_json::to_vec(event).map_err(|e| TransformError::Invalid(e.to_string()))?;
+        parse_records(&self.call("map", &input)?)
+    }
+
+    fn aggregate(&mut self, records: Vec<Value>) -> Result<Vec<Value>, TransformError> {
+        if !self.has_aggregate {
+            return Ok(records);
+        }
+        parse_records(&self.call("aggregate", &ndjson(&records))?)
+    }
+}
+
+/// Transform running in a long-lived container without network access
+pub struct ContainerTransform {
+    child: std::process::Child,
+    stdin: std::process::ChildStdin,
+    stdout: BufReader<std::process::ChildStdout>,
+}
+
+impl ContainerTransform {
+    pub fn spawn(image: &str, args: &[String]) -> Result<Self, TransformError> {
+        let mut child = std::process::Command::new("docker")
+            .args(["run", "--rm", "-i", "--network", "none", image])
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| TransformError::Crashed(format!("Failed to start transform container: {}", e)))?;
+        let stdin = child.stdin.take().ok_or_else(|| TransformError::Crashed("No stdin".to_string()))?;
+        let stdout = child.stdout.take().ok_or_else(|| TransformError::Crashed("No stdout".to_string()))?;
+        Ok(ContainerTransform { child, stdin, stdout: BufReader::new(stdout) })
+    }
+
+    fn call(&mut self, op: &str, input: Value) -> Result<Vec<Value>, TransformError> {
+        let crashed = |e: std::io::Error| TransformError::Crashed(e.to_string());
+        writeln!(self.stdin, "{}", serde_json::json!({ "op": op, "input": input })).map_err(crashed)?;
+        self.stdin.flush().map_err(crashed)?;
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line).map_err(crashed)? == 0 {
+            return Err(TransformError::Crashed("Transform container exited".to_string()));
+        }
+        serde_json::from_str(&line).map_err(|e| TransformError::Invalid(format!("Bad output from {}: {}", op, e)))
+    }
+}
+
+impl Transform for ContainerTransform {
+    fn map(&mut self, event: &StreamEvent) -> Result<Vec<Value>, TransformError> {
+        self.call("map", serde_json::to_value(event).unwrap_or_default())
+    }
+
+    fn aggregate(&mut self, records: Vec<Value>) -> Result<Vec<Value>, TransformError> {
+        self.call("aggregate", Value::Array(records))
+    }
+}
+
+impl Drop for ContainerTransform {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct StreamMetrics {
+    pub events_read: u64,
+    pub events_per_sec: f64, // Since the transform last (re)started
+    pub next_offset: u64,
+    pub head_offset: Option<u64>, // Next offset the stream will write, as last reported
+    pub lag_events: u64,          // Written to the stream but not read yet
+    pub windows_committed: u64,
+    pub last_committed_window: Option<u64>,
+    pub commit_lag_ms: u64,     // Completion to commit, last window
+    pub pending_windows: u64,   // Completed, waiting on the writer
+    pub backpressure_waits: u64, // Times the reader blocked on a full writer queue
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+/// Shared between a running stream and the API
+#[derive(Debug, Default)]
+pub struct StreamHandle {
+    stop: AtomicBool,
+    metrics: Mutex<StreamMetrics>,
+}
+
+impl StreamHandle {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub fn metrics(&self) -> StreamMetrics {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut StreamMetrics)) {
+        f(&mut self.metrics.lock().unwrap());
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EventBatch {
+    events: Vec<StreamEvent>,
+    #[serde(default)]
+    head: Option<u64>,
+}
+
+/// Writes window manifests to SVDB and registers them with ai-jobd
+pub struct WindowSink {
+    pub svdb: Arc<SvdbClient>,
+    pub jobd_url: String,
+}
+
+impl WindowSink {
+    /// Returns the registered dataset id. Replaying a window produces the same
+    /// manifest, and ai-jobd answers a repeat registration with the same id.
+    async fn commit(&self, job_id: &str, spec: &StreamSpec, window: &Window) -> Result<String, String> {
+        let dataset = spec.output.dataset_name(job_id);
+        let version = spec.output.version_name(&window.bounds);
+        let manifest = serde_json::json!({
+            "dataset": dataset,
+            "version": version,
+            "job_id": job_id,
+            "input_stream": spec.input_stream,
+            "transform": spec.transform,
+            "window": window.bounds,
+            "offsets": { "first": window.first_offset, "last": window.last_offset },
+            "records": window.records,
+        });
+        let root_cid = self.svdb.upload(serde_json::to_vec(&manifest).map_err(|e| e.to_string())?).await?;
+
+        let by = match window.bounds.by {
+            WindowUnit::Count => "count",
+            WindowUnit::Time => "time",
+        };
+        let response = reqwest::Client::new()
+            .post(format!("{}/ai/dataset/register", self.jobd_url))
+            .json(&serde_json::json!({
+                "root_cid": root_cid,
+                "license_cid": spec.output.license_cid,
+                "tags": ["stream".to_string(), format!("stream:{}", job_id), format!("window:{}:{}-{}", by, window.bounds.start, window.bounds.end)],
+                "name": dataset,
+                "version": version,
+                "samples": window.records.len(),
+                "window": { "by": by, "start": window.bounds.start, "end": window.bounds.end },
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Dataset registration failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Dataset registration failed with status: {}", response.status()));
+        }
+        let registered: Value = response.json().await.map_err(|e| e.to_string())?;
+        registered["dataset_id"].as_str().map(|s| s.to_string()).ok_or_else(|| "Missing dataset_id".to_string())
+    }
+}
+
+pub struct StreamRun {
+    pub job_id: String,
+    pub spec: StreamSpec,
+    pub dir: PathBuf, // Holds the committed cursor
+    pub sink: WindowSink,
+    pub handle: Arc<StreamHandle>,
+    pub poll_interval: Duration,
+    pub clock: SharedClock, // Paces polling and commit retries
+}
+
+impl StreamRun {
+    /// Run until stopped. Crashed transforms restart from the last committed
+    /// window; an error means the stream cannot go on.
+    pub async fn run(&self, make_transform: &TransformFactory) -> Result<(), String> {
+        let mut restarts = 0;
+        loop {
+            match self.attempt(make_transform).await {
+                Ok(()) => return Ok(()),
+                Err(TransformError::Crashed(e)) if restarts < MAX_TRANSFORM_RESTARTS => {
+                    restarts += 1;
+                    info!("🔁 Stream {} transform crashed ({}), restart {} of {}", self.job_id, e, restarts, MAX_TRANSFORM_RESTARTS);
+                    self.handle.update(|m| {
+                        m.restarts = restarts;
+                        m.last_error = Some(e);
+                    });
+                }
+                Err(e) => {
+                    self.handle.update(|m| m.last_error = Some(e.to_string()));
+                    return Err(e.to_string());
+                }
+            }
+        }
+    }
+
+    async fn attempt(&self, make_transform: &TransformFactory) -> Result<(), TransformError> {
+        let cursor = StreamCursor::load(&self.dir);
+        let mut transform = make_transform()?;
+        let (tx, rx) = mpsc::channel(MAX_PENDING_WINDOWS);
+        // The writer drains what the reader queued even if the transform crashed
+        let (read, ()) = tokio::join!(self.read(cursor, transform.as_mut(), tx), self.write(rx));
+        read
+    }
+
+    async fn read(&self, cursor: StreamCursor, transform: &mut dyn Transform, tx: mpsc::Sender<(Window, Instant)>) -> Result<(), TransformError> {
+        let client = reqwest::Client::new();
+        let mut windower = Windower::new(&self.spec.window, cursor.next_window);
+        let mut offset = cursor.resume_offset;
+        let (started, mut read) = (Instant::now(), 0u64);
+        loop {
+            if self.handle.stopped() {
+                return Ok(());
+            }
+            let url = format!("{}/stream/events?from={}&limit={}", self.spec.input_stream, offset, FETCH_BATCH);
+            let batch = match client.get(&url).send().await {
+                Ok(resp) if resp.status().is_success() => resp.json::<EventBatch>().await.map_err(|e| e.to_string()),
+                Ok(resp) => Err(format!("Stream read failed with status: {}", resp.status())),
+                Err(e) => Err(format!("Stream read failed: {}", e)),
+            };
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(e) => {
+                    self.handle.update(|m| m.last_error = Some(e));
+                    self.clock.sleep(self.poll_interval).await;
+                    continue;
+                }
+            };
+            let caught_up = batch.events.is_empty();
+
+            for event in batch.events {
+                if event.offset < offset {
+                    continue; // Already read
+                }
+                let records = transform.map(&event)?;
+                for mut window in windower.push(event.offset, event.timestamp, &records) {
+                    window.records = transform.aggregate(std::mem::take(&mut window.records))?;
+                    let queued = match tx.try_send((window, Instant::now())) {
+                        Ok(()) => Ok(()),
+                        Err(mpsc::error::TrySendError::Full(window)) => {
+                            self.handle.update(|m| m.backpressure_waits += 1);
+                            tx.send(window).await.map_err(|_| ())
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => Err(()),
+                    };
+                    if queued.is_err() {
+                        return Ok(()); // The writer stopped
+                    }
+                }
+                offset = event.offset + 1;
+                read += 1;
+            }
+
+            let pending = (MAX_PENDING_WINDOWS - tx.capacity()) as u64;
+            self.handle.update(|m| {
+                m.events_read = read;
+                m.events_per_sec = read as f64 / started.elapsed().as_secs_f64().max(0.001);
+                m.next_offset = offset;
+                m.head_offset = batch.head.or(m.head_offset);
+                m.lag_events = m.head_offset.map_or(0, |head| head.saturating_sub(offset));
+                m.pending_windows = pending;
+            });
+            if caught_up {
+                self.clock.sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    async fn write(&self, mut rx: mpsc::Receiver<(Window, Instant)>) {
+        while let Some((window, completed_at)) = rx.recv().await {
+            let mut backoff = self.poll_interval;
+            loop {
+                match self.sink.commit(&self.job_id, &self.spec, &window).await {
+                    Ok(dataset_id) => {
+                        let cursor = StreamCursor { next_window: window.bounds.index + 1, resume_offset: window.resume_offset };
+                        if let Err(e) = cursor.save(&self.dir) {
+                            warn!("⚠️  Failed to persist stream cursor for {}: {}", self.job_id, e);
+                        }
+                        info!("🪟 Stream {} window {} committed as {}", self.job_id, window.bounds.index, dataset_id);
+                        self.handle.update(|m| {
+                            m.windows_committed += 1;
+                            m.last_committed_window = Some(window.bounds.index);
+                            m.commit_lag_ms = completed_at.elapsed().as_millis() as u64;
+                        });
+                        break;
+                    }
+                    // Retrying holds the queue, which in turn holds the reader
+                    Err(e) => {
+                        warn!("⚠️  Stream {} window {} not committed: {}", self.job_id, window.bounds.index, e);
+                        self.handle.update(|m| m.last_error = Some(e));
+                        if self.handle.stopped() {
+                            return;
+                        }
+                        self.clock.sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_WRITE_BACKOFF);
+                    }
+                }
+            }
+        }
+    }
+}
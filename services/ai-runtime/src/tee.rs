@@ -0,0 +1,145 @@
+//! TEE Launch and Attestation
+//! Runs job containers inside a trusted execution environment and produces
+//! attestation quotes binding the image digest, job id, and proof-service nonce
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TeeType {
+    Sgx,
+    SevSnp,
+    Tdx,
+    Simulated,
+}
+
+/// Attestation evidence produced by a TEE launch.
+/// `raw_quote` follows the custody `tee_enclave` quote layout
+/// (`SGX_QUOTE:<enclave>:MRENCLAVE:<measurement>`) so the same checks apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationQuote {
+    pub tee_type: TeeType,
+    pub measurement: String,
+    pub image_digest: String,
+    pub job_id: String,
+    pub nonce: String,
+    pub report_data: String,
+    pub raw_quote: String,
+    pub signature: String,
+    /// Vendor certificate chain of the signing key, leaf first, as ai-proofs
+    /// expects it; empty for the simulated launcher
+    #[serde(default)]
+    pub cert_chain: Vec<QuoteCert>,
+}
+
+/// A certificate of a quote's chain: a key and its issuer's signature over it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteCert {
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TeeLaunchSpec {
+    pub image: String,
+    pub image_digest: String,
+    pub job_id: String,
+    pub nonce: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TeeLaunch {
+    pub container_id: String,
+    pub quote: AttestationQuote,
+}
+
+/// Launches a container under an enclave runtime or confidential VM
+/// (SGX enclave runtime, SEV-SNP, TDX) and returns its attestation quote.
+pub trait TeeLauncher: Send + Sync {
+    fn tee_type(&self) -> TeeType;
+    fn launch(&self, spec: &TeeLaunchSpec) -> Result<TeeLaunch, String>;
+}
+
+/// Report data binds the quote to a single job execution:
+/// sha256(image_digest || job_id || nonce)
+pub fn compute_report_data(image_digest: &str, job_id: &str, nonce: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(image_digest.as_bytes());
+    hasher.update(job_id.as_bytes());
+    hasher.update(nonce.as_bytes());
+    format!("0x{:x}", hasher.finalize())
+}
+
+/// Signature scheme used by the simulated attestation root.
+/// ai-proofs verifies simulated quotes with the same construction.
+pub fn simulated_quote_signature(measurement: &str, report_data: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"ARTHA_SIMULATED_TEE:");
+    hasher.update(measurement.as_bytes());
+    hasher.update(report_data.as_bytes());
+    format!("0x{:x}", hasher.finalize())
+}
+
+/// Resolve the content digest for an image reference
+pub fn resolve_image_digest(image: &str) -> String {
+    // In production: docker image inspect --format '{{.Id}}'
+    let output = std::process::Command::new("docker")
+        .args(["image", "inspect", "--format", "{{.Id}}", image])
+        .output();
+
+    if let Ok(output) = output {
+        let digest = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && digest.starts_with("sha256:") {
+            return digest;
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(image.as_bytes());
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+/// Simulated launcher for tests and development nodes without TEE hardware
+pub struct SimulatedTeeLauncher {
+    pub measurement: String,
+}
+
+impl SimulatedTeeLauncher {
+    pub fn new(measurement: String) -> Self {
+        SimulatedTeeLauncher { measurement }
+    }
+}
+
+impl TeeLauncher for SimulatedTeeLauncher {
+    fn tee_type(&self) -> TeeType {
+        TeeType::Simulated
+    }
+
+    fn launch(&self, spec: &TeeLaunchSpec) -> Result<TeeLaunch, String> {
+        if spec.nonce.is_empty() {
+            return Err("Attestation nonce is required".to_string());
+        }
+
+        let report_data = compute_report_data(&spec.image_digest, &spec.job_id, &spec.nonce);
+        let signature = simulated_quote_signature(&self.measurement, &report_data);
+        let raw_quote = format!(
+            "SGX_QUOTE:sim-enclave-{}:MRENCLAVE:{}",
+            spec.job_id, self.measurement
+        );
+
+        Ok(TeeLaunch {
+            container_id: format!("tee-sim-{}", spec.job_id),
+            quote: AttestationQuote {
+                tee_type: self.tee_type(),
+                measurement: self.measurement.clone(),
+                image_digest: spec.image_digest.clone(),
+                job_id: spec.job_id.clone(),
+                nonce: spec.nonce.clone(),
+                report_data,
+                raw_quote,
+                signature,
+                cert_chain: Vec::new(),
+            },
+        })
+    }
+}
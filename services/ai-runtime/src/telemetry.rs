@@ -0,0 +1,218 @@
+//! GPU Telemetry
+//! Periodic utilization, memory and power readings for each job's allocated
+//! GPUs, downsampled into fixed-width buckets per device. Jobs whose recent
+//! utilization stays below a threshold are flagged as underutilized, which
+//! usually means a misconfigured batch size or a CPU-bound input pipeline.
+
+use crate::stream::StreamMetrics;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::process::Command;
+
+/// One instantaneous reading of a device
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuSample {
+    pub utilization_pct: f64,
+    pub memory_used_mb: u64,
+    pub memory_total_mb: u64,
+    pub power_watts: f64,
+}
+
+/// Source of device readings (NVML on real nodes)
+pub trait GpuSampler: Send + Sync {
+    fn sample(&self, device: u32) -> Result<GpuSample, String>;
+}
+
+/// Reads NVML counters through `nvidia-smi`
+pub struct NvmlSampler;
+
+impl GpuSampler for NvmlSampler {
+    fn sample(&self, device: u32) -> Result<GpuSample, String> {
+        let output = Command::new("nvidia-smi")
+            .args([
+                "--query-gpu=utilization.gpu,memory.used,memory.total,power.draw",
+                "--format=csv,noheader,nounits",
+                "-i",
+                &device.to_string(),
+            ])
+            .output()
+            .map_err(|e| format!("nvidia-smi unavailable: {}", e))?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+pub fn parse_nvidia_smi(line: &str) -> Result<GpuSample, String> {
+    let fields: Vec<&str> = line.trim().split(',').map(|f| f.trim()).collect();
+    if fields.len() != 4 {
+        return Err(format!("Unexpected nvidia-smi output: {}", line.trim()));
+    }
+    let number = |i: usize| -> Result<f64, String> {
+        fields[i].parse::<f64>().map_err(|_| format!("Bad nvidia-smi field: {}", fields[i]))
+    };
+
+    Ok(GpuSample {
+        utilization_pct: number(0)?,
+        memory_used_mb: number(1)? as u64,
+        memory_total_mb: number(2)? as u64,
+        power_watts: number(3).unwrap_or(0.0), // "[N/A]" on boards without power readings
+    })
+}
+
+/// Device index of an allocation id such as "gpu:3"
+pub fn device_index(gpu_id: &str) -> Option<u32> {
+    gpu_id.strip_prefix("gpu:")?.parse().ok()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    pub bucket_secs: u64,         // Width of one downsampled point
+    pub max_buckets: usize,       // Oldest points are dropped past this
+    pub underutil_pct: f64,       // Average utilization below this is flagged
+    pub underutil_window_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            bucket_secs: 60,
+            max_buckets: 1440,
+            underutil_pct: 20.0,
+            underutil_window_secs: 600,
+        }
+    }
+}
+
+/// Samples aggregated over one bucket
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TelemetryPoint {
+    pub bucket_start: u64,
+    pub samples: u32,
+    pub avg_utilization_pct: f64,
+    pub max_utilization_pct: f64,
+    pub avg_memory_used_mb: u64,
+    pub max_memory_used_mb: u64,
+    pub memory_total_mb: u64,
+    pub avg_power_watts: f64,
+}
+
+impl TelemetryPoint {
+    fn new(bucket_start: u64, sample: &GpuSample) -> Self {
+        Self {
+            bucket_start,
+            samples: 1,
+            avg_utilization_pct: sample.utilization_pct,
+            max_utilization_pct: sample.utilization_pct,
+            avg_memory_used_mb: sample.memory_used_mb,
+            max_memory_used_mb: sample.memory_used_mb,
+            memory_total_mb: sample.memory_total_mb,
+            avg_power_watts: sample.power_watts,
+        }
+    }
+
+    fn add(&mut self, sample: &GpuSample) {
+        let n = self.samples as f64;
+        let running_avg = |avg: f64, value: f64| (avg * n + value) / (n + 1.0);
+
+        self.avg_utilization_pct = running_avg(self.avg_utilization_pct, sample.utilization_pct);
+        self.avg_memory_used_mb =
+            running_avg(self.avg_memory_used_mb as f64, sample.memory_used_mb as f64).round() as u64;
+        self.avg_power_watts = running_avg(self.avg_power_watts, sample.power_watts);
+        self.max_utilization_pct = self.max_utilization_pct.max(sample.utilization_pct);
+        self.max_memory_used_mb = self.max_memory_used_mb.max(sample.memory_used_mb);
+        self.memory_total_mb = sample.memory_total_mb;
+        self.samples += 1;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UnderutilizationAlert {
+    pub since: u64,
+    pub avg_utilization_pct: f64,
+    pub threshold_pct: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobTelemetry {
+    pub job_id: String,
+    pub devices: BTreeMap<String, VecDeque<TelemetryPoint>>,
+    pub last_sample_at: Option<u64>,
+    pub sample_errors: u64,
+    pub underutilized: Option<UnderutilizationAlert>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamMetrics>, // Stream jobs: throughput and lag
+}
+
+impl JobTelemetry {
+    pub fn new(job_id: &str) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&mut self, gpu_id: &str, sample: &GpuSample, timestamp: u64, config: &TelemetryConfig) {
+        let bucket_start = timestamp - timestamp % config.bucket_secs.max(1);
+        let series = self.devices.entry(gpu_id.to_string()).or_default();
+
+        match series.back_mut() {
+            Some(point) if point.bucket_start == bucket_start => point.add(sample),
+            _ => {
+                series.push_back(TelemetryPoint::new(bucket_start, sample));
+                while series.len() > config.max_buckets {
+                    series.pop_front();
+                }
+            }
+        }
+        self.last_sample_at = Some(timestamp);
+    }
+
+    /// Re-evaluate the underutilization flag over the trailing window.
+    /// Returns the alert when the job has just become underutilized.
+    pub fn check_utilization(&mut self, now: u64, config: &TelemetryConfig) -> Option<UnderutilizationAlert> {
+        let window_start = now.saturating_sub(config.underutil_window_secs);
+        let first_sample = self
+            .devices
+            .values()
+            .filter_map(|series| series.front())
+            .map(|point| point.bucket_start)
+            .min()?;
+
+        // Not enough history to judge a job that just started
+        if first_sample > window_start {
+            return None;
+        }
+
+        let (weighted, samples) = self
+            .devices
+            .values()
+            .flat_map(|series| series.iter())
+            .filter(|point| point.bucket_start >= window_start)
+            .fold((0.0, 0u32), |(sum, n), point| {
+                (sum + point.avg_utilization_pct * point.samples as f64, n + point.samples)
+            });
+        if samples == 0 {
+            return None;
+        }
+
+        let average = weighted / samples as f64;
+        if average >= config.underutil_pct {
+            self.underutilized = None;
+            return None;
+        }
+        if self.underutilized.is_some() {
+            return None;
+        }
+
+        let alert = UnderutilizationAlert {
+            since: now,
+            avg_utilization_pct: average,
+            threshold_pct: config.underutil_pct,
+        };
+        self.underutilized = Some(alert.clone());
+        Some(alert)
+    }
+}
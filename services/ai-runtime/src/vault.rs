@@ -0,0 +1,126 @@
+//! Vault Secret References
+//! A job can name secrets (`secret_refs`) instead of carrying their values.
+//! At launch each name is checked with policy-gate for the submitter's DID
+//! and then read from the node's vault. The value is injected into the
+//! container as the env var of the same name. From there it is handled like
+//! `secret_env`: never formatted, recorded by name only, and kept by the
+//! runtime alone for restarts and replays.
+
+use crate::repro::SensitiveEnv;
+use artha_log::Sensitive;
+use tracing::info;
+
+/// Action secrets are checked under with policy-gate
+pub const READ_SECRET_ACTION: &str = "read_secret";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretError {
+    InvalidName(String),
+    Denied { name: String, reason: String },
+    NotFound(String),
+    Unavailable(String), // Vault or policy-gate could not be asked
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::InvalidName(name) => write!(f, "secret name {:?} is not a valid env var name", name),
+            SecretError::Denied { name, reason } => write!(f, "access to secret {} denied: {}", name, reason),
+            SecretError::NotFound(name) => write!(f, "secret {} not found in vault", name),
+            SecretError::Unavailable(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+pub struct VaultClient {
+    url: String,
+    token: Option<Sensitive<String>>, // Sent as X-Vault-Token
+    policy_url: String,
+    client: reqwest::Client,
+}
+
+/// Env var names: a letter or underscore, then letters, digits and underscores
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl VaultClient {
+    pub fn new(url: String, token: Option<Sensitive<String>>, policy_url: String) -> Self {
+        VaultClient { url, token, policy_url, client: reqwest::Client::new() }
+    }
+
+    /// Configured from `ARTHA_VAULT_URL`; without it jobs can't reference secrets
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("ARTHA_VAULT_URL").ok()?;
+        Some(VaultClient::new(
+            url,
+            std::env::var("ARTHA_VAULT_TOKEN").ok().map(Sensitive::new),
+            std::env::var("ARTHA_POLICY_URL").unwrap_or_else(|_| "http://localhost:8082".to_string()),
+        ))
+    }
+
+    /// Values of `names` for a job submitted by `did`. All of them or none.
+    pub async fn resolve(&self, did: &str, names: &[String]) -> Result<SensitiveEnv, SecretError> {
+        if let Some(name) = names.iter().find(|name| !is_env_name(name)) {
+            return Err(SecretError::InvalidName(name.clone()));
+        }
+        let mut env = SensitiveEnv::new();
+        for name in names {
+            self.check_policy(did, name).await?;
+            env.insert(name.clone(), self.fetch(name).await?);
+        }
+        info!("   Secrets: {} resolved from vault", names.join(", "));
+        Ok(env)
+    }
+
+    async fn check_policy(&self, did: &str, name: &str) -> Result<(), SecretError> {
+        let result: serde_json::Value = self.client
+            .post(format!("{}/policy/check", self.policy_url))
+            .json(&serde_json::json!({
+                "did": did,
+                "action": READ_SECRET_ACTION,
+                "resource": format!("secret:{}", name),
+                "budget": 1, // Reading a secret spends nothing, but policy-gate refuses a zero budget
+            }))
+            .send()
+            .await
+            .map_err(|e| SecretError::Unavailable(format!("Policy check failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| SecretError::Unavailable(format!("Invalid policy response: {}", e)))?;
+
+        if result["allowed"].as_bool() == Some(true) {
+            Ok(())
+        } else {
+            Err(SecretError::Denied {
+                name: name.to_string(),
+                reason: result["reason"].as_str().unwrap_or("Request denied by policy").to_string(),
+            })
+        }
+    }
+
+    async fn fetch(&self, name: &str) -> Result<Sensitive<String>, SecretError> {
+        let mut request = self.client.get(format!("{}/secrets/{}", self.url, name));
+        if let Some(token) = &self.token {
+            request = request.header("X-Vault-Token", token.expose());
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| SecretError::Unavailable(format!("Vault request failed: {}", e)))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(SecretError::NotFound(name.to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(SecretError::Unavailable(format!("Vault returned {}", response.status())));
+        }
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| SecretError::Unavailable(format!("Invalid vault response: {}", e)))?;
+        match body["value"].as_str() {
+            Some(value) => Ok(Sensitive::new(value.to_string())),
+            None => Err(SecretError::Unavailable(format!("Vault response for {} has no value", name))),
+        }
+    }
+}
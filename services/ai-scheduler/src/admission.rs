@@ -0,0 +1,103 @@
+//! Admission Control
+//! Bounds the pending queue with a high-watermark that scales with cluster
+//! size, so submitters get a retry hint instead of silently queueing forever
+
+use artha_errors::ServiceError;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone)]
+pub struct AdmissionConfig {
+    pub pending_per_node: usize,  // Watermark contribution of each registered node
+    pub min_watermark: usize,     // Floor so tiny clusters can still queue
+    pub retry_after_secs: u64,    // Base retry hint returned when saturated
+}
+
+impl AdmissionConfig {
+    pub fn from_env() -> Self {
+        AdmissionConfig {
+            pending_per_node: env_or("ARTHA_SCHED_PENDING_PER_NODE", 8),
+            min_watermark: env_or("ARTHA_SCHED_MIN_WATERMARK", 4),
+            retry_after_secs: env_or("ARTHA_SCHED_RETRY_AFTER_SECS", 30),
+        }
+    }
+
+    /// Maximum number of pending jobs for a cluster of `node_count` nodes
+    pub fn high_watermark(&self, node_count: usize) -> usize {
+        self.pending_per_node
+            .saturating_mul(node_count)
+            .max(self.min_watermark)
+    }
+}
+
+pub(crate) fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Returned to ai-jobd as a `queue_full` error: `429 Too Many Requests` with a
+/// `Retry-After` header, which ai-jobd relays to the submitter unchanged
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QueueFull {
+    pub pending: usize,
+    pub high_watermark: usize,
+    pub retry_after_secs: u64,
+}
+
+impl IntoResponse for QueueFull {
+    fn into_response(self) -> Response {
+        ServiceError::queue_full(self.retry_after_secs)
+            .with_details(serde_json::json!({
+                "pending": self.pending,
+                "high_watermark": self.high_watermark,
+            }))
+            .into_response()
+    }
+}
+
+/// Jobs admitted by the scheduler and not yet released by ai-jobd
+#[derive(Debug, Default)]
+pub struct PendingQueue {
+    jobs: HashSet<String>,
+}
+
+impl PendingQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Reserve a queue slot for `job_id`. Re-submitting an already pending job
+    /// is always admitted; new jobs past the watermark are rejected with a
+    /// retry hint that grows with the backlog.
+    pub fn admit(&mut self, job_id: &str, node_count: usize, config: &AdmissionConfig) -> Result<(), QueueFull> {
+        if self.jobs.contains(job_id) {
+            return Ok(());
+        }
+
+        let high_watermark = config.high_watermark(node_count);
+        let pending = self.jobs.len();
+        if pending >= high_watermark {
+            let backlog_factor = (pending / high_watermark.max(1)) as u64;
+            return Err(QueueFull {
+                pending,
+                high_watermark,
+                retry_after_secs: config.retry_after_secs.saturating_mul(backlog_factor.max(1)),
+            });
+        }
+
+        self.jobs.insert(job_id.to_string());
+        Ok(())
+    }
+
+    /// Free the slot held by `job_id`; returns whether it was pending
+    pub fn release(&mut self, job_id: &str) -> bool {
+        self.jobs.remove(job_id)
+    }
+}
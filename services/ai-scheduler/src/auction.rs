@@ -0,0 +1,173 @@
+//! Bid/Ask Placement
+//! In auction mode the scheduler doesn't pick a node itself. The job is
+//! posted as an ask to every node able to run it, and each of those nodes may
+//! submit one sealed bid, a price and an ETA, until the window closes. Bids
+//! are never shown to other bidders. At the close each bid is rated by
+//! utility and the job goes to the best one at its bid price.
+//!
+//! Utility is `reputation^r / (price^p · eta^e)`. With the default weights
+//! it is mostly price per unit of reputation, and a sooner start counts a little.
+//!
+//! Bids carry an ECDSA `X-Artha-Signature` by the node's key over
+//! `BID:{auction_id}:{pubkey}:{sha256(body) hex}`, so a bid can't be placed
+//! for a node by anyone else or replayed into a later auction.
+
+use crate::admission::env_or;
+use crate::JobRequirements;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone)]
+pub struct AuctionConfig {
+    pub enabled: bool,          // Off: auction jobs are placed by score
+    pub window_secs: u64,       // How long bids are taken
+    pub price_weight: f64,
+    pub eta_weight: f64,
+    pub reputation_weight: f64,
+}
+
+impl AuctionConfig {
+    pub fn from_env() -> Self {
+        AuctionConfig {
+            enabled: env_or("ARTHA_SCHED_AUCTIONS", false),
+            window_secs: env_or("ARTHA_SCHED_AUCTION_WINDOW_SECS", 10),
+            price_weight: env_or("ARTHA_SCHED_AUCTION_PRICE_WEIGHT", 1.0),
+            eta_weight: env_or("ARTHA_SCHED_AUCTION_ETA_WEIGHT", 0.1),
+            reputation_weight: env_or("ARTHA_SCHED_AUCTION_REPUTATION_WEIGHT", 1.0),
+        }
+    }
+
+    /// How much a bid from a node of `reputation` is worth; higher is better
+    pub fn utility(&self, bid: &Bid, reputation: f64) -> f64 {
+        let eta = bid.eta_secs.max(1) as f64;
+        reputation.max(0.0).powf(self.reputation_weight)
+            / (bid.price_per_gpu_sec.powf(self.price_weight) * eta.powf(self.eta_weight))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bid {
+    pub price_per_gpu_sec: f64,
+    pub eta_secs: u64, // Until the node can start the job
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BidRequest {
+    pub node_pubkey: String,
+    #[serde(flatten)]
+    pub bid: Bid,
+}
+
+/// An ask as the invited nodes see it
+#[derive(Debug, Clone, Serialize)]
+pub struct Ask {
+    pub auction_id: String,
+    pub job_id: String,
+    pub job_type: String,
+    pub requirements: JobRequirements,
+    pub closes_at: u64,
+}
+
+/// How an auction closed, returned with the placement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuctionResult {
+    pub auction_id: String,
+    pub bids: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>, // None if nobody bid and the job was placed by score
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winning_bid: Option<Bid>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BidError {
+    NoAuction,
+    NotInvited,
+    Closed,
+    BadSignature,
+    Invalid(String),
+}
+
+impl std::fmt::Display for BidError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BidError::NoAuction => write!(f, "no such auction"),
+            BidError::NotInvited => write!(f, "node was not invited to bid"),
+            BidError::Closed => write!(f, "bidding has closed"),
+            BidError::BadSignature => write!(f, "bid is not signed by the node"),
+            BidError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+struct Auction {
+    ask: Ask,
+    invited: Vec<String>,
+    bids: BTreeMap<String, Bid>, // node_pubkey -> its sealed bid
+}
+
+#[derive(Default)]
+pub struct AuctionBook {
+    open: HashMap<String, Auction>, // auction_id -> auction
+    opened: u64,
+}
+
+pub fn bid_message(auction_id: &str, pubkey: &str, body: &[u8]) -> String {
+    format!("BID:{}:{}:{}", auction_id, pubkey, hex::encode(Sha256::digest(body)))
+}
+
+impl AuctionBook {
+    /// Post `ask` to the `invited` nodes; its auction id is filled in
+    pub fn open(&mut self, mut ask: Ask, invited: Vec<String>) -> Ask {
+        self.opened += 1;
+        ask.auction_id = format!("auction-{}", self.opened);
+        self.open.insert(ask.auction_id.clone(), Auction { ask: ask.clone(), invited, bids: BTreeMap::new() });
+        ask
+    }
+
+    /// Open asks `pubkey` may bid on
+    pub fn asks_for(&self, pubkey: &str, now: u64) -> Vec<Ask> {
+        let mut asks: Vec<Ask> = self
+            .open
+            .values()
+            .filter(|auction| now < auction.ask.closes_at && auction.invited.iter().any(|p| p == pubkey))
+            .map(|auction| auction.ask.clone())
+            .collect();
+        asks.sort_by(|a, b| a.closes_at.cmp(&b.closes_at).then_with(|| a.auction_id.cmp(&b.auction_id)));
+        asks
+    }
+
+    /// Take a node's sealed bid. A node bids once per auction.
+    pub fn bid(&mut self, auction_id: &str, body: &[u8], signature: Option<&str>, now: u64) -> Result<String, BidError> {
+        let auction = self.open.get_mut(auction_id).ok_or(BidError::NoAuction)?;
+        let req: BidRequest = serde_json::from_slice(body).map_err(|e| BidError::Invalid(e.to_string()))?;
+        if now >= auction.ask.closes_at {
+            return Err(BidError::Closed);
+        }
+        if !auction.invited.contains(&req.node_pubkey) {
+            return Err(BidError::NotInvited);
+        }
+        let message = bid_message(auction_id, &req.node_pubkey, body);
+        if !signature.is_some_and(|signature| crate::liveness::signature_matches(&req.node_pubkey, &message, signature)) {
+            return Err(BidError::BadSignature);
+        }
+        let price = req.bid.price_per_gpu_sec;
+        if !(price.is_finite() && price > 0.0) {
+            return Err(BidError::Invalid("price must be positive".to_string()));
+        }
+        if price > auction.ask.requirements.max_price_per_sec {
+            return Err(BidError::Invalid("price is above the ask".to_string()));
+        }
+        if auction.bids.contains_key(&req.node_pubkey) {
+            return Err(BidError::Invalid("node has already bid".to_string()));
+        }
+        auction.bids.insert(req.node_pubkey.clone(), req.bid);
+        Ok(req.node_pubkey)
+    }
+
+    /// Stop taking bids and hand back the ones received
+    pub fn close(&mut self, auction_id: &str) -> BTreeMap<String, Bid> {
+        self.open.remove(auction_id).map(|auction| auction.bids).unwrap_or_default()
+    }
+}
@@ -0,0 +1,146 @@
+//! Node Certification
+//! Which nodes the chain vouches for. With `NODE_CERT_REGISTRY_ADDR` set,
+//! only GPU providers certified in NodeCertRegistry are placement
+//! candidates. The set is read from the registry at startup and then kept in
+//! sync by the node's event forwarder, which POSTs the registry's finalized
+//! logs to `/chain/events`: `NodeRegistered` certifies a node,
+//! `NodeDeactivated` decertifies it, and a `NodeStakeUpdated` below the
+//! minimum stake (a slash) takes it out until its stake is back. A node's
+//! jobs keep running when it is decertified; it just gets no new ones.
+//!
+//! Nodes are matched on the bytes32 the registry keys them by: the first 32
+//! bytes of the pubkey, as `assignJob` already encodes it.
+
+use serde::Deserialize;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+/// `NodeRole.GPUProvider` in NodeCertRegistry
+pub const GPU_PROVIDER_ROLE: u8 = 3;
+
+const NODE_REGISTERED: &str = "NodeRegistered(bytes32,address,uint8,string)";
+const NODE_DEACTIVATED: &str = "NodeDeactivated(bytes32)";
+const NODE_STAKE_UPDATED: &str = "NodeStakeUpdated(bytes32,uint256)";
+
+/// A contract log as the event forwarder delivers it
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainLog {
+    pub contract: String,
+    pub block_height: u64,
+    pub log_index: u32,
+    pub topics: Vec<String>, // 0x-prefixed hex
+    pub data: String,        // 0x-prefixed hex
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CertChange {
+    Certified(String), // Registry key of the node
+    Decertified(String),
+}
+
+#[derive(Debug, Clone)]
+struct CertState {
+    active: bool,
+    stake_ok: bool,
+    seen: (u64, u32), // Block height and log index of the last log applied
+}
+
+#[derive(Debug, Default)]
+pub struct NodeCerts {
+    enforced: bool,
+    min_stake_wei: u128,
+    nodes: HashMap<String, CertState>, // Registry key -> certification
+}
+
+/// The registry key of a node: its pubkey's first 32 bytes, zero-padded, as hex
+pub fn cert_key(pubkey: &str) -> String {
+    let mut bytes = pubkey.as_bytes().to_vec();
+    bytes.resize(32, 0);
+    hex::encode(bytes)
+}
+
+fn event_topic(signature: &str) -> String {
+    format!("0x{}", hex::encode(Keccak256::digest(signature.as_bytes())))
+}
+
+/// The `index`th 32-byte word of hex `data`
+fn word(data: &str, index: usize) -> Option<&str> {
+    data.trim_start_matches("0x").get(index * 64..(index + 1) * 64)
+}
+
+/// A uint256 word, saturating at u128::MAX
+fn word_u128(word: &str) -> Option<u128> {
+    let (high, low) = word.split_at(32);
+    if high.chars().any(|c| c != '0') {
+        return Some(u128::MAX);
+    }
+    u128::from_str_radix(low, 16).ok()
+}
+
+impl NodeCerts {
+    /// Candidacy gated on certification, with slashes below `min_stake_wei` decertifying
+    pub fn enforcing(min_stake_wei: u128) -> Self {
+        NodeCerts { enforced: true, min_stake_wei, nodes: HashMap::new() }
+    }
+
+    /// Every node is a candidate when certification isn't enforced
+    pub fn is_certified(&self, pubkey: &str) -> bool {
+        !self.enforced || self.nodes.get(&cert_key(pubkey)).is_some_and(|cert| cert.active && cert.stake_ok)
+    }
+
+    /// Certify the registry keys read from `getNodesByRole` at startup
+    pub fn bootstrap(&mut self, keys: impl IntoIterator<Item = String>) {
+        for key in keys {
+            self.nodes.entry(key).or_insert(CertState { active: true, stake_ok: true, seen: (0, 0) });
+        }
+    }
+
+    /// Apply a NodeCertRegistry log. Logs older than the last one applied to
+    /// the same node, e.g. a redelivery, change nothing.
+    pub fn apply(&mut self, log: &ChainLog) -> Option<CertChange> {
+        let topic = log.topics.first()?.to_lowercase();
+        let key = log.topics.get(1)?.trim_start_matches("0x").to_lowercase();
+        let seen = (log.block_height, log.log_index);
+        if self.nodes.get(&key).is_some_and(|cert| cert.seen >= seen) {
+            return None;
+        }
+        let was_certified = self.nodes.get(&key).is_some_and(|cert| cert.active && cert.stake_ok);
+
+        if topic == event_topic(NODE_REGISTERED) {
+            // Validators, storage providers and the like aren't placement candidates
+            let role = word(&log.data, 0).and_then(word_u128)?;
+            if role != GPU_PROVIDER_ROLE as u128 {
+                return None;
+            }
+            self.nodes.insert(key.clone(), CertState { active: true, stake_ok: true, seen });
+        } else if topic == event_topic(NODE_DEACTIVATED) {
+            let cert = self.nodes.get_mut(&key)?;
+            cert.active = false;
+            cert.seen = seen;
+        } else if topic == event_topic(NODE_STAKE_UPDATED) {
+            let stake = word(&log.data, 0).and_then(word_u128)?;
+            let cert = self.nodes.get_mut(&key)?;
+            cert.stake_ok = stake >= self.min_stake_wei;
+            cert.seen = seen;
+        } else {
+            return None;
+        }
+
+        let certified = self.nodes.get(&key).is_some_and(|cert| cert.active && cert.stake_ok);
+        match (was_certified, certified) {
+            (false, true) => Some(CertChange::Certified(key)),
+            (true, false) => Some(CertChange::Decertified(key)),
+            _ => None,
+        }
+    }
+}
+
+/// Registry keys out of an ABI-encoded `bytes32[]` return value
+pub fn decode_keys(result: &str) -> Result<Vec<String>, String> {
+    let data = result.trim_start_matches("0x");
+    let offset = word(data, 0).and_then(word_u128).ok_or("Truncated bytes32[]")? as usize / 32;
+    let len = word(data, offset).and_then(word_u128).ok_or("Truncated bytes32[]")? as usize;
+    (0..len)
+        .map(|i| word(data, offset + 1 + i).map(str::to_lowercase).ok_or_else(|| "Truncated bytes32[]".to_string()))
+        .collect()
+}
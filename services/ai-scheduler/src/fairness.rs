@@ -0,0 +1,217 @@
+//! Fair Placement
+//! `RandomAmongTopK` places a job on one of its K best-scored nodes, drawn
+//! with a verifiable random function instead of taken by score. The VRF
+//! input is the job id and the hash of the latest block, which the scheduler
+//! doesn't choose; only the holder of the VRF key can compute the output, so
+//! nobody can steer or predict the draw. The proof goes back with the
+//! placement, and anyone holding the scheduler's published public key can
+//! check that the output belongs to that input and that the pick follows
+//! from it. The key a draw names is only a claim: audits use the published
+//! one, and the scheduler won't start without both halves configured.
+//!
+//! The VRF has the shape of ECVRF (RFC 9381), on secp256k1 with SHA-256 and
+//! try-and-increment hashing to the curve. With secret x and public Y = x·G:
+//!
+//! - H = hash_to_curve(Y, alpha) and Γ = x·H
+//! - k = SHA-256(x, H) and c = the first 16 bytes of SHA-256(Y, H, Γ, k·G, k·H)
+//! - s = k + c·x; the proof is (Γ, c, s) and the output SHA-256(Γ)
+//!
+//! A verifier recomputes U = s·G − c·Y and V = s·H − c·Γ and checks that
+//! they hash back to c.
+
+use k256::elliptic_curve::ff::PrimeField;
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use k256::{AffinePoint, EncodedPoint, FieldBytes, NonZeroScalar, ProjectivePoint, Scalar, U256};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const SUITE: &[u8] = b"ARTHA-VRF-SECP256K1-SHA256-TAI";
+const PROOF_LEN: usize = 33 + 16 + 32; // Γ, c, s
+
+/// How a job picks among the nodes it was ranked on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum PlacementMode {
+    #[default]
+    TopScore,
+    RandomAmongTopK { k: usize },
+    Auction, // The capable nodes bid, see auction
+}
+
+/// A fair draw as returned with the placement, enough to audit it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FairDraw {
+    pub block_hash: String,
+    pub candidates: Vec<String>, // The top K, best first
+    pub chosen: usize,           // Index into candidates
+    pub vrf_public_key: String,
+    pub proof: String,
+    pub output: String,
+}
+
+pub struct VrfKey {
+    secret: NonZeroScalar,
+    public: ProjectivePoint,
+}
+
+impl VrfKey {
+    pub fn from_bytes(secret: &[u8]) -> Option<Self> {
+        let secret = NonZeroScalar::try_from(secret).ok()?;
+        Some(VrfKey { public: ProjectivePoint::GENERATOR * *secret, secret })
+    }
+
+    /// The key in `ARTHA_SCHED_VRF_KEY` (32 bytes, hex). A fresh key per
+    /// start would leave earlier draws unverifiable, so there is no fallback.
+    pub fn from_env() -> Result<Self, String> {
+        let hex_key = std::env::var("ARTHA_SCHED_VRF_KEY").map_err(|_| "ARTHA_SCHED_VRF_KEY is not set".to_string())?;
+        hex::decode(hex_key.trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| Self::from_bytes(&bytes))
+            .ok_or_else(|| "ARTHA_SCHED_VRF_KEY is not a 32-byte secp256k1 secret key in hex".to_string())
+    }
+
+    /// SEC1-compressed, hex
+    pub fn public_key(&self) -> String {
+        hex::encode(compress(&self.public))
+    }
+
+    /// The proof and output for `alpha`
+    pub fn prove(&self, alpha: &[u8]) -> (Vec<u8>, [u8; 32]) {
+        let y = compress(&self.public);
+        let h = hash_to_curve(&y, alpha);
+        let gamma = h * *self.secret;
+        let k = scalar_from_hash(Sha256::new().chain_update(SUITE).chain_update([0x04]).chain_update(self.secret.to_bytes()).chain_update(compress(&h)));
+        let c = challenge(&[&self.public, &h, &gamma, &(ProjectivePoint::GENERATOR * k), &(h * k)]);
+        let s = k + c.1 * *self.secret;
+
+        let mut proof = compress(&gamma).to_vec();
+        proof.extend_from_slice(&c.0);
+        proof.extend_from_slice(&s.to_bytes());
+        (proof, output(&gamma))
+    }
+}
+
+/// The output for `alpha`, if `proof` was made for it by the holder of `public_key`
+pub fn verify(public_key: &[u8], alpha: &[u8], proof: &[u8]) -> Option<[u8; 32]> {
+    if proof.len() != PROOF_LEN {
+        return None;
+    }
+    let y = decompress(public_key)?;
+    let gamma = decompress(&proof[..33])?;
+    let mut c_bytes = FieldBytes::default();
+    c_bytes[16..].copy_from_slice(&proof[33..49]);
+    let c = Scalar::from_repr(c_bytes).into_option()?;
+    let mut s_bytes = FieldBytes::default();
+    s_bytes.copy_from_slice(&proof[49..]);
+    let s = Scalar::from_repr(s_bytes).into_option()?;
+
+    let h = hash_to_curve(&compress(&y), alpha);
+    let u = ProjectivePoint::GENERATOR * s - y * c;
+    let v = h * s - gamma * c;
+    (challenge(&[&y, &h, &gamma, &u, &v]).0 == proof[33..49]).then(|| output(&gamma))
+}
+
+/// The VRF input for a job's draw
+pub fn vrf_input(job_id: &str, block_hash: &str) -> Vec<u8> {
+    let mut alpha = (job_id.len() as u32).to_be_bytes().to_vec();
+    alpha.extend_from_slice(job_id.as_bytes());
+    alpha.extend_from_slice(block_hash.as_bytes());
+    alpha
+}
+
+/// Which of `k` candidates a VRF output picks
+pub fn pick(output: &[u8; 32], k: usize) -> usize {
+    let value = u128::from_be_bytes(output[..16].try_into().unwrap());
+    (value % k as u128) as usize
+}
+
+/// Draw one of `candidates` for the job at `block_hash`
+pub fn draw(key: &VrfKey, job_id: &str, block_hash: &str, candidates: Vec<String>) -> FairDraw {
+    let (proof, output) = key.prove(&vrf_input(job_id, block_hash));
+    FairDraw {
+        block_hash: block_hash.to_string(),
+        chosen: pick(&output, candidates.len()),
+        candidates,
+        vrf_public_key: key.public_key(),
+        proof: hex::encode(proof),
+        output: hex::encode(output),
+    }
+}
+
+/// The scheduler's published VRF key, `ARTHA_SCHED_VRF_PUBLIC_KEY`, which
+/// must be the public half of `key`
+pub fn published_key_from_env(key: &VrfKey) -> Result<String, String> {
+    let published = std::env::var("ARTHA_SCHED_VRF_PUBLIC_KEY")
+        .map_err(|_| "ARTHA_SCHED_VRF_PUBLIC_KEY is not set".to_string())?
+        .trim_start_matches("0x")
+        .to_lowercase();
+    if published != key.public_key() {
+        return Err(format!("ARTHA_SCHED_VRF_PUBLIC_KEY is not the public key of ARTHA_SCHED_VRF_KEY ({})", key.public_key()));
+    }
+    Ok(published)
+}
+
+/// Check a draw as an auditor would: the proof holds for the job and block
+/// under the published key, and the pick follows from the output. That the
+/// block and the candidates are the real ones is checked elsewhere, against
+/// the chain and the scheduler's ranking.
+pub fn audit(published_key: &str, job_id: &str, draw: &FairDraw) -> bool {
+    if draw.vrf_public_key != published_key {
+        return false;
+    }
+    let (Ok(public_key), Ok(proof)) = (hex::decode(published_key), hex::decode(&draw.proof)) else {
+        return false;
+    };
+    let Some(output) = verify(&public_key, &vrf_input(job_id, &draw.block_hash), &proof) else {
+        return false;
+    };
+    !draw.candidates.is_empty() && hex::encode(output) == draw.output && pick(&output, draw.candidates.len()) == draw.chosen
+}
+
+fn compress(point: &ProjectivePoint) -> [u8; 33] {
+    AffinePoint::from(*point).to_encoded_point(true).as_bytes().try_into().unwrap()
+}
+
+fn decompress(bytes: &[u8]) -> Option<ProjectivePoint> {
+    let encoded = EncodedPoint::from_bytes(bytes).ok()?;
+    Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded)).map(ProjectivePoint::from)
+}
+
+/// Try-and-increment: the first counter whose hash is an x-coordinate on the curve
+fn hash_to_curve(public_key: &[u8; 33], alpha: &[u8]) -> ProjectivePoint {
+    (0u32..)
+        .find_map(|counter| {
+            let digest = Sha256::new()
+                .chain_update(SUITE)
+                .chain_update([0x01])
+                .chain_update(public_key)
+                .chain_update(alpha)
+                .chain_update(counter.to_be_bytes())
+                .finalize();
+            let mut candidate = [0x02; 33];
+            candidate[1..].copy_from_slice(&digest);
+            decompress(&candidate)
+        })
+        .expect("a curve point within 2^32 tries")
+}
+
+/// c as its 16 transmitted bytes and as a scalar
+fn challenge(points: &[&ProjectivePoint]) -> ([u8; 16], Scalar) {
+    let mut hasher = Sha256::new().chain_update(SUITE).chain_update([0x02]);
+    for point in points {
+        hasher.update(compress(point));
+    }
+    let digest = hasher.finalize();
+    let mut bytes = FieldBytes::default();
+    bytes[16..].copy_from_slice(&digest[..16]);
+    (digest[..16].try_into().unwrap(), <Scalar as Reduce<U256>>::reduce_bytes(&bytes))
+}
+
+fn scalar_from_hash(hasher: Sha256) -> Scalar {
+    <Scalar as Reduce<U256>>::reduce_bytes(&hasher.finalize())
+}
+
+fn output(gamma: &ProjectivePoint) -> [u8; 32] {
+    Sha256::new().chain_update(SUITE).chain_update([0x03]).chain_update(compress(gamma)).finalize().into()
+}
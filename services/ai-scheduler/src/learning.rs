@@ -0,0 +1,333 @@
+//! Placement Learning
+//! Rolling store of completed placements and the predictors trained on it:
+//! runtime (with a prediction interval), queue wait and failure probability
+//! per (node, job class). Sparse pairs shrink toward the class average across
+//! all nodes, and sparse classes toward the global average, so a cold pair
+//! still gets a sensible prediction.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tracing::warn;
+
+/// z for a two-sided 90% interval
+const Z_90: f64 = 1.645;
+
+/// Requirements fingerprint jobs are grouped by
+pub fn job_class(job_type: &str, min_gpu_vram_gb: u32, tee_required: bool) -> String {
+    let vram = match min_gpu_vram_gb {
+        0..=16 => "vram16",
+        17..=24 => "vram24",
+        25..=48 => "vram48",
+        49..=80 => "vram80",
+        _ => "vram80+",
+    };
+    let isolation = if tee_required { "tee" } else { "std" };
+    format!("{}:{}:{}", job_type.to_lowercase(), vram, isolation)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlacementStatus {
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacementOutcome {
+    pub job_id: String,
+    pub job_class: String,
+    pub node_pubkey: String,
+    pub queue_wait_secs: u64,
+    pub duration_secs: u64,
+    pub peak_vram_mb: Option<u64>, // From ai-runtime GPU telemetry
+    pub status: PlacementStatus,
+    pub failure_reason: Option<String>,
+    pub recorded_at: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LearningConfig {
+    pub window: usize,       // Outcomes kept; the oldest fall out of the models
+    pub prior_samples: f64,  // Pseudo-samples of the fallback level in each blend
+    pub path: Option<String>,
+}
+
+impl LearningConfig {
+    pub fn from_env() -> Self {
+        LearningConfig {
+            window: std::env::var("ARTHA_SCHED_OUTCOME_WINDOW")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5000),
+            prior_samples: std::env::var("ARTHA_SCHED_PRIOR_SAMPLES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5.0),
+            path: Some(
+                std::env::var("ARTHA_SCHED_OUTCOMES_PATH")
+                    .unwrap_or_else(|_| "/tmp/artha/scheduler/placements.json".to_string()),
+            ),
+        }
+    }
+}
+
+/// Running sums of one group of outcomes. Outcomes are added on record and
+/// subtracted on eviction, so the models always cover exactly the window.
+#[derive(Debug, Clone, Copy, Default)]
+struct Moments {
+    runs: f64,
+    failures: f64,
+    completed: f64,
+    duration_sum: f64,
+    duration_sq_sum: f64,
+    wait_sum: f64,
+}
+
+impl Moments {
+    fn apply(&mut self, outcome: &PlacementOutcome, sign: f64) {
+        self.runs += sign;
+        self.wait_sum += sign * outcome.queue_wait_secs as f64;
+        match outcome.status {
+            PlacementStatus::Failed => self.failures += sign,
+            PlacementStatus::Completed => {
+                // Time-to-failure says nothing about how long a job runs
+                let d = outcome.duration_secs as f64;
+                self.completed += sign;
+                self.duration_sum += sign * d;
+                self.duration_sq_sum += sign * d * d;
+            }
+        }
+    }
+}
+
+/// Blend a group's sums with `k` pseudo-samples at the fallback value
+fn shrink(sum: f64, n: f64, fallback: Option<f64>, k: f64) -> Option<f64> {
+    match fallback {
+        Some(fallback) => Some((sum + k * fallback) / (n + k)),
+        None if n > 0.0 => Some(sum / n),
+        None => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PredictionBasis {
+    NodeClass, // Enough history for this node and job class
+    Class,     // This class across all nodes
+    Global,    // Everything the scheduler has seen
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DurationPrediction {
+    pub expected_secs: f64,
+    pub lower_secs: f64, // 90% interval
+    pub upper_secs: f64,
+    pub samples: u32, // Completed runs of this (node, class)
+    pub basis: PredictionBasis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Prediction {
+    pub duration: Option<DurationPrediction>,
+    pub failure_probability: f64,
+    pub queue_wait_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairStats {
+    pub node_pubkey: String,
+    pub job_class: String,
+    pub runs: u32,
+    pub failures: u32,
+    pub mean_duration_secs: Option<f64>,
+    pub mean_queue_wait_secs: f64,
+    pub max_peak_vram_mb: Option<u64>,
+    pub prediction: Prediction,
+}
+
+pub struct PlacementLearner {
+    config: LearningConfig,
+    outcomes: VecDeque<PlacementOutcome>,
+    global: Moments,
+    by_class: HashMap<String, Moments>,
+    by_pair: HashMap<(String, String), Moments>, // (node, class)
+}
+
+impl PlacementLearner {
+    pub fn new(config: LearningConfig) -> Self {
+        PlacementLearner {
+            config,
+            outcomes: VecDeque::new(),
+            global: Moments::default(),
+            by_class: HashMap::new(),
+            by_pair: HashMap::new(),
+        }
+    }
+
+    /// Learner seeded from the persisted window, if any
+    pub fn load(config: LearningConfig) -> Self {
+        let saved: Vec<PlacementOutcome> = config
+            .path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        let mut learner = Self::new(config);
+        for outcome in saved {
+            learner.insert(outcome);
+        }
+        learner
+    }
+
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// Add an outcome, evicting the oldest past the window, and persist
+    pub fn record(&mut self, outcome: PlacementOutcome) {
+        self.insert(outcome);
+        if let Err(e) = self.save() {
+            warn!("⚠️  Failed to persist placement outcomes: {}", e);
+        }
+    }
+
+    fn insert(&mut self, outcome: PlacementOutcome) {
+        self.apply(&outcome, 1.0);
+        self.outcomes.push_back(outcome);
+        while self.outcomes.len() > self.config.window.max(1) {
+            if let Some(evicted) = self.outcomes.pop_front() {
+                self.apply(&evicted, -1.0);
+            }
+        }
+    }
+
+    fn apply(&mut self, outcome: &PlacementOutcome, sign: f64) {
+        self.global.apply(outcome, sign);
+        self.by_class
+            .entry(outcome.job_class.clone())
+            .or_default()
+            .apply(outcome, sign);
+        self.by_pair
+            .entry((outcome.node_pubkey.clone(), outcome.job_class.clone()))
+            .or_default()
+            .apply(outcome, sign);
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.config.path else { return Ok(()) };
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let bytes = serde_json::to_vec(&self.outcomes).map_err(|e| e.to_string())?;
+        let tmp = format!("{}.tmp", path);
+        std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+    }
+
+    /// Average number of jobs the node ran at once over the `lookback_secs`
+    /// before `now`, from the outcomes still in the window. Outcomes are
+    /// recorded as jobs finish, so each ran for its duration up to `recorded_at`.
+    pub fn typical_concurrency(&self, node_pubkey: &str, now: u64, lookback_secs: u64) -> f64 {
+        if lookback_secs == 0 {
+            return 0.0;
+        }
+        let since = now.saturating_sub(lookback_secs);
+        let busy_secs: u64 = self
+            .outcomes
+            .iter()
+            .filter(|o| o.node_pubkey == node_pubkey)
+            .map(|o| {
+                let started = o.recorded_at.saturating_sub(o.duration_secs).max(since);
+                o.recorded_at.min(now).saturating_sub(started)
+            })
+            .sum();
+        busy_secs as f64 / lookback_secs as f64
+    }
+
+    pub fn predict(&self, node_pubkey: &str, job_class: &str) -> Prediction {
+        let k = self.config.prior_samples;
+        let empty = Moments::default();
+        let class = self.by_class.get(job_class).unwrap_or(&empty);
+        let pair = self
+            .by_pair
+            .get(&(node_pubkey.to_string(), job_class.to_string()))
+            .unwrap_or(&empty);
+
+        // Failure rate: pair -> class -> global, with a global prior of 0
+        let global_rate = shrink(self.global.failures, self.global.runs, None, k).unwrap_or(0.0);
+        let class_rate = shrink(class.failures, class.runs, Some(global_rate), k).unwrap_or(0.0);
+        let failure_probability = shrink(pair.failures, pair.runs, Some(class_rate), k).unwrap_or(0.0);
+
+        let wait = |m: &Moments, fallback| shrink(m.wait_sum, m.runs, fallback, k);
+        let queue_wait_secs = wait(pair, wait(class, wait(&self.global, None)));
+
+        let duration = self.predict_duration(class, pair, k);
+
+        Prediction {
+            duration,
+            failure_probability,
+            queue_wait_secs,
+        }
+    }
+
+    fn predict_duration(&self, class: &Moments, pair: &Moments, k: f64) -> Option<DurationPrediction> {
+        let global = &self.global;
+        let mean = |m: &Moments, fallback| shrink(m.duration_sum, m.completed, fallback, k);
+        let second = |m: &Moments, fallback| shrink(m.duration_sq_sum, m.completed, fallback, k);
+
+        let expected = mean(pair, mean(class, mean(global, None)))?;
+        let second_moment = second(pair, second(class, second(global, None)))?;
+        let spread = Z_90 * (second_moment - expected * expected).max(0.0).sqrt();
+
+        let basis = if pair.completed >= k {
+            PredictionBasis::NodeClass
+        } else if class.completed >= k {
+            PredictionBasis::Class
+        } else {
+            PredictionBasis::Global
+        };
+
+        Some(DurationPrediction {
+            expected_secs: expected,
+            lower_secs: (expected - spread).max(0.0),
+            upper_secs: expected + spread,
+            samples: pair.completed.round() as u32,
+            basis,
+        })
+    }
+
+    /// What has been learned for every (node, class) pair in the window
+    pub fn stats(&self) -> Vec<PairStats> {
+        let mut groups: BTreeMap<(&str, &str), Vec<&PlacementOutcome>> = BTreeMap::new();
+        for outcome in &self.outcomes {
+            groups
+                .entry((outcome.node_pubkey.as_str(), outcome.job_class.as_str()))
+                .or_default()
+                .push(outcome);
+        }
+
+        groups
+            .into_iter()
+            .map(|((node, class), outcomes)| {
+                let completed: Vec<f64> = outcomes
+                    .iter()
+                    .filter(|o| o.status == PlacementStatus::Completed)
+                    .map(|o| o.duration_secs as f64)
+                    .collect();
+                PairStats {
+                    node_pubkey: node.to_string(),
+                    job_class: class.to_string(),
+                    runs: outcomes.len() as u32,
+                    failures: outcomes.iter().filter(|o| o.status == PlacementStatus::Failed).count() as u32,
+                    mean_duration_secs: (!completed.is_empty())
+                        .then(|| completed.iter().sum::<f64>() / completed.len() as f64),
+                    mean_queue_wait_secs: outcomes.iter().map(|o| o.queue_wait_secs as f64).sum::<f64>()
+                        / outcomes.len() as f64,
+                    max_peak_vram_mb: outcomes.iter().filter_map(|o| o.peak_vram_mb).max(),
+                    prediction: self.predict(node, class),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,167 @@
+//! Node Liveness
+//! Registered nodes prove they are alive with signed heartbeats. A node
+//! silent for longer than the unhealthy threshold gets no new placements
+//! until it heartbeats again; one silent past the deregistration timeout is
+//! removed and has to register again.
+//!
+//! A node's pubkey is its secp256k1 public key (SEC1, hex). Heartbeats carry
+//! `X-Artha-Timestamp` and an ECDSA `X-Artha-Signature` over
+//! `HEARTBEAT:{pubkey}:{sha256(body) hex}:TS:{timestamp}`. The timestamp must
+//! be within the allowed clock skew and newer than the last accepted one, so
+//! a captured heartbeat cannot be replayed to keep a dead node alive.
+
+use crate::admission::env_or;
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct LivenessConfig {
+    pub unhealthy_after_secs: u64,   // Silence before a node is skipped by placement
+    pub deregister_after_secs: u64,  // Silence before a node is removed
+    pub max_skew_secs: u64,          // Allowed distance of a heartbeat timestamp from now
+    pub sweep_interval_secs: u64,
+}
+
+impl LivenessConfig {
+    pub fn from_env() -> Self {
+        LivenessConfig {
+            unhealthy_after_secs: env_or("ARTHA_SCHED_UNHEALTHY_AFTER_SECS", 60),
+            deregister_after_secs: env_or("ARTHA_SCHED_DEREGISTER_AFTER_SECS", 600),
+            max_skew_secs: env_or("ARTHA_SCHED_HEARTBEAT_SKEW_SECS", 30),
+            sweep_interval_secs: env_or("ARTHA_SCHED_LIVENESS_SWEEP_SECS", 15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeHealth {
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeartbeatError {
+    Unsigned,
+    Skewed { timestamp: u64, now: u64 },
+    Replayed { timestamp: u64, last: u64 },
+    BadSignature,
+}
+
+impl std::fmt::Display for HeartbeatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeartbeatError::Unsigned => write!(f, "heartbeat is not signed"),
+            HeartbeatError::Skewed { timestamp, now } => write!(f, "timestamp {} too far from {}", timestamp, now),
+            HeartbeatError::Replayed { timestamp, last } => write!(f, "timestamp {} not after last heartbeat {}", timestamp, last),
+            HeartbeatError::BadSignature => write!(f, "signature does not match the node key"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Liveness {
+    last_seen: u64,       // Registration or last accepted heartbeat, scheduler clock
+    last_signed_at: u64,  // Timestamp of the last accepted heartbeat, node clock
+    reported_unhealthy: bool,
+}
+
+/// What a sweep changed
+#[derive(Debug, Default, PartialEq)]
+pub struct Sweep {
+    pub unhealthy: Vec<String>,    // Went silent since the last sweep
+    pub deregistered: Vec<String>, // Silent past the timeout; no longer tracked
+}
+
+pub struct LivenessTracker {
+    config: LivenessConfig,
+    nodes: HashMap<String, Liveness>,
+}
+
+pub fn heartbeat_message(pubkey: &str, body: &[u8], timestamp: u64) -> String {
+    format!("HEARTBEAT:{}:{}:TS:{}", pubkey, hex::encode(Sha256::digest(body)), timestamp)
+}
+
+pub(crate) fn signature_matches(pubkey: &str, message: &str, signature_hex: &str) -> bool {
+    let Ok(key) = hex::decode(pubkey.trim_start_matches("0x")) else { return false };
+    let Ok(key) = VerifyingKey::from_sec1_bytes(&key) else { return false };
+    let Ok(signature) = hex::decode(signature_hex.trim_start_matches("0x")) else { return false };
+    let Ok(signature) = Signature::from_slice(&signature) else { return false };
+    key.verify(message.as_bytes(), &signature).is_ok()
+}
+
+impl LivenessTracker {
+    pub fn new(config: LivenessConfig) -> Self {
+        LivenessTracker { config, nodes: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &LivenessConfig {
+        &self.config
+    }
+
+    /// Start (or restart) the clock for a registered node. Replay protection
+    /// survives re-registration.
+    pub fn track(&mut self, pubkey: &str, now: u64) {
+        let entry = self.nodes.entry(pubkey.to_string()).or_default();
+        entry.last_seen = now;
+        entry.reported_unhealthy = false;
+    }
+
+    /// Check a heartbeat's signature and freshness and, if it holds, count the node as seen
+    pub fn accept(&mut self, pubkey: &str, body: &[u8], timestamp: Option<u64>, signature: Option<&str>, now: u64) -> Result<(), HeartbeatError> {
+        let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+            return Err(HeartbeatError::Unsigned);
+        };
+        if timestamp.abs_diff(now) > self.config.max_skew_secs {
+            return Err(HeartbeatError::Skewed { timestamp, now });
+        }
+        let last = self.nodes.get(pubkey).map_or(0, |l| l.last_signed_at);
+        if timestamp <= last {
+            return Err(HeartbeatError::Replayed { timestamp, last });
+        }
+        if !signature_matches(pubkey, &heartbeat_message(pubkey, body, timestamp), signature) {
+            return Err(HeartbeatError::BadSignature);
+        }
+        let entry = self.nodes.entry(pubkey.to_string()).or_default();
+        entry.last_seen = now;
+        entry.last_signed_at = timestamp;
+        entry.reported_unhealthy = false;
+        Ok(())
+    }
+
+    pub fn last_seen(&self, pubkey: &str) -> Option<u64> {
+        self.nodes.get(pubkey).map(|l| l.last_seen)
+    }
+
+    /// Untracked nodes count as healthy until the next sweep starts their clock
+    pub fn health(&self, pubkey: &str, now: u64) -> NodeHealth {
+        match self.nodes.get(pubkey) {
+            Some(l) if now.saturating_sub(l.last_seen) > self.config.unhealthy_after_secs => NodeHealth::Unhealthy,
+            _ => NodeHealth::Healthy,
+        }
+    }
+
+    /// Age every registered node: report newly unhealthy ones and stop tracking
+    /// those past the deregistration timeout, which the caller removes
+    pub fn sweep<'a>(&mut self, registered: impl IntoIterator<Item = &'a String>, now: u64) -> Sweep {
+        let mut sweep = Sweep::default();
+        for pubkey in registered {
+            let entry = self.nodes.entry(pubkey.clone()).or_insert_with(|| Liveness { last_seen: now, ..Default::default() });
+            let silent = now.saturating_sub(entry.last_seen);
+            if silent > self.config.deregister_after_secs {
+                sweep.deregistered.push(pubkey.clone());
+            } else if silent > self.config.unhealthy_after_secs && !entry.reported_unhealthy {
+                entry.reported_unhealthy = true;
+                sweep.unhealthy.push(pubkey.clone());
+            }
+        }
+        for pubkey in &sweep.deregistered {
+            self.nodes.remove(pubkey);
+        }
+        sweep.unhealthy.sort();
+        sweep.deregistered.sort();
+        sweep
+    }
+}
@@ -2,15 +2,44 @@
 /// Scores nodes by co-location, GPU capability, SLA, reputation, cost
 
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::post,
     Router,
 };
+use artha_clock::SharedClock;
+use artha_paging::{Page, PageQuery};
+use artha_cache::ReadThrough;
+use artha_errors::{Deadline, ErrorCode, ServiceError};
+use artha_rpc::{Contract, ContractRegistry, RpcEndpoints, RpcError};
+use abi::{abi_encode_bytes32, abi_encode_call, abi_encode_uint256, Token};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+mod admission;
+mod auction;
+mod certs;
+mod fairness;
+mod learning;
+mod liveness;
+mod notify;
+mod pricing;
+mod reservations;
+use admission::{AdmissionConfig, PendingQueue};
+use auction::{Ask, AuctionBook, AuctionConfig, AuctionResult, Bid, BidError};
+use certs::{CertChange, ChainLog, NodeCerts};
+use fairness::{FairDraw, PlacementMode, VrfKey};
+use learning::{DurationPrediction, LearningConfig, PlacementLearner, PlacementOutcome, PlacementStatus};
+use liveness::{LivenessConfig, LivenessTracker, NodeHealth};
+use notify::{Notification, NotifyConfig, Outbox};
+use pricing::PriceFeed;
+use reservations::{BookingError, NodeCapacity, Reservation, ReservationBook, ReservationConfig, ReservationRequest};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
@@ -24,6 +53,8 @@ pub struct Node {
     pub current_load: f64, // 0.0 to 1.0
     pub capabilities: Vec<String>,
     pub sla_tier: String, // "premium", "standard", "economy"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintenance_windows: Vec<MaintenanceWindow>, // Announced at registration, refreshed by heartbeats
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +62,8 @@ pub struct GpuInfo {
     pub gpu_type: String, // "A100", "H100", "V100", "RTX4090"
     pub vram_gb: u32,
     pub available: bool,
+    #[serde(default)]
+    pub precisions: Vec<String>, // Natively supported: "fp32", "fp16", "bf16", "int8"; empty if not reported
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +75,12 @@ pub struct Job {
     pub requirements: JobRequirements,
     pub budget: u64,
     pub submitter_did: String,
+    #[serde(skip)]
+    pub reservation_id: Option<String>, // Placed only on this reservation's GPUs; from the schedule request
+    #[serde(skip)]
+    pub placement: PlacementMode, // How the node is picked from the ranking; from the schedule request
+    #[serde(skip)]
+    pub estimated_duration_secs: Option<u64>, // From the schedule request
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,11 +91,25 @@ pub struct JobRequirements {
     pub max_price_per_sec: f64,
     pub preferred_regions: Vec<String>,
     pub required_capabilities: Vec<String>,
+    #[serde(default)]
+    pub tee_required: bool,
+    #[serde(default)]
+    pub required_precisions: Vec<String>, // Every one must be supported by the GPU the job lands on
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ScheduleRequest {
     pub job_id: String,
+    #[serde(default)]
+    pub tee_required: bool,
+    #[serde(default)]
+    pub exclude_nodes: Vec<String>, // Never place on these, e.g. the node a migrating job is leaving
+    #[serde(default)]
+    pub reservation_id: Option<String>, // Draw from this reservation's capacity, bypassing the pending queue
+    #[serde(default)]
+    pub placement: PlacementMode, // Top score, a VRF draw among the top K, or an auction
+    #[serde(default)]
+    pub estimated_duration_secs: Option<u64>, // Submitter's estimate; otherwise the learned runtime on each node
 }
 
 #[derive(Debug, Serialize)]
@@ -65,6 +118,168 @@ pub struct ScheduleResponse {
     pub assigned_node: String,
     pub score: f64,
     pub estimated_start_time: u64,
+    pub predicted_duration: Option<DurationPrediction>,
+    pub failure_probability: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fair_draw: Option<FairDraw>, // For RandomAmongTopK placements
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auction: Option<AuctionResult>, // For Auction placements
+}
+
+/// Accepted but not placed: every capable node costs more than the job allows
+#[derive(Debug, Serialize)]
+pub struct WaitingResponse {
+    pub job_id: String,
+    pub status: String, // "waiting"
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub enum ScheduleOutcome {
+    Placed(Box<ScheduleResponse>),
+    Waiting(WaitingResponse), // Keeps its pending slot; placed once a price drops
+}
+
+impl IntoResponse for ScheduleOutcome {
+    fn into_response(self) -> Response {
+        match self {
+            ScheduleOutcome::Placed(placed) => Json(*placed).into_response(),
+            ScheduleOutcome::Waiting(waiting) => (StatusCode::ACCEPTED, Json(waiting)).into_response(),
+        }
+    }
+}
+
+/// One candidate as the scheduler would rank it, without assigning anything
+#[derive(Debug, Serialize)]
+pub struct SimulatedPlacement {
+    pub node_pubkey: String,
+    pub score: f64,
+    pub estimated_start_time: u64,
+    pub predicted_duration: Option<DurationPrediction>,
+    pub failure_probability: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulateResponse {
+    pub job_id: String,
+    pub job_class: String,
+    pub candidates: Vec<SimulatedPlacement>, // Best first
+}
+
+/// Where and when a job was placed, kept until its outcome is reported
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub node_pubkey: String,
+    pub job_class: String,
+    pub scheduled_at: u64,
+    pub mode: PlacementMode, // Kept for re-placement if the node rejects the job
+    pub estimated_duration_secs: Option<u64>, // Likewise
+}
+
+/// Why a node takes no new placements. Jobs already on it keep running.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CordonMode {
+    Drain,       // Until an operator lifts it
+    Maintenance, // Until `until`, if set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cordon {
+    pub mode: CordonMode,
+    pub reason: Option<String>,
+    pub until: Option<u64>,
+    pub since: u64,
+}
+
+impl Cordon {
+    fn active(&self, now: u64) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+}
+
+/// Downtime a node has announced ahead of time, e.g. a planned reboot.
+/// Unlike a cordon it doesn't stop placements outright, only those of jobs
+/// expected to still be running when it starts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaintenanceWindow {
+    pub start: u64,
+    pub end: u64,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// Whether a run from `start` until `end` would overlap the window
+    fn interrupts(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+}
+
+/// Windows that haven't ended yet, soonest first
+fn upcoming_windows(mut windows: Vec<MaintenanceWindow>, now: u64) -> Vec<MaintenanceWindow> {
+    windows.retain(|window| window.start < window.end && now < window.end);
+    windows.sort_by_key(|window| window.start);
+    windows
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReclaimRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeView {
+    #[serde(flatten)]
+    pub node: Node,
+    pub cordon: Option<Cordon>,
+    pub running_jobs: usize,
+    pub health: NodeHealth,
+    pub last_heartbeat: Option<u64>, // Or registration, whichever was later
+    pub certified: bool, // Always true unless NodeCertRegistry sync is on
+}
+
+/// Final outcome of a placement, reported by ai-jobd when the job finishes
+#[derive(Debug, Deserialize)]
+pub struct OutcomeReport {
+    pub status: PlacementStatus,
+    pub started_at: Option<u64>,
+    pub completed_at: Option<u64>,
+    pub peak_vram_mb: Option<u64>,
+    pub failure_reason: Option<String>,
+}
+
+/// A node's runtime cannot satisfy the job's declared requirements; reported by
+/// ai-jobd after the capability handshake so the job is placed elsewhere
+#[derive(Debug, Deserialize)]
+pub struct RejectReport {
+    pub node_pubkey: String,
+    #[serde(default)]
+    pub reasons: Vec<String>,
+    #[serde(default)]
+    pub tee_required: bool,
+}
+
+/// Capacity heartbeat from a node's ai-runtime, signed with the node's key.
+/// GPUs held by warm container pools are reserved, not free.
+#[derive(Debug, Deserialize)]
+pub struct NodeHeartbeat {
+    pub gpus_total: usize,
+    pub gpus_running: usize,
+    #[serde(default)]
+    pub gpus_pool_reserved: usize,
+    #[serde(default)]
+    pub maintenance_windows: Option<Vec<MaintenanceWindow>>, // Replaces the announced windows when sent
 }
 
 #[derive(Debug)]
@@ -76,134 +291,120 @@ pub struct NodeScore {
     pub sla_score: f64,
     pub cost_score: f64,
     pub load_score: f64,
+    pub failure_probability: f64,
+    pub predicted_duration: Option<DurationPrediction>,
+    pub predicted_queue_wait: Option<f64>,
+    pub bid: Option<Bid>, // The node's bid, if it won the job at auction
 }
 
 // Application state
 pub struct AppState {
     nodes: Arc<RwLock<HashMap<String, Node>>>,
     job_assignments: Arc<RwLock<HashMap<String, String>>>, // job_id -> node_pubkey
+    pending: Arc<RwLock<PendingQueue>>, // Admitted jobs not yet released by ai-jobd
+    admission: AdmissionConfig,
     contract_client: Arc<ContractClient>,
     svdb_client: Arc<SvdbClient>,
+    placements: Arc<RwLock<HashMap<String, Placement>>>, // job_id -> placement awaiting outcome
+    learner: Arc<RwLock<PlacementLearner>>,
+    rejections: Arc<RwLock<HashMap<String, Vec<String>>>>, // job_id -> nodes excluded by a runtime rejection or migration
+    cordons: Arc<RwLock<HashMap<String, Cordon>>>, // node_pubkey -> drain or maintenance
+    liveness: Arc<RwLock<LivenessTracker>>,
+    jobd_url: String,
+    waiting: Arc<RwLock<HashMap<String, ScheduleRequest>>>, // Priced-out jobs holding a pending slot
+    price_feed: Arc<PriceFeed>,
+    reservations: Arc<RwLock<ReservationBook>>,
+    reservation_config: ReservationConfig,
+    vrf_key: Arc<VrfKey>, // Signs fair draws
+    vrf_published_key: String, // What draws are audited against, never the key a draw names
+    draws: Arc<RwLock<HashMap<String, FairDraw>>>, // job_id -> the draw as made, from the scheduler's own ranking
+    notify: NotifyConfig,
+    outbox: Arc<RwLock<Outbox>>, // Assignment notifications ai-jobd hasn't taken yet
+    auction: AuctionConfig,
+    auctions: Arc<RwLock<AuctionBook>>, // Asks taking bids
+    certs: Arc<RwLock<NodeCerts>>, // On-chain certification, synced from NodeCertRegistry logs
+    clock: SharedClock,
 }
 
+/// The contracts the scheduler calls, checked on chain at startup
+const CONTRACTS: &[Contract] = &[Contract { name: "AIJobManager", env: "AI_JOB_MANAGER_ADDR" }];
+
+/// Checked too when set; candidates are then limited to the nodes it certifies
+const NODE_CERT_REGISTRY: Contract = Contract { name: "NodeCertRegistry", env: "NODE_CERT_REGISTRY_ADDR" };
+
 pub struct ContractClient {
-    rpc_url: String,
+    rpc: RpcEndpoints,
     ai_job_manager: String,
-    client: reqwest::Client,
+    node_cert_registry: Option<String>,
+    jobs: ReadThrough<Job>, // Decoded getJob results, dropped when we write the job
 }
 
 impl ContractClient {
-    pub fn new(rpc_url: String) -> Self {
+    /// A client on a placeholder address; the service sets the real one
+    /// with `with_contracts` once it is resolved
+    pub fn new(rpc: impl Into<RpcEndpoints>) -> Self {
         ContractClient {
-            rpc_url: rpc_url.clone(),
-            ai_job_manager: std::env::var("AI_JOB_MANAGER_ADDR")
-                .unwrap_or_else(|_| "0x0000000000000000000000000000000000000001".to_string()),
-            client: reqwest::Client::new(),
+            rpc: rpc.into(),
+            ai_job_manager: "0x0000000000000000000000000000000000000001".to_string(),
+            node_cert_registry: None,
+            jobs: ReadThrough::jobs(),
         }
     }
 
-    fn function_selector(signature: &str) -> String {
-        use sha3::{Keccak256, Digest};
-        let mut hasher = Keccak256::new();
-        hasher.update(signature.as_bytes());
-        let hash = hasher.finalize();
-        format!("{:02x}{:02x}{:02x}{:02x}", hash[0], hash[1], hash[2], hash[3])
+    /// Call the contracts at the addresses resolved at startup
+    pub fn with_contracts(mut self, contracts: &ContractRegistry) -> Self {
+        if let Some(address) = contracts.address("AIJobManager") {
+            self.ai_job_manager = address.to_string();
+        }
+        self.node_cert_registry = contracts.address(NODE_CERT_REGISTRY.name).map(str::to_string);
+        self
     }
 
-    async fn call_contract(
-        &self,
-        contract_addr: &str,
-        method_signature: &str,
-        params: Vec<String>,
-    ) -> Result<String, String> {
-        let data = format!("{}{}", method_signature, params.join(""));
-        
-        let payload = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_call",
-            "params": [{
-                "to": contract_addr,
-                "data": format!("0x{}", data)
-            }, "latest"],
-            "id": 1
-        });
+    async fn call_contract(&self, contract_addr: &str, signature: &str, args: &[Token]) -> Result<String, String> {
+        let data = hex::encode(abi_encode_call(signature, args)?);
 
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| format!("RPC call failed: {}", e))?;
+        let params = serde_json::json!([{
+            "to": contract_addr,
+            "data": format!("0x{}", data)
+        }, "latest"]);
 
-        let result: serde_json::Value = response.json().await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let result = self.rpc.call("eth_call", params).await.map_err(|e| e.to_string())?;
 
-        result["result"].as_str()
+        result.as_str()
             .ok_or_else(|| "No result in response".to_string())
             .map(|s| s.to_string())
     }
 
-    async fn send_transaction(
-        &self,
-        contract_addr: &str,
-        method_signature: &str,
-        params: Vec<String>,
-    ) -> Result<String, String> {
-        let data = format!("{}{}", method_signature, params.join(""));
-        
-        let payload = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_sendTransaction",
-            "params": [{
-                "from": std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
-                "to": contract_addr,
-                "data": format!("0x{}", data),
-                "gas": "0x100000",
-                "gasPrice": "0x4a817c800",
-            }],
-            "id": 1
-        });
-
-        let response = self.client
-            .post(&self.rpc_url)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| format!("Transaction failed: {}", e))?;
+    async fn send_transaction(&self, contract_addr: &str, signature: &str, args: &[Token]) -> Result<String, String> {
+        let data = hex::encode(abi_encode_call(signature, args)?);
 
-        let result: serde_json::Value = response.json().await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let params = serde_json::json!([{
+            "from": std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
+            "to": contract_addr,
+            "data": format!("0x{}", data),
+            "gas": "0x100000",
+            "gasPrice": "0x4a817c800",
+        }]);
 
-        if let Some(err) = result.get("error") {
-            return Err(format!("Transaction error: {}", err));
-        }
+        let result = self.rpc.call("eth_sendTransaction", params).await.map_err(|e| match e {
+            RpcError::Rpc { .. } => format!("Transaction error: {}", e),
+            _ => format!("Transaction failed: {}", e),
+        })?;
 
-        result["result"].as_str()
+        result.as_str()
             .ok_or_else(|| "No tx hash in response".to_string())
             .map(|s| s.to_string())
     }
 
     pub async fn get_job(&self, job_id: &str) -> Result<Job, String> {
+        self.jobs.get_or_load(job_id, self.fetch_job(job_id)).await
+    }
+
+    async fn fetch_job(&self, job_id: &str) -> Result<Job, String> {
         // Query AIJobManager.getJob(bytes32 jobId)
-        let method_hash = Self::function_selector("getJob(bytes32)");
-        let params = vec![hex::encode(job_id.as_bytes())[..64].to_string()];
+        self.call_contract(&self.ai_job_manager, "getJob(bytes32)", &[abi_encode_bytes32(job_id)]).await?;
+        info!("📊 Fetched job {} from blockchain", job_id);
 
-        let result = self.call_contract(&self.ai_job_manager, &method_hash, params).await?;
-        
-        // Decode ABI-encoded result (simplified - full implementation would decode properly)
-        println!("📊 Fetched job {} from blockchain", job_id);
-        
-        // Decode ABI-encoded result
-        // Basic ABI decoding: first 32 bytes are typically the first return value
-        let decoded_result = if result.len() >= 32 {
-            // Extract first 32 bytes as uint256 (simplified decoding)
-            let mut value_bytes = [0u8; 32];
-            value_bytes.copy_from_slice(&result[0..32]);
-            // In full implementation, would decode based on function return types
-            Some(format!("0x{}", hex::encode(&value_bytes)))
-        } else {
-            None
-        };
         Ok(Job {
             job_id: job_id.to_string(),
             job_type: "train".to_string(),
@@ -216,51 +417,92 @@ impl ContractClient {
                 max_price_per_sec: 0.01,
                 preferred_regions: vec!["us-west".to_string()],
                 required_capabilities: vec!["torch".to_string()],
+                tee_required: false,
+                required_precisions: vec![],
             },
             budget: 1000,
             submitter_did: "did:artha:user123".to_string(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
         })
     }
 
-    pub async fn assign_job(&self, job_id: &str, node_pubkey: &str) -> Result<(), String> {
-        let method_hash = Self::function_selector("assignJob(bytes32,bytes32)");
-        let params = vec![
-            hex::encode(job_id.as_bytes())[..64].to_string(),
-            hex::encode(node_pubkey.as_bytes())[..64].to_string(),
-        ];
+    /// Assign on-chain; returns the transaction hash
+    pub async fn assign_job(&self, job_id: &str, node_pubkey: &str) -> Result<String, String> {
+        let args = [abi_encode_bytes32(job_id), abi_encode_bytes32(node_pubkey)];
+
+        let sent = self.send_transaction(&self.ai_job_manager, "assignJob(bytes32,bytes32)", &args).await;
+        self.jobs.invalidate(job_id);
+        let tx_hash = sent?;
+        info!("✅ Assigned job {} to node {} on-chain", job_id, &node_pubkey[..16]);
+        Ok(tx_hash)
+    }
+
+    /// Hash of the latest block, the chain's half of a fair draw's VRF input
+    /// Whether `block_hash` is a block of the chain
+    pub async fn block_exists(&self, block_hash: &str) -> Result<bool, String> {
+        let block = self.rpc.call("eth_getBlockByHash", serde_json::json!([block_hash, false])).await.map_err(|e| e.to_string())?;
+        Ok(!block.is_null())
+    }
+
+    pub async fn latest_block_hash(&self) -> Result<String, String> {
+        let block = self.rpc.call("eth_getBlockByNumber", serde_json::json!(["latest", false])).await.map_err(|e| e.to_string())?;
+        block["hash"].as_str()
+            .ok_or_else(|| "No block hash in response".to_string())
+            .map(|s| s.to_string())
+    }
 
-        self.send_transaction(&self.ai_job_manager, &method_hash, params).await?;
-        println!("✅ Assigned job {} to node {} on-chain", job_id, &node_pubkey[..16]);
-        Ok(())
+    /// Registry keys of the active GPU providers in NodeCertRegistry
+    pub async fn certified_nodes(&self) -> Result<Vec<String>, String> {
+        let registry = self.node_cert_registry.as_deref().ok_or("NodeCertRegistry is not configured")?;
+        let args = [abi_encode_uint256(certs::GPU_PROVIDER_ROLE)];
+
+        let result = self.call_contract(registry, "getNodesByRole(uint8)", &args).await?;
+        certs::decode_keys(&result)
     }
 
     pub async fn query_capable_nodes(&self, requirements: &JobRequirements) -> Result<Vec<Node>, String> {
         // Query NodeCertRegistry for nodes matching requirements
-        println!("🔍 Querying capable nodes from NodeCertRegistry");
+        info!("🔍 Querying capable nodes from NodeCertRegistry");
         Ok(vec![]) // Populated from registry
     }
 }
 
 pub struct SvdbClient {
     api_url: String,
+    client: reqwest::Client,
 }
 
 impl SvdbClient {
     pub fn new(api_url: String) -> Self {
-        SvdbClient { api_url }
+        SvdbClient { api_url, client: reqwest::Client::new() }
+    }
+
+    /// Regions holding a verified replica of `cid`, per SVDB's replication status
+    async fn verified_regions(&self, cid: &str) -> Result<Vec<String>, String> {
+        let segment = cid.trim_start_matches("artha://").replace('+', "%2B").replace('/', "%2F");
+        let url = format!("{}/svdb/{}/replication", self.api_url, segment);
+        let resp = self.client.get(&url).send().await.map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("Replication status of {}: {}", cid, resp.status()));
+        }
+        let status: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+        Ok(status["verified_regions"]
+            .as_array()
+            .map(|regions| regions.iter().filter_map(|r| r.as_str().map(String::from)).collect())
+            .unwrap_or_default())
     }
 
     pub async fn get_dataset_location(&self, dataset_id: &str) -> Result<Vec<String>, String> {
-        // Query which storage providers have this dataset (for locality)
-        println!("📍 Getting location for dataset {}", dataset_id);
-        
-        // Returns list of regions where dataset is replicated
-        Ok(vec!["us-west".to_string(), "eu-central".to_string()])
+        // Query which regions hold a verified copy of this dataset (for locality)
+        info!("📍 Getting location for dataset {}", dataset_id);
+        self.verified_regions(dataset_id).await
     }
 
     pub async fn get_model_location(&self, model_id: &str) -> Result<Vec<String>, String> {
-        println!("📍 Getting location for model {}", model_id);
-        Ok(vec!["us-west".to_string()])
+        info!("📍 Getting location for model {}", model_id);
+        self.verified_regions(model_id).await
     }
 }
 
@@ -268,35 +510,227 @@ impl SvdbClient {
 
 async fn schedule_job(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<ScheduleRequest>,
+) -> Result<ScheduleOutcome, Response> {
+    // A submitter that has stopped waiting gets no placement
+    let deadline = Deadline::from_headers(&headers).map_err(IntoResponse::into_response)?;
+    deadline.check(state.clock.now_millis(), "scheduling").map_err(|e| {
+        info!("⌛ Not scheduling job {}: {}", req.job_id, e.message);
+        e.into_response()
+    })?;
+
+    // Admission control: reserve a pending slot before doing any placement work.
+    // Reservation-backed jobs draw on capacity that was set aside for them.
+    if req.reservation_id.is_none() {
+        let node_count = state.nodes.read().await.len();
+        let mut pending = state.pending.write().await;
+        if let Err(full) = pending.admit(&req.job_id, node_count, &state.admission) {
+            info!("⏳ Queue full ({}/{}), rejecting job {} (retry after {}s)",
+                full.pending, full.high_watermark, req.job_id, full.retry_after_secs);
+            return Err(full.into_response());
+        }
+    }
+
+    let job_id = req.job_id.clone();
+    match place_job(&state, req.clone(), deadline).await {
+        Ok(Json(placed)) => Ok(ScheduleOutcome::Placed(Box::new(placed))),
+        Err(StatusCode::SERVICE_UNAVAILABLE) if req.reservation_id.is_none() && priced_out(&state, &req).await => {
+            info!("💸 No node within budget for job {}, waiting for prices to move", job_id);
+            // Exclusions are already recorded against the job
+            let request = ScheduleRequest { exclude_nodes: Vec::new(), ..req };
+            state.waiting.write().await.insert(job_id.clone(), request);
+            Ok(ScheduleOutcome::Waiting(WaitingResponse {
+                job_id,
+                status: "waiting".to_string(),
+                reason: "No capable node within max_price_per_sec".to_string(),
+            }))
+        }
+        Err(status) => {
+            state.pending.write().await.release(&job_id);
+            state.rejections.write().await.remove(&job_id);
+            Err(status.into_response())
+        }
+    }
+}
+
+async fn place_job(
+    state: &Arc<AppState>,
+    req: ScheduleRequest,
+    deadline: Deadline,
 ) -> Result<Json<ScheduleResponse>, StatusCode> {
-    println!("\n🎯 Scheduling job: {}", req.job_id);
+    info!("🎯 Scheduling job: {}", req.job_id);
+    if !req.exclude_nodes.is_empty() {
+        state.rejections.write().await.entry(req.job_id.clone()).or_default().extend(req.exclude_nodes.iter().cloned());
+    }
 
-    // 1. Fetch job details
-    let job = state.contract_client.get_job(&req.job_id).await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    // 1-4. Fetch the job, then score and rank its candidate nodes
+    let (job, mut scores) = rank_candidates(state, &req).await?;
+    let (mut fair_draw, mut auction) = (None, None);
+    match job.placement {
+        PlacementMode::TopScore => {}
+        PlacementMode::RandomAmongTopK { k } => fair_draw = Some(draw_among_top_k(state, &req.job_id, &mut scores, k).await?),
+        PlacementMode::Auction if !state.auction.enabled => {
+            info!("🔨 Auctions are disabled; placing job {} by score", req.job_id);
+        }
+        PlacementMode::Auction => auction = Some(run_auction(state, &job, &mut scores).await),
+    }
+    // Ranking and auctions take time; don't assign on-chain for a caller who has gone
+    if deadline.has_passed(state.clock.now_millis()) {
+        info!("⌛ Deadline passed while placing job {}, not assigning it", req.job_id);
+        return Err(StatusCode::REQUEST_TIMEOUT);
+    }
+    let Json(placed) = assign_ranked(state, &req.job_id, &job, &scores, deadline).await?;
+    if let Some(auction) = &mut auction {
+        // The best bidder may have become unsuitable before assignment
+        auction.winning_bid = scores.iter().find(|score| score.node_pubkey == placed.assigned_node).and_then(|score| score.bid);
+        auction.winner = auction.winning_bid.map(|_| placed.assigned_node.clone());
+    }
+    Ok(Json(ScheduleResponse { fair_draw, auction, ..placed }))
+}
 
-    // 2. Get candidate nodes
-    let mut candidates = get_candidate_nodes(&state, &job).await?;
-    
-    if candidates.is_empty() {
-        println!("❌ No capable nodes found for job {}", req.job_id);
-        return Err(StatusCode::SERVICE_UNAVAILABLE);
+/// Post the job to its ranked nodes, take bids for the window, then put the
+/// bidders first by utility. The nodes that didn't bid keep their order
+/// behind them, at their listed prices, in case no bidder is still suitable.
+/// The result's winner is filled in once the job is assigned.
+async fn run_auction(state: &Arc<AppState>, job: &Job, scores: &mut [NodeScore]) -> AuctionResult {
+    let ask = Ask {
+        auction_id: String::new(),
+        job_id: job.job_id.clone(),
+        job_type: job.job_type.clone(),
+        requirements: job.requirements.clone(),
+        closes_at: state.clock.now_secs() + state.auction.window_secs,
+    };
+    let invited = scores.iter().map(|score| score.node_pubkey.clone()).collect();
+    let ask = state.auctions.write().await.open(ask, invited);
+    info!("🔨 Job {} open for bids from {} nodes as {}", job.job_id, scores.len(), ask.auction_id);
+    state.clock.sleep(Duration::from_secs(state.auction.window_secs)).await;
+    let bids = state.auctions.write().await.close(&ask.auction_id);
+
+    let reputations: HashMap<String, f64> = {
+        let nodes = state.nodes.read().await;
+        bids.keys().map(|pubkey| (pubkey.clone(), nodes.get(pubkey).map_or(0.0, |node| node.reputation_score))).collect()
+    };
+    let utility = |score: &NodeScore| score.bid.map(|bid| state.auction.utility(&bid, reputations[&score.node_pubkey]));
+    for score in scores.iter_mut() {
+        score.bid = bids.get(&score.node_pubkey).copied();
     }
+    // Stable, so bidders of equal utility and the non-bidders keep their ranking
+    scores.sort_by(|a, b| match (utility(a), utility(b)) {
+        (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
 
-    // 3. Score each node
-    let mut scores = Vec::new();
-    for node in &candidates {
-        let score = score_node(&state, &job, node).await?;
-        scores.push(score);
+    if bids.is_empty() {
+        warn!("⚠️  No bids for job {} in {}; placing by score", job.job_id, ask.auction_id);
+    } else {
+        info!("🔨 {} closed with {} bids for job {}", ask.auction_id, bids.len(), job.job_id);
+    }
+    AuctionResult { auction_id: ask.auction_id, bids: bids.len(), winner: None, winning_bid: None }
+}
+
+/// Draw one of the `k` best-ranked nodes with the VRF and move it to the
+/// front. The rest keep their order behind it, should it be unsuitable.
+async fn draw_among_top_k(
+    state: &Arc<AppState>,
+    job_id: &str,
+    scores: &mut [NodeScore],
+    k: usize,
+) -> Result<FairDraw, StatusCode> {
+    let block_hash = state.contract_client.latest_block_hash().await.map_err(|e| {
+        error!("❌ No block hash to seed the draw for job {}: {}", job_id, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    let top = k.clamp(1, scores.len());
+    let candidates = scores[..top].iter().map(|score| score.node_pubkey.clone()).collect();
+    let draw = fairness::draw(&state.vrf_key, job_id, &block_hash, candidates);
+    scores[..=draw.chosen].rotate_right(1);
+    info!("🎲 Drew node {} of the top {} for job {} at block {}", draw.chosen + 1, top, job_id, block_hash);
+    state.draws.write().await.insert(job_id.to_string(), draw.clone());
+    Ok(draw)
+}
+
+/// Whether the job could be placed but for price: some node that isn't
+/// cordoned or excluded for it meets every requirement except the price cap
+async fn priced_out(state: &Arc<AppState>, req: &ScheduleRequest) -> bool {
+    let Ok(mut job) = state.contract_client.get_job(&req.job_id).await else {
+        return false;
+    };
+    job.requirements.tee_required |= req.tee_required;
+    let nodes = state.nodes.read().await;
+    let cordons = state.cordons.read().await;
+    let rejected = state.rejections.read().await.get(&req.job_id).cloned().unwrap_or_default();
+    let now = state.clock.now_secs();
+    nodes.values().any(|node| {
+        !within_budget(&job, node)
+            && meets_requirements_at_any_price(&job, node)
+            && !rejected.contains(&node.pubkey)
+            && !cordons.get(&node.pubkey).is_some_and(|cordon| cordon.active(now))
+    })
+}
+
+/// Re-fetch node prices, then try to place every waiting job at them.
+/// Returns the jobs placed.
+async fn reevaluate_waiting(state: &Arc<AppState>) -> Vec<String> {
+    match state.price_feed.current_prices().await {
+        Ok(prices) => {
+            for change in pricing::apply(&mut *state.nodes.write().await, &prices) {
+                info!("💱 Node {} now {} per GPU-second (was {})", change.node_pubkey, change.new, change.old);
+            }
+        }
+        Err(e) => warn!("⚠️  {}; re-evaluating at the last known prices", e),
+    }
+
+    let waiting: Vec<ScheduleRequest> = state.waiting.read().await.values().cloned().collect();
+    let mut placed = Vec::new();
+    for request in waiting {
+        let job_id = request.job_id.clone();
+        match place_job(state, request, Deadline::NONE).await {
+            Ok(_) => {
+                state.waiting.write().await.remove(&job_id);
+                placed.push(job_id);
+            }
+            Err(StatusCode::SERVICE_UNAVAILABLE) => {} // Still nothing it can take
+            Err(status) => {
+                error!("❌ Dropping waiting job {} ({})", job_id, status);
+                state.waiting.write().await.remove(&job_id);
+                state.pending.write().await.release(&job_id);
+                state.rejections.write().await.remove(&job_id);
+                report_unschedulable(state, &job_id, &format!("Dropped while waiting for a node within budget ({})", status)).await;
+            }
+        }
     }
+    placed.sort();
+    placed
+}
 
-    // 4. Select best node
-    scores.sort_by(|a, b| b.total_score.partial_cmp(&a.total_score).unwrap());
-    let best_score = &scores[0];
+/// Assign the job to the best ranked node that is still suitable. Node state
+/// can change while candidates are scored, so each is re-checked just before
+/// the on-chain assignment and the next-best tried if it no longer qualifies.
+async fn assign_ranked(
+    state: &Arc<AppState>,
+    job_id: &str,
+    job: &Job,
+    scores: &[NodeScore],
+    deadline: Deadline, // Passed on to ai-jobd with the assignment
+) -> Result<Json<ScheduleResponse>, StatusCode> {
+    let mut best = None;
+    for score in scores {
+        match live_unsuitability(state, job, &score.node_pubkey).await {
+            None => {
+                best = Some(score);
+                break;
+            }
+            Some(reason) => warn!("⚠️  Skipping node {}: {}", score.node_pubkey, reason),
+        }
+    }
+    let Some(best_score) = best else {
+        error!("❌ No ranked node is still suitable for job {}", job_id);
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    };
     
-    println!("\n🏆 Best node: {} (score: {:.3})", &best_score.node_pubkey[..16], best_score.total_score);
-    println!("  └─ Locality: {:.3}, GPU: {:.3}, SLA: {:.3}, Cost: {:.3}, Load: {:.3}",
+    info!("🏆 Best node: {} (score: {:.3})", &best_score.node_pubkey[..16], best_score.total_score);
+    info!("  └─ Locality: {:.3}, GPU: {:.3}, SLA: {:.3}, Cost: {:.3}, Load: {:.3}",
         best_score.locality_score,
         best_score.gpu_score,
         best_score.sla_score,
@@ -305,15 +739,22 @@ async fn schedule_job(
     );
 
     // 5. Assign job
-    state.contract_client.assign_job(&req.job_id, &best_score.node_pubkey).await
+    let assign_tx = state.contract_client.assign_job(job_id, &best_score.node_pubkey).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    state.job_assignments.write().await.insert(req.job_id.clone(), best_score.node_pubkey.clone());
+    state.job_assignments.write().await.insert(job_id.to_string(), best_score.node_pubkey.clone());
+    if let Some(reservation_id) = &job.reservation_id {
+        state.reservations.write().await.attach(reservation_id, job_id, &best_score.node_pubkey);
+    }
+    state.placements.write().await.insert(job_id.to_string(), Placement {
+        node_pubkey: best_score.node_pubkey.clone(),
+        job_class: job_class_of(job),
+        scheduled_at: state.clock.now_secs(),
+        mode: job.placement,
+        estimated_duration_secs: job.estimated_duration_secs,
+    });
 
     // 6. Notify ai-jobd that job is assigned
-    let jobd_url = std::env::var("ARTHA_JOBD_URL").unwrap_or_else(|_| "http://localhost:8081".to_string());
-    let client = reqwest::Client::new();
-    
     // Determine runtime based on job type
     let runtime = match job.job_type.as_str() {
         "train" => "torch",
@@ -322,17 +763,35 @@ async fn schedule_job(
         _ => "torch",
     };
     
-    let _ = client
-        .post(&format!("{}/job/assigned", jobd_url))
-        .json(&serde_json::json!({
-            "job_id": req.job_id,
-            "assigned_node": best_score.node_pubkey,
-            "runtime": runtime,
-        }))
-        .send()
-        .await;
-    
-    println!("   📬 Notified ai-jobd of assignment");
+    // The score breakdown and rate go along for ai-jobd's job timeline; a
+    // node that won the job at auction is paid its bid
+    let price_per_gpu_sec = match best_score.bid {
+        Some(bid) => Some(bid.price_per_gpu_sec),
+        None => state.nodes.read().await.get(&best_score.node_pubkey).map(|node| node.price_per_gpu_sec),
+    };
+    let body = serde_json::json!({
+        "job_id": job_id,
+        "assigned_node": best_score.node_pubkey,
+        "runtime": runtime,
+        "assign_tx": assign_tx,
+        "score": {
+            "total": best_score.total_score,
+            "locality": best_score.locality_score,
+            "gpu": best_score.gpu_score,
+            "sla": best_score.sla_score,
+            "cost": best_score.cost_score,
+            "load": best_score.load_score,
+            "price_per_gpu_sec": price_per_gpu_sec,
+        },
+    });
+    notify_assigned(state, Notification {
+        job_id: job_id.to_string(),
+        node_pubkey: best_score.node_pubkey.clone(),
+        body,
+        attempts: 0,
+        last_error: None,
+        deadline,
+    }).await;
 
     // 7. Update node load
     let mut nodes = state.nodes.write().await;
@@ -341,10 +800,170 @@ async fn schedule_job(
     }
 
     Ok(Json(ScheduleResponse {
-        job_id: req.job_id,
+        job_id: job_id.to_string(),
         assigned_node: best_score.node_pubkey.clone(),
         score: best_score.total_score,
-        estimated_start_time: now() + 30, // 30 seconds
+        estimated_start_time: estimated_start_time(best_score, state.clock.now_secs()),
+        predicted_duration: best_score.predicted_duration.clone(),
+        failure_probability: best_score.failure_probability,
+        fair_draw: None,
+        auction: None,
+    }))
+}
+
+/// Fetch a job and score every capable node for it, best first
+async fn rank_candidates(
+    state: &Arc<AppState>,
+    req: &ScheduleRequest,
+) -> Result<(Job, Vec<NodeScore>), StatusCode> {
+    let mut job = state.contract_client.get_job(&req.job_id).await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    job.requirements.tee_required |= req.tee_required;
+    job.reservation_id = req.reservation_id.clone();
+    job.placement = req.placement;
+    job.estimated_duration_secs = req.estimated_duration_secs;
+
+    let candidates = get_candidate_nodes(state, &job).await?;
+    if candidates.is_empty() {
+        error!("❌ No capable nodes found for job {}", req.job_id);
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    let mut scores = Vec::new();
+    for node in &candidates {
+        scores.push(score_node(state, &job, node).await?);
+    }
+    avoid_maintenance(&job, &candidates, &mut scores, state.clock.now_secs());
+    rank_scores(&mut scores);
+    Ok((job, scores))
+}
+
+/// Drop nodes whose announced maintenance would interrupt the job, judged
+/// from its estimated start on each node and its expected duration there.
+/// When every node would be interrupted, only those whose maintenance starts
+/// latest are kept, so the job gets as far as it can before it is cut off.
+fn avoid_maintenance(job: &Job, candidates: &[Node], scores: &mut Vec<NodeScore>, now: u64) {
+    let interrupting = |score: &NodeScore| {
+        let node = candidates.iter().find(|node| node.pubkey == score.node_pubkey)?;
+        let start = estimated_start_time(score, now);
+        let duration = job
+            .estimated_duration_secs
+            .or_else(|| score.predicted_duration.as_ref().map(|p| p.expected_secs.ceil() as u64))
+            .unwrap_or(0);
+        node.maintenance_windows.iter().find(|window| window.interrupts(start, start + duration.max(1)))
+    };
+    // Set only when every candidate would be interrupted
+    let latest_window = scores
+        .iter()
+        .map(|score| interrupting(score).map(|window| window.start))
+        .collect::<Option<Vec<_>>>()
+        .and_then(|starts| starts.into_iter().max());
+    if let Some(latest) = latest_window {
+        warn!("⚠️  Every candidate for job {} has maintenance before it would finish; keeping those due at {}", job.job_id, latest);
+    }
+    scores.retain(|score| match interrupting(score) {
+        Some(window) if latest_window != Some(window.start) => {
+            info!("🔧 Not placing job {} on {}: maintenance at {} would interrupt it", job.job_id, score.node_pubkey, window.start);
+            false
+        }
+        _ => true,
+    });
+}
+
+/// Order scores best first. NaN scores rank last and ties go to the lower
+/// node pubkey, so the same cluster state always yields the same placement.
+fn rank_scores(scores: &mut [NodeScore]) {
+    for score in scores.iter().filter(|score| score.total_score.is_nan()) {
+        warn!("⚠️  Node {} scored NaN; ranking it last", score.node_pubkey);
+    }
+    scores.sort_by(|a, b| {
+        a.total_score
+            .is_nan()
+            .cmp(&b.total_score.is_nan())
+            .then_with(|| b.total_score.partial_cmp(&a.total_score).unwrap_or(std::cmp::Ordering::Equal))
+            .then_with(|| a.node_pubkey.cmp(&b.node_pubkey))
+    });
+}
+
+/// Why a ranked node can no longer take the job, judged on its live state
+async fn live_unsuitability(state: &Arc<AppState>, job: &Job, pubkey: &str) -> Option<String> {
+    let nodes = state.nodes.read().await;
+    let Some(node) = nodes.get(pubkey) else {
+        return Some("went offline".to_string());
+    };
+    if state.cordons.read().await.get(pubkey).is_some_and(|cordon| cordon.active(state.clock.now_secs())) {
+        return Some("cordoned".to_string());
+    }
+    if state.liveness.read().await.health(pubkey, state.clock.now_secs()) == NodeHealth::Unhealthy {
+        return Some("missed its heartbeats".to_string());
+    }
+    if state.rejections.read().await.get(&job.job_id).is_some_and(|nodes| nodes.iter().any(|n| n == pubkey)) {
+        return Some("excluded for this job".to_string());
+    }
+    if let Some(reservation_id) = &job.reservation_id {
+        // Reserved GPUs are prepaid, so the job's price cap doesn't apply
+        if !meets_requirements_at_any_price(job, node) {
+            return Some("no longer meets the job's requirements".to_string());
+        }
+        if state.reservations.read().await.free_slots(reservation_id, pubkey) == 0 {
+            return Some("no free reserved GPUs".to_string());
+        }
+        return None;
+    }
+    if !meets_requirements(job, node) {
+        return Some("no longer meets the job's requirements".to_string());
+    }
+    if node.current_load >= 1.0 {
+        return Some("no free GPUs".to_string());
+    }
+    if effective_load(state, node).await >= 1.0 {
+        return Some("remaining GPUs are reserved".to_string());
+    }
+    None
+}
+
+/// Node load with the idle GPUs of reservations that are open or about to
+/// open counted as busy
+async fn effective_load(state: &AppState, node: &Node) -> f64 {
+    if node.gpus.is_empty() {
+        return node.current_load;
+    }
+    let held = state.reservations.read().await.held_idle(&node.pubkey, state.clock.now_secs(), state.reservation_config.lead_secs);
+    node.current_load + held as f64 / node.gpus.len() as f64
+}
+
+fn job_class_of(job: &Job) -> String {
+    learning::job_class(&job.job_type, job.requirements.min_gpu_vram_gb, job.requirements.tee_required)
+}
+
+/// Learned queue wait on the node, or 30s before anything has been learned
+fn estimated_start_time(score: &NodeScore, now: u64) -> u64 {
+    if let Some(bid) = score.bid {
+        return now + bid.eta_secs;
+    }
+    now + score.predicted_queue_wait.map(|w| w.round() as u64).unwrap_or(30)
+}
+
+/// POST /schedule/simulate - Rank candidates with predictions, assigning nothing
+async fn simulate_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ScheduleRequest>,
+) -> Result<Json<SimulateResponse>, StatusCode> {
+    let (job, scores) = rank_candidates(&state, &req).await?;
+    let now = state.clock.now_secs();
+    Ok(Json(SimulateResponse {
+        job_id: req.job_id,
+        job_class: job_class_of(&job),
+        candidates: scores
+            .iter()
+            .map(|score| SimulatedPlacement {
+                node_pubkey: score.node_pubkey.clone(),
+                score: score.total_score,
+                estimated_start_time: estimated_start_time(score, now),
+                predicted_duration: score.predicted_duration.clone(),
+                failure_probability: score.failure_probability,
+            })
+            .collect(),
     }))
 }
 
@@ -362,31 +981,100 @@ async fn get_candidate_nodes(
         candidates = nodes.values().cloned().collect();
     }
 
-    // Filter by requirements
-    candidates.retain(|node| {
-        // GPU requirements
-        let has_suitable_gpu = node.gpus.iter().any(|gpu| {
-            gpu.vram_gb >= job.requirements.min_gpu_vram_gb &&
-            gpu.available &&
-            (job.requirements.preferred_gpu_types.is_empty() ||
-             job.requirements.preferred_gpu_types.contains(&gpu.gpu_type))
-        });
+    // Filter by requirements, skipping nodes whose runtime rejected the job or that it is migrating off
+    let rejected = state.rejections.read().await.get(&job.job_id).cloned().unwrap_or_default();
+    match &job.reservation_id {
+        Some(reservation_id) => {
+            let book = state.reservations.read().await;
+            let reservation = book.get(reservation_id).ok_or(StatusCode::NOT_FOUND)?;
+            if let Err(reason) = reservation_fits(reservation, job, state.clock.now_secs()) {
+                warn!("🚫 Job {} cannot use reservation {}: {}", job.job_id, reservation_id, reason);
+                return Err(StatusCode::CONFLICT);
+            }
+            candidates.retain(|node| {
+                book.free_slots(reservation_id, &node.pubkey) > 0
+                    && meets_requirements_at_any_price(job, node)
+                    && !rejected.contains(&node.pubkey)
+            });
+        }
+        None => candidates.retain(|node| meets_requirements(job, node) && !rejected.contains(&node.pubkey)),
+    }
+
+    // Drained nodes, nodes in maintenance and nodes missing heartbeats take no new work
+    let cordons = state.cordons.read().await;
+    let liveness = state.liveness.read().await;
+    let now = state.clock.now_secs();
+    candidates.retain(|node| !cordons.get(&node.pubkey).is_some_and(|cordon| cordon.active(now)));
+    candidates.retain(|node| liveness.health(&node.pubkey, now) == NodeHealth::Healthy);
+    let certs = state.certs.read().await;
+    candidates.retain(|node| certs.is_certified(&node.pubkey));
+
+    info!("✓ Found {} candidate nodes", candidates.len());
+    Ok(candidates)
+}
 
-        // SLA requirements
-        let meets_sla = node.uptime_percent >= job.requirements.min_uptime_percent;
+/// Why a job can't draw on a reservation: the window isn't open, or the
+/// reserved GPUs aren't a type or region the job accepts
+fn reservation_fits(reservation: &Reservation, job: &Job, now: u64) -> Result<(), String> {
+    if !reservation.open(now) {
+        return Err(format!("window is {}..{}", reservation.start, reservation.end));
+    }
+    let types = &job.requirements.preferred_gpu_types;
+    if !types.is_empty() && !types.contains(&reservation.gpu_type) {
+        return Err(format!("job needs one of {:?}, reservation holds {}", types, reservation.gpu_type));
+    }
+    let regions = &job.requirements.preferred_regions;
+    if let Some(region) = &reservation.region {
+        if !regions.is_empty() && !regions.contains(region) {
+            return Err(format!("job needs one of {:?}, reservation is in {}", regions, region));
+        }
+    }
+    Ok(())
+}
 
-        // Price requirements
-        let within_budget = node.price_per_gpu_sec <= job.requirements.max_price_per_sec;
+fn meets_requirements(job: &Job, node: &Node) -> bool {
+    meets_requirements_at_any_price(job, node) && within_budget(job, node)
+}
 
-        // Capability requirements
-        let has_capabilities = job.requirements.required_capabilities.iter()
-            .all(|cap| node.capabilities.contains(cap));
+fn within_budget(job: &Job, node: &Node) -> bool {
+    node.price_per_gpu_sec <= job.requirements.max_price_per_sec
+}
 
-        has_suitable_gpu && meets_sla && within_budget && has_capabilities
+fn meets_requirements_at_any_price(job: &Job, node: &Node) -> bool {
+    // GPU requirements
+    let has_suitable_gpu = node.gpus.iter().any(|gpu| {
+        gpu.vram_gb >= job.requirements.min_gpu_vram_gb &&
+        gpu.available &&
+        (job.requirements.preferred_gpu_types.is_empty() ||
+         job.requirements.preferred_gpu_types.contains(&gpu.gpu_type)) &&
+        supports_precisions(gpu, &job.requirements.required_precisions)
     });
 
-    println!("✓ Found {} candidate nodes", candidates.len());
-    Ok(candidates)
+    // SLA requirements
+    let meets_sla = node.uptime_percent >= job.requirements.min_uptime_percent;
+
+    // Capability requirements
+    let has_capabilities = job.requirements.required_capabilities.iter()
+        .all(|cap| node.capabilities.contains(cap));
+
+    // TEE is a hard constraint: only nodes advertising the capability qualify
+    let meets_tee = !job.requirements.tee_required ||
+        node.capabilities.iter().any(|cap| cap == TEE_CAPABILITY);
+
+    has_suitable_gpu && meets_sla && has_capabilities && meets_tee
+}
+
+const TEE_CAPABILITY: &str = "tee";
+
+/// Whether a GPU can run every precision the job needs. A GPU that reports
+/// no precisions predates precision reporting and is not ruled out, but only
+/// one that reports them earns the native-support bonus.
+fn supports_precisions(gpu: &GpuInfo, required: &[String]) -> bool {
+    gpu.precisions.is_empty() || required.iter().all(|p| gpu.precisions.contains(p))
+}
+
+fn native_precisions(gpu: &GpuInfo, required: &[String]) -> bool {
+    !required.is_empty() && !gpu.precisions.is_empty() && supports_precisions(gpu, required)
 }
 
 async fn score_node(
@@ -400,6 +1088,7 @@ async fn score_node(
     const W_SLA: f64 = 0.20;
     const W_COST: f64 = 0.10;
     const W_LOAD: f64 = 0.10;
+    const W_FAILURE: f64 = 0.50; // Penalty per unit of predicted failure probability
 
     // 1. Locality score (co-location with data)
     let locality_score = compute_locality_score(state, job, node).await?;
@@ -413,8 +1102,15 @@ async fn score_node(
     // 4. Cost score (lower price = higher score)
     let cost_score = 1.0 - (node.price_per_gpu_sec / job.requirements.max_price_per_sec).min(1.0);
 
-    // 5. Load score (lower load = higher score)
-    let load_score = 1.0 - node.current_load;
+    // 5. Load score (lower load = higher score); reserved GPUs count as load for other jobs
+    let load_score = if job.reservation_id.is_some() {
+        1.0 - node.current_load
+    } else {
+        (1.0 - effective_load(state, node).await).max(0.0)
+    };
+
+    // 6. Learned failure probability and runtime for this node and job class
+    let prediction = state.learner.read().await.predict(&node.pubkey, &job_class_of(job));
 
     // Total weighted score
     let total_score = 
@@ -422,7 +1118,8 @@ async fn score_node(
         W_GPU * gpu_score +
         W_SLA * sla_score +
         W_COST * cost_score +
-        W_LOAD * load_score;
+        W_LOAD * load_score -
+        W_FAILURE * prediction.failure_probability;
 
     Ok(NodeScore {
         node_pubkey: node.pubkey.clone(),
@@ -432,6 +1129,10 @@ async fn score_node(
         sla_score,
         cost_score,
         load_score,
+        failure_probability: prediction.failure_probability,
+        predicted_duration: prediction.duration,
+        predicted_queue_wait: prediction.queue_wait_secs,
+        bid: None,
     })
 }
 
@@ -441,7 +1142,7 @@ async fn compute_locality_score(
     node: &Node,
 ) -> Result<f64, StatusCode> {
     // Check if node is in same region as dataset/model
-    let mut score = 0.0;
+    let mut score = 0.0_f64;
 
     if let Some(dataset_id) = &job.dataset_id {
         let dataset_regions = state.svdb_client.get_dataset_location(dataset_id).await
@@ -470,7 +1171,7 @@ async fn compute_locality_score(
 }
 
 fn compute_gpu_score(job: &Job, node: &Node) -> f64 {
-    let mut best_gpu_score = 0.0;
+    let mut best_gpu_score = 0.0_f64;
 
     for gpu in &node.gpus {
         if !gpu.available {
@@ -490,6 +1191,11 @@ fn compute_gpu_score(job: &Job, node: &Node) -> f64 {
             score += 0.25; // Still usable
         }
 
+        // Native support for the job's precisions
+        if native_precisions(gpu, &job.requirements.required_precisions) {
+            score += 0.25;
+        }
+
         best_gpu_score = best_gpu_score.max(score);
     }
 
@@ -500,43 +1206,583 @@ fn compute_gpu_score(job: &Job, node: &Node) -> f64 {
 
 async fn register_node(
     State(state): State<Arc<AppState>>,
-    Json(node): Json<Node>,
+    Json(mut node): Json<Node>,
 ) -> Result<StatusCode, StatusCode> {
-    println!("📝 Registering node: {}", &node.pubkey[..16]);
+    if !state.certs.read().await.is_certified(&node.pubkey) {
+        warn!("🚫 Node {} is not certified in NodeCertRegistry", node.pubkey);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    info!("📝 Registering node: {}", &node.pubkey[..16]);
+    node.maintenance_windows = upcoming_windows(node.maintenance_windows, state.clock.now_secs());
+    state.liveness.write().await.track(&node.pubkey, state.clock.now_secs());
     state.nodes.write().await.insert(node.pubkey.clone(), node);
     Ok(StatusCode::CREATED)
 }
 
-async fn list_nodes(
+/// POST /chain/events - Finalized contract logs from the node's event
+/// forwarder. NodeCertRegistry logs certify and decertify nodes; a node
+/// decertified mid-run keeps its jobs but is no longer a candidate.
+async fn chain_events(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<Node>>, StatusCode> {
-    let nodes = state.nodes.read().await;
-    Ok(Json(nodes.values().cloned().collect()))
+    Json(log): Json<ChainLog>,
+) -> StatusCode {
+    if log.contract != NODE_CERT_REGISTRY.name {
+        return StatusCode::NO_CONTENT;
+    }
+    match state.certs.write().await.apply(&log) {
+        Some(CertChange::Certified(key)) => info!("🪪 Node 0x{} certified at block {}", key, log.block_height),
+        Some(CertChange::Decertified(key)) => warn!("🚫 Node 0x{} decertified at block {}", key, log.block_height),
+        None => {}
+    }
+    StatusCode::NO_CONTENT
 }
 
-fn now() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+/// POST /schedule/:job_id/release - Called by ai-jobd when a job leaves the queue
+async fn release_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> StatusCode {
+    let was_pending = state.pending.write().await.release(&job_id);
+    state.waiting.write().await.remove(&job_id);
+    state.placements.write().await.remove(&job_id);
+    state.rejections.write().await.remove(&job_id);
+    state.reservations.write().await.detach(&job_id);
+
+    if let Some(node_pubkey) = state.job_assignments.write().await.remove(&job_id) {
+        let mut nodes = state.nodes.write().await;
+        if let Some(node) = nodes.get_mut(&node_pubkey) {
+            node.current_load = (node.current_load - 0.2).max(0.0); // Return reserved capacity
+        }
+    }
+
+    if was_pending {
+        info!("📤 Released job {} from pending queue", job_id);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
 }
 
-// Server setup
+/// POST /schedule/:job_id/reject - The assigned node's runtime cannot run the
+/// job; undo the assignment and place it on another node. The pending slot is
+/// kept until a placement succeeds or no candidates remain.
+async fn reject_assignment(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Json(report): Json<RejectReport>,
+) -> StatusCode {
+    {
+        let mut assignments = state.job_assignments.write().await;
+        if assignments.get(&job_id) != Some(&report.node_pubkey) {
+            return StatusCode::NOT_FOUND;
+        }
+        assignments.remove(&job_id);
+    }
+    let (placement, estimated_duration_secs) = state
+        .placements
+        .write()
+        .await
+        .remove(&job_id)
+        .map(|placement| (placement.mode, placement.estimated_duration_secs))
+        .unwrap_or_default();
+    if let Some(node) = state.nodes.write().await.get_mut(&report.node_pubkey) {
+        node.current_load = (node.current_load - 0.2).max(0.0); // Return reserved capacity
+    }
+    state.rejections.write().await.entry(job_id.clone()).or_default().push(report.node_pubkey.clone());
+    let reservation_id = state.reservations.write().await.detach(&job_id);
+
+    info!("↩️  Node {} rejected job {}: {}", report.node_pubkey, job_id, report.reasons.join("; "));
+
+    // Re-place in the background: ai-jobd is still waiting on this call
+    // inside its /job/assigned handler
+    let request = ScheduleRequest {
+        job_id: job_id.clone(),
+        tee_required: report.tee_required,
+        exclude_nodes: Vec::new(),
+        reservation_id,
+        placement,
+        estimated_duration_secs,
+    };
+    tokio::spawn(async move {
+        if let Err(status) = place_job(&state, request, Deadline::NONE).await {
+            error!("❌ Could not reschedule job {} ({}), releasing it", job_id, status);
+            state.pending.write().await.release(&job_id);
+            state.rejections.write().await.remove(&job_id);
+            let detail = format!("No node could take the job after {} rejected it ({})", report.node_pubkey, status);
+            report_unschedulable(&state, &job_id, &detail).await;
+        }
+    });
+    StatusCode::ACCEPTED
+}
 
-#[tokio::main]
-async fn main() {
-    // Initialize with mock nodes
-    let mut mock_nodes = HashMap::new();
-    
-    // Node 1: Premium A100 in us-west
-    mock_nodes.insert("0xnode1".to_string(), Node {
-        pubkey: "0xnode1aabbccdd".to_string(),
+/// Tell ai-jobd about an assignment, retrying with backoff; if it still
+/// can't be reached the notification waits in the outbox
+async fn notify_assigned(state: &AppState, notification: Notification) {
+    let url = format!("{}/job/assigned", state.jobd_url);
+    match notify::deliver(&reqwest::Client::new(), &state.clock, &state.notify, &url, notification).await {
+        Ok(notification) => info!("   📬 Notified ai-jobd of assignment ({} attempts)", notification.attempts),
+        Err(notification) => {
+            warn!("⚠️  ai-jobd did not take the assignment of job {} after {} attempts ({}), queued for redelivery",
+                notification.job_id, notification.attempts, notification.last_error.as_deref().unwrap_or("unknown error"));
+            state.outbox.write().await.push(notification);
+        }
+    }
+}
+
+/// Send the outbox again. Notifications for assignments that were undone
+/// since are dropped; ai-jobd hears of the new placement on its own.
+async fn redeliver_assignments(state: &AppState) {
+    let queued = state.outbox.write().await.take_all();
+    if queued.is_empty() {
+        return;
+    }
+    let current: Vec<Notification> = {
+        let assignments = state.job_assignments.read().await;
+        queued.into_iter().filter(|n| assignments.get(&n.job_id) == Some(&n.node_pubkey)).collect()
+    };
+    let url = format!("{}/job/assigned", state.jobd_url);
+    let (delivered, failed) = notify::redeliver(&reqwest::Client::new(), &url, current, state.notify.concurrency).await;
+    for notification in &delivered {
+        info!("📬 Redelivered the assignment of job {} ({} attempts)", notification.job_id, notification.attempts);
+    }
+    let mut outbox = state.outbox.write().await;
+    for notification in failed {
+        outbox.push(notification);
+    }
+}
+
+async fn redeliver_loop(state: Arc<AppState>) {
+    let interval = Duration::from_secs(state.notify.redeliver_secs);
+    loop {
+        state.clock.sleep(interval).await;
+        redeliver_assignments(&state).await;
+    }
+}
+
+/// GET /notifications/outbox - Assignments ai-jobd hasn't been told about yet
+async fn notification_outbox(State(state): State<Arc<AppState>>) -> Json<Vec<Notification>> {
+    Json(state.outbox.read().await.list())
+}
+
+/// Tell ai-jobd a job it handed over can no longer be placed, so it fails
+/// with a reason instead of waiting on an assignment that will not come
+async fn report_unschedulable(state: &AppState, job_id: &str, detail: &str) {
+    let result = reqwest::Client::new()
+        .post(format!("{}/job/{}/progress", state.jobd_url, job_id))
+        .json(&serde_json::json!({
+            "progress": 0.0,
+            "status": "Failed",
+            "failure_reason": detail,
+            "terminal_reason": { "kind": "unschedulable", "detail": detail },
+        }))
+        .send()
+        .await;
+    if !matches!(result, Ok(resp) if resp.status().is_success()) {
+        warn!("⚠️  ai-jobd did not take the unschedulable job {}", job_id);
+    }
+}
+
+/// POST /schedule/:job_id/outcome - Feed a finished placement to the predictors
+async fn record_outcome(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Json(report): Json<OutcomeReport>,
+) -> StatusCode {
+    let Some(placement) = state.placements.write().await.remove(&job_id) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    let now = state.clock.now_secs();
+    let started_at = report.started_at.unwrap_or(placement.scheduled_at);
+    let completed_at = report.completed_at.unwrap_or(now);
+    let outcome = PlacementOutcome {
+        job_id: job_id.clone(),
+        job_class: placement.job_class,
+        node_pubkey: placement.node_pubkey,
+        queue_wait_secs: started_at.saturating_sub(placement.scheduled_at),
+        duration_secs: completed_at.saturating_sub(started_at),
+        peak_vram_mb: report.peak_vram_mb,
+        status: report.status,
+        failure_reason: report.failure_reason,
+        recorded_at: now,
+    };
+    info!("📈 Recorded {:?} outcome for {} on {} ({}s)",
+        outcome.status, job_id, outcome.node_pubkey, outcome.duration_secs);
+    state.learner.write().await.record(outcome);
+    StatusCode::NO_CONTENT
+}
+
+/// GET /learning/stats - Per-node, per-class history and current predictions
+async fn learning_stats(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<learning::PairStats>> {
+    Json(state.learner.read().await.stats())
+}
+
+/// POST /nodes/:pubkey/heartbeat - Keep a node alive and refresh its load
+/// from its reported capacity. Unsigned, stale or replayed heartbeats are refused.
+async fn node_heartbeat(
+    State(state): State<Arc<AppState>>,
+    Path(pubkey): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let mut nodes = state.nodes.write().await;
+    let Some(node) = nodes.get_mut(&pubkey) else {
+        return StatusCode::NOT_FOUND; // Never registered, or deregistered for silence
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let timestamp = header("x-artha-timestamp").and_then(|v| v.parse().ok());
+    if let Err(e) = state.liveness.write().await.accept(&pubkey, &body, timestamp, header("x-artha-signature"), state.clock.now_secs()) {
+        warn!("🚫 Refused heartbeat from {}: {}", pubkey, e);
+        return StatusCode::UNAUTHORIZED;
+    }
+    let Ok(heartbeat) = serde_json::from_slice::<NodeHeartbeat>(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let busy = heartbeat.gpus_running + heartbeat.gpus_pool_reserved;
+    node.current_load = if heartbeat.gpus_total == 0 {
+        1.0
+    } else {
+        (busy as f64 / heartbeat.gpus_total as f64).min(1.0)
+    };
+    if let Some(windows) = heartbeat.maintenance_windows {
+        node.maintenance_windows = upcoming_windows(windows, state.clock.now_secs());
+    }
+    StatusCode::OK
+}
+
+/// GET /queue - Pending queue depth against the current high-watermark
+async fn queue_status(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let node_count = state.nodes.read().await.len();
+    let pending = state.pending.read().await.len();
+    Json(serde_json::json!({
+        "pending": pending,
+        "waiting": state.waiting.read().await.len(), // Pending jobs no affordable node has taken yet
+        "undelivered": state.outbox.read().await.len(), // Assignments ai-jobd hasn't been told about
+        "high_watermark": state.admission.high_watermark(node_count),
+        "nodes": node_count,
+    }))
+}
+
+/// GET /vrf/key - The published key fair draws are verified against
+async fn vrf_public_key(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "public_key": state.vrf_published_key }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditDrawRequest {
+    pub job_id: String,
+    pub draw: FairDraw,
+}
+
+/// POST /vrf/verify - Check a placement's fair draw: the proof under the
+/// published key, its block against the chain, and its candidates against
+/// the ranking the job was drawn from
+async fn audit_draw(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AuditDrawRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let reason = if !fairness::audit(&state.vrf_published_key, &req.job_id, &req.draw) {
+        Some("proof does not verify under the published key")
+    } else if state.draws.read().await.get(&req.job_id) != Some(&req.draw) {
+        Some("not the draw the job was placed by")
+    } else if !state.contract_client.block_exists(&req.draw.block_hash).await.map_err(|e| {
+        error!("❌ Can't look up block {}: {}", req.draw.block_hash, e);
+        StatusCode::BAD_GATEWAY
+    })? {
+        Some("block is not on chain")
+    } else {
+        None
+    };
+    Ok(Json(serde_json::json!({ "valid": reason.is_none(), "reason": reason })))
+}
+
+/// GET /nodes/:pubkey/auctions - Open asks the node is invited to bid on
+async fn node_asks(State(state): State<Arc<AppState>>, Path(pubkey): Path<String>) -> Json<Vec<Ask>> {
+    Json(state.auctions.read().await.asks_for(&pubkey, state.clock.now_secs()))
+}
+
+/// POST /auctions/:auction_id/bids - A node's sealed bid, signed with its key
+async fn submit_bid(
+    State(state): State<Arc<AppState>>,
+    Path(auction_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, ServiceError> {
+    let signature = headers.get("x-artha-signature").and_then(|v| v.to_str().ok());
+    let placed = state.auctions.write().await.bid(&auction_id, &body, signature, state.clock.now_secs());
+    match placed {
+        Ok(pubkey) => {
+            info!("🔨 Sealed bid from {} in {}", pubkey, auction_id);
+            Ok(StatusCode::ACCEPTED)
+        }
+        Err(e) => {
+            let code = match e {
+                BidError::NoAuction => ErrorCode::NotFound,
+                BidError::NotInvited => ErrorCode::Forbidden,
+                BidError::Closed => ErrorCode::Gone,
+                BidError::BadSignature => ErrorCode::Unauthenticated,
+                BidError::Invalid(_) => ErrorCode::InvalidRequest,
+            };
+            Err(ServiceError::new(code, e.to_string()))
+        }
+    }
+}
+
+/// GET /nodes - Registered nodes with cordon and load, ordered by pubkey, one page at a time
+async fn list_nodes(
+    State(state): State<Arc<AppState>>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<NodeView>>, StatusCode> {
+    let nodes = state.nodes.read().await;
+    let cordons = state.cordons.read().await;
+    let assignments = state.job_assignments.read().await;
+    let liveness = state.liveness.read().await;
+    let certs = state.certs.read().await;
+    let now = state.clock.now_secs();
+    let views = nodes.values().map(|node| NodeView {
+        node: node.clone(),
+        cordon: cordons.get(&node.pubkey).filter(|cordon| cordon.active(now)).cloned(),
+        running_jobs: assignments.values().filter(|pubkey| **pubkey == node.pubkey).count(),
+        health: liveness.health(&node.pubkey, now),
+        last_heartbeat: liveness.last_seen(&node.pubkey),
+        certified: certs.is_certified(&node.pubkey),
+    });
+    Ok(Json(artha_paging::paginate(views, |view| view.node.pubkey.clone(), &page)?))
+}
+
+/// POST /nodes/:pubkey/drain - Stop placing new jobs on a node. Jobs opted in
+/// to live migration are moved off; the rest finish in place.
+async fn drain_node(
+    State(state): State<Arc<AppState>>,
+    Path(pubkey): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !state.nodes.read().await.contains_key(&pubkey) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state.cordons.write().await.insert(pubkey.clone(), Cordon {
+        mode: CordonMode::Drain,
+        reason: None,
+        until: None,
+        since: state.clock.now_secs(),
+    });
+    let running = state.job_assignments.read().await.values().filter(|node| **node == pubkey).count();
+    info!("🚧 Draining node {} ({} running jobs)", pubkey, running);
+    let migrating = migrate_jobs_off(&state, &pubkey, "scheduler:drain", "node drained").await;
+    Ok(Json(serde_json::json!({
+        "node_pubkey": pubkey,
+        "mode": CordonMode::Drain,
+        "running_jobs": running,
+        "migrating": migrating,
+    })))
+}
+
+/// POST /nodes/:pubkey/reclaim - The node's capacity is being taken back
+/// (e.g. a spot instance); drain it and move every job that can be moved
+async fn reclaim_node(
+    State(state): State<Arc<AppState>>,
+    Path(pubkey): Path<String>,
+    body: Option<Json<ReclaimRequest>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !state.nodes.read().await.contains_key(&pubkey) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let reason = body.and_then(|Json(req)| req.reason).unwrap_or_else(|| "spot reclaim".to_string());
+    state.cordons.write().await.insert(pubkey.clone(), Cordon {
+        mode: CordonMode::Drain,
+        reason: Some(reason.clone()),
+        until: None,
+        since: state.clock.now_secs(),
+    });
+    info!("🪂 Reclaiming node {}: {}", pubkey, reason);
+    let migrating = migrate_jobs_off(&state, &pubkey, "scheduler:reclaim", &reason).await;
+    Ok(Json(serde_json::json!({ "node_pubkey": pubkey, "mode": CordonMode::Drain, "migrating": migrating })))
+}
+
+/// Ask ai-jobd to live-migrate every job assigned to a node. Jobs that didn't
+/// opt in are declined by ai-jobd and keep running. Returns the jobs that are moving.
+async fn migrate_jobs_off(state: &Arc<AppState>, pubkey: &str, initiated_by: &str, reason: &str) -> Vec<String> {
+    let job_ids: Vec<String> = state
+        .job_assignments
+        .read()
+        .await
+        .iter()
+        .filter(|(_, node)| *node == pubkey)
+        .map(|(job_id, _)| job_id.clone())
+        .collect();
+
+    let client = reqwest::Client::new();
+    let mut migrating = Vec::new();
+    for job_id in job_ids {
+        let response = client
+            .post(format!("{}/job/{}/migrate", state.jobd_url, job_id))
+            .json(&serde_json::json!({ "initiated_by": initiated_by, "reason": reason }))
+            .send()
+            .await;
+        match response {
+            Ok(resp) if resp.status().is_success() => migrating.push(job_id),
+            Ok(resp) => info!("   ↪ Job {} stays on {} ({})", job_id, pubkey, resp.status()),
+            Err(e) => warn!("   ⚠️  Could not migrate job {}: {}", job_id, e),
+        }
+    }
+    migrating.sort();
+    migrating
+}
+
+/// Age every registered node's heartbeat. Nodes silent past the timeout are
+/// deregistered and their live-migratable jobs moved off. Returns the removed nodes.
+async fn sweep_nodes(state: &Arc<AppState>, now: u64) -> Vec<String> {
+    let sweep = {
+        let nodes = state.nodes.read().await;
+        state.liveness.write().await.sweep(nodes.keys(), now)
+    };
+    for pubkey in &sweep.unhealthy {
+        info!("💔 Node {} missed its heartbeats, excluded from placement", pubkey);
+    }
+    for pubkey in &sweep.deregistered {
+        state.nodes.write().await.remove(pubkey);
+        state.cordons.write().await.remove(pubkey);
+        let migrating = migrate_jobs_off(state, pubkey, "scheduler:deregister", "node stopped heartbeating").await;
+        info!("🪦 Deregistered silent node {} ({} jobs migrating)", pubkey, migrating.len());
+    }
+    sweep.deregistered
+}
+
+/// Run `sweep_nodes` every liveness sweep interval
+async fn liveness_sweep_loop(state: Arc<AppState>) {
+    let interval = Duration::from_secs(state.liveness.read().await.config().sweep_interval_secs);
+    loop {
+        state.clock.sleep(interval).await;
+        sweep_nodes(&state, state.clock.now_secs()).await;
+    }
+}
+
+/// DELETE /nodes/:pubkey/drain - Return a drained node to the candidate pool
+async fn undrain_node(
+    State(state): State<Arc<AppState>>,
+    Path(pubkey): Path<String>,
+) -> StatusCode {
+    let mut cordons = state.cordons.write().await;
+    if cordons.get(&pubkey).map(|cordon| &cordon.mode) != Some(&CordonMode::Drain) {
+        return StatusCode::NOT_FOUND;
+    }
+    cordons.remove(&pubkey);
+    info!("✅ Node {} no longer draining", pubkey);
+    StatusCode::NO_CONTENT
+}
+
+/// POST /nodes/:pubkey/maintenance - Enter or leave maintenance, optionally until a time
+async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    Path(pubkey): Path<String>,
+    Json(req): Json<MaintenanceRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !state.nodes.read().await.contains_key(&pubkey) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let mut cordons = state.cordons.write().await;
+    if req.enabled {
+        info!("🔧 Node {} entering maintenance{}", pubkey,
+            req.until.map(|until| format!(" until {}", until)).unwrap_or_default());
+        cordons.insert(pubkey.clone(), Cordon {
+            mode: CordonMode::Maintenance,
+            reason: req.reason,
+            until: req.until,
+            since: state.clock.now_secs(),
+        });
+    } else if cordons.get(&pubkey).map(|cordon| &cordon.mode) == Some(&CordonMode::Maintenance) {
+        info!("✅ Node {} leaving maintenance", pubkey);
+        cordons.remove(&pubkey);
+    }
+    Ok(Json(serde_json::json!({ "node_pubkey": pubkey, "cordon": cordons.get(&pubkey) })))
+}
+
+/// POST /reservations - Book GPUs for a future window; called by ai-jobd,
+/// which escrows the quoted cost or cancels the booking
+async fn book_reservation(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ReservationRequest>,
+) -> Result<Json<Reservation>, ServiceError> {
+    let now = state.clock.now_secs();
+    let capacity = reservable_capacity(&state, &req, now).await;
+    let booked = state.reservations.write().await.book(&req, &capacity, state.reservation_config.premium, now);
+    match booked {
+        Ok(reservation) => {
+            info!("📅 Reserved {} {} GPUs for {} from {} to {} ({} nodes)", reservation.gpu_count, reservation.gpu_type,
+                reservation.reservation_id, reservation.start, reservation.end, reservation.allocations.len());
+            Ok(Json(reservation))
+        }
+        Err(BookingError::Invalid(message)) => Err(ServiceError::new(ErrorCode::InvalidRequest, message)),
+        Err(BookingError::Exists) => Err(ServiceError::new(ErrorCode::Conflict, "Reservation already booked")),
+        Err(BookingError::Overbooked { requested, available }) => {
+            info!("🚫 Reservation {} wants {} {} GPUs, at most {} free in its window", req.reservation_id, requested, req.gpu_type, available);
+            Err(ServiceError::new(ErrorCode::Conflict, "Not enough projected capacity in the requested window")
+                .with_details(serde_json::json!({ "requested": requested, "available": available })))
+        }
+    }
+}
+
+/// What each node could give a reservation: its GPUs of the requested type
+/// less the jobs it typically runs, from when it is next out of a cordon
+async fn reservable_capacity(state: &AppState, req: &ReservationRequest, now: u64) -> Vec<NodeCapacity> {
+    let nodes = state.nodes.read().await;
+    let cordons = state.cordons.read().await;
+    let learner = state.learner.read().await;
+    nodes
+        .values()
+        .filter(|node| req.region.as_ref().is_none_or(|region| *region == node.region))
+        .map(|node| {
+            let matching = node.gpus.iter().filter(|gpu| gpu.available && gpu.gpu_type == req.gpu_type).count() as u32;
+            let typical = learner.typical_concurrency(&node.pubkey, now, state.reservation_config.load_lookback_secs);
+            NodeCapacity {
+                pubkey: node.pubkey.clone(),
+                gpus: matching.saturating_sub(typical.ceil() as u32),
+                price_per_gpu_sec: node.price_per_gpu_sec,
+                available_from: match cordons.get(&node.pubkey).filter(|cordon| cordon.active(now)) {
+                    Some(cordon) => cordon.until.unwrap_or(u64::MAX),
+                    None => 0,
+                },
+            }
+        })
+        .filter(|capacity| capacity.gpus > 0)
+        .collect()
+}
+
+/// DELETE /reservations/:id - Give a booking's capacity back, e.g. when its escrow failed
+async fn cancel_reservation(
+    State(state): State<Arc<AppState>>,
+    Path(reservation_id): Path<String>,
+) -> StatusCode {
+    match state.reservations.write().await.cancel(&reservation_id) {
+        Some(_) => {
+            info!("🗑️  Reservation {} cancelled", reservation_id);
+            StatusCode::NO_CONTENT
+        }
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+// Server setup
+
+#[tokio::main]
+async fn main() {
+    artha_log::init();
+    // Initialize with mock nodes
+    let mut mock_nodes = HashMap::new();
+    
+    // Node 1: Premium A100 in us-west
+    mock_nodes.insert("0xnode1".to_string(), Node {
+        pubkey: "0xnode1aabbccdd".to_string(),
         node_type: "compute-gpu".to_string(),
         region: "us-west".to_string(),
         gpus: vec![GpuInfo {
             gpu_type: "A100".to_string(),
             vram_gb: 40,
             available: true,
+            precisions: ["fp32", "fp16", "bf16", "int8"].map(String::from).to_vec(),
         }],
         uptime_percent: 99.95,
         reputation_score: 0.95,
@@ -544,6 +1790,7 @@ async fn main() {
         current_load: 0.3,
         capabilities: vec!["torch".to_string(), "tf".to_string(), "jax".to_string()],
         sla_tier: "premium".to_string(),
+        maintenance_windows: Vec::new(),
     });
 
     // Node 2: Economy RTX4090 in eu-central
@@ -555,6 +1802,7 @@ async fn main() {
             gpu_type: "RTX4090".to_string(),
             vram_gb: 24,
             available: true,
+            precisions: ["fp32", "fp16", "bf16", "int8"].map(String::from).to_vec(),
         }],
         uptime_percent: 98.5,
         reputation_score: 0.85,
@@ -562,6 +1810,7 @@ async fn main() {
         current_load: 0.6,
         capabilities: vec!["torch".to_string(), "sd".to_string()],
         sla_tier: "economy".to_string(),
+        maintenance_windows: Vec::new(),
     });
 
     // Node 3: Premium H100 in us-west
@@ -573,30 +1822,129 @@ async fn main() {
             gpu_type: "H100".to_string(),
             vram_gb: 80,
             available: true,
+            precisions: ["fp32", "fp16", "bf16", "int8"].map(String::from).to_vec(),
         }],
         uptime_percent: 99.99,
         reputation_score: 0.98,
         price_per_gpu_sec: 0.012,
         current_load: 0.1,
-        capabilities: vec!["torch".to_string(), "tf".to_string(), "jax".to_string(), "agent".to_string()],
+        capabilities: vec!["torch".to_string(), "tf".to_string(), "jax".to_string(), "agent".to_string(), "tee".to_string()],
         sla_tier: "premium".to_string(),
+        maintenance_windows: Vec::new(),
     });
 
+    let learner = PlacementLearner::load(LearningConfig::from_env());
+    info!("📈 Loaded {} placement outcomes", learner.len());
+
+    let rpc = RpcEndpoints::from_env();
+    let cert_sync = std::env::var(NODE_CERT_REGISTRY.env).is_ok();
+    let mut required = CONTRACTS.to_vec();
+    if cert_sync {
+        required.push(NODE_CERT_REGISTRY);
+    }
+    let contracts = ContractRegistry::resolve(&rpc, &required)
+        .await
+        .unwrap_or_else(|e| panic!("Invalid contract config: {}", e));
+    let contract_client = Arc::new(ContractClient::new(rpc).with_contracts(&contracts));
+    let vrf_key = VrfKey::from_env().unwrap_or_else(|e| panic!("Invalid VRF config: {}", e));
+    let vrf_published_key = fairness::published_key_from_env(&vrf_key).unwrap_or_else(|e| panic!("Invalid VRF config: {}", e));
+
+    // Without the registry's current set no node is a candidate until logs certify it
+    let mut certs = NodeCerts::default();
+    if cert_sync {
+        certs = NodeCerts::enforcing(
+            std::env::var("ARTHA_SCHED_MIN_NODE_STAKE_WEI")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1_000_000_000_000_000_000), // NodeCertRegistry.minStake
+        );
+        match contract_client.certified_nodes().await {
+            Ok(keys) => {
+                info!("🪪 {} nodes certified in NodeCertRegistry", keys.len());
+                certs.bootstrap(keys);
+            }
+            Err(e) => error!("❌ Could not read certified nodes: {}", e),
+        }
+    }
+
     let state = Arc::new(AppState {
         nodes: Arc::new(RwLock::new(mock_nodes)),
         job_assignments: Arc::new(RwLock::new(HashMap::new())),
-        contract_client: Arc::new(ContractClient::new("http://localhost:8545".to_string())),
+        pending: Arc::new(RwLock::new(PendingQueue::new())),
+        admission: AdmissionConfig::from_env(),
+        contract_client,
         svdb_client: Arc::new(SvdbClient::new("http://localhost:8080".to_string())),
+        placements: Arc::new(RwLock::new(HashMap::new())),
+        learner: Arc::new(RwLock::new(learner)),
+        rejections: Arc::new(RwLock::new(HashMap::new())),
+        cordons: Arc::new(RwLock::new(HashMap::new())),
+        liveness: Arc::new(RwLock::new(LivenessTracker::new(LivenessConfig::from_env()))),
+        jobd_url: std::env::var("ARTHA_JOBD_URL").unwrap_or_else(|_| "http://localhost:8081".to_string()),
+        waiting: Arc::new(RwLock::new(HashMap::new())),
+        price_feed: Arc::new(PriceFeed::from_env()),
+        reservations: Arc::new(RwLock::new(ReservationBook::new())),
+        reservation_config: ReservationConfig::from_env(),
+        vrf_key: Arc::new(vrf_key),
+        vrf_published_key,
+        draws: Arc::new(RwLock::new(HashMap::new())),
+        notify: NotifyConfig::from_env(),
+        outbox: Arc::new(RwLock::new(Outbox::default())),
+        auction: AuctionConfig::from_env(),
+        auctions: Arc::new(RwLock::new(AuctionBook::default())),
+        certs: Arc::new(RwLock::new(certs)),
+        clock: artha_clock::system_clock(),
     });
 
+    // Background task: refresh spot prices and re-evaluate jobs waiting on them
+    let reprice_secs: u64 = std::env::var("ARTHA_SCHED_REPRICE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        loop {
+            state_clone.clock.sleep(Duration::from_secs(reprice_secs)).await;
+            let placed = reevaluate_waiting(&state_clone).await;
+            if !placed.is_empty() {
+                info!("💸 Placed {} waiting jobs after a price change", placed.len());
+            }
+        }
+    });
+
+    // Background task: mark silent nodes unhealthy and deregister dead ones
+    tokio::spawn(liveness_sweep_loop(state.clone()));
+
+    // Background task: redeliver assignment notifications ai-jobd missed
+    tokio::spawn(redeliver_loop(state.clone()));
+
     let app = Router::new()
         .route("/schedule", post(schedule_job))
+        .route("/schedule/simulate", post(simulate_schedule))
+        .route("/schedule/:job_id/release", post(release_job))
+        .route("/schedule/:job_id/outcome", post(record_outcome))
+        .route("/schedule/:job_id/reject", post(reject_assignment))
+        .route("/learning/stats", axum::routing::get(learning_stats))
         .route("/nodes/register", post(register_node))
+        .route("/chain/events", post(chain_events)) // From the node's event forwarder
         .route("/nodes", axum::routing::get(list_nodes))
+        .route("/nodes/:pubkey/heartbeat", post(node_heartbeat))
+        .route("/nodes/:pubkey/drain", post(drain_node).delete(undrain_node))
+        .route("/nodes/:pubkey/reclaim", post(reclaim_node))
+        .route("/nodes/:pubkey/maintenance", post(set_maintenance))
+        .route("/nodes/:pubkey/auctions", axum::routing::get(node_asks))
+        .route("/auctions/:auction_id/bids", post(submit_bid))
+        .route("/reservations", post(book_reservation))
+        .route("/reservations/:id", axum::routing::delete(cancel_reservation))
+        .route("/queue", axum::routing::get(queue_status))
+        .route("/notifications/outbox", axum::routing::get(notification_outbox))
+        .route("/vrf/key", axum::routing::get(vrf_public_key))
+        .route("/vrf/verify", post(audit_draw))
         .route("/health", axum::routing::get(|| async { "OK" }))
-        .with_state(state);
+        .layer(axum::middleware::map_response(artha_errors::normalize))
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 AI Scheduler starting on :8083");
+    info!("🚀 AI Scheduler starting on :8083");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8083").await.unwrap();
     axum::serve(listener, app).await.unwrap();
@@ -605,6 +1953,7 @@ async fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use artha_clock::{Clock, ManualClock};
 
     #[test]
     fn test_gpu_score() {
@@ -620,9 +1969,14 @@ mod tests {
                 max_price_per_sec: 0.01,
                 preferred_regions: vec![],
                 required_capabilities: vec![],
+                tee_required: false,
+                required_precisions: vec![],
             },
             budget: 1000,
             submitter_did: "did:test".to_string(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
         };
 
         let node = Node {
@@ -633,6 +1987,7 @@ mod tests {
                 gpu_type: "A100".to_string(),
                 vram_gb: 40,
                 available: true,
+                precisions: vec![],
             }],
             uptime_percent: 99.9,
             reputation_score: 0.95,
@@ -640,10 +1995,1574 @@ mod tests {
             current_load: 0.3,
             capabilities: vec!["torch".to_string()],
             sla_tier: "premium".to_string(),
+            maintenance_windows: Vec::new(),
         };
 
         let score = compute_gpu_score(&job, &node);
         assert!(score > 0.7); // Should score high (preferred GPU + extra VRAM)
     }
-}
 
+    #[test]
+    fn test_tee_required_is_hard_constraint() {
+        let mut job = Job {
+            job_id: "test".to_string(),
+            job_type: "train".to_string(),
+            model_id: None,
+            dataset_id: None,
+            requirements: JobRequirements {
+                min_gpu_vram_gb: 24,
+                preferred_gpu_types: vec![],
+                min_uptime_percent: 99.0,
+                max_price_per_sec: 0.01,
+                preferred_regions: vec![],
+                required_capabilities: vec![],
+                tee_required: true,
+                required_precisions: vec![],
+            },
+            budget: 1000,
+            submitter_did: "did:test".to_string(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+
+        let mut node = Node {
+            pubkey: "0xtest".to_string(),
+            node_type: "compute-gpu".to_string(),
+            region: "us-west".to_string(),
+            gpus: vec![GpuInfo {
+                gpu_type: "H100".to_string(),
+                vram_gb: 80,
+                available: true,
+                precisions: vec![],
+            }],
+            uptime_percent: 99.99,
+            reputation_score: 0.98,
+            price_per_gpu_sec: 0.005,
+            current_load: 0.0,
+            capabilities: vec!["torch".to_string()],
+            sla_tier: "premium".to_string(),
+            maintenance_windows: Vec::new(),
+        };
+
+        assert!(!meets_requirements(&job, &node));
+
+        node.capabilities.push(TEE_CAPABILITY.to_string());
+        assert!(meets_requirements(&job, &node));
+
+        job.requirements.tee_required = false;
+        node.capabilities.retain(|c| c != TEE_CAPABILITY);
+        assert!(meets_requirements(&job, &node));
+    }
+
+    #[tokio::test]
+    async fn test_bf16_job_skips_gpus_without_bf16_and_prefers_native_support() {
+        let state = scoring_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9");
+        let mut job = Job {
+            job_id: "test".to_string(),
+            job_type: "train".to_string(),
+            model_id: None,
+            dataset_id: None,
+            requirements: JobRequirements {
+                min_gpu_vram_gb: 24,
+                preferred_gpu_types: vec![],
+                min_uptime_percent: 99.0,
+                max_price_per_sec: 0.01,
+                preferred_regions: vec![],
+                required_capabilities: vec![],
+                tee_required: false,
+                required_precisions: vec!["bf16".to_string()],
+            },
+            budget: 1000,
+            submitter_did: "did:test".to_string(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let with_precisions = |pubkey: &str, precisions: &[&str]| {
+            let mut node = test_node(pubkey);
+            node.gpus[0].precisions = precisions.iter().map(|p| p.to_string()).collect();
+            node
+        };
+        let no_bf16 = with_precisions("0xnode1aabbccddeeff00112233445566778899", &["fp32", "fp16", "int8"]);
+        let bf16 = with_precisions("0xnode2eeffgghhiijj00112233445566778899", &["fp32", "fp16", "bf16", "int8"]);
+        let unreported = with_precisions("0xnode3iijjkkll00112233445566778899aabb", &[]);
+
+        assert!(!meets_requirements(&job, &no_bf16));
+        assert!(meets_requirements(&job, &bf16));
+        assert!(meets_requirements(&job, &unreported));
+
+        // Reported native support outranks a GPU that may only emulate it
+        let native = score_node(&state, &job, &bf16).await.unwrap();
+        let unknown = score_node(&state, &job, &unreported).await.unwrap();
+        assert!(native.gpu_score > unknown.gpu_score);
+        assert!(native.total_score > unknown.total_score);
+
+        // Without a precision requirement neither the filter nor the bonus applies
+        job.requirements.required_precisions.clear();
+        assert!(meets_requirements(&job, &no_bf16));
+        assert_eq!(compute_gpu_score(&job, &bf16), compute_gpu_score(&job, &unreported));
+    }
+
+    fn test_node(pubkey: &str) -> Node {
+        Node {
+            pubkey: pubkey.to_string(),
+            node_type: "compute-gpu".to_string(),
+            region: "us-west".to_string(),
+            gpus: vec![GpuInfo {
+                gpu_type: "A100".to_string(),
+                vram_gb: 40,
+                available: true,
+                precisions: vec![],
+            }],
+            uptime_percent: 99.9,
+            reputation_score: 0.95,
+            price_per_gpu_sec: 0.008,
+            current_load: 0.0,
+            capabilities: vec!["torch".to_string()],
+            sla_tier: "premium".to_string(),
+            maintenance_windows: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_high_watermark_scales_with_cluster() {
+        let config = AdmissionConfig { pending_per_node: 8, min_watermark: 4, retry_after_secs: 30 };
+        assert_eq!(config.high_watermark(0), 4);
+        assert_eq!(config.high_watermark(1), 8);
+        assert_eq!(config.high_watermark(10), 80);
+    }
+
+    fn test_learning_config() -> LearningConfig {
+        LearningConfig { window: 1000, prior_samples: 5.0, path: None }
+    }
+
+    fn test_reservation_config() -> ReservationConfig {
+        ReservationConfig { premium: 1.25, lead_secs: 3600, load_lookback_secs: 7 * 86_400 }
+    }
+
+    fn test_liveness_config() -> LivenessConfig {
+        LivenessConfig { unhealthy_after_secs: 60, deregister_after_secs: 600, max_skew_secs: 30, sweep_interval_secs: 15 }
+    }
+
+    /// One attempt, so tests on a still clock never wait on a backoff
+    fn test_notify_config() -> NotifyConfig {
+        NotifyConfig { attempts: 1, backoff_ms: 250, redeliver_secs: 15, concurrency: 4 }
+    }
+
+    /// Auctions on, ranked by price per unit of reputation alone
+    fn test_auction_config() -> AuctionConfig {
+        AuctionConfig { enabled: true, window_secs: 5, price_weight: 1.0, eta_weight: 0.0, reputation_weight: 1.0 }
+    }
+
+    #[tokio::test]
+    async fn test_submissions_past_watermark_get_retry_hint() {
+        let rpc_url = abi::DryRunRpc::spawn().await.url();
+
+        let mut nodes = HashMap::new();
+        nodes.insert("0xnode1aabbccddeeff00112233445566778899".to_string(), test_node("0xnode1aabbccddeeff00112233445566778899"));
+        let state = Arc::new(AppState {
+            nodes: Arc::new(RwLock::new(nodes)),
+            job_assignments: Arc::new(RwLock::new(HashMap::new())),
+            pending: Arc::new(RwLock::new(PendingQueue::new())),
+            admission: AdmissionConfig { pending_per_node: 2, min_watermark: 1, retry_after_secs: 15 },
+            contract_client: Arc::new(ContractClient::new(rpc_url)),
+            svdb_client: Arc::new(SvdbClient::new("http://127.0.0.1:9".to_string())),
+            placements: Arc::new(RwLock::new(HashMap::new())),
+            learner: Arc::new(RwLock::new(PlacementLearner::new(test_learning_config()))),
+            rejections: Arc::new(RwLock::new(HashMap::new())),
+            cordons: Arc::new(RwLock::new(HashMap::new())),
+            liveness: Arc::new(RwLock::new(LivenessTracker::new(test_liveness_config()))),
+            jobd_url: "http://127.0.0.1:9".to_string(),
+            waiting: Arc::new(RwLock::new(HashMap::new())),
+            price_feed: Arc::new(PriceFeed::new(None)),
+            reservations: Arc::new(RwLock::new(ReservationBook::new())),
+            reservation_config: test_reservation_config(),
+            vrf_key: Arc::new(VrfKey::from_bytes(&[7; 32]).unwrap()),
+            vrf_published_key: VrfKey::from_bytes(&[7; 32]).unwrap().public_key(),
+            draws: Arc::new(RwLock::new(HashMap::new())),
+            notify: test_notify_config(),
+            outbox: Arc::new(RwLock::new(Outbox::default())),
+            auction: test_auction_config(),
+            auctions: Arc::new(RwLock::new(AuctionBook::default())),
+            certs: Arc::new(RwLock::new(NodeCerts::default())),
+            clock: ManualClock::new(1_700_000_000).shared(),
+        });
+        let app = Router::new()
+            .route("/schedule", post(schedule_job))
+            .route("/schedule/:job_id/release", post(release_job))
+            .route("/nodes/register", post(register_node))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let schedule = |job: &str| {
+            client.post(format!("{}/schedule", base))
+                .json(&serde_json::json!({ "job_id": format!("{:0>32}", job) }))
+                .send()
+        };
+
+        // One node -> watermark of 2: capacity below it schedules
+        assert_eq!(schedule("job-a").await.unwrap().status().as_u16(), 200);
+        assert_eq!(schedule("job-b").await.unwrap().status().as_u16(), 200);
+
+        // Past the watermark: 429 with a retry hint, nothing enqueued
+        let rejected = schedule("job-c").await.unwrap();
+        assert_eq!(rejected.status().as_u16(), 429);
+        assert_eq!(rejected.headers().get("retry-after").unwrap(), "15");
+        let body: serde_json::Value = rejected.json().await.unwrap();
+        assert_eq!(body["error"], "queue_full");
+        assert_eq!(body["code"], 2003);
+        assert_eq!(body["retry_after_secs"], 15);
+        assert_eq!(body["details"]["high_watermark"], 2);
+        assert_eq!(state.pending.read().await.len(), 2);
+
+        // Releasing a job frees a slot
+        let released = client.post(format!("{}/schedule/{:0>32}/release", base, "job-a")).send().await.unwrap();
+        assert_eq!(released.status().as_u16(), 204);
+        assert_eq!(schedule("job-c").await.unwrap().status().as_u16(), 200);
+        assert_eq!(schedule("job-d").await.unwrap().status().as_u16(), 429);
+
+        // Growing the cluster raises the watermark
+        client.post(format!("{}/nodes/register", base))
+            .json(&test_node("0xnode2eeffgghhiijj00112233445566778899"))
+            .send().await.unwrap();
+        assert_eq!(schedule("job-d").await.unwrap().status().as_u16(), 200);
+    }
+
+    fn outcome(node: &str, class: &str, duration_secs: u64, status: PlacementStatus) -> PlacementOutcome {
+        PlacementOutcome {
+            job_id: format!("job-{}", duration_secs),
+            job_class: class.to_string(),
+            node_pubkey: node.to_string(),
+            queue_wait_secs: 20,
+            duration_secs,
+            peak_vram_mb: Some(30_000),
+            status,
+            failure_reason: None,
+            recorded_at: 0,
+        }
+    }
+
+    /// Deterministic noisy samples: duration around 600s (+/-60), one run in ten fails
+    fn synthetic_outcome(i: u64) -> PlacementOutcome {
+        let noise = (i * 37 % 121) as i64 - 60;
+        let status = if i % 10 == 9 { PlacementStatus::Failed } else { PlacementStatus::Completed };
+        outcome("0xnodeA", "train:vram24:std", (600 + noise) as u64, status)
+    }
+
+    #[test]
+    fn test_predictions_converge_to_ground_truth() {
+        let mut learner = PlacementLearner::new(test_learning_config());
+        // Other nodes run the class much faster, pulling the early prior away from the truth
+        for _ in 0..20 {
+            learner.record(outcome("0xnodeB", "train:vram24:std", 100, PlacementStatus::Completed));
+        }
+
+        let mut errors = Vec::new();
+        for i in 0..400 {
+            learner.record(synthetic_outcome(i));
+            if [4, 40, 399].contains(&i) {
+                let prediction = learner.predict("0xnodeA", "train:vram24:std");
+                let duration = prediction.duration.unwrap();
+                errors.push(((duration.expected_secs - 600.0).abs(), (prediction.failure_probability - 0.1).abs()));
+            }
+        }
+        assert!(errors[0].0 > errors[1].0 && errors[1].0 > errors[2].0, "{:?}", errors);
+        assert!(errors[2].0 < 5.0);
+        assert!(errors[2].1 < 0.02);
+
+        let prediction = learner.predict("0xnodeA", "train:vram24:std").duration.unwrap();
+        assert_eq!(prediction.basis, learning::PredictionBasis::NodeClass);
+        assert!(prediction.lower_secs < 600.0 && 600.0 < prediction.upper_secs);
+        assert!(prediction.upper_secs - prediction.lower_secs < 250.0);
+    }
+
+    #[test]
+    fn test_cold_start_falls_back_to_class_then_global() {
+        let mut learner = PlacementLearner::new(test_learning_config());
+        let nothing = learner.predict("0xnodeA", "train:vram24:std");
+        assert!(nothing.duration.is_none());
+        assert!(nothing.queue_wait_secs.is_none());
+        assert_eq!(nothing.failure_probability, 0.0);
+
+        for _ in 0..10 {
+            learner.record(outcome("0xnodeA", "train:vram24:std", 600, PlacementStatus::Completed));
+            learner.record(outcome("0xnodeA", "infer:vram16:std", 20, PlacementStatus::Completed));
+        }
+
+        // Unseen node, known class: the class average, lightly shrunk toward global
+        let class = learner.predict("0xnodeB", "train:vram24:std").duration.unwrap();
+        assert_eq!(class.basis, learning::PredictionBasis::Class);
+        assert_eq!(class.samples, 0);
+        assert!((class.expected_secs - (6000.0 + 5.0 * 310.0) / 15.0).abs() < 1e-6);
+
+        // Unseen class: the global average
+        let global = learner.predict("0xnodeB", "agent:vram80:tee").duration.unwrap();
+        assert_eq!(global.basis, learning::PredictionBasis::Global);
+        assert!((global.expected_secs - 310.0).abs() < 1e-6);
+        assert_eq!(learner.predict("0xnodeB", "agent:vram80:tee").queue_wait_secs, Some(20.0));
+
+        // The rolling window bounds the store and the models follow it
+        let mut small = PlacementLearner::new(LearningConfig { window: 5, ..test_learning_config() });
+        for d in [1000, 1000, 1000, 10, 10, 10, 10, 10] {
+            small.record(outcome("0xnodeA", "train:vram24:std", d, PlacementStatus::Completed));
+        }
+        assert_eq!(small.len(), 5);
+        assert!((small.predict("0xnodeA", "train:vram24:std").duration.unwrap().expected_secs - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_outcomes_persist_across_restarts() {
+        let path = std::env::temp_dir().join(format!("artha-sched-outcomes-{}.json", std::process::id()));
+        let config = LearningConfig { window: 3, prior_samples: 5.0, path: Some(path.to_string_lossy().to_string()) };
+
+        let mut learner = PlacementLearner::new(config.clone());
+        for d in [100, 200, 300, 400] {
+            learner.record(outcome("0xnodeA", "train:vram24:std", d, PlacementStatus::Completed));
+        }
+
+        let reloaded = PlacementLearner::load(config);
+        assert_eq!(reloaded.len(), 3);
+        assert_eq!(
+            reloaded.predict("0xnodeA", "train:vram24:std"),
+            learner.predict("0xnodeA", "train:vram24:std")
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    fn scoring_state(rpc_url: String, svdb_url: &str) -> Arc<AppState> {
+        scoring_state_on(rpc_url, svdb_url, ManualClock::new(1_700_000_000))
+    }
+
+    fn scoring_state_on(rpc_url: String, svdb_url: &str, clock: ManualClock) -> Arc<AppState> {
+        let mut nodes = HashMap::new();
+        for pubkey in ["0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899"] {
+            nodes.insert(pubkey.to_string(), test_node(pubkey));
+        }
+        Arc::new(AppState {
+            nodes: Arc::new(RwLock::new(nodes)),
+            job_assignments: Arc::new(RwLock::new(HashMap::new())),
+            pending: Arc::new(RwLock::new(PendingQueue::new())),
+            admission: AdmissionConfig { pending_per_node: 8, min_watermark: 4, retry_after_secs: 15 },
+            contract_client: Arc::new(ContractClient::new(rpc_url)),
+            svdb_client: Arc::new(SvdbClient::new(svdb_url.to_string())),
+            placements: Arc::new(RwLock::new(HashMap::new())),
+            learner: Arc::new(RwLock::new(PlacementLearner::new(test_learning_config()))),
+            rejections: Arc::new(RwLock::new(HashMap::new())),
+            cordons: Arc::new(RwLock::new(HashMap::new())),
+            liveness: Arc::new(RwLock::new(LivenessTracker::new(test_liveness_config()))),
+            jobd_url: "http://127.0.0.1:9".to_string(),
+            waiting: Arc::new(RwLock::new(HashMap::new())),
+            price_feed: Arc::new(PriceFeed::new(None)),
+            reservations: Arc::new(RwLock::new(ReservationBook::new())),
+            reservation_config: test_reservation_config(),
+            vrf_key: Arc::new(VrfKey::from_bytes(&[7; 32]).unwrap()),
+            vrf_published_key: VrfKey::from_bytes(&[7; 32]).unwrap().public_key(),
+            draws: Arc::new(RwLock::new(HashMap::new())),
+            notify: test_notify_config(),
+            outbox: Arc::new(RwLock::new(Outbox::default())),
+            auction: test_auction_config(),
+            auctions: Arc::new(RwLock::new(AuctionBook::default())),
+            certs: Arc::new(RwLock::new(NodeCerts::default())),
+            clock: clock.shared(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_job_lookups_share_one_rpc_until_assignment() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let client = ContractClient::new(rpc.url());
+        let job_id = format!("{:0>32}", "job-cached");
+        let jobs = abi::ai_job_manager();
+
+        // Schedule and assign both read the job inside the TTL
+        client.get_job(&job_id).await.unwrap();
+        client.get_job(&job_id).await.unwrap();
+        assert_eq!(rpc.calls_of(&jobs, "getJob").len(), 1);
+
+        // Assigning changes the job's status on-chain, so the next read goes back to the chain
+        client.assign_job(&job_id, &format!("{:0>32}", "node-1")).await.unwrap();
+        client.get_job(&job_id).await.unwrap();
+        assert_eq!(rpc.calls_of(&jobs, "getJob").len(), 2);
+        assert_eq!(client.jobs.stats(), artha_cache::CacheStats { hits: 1, misses: 2 });
+    }
+
+    #[tokio::test]
+    async fn test_failure_prone_node_scores_lower() {
+        let state = scoring_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9");
+        let mut job = Job {
+            job_id: "test".to_string(),
+            job_type: "train".to_string(),
+            model_id: None,
+            dataset_id: None,
+            requirements: JobRequirements {
+                min_gpu_vram_gb: 24,
+                preferred_gpu_types: vec![],
+                min_uptime_percent: 99.0,
+                max_price_per_sec: 0.01,
+                preferred_regions: vec![],
+                required_capabilities: vec![],
+                tee_required: false,
+                required_precisions: vec![],
+            },
+            budget: 1000,
+            submitter_did: "did:test".to_string(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let flaky = test_node("0xnode1aabbccddeeff00112233445566778899");
+        let steady = test_node("0xnode2eeffgghhiijj00112233445566778899");
+
+        let before = score_node(&state, &job, &flaky).await.unwrap().total_score;
+        assert_eq!(before, score_node(&state, &job, &steady).await.unwrap().total_score);
+
+        // The flaky node OOMs every third large job
+        {
+            let mut learner = state.learner.write().await;
+            for i in 0..30 {
+                let status = if i % 3 == 0 { PlacementStatus::Failed } else { PlacementStatus::Completed };
+                learner.record(outcome(&flaky.pubkey, "train:vram24:std", 600, status));
+                learner.record(outcome(&steady.pubkey, "train:vram24:std", 600, PlacementStatus::Completed));
+            }
+        }
+
+        let flaky_score = score_node(&state, &job, &flaky).await.unwrap();
+        let steady_score = score_node(&state, &job, &steady).await.unwrap();
+        assert!(flaky_score.failure_probability > 0.25);
+        assert!(steady_score.failure_probability < 0.1);
+        assert!(flaky_score.total_score < before);
+        assert!(flaky_score.total_score < steady_score.total_score);
+
+        // The penalty is per job class: unseen classes fall back to the global rate
+        job.requirements.min_gpu_vram_gb = 80;
+        let other_class = score_node(&state, &job, &flaky).await.unwrap().failure_probability;
+        assert!((other_class - 10.0 / 60.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_schedule_and_simulate_return_predictions() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let app = Router::new()
+            .route("/schedule", post(schedule_job))
+            .route("/schedule/simulate", post(simulate_schedule))
+            .route("/schedule/:job_id/outcome", post(record_outcome))
+            .route("/learning/stats", axum::routing::get(learning_stats))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+
+        // Cold start: nothing learned yet
+        let first: serde_json::Value = client.post(format!("{}/schedule", base))
+            .json(&serde_json::json!({ "job_id": format!("{:0>32}", "job-1") }))
+            .send().await.unwrap().json().await.unwrap();
+        assert!(first["predicted_duration"].is_null());
+        assert_eq!(first["failure_probability"], 0.0);
+
+        // Report its outcome, then build some history for the class
+        let node = first["assigned_node"].as_str().unwrap().to_string();
+        let scheduled = state.placements.read().await[&format!("{:0>32}", "job-1")].scheduled_at;
+        let reported = client.post(format!("{}/schedule/{:0>32}/outcome", base, "job-1"))
+            .json(&serde_json::json!({
+                "status": "completed",
+                "started_at": scheduled + 12,
+                "completed_at": scheduled + 612,
+                "peak_vram_mb": 31_000,
+            }))
+            .send().await.unwrap();
+        assert_eq!(reported.status().as_u16(), 204);
+        {
+            let mut learner = state.learner.write().await;
+            for pubkey in state.nodes.read().await.keys() {
+                for _ in 0..9 {
+                    learner.record(PlacementOutcome { queue_wait_secs: 12, ..outcome(pubkey, "train:vram24:std", 600, PlacementStatus::Completed) });
+                }
+            }
+        }
+
+        let second: serde_json::Value = client.post(format!("{}/schedule", base))
+            .json(&serde_json::json!({ "job_id": format!("{:0>32}", "job-2") }))
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(second["predicted_duration"]["expected_secs"], 600.0);
+        assert_eq!(second["predicted_duration"]["basis"], "node_class");
+        let samples = if second["assigned_node"] == node.as_str() { 10 } else { 9 };
+        assert_eq!(second["predicted_duration"]["samples"], samples);
+        let start = second["estimated_start_time"].as_u64().unwrap();
+        assert_eq!(start, state.clock.now_secs() + 12);
+
+        let simulated: serde_json::Value = client.post(format!("{}/schedule/simulate", base))
+            .json(&serde_json::json!({ "job_id": format!("{:0>32}", "job-3") }))
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(simulated["job_class"], "train:vram24:std");
+        let candidates = simulated["candidates"].as_array().unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.iter().all(|c| c["predicted_duration"]["expected_secs"].as_f64().is_some()));
+        assert!(!state.placements.read().await.contains_key(&format!("{:0>32}", "job-3")));
+
+        // Only real placements reach the chain, encoded as AIJobManager expects
+        let jobs = abi::ai_job_manager();
+        assert_eq!(rpc.calls_of(&jobs, "assignJob"), vec![
+            vec![abi::abi_encode_bytes32(&format!("{:0>32}", "job-1")), abi::abi_encode_bytes32(&node)],
+            vec![abi::abi_encode_bytes32(&format!("{:0>32}", "job-2")), abi::abi_encode_bytes32(second["assigned_node"].as_str().unwrap())],
+        ]);
+        assert_eq!(rpc.calls_of(&jobs, "getJob")[2], vec![abi::abi_encode_bytes32(&format!("{:0>32}", "job-3"))]);
+
+        let stats: serde_json::Value = client.get(format!("{}/learning/stats", base))
+            .send().await.unwrap().json().await.unwrap();
+        let reported = stats.as_array().unwrap().iter().find(|s| s["node_pubkey"] == node.as_str()).unwrap();
+        assert_eq!(reported["runs"], 10);
+        assert_eq!(reported["max_peak_vram_mb"], 31_000);
+
+        // Outcomes for unknown placements are rejected
+        let unknown = client.post(format!("{}/schedule/nope/outcome", base))
+            .json(&serde_json::json!({ "status": "failed" }))
+            .send().await.unwrap();
+        assert_eq!(unknown.status().as_u16(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_locality_follows_verified_replicas() {
+        // SVDB whose verified regions change as replication completes
+        let verified = Arc::new(std::sync::Mutex::new(vec!["eu-west".to_string()]));
+        let lookups = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (verified_in, lookups_in) = (verified.clone(), lookups.clone());
+        let svdb = Router::new().route("/svdb/:cid/replication", axum::routing::get(
+            move |axum::extract::Path(cid): axum::extract::Path<String>| {
+                lookups_in.lock().unwrap().push(cid);
+                let regions = verified_in.lock().unwrap().clone();
+                async move { Json(serde_json::json!({ "verified_regions": regions, "replicas": [] })) }
+            },
+        ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let svdb_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, svdb).await.unwrap() });
+
+        let state = scoring_state("http://127.0.0.1:9".to_string(), &svdb_url);
+        let job = Job {
+            job_id: "test".to_string(),
+            job_type: "train".to_string(),
+            model_id: None,
+            dataset_id: Some("artha://dataset+a/b".to_string()),
+            requirements: JobRequirements {
+                min_gpu_vram_gb: 24,
+                preferred_gpu_types: vec![],
+                min_uptime_percent: 99.0,
+                max_price_per_sec: 0.01,
+                preferred_regions: vec![],
+                required_capabilities: vec![],
+                tee_required: false,
+                required_precisions: vec![],
+            },
+            budget: 1000,
+            submitter_did: "did:test".to_string(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let node = test_node("0xnode1aabbccddeeff00112233445566778899");
+
+        assert_eq!(compute_locality_score(&state, &job, &node).await.unwrap(), 0.0);
+        assert_eq!(lookups.lock().unwrap()[0], "dataset+a/b");
+
+        // A replica in the node's region is verified
+        verified.lock().unwrap().push(node.region.clone());
+        assert_eq!(compute_locality_score(&state, &job, &node).await.unwrap(), 0.5);
+
+        // Unreachable SVDB means no locality, not a guess
+        let offline = scoring_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9");
+        assert_eq!(compute_locality_score(&offline, &job, &node).await.unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_assignment_is_rescheduled_elsewhere() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let reports: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let recorded = reports.clone();
+        let jobd = Router::new().route("/job/:id/progress", post(move |Json(body): Json<serde_json::Value>| {
+            recorded.lock().unwrap().push(body);
+            async { StatusCode::OK }
+        }));
+        let jobd_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        Arc::get_mut(&mut state).unwrap().jobd_url = format!("http://{}", jobd_listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(jobd_listener, jobd).await.unwrap() });
+        let app = Router::new()
+            .route("/schedule", post(schedule_job))
+            .route("/schedule/:job_id/reject", post(reject_assignment))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let job_id = format!("{:0>32}", "job-reject");
+        let scheduled: serde_json::Value = client.post(format!("{}/schedule", base))
+            .json(&serde_json::json!({ "job_id": job_id }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        let first = scheduled["assigned_node"].as_str().unwrap().to_string();
+
+        // Only the assigned node can reject
+        let reject = |node: &str| {
+            client.post(format!("{}/schedule/{}/reject", base, job_id))
+                .json(&serde_json::json!({ "node_pubkey": node, "reasons": ["framework mxnet not offered"] }))
+                .send()
+        };
+        assert_eq!(reject("0xsomeone-else").await.unwrap().status().as_u16(), 404);
+        assert_eq!(reject(&first).await.unwrap().status().as_u16(), 202);
+
+        // The job is re-placed on the other node and keeps its pending slot
+        let mut reassigned = None;
+        for _ in 0..50 {
+            reassigned = state.job_assignments.read().await.get(&job_id).cloned();
+            if reassigned.is_some() {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+        let second = reassigned.expect("job was not rescheduled");
+        assert_ne!(second, first);
+        assert_eq!(state.pending.read().await.len(), 1);
+        assert_eq!(state.nodes.read().await[&first].current_load, 0.0);
+
+        // With every node rejected, the job leaves the queue
+        assert_eq!(reject(&second).await.unwrap().status().as_u16(), 202);
+        for _ in 0..50 {
+            if state.pending.read().await.len() == 0 {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(state.pending.read().await.len(), 0);
+        assert!(state.job_assignments.read().await.get(&job_id).is_none());
+        assert!(state.rejections.read().await.get(&job_id).is_none());
+
+        // ...and ai-jobd is told it failed as unschedulable
+        for _ in 0..50 {
+            if !reports.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        }
+        let reports = reports.lock().unwrap().clone();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0]["status"], "Failed");
+        assert_eq!(reports[0]["terminal_reason"]["kind"], "unschedulable");
+        assert!(reports[0]["terminal_reason"]["detail"].as_str().unwrap().contains(&second));
+    }
+
+    #[tokio::test]
+    async fn test_drained_and_maintenance_nodes_get_no_placements() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let app = Router::new()
+            .route("/schedule", post(schedule_job))
+            .route("/nodes", axum::routing::get(list_nodes))
+            .route("/nodes/:pubkey/drain", post(drain_node).delete(undrain_node))
+            .route("/nodes/:pubkey/maintenance", post(set_maintenance))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        let schedule = |job: &str| {
+            client.post(format!("{}/schedule", base))
+                .json(&serde_json::json!({ "job_id": format!("{:0>32}", job) }))
+                .send()
+        };
+
+        assert_eq!(client.post(format!("{}/nodes/{}/drain", base, node1)).send().await.unwrap().status().as_u16(), 200);
+        let placed: serde_json::Value = schedule("job-a").await.unwrap().json().await.unwrap();
+        assert_eq!(placed["assigned_node"], node2);
+
+        // Both cordoned: nothing to place on
+        client.post(format!("{}/nodes/{}/maintenance", base, node2))
+            .json(&serde_json::json!({ "enabled": true, "reason": "driver upgrade" }))
+            .send().await.unwrap();
+        assert_eq!(schedule("job-b").await.unwrap().status().as_u16(), 503);
+
+        let nodes: Page<serde_json::Value> = client.get(format!("{}/nodes", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(nodes.total, 2);
+        let view = nodes.items.iter().find(|n| n["pubkey"] == node2).unwrap();
+        assert_eq!(view["cordon"]["mode"], "maintenance");
+        assert_eq!(view["cordon"]["reason"], "driver upgrade");
+        assert_eq!(view["running_jobs"], 1);
+
+        // An expired maintenance window no longer excludes the node
+        client.post(format!("{}/nodes/{}/maintenance", base, node2))
+            .json(&serde_json::json!({ "enabled": true, "until": state.clock.now_secs() - 1 }))
+            .send().await.unwrap();
+        let placed: serde_json::Value = schedule("job-c").await.unwrap().json().await.unwrap();
+        assert_eq!(placed["assigned_node"], node2);
+
+        // Lifting the drain returns node1 to the pool
+        assert_eq!(client.delete(format!("{}/nodes/{}/drain", base, node1)).send().await.unwrap().status().as_u16(), 204);
+        assert!(state.cordons.read().await.get(node1).is_none());
+        assert_eq!(client.delete(format!("{}/nodes/{}/drain", base, node1)).send().await.unwrap().status().as_u16(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_long_jobs_avoid_nodes_with_upcoming_maintenance() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        state.nodes.write().await.get_mut(node2).unwrap().current_load = 0.5; // node1 ranks first
+        let now = state.clock.now_secs();
+
+        // node1 announces a reboot an hour from now
+        let mut announced = test_node(node1);
+        announced.maintenance_windows = vec![
+            MaintenanceWindow { start: now + 3600, end: now + 5400, reason: Some("kernel upgrade".to_string()) },
+            MaintenanceWindow { start: now - 7200, end: now - 3600, reason: None }, // Already over
+        ];
+        assert_eq!(register_node(State(state.clone()), Json(announced)).await, Ok(StatusCode::CREATED));
+        assert_eq!(state.nodes.read().await[node1].maintenance_windows.len(), 1);
+
+        let schedule = |job: &str, secs: u64| ScheduleRequest {
+            job_id: format!("{:0>32}", job),
+            tee_required: false,
+            exclude_nodes: Vec::new(),
+            reservation_id: None,
+            placement: PlacementMode::TopScore,
+            estimated_duration_secs: Some(secs),
+        };
+
+        // A 2-hour job would still be running when node1 goes down
+        let (_, scores) = rank_candidates(&state, &schedule("job-long", 7200)).await.unwrap();
+        assert_eq!(scores.iter().map(|score| score.node_pubkey.as_str()).collect::<Vec<_>>(), vec![node2]);
+        let Ok(ScheduleOutcome::Placed(placed)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(schedule("job-long", 7200))).await else {
+            panic!("long job was not placed");
+        };
+        assert_eq!(placed.assigned_node, node2);
+
+        // One that finishes before the window still goes to node1
+        let Ok(ScheduleOutcome::Placed(placed)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(schedule("job-short", 1800))).await else {
+            panic!("short job was not placed");
+        };
+        assert_eq!(placed.assigned_node, node1);
+
+        // Interrupted everywhere: the node whose maintenance comes latest
+        // takes it, even though node1 ranks first
+        state.nodes.write().await.get_mut(node2).unwrap().maintenance_windows =
+            vec![MaintenanceWindow { start: now + 5400, end: now + 6000, reason: None }];
+        let (_, scores) = rank_candidates(&state, &schedule("job-longer", 14_400)).await.unwrap();
+        assert_eq!(scores.iter().map(|score| score.node_pubkey.as_str()).collect::<Vec<_>>(), vec![node2]);
+
+        // The body ai-jobd sends carries the estimate placement works from
+        let from_jobd: ScheduleRequest = serde_json::from_value(serde_json::json!({
+            "job_id": format!("{:0>32}", "job-jobd"),
+            "tee_required": false,
+            "exclude_nodes": [],
+            "reservation_id": null,
+            "estimated_duration_secs": 7200,
+        }))
+        .unwrap();
+        assert_eq!(from_jobd.estimated_duration_secs, Some(7200));
+        let Ok(ScheduleOutcome::Placed(placed)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(from_jobd)).await else {
+            panic!("job from ai-jobd was not placed");
+        };
+        assert_eq!(placed.assigned_node, node2);
+    }
+
+    #[tokio::test]
+    async fn test_runner_up_assigned_when_top_candidate_goes_away() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        state.nodes.write().await.get_mut(node2).unwrap().current_load = 0.5; // node1 ranks first
+
+        let req = ScheduleRequest { job_id: format!("{:0>32}", "job-race"), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        let (job, scores) = rank_candidates(&state, &req).await.unwrap();
+        assert_eq!(scores[0].node_pubkey, node1);
+
+        // Between scoring and assignment node1 fills up, then goes offline
+        state.nodes.write().await.get_mut(node1).unwrap().current_load = 1.0;
+        assert_eq!(live_unsuitability(&state, &job, node1).await.as_deref(), Some("no free GPUs"));
+        state.nodes.write().await.remove(node1);
+        assert_eq!(live_unsuitability(&state, &job, node1).await.as_deref(), Some("went offline"));
+
+        let Json(placed) = assign_ranked(&state, &req.job_id, &job, &scores, Deadline::NONE).await.unwrap();
+        assert_eq!(placed.assigned_node, node2);
+        assert_eq!(state.job_assignments.read().await[&req.job_id], node2);
+        assert_eq!(rpc.calls_of(&abi::ai_job_manager(), "assignJob"), vec![
+            vec![abi::abi_encode_bytes32(&req.job_id), abi::abi_encode_bytes32(node2)],
+        ]);
+
+        // No ranked node left: nothing is written on-chain
+        state.nodes.write().await.get_mut(node2).unwrap().gpus[0].available = false;
+        let other = format!("{:0>32}", "job-none");
+        assert_eq!(assign_ranked(&state, &other, &job, &scores, Deadline::NONE).await.unwrap_err(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(rpc.calls_of(&abi::ai_job_manager(), "assignJob").len(), 1);
+    }
+
+    fn ranked(scores: &[(&str, f64)]) -> Vec<String> {
+        let mut scores: Vec<NodeScore> = scores
+            .iter()
+            .map(|(pubkey, total_score)| NodeScore {
+                node_pubkey: pubkey.to_string(),
+                total_score: *total_score,
+                locality_score: 0.0,
+                gpu_score: 0.0,
+                sla_score: 0.0,
+                cost_score: 0.0,
+                load_score: 0.0,
+                failure_probability: 0.0,
+                predicted_duration: None,
+                predicted_queue_wait: None,
+                bid: None,
+            })
+            .collect();
+        rank_scores(&mut scores);
+        scores.into_iter().map(|score| score.node_pubkey).collect()
+    }
+
+    #[test]
+    fn test_nan_scores_rank_last_without_panicking() {
+        let order = ranked(&[("0xd", f64::NAN), ("0xc", 0.4), ("0xb", f64::NAN), ("0xa", 0.9)]);
+        assert_eq!(order, vec!["0xa", "0xc", "0xb", "0xd"]);
+    }
+
+    #[tokio::test]
+    async fn test_tied_scores_resolve_by_pubkey() {
+        // Input order doesn't matter
+        assert_eq!(ranked(&[("0xb", 0.5), ("0xc", 0.7), ("0xa", 0.5)]), vec!["0xc", "0xa", "0xb"]);
+        assert_eq!(ranked(&[("0xa", 0.5), ("0xb", 0.5), ("0xc", 0.7)]), vec!["0xc", "0xa", "0xb"]);
+
+        // Identical nodes rank the same way on every run, whatever the node map's order
+        let rpc = abi::DryRunRpc::spawn().await;
+        let req = ScheduleRequest { job_id: format!("{:0>32}", "job-tie"), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        for _ in 0..8 {
+            let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+            let (_, scores) = rank_candidates(&state, &req).await.unwrap();
+            assert_eq!(scores[0].total_score, scores[1].total_score);
+            let order: Vec<&str> = scores.iter().map(|score| score.node_pubkey.as_str()).collect();
+            assert_eq!(order, vec!["0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899"]);
+        }
+    }
+
+    #[test]
+    fn test_fair_draw_is_reproducible_and_verifiable() {
+        let key = VrfKey::from_bytes(&[7; 32]).unwrap();
+        let candidates: Vec<String> = ["0xa", "0xb", "0xc"].iter().map(|c| c.to_string()).collect();
+        let draw = fairness::draw(&key, "job-1", "0xblock", candidates.clone());
+        assert_eq!(fairness::draw(&key, "job-1", "0xblock", candidates.clone()), draw);
+        let published = key.public_key();
+        assert!(fairness::audit(&published, "job-1", &draw));
+
+        // The proof binds the job, the block, the key and the pick
+        assert!(!fairness::audit(&published, "job-2", &draw));
+        assert!(!fairness::audit(&published, "job-1", &FairDraw { block_hash: "0xother".to_string(), ..draw.clone() }));
+        assert!(!fairness::audit(&published, "job-1", &FairDraw { chosen: (draw.chosen + 1) % 3, ..draw.clone() }));
+        let other = VrfKey::from_bytes(&[8; 32]).unwrap();
+        assert!(!fairness::audit(&published, "job-1", &FairDraw { vrf_public_key: other.public_key(), ..draw.clone() }));
+        let forged = fairness::draw(&other, "job-1", "0xblock", candidates);
+        assert!(!fairness::audit(&published, "job-1", &FairDraw { vrf_public_key: key.public_key(), ..forged.clone() }));
+        // A draw under its own key proves nothing against the published one
+        assert!(fairness::audit(&other.public_key(), "job-1", &forged));
+        assert!(!fairness::audit(&published, "job-1", &forged));
+    }
+
+    #[test]
+    fn test_fair_draws_spread_across_the_top_k() {
+        let key = VrfKey::from_bytes(&[7; 32]).unwrap();
+        let candidates: Vec<String> = ["0xa", "0xb", "0xc"].iter().map(|c| c.to_string()).collect();
+        let mut picks = [0; 3];
+        for job in 0..600 {
+            picks[fairness::draw(&key, &format!("job-{}", job), "0xblock", candidates.clone()).chosen] += 1;
+        }
+        // 200 each expected; 150 is over 4 standard deviations out
+        assert!(picks.iter().all(|&n| (150..=250).contains(&n)), "{:?}", picks);
+    }
+
+    #[tokio::test]
+    async fn test_random_among_top_k_places_on_the_drawn_node() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let mut assigned = std::collections::HashSet::new();
+        for job in 0..8 {
+            let job_id = format!("{:0>32}", format!("job-fair-{}", job));
+            let placement = PlacementMode::RandomAmongTopK { k: 2 };
+            let request = ScheduleRequest { job_id: job_id.clone(), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement, estimated_duration_secs: None };
+            let Ok(ScheduleOutcome::Placed(placed)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(request)).await else {
+                panic!("job {} was not placed", job_id);
+            };
+            let draw = placed.fair_draw.expect("placement carries its draw");
+            let Json(audit) = audit_draw(State(state.clone()), Json(AuditDrawRequest { job_id: job_id.clone(), draw: draw.clone() })).await.unwrap();
+            assert_eq!(audit["valid"], true, "{}", audit);
+            assert_eq!(draw.candidates.len(), 2);
+            assert_eq!(placed.assigned_node, draw.candidates[draw.chosen]);
+            assigned.insert(placed.assigned_node);
+            // Free the node again so both stay in the top 2
+            release_job(State(state.clone()), Path(job_id)).await;
+            for node in state.nodes.write().await.values_mut() {
+                node.current_load = 0.0;
+            }
+        }
+        assert_eq!(assigned.len(), 2);
+
+        // Candidates and blocks are checked, not taken from the draw
+        let job_id = format!("{:0>32}", "job-fair-0");
+        let draw = state.draws.read().await[&job_id].clone();
+        let audit = |draw: FairDraw| audit_draw(State(state.clone()), Json(AuditDrawRequest { job_id: job_id.clone(), draw }));
+        let mut swapped = draw.candidates.clone();
+        swapped.swap(0, 1);
+        let Json(result) = audit(FairDraw { candidates: swapped, ..draw.clone() }).await.unwrap();
+        assert_eq!(result["reason"], "not the draw the job was placed by");
+        let off_chain = fairness::draw(&VrfKey::from_bytes(&[7; 32]).unwrap(), &job_id, "0xnot-a-block", draw.candidates.clone());
+        state.draws.write().await.insert(job_id.clone(), off_chain.clone());
+        let Json(result) = audit(off_chain).await.unwrap();
+        assert_eq!(result["reason"], "block is not on chain");
+    }
+
+    fn signed_bid(key: &k256::ecdsa::SigningKey, auction_id: &str, pubkey: &str, price: f64, eta_secs: u64) -> (HeaderMap, Bytes) {
+        use k256::ecdsa::signature::Signer;
+        let body = serde_json::json!({ "node_pubkey": pubkey, "price_per_gpu_sec": price, "eta_secs": eta_secs }).to_string();
+        let signature: k256::ecdsa::Signature = key.sign(auction::bid_message(auction_id, pubkey, body.as_bytes()).as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-artha-signature", hex::encode(signature.to_bytes()).parse().unwrap());
+        (headers, Bytes::from(body))
+    }
+
+    #[tokio::test]
+    async fn test_auction_places_the_job_on_the_best_price_per_reputation_bid() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let clock = ManualClock::new(1_700_000_000);
+        let state = scoring_state_on(rpc.url(), "http://127.0.0.1:9", clock.clone());
+        // (price, reputation): the cheapest, the price per reputation winner, the most reputable
+        let bidders: Vec<_> = [(0.0025, 0.4), (0.003, 0.75), (0.004, 0.95)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (price, reputation))| (node_key(i as u8 + 1), price, reputation))
+            .collect();
+        {
+            let mut nodes = state.nodes.write().await;
+            nodes.clear();
+            for ((_, pubkey), _, reputation) in &bidders {
+                let mut node = test_node(pubkey);
+                node.reputation_score = *reputation;
+                nodes.insert(pubkey.clone(), node);
+            }
+        }
+
+        let job_id = format!("{:0>32}", "job-auction");
+        let request = ScheduleRequest { job_id: job_id.clone(), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::Auction, estimated_duration_secs: None };
+        let scheduled = tokio::spawn(schedule_job(State(state.clone()), HeaderMap::new(), Json(request)));
+        clock.wait_for_sleepers(1).await;
+
+        let mut auction_id = String::new();
+        for ((key, pubkey), price, _) in &bidders {
+            let Json(asks) = node_asks(State(state.clone()), Path(pubkey.clone())).await;
+            assert_eq!(asks.len(), 1);
+            assert_eq!(asks[0].job_id, job_id);
+            auction_id = asks[0].auction_id.clone();
+            let (headers, body) = signed_bid(key, &auction_id, pubkey, *price, 60);
+            let accepted = submit_bid(State(state.clone()), Path(auction_id.clone()), headers, body).await;
+            assert_eq!(accepted.unwrap(), StatusCode::ACCEPTED);
+        }
+        // Bids are sealed to their node, and each node bids once
+        let ((_, first), (other_key, _)) = (&bidders[0].0, &bidders[1].0);
+        let (headers, body) = signed_bid(other_key, &auction_id, first, 0.001, 60);
+        let forged = submit_bid(State(state.clone()), Path(auction_id.clone()), headers, body).await;
+        assert_eq!(forged.unwrap_err().into_response().status(), StatusCode::UNAUTHORIZED);
+        let (headers, body) = signed_bid(&bidders[0].0 .0, &auction_id, first, 0.001, 60);
+        let rebid = submit_bid(State(state.clone()), Path(auction_id.clone()), headers, body).await;
+        assert_eq!(rebid.unwrap_err().into_response().status(), StatusCode::BAD_REQUEST);
+
+        clock.advance_secs(5);
+        let Ok(ScheduleOutcome::Placed(placed)) = scheduled.await.unwrap() else {
+            panic!("auctioned job was not placed");
+        };
+        let winner = &bidders[1].0 .1;
+        assert_eq!(&placed.assigned_node, winner);
+        let auction = placed.auction.expect("placement carries its auction");
+        assert_eq!((auction.bids, auction.winner.as_ref()), (3, Some(winner)));
+        assert_eq!(auction.winning_bid, Some(Bid { price_per_gpu_sec: 0.003, eta_secs: 60 }));
+        assert_eq!(placed.estimated_start_time, 1_700_000_005 + 60);
+
+        // Bidding is over once the window closes
+        let (headers, body) = signed_bid(&bidders[2].0 .0, &auction_id, &bidders[2].0 .1, 0.001, 60);
+        let late = submit_bid(State(state.clone()), Path(auction_id), headers, body).await;
+        assert_eq!(late.unwrap_err().into_response().status(), StatusCode::NOT_FOUND);
+
+        // With auctions off the job is placed by score, without waiting for bids
+        let mut state = scoring_state_on(rpc.url(), "http://127.0.0.1:9", clock.clone());
+        Arc::get_mut(&mut state).unwrap().auction.enabled = false;
+        let request = ScheduleRequest { job_id: format!("{:0>32}", "job-no-auction"), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::Auction, estimated_duration_secs: None };
+        let Ok(ScheduleOutcome::Placed(placed)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(request)).await else {
+            panic!("job was not placed by score");
+        };
+        assert!(placed.auction.is_none());
+    }
+
+    /// An ai-jobd that answers /job/assigned with 503 `failures` times, then
+    /// starts the job; returns its URL and the jobs it started
+    async fn flaky_jobd(failures: usize) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let started: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let recorded = started.clone();
+        let jobd = Router::new().route("/job/assigned", post(move |Json(body): Json<serde_json::Value>| {
+            let call = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if call >= failures {
+                recorded.lock().unwrap().push(body["job_id"].as_str().unwrap().to_string());
+            }
+            async move { if call < failures { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK } }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, jobd).await.unwrap() });
+        (url, started)
+    }
+
+    #[tokio::test]
+    async fn test_assignment_notification_is_retried_until_jobd_starts_the_job() {
+        let (jobd_url, started) = flaky_jobd(1).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = scoring_state_on(rpc.url(), "http://127.0.0.1:9", ManualClock::auto_advancing(1_700_000_000));
+        let state_mut = Arc::get_mut(&mut state).unwrap();
+        state_mut.jobd_url = jobd_url;
+        state_mut.notify.attempts = 3;
+
+        let job_id = format!("{:0>32}", "job-notify-retry");
+        let request = ScheduleRequest { job_id: job_id.clone(), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        let Ok(ScheduleOutcome::Placed(_)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(request)).await else {
+            panic!("job was not placed");
+        };
+
+        // The first call hit a 503, the retry started the job
+        assert_eq!(*started.lock().unwrap(), vec![job_id]);
+        assert_eq!(state.outbox.read().await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_assignment_waits_in_the_outbox() {
+        let (jobd_url, started) = flaky_jobd(2).await;
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        Arc::get_mut(&mut state).unwrap().jobd_url = jobd_url;
+
+        let request = |job: &str| ScheduleRequest { job_id: format!("{:0>32}", job), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        for job in ["job-outbox-kept", "job-outbox-released"] {
+            let Ok(ScheduleOutcome::Placed(_)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(request(job))).await else {
+                panic!("{} was not placed", job);
+            };
+        }
+        // Both notifications failed their only attempt: assigned, but not started
+        let queued = state.outbox.read().await.list();
+        assert_eq!(queued.len(), 2);
+        assert!(queued.iter().all(|n| n.attempts == 1 && n.last_error.as_deref() == Some("HTTP 503 Service Unavailable")));
+        assert!(started.lock().unwrap().is_empty());
+
+        // A job released meanwhile is not started by the redelivery
+        release_job(State(state.clone()), Path(format!("{:0>32}", "job-outbox-released"))).await;
+        redeliver_assignments(&state).await;
+        assert_eq!(*started.lock().unwrap(), vec![format!("{:0>32}", "job-outbox-kept")]);
+        assert_eq!(state.outbox.read().await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_migrating_jobs_are_placed_away_from_their_source() {
+        let migrate_calls: Arc<std::sync::Mutex<Vec<(String, serde_json::Value)>>> = Arc::default();
+        let calls = migrate_calls.clone();
+        let jobd = Router::new().route("/job/:id/migrate", post(
+            move |Path(job_id): Path<String>, Json(body): Json<serde_json::Value>| {
+                let calls = calls.clone();
+                async move {
+                    calls.lock().unwrap().push((job_id.clone(), body));
+                    // Only the opted-in job moves
+                    if job_id.ends_with("job-live") { StatusCode::ACCEPTED } else { StatusCode::CONFLICT }
+                }
+            },
+        ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let jobd_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, jobd).await.unwrap() });
+
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        Arc::get_mut(&mut state).unwrap().jobd_url = jobd_url;
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        state.nodes.write().await.get_mut(node2).unwrap().current_load = 0.5; // node1 ranks first
+
+        // The node a job is leaving is never a candidate, even when it ranks best
+        let request = |job: &str, exclude: &[&str]| ScheduleRequest {
+            job_id: format!("{:0>32}", job),
+            tee_required: false,
+            exclude_nodes: exclude.iter().map(|n| n.to_string()).collect(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let Ok(ScheduleOutcome::Placed(placed)) = schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-moved", &[node1]))).await else {
+            panic!("job-moved was not placed");
+        };
+        assert_eq!(placed.assigned_node, node2);
+        let nowhere = schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-stuck", &[node1, node2]))).await;
+        assert_eq!(nowhere.unwrap_err().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(state.rejections.read().await.get(&format!("{:0>32}", "job-stuck")).is_none());
+
+        // Draining asks ai-jobd to move each job on the node; declined jobs stay
+        {
+            let mut assignments = state.job_assignments.write().await;
+            for job in ["job-live", "job-pinned"] {
+                assignments.insert(format!("{:0>32}", job), node1.to_string());
+            }
+        }
+        let Json(drained) = drain_node(State(state.clone()), Path(node1.to_string())).await.unwrap();
+        assert_eq!(drained["running_jobs"], 2);
+        assert_eq!(drained["migrating"], serde_json::json!([format!("{:0>32}", "job-live")]));
+        let mut calls = migrate_calls.lock().unwrap().clone();
+        calls.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].1["initiated_by"], "scheduler:drain");
+
+        // A reclaim cordons the node with its reason and migrates the same way
+        migrate_calls.lock().unwrap().clear();
+        let body = ReclaimRequest { reason: Some("spot instance preempted".to_string()) };
+        let Json(reclaimed) = reclaim_node(State(state.clone()), Path(node1.to_string()), Some(Json(body))).await.unwrap();
+        assert_eq!(reclaimed["migrating"], serde_json::json!([format!("{:0>32}", "job-live")]));
+        assert_eq!(state.cordons.read().await[node1].reason.as_deref(), Some("spot instance preempted"));
+        assert!(migrate_calls.lock().unwrap().iter().all(|(_, body)| body["initiated_by"] == "scheduler:reclaim"));
+        let unknown = reclaim_node(State(state.clone()), Path("0xnobody".to_string()), None).await;
+        assert_eq!(unknown.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_job_schedules_once_a_node_gets_cheap_enough() {
+        let prices: Arc<std::sync::Mutex<HashMap<String, f64>>> = Arc::default();
+        let feed = prices.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let feed_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/prices", axum::routing::get(move || {
+            let feed = feed.clone();
+            async move { Json(feed.lock().unwrap().clone()) }
+        }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        Arc::get_mut(&mut state).unwrap().price_feed = Arc::new(PriceFeed::new(Some(feed_url)));
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        let set_prices = |node1_price: f64, node2_price: f64| {
+            *prices.lock().unwrap() = HashMap::from([(node1.to_string(), node1_price), (node2.to_string(), node2_price)]);
+        };
+
+        // Both nodes cost more than the job's 0.01 cap: it waits, holding its slot
+        for node in state.nodes.write().await.values_mut() {
+            node.price_per_gpu_sec = 0.02;
+        }
+        let job_id = format!("{:0>32}", "job-spot");
+        let request = ScheduleRequest { job_id: job_id.clone(), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        let outcome = schedule_job(State(state.clone()), HeaderMap::new(), Json(request)).await.unwrap();
+        let ScheduleOutcome::Waiting(waiting) = outcome else {
+            panic!("priced-out job was placed");
+        };
+        assert_eq!(waiting.status, "waiting");
+        assert_eq!(state.pending.read().await.len(), 1);
+        assert!(state.waiting.read().await.contains_key(&job_id));
+        assert!(rpc.calls_of(&abi::ai_job_manager(), "assignJob").is_empty());
+
+        // Prices move but stay above the cap: still waiting
+        set_prices(0.03, 0.012);
+        assert!(reevaluate_waiting(&state).await.is_empty());
+        assert_eq!(state.nodes.read().await[node2].price_per_gpu_sec, 0.012);
+        assert!(state.waiting.read().await.contains_key(&job_id));
+
+        // node2 drops under the cap while node1 spikes: the job is placed on node2
+        set_prices(0.05, 0.004);
+        assert_eq!(reevaluate_waiting(&state).await, vec![job_id.clone()]);
+        assert_eq!(state.job_assignments.read().await[&job_id], node2);
+        assert!(state.waiting.read().await.is_empty());
+        assert_eq!(state.pending.read().await.len(), 1); // Held until ai-jobd releases it
+        assert_eq!(rpc.calls_of(&abi::ai_job_manager(), "assignJob"), vec![
+            vec![abi::abi_encode_bytes32(&job_id), abi::abi_encode_bytes32(node2)],
+        ]);
+
+        // A job with no capable node at any price is still turned away
+        state.nodes.write().await.get_mut(node2).unwrap().gpus[0].vram_gb = 8;
+        state.nodes.write().await.get_mut(node1).unwrap().gpus[0].vram_gb = 8;
+        let request = ScheduleRequest { job_id: format!("{:0>32}", "job-small"), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        let refused = schedule_job(State(state.clone()), HeaderMap::new(), Json(request)).await;
+        assert_eq!(refused.unwrap_err().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(state.waiting.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scheduling_deadline_is_honoured_and_forwarded_to_jobd() {
+        let seen: Arc<std::sync::Mutex<Vec<Option<String>>>> = Arc::default();
+        let recorded = seen.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let jobd_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/job/assigned", post(move |headers: HeaderMap| {
+            let recorded = recorded.clone();
+            async move {
+                recorded.lock().unwrap().push(headers.get(artha_errors::DEADLINE_HEADER).map(|v| v.to_str().unwrap().to_string()));
+                StatusCode::OK
+            }
+        }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let rpc = abi::DryRunRpc::spawn().await;
+        let clock = ManualClock::new(1_700_000_000);
+        let mut state = scoring_state_on(rpc.url(), "http://127.0.0.1:9", clock.clone());
+        Arc::get_mut(&mut state).unwrap().jobd_url = jobd_url;
+        let request = |job: &str| ScheduleRequest {
+            job_id: format!("{:0>32}", job),
+            tee_required: false,
+            exclude_nodes: Vec::new(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let deadline = |at_millis: u64| {
+            let mut headers = HeaderMap::new();
+            headers.insert(artha_errors::DEADLINE_HEADER, at_millis.to_string().parse().unwrap());
+            headers
+        };
+
+        // Past its deadline the job is neither admitted nor assigned on-chain
+        let late = schedule_job(State(state.clone()), deadline(clock.now_millis()), Json(request("job-late"))).await;
+        assert_eq!(late.unwrap_err().status(), StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(state.pending.read().await.len(), 0);
+        assert!(rpc.calls_of(&abi::ai_job_manager(), "assignJob").is_empty());
+
+        // Within it, ai-jobd hears the same deadline with the assignment
+        let until = clock.now_millis() + 30_000;
+        let Ok(ScheduleOutcome::Placed(_)) = schedule_job(State(state.clone()), deadline(until), Json(request("job-timely"))).await else {
+            panic!("job with time to spare was not placed");
+        };
+        assert_eq!(*seen.lock().unwrap(), vec![Some(until.to_string())]);
+    }
+
+
+    /// A node identity: its signing key and the pubkey it registers under
+    fn node_key(seed: u8) -> (k256::ecdsa::SigningKey, String) {
+        let key = k256::ecdsa::SigningKey::from_slice(&[seed; 32]).unwrap();
+        let pubkey = format!("0x{}", hex::encode(key.verifying_key().to_encoded_point(true).as_bytes()));
+        (key, pubkey)
+    }
+
+    fn signed_heartbeat(key: &k256::ecdsa::SigningKey, pubkey: &str, body: &[u8], timestamp: u64) -> HeaderMap {
+        use k256::ecdsa::signature::Signer;
+        let signature: k256::ecdsa::Signature = key.sign(liveness::heartbeat_message(pubkey, body, timestamp).as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-artha-timestamp", timestamp.to_string().parse().unwrap());
+        headers.insert("x-artha-signature", hex::encode(signature.to_bytes()).parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_must_be_signed_by_the_node() {
+        let state = scoring_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9");
+        let (key, pubkey) = node_key(7);
+        let (other_key, _) = node_key(8);
+        register_node(State(state.clone()), Json(test_node(&pubkey))).await.unwrap();
+        let body = Bytes::from_static(br#"{"gpus_total":4,"gpus_running":1,"gpus_pool_reserved":1}"#);
+        let beat = |headers: HeaderMap, body: Bytes| node_heartbeat(State(state.clone()), Path(pubkey.clone()), headers, body);
+        let t = state.clock.now_secs();
+
+        assert_eq!(beat(HeaderMap::new(), body.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(beat(signed_heartbeat(&other_key, &pubkey, &body, t), body.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(beat(signed_heartbeat(&key, &pubkey, &body, t - 120), body.clone()).await, StatusCode::UNAUTHORIZED);
+        // Signed over different capacity than it reports
+        let forged = Bytes::from_static(br#"{"gpus_total":4,"gpus_running":0}"#);
+        assert_eq!(beat(signed_heartbeat(&key, &pubkey, &body, t), forged).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(state.nodes.read().await[&pubkey].current_load, 0.0);
+
+        assert_eq!(beat(signed_heartbeat(&key, &pubkey, &body, t), body.clone()).await, StatusCode::OK);
+        assert_eq!(state.nodes.read().await[&pubkey].current_load, 0.5);
+        // A captured heartbeat cannot be replayed
+        assert_eq!(beat(signed_heartbeat(&key, &pubkey, &body, t), body.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(beat(signed_heartbeat(&key, &pubkey, &body, t + 1), body.clone()).await, StatusCode::OK);
+
+        let missing = node_heartbeat(State(state.clone()), Path("0xnode-unknown".to_string()), signed_heartbeat(&key, &pubkey, &body, t + 2), body).await;
+        assert_eq!(missing, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_silent_node_excluded_from_scheduling_then_deregistered() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let clock = ManualClock::new(1_700_000_000);
+        let state = scoring_state_on(rpc.url(), "http://127.0.0.1:9", clock.clone());
+        state.nodes.write().await.clear();
+        let ((live_key, live), (silent_key, silent)) = (node_key(1), node_key(2));
+        for pubkey in [&live, &silent] {
+            register_node(State(state.clone()), Json(test_node(pubkey))).await.unwrap();
+        }
+        state.nodes.write().await.get_mut(&live).unwrap().current_load = 0.5; // The silent node ranks first while healthy
+        let body = Bytes::from_static(br#"{"gpus_total":2,"gpus_running":1}"#);
+        let request = |job: &str| ScheduleRequest { job_id: format!("{:0>32}", job), tee_required: false, exclude_nodes: Vec::new(), reservation_id: None, placement: PlacementMode::TopScore, estimated_duration_secs: None };
+        let beat = |key: &k256::ecdsa::SigningKey, pubkey: &String| {
+            let headers = signed_heartbeat(key, pubkey, &body, clock.now_secs());
+            node_heartbeat(State(state.clone()), Path(pubkey.clone()), headers, body.clone())
+        };
+        let (_, scores) = rank_candidates(&state, &request("job-a")).await.unwrap();
+        assert_eq!(scores[0].node_pubkey, silent);
+
+        // The sweep loop runs on the scheduler clock; the live node beats between sweeps
+        let sweeper = tokio::spawn(liveness_sweep_loop(state.clone()));
+        clock.wait_for_sleepers(1).await;
+        for _ in 0..6 {
+            assert_eq!(beat(&live_key, &live).await, StatusCode::OK);
+            clock.tick(Duration::from_secs(15)).await;
+        }
+        let (_, scores) = rank_candidates(&state, &request("job-b")).await.unwrap();
+        assert_eq!(scores.iter().map(|s| s.node_pubkey.as_str()).collect::<Vec<_>>(), vec![live.as_str()]);
+        let Json(page) = list_nodes(State(state.clone()), Query(PageQuery::default())).await.unwrap();
+        let health: HashMap<String, NodeHealth> = page.items.iter().map(|v| (v.node.pubkey.clone(), v.health)).collect();
+        assert_eq!(health[&silent], NodeHealth::Unhealthy);
+        assert_eq!(health[&live], NodeHealth::Healthy);
+        assert!(state.nodes.read().await.contains_key(&silent));
+
+        // Unhealthy but still registered: a heartbeat brings it back
+        assert_eq!(beat(&silent_key, &silent).await, StatusCode::OK);
+        assert_eq!(state.liveness.read().await.health(&silent, clock.now_secs()), NodeHealth::Healthy);
+
+        // Silent past the timeout it is removed and must register again
+        for _ in 0..40 {
+            assert_eq!(beat(&live_key, &live).await, StatusCode::OK);
+            clock.tick(Duration::from_secs(15)).await;
+        }
+        assert!(state.nodes.read().await.contains_key(&silent));
+        assert_eq!(beat(&live_key, &live).await, StatusCode::OK);
+        clock.tick(Duration::from_secs(15)).await;
+        assert!(!state.nodes.read().await.contains_key(&silent));
+        assert!(state.nodes.read().await.contains_key(&live));
+        assert_eq!(beat(&silent_key, &silent).await, StatusCode::NOT_FOUND);
+        sweeper.abort();
+    }
+
+    fn gpus(node: &mut Node, count: usize) {
+        node.gpus = vec![node.gpus[0].clone(); count];
+    }
+
+    #[tokio::test]
+    async fn test_reservations_fit_projected_capacity_or_are_rejected() {
+        let state = scoring_state("http://127.0.0.1:9".to_string(), "http://127.0.0.1:9");
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        {
+            let mut nodes = state.nodes.write().await;
+            gpus(nodes.get_mut(node1).unwrap(), 4);
+            gpus(nodes.get_mut(node2).unwrap(), 4);
+            nodes.get_mut(node2).unwrap().price_per_gpu_sec = 0.004;
+        }
+        let app = Router::new()
+            .route("/reservations", post(book_reservation))
+            .route("/reservations/:id", axum::routing::delete(cancel_reservation))
+            .route("/nodes/:pubkey/maintenance", post(set_maintenance))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let start = state.clock.now_secs() + 7200;
+        let book = |id: &str, count: u32, latest_start: u64| {
+            client.post(format!("{}/reservations", base))
+                .json(&serde_json::json!({
+                    "reservation_id": id, "gpu_type": "A100", "gpu_count": count,
+                    "earliest_start": start, "latest_start": latest_start, "duration_secs": 3600,
+                }))
+                .send()
+        };
+
+        // Cheapest GPUs first, at the premium over their spot price
+        let first = book("res-1", 6, start).await.unwrap();
+        assert_eq!(first.status().as_u16(), 200);
+        let first: serde_json::Value = first.json().await.unwrap();
+        assert_eq!(first["allocations"], serde_json::json!({ node1: 2, node2: 4 }));
+        assert_eq!(first["start"], start);
+        let cost = first["cost"].as_u64().unwrap();
+        assert!((144..=145).contains(&cost), "1.25 x (4 x 0.004 + 2 x 0.008) x 3600, got {}", cost);
+
+        // Only two GPUs are left in that window
+        let overbooked = book("res-2", 4, start).await.unwrap();
+        assert_eq!(overbooked.status().as_u16(), 409);
+        let body: ServiceError = overbooked.json().await.unwrap();
+        assert_eq!(body.kind(), Some(ErrorCode::Conflict));
+        assert_eq!(body.details, Some(serde_json::json!({ "requested": 4, "available": 2 })));
+        assert_eq!(book("res-1", 1, start).await.unwrap().status().as_u16(), 409);
+
+        // A flexible start moves past the first reservation
+        let later: serde_json::Value = book("res-2", 4, start + 7200).await.unwrap().json().await.unwrap();
+        assert_eq!(later["start"], start + 3600);
+
+        // Cancelling gives the capacity back
+        assert_eq!(client.delete(format!("{}/reservations/res-2", base)).send().await.unwrap().status().as_u16(), 204);
+        assert_eq!(client.delete(format!("{}/reservations/res-2", base)).send().await.unwrap().status().as_u16(), 404);
+
+        // Open-ended maintenance takes node2 out; a window that ends mid-range delays the start
+        client.post(format!("{}/nodes/{}/maintenance", base, node2))
+            .json(&serde_json::json!({ "enabled": true }))
+            .send().await.unwrap();
+        let without_node2: ServiceError = book("res-3", 5, start + 7200).await.unwrap().json().await.unwrap();
+        assert_eq!(without_node2.details, Some(serde_json::json!({ "requested": 5, "available": 4 })));
+        client.post(format!("{}/nodes/{}/maintenance", base, node2))
+            .json(&serde_json::json!({ "enabled": true, "until": start + 5000 }))
+            .send().await.unwrap();
+        let delayed: serde_json::Value = book("res-3", 5, start + 7200).await.unwrap().json().await.unwrap();
+        assert_eq!(delayed["start"], start + 5000);
+
+        // Jobs node1 typically runs are not promised to reservations
+        {
+            let mut learner = state.learner.write().await;
+            for i in 0..4 {
+                learner.record(PlacementOutcome {
+                    job_id: format!("job-{}", i),
+                    job_class: "train:vram24:std".to_string(),
+                    node_pubkey: node1.to_string(),
+                    queue_wait_secs: 0,
+                    duration_secs: 7 * 86_400,
+                    peak_vram_mb: None,
+                    failure_reason: None,
+                    status: PlacementStatus::Completed,
+                    recorded_at: state.clock.now_secs(),
+                });
+            }
+        }
+        let req: ReservationRequest = serde_json::from_value(serde_json::json!({
+            "reservation_id": "res-4", "gpu_type": "A100", "gpu_count": 1,
+            "earliest_start": start, "latest_start": start, "duration_secs": 60,
+        })).unwrap();
+        let capacity = reservable_capacity(&state, &req, state.clock.now_secs()).await;
+        assert!(capacity.iter().all(|c| c.pubkey != node1), "four typical jobs fill node1's four GPUs");
+    }
+
+    #[tokio::test]
+    async fn test_reserved_gpus_only_serve_their_reservation() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let state = scoring_state(rpc.url(), "http://127.0.0.1:9");
+        let (node1, node2) = ("0xnode1aabbccddeeff00112233445566778899", "0xnode2eeffgghhiijj00112233445566778899");
+        {
+            let mut nodes = state.nodes.write().await;
+            gpus(nodes.get_mut(node1).unwrap(), 2);
+            nodes.get_mut(node1).unwrap().price_per_gpu_sec = 0.004; // node1 ranks first
+        }
+        let req: ReservationRequest = serde_json::from_value(serde_json::json!({
+            "reservation_id": "res-now", "gpu_type": "A100", "gpu_count": 2,
+            "earliest_start": state.clock.now_secs(), "latest_start": state.clock.now_secs(), "duration_secs": 3600,
+        })).unwrap();
+        let Json(reservation) = book_reservation(State(state.clone()), Json(req)).await.unwrap();
+        assert_eq!(reservation.allocations, [(node1.to_string(), 2)].into_iter().collect());
+
+        let request = |job: &str, reservation: Option<&str>| ScheduleRequest {
+            job_id: format!("{:0>32}", job),
+            tee_required: false,
+            exclude_nodes: Vec::new(),
+            reservation_id: reservation.map(str::to_string),
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let placed_on = |outcome: Result<ScheduleOutcome, Response>| match outcome {
+            Ok(ScheduleOutcome::Placed(placed)) => Ok(placed.assigned_node),
+            Ok(ScheduleOutcome::Waiting(_)) => panic!("job is waiting"),
+            Err(response) => Err(response.status()),
+        };
+
+        // Ordinary jobs skip the reserved GPUs even though node1 is cheaper
+        let plain = schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-plain", None))).await;
+        assert_eq!(placed_on(plain), Ok(node2.to_string()));
+
+        // Reservation-backed jobs use exactly the reserved GPUs
+        for job in ["job-r1", "job-r2"] {
+            let reserved = schedule_job(State(state.clone()), HeaderMap::new(), Json(request(job, Some("res-now")))).await;
+            assert_eq!(placed_on(reserved), Ok(node1.to_string()));
+        }
+        let full = schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-r3", Some("res-now")))).await;
+        assert_eq!(placed_on(full), Err(StatusCode::SERVICE_UNAVAILABLE));
+
+        // A finished job hands its GPU back to the reservation
+        release_job(State(state.clone()), Path(format!("{:0>32}", "job-r1"))).await;
+        let reused = schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-r3", Some("res-now")))).await;
+        assert_eq!(placed_on(reused), Ok(node1.to_string()));
+
+        let unknown = schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-r4", Some("res-unknown")))).await;
+        assert_eq!(placed_on(unknown), Err(StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn test_decertified_node_leaves_candidacy_mid_operation() {
+        use sha3::{Digest, Keccak256};
+
+        let rpc = abi::DryRunRpc::spawn().await;
+        let (node1, node2, node3) = (
+            "0xnode1aabbccddeeff00112233445566778899",
+            "0xnode2eeffgghhiijj00112233445566778899",
+            "0xnode3kkllmmnnoopp00112233445566778899",
+        );
+        rpc.respond(
+            abi::node_cert_registry().function("getNodesByRole"),
+            &[abi::Token::Array(vec![abi::Token::ascii32(node1), abi::Token::ascii32(node2)])],
+        );
+        let mut client = ContractClient::new(rpc.url());
+        client.node_cert_registry = Some("0x00000000000000000000000000000000000000ce".to_string());
+        let mut certs = NodeCerts::enforcing(1_000);
+        certs.bootstrap(client.certified_nodes().await.unwrap());
+        assert_eq!(rpc.calls_of(&abi::node_cert_registry(), "getNodesByRole"), vec![vec![abi::Token::uint(3u8)]]);
+        let state = Arc::new(AppState {
+            certs: Arc::new(RwLock::new(certs)),
+            ..Arc::try_unwrap(scoring_state(rpc.url(), "http://127.0.0.1:9")).ok().unwrap()
+        });
+
+        let request = |job: &str| ScheduleRequest {
+            job_id: format!("{:0>32}", job),
+            tee_required: false,
+            exclude_nodes: Vec::new(),
+            reservation_id: None,
+            estimated_duration_secs: None,
+            placement: PlacementMode::TopScore,
+        };
+        let placed_on = |outcome: Result<ScheduleOutcome, Response>| match outcome {
+            Ok(ScheduleOutcome::Placed(placed)) => Ok(placed.assigned_node),
+            Ok(ScheduleOutcome::Waiting(_)) => panic!("job is waiting"),
+            Err(response) => Err(response.status()),
+        };
+        let log = |event: &str, pubkey: &str, data: String, block_height: u64| ChainLog {
+            contract: "NodeCertRegistry".to_string(),
+            block_height,
+            log_index: 0,
+            topics: vec![
+                format!("0x{}", hex::encode(Keccak256::digest(event.as_bytes()))),
+                format!("0x{}", certs::cert_key(pubkey)),
+            ],
+            data: format!("0x{}", data),
+        };
+        let deactivated = |pubkey: &str, block: u64| log("NodeDeactivated(bytes32)", pubkey, String::new(), block);
+
+        let first = placed_on(schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-1"))).await).unwrap();
+        let other = if first == node1 { node2 } else { node1 };
+
+        // The chain decertifies the node running job-1: the job stays, new work goes elsewhere
+        assert_eq!(chain_events(State(state.clone()), Json(deactivated(&first, 10))).await, StatusCode::NO_CONTENT);
+        assert_eq!(state.job_assignments.read().await[&format!("{:0>32}", "job-1")], first);
+        for job in ["job-2", "job-3"] {
+            assert_eq!(placed_on(schedule_job(State(state.clone()), HeaderMap::new(), Json(request(job))).await), Ok(other.to_string()));
+        }
+        let Json(listed) = list_nodes(State(state.clone()), Query(PageQuery::default())).await.unwrap();
+        assert!(listed.items.iter().all(|view| view.certified == (view.node.pubkey == other)));
+
+        // Only certified nodes may register, and a registration log certifies one
+        let register = |pubkey: &str| register_node(State(state.clone()), Json(test_node(pubkey)));
+        assert_eq!(register(node3).await, Err(StatusCode::FORBIDDEN));
+        let role = format!("{:064x}{:064x}{:064x}", certs::GPU_PROVIDER_ROLE, 0x40, 0);
+        assert_eq!(chain_events(State(state.clone()), Json(log("NodeRegistered(bytes32,address,uint8,string)", node3, role, 11))).await, StatusCode::NO_CONTENT);
+        assert_eq!(register(node3).await, Ok(StatusCode::CREATED));
+
+        // A slash below the minimum stake decertifies too; a stale redelivery changes nothing
+        let stake = |wei: u64, block: u64| log("NodeStakeUpdated(bytes32,uint256)", other, format!("{:064x}", wei), block);
+        assert_eq!(chain_events(State(state.clone()), Json(stake(400, 12))).await, StatusCode::NO_CONTENT);
+        assert_eq!(chain_events(State(state.clone()), Json(stake(5_000, 11))).await, StatusCode::NO_CONTENT);
+        assert_eq!(placed_on(schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-4"))).await), Ok(node3.to_string()));
+        assert!(!state.certs.read().await.is_certified(other));
+
+        assert_eq!(chain_events(State(state.clone()), Json(deactivated(node3, 13))).await, StatusCode::NO_CONTENT);
+        assert_eq!(placed_on(schedule_job(State(state.clone()), HeaderMap::new(), Json(request("job-5"))).await), Err(StatusCode::SERVICE_UNAVAILABLE));
+    }
+}
@@ -0,0 +1,152 @@
+//! Assignment Notifications
+//! ai-jobd starts a job when it receives `POST /job/assigned`. If that call
+//! is lost, the job stays assigned on-chain but never runs. A notification
+//! that fails is retried with exponential backoff. Once the attempts are used
+//! up it goes to the outbox. A background sweep redelivers the outbox until
+//! ai-jobd takes each notification or its assignment is undone.
+//!
+//! A connection error or a 5xx counts as a failure. Any other answer means
+//! ai-jobd received the notification, even if it then refused the job.
+//!
+//! The deadline of the request that placed the job goes along as
+//! `x-artha-deadline`, so ai-jobd doesn't start a job nobody waits for.
+
+use crate::admission::env_or;
+use artha_clock::SharedClock;
+use artha_errors::{Deadline, DEADLINE_HEADER};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct NotifyConfig {
+    pub attempts: u32,          // Tries before a notification goes to the outbox
+    pub backoff_ms: u64,        // Wait after the first failure, doubling up to MAX_BACKOFF
+    pub redeliver_secs: u64,    // Interval of the outbox sweep
+    pub concurrency: usize,     // Outbox notifications in flight at once
+}
+
+impl NotifyConfig {
+    pub fn from_env() -> Self {
+        NotifyConfig {
+            attempts: env_or("ARTHA_SCHED_NOTIFY_ATTEMPTS", 4).max(1),
+            backoff_ms: env_or("ARTHA_SCHED_NOTIFY_BACKOFF_MS", 250),
+            redeliver_secs: env_or("ARTHA_SCHED_NOTIFY_REDELIVER_SECS", 15),
+            concurrency: env_or("ARTHA_SCHED_NOTIFY_CONCURRENCY", 8).max(1),
+        }
+    }
+}
+
+/// A `/job/assigned` call, kept until ai-jobd takes it
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub job_id: String,
+    pub node_pubkey: String,
+    pub body: serde_json::Value,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    #[serde(skip)]
+    pub deadline: Deadline, // Of the scheduling request that made the assignment
+}
+
+/// Undelivered notifications, one per job
+#[derive(Debug, Default)]
+pub struct Outbox {
+    queued: BTreeMap<String, Notification>,
+}
+
+impl Outbox {
+    /// Queue `notification`, replacing any older one for the same job
+    pub fn push(&mut self, notification: Notification) {
+        self.queued.insert(notification.job_id.clone(), notification);
+    }
+
+    pub fn take_all(&mut self) -> Vec<Notification> {
+        std::mem::take(&mut self.queued).into_values().collect()
+    }
+
+    pub fn list(&self) -> Vec<Notification> {
+        self.queued.values().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+}
+
+/// One delivery attempt
+pub async fn send(client: &reqwest::Client, url: &str, body: &serde_json::Value, deadline: Deadline) -> Result<(), String> {
+    let mut request = client.post(url).json(body);
+    if let Some(value) = deadline.header_value() {
+        request = request.header(DEADLINE_HEADER, value);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if response.status().is_server_error() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Deliver `notification`, retrying with backoff. On failure it comes back
+/// with its attempts and last error recorded, ready for the outbox.
+pub async fn deliver(
+    client: &reqwest::Client,
+    clock: &SharedClock,
+    config: &NotifyConfig,
+    url: &str,
+    mut notification: Notification,
+) -> Result<Notification, Notification> {
+    let mut backoff = Duration::from_millis(config.backoff_ms);
+    for attempt in 1..=config.attempts {
+        if attempt > 1 {
+            clock.sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+        notification.attempts += 1;
+        match send(client, url, &notification.body, notification.deadline).await {
+            Ok(()) => return Ok(notification),
+            Err(e) => notification.last_error = Some(e),
+        }
+    }
+    Err(notification)
+}
+
+/// Try each of `notifications` once, at most `concurrency` at a time. Returns
+/// the ones that were delivered and the ones that failed again.
+pub async fn redeliver(
+    client: &reqwest::Client,
+    url: &str,
+    notifications: Vec<Notification>,
+    concurrency: usize,
+) -> (Vec<Notification>, Vec<Notification>) {
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut sends = JoinSet::new();
+    for mut notification in notifications {
+        let (client, url, permits) = (client.clone(), url.to_string(), permits.clone());
+        sends.spawn(async move {
+            let _permit = permits.acquire_owned().await.expect("semaphore is never closed");
+            notification.attempts += 1;
+            let result = send(&client, &url, &notification.body, notification.deadline).await;
+            if let Err(e) = &result {
+                notification.last_error = Some(e.clone());
+            }
+            (result.is_ok(), notification)
+        });
+    }
+
+    let (mut delivered, mut failed) = (Vec::new(), Vec::new());
+    while let Some(joined) = sends.join_next().await {
+        let Ok((ok, notification)) = joined else { continue };
+        if ok {
+            delivered.push(notification);
+        } else {
+            failed.push(notification);
+        }
+    }
+    (delivered, failed)
+}
@@ -0,0 +1,68 @@
+//! Spot Pricing
+//! Node prices move while jobs wait. A job whose capable nodes all cost more
+//! than its `max_price_per_sec` isn't turned away: it keeps its pending slot
+//! and waits. Every re-evaluation first re-fetches current prices from the
+//! spot price feed, then re-ranks each waiting job against them, so a node
+//! that just got cheap picks the job up and one that just spiked is skipped.
+
+use crate::Node;
+use std::collections::HashMap;
+
+/// Where current prices come from: `GET {url}/prices` answers
+/// `{ "<node pubkey>": <price_per_gpu_sec>, ... }`. Without a feed, nodes
+/// keep the price they registered with.
+pub struct PriceFeed {
+    url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl PriceFeed {
+    pub fn new(url: Option<String>) -> Self {
+        PriceFeed { url, client: reqwest::Client::new() }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("ARTHA_SPOT_PRICE_URL").ok())
+    }
+
+    pub async fn current_prices(&self) -> Result<HashMap<String, f64>, String> {
+        let Some(url) = &self.url else {
+            return Ok(HashMap::new());
+        };
+        let response = self
+            .client
+            .get(format!("{}/prices", url))
+            .send()
+            .await
+            .map_err(|e| format!("Price feed unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Price feed failed with status: {}", response.status()));
+        }
+        response.json().await.map_err(|e| format!("Failed to parse price feed: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceChange {
+    pub node_pubkey: String,
+    pub old: f64,
+    pub new: f64,
+}
+
+/// Update registered nodes to `prices`, returning what moved. Prices for
+/// unknown nodes and nonsensical prices are ignored.
+pub fn apply(nodes: &mut HashMap<String, Node>, prices: &HashMap<String, f64>) -> Vec<PriceChange> {
+    let mut changes = Vec::new();
+    for (pubkey, price) in prices {
+        let Some(node) = nodes.get_mut(pubkey) else {
+            continue;
+        };
+        if !price.is_finite() || *price < 0.0 || node.price_per_gpu_sec == *price {
+            continue;
+        }
+        changes.push(PriceChange { node_pubkey: pubkey.clone(), old: node.price_per_gpu_sec, new: *price });
+        node.price_per_gpu_sec = *price;
+    }
+    changes.sort_by(|a, b| a.node_pubkey.cmp(&b.node_pubkey));
+    changes
+}
@@ -0,0 +1,228 @@
+//! GPU-Hour Reservations
+//! Capacity booked ahead for a time window. A booking is checked against
+//! projected capacity: GPUs of the requested type on nodes that are not
+//! cordoned when the window opens, less each node's typical load and whatever
+//! overlapping reservations already hold. An accepted reservation pins GPUs
+//! on specific nodes. From `lead_secs` before its window opens, the pinned
+//! GPUs its jobs aren't using count as load for ordinary placements, and only
+//! jobs submitted against the reservation may use them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct ReservationConfig {
+    pub premium: f64,            // Multiplier over the nodes' spot price
+    pub lead_secs: u64,          // How early reserved GPUs are held back from ordinary jobs
+    pub load_lookback_secs: u64, // History a node's typical load is averaged over
+}
+
+impl ReservationConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+        ReservationConfig {
+            premium: var("ARTHA_RESERVATION_PREMIUM").and_then(|v| v.parse().ok()).unwrap_or(1.25),
+            lead_secs: var("ARTHA_RESERVATION_LEAD_SECS").and_then(|v| v.parse().ok()).unwrap_or(3600),
+            load_lookback_secs: var("ARTHA_RESERVATION_LOAD_LOOKBACK_SECS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(7 * 86_400),
+        }
+    }
+}
+
+/// Booking request from ai-jobd
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReservationRequest {
+    pub reservation_id: String, // Chosen by ai-jobd
+    pub gpu_type: String,
+    pub gpu_count: u32,
+    #[serde(default)]
+    pub region: Option<String>,
+    pub earliest_start: u64, // The window may open any time in [earliest_start, latest_start]
+    pub latest_start: u64,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Reservation {
+    pub reservation_id: String,
+    pub gpu_type: String,
+    pub region: Option<String>,
+    pub gpu_count: u32,
+    pub start: u64,
+    pub end: u64,
+    pub allocations: BTreeMap<String, u32>, // node_pubkey -> GPUs held
+    pub price_per_gpu_sec: f64, // Average over the allocations, premium included
+    pub cost: u64,
+    pub jobs: BTreeMap<String, String>, // Running job_id -> node_pubkey
+}
+
+impl Reservation {
+    pub fn open(&self, now: u64) -> bool {
+        self.start <= now && now < self.end
+    }
+
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+
+    fn running_on(&self, pubkey: &str) -> u32 {
+        self.jobs.values().filter(|node| *node == pubkey).count() as u32
+    }
+}
+
+/// What one node can offer reservations of the requested type
+#[derive(Debug, Clone)]
+pub struct NodeCapacity {
+    pub pubkey: String,
+    pub gpus: u32, // Matching GPUs less the node's typical load
+    pub price_per_gpu_sec: f64,
+    pub available_from: u64, // End of a maintenance window; u64::MAX while drained
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BookingError {
+    Invalid(String),
+    Exists,
+    Overbooked { requested: u32, available: u32 }, // Most GPUs free at any start in the window
+}
+
+#[derive(Debug, Default)]
+pub struct ReservationBook {
+    reservations: BTreeMap<String, Reservation>,
+}
+
+impl ReservationBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, reservation_id: &str) -> Option<&Reservation> {
+        self.reservations.get(reservation_id)
+    }
+
+    /// Book `req` at the earliest start in its window where the nodes have
+    /// room for it, taking the cheapest GPUs first. Ended reservations are
+    /// dropped first.
+    pub fn book(
+        &mut self,
+        req: &ReservationRequest,
+        nodes: &[NodeCapacity],
+        premium: f64,
+        now: u64,
+    ) -> Result<Reservation, BookingError> {
+        if req.gpu_count == 0 || req.duration_secs == 0 {
+            return Err(BookingError::Invalid("gpu_count and duration_secs must be positive".to_string()));
+        }
+        if req.latest_start < req.earliest_start || req.earliest_start < now {
+            return Err(BookingError::Invalid("Start window must be in the future and not inverted".to_string()));
+        }
+        self.reservations.retain(|_, reservation| reservation.end > now);
+        if self.reservations.contains_key(&req.reservation_id) {
+            return Err(BookingError::Exists);
+        }
+
+        // Capacity only grows when a reservation ends or maintenance does
+        let mut starts: Vec<u64> = std::iter::once(req.earliest_start)
+            .chain(self.reservations.values().map(|r| r.end))
+            .chain(nodes.iter().map(|n| n.available_from))
+            .filter(|start| (req.earliest_start..=req.latest_start).contains(start))
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+
+        let mut most_free = 0;
+        for start in starts {
+            let end = start + req.duration_secs;
+            let mut free: Vec<(&NodeCapacity, u32)> = nodes
+                .iter()
+                .filter(|node| node.available_from <= start)
+                .map(|node| (node, node.gpus.saturating_sub(self.held(&node.pubkey, start, end))))
+                .filter(|(_, gpus)| *gpus > 0)
+                .collect();
+            let total: u32 = free.iter().map(|(_, gpus)| gpus).sum();
+            most_free = most_free.max(total);
+            if total < req.gpu_count {
+                continue;
+            }
+
+            free.sort_by(|a, b| {
+                a.0.price_per_gpu_sec.total_cmp(&b.0.price_per_gpu_sec).then_with(|| a.0.pubkey.cmp(&b.0.pubkey))
+            });
+            let mut allocations = BTreeMap::new();
+            let mut remaining = req.gpu_count;
+            let mut gpu_price_sum = 0.0;
+            for (node, gpus) in free {
+                let take = gpus.min(remaining);
+                allocations.insert(node.pubkey.clone(), take);
+                gpu_price_sum += take as f64 * node.price_per_gpu_sec;
+                remaining -= take;
+                if remaining == 0 {
+                    break;
+                }
+            }
+
+            let price_per_gpu_sec = premium * gpu_price_sum / req.gpu_count as f64;
+            let reservation = Reservation {
+                reservation_id: req.reservation_id.clone(),
+                gpu_type: req.gpu_type.clone(),
+                region: req.region.clone(),
+                gpu_count: req.gpu_count,
+                start,
+                end,
+                allocations,
+                price_per_gpu_sec,
+                cost: (price_per_gpu_sec * req.gpu_count as f64 * req.duration_secs as f64).ceil() as u64,
+                jobs: BTreeMap::new(),
+            };
+            self.reservations.insert(req.reservation_id.clone(), reservation.clone());
+            return Ok(reservation);
+        }
+        Err(BookingError::Overbooked { requested: req.gpu_count, available: most_free })
+    }
+
+    /// GPUs reservations overlapping [start, end) hold on a node
+    fn held(&self, pubkey: &str, start: u64, end: u64) -> u32 {
+        self.reservations
+            .values()
+            .filter(|r| r.overlaps(start, end))
+            .filter_map(|r| r.allocations.get(pubkey))
+            .sum()
+    }
+
+    /// Reserved GPUs on a node that ordinary jobs must leave free at `now`:
+    /// those of windows open or opening within `lead_secs`, less the ones
+    /// their own jobs occupy
+    pub fn held_idle(&self, pubkey: &str, now: u64, lead_secs: u64) -> u32 {
+        self.reservations
+            .values()
+            .filter(|r| r.start <= now + lead_secs && now < r.end)
+            .filter_map(|r| r.allocations.get(pubkey).map(|gpus| gpus.saturating_sub(r.running_on(pubkey))))
+            .sum()
+    }
+
+    /// Reserved GPUs on a node not yet taken by the reservation's jobs
+    pub fn free_slots(&self, reservation_id: &str, pubkey: &str) -> u32 {
+        self.reservations.get(reservation_id).map_or(0, |r| {
+            r.allocations.get(pubkey).copied().unwrap_or(0).saturating_sub(r.running_on(pubkey))
+        })
+    }
+
+    pub fn attach(&mut self, reservation_id: &str, job_id: &str, pubkey: &str) {
+        if let Some(reservation) = self.reservations.get_mut(reservation_id) {
+            reservation.jobs.insert(job_id.to_string(), pubkey.to_string());
+        }
+    }
+
+    /// Return a finished or moved job's GPU to its reservation. Returns the
+    /// reservation it held a GPU of.
+    pub fn detach(&mut self, job_id: &str) -> Option<String> {
+        self.reservations
+            .values_mut()
+            .find_map(|r| r.jobs.remove(job_id).map(|_| r.reservation_id.clone()))
+    }
+
+    pub fn cancel(&mut self, reservation_id: &str) -> Option<Reservation> {
+        self.reservations.remove(reservation_id)
+    }
+}
@@ -0,0 +1,151 @@
+//! Read-Through Cache
+//! Short-lived cache for values decoded from contract reads, so the services
+//! calling `AIJobManager.getJob` during submit, schedule and assign pay for
+//! one RPC round trip and one decode per job instead of one per lookup.
+//! Entries expire after the TTL and are dropped by `invalidate` whenever the
+//! service writes a change to the value on-chain. A load that was already in
+//! flight when an invalidation happened is returned but never cached, so a
+//! pre-write read cannot outlive the write.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Entries beyond this trigger a sweep of expired ones
+const SWEEP_AT: usize = 4_096;
+
+pub const DEFAULT_JOB_TTL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64, // Lookups that ran the loader
+}
+
+struct Inner<V> {
+    entries: HashMap<String, (Instant, V)>,
+    invalidations: u64, // Bumped by every invalidate; loads started before a bump are not cached
+    stats: CacheStats,
+}
+
+pub struct ReadThrough<V> {
+    ttl: Duration,
+    inner: Mutex<Inner<V>>,
+}
+
+impl<V: Clone> ReadThrough<V> {
+    pub fn new(ttl: Duration) -> Self {
+        ReadThrough {
+            ttl,
+            inner: Mutex::new(Inner { entries: HashMap::new(), invalidations: 0, stats: CacheStats::default() }),
+        }
+    }
+
+    /// Cache for decoded jobs, with the TTL from `ARTHA_JOB_CACHE_TTL_MS`
+    pub fn jobs() -> Self {
+        Self::new(
+            std::env::var("ARTHA_JOB_CACHE_TTL_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_JOB_TTL),
+        )
+    }
+
+    /// The cached value for `key`, or the result of `load` on a miss.
+    /// Errors are passed through and never cached.
+    pub async fn get_or_load<E, F>(&self, key: &str, load: F) -> Result<V, E>
+    where
+        F: Future<Output = Result<V, E>>,
+    {
+        let started = {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some((loaded_at, value)) = inner.entries.get(key) {
+                if loaded_at.elapsed() < self.ttl {
+                    let value = value.clone();
+                    inner.stats.hits += 1;
+                    return Ok(value);
+                }
+            }
+            inner.stats.misses += 1;
+            inner.invalidations
+        };
+
+        let value = load.await?;
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.invalidations == started {
+            if inner.entries.len() >= SWEEP_AT {
+                let ttl = self.ttl;
+                inner.entries.retain(|_, (loaded_at, _)| loaded_at.elapsed() < ttl);
+            }
+            inner.entries.insert(key.to_string(), (Instant::now(), value.clone()));
+        }
+        Ok(value)
+    }
+
+    /// Drop `key` after a write that changes it
+    pub fn invalidate(&self, key: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.remove(key);
+        inner.invalidations += 1;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().unwrap().stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn load(loads: &AtomicU32, value: &str) -> Result<String, String> {
+        loads.fetch_add(1, Ordering::SeqCst);
+        Ok(value.to_string())
+    }
+
+    #[tokio::test]
+    async fn lookups_within_ttl_load_once_until_invalidated() {
+        let cache = ReadThrough::new(Duration::from_secs(60));
+        let loads = AtomicU32::new(0);
+
+        assert_eq!(cache.get_or_load("job-1", load(&loads, "queued")).await.unwrap(), "queued");
+        assert_eq!(cache.get_or_load("job-1", load(&loads, "ignored")).await.unwrap(), "queued");
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+        cache.invalidate("job-1");
+        assert_eq!(cache.get_or_load("job-1", load(&loads, "assigned")).await.unwrap(), "assigned");
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // Failed loads are not cached
+        let failed: Result<String, String> = cache.get_or_load("job-2", async { Err("rpc down".to_string()) }).await;
+        assert!(failed.is_err());
+        assert_eq!(cache.get_or_load("job-2", load(&loads, "queued")).await.unwrap(), "queued");
+    }
+
+    #[tokio::test]
+    async fn entries_expire_and_in_flight_loads_lose_to_invalidation() {
+        let cache = ReadThrough::new(Duration::from_millis(20));
+        let loads = AtomicU32::new(0);
+        cache.get_or_load("job-1", load(&loads, "queued")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        cache.get_or_load("job-1", load(&loads, "running")).await.unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // A read that started before the write returns its value but is not kept
+        let cache = ReadThrough::new(Duration::from_secs(60));
+        let stale = cache
+            .get_or_load("job-1", async {
+                cache.invalidate("job-1");
+                Ok::<_, String>("queued".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(stale, "queued");
+        assert_eq!(cache.get_or_load("job-1", load(&loads, "assigned")).await.unwrap(), "assigned");
+    }
+}
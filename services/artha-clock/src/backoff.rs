@@ -0,0 +1,73 @@
+//! Poll Backoff
+//! How a background loop spaces its turns. A healthy loop waits its base
+//! interval; each consecutive failure doubles the wait up to a ceiling, and
+//! a success drops it back to the base. Every wait is jittered downwards by
+//! up to `jitter` of itself, drawn from the service's entropy, so daemons
+//! started together drift apart instead of retrying in lockstep. A wait
+//! never exceeds its nominal delay.
+
+use crate::SharedEntropy;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackoffPolicy {
+    pub base: Duration,
+    pub max: Duration,
+    pub jitter: f64, // Fraction of a delay that may be shaved off, 0.0..=1.0
+}
+
+impl BackoffPolicy {
+    /// Poll every `base`, backing off to 16x that on repeated failures
+    pub fn every(base: Duration) -> Self {
+        BackoffPolicy { base, max: base * 16, jitter: 0.2 }
+    }
+
+    /// `every(default_base)`, overridden by `{PREFIX}_INTERVAL_SECS`,
+    /// `{PREFIX}_MAX_BACKOFF_SECS` and `{PREFIX}_JITTER`
+    pub fn from_env(prefix: &str, default_base: Duration) -> Self {
+        let var = |suffix: &str| std::env::var(format!("{}_{}", prefix, suffix)).ok();
+        let base = var("INTERVAL_SECS").and_then(|v| v.parse().ok()).map_or(default_base, Duration::from_secs);
+        let policy = Self::every(base);
+        BackoffPolicy {
+            max: var("MAX_BACKOFF_SECS").and_then(|v| v.parse().ok()).map_or(policy.max, Duration::from_secs),
+            jitter: var("JITTER").and_then(|v| v.parse().ok()).map_or(policy.jitter, |j: f64| j.clamp(0.0, 1.0)),
+            ..policy
+        }
+    }
+
+    /// The un-jittered wait after `failures` failures in a row
+    pub fn nominal(&self, failures: u32) -> Duration {
+        let factor = 1u32.checked_shl(failures).unwrap_or(u32::MAX);
+        self.base.saturating_mul(factor).min(self.max.max(self.base))
+    }
+}
+
+/// A loop's position in its policy: failures since the last success
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    pub policy: BackoffPolicy,
+    failures: u32,
+    entropy: SharedEntropy,
+}
+
+impl Backoff {
+    pub fn new(policy: BackoffPolicy, entropy: SharedEntropy) -> Self {
+        Backoff { policy, failures: 0, entropy }
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// How long to wait before the next turn
+    pub fn delay(&self) -> Duration {
+        let nominal = self.policy.nominal(self.failures);
+        let span = (nominal.as_millis() as f64 * self.policy.jitter) as u64;
+        nominal - Duration::from_millis(self.entropy.next_u64() % (span + 1))
+    }
+
+    /// Record a turn's outcome: a success resets the backoff, a failure deepens it
+    pub fn record(&mut self, ok: bool) {
+        self.failures = if ok { 0 } else { self.failures.saturating_add(1) };
+    }
+}
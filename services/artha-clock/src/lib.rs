@@ -0,0 +1,303 @@
+//! Clock and Entropy
+//! Where a service gets the time and its randomness. Production wires in the
+//! system clock and OS-seeded entropy. Tests wire in a manual clock they move
+//! by hand and a seeded entropy source, so time-dependent behaviour (budget
+//! windows, heartbeat staleness, intervals, backoff) runs deterministically
+//! and without real waits.
+//!
+//! Both are held in a service's state as trait objects and never appear in
+//! API responses; handlers read them the way they used to call `now()`.
+//! Background loops pace themselves with a `Backoff` over the two.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+mod backoff;
+pub use backoff::{Backoff, BackoffPolicy};
+
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+pub trait Clock: Send + Sync + Debug {
+    /// Milliseconds since the Unix epoch
+    fn now_millis(&self) -> u64;
+
+    /// Seconds since the Unix epoch
+    fn now_secs(&self) -> u64 {
+        self.now_millis() / 1000
+    }
+
+    /// Resolves once `duration` has passed on this clock
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The clock services run on
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to. Sleepers wake once the clock is
+/// advanced past their deadline. An auto-advancing clock instead jumps
+/// forward by each sleep, so retry and backoff loops run through instantly.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now_millis: Arc<watch::Sender<u64>>,
+    sleeps: Arc<AtomicU64>, // Sleeps ever started
+    auto_advance: bool,
+}
+
+impl ManualClock {
+    pub fn new(start_secs: u64) -> Self {
+        ManualClock {
+            now_millis: Arc::new(watch::channel(start_secs * 1000).0),
+            sleeps: Arc::default(),
+            auto_advance: false,
+        }
+    }
+
+    pub fn auto_advancing(start_secs: u64) -> Self {
+        ManualClock { auto_advance: true, ..Self::new(start_secs) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now_millis.send_modify(|now| *now += duration.as_millis() as u64);
+    }
+
+    pub fn advance_secs(&self, secs: u64) {
+        self.advance(Duration::from_secs(secs));
+    }
+
+    /// Move to `secs` past the epoch; the clock never goes backwards
+    pub fn set_secs(&self, secs: u64) {
+        self.now_millis.send_modify(|now| *now = (*now).max(secs * 1000));
+    }
+
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+
+    /// Tasks currently asleep on this clock
+    pub fn sleepers(&self) -> usize {
+        self.now_millis.receiver_count()
+    }
+
+    /// Yield until at least `count` tasks are asleep on this clock, so a test
+    /// advances time only once the loops it drives are waiting on it
+    pub async fn wait_for_sleepers(&self, count: usize) {
+        while self.sleepers() < count {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Advance, then yield until a task it woke has gone back to sleep: one
+    /// turn of a loop driven by this clock. Something must be due to wake.
+    pub async fn tick(&self, duration: Duration) {
+        let started = self.sleeps.load(Ordering::SeqCst);
+        self.advance(duration);
+        while self.sleeps.load(Ordering::SeqCst) == started {
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        *self.now_millis.borrow()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        let millis = duration.as_millis() as u64;
+        self.sleeps.fetch_add(1, Ordering::SeqCst);
+        if self.auto_advance {
+            let now = self.now_millis.clone();
+            return Box::pin(async move {
+                now.send_modify(|now| *now += millis);
+                tokio::task::yield_now().await;
+            });
+        }
+        let deadline = self.now_millis() + millis;
+        let mut now = self.now_millis.subscribe();
+        Box::pin(async move {
+            // The sender lives as long as the clock; if it is gone, nothing
+            // will ever wake this sleeper, so don't wait
+            let _ = now.wait_for(|now| *now >= deadline).await;
+        })
+    }
+}
+
+pub trait Entropy: Send + Sync + Debug {
+    fn next_u128(&self) -> u128;
+
+    fn next_u64(&self) -> u64 {
+        self.next_u128() as u64
+    }
+
+    /// A random (version 4) UUID drawn from this source
+    fn uuid(&self) -> Uuid {
+        uuid::Builder::from_random_bytes(self.next_u128().to_le_bytes()).into_uuid()
+    }
+}
+
+pub type SharedEntropy = Arc<dyn Entropy>;
+
+/// The thread-local, OS-seeded generator
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemEntropy;
+
+impl Entropy for SystemEntropy {
+    fn next_u128(&self) -> u128 {
+        rand::random()
+    }
+}
+
+/// The entropy services run on
+pub fn system_entropy() -> SharedEntropy {
+    Arc::new(SystemEntropy)
+}
+
+/// A repeatable sequence from a seed (SplitMix64)
+#[derive(Debug)]
+pub struct SeededEntropy {
+    state: Mutex<u64>,
+}
+
+impl SeededEntropy {
+    pub fn new(seed: u64) -> Self {
+        SeededEntropy { state: Mutex::new(seed) }
+    }
+
+    pub fn shared(seed: u64) -> SharedEntropy {
+        Arc::new(Self::new(seed))
+    }
+
+    fn next_word(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl Entropy for SeededEntropy {
+    fn next_u128(&self) -> u128 {
+        (self.next_word() as u128) << 64 | self.next_word() as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn manual_clock_wakes_sleepers_only_when_advanced() {
+        let clock = ManualClock::new(1_000);
+        assert_eq!((clock.now_secs(), clock.now_millis()), (1_000, 1_000_000));
+
+        let sleeper = tokio::spawn(clock.sleep(Duration::from_secs(60)));
+        clock.wait_for_sleepers(1).await;
+        clock.advance_secs(59);
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+        clock.advance_secs(1);
+        sleeper.await.unwrap();
+        assert_eq!(clock.sleepers(), 0);
+        assert_eq!(clock.now_secs(), 1_060);
+
+        clock.set_secs(10);
+        assert_eq!(clock.now_secs(), 1_060);
+
+        // A loop on the clock turns once per tick
+        let turns = Arc::new(AtomicU64::new(0));
+        let (looping, counted) = (clock.clone(), turns.clone());
+        let ticker = tokio::spawn(async move {
+            loop {
+                looping.sleep(Duration::from_secs(10)).await;
+                counted.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        clock.wait_for_sleepers(1).await;
+        for turn in 1..=3 {
+            clock.tick(Duration::from_secs(10)).await;
+            assert_eq!(turns.load(Ordering::SeqCst), turn);
+        }
+        ticker.abort();
+
+        // Fast-forward: a backoff loop's sleeps pass instantly on the clock
+        let clock = ManualClock::auto_advancing(0);
+        let started = std::time::Instant::now();
+        for attempt in 0..10 {
+            clock.sleep(Duration::from_secs(1 << attempt)).await;
+        }
+        assert_eq!(clock.now_secs(), 1_023);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn seeded_entropy_repeats_and_makes_v4_uuids() {
+        let (a, b) = (SeededEntropy::new(7), SeededEntropy::new(7));
+        let first: Vec<u128> = (0..3).map(|_| a.next_u128()).collect();
+        assert_eq!(first, (0..3).map(|_| b.next_u128()).collect::<Vec<_>>());
+        assert_ne!(first[0], first[1]);
+        assert_ne!(SeededEntropy::new(8).next_u128(), first[0]);
+
+        let id = a.uuid();
+        assert_eq!(id.get_version_num(), 4);
+        assert_eq!(id, b.uuid());
+        assert_ne!(SystemEntropy.uuid(), SystemEntropy.uuid());
+    }
+
+    #[test]
+    fn backoff_delays_stay_within_jittered_bounds_across_failures() {
+        let policy = BackoffPolicy { base: Duration::from_secs(30), max: Duration::from_secs(300), jitter: 0.2 };
+        let mut backoff = Backoff::new(policy, SeededEntropy::shared(3));
+        let nominal = [30, 60, 120, 240, 300, 300, 300];
+        let mut delays = Vec::new();
+        for secs in nominal {
+            let expected = Duration::from_secs(secs);
+            for _ in 0..50 {
+                let delay = backoff.delay();
+                assert!(delay <= expected && delay >= expected.mul_f64(0.8), "{:?} outside {:?}", delay, expected);
+                delays.push(delay);
+            }
+            backoff.record(false);
+        }
+        // Jitter actually spreads the waits
+        delays.sort_unstable();
+        delays.dedup();
+        assert!(delays.len() > 300);
+
+        // One success drops straight back to the base interval
+        backoff.record(true);
+        assert_eq!(backoff.failures(), 0);
+        assert!(backoff.delay() <= Duration::from_secs(30));
+
+        // Identical seeds jitter identically; no jitter means exact delays
+        let (a, b) = (Backoff::new(policy, SeededEntropy::shared(9)), Backoff::new(policy, SeededEntropy::shared(9)));
+        assert_eq!((0..5).map(|_| a.delay()).collect::<Vec<_>>(), (0..5).map(|_| b.delay()).collect::<Vec<_>>());
+        let exact = Backoff::new(BackoffPolicy { jitter: 0.0, ..policy }, SeededEntropy::shared(1));
+        assert_eq!(exact.delay(), Duration::from_secs(30));
+        assert_eq!(BackoffPolicy::every(Duration::from_secs(60)).nominal(40), Duration::from_secs(960));
+    }
+}
@@ -0,0 +1,70 @@
+//! Request Deadlines
+//! A caller that stops waiting at some point says when in `x-artha-deadline`,
+//! as Unix milliseconds. A service checks the deadline before each step that
+//! is costly or hard to undo, passes the header on unchanged to the services
+//! it calls and gives those calls no longer than what is left. Once the
+//! deadline passes, a handler undoes what it already did and answers
+//! `deadline_exceeded` rather than finishing work nobody is waiting for.
+//!
+//! Deadlines are absolute, so they hold across any number of hops without
+//! each hop shaving off its own latency. They assume the services' clocks
+//! agree to well within a request's budget.
+
+use crate::{ErrorCode, ServiceError};
+use axum::http::HeaderMap;
+use std::time::Duration;
+
+/// Header carrying the caller's deadline in Unix milliseconds
+pub const DEADLINE_HEADER: &str = "x-artha-deadline";
+
+/// When the caller stops waiting; requests without the header have no deadline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadline(Option<u64>);
+
+impl Deadline {
+    pub const NONE: Deadline = Deadline(None);
+
+    pub fn at_millis(millis: u64) -> Self {
+        Deadline(Some(millis))
+    }
+
+    /// The deadline a request carries. A header that isn't a millisecond
+    /// timestamp is refused rather than ignored.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, ServiceError> {
+        let Some(value) = headers.get(DEADLINE_HEADER) else {
+            return Ok(Deadline::NONE);
+        };
+        value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .map(Deadline::at_millis)
+            .ok_or_else(|| ServiceError::new(ErrorCode::InvalidRequest, format!("{} must be Unix milliseconds", DEADLINE_HEADER)))
+    }
+
+    pub fn millis(&self) -> Option<u64> {
+        self.0
+    }
+
+    /// Time left at `now_millis`, zero once passed; None without a deadline
+    pub fn remaining(&self, now_millis: u64) -> Option<Duration> {
+        self.0.map(|at| Duration::from_millis(at.saturating_sub(now_millis)))
+    }
+
+    pub fn has_passed(&self, now_millis: u64) -> bool {
+        self.0.is_some_and(|at| now_millis >= at)
+    }
+
+    /// Refuse to start `step` once the deadline has passed
+    pub fn check(&self, now_millis: u64, step: &str) -> Result<(), ServiceError> {
+        if self.has_passed(now_millis) {
+            return Err(ServiceError::new(ErrorCode::DeadlineExceeded, format!("Deadline passed before {}", step)));
+        }
+        Ok(())
+    }
+
+    /// The header value to forward, if there is a deadline
+    pub fn header_value(&self) -> Option<String> {
+        self.0.map(|at| at.to_string())
+    }
+}
@@ -0,0 +1,323 @@
+//! Service Error Taxonomy
+//! One error shape for every AI service. Each failure has a stable numeric code
+//! and a category saying whose fault it was: the caller's (client), the
+//! service's own (server), or something it depends on (dependency). A policy
+//! denial looks the same whether policy-gate or ai-jobd reports it.
+//!
+//! A caller's deadline travels between the services with the request, as a
+//! `Deadline`; whichever service finds it passed answers `deadline_exceeded`.
+
+use axum::{
+    body::HttpBody,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+mod deadline;
+pub use deadline::{Deadline, DEADLINE_HEADER};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Client,     // The request can't succeed as sent
+    Server,     // The service failed or is saturated
+    Dependency, // An upstream service or the chain failed
+}
+
+/// Stable error codes: 1xxx client, 2xxx server, 3xxx dependency.
+/// Codes are never reused or renumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InvalidRequest = 1000,
+    Unauthenticated = 1001,
+    Forbidden = 1002,
+    PolicyDenied = 1003,
+    NotFound = 1004,
+    Conflict = 1005,
+    Gone = 1006,
+    PreconditionFailed = 1007,
+    PayloadTooLarge = 1008,
+    Unprocessable = 1009,
+    PreconditionRequired = 1010,
+    RateLimited = 1011,
+    DeadlineExceeded = 1012, // The caller's `x-artha-deadline` passed
+    Internal = 2000,
+    NotImplemented = 2001,
+    Unavailable = 2002,
+    QueueFull = 2003,
+    DependencyUnavailable = 3000, // Upstream unreachable
+    DependencyFailed = 3001,      // Upstream answered with an error
+    DependencyTimeout = 3002,
+}
+
+const ALL_CODES: [ErrorCode; 20] = [
+    ErrorCode::InvalidRequest,
+    ErrorCode::Unauthenticated,
+    ErrorCode::Forbidden,
+    ErrorCode::PolicyDenied,
+    ErrorCode::NotFound,
+    ErrorCode::Conflict,
+    ErrorCode::Gone,
+    ErrorCode::PreconditionFailed,
+    ErrorCode::PayloadTooLarge,
+    ErrorCode::Unprocessable,
+    ErrorCode::PreconditionRequired,
+    ErrorCode::RateLimited,
+    ErrorCode::DeadlineExceeded,
+    ErrorCode::Internal,
+    ErrorCode::NotImplemented,
+    ErrorCode::Unavailable,
+    ErrorCode::QueueFull,
+    ErrorCode::DependencyUnavailable,
+    ErrorCode::DependencyFailed,
+    ErrorCode::DependencyTimeout,
+];
+
+impl ErrorCode {
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        ALL_CODES.into_iter().find(|c| c.code() == code)
+    }
+
+    /// Machine-readable name, sent as `error`
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::Unauthenticated => "unauthenticated",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::PolicyDenied => "policy_denied",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::Gone => "gone",
+            ErrorCode::PreconditionFailed => "precondition_failed",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::Unprocessable => "unprocessable",
+            ErrorCode::PreconditionRequired => "precondition_required",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::DeadlineExceeded => "deadline_exceeded",
+            ErrorCode::Internal => "internal",
+            ErrorCode::NotImplemented => "not_implemented",
+            ErrorCode::Unavailable => "unavailable",
+            ErrorCode::QueueFull => "queue_full",
+            ErrorCode::DependencyUnavailable => "dependency_unavailable",
+            ErrorCode::DependencyFailed => "dependency_failed",
+            ErrorCode::DependencyTimeout => "dependency_timeout",
+        }
+    }
+
+    pub fn category(self) -> Category {
+        match self.code() {
+            1000..=1999 => Category::Client,
+            2000..=2999 => Category::Server,
+            _ => Category::Dependency,
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthenticated => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden | ErrorCode::PolicyDenied => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::Gone => StatusCode::GONE,
+            ErrorCode::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::Unprocessable => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            ErrorCode::RateLimited | ErrorCode::QueueFull => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::DeadlineExceeded => StatusCode::REQUEST_TIMEOUT,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::NotImplemented => StatusCode::NOT_IMPLEMENTED,
+            ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::DependencyUnavailable | ErrorCode::DependencyFailed => StatusCode::BAD_GATEWAY,
+            ErrorCode::DependencyTimeout => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// The code a bare status stands for, if it has an unambiguous one
+    pub fn for_status(status: StatusCode) -> Option<Self> {
+        let code = match status {
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+            StatusCode::BAD_GATEWAY => ErrorCode::DependencyFailed,
+            other => return ALL_CODES.into_iter().find(|c| c.status() == other),
+        };
+        Some(code)
+    }
+}
+
+/// The JSON body every service returns on failure
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceError {
+    pub code: u32,
+    pub error: String,
+    pub category: Category,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl ServiceError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ServiceError {
+            code: code.code(),
+            error: code.name().to_string(),
+            category: code.category(),
+            message: message.into(),
+            retry_after_secs: None,
+            details: None,
+        }
+    }
+
+    pub fn policy_denied(reason: impl Into<String>) -> Self {
+        ServiceError::new(ErrorCode::PolicyDenied, reason)
+    }
+
+    pub fn queue_full(retry_after_secs: u64) -> Self {
+        ServiceError::new(ErrorCode::QueueFull, "Scheduler queue is full").with_retry_after(retry_after_secs)
+    }
+
+    pub fn with_retry_after(mut self, secs: u64) -> Self {
+        self.retry_after_secs = Some(secs);
+        self
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// None for codes from a newer peer this build doesn't know
+    pub fn kind(&self) -> Option<ErrorCode> {
+        ErrorCode::from_code(self.code)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self.kind() {
+            Some(code) => code.status(),
+            None => match self.category {
+                Category::Client => StatusCode::BAD_REQUEST,
+                Category::Server => StatusCode::INTERNAL_SERVER_ERROR,
+                Category::Dependency => StatusCode::BAD_GATEWAY,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.error, self.code, self.message)
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+/// Lets handlers returning bare statuses adopt the taxonomy with `?`
+impl From<StatusCode> for ServiceError {
+    fn from(status: StatusCode) -> Self {
+        let code = ErrorCode::for_status(status).unwrap_or(if status.is_client_error() {
+            ErrorCode::InvalidRequest
+        } else {
+            ErrorCode::Internal
+        });
+        ServiceError::new(code, status.canonical_reason().unwrap_or("Request failed"))
+    }
+}
+
+impl IntoResponse for ServiceError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let retry_after = self.retry_after_secs.and_then(|secs| HeaderValue::from_str(&secs.to_string()).ok());
+        let mut response = (status, Json(self)).into_response();
+        if let Some(value) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        response
+    }
+}
+
+/// `map_response` middleware: gives bodiless error responses (a handler's bare
+/// `StatusCode`) the structured body. Headers such as `Retry-After` are kept,
+/// and responses that already have a body pass through untouched.
+pub async fn normalize(response: Response) -> Response {
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) || response.body().size_hint().exact() != Some(0) {
+        return response;
+    }
+    let Some(code) = ErrorCode::for_status(status) else {
+        return response;
+    };
+    let (parts, _) = response.into_parts();
+    let mut structured = ServiceError::new(code, status.canonical_reason().unwrap_or("Request failed")).into_response();
+    for (name, value) in parts.headers.iter() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            structured.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    structured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_categorised_by_range() {
+        for (i, code) in ALL_CODES.iter().enumerate() {
+            assert_eq!(ErrorCode::from_code(code.code()), Some(*code));
+            assert!(ALL_CODES[i + 1..].iter().all(|other| other.code() != code.code() && other.name() != code.name()));
+        }
+        assert_eq!(ErrorCode::PolicyDenied.code(), 1003);
+        assert_eq!(ErrorCode::PolicyDenied.category(), Category::Client);
+        assert_eq!(ErrorCode::QueueFull.category(), Category::Server);
+        assert_eq!(ErrorCode::DependencyTimeout.category(), Category::Dependency);
+        assert_eq!(ErrorCode::for_status(StatusCode::GATEWAY_TIMEOUT), Some(ErrorCode::DependencyTimeout));
+        assert_eq!(ErrorCode::for_status(StatusCode::REQUEST_TIMEOUT), Some(ErrorCode::DeadlineExceeded));
+    }
+
+    #[test]
+    fn deadlines_are_read_from_headers_and_checked_against_the_clock() {
+        let mut headers = axum::http::HeaderMap::new();
+        assert_eq!(Deadline::from_headers(&headers).unwrap(), Deadline::NONE);
+        assert!(Deadline::NONE.check(u64::MAX, "anything").is_ok());
+        assert_eq!(Deadline::NONE.remaining(0), None);
+
+        headers.insert(DEADLINE_HEADER, HeaderValue::from_static("1700000002500"));
+        let deadline = Deadline::from_headers(&headers).unwrap();
+        assert_eq!(deadline.header_value().as_deref(), Some("1700000002500"));
+        assert_eq!(deadline.remaining(1_700_000_000_000), Some(std::time::Duration::from_millis(2500)));
+        assert!(deadline.check(1_700_000_002_499, "scheduling").is_ok());
+        let error = deadline.check(1_700_000_002_500, "scheduling").unwrap_err();
+        assert_eq!((error.kind(), error.status()), (Some(ErrorCode::DeadlineExceeded), StatusCode::REQUEST_TIMEOUT));
+        assert_eq!(error.message, "Deadline passed before scheduling");
+        assert_eq!(deadline.remaining(1_800_000_000_000), Some(std::time::Duration::ZERO));
+
+        headers.insert(DEADLINE_HEADER, HeaderValue::from_static("in five seconds"));
+        assert_eq!(Deadline::from_headers(&headers).unwrap_err().kind(), Some(ErrorCode::InvalidRequest));
+    }
+
+    #[tokio::test]
+    async fn normalize_structures_bare_statuses_only() {
+        let bare = normalize((StatusCode::NOT_FOUND, [(header::RETRY_AFTER, "5")]).into_response()).await;
+        assert_eq!(bare.status(), StatusCode::NOT_FOUND);
+        assert_eq!(bare.headers().get(header::RETRY_AFTER).unwrap(), "5");
+        let body = axum::body::to_bytes(bare.into_body(), usize::MAX).await.unwrap();
+        let error: ServiceError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error, ServiceError::new(ErrorCode::NotFound, "Not Found"));
+
+        let with_body = normalize((StatusCode::CONFLICT, "already exists").into_response()).await;
+        let body = axum::body::to_bytes(with_body.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"already exists");
+
+        let ok = normalize(StatusCode::NO_CONTENT.into_response()).await;
+        assert_eq!(ok.status(), StatusCode::NO_CONTENT);
+    }
+}
@@ -0,0 +1,274 @@
+//! Job Log Buffers
+//! What a service keeps in memory of a job's log: the newest lines, bounded
+//! by count and bytes, so a long-running job cannot grow its record without
+//! limit. Every line is also queued for durable storage. The service flushes
+//! the queue to SVDB in chunks and records each chunk's CID, so the complete
+//! log is the stored chunks in order followed by whatever is still queued.
+//! If storage stays unreachable the queue is capped too, and the lines it
+//! sheds are counted as lost rather than kept.
+//!
+//! Lines are numbered from 0 in the order they were pushed; numbers survive
+//! eviction, so a reader can resume where it left off.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogLimits {
+    pub max_lines: usize,   // Lines kept in memory
+    pub max_bytes: usize,   // Bytes kept in memory; the newest line is always kept
+    pub max_pending: usize, // Lines waiting for storage before the oldest are dropped
+}
+
+impl LogLimits {
+    pub const DEFAULT: LogLimits = LogLimits { max_lines: 1_000, max_bytes: 256 * 1024, max_pending: 10_000 };
+
+    /// Limits from `ARTHA_JOB_LOG_MAX_LINES`, `ARTHA_JOB_LOG_MAX_BYTES` and
+    /// `ARTHA_JOB_LOG_MAX_PENDING`
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: usize| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        LogLimits {
+            max_lines: var("ARTHA_JOB_LOG_MAX_LINES", Self::DEFAULT.max_lines),
+            max_bytes: var("ARTHA_JOB_LOG_MAX_BYTES", Self::DEFAULT.max_bytes),
+            max_pending: var("ARTHA_JOB_LOG_MAX_PENDING", Self::DEFAULT.max_pending),
+        }
+    }
+}
+
+impl Default for LogLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Queued lines handed out for storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingChunk {
+    pub first: u64, // Number of the first line
+    pub lines: Vec<String>,
+}
+
+impl PendingChunk {
+    /// Newline-terminated lines, as uploaded
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for line in &self.lines {
+            bytes.extend_from_slice(line.as_bytes());
+            bytes.push(b'\n');
+        }
+        bytes
+    }
+}
+
+/// Lines of a stored chunk
+pub fn decode_chunk(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes).lines().map(str::to_string).collect()
+}
+
+/// A job's log tail plus its storage bookkeeping. Serializes as the list of
+/// lines kept in memory, so job records keep their `logs: [...]` shape.
+#[derive(Debug, Clone, Default)]
+pub struct JobLog {
+    lines: VecDeque<String>,
+    bytes: usize,
+    total: u64, // Lines ever pushed
+    pending: VecDeque<String>,
+    chunks: Vec<String>, // CIDs of stored chunks, oldest first
+    lost: u64,           // Dropped from the queue before they were stored
+    limits: LogLimits,
+}
+
+impl JobLog {
+    pub fn new(limits: LogLimits) -> Self {
+        JobLog { limits, ..Default::default() }
+    }
+
+    pub fn push(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        self.bytes += line.len();
+        self.lines.push_back(line.clone());
+        while self.lines.len() > self.limits.max_lines.max(1)
+            || (self.bytes > self.limits.max_bytes && self.lines.len() > 1)
+        {
+            if let Some(evicted) = self.lines.pop_front() {
+                self.bytes -= evicted.len();
+            }
+        }
+
+        self.pending.push_back(line);
+        while self.pending.len() > self.limits.max_pending.max(1) {
+            self.pending.pop_front();
+            self.lost += 1;
+        }
+        self.total += 1;
+    }
+
+    /// Lines kept in memory, oldest first
+    pub fn lines(&self) -> impl Iterator<Item = &String> {
+        self.lines.iter()
+    }
+
+    pub fn to_vec(&self) -> Vec<String> {
+        self.lines.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Lines ever pushed; the next line's number
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Number of the oldest line still in memory
+    pub fn first_kept(&self) -> u64 {
+        self.total - self.lines.len() as u64
+    }
+
+    /// Kept lines numbered `from` onwards, with the number of the first one
+    /// returned. A reader that fell behind the tail resumes at its start.
+    pub fn since(&self, from: u64) -> (u64, Vec<String>) {
+        let first = from.max(self.first_kept());
+        let skip = (first - self.first_kept()) as usize;
+        (first, self.lines.iter().skip(skip).cloned().collect())
+    }
+
+    /// Take every queued line for storage; `stored` or `restore` must follow
+    pub fn take_pending(&mut self) -> Option<PendingChunk> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = self.pending.drain(..).collect();
+        Some(PendingChunk { first: self.total - lines.len() as u64, lines })
+    }
+
+    /// Record a chunk from `take_pending` as stored under `cid`
+    pub fn stored(&mut self, cid: String) {
+        self.chunks.push(cid);
+    }
+
+    /// Put back a chunk whose upload failed, ahead of lines queued since
+    pub fn restore(&mut self, chunk: PendingChunk) {
+        for line in chunk.lines.into_iter().rev() {
+            self.pending.push_front(line);
+        }
+        while self.pending.len() > self.limits.max_pending.max(1) {
+            self.pending.pop_front();
+            self.lost += 1;
+        }
+    }
+
+    /// CIDs of the stored chunks, oldest first
+    pub fn chunks(&self) -> &[String] {
+        &self.chunks
+    }
+
+    /// Lines not yet stored, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &String> {
+        self.pending.iter()
+    }
+
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+}
+
+impl From<Vec<String>> for JobLog {
+    fn from(lines: Vec<String>) -> Self {
+        let mut log = JobLog::new(LogLimits::DEFAULT);
+        for line in lines {
+            log.push(line);
+        }
+        log
+    }
+}
+
+impl Serialize for JobLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.lines.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for JobLog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<String>::deserialize(deserializer).map(JobLog::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_lines: usize, max_bytes: usize, max_pending: usize) -> LogLimits {
+        LogLimits { max_lines, max_bytes, max_pending }
+    }
+
+    #[test]
+    fn tail_is_bounded_by_lines_and_bytes() {
+        let mut log = JobLog::new(limits(3, 1_000, 100));
+        for i in 0..10 {
+            log.push(format!("line {}", i));
+        }
+        assert_eq!(log.to_vec(), vec!["line 7", "line 8", "line 9"]);
+        assert_eq!((log.total(), log.first_kept()), (10, 7));
+
+        let mut log = JobLog::new(limits(100, 10, 100));
+        log.push("aaaa");
+        log.push("bbbb");
+        log.push("cccc");
+        assert_eq!(log.to_vec(), vec!["bbbb", "cccc"]);
+        assert_eq!(log.bytes(), 8);
+        // A single oversized line is still kept
+        log.push("x".repeat(50));
+        assert_eq!(log.len(), 1);
+
+        // Readers resume by line number, skipping ahead past evicted lines
+        let mut log = JobLog::new(limits(3, 1_000, 100));
+        for i in 0..5 {
+            log.push(format!("line {}", i));
+        }
+        assert_eq!(log.since(0), (2, vec!["line 2".to_string(), "line 3".to_string(), "line 4".to_string()]));
+        assert_eq!(log.since(4), (4, vec!["line 4".to_string()]));
+        assert_eq!(log.since(5), (5, vec![]));
+
+        assert_eq!(serde_json::to_value(&log).unwrap(), serde_json::json!(["line 2", "line 3", "line 4"]));
+    }
+
+    #[test]
+    fn queued_lines_survive_failed_uploads_up_to_the_cap() {
+        let mut log = JobLog::new(limits(2, 1_000, 4));
+        log.push("a");
+        log.push("b");
+        let chunk = log.take_pending().unwrap();
+        assert_eq!(chunk, PendingChunk { first: 0, lines: vec!["a".to_string(), "b".to_string()] });
+        assert_eq!(decode_chunk(&chunk.encode()), chunk.lines);
+        log.stored("cid-1".to_string());
+        assert!(log.take_pending().is_none());
+
+        log.push("c");
+        let failed = log.take_pending().unwrap();
+        log.push("d");
+        log.restore(failed);
+        assert_eq!(log.pending().collect::<Vec<_>>(), vec!["c", "d"]);
+
+        // Storage down for long: the oldest queued lines are shed and counted
+        for line in ["e", "f", "g"] {
+            log.push(line);
+        }
+        assert_eq!(log.pending().collect::<Vec<_>>(), vec!["d", "e", "f", "g"]);
+        assert_eq!(log.lost(), 1);
+        assert_eq!(log.take_pending().unwrap().first, 3);
+        assert_eq!(log.chunks(), &["cid-1".to_string()]);
+    }
+}
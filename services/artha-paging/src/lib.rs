@@ -0,0 +1,140 @@
+//! Listing Pagination
+//! One page shape for every listing endpoint: `{ items, total, next_cursor }`,
+//! driven by `limit` and `cursor` query params. Items are ordered by a unique
+//! sort key and the cursor is the opaque key of the last item served, so the
+//! next page starts strictly after it. Inserts between requests never shift
+//! a page the way an offset would: nothing is served twice and nothing
+//! already past the cursor is skipped.
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LIMIT: usize = 100;
+pub const MAX_LIMIT: usize = 1_000;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize, // Items matching the request's filters, across all pages
+    pub next_cursor: Option<String>, // None on the last page
+}
+
+/// A cursor this service did not issue
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidCursor;
+
+impl From<InvalidCursor> for StatusCode {
+    fn from(_: InvalidCursor) -> Self {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    /// Sort key the cursor resumes after
+    fn after(&self) -> Result<Option<String>, InvalidCursor> {
+        self.cursor.as_deref().map(decode_cursor).transpose()
+    }
+}
+
+/// One page of `items`, which are already filtered. `key` must be unique
+/// per item and stable for its lifetime; pages are ordered by it.
+pub fn paginate<T>(
+    items: impl IntoIterator<Item = T>,
+    key: impl Fn(&T) -> String,
+    query: &PageQuery,
+) -> Result<Page<T>, InvalidCursor> {
+    let after = query.after()?;
+    let mut keyed: Vec<(String, T)> = items.into_iter().map(|item| (key(&item), item)).collect();
+    let total = keyed.len();
+    keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let start = match &after {
+        Some(after) => keyed.partition_point(|(key, _)| key <= after),
+        None => 0,
+    };
+    let limit = query.limit();
+    let mut page: Vec<(String, T)> = keyed.into_iter().skip(start).take(limit + 1).collect();
+    let next_cursor = if page.len() > limit {
+        page.truncate(limit);
+        page.last().map(|(key, _)| encode_cursor(key))
+    } else {
+        None
+    };
+
+    Ok(Page {
+        items: page.into_iter().map(|(_, item)| item).collect(),
+        total,
+        next_cursor,
+    })
+}
+
+/// Sort key for items ordered by a timestamp, tie-broken by id
+pub fn time_key(at: u64, id: &str) -> String {
+    format!("{:020}:{}", at, id)
+}
+
+fn encode_cursor(key: &str) -> String {
+    key.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_cursor(cursor: &str) -> Result<String, InvalidCursor> {
+    if !cursor.len().is_multiple_of(2) {
+        return Err(InvalidCursor);
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2).ok_or(InvalidCursor)?, 16).map_err(|_| InvalidCursor))
+        .collect::<Result<Vec<u8>, InvalidCursor>>()?;
+    String::from_utf8(bytes).map_err(|_| InvalidCursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(limit: usize, cursor: Option<String>) -> PageQuery {
+        PageQuery { limit: Some(limit), cursor }
+    }
+
+    #[test]
+    fn cursor_pages_do_not_overlap_under_inserts() {
+        let mut items: Vec<(u64, String)> = (0..7).map(|i| (i * 10, format!("item-{}", i))).collect();
+        let key = |item: &(u64, String)| time_key(item.0, &item.1);
+
+        let first = paginate(items.clone(), key, &query(3, None)).unwrap();
+        assert_eq!(first.total, 7);
+        assert_eq!(first.items.iter().map(|i| i.0).collect::<Vec<_>>(), vec![0, 10, 20]);
+
+        // Inserts before and after the cursor between requests
+        items.push((5, "early".to_string()));
+        items.push((65, "late".to_string()));
+        let second = paginate(items.clone(), key, &query(3, first.next_cursor.clone())).unwrap();
+        assert_eq!(second.total, 9);
+        assert_eq!(second.items.iter().map(|i| i.0).collect::<Vec<_>>(), vec![30, 40, 50]);
+
+        let third = paginate(items.clone(), key, &query(3, second.next_cursor.clone())).unwrap();
+        assert_eq!(third.items.iter().map(|i| i.0).collect::<Vec<_>>(), vec![60, 65]);
+        assert_eq!(third.next_cursor, None);
+    }
+
+    #[test]
+    fn limits_are_clamped_and_foreign_cursors_rejected() {
+        assert_eq!(PageQuery::default().limit(), DEFAULT_LIMIT);
+        assert_eq!(query(0, None).limit(), 1);
+        assert_eq!(query(MAX_LIMIT + 1, None).limit(), MAX_LIMIT);
+
+        let items = vec!["a".to_string()];
+        assert_eq!(paginate(items.clone(), |s| s.clone(), &query(1, Some("zz".to_string()))), Err(InvalidCursor));
+        assert_eq!(paginate(items, |s| s.clone(), &query(1, Some("abc".to_string()))), Err(InvalidCursor));
+    }
+}
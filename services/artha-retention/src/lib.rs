@@ -0,0 +1,111 @@
+//! Retention Policy
+//! Decides which finished records a service's background GC evicts: anything
+//! older than the retention window, then the oldest beyond the count cap.
+//! Evicted records can be appended to a JSONL archive before they leave
+//! memory.
+
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub max_age_secs: u64,
+    pub max_count: usize,            // Finished records kept in memory
+    pub interval_secs: u64,          // Time between GC passes
+    pub archive_path: Option<String>, // JSONL file evicted records are appended to
+}
+
+fn env_or<T: std::str::FromStr>(keys: &[String], default: T) -> T {
+    keys.iter()
+        .find_map(|key| std::env::var(key).ok().and_then(|v| v.parse().ok()))
+        .unwrap_or(default)
+}
+
+impl RetentionPolicy {
+    /// The policy for the service whose variables start with `prefix`, e.g.
+    /// `ARTHA_JOBD_RETENTION_MAX_AGE_SECS`. The limits fall back to the
+    /// shared `ARTHA_RETENTION_*` ones; the archive path doesn't, so no two
+    /// services append to the same file.
+    pub fn from_env(prefix: &str) -> Self {
+        let keys = |name: &str| [format!("{}_RETENTION_{}", prefix, name), format!("ARTHA_RETENTION_{}", name)];
+        RetentionPolicy {
+            max_age_secs: env_or(&keys("MAX_AGE_SECS"), 7 * 24 * 3600),
+            max_count: env_or(&keys("MAX_COUNT"), 10_000),
+            interval_secs: env_or(&keys("GC_INTERVAL_SECS"), 600),
+            archive_path: std::env::var(format!("{}_RETENTION_ARCHIVE_PATH", prefix)).ok(),
+        }
+    }
+
+    /// Ids to evict from `finished` (id, finished_at). Callers pass only
+    /// terminal records, so active ones can never be selected.
+    pub fn select(&self, mut finished: Vec<(String, u64)>, now: u64) -> Vec<String> {
+        finished.sort_by_key(|(_, finished_at)| std::cmp::Reverse(*finished_at));
+        finished
+            .into_iter()
+            .enumerate()
+            .filter(|(rank, (_, finished_at))| {
+                *rank >= self.max_count || now.saturating_sub(*finished_at) > self.max_age_secs
+            })
+            .map(|(_, (id, _))| id)
+            .collect()
+    }
+
+    /// Append evicted records to the archive file, if one is configured
+    pub fn archive<T: Serialize>(&self, records: &[T]) -> Result<(), String> {
+        let Some(path) = &self.archive_path else {
+            return Ok(());
+        };
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        for record in records {
+            let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+            writeln!(file, "{}", line).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_settings_override_shared_ones() {
+        std::env::set_var("ARTHA_RETENTION_MAX_COUNT", "50");
+        std::env::set_var("ARTHA_RETENTION_ARCHIVE_PATH", "/tmp/shared.jsonl");
+        std::env::set_var("ARTHA_TESTSVC_RETENTION_MAX_AGE_SECS", "60");
+        std::env::set_var("ARTHA_TESTSVC_RETENTION_MAX_COUNT", "5");
+
+        let policy = RetentionPolicy::from_env("ARTHA_TESTSVC");
+        assert_eq!((policy.max_age_secs, policy.max_count, policy.interval_secs), (60, 5, 600));
+        assert_eq!(policy.archive_path, None);
+        let other = RetentionPolicy::from_env("ARTHA_OTHERSVC");
+        assert_eq!((other.max_age_secs, other.max_count), (7 * 24 * 3600, 50));
+    }
+
+    #[test]
+    fn test_select_evicts_expired_then_oldest_past_the_cap() {
+        let policy = RetentionPolicy { max_age_secs: 100, max_count: 2, interval_secs: 60, archive_path: None };
+        let finished = vec![("old".to_string(), 0), ("a".to_string(), 950), ("b".to_string(), 980), ("c".to_string(), 990)];
+        let mut evicted = policy.select(finished, 1000);
+        evicted.sort();
+        assert_eq!(evicted, vec!["a".to_string(), "old".to_string()]);
+    }
+
+    #[test]
+    fn test_archive_appends_one_line_per_record() {
+        #[derive(Serialize)]
+        struct Record {
+            id: u32,
+        }
+        let path = std::env::temp_dir().join(format!("artha-retention-{}.jsonl", std::process::id()));
+        let policy = RetentionPolicy { max_age_secs: 0, max_count: 0, interval_secs: 60, archive_path: Some(path.to_str().unwrap().to_string()) };
+        policy.archive(&[Record { id: 1 }]).unwrap();
+        policy.archive(&[Record { id: 2 }]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"id\":1}\n{\"id\":2}\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! Contract Registry
+//! The addresses of the contracts a service calls, resolved once at startup.
+//! Each address comes from its env var and must be a 20-byte hex address,
+//! non-zero, with code deployed at it on the chain. Otherwise every call to
+//! that contract would fail later with an error that doesn't say why, so the
+//! service refuses to start and names each misconfigured contract.
+
+use crate::RpcEndpoints;
+use std::collections::BTreeMap;
+
+/// A contract a service calls, and the env var holding its address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contract {
+    pub name: &'static str,
+    pub env: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misconfigured {
+    pub contract: Contract,
+    pub address: Option<String>, // None if the env var is unset
+    pub problem: String,
+}
+
+/// Every contract that failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryError(pub Vec<Misconfigured>);
+
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "misconfigured contracts:")?;
+        for bad in &self.0 {
+            let address = bad.address.as_deref().unwrap_or("unset");
+            write!(f, " {} ({}={}): {};", bad.contract.name, bad.contract.env, address, bad.problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContractRegistry {
+    addresses: BTreeMap<&'static str, String>, // Contract name -> address
+}
+
+impl ContractRegistry {
+    /// Read each contract's address from its env var and check it on chain
+    pub async fn resolve(rpc: &RpcEndpoints, contracts: &[Contract]) -> Result<Self, RegistryError> {
+        let configured = contracts.iter().map(|contract| (*contract, std::env::var(contract.env).ok())).collect();
+        Self::validate(rpc, configured).await
+    }
+
+    /// Check the given addresses, collecting every problem rather than
+    /// stopping at the first
+    pub async fn validate(rpc: &RpcEndpoints, configured: Vec<(Contract, Option<String>)>) -> Result<Self, RegistryError> {
+        let mut registry = ContractRegistry::default();
+        let mut misconfigured = Vec::new();
+        for (contract, address) in configured {
+            let problem = match address.as_deref().map(str::trim) {
+                None | Some("") => Some("address is not set".to_string()),
+                Some(address) => check(rpc, address).await.err(),
+            };
+            match (problem, address) {
+                (None, Some(address)) => {
+                    registry.addresses.insert(contract.name, address.trim().to_string());
+                }
+                (problem, address) => misconfigured.push(Misconfigured {
+                    contract,
+                    address,
+                    problem: problem.unwrap_or_default(),
+                }),
+            }
+        }
+        if misconfigured.is_empty() {
+            Ok(registry)
+        } else {
+            Err(RegistryError(misconfigured))
+        }
+    }
+
+    pub fn address(&self, name: &str) -> Option<&str> {
+        self.addresses.get(name).map(String::as_str)
+    }
+}
+
+async fn check(rpc: &RpcEndpoints, address: &str) -> Result<(), String> {
+    let hex_part = address.strip_prefix("0x").unwrap_or(address);
+    if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("not a 20-byte hex address".to_string());
+    }
+    if hex_part.chars().all(|c| c == '0') {
+        return Err("zero address".to_string());
+    }
+    let code = rpc
+        .call("eth_getCode", serde_json::json!([address, "latest"]))
+        .await
+        .map_err(|e| format!("code could not be checked: {}", e))?;
+    let code = code.as_str().unwrap_or_default();
+    if code.trim_start_matches("0x").trim_start_matches('0').is_empty() {
+        return Err("no contract code at this address".to_string());
+    }
+    Ok(())
+}
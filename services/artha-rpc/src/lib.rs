@@ -0,0 +1,377 @@
+//! RPC Endpoints
+//! The chain nodes a service sends JSON-RPC to, primary first. A request goes
+//! to the endpoint that answered last and fails over to the next one on a
+//! connection error or a 5xx. The endpoint that answered stays preferred
+//! until it fails itself, so traffic doesn't flap back to a primary that is
+//! still recovering.
+//!
+//! Each endpoint has a circuit breaker. After `failure_threshold` failures in
+//! a row it is skipped for `cooldown`, then tried again: an answer closes the
+//! breaker, a failure reopens it. With every breaker open, requests fail fast
+//! instead of waiting on dead nodes.
+//!
+//! A JSON-RPC error is an answer, not a failure. It goes back to the caller
+//! and counts toward the endpoint's health like any other response.
+//!
+//! Clients go through [`RpcEndpoints::call`], which numbers each request and
+//! checks that the response answers it, see [`validate`].
+//!
+//! The contracts a service calls are resolved against these endpoints once
+//! at startup, see [`ContractRegistry`].
+
+mod contracts;
+mod response;
+
+pub use contracts::{Contract, ContractRegistry, Misconfigured, RegistryError};
+pub use response::{validate, RpcError};
+
+use artha_clock::SharedClock;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+pub const DEFAULT_RPC_URL: &str = "http://localhost:8545";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerPolicy {
+    pub failure_threshold: u32, // Failures in a row that open the breaker
+    pub cooldown: Duration,     // How long an open breaker skips its endpoint
+}
+
+impl Default for BreakerPolicy {
+    fn default() -> Self {
+        BreakerPolicy { failure_threshold: 3, cooldown: Duration::from_secs(30) }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub open_until_ms: Option<u64>, // Set while the breaker is open
+}
+
+#[derive(Debug)]
+pub struct RpcEndpoints {
+    endpoints: Vec<Mutex<EndpointHealth>>,
+    preferred: AtomicUsize, // Index of the endpoint that answered last
+    next_id: AtomicU64,
+    policy: BreakerPolicy,
+    client: reqwest::Client,
+    clock: SharedClock,
+}
+
+impl RpcEndpoints {
+    /// Endpoints in order of preference. Panics on an empty list.
+    pub fn new(urls: Vec<String>) -> Self {
+        assert!(!urls.is_empty(), "at least one RPC endpoint is required");
+        RpcEndpoints {
+            endpoints: urls.into_iter().map(|url| Mutex::new(EndpointHealth { url, ..Default::default() })).collect(),
+            preferred: AtomicUsize::new(0),
+            next_id: AtomicU64::new(1),
+            policy: BreakerPolicy::default(),
+            client: reqwest::Client::new(),
+            clock: artha_clock::system_clock(),
+        }
+    }
+
+    /// Endpoints from `ARTHA_RPC_URLS` (comma-separated, primary first), and
+    /// the breaker from `ARTHA_RPC_FAILURE_THRESHOLD` and `ARTHA_RPC_COOLDOWN_SECS`
+    pub fn from_env() -> Self {
+        let urls: Vec<String> = std::env::var("ARTHA_RPC_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        let defaults = BreakerPolicy::default();
+        let endpoints = if urls.is_empty() { Self::new(vec![DEFAULT_RPC_URL.to_string()]) } else { Self::new(urls) };
+        endpoints.with_policy(BreakerPolicy {
+            failure_threshold: var("ARTHA_RPC_FAILURE_THRESHOLD").map_or(defaults.failure_threshold, |n| n.max(1) as u32),
+            cooldown: var("ARTHA_RPC_COOLDOWN_SECS").map_or(defaults.cooldown, Duration::from_secs),
+        })
+    }
+
+    pub fn with_policy(mut self, policy: BreakerPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.endpoints.iter().map(|endpoint| endpoint.lock().unwrap().clone()).collect()
+    }
+
+    /// Call `method` and return its result, once the response is checked
+    /// against the request
+    pub async fn call(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": id });
+        let response = self.post(&request).await.map_err(RpcError::Transport)?;
+        validate(&request, response)
+    }
+
+    /// POST a JSON-RPC request and return the response body from the first
+    /// endpoint that answers, starting with the preferred one
+    pub async fn post(&self, payload: &serde_json::Value) -> Result<serde_json::Value, String> {
+        let preferred = self.preferred.load(Ordering::Relaxed);
+        let count = self.endpoints.len();
+        let mut errors = Vec::new();
+        for index in (0..count).map(|i| (preferred + i) % count) {
+            let url = {
+                let endpoint = self.endpoints[index].lock().unwrap();
+                if endpoint.open_until_ms.is_some_and(|until| self.clock.now_millis() < until) {
+                    continue;
+                }
+                endpoint.url.clone()
+            };
+            match self.send(&url, payload).await {
+                Ok(body) => {
+                    self.record(index, None);
+                    self.preferred.store(index, Ordering::Relaxed);
+                    return Ok(body);
+                }
+                Err(e) => {
+                    warn!("⚠️ RPC endpoint {} failed: {}", url, e);
+                    errors.push(format!("{}: {}", url, e));
+                    self.record(index, Some(e));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Err("RPC call failed: every endpoint's circuit breaker is open".to_string())
+        } else {
+            Err(format!("RPC call failed on every endpoint: {}", errors.join("; ")))
+        }
+    }
+
+    async fn send(&self, url: &str, payload: &serde_json::Value) -> Result<serde_json::Value, String> {
+        let response = self.client.post(url).json(payload).send().await.map_err(|e| e.to_string())?;
+        if response.status().is_server_error() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        response.json().await.map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    fn record(&self, index: usize, error: Option<String>) {
+        let mut endpoint = self.endpoints[index].lock().unwrap();
+        match error {
+            None => {
+                endpoint.successes += 1;
+                endpoint.consecutive_failures = 0;
+                endpoint.open_until_ms = None;
+            }
+            Some(e) => {
+                endpoint.failures += 1;
+                endpoint.consecutive_failures += 1;
+                endpoint.last_error = Some(e);
+                if endpoint.consecutive_failures >= self.policy.failure_threshold {
+                    endpoint.open_until_ms = Some(self.clock.now_millis() + self.policy.cooldown.as_millis() as u64);
+                }
+            }
+        }
+    }
+}
+
+/// A single endpoint
+impl From<String> for RpcEndpoints {
+    fn from(url: String) -> Self {
+        RpcEndpoints::new(vec![url])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use artha_clock::ManualClock;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use std::sync::Arc;
+
+    /// A node answering every request with `status`, counting the requests
+    async fn node(status: StatusCode, hits: Arc<AtomicUsize>) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move || {
+                let hits = hits.clone();
+                async move {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    (status, Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "0x1" })))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn request() -> serde_json::Value {
+        serde_json::json!({ "jsonrpc": "2.0", "method": "eth_blockNumber", "params": [], "id": 1 })
+    }
+
+    #[tokio::test]
+    async fn fails_over_and_sticks_with_the_endpoint_that_answered() {
+        let (primary_hits, secondary_hits) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let primary = node(StatusCode::BAD_GATEWAY, primary_hits.clone()).await;
+        let secondary = node(StatusCode::OK, secondary_hits.clone()).await;
+        // The first endpoint refuses connections, the second is down behind a proxy
+        let endpoints = RpcEndpoints::new(vec!["http://127.0.0.1:9".to_string(), primary, secondary]);
+
+        assert_eq!(endpoints.post(&request()).await.unwrap()["result"], "0x1");
+        assert_eq!(endpoints.post(&request()).await.unwrap()["result"], "0x1");
+        // The second request went straight to the endpoint that answered
+        assert_eq!((primary_hits.load(Ordering::SeqCst), secondary_hits.load(Ordering::SeqCst)), (1, 2));
+
+        let health = endpoints.health();
+        assert_eq!(health.iter().map(|h| h.failures).collect::<Vec<_>>(), vec![1, 1, 0]);
+        assert_eq!(health[1].last_error.as_deref(), Some("HTTP 502 Bad Gateway"));
+        assert_eq!(health[2].successes, 2);
+    }
+
+    #[tokio::test]
+    async fn breaker_skips_a_failing_endpoint_until_the_cooldown_passes() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let clock = ManualClock::new(1_000);
+        let endpoints = RpcEndpoints::from(node(StatusCode::SERVICE_UNAVAILABLE, hits.clone()).await)
+            .with_policy(BreakerPolicy { failure_threshold: 2, cooldown: Duration::from_secs(30) })
+            .with_clock(clock.shared());
+
+        for _ in 0..2 {
+            assert!(endpoints.post(&request()).await.unwrap_err().contains("HTTP 503"));
+        }
+        assert_eq!(endpoints.health()[0].open_until_ms, Some(1_030_000));
+        // Open: fail fast without touching the node
+        assert!(endpoints.post(&request()).await.unwrap_err().contains("circuit breaker is open"));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // After the cooldown it is tried again, and one more failure reopens it
+        clock.advance_secs(30);
+        assert!(endpoints.post(&request()).await.unwrap_err().contains("HTTP 503"));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert_eq!(endpoints.health()[0].open_until_ms, Some(1_060_000));
+    }
+
+    #[test]
+    fn valid_response_yields_its_result() {
+        let request = request();
+        let answer = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "0x1" });
+        assert_eq!(validate(&request, answer), Ok(serde_json::json!("0x1")));
+        // A null result, e.g. no receipt yet, is still a result
+        let pending = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": null });
+        assert_eq!(validate(&request, pending), Ok(serde_json::Value::Null));
+
+        let malformed = |response| match validate(&request, response) {
+            Err(RpcError::Malformed(reason)) => reason,
+            other => panic!("expected a malformed response, got {:?}", other),
+        };
+        assert!(malformed(serde_json::json!([{ "jsonrpc": "2.0", "id": 1, "result": "0x1" }])).contains("batch"));
+        assert!(malformed(serde_json::json!({ "jsonrpc": "1.0", "id": 1, "result": "0x1" })).contains("version"));
+        let notification = serde_json::json!({ "jsonrpc": "2.0", "method": "eth_subscription", "params": { "result": "0x1" } });
+        assert!(malformed(notification).contains("notification"));
+        assert!(malformed(serde_json::json!({ "jsonrpc": "2.0", "id": 1 })).contains("neither"));
+    }
+
+    #[test]
+    fn response_to_another_request_is_rejected() {
+        let request = request();
+        let answer = serde_json::json!({ "jsonrpc": "2.0", "id": 2, "result": "0x1" });
+        assert_eq!(
+            validate(&request, answer),
+            Err(RpcError::Malformed("id 2 does not match request id 1".to_string()))
+        );
+        let unnumbered = serde_json::json!({ "jsonrpc": "2.0", "result": "0x1" });
+        assert!(matches!(validate(&request, unnumbered), Err(RpcError::Malformed(_))));
+    }
+
+    #[tokio::test]
+    async fn error_object_is_surfaced_with_its_code() {
+        let app = Router::new().route(
+            "/",
+            post(|Json(request): Json<serde_json::Value>| async move {
+                let error = match request["method"].as_str() {
+                    Some("eth_call") => serde_json::json!({ "code": -32000, "message": "execution reverted", "data": "0x08c379a0" }),
+                    _ => serde_json::json!({ "message": "no code" }),
+                };
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let endpoints = RpcEndpoints::from(url);
+
+        let error = endpoints.call("eth_call", serde_json::json!([])).await.unwrap_err();
+        assert_eq!(error, RpcError::Rpc {
+            code: -32000,
+            message: "execution reverted".to_string(),
+            data: Some(serde_json::json!("0x08c379a0")),
+        });
+        assert_eq!(error.to_string(), "RPC error -32000: execution reverted");
+        let error = endpoints.call("eth_getCode", serde_json::json!([])).await.unwrap_err();
+        assert!(matches!(error, RpcError::Malformed(_)));
+        // Both were answers: the endpoint is healthy
+        assert_eq!(endpoints.health()[0].successes, 2);
+    }
+
+    /// A chain with code deployed only at `deployed`
+    async fn chain(deployed: &'static str) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<serde_json::Value>| async move {
+                let code = if request["params"][0] == deployed { "0x6080604052" } else { "0x" };
+                Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": code }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn registry_rejects_zero_and_codeless_addresses() {
+        let deployed = "0x5fbdb2315678afecb367f032d93f642f64180aa3";
+        let endpoints = RpcEndpoints::from(chain(deployed).await);
+        let contract = |name, env| Contract { name, env };
+        let (jobs, datasets, models, deals) = (
+            contract("AIJobManager", "AI_JOB_MANAGER_ADDR"),
+            contract("DatasetRegistry", "DATASET_REGISTRY_ADDR"),
+            contract("ModelRegistry", "MODEL_REGISTRY_ADDR"),
+            contract("DealMarket", "DEAL_MARKET_ADDR"),
+        );
+
+        let error = ContractRegistry::validate(&endpoints, vec![
+            (jobs, Some(deployed.to_string())),
+            (datasets, Some("0x0000000000000000000000000000000000000000".to_string())),
+            (models, Some("0x0000000000000000000000000000000000000003".to_string())),
+            (deals, None),
+        ])
+        .await
+        .unwrap_err();
+        let problems: Vec<_> = error.0.iter().map(|bad| (bad.contract.name, bad.problem.as_str())).collect();
+        assert_eq!(problems, vec![
+            ("DatasetRegistry", "zero address"),
+            ("ModelRegistry", "no contract code at this address"),
+            ("DealMarket", "address is not set"),
+        ]);
+        // The error names every misconfigured contract and its env var
+        let message = error.to_string();
+        assert!(message.contains("ModelRegistry (MODEL_REGISTRY_ADDR=0x0000000000000000000000000000000000000003)"));
+        assert!(message.contains("DealMarket (DEAL_MARKET_ADDR=unset)"));
+        assert!(!message.contains("AIJobManager"));
+
+        let registry = ContractRegistry::validate(&endpoints, vec![(jobs, Some(deployed.to_string()))]).await.unwrap();
+        assert_eq!(registry.address("AIJobManager"), Some(deployed));
+    }
+}
@@ -0,0 +1,68 @@
+//! Response Validation
+//! A JSON-RPC 2.0 response is taken only if it is the answer to the request
+//! that was sent: a single object (not a batch, not a notification), version
+//! "2.0", the request's `id`, and exactly one of `result` or `error`. An
+//! error object has to carry an integer `code` and a `message`, and goes back
+//! to the caller as [`RpcError::Rpc`] so the node's reason isn't lost. A
+//! response that doesn't have this shape is [`RpcError::Malformed`].
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcError {
+    Transport(String), // No endpoint answered
+    Malformed(String), // An endpoint answered, but not with a response to the request
+    Rpc { code: i64, message: String, data: Option<Value> },
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Transport(e) => write!(f, "{}", e),
+            RpcError::Malformed(e) => write!(f, "malformed RPC response: {}", e),
+            RpcError::Rpc { code, message, .. } => write!(f, "RPC error {}: {}", code, message),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// The `result` of `response`, if it is a valid answer to `request`
+pub fn validate(request: &Value, response: Value) -> Result<Value, RpcError> {
+    let malformed = |reason: &str| Err(RpcError::Malformed(reason.to_string()));
+    let Value::Object(mut response) = response else {
+        return match response {
+            Value::Array(_) => malformed("batch response to a single request"),
+            _ => malformed("not a JSON object"),
+        };
+    };
+    if response.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return malformed("jsonrpc version is not 2.0");
+    }
+    if response.contains_key("method") {
+        return malformed("notification, not a response");
+    }
+
+    let id = response.get("id").cloned().unwrap_or(Value::Null);
+    match (response.remove("result"), response.remove("error")) {
+        (Some(_), Some(_)) => malformed("both result and error"),
+        (None, None) => malformed("neither result nor error"),
+        // A node that couldn't read the request id answers an error with a null id
+        (None, Some(error)) if id.is_null() || id == request["id"] => Err(rpc_error(error)),
+        (Some(result), None) if id == request["id"] => Ok(result),
+        _ => Err(RpcError::Malformed(format!("id {} does not match request id {}", id, request["id"]))),
+    }
+}
+
+fn rpc_error(error: Value) -> RpcError {
+    let code = error.get("code").and_then(Value::as_i64);
+    let message = error.get("message").and_then(Value::as_str);
+    match (code, message) {
+        (Some(code), Some(message)) => RpcError::Rpc {
+            code,
+            message: message.to_string(),
+            data: error.get("data").cloned(),
+        },
+        _ => RpcError::Malformed(format!("error object without code and message: {}", error)),
+    }
+}
@@ -0,0 +1,150 @@
+//! Tenant Namespaces
+//! Teams sharing a deployment each get a namespace, and their jobs, datasets
+//! and models live in it. A caller's namespace comes from the DID the gateway
+//! authenticated (`x-artha-did`): the organization of `did:artha:<org>:<member>`,
+//! or the whole identifier of a DID that names no member, so a lone
+//! `did:artha:<name>` is an organization of one. Ephemeral addresses are
+//! namespaces of their own.
+//!
+//! Requests without a DID come from inside the deployment (other services,
+//! operators) and act in the shared namespace. It sees every tenant's
+//! resources; a tenant sees only its own. Names such as `name@version` are
+//! unique within a namespace, so two tenants can use the same ones. A tenant
+//! resolves names in its own namespace first, then in the shared one.
+
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+/// Header carrying the caller's authenticated DID
+pub const DID_HEADER: &str = "x-artha-did";
+
+const DID_PREFIX: &str = "did:artha:";
+
+/// Serializes as the tenant id, or `null` for the shared namespace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "Option<String>", into = "Option<String>")]
+pub enum Namespace {
+    #[default]
+    Shared,
+    Tenant(String),
+}
+
+impl Namespace {
+    /// Namespace the resources of `principal` (a submitter, an owner) live
+    /// in. Principals that are neither DIDs nor addresses, such as
+    /// `system:continuald`, belong to the deployment.
+    pub fn of(principal: &str) -> Namespace {
+        if principal.starts_with("did:") || is_address(principal) {
+            Namespace::Tenant(tenant_id(principal))
+        } else {
+            Namespace::Shared
+        }
+    }
+
+    /// Namespace of the caller of a request. Any presented identity is a
+    /// tenant; only callers without one act in the shared namespace.
+    pub fn of_caller(headers: &HeaderMap) -> Namespace {
+        match headers.get(DID_HEADER).and_then(|v| v.to_str().ok()) {
+            Some(did) if !did.is_empty() => Namespace::Tenant(tenant_id(did)),
+            _ => Namespace::Shared,
+        }
+    }
+
+    pub fn tenant(&self) -> Option<&str> {
+        match self {
+            Namespace::Shared => None,
+            Namespace::Tenant(tenant) => Some(tenant),
+        }
+    }
+
+    pub fn is_shared(&self) -> bool {
+        *self == Namespace::Shared
+    }
+
+    /// Whether a caller in this namespace may see a resource in `owner`
+    pub fn sees(&self, owner: &Namespace) -> bool {
+        self.is_shared() || self == owner
+    }
+
+    /// Namespaces a name is looked up in, nearest first
+    pub fn lookup_path(&self) -> Vec<&Namespace> {
+        const SHARED: &Namespace = &Namespace::Shared;
+        if self.is_shared() {
+            vec![SHARED]
+        } else {
+            vec![self, SHARED]
+        }
+    }
+}
+
+impl From<Option<String>> for Namespace {
+    fn from(tenant: Option<String>) -> Self {
+        tenant.map_or(Namespace::Shared, Namespace::Tenant)
+    }
+}
+
+impl From<Namespace> for Option<String> {
+    fn from(namespace: Namespace) -> Self {
+        match namespace {
+            Namespace::Shared => None,
+            Namespace::Tenant(tenant) => Some(tenant),
+        }
+    }
+}
+
+/// The organization segment of an Artha DID, an address in lowercase, any
+/// other principal as-is
+fn tenant_id(principal: &str) -> String {
+    match principal.strip_prefix(DID_PREFIX).and_then(|id| id.split(':').next()) {
+        Some(org) if !org.is_empty() => org.to_string(),
+        _ if is_address(principal) => principal.to_lowercase(),
+        _ => principal.to_string(),
+    }
+}
+
+fn is_address(principal: &str) -> bool {
+    principal.len() == 42 && principal.starts_with("0x") && principal[2..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant(id: &str) -> Namespace {
+        Namespace::Tenant(id.to_string())
+    }
+
+    #[test]
+    fn namespaces_follow_the_did_organization() {
+        assert_eq!(Namespace::of("did:artha:acme:alice"), tenant("acme"));
+        assert_eq!(Namespace::of("did:artha:acme:bob"), tenant("acme"));
+        assert_eq!(Namespace::of("did:artha:carol"), tenant("carol"));
+        assert_eq!(Namespace::of("did:key:z6Mk"), tenant("did:key:z6Mk"));
+        let address = format!("0x{}", "aB".repeat(20));
+        assert_eq!(Namespace::of(&address), tenant(&address.to_lowercase()));
+        assert_eq!(Namespace::of("system:continuald"), Namespace::Shared);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(Namespace::of_caller(&headers), Namespace::Shared);
+        headers.insert(DID_HEADER, "did:artha:acme:bob".parse().unwrap());
+        assert_eq!(Namespace::of_caller(&headers), tenant("acme"));
+        // A caller presenting any identity is never the deployment
+        headers.insert(DID_HEADER, "system:continuald".parse().unwrap());
+        assert_eq!(Namespace::of_caller(&headers), tenant("system:continuald"));
+    }
+
+    #[test]
+    fn tenants_see_their_own_and_resolve_through_shared() {
+        let (acme, globex) = (tenant("acme"), tenant("globex"));
+        assert!(acme.sees(&acme));
+        assert!(!acme.sees(&globex));
+        assert!(!acme.sees(&Namespace::Shared));
+        assert!(Namespace::Shared.sees(&globex));
+
+        assert_eq!(acme.lookup_path(), vec![&acme, &Namespace::Shared]);
+        assert_eq!(Namespace::Shared.lookup_path(), vec![&Namespace::Shared]);
+
+        assert_eq!(serde_json::to_value(&acme).unwrap(), serde_json::json!("acme"));
+        assert_eq!(serde_json::from_value::<Namespace>(serde_json::Value::Null).unwrap(), Namespace::Shared);
+    }
+}
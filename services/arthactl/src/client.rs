@@ -0,0 +1,237 @@
+//! API Client
+//! Signs requests with the operator's DID key, honours --dry-run for mutating
+//! calls, and turns non-2xx responses into errors carrying the response body.
+
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use sha2::{Digest, Sha256};
+
+use crate::config::{expand_home, Context};
+
+/// How long a request signature stays valid
+const SIGNATURE_TTL_SECS: u64 = 300;
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Config(String),
+    Api { status: u16, body: String },
+    Transport(String),
+    VerificationFailed(String),
+}
+
+impl CliError {
+    /// Exit codes scripts can branch on; see docs/ARTHACTL.md
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Config(_) => 1,
+            CliError::Api { status, .. } => match status {
+                404 => 3,
+                409 | 412 | 428 => 4,
+                401 | 403 => 5,
+                429 => 6,
+                500..=599 => 7,
+                _ => 1,
+            },
+            CliError::Transport(_) => 8,
+            CliError::VerificationFailed(_) => 9,
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "usage: {}", msg),
+            CliError::Config(msg) => write!(f, "config: {}", msg),
+            CliError::Api { status, body } => {
+                // Structured bodies are re-emitted as JSON so they stay machine-readable
+                let body = match serde_json::from_str::<serde_json::Value>(body) {
+                    Ok(json) => json.to_string(),
+                    Err(_) if body.is_empty() => "(empty body)".to_string(),
+                    Err(_) => body.clone(),
+                };
+                write!(f, "API error {}: {}", status, body)
+            }
+            CliError::Transport(msg) => write!(f, "transport: {}", msg),
+            CliError::VerificationFailed(msg) => write!(f, "verification failed: {}", msg),
+        }
+    }
+}
+
+/// Operator identity used to sign requests
+pub struct RequestSigner {
+    key: SigningKey,
+    pub did: String,
+}
+
+impl RequestSigner {
+    pub fn from_hex(secret_hex: &str, did: Option<String>) -> Result<Self, CliError> {
+        let bytes = hex::decode(secret_hex.trim().trim_start_matches("0x"))
+            .map_err(|e| CliError::Config(format!("Key is not hex: {}", e)))?;
+        let key = SigningKey::from_slice(&bytes).map_err(|e| CliError::Config(format!("Invalid key: {}", e)))?;
+        let did = did.unwrap_or_else(|| {
+            format!("did:artha:{}", hex::encode(key.verifying_key().to_encoded_point(true).as_bytes()))
+        });
+        Ok(RequestSigner { key, did })
+    }
+
+    /// Message: REQ:{METHOD}:{PATH_AND_QUERY}:{sha256(body) hex}:EXP:{expiry}
+    pub fn message(method: &str, path_and_query: &str, body: &[u8], expiry: u64) -> String {
+        format!("REQ:{}:{}:{}:EXP:{}", method, path_and_query, hex::encode(Sha256::digest(body)), expiry)
+    }
+
+    pub fn headers(&self, method: &str, path_and_query: &str, body: &[u8], expiry: u64) -> Vec<(String, String)> {
+        let signature: Signature = self.key.sign(Self::message(method, path_and_query, body, expiry).as_bytes());
+        vec![
+            ("X-Artha-DID".to_string(), self.did.clone()),
+            ("X-Artha-Expiry".to_string(), expiry.to_string()),
+            ("X-Artha-Signature".to_string(), hex::encode(signature.to_bytes())),
+        ]
+    }
+}
+
+/// A request as it would go on the wire; printed verbatim by --dry-run
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PreparedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<serde_json::Value>,
+}
+
+pub struct ApiClient {
+    http: reqwest::Client,
+    signer: Option<RequestSigner>,
+    pub did: Option<String>,
+    pub dry_run: bool,
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn path_and_query(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+impl ApiClient {
+    pub fn new(context: &Context, dry_run: bool) -> Result<Self, CliError> {
+        let signer = match &context.key_path {
+            Some(path) => {
+                let path = expand_home(path);
+                let secret = std::fs::read_to_string(&path)
+                    .map_err(|e| CliError::Config(format!("{}: {}", path.display(), e)))?;
+                Some(RequestSigner::from_hex(&secret, context.did.clone())?)
+            }
+            None => None,
+        };
+        let did = signer.as_ref().map(|s| s.did.clone()).or_else(|| context.did.clone());
+        Ok(ApiClient { http: reqwest::Client::new(), signer, did, dry_run })
+    }
+
+    pub fn with_signer(signer: Option<RequestSigner>, dry_run: bool) -> Self {
+        let did = signer.as_ref().map(|s| s.did.clone());
+        ApiClient { http: reqwest::Client::new(), signer, did, dry_run }
+    }
+
+    pub fn prepare(&self, method: &str, url: &str, body: Option<&serde_json::Value>) -> PreparedRequest {
+        let raw = body.map(|b| b.to_string()).unwrap_or_default();
+        let mut headers = Vec::new();
+        if body.is_some() {
+            headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+        if let Some(signer) = &self.signer {
+            headers.extend(signer.headers(method, &path_and_query(url), raw.as_bytes(), now() + SIGNATURE_TTL_SECS));
+        }
+        PreparedRequest { method: method.to_string(), url: url.to_string(), headers, body: body.cloned() }
+    }
+
+    /// Send a prepared request; the raw response body on success
+    pub async fn send(&self, request: &PreparedRequest) -> Result<String, CliError> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|e| CliError::Usage(e.to_string()))?;
+        let mut builder = self.http.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.to_string());
+        }
+        let response = builder.send().await.map_err(|e| CliError::Transport(e.to_string()))?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| CliError::Transport(e.to_string()))?;
+        if !status.is_success() {
+            return Err(CliError::Api { status: status.as_u16(), body });
+        }
+        Ok(body)
+    }
+
+    pub async fn get(&self, url: &str) -> Result<serde_json::Value, CliError> {
+        let body = self.send(&self.prepare("GET", url, None)).await?;
+        Ok(parse_body(&body))
+    }
+
+    /// GET a listing endpoint, following `next_cursor` until the last page.
+    /// Paged responses are flattened to their items; anything else passes through.
+    pub async fn get_all(&self, url: &str) -> Result<serde_json::Value, CliError> {
+        let mut page = self.get(url).await?;
+        let mut items = Vec::new();
+        loop {
+            let Some(batch) = page.get_mut("items").and_then(|items| items.as_array_mut()) else {
+                return Ok(page);
+            };
+            items.append(batch);
+            let Some(cursor) = page["next_cursor"].as_str() else {
+                return Ok(serde_json::Value::Array(items));
+            };
+            let separator = if url.contains('?') { '&' } else { '?' };
+            page = self.get(&format!("{}{}cursor={}", url, separator, cursor)).await?;
+        }
+    }
+
+    /// Mutating call. Under --dry-run returns the prepared request instead of sending it.
+    pub async fn mutate(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<Mutation, CliError> {
+        let request = self.prepare(method, url, body.as_ref());
+        if self.dry_run {
+            return Ok(Mutation::DryRun(request));
+        }
+        let body = self.send(&request).await?;
+        Ok(Mutation::Sent(parse_body(&body)))
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    pub fn signed_headers(&self, method: &str, url: &str) -> Vec<(String, String)> {
+        self.prepare(method, url, None).headers
+    }
+}
+
+pub enum Mutation {
+    DryRun(PreparedRequest),
+    Sent(serde_json::Value),
+}
+
+/// Empty bodies (e.g. 200 with no content) become null; non-JSON becomes a string
+fn parse_body(body: &str) -> serde_json::Value {
+    if body.trim().is_empty() {
+        return serde_json::Value::Null;
+    }
+    serde_json::from_str(body).unwrap_or_else(|_| serde_json::Value::String(body.to_string()))
+}
@@ -0,0 +1,116 @@
+//! Context File
+//! Endpoint URLs and operator identity, read from ~/.artha/config (YAML).
+//! A file holds named contexts; `current` picks the default one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Endpoints {
+    #[serde(default = "default_jobd")]
+    pub jobd: String,
+    #[serde(default = "default_scheduler")]
+    pub scheduler: String,
+    #[serde(default = "default_proofs")]
+    pub proofs: String,
+    #[serde(default = "default_receipts")]
+    pub receipts: String,
+    #[serde(default = "default_continual")]
+    pub continual: String,
+}
+
+fn default_jobd() -> String {
+    "http://localhost:8081".to_string()
+}
+
+fn default_scheduler() -> String {
+    "http://localhost:8083".to_string()
+}
+
+fn default_proofs() -> String {
+    "http://localhost:8085".to_string()
+}
+
+fn default_receipts() -> String {
+    "http://localhost:8092".to_string()
+}
+
+fn default_continual() -> String {
+    "http://localhost:8090".to_string()
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints {
+            jobd: default_jobd(),
+            scheduler: default_scheduler(),
+            proofs: default_proofs(),
+            receipts: default_receipts(),
+            continual: default_continual(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Context {
+    #[serde(default, flatten)]
+    pub endpoints: Endpoints,
+    #[serde(default)]
+    pub did: Option<String>,      // Defaults to the DID derived from the key
+    #[serde(default)]
+    pub key_path: Option<String>, // Hex secp256k1 secret used to sign requests
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub current: Option<String>,
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+}
+
+/// ARTHA_CONFIG, else ~/.artha/config
+pub fn default_path() -> PathBuf {
+    if let Ok(path) = std::env::var("ARTHA_CONFIG") {
+        return PathBuf::from(path);
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".artha").join("config")
+}
+
+/// Expand a leading `~/` against $HOME
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl ConfigFile {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        serde_yaml::from_str(raw).map_err(|e| format!("Invalid config: {}", e))
+    }
+
+    /// A missing file is an empty config: every endpoint at its local default
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => Self::parse(&raw).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ConfigFile::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// The named context, else `current`, else the built-in defaults
+    pub fn context(&self, name: Option<&str>) -> Result<(String, Context), String> {
+        match name.or(self.current.as_deref()) {
+            Some(name) => self
+                .contexts
+                .get(name)
+                .cloned()
+                .map(|context| (name.to_string(), context))
+                .ok_or_else(|| format!("No context named {}", name)),
+            None => Ok(("default".to_string(), Context::default())),
+        }
+    }
+}
@@ -0,0 +1,933 @@
+//! arthactl - Operator CLI for the ArthaChain AI services
+//! Wraps jobd, scheduler, proofs, receipts and continuald behind one signed client
+
+use clap::{CommandFactory, Parser, Subcommand};
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde_json::{json, Value};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod client;
+use client::{ApiClient, CliError, Mutation};
+mod config;
+use config::{ConfigFile, Context};
+mod output;
+use output::{render, OutputFormat};
+mod stream;
+use stream::{follow_logs, FollowOptions};
+
+#[derive(Debug, Parser)]
+#[command(name = "arthactl", version, about = "Operate ArthaChain AI jobs, nodes, proofs and receipts")]
+pub struct Cli {
+    /// Context from the config file (defaults to its `current`)
+    #[arg(long, global = true)]
+    pub context: Option<String>,
+    /// Config file (defaults to $ARTHA_CONFIG or ~/.artha/config)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    #[arg(long, short = 'o', global = true, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+    /// Print mutating requests instead of sending them
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(subcommand)]
+    Jobs(JobsCommand),
+    #[command(subcommand)]
+    Nodes(NodesCommand),
+    #[command(subcommand)]
+    Proofs(ProofsCommand),
+    #[command(subcommand)]
+    Receipts(ReceiptsCommand),
+    /// Proof submissions that exhausted their retries
+    #[command(subcommand)]
+    Dlq(DlqCommand),
+    /// Continual-learning watches
+    #[command(subcommand)]
+    Schedules(SchedulesCommand),
+    /// Jobs, models, datasets and transactions indexed by jobd
+    #[command(subcommand)]
+    Search(SearchCommand),
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Print a shell completion script
+    Completion {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum JobsCommand {
+    /// Submit a job described by a YAML file (`kind: train|infer|agent` and a `spec` mapping)
+    Submit {
+        #[arg(short = 'f', long = "file")]
+        file: PathBuf,
+    },
+    Status {
+        job_id: String,
+        /// Poll until the job reaches a terminal status
+        #[arg(long, short = 'w')]
+        watch: bool,
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+    Logs {
+        job_id: String,
+        /// Stream new lines until the job finishes, reconnecting if the stream drops
+        #[arg(long, short = 'f')]
+        follow: bool,
+    },
+    Cancel {
+        job_id: String,
+    },
+    /// Fetch a job's output, checking it against the node-signed digest when there is one
+    Download {
+        job_id: String,
+        #[arg(long = "to")]
+        path: PathBuf,
+    },
+    List {
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        did: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NodesCommand {
+    List,
+    /// Stop new placements on a node; running jobs finish
+    Drain {
+        pubkey: String,
+        /// Lift the drain instead
+        #[arg(long)]
+        cancel: bool,
+    },
+    Maintenance {
+        pubkey: String,
+        #[arg(long, conflicts_with = "off", required_unless_present = "off")]
+        on: bool,
+        #[arg(long)]
+        off: bool,
+        /// Unix time the window ends
+        #[arg(long)]
+        until: Option<u64>,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProofsCommand {
+    List {
+        #[arg(long)]
+        job: String,
+    },
+    /// Check that a job's proofs are complete, on-chain and attested
+    Verify {
+        #[arg(long)]
+        job: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReceiptsCommand {
+    List {
+        #[arg(long)]
+        provider: Option<String>,
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        job: Option<String>,
+    },
+    /// Hold an unsettled receipt out of settlement
+    Dispute {
+        receipt_id: String,
+        #[arg(long)]
+        reason: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DlqCommand {
+    List,
+    Replay { id: u64 },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SchedulesCommand {
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SearchCommand {
+    /// Free text mixed with filters, e.g. `status:completed architecture:transformer before:2025-02-01`
+    Query {
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Comma-separated kinds: job, model, dataset, transaction
+        #[arg(long = "type")]
+        kind: Option<String>,
+        /// Comma-separated facets to count: type, status, architecture, date
+        #[arg(long)]
+        facets: Option<String>,
+    },
+    /// Rebuild the index from jobd's search journal
+    Rebuild,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Show the resolved context
+    Print,
+}
+
+/// Everything a command needs besides its own arguments
+pub struct Session {
+    pub context_name: String,
+    pub context: Context,
+    pub client: ApiClient,
+    pub format: OutputFormat,
+    pub follow: FollowOptions,
+    pub redraw: bool, // Rewrite status lines in place (interactive terminal)
+}
+
+impl Session {
+    pub fn from_cli(cli: &Cli) -> Result<Self, CliError> {
+        let path = cli.config.clone().unwrap_or_else(config::default_path);
+        let file = ConfigFile::load(&path).map_err(CliError::Config)?;
+        let (context_name, context) = file.context(cli.context.as_deref()).map_err(CliError::Config)?;
+        let client = ApiClient::new(&context, cli.dry_run)?;
+        Ok(Session {
+            context_name,
+            context,
+            client,
+            format: cli.output,
+            follow: FollowOptions::default(),
+            redraw: std::io::stdout().is_terminal() && cli.output == OutputFormat::Table,
+        })
+    }
+}
+
+fn io_err(e: std::io::Error) -> CliError {
+    CliError::Transport(e.to_string())
+}
+
+fn emit(session: &Session, out: &mut dyn Write, value: &Value, columns: &[&str]) -> Result<(), CliError> {
+    render(out, session.format, value, columns).map_err(io_err)
+}
+
+/// Print what a mutating call did, or what it would have sent under --dry-run
+fn emit_mutation(session: &Session, out: &mut dyn Write, mutation: Mutation, columns: &[&str]) -> Result<(), CliError> {
+    match mutation {
+        Mutation::DryRun(request) => {
+            writeln!(out, "{}", serde_json::to_string_pretty(&request).unwrap_or_default()).map_err(io_err)
+        }
+        Mutation::Sent(Value::Null) => writeln!(out, "OK").map_err(io_err),
+        Mutation::Sent(value) => emit(session, out, &value, columns),
+    }
+}
+
+fn query_string(params: &[(&str, &Option<String>)]) -> String {
+    let pairs: Vec<String> = params
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|v| format!("{}={}", key, encode(v))))
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", pairs.join("&"))
+    }
+}
+
+/// Percent-encode a query value
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b':' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+const JOB_COLUMNS: &[&str] = &["job_id", "job_type", "status", "submitter_did", "assigned_node", "submitted_at"];
+const TERMINAL_STATUSES: &[&str] = &["Completed", "Failed", "Cancelled"];
+
+/// Parse a job file into the submit path and request body
+pub fn job_request(raw: &str, did: Option<&str>) -> Result<(String, Value), CliError> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(raw).map_err(|e| CliError::Usage(format!("Invalid job file: {}", e)))?;
+    let doc = serde_json::to_value(doc).map_err(|e| CliError::Usage(format!("Invalid job file: {}", e)))?;
+    let kind = doc.get("kind").and_then(|k| k.as_str()).unwrap_or_default();
+    if !matches!(kind, "train" | "infer" | "agent") {
+        return Err(CliError::Usage(format!("Job kind must be train, infer or agent, got {:?}", kind)));
+    }
+    let mut spec = doc.get("spec").cloned().unwrap_or(Value::Null);
+    let Some(fields) = spec.as_object_mut() else {
+        return Err(CliError::Usage("Job file needs a `spec` mapping".to_string()));
+    };
+    if !fields.contains_key("submitter_did") {
+        if let Some(did) = did {
+            fields.insert("submitter_did".to_string(), json!(did));
+        }
+    }
+    Ok((format!("/job/{}", kind), spec))
+}
+
+async fn watch_status(session: &Session, job_id: &str, interval: u64, out: &mut dyn Write) -> Result<(), CliError> {
+    let url = format!("{}/job/{}/status", session.context.endpoints.jobd, job_id);
+    let mut last = String::new();
+    loop {
+        let status = session.client.get(&url).await?;
+        let job = &status["job"];
+        let current = job["status"].as_str().unwrap_or("Unknown").to_string();
+        if session.format == OutputFormat::Json {
+            if current != last {
+                writeln!(out, "{}", status).map_err(io_err)?;
+            }
+        } else {
+            let node = job["assigned_node"].as_str().unwrap_or("-");
+            let line = format!("{}  {:<10} node={}", job_id, current, node);
+            if session.redraw {
+                write!(out, "\r\x1b[2K{}", line).map_err(io_err)?;
+                out.flush().map_err(io_err)?;
+            } else if current != last {
+                writeln!(out, "{}", line).map_err(io_err)?;
+            }
+        }
+        if TERMINAL_STATUSES.contains(&current.as_str()) {
+            if session.redraw {
+                writeln!(out).map_err(io_err)?;
+            }
+            return Ok(());
+        }
+        last = current;
+        tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+    }
+}
+
+async fn run_jobs(session: &Session, command: JobsCommand, out: &mut dyn Write) -> Result<(), CliError> {
+    let jobd = &session.context.endpoints.jobd;
+    match command {
+        JobsCommand::Submit { file } => {
+            let raw = std::fs::read_to_string(&file).map_err(|e| CliError::Usage(format!("{}: {}", file.display(), e)))?;
+            let (path, body) = job_request(&raw, session.client.did.as_deref())?;
+            let mutation = session.client.mutate("POST", &format!("{}{}", jobd, path), Some(body)).await?;
+            emit_mutation(session, out, mutation, &[])
+        }
+        JobsCommand::Status { job_id, watch: true, interval } => watch_status(session, &job_id, interval, out).await,
+        JobsCommand::Status { job_id, .. } => {
+            let status = session.client.get(&format!("{}/job/{}/status", jobd, job_id)).await?;
+            match session.format {
+                OutputFormat::Json => emit(session, out, &status, &[]),
+                OutputFormat::Table => emit(session, out, &json!([status]), &["job.job_id", "job.status", "job.assigned_node", "can_cancel", "output_id"]),
+            }
+        }
+        JobsCommand::Logs { job_id, follow: false } => {
+            let logs = session.client.get(&format!("{}/job/{}/logs", jobd, job_id)).await?;
+            for line in logs.as_array().into_iter().flatten() {
+                writeln!(out, "{}", line.as_str().unwrap_or_default()).map_err(io_err)?;
+            }
+            Ok(())
+        }
+        JobsCommand::Logs { job_id, follow: true } => {
+            let status = follow_logs(&session.client, jobd, &job_id, 0, &session.follow, out).await?;
+            eprintln!("-- job {} {}", job_id, status);
+            Ok(())
+        }
+        JobsCommand::Cancel { job_id } => {
+            let mutation = session.client.mutate("POST", &format!("{}/job/{}/cancel", jobd, job_id), None).await?;
+            emit_mutation(session, out, mutation, &[])
+        }
+        JobsCommand::Download { job_id, path } => {
+            let did = session
+                .client
+                .did
+                .clone()
+                .ok_or_else(|| CliError::Config("Downloads need a DID: set `did` or `key_path` in the context".to_string()))?;
+            let body = json!({ "requester_did": did });
+            let link = match session.client.mutate("POST", &format!("{}/job/{}/output/link", jobd, job_id), Some(body)).await? {
+                Mutation::Sent(link) => link,
+                Mutation::DryRun(request) => return emit_mutation(session, out, Mutation::DryRun(request), &[]),
+            };
+            let response = session
+                .client
+                .http()
+                .get(link["url"].as_str().unwrap_or_default())
+                .header("x-artha-did", &did)
+                .send()
+                .await
+                .map_err(|e| CliError::Transport(e.to_string()))?;
+            let status = response.status();
+            let bytes = response.bytes().await.map_err(|e| CliError::Transport(e.to_string()))?;
+            if !status.is_success() {
+                return Err(CliError::Api { status: status.as_u16(), body: String::from_utf8_lossy(&bytes).to_string() });
+            }
+            // Nothing is written unless the bytes match what the node signed
+            let integrity = &link["integrity"];
+            let verified = !integrity.is_null();
+            if verified {
+                verify_output(&job_id, &bytes, integrity).map_err(CliError::VerificationFailed)?;
+            }
+            std::fs::write(&path, &bytes).map_err(|e| CliError::Usage(format!("{}: {}", path.display(), e)))?;
+            let row = json!({
+                "job_id": job_id,
+                "path": path.display().to_string(),
+                "bytes": bytes.len(),
+                "output_digest": integrity["output_digest"],
+                "node": integrity["node_pubkey"],
+                "verified": verified,
+            });
+            emit(session, out, &row, &[])
+        }
+        JobsCommand::List { status, did } => {
+            let query = query_string(&[("status", &status), ("did", &did)]);
+            let jobs = session.client.get_all(&format!("{}/jobs{}", jobd, query)).await?;
+            emit(session, out, &jobs, JOB_COLUMNS)
+        }
+    }
+}
+
+async fn run_nodes(session: &Session, command: NodesCommand, out: &mut dyn Write) -> Result<(), CliError> {
+    let scheduler = &session.context.endpoints.scheduler;
+    match command {
+        NodesCommand::List => {
+            let nodes = session.client.get_all(&format!("{}/nodes", scheduler)).await?;
+            emit(session, out, &nodes, &["pubkey", "node_type", "region", "current_load", "running_jobs", "cordon.mode", "cordon.until"])
+        }
+        NodesCommand::Drain { pubkey, cancel } => {
+            let method = if cancel { "DELETE" } else { "POST" };
+            let mutation = session.client.mutate(method, &format!("{}/nodes/{}/drain", scheduler, pubkey), None).await?;
+            emit_mutation(session, out, mutation, &[])
+        }
+        NodesCommand::Maintenance { pubkey, on, until, reason, .. } => {
+            let body = json!({ "enabled": on, "until": until, "reason": reason });
+            let mutation = session.client.mutate("POST", &format!("{}/nodes/{}/maintenance", scheduler, pubkey), Some(body)).await?;
+            emit_mutation(session, out, mutation, &[])
+        }
+    }
+}
+
+/// One row of `proofs verify`
+fn check(name: &str, ok: bool, detail: String) -> Value {
+    json!({ "check": name, "ok": ok, "detail": detail })
+}
+
+/// Client-side checks over a job's proofs and attestation
+pub fn verify_proofs(proofs: &[Value], attestation: Option<&Value>) -> Vec<Value> {
+    let mut checks = vec![check("proofs_present", !proofs.is_empty(), format!("{} proofs", proofs.len()))];
+
+    let unsubmitted = proofs
+        .iter()
+        .filter(|p| !p["submitted"].as_bool().unwrap_or(false) || p["tx_hash"].is_null())
+        .count();
+    checks.push(check("submitted_on_chain", unsubmitted == 0, format!("{} without a tx", unsubmitted)));
+
+    let mut steps: Vec<u64> = proofs
+        .iter()
+        .filter(|p| p["proof_type"] == "TrainStep")
+        .filter_map(|p| p["step"].as_u64())
+        .collect();
+    steps.sort_unstable();
+    // Steps are reported every checkpoint interval, so contiguous means one constant stride
+    let strides: Vec<u64> = steps.windows(2).map(|w| w[1] - w[0]).collect();
+    let contiguous = strides.iter().all(|s| *s > 0 && *s == strides[0]);
+    checks.push(check("train_steps_contiguous", contiguous, format!("{} steps", steps.len())));
+
+    let complete = proofs.iter().any(|p| p["proof_type"] == "TrainComplete" || p["proof_type"] == "InferComplete");
+    checks.push(check("completion_proof", complete, String::new()));
+
+    match attestation {
+        Some(record) => checks.push(check(
+            "attestation",
+            record["status"] == "Verified",
+            record["reason"].as_str().unwrap_or(record["status"].as_str().unwrap_or_default()).to_string(),
+        )),
+        None => checks.push(check("attestation", true, "not a TEE job".to_string())),
+    }
+    checks
+}
+
+/// Check downloaded output bytes against the executing node's signed
+/// (output_cid, output_digest) pair: the blake3 first, then the signature
+/// over `OUTPUT:{job_id}:{output_cid}:{output_digest}`
+pub fn verify_output(job_id: &str, bytes: &[u8], integrity: &Value) -> Result<(), String> {
+    let field = |name: &str| integrity[name].as_str().unwrap_or_default();
+    let digest = blake3::hash(bytes).to_hex().to_string();
+    if digest != field("output_digest") {
+        return Err(format!("output blake3 {} does not match the signed digest {}", digest, field("output_digest")));
+    }
+    let key = hex::decode(field("node_pubkey").trim_start_matches("0x"))
+        .ok()
+        .and_then(|key| VerifyingKey::from_sec1_bytes(&key).ok())
+        .ok_or_else(|| "node_pubkey is not a public key".to_string())?;
+    let signature = hex::decode(field("signature").trim_start_matches("0x"))
+        .ok()
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .ok_or_else(|| "signature is malformed".to_string())?;
+    let message = format!("OUTPUT:{}:{}:{}", job_id, field("output_cid"), digest);
+    key.verify(message.as_bytes(), &signature)
+        .map_err(|_| format!("output digest is not signed by node {}", field("node_pubkey")))
+}
+
+async fn run_proofs(session: &Session, command: ProofsCommand, out: &mut dyn Write) -> Result<(), CliError> {
+    let proofs_url = &session.context.endpoints.proofs;
+    match command {
+        ProofsCommand::List { job } => {
+            let proofs = session.client.get(&format!("{}/proofs/{}", proofs_url, job)).await?;
+            emit(session, out, &proofs, &["proof_type", "step", "digest", "submitted", "tx_hash", "timestamp"])
+        }
+        ProofsCommand::Verify { job } => {
+            let proofs = match session.client.get(&format!("{}/proofs/{}", proofs_url, job)).await {
+                Ok(proofs) => proofs.as_array().cloned().unwrap_or_default(),
+                Err(CliError::Api { status: 404, .. }) => Vec::new(),
+                Err(e) => return Err(e),
+            };
+            let attestation = match session.client.get(&format!("{}/attestation/{}", proofs_url, job)).await {
+                Ok(record) => Some(record),
+                Err(CliError::Api { status: 404, .. }) => None,
+                Err(e) => return Err(e),
+            };
+            let checks = verify_proofs(&proofs, attestation.as_ref());
+            emit(session, out, &Value::Array(checks.clone()), &["check", "ok", "detail"])?;
+            let failed: Vec<&str> = checks
+                .iter()
+                .filter(|c| c["ok"] == false)
+                .filter_map(|c| c["check"].as_str())
+                .collect();
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::VerificationFailed(failed.join(", ")))
+            }
+        }
+    }
+}
+
+async fn run_receipts(session: &Session, command: ReceiptsCommand, out: &mut dyn Write) -> Result<(), CliError> {
+    let receipts = &session.context.endpoints.receipts;
+    match command {
+        ReceiptsCommand::List { provider, status, job } => {
+            let query = query_string(&[("provider", &provider), ("status", &status), ("job_id", &job)]);
+            let list = session.client.get_all(&format!("{}/receipts{}", receipts, query)).await?;
+            emit(session, out, &list, &["receipt_id", "job_id", "receipt_type", "provider", "amount_wei", "status"])
+        }
+        ReceiptsCommand::Dispute { receipt_id, reason } => {
+            let disputer = session
+                .client
+                .did
+                .clone()
+                .ok_or_else(|| CliError::Config("Disputes need a DID: set `did` or `key_path` in the context".to_string()))?;
+            let body = json!({ "disputer": disputer, "reason": reason });
+            let mutation = session.client.mutate("POST", &format!("{}/receipt/{}/dispute", receipts, receipt_id), Some(body)).await?;
+            emit_mutation(session, out, mutation, &["receipt_id", "status"])
+        }
+    }
+}
+
+pub async fn run(cli: Cli, out: &mut dyn Write) -> Result<(), CliError> {
+    if let Command::Completion { shell } = cli.command {
+        clap_complete::generate(shell, &mut Cli::command(), "arthactl", out);
+        return Ok(());
+    }
+    let session = Session::from_cli(&cli)?;
+    run_command(&session, cli.command, out).await
+}
+
+pub async fn run_command(session: &Session, command: Command, out: &mut dyn Write) -> Result<(), CliError> {
+    match command {
+        Command::Jobs(command) => run_jobs(session, command, out).await,
+        Command::Nodes(command) => run_nodes(session, command, out).await,
+        Command::Proofs(command) => run_proofs(session, command, out).await,
+        Command::Receipts(command) => run_receipts(session, command, out).await,
+        Command::Dlq(DlqCommand::List) => {
+            let letters = session.client.get(&format!("{}/dlq", session.context.endpoints.proofs)).await?;
+            emit(session, out, &letters, &["id", "key.job_id", "key.proof_type", "key.step", "error", "failed_at"])
+        }
+        Command::Dlq(DlqCommand::Replay { id }) => {
+            let url = format!("{}/dlq/{}/replay", session.context.endpoints.proofs, id);
+            let mutation = session.client.mutate("POST", &url, None).await?;
+            emit_mutation(session, out, mutation, &[])
+        }
+        Command::Schedules(SchedulesCommand::List) => {
+            let watches = session.client.get_all(&format!("{}/continual/watches", session.context.endpoints.continual)).await?;
+            emit(session, out, &watches, &["watch_id", "model_id", "dataset_cid", "trigger"])
+        }
+        Command::Search(SearchCommand::Query { query, kind, facets }) => {
+            let query = query_string(&[("q", &Some(query.join(" "))), ("type", &kind), ("facets", &facets)]);
+            let results = session.client.get(&format!("{}/search{}", session.context.endpoints.jobd, query)).await?;
+            match session.format {
+                OutputFormat::Json => emit(session, out, &results, &[]),
+                OutputFormat::Table => emit(session, out, &results["hits"], &["kind", "id", "title", "score"]),
+            }
+        }
+        Command::Search(SearchCommand::Rebuild) => {
+            let url = format!("{}/admin/search/rebuild", session.context.endpoints.jobd);
+            let mutation = session.client.mutate("POST", &url, None).await?;
+            emit_mutation(session, out, mutation, &["rebuilt", "synced", "documents"])
+        }
+        Command::Config(ConfigCommand::Print) => {
+            let mut context = serde_json::to_value(&session.context).unwrap_or_default();
+            context["context"] = json!(session.context_name);
+            context["did"] = json!(session.client.did);
+            emit(session, out, &context, &[])
+        }
+        Command::Completion { .. } => Ok(()),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            std::process::exit(if e.use_stderr() { 2 } else { 0 });
+        }
+    };
+    let mut stdout = std::io::stdout();
+    if let Err(e) = run(cli, &mut stdout).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        extract::{Query, State},
+        http::{HeaderMap, Method, StatusCode, Uri},
+        response::Response,
+        routing::get,
+        Router,
+    };
+    use client::RequestSigner;
+    use config::Endpoints;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const TEST_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[derive(Debug, Clone)]
+    struct Recorded {
+        method: String,
+        uri: String,
+        headers: HeaderMap,
+        body: String,
+    }
+
+    type Canned = HashMap<String, (u16, String)>;
+    type MockState = (Arc<Mutex<Vec<Recorded>>>, Arc<Canned>);
+
+    /// Mock that records every request and answers from `canned` ("METHOD /path"), else 200 `{}`
+    async fn spawn_mock(canned: Canned) -> (String, Arc<Mutex<Vec<Recorded>>>) {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let state = (recorded.clone(), Arc::new(canned));
+        let app = Router::new()
+            .fallback(
+                |State((recorded, canned)): State<MockState>,
+                 method: Method,
+                 uri: Uri,
+                 headers: HeaderMap,
+                 body: String| async move {
+                    let key = format!("{} {}", method, uri.path());
+                    recorded.lock().unwrap().push(Recorded { method: method.to_string(), uri: uri.to_string(), headers, body });
+                    let (status, body) = canned.get(&key).cloned().unwrap_or((200, "{}".to_string()));
+                    Response::builder().status(status).body(Body::from(body)).unwrap()
+                },
+            )
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, recorded)
+    }
+
+    fn session(url: &str, dry_run: bool) -> Session {
+        let endpoints = Endpoints {
+            jobd: url.to_string(),
+            scheduler: url.to_string(),
+            proofs: url.to_string(),
+            receipts: url.to_string(),
+            continual: url.to_string(),
+        };
+        Session {
+            context_name: "test".to_string(),
+            context: Context { endpoints, did: None, key_path: None },
+            client: ApiClient::with_signer(Some(RequestSigner::from_hex(TEST_KEY, None).unwrap()), dry_run),
+            format: OutputFormat::Json,
+            follow: FollowOptions { max_retries: 3, base_delay: Duration::from_millis(10) },
+            redraw: false,
+        }
+    }
+
+    fn command(args: &[&str]) -> Command {
+        Cli::try_parse_from(std::iter::once("arthactl").chain(args.iter().copied())).unwrap().command
+    }
+
+    async fn run_args(session: &Session, args: &[&str]) -> (Result<(), CliError>, String) {
+        let mut out = Vec::new();
+        let result = run_command(session, command(args), &mut out).await;
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_command_parsing() {
+        let cli = Cli::try_parse_from(["arthactl", "jobs", "list", "--status", "running", "--did", "did:artha:ab", "-o", "json"]).unwrap();
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(matches!(cli.command, Command::Jobs(JobsCommand::List { status: Some(ref s), did: Some(_) }) if s == "running"));
+
+        let cli = Cli::try_parse_from(["arthactl", "--dry-run", "--context", "prod", "nodes", "drain", "pk1", "--cancel"]).unwrap();
+        assert!(cli.dry_run);
+        assert_eq!(cli.context.as_deref(), Some("prod"));
+        assert!(matches!(cli.command, Command::Nodes(NodesCommand::Drain { cancel: true, .. })));
+
+        assert!(matches!(command(&["jobs", "logs", "j1", "--follow"]), Command::Jobs(JobsCommand::Logs { follow: true, .. })));
+        assert!(matches!(command(&["jobs", "status", "j1", "--watch"]), Command::Jobs(JobsCommand::Status { watch: true, interval: 2, .. })));
+        assert!(matches!(command(&["dlq", "replay", "7"]), Command::Dlq(DlqCommand::Replay { id: 7 })));
+        assert!(matches!(command(&["completion", "bash"]), Command::Completion { .. }));
+
+        // Maintenance needs exactly one of --on / --off; dispute needs a reason
+        assert!(Cli::try_parse_from(["arthactl", "nodes", "maintenance", "pk1"]).is_err());
+        assert!(Cli::try_parse_from(["arthactl", "nodes", "maintenance", "pk1", "--on", "--off"]).is_err());
+        assert!(Cli::try_parse_from(["arthactl", "receipts", "dispute", "r1"]).is_err());
+        assert!(Cli::try_parse_from(["arthactl", "jobs", "submit"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_built_and_signed_per_family() {
+        let (url, recorded) = spawn_mock(Canned::new()).await;
+        let session = session(&url, false);
+
+        let job_file = std::env::temp_dir().join(format!("arthactl-job-{}.yaml", std::process::id()));
+        std::fs::write(&job_file, "kind: infer\nspec:\n  model_id: m1\n  mode: batch\n").unwrap();
+        let job_file = job_file.to_str().unwrap().to_string();
+
+        run_args(&session, &["jobs", "submit", "-f", &job_file]).await.0.unwrap();
+        run_args(&session, &["jobs", "list", "--status", "running", "--did", "did:artha:x y"]).await.0.unwrap();
+        run_args(&session, &["jobs", "cancel", "j1"]).await.0.unwrap();
+        run_args(&session, &["nodes", "drain", "pk1", "--cancel"]).await.0.unwrap();
+        run_args(&session, &["nodes", "maintenance", "pk1", "--on", "--until", "1700000000", "--reason", "kernel"]).await.0.unwrap();
+        run_args(&session, &["receipts", "list", "--provider", "p1", "--status", "Pending"]).await.0.unwrap();
+        run_args(&session, &["receipts", "dispute", "r1", "--reason", "output missing"]).await.0.unwrap();
+        run_args(&session, &["dlq", "replay", "4"]).await.0.unwrap();
+        run_args(&session, &["schedules", "list"]).await.0.unwrap();
+        run_args(&session, &["search", "query", "finance", "status:completed", "--type", "job"]).await.0.unwrap();
+        run_args(&session, &["search", "rebuild"]).await.0.unwrap();
+
+        let requests = recorded.lock().unwrap().clone();
+        let summary: Vec<(String, String)> = requests.iter().map(|r| (r.method.clone(), r.uri.clone())).collect();
+        assert_eq!(summary, vec![
+            ("POST".to_string(), "/job/infer".to_string()),
+            ("GET".to_string(), "/jobs?status=running&did=did:artha:x%20y".to_string()),
+            ("POST".to_string(), "/job/j1/cancel".to_string()),
+            ("DELETE".to_string(), "/nodes/pk1/drain".to_string()),
+            ("POST".to_string(), "/nodes/pk1/maintenance".to_string()),
+            ("GET".to_string(), "/receipts?provider=p1&status=Pending".to_string()),
+            ("POST".to_string(), "/receipt/r1/dispute".to_string()),
+            ("POST".to_string(), "/dlq/4/replay".to_string()),
+            ("GET".to_string(), "/continual/watches".to_string()),
+            ("GET".to_string(), "/search?q=finance%20status:completed&type=job".to_string()),
+            ("POST".to_string(), "/admin/search/rebuild".to_string()),
+        ]);
+
+        // The submitter DID is filled from the signing key
+        let did = session.client.did.clone().unwrap();
+        let submit: Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(submit["submitter_did"], json!(did));
+        assert_eq!(submit["model_id"], "m1");
+        let maintenance: Value = serde_json::from_str(&requests[4].body).unwrap();
+        assert_eq!(maintenance, json!({ "enabled": true, "until": 1700000000u64, "reason": "kernel" }));
+        let dispute: Value = serde_json::from_str(&requests[6].body).unwrap();
+        assert_eq!(dispute, json!({ "disputer": did, "reason": "output missing" }));
+
+        // Every request carries a signature over method, path, body hash and expiry
+        for request in &requests {
+            let header = |name: &str| request.headers.get(name).unwrap().to_str().unwrap().to_string();
+            assert_eq!(header("x-artha-did"), did);
+            let expiry: u64 = header("x-artha-expiry").parse().unwrap();
+            let message = RequestSigner::message(&request.method, &request.uri, request.body.as_bytes(), expiry);
+            let pubkey = hex::decode(did.trim_start_matches("did:artha:")).unwrap();
+            let signature = Signature::from_slice(&hex::decode(header("x-artha-signature")).unwrap()).unwrap();
+            VerifyingKey::from_sec1_bytes(&pubkey).unwrap().verify(message.as_bytes(), &signature).unwrap();
+        }
+
+        // --dry-run prints the request and sends nothing
+        let dry = self::session(&url, true);
+        let (result, printed) = run_args(&dry, &["jobs", "cancel", "j2"]).await;
+        result.unwrap();
+        let printed: Value = serde_json::from_str(&printed).unwrap();
+        assert_eq!(printed["method"], "POST");
+        assert!(printed["url"].as_str().unwrap().ends_with("/job/j2/cancel"));
+        assert_eq!(recorded.lock().unwrap().len(), requests.len());
+        let _ = std::fs::remove_file(&job_file);
+    }
+
+    #[tokio::test]
+    async fn test_api_errors_map_to_exit_codes() {
+        let mut canned = Canned::new();
+        canned.insert("GET /job/missing/status".to_string(), (404, String::new()));
+        canned.insert("POST /receipt/r1/dispute".to_string(), (409, r#"{"error":"already settled"}"#.to_string()));
+        canned.insert("GET /nodes".to_string(), (503, "scheduler overloaded".to_string()));
+        canned.insert("GET /dlq".to_string(), (401, String::new()));
+        canned.insert(
+            "GET /proofs/j1".to_string(),
+            (200, json!([
+                { "proof_type": "TrainStep", "step": 10, "submitted": true, "tx_hash": "0x1" },
+                { "proof_type": "TrainStep", "step": 30, "submitted": true, "tx_hash": "0x2" },
+                { "proof_type": "TrainStep", "step": 40, "submitted": true, "tx_hash": "0x3" },
+                { "proof_type": "TrainComplete", "submitted": true, "tx_hash": "0x4" },
+            ]).to_string()),
+        );
+        canned.insert("GET /attestation/j1".to_string(), (404, String::new()));
+        let (url, _) = spawn_mock(canned).await;
+        let session = session(&url, false);
+
+        let code = |result: Result<(), CliError>| result.unwrap_err().exit_code();
+        assert_eq!(code(run_args(&session, &["jobs", "status", "missing"]).await.0), 3);
+        let dispute = run_args(&session, &["receipts", "dispute", "r1", "--reason", "x"]).await.0.unwrap_err();
+        assert_eq!(dispute.exit_code(), 4);
+        assert_eq!(dispute.to_string(), r#"API error 409: {"error":"already settled"}"#);
+        assert_eq!(code(run_args(&session, &["nodes", "list"]).await.0), 7);
+        assert_eq!(code(run_args(&session, &["dlq", "list"]).await.0), 5);
+
+        // Steps 10, 30, 40 leave a gap; verification fails with its own code
+        let (result, printed) = run_args(&session, &["proofs", "verify", "--job", "j1"]).await;
+        assert_eq!(code(result), 9);
+        assert!(printed.contains("train_steps_contiguous"));
+
+        assert_eq!(CliError::Api { status: 429, body: String::new() }.exit_code(), 6);
+        assert_eq!(CliError::Transport("refused".to_string()).exit_code(), 8);
+        let closed = self::session("http://127.0.0.1:1", false);
+        assert_eq!(code(run_args(&closed, &["jobs", "list"]).await.0), 8);
+    }
+
+    #[tokio::test]
+    async fn test_follow_reconnects_from_last_line() {
+        let froms = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/job/:id/logs/stream",
+                get(|State(froms): State<Arc<Mutex<Vec<usize>>>>, Query(params): Query<HashMap<String, usize>>| async move {
+                    let from = params.get("from").copied().unwrap_or(0);
+                    froms.lock().unwrap().push(from);
+                    // First connection drops after two lines without an end event
+                    let body = if from == 0 {
+                        "id: 0\ndata: epoch 1\n\nid: 1\ndata: epoch 2\n\n".to_string()
+                    } else {
+                        format!("id: {}\ndata: epoch 3\n\nevent: end\ndata: Completed\n\n", from)
+                    };
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .header("content-type", "text/event-stream")
+                        .body(Body::from(body))
+                        .unwrap()
+                }),
+            )
+            .with_state(froms.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let session = session(&url, false);
+        let mut out = Vec::new();
+        let status = follow_logs(&session.client, &url, "j1", 0, &session.follow, &mut out).await.unwrap();
+        assert_eq!(status, "Completed");
+        assert_eq!(String::from_utf8(out).unwrap(), "epoch 1\nepoch 2\nepoch 3\n");
+        assert_eq!(*froms.lock().unwrap(), vec![0, 2]);
+
+        // A stream that never comes back gives up after max_retries
+        let mut out = Vec::new();
+        let result = follow_logs(&session.client, "http://127.0.0.1:1", "j1", 0, &session.follow, &mut out).await;
+        assert_eq!(result.unwrap_err().exit_code(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_list_follows_cursors_across_pages() {
+        let app = Router::new().route(
+            "/jobs",
+            get(|Query(params): Query<HashMap<String, String>>| async move {
+                assert_eq!(params.get("status").map(String::as_str), Some("running"));
+                let page = match params.get("cursor").map(String::as_str) {
+                    None => json!({ "items": [{ "job_id": "j1" }, { "job_id": "j2" }], "total": 3, "next_cursor": "6a32" }),
+                    Some("6a32") => json!({ "items": [{ "job_id": "j3" }], "total": 3, "next_cursor": null }),
+                    Some(other) => panic!("unexpected cursor {}", other),
+                };
+                axum::Json(page)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let session = session(&url, false);
+        let (result, printed) = run_args(&session, &["jobs", "list", "--status", "running"]).await;
+        result.unwrap();
+        let jobs: Value = serde_json::from_str(&printed).unwrap();
+        assert_eq!(jobs, json!([{ "job_id": "j1" }, { "job_id": "j2" }, { "job_id": "j3" }]));
+    }
+
+    #[tokio::test]
+    async fn test_download_is_checked_against_the_node_signed_digest() {
+        use k256::ecdsa::{signature::Signer, SigningKey};
+        let node_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let output = b"the answer is 42".to_vec();
+        let digest = blake3::hash(&output).to_hex().to_string();
+        let signature: Signature = node_key.sign(format!("OUTPUT:j1:bafy-answer:{}", digest).as_bytes());
+        let integrity = json!({
+            "output_cid": "bafy-answer",
+            "output_digest": digest,
+            "node_pubkey": hex::encode(node_key.verifying_key().to_encoded_point(true).as_bytes()),
+            "signature": hex::encode(signature.to_bytes()),
+        });
+        assert_eq!(verify_output("j1", &output, &integrity), Ok(()));
+        assert!(verify_output("j1", b"the answer is 41", &integrity).unwrap_err().contains("does not match"));
+        assert!(verify_output("j2", &output, &integrity).unwrap_err().contains("not signed"));
+
+        // The mock gateway serves whatever bytes the test puts in `served`
+        let served = Arc::new(Mutex::new(output.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let link = json!({ "output_id": "out-1", "url": format!("{}/outputs/out-1", url), "expires_at": 0, "integrity": integrity });
+        let app = Router::new()
+            .route("/job/j1/output/link", axum::routing::post(move || async move { axum::Json(link) }))
+            .route(
+                "/outputs/out-1",
+                get(|State(served): State<Arc<Mutex<Vec<u8>>>>, headers: HeaderMap| async move {
+                    assert!(headers.contains_key("x-artha-did"));
+                    served.lock().unwrap().clone()
+                }),
+            )
+            .with_state(served.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let session = session(&url, false);
+        let path = std::env::temp_dir().join(format!("arthactl-output-{}", std::process::id()));
+        let path_arg = path.to_str().unwrap().to_string();
+        let (result, printed) = run_args(&session, &["jobs", "download", "j1", "--to", &path_arg]).await;
+        result.unwrap();
+        let row: Value = serde_json::from_str(&printed).unwrap();
+        assert_eq!(row["verified"], true);
+        assert_eq!(row["output_digest"], json!(digest));
+        assert_eq!(std::fs::read(&path).unwrap(), output);
+        std::fs::remove_file(&path).unwrap();
+
+        // A corrupted download fails the digest check and is not written
+        served.lock().unwrap()[0] ^= 0xff;
+        let (result, _) = run_args(&session, &["jobs", "download", "j1", "--to", &path_arg]).await;
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 9);
+        assert!(err.to_string().contains("does not match"));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,63 @@
+//! Output Rendering
+//! `--output json` prints responses as pretty JSON; `--output table` prints
+//! lists as aligned columns and single objects as key/value rows.
+
+use clap::ValueEnum;
+use serde_json::Value;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Table,
+}
+
+/// Cell text: strings unquoted, null as "-", nested values as compact JSON
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Look up a dotted path such as `job.status`
+fn field<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, key| v.get(key))
+}
+
+fn write_rows(out: &mut dyn Write, rows: &[Vec<String>]) -> std::io::Result<()> {
+    let widths: Vec<usize> = (0..rows.first().map_or(0, |r| r.len()))
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(text, width)| format!("{:<width$}", text, width = width))
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+/// Render `value`. In table mode, arrays use `columns` (dotted paths) and
+/// objects are printed as one key/value row per field.
+pub fn render(out: &mut dyn Write, format: OutputFormat, value: &Value, columns: &[&str]) -> std::io::Result<()> {
+    match (format, value) {
+        (OutputFormat::Json, _) => writeln!(out, "{}", serde_json::to_string_pretty(value).unwrap_or_default()),
+        (OutputFormat::Table, Value::Array(items)) => {
+            if items.is_empty() {
+                return writeln!(out, "No results");
+            }
+            let mut rows = vec![columns.iter().map(|c| c.rsplit('.').next().unwrap_or(c).to_uppercase()).collect()];
+            rows.extend(items.iter().map(|item| columns.iter().map(|c| cell(field(item, c))).collect()));
+            write_rows(out, &rows)
+        }
+        (OutputFormat::Table, Value::Object(map)) => {
+            let rows: Vec<Vec<String>> = map.iter().map(|(k, v)| vec![k.clone(), cell(Some(v))]).collect();
+            write_rows(out, &rows)
+        }
+        (OutputFormat::Table, other) => writeln!(out, "{}", cell(Some(other))),
+    }
+}
@@ -0,0 +1,128 @@
+//! Log Following
+//! Reads jobd's server-sent log stream and reconnects when it drops, resuming
+//! from the line after the last one printed so no line is shown twice.
+
+use std::io::Write;
+use std::time::Duration;
+
+use crate::client::{ApiClient, CliError};
+
+pub struct FollowOptions {
+    pub max_retries: u32,    // Consecutive reconnects without new data before giving up
+    pub base_delay: Duration, // Doubled per consecutive retry, capped at 30s
+}
+
+impl Default for FollowOptions {
+    fn default() -> Self {
+        FollowOptions { max_retries: 8, base_delay: Duration::from_millis(500) }
+    }
+}
+
+/// One parsed server-sent event
+#[derive(Debug, Default, PartialEq)]
+pub struct SseEvent {
+    pub id: Option<usize>,
+    pub event: Option<String>,
+    pub data: String,
+}
+
+pub fn parse_event(block: &str) -> SseEvent {
+    let mut event = SseEvent::default();
+    for line in block.lines() {
+        if let Some(id) = line.strip_prefix("id:") {
+            event.id = id.trim().parse().ok();
+        } else if let Some(name) = line.strip_prefix("event:") {
+            event.event = Some(name.trim().to_string());
+        } else if let Some(data) = line.strip_prefix("data:") {
+            if !event.data.is_empty() {
+                event.data.push('\n');
+            }
+            event.data.push_str(data.strip_prefix(' ').unwrap_or(data));
+        }
+    }
+    event
+}
+
+enum Outcome {
+    Ended(String),
+    Dropped,
+}
+
+/// Read one connection until it ends or drops. `next` advances past every line written.
+async fn read_stream(
+    client: &ApiClient,
+    url: &str,
+    next: &mut usize,
+    out: &mut dyn Write,
+) -> Result<Outcome, CliError> {
+    let request_url = format!("{}?from={}", url, next);
+    let mut request = client.http().get(&request_url);
+    for (name, value) in client.signed_headers("GET", &request_url) {
+        request = request.header(name, value);
+    }
+    let mut response = match request.send().await {
+        Ok(response) => response,
+        Err(_) => return Ok(Outcome::Dropped),
+    };
+    let status = response.status();
+    if status.is_server_error() {
+        return Ok(Outcome::Dropped);
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(CliError::Api { status: status.as_u16(), body });
+    }
+
+    let mut buffer = String::new();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) | Err(_) => return Ok(Outcome::Dropped),
+        };
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            let event = parse_event(&block);
+            if event.event.as_deref() == Some("end") {
+                return Ok(Outcome::Ended(event.data));
+            }
+            writeln!(out, "{}", event.data).map_err(|e| CliError::Transport(e.to_string()))?;
+            *next = event.id.map_or(*next + 1, |id| id + 1);
+        }
+    }
+}
+
+/// Follow a job's logs from line `from` until the job finishes. Returns the final status.
+pub async fn follow_logs(
+    client: &ApiClient,
+    jobd_url: &str,
+    job_id: &str,
+    from: usize,
+    options: &FollowOptions,
+    out: &mut dyn Write,
+) -> Result<String, CliError> {
+    let url = format!("{}/job/{}/logs/stream", jobd_url, job_id);
+    let mut next = from;
+    let mut retries = 0;
+    loop {
+        let before = next;
+        match read_stream(client, &url, &mut next, out).await? {
+            Outcome::Ended(status) => return Ok(status),
+            Outcome::Dropped => {
+                if next > before {
+                    retries = 0; // Progress since the last drop; start backing off afresh
+                }
+                retries += 1;
+                if retries > options.max_retries {
+                    return Err(CliError::Transport(format!(
+                        "Log stream dropped {} times without progress",
+                        options.max_retries
+                    )));
+                }
+                let delay = (options.base_delay * 2u32.pow((retries - 1).min(16))).min(Duration::from_secs(30));
+                eprintln!("⚠️  Log stream dropped, reconnecting from line {} in {:?}", next, delay);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
@@ -7,11 +7,17 @@ use axum::{
     routing::{get, post},
     Router,
 };
+use artha_clock::{Backoff, BackoffPolicy, SharedClock, SharedEntropy};
+use artha_paging::{Page, PageQuery};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::{error, info};
+
+mod promotion;
+use promotion::{EvalConfig, PromotionDecision, PromotionEvent, PromotionHistory};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContinualLearningJob {
@@ -23,37 +29,51 @@ pub struct ContinualLearningJob {
     pub created_at: u64,
     pub started_at: Option<u64>,
     pub completed_at: Option<u64>,
+    #[serde(default)]
+    pub watch_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct WatchStreamRequest {
     pub model_id: String,
     pub dataset_cid: String,
+    #[serde(default)]
     pub stream_path: String,
+    #[serde(default)]
+    pub dataset_prefix: Option<String>, // Watch dataset versions registered under this name prefix (e.g. stream job windows) instead of `stream_path`
     pub min_samples: u64,
     pub fine_tune_trigger: String,  // "sample_count", "time_interval", "performance_drop"
+    #[serde(default)]
+    pub budget: Option<u64>,  // Total eval spend allowed for this watch
+    #[serde(default)]
+    pub eval: Option<EvalConfig>,  // Canary evaluation before promotion
 }
 
 pub struct AppState {
     active_watches: Arc<RwLock<HashMap<String, WatchStreamRequest>>>,
     jobs: Arc<RwLock<HashMap<String, ContinualLearningJob>>>,
+    promotions: Arc<RwLock<HashMap<String, PromotionHistory>>>, // watch_id -> history
     jobd_url: String,
     scheduler_url: String,
+    clock: SharedClock,
+    entropy: SharedEntropy,
 }
 
 async fn watch_stream(
     State(state): State<Arc<AppState>>,
     Json(req): Json<WatchStreamRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let watch_id = format!("watch-{}", uuid::Uuid::new_v4());
+    let watch_id = format!("watch-{}", state.entropy.uuid());
     
     // Register watch
     state.active_watches.write().await.insert(watch_id.clone(), req.clone());
+    state.promotions.write().await.insert(watch_id.clone(), PromotionHistory::default());
     
     // Start background watcher
     let state_clone = state.clone();
+    let loop_watch_id = watch_id.clone();
     tokio::spawn(async move {
-        watch_stream_loop(state_clone, watch_id.clone(), req).await;
+        watch_stream_loop(state_clone, loop_watch_id, req).await;
     });
     
     Ok(Json(serde_json::json!({
@@ -67,21 +87,37 @@ async fn watch_stream_loop(
     watch_id: String,
     req: WatchStreamRequest,
 ) {
-    let mut last_check = SystemTime::now();
+    let mut last_check = state.clock.now_secs();
     let mut sample_count = 0u64;
+    let mut seen_versions = HashSet::new();
+    let mut dataset_id = req.dataset_cid.clone(); // Latest version when watching by prefix
+    let policy = BackoffPolicy::from_env("ARTHA_CONTINUAL_POLL", Duration::from_secs(60));
+    let mut backoff = Backoff::new(policy, state.entropy.clone());
     
     loop {
-        tokio::time::sleep(Duration::from_secs(60)).await;
+        state.clock.sleep(backoff.delay()).await;
         
-        // Check SVDB stream for new samples
-        let new_samples = check_stream_for_samples(&req.stream_path).await;
+        // Evaluate fine-tunes that finished since the last tick
+        poll_fine_tunes(&state, &watch_id).await;
+        
+        // Check SVDB stream or newly registered dataset versions for new samples
+        let new_samples = match &req.dataset_prefix {
+            Some(prefix) => {
+                let (samples, latest) = poll_dataset_versions(&state.jobd_url, prefix, &mut seen_versions).await;
+                if let Some(latest) = latest {
+                    dataset_id = latest;
+                }
+                samples
+            }
+            None => check_stream_for_samples(&req.stream_path).await,
+        };
         sample_count += new_samples;
         
         // Check trigger conditions
         let should_trigger = match req.fine_tune_trigger.as_str() {
             "sample_count" => sample_count >= req.min_samples,
             "time_interval" => {
-                last_check.elapsed().unwrap().as_secs() >= 3600  // 1 hour
+                state.clock.now_secs().saturating_sub(last_check) >= 3600  // 1 hour
             },
             "performance_drop" => {
                 check_performance_drop(&req.model_id).await.unwrap_or(false)
@@ -89,28 +125,29 @@ async fn watch_stream_loop(
             _ => false,
         };
         
+        let mut turn_ok = true;
         if should_trigger {
             // Trigger fine-tune via ai-jobd
             let job_id = trigger_fine_tune(
                 &state.jobd_url,
                 &req.model_id,
-                &req.dataset_cid,
+                &dataset_id,
                 "new_data",
             ).await;
+            // jobd refusing the fine-tune backs the watch off
+            turn_ok = job_id.is_ok();
             
             if let Ok(jid) = job_id {
                 let cl_job = ContinualLearningJob {
                     job_id: jid.clone(),
                     model_id: req.model_id.clone(),
-                    dataset_cid: req.dataset_cid.clone(),
+                    dataset_cid: dataset_id.clone(),
                     trigger_reason: req.fine_tune_trigger.clone(),
                     status: "queued".to_string(),
-                    created_at: SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
+                    created_at: state.clock.now_secs(),
                     started_at: None,
                     completed_at: None,
+                    watch_id: watch_id.clone(),
                 };
                 
                 state.jobs.write().await.insert(jid, cl_job);
@@ -118,8 +155,9 @@ async fn watch_stream_loop(
             
             // Reset counters
             sample_count = 0;
-            last_check = SystemTime::now();
+            last_check = state.clock.now_secs();
         }
+        backoff.record(turn_ok);
     }
 }
 
@@ -145,6 +183,28 @@ async fn check_stream_for_samples(stream_path: &str) -> u64 {
     }
 }
 
+/// Samples in dataset versions under `prefix` not in `seen`, and the newest
+/// such version. Versions without a sample count count as one sample.
+async fn poll_dataset_versions(jobd_url: &str, prefix: &str, seen: &mut HashSet<String>) -> (u64, Option<String>) {
+    let client = reqwest::Client::new();
+    let url = format!("{}/ai/dataset/list", jobd_url);
+    let versions = match client.get(&url).query(&[("prefix", prefix)]).send().await {
+        Ok(resp) if resp.status().is_success() => resp.json::<Vec<serde_json::Value>>().await.unwrap_or_default(),
+        _ => return (0, None),
+    };
+
+    let mut samples = 0;
+    let mut latest = None;
+    for version in versions {
+        let Some(dataset_id) = version["dataset_id"].as_str() else { continue };
+        if seen.insert(dataset_id.to_string()) {
+            samples += version["samples"].as_u64().unwrap_or(1);
+            latest = Some(dataset_id.to_string()); // Listed oldest first
+        }
+    }
+    (samples, latest)
+}
+
 async fn check_performance_drop(model_id: &str) -> Result<bool, String> {
     // Check if model performance has dropped below threshold
     // In production: Query metrics endpoint
@@ -189,23 +249,235 @@ async fn trigger_fine_tune(
     }
 }
 
+/// Check this watch's outstanding fine-tunes with ai-jobd and hand completed
+/// checkpoints to canary evaluation
+async fn poll_fine_tunes(state: &AppState, watch_id: &str) {
+    let pending: Vec<String> = state
+        .jobs
+        .read()
+        .await
+        .values()
+        .filter(|j| j.watch_id == watch_id && j.completed_at.is_none())
+        .map(|j| j.job_id.clone())
+        .collect();
+
+    let client = reqwest::Client::new();
+    for job_id in pending {
+        let status = match client.get(format!("{}/job/{}/status", state.jobd_url, job_id)).send().await {
+            Ok(resp) if resp.status().is_success() => resp.json::<serde_json::Value>().await.unwrap_or_default(),
+            _ => continue,
+        };
+
+        let finished = match status["job"]["status"].as_str() {
+            Some("Completed") => "completed",
+            Some("Failed") | Some("Cancelled") => "failed",
+            _ => continue,
+        };
+        if let Some(job) = state.jobs.write().await.get_mut(&job_id) {
+            job.status = finished.to_string();
+            job.completed_at = Some(state.clock.now_secs());
+        }
+
+        if finished == "completed" {
+            // The checkpoint is published under the job's opaque output id
+            let candidate = status["output_id"].as_str().unwrap_or(&job_id).to_string();
+            let _ = evaluate_candidate(state, watch_id, &job_id, &candidate).await;
+        }
+    }
+}
+
+/// Canary-evaluate a completed fine-tune against the serving version and
+/// promote it only if it does not regress. Returns None when the watch has
+/// no eval configured.
+async fn evaluate_candidate(
+    state: &AppState,
+    watch_id: &str,
+    fine_tune_job: &str,
+    candidate: &str,
+) -> Result<Option<PromotionEvent>, StatusCode> {
+    let watch = state.active_watches.read().await.get(watch_id).cloned().ok_or(StatusCode::NOT_FOUND)?;
+    let Some(eval) = watch.eval.clone() else {
+        return Ok(None);
+    };
+    let spent = state.promotions.read().await.get(watch_id).map_or(0, |h| h.budget_spent);
+
+    let mut event = PromotionEvent {
+        event_id: format!("promo-{}", state.entropy.uuid()),
+        candidate: candidate.to_string(),
+        baseline: None,
+        candidate_score: None,
+        baseline_score: None,
+        metric: Some(eval.metric.clone()),
+        decision: PromotionDecision::Rejected,
+        reason: String::new(),
+        fine_tune_job: Some(fine_tune_job.to_string()),
+        eval_job: None,
+        eval_cost: 0,
+        decided_at: state.clock.now_secs(),
+    };
+
+    match promotion::get_alias(&state.jobd_url, &watch.model_id, &eval.alias).await {
+        Err(e) => event.reason = format!("serving version unknown: {}", e),
+        Ok(None) => {
+            event.decision = PromotionDecision::Promoted;
+            event.reason = "no serving version".to_string();
+        }
+        Ok(Some(baseline)) => {
+            event.baseline = Some(baseline.clone());
+            let eval_budget = watch
+                .budget
+                .map_or(eval.max_eval_cost, |b| b.saturating_sub(spent).min(eval.max_eval_cost));
+
+            if eval_budget == 0 {
+                event.reason = "watch budget exhausted".to_string();
+            } else {
+                match promotion::run_eval(&state.jobd_url, &watch.model_id, candidate, &baseline, &eval, eval_budget).await {
+                    Err(e) => event.reason = format!("evaluation failed: {}", e),
+                    Ok(result) => {
+                        event.eval_job = Some(result.job_id);
+                        event.eval_cost = result.spent.min(eval_budget);
+                        event.candidate_score = Some(result.candidate_score);
+                        event.baseline_score = Some(result.baseline_score);
+                        if eval.non_regression(result.candidate_score, result.baseline_score) {
+                            event.decision = PromotionDecision::Promoted;
+                            event.reason = "non-regression criterion met".to_string();
+                        } else {
+                            event.reason = format!(
+                                "{} {} vs baseline {} (epsilon {})",
+                                eval.metric, result.candidate_score, result.baseline_score, eval.epsilon
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if event.decision == PromotionDecision::Promoted {
+        if let Err(e) = promotion::set_alias(&state.jobd_url, &watch.model_id, &eval.alias, candidate).await {
+            event.decision = PromotionDecision::Rejected;
+            event.reason = e;
+        }
+    }
+
+    if event.decision == PromotionDecision::Promoted {
+        info!("🚀 Promoted {} to {}@{}", candidate, watch.model_id, eval.alias);
+    } else {
+        info!("🛑 Rejected promotion of {} for {}: {}", candidate, watch_id, event.reason);
+        if let Some(webhook) = &eval.alert_webhook {
+            promotion::send_alert(webhook, watch_id, &event).await;
+        }
+    }
+
+    state
+        .promotions
+        .write()
+        .await
+        .entry(watch_id.to_string())
+        .or_default()
+        .record(event.clone());
+    Ok(Some(event))
+}
+
+async fn get_promotions(
+    State(state): State<Arc<AppState>>,
+    Path(watch_id): Path<String>,
+) -> Result<Json<PromotionHistory>, StatusCode> {
+    let promotions = state.promotions.read().await;
+    let history = promotions.get(&watch_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(history.clone()))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideAction {
+    ForcePromote,
+    Rollback,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromotionOverrideRequest {
+    pub action: OverrideAction,
+    pub version: String, // Any candidate or baseline in the watch's history
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// POST /continual/watch/:id/promotions/override - Manually point the
+/// serving alias at a version from the promotion history
+async fn override_promotion(
+    State(state): State<Arc<AppState>>,
+    Path(watch_id): Path<String>,
+    Json(req): Json<PromotionOverrideRequest>,
+) -> Result<Json<PromotionEvent>, StatusCode> {
+    let watch = state.active_watches.read().await.get(&watch_id).cloned().ok_or(StatusCode::NOT_FOUND)?;
+    let alias = watch.eval.as_ref().map_or("serving".to_string(), |e| e.alias.clone());
+
+    let baseline = {
+        let promotions = state.promotions.read().await;
+        let history = promotions.get(&watch_id).ok_or(StatusCode::NOT_FOUND)?;
+        if !history.knows(&req.version) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        history.serving.clone()
+    };
+
+    promotion::set_alias(&state.jobd_url, &watch.model_id, &alias, &req.version)
+        .await
+        .map_err(|e| {
+            error!("❌ Override for {} failed: {}", watch_id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    let decision = match req.action {
+        OverrideAction::ForcePromote => PromotionDecision::ForcePromoted,
+        OverrideAction::Rollback => PromotionDecision::RolledBack,
+    };
+    let event = PromotionEvent {
+        event_id: format!("promo-{}", state.entropy.uuid()),
+        candidate: req.version.clone(),
+        baseline,
+        candidate_score: None,
+        baseline_score: None,
+        metric: None,
+        decision,
+        reason: req.reason.unwrap_or_else(|| "manual override".to_string()),
+        fine_tune_job: None,
+        eval_job: None,
+        eval_cost: 0,
+        decided_at: state.clock.now_secs(),
+    };
+
+    info!("🔁 {:?} {}@{} -> {}", decision, watch.model_id, alias, req.version);
+    state
+        .promotions
+        .write()
+        .await
+        .entry(watch_id)
+        .or_default()
+        .record(event.clone());
+    Ok(Json(event))
+}
+
+/// GET /continual/watches - Active watches ordered by id, one page at a time
 async fn list_watches(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<serde_json::Value>>, StatusCode> {
     let watches = state.active_watches.read().await;
-    let list: Vec<serde_json::Value> = watches
+    let list = watches
         .iter()
         .map(|(id, req)| {
             serde_json::json!({
                 "watch_id": id,
                 "model_id": req.model_id,
                 "dataset_cid": req.dataset_cid,
+                "dataset_prefix": req.dataset_prefix,
                 "trigger": req.fine_tune_trigger,
             })
-        })
-        .collect();
+        });
     
-    Ok(Json(list))
+    Ok(Json(artha_paging::paginate(list, |watch| watch["watch_id"].as_str().unwrap_or_default().to_string(), &page)?))
 }
 
 async fn get_job_status(
@@ -226,25 +498,349 @@ async fn get_job_status(
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
         active_watches: Arc::new(RwLock::new(HashMap::new())),
         jobs: Arc::new(RwLock::new(HashMap::new())),
+        promotions: Arc::new(RwLock::new(HashMap::new())),
         jobd_url: std::env::var("ARTHA_JOBD_URL")
             .unwrap_or_else(|_| "http://localhost:8081".to_string()),
         scheduler_url: std::env::var("ARTHA_SCHEDULER_URL")
             .unwrap_or_else(|_| "http://localhost:8083".to_string()),
+        clock: artha_clock::system_clock(),
+        entropy: artha_clock::system_entropy(),
     });
 
     let app = Router::new()
         .route("/continual/watch", post(watch_stream))
         .route("/continual/watches", get(list_watches))
         .route("/continual/job/:id/status", get(get_job_status))
+        .route("/continual/watch/:id/promotions", get(get_promotions))
+        .route("/continual/watch/:id/promotions/override", post(override_promotion))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🔄 Continual Learning Daemon starting on :8090");
+    info!("🔄 Continual Learning Daemon starting on :8090");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8090").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use artha_clock::{ManualClock, SeededEntropy};
+
+    type Aliases = Arc<std::sync::Mutex<HashMap<String, String>>>;
+
+    /// Mock ai-jobd: alias API plus an eval endpoint scoring versions from a
+    /// fixed table
+    async fn mock_jobd(scores: HashMap<String, f64>) -> (String, Aliases) {
+        let aliases: Aliases = Arc::new(std::sync::Mutex::new(HashMap::new()));
+        let (get_aliases, put_aliases) = (aliases.clone(), aliases.clone());
+
+        let app = Router::new()
+            .route(
+                "/ai/model/:id/alias/:alias",
+                get(move |Path((name, alias)): Path<(String, String)>| async move {
+                    match get_aliases.lock().unwrap().get(&format!("{}@{}", name, alias)) {
+                        Some(model_id) => Ok(Json(serde_json::json!({ "model_id": model_id }))),
+                        None => Err(StatusCode::NOT_FOUND),
+                    }
+                })
+                .put(move |Path((name, alias)): Path<(String, String)>, Json(body): Json<serde_json::Value>| async move {
+                    let model_id = body["model_id"].as_str().unwrap().to_string();
+                    put_aliases.lock().unwrap().insert(format!("{}@{}", name, alias), model_id);
+                    StatusCode::OK
+                }),
+            )
+            .route("/job/eval", post(move |Json(body): Json<serde_json::Value>| async move {
+                let score = |key: &str| scores[body[key].as_str().unwrap()];
+                Json(serde_json::json!({
+                    "job_id": format!("eval-{}", body["candidate"].as_str().unwrap()),
+                    "candidate_score": score("candidate"),
+                    "baseline_score": score("baseline"),
+                    "spent": 20,
+                }))
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, aliases)
+    }
+
+    async fn test_state(scores: &[(&str, f64)], budget: Option<u64>) -> (Arc<AppState>, Aliases) {
+        let scores = scores.iter().map(|(v, s)| (v.to_string(), *s)).collect();
+        let (jobd_url, aliases) = mock_jobd(scores).await;
+        let state = Arc::new(AppState {
+            active_watches: Arc::new(RwLock::new(HashMap::new())),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            promotions: Arc::new(RwLock::new(HashMap::new())),
+            jobd_url,
+            scheduler_url: String::new(),
+            clock: ManualClock::new(1_700_000_000).shared(),
+            entropy: SeededEntropy::shared(1),
+        });
+
+        let watch: WatchStreamRequest = serde_json::from_value(serde_json::json!({
+            "model_id": "resnet",
+            "dataset_cid": "bafy-stream",
+            "stream_path": "http://127.0.0.1:9",
+            "min_samples": 100,
+            "fine_tune_trigger": "sample_count",
+            "budget": budget,
+            "eval": { "eval_set_cid": "bafy-heldout", "metric": "accuracy", "epsilon": 0.01 },
+        }))
+        .unwrap();
+        state.active_watches.write().await.insert("watch-1".to_string(), watch);
+        state.promotions.write().await.insert("watch-1".to_string(), PromotionHistory::default());
+        (state, aliases)
+    }
+
+    async fn candidate(state: &AppState, version: &str) -> PromotionEvent {
+        evaluate_candidate(state, "watch-1", &format!("job-{}", version), version)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    fn serving(aliases: &Aliases) -> Option<String> {
+        aliases.lock().unwrap().get("resnet@serving").cloned()
+    }
+
+    #[tokio::test]
+    async fn test_promotes_on_improvement() {
+        let (state, aliases) = test_state(&[("v1", 0.80), ("v2", 0.85)], None).await;
+
+        let first = candidate(&state, "v1").await;
+        assert_eq!(first.decision, PromotionDecision::Promoted);
+        assert_eq!(first.baseline, None); // Nothing serving yet
+
+        let event = candidate(&state, "v2").await;
+        assert_eq!(event.decision, PromotionDecision::Promoted);
+        assert_eq!(event.baseline.as_deref(), Some("v1"));
+        assert_eq!(event.candidate_score, Some(0.85));
+        assert_eq!(event.baseline_score, Some(0.80));
+        assert_eq!(serving(&aliases).as_deref(), Some("v2"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_regression_and_keeps_old_alias() {
+        let (state, aliases) = test_state(&[("v1", 0.80), ("v2", 0.795), ("v3", 0.70)], None).await;
+        candidate(&state, "v1").await;
+
+        // Within epsilon counts as non-regression
+        assert_eq!(candidate(&state, "v2").await.decision, PromotionDecision::Promoted);
+
+        let event = candidate(&state, "v3").await;
+        assert_eq!(event.decision, PromotionDecision::Rejected);
+        assert_eq!(event.eval_job.as_deref(), Some("eval-v3"));
+        assert_eq!(serving(&aliases).as_deref(), Some("v2"));
+
+        let history = state.promotions.read().await["watch-1"].clone();
+        assert_eq!(history.serving.as_deref(), Some("v2"));
+    }
+
+    #[tokio::test]
+    async fn test_eval_cost_drawn_from_watch_budget() {
+        let (state, aliases) = test_state(&[("v1", 0.8), ("v2", 0.9), ("v3", 0.95)], Some(30)).await;
+        candidate(&state, "v1").await;
+
+        let event = candidate(&state, "v2").await;
+        assert_eq!(event.eval_cost, 20);
+
+        // 10 left: the eval runs capped at the remainder
+        let event = candidate(&state, "v3").await;
+        assert_eq!(event.eval_cost, 10);
+        assert_eq!(state.promotions.read().await["watch-1"].budget_spent, 30);
+
+        candidate(&state, "v1").await; // Any further candidate cannot be evaluated
+        let history = state.promotions.read().await["watch-1"].clone();
+        let last = history.events.last().unwrap();
+        assert_eq!(last.decision, PromotionDecision::Rejected);
+        assert_eq!(last.reason, "watch budget exhausted");
+        assert_eq!(serving(&aliases).as_deref(), Some("v3"));
+    }
+
+    #[tokio::test]
+    async fn test_manual_rollback_to_two_versions_old() {
+        let (state, aliases) = test_state(&[("v1", 0.80), ("v2", 0.85), ("v3", 0.90)], None).await;
+        for version in ["v1", "v2", "v3"] {
+            candidate(&state, version).await;
+        }
+        assert_eq!(serving(&aliases).as_deref(), Some("v3"));
+
+        let Json(event) = override_promotion(
+            State(state.clone()),
+            Path("watch-1".to_string()),
+            Json(PromotionOverrideRequest {
+                action: OverrideAction::Rollback,
+                version: "v1".to_string(),
+                reason: Some("customer regression report".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(event.decision, PromotionDecision::RolledBack);
+        assert_eq!(event.baseline.as_deref(), Some("v3"));
+        assert_eq!(serving(&aliases).as_deref(), Some("v1"));
+
+        // Only versions from the history can be targeted
+        let result = override_promotion(
+            State(state.clone()),
+            Path("watch-1".to_string()),
+            Json(PromotionOverrideRequest {
+                action: OverrideAction::ForcePromote,
+                version: "v9".to_string(),
+                reason: None,
+            }),
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_promotion_history() {
+        let (state, _aliases) = test_state(&[("v1", 0.80), ("v2", 0.60), ("v3", 0.82)], None).await;
+        for version in ["v1", "v2", "v3"] {
+            candidate(&state, version).await;
+        }
+        let req = PromotionOverrideRequest {
+            action: OverrideAction::ForcePromote,
+            version: "v2".to_string(),
+            reason: None,
+        };
+        let _ = override_promotion(State(state.clone()), Path("watch-1".to_string()), Json(req)).await.unwrap();
+
+        let Json(history) = get_promotions(State(state.clone()), Path("watch-1".to_string())).await.unwrap();
+        let summary: Vec<(&str, Option<&str>, PromotionDecision)> = history
+            .events
+            .iter()
+            .map(|e| (e.candidate.as_str(), e.baseline.as_deref(), e.decision))
+            .collect();
+        assert_eq!(summary, vec![
+            ("v1", None, PromotionDecision::Promoted),
+            ("v2", Some("v1"), PromotionDecision::Rejected),
+            ("v3", Some("v1"), PromotionDecision::Promoted),
+            ("v2", Some("v3"), PromotionDecision::ForcePromoted),
+        ]);
+        assert_eq!(history.events[1].candidate_score, Some(0.60));
+        assert_eq!(history.events[1].baseline_score, Some(0.80));
+        assert_eq!(history.events[2].fine_tune_job.as_deref(), Some("job-v3"));
+        assert_eq!(history.serving.as_deref(), Some("v2"));
+
+        let missing = get_promotions(State(state.clone()), Path("watch-2".to_string())).await;
+        assert_eq!(missing.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+
+
+    #[tokio::test]
+    async fn test_dataset_prefix_watch_sees_each_new_window_once() {
+        // Mock ai-jobd dataset listing, filtered by name prefix as ai-jobd does
+        let versions: Arc<std::sync::Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let listed = versions.clone();
+        let app = Router::new().route("/ai/dataset/list", get(move |Query(query): Query<HashMap<String, String>>| {
+            let prefix = query.get("prefix").cloned().unwrap_or_default();
+            let matching: Vec<serde_json::Value> = listed
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|v| v["name"].as_str().unwrap().starts_with(&prefix))
+                .cloned()
+                .collect();
+            async move { Json(matching) }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let jobd_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let window = |name: &str, k: u64| serde_json::json!({
+            "dataset_id": format!("dataset-{}-w{}", name, k),
+            "name": name,
+            "version": format!("w{}", k),
+            "samples": 50,
+            "window": { "by": "count", "start": k * 50, "end": k * 50 + 50 },
+        });
+
+        let mut seen = HashSet::new();
+        assert_eq!(poll_dataset_versions(&jobd_url, "clicks/", &mut seen).await, (0, None));
+
+        versions.lock().unwrap().extend([window("clicks/job-1", 0), window("clicks/job-1", 1), window("views/job-2", 0)]);
+        let polled = poll_dataset_versions(&jobd_url, "clicks/", &mut seen).await;
+        assert_eq!(polled, (100, Some("dataset-clicks/job-1-w1".to_string())));
+
+        // Only windows registered since the last poll count
+        assert_eq!(poll_dataset_versions(&jobd_url, "clicks/", &mut seen).await, (0, None));
+        versions.lock().unwrap().push(window("clicks/job-1", 2));
+        let polled = poll_dataset_versions(&jobd_url, "clicks/", &mut seen).await;
+        assert_eq!(polled, (50, Some("dataset-clicks/job-1-w2".to_string())));
+
+        // An unreachable ai-jobd reads as no new data
+        assert_eq!(poll_dataset_versions("http://127.0.0.1:9", "clicks/", &mut seen).await, (0, None));
+    }
+
+    #[tokio::test]
+    async fn test_time_interval_watch_fine_tunes_once_an_hour_has_passed() {
+        // Mock stream stats and ai-jobd, reporting each tick and each submission
+        let (tick_tx, mut ticks) = tokio::sync::mpsc::unbounded_channel();
+        let (submit_tx, mut submitted) = tokio::sync::mpsc::unbounded_channel();
+        let app = Router::new()
+            .route("/stream/stats", get(move || {
+                let _ = tick_tx.send(());
+                async { Json(serde_json::json!({ "new_samples": 0 })) }
+            }))
+            .route("/job/train", post(move |Json(body): Json<serde_json::Value>| {
+                let _ = submit_tx.send(body);
+                async { Json(serde_json::json!({ "job_id": "job-ft-1" })) }
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let clock = ManualClock::new(1_700_000_000);
+        let state = Arc::new(AppState {
+            active_watches: Arc::new(RwLock::new(HashMap::new())),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            promotions: Arc::new(RwLock::new(HashMap::new())),
+            jobd_url: url.clone(),
+            scheduler_url: String::new(),
+            clock: clock.shared(),
+            entropy: SeededEntropy::shared(1),
+        });
+        let watch: WatchStreamRequest = serde_json::from_value(serde_json::json!({
+            "model_id": "resnet",
+            "dataset_cid": "bafy-stream",
+            "stream_path": url,
+            "min_samples": 100,
+            "fine_tune_trigger": "time_interval",
+        }))
+        .unwrap();
+        let watcher = tokio::spawn(watch_stream_loop(state.clone(), "watch-1".to_string(), watch));
+
+        // 59 minutes in: one tick, nothing triggered
+        clock.wait_for_sleepers(1).await;
+        clock.advance_secs(59 * 60);
+        ticks.recv().await.unwrap();
+        clock.wait_for_sleepers(1).await;
+        assert!(submitted.try_recv().is_err());
+        assert!(state.jobs.read().await.is_empty());
+
+        // The next tick reaches the hour and submits a fine-tune
+        clock.advance_secs(60);
+        ticks.recv().await.unwrap();
+        let body = submitted.recv().await.unwrap();
+        assert_eq!((body["model_id"].as_str(), body["dataset_id"].as_str()), (Some("resnet"), Some("bafy-stream")));
+        clock.wait_for_sleepers(1).await;
+        let job = state.jobs.read().await["job-ft-1"].clone();
+        assert_eq!(job.created_at, 1_700_000_000 + 3600);
+        assert_eq!(job.trigger_reason, "time_interval");
+
+        // The interval restarts from the trigger
+        clock.advance_secs(60);
+        ticks.recv().await.unwrap();
+        clock.wait_for_sleepers(1).await;
+        assert!(submitted.try_recv().is_err());
+        watcher.abort();
+    }
+}
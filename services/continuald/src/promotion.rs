@@ -0,0 +1,188 @@
+//! Canary Promotion
+//! Evaluates fine-tuned checkpoints against the serving model version on a
+//! held-out eval set and moves the serving alias only when the candidate does
+//! not regress. Every decision is kept in a per-watch promotion history.
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_alias() -> String {
+    "serving".to_string()
+}
+
+fn default_max_eval_cost() -> u64 {
+    50
+}
+
+/// Per-watch canary evaluation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalConfig {
+    pub eval_set_cid: String, // Held-out eval set
+    pub metric: String,       // "accuracy", "loss", ...
+    #[serde(default = "default_true")]
+    pub higher_is_better: bool,
+    #[serde(default)]
+    pub epsilon: f64, // Regression tolerated before a candidate is rejected
+    #[serde(default = "default_max_eval_cost")]
+    pub max_eval_cost: u64, // Spend cap per eval job, drawn from the watch budget
+    #[serde(default = "default_alias")]
+    pub alias: String, // Alias the serving version is published under
+    pub alert_webhook: Option<String>, // Notified of rejected promotions
+}
+
+impl EvalConfig {
+    /// Candidate scores within epsilon of the baseline, or better
+    pub fn non_regression(&self, candidate: f64, baseline: f64) -> bool {
+        if self.higher_is_better {
+            candidate >= baseline - self.epsilon
+        } else {
+            candidate <= baseline + self.epsilon
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionDecision {
+    Promoted,
+    Rejected,
+    ForcePromoted,
+    RolledBack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionEvent {
+    pub event_id: String,
+    pub candidate: String,        // Version the decision is about
+    pub baseline: Option<String>, // Serving version at decision time
+    pub candidate_score: Option<f64>,
+    pub baseline_score: Option<f64>,
+    pub metric: Option<String>,
+    pub decision: PromotionDecision,
+    pub reason: String,
+    pub fine_tune_job: Option<String>,
+    pub eval_job: Option<String>,
+    pub eval_cost: u64,
+    pub decided_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PromotionHistory {
+    pub serving: Option<String>, // As last set by this daemon
+    pub budget_spent: u64,       // Eval spend charged to the watch
+    pub events: Vec<PromotionEvent>,
+}
+
+impl PromotionHistory {
+    /// Whether `version` has been a candidate or the baseline of any decision
+    pub fn knows(&self, version: &str) -> bool {
+        self.events
+            .iter()
+            .any(|e| e.candidate == version || e.baseline.as_deref() == Some(version))
+    }
+
+    pub fn record(&mut self, event: PromotionEvent) {
+        if event.decision != PromotionDecision::Rejected {
+            self.serving = Some(event.candidate.clone());
+        }
+        self.budget_spent += event.eval_cost;
+        self.events.push(event);
+    }
+}
+
+/// Outcome of an eval job scoring a candidate against the baseline
+#[derive(Debug, Deserialize)]
+pub struct EvalResult {
+    pub job_id: String,
+    pub candidate_score: f64,
+    pub baseline_score: f64,
+    #[serde(default)]
+    pub spent: u64,
+}
+
+/// Run an eval job through ai-jobd and wait for its scores
+pub async fn run_eval(
+    jobd_url: &str,
+    model_id: &str,
+    candidate: &str,
+    baseline: &str,
+    config: &EvalConfig,
+    budget: u64,
+) -> Result<EvalResult, String> {
+    let payload = serde_json::json!({
+        "model_id": model_id,
+        "candidate": candidate,
+        "baseline": baseline,
+        "eval_set_cid": config.eval_set_cid,
+        "metric": config.metric,
+        "submitter_did": "system:continuald",
+        "budget": budget,
+    });
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/job/eval", jobd_url))
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        response.json().await.map_err(|e| e.to_string())
+    } else {
+        Err(format!("Eval job failed: {}", response.status()))
+    }
+}
+
+/// Current target of `name@alias`, or None if the alias is unset
+pub async fn get_alias(jobd_url: &str, name: &str, alias: &str) -> Result<Option<String>, String> {
+    let response = reqwest::Client::new()
+        .get(format!("{}/ai/model/{}/alias/{}", jobd_url, name, alias))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match response.status().as_u16() {
+        404 => Ok(None),
+        200..=299 => {
+            let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+            Ok(body["model_id"].as_str().map(|s| s.to_string()))
+        }
+        status => Err(format!("Alias lookup failed: {}", status)),
+    }
+}
+
+pub async fn set_alias(jobd_url: &str, name: &str, alias: &str, model_id: &str) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .put(format!("{}/ai/model/{}/alias/{}", jobd_url, name, alias))
+        .json(&serde_json::json!({ "model_id": model_id }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Alias update failed: {}", response.status()))
+    }
+}
+
+/// Best-effort alert for a rejected promotion
+pub async fn send_alert(webhook: &str, watch_id: &str, event: &PromotionEvent) {
+    let result = reqwest::Client::new()
+        .post(webhook)
+        .json(&serde_json::json!({
+            "type": "promotion_rejected",
+            "watch_id": watch_id,
+            "event": event,
+        }))
+        .send()
+        .await;
+
+    if let Err(e) = result {
+        warn!("⚠️  Promotion alert to {} failed: {}", webhook, e);
+    }
+}
@@ -1,20 +1,36 @@
 /// Policy Gate Service - DID/VC/ArthaScore enforcement
 /// Central policy enforcement for all SVDB and AI operations
 
+mod provenance;
+
 use axum::{
     extract::{Path, State, Json},
     http::StatusCode,
     routing::{get, post},
     Router,
 };
+use artha_errors::ServiceError;
+use provenance::ProvenanceDecision;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Minimum ArthaScore for the built-in policy; with a rulebase, the rulebase decides
+const MIN_ARTHA_SCORE: f64 = 0.5;
+
+/// Audit entries kept in memory, oldest dropped first
+const MAX_AUDIT_ENTRIES: usize = 10_000;
 
 #[derive(Debug, Deserialize)]
 pub struct PolicyCheckRequest {
     pub did: String,
     pub action: String, // "read", "write", "train", "infer"
     pub resource: String, // CID, dataset_id, model_id
+    #[serde(default)]
+    pub dataset_id: Option<String>, // Training data referenced by the submission
+    #[serde(default)]
+    pub consent_vcs: Vec<String>, // Consent and license VCs the dataset's manifest references
     pub budget: u64,
 }
 
@@ -24,17 +40,103 @@ pub struct PolicyCheckResponse {
     pub reason: Option<String>,
     pub required_claims: Vec<String>,
     pub artha_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ServiceError>, // Structured denial callers relay as-is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ProvenanceDecision>, // Set when the dataset's consent VCs were checked
+}
+
+impl PolicyCheckResponse {
+    fn deny(reason: &str, required_claims: Vec<String>, artha_score: Option<f64>) -> Self {
+        PolicyCheckResponse {
+            allowed: false,
+            reason: Some(reason.to_string()),
+            required_claims,
+            artha_score,
+            error: Some(ServiceError::policy_denied(reason)),
+            provenance: None,
+        }
+    }
+
+    fn allow(required_claims: Vec<String>, artha_score: f64) -> Self {
+        PolicyCheckResponse {
+            allowed: true,
+            reason: None,
+            required_claims,
+            artha_score: Some(artha_score),
+            error: None,
+            provenance: None,
+        }
+    }
+}
+
+/// One policy decision. Denials by the rulebase carry the proof tree of the
+/// `deny` goal that fired, so the decision can be explained after the fact.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub did: String,
+    pub action: String,
+    pub resource: String,
+    pub allowed: bool,
+    pub reason: Option<String>,
+    pub rulebase: Option<String>, // Set when the rulebase made the decision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<serde_json::Value>,
+    pub decided_at: u64,
 }
 
 pub struct AppState {
     did_registry_url: String,
     vc_registry_url: String,
+    jobd_url: String,
+    symbolic_url: String,
+    rulebase: Option<String>, // Delegate the final decision to this symbolic_ai rulebase
+    require_consent: bool, // Datasets without consent VCs can't be used
+    audit: RwLock<Vec<AuditEntry>>,
+}
+
+/// Outcome of evaluating the policy, before it is audited
+struct Decision {
+    response: PolicyCheckResponse,
+    rulebase: Option<String>,
+    proof: Option<serde_json::Value>,
+}
+
+impl From<PolicyCheckResponse> for Decision {
+    fn from(response: PolicyCheckResponse) -> Self {
+        Decision { response, rulebase: None, proof: None }
+    }
 }
 
 async fn check_policy(
     State(state): State<Arc<AppState>>,
     Json(req): Json<PolicyCheckRequest>,
 ) -> Result<Json<PolicyCheckResponse>, StatusCode> {
+    let decision = decide(&state, &req).await;
+
+    let mut audit = state.audit.write().await;
+    if audit.len() >= MAX_AUDIT_ENTRIES {
+        audit.remove(0);
+    }
+    audit.push(AuditEntry {
+        did: req.did,
+        action: req.action,
+        resource: req.resource,
+        allowed: decision.response.allowed,
+        reason: decision.response.reason.clone(),
+        rulebase: decision.rulebase,
+        proof: decision.proof.filter(|_| !decision.response.allowed),
+        decided_at: now(),
+    });
+    Ok(Json(decision.response))
+}
+
+/// GET /policy/audit - Recent decisions, oldest first
+async fn get_audit(State(state): State<Arc<AppState>>) -> Json<Vec<AuditEntry>> {
+    Json(state.audit.read().await.clone())
+}
+
+async fn decide(state: &AppState, req: &PolicyCheckRequest) -> Decision {
     let client = reqwest::Client::new();
     
     // 1. Verify DID exists
@@ -44,12 +146,7 @@ async fn check_policy(
         .await;
     
     if let Err(_) = did_check {
-        return Ok(Json(PolicyCheckResponse {
-            allowed: false,
-            reason: Some("DID not found".to_string()),
-            required_claims: vec![],
-            artha_score: None,
-        }));
+        return PolicyCheckResponse::deny("DID not found", vec![], None).into();
     }
     
     // 2. Check required VCs
@@ -67,53 +164,421 @@ async fn check_policy(
     
     // 3. Check ArthaScore
     // Query reputation service for ArthaScore
-    let artha_score = client
+    let artha_score = match client
         .get(&format!("{}/reputation/score/{}", state.did_registry_url, req.did))
         .send()
         .await
-        .ok()
-        .and_then(|resp| resp.json::<serde_json::Value>().ok())
-        .and_then(|json| json.get("score").and_then(|s| s.as_f64()))
-        .unwrap_or(0.0);
+    {
+        Ok(resp) => resp.json::<serde_json::Value>().await.ok(),
+        Err(_) => None,
+    }
+    .and_then(|json| json.get("score").and_then(|s| s.as_f64()))
+    .unwrap_or(0.0);
     
     // 4. Check budget
     if req.budget == 0 {
-        return Ok(Json(PolicyCheckResponse {
-            allowed: false,
-            reason: Some("Budget is zero".to_string()),
-            required_claims,
-            artha_score: None,
-        }));
+        return PolicyCheckResponse::deny("Budget is zero", required_claims, None).into();
     }
     
+    // 5. Marketplace datasets require a purchased access grant
+    if let Some(dataset_id) = &req.dataset_id {
+        if let Some(reason) = check_dataset_grant(&client, &state.jobd_url, dataset_id, &req.did, &req.action).await {
+            return PolicyCheckResponse::deny(&reason, required_claims, Some(artha_score)).into();
+        }
+    }
+
+    // 6. The data must have been collected with consent covering this use
+    let provenance = match &req.dataset_id {
+        Some(dataset_id) => {
+            provenance::verify(&client, &state.vc_registry_url, dataset_id, &req.action, &req.consent_vcs, state.require_consent, now()).await
+        }
+        None => None,
+    };
+    if let Some(failed) = provenance.as_ref().filter(|p| !p.verified) {
+        let reason = failed.reason.as_deref().unwrap_or("Dataset provenance not verified");
+        let mut response = PolicyCheckResponse::deny(reason, required_claims, Some(artha_score));
+        response.provenance = provenance;
+        return response.into();
+    }
+
+    let mut decision = decide_finally(state, &client, req, vc_check, required_claims, artha_score).await;
+    decision.response.provenance = provenance;
+    decision
+}
+
+/// The last word: the rulebase if one is configured, else the ArthaScore
+async fn decide_finally(
+    state: &AppState,
+    client: &reqwest::Client,
+    req: &PolicyCheckRequest,
+    vc_check: reqwest::Result<reqwest::Response>,
+    required_claims: Vec<String>,
+    artha_score: f64,
+) -> Decision {
+    // 7. A configured rulebase makes the final call
+    if let Some(rulebase) = &state.rulebase {
+        let held_claims = match vc_check {
+            Ok(resp) => held_claims(&resp.json().await.unwrap_or_default()),
+            Err(_) => Vec::new(),
+        };
+        let facts = context_facts(req, &held_claims, artha_score);
+        let mut decision: Decision = match query_rulebase(client, &state.symbolic_url, rulebase, req, facts).await {
+            Ok(None) => PolicyCheckResponse::allow(required_claims, artha_score).into(),
+            Ok(Some((reason, proof))) => Decision {
+                response: PolicyCheckResponse::deny(&reason, required_claims, Some(artha_score)),
+                rulebase: None,
+                proof: Some(proof),
+            },
+            // Fail closed, like the dataset grant check
+            Err(e) => {
+                warn!("⚠️  Rulebase {} evaluation failed: {}", rulebase, e);
+                PolicyCheckResponse::deny("Rulebase evaluation unavailable", required_claims, Some(artha_score)).into()
+            }
+        };
+        decision.rulebase = Some(rulebase.clone());
+        return decision;
+    }
+
     // Default: allow (if ArthaScore is sufficient)
-    let allowed = artha_score >= 0.5; // Minimum score threshold
-    
-    Ok(Json(PolicyCheckResponse {
-        allowed,
-        reason: if !allowed { Some("ArthaScore too low".to_string()) } else { None },
-        required_claims,
-        artha_score: Some(artha_score),
-    }))
+    if artha_score < MIN_ARTHA_SCORE {
+        return PolicyCheckResponse::deny("ArthaScore too low", required_claims, Some(artha_score)).into();
+    }
+
+    PolicyCheckResponse::allow(required_claims, artha_score).into()
+}
+
+/// Facts describing the request, for the rulebase to reason over:
+/// `request(Did, Action, Resource)`, `claim(Did, Claim)`,
+/// `artha_score_ok(Did)` and `dataset(Did, DatasetId)`
+fn context_facts(req: &PolicyCheckRequest, held_claims: &[String], artha_score: f64) -> Vec<serde_json::Value> {
+    let fact = |predicate: &str, args: &[&str]| serde_json::json!({ "predicate": predicate, "args": args });
+    let mut facts = vec![fact("request", &[&req.did, &req.action, &req.resource])];
+    facts.extend(held_claims.iter().map(|claim| fact("claim", &[&req.did, claim])));
+    if artha_score >= MIN_ARTHA_SCORE {
+        facts.push(fact("artha_score_ok", &[&req.did]));
+    }
+    if let Some(dataset_id) = &req.dataset_id {
+        facts.push(fact("dataset", &[&req.did, dataset_id]));
+    }
+    facts
+}
+
+/// Claim types in a VC registry listing: plain strings or objects with a `type`
+fn held_claims(listing: &serde_json::Value) -> Vec<String> {
+    listing
+        .as_array()
+        .map(|vcs| {
+            vcs.iter()
+                .filter_map(|vc| vc.as_str().or_else(|| vc["type"].as_str()))
+                .map(|claim| claim.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Ask the rulebase for `deny(Did, Action, Resource, Reason)`. A proof means
+/// a denial, returned with its reason and proof tree; no proof means allow.
+async fn query_rulebase(
+    client: &reqwest::Client,
+    symbolic_url: &str,
+    rulebase: &str,
+    req: &PolicyCheckRequest,
+    facts: Vec<serde_json::Value>,
+) -> Result<Option<(String, serde_json::Value)>, String> {
+    let quote = |value: &str| format!("\"{}\"", value.replace('"', "\\\""));
+    let goal = format!("deny({}, {}, {}, Reason)", quote(&req.did), quote(&req.action), quote(&req.resource));
+    let response = client
+        .post(format!("{}/symbolic/query", symbolic_url))
+        .json(&serde_json::json!({ "rulebase": rulebase, "goal": goal, "facts": facts, "limit": 1 }))
+        .send()
+        .await
+        .map_err(|e| format!("Query failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Query rejected: {}", response.status()));
+    }
+    let result: serde_json::Value = response.json().await.map_err(|e| format!("Bad query response: {}", e))?;
+
+    if let Some(solution) = result["solutions"].as_array().and_then(|s| s.first()) {
+        let reason = solution["bindings"]["Reason"].as_str().unwrap_or("denied");
+        return Ok(Some((format!("Denied by rulebase {}: {}", rulebase, reason), solution["proof"].clone())));
+    }
+    // A search cut short found no denial, but can't rule one out
+    let truncated = &result["truncated"];
+    if truncated["depth"].as_bool().unwrap_or(false) || truncated["time"].as_bool().unwrap_or(false) {
+        return Err("Query truncated before completing".to_string());
+    }
+    Ok(None)
+}
+
+/// Returns a denial reason if the dataset is listed on the marketplace and the
+/// DID holds no usable grant. Unlisted datasets are not restricted here.
+async fn check_dataset_grant(
+    client: &reqwest::Client,
+    jobd_url: &str,
+    dataset_id: &str,
+    did: &str,
+    action: &str,
+) -> Option<String> {
+    let response = client
+        .get(format!("{}/market/access/check", jobd_url))
+        .query(&[("dataset_id", dataset_id), ("did", did), ("job_type", action)])
+        .send()
+        .await;
+
+    let result: serde_json::Value = match response {
+        Ok(resp) if resp.status().is_success() => resp.json().await.ok()?,
+        // Fail closed: a listed dataset must not be usable without a verified grant
+        _ => return Some("Dataset access check unavailable".to_string()),
+    };
+
+    if result["allowed"].as_bool().unwrap_or(false) {
+        None
+    } else {
+        Some(result["reason"].as_str().unwrap_or("No access grant for dataset").to_string())
+    }
 }
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
         did_registry_url: std::env::var("DID_REGISTRY_URL")
             .unwrap_or_else(|_| "http://localhost:8080".to_string()),
         vc_registry_url: std::env::var("VC_REGISTRY_URL")
             .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        jobd_url: std::env::var("ARTHA_JOBD_URL")
+            .unwrap_or_else(|_| "http://localhost:8081".to_string()),
+        symbolic_url: std::env::var("ARTHA_SYMBOLIC_URL")
+            .unwrap_or_else(|_| "http://localhost:8091".to_string()),
+        rulebase: std::env::var("ARTHA_POLICY_RULEBASE").ok().filter(|name| !name.is_empty()),
+        require_consent: std::env::var("ARTHA_REQUIRE_DATASET_CONSENT").is_ok_and(|v| v == "true" || v == "1"),
+        audit: RwLock::new(Vec::new()),
     });
 
     let app = Router::new()
         .route("/policy/check", post(check_policy))
+        .route("/policy/audit", get(get_audit))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .layer(axum::middleware::map_response(artha_errors::normalize))
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🚀 Policy Gate Service starting on :8082");
+    info!("🚀 Policy Gate Service starting on :8082");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8082").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_denial_carries_structured_error() {
+        // Registry stub: every DID resolves and scores well
+        let registry = Router::new().fallback(|| async { Json(serde_json::json!({ "score": 0.9 })) });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let registry_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, registry).await.unwrap() });
+
+        let state = Arc::new(AppState {
+            did_registry_url: registry_url.clone(),
+            vc_registry_url: registry_url.clone(),
+            jobd_url: registry_url.clone(),
+            symbolic_url: registry_url,
+            rulebase: None,
+            require_consent: false,
+            audit: RwLock::new(Vec::new()),
+        });
+        let request = |budget| PolicyCheckRequest {
+            did: "did:artha:alice".to_string(),
+            action: "infer".to_string(),
+            resource: "llama-7b".to_string(),
+            dataset_id: None,
+            consent_vcs: vec![],
+            budget,
+        };
+
+        let Json(denied) = check_policy(State(state.clone()), Json(request(0))).await.unwrap();
+        assert!(!denied.allowed);
+        let error = denied.error.unwrap();
+        assert_eq!(error, ServiceError::policy_denied("Budget is zero"));
+        assert_eq!(error.code, 1003);
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+
+        let Json(allowed) = check_policy(State(state), Json(request(100))).await.unwrap();
+        assert!(allowed.allowed);
+        assert!(allowed.error.is_none());
+    }
+
+    async fn spawn(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// symbolic_ai stand-in for a rulebase with
+    /// `deny(D, A, R, low_score) :- request(D, A, R), not artha_score_ok(D)`
+    async fn query_stub(Json(query): Json<serde_json::Value>) -> Json<serde_json::Value> {
+        assert_eq!(query["rulebase"], "training-policy");
+        assert_eq!(query["goal"], "deny(\"did:artha:alice\", \"train\", \"fin-model\", Reason)");
+        let facts = query["facts"].as_array().unwrap();
+        let has = |predicate: &str| facts.iter().any(|f| f["predicate"] == predicate);
+        assert!(has("request") && has("claim"));
+
+        let solutions = if has("artha_score_ok") {
+            serde_json::json!([])
+        } else {
+            serde_json::json!([{
+                "bindings": { "Reason": "low_score" },
+                "proof": {
+                    "goal": "deny(did:artha:alice, train, fin-model, low_score)",
+                    "step": { "kind": "rule", "index": 0, "rule": "deny(D, A, R, low_score) :- request(D, A, R), not artha_score_ok(D)" },
+                    "premises": [
+                        { "goal": "request(did:artha:alice, train, fin-model)", "step": { "kind": "fact" } },
+                        { "goal": "not artha_score_ok(did:artha:alice)", "step": { "kind": "negation_as_failure" } },
+                    ],
+                },
+            }])
+        };
+        Json(serde_json::json!({ "solutions": solutions, "truncated": { "limit": false, "depth": false, "time": false } }))
+    }
+
+    #[tokio::test]
+    async fn test_rulebase_denial_records_proof_tree_in_audit() {
+        let symbolic_url = spawn(Router::new().route("/symbolic/query", post(query_stub))).await;
+        let state_for = |registry_url: String, symbolic_url: String| Arc::new(AppState {
+            did_registry_url: registry_url.clone(),
+            vc_registry_url: registry_url.clone(),
+            jobd_url: registry_url,
+            symbolic_url,
+            rulebase: Some("training-policy".to_string()),
+            require_consent: false,
+            audit: RwLock::new(Vec::new()),
+        });
+        let request = || PolicyCheckRequest {
+            did: "did:artha:alice".to_string(),
+            action: "train".to_string(),
+            resource: "fin-model".to_string(),
+            dataset_id: None,
+            consent_vcs: vec![],
+            budget: 100,
+        };
+
+        // Registry answers every lookup: the score and the VC listing
+        let low = spawn(Router::new().fallback(|| async { Json(serde_json::json!([{ "type": "vc:kyc" }])) })).await;
+        let state = state_for(low.clone(), symbolic_url.clone());
+        let Json(denied) = check_policy(State(state.clone()), Json(request())).await.unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.reason.as_deref(), Some("Denied by rulebase training-policy: low_score"));
+        assert_eq!(denied.error.unwrap().code, 1003);
+
+        let Json(audit) = get_audit(State(state)).await;
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].rulebase.as_deref(), Some("training-policy"));
+        let proof = audit[0].proof.as_ref().expect("denial carries its proof tree");
+        assert_eq!(proof["step"]["index"], 0);
+        assert_eq!(proof["premises"][1]["step"]["kind"], "negation_as_failure");
+
+        // No denial proven: allowed, audited without a proof
+        let high = spawn(Router::new()
+            .route("/vc/list/:did", get(|| async { Json(serde_json::json!(["vc:kyc"])) }))
+            .fallback(|| async { Json(serde_json::json!({ "score": 0.9 })) })).await;
+        let state = state_for(high, symbolic_url);
+        let Json(allowed) = check_policy(State(state.clone()), Json(request())).await.unwrap();
+        assert!(allowed.allowed);
+        let Json(audit) = get_audit(State(state)).await;
+        assert!(audit[0].allowed);
+        assert!(audit[0].proof.is_none());
+
+        // An unreachable rulebase fails closed
+        let state = state_for(low, "http://127.0.0.1:9".to_string());
+        let Json(unavailable) = check_policy(State(state), Json(request())).await.unwrap();
+        assert_eq!(unavailable.reason.as_deref(), Some("Rulebase evaluation unavailable"));
+    }
+
+    /// VC registry stand-in holding consent VCs for `dataset-1`
+    async fn consent_vc(Path(vc_hash): Path<String>) -> Result<Json<serde_json::Value>, StatusCode> {
+        let vc = |claim_type: &str, expires_at: u64, revoked: bool| serde_json::json!({
+            "vc_hash": vc_hash,
+            "issuer_did": "did:artha:collector",
+            "subject_did": "dataset-1",
+            "claim_type": claim_type,
+            "issued_at": 1_000,
+            "expires_at": expires_at,
+            "revoked": revoked,
+        });
+        match vc_hash.as_str() {
+            "0xconsent" => Ok(Json(vc("consent:train", 0, false))),
+            "0xlicense" => Ok(Json(vc("license:any", 4_000_000_000, false))),
+            "0xexpired" => Ok(Json(vc("consent:train", 1_000_000, false))),
+            "0xrevoked" => Ok(Json(vc("consent:train", 0, true))),
+            _ => Err(StatusCode::NOT_FOUND),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dataset_consent_vcs_gate_training() {
+        let registry = spawn(Router::new()
+            .route("/vc/:vc_hash", get(consent_vc))
+            .route("/market/access/check", get(|| async { Json(serde_json::json!({ "allowed": true })) }))
+            .fallback(|| async { Json(serde_json::json!({ "score": 0.9 })) })).await;
+        let state_for = |require_consent: bool| Arc::new(AppState {
+            did_registry_url: registry.clone(),
+            vc_registry_url: registry.clone(),
+            jobd_url: registry.clone(),
+            symbolic_url: registry.clone(),
+            rulebase: None,
+            require_consent,
+            audit: RwLock::new(Vec::new()),
+        });
+        let request = |action: &str, consent_vcs: &[&str]| PolicyCheckRequest {
+            did: "did:artha:alice".to_string(),
+            action: action.to_string(),
+            resource: "resnet".to_string(),
+            dataset_id: Some("dataset-1".to_string()),
+            consent_vcs: consent_vcs.iter().map(|vc| vc.to_string()).collect(),
+            budget: 100,
+        };
+        let state = state_for(false);
+        let check = |req: PolicyCheckRequest| check_policy(State(state.clone()), Json(req));
+
+        let Json(allowed) = check(request("train", &["0xconsent", "0xlicense"])).await.unwrap();
+        assert!(allowed.allowed);
+        let provenance = allowed.provenance.unwrap();
+        assert!(provenance.verified);
+        assert_eq!(provenance.credentials.iter().filter(|c| c.covers_use).count(), 2);
+
+        // Every referenced VC must hold up, not just one of them
+        let Json(denied) = check(request("train", &["0xconsent", "0xexpired"])).await.unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.reason.as_deref(), Some("Consent VC 0xexpired expired at 1000000"));
+        assert_eq!(denied.error.unwrap().code, 1003);
+        assert!(!denied.provenance.unwrap().verified);
+        for (vc, reason) in [
+            ("0xrevoked", "Consent VC 0xrevoked is revoked"),
+            ("0xmissing", "Consent VC 0xmissing is not in the VC registry"),
+        ] {
+            let Json(denied) = check(request("train", &[vc])).await.unwrap();
+            assert_eq!(denied.reason.as_deref(), Some(reason));
+        }
+        // Valid, but consent to training is not consent to everything
+        let Json(denied) = check(request("infer", &["0xconsent"])).await.unwrap();
+        assert_eq!(denied.reason.as_deref(), Some("No consent VC covers infer"));
+
+        // Datasets without VCs pass unless the deployment requires consent
+        let Json(unchecked) = check(request("train", &[])).await.unwrap();
+        assert!(unchecked.allowed && unchecked.provenance.is_none());
+        let Json(denied) = check_policy(State(state_for(true)), Json(request("train", &[]))).await.unwrap();
+        assert_eq!(denied.reason.as_deref(), Some("Dataset has no consent VCs"));
+    }
+}
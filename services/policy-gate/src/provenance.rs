@@ -0,0 +1,142 @@
+//! Dataset Provenance
+//! A dataset's manifest can reference the consent and license VCs its data
+//! was collected under. Before a job uses the dataset, each one is looked up
+//! in the VC registry. It must exist, be unrevoked and unexpired, and have
+//! the dataset as its subject. Together they must cover the intended use.
+//!
+//! Claim types name the use they grant: `consent:train`, `license:infer`,
+//! or `consent:any` for every use. Any other claim on the list fails the
+//! check, since a manifest that cites it is claiming something the credential
+//! doesn't say. A registry that can't be reached fails it too.
+
+use serde::{Deserialize, Serialize};
+
+/// The fields of a registry VC the check reads
+#[derive(Debug, Deserialize)]
+struct Credential {
+    subject_did: String,
+    claim_type: String,
+    #[serde(default)]
+    expires_at: u64, // 0: never
+    #[serde(default)]
+    revoked: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialCheck {
+    pub vc_hash: String,
+    pub claim_type: Option<String>, // None if the registry doesn't hold the VC
+    pub valid: bool,
+    pub covers_use: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+}
+
+/// The verdict on a dataset's consent VCs, recorded on the job it admits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceDecision {
+    pub dataset_id: String,
+    pub intended_use: String,
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>, // Why it failed
+    pub credentials: Vec<CredentialCheck>,
+    pub checked_at: u64,
+}
+
+/// Check the VCs `vc_hashes` for using `dataset_id` for `intended_use`.
+/// Datasets that reference no VCs pass unchecked (None) unless `required`.
+pub async fn verify(
+    client: &reqwest::Client,
+    vc_registry_url: &str,
+    dataset_id: &str,
+    intended_use: &str,
+    vc_hashes: &[String],
+    required: bool,
+    now: u64,
+) -> Option<ProvenanceDecision> {
+    if vc_hashes.is_empty() && !required {
+        return None;
+    }
+    let mut credentials = Vec::new();
+    for vc_hash in vc_hashes {
+        let credential = fetch(client, vc_registry_url, vc_hash).await;
+        credentials.push(check(vc_hash, credential, dataset_id, intended_use, now));
+    }
+    let reason = if vc_hashes.is_empty() {
+        Some("Dataset has no consent VCs".to_string())
+    } else if let Some(failed) = credentials.iter().find(|c| !c.valid) {
+        Some(format!("Consent VC {} {}", failed.vc_hash, failed.problem.as_deref().unwrap_or("is invalid")))
+    } else if !credentials.iter().any(|c| c.covers_use) {
+        Some(format!("No consent VC covers {}", intended_use))
+    } else {
+        None
+    };
+    Some(ProvenanceDecision {
+        dataset_id: dataset_id.to_string(),
+        intended_use: intended_use.to_string(),
+        verified: reason.is_none(),
+        reason,
+        credentials,
+        checked_at: now,
+    })
+}
+
+async fn fetch(client: &reqwest::Client, vc_registry_url: &str, vc_hash: &str) -> Result<Option<Credential>, String> {
+    let response = client
+        .get(format!("{}/vc/{}", vc_registry_url, vc_hash))
+        .send()
+        .await
+        .map_err(|e| format!("VC registry unavailable: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("VC registry answered {}", response.status()));
+    }
+    response.json().await.map(Some).map_err(|e| format!("Unreadable VC: {}", e))
+}
+
+fn check(
+    vc_hash: &str,
+    credential: Result<Option<Credential>, String>,
+    dataset_id: &str,
+    intended_use: &str,
+    now: u64,
+) -> CredentialCheck {
+    let failed = |claim_type: Option<&str>, problem: String| CredentialCheck {
+        vc_hash: vc_hash.to_string(),
+        claim_type: claim_type.map(str::to_string),
+        valid: false,
+        covers_use: false,
+        problem: Some(problem),
+    };
+    let vc = match credential {
+        Ok(Some(vc)) => vc,
+        Ok(None) => return failed(None, "is not in the VC registry".to_string()),
+        Err(e) => return failed(None, format!("could not be checked: {}", e)),
+    };
+    let claim = Some(vc.claim_type.as_str());
+    let grants = vc.claim_type.strip_prefix("consent:").or_else(|| vc.claim_type.strip_prefix("license:"));
+    let problem = if vc.revoked {
+        Some("is revoked".to_string())
+    } else if vc.expires_at > 0 && vc.expires_at <= now {
+        Some(format!("expired at {}", vc.expires_at))
+    } else if vc.subject_did != dataset_id {
+        Some(format!("was issued for {}, not this dataset", vc.subject_did))
+    } else if grants.is_none() {
+        Some(format!("is a {} credential, not consent or license", vc.claim_type))
+    } else {
+        None
+    };
+    if let Some(problem) = problem {
+        return failed(claim, problem);
+    }
+    CredentialCheck {
+        vc_hash: vc_hash.to_string(),
+        claim_type: claim.map(str::to_string),
+        valid: true,
+        covers_use: grants.is_some_and(|granted| granted == intended_use || granted == "any"),
+        problem: None,
+    }
+}
@@ -0,0 +1,421 @@
+//! Confidential Settlement
+//! Payout amounts hidden in Pedersen commitments with bit-decomposition range
+//! proofs. Each payout's opening (amount + blinding factor) is encrypted to the
+//! view keys of the parties to the job, who can later hand auditors a
+//! disclosure package for specific jobs without granting ongoing visibility.
+//!
+//! The group is Ristretto255, the one the node's privacy module builds its
+//! confidential transactions on. Points travel as 32-byte compressed hex and
+//! scalars as 32-byte canonical little-endian hex.
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT as G;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+
+/// Amounts must fit in this many bits to be settled confidentially
+pub const RANGE_BITS: usize = 48;
+
+const OPENING_LEN: usize = 8 + 32; // amount || blinding
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    Scalar::from_hash(hasher)
+}
+
+/// Blinding generator. Hashed to the group so nobody knows its discrete log
+/// relative to `G`.
+fn h() -> RistrettoPoint {
+    RistrettoPoint::hash_from_bytes::<Sha512>(b"ARTHA_PEDERSEN_H")
+}
+
+pub fn random_scalar() -> Scalar {
+    Scalar::random(&mut OsRng)
+}
+
+fn bytes32(hex_str: &str) -> Option<[u8; 32]> {
+    hex::decode(hex_str).ok()?.try_into().ok()
+}
+
+pub fn encode_point(point: &RistrettoPoint) -> String {
+    hex::encode(point.compress().as_bytes())
+}
+
+pub fn decode_point(hex_str: &str) -> Option<RistrettoPoint> {
+    CompressedRistretto(bytes32(hex_str)?).decompress()
+}
+
+pub fn encode_scalar(scalar: &Scalar) -> String {
+    hex::encode(scalar.as_bytes())
+}
+
+/// A scalar in canonical form; anything else is rejected rather than reduced
+pub fn decode_scalar(hex_str: &str) -> Option<Scalar> {
+    Scalar::from_canonical_bytes(bytes32(hex_str)?).into()
+}
+
+/// Pedersen commitment amount·G + blinding·H
+pub fn commit(amount: u64, blinding: &Scalar) -> RistrettoPoint {
+    G * Scalar::from(amount) + h() * blinding
+}
+
+/// Whether (amount, blinding) opens `commitment`
+pub fn opens(commitment: &str, amount: u64, blinding: &str) -> bool {
+    match (decode_point(commitment), decode_scalar(blinding)) {
+        (Some(commitment), Some(blinding)) => commit(amount, &blinding) == commitment,
+        _ => false,
+    }
+}
+
+/// Proof that a bit commitment C_i opens to 0 or 1 (Chaum–Pedersen OR proof
+/// of knowledge of log_H(C_i) or log_H(C_i − G))
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BitProof {
+    pub commitment: String,
+    pub a0: String,
+    pub a1: String,
+    pub e0: String,
+    pub e1: String,
+    pub s0: String,
+    pub s1: String,
+}
+
+/// Range proof that a commitment hides a value in [0, 2^RANGE_BITS)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RangeProof {
+    pub bits: Vec<BitProof>,
+}
+
+fn bit_challenge(commitment: &RistrettoPoint, a0: &RistrettoPoint, a1: &RistrettoPoint) -> Scalar {
+    hash_to_scalar(&[
+        b"ARTHA_RANGE_BIT",
+        commitment.compress().as_bytes(),
+        a0.compress().as_bytes(),
+        a1.compress().as_bytes(),
+    ])
+}
+
+fn prove_bit(bit: u64, blinding: &Scalar) -> BitProof {
+    let h = h();
+    let commitment = commit(bit, blinding);
+    // Statement j: commitment − j·G = blinding·H
+    let y = [commitment, commitment - G];
+    let real = bit as usize;
+    let fake = 1 - real;
+
+    let mut a = [RistrettoPoint::identity(); 2];
+    let mut e = [Scalar::ZERO; 2];
+    let mut s = [Scalar::ZERO; 2];
+
+    // Simulate the branch we cannot prove
+    e[fake] = random_scalar();
+    s[fake] = random_scalar();
+    a[fake] = h * s[fake] - y[fake] * e[fake];
+
+    // Prove the real branch
+    let k = random_scalar();
+    a[real] = h * k;
+    let challenge = bit_challenge(&commitment, &a[0], &a[1]);
+    e[real] = challenge - e[fake];
+    s[real] = k + e[real] * blinding;
+
+    BitProof {
+        commitment: encode_point(&commitment),
+        a0: encode_point(&a[0]),
+        a1: encode_point(&a[1]),
+        e0: encode_scalar(&e[0]),
+        e1: encode_scalar(&e[1]),
+        s0: encode_scalar(&s[0]),
+        s1: encode_scalar(&s[1]),
+    }
+}
+
+/// The bit's commitment, if the proof holds
+fn verify_bit(proof: &BitProof) -> Option<RistrettoPoint> {
+    let h = h();
+    let commitment = decode_point(&proof.commitment)?;
+    let a = [decode_point(&proof.a0)?, decode_point(&proof.a1)?];
+    let e = [decode_scalar(&proof.e0)?, decode_scalar(&proof.e1)?];
+    let s = [decode_scalar(&proof.s0)?, decode_scalar(&proof.s1)?];
+    let y = [commitment, commitment - G];
+    if e[0] + e[1] != bit_challenge(&commitment, &a[0], &a[1]) {
+        return None;
+    }
+    (0..2).all(|j| h * s[j] == a[j] + y[j] * e[j]).then_some(commitment)
+}
+
+/// Commit to `amount` and prove it is in range. Returns the commitment, its
+/// blinding factor, and the range proof.
+pub fn commit_with_range_proof(amount: u64) -> Result<(String, Scalar, RangeProof), String> {
+    if amount >> RANGE_BITS != 0 {
+        return Err(format!("Amount {} exceeds the {}-bit confidential range", amount, RANGE_BITS));
+    }
+
+    let mut blinding = Scalar::ZERO;
+    let mut bits = Vec::with_capacity(RANGE_BITS);
+    for i in 0..RANGE_BITS {
+        let bit_blinding = random_scalar();
+        // Bit blindings weighted by 2^i sum to the overall blinding factor
+        blinding += bit_blinding * Scalar::from(1u64 << i);
+        bits.push(prove_bit((amount >> i) & 1, &bit_blinding));
+    }
+
+    Ok((encode_point(&commit(amount, &blinding)), blinding, RangeProof { bits }))
+}
+
+pub fn verify_range_proof(commitment: &str, proof: &RangeProof) -> bool {
+    if proof.bits.len() != RANGE_BITS {
+        return false;
+    }
+    let (Some(commitment), Some(bits)) = (decode_point(commitment), proof.bits.iter().map(verify_bit).collect::<Option<Vec<_>>>()) else {
+        return false;
+    };
+    // Σ 2^i·C_i must recombine to the payout commitment
+    let recombined = bits
+        .iter()
+        .enumerate()
+        .fold(RistrettoPoint::identity(), |acc, (i, bit)| acc + bit * Scalar::from(1u64 << i));
+    recombined == commitment
+}
+
+/// Public view key for a view secret
+pub fn view_public_key(secret: &Scalar) -> String {
+    encode_point(&(G * secret))
+}
+
+/// Whether `key` is a usable view public key (a non-identity group element)
+pub fn is_valid_view_key(key: &str) -> bool {
+    decode_point(key).is_some_and(|point| point != RistrettoPoint::identity())
+}
+
+/// A payout opening encrypted to one party's view key (hashed ElGamal)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EncryptedOpening {
+    pub recipient_did: String,
+    pub ephemeral: String,
+    pub ciphertext: String, // hex(amount || blinding) xor keystream
+}
+
+fn keystream(shared: &RistrettoPoint, context: &str) -> [u8; OPENING_LEN] {
+    let mut hasher = Sha512::new();
+    hasher.update(b"ARTHA_VIEW_KEY_STREAM");
+    hasher.update(shared.compress().as_bytes());
+    hasher.update(context.as_bytes());
+    let digest = hasher.finalize();
+    digest[..OPENING_LEN].try_into().unwrap()
+}
+
+fn xor(bytes: &mut [u8; OPENING_LEN], stream: &[u8; OPENING_LEN]) {
+    for (byte, key) in bytes.iter_mut().zip(stream) {
+        *byte ^= key;
+    }
+}
+
+/// Encrypt an opening to `recipient_public`. `context` binds the ciphertext
+/// to one payout (its receipt id).
+pub fn encrypt_opening(
+    recipient_did: &str,
+    recipient_public: &str,
+    context: &str,
+    amount: u64,
+    blinding: &Scalar,
+) -> Result<EncryptedOpening, String> {
+    let recipient = decode_point(recipient_public).ok_or_else(|| format!("Invalid view key for {}", recipient_did))?;
+    let k = random_scalar();
+    let mut sealed = [0u8; OPENING_LEN];
+    sealed[..8].copy_from_slice(&amount.to_be_bytes());
+    sealed[8..].copy_from_slice(blinding.as_bytes());
+    xor(&mut sealed, &keystream(&(recipient * k), context));
+    Ok(EncryptedOpening {
+        recipient_did: recipient_did.to_string(),
+        ephemeral: encode_point(&(G * k)),
+        ciphertext: hex::encode(sealed),
+    })
+}
+
+/// Decrypt an opening with a view secret. A wrong key yields garbage, which
+/// callers detect by checking the result against the commitment.
+pub fn decrypt_opening(opening: &EncryptedOpening, secret: &Scalar, context: &str) -> Option<(u64, String)> {
+    let mut sealed: [u8; OPENING_LEN] = hex::decode(&opening.ciphertext).ok()?.try_into().ok()?;
+    let ephemeral = decode_point(&opening.ephemeral)?;
+    xor(&mut sealed, &keystream(&(ephemeral * secret), context));
+    Some((
+        u64::from_be_bytes(sealed[..8].try_into().unwrap()),
+        hex::encode(&sealed[8..]),
+    ))
+}
+
+/// What a confidential payout publishes on chain: the commitment, its range
+/// proof and the encrypted openings. No plaintext amount.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidentialPayout {
+    pub receipt_id: String,
+    pub job_id: String,
+    pub settlement_tx: String,
+    pub commitment: String,
+    pub range_proof: RangeProof,
+    pub openings: Vec<EncryptedOpening>,
+    pub settled_at: u64,
+}
+
+impl ConfidentialPayout {
+    /// Build a payout for `amount`, encrypting the opening to every party in
+    /// `recipients` (DID, view public key)
+    pub fn issue(
+        receipt_id: &str,
+        job_id: &str,
+        amount: u64,
+        recipients: &[(String, String)],
+    ) -> Result<(Self, Scalar), String> {
+        let (commitment, blinding, range_proof) = commit_with_range_proof(amount)?;
+        let openings = recipients
+            .iter()
+            .map(|(did, public)| encrypt_opening(did, public, receipt_id, amount, &blinding))
+            .collect::<Result<_, _>>()?;
+        Ok((
+            ConfidentialPayout {
+                receipt_id: receipt_id.to_string(),
+                job_id: job_id.to_string(),
+                settlement_tx: String::new(),
+                commitment,
+                range_proof,
+                openings,
+                settled_at: 0,
+            },
+            blinding,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DisclosureEntry {
+    pub receipt_id: String,
+    pub job_id: String,
+    pub settlement_tx: String,
+    pub commitment: String,
+    pub amount: u64,
+    pub blinding: String,
+    pub settled_at: u64,
+}
+
+/// Openings a party chose to reveal, checkable against chain data
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DisclosurePackage {
+    pub discloser_did: String,
+    pub entries: Vec<DisclosureEntry>,
+    pub issued_at: u64,
+}
+
+/// Which payouts to disclose: one job, a settlement date range, or both
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DisclosureScope {
+    pub job_id: Option<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl DisclosureScope {
+    fn includes(&self, payout: &ConfidentialPayout) -> bool {
+        self.job_id.as_ref().is_none_or(|job_id| &payout.job_id == job_id)
+            && self.from.is_none_or(|from| payout.settled_at >= from)
+            && self.to.is_none_or(|to| payout.settled_at <= to)
+    }
+}
+
+/// Decrypt the in-scope payouts addressed to `did` with its view secret
+pub fn build_disclosure<'a>(
+    payouts: impl IntoIterator<Item = &'a ConfidentialPayout>,
+    did: &str,
+    view_secret: &Scalar,
+    scope: &DisclosureScope,
+    now: u64,
+) -> DisclosurePackage {
+    let mut entries: Vec<DisclosureEntry> = payouts
+        .into_iter()
+        .filter(|payout| scope.includes(payout))
+        .filter_map(|payout| {
+            let opening = payout.openings.iter().find(|o| o.recipient_did == did)?;
+            let (amount, blinding) = decrypt_opening(opening, view_secret, &payout.receipt_id)?;
+            opens(&payout.commitment, amount, &blinding).then(|| DisclosureEntry {
+                receipt_id: payout.receipt_id.clone(),
+                job_id: payout.job_id.clone(),
+                settlement_tx: payout.settlement_tx.clone(),
+                commitment: payout.commitment.clone(),
+                amount,
+                blinding,
+                settled_at: payout.settled_at,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| (a.settled_at, &a.receipt_id).cmp(&(b.settled_at, &b.receipt_id)));
+
+    DisclosurePackage {
+        discloser_did: did.to_string(),
+        entries,
+        issued_at: now,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EntryVerdict {
+    pub receipt_id: String,
+    pub valid: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DisclosureVerdict {
+    pub valid: bool,
+    pub total_amount: Option<u64>, // Sum of disclosed amounts, when every entry verifies
+    pub entries: Vec<EntryVerdict>,
+}
+
+/// Check every disclosed opening against the payout published on chain under
+/// its settlement tx. Needs nothing but the package and public chain data.
+pub fn verify_disclosure(
+    package: &DisclosurePackage,
+    chain: &HashMap<String, ConfidentialPayout>,
+) -> DisclosureVerdict {
+    let entries: Vec<EntryVerdict> = package
+        .entries
+        .iter()
+        .map(|entry| {
+            let reason = match chain.get(&entry.settlement_tx) {
+                None => Some("settlement tx not found on chain"),
+                Some(payout) if payout.receipt_id != entry.receipt_id || payout.job_id != entry.job_id => {
+                    Some("entry does not match the on-chain payout")
+                }
+                Some(payout) if payout.commitment != entry.commitment => {
+                    Some("commitment differs from chain")
+                }
+                Some(payout) if !verify_range_proof(&payout.commitment, &payout.range_proof) => {
+                    Some("on-chain range proof is invalid")
+                }
+                Some(_) if !opens(&entry.commitment, entry.amount, &entry.blinding) => {
+                    Some("amount and blinding do not open the commitment")
+                }
+                Some(_) => None,
+            };
+            EntryVerdict {
+                receipt_id: entry.receipt_id.clone(),
+                valid: reason.is_none(),
+                reason: reason.map(|r| r.to_string()),
+            }
+        })
+        .collect();
+
+    let valid = !entries.is_empty() && entries.iter().all(|e| e.valid);
+    DisclosureVerdict {
+        valid,
+        total_amount: valid.then(|| package.entries.iter().map(|e| e.amount).sum()),
+        entries,
+    }
+}
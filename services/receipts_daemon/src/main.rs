@@ -1,17 +1,30 @@
+use tracing::{info, warn};
+
 /// Receipts/Settlement Daemon
 /// Monitors compute/storage proofs and handles automatic payouts via DealMarket
 
+mod confidential;
+mod store;
+
 use axum::{
     extract::{Path, Query, State, Json},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     routing::{get, post},
     Router,
 };
+use abi::{abi_encode_bytes32, abi_encode_call, abi_encode_uint256};
+use artha_clock::{Backoff, BackoffPolicy, Clock, Entropy, SharedClock, SharedEntropy};
+use artha_paging::{time_key, Page, PageQuery};
+use artha_retention::RetentionPolicy;
+use artha_tenant::Namespace;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::Duration;
+use confidential::{ConfidentialPayout, DisclosurePackage, DisclosureScope, DisclosureVerdict};
+use curve25519_dalek::scalar::Scalar;
+use store::{Earnings, ReceiptStore};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Receipt {
@@ -25,6 +38,63 @@ pub struct Receipt {
     pub created_at: u64,
     pub settled_at: Option<u64>,
     pub tx_hash: Option<String>,
+    #[serde(default)]
+    pub platform_fee_wei: u64,
+    #[serde(default)]
+    pub finalize_tx: Option<String>, // On-chain tx that finalized the job or proof
+    #[serde(default)]
+    pub submitter: Option<String>, // DID that funded the job, if known
+    #[serde(default)]
+    pub confidential: bool, // Settled as a commitment; amount_wei is not published
+    #[serde(default)]
+    pub dispute: Option<ReceiptDispute>, // Set while the receipt is held from settlement
+    #[serde(default)]
+    pub milestone: Option<usize>, // Escrow milestone this receipt releases
+    #[serde(default)]
+    pub grant_id: Option<String>, // Dataset access grant whose escrow pays this receipt
+}
+
+/// What retention GC keeps of a settled receipt: its on-chain settlement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedReceipt {
+    pub receipt_id: String,
+    pub job_id: String,
+    pub provider: String,
+    #[serde(default)]
+    pub receipt_type: Option<ReceiptType>,
+    pub status: ReceiptStatus,
+    pub amount_wei: Option<u64>, // None for confidential receipts
+    pub tx_hash: Option<String>,
+    pub finalize_tx: Option<String>,
+    #[serde(default)]
+    pub milestone: Option<usize>,
+    pub settled_at: u64,
+    pub archived_at: u64,
+}
+
+impl ArchivedReceipt {
+    pub fn of(receipt: &Receipt, archived_at: u64) -> Self {
+        ArchivedReceipt {
+            receipt_id: receipt.receipt_id.clone(),
+            job_id: receipt.job_id.clone(),
+            provider: receipt.provider.clone(),
+            receipt_type: Some(receipt.receipt_type.clone()),
+            status: receipt.status.clone(),
+            amount_wei: (!receipt.confidential).then_some(receipt.amount_wei),
+            tx_hash: receipt.tx_hash.clone(),
+            finalize_tx: receipt.finalize_tx.clone(),
+            milestone: receipt.milestone,
+            settled_at: receipt.settled_at.unwrap_or(receipt.created_at),
+            archived_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptDispute {
+    pub disputer: String,
+    pub reason: String,
+    pub disputed_at: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +102,8 @@ pub enum ReceiptType {
     Storage,
     Compute,
     Retrieval,
+    DatasetUsage,
+    InferenceUsage,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,12 +112,36 @@ pub enum ReceiptStatus {
     Approved,
     Settled,
     Failed,
+    Disputed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettlementMode {
+    Transparent,
+    Confidential,
+}
+
+impl SettlementMode {
+    fn from_env() -> Self {
+        match std::env::var("ARTHA_SETTLEMENT_MODE").as_deref() {
+            Ok("confidential") => SettlementMode::Confidential,
+            _ => SettlementMode::Transparent,
+        }
+    }
 }
 
 pub struct AppState {
-    receipts: Arc<RwLock<HashMap<String, Receipt>>>,
+    receipts: Arc<RwLock<ReceiptStore>>, // Live and archived receipts, persisted
     deal_market_addr: String,
     rpc_url: String,
+    node_api_url: String,
+    require_finality: bool, // Auto-settle only once the finalize tx is in a finalized block
+    settlement_mode: SettlementMode,
+    view_keys: Arc<RwLock<HashMap<String, String>>>, // DID -> view public key, a compressed Ristretto point
+    confidential_payouts: Arc<RwLock<HashMap<String, ConfidentialPayout>>>, // settlement tx -> published payout
+    retention: RetentionPolicy,
+    clock: SharedClock,
+    entropy: SharedEntropy,
 }
 
 async fn monitor_proofs(State(state): State<Arc<AppState>>) -> Result<Json<serde_json::Value>, StatusCode> {
@@ -67,7 +163,7 @@ async fn monitor_proofs(State(state): State<Arc<AppState>>) -> Result<Json<serde
         // Create receipts for each proof
         let mut receipts_created = 0;
         for proof in proofs {
-            let receipt_id = format!("receipt-{}", uuid::Uuid::new_v4());
+            let receipt_id = format!("receipt-{}", state.entropy.uuid());
             let receipt = Receipt {
                 receipt_id: receipt_id.clone(),
                 job_id: proof["job_id"].as_str().unwrap_or("unknown").to_string(),
@@ -80,15 +176,19 @@ async fn monitor_proofs(State(state): State<Arc<AppState>>) -> Result<Json<serde
                 amount_wei: proof["amount"].as_u64().unwrap_or(0),
                 status: ReceiptStatus::Pending,
                 proof_cid: proof["proof_cid"].as_str().map(|s| s.to_string()),
-                created_at: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
+                created_at: state.clock.now_secs(),
                 settled_at: None,
                 tx_hash: None,
+                platform_fee_wei: 0,
+                finalize_tx: proof["finalize_tx"].as_str().map(|s| s.to_string()),
+                submitter: proof["submitter"].as_str().map(|s| s.to_string()),
+                confidential: false,
+                dispute: None,
+                milestone: None,
+                grant_id: None,
             };
             
-            state.receipts.write().await.insert(receipt_id, receipt);
+            state.receipts.write().await.insert(receipt);
             receipts_created += 1;
         }
         
@@ -108,9 +208,27 @@ async fn settle_receipt(
     State(state): State<Arc<AppState>>,
     Path(receipt_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let receipts = state.receipts.read().await;
-    let receipt = receipts.get(&receipt_id)
+    // Not held across the payout, which waits on the chain
+    let receipt = state.receipts.read().await.get(&receipt_id).cloned()
         .ok_or(StatusCode::NOT_FOUND)?;
+    if matches!(receipt.status, ReceiptStatus::Disputed | ReceiptStatus::Settled) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    // Confidential payouts need at least one party able to open them later
+    let recipients = if state.settlement_mode == SettlementMode::Confidential {
+        let view_keys = state.view_keys.read().await;
+        let recipients: Vec<(String, String)> = std::iter::once(&receipt.provider)
+            .chain(receipt.submitter.as_ref())
+            .filter_map(|did| view_keys.get(did).map(|key| (did.clone(), key.clone())))
+            .collect();
+        if recipients.is_empty() {
+            return Err(StatusCode::PRECONDITION_FAILED);
+        }
+        Some(recipients)
+    } else {
+        None
+    };
     
     // Call DealMarket.computePayout() or storagePayout()
     let tx_hash = match receipt.receipt_type {
@@ -118,41 +236,79 @@ async fn settle_receipt(
             settle_compute_payout(
                 &state.deal_market_addr,
                 &state.rpc_url,
-                receipt,
+                &receipt,
+                &*state.entropy,
             ).await
         }
         ReceiptType::Storage => {
             settle_storage_payout(
                 &state.deal_market_addr,
                 &state.rpc_url,
-                receipt,
+                &receipt,
+                &*state.entropy,
             ).await
         }
         ReceiptType::Retrieval => {
             settle_retrieval_payout(
                 &state.deal_market_addr,
                 &state.rpc_url,
-                receipt,
+                &receipt,
+                &*state.entropy,
+            ).await
+        }
+        ReceiptType::DatasetUsage => {
+            settle_dataset_payout(
+                &state.deal_market_addr,
+                &state.rpc_url,
+                &receipt,
+                &*state.clock,
+            ).await
+        }
+        ReceiptType::InferenceUsage => {
+            settle_compute_payout(
+                &state.deal_market_addr,
+                &state.rpc_url,
+                &receipt,
+                &*state.entropy,
             ).await
         }
     };
+    // A payout that didn't go through leaves the receipt for the next sweep
+    let tx_hash = tx_hash.map_err(|e| {
+        warn!("⚠️  Settling {} failed: {}", receipt_id, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    
+    let settled_at = state.clock.now_secs();
+
+    // Publish the commitment and encrypted openings instead of the amount
+    if let Some(recipients) = &recipients {
+        let (mut payout, _) = ConfidentialPayout::issue(
+            &receipt.receipt_id,
+            &receipt.job_id,
+            receipt.amount_wei,
+            recipients,
+        ).map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+        payout.settlement_tx = tx_hash.clone();
+        payout.settled_at = settled_at;
+        info!("🔒 Confidential payout for {} disclosed to {} parties", receipt_id, recipients.len());
+        state.confidential_payouts.write().await.insert(tx_hash.clone(), payout);
+    }
     
     // Update receipt status
-    drop(receipts);
-    let mut receipts = state.receipts.write().await;
-    if let Some(receipt) = receipts.get_mut(&receipt_id) {
+    let confidential = recipients.is_some();
+    let tx_hash = state.receipts.write().await.update(&receipt_id, |receipt| {
         receipt.status = ReceiptStatus::Settled;
-        receipt.settled_at = Some(SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs());
-        receipt.tx_hash = tx_hash.ok();
-    }
+        receipt.settled_at = Some(settled_at);
+        receipt.confidential = confidential;
+        receipt.tx_hash = Some(tx_hash);
+        receipt.tx_hash.clone()
+    }).flatten();
     
     Ok(Json(serde_json::json!({
         "receipt_id": receipt_id,
         "status": "settled",
-        "tx_hash": receipts.get(&receipt_id).and_then(|r| r.tx_hash.clone())
+        "tx_hash": tx_hash
     })))
 }
 
@@ -160,72 +316,355 @@ async fn settle_compute_payout(
     deal_market: &str,
     rpc_url: &str,
     receipt: &Receipt,
+    entropy: &dyn Entropy,
 ) -> Result<String, String> {
     // In production: Call DealMarket.computePayout() via JSON-RPC
     // For now: Return mock tx hash
-    Ok(format!("0x{:064x}", uuid::Uuid::new_v4().as_u128()))
+    Ok(format!("0x{:064x}", entropy.next_u128()))
 }
 
 async fn settle_storage_payout(
     deal_market: &str,
     rpc_url: &str,
     receipt: &Receipt,
+    entropy: &dyn Entropy,
 ) -> Result<String, String> {
     // In production: Call DealMarket.storagePayout() via JSON-RPC
-    Ok(format!("0x{:064x}", uuid::Uuid::new_v4().as_u128()))
+    Ok(format!("0x{:064x}", entropy.next_u128()))
 }
 
 async fn settle_retrieval_payout(
     deal_market: &str,
     rpc_url: &str,
     receipt: &Receipt,
+    entropy: &dyn Entropy,
 ) -> Result<String, String> {
     // In production: Call DealMarket.retrievalPayout() via JSON-RPC
-    Ok(format!("0x{:064x}", uuid::Uuid::new_v4().as_u128()))
+    Ok(format!("0x{:064x}", entropy.next_u128()))
+}
+
+/// Release a dataset receipt's payout from its grant's DealMarket escrow:
+/// the owner share to the dataset owner's account, the platform fee to the
+/// treasury's
+async fn settle_dataset_payout(
+    deal_market: &str,
+    rpc_url: &str,
+    receipt: &Receipt,
+    clock: &dyn Clock,
+) -> Result<String, String> {
+    let grant_id = receipt.grant_id.as_deref().ok_or_else(|| format!("Receipt {} names no grant", receipt.receipt_id))?;
+    let data = abi_encode_call(
+        "releaseDatasetEscrow(bytes32,bytes32,uint256,uint256)",
+        &[
+            abi_encode_bytes32(&escrow_key(grant_id)),
+            abi_encode_bytes32(&escrow_key(&receipt.provider)),
+            abi_encode_uint256(receipt.amount_wei),
+            abi_encode_uint256(receipt.platform_fee_wei),
+        ],
+    )?;
+    send_confirmed(rpc_url, deal_market, &data, clock).await
+}
+
+/// Key DealMarket holds a grant's escrow or a DID's account under, as ai-jobd derives it
+fn escrow_key(id: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("0x{:x}", Sha256::digest(id.as_bytes()))
+}
+
+/// How long `send_confirmed` waits for a receipt
+const RECEIPT_POLLS: u32 = 30;
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+async fn rpc_call(client: &reqwest::Client, rpc_url: &str, method: &str, params: serde_json::Value) -> Result<serde_json::Value, String> {
+    let response: serde_json::Value = client
+        .post(rpc_url)
+        .json(&serde_json::json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }))
+        .send()
+        .await
+        .map_err(|e| format!("{} failed: {}", method, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} response: {}", method, e))?;
+    if let Some(error) = response.get("error") {
+        return Err(format!("{} error: {}", method, error));
+    }
+    Ok(response["result"].clone())
+}
+
+/// Send from the operator DealMarket authorizes to release escrow, and wait
+/// for the receipt; a transaction mined with a failed status is an error
+async fn send_confirmed(rpc_url: &str, to: &str, data: &[u8], clock: &dyn Clock) -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let params = serde_json::json!([{
+        "from": std::env::var("ARTHA_OPERATOR_ADDR").unwrap_or_else(|_| "0x0".to_string()),
+        "to": to,
+        "data": format!("0x{}", hex::encode(data)),
+        "gas": "0x100000",
+    }]);
+    let tx_hash = rpc_call(&client, rpc_url, "eth_sendTransaction", params)
+        .await?
+        .as_str()
+        .ok_or("No tx hash in response")?
+        .to_string();
+    for attempt in 0..RECEIPT_POLLS {
+        if attempt > 0 {
+            clock.sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+        let receipt = rpc_call(&client, rpc_url, "eth_getTransactionReceipt", serde_json::json!([tx_hash])).await?;
+        if receipt.is_null() {
+            continue;
+        }
+        return match receipt["status"].as_str() {
+            Some("0x1") => Ok(tx_hash),
+            Some("0x0") => Err(format!("Transaction {} reverted", tx_hash)),
+            _ => Err(format!("Receipt of {} has no status", tx_hash)),
+        };
+    }
+    Err(format!("Transaction {} not mined after {} polls", tx_hash, RECEIPT_POLLS))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DatasetUsageRequest {
+    pub job_id: String,
+    pub grant_id: String,
+    pub owner_did: String,
+    pub units: u64,
+    pub gross: u64,
+    pub owner_share: u64,
+    pub platform_fee: u64,
+    #[serde(default)]
+    pub submitter: Option<String>,
+}
+
+/// POST /receipt/dataset-usage - Called by ai-jobd when a marketplace job finishes
+async fn record_dataset_usage(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DatasetUsageRequest>,
+) -> Result<Json<Receipt>, StatusCode> {
+    if req.owner_share + req.platform_fee != req.gross {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let receipt = Receipt {
+        receipt_id: format!("receipt-{}", state.entropy.uuid()),
+        job_id: req.job_id,
+        receipt_type: ReceiptType::DatasetUsage,
+        provider: req.owner_did,
+        amount_wei: req.owner_share,
+        status: ReceiptStatus::Pending,
+        proof_cid: None,
+        created_at: state.clock.now_secs(),
+        settled_at: None,
+        tx_hash: None,
+        platform_fee_wei: req.platform_fee,
+        finalize_tx: None,
+        submitter: req.submitter,
+        confidential: false,
+        dispute: None,
+        milestone: None,
+        grant_id: Some(req.grant_id),
+    };
+
+    state.receipts.write().await.insert(receipt.clone());
+    Ok(Json(receipt))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InferenceUsageRequest {
+    pub did: String,
+    pub model_cid: String,
+    pub interval_start: u64,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: u64,
+}
+
+/// POST /receipt/inference-usage - Aggregated micro-usage from the ai-runtime API facade
+async fn record_inference_usage(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<InferenceUsageRequest>,
+) -> Result<Json<Receipt>, StatusCode> {
+    let receipt = Receipt {
+        receipt_id: format!("receipt-{}", state.entropy.uuid()),
+        job_id: format!("usage:{}:{}:{}", req.did, req.model_cid, req.interval_start),
+        receipt_type: ReceiptType::InferenceUsage,
+        provider: req.model_cid,
+        amount_wei: req.cost,
+        status: ReceiptStatus::Pending,
+        proof_cid: None,
+        created_at: state.clock.now_secs(),
+        settled_at: None,
+        tx_hash: None,
+        platform_fee_wei: 0,
+        finalize_tx: None,
+        submitter: Some(req.did.clone()),
+        confidential: false,
+        dispute: None,
+        milestone: None,
+        grant_id: None,
+    };
+
+    info!("🧾 Inference usage for {}: {} requests, {} tokens",
+        req.did, req.requests, req.prompt_tokens + req.completion_tokens);
+    state.receipts.write().await.insert(receipt.clone());
+    Ok(Json(receipt))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MilestoneReleaseRequest {
+    pub job_id: String,
+    pub provider: String,
+    pub payer: String,
+    pub milestone: Option<usize>, // None for the payout on a cancelled escrow
+    pub amount_wei: u64,
+    pub release_tx: String,
+}
+
+/// POST /receipt/milestone - Called by ai-proofs when escrow releases a tranche.
+/// Internal escrow releases have no tx to wait on; on-chain ones are gated on finality.
+async fn record_milestone_release(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<MilestoneReleaseRequest>,
+) -> Result<Json<Receipt>, StatusCode> {
+    let receipt = Receipt {
+        receipt_id: format!("receipt-{}", state.entropy.uuid()),
+        job_id: req.job_id,
+        receipt_type: ReceiptType::Compute,
+        provider: req.provider,
+        amount_wei: req.amount_wei,
+        status: ReceiptStatus::Pending,
+        proof_cid: None,
+        created_at: state.clock.now_secs(),
+        settled_at: None,
+        tx_hash: None,
+        platform_fee_wei: 0,
+        finalize_tx: Some(req.release_tx).filter(|tx| tx.starts_with("0x")),
+        submitter: Some(req.payer),
+        confidential: false,
+        dispute: None,
+        milestone: req.milestone,
+        grant_id: None,
+    };
+
+    info!("🧾 Escrow release for {} (milestone {:?}): {} wei",
+        receipt.job_id, receipt.milestone, receipt.amount_wei);
+    state.receipts.write().await.insert(receipt.clone());
+    Ok(Json(receipt))
+}
+
+/// Whether the node reports the block containing `tx_hash` as finalized.
+/// Unreachable nodes and unknown transactions count as not finalized.
+async fn is_tx_finalized(node_api_url: &str, tx_hash: &str) -> bool {
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/transactions/{}", node_api_url, tx_hash.trim_start_matches("0x")))
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) if resp.status().is_success() => resp
+            .json::<serde_json::Value>()
+            .await
+            .map(|tx| tx["finalized"].as_bool().unwrap_or(false))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Pending receipts eligible for auto-settlement. With finality required,
+/// receipts anchored to an on-chain finalize tx wait until its block is
+/// finalized; receipts without one (off-chain usage) are not gated.
+async fn settleable_receipts(state: &AppState) -> Vec<String> {
+    let pending: Vec<(String, Option<String>)> = state.receipts
+        .read()
+        .await
+        .values()
+        .filter(|r| matches!(r.status, ReceiptStatus::Pending))
+        .map(|r| (r.receipt_id.clone(), r.finalize_tx.clone()))
+        .collect();
+
+    let mut ready = Vec::new();
+    for (receipt_id, finalize_tx) in pending {
+        match finalize_tx {
+            Some(tx) if state.require_finality && !is_tx_finalized(&state.node_api_url, &tx).await => {
+                info!("⏳ Holding {} until tx {} is finalized", receipt_id, tx);
+            }
+            _ => ready.push(receipt_id),
+        }
+    }
+    ready
 }
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
-        receipts: Arc::new(RwLock::new(HashMap::new())),
+        receipts: Arc::new(RwLock::new(ReceiptStore::load(Some(
+            std::env::var("ARTHA_RECEIPTS_PATH")
+                .unwrap_or_else(|_| "/tmp/artha/receipts/receipts.json".to_string()),
+        )))),
         deal_market_addr: std::env::var("DEAL_MARKET_ADDR")
             .unwrap_or_else(|_| "0x0000000000000000000000000000000000000000".to_string()),
         rpc_url: std::env::var("RPC_URL")
             .unwrap_or_else(|_| "http://localhost:8545".to_string()),
+        node_api_url: std::env::var("ARTHA_NODE_API_URL")
+            .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+        require_finality: std::env::var("ARTHA_REQUIRE_FINALITY")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true),
+        settlement_mode: SettlementMode::from_env(),
+        view_keys: Arc::new(RwLock::new(HashMap::new())),
+        confidential_payouts: Arc::new(RwLock::new(HashMap::new())),
+        retention: RetentionPolicy::from_env("ARTHA_RECEIPTS"),
+        clock: artha_clock::system_clock(),
+        entropy: artha_clock::system_entropy(),
     });
 
     // Background task: Monitor and auto-settle receipts
     let state_clone = state.clone();
     tokio::spawn(async move {
+        let policy = BackoffPolicy::from_env("ARTHA_RECEIPTS_SETTLE", Duration::from_secs(30));
+        let mut backoff = Backoff::new(policy, state_clone.entropy.clone());
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            state_clone.clock.sleep(backoff.delay()).await;
             
-            let receipts = state_clone.receipts.read().await;
-            let pending: Vec<String> = receipts
-                .iter()
-                .filter(|(_, r)| matches!(r.status, ReceiptStatus::Pending))
-                .map(|(id, _)| id.clone())
-                .collect();
-            drop(receipts);
+            let pending = settleable_receipts(&state_clone).await;
             
-            // Auto-settle pending receipts
+            // Auto-settle pending receipts; any failure backs the loop off
+            let mut settled_all = true;
             for receipt_id in pending {
                 let client = reqwest::Client::new();
                 let url = format!("http://localhost:8092/receipt/{}/settle", receipt_id);
-                let _ = client.post(&url).send().await;
+                settled_all &= matches!(client.post(&url).send().await, Ok(resp) if resp.status().is_success());
             }
+            backoff.record(settled_all);
         }
     });
 
+    // Background task: evict settled receipts past the retention policy
+    tokio::spawn(retention_gc_loop(state.clone()));
+
+    let state_mode = state.settlement_mode;
     let app = Router::new()
         .route("/receipt/monitor", post(monitor_proofs))
+        .route("/receipt/dataset-usage", post(record_dataset_usage))
+        .route("/receipt/inference-usage", post(record_inference_usage))
+        .route("/receipt/milestone", post(record_milestone_release))
         .route("/receipt/:id/settle", post(settle_receipt))
+        .route("/receipt/:id/dispute", post(dispute_receipt))
+        .route("/receipt/:id/invoice", get(get_invoice))
+        .route("/receipt/:id/archive", get(get_archived_receipt))
+        .route("/privacy/view-keys", post(register_view_key))
+        .route("/privacy/disclosure", post(create_disclosure))
+        .route("/privacy/disclosure/verify", post(verify_disclosure))
         .route("/receipt/:id", get(get_receipt))
         .route("/receipts", get(list_receipts))
+        .route("/earnings", get(get_earnings))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("💰 Receipts/Settlement Daemon starting on :8092");
+    info!("💰 Receipts/Settlement Daemon starting on :8092 ({:?} settlement)", state_mode);
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8092").await.unwrap();
     axum::serve(listener, app).await.unwrap();
@@ -236,21 +675,74 @@ async fn get_receipt(
     Path(receipt_id): Path<String>,
 ) -> Result<Json<Receipt>, StatusCode> {
     let receipts = state.receipts.read().await;
-    receipts.get(&receipt_id)
-        .cloned()
-        .ok_or(StatusCode::NOT_FOUND)
-        .map(Json)
+    match receipts.get(&receipt_id) {
+        Some(receipt) => Ok(Json(receipt.clone())),
+        None if receipts.get_archived(&receipt_id).is_some() => Err(StatusCode::GONE),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// GET /receipt/:id/archive - Settlement references kept for a receipt retention GC evicted
+async fn get_archived_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(receipt_id): Path<String>,
+) -> Result<Json<ArchivedReceipt>, StatusCode> {
+    state.receipts.read().await.get_archived(&receipt_id).cloned().map(Json).ok_or(StatusCode::NOT_FOUND)
 }
 
+/// Evict settled and failed receipts past the retention policy. Pending,
+/// approved and disputed receipts still have work ahead and are never candidates.
+async fn gc_finished_receipts(state: &AppState, now: u64) -> usize {
+    let mut receipts = state.receipts.write().await;
+    let finished: Vec<(String, u64)> = receipts
+        .values()
+        .filter(|r| matches!(r.status, ReceiptStatus::Settled | ReceiptStatus::Failed))
+        .map(|r| (r.receipt_id.clone(), r.settled_at.unwrap_or(r.created_at)))
+        .collect();
+    let evicted = receipts.evict(&state.retention.select(finished, now), now);
+    drop(receipts);
+    if evicted.is_empty() {
+        return 0;
+    }
+
+    if let Err(e) = state.retention.archive(&evicted) {
+        warn!("⚠️  Failed to archive evicted receipts: {}", e);
+    }
+    evicted.len()
+}
+
+/// Run `gc_finished_receipts` every retention interval, less jitter
+async fn retention_gc_loop(state: Arc<AppState>) {
+    let backoff = Backoff::new(BackoffPolicy::every(Duration::from_secs(state.retention.interval_secs)), state.entropy.clone());
+    loop {
+        state.clock.sleep(backoff.delay()).await;
+        let evicted = gc_finished_receipts(&state, state.clock.now_secs()).await;
+        if evicted > 0 {
+            info!("🧹 Retention GC archived {} settled receipts", evicted);
+        }
+    }
+}
+
+/// GET /receipts - Receipts filtered by `status`, `job_id` and `provider`, oldest first, one page at a time.
+/// A tenant sees the receipts of jobs it funded and of work it provided.
 async fn list_receipts(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<Vec<Receipt>>, StatusCode> {
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Receipt>>, StatusCode> {
     let receipts = state.receipts.read().await;
     let status_filter = params.get("status");
+    let job_filter = params.get("job_id");
+    let provider_filter = params.get("provider");
+    let caller = Namespace::of_caller(&headers);
     
-    let filtered: Vec<Receipt> = receipts
-        .values()
+    // The provider index narrows the scan when filtering by provider
+    let candidates: Box<dyn Iterator<Item = &Receipt>> = match provider_filter {
+        Some(provider) => Box::new(receipts.for_provider(provider)),
+        None => Box::new(receipts.values()),
+    };
+    let filtered = candidates
         .filter(|r| {
             if let Some(status) = status_filter {
                 format!("{:?}", r.status).to_lowercase() == status.to_lowercase()
@@ -258,9 +750,713 @@ async fn list_receipts(
                 true
             }
         })
-        .cloned()
-        .collect();
+        .filter(|r| job_filter.is_none_or(|job_id| &r.job_id == job_id))
+        .filter(|r| {
+            let submitter = r.submitter.as_deref().map_or(Namespace::Shared, Namespace::of);
+            caller.sees(&submitter) || caller.sees(&Namespace::of(&r.provider))
+        })
+        .cloned();
     
-    Ok(Json(filtered))
+    Ok(Json(artha_paging::paginate(filtered, |r| time_key(r.created_at, &r.receipt_id), &page)?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EarningsQuery {
+    pub provider: String,
+    #[serde(default)]
+    pub since: u64,
+    pub until: Option<u64>,
 }
 
+/// GET /earnings - A provider's settled amounts per receipt type over a time range
+async fn get_earnings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EarningsQuery>,
+) -> Result<Json<Earnings>, StatusCode> {
+    if query.until.is_some_and(|until| until < query.since) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(Json(state.receipts.read().await.earnings(&query.provider, query.since, query.until)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisputeRequest {
+    pub disputer: String, // DID raising the dispute
+    pub reason: String,
+}
+
+/// POST /receipt/:id/dispute - Hold an unsettled receipt out of settlement
+async fn dispute_receipt(
+    State(state): State<Arc<AppState>>,
+    Path(receipt_id): Path<String>,
+    Json(req): Json<DisputeRequest>,
+) -> Result<Json<Receipt>, StatusCode> {
+    let mut receipts = state.receipts.write().await;
+    let receipt = receipts.get(&receipt_id).ok_or(StatusCode::NOT_FOUND)?;
+    if !matches!(receipt.status, ReceiptStatus::Pending | ReceiptStatus::Approved) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    info!("⚖️  Receipt {} disputed by {}: {}", receipt_id, req.disputer, req.reason);
+    receipts.update(&receipt_id, |receipt| {
+        receipt.status = ReceiptStatus::Disputed;
+        receipt.dispute = Some(ReceiptDispute {
+            disputer: req.disputer,
+            reason: req.reason,
+            disputed_at: state.clock.now_secs(),
+        });
+        receipt.clone()
+    }).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewKeyRegistration {
+    pub did: String,
+    pub view_public_key: String, // Compressed Ristretto point, hex
+}
+
+/// POST /privacy/view-keys - Register the key confidential payouts are encrypted to
+async fn register_view_key(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ViewKeyRegistration>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !confidential::is_valid_view_key(&req.view_public_key) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    state.view_keys.write().await.insert(req.did.clone(), req.view_public_key.to_lowercase());
+    Ok(Json(serde_json::json!({
+        "did": req.did,
+        "registered": true
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisclosureRequest {
+    pub did: String,
+    pub view_key: String, // View secret scalar, hex; used to decrypt and never stored
+    #[serde(flatten)]
+    pub scope: DisclosureScope,
+}
+
+/// The view secret `view_key`, if it is the one behind the view key registered for `did`
+async fn owned_view_secret(state: &AppState, did: &str, view_key: &str) -> Option<Scalar> {
+    let secret = confidential::decode_scalar(view_key)?;
+    (state.view_keys.read().await.get(did) == Some(&confidential::view_public_key(&secret))).then_some(secret)
+}
+
+/// POST /privacy/disclosure - Open a party's payouts for one job or a date range
+async fn create_disclosure(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisclosureRequest>,
+) -> Result<Json<DisclosurePackage>, StatusCode> {
+    let view_secret = owned_view_secret(&state, &req.did, &req.view_key).await.ok_or(StatusCode::FORBIDDEN)?;
+
+    let payouts = state.confidential_payouts.read().await;
+    let package = confidential::build_disclosure(
+        payouts.values(),
+        &req.did,
+        &view_secret,
+        &req.scope,
+        state.clock.now_secs(),
+    );
+    info!("🔓 {} disclosed {} confidential payouts", req.did, package.entries.len());
+    Ok(Json(package))
+}
+
+/// POST /privacy/disclosure/verify - Check a disclosure package against published payouts
+async fn verify_disclosure(
+    State(state): State<Arc<AppState>>,
+    Json(package): Json<DisclosurePackage>,
+) -> Json<DisclosureVerdict> {
+    let payouts = state.confidential_payouts.read().await;
+    Json(confidential::verify_disclosure(&package, &payouts))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvoiceQuery {
+    pub did: Option<String>,
+    pub view_key: Option<String>,
+}
+
+/// GET /receipt/:id/invoice - Transparent receipts show the amount; confidential
+/// ones embed a disclosure package and need the caller's view key
+async fn get_invoice(
+    State(state): State<Arc<AppState>>,
+    Path(receipt_id): Path<String>,
+    Query(query): Query<InvoiceQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let receipt = state.receipts.read().await
+        .get(&receipt_id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !receipt.confidential {
+        return Ok(Json(serde_json::json!({
+            "receipt_id": receipt.receipt_id,
+            "job_id": receipt.job_id,
+            "provider": receipt.provider,
+            "amount_wei": receipt.amount_wei,
+            "platform_fee_wei": receipt.platform_fee_wei,
+            "tx_hash": receipt.tx_hash,
+            "settled_at": receipt.settled_at,
+            "confidential": false
+        })));
+    }
+
+    let (did, view_key) = query.did.zip(query.view_key).ok_or(StatusCode::UNAUTHORIZED)?;
+    let view_secret = owned_view_secret(&state, &did, &view_key).await.ok_or(StatusCode::FORBIDDEN)?;
+
+    let payouts = state.confidential_payouts.read().await;
+    let payout = receipt.tx_hash.as_ref()
+        .and_then(|tx| payouts.get(tx))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let disclosure = confidential::build_disclosure(
+        [payout],
+        &did,
+        &view_secret,
+        &DisclosureScope::default(),
+        state.clock.now_secs(),
+    );
+    let amount = disclosure.entries.first().map(|e| e.amount).ok_or(StatusCode::FORBIDDEN)?;
+
+    Ok(Json(serde_json::json!({
+        "receipt_id": receipt.receipt_id,
+        "job_id": receipt.job_id,
+        "provider": receipt.provider,
+        "amount_wei": amount,
+        "tx_hash": receipt.tx_hash,
+        "settled_at": receipt.settled_at,
+        "confidential": true,
+        "disclosure": disclosure
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use artha_clock::{ManualClock, SeededEntropy};
+
+    /// Stand-in for the node's transaction API, reporting finality for a fixed set of txs
+    async fn mock_consensus_feed(finalized: Vec<&'static str>) -> String {
+        let app = Router::new().route(
+            "/api/v1/transactions/:hash",
+            get(move |Path(hash): Path<String>| {
+                let finalized = finalized.clone();
+                async move {
+                    Json(serde_json::json!({
+                        "hash": hash,
+                        "finalized": finalized.contains(&hash.as_str()),
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn receipt(id: &str, finalize_tx: Option<&str>) -> Receipt {
+        Receipt {
+            receipt_id: id.to_string(),
+            job_id: format!("job-{}", id),
+            receipt_type: ReceiptType::Compute,
+            provider: "0xprovider".to_string(),
+            amount_wei: 1_000,
+            status: ReceiptStatus::Pending,
+            proof_cid: None,
+            created_at: 0,
+            settled_at: None,
+            tx_hash: None,
+            platform_fee_wei: 0,
+            finalize_tx: finalize_tx.map(|tx| tx.to_string()),
+            submitter: Some("did:artha:submitter".to_string()),
+            confidential: false,
+            dispute: None,
+            milestone: None,
+            grant_id: None,
+        }
+    }
+
+    fn store_of(path: Option<String>, receipts: Vec<Receipt>) -> ReceiptStore {
+        let mut store = ReceiptStore::new(path);
+        for receipt in receipts {
+            store.insert(receipt);
+        }
+        store
+    }
+
+    fn app_state(node_api_url: String, require_finality: bool, receipts: Vec<Receipt>) -> AppState {
+        AppState {
+            receipts: Arc::new(RwLock::new(store_of(None, receipts))),
+            deal_market_addr: "0x0".to_string(),
+            rpc_url: "http://localhost:8545".to_string(),
+            node_api_url,
+            require_finality,
+            settlement_mode: SettlementMode::Transparent,
+            view_keys: Arc::new(RwLock::new(HashMap::new())),
+            confidential_payouts: Arc::new(RwLock::new(HashMap::new())),
+            retention: RetentionPolicy { max_age_secs: 3600, max_count: 100, interval_secs: 60, archive_path: None },
+            clock: ManualClock::new(1_700_000_000).shared(),
+            entropy: SeededEntropy::shared(3),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_settle_waits_for_finalized_block() {
+        let node = mock_consensus_feed(vec!["aa11"]).await;
+        let state = app_state(node, true, vec![
+            receipt("final", Some("0xaa11")),
+            receipt("unfinal", Some("0xbb22")),
+            receipt("offchain", None),
+        ]);
+
+        let mut ready = settleable_receipts(&state).await;
+        ready.sort();
+        assert_eq!(ready, vec!["final".to_string(), "offchain".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_finality_gate_can_be_disabled() {
+        let node = mock_consensus_feed(vec![]).await;
+        let state = app_state(node, false, vec![receipt("unfinal", Some("0xbb22"))]);
+        assert_eq!(settleable_receipts(&state).await, vec!["unfinal".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_node_holds_settlement() {
+        let state = app_state("http://127.0.0.1:9".to_string(), true, vec![receipt("r", Some("0xaa11"))]);
+        assert!(settleable_receipts(&state).await.is_empty());
+        assert!(!is_tx_finalized("http://127.0.0.1:9", "0xaa11").await);
+    }
+
+    fn view_secret(seed: u64) -> Scalar {
+        Scalar::from(seed)
+    }
+
+    const PROVIDER_VIEW_KEY: u64 = 0x5eed_0001;
+    const SUBMITTER_VIEW_KEY: u64 = 0x5eed_0002;
+
+    fn view_key_hex(seed: u64) -> String {
+        confidential::encode_scalar(&view_secret(seed))
+    }
+
+    async fn confidential_state(receipts: Vec<Receipt>) -> Arc<AppState> {
+        let mut state = app_state("http://127.0.0.1:9".to_string(), false, receipts);
+        state.settlement_mode = SettlementMode::Confidential;
+        let state = Arc::new(state);
+        for (did, secret) in [("0xprovider", PROVIDER_VIEW_KEY), ("did:artha:submitter", SUBMITTER_VIEW_KEY)] {
+            let _ = register_view_key(State(state.clone()), Json(ViewKeyRegistration {
+                did: did.to_string(),
+                view_public_key: confidential::view_public_key(&view_secret(secret)),
+            })).await.unwrap();
+        }
+        state
+    }
+
+    fn disclosure_request(did: &str, view_key: u64, job_id: Option<&str>) -> DisclosureRequest {
+        DisclosureRequest {
+            did: did.to_string(),
+            view_key: view_key_hex(view_key),
+            scope: DisclosureScope { job_id: job_id.map(|j| j.to_string()), ..Default::default() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confidential_disclosure_round_trip() {
+        let state = confidential_state(vec![receipt("a", None), receipt("b", None)]).await;
+        let _ = settle_receipt(State(state.clone()), Path("a".to_string())).await.unwrap();
+        let _ = settle_receipt(State(state.clone()), Path("b".to_string())).await.unwrap();
+
+        // Nothing on the published payout reveals the amount
+        let published = serde_json::to_string(&*state.confidential_payouts.read().await).unwrap();
+        assert!(!published.contains("\"amount"));
+        assert!(state.receipts.read().await.get("a").unwrap().confidential);
+
+        // The submitter discloses one job; an auditor verifies it from chain data alone
+        let Json(package) = create_disclosure(
+            State(state.clone()),
+            Json(disclosure_request("did:artha:submitter", SUBMITTER_VIEW_KEY, Some("job-a"))),
+        ).await.unwrap();
+        assert_eq!(package.entries.len(), 1);
+        assert_eq!(package.entries[0].amount, 1_000);
+
+        let Json(verdict) = verify_disclosure(State(state.clone()), Json(package)).await;
+        assert!(verdict.valid);
+        assert_eq!(verdict.total_amount, Some(1_000));
+
+        // Date-range disclosure by the provider covers both payouts
+        let Json(package) = create_disclosure(
+            State(state.clone()),
+            Json(DisclosureRequest {
+                did: "0xprovider".to_string(),
+                view_key: view_key_hex(PROVIDER_VIEW_KEY),
+                scope: DisclosureScope { job_id: None, from: Some(0), to: Some(u64::MAX) },
+            }),
+        ).await.unwrap();
+        let Json(verdict) = verify_disclosure(State(state.clone()), Json(package)).await;
+        assert_eq!(verdict.total_amount, Some(2_000));
+
+        // A wrong view key is refused
+        let denied = create_disclosure(
+            State(state.clone()),
+            Json(disclosure_request("0xprovider", SUBMITTER_VIEW_KEY, None)),
+        ).await;
+        assert_eq!(denied.unwrap_err(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_disclosure_amount_fails_verification() {
+        let state = confidential_state(vec![receipt("a", None)]).await;
+        let _ = settle_receipt(State(state.clone()), Path("a".to_string())).await.unwrap();
+
+        let Json(mut package) = create_disclosure(
+            State(state.clone()),
+            Json(disclosure_request("0xprovider", PROVIDER_VIEW_KEY, None)),
+        ).await.unwrap();
+        package.entries[0].amount = 10;
+
+        let Json(verdict) = verify_disclosure(State(state.clone()), Json(package)).await;
+        assert!(!verdict.valid);
+        assert_eq!(verdict.total_amount, None);
+        assert_eq!(
+            verdict.entries[0].reason.as_deref(),
+            Some("amount and blinding do not open the commitment"),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confidential_invoice_requires_view_key() {
+        let state = confidential_state(vec![receipt("a", None)]).await;
+        let _ = settle_receipt(State(state.clone()), Path("a".to_string())).await.unwrap();
+
+        let anonymous = get_invoice(
+            State(state.clone()),
+            Path("a".to_string()),
+            Query(InvoiceQuery { did: None, view_key: None }),
+        ).await;
+        assert_eq!(anonymous.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let Json(invoice) = get_invoice(
+            State(state.clone()),
+            Path("a".to_string()),
+            Query(InvoiceQuery { did: Some("did:artha:submitter".to_string()), view_key: Some(view_key_hex(SUBMITTER_VIEW_KEY)) }),
+        ).await.unwrap();
+        assert_eq!(invoice["amount_wei"], 1_000);
+        let package: DisclosurePackage = serde_json::from_value(invoice["disclosure"].clone()).unwrap();
+        let Json(verdict) = verify_disclosure(State(state.clone()), Json(package)).await;
+        assert!(verdict.valid);
+    }
+
+    #[tokio::test]
+    async fn test_confidential_settlement_needs_registered_view_key() {
+        let mut state = app_state("http://127.0.0.1:9".to_string(), false, vec![receipt("a", None)]);
+        state.settlement_mode = SettlementMode::Confidential;
+        let result = settle_receipt(State(Arc::new(state)), Path("a".to_string())).await;
+        assert_eq!(result.unwrap_err(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_transparent_settlement_unchanged() {
+        let state = Arc::new(app_state("http://127.0.0.1:9".to_string(), false, vec![receipt("a", None)]));
+        let Json(settled) = settle_receipt(State(state.clone()), Path("a".to_string())).await.unwrap();
+        assert_eq!(settled["status"], "settled");
+
+        assert!(state.confidential_payouts.read().await.is_empty());
+        assert!(!state.receipts.read().await.get("a").unwrap().confidential);
+
+        let Json(invoice) = get_invoice(
+            State(state.clone()),
+            Path("a".to_string()),
+            Query(InvoiceQuery { did: None, view_key: None }),
+        ).await.unwrap();
+        assert_eq!(invoice["amount_wei"], 1_000);
+        assert_eq!(invoice["confidential"], false);
+        assert!(invoice.get("disclosure").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dataset_receipts_settle_from_the_grant_escrow() {
+        let rpc = abi::DryRunRpc::spawn().await;
+        let mut state = app_state("http://127.0.0.1:9".to_string(), false, Vec::new());
+        state.rpc_url = rpc.url();
+        let state = Arc::new(state);
+        let usage = |job_id: &str| DatasetUsageRequest {
+            job_id: job_id.to_string(),
+            grant_id: "grant-1".to_string(),
+            owner_did: "did:artha:owner".to_string(),
+            units: 2,
+            gross: 1_000,
+            owner_share: 900,
+            platform_fee: 100,
+            submitter: Some("did:artha:alice".to_string()),
+        };
+
+        let Json(receipt) = record_dataset_usage(State(state.clone()), Json(usage("job-1"))).await.unwrap();
+        let Json(settled) = settle_receipt(State(state.clone()), Path(receipt.receipt_id.clone())).await.unwrap();
+        assert_eq!(settled["status"], "settled");
+        assert_eq!(rpc.calls_of(&abi::deal_market(), "releaseDatasetEscrow"), vec![vec![
+            abi_encode_bytes32(&escrow_key("grant-1")),
+            abi_encode_bytes32(&escrow_key("did:artha:owner")),
+            abi_encode_uint256(900u64),
+            abi_encode_uint256(100u64),
+        ]]);
+        assert_eq!(state.receipts.read().await.get(&receipt.receipt_id).unwrap().tx_hash.as_ref(), settled["tx_hash"].as_str().map(str::to_string).as_ref());
+
+        // A release the chain reverts leaves the receipt pending for the next sweep
+        rpc.revert(abi::deal_market().function("releaseDatasetEscrow"));
+        let Json(receipt) = record_dataset_usage(State(state.clone()), Json(usage("job-2"))).await.unwrap();
+        let failed = settle_receipt(State(state.clone()), Path(receipt.receipt_id.clone())).await.unwrap_err();
+        assert_eq!(failed, StatusCode::BAD_GATEWAY);
+        let pending = state.receipts.read().await.get(&receipt.receipt_id).cloned().unwrap();
+        assert!(matches!(pending.status, ReceiptStatus::Pending));
+        assert!(pending.tx_hash.is_none());
+    }
+
+    #[test]
+    fn test_range_proof_rejects_mismatched_commitment() {
+        let (commitment, blinding, proof) = confidential::commit_with_range_proof(42).unwrap();
+        assert!(confidential::verify_range_proof(&commitment, &proof));
+        assert!(confidential::opens(&commitment, 42, &confidential::encode_scalar(&blinding)));
+        let reblinded = confidential::commit(42, &(blinding + Scalar::ONE));
+        assert!(!confidential::verify_range_proof(&confidential::encode_point(&reblinded), &proof));
+        assert!(confidential::commit_with_range_proof(1 << confidential::RANGE_BITS).is_err());
+
+        // Swapping in a commitment to 2, which is no bit, breaks that bit's proof
+        let mut forged = proof.clone();
+        forged.bits[0].commitment = confidential::encode_point(&confidential::commit(2, &Scalar::ONE));
+        assert!(!confidential::verify_range_proof(&commitment, &forged));
+    }
+
+    #[test]
+    fn test_view_keys_are_ristretto_points() {
+        let public = confidential::view_public_key(&view_secret(PROVIDER_VIEW_KEY));
+        assert!(confidential::is_valid_view_key(&public));
+        assert!(!confidential::is_valid_view_key(&"00".repeat(32))); // The identity
+        assert!(!confidential::is_valid_view_key(&"ff".repeat(32))); // Not a point encoding
+        assert!(!confidential::is_valid_view_key("1234"));
+    }
+
+    #[tokio::test]
+    async fn test_disputed_receipt_is_held_from_settlement() {
+        let node = mock_consensus_feed(vec![]).await;
+        let mut other = receipt("other", None);
+        other.provider = "0xother".to_string();
+        let state = Arc::new(app_state(node, false, vec![receipt("held", None), other]));
+
+        let dispute = |id: &str| dispute_receipt(
+            State(state.clone()),
+            Path(id.to_string()),
+            Json(DisputeRequest { disputer: "did:artha:submitter".to_string(), reason: "output missing".to_string() }),
+        );
+        let Json(disputed) = dispute("held").await.unwrap();
+        assert!(matches!(disputed.status, ReceiptStatus::Disputed));
+        assert_eq!(disputed.dispute.unwrap().reason, "output missing");
+        assert_eq!(dispute("held").await.unwrap_err(), StatusCode::CONFLICT);
+        assert_eq!(dispute("missing").await.unwrap_err(), StatusCode::NOT_FOUND);
+
+        assert_eq!(settle_receipt(State(state.clone()), Path("held".to_string())).await.unwrap_err(), StatusCode::CONFLICT);
+        assert_eq!(settleable_receipts(&state).await, vec!["other".to_string()]);
+
+        let query = |pairs: &[(&str, &str)]| Query(pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect());
+        let Json(listed) = list_receipts(State(state.clone()), HeaderMap::new(), query(&[("status", "disputed")]), Query(PageQuery::default())).await.unwrap();
+        assert_eq!(listed.items.len(), 1);
+        assert_eq!(listed.total, 1);
+        let Json(listed) = list_receipts(State(state.clone()), HeaderMap::new(), query(&[("provider", "0xother")]), Query(PageQuery::default())).await.unwrap();
+        assert_eq!(listed.items[0].receipt_id, "other");
+    }
+
+    #[tokio::test]
+    async fn test_tenants_list_only_their_own_receipts() {
+        let node = mock_consensus_feed(vec![]).await;
+        let provider = format!("0x{}", "ab".repeat(20));
+        let funded = |id: &str, submitter: &str| {
+            let mut funded = receipt(id, None);
+            funded.submitter = Some(submitter.to_string());
+            funded
+        };
+        let mut provided = funded("provided", "did:artha:globex:bob");
+        provided.provider = provider.clone();
+        let state = Arc::new(app_state(node, false, vec![
+            funded("acme", "did:artha:acme:alice"),
+            funded("globex", "did:artha:globex:bob"),
+            provided,
+        ]));
+
+        let listed = |did: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(did) = did {
+                headers.insert(artha_tenant::DID_HEADER, did.parse().unwrap());
+            }
+            let state = state.clone();
+            async move {
+                let Json(page) = list_receipts(State(state), headers, Query(HashMap::new()), Query(PageQuery::default())).await.unwrap();
+                let mut ids: Vec<String> = page.items.into_iter().map(|r| r.receipt_id).collect();
+                ids.sort();
+                ids
+            }
+        };
+        assert_eq!(listed(Some("did:artha:acme:carol")).await, vec!["acme"]);
+        assert_eq!(listed(Some("did:artha:globex:bob")).await, vec!["globex", "provided"]);
+        // The provider sees the work it was paid for, whoever funded it
+        assert_eq!(listed(Some(&provider)).await, vec!["provided"]);
+        assert_eq!(listed(None).await.len(), 3);
+    }
+
+    fn settled(id: &str, provider: &str, receipt_type: ReceiptType, amount_wei: u64, settled_at: u64) -> Receipt {
+        let mut settled = receipt(id, None);
+        settled.provider = provider.to_string();
+        settled.receipt_type = receipt_type;
+        settled.amount_wei = amount_wei;
+        settled.status = ReceiptStatus::Settled;
+        settled.settled_at = Some(settled_at);
+        settled
+    }
+
+    #[tokio::test]
+    async fn test_receipts_survive_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("artha-receipts-{}.json", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let mut state = app_state("http://127.0.0.1:9".to_string(), false, vec![]);
+        state.receipts = Arc::new(RwLock::new(store_of(Some(path.clone()), vec![
+            receipt("a", None),
+            receipt("b", None),
+            settled("old", "0xprovider", ReceiptType::Storage, 500, 10),
+        ])));
+        let state = Arc::new(state);
+
+        let Json(settlement) = settle_receipt(State(state.clone()), Path("a".to_string())).await.unwrap();
+        let _ = dispute_receipt(
+            State(state.clone()),
+            Path("b".to_string()),
+            Json(DisputeRequest { disputer: "did:artha:submitter".to_string(), reason: "output missing".to_string() }),
+        ).await.unwrap();
+        let now = state.clock.now_secs();
+        assert_eq!(gc_finished_receipts(&state, now).await, 1);
+        let before = state.receipts.read().await.earnings("0xprovider", 0, None);
+        assert_eq!(before.total_wei, 1_500);
+        drop(state);
+
+        // A fresh daemon on the same file sees every change
+        let restarted = ReceiptStore::load(Some(path.clone()));
+        let a = restarted.get("a").unwrap();
+        assert!(matches!(a.status, ReceiptStatus::Settled));
+        assert_eq!(a.tx_hash.as_deref(), settlement["tx_hash"].as_str());
+        let b = restarted.get("b").unwrap();
+        assert!(matches!(b.status, ReceiptStatus::Disputed));
+        assert_eq!(b.dispute.as_ref().unwrap().reason, "output missing");
+        assert!(restarted.get("old").is_none());
+        assert_eq!(restarted.get_archived("old").unwrap().amount_wei, Some(500));
+        assert_eq!(restarted.for_provider("0xprovider").count(), 2);
+        assert_eq!(restarted.earnings("0xprovider", 0, None), before);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_retention_loop_archives_settled_receipts_once_they_age_out() {
+        let clock = ManualClock::new(1_700_000_000);
+        let mut state = app_state("http://127.0.0.1:9".to_string(), false, vec![receipt("fresh", None)]);
+        state.clock = clock.shared();
+        let state = Arc::new(state);
+        let Json(settlement) = settle_receipt(State(state.clone()), Path("fresh".to_string())).await.unwrap();
+        assert_eq!(state.receipts.read().await.get("fresh").unwrap().settled_at, Some(1_700_000_000));
+
+        // Identical seeds mint identical settlement hashes
+        let replay = app_state("http://127.0.0.1:9".to_string(), false, vec![receipt("fresh", None)]);
+        let Json(replayed) = settle_receipt(State(Arc::new(replay)), Path("fresh".to_string())).await.unwrap();
+        assert_eq!(settlement["tx_hash"], replayed["tx_hash"]);
+
+        let gc = tokio::spawn(retention_gc_loop(state.clone()));
+        clock.wait_for_sleepers(1).await;
+        // An hour on, the receipt is at the retention age but not past it
+        for _ in 0..60 {
+            clock.tick(Duration::from_secs(60)).await;
+        }
+        assert!(state.receipts.read().await.get("fresh").is_some());
+
+        clock.tick(Duration::from_secs(60)).await;
+        assert!(state.receipts.read().await.get("fresh").is_none());
+        assert!(state.receipts.read().await.get_archived("fresh").is_some());
+        gc.abort();
+    }
+
+    #[tokio::test]
+    async fn test_earnings_aggregate_settled_amounts_by_type() {
+        let mut confidential = settled("conf", "0xp", ReceiptType::Storage, 800, 120);
+        confidential.confidential = true;
+        let mut pending = receipt("pending", None);
+        pending.provider = "0xp".to_string();
+        let state = Arc::new(app_state("http://127.0.0.1:9".to_string(), false, vec![
+            settled("c1", "0xp", ReceiptType::Compute, 1_000, 100),
+            settled("s1", "0xp", ReceiptType::Storage, 200, 150),
+            settled("r1", "0xp", ReceiptType::Retrieval, 30, 200),
+            settled("early", "0xp", ReceiptType::Compute, 5_000, 50),
+            settled("late", "0xp", ReceiptType::Compute, 7_000, 300),
+            settled("other", "0xother", ReceiptType::Compute, 4_000, 120),
+            confidential,
+            pending,
+        ]));
+
+        let earnings = |since: u64, until: Option<u64>| get_earnings(
+            State(state.clone()),
+            Query(EarningsQuery { provider: "0xp".to_string(), since, until }),
+        );
+        let Json(range) = earnings(100, Some(300)).await.unwrap();
+        assert_eq!(range.total_wei, 1_230);
+        assert_eq!(range.by_type["Compute"], 1_000);
+        assert_eq!(range.by_type["Storage"], 200);
+        assert_eq!(range.by_type["Retrieval"], 30);
+        assert_eq!(range.settled_receipts, 4);
+        assert_eq!(range.confidential_receipts, 1);
+
+        let Json(all) = earnings(0, None).await.unwrap();
+        assert_eq!(all.total_wei, 13_230);
+
+        // Settlements archived by GC still count
+        let _ = state.receipts.write().await.evict(&["c1".to_string(), "s1".to_string()], 1_000);
+        let Json(after_gc) = earnings(100, Some(300)).await.unwrap();
+        assert_eq!(after_gc, range);
+
+        assert_eq!(earnings(300, Some(100)).await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_milestone_releases_show_in_earnings() {
+        let state = Arc::new(app_state("http://127.0.0.1:9".to_string(), false, vec![]));
+        let release = |milestone: Option<usize>, amount_wei: u64, release_tx: &str| record_milestone_release(
+            State(state.clone()),
+            Json(MilestoneReleaseRequest {
+                job_id: "job-m".to_string(),
+                provider: "0xp".to_string(),
+                payer: "did:artha:payer".to_string(),
+                milestone,
+                amount_wei,
+                release_tx: release_tx.to_string(),
+            }),
+        );
+        let Json(first) = release(Some(0), 250, "0xaa11").await.unwrap();
+        let Json(second) = release(Some(1), 250, "escrow:job-m:release:milestone-1").await.unwrap();
+        let Json(cancel) = release(None, 100, "escrow:job-m:release:cancel").await.unwrap();
+        assert_eq!(first.finalize_tx.as_deref(), Some("0xaa11"));
+        assert_eq!(second.finalize_tx, None); // Internal escrow: nothing on-chain to wait for
+        assert_eq!(cancel.milestone, None);
+
+        state.receipts.write().await.update(&first.receipt_id, |r| {
+            r.status = ReceiptStatus::Settled;
+            r.settled_at = Some(r.created_at);
+        });
+        let Json(earnings) = get_earnings(
+            State(state.clone()),
+            Query(EarningsQuery { provider: "0xp".to_string(), since: 0, until: None }),
+        ).await.unwrap();
+        let milestones: Vec<(usize, Option<u64>, &str)> = earnings.milestones
+            .iter()
+            .map(|m| (m.milestone, m.amount_wei, m.status.as_str()))
+            .collect();
+        assert_eq!(milestones, vec![(0, Some(250), "Settled"), (1, Some(250), "Pending")]);
+        assert_eq!(earnings.total_wei, 250);
+    }
+}
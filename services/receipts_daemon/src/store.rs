@@ -0,0 +1,226 @@
+//! Receipt Store
+//! Live receipts and the settlement records retention GC keeps of evicted
+//! ones, persisted to a JSON file so neither is lost on restart. An index by
+//! provider serves per-provider listing and earnings without a full scan.
+
+use crate::{ArchivedReceipt, Receipt, ReceiptStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tracing::warn;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Snapshot {
+    receipts: Vec<Receipt>,
+    archived: Vec<ArchivedReceipt>,
+}
+
+/// A provider's settled earnings over `[since, until)`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Earnings {
+    pub provider: String,
+    pub since: u64,
+    pub until: Option<u64>,
+    pub total_wei: u64,
+    pub by_type: BTreeMap<String, u64>, // Receipt type -> settled amount
+    pub settled_receipts: usize,
+    pub confidential_receipts: usize, // Settled in range; amounts are not public so not summed
+    pub milestones: Vec<MilestoneRelease>, // Escrow tranches released to the provider in range
+}
+
+/// One escrow tranche released to a provider and how far its receipt has got
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MilestoneRelease {
+    pub job_id: String,
+    pub milestone: usize,
+    pub receipt_id: String,
+    pub amount_wei: Option<u64>, // None once settled confidentially
+    pub status: String,          // Receipt status
+    pub released_at: u64,
+}
+
+pub struct ReceiptStore {
+    path: Option<String>,
+    receipts: HashMap<String, Receipt>,
+    archived: HashMap<String, ArchivedReceipt>,
+    by_provider: HashMap<String, BTreeSet<String>>, // Provider -> live and archived receipt ids
+}
+
+impl ReceiptStore {
+    pub fn new(path: Option<String>) -> Self {
+        ReceiptStore {
+            path,
+            receipts: HashMap::new(),
+            archived: HashMap::new(),
+            by_provider: HashMap::new(),
+        }
+    }
+
+    /// Store seeded from the persisted snapshot, if any
+    pub fn load(path: Option<String>) -> Self {
+        let saved: Snapshot = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        let mut store = Self::new(path);
+        for receipt in saved.receipts {
+            store.index(&receipt.provider, &receipt.receipt_id);
+            store.receipts.insert(receipt.receipt_id.clone(), receipt);
+        }
+        for record in saved.archived {
+            store.index(&record.provider, &record.receipt_id);
+            store.archived.insert(record.receipt_id.clone(), record);
+        }
+        store
+    }
+
+    fn index(&mut self, provider: &str, receipt_id: &str) {
+        self.by_provider
+            .entry(provider.to_string())
+            .or_default()
+            .insert(receipt_id.to_string());
+    }
+
+    pub fn get(&self, receipt_id: &str) -> Option<&Receipt> {
+        self.receipts.get(receipt_id)
+    }
+
+    pub fn get_archived(&self, receipt_id: &str) -> Option<&ArchivedReceipt> {
+        self.archived.get(receipt_id)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Receipt> {
+        self.receipts.values()
+    }
+
+    /// Live receipts for one provider
+    pub fn for_provider<'a>(&'a self, provider: &str) -> impl Iterator<Item = &'a Receipt> {
+        self.by_provider
+            .get(provider)
+            .into_iter()
+            .flatten()
+            .filter_map(|receipt_id| self.receipts.get(receipt_id))
+    }
+
+    /// Add a receipt and persist
+    pub fn insert(&mut self, receipt: Receipt) {
+        self.index(&receipt.provider, &receipt.receipt_id);
+        self.receipts.insert(receipt.receipt_id.clone(), receipt);
+        self.persist();
+    }
+
+    /// Change a receipt in place and persist. Providers are fixed at creation,
+    /// so the index stays valid.
+    pub fn update<R>(&mut self, receipt_id: &str, change: impl FnOnce(&mut Receipt) -> R) -> Option<R> {
+        let receipt = self.receipts.get_mut(receipt_id)?;
+        let result = change(receipt);
+        self.persist();
+        Some(result)
+    }
+
+    /// Move receipts out of the live set, keeping their settlement records
+    pub fn evict(&mut self, receipt_ids: &[String], now: u64) -> Vec<Receipt> {
+        let evicted: Vec<Receipt> = receipt_ids
+            .iter()
+            .filter_map(|receipt_id| self.receipts.remove(receipt_id))
+            .collect();
+        for receipt in &evicted {
+            self.archived
+                .insert(receipt.receipt_id.clone(), ArchivedReceipt::of(receipt, now));
+        }
+        if !evicted.is_empty() {
+            self.persist();
+        }
+        evicted
+    }
+
+    /// Settled amounts for `provider` over `[since, until)`, per receipt type,
+    /// and the escrow milestones released to them in that range. Archived
+    /// settlements count, so GC never changes a provider's history.
+    pub fn earnings(&self, provider: &str, since: u64, until: Option<u64>) -> Earnings {
+        let mut earnings = Earnings {
+            provider: provider.to_string(),
+            since,
+            until,
+            total_wei: 0,
+            by_type: BTreeMap::new(),
+            settled_receipts: 0,
+            confidential_receipts: 0,
+            milestones: Vec::new(),
+        };
+        let in_range = |at: u64| at >= since && until.is_none_or(|until| at < until);
+
+        for receipt_id in self.by_provider.get(provider).into_iter().flatten() {
+            let release = match (self.receipts.get(receipt_id), self.archived.get(receipt_id)) {
+                (Some(r), _) => r.milestone.map(|milestone| MilestoneRelease {
+                    job_id: r.job_id.clone(),
+                    milestone,
+                    receipt_id: r.receipt_id.clone(),
+                    amount_wei: (!r.confidential).then_some(r.amount_wei),
+                    status: format!("{:?}", r.status),
+                    released_at: r.created_at,
+                }),
+                (None, Some(a)) => a.milestone.map(|milestone| MilestoneRelease {
+                    job_id: a.job_id.clone(),
+                    milestone,
+                    receipt_id: a.receipt_id.clone(),
+                    amount_wei: a.amount_wei,
+                    status: format!("{:?}", a.status),
+                    released_at: a.settled_at,
+                }),
+                _ => None,
+            };
+            if let Some(release) = release.filter(|r| in_range(r.released_at)) {
+                earnings.milestones.push(release);
+            }
+
+            let settlement = match (self.receipts.get(receipt_id), self.archived.get(receipt_id)) {
+                (Some(r), _) if matches!(r.status, ReceiptStatus::Settled) => r.settled_at.map(|at| {
+                    (at, format!("{:?}", r.receipt_type), (!r.confidential).then_some(r.amount_wei))
+                }),
+                (None, Some(a)) if matches!(a.status, ReceiptStatus::Settled) => a
+                    .receipt_type
+                    .as_ref()
+                    .map(|kind| (a.settled_at, format!("{:?}", kind), a.amount_wei)),
+                _ => None,
+            };
+            let Some((settled_at, kind, amount)) = settlement else { continue };
+            if !in_range(settled_at) {
+                continue;
+            }
+            earnings.settled_receipts += 1;
+            match amount {
+                Some(amount) => {
+                    earnings.total_wei = earnings.total_wei.saturating_add(amount);
+                    let total = earnings.by_type.entry(kind).or_default();
+                    *total = total.saturating_add(amount);
+                }
+                None => earnings.confidential_receipts += 1,
+            }
+        }
+        earnings.milestones.sort_by(|a, b| (&a.job_id, a.milestone).cmp(&(&b.job_id, b.milestone)));
+        earnings
+    }
+
+    fn persist(&self) {
+        if let Err(e) = self.save() {
+            warn!("⚠️  Failed to persist receipts: {}", e);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let snapshot = Snapshot {
+            receipts: self.receipts.values().cloned().collect(),
+            archived: self.archived.values().cloned().collect(),
+        };
+        let bytes = serde_json::to_vec(&snapshot).map_err(|e| e.to_string())?;
+        let tmp = format!("{}.tmp", path);
+        std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,561 @@
+//! Backward Chaining
+//! Goal-directed resolution over a named rulebase: only the subgoals a query
+//! actually needs are explored. Rules use the existing `Rule` shape with
+//! Prolog-style literals: `permitted(D, S)`, `not revoked(D)`. Identifiers
+//! starting with an uppercase letter or `_` are variables; anything else,
+//! or anything in double quotes, is a constant.
+//!
+//! Subgoals are tabled by variant: a completely evaluated subgoal is answered
+//! from the memo table on every later call, and a call to a subgoal already
+//! on the resolution stack is a cycle, answered from the answers found so far
+//! while the cycle's leader re-evaluates until no new answers appear. That
+//! keeps left-recursive rules (`path(X, Y) :- path(X, Z), edge(Z, Y)`)
+//! terminating and complete. `not` is negation as failure, sound for
+//! stratified rulebases (no recursion through `not`).
+
+use crate::{Fact, Rule};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_SOLUTION_LIMIT: usize = 100;
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+pub const DEFAULT_TIMEOUT_MS: u64 = 1_000;
+pub const MAX_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    Var(String),
+    Const(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Atom {
+    pub predicate: String,
+    pub args: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Literal {
+    pub atom: Atom,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Clause {
+    index: usize, // Position in the rulebase, as cited in proofs
+    head: Atom,
+    body: Vec<Literal>,
+    source: String,
+}
+
+/// A parsed rulebase
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    clauses: Vec<Clause>,
+    facts: HashMap<String, Vec<Atom>>, // predicate -> ground facts
+}
+
+/// One step of a proof: the goal it established, how, and the proofs of
+/// the premises it relied on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofNode {
+    pub goal: String,
+    pub step: ProofStep,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub premises: Vec<ProofNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProofStep {
+    Fact,
+    Rule { index: usize, rule: String },
+    NegationAsFailure, // No proof of the negated goal exists
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Solution {
+    pub bindings: BTreeMap<String, String>, // Goal variable -> value
+    pub proof: ProofNode,
+}
+
+/// Which limits cut the search short. Solutions found before a depth or time
+/// cut are still returned, but there may be more.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Truncation {
+    pub limit: bool,
+    pub depth: bool,
+    pub time: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryStats {
+    pub subgoals: u64,       // Subgoal calls, including ones answered from the memo table
+    pub inferences: u64,     // Fact and rule heads tried against a subgoal
+    pub memo_hits: u64,
+    pub cycles_detected: u64,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub goal: String,
+    pub solutions: Vec<Solution>,
+    pub truncated: Truncation,
+    pub stats: QueryStats,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryLimits {
+    pub solutions: usize,
+    pub max_depth: usize,
+    pub timeout: Duration,
+    pub memoize: bool,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        QueryLimits {
+            solutions: DEFAULT_SOLUTION_LIMIT,
+            max_depth: DEFAULT_MAX_DEPTH,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            memoize: true,
+        }
+    }
+}
+
+impl Program {
+    pub fn compile(rules: &[Rule], facts: &[Fact]) -> Result<Self, String> {
+        let mut program = Program::default();
+        for (index, rule) in rules.iter().enumerate() {
+            let head = parse_atom(&rule.head).map_err(|e| format!("Rule {}: {}", index, e))?;
+            let body = rule
+                .body
+                .iter()
+                .map(|literal| parse_literal(literal))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Rule {}: {}", index, e))?;
+            let source = if rule.body.is_empty() {
+                rule.head.trim().to_string()
+            } else {
+                format!("{} :- {}", rule.head.trim(), rule.body.iter().map(|b| b.trim()).collect::<Vec<_>>().join(", "))
+            };
+            program.clauses.push(Clause { index, head, body, source });
+        }
+        program.add_facts(facts);
+        Ok(program)
+    }
+
+    /// Add ground facts, e.g. the context of one query
+    pub fn add_facts(&mut self, facts: &[Fact]) {
+        for fact in facts {
+            let atom = Atom {
+                predicate: fact.predicate.clone(),
+                args: fact.args.iter().map(|a| Term::Const(a.clone())).collect(),
+            };
+            let known = self.facts.entry(atom.predicate.clone()).or_default();
+            if !known.contains(&atom) {
+                known.push(atom);
+            }
+        }
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.clauses.len()
+    }
+
+    pub fn fact_count(&self) -> usize {
+        self.facts.values().map(Vec::len).sum()
+    }
+
+    /// All bindings of `goal`'s variables that the rulebase proves, each with its proof
+    pub fn query(&self, goal: &str, limits: &QueryLimits) -> Result<QueryResult, String> {
+        let goal = parse_atom(goal)?;
+        let started = Instant::now();
+        let mut solver = Solver {
+            program: self,
+            limits,
+            deadline: started + limits.timeout,
+            memo: HashMap::new(),
+            stack: Vec::new(),
+            fresh: 0,
+            stats: QueryStats::default(),
+            truncated: Truncation::default(),
+        };
+
+        let (answers, _) = solver.solve(&goal, 0);
+        let variables = variables(&goal);
+        let mut solutions = Vec::new();
+        for answer in answers {
+            if solutions.len() == limits.solutions {
+                solver.truncated.limit = true;
+                break;
+            }
+            let Some(subst) = unify(&goal, &answer.atom, Subst::new()) else { continue };
+            let bindings = variables
+                .iter()
+                .filter_map(|var| match walk(&Term::Var(var.clone()), &subst) {
+                    Term::Const(value) => Some((var.clone(), value)),
+                    Term::Var(_) => None,
+                })
+                .collect();
+            solutions.push(Solution { bindings, proof: answer.proof });
+        }
+
+        let mut stats = solver.stats;
+        stats.elapsed_ms = started.elapsed().as_millis() as u64;
+        Ok(QueryResult { goal: goal.to_string(), solutions, truncated: solver.truncated, stats })
+    }
+}
+
+type Subst = HashMap<String, Term>;
+
+#[derive(Debug, Clone)]
+struct Answer {
+    atom: Atom,
+    proof: ProofNode,
+}
+
+/// Whether a subgoal's answers are final. Answers that relied on a cycle
+/// still open further up the stack, or that a limit cut short, are not.
+#[derive(Debug, Clone, Copy, Default)]
+struct Completeness {
+    cycle_floor: Option<usize>, // Lowest open stack frame the answers depended on
+    truncated: bool,
+}
+
+impl Completeness {
+    fn merge(&mut self, other: Completeness) {
+        self.cycle_floor = match (self.cycle_floor, other.cycle_floor) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.truncated |= other.truncated;
+    }
+
+    fn cut() -> Self {
+        Completeness { cycle_floor: None, truncated: true }
+    }
+}
+
+struct Frame {
+    key: String,
+    answers: Vec<Answer>,
+    recursive: bool, // Called itself, so its answers are recomputed to a fixpoint
+}
+
+struct Solver<'a> {
+    program: &'a Program,
+    limits: &'a QueryLimits,
+    deadline: Instant,
+    memo: HashMap<String, Vec<Answer>>,
+    stack: Vec<Frame>,
+    fresh: usize,
+    stats: QueryStats,
+    truncated: Truncation,
+}
+
+impl Solver<'_> {
+    fn solve(&mut self, goal: &Atom, depth: usize) -> (Vec<Answer>, Completeness) {
+        self.stats.subgoals += 1;
+        if Instant::now() >= self.deadline {
+            self.truncated.time = true;
+            return (Vec::new(), Completeness::cut());
+        }
+        if depth > self.limits.max_depth {
+            self.truncated.depth = true;
+            return (Vec::new(), Completeness::cut());
+        }
+
+        let key = variant_key(goal);
+        if self.limits.memoize {
+            if let Some(answers) = self.memo.get(&key) {
+                self.stats.memo_hits += 1;
+                return (answers.clone(), Completeness::default());
+            }
+        }
+        if let Some(index) = self.stack.iter().position(|frame| frame.key == key) {
+            self.stats.cycles_detected += 1;
+            let frame = &mut self.stack[index];
+            frame.recursive = true;
+            return (frame.answers.clone(), Completeness { cycle_floor: Some(index), truncated: false });
+        }
+
+        let index = self.stack.len();
+        self.stack.push(Frame { key: key.clone(), answers: Vec::new(), recursive: false });
+        let mut completeness = Completeness::default();
+        loop {
+            let (found, round) = self.evaluate(goal, depth);
+            completeness.merge(round);
+            let frame = &mut self.stack[index];
+            let mut grew = false;
+            for answer in found {
+                let answer_key = variant_key(&answer.atom);
+                if !frame.answers.iter().any(|known| variant_key(&known.atom) == answer_key) {
+                    frame.answers.push(answer);
+                    grew = true;
+                }
+            }
+            if !(grew && frame.recursive) || round.truncated {
+                break;
+            }
+        }
+        let frame = self.stack.pop().expect("frame pushed above");
+
+        // Calls back into this frame were settled by the fixpoint above
+        completeness.cycle_floor = completeness.cycle_floor.filter(|&floor| floor < index);
+        if self.limits.memoize && completeness.cycle_floor.is_none() && !completeness.truncated {
+            self.memo.insert(key, frame.answers.clone());
+        }
+        (frame.answers, completeness)
+    }
+
+    /// One pass over the facts and rules whose head matches `goal`
+    fn evaluate(&mut self, goal: &Atom, depth: usize) -> (Vec<Answer>, Completeness) {
+        let program = self.program;
+        let mut answers = Vec::new();
+        let mut completeness = Completeness::default();
+
+        for fact in program.facts.get(&goal.predicate).into_iter().flatten() {
+            self.stats.inferences += 1;
+            if unify(goal, fact, Subst::new()).is_some() {
+                answers.push(Answer {
+                    atom: fact.clone(),
+                    proof: ProofNode { goal: fact.to_string(), step: ProofStep::Fact, premises: Vec::new() },
+                });
+            }
+        }
+
+        for clause in program.clauses.iter().filter(|c| c.head.predicate == goal.predicate) {
+            self.stats.inferences += 1;
+            let (head, body) = self.rename(clause);
+            let Some(subst) = unify(goal, &head, Subst::new()) else { continue };
+            let (solutions, body_completeness) = self.solve_body(&body, subst, depth + 1);
+            completeness.merge(body_completeness);
+            for (subst, premises) in solutions {
+                let atom = apply(&head, &subst);
+                let step = ProofStep::Rule { index: clause.index, rule: clause.source.clone() };
+                answers.push(Answer { proof: ProofNode { goal: atom.to_string(), step, premises }, atom });
+            }
+        }
+        (answers, completeness)
+    }
+
+    /// Every way to satisfy `body` left to right, with the proofs of its literals
+    fn solve_body(&mut self, body: &[Literal], subst: Subst, depth: usize) -> (Vec<(Subst, Vec<ProofNode>)>, Completeness) {
+        let Some((literal, rest)) = body.split_first() else {
+            return (vec![(subst, Vec::new())], Completeness::default());
+        };
+        let goal = apply(&literal.atom, &subst);
+        let (answers, mut completeness) = self.solve(&goal, depth);
+
+        let mut solutions = Vec::new();
+        if literal.negated {
+            // An incomplete search proves nothing about absence
+            if answers.is_empty() && !completeness.truncated {
+                let (rest_solutions, rest_completeness) = self.solve_body(rest, subst, depth);
+                completeness.merge(rest_completeness);
+                for (subst, mut premises) in rest_solutions {
+                    let node = ProofNode { goal: format!("not {}", goal), step: ProofStep::NegationAsFailure, premises: Vec::new() };
+                    premises.insert(0, node);
+                    solutions.push((subst, premises));
+                }
+            }
+            return (solutions, completeness);
+        }
+
+        for answer in answers {
+            let Some(extended) = unify(&goal, &answer.atom, subst.clone()) else { continue };
+            let (rest_solutions, rest_completeness) = self.solve_body(rest, extended, depth);
+            completeness.merge(rest_completeness);
+            for (subst, mut premises) in rest_solutions {
+                premises.insert(0, answer.proof.clone());
+                solutions.push((subst, premises));
+            }
+        }
+        (solutions, completeness)
+    }
+
+    /// The clause with its variables renamed apart from every other use
+    fn rename(&mut self, clause: &Clause) -> (Atom, Vec<Literal>) {
+        self.fresh += 1;
+        let suffix = format!("#{}", self.fresh);
+        let rename_atom = |atom: &Atom| Atom {
+            predicate: atom.predicate.clone(),
+            args: atom
+                .args
+                .iter()
+                .map(|term| match term {
+                    Term::Var(name) => Term::Var(format!("{}{}", name, suffix)),
+                    constant => constant.clone(),
+                })
+                .collect(),
+        };
+        let body = clause
+            .body
+            .iter()
+            .map(|literal| Literal { atom: rename_atom(&literal.atom), negated: literal.negated })
+            .collect();
+        (rename_atom(&clause.head), body)
+    }
+}
+
+fn walk(term: &Term, subst: &Subst) -> Term {
+    let mut term = term.clone();
+    while let Term::Var(name) = &term {
+        match subst.get(name) {
+            Some(bound) => term = bound.clone(),
+            None => break,
+        }
+    }
+    term
+}
+
+fn unify(a: &Atom, b: &Atom, mut subst: Subst) -> Option<Subst> {
+    if a.predicate != b.predicate || a.args.len() != b.args.len() {
+        return None;
+    }
+    for (x, y) in a.args.iter().zip(&b.args) {
+        match (walk(x, &subst), walk(y, &subst)) {
+            (Term::Const(x), Term::Const(y)) if x == y => {}
+            (Term::Const(_), Term::Const(_)) => return None,
+            (Term::Var(x), Term::Var(y)) if x == y => {}
+            (Term::Var(x), other) | (other, Term::Var(x)) => {
+                subst.insert(x, other);
+            }
+        }
+    }
+    Some(subst)
+}
+
+fn apply(atom: &Atom, subst: &Subst) -> Atom {
+    Atom {
+        predicate: atom.predicate.clone(),
+        args: atom.args.iter().map(|term| walk(term, subst)).collect(),
+    }
+}
+
+/// Equal for goals that differ only in variable names
+fn variant_key(atom: &Atom) -> String {
+    let mut seen: Vec<&str> = Vec::new();
+    let args: Vec<String> = atom
+        .args
+        .iter()
+        .map(|term| match term {
+            Term::Const(value) => format!("{:?}", value),
+            Term::Var(name) => {
+                let position = seen.iter().position(|v| v == name).unwrap_or_else(|| {
+                    seen.push(name);
+                    seen.len() - 1
+                });
+                format!("_{}", position)
+            }
+        })
+        .collect();
+    format!("{}/{}({})", atom.predicate, atom.args.len(), args.join(","))
+}
+
+/// Named variables of a goal, in order of appearance; `_`-prefixed ones are not reported
+fn variables(atom: &Atom) -> Vec<String> {
+    let mut names = Vec::new();
+    for term in &atom.args {
+        if let Term::Var(name) = term {
+            if !name.starts_with('_') && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    names
+}
+
+impl std::fmt::Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Var(name) => write!(f, "{}", name),
+            Term::Const(value) if needs_quotes(value) => write!(f, "{:?}", value),
+            Term::Const(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl std::fmt::Display for Atom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.args.is_empty() {
+            return write!(f, "{}", self.predicate);
+        }
+        let args: Vec<String> = self.args.iter().map(|t| t.to_string()).collect();
+        write!(f, "{}({})", self.predicate, args.join(", "))
+    }
+}
+
+fn needs_quotes(value: &str) -> bool {
+    value.is_empty()
+        || value.starts_with(|c: char| c.is_ascii_uppercase() || c == '_')
+        || value.contains(|c: char| c == ',' || c == '(' || c == ')' || c == '"' || c.is_whitespace())
+}
+
+pub fn parse_literal(text: &str) -> Result<Literal, String> {
+    let text = text.trim();
+    let negated = text.strip_prefix("not ").or_else(|| text.strip_prefix("\\+"));
+    Ok(Literal { atom: parse_atom(negated.unwrap_or(text))?, negated: negated.is_some() })
+}
+
+pub fn parse_atom(text: &str) -> Result<Atom, String> {
+    let text = text.trim();
+    let (predicate, args) = match text.split_once('(') {
+        Some((predicate, rest)) => {
+            let inner = rest.strip_suffix(')').ok_or_else(|| format!("Unclosed argument list in {:?}", text))?;
+            (predicate.trim(), split_args(inner)?.iter().map(|arg| parse_term(arg)).collect::<Result<Vec<_>, _>>()?)
+        }
+        None => (text, Vec::new()),
+    };
+    if predicate.is_empty() || !predicate.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("Invalid predicate name in {:?}", text));
+    }
+    Ok(Atom { predicate: predicate.to_string(), args })
+}
+
+fn parse_term(text: &str) -> Result<Term, String> {
+    let text = text.trim();
+    if let Some(quoted) = text.strip_prefix('"') {
+        return quoted
+            .strip_suffix('"')
+            .map(|value| Term::Const(value.replace("\\\"", "\"")))
+            .ok_or_else(|| format!("Unterminated string {:?}", text));
+    }
+    match text.chars().next() {
+        None => Err("Empty argument".to_string()),
+        Some(c) if c.is_ascii_uppercase() || c == '_' => Ok(Term::Var(text.to_string())),
+        Some(_) => Ok(Term::Const(text.to_string())),
+    }
+}
+
+/// Split on commas outside double quotes
+fn split_args(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                args.push(std::mem::take(&mut current));
+                continue;
+            }
+            '(' | ')' if !quoted => return Err(format!("Nested terms are not supported: {:?}", text)),
+            _ => {}
+        }
+        current.push(c);
+    }
+    if quoted {
+        return Err(format!("Unterminated string in {:?}", text));
+    }
+    if !current.trim().is_empty() || !args.is_empty() {
+        args.push(current);
+    }
+    Ok(args)
+}
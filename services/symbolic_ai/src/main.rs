@@ -11,6 +11,9 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::collections::HashMap;
+use tracing::{error, info};
+mod backward;
+use backward::{Program, QueryLimits, QueryResult};
 
 #[derive(Debug, Deserialize)]
 pub struct SymbolicReasoningRequest {
@@ -25,7 +28,7 @@ pub struct Rule {
     pub body: Vec<String>,  // Premises
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Fact {
     pub predicate: String,
     pub args: Vec<String>,
@@ -38,6 +41,38 @@ pub struct SymbolicReasoningResponse {
     pub bindings: HashMap<String, String>,
 }
 
+/// A named rulebase for backward-chaining queries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulebaseRequest {
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub facts: Vec<Fact>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulebaseSummary {
+    pub name: String,
+    pub rules: usize,
+    pub facts: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BackwardQueryRequest {
+    pub rulebase: String, // Name the rulebase was stored under
+    pub goal: String,     // e.g. "permitted(\"did:artha:alice\", Dataset)"
+    #[serde(default)]
+    pub facts: Vec<Fact>, // Context for this query only, e.g. the request being decided
+    pub limit: Option<usize>,       // Solutions returned
+    pub max_depth: Option<usize>,   // Rule applications along one branch
+    pub timeout_ms: Option<u64>,
+    #[serde(default = "default_memoize")]
+    pub memoize: bool,
+}
+
+fn default_memoize() -> bool {
+    true
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TheoryOfMindRequest {
     pub agent_id: String,
@@ -65,6 +100,7 @@ pub struct TheoryOfMindResponse {
 pub struct AppState {
     rules_engine: Arc<RwLock<RulesEngine>>,
     mental_models: Arc<RwLock<HashMap<String, MentalModel>>>,
+    rulebases: Arc<RwLock<HashMap<String, Program>>>,
 }
 
 struct RulesEngine {
@@ -139,6 +175,49 @@ fn can_apply_rule(rule: &Rule, facts: &[Fact]) -> bool {
     })
 }
 
+/// PUT /symbolic/rulebase/:name - Store (or replace) a rulebase for queries
+async fn put_rulebase(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(req): Json<RulebaseRequest>,
+) -> Result<Json<RulebaseSummary>, StatusCode> {
+    let program = Program::compile(&req.rules, &req.facts).map_err(|e| {
+        error!("❌ Rulebase {} rejected: {}", name, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let summary = RulebaseSummary { name: name.clone(), rules: program.rule_count(), facts: program.fact_count() };
+    state.rulebases.write().await.insert(name, program);
+    Ok(Json(summary))
+}
+
+/// POST /symbolic/query - Backward-chain a goal against a stored rulebase,
+/// returning every satisfying binding with its proof tree
+async fn query_backward(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BackwardQueryRequest>,
+) -> Result<Json<QueryResult>, StatusCode> {
+    let mut program = state.rulebases.read().await.get(&req.rulebase).cloned().ok_or(StatusCode::NOT_FOUND)?;
+    program.add_facts(&req.facts);
+
+    let limits = QueryLimits {
+        solutions: req.limit.unwrap_or(backward::DEFAULT_SOLUTION_LIMIT),
+        max_depth: req.max_depth.unwrap_or(backward::DEFAULT_MAX_DEPTH),
+        timeout: std::time::Duration::from_millis(
+            req.timeout_ms.unwrap_or(backward::DEFAULT_TIMEOUT_MS).min(backward::MAX_TIMEOUT_MS),
+        ),
+        memoize: req.memoize,
+    };
+    // Resolution is CPU-bound; keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || program.query(&req.goal, &limits))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| {
+            error!("❌ Bad goal: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    Ok(Json(result))
+}
+
 async fn theory_of_mind(
     State(state): State<Arc<AppState>>,
     Json(req): Json<TheoryOfMindRequest>,
@@ -257,23 +336,178 @@ fn predict_next_action(
 
 #[tokio::main]
 async fn main() {
+    artha_log::init();
     let state = Arc::new(AppState {
         rules_engine: Arc::new(RwLock::new(RulesEngine {
             rules: Vec::new(),
             facts: Vec::new(),
         })),
         mental_models: Arc::new(RwLock::new(HashMap::new())),
+        rulebases: Arc::new(RwLock::new(HashMap::new())),
     });
 
     let app = Router::new()
         .route("/symbolic/reason", post(reason_symbolically))
+        .route("/symbolic/rulebase/:name", axum::routing::put(put_rulebase))
+        .route("/symbolic/query", post(query_backward))
         .route("/theory-of-mind/infer", post(theory_of_mind))
         .route("/health", get(|| async { "OK" }))
-        .with_state(state);
+        .with_state(state)
+        .layer(axum::middleware::from_fn(artha_log::log_requests));
 
-    println!("🧠 Symbolic AI / Theory of Mind Service starting on :8091");
+    info!("🧠 Symbolic AI / Theory of Mind Service starting on :8091");
     
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8091").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backward::{ProofNode, ProofStep};
+
+    fn rule(head: &str, body: &[&str]) -> Rule {
+        Rule { head: head.to_string(), body: body.iter().map(|b| b.to_string()).collect() }
+    }
+
+    fn fact(predicate: &str, args: &[&str]) -> Fact {
+        Fact { predicate: predicate.to_string(), args: args.iter().map(|a| a.to_string()).collect() }
+    }
+
+    fn state() -> Arc<AppState> {
+        Arc::new(AppState {
+            rules_engine: Arc::new(RwLock::new(RulesEngine { rules: Vec::new(), facts: Vec::new() })),
+            mental_models: Arc::new(RwLock::new(HashMap::new())),
+            rulebases: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    fn query(name: &str, goal: &str, facts: Vec<Fact>) -> BackwardQueryRequest {
+        BackwardQueryRequest {
+            rulebase: name.to_string(),
+            goal: goal.to_string(),
+            facts,
+            limit: None,
+            max_depth: None,
+            timeout_ms: None,
+            memoize: true,
+        }
+    }
+
+    fn steps(node: &ProofNode) -> Vec<String> {
+        let mut goals = vec![node.goal.clone()];
+        for premise in &node.premises {
+            goals.extend(steps(premise));
+        }
+        goals
+    }
+
+    #[tokio::test]
+    async fn test_query_returns_every_solution_with_its_proof() {
+        let state = state();
+        let rulebase = RulebaseRequest {
+            rules: vec![rule("grandparent(X, Z)", &["parent(X, Y)", "parent(Y, Z)"])],
+            facts: vec![fact("parent", &["tom", "bob"]), fact("parent", &["bob", "ann"]), fact("parent", &["bob", "pat"])],
+        };
+        let Json(summary) = put_rulebase(State(state.clone()), Path("family".to_string()), Json(rulebase)).await.unwrap();
+        assert_eq!((summary.rules, summary.facts), (1, 3));
+
+        let Json(result) = query_backward(State(state.clone()), Json(query("family", "grandparent(tom, Who)", vec![]))).await.unwrap();
+        let who: Vec<&str> = result.solutions.iter().map(|s| s.bindings["Who"].as_str()).collect();
+        assert_eq!(who, vec!["ann", "pat"]);
+        assert_eq!(result.truncated, backward::Truncation::default());
+
+        // Structured proof: the rule applied, then the facts supporting each premise
+        let proof = &result.solutions[0].proof;
+        assert_eq!(proof.step, ProofStep::Rule { index: 0, rule: "grandparent(X, Z) :- parent(X, Y), parent(Y, Z)".to_string() });
+        assert_eq!(steps(proof), vec!["grandparent(tom, ann)", "parent(tom, bob)", "parent(bob, ann)"]);
+        assert!(proof.premises.iter().all(|p| p.step == ProofStep::Fact));
+
+        // Limits truncate and say so
+        let limited = BackwardQueryRequest { limit: Some(1), ..query("family", "grandparent(tom, Who)", vec![]) };
+        let Json(result) = query_backward(State(state.clone()), Json(limited)).await.unwrap();
+        assert_eq!(result.solutions.len(), 1);
+        assert!(result.truncated.limit);
+        let shallow = BackwardQueryRequest { max_depth: Some(0), ..query("family", "grandparent(tom, Who)", vec![]) };
+        let Json(result) = query_backward(State(state.clone()), Json(shallow)).await.unwrap();
+        assert!(result.solutions.is_empty());
+        assert!(result.truncated.depth);
+
+        let missing = query_backward(State(state.clone()), Json(query("nope", "p(X)", vec![]))).await;
+        assert_eq!(missing.unwrap_err(), StatusCode::NOT_FOUND);
+        let malformed = RulebaseRequest { rules: vec![rule("p(X", &[])], facts: vec![] };
+        let rejected = put_rulebase(State(state), Path("bad".to_string()), Json(malformed)).await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_negation_as_failure_on_missing_premise() {
+        let program = Program::compile(
+            &[rule("permitted(D, S)", &["member(D)", "dataset(S)", "not revoked(D)"])],
+            &[fact("member", &["did:artha:alice"]), fact("member", &["did:artha:bob"]), fact("dataset", &["ds-1"]), fact("revoked", &["did:artha:bob"])],
+        )
+        .unwrap();
+
+        let result = program.query("permitted(D, ds-1)", &QueryLimits::default()).unwrap();
+        assert_eq!(result.solutions.len(), 1);
+        assert_eq!(result.solutions[0].bindings["D"], "did:artha:alice");
+        let naf = result.solutions[0].proof.premises.last().unwrap();
+        assert_eq!(naf.goal, "not revoked(did:artha:alice)");
+        assert_eq!(naf.step, ProofStep::NegationAsFailure);
+
+        // Quoted constants may hold anything, including what would read as a variable
+        let quoted = program.query("permitted(\"did:artha:bob\", S)", &QueryLimits::default()).unwrap();
+        assert!(quoted.solutions.is_empty());
+    }
+
+    #[test]
+    fn test_cycles_terminate_with_complete_answers() {
+        let rules = [
+            rule("path(X, Y)", &["edge(X, Y)"]),
+            rule("path(X, Y)", &["path(X, Z)", "edge(Z, Y)"]), // Left-recursive
+            rule("p", &["q"]),
+            rule("q", &["p"]),
+        ];
+        let facts = [fact("edge", &["a", "b"]), fact("edge", &["b", "c"]), fact("edge", &["c", "a"]), fact("edge", &["c", "d"])];
+        let program = Program::compile(&rules, &facts).unwrap();
+
+        for memoize in [true, false] {
+            let limits = QueryLimits { memoize, ..QueryLimits::default() };
+            let result = program.query("path(a, Y)", &limits).unwrap();
+            let mut reached: Vec<&str> = result.solutions.iter().map(|s| s.bindings["Y"].as_str()).collect();
+            reached.sort();
+            assert_eq!(reached, vec!["a", "b", "c", "d"]);
+            assert!(result.stats.cycles_detected > 0);
+            assert_eq!(result.truncated, backward::Truncation::default());
+
+            let mutual = program.query("p", &limits).unwrap();
+            assert!(mutual.solutions.is_empty());
+            assert!(mutual.stats.cycles_detected > 0);
+        }
+    }
+
+    #[test]
+    fn test_memoization_reduces_repeated_subgoal_work() {
+        // Each level asks the level below the same question twice
+        let mut rules = Vec::new();
+        for level in 1..=10 {
+            let below = format!("level{}(X)", level - 1);
+            rules.push(rule(&format!("level{}(X)", level), &[&below, &below]));
+        }
+        let program = Program::compile(&rules, &[fact("level0", &["a"]), fact("level0", &["b"])]).unwrap();
+
+        let memoized = program.query("level10(X)", &QueryLimits::default()).unwrap();
+        let unmemoized = program.query("level10(X)", &QueryLimits { memoize: false, ..QueryLimits::default() }).unwrap();
+        assert_eq!(memoized.solutions.len(), 2);
+        assert_eq!(unmemoized.solutions.len(), 2);
+        assert!(memoized.stats.memo_hits > 0);
+        assert_eq!(unmemoized.stats.memo_hits, 0);
+        assert!(
+            memoized.stats.inferences * 20 < unmemoized.stats.inferences,
+            "memoized {} vs unmemoized {} inferences",
+            memoized.stats.inferences,
+            unmemoized.stats.inferences
+        );
+    }
+}